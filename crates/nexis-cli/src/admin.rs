@@ -0,0 +1,409 @@
+//! `nexis-cli admin`: operator commands against the gateway's admin API.
+//!
+//! Every call uses the selected profile's token, which must belong to a
+//! member listed in the gateway's `auth.admin_members`.
+
+use clap::{Subcommand, ValueEnum};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parse_room_id, render, CliClient, CliError, MemberProfileResponse, OutputFormat,
+    RoomSettingsResponse,
+};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum AdminCommands {
+    #[command(about = "List rooms")]
+    ListRooms {
+        #[arg(long, default_value_t = 100, help = "Maximum number of rooms")]
+        limit: usize,
+        #[arg(long, default_value_t = 0, help = "Rooms to skip")]
+        offset: usize,
+        #[arg(long, help = "Only rooms whose name contains this text")]
+        search: Option<String>,
+        #[arg(long, value_enum, help = "Order of the rooms [default: name]")]
+        sort: Option<RoomSort>,
+    },
+    #[command(about = "Delete a room with its messages and members")]
+    DeleteRoom {
+        #[arg(help = "Room ID")]
+        room_id: String,
+    },
+    #[command(about = "Set how long members wait between messages in a room")]
+    SlowMode {
+        #[arg(help = "Room ID")]
+        room_id: String,
+        #[arg(help = "Seconds between each member's messages; 0 turns slow mode off")]
+        seconds: u64,
+    },
+    #[command(about = "List member profiles")]
+    ListMembers,
+    #[command(about = "Revoke a token, or every refresh token of a member")]
+    RevokeToken {
+        #[arg(
+            required_unless_present = "member",
+            conflicts_with = "member",
+            help = "Access token or refresh token (nrt_...)"
+        )]
+        token: Option<String>,
+        #[arg(long, help = "Revoke all refresh tokens issued to this member")]
+        member: Option<String>,
+    },
+    #[command(about = "Rebuild the gateway's keyword search index")]
+    Reindex,
+    #[command(about = "Manage tenants (multi-tenant gateways)")]
+    Tenant {
+        #[command(subcommand)]
+        command: TenantCommands,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum TenantCommands {
+    #[command(about = "Create a tenant")]
+    Create {
+        #[arg(help = "Display name")]
+        name: String,
+        #[arg(help = "URL-safe slug")]
+        slug: String,
+    },
+    #[command(about = "Suspend a tenant; its members are turned away")]
+    Suspend {
+        #[arg(help = "Tenant ID")]
+        tenant_id: String,
+    },
+    #[command(about = "Resume a suspended tenant")]
+    Resume {
+        #[arg(help = "Tenant ID")]
+        tenant_id: String,
+    },
+}
+
+/// Order of [`CliClient::list_rooms`] results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RoomSort {
+    /// By name
+    Name,
+    /// Most members first
+    Members,
+    /// Most recent message first
+    Activity,
+}
+
+impl RoomSort {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Members => "members",
+            Self::Activity => "activity",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomSummary {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member_count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomListResponse {
+    pub rooms: Vec<RoomSummary>,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemberListResponse {
+    pub members: Vec<MemberProfileResponse>,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokeTokenResponse {
+    pub revoked: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReindexResponse {
+    pub indexed: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TenantResponse {
+    pub id: String,
+    pub name: String,
+    pub slug: String,
+    pub is_active: bool,
+    #[serde(default)]
+    pub quota: serde_json::Value,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RevokeTokenRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    member_id: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct CreateTenantRequest {
+    name: String,
+    slug: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DeletedRoomOutput {
+    room_id: String,
+    deleted: bool,
+}
+
+impl CliClient {
+    /// Rooms the caller is a member of, or every room for admins.
+    pub async fn list_rooms(
+        &self,
+        limit: usize,
+        offset: usize,
+        search: Option<&str>,
+        sort: Option<RoomSort>,
+    ) -> Result<RoomListResponse, CliError> {
+        self.get_json(&room_list_path(limit, offset, search, sort))
+            .await
+    }
+
+    pub async fn delete_room(&self, room_id: &str) -> Result<(), CliError> {
+        parse_room_id(room_id)?;
+        let response = self
+            .request(
+                reqwest::Method::DELETE,
+                &format!("/v1/admin/rooms/{room_id}"),
+            )
+            .send()
+            .await
+            .map_err(|err| CliError::HttpTransport(err.to_string()))?;
+        if response.status() != StatusCode::NO_CONTENT {
+            let status = response.status().as_u16();
+            let body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "<unable to read body>".to_string());
+            return Err(CliError::HttpStatus { status, body });
+        }
+        Ok(())
+    }
+
+    /// Turn slow mode on with a pause of `seconds`, or off with `None`.
+    pub async fn set_slow_mode(
+        &self,
+        room_id: &str,
+        seconds: Option<u64>,
+    ) -> Result<RoomSettingsResponse, CliError> {
+        parse_room_id(room_id)?;
+        let response = self
+            .request(
+                reqwest::Method::PATCH,
+                &format!("/v1/rooms/{room_id}/settings"),
+            )
+            .json(&serde_json::json!({ "slowModeSecs": seconds }))
+            .send()
+            .await
+            .map_err(|err| CliError::HttpTransport(err.to_string()))?;
+        if response.status() != StatusCode::OK {
+            let status = response.status().as_u16();
+            let body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "<unable to read body>".to_string());
+            return Err(CliError::HttpStatus { status, body });
+        }
+        response
+            .json()
+            .await
+            .map_err(|err| CliError::Decode(err.to_string()))
+    }
+
+    pub async fn list_members(&self) -> Result<MemberListResponse, CliError> {
+        self.get_json("/v1/members").await
+    }
+
+    pub async fn revoke_token(
+        &self,
+        token: Option<String>,
+        member_id: Option<String>,
+    ) -> Result<RevokeTokenResponse, CliError> {
+        let payload = RevokeTokenRequest { token, member_id };
+        self.post_json("/v1/admin/tokens/revoke", &payload).await
+    }
+
+    pub async fn reindex(&self) -> Result<ReindexResponse, CliError> {
+        self.post_json("/v1/admin/reindex", &serde_json::json!({}))
+            .await
+    }
+
+    pub async fn create_tenant(
+        &self,
+        name: String,
+        slug: String,
+    ) -> Result<TenantResponse, CliError> {
+        self.post_json("/v1/admin/tenants", &CreateTenantRequest { name, slug })
+            .await
+    }
+
+    /// Suspend (`active == false`) or resume a tenant.
+    pub async fn set_tenant_active(
+        &self,
+        tenant_id: &str,
+        active: bool,
+    ) -> Result<TenantResponse, CliError> {
+        if tenant_id.trim().is_empty() {
+            return Err(CliError::InvalidArgument(
+                "tenant id cannot be empty".to_string(),
+            ));
+        }
+        let action = if active { "resume" } else { "suspend" };
+        self.post_json(
+            &format!("/v1/admin/tenants/{tenant_id}/{action}"),
+            &serde_json::json!({}),
+        )
+        .await
+    }
+}
+
+pub async fn run_admin_command(
+    client: &CliClient,
+    command: AdminCommands,
+    format: OutputFormat,
+) -> Result<String, CliError> {
+    match command {
+        AdminCommands::ListRooms {
+            limit,
+            offset,
+            search,
+            sort,
+        } => {
+            let rooms = client
+                .list_rooms(limit, offset, search.as_deref(), sort)
+                .await?;
+            render(format, &rooms, format_room_list)
+        }
+        AdminCommands::DeleteRoom { room_id } => {
+            client.delete_room(&room_id).await?;
+            let output = DeletedRoomOutput {
+                room_id,
+                deleted: true,
+            };
+            render(format, &output, |output| {
+                format!("room deleted: {}", output.room_id)
+            })
+        }
+        AdminCommands::SlowMode { room_id, seconds } => {
+            let settings = client
+                .set_slow_mode(&room_id, Some(seconds).filter(|&secs| secs > 0))
+                .await?;
+            render(format, &settings, |settings| {
+                match settings.slow_mode_secs {
+                    Some(secs) => format!(
+                        "slow mode on in {}: one message every {secs}s",
+                        settings.room_id
+                    ),
+                    None => format!("slow mode off in {}", settings.room_id),
+                }
+            })
+        }
+        AdminCommands::ListMembers => {
+            let members = client.list_members().await?;
+            render(format, &members, format_member_list)
+        }
+        AdminCommands::RevokeToken { token, member } => {
+            let response = client.revoke_token(token, member).await?;
+            render(format, &response, |response| match response.revoked {
+                0 => "nothing to revoke: the token was unknown, expired or already revoked"
+                    .to_string(),
+                revoked => format!("revoked {revoked} token(s)"),
+            })
+        }
+        AdminCommands::Reindex => {
+            let response = client.reindex().await?;
+            render(format, &response, |response| {
+                format!("search index rebuilt: {} messages", response.indexed)
+            })
+        }
+        AdminCommands::Tenant { command } => {
+            let tenant = match command {
+                TenantCommands::Create { name, slug } => client.create_tenant(name, slug).await?,
+                TenantCommands::Suspend { tenant_id } => {
+                    client.set_tenant_active(&tenant_id, false).await?
+                }
+                TenantCommands::Resume { tenant_id } => {
+                    client.set_tenant_active(&tenant_id, true).await?
+                }
+            };
+            render(format, &tenant, |tenant| {
+                let status = if tenant.is_active {
+                    "active"
+                } else {
+                    "suspended"
+                };
+                format!(
+                    "tenant {} ({}): {}, {status}",
+                    tenant.id, tenant.slug, tenant.name
+                )
+            })
+        }
+    }
+}
+
+/// `GET /v1/rooms` with its query string encoded.
+pub(crate) fn room_list_path(
+    limit: usize,
+    offset: usize,
+    search: Option<&str>,
+    sort: Option<RoomSort>,
+) -> String {
+    let mut url = reqwest::Url::parse("http://localhost/v1/rooms").expect("static URL parses");
+    {
+        let mut query = url.query_pairs_mut();
+        query
+            .append_pair("limit", &limit.to_string())
+            .append_pair("offset", &offset.to_string());
+        if let Some(search) = search {
+            query.append_pair("q", search);
+        }
+        if let Some(sort) = sort {
+            query.append_pair("sort", sort.as_str());
+        }
+    }
+    format!("/v1/rooms?{}", url.query().unwrap_or_default())
+}
+
+fn format_room_list(rooms: &RoomListResponse) -> String {
+    let mut output = format!("{} room(s)", rooms.total);
+    for room in &rooms.rooms {
+        output.push_str(&format!("\n  {}  {}", room.id, room.name));
+        if let Some(count) = room.member_count {
+            output.push_str(&format!("  ({count} members)"));
+        }
+        if let Some(topic) = &room.topic {
+            output.push_str(&format!("  - {topic}"));
+        }
+    }
+    output
+}
+
+fn format_member_list(members: &MemberListResponse) -> String {
+    let mut output = format!("{} member(s)", members.total);
+    for member in &members.members {
+        output.push_str(&format!("\n  {}  {}", member.id, member.member_type));
+        if let Some(name) = &member.display_name {
+            output.push_str(&format!("  {name}"));
+        }
+    }
+    output
+}
@@ -0,0 +1,172 @@
+//! `nexis-cli eval`: score an AI provider against a suite of golden prompts.
+//!
+//! Suites are TOML files in the format of [`nexis_runtime::eval`]. The
+//! provider, and the judge grading `judge` expectations, are configured from
+//! the environment like `test-provider`.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::{Args, Subcommand};
+use nexis_runtime::{
+    AIProvider, AnthropicProvider, EvalReport, EvalRunner, EvalSuite, OpenAICompatibleProvider,
+    OpenAIProvider,
+};
+
+use crate::{render, CliError, OutputFormat};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum EvalCommands {
+    #[command(about = "Run a suite of golden prompts and print a scored report")]
+    Run(EvalRunArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct EvalRunArgs {
+    #[arg(help = "Suite file (TOML)")]
+    pub suite: PathBuf,
+    #[arg(
+        long,
+        default_value = "openai",
+        help = "Provider to evaluate (openai, anthropic, ollama or vllm)"
+    )]
+    pub provider: String,
+    #[arg(long, help = "Model to use instead of the ones the suite names")]
+    pub model: Option<String>,
+    #[arg(
+        long,
+        help = "Provider grading `judge` expectations (openai, anthropic, ollama or vllm)"
+    )]
+    pub judge_provider: Option<String>,
+    #[arg(long, requires = "judge_provider", help = "Model the judge uses")]
+    pub judge_model: Option<String>,
+}
+
+fn provider_from_env(name: &str) -> Result<Arc<dyn AIProvider>, CliError> {
+    match name {
+        "openai" => Ok(Arc::new(OpenAIProvider::from_env())),
+        "anthropic" => Ok(Arc::new(AnthropicProvider::from_env())),
+        "ollama" => Ok(Arc::new(OpenAICompatibleProvider::ollama_from_env())),
+        "vllm" => Ok(Arc::new(OpenAICompatibleProvider::vllm_from_env())),
+        other => Err(CliError::InvalidArgument(format!(
+            "Unknown provider: {other}"
+        ))),
+    }
+}
+
+/// Read and parse the suite at `path`.
+pub fn load_suite(path: &Path) -> Result<EvalSuite, CliError> {
+    let text = std::fs::read_to_string(path).map_err(|err| {
+        CliError::InvalidArgument(format!("failed to read {}: {err}", path.display()))
+    })?;
+    toml::from_str(&text).map_err(|err| {
+        CliError::InvalidArgument(format!("invalid suite {}: {err}", path.display()))
+    })
+}
+
+pub async fn run_eval_command(
+    command: EvalCommands,
+    format: OutputFormat,
+) -> Result<String, CliError> {
+    match command {
+        EvalCommands::Run(args) => {
+            let suite = load_suite(&args.suite)?;
+            let mut runner = EvalRunner::new(provider_from_env(&args.provider)?);
+            if let Some(model) = args.model {
+                runner = runner.with_model(model);
+            }
+            if let Some(judge) = &args.judge_provider {
+                runner = runner.with_judge(provider_from_env(judge)?, args.judge_model);
+            }
+            let report = runner
+                .run(&suite)
+                .await
+                .map_err(|err| CliError::InvalidArgument(err.to_string()))?;
+            render(format, &report, format_report)
+        }
+    }
+}
+
+/// A report for people: one line per case, the failed checks beneath it.
+pub fn format_report(report: &EvalReport) -> String {
+    let mut output = format!("suite {} on {}", report.suite, report.provider);
+    for case in &report.cases {
+        let status = if case.passed { "PASS" } else { "FAIL" };
+        let _ = write!(
+            output,
+            "\n  {status}  {}  score {:.2}  ({} ms)",
+            case.name, case.score, case.latency_ms
+        );
+        if let Some(error) = &case.error {
+            let _ = write!(output, "\n        error: {error}");
+        }
+        for check in case.checks.iter().filter(|check| !check.passed) {
+            let _ = write!(output, "\n        failed: {}", check.expectation);
+            if let Some(detail) = &check.detail {
+                let _ = write!(output, " ({detail})");
+            }
+        }
+    }
+    let _ = write!(
+        output,
+        "\n{} passed, {} failed, score {:.2}",
+        report.passed, report.failed, report.score
+    );
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nexis_runtime::eval::{CaseReport, CheckResult};
+
+    #[test]
+    fn format_report_lists_failed_checks() {
+        let report = EvalReport {
+            suite: "golden".to_string(),
+            provider: "openai".to_string(),
+            cases: vec![
+                CaseReport {
+                    name: "refunds".to_string(),
+                    output: Some("30 days".to_string()),
+                    error: None,
+                    checks: vec![CheckResult {
+                        expectation: "contains \"30 days\"".to_string(),
+                        passed: true,
+                        score: 1.0,
+                        detail: None,
+                    }],
+                    score: 1.0,
+                    passed: true,
+                    latency_ms: 12,
+                },
+                CaseReport {
+                    name: "json".to_string(),
+                    output: Some("nope".to_string()),
+                    error: None,
+                    checks: vec![CheckResult {
+                        expectation: "conforms to JSON schema".to_string(),
+                        passed: false,
+                        score: 0.0,
+                        detail: Some("not JSON".to_string()),
+                    }],
+                    score: 0.0,
+                    passed: false,
+                    latency_ms: 8,
+                },
+            ],
+            passed: 1,
+            failed: 1,
+            score: 0.5,
+        };
+        assert_eq!(
+            format_report(&report),
+            "suite golden on openai\n  \
+             PASS  refunds  score 1.00  (12 ms)\n  \
+             FAIL  json  score 0.00  (8 ms)\n        \
+             failed: conforms to JSON schema (not JSON)\n\
+             1 passed, 1 failed, score 0.50"
+        );
+    }
+}
@@ -0,0 +1,352 @@
+//! `nexis-cli import`: post messages from a JSONL file into an existing room.
+//!
+//! Each line is `{"sender": "...", "text": "...", "timestamp": "..."}` with an
+//! optional RFC 3339 timestamp. The gateway stamps messages on arrival, so
+//! timestamps only have to be in order. Completed lines are recorded in a
+//! checkpoint file, and re-running the same import skips them.
+
+use std::collections::BTreeSet;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{parse_room_id, CliClient, CliError};
+
+/// How often, in completed messages, the checkpoint is written.
+const CHECKPOINT_EVERY: usize = 50;
+const PROGRESS_WIDTH: usize = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImportMessage {
+    pub sender: String,
+    pub text: String,
+    #[serde(default)]
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// A message together with its 1-based line number in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportLine {
+    pub line: usize,
+    pub message: ImportMessage,
+}
+
+/// Parse and validate every line, collecting all problems rather than
+/// stopping at the first.
+pub fn parse_messages(raw: &str) -> Result<Vec<ImportLine>, Vec<String>> {
+    let mut lines = Vec::new();
+    let mut problems = Vec::new();
+    let mut previous: Option<DateTime<Utc>> = None;
+    for (index, text) in raw.lines().enumerate() {
+        let line = index + 1;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let message = match serde_json::from_str::<ImportMessage>(text) {
+            Ok(message) => message,
+            Err(err) => {
+                problems.push(format!("line {line}: {err}"));
+                continue;
+            }
+        };
+        if !message.sender.starts_with("nexis:") {
+            problems.push(format!(
+                "line {line}: sender `{}` is not a member id",
+                message.sender
+            ));
+        }
+        if message.text.trim().is_empty() {
+            problems.push(format!("line {line}: text cannot be empty"));
+        }
+        if let Some(timestamp) = message.timestamp {
+            if previous.is_some_and(|previous| timestamp < previous) {
+                problems.push(format!(
+                    "line {line}: timestamp is earlier than the previous message"
+                ));
+            }
+            previous = Some(timestamp);
+        }
+        lines.push(ImportLine { line, message });
+    }
+    if problems.is_empty() {
+        Ok(lines)
+    } else {
+        Err(problems)
+    }
+}
+
+/// Lines of one file already posted to one room.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub room_id: String,
+    pub done: BTreeSet<usize>,
+}
+
+impl Checkpoint {
+    /// Default checkpoint location next to the imported file.
+    pub fn path_for(file: &Path) -> PathBuf {
+        let mut name = file.as_os_str().to_owned();
+        name.push(".checkpoint");
+        PathBuf::from(name)
+    }
+
+    /// The checkpoint for `room_id`, or an empty one when there is none.
+    pub fn load(path: &Path, room_id: &str) -> Result<Self, CliError> {
+        let raw = match std::fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    room_id: room_id.to_string(),
+                    done: BTreeSet::new(),
+                })
+            }
+            Err(err) => {
+                return Err(CliError::InvalidArgument(format!(
+                    "failed to read {}: {err}",
+                    path.display()
+                )))
+            }
+        };
+        let checkpoint: Self = serde_json::from_str(&raw).map_err(|err| {
+            CliError::InvalidArgument(format!("invalid checkpoint {}: {err}", path.display()))
+        })?;
+        if checkpoint.room_id != room_id {
+            return Err(CliError::InvalidArgument(format!(
+                "checkpoint {} belongs to room {}; remove it to import into {room_id}",
+                path.display(),
+                checkpoint.room_id
+            )));
+        }
+        Ok(checkpoint)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), CliError> {
+        let raw = serde_json::to_string(self).map_err(|err| CliError::Decode(err.to_string()))?;
+        std::fs::write(path, raw).map_err(|err| {
+            CliError::InvalidArgument(format!("failed to write {}: {err}", path.display()))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// Messages in flight at once; above 1 the room order may differ from
+    /// the file order.
+    pub concurrency: usize,
+    pub dry_run: bool,
+    pub checkpoint: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportSummary {
+    pub room_id: String,
+    pub total: usize,
+    pub imported: usize,
+    /// Lines skipped because an earlier run already posted them.
+    pub skipped: usize,
+    pub dry_run: bool,
+}
+
+/// Post `raw`'s messages to `room_id`. On the first failure no further
+/// messages are started, in-flight ones finish, and the checkpoint is saved
+/// so the next run resumes after the last posted line.
+pub async fn import_messages(
+    client: &CliClient,
+    room_id: &str,
+    raw: &str,
+    options: &ImportOptions,
+) -> Result<ImportSummary, CliError> {
+    parse_room_id(room_id)?;
+    let lines = parse_messages(raw).map_err(|problems| {
+        CliError::InvalidArgument(format!("invalid import file:\n  {}", problems.join("\n  ")))
+    })?;
+    let mut checkpoint = Checkpoint::load(&options.checkpoint, room_id)?;
+    let pending: Vec<ImportLine> = lines
+        .iter()
+        .filter(|line| !checkpoint.done.contains(&line.line))
+        .cloned()
+        .collect();
+    let mut summary = ImportSummary {
+        room_id: room_id.to_string(),
+        total: lines.len(),
+        imported: 0,
+        skipped: lines.len() - pending.len(),
+        dry_run: options.dry_run,
+    };
+    if options.dry_run {
+        return Ok(summary);
+    }
+
+    let progress = Progress::new(summary.total, summary.skipped);
+    let failed = Arc::new(AtomicBool::new(false));
+    let mut results = futures::stream::iter(pending)
+        .map(|line| {
+            let failed = Arc::clone(&failed);
+            async move {
+                if failed.load(Ordering::SeqCst) {
+                    return (line.line, None);
+                }
+                let result = client
+                    .send_message(room_id.to_string(), line.message.sender, line.message.text)
+                    .await;
+                if result.is_err() {
+                    failed.store(true, Ordering::SeqCst);
+                }
+                (line.line, Some(result))
+            }
+        })
+        .buffer_unordered(options.concurrency.max(1));
+
+    let mut first_error = None;
+    while let Some((line, result)) = results.next().await {
+        match result {
+            Some(Ok(_)) => {
+                checkpoint.done.insert(line);
+                summary.imported += 1;
+                progress.update(summary.skipped + summary.imported);
+                if summary.imported.is_multiple_of(CHECKPOINT_EVERY) {
+                    checkpoint.save(&options.checkpoint)?;
+                }
+            }
+            Some(Err(err)) => {
+                first_error.get_or_insert((line, err));
+            }
+            None => {}
+        }
+    }
+    progress.finish();
+
+    if let Some((line, err)) = first_error {
+        checkpoint.save(&options.checkpoint)?;
+        return Err(CliError::InvalidArgument(format!(
+            "import stopped at line {line} after {} message(s): {err}; \
+             re-run the same command to resume",
+            summary.imported
+        )));
+    }
+    if options.checkpoint.exists() {
+        std::fs::remove_file(&options.checkpoint).map_err(|err| {
+            CliError::InvalidArgument(format!(
+                "failed to remove {}: {err}",
+                options.checkpoint.display()
+            ))
+        })?;
+    }
+    Ok(summary)
+}
+
+/// Progress bar on stderr, drawn only when stderr is a terminal.
+struct Progress {
+    total: usize,
+    enabled: bool,
+}
+
+impl Progress {
+    fn new(total: usize, done: usize) -> Self {
+        let progress = Self {
+            total,
+            enabled: std::io::stderr().is_terminal() && total > 0,
+        };
+        progress.update(done);
+        progress
+    }
+
+    fn update(&self, done: usize) {
+        if !self.enabled {
+            return;
+        }
+        let filled = done * PROGRESS_WIDTH / self.total;
+        eprint!(
+            "\r[{}{}] {done}/{}",
+            "=".repeat(filled),
+            " ".repeat(PROGRESS_WIDTH - filled),
+            self.total
+        );
+        let _ = std::io::stderr().flush();
+    }
+
+    fn finish(&self) {
+        if self.enabled {
+            eprintln!();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_messages_reports_every_bad_line() {
+        let raw = concat!(
+            r#"{"sender":"nexis:human:alice","text":"hi","timestamp":"2026-01-01T10:00:00Z"}"#,
+            "\n\n",
+            r#"{"sender":"alice","text":""}"#,
+            "\n",
+            r#"{"sender":"nexis:human:bob","text":"late","timestamp":"2026-01-01T09:00:00Z"}"#,
+            "\nnot json\n",
+        );
+        let problems = parse_messages(raw).unwrap_err();
+        assert_eq!(problems.len(), 4, "{problems:?}");
+        assert!(problems[0].starts_with("line 3: sender `alice`"));
+        assert!(problems[1].starts_with("line 3: text"));
+        assert!(problems[2].starts_with("line 4: timestamp"));
+        assert!(problems[3].starts_with("line 5:"));
+
+        let lines = parse_messages(concat!(
+            r#"{"sender":"nexis:human:alice","text":"one"}"#,
+            "\n\n",
+            r#"{"sender":"nexis:ai:bot","text":"two"}"#,
+        ))
+        .unwrap();
+        assert_eq!(
+            lines.iter().map(|line| line.line).collect::<Vec<_>>(),
+            [1, 3]
+        );
+    }
+
+    #[tokio::test]
+    async fn dry_run_skips_checkpointed_lines_without_posting() {
+        let dir = std::env::temp_dir().join(format!("nexis-cli-import-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let checkpoint = Checkpoint::path_for(&dir.join("seed.jsonl"));
+        Checkpoint {
+            room_id: "room_1".to_string(),
+            done: BTreeSet::from([1]),
+        }
+        .save(&checkpoint)
+        .unwrap();
+
+        let raw = concat!(
+            r#"{"sender":"nexis:human:alice","text":"one"}"#,
+            "\n",
+            r#"{"sender":"nexis:human:bob","text":"two"}"#,
+        );
+        let options = ImportOptions {
+            concurrency: 4,
+            dry_run: true,
+            checkpoint: checkpoint.clone(),
+        };
+        // Nothing listens on the discard port, so any request would fail.
+        let client = CliClient::new("http://127.0.0.1:9");
+        let summary = import_messages(&client, "room_1", raw, &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            (summary.total, summary.skipped, summary.imported),
+            (2, 1, 0)
+        );
+
+        let err = import_messages(&client, "room_2", raw, &options)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("belongs to room room_1"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
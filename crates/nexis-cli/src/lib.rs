@@ -1,14 +1,34 @@
+use std::borrow::Cow;
 use std::time::Duration;
-use std::{env, path::PathBuf};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use futures::{SinkExt, StreamExt};
+use nexis_core::archive::{RoomArchive, ARCHIVE_CONTENT_TYPE};
+use nexis_protocol::codec::Encoding;
+use nexis_protocol::{Capabilities, RoomId};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::time::timeout;
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 
+pub mod admin;
+pub mod eval;
+pub mod import;
+pub mod listen;
+pub mod profile;
+pub mod transcript;
+
+use admin::AdminCommands;
+use eval::EvalCommands;
+use import::{import_messages, Checkpoint, ImportOptions};
+use profile::{CliConfig, Connection};
+use transcript::{DateRange, MessagePage, TranscriptFormat};
+
 pub fn crate_name() -> &'static str {
     "nexis-cli"
 }
@@ -24,16 +44,44 @@ pub struct Cli {
     #[arg(
         long,
         global = true,
-        default_value = "http://127.0.0.1:8080",
-        help = "Control Plane base HTTP URL"
+        help = "Control Plane base HTTP URL [default: from the profile, else http://127.0.0.1:8080]"
     )]
-    pub server: String,
+    pub server: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        help = "Config profile to use instead of the current one"
+    )]
+    pub profile: Option<String>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Output format; `json` prints one JSON document per command"
+    )]
+    pub output: OutputFormat,
     #[command(subcommand)]
     pub command: Commands,
 }
 
+/// How command results are written to stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    /// Machine-readable JSON with stable field names, for scripts.
+    Json,
+}
+
 #[derive(Debug, Clone, Subcommand)]
 pub enum Commands {
+    #[command(about = "Log in with a member secret and store the tokens in the profile")]
+    Login {
+        #[arg(help = "Member ID, e.g. nexis:human:alice@example.com")]
+        member_id: String,
+    },
+    #[command(about = "Revoke the profile's tokens and forget them")]
+    Logout,
     #[command(about = "Create a room")]
     CreateRoom {
         #[arg(help = "Room name")]
@@ -50,6 +98,11 @@ pub enum Commands {
         #[arg(help = "Message body")]
         text: String,
     },
+    #[command(about = "Show a room and its messages")]
+    GetRoom {
+        #[arg(help = "Room ID")]
+        room_id: String,
+    },
     #[command(about = "Connect to WebSocket endpoint")]
     Connect {
         #[arg(long, default_value = "ws://127.0.0.1:8080/ws", help = "WebSocket URL")]
@@ -63,9 +116,32 @@ pub enum Commands {
         )]
         timeout_ms: u64,
     },
+    #[command(about = "Print a room's messages and events live")]
+    Listen {
+        #[arg(help = "Room ID")]
+        room_id: String,
+        #[arg(long, help = "WebSocket URL (defaults to the server's /ws endpoint)")]
+        url: Option<String>,
+        #[arg(
+            long,
+            default_value_t = 30_000,
+            help = "Longest delay between reconnect attempts in milliseconds"
+        )]
+        max_backoff_ms: u64,
+        #[arg(
+            long,
+            default_value = "json",
+            help = "Frame encoding to request: json, or msgpack for binary frames"
+        )]
+        encoding: Encoding,
+    },
     #[command(about = "Test AI provider connection")]
     TestProvider {
-        #[arg(short, long, help = "Provider to test (openai or anthropic)")]
+        #[arg(
+            short,
+            long,
+            help = "Provider to test (openai, anthropic, ollama or vllm)"
+        )]
         provider: String,
         #[arg(short, long, help = "Prompt to send")]
         prompt: String,
@@ -83,11 +159,120 @@ pub enum Commands {
         #[arg(long, help = "Minimum similarity score (0.0-1.0)")]
         min_score: Option<f32>,
     },
+    #[command(about = "Find messages similar to a message")]
+    Similar {
+        #[arg(help = "Message ID or search result ID")]
+        message_id: String,
+        #[arg(long, default_value_t = 10, help = "Maximum number of results")]
+        limit: usize,
+        #[arg(long, help = "Filter by room ID")]
+        room: Option<String>,
+        #[arg(long, help = "Minimum similarity score (0.0-1.0)")]
+        min_score: Option<f32>,
+    },
+    #[command(about = "Export a room to a JSONL archive")]
+    ExportRoom {
+        #[arg(help = "Room ID")]
+        room_id: String,
+        #[arg(long, short, help = "Write the archive to this file instead of stdout")]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Export a room's messages as a transcript")]
+    Export {
+        #[arg(help = "Room ID")]
+        room_id: String,
+        #[arg(long, value_enum, default_value_t = TranscriptFormat::Jsonl)]
+        format: TranscriptFormat,
+        #[arg(
+            long,
+            value_parser = transcript::parse_since,
+            help = "Only messages from this date (YYYY-MM-DD) or RFC 3339 time on"
+        )]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        #[arg(
+            long,
+            value_parser = transcript::parse_until,
+            help = "Only messages up to this date (inclusive) or before this RFC 3339 time"
+        )]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        #[arg(
+            long,
+            short,
+            help = "Write the transcript to this file instead of stdout"
+        )]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Import a room from a JSONL archive (admin only)")]
+    ImportRoom {
+        #[arg(help = "Archive file produced by export-room")]
+        file: PathBuf,
+    },
+    #[command(about = "Post messages from a JSONL file into a room")]
+    Import {
+        #[arg(help = "Room ID")]
+        room_id: String,
+        #[arg(help = "JSONL file with one {sender, text, timestamp?} object per line")]
+        file: PathBuf,
+        #[arg(long, default_value_t = 1, help = "Messages to post at once")]
+        concurrency: usize,
+        #[arg(long, help = "Validate the file without posting anything")]
+        dry_run: bool,
+        #[arg(long, help = "Checkpoint file (defaults to <file>.checkpoint)")]
+        checkpoint: Option<PathBuf>,
+    },
+    #[command(about = "Invite the members listed in a CSV file to a room")]
+    InviteBulk {
+        #[arg(help = "Room ID")]
+        room_id: String,
+        #[arg(help = "CSV file with a member ID or email address at the start of each line")]
+        file: PathBuf,
+    },
+    #[command(about = "Show a member's directory profile")]
+    Whois {
+        #[arg(help = "Member ID, e.g. nexis:agent:openai/gpt-4o")]
+        member_id: String,
+    },
+    #[command(about = "Server management; requires an admin member's token")]
+    Admin {
+        #[command(subcommand)]
+        command: AdminCommands,
+    },
     #[command(about = "Manage Agent role configurations")]
     Agent {
         #[command(subcommand)]
         command: AgentCommands,
     },
+    #[command(about = "Score an AI provider against golden prompts")]
+    Eval {
+        #[command(subcommand)]
+        command: EvalCommands,
+    },
+    #[command(about = "Manage connection profiles in the CLI config file")]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigCommands {
+    #[command(about = "Set a key on the selected profile; an empty value clears it")]
+    Set {
+        #[arg(help = "server, member, token or refresh-token")]
+        key: String,
+        #[arg(help = "New value")]
+        value: String,
+    },
+    #[command(about = "Show the selected profile, or a single key of it")]
+    Get {
+        #[arg(help = "server, member, token or refresh-token")]
+        key: Option<String>,
+    },
+    #[command(about = "Make a profile the default for later commands")]
+    UseProfile {
+        #[arg(help = "Profile name")]
+        name: String,
+    },
 }
 
 #[derive(Debug, Clone, Subcommand)]
@@ -113,7 +298,7 @@ pub struct AgentRunArgs {
     #[arg(
         long,
         default_value = "openai",
-        help = "Provider to use (openai or anthropic)"
+        help = "Provider to use (openai, anthropic, ollama or vllm)"
     )]
     pub provider: String,
     #[arg(long, short, help = "Use streaming")]
@@ -130,6 +315,8 @@ pub enum CliError {
     HttpTransport(String),
     #[error("http status {status}: {body}")]
     HttpStatus { status: u16, body: String },
+    #[error("slow mode is on in this room; try again in {retry_after_secs}s")]
+    SlowMode { retry_after_secs: u64 },
     #[error("json decode error: {0}")]
     Decode(String),
     #[error("websocket error: {0}")]
@@ -138,11 +325,26 @@ pub enum CliError {
     WebSocketTimeout { timeout_ms: u64 },
     #[error("connection closed before receiving a websocket frame")]
     WebSocketClosed,
+    #[error("config error: {0}")]
+    Config(String),
+}
+
+/// Parse a room id argument, e.g. `room_general`.
+pub fn parse_room_id(value: &str) -> Result<RoomId, CliError> {
+    if value.trim().is_empty() {
+        return Err(CliError::InvalidArgument(
+            "room id cannot be empty".to_string(),
+        ));
+    }
+    value
+        .parse()
+        .map_err(|err| CliError::InvalidArgument(format!("invalid room id '{value}': {err}")))
 }
 
 #[derive(Debug, Clone)]
 pub struct CliClient {
     base_url: String,
+    token: Option<String>,
     http: reqwest::Client,
 }
 
@@ -153,7 +355,7 @@ struct CreateRoomRequest {
     topic: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateRoomResponse {
     pub id: String,
     pub name: String,
@@ -167,14 +369,64 @@ struct SendMessageRequest {
     text: String,
     #[serde(rename = "replyTo", skip_serializing_if = "Option::is_none")]
     reply_to: Option<String>,
+    #[serde(rename = "streamId", skip_serializing_if = "Option::is_none")]
+    stream_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendMessageResponse {
     pub id: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenStreamResponse {
+    stream_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AppendStreamRequest<'a> {
+    delta: &'a str,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LoginRequest {
+    member_id: String,
+    secret: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginResponse {
+    pub member_id: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LogoutRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenPairResponse {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredMessage {
     pub id: String,
     pub sender: String,
@@ -182,7 +434,7 @@ pub struct StoredMessage {
     pub reply_to: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoomInfoResponse {
     pub id: String,
     pub name: String,
@@ -191,6 +443,14 @@ pub struct RoomInfoResponse {
     pub messages: Vec<StoredMessage>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRoomResponse {
+    pub id: String,
+    pub name: String,
+    pub members: usize,
+    pub messages: usize,
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct InviteMemberRequest {
     #[serde(rename = "memberId")]
@@ -205,6 +465,119 @@ pub struct InviteMemberResponse {
     pub member_id: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkInviteResponse {
+    pub room_id: String,
+    pub invited: usize,
+    pub failed: usize,
+    pub results: Vec<BulkInviteResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkInviteResult {
+    pub entry: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member_id: Option<String>,
+    /// `invited`, `already_member` or `failed`.
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct JoinRoomRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JoinRoomResponse {
+    pub room_id: String,
+    pub member_id: String,
+    /// `joined`, or `pending` while the join request awaits approval.
+    pub status: String,
+}
+
+impl JoinRoomResponse {
+    pub fn is_joined(&self) -> bool {
+        self.status == "joined"
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateInvitationRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_in_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvitationResponse {
+    pub token: String,
+    pub room_id: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// An alternative generation of an AI reply.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegenerateResponse {
+    pub message_id: String,
+    pub content: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub branch: MessageBranch,
+}
+
+/// Place of a regenerated AI reply among the generations of the same reply.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageBranch {
+    /// Id of the first generation.
+    pub origin_id: String,
+    /// Position among the generations; the first one is 0.
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomSettingsResponse {
+    pub room_id: String,
+    #[serde(default)]
+    pub ai_provider: Option<String>,
+    #[serde(default)]
+    pub ai_model: Option<String>,
+    /// Models `@ai` may pick in the room; any model when empty.
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    /// Seconds each member waits between messages; off when unset.
+    #[serde(default)]
+    pub slow_mode_secs: Option<u64>,
+}
+
+impl RoomSettingsResponse {
+    pub fn model_allowed(&self, model: &str) -> bool {
+        self.allowed_models.is_empty() || self.allowed_models.iter().any(|m| m == model)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberProfileResponse {
+    pub id: String,
+    pub member_type: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub public_key: Option<String>,
+    #[serde(default)]
+    pub capabilities: Capabilities,
+    pub created_at: String,
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct SearchRequest {
     query: String,
@@ -212,28 +585,69 @@ struct SearchRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     min_score: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    room_id: Option<uuid::Uuid>,
+    room_id: Option<RoomId>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResponse {
     pub query: String,
     pub results: Vec<SearchResultItem>,
     pub total: usize,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResultItem {
     pub id: uuid::Uuid,
     pub score: f32,
     pub content: String,
-    pub room_id: Option<uuid::Uuid>,
+    pub room_id: Option<RoomId>,
+}
+
+/// `test-provider` result as printed by `--output json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderTestResponse {
+    pub provider: String,
+    pub model: Option<String>,
+    pub content: String,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ConnectOutput {
+    reply: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ExportOutput {
+    room_id: String,
+    path: PathBuf,
+    records: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AgentSummary {
+    id: String,
+    name: String,
+    role: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AgentListOutput {
+    dir: PathBuf,
+    agents: Vec<AgentSummary>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AgentRunOutput {
+    agent: String,
+    content: String,
 }
 
 impl CliClient {
     pub fn new(base_url: impl Into<String>) -> Self {
         Self {
             base_url: base_url.into(),
+            token: None,
             http: reqwest::Client::builder()
                 .timeout(Duration::from_secs(15))
                 .build()
@@ -241,10 +655,75 @@ impl CliClient {
         }
     }
 
+    /// Client for a resolved profile connection.
+    pub fn for_connection(connection: &Connection) -> Self {
+        Self::new(connection.server.clone()).with_token(connection.token.clone())
+    }
+
+    /// Send `token` as a bearer token on every request.
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
     fn endpoint(&self, path: &str) -> String {
         format!("{}{}", self.base_url.trim_end_matches('/'), path)
     }
 
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.http.request(method, self.endpoint(path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Trade a member id and its secret for an access/refresh token pair.
+    pub async fn login(&self, member_id: &str, secret: &str) -> Result<LoginResponse, CliError> {
+        if member_id.trim().is_empty() {
+            return Err(CliError::InvalidArgument(
+                "member id cannot be empty".to_string(),
+            ));
+        }
+        if secret.is_empty() {
+            return Err(CliError::InvalidArgument(
+                "secret cannot be empty".to_string(),
+            ));
+        }
+        let payload = LoginRequest {
+            member_id: member_id.to_string(),
+            secret: secret.to_string(),
+        };
+        self.post_json("/v1/auth/login", &payload).await
+    }
+
+    /// Revoke the client's access token and, when given, its refresh token.
+    pub async fn logout(&self, refresh_token: Option<String>) -> Result<(), CliError> {
+        let response = self
+            .request(reqwest::Method::POST, "/v1/auth/logout")
+            .json(&LogoutRequest { refresh_token })
+            .send()
+            .await
+            .map_err(|err| CliError::HttpTransport(err.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "<unable to read body>".to_string());
+            return Err(CliError::HttpStatus { status, body });
+        }
+        Ok(())
+    }
+
+    /// Exchange a refresh token for a new access/refresh token pair.
+    pub async fn refresh_token(&self, refresh_token: &str) -> Result<TokenPairResponse, CliError> {
+        let payload = RefreshRequest {
+            refresh_token: refresh_token.to_string(),
+        };
+        self.post_json("/v1/auth/refresh", &payload).await
+    }
+
     pub async fn create_room(
         &self,
         name: String,
@@ -277,11 +756,7 @@ impl CliClient {
         text: String,
         reply_to: Option<String>,
     ) -> Result<SendMessageResponse, CliError> {
-        if room_id.trim().is_empty() {
-            return Err(CliError::InvalidArgument(
-                "room id cannot be empty".to_string(),
-            ));
-        }
+        parse_room_id(&room_id)?;
         if sender.trim().is_empty() {
             return Err(CliError::InvalidArgument(
                 "sender cannot be empty".to_string(),
@@ -298,6 +773,70 @@ impl CliClient {
             sender,
             text,
             reply_to,
+            stream_id: None,
+        };
+        self.post_json("/v1/messages", &payload)
+            .await
+            .map_err(slow_mode_error)
+    }
+
+    /// Open a stream for a message the logged-in member is still writing in
+    /// a room. Returns the stream id, which becomes the message's id.
+    pub async fn open_stream(&self, room_id: &str) -> Result<String, CliError> {
+        parse_room_id(room_id)?;
+        let response: OpenStreamResponse = self
+            .post_json(
+                &format!("/v1/rooms/{room_id}/streams"),
+                &serde_json::json!({}),
+            )
+            .await?;
+        Ok(response.stream_id)
+    }
+
+    /// Push the next chunk of a streamed message to the room's members.
+    pub async fn append_stream(
+        &self,
+        room_id: &str,
+        stream_id: &str,
+        delta: &str,
+    ) -> Result<(), CliError> {
+        let path = format!("/v1/rooms/{room_id}/streams/{stream_id}/append");
+        self.send_expecting_success(
+            reqwest::Method::POST,
+            &path,
+            Some(&AppendStreamRequest { delta }),
+        )
+        .await
+    }
+
+    /// Abandon a streamed message without sending it.
+    pub async fn abort_stream(&self, room_id: &str, stream_id: &str) -> Result<(), CliError> {
+        let path = format!("/v1/rooms/{room_id}/streams/{stream_id}");
+        self.send_expecting_success::<()>(reqwest::Method::DELETE, &path, None)
+            .await
+    }
+
+    /// Send the finished text of a streamed message as `sender`, the member
+    /// that opened the stream.
+    pub async fn finish_stream(
+        &self,
+        room_id: String,
+        stream_id: String,
+        sender: String,
+        text: String,
+    ) -> Result<SendMessageResponse, CliError> {
+        parse_room_id(&room_id)?;
+        if text.trim().is_empty() {
+            return Err(CliError::InvalidArgument(
+                "message text cannot be empty".to_string(),
+            ));
+        }
+        let payload = SendMessageRequest {
+            room_id,
+            sender,
+            text,
+            reply_to: None,
+            stream_id: Some(stream_id),
         };
         self.post_json("/v1/messages", &payload).await
     }
@@ -319,12 +858,52 @@ impl CliClient {
     }
 
     pub async fn get_room(&self, room_id: &str) -> Result<RoomInfoResponse, CliError> {
-        if room_id.trim().is_empty() {
+        parse_room_id(room_id)?;
+        self.get_json(&format!("/v1/rooms/{room_id}")).await
+    }
+
+    /// Generate an alternative to an AI reply, posted to the reply's room as
+    /// its sibling.
+    pub async fn regenerate_message(
+        &self,
+        message_id: &str,
+    ) -> Result<RegenerateResponse, CliError> {
+        if message_id.trim().is_empty() {
             return Err(CliError::InvalidArgument(
-                "room id cannot be empty".to_string(),
+                "message id cannot be empty".to_string(),
             ));
         }
-        self.get_json(&format!("/v1/rooms/{room_id}")).await
+        let path = format!("/v1/messages/{message_id}/regenerate");
+        self.post_json(&path, &serde_json::json!({})).await
+    }
+
+    pub async fn room_settings(&self, room_id: &str) -> Result<RoomSettingsResponse, CliError> {
+        parse_room_id(room_id)?;
+        self.get_json(&format!("/v1/rooms/{room_id}/settings"))
+            .await
+    }
+
+    /// One page of a room's messages, oldest first, starting after the
+    /// message id `after`.
+    pub async fn message_page(
+        &self,
+        room_id: &str,
+        after: Option<&str>,
+        range: DateRange,
+        limit: usize,
+    ) -> Result<MessagePage, CliError> {
+        parse_room_id(room_id)?;
+        let mut path = format!("/v1/rooms/{room_id}/messages?limit={limit}");
+        if let Some(after) = after {
+            path.push_str(&format!("&after={after}"));
+        }
+        for (name, bound) in [("since", range.since), ("until", range.until)] {
+            if let Some(bound) = bound {
+                let bound = bound.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true);
+                path.push_str(&format!("&{name}={bound}"));
+            }
+        }
+        self.get_json(&path).await
     }
 
     pub async fn invite_member(
@@ -332,11 +911,7 @@ impl CliClient {
         room_id: &str,
         member_id: &str,
     ) -> Result<InviteMemberResponse, CliError> {
-        if room_id.trim().is_empty() {
-            return Err(CliError::InvalidArgument(
-                "room id cannot be empty".to_string(),
-            ));
-        }
+        parse_room_id(room_id)?;
         if member_id.trim().is_empty() {
             return Err(CliError::InvalidArgument(
                 "member id cannot be empty".to_string(),
@@ -349,11 +924,96 @@ impl CliClient {
             .await
     }
 
+    /// Invite the members listed in `csv`, one member ID or email address
+    /// at the start of each line, reporting each entry's outcome.
+    pub async fn invite_bulk(
+        &self,
+        room_id: &str,
+        csv: String,
+    ) -> Result<BulkInviteResponse, CliError> {
+        parse_room_id(room_id)?;
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("/v1/rooms/{room_id}/invite/bulk"),
+            )
+            .header("content-type", "text/csv")
+            .body(csv)
+            .send()
+            .await
+            .map_err(|err| CliError::HttpTransport(err.to_string()))?;
+        if response.status() != StatusCode::OK {
+            let status = response.status().as_u16();
+            let body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "<unable to read body>".to_string());
+            return Err(CliError::HttpStatus { status, body });
+        }
+        response
+            .json::<BulkInviteResponse>()
+            .await
+            .map_err(|err| CliError::Decode(err.to_string()))
+    }
+
+    /// Join a room, presenting an invitation token if the room needs one.
+    pub async fn join_room(
+        &self,
+        room_id: &str,
+        token: Option<&str>,
+    ) -> Result<JoinRoomResponse, CliError> {
+        parse_room_id(room_id)?;
+        let payload = JoinRoomRequest {
+            token: token.map(str::to_string),
+        };
+        self.post_json(&format!("/v1/rooms/{room_id}/join"), &payload)
+            .await
+    }
+
+    /// Create a single-use invitation to a room (admin only).
+    pub async fn create_invitation(
+        &self,
+        room_id: &str,
+        expires_in_secs: Option<u64>,
+    ) -> Result<InvitationResponse, CliError> {
+        parse_room_id(room_id)?;
+        let payload = CreateInvitationRequest { expires_in_secs };
+        self.post_json(&format!("/v1/rooms/{room_id}/invitations"), &payload)
+            .await
+    }
+
+    /// Approve a member's request to join a room (admin only).
+    pub async fn approve_join(
+        &self,
+        room_id: &str,
+        member_id: &str,
+    ) -> Result<JoinRoomResponse, CliError> {
+        parse_room_id(room_id)?;
+        if member_id.trim().is_empty() {
+            return Err(CliError::InvalidArgument(
+                "member id cannot be empty".to_string(),
+            ));
+        }
+        let path = format!("/v1/rooms/{room_id}/join-requests/{member_id}/approve");
+        self.post_json(&path, &serde_json::json!({})).await
+    }
+
+    pub async fn get_member(&self, member_id: &str) -> Result<MemberProfileResponse, CliError> {
+        if member_id.trim().is_empty() {
+            return Err(CliError::InvalidArgument(
+                "member id cannot be empty".to_string(),
+            ));
+        }
+        // Agent identifiers may contain `/`.
+        let member_id = member_id.replace('/', "%2F");
+        self.get_json(&format!("/v1/members/{member_id}")).await
+    }
+
     pub async fn search(
         &self,
         query: &str,
         limit: usize,
-        room_id: Option<uuid::Uuid>,
+        room_id: Option<RoomId>,
         min_score: Option<f32>,
     ) -> Result<SearchResponse, CliError> {
         if query.trim().is_empty() {
@@ -370,20 +1030,94 @@ impl CliClient {
         self.post_json("/v1/search", &payload).await
     }
 
+    /// Messages semantically close to `message_id`, excluding the message itself.
+    pub async fn similar(
+        &self,
+        message_id: &str,
+        limit: usize,
+        room_id: Option<&str>,
+        min_score: Option<f32>,
+    ) -> Result<SearchResponse, CliError> {
+        if message_id.trim().is_empty() {
+            return Err(CliError::InvalidArgument(
+                "message id cannot be empty".to_string(),
+            ));
+        }
+        let mut path = format!("/v1/messages/{message_id}/similar?limit={limit}");
+        if let Some(room_id) = room_id {
+            let room_id = parse_room_id(room_id)?;
+            path.push_str(&format!("&room_id={room_id}"));
+        }
+        if let Some(min_score) = min_score {
+            path.push_str(&format!("&min_score={min_score}"));
+        }
+        self.get_json(&path).await
+    }
+
+    /// Download a room archive as raw JSONL.
+    pub async fn export_room(&self, room_id: &str) -> Result<String, CliError> {
+        parse_room_id(room_id)?;
+        let response = self
+            .request(reqwest::Method::GET, &format!("/v1/rooms/{room_id}/export"))
+            .send()
+            .await
+            .map_err(|err| CliError::HttpTransport(err.to_string()))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|err| CliError::Decode(err.to_string()))?;
+        if status != StatusCode::OK {
+            return Err(CliError::HttpStatus {
+                status: status.as_u16(),
+                body,
+            });
+        }
+        Ok(body)
+    }
+
+    /// Validate an archive locally, then upload it to recreate the room.
+    pub async fn import_room(&self, archive: String) -> Result<ImportRoomResponse, CliError> {
+        RoomArchive::from_jsonl(&archive)
+            .map_err(|err| CliError::InvalidArgument(format!("invalid archive: {err}")))?;
+
+        let response = self
+            .request(reqwest::Method::POST, "/v1/rooms/import")
+            .header("content-type", ARCHIVE_CONTENT_TYPE)
+            .body(archive)
+            .send()
+            .await
+            .map_err(|err| CliError::HttpTransport(err.to_string()))?;
+        if response.status() != StatusCode::CREATED {
+            let status = response.status().as_u16();
+            let body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "<unable to read body>".to_string());
+            return Err(CliError::HttpStatus { status, body });
+        }
+        response
+            .json::<ImportRoomResponse>()
+            .await
+            .map_err(|err| CliError::Decode(err.to_string()))
+    }
+
     async fn post_json<TReq, TRes>(&self, path: &str, payload: &TReq) -> Result<TRes, CliError>
     where
         TReq: Serialize + Sync,
         TRes: for<'de> Deserialize<'de>,
     {
         let response = self
-            .http
-            .post(self.endpoint(path))
+            .request(reqwest::Method::POST, path)
             .json(payload)
             .send()
             .await
             .map_err(|err| CliError::HttpTransport(err.to_string()))?;
 
-        if response.status() != StatusCode::OK && response.status() != StatusCode::CREATED {
+        if !matches!(
+            response.status(),
+            StatusCode::OK | StatusCode::CREATED | StatusCode::ACCEPTED
+        ) {
             let status = response.status().as_u16();
             let body = response
                 .text()
@@ -398,13 +1132,41 @@ impl CliClient {
             .map_err(|err| CliError::Decode(err.to_string()))
     }
 
+    /// Send a request whose response carries no body worth reading.
+    async fn send_expecting_success<TReq>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        payload: Option<&TReq>,
+    ) -> Result<(), CliError>
+    where
+        TReq: Serialize + Sync,
+    {
+        let mut request = self.request(method, path);
+        if let Some(payload) = payload {
+            request = request.json(payload);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|err| CliError::HttpTransport(err.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "<unable to read body>".to_string());
+            return Err(CliError::HttpStatus { status, body });
+        }
+        Ok(())
+    }
+
     async fn get_json<TRes>(&self, path: &str) -> Result<TRes, CliError>
     where
         TRes: for<'de> Deserialize<'de>,
     {
         let response = self
-            .http
-            .get(self.endpoint(path))
+            .request(reqwest::Method::GET, path)
             .send()
             .await
             .map_err(|err| CliError::HttpTransport(err.to_string()))?;
@@ -425,6 +1187,41 @@ impl CliClient {
     }
 }
 
+/// Line editor helper that echoes `*` instead of the typed characters.
+struct MaskedInput;
+
+impl rustyline::Helper for MaskedInput {}
+impl rustyline::completion::Completer for MaskedInput {
+    type Candidate = String;
+}
+impl rustyline::hint::Hinter for MaskedInput {
+    type Hint = String;
+}
+impl rustyline::validate::Validator for MaskedInput {}
+impl rustyline::highlight::Highlighter for MaskedInput {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        Cow::Owned("*".repeat(line.chars().count()))
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+        true
+    }
+}
+
+/// Login secret from `NEXIS_SECRET`, else read from the terminal without
+/// echoing it (or from stdin when it is piped).
+pub fn read_secret(prompt: &str) -> Result<String, CliError> {
+    if let Ok(secret) = env::var("NEXIS_SECRET") {
+        return Ok(secret);
+    }
+    let mut editor = rustyline::Editor::<MaskedInput, rustyline::history::DefaultHistory>::new()
+        .map_err(|err| CliError::InvalidArgument(format!("cannot read secret: {err}")))?;
+    editor.set_helper(Some(MaskedInput));
+    editor
+        .readline(prompt)
+        .map_err(|err| CliError::InvalidArgument(format!("cannot read secret: {err}")))
+}
+
 pub async fn connect_websocket_once(
     url: &str,
     message: Option<String>,
@@ -451,26 +1248,143 @@ pub async fn connect_websocket_once(
             Some(Ok(_)) => Ok(None),
             Some(Err(err)) => Err(CliError::WebSocket(err.to_string())),
         }
-    } else {
-        Ok(None)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Render `value` for `format`: as JSON, or through `text` for people.
+fn render<T: Serialize>(
+    format: OutputFormat,
+    value: &T,
+    text: impl FnOnce(&T) -> String,
+) -> Result<String, CliError> {
+    match format {
+        OutputFormat::Text => Ok(text(value)),
+        OutputFormat::Json => {
+            serde_json::to_string(value).map_err(|err| CliError::Decode(err.to_string()))
+        }
+    }
+}
+
+/// [`CliError::SlowMode`] for a message refused by the room's slow mode.
+fn slow_mode_error(err: CliError) -> CliError {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct SlowModeBody {
+        code: String,
+        retry_after_secs: u64,
+    }
+
+    match &err {
+        CliError::HttpStatus { status: 429, body } => {
+            match serde_json::from_str::<SlowModeBody>(body) {
+                Ok(body) if body.code == "SLOW_MODE" => CliError::SlowMode {
+                    retry_after_secs: body.retry_after_secs,
+                },
+                _ => err,
+            }
+        }
+        _ => err,
+    }
+}
+
+/// `--output json` form of a failed command, written to stderr.
+pub fn error_json(err: &CliError) -> String {
+    let mut body = serde_json::json!({ "error": err.to_string() });
+    if let CliError::HttpStatus { status, .. } = err {
+        body["status"] = serde_json::json!(status);
+    }
+    body.to_string()
+}
+
+pub async fn run(cli: Cli) -> Result<String, CliError> {
+    run_with_config(cli, &CliConfig::default_path()).await
+}
+
+/// Run `cli` with profiles read from `config_path`. A request rejected with
+/// 401 is retried once after refreshing the profile's token; the new pair is
+/// written back to the profile.
+pub async fn run_with_config(cli: Cli, config_path: &Path) -> Result<String, CliError> {
+    let mut config = CliConfig::load(config_path)?;
+    if let Commands::Config { command } = cli.command {
+        return run_config_command(
+            command,
+            &mut config,
+            config_path,
+            cli.profile.as_deref(),
+            cli.output,
+        );
+    }
+
+    let connection = config.resolve(cli.profile.as_deref(), cli.server.as_deref(), |key| {
+        env::var(key).ok()
+    });
+    match cli.command {
+        Commands::Login { member_id } => {
+            let secret = read_secret("secret: ")?;
+            return login(
+                &mut config,
+                config_path,
+                &connection,
+                &member_id,
+                &secret,
+                cli.output,
+            )
+            .await;
+        }
+        Commands::Logout => return logout(&mut config, config_path, &connection, cli.output).await,
+        _ => {}
+    }
+    let result = run_command(cli.clone(), &connection).await;
+    let refresh_token = config
+        .profile(&connection.profile)
+        .filter(|profile| profile.token == connection.token)
+        .and_then(|profile| profile.refresh_token.clone());
+    match (result, refresh_token) {
+        (Err(CliError::HttpStatus { status: 401, .. }), Some(refresh_token)) => {
+            let pair = CliClient::new(connection.server.clone())
+                .refresh_token(&refresh_token)
+                .await?;
+            config.store_tokens(
+                &connection.profile,
+                pair.access_token.clone(),
+                Some(pair.refresh_token),
+            );
+            config.save(config_path)?;
+            let connection = Connection {
+                token: Some(pair.access_token),
+                ..connection
+            };
+            run_command(cli, &connection).await
+        }
+        (result, _) => result,
     }
 }
 
-pub async fn run(cli: Cli) -> Result<String, CliError> {
+async fn run_command(cli: Cli, connection: &Connection) -> Result<String, CliError> {
+    let format = cli.output;
     match cli.command {
         Commands::CreateRoom { name, topic } => {
-            let client = CliClient::new(cli.server);
+            let client = CliClient::for_connection(connection);
             let created = client.create_room(name, topic).await?;
-            Ok(format!("room created: {} ({})", created.id, created.name))
+            render(format, &created, |created| {
+                format!("room created: {} ({})", created.id, created.name)
+            })
         }
         Commands::SendMessage {
             room_id,
             sender,
             text,
         } => {
-            let client = CliClient::new(cli.server);
+            let client = CliClient::for_connection(connection);
             let sent = client.send_message(room_id, sender, text).await?;
-            Ok(format!("message sent: {}", sent.id))
+            render(format, &sent, |sent| format!("message sent: {}", sent.id))
+        }
+        Commands::GetRoom { room_id } => {
+            let client = CliClient::for_connection(connection);
+            let room = client.get_room(&room_id).await?;
+            render(format, &room, format_room)
         }
         Commands::Connect {
             url,
@@ -478,47 +1392,463 @@ pub async fn run(cli: Cli) -> Result<String, CliError> {
             timeout_ms,
         } => {
             let reply = connect_websocket_once(&url, message, timeout_ms).await?;
-            match reply {
-                Some(text) => Ok(format!("ws reply: {text}")),
-                None => Ok("ws connected".to_string()),
-            }
+            render(format, &ConnectOutput { reply }, |output| {
+                match &output.reply {
+                    Some(text) => format!("ws reply: {text}"),
+                    None => "ws connected".to_string(),
+                }
+            })
+        }
+        Commands::Listen {
+            room_id,
+            url,
+            max_backoff_ms,
+            encoding,
+        } => {
+            parse_room_id(&room_id)?;
+            let url = url.unwrap_or_else(|| listen::websocket_url(&connection.server));
+            let url = listen::with_encoding(&url, encoding);
+            listen::listen(
+                &url,
+                connection.token.as_deref(),
+                &room_id,
+                format,
+                Duration::from_millis(max_backoff_ms),
+            )
+            .await
         }
         Commands::TestProvider {
             provider,
             prompt,
             stream,
-        } => test_provider(&provider, &prompt, stream).await,
+        } => test_provider(&provider, &prompt, stream, format).await,
         Commands::Search {
             query,
             limit,
             room,
             min_score,
         } => {
-            let client = CliClient::new(cli.server);
-            let room_id = room.and_then(|r| r.parse::<uuid::Uuid>().ok());
+            let client = CliClient::for_connection(connection);
+            let room_id = room.as_deref().map(parse_room_id).transpose()?;
             let response = client.search(&query, limit, room_id, min_score).await?;
-            let mut output = format!("Search results for: {}\n\n", response.query);
-            if response.results.is_empty() {
-                output.push_str("No results found.\n");
-            } else {
-                for (i, result) in response.results.iter().enumerate() {
-                    output.push_str(&format!(
-                        "{}. [score: {:.3}] {}\n",
-                        i + 1,
-                        result.score,
-                        result.content.chars().take(100).collect::<String>()
-                    ));
-                    if let Some(room_id) = result.room_id {
-                        output.push_str(&format!("   Room: {}\n", room_id));
-                    }
-                    output.push('\n');
+            render(format, &response, |response| {
+                format_search_results(&format!("Search results for: {}", response.query), response)
+            })
+        }
+        Commands::Similar {
+            message_id,
+            limit,
+            room,
+            min_score,
+        } => {
+            let client = CliClient::for_connection(connection);
+            let response = client
+                .similar(&message_id, limit, room.as_deref(), min_score)
+                .await?;
+            render(format, &response, |response| {
+                format_search_results(
+                    &format!("Messages similar to {message_id}: {}", response.query),
+                    response,
+                )
+            })
+        }
+        Commands::ExportRoom { room_id, output } => {
+            let client = CliClient::for_connection(connection);
+            let archive = client.export_room(&room_id).await?;
+            match output {
+                Some(path) => {
+                    tokio::fs::write(&path, &archive).await.map_err(|err| {
+                        CliError::InvalidArgument(format!(
+                            "failed to write {}: {err}",
+                            path.display()
+                        ))
+                    })?;
+                    let exported = ExportOutput {
+                        room_id,
+                        path,
+                        records: archive.lines().count(),
+                    };
+                    render(format, &exported, |exported| {
+                        format!(
+                            "room exported: {} -> {} ({} records)",
+                            exported.room_id,
+                            exported.path.display(),
+                            exported.records
+                        )
+                    })
+                }
+                // The archive is JSONL already, in either format.
+                None => Ok(archive.trim_end().to_string()),
+            }
+        }
+        Commands::Export {
+            room_id,
+            format: transcript_format,
+            since,
+            until,
+            output,
+        } => {
+            let client = CliClient::for_connection(connection);
+            let messages =
+                transcript::fetch_transcript(&client, &room_id, DateRange { since, until }).await?;
+            let rendered = transcript::render_transcript(
+                &room_id,
+                &messages,
+                transcript_format,
+                &connection.server,
+            )?;
+            match output {
+                Some(path) => {
+                    tokio::fs::write(&path, &rendered).await.map_err(|err| {
+                        CliError::InvalidArgument(format!(
+                            "failed to write {}: {err}",
+                            path.display()
+                        ))
+                    })?;
+                    let exported = ExportOutput {
+                        room_id,
+                        path,
+                        records: messages.len(),
+                    };
+                    render(format, &exported, |exported| {
+                        format!(
+                            "transcript exported: {} -> {} ({} messages)",
+                            exported.room_id,
+                            exported.path.display(),
+                            exported.records
+                        )
+                    })
+                }
+                // The transcript is already in the requested format.
+                None => Ok(rendered.trim_end().to_string()),
+            }
+        }
+        Commands::ImportRoom { file } => {
+            let archive = tokio::fs::read_to_string(&file).await.map_err(|err| {
+                CliError::InvalidArgument(format!("failed to read {}: {err}", file.display()))
+            })?;
+            let client = CliClient::for_connection(connection);
+            let imported = client.import_room(archive).await?;
+            render(format, &imported, |imported| {
+                format!(
+                    "room imported: {} ({}), {} members, {} messages",
+                    imported.id, imported.name, imported.members, imported.messages
+                )
+            })
+        }
+        Commands::Import {
+            room_id,
+            file,
+            concurrency,
+            dry_run,
+            checkpoint,
+        } => {
+            let raw = tokio::fs::read_to_string(&file).await.map_err(|err| {
+                CliError::InvalidArgument(format!("failed to read {}: {err}", file.display()))
+            })?;
+            let options = ImportOptions {
+                concurrency,
+                dry_run,
+                checkpoint: checkpoint.unwrap_or_else(|| Checkpoint::path_for(&file)),
+            };
+            let client = CliClient::for_connection(connection);
+            let summary = import_messages(&client, &room_id, &raw, &options).await?;
+            render(format, &summary, |summary| {
+                if summary.dry_run {
+                    format!(
+                        "import file is valid: {} message(s) to post to {}, {} already imported",
+                        summary.total - summary.skipped,
+                        summary.room_id,
+                        summary.skipped
+                    )
+                } else {
+                    format!(
+                        "imported {} message(s) into {}, {} already imported",
+                        summary.imported, summary.room_id, summary.skipped
+                    )
+                }
+            })
+        }
+        Commands::InviteBulk { room_id, file } => {
+            let csv = tokio::fs::read_to_string(&file).await.map_err(|err| {
+                CliError::InvalidArgument(format!("failed to read {}: {err}", file.display()))
+            })?;
+            let client = CliClient::for_connection(connection);
+            let report = client.invite_bulk(&room_id, csv).await?;
+            render(format, &report, format_bulk_invite)
+        }
+        Commands::Whois { member_id } => {
+            let client = CliClient::for_connection(connection);
+            let profile = client.get_member(&member_id).await?;
+            render(format, &profile, format_member_profile)
+        }
+        Commands::Admin { command } => {
+            let client = CliClient::for_connection(connection);
+            admin::run_admin_command(&client, command, format).await
+        }
+        Commands::Agent { command } => run_agent_command(command, format).await,
+        Commands::Eval { command } => eval::run_eval_command(command, format).await,
+        Commands::Config { .. } | Commands::Login { .. } | Commands::Logout => {
+            unreachable!("profile commands run before connecting")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LoginOutput {
+    profile: String,
+    member_id: String,
+    expires_in: u64,
+}
+
+/// Log `member_id` in and remember the issued tokens on the profile.
+pub async fn login(
+    config: &mut CliConfig,
+    config_path: &Path,
+    connection: &Connection,
+    member_id: &str,
+    secret: &str,
+    format: OutputFormat,
+) -> Result<String, CliError> {
+    let issued = CliClient::new(connection.server.clone())
+        .login(member_id, secret)
+        .await?;
+    config.store_tokens(
+        &connection.profile,
+        issued.access_token,
+        Some(issued.refresh_token),
+    );
+    config.profile_mut(&connection.profile).member = Some(issued.member_id.clone());
+    config.save(config_path)?;
+    let logged_in = LoginOutput {
+        profile: connection.profile.clone(),
+        member_id: issued.member_id,
+        expires_in: issued.expires_in,
+    };
+    render(format, &logged_in, |logged_in| {
+        format!(
+            "logged in as {} (profile {})",
+            logged_in.member_id, logged_in.profile
+        )
+    })
+}
+
+/// Revoke the profile's tokens on the gateway, then drop them locally even
+/// if the gateway could not be reached.
+pub async fn logout(
+    config: &mut CliConfig,
+    config_path: &Path,
+    connection: &Connection,
+    format: OutputFormat,
+) -> Result<String, CliError> {
+    let profile = config.profile_mut(&connection.profile);
+    let refresh_token = profile.refresh_token.take();
+    let revoked = match profile.token.take() {
+        Some(token) => CliClient::new(connection.server.clone())
+            .with_token(Some(token))
+            .logout(refresh_token)
+            .await
+            .is_ok(),
+        None => false,
+    };
+    config.save(config_path)?;
+    let logged_out = serde_json::json!({ "profile": connection.profile, "revoked": revoked });
+    render(format, &logged_out, |_| {
+        format!("logged out of profile {}", connection.profile)
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ConfigValueOutput {
+    profile: String,
+    key: String,
+    value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ProfileOutput {
+    profile: String,
+    current: bool,
+    server: Option<String>,
+    member: Option<String>,
+    has_token: bool,
+    has_refresh_token: bool,
+}
+
+fn run_config_command(
+    command: ConfigCommands,
+    config: &mut CliConfig,
+    config_path: &Path,
+    profile_flag: Option<&str>,
+    format: OutputFormat,
+) -> Result<String, CliError> {
+    let name = config.profile_name(profile_flag, |key| env::var(key).ok());
+    match command {
+        ConfigCommands::Set { key, value } => {
+            config.profile_mut(&name).set(&key, &value)?;
+            config.save(config_path)?;
+            let set = ConfigValueOutput {
+                value: config.profile_mut(&name).get(&key)?.map(str::to_string),
+                profile: name,
+                key,
+            };
+            render(format, &set, |set| match &set.value {
+                Some(_) => format!("{} set for profile {}", set.key, set.profile),
+                None => format!("{} cleared for profile {}", set.key, set.profile),
+            })
+        }
+        ConfigCommands::Get { key: Some(key) } => {
+            let profile = config.profile(&name).cloned().unwrap_or_default();
+            let value = ConfigValueOutput {
+                value: profile.get(&key)?.map(str::to_string),
+                profile: name,
+                key,
+            };
+            render(format, &value, |value| {
+                value.value.clone().unwrap_or_default()
+            })
+        }
+        ConfigCommands::Get { key: None } => {
+            let profile = config.profile(&name).cloned().unwrap_or_default();
+            let shown = ProfileOutput {
+                current: config.profile_name(None, |_| None) == name,
+                server: profile.server,
+                member: profile.member,
+                has_token: profile.token.is_some(),
+                has_refresh_token: profile.refresh_token.is_some(),
+                profile: name,
+            };
+            render(format, &shown, format_profile)
+        }
+        ConfigCommands::UseProfile { name } => {
+            if name.trim().is_empty() {
+                return Err(CliError::InvalidArgument(
+                    "profile name cannot be empty".to_string(),
+                ));
+            }
+            config.current_profile = Some(name.clone());
+            config.save(config_path)?;
+            let known = config.profile(&name).is_some();
+            render(format, &serde_json::json!({ "profile": name }), |_| {
+                if known {
+                    format!("using profile {name}")
+                } else {
+                    format!("using profile {name} (not configured yet; see `config set`)")
                 }
-                output.push_str(&format!("Total: {} results\n", response.total));
+            })
+        }
+    }
+}
+
+fn format_profile(profile: &ProfileOutput) -> String {
+    let unset = || "(unset)".to_string();
+    let stored = |present: bool| if present { "stored" } else { "(unset)" };
+    let mut output = format!("profile {}", profile.profile);
+    if profile.current {
+        output.push_str(" (current)");
+    }
+    output.push_str(&format!(
+        "\n  server:        {}",
+        profile.server.clone().unwrap_or_else(unset)
+    ));
+    output.push_str(&format!(
+        "\n  member:        {}",
+        profile.member.clone().unwrap_or_else(unset)
+    ));
+    output.push_str(&format!("\n  token:         {}", stored(profile.has_token)));
+    output.push_str(&format!(
+        "\n  refresh-token: {}",
+        stored(profile.has_refresh_token)
+    ));
+    output
+}
+
+fn format_search_results(heading: &str, response: &SearchResponse) -> String {
+    let mut output = format!("{heading}\n\n");
+    if response.results.is_empty() {
+        output.push_str("No results found.\n");
+    } else {
+        for (i, result) in response.results.iter().enumerate() {
+            output.push_str(&format!(
+                "{}. [score: {:.3}] {}\n",
+                i + 1,
+                result.score,
+                result.content.chars().take(100).collect::<String>()
+            ));
+            output.push_str(&format!("   Id: {}\n", result.id));
+            if let Some(room_id) = &result.room_id {
+                output.push_str(&format!("   Room: {}\n", room_id));
             }
-            Ok(output)
+            output.push('\n');
+        }
+        output.push_str(&format!("Total: {} results\n", response.total));
+    }
+    output
+}
+
+fn format_bulk_invite(report: &BulkInviteResponse) -> String {
+    let already = report
+        .results
+        .len()
+        .saturating_sub(report.invited + report.failed);
+    let mut output = format!(
+        "invited {} member(s) to {}, {already} already in the room, {} failed",
+        report.invited, report.room_id, report.failed
+    );
+    for result in report
+        .results
+        .iter()
+        .filter(|result| result.status == "failed")
+    {
+        let error = result.error.as_deref().unwrap_or("failed");
+        output.push_str(&format!("\n  {}: {error}", result.entry));
+    }
+    output
+}
+
+fn format_member_profile(profile: &MemberProfileResponse) -> String {
+    let mut output = format!("{}\n", profile.id);
+    output.push_str(&format!("  type:         {}\n", profile.member_type));
+    if let Some(name) = &profile.display_name {
+        output.push_str(&format!("  display name: {name}\n"));
+    }
+    if let Some(url) = &profile.avatar_url {
+        output.push_str(&format!("  avatar:       {url}\n"));
+    }
+    if let Some(key) = &profile.public_key {
+        output.push_str(&format!("  public key:   {key}\n"));
+    }
+    let capabilities = &profile.capabilities;
+    for (label, names) in [
+        ("content", &capabilities.content_types),
+        ("tools", &capabilities.tools),
+        ("languages", &capabilities.languages),
+    ] {
+        if !names.is_empty() {
+            let label = format!("{label}:");
+            output.push_str(&format!("  {label:<13} {}\n", names.join(", ")));
         }
-        Commands::Agent { command } => run_agent_command(command).await,
     }
+    if let Some(tokens) = capabilities.max_context_tokens {
+        output.push_str(&format!("  max context:  {tokens} tokens\n"));
+    }
+    output.push_str(&format!("  joined:       {}", profile.created_at));
+    output
+}
+
+fn format_room(room: &RoomInfoResponse) -> String {
+    let mut output = format!("{} ({})\n", room.id, room.name);
+    if let Some(topic) = &room.topic {
+        output.push_str(&format!("  topic:    {topic}\n"));
+    }
+    output.push_str(&format!("  messages: {}", room.messages.len()));
+    for message in &room.messages {
+        output.push_str(&format!(
+            "\n  [{}] {}: {}",
+            message.id, message.sender, message.text
+        ));
+    }
+    output
 }
 
 fn resolve_agent_dir(dir: Option<PathBuf>) -> Result<PathBuf, CliError> {
@@ -530,10 +1860,13 @@ fn resolve_agent_dir(dir: Option<PathBuf>) -> Result<PathBuf, CliError> {
     }
 }
 
-async fn run_agent_command(command: AgentCommands) -> Result<String, CliError> {
+async fn run_agent_command(
+    command: AgentCommands,
+    format: OutputFormat,
+) -> Result<String, CliError> {
     use nexis_runtime::{
         compose_agent_prompt, AIProvider, AgentRegistry, AnthropicProvider, GenerateRequest,
-        OpenAIProvider, StreamChunk,
+        OpenAICompatibleProvider, OpenAIProvider, StreamChunk,
     };
     use std::sync::Arc;
 
@@ -547,18 +1880,34 @@ async fn run_agent_command(command: AgentCommands) -> Result<String, CliError> {
                 ))
             })?;
 
-            let ids = registry.list();
-            if ids.is_empty() {
-                return Ok(format!("No agents found in {}", dir.display()));
-            }
-
-            let mut output = format!("Agents in {}:\n", registry.dir().display());
-            for id in ids {
-                if let Some(config) = registry.get(&id) {
-                    output.push_str(&format!("- {}: {} ({})\n", id, config.name, config.role));
+            let agents = registry
+                .list()
+                .into_iter()
+                .filter_map(|id| {
+                    registry.get(&id).map(|config| AgentSummary {
+                        name: config.name.clone(),
+                        role: config.role.clone(),
+                        id,
+                    })
+                })
+                .collect();
+            let listed = AgentListOutput {
+                dir: registry.dir().to_path_buf(),
+                agents,
+            };
+            render(format, &listed, |listed| {
+                if listed.agents.is_empty() {
+                    return format!("No agents found in {}", dir.display());
                 }
-            }
-            Ok(output)
+                let mut output = format!("Agents in {}:\n", listed.dir.display());
+                for agent in &listed.agents {
+                    output.push_str(&format!(
+                        "- {}: {} ({})\n",
+                        agent.id, agent.name, agent.role
+                    ));
+                }
+                output
+            })
         }
         AgentCommands::Run(args) => {
             let dir = resolve_agent_dir(args.dir)?;
@@ -579,6 +1928,8 @@ async fn run_agent_command(command: AgentCommands) -> Result<String, CliError> {
             let provider: Arc<dyn AIProvider> = match args.provider.as_str() {
                 "openai" => Arc::new(OpenAIProvider::from_env()),
                 "anthropic" => Arc::new(AnthropicProvider::from_env()),
+                "ollama" => Arc::new(OpenAICompatibleProvider::ollama_from_env()),
+                "vllm" => Arc::new(OpenAICompatibleProvider::vllm_from_env()),
                 other => {
                     return Err(CliError::InvalidArgument(format!(
                         "Unknown provider: {}",
@@ -597,9 +1948,10 @@ async fn run_agent_command(command: AgentCommands) -> Result<String, CliError> {
                     "agent_name": agent.name,
                     "agent_role": agent.role
                 })),
+                images: Vec::new(),
             };
 
-            if args.stream {
+            let content = if args.stream {
                 let mut stream = provider
                     .generate_stream(req)
                     .await
@@ -611,27 +1963,46 @@ async fn run_agent_command(command: AgentCommands) -> Result<String, CliError> {
                         StreamChunk::Done => {}
                     }
                 }
-                Ok(output)
+                output
             } else {
-                let resp = provider
+                provider
                     .generate(req)
                     .await
-                    .map_err(|e| CliError::HttpTransport(e.to_string()))?;
-                Ok(resp.content)
-            }
+                    .map_err(|e| CliError::HttpTransport(e.to_string()))?
+                    .content
+            };
+            let ran = AgentRunOutput {
+                agent: args.agent,
+                content,
+            };
+            render(format, &ran, |ran| ran.content.clone())
         }
     }
 }
 
-async fn test_provider(provider: &str, prompt: &str, stream: bool) -> Result<String, CliError> {
-    use nexis_runtime::{AIProvider, AnthropicProvider, GenerateRequest, OpenAIProvider};
+async fn test_provider(
+    provider: &str,
+    prompt: &str,
+    stream: bool,
+    format: OutputFormat,
+) -> Result<String, CliError> {
+    use nexis_runtime::{
+        AIProvider, AnthropicProvider, GenerateRequest, OpenAICompatibleProvider, OpenAIProvider,
+    };
     use std::sync::Arc;
 
-    println!("Testing {} provider...", provider);
+    // Progress lines would corrupt the JSON document on stdout.
+    let verbose = format == OutputFormat::Text;
+    if verbose {
+        println!("Testing {} provider...", provider);
+    }
 
+    let name = provider;
     let provider: Arc<dyn AIProvider> = match provider {
         "openai" => Arc::new(OpenAIProvider::from_env()),
         "anthropic" => Arc::new(AnthropicProvider::from_env()),
+        "ollama" => Arc::new(OpenAICompatibleProvider::ollama_from_env()),
+        "vllm" => Arc::new(OpenAICompatibleProvider::vllm_from_env()),
         _ => {
             return Err(CliError::InvalidArgument(format!(
                 "Unknown provider: {}",
@@ -646,42 +2017,77 @@ async fn test_provider(provider: &str, prompt: &str, stream: bool) -> Result<Str
         max_tokens: Some(100),
         temperature: Some(0.7),
         metadata: None,
+        images: Vec::new(),
     };
 
     if stream {
-        println!("Streaming response:\n");
+        if verbose {
+            println!("Streaming response:\n");
+        }
         use futures::StreamExt;
         let mut stream = provider
             .generate_stream(req)
             .await
             .map_err(|e| CliError::HttpTransport(e.to_string()))?;
 
+        let mut content = String::new();
         while let Some(chunk) = stream.next().await {
             match chunk.map_err(|e| CliError::HttpTransport(e.to_string()))? {
-                nexis_runtime::StreamChunk::Delta { text } => print!("{}", text),
-                nexis_runtime::StreamChunk::Done => println!(),
+                nexis_runtime::StreamChunk::Delta { text } => {
+                    if verbose {
+                        print!("{}", text);
+                    }
+                    content.push_str(&text);
+                }
+                nexis_runtime::StreamChunk::Done => {
+                    if verbose {
+                        println!();
+                    }
+                }
             }
         }
-        Ok("Stream completed".to_string())
+        let tested = ProviderTestResponse {
+            provider: name.to_string(),
+            model: None,
+            content,
+            finish_reason: None,
+        };
+        render(format, &tested, |_| "Stream completed".to_string())
     } else {
-        println!("Sending request...\n");
+        if verbose {
+            println!("Sending request...\n");
+        }
         let resp = provider
             .generate(req)
             .await
             .map_err(|e| CliError::HttpTransport(e.to_string()))?;
-        println!("Response: {}", resp.content);
-        println!("Model: {:?}", resp.model);
-        println!("Finish reason: {:?}", resp.finish_reason);
-        Ok(format!("Response: {}", resp.content))
+        if verbose {
+            println!("Response: {}", resp.content);
+            println!("Model: {:?}", resp.model);
+            println!("Finish reason: {:?}", resp.finish_reason);
+        }
+        let tested = ProviderTestResponse {
+            provider: name.to_string(),
+            model: resp.model,
+            content: resp.content,
+            finish_reason: resp.finish_reason,
+        };
+        render(format, &tested, |tested| {
+            format!("Response: {}", tested.content)
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        connect_websocket_once, run, AgentCommands, AgentListArgs, AgentRunArgs, Cli, CliClient,
-        CliError, Commands,
+        connect_websocket_once, format_bulk_invite, format_member_profile, format_search_results,
+        run, slow_mode_error, AgentCommands, AgentListArgs, AgentRunArgs, BulkInviteResponse, Cli,
+        CliClient, CliError, Commands, MemberProfileResponse, OutputFormat, SearchResponse,
     };
+    use crate::admin::{AdminCommands, TenantCommands};
+    use crate::eval::EvalCommands;
+    use crate::profile::CliConfig;
     use clap::Parser;
     use futures::{SinkExt, StreamExt};
     use httpmock::{Method::POST, MockServer};
@@ -742,6 +2148,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_parses_eval_run_command() {
+        let cli = Cli::parse_from([
+            "nexis-cli",
+            "eval",
+            "run",
+            "suites/support.toml",
+            "--judge-provider",
+            "anthropic",
+        ]);
+        match cli.command {
+            Commands::Eval {
+                command: EvalCommands::Run(args),
+            } => {
+                assert_eq!(args.suite, std::path::Path::new("suites/support.toml"));
+                assert_eq!(args.provider, "openai");
+                assert_eq!(args.judge_provider.as_deref(), Some("anthropic"));
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
     #[test]
     fn cli_parses_agent_run_command() {
         let cli = Cli::parse_from([
@@ -792,7 +2220,9 @@ constraints: []
         .expect("should write file");
 
         let output = run(Cli {
-            server: "http://127.0.0.1:8080".to_string(),
+            server: None,
+            profile: None,
+            output: OutputFormat::Text,
             command: Commands::Agent {
                 command: AgentCommands::List(AgentListArgs {
                     dir: Some(dir.clone()),
@@ -806,11 +2236,121 @@ constraints: []
         assert!(output.contains("Workspace Coder"));
     }
 
+    #[tokio::test]
+    async fn json_output_uses_stable_field_names() {
+        let cli = Cli::parse_from(["nexis-cli", "--output", "json", "get-room", "room_general"]);
+        assert_eq!(cli.output, OutputFormat::Json);
+        assert!(
+            matches!(cli.command, Commands::GetRoom { ref room_id } if room_id == "room_general")
+        );
+        assert_eq!(
+            Cli::parse_from(["nexis-cli", "get-room", "room_general"]).output,
+            OutputFormat::Text
+        );
+
+        let dir = temp_dir("json");
+        fs::write(
+            dir.join("workspace-coder.yaml"),
+            "name: Workspace Coder\nrole: Product Engineer\nskills: []\nvibe: Fast\nconstraints: []\n",
+        )
+        .expect("should write file");
+        let output = run(Cli {
+            server: None,
+            profile: None,
+            output: OutputFormat::Json,
+            command: Commands::Agent {
+                command: AgentCommands::List(AgentListArgs {
+                    dir: Some(dir.clone()),
+                }),
+            },
+        })
+        .await
+        .expect("list should succeed");
+        let listed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            listed["agents"],
+            json!([{"id": "workspace-coder", "name": "Workspace Coder", "role": "Product Engineer"}])
+        );
+
+        let response: SearchResponse = serde_json::from_value(json!({
+            "query": "deploy",
+            "results": [{
+                "id": "550e8400-e29b-41d4-a716-446655440000",
+                "score": 0.5,
+                "content": "deploy on monday",
+                "room_id": null
+            }],
+            "total": 1
+        }))
+        .unwrap();
+        let rendered = super::render(OutputFormat::Json, &response, |_| String::new()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(value["results"][0]["content"], "deploy on monday");
+        assert_eq!(value["total"], 1);
+
+        let err = CliError::HttpStatus {
+            status: 404,
+            body: "room not found".to_string(),
+        };
+        let error: serde_json::Value = serde_json::from_str(&super::error_json(&err)).unwrap();
+        assert_eq!(error["status"], 404);
+        assert!(error["error"].as_str().unwrap().contains("room not found"));
+    }
+
+    #[tokio::test]
+    async fn config_commands_edit_profiles() {
+        let path = temp_dir("config").join("config.toml");
+        let config = |args: &[&str]| {
+            let cli = Cli::parse_from(["nexis-cli"].iter().chain(args));
+            let path = path.clone();
+            async move { super::run_with_config(cli, &path).await }
+        };
+
+        config(&[
+            "--profile",
+            "prod",
+            "config",
+            "set",
+            "server",
+            "https://nexis.ai",
+        ])
+        .await
+        .unwrap();
+        config(&["--profile", "prod", "config", "set", "token", "jwt"])
+            .await
+            .unwrap();
+        assert!(config(&["config", "set", "password", "x"]).await.is_err());
+        let output = config(&["config", "use-profile", "prod"]).await.unwrap();
+        assert_eq!(output, "using profile prod");
+
+        assert_eq!(
+            config(&["config", "get", "server"]).await.unwrap(),
+            "https://nexis.ai"
+        );
+        let shown = config(&["config", "get"]).await.unwrap();
+        assert!(shown.starts_with("profile prod (current)"));
+        assert!(shown.contains("token:         stored"));
+        assert!(!shown.contains("jwt"));
+
+        let shown = config(&["--output", "json", "--profile", "dev", "config", "get"])
+            .await
+            .unwrap();
+        let shown: serde_json::Value = serde_json::from_str(&shown).unwrap();
+        assert_eq!(shown["current"], false);
+        assert_eq!(shown["has_token"], false);
+
+        let saved = CliConfig::load(&path).unwrap();
+        assert_eq!(saved.current_profile.as_deref(), Some("prod"));
+        assert_eq!(saved.profile("prod").unwrap().token.as_deref(), Some("jwt"));
+    }
+
     #[tokio::test]
     async fn agent_run_rejects_unknown_agent() {
         let dir = temp_dir("run");
         let err = run(Cli {
-            server: "http://127.0.0.1:8080".to_string(),
+            server: None,
+            profile: None,
+            output: OutputFormat::Text,
             command: Commands::Agent {
                 command: AgentCommands::Run(AgentRunArgs {
                     agent: "missing".to_string(),
@@ -869,6 +2409,325 @@ constraints: []
         assert_eq!(room.name, "general");
     }
 
+    #[test]
+    fn cli_parses_similar_and_formats_results() {
+        let cli = Cli::parse_from([
+            "nexis-cli",
+            "similar",
+            "msg_abc",
+            "--limit",
+            "3",
+            "--min-score",
+            "0.7",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Commands::Similar { message_id, limit: 3, room: None, min_score: Some(score) }
+                if message_id == "msg_abc" && score == 0.7
+        ));
+
+        let response: SearchResponse = serde_json::from_value(json!({
+            "query": "deploy friday",
+            "results": [{
+                "id": "550e8400-e29b-41d4-a716-446655440000",
+                "score": 0.91,
+                "content": "release monday",
+                "room_id": null
+            }],
+            "total": 1
+        }))
+        .unwrap();
+        let output = format_search_results("Messages similar to msg_abc", &response);
+        assert!(output.contains("1. [score: 0.910] release monday"));
+        assert!(output.contains("Id: 550e8400-e29b-41d4-a716-446655440000"));
+        assert!(output.contains("Total: 1 results"));
+    }
+
+    #[test]
+    fn cli_parses_whois_and_formats_profile() {
+        let cli = Cli::parse_from(["nexis-cli", "whois", "nexis:agent:openai/reviewer"]);
+        assert!(
+            matches!(cli.command, Commands::Whois { member_id } if member_id == "nexis:agent:openai/reviewer")
+        );
+
+        let profile: MemberProfileResponse = serde_json::from_value(json!({
+            "id": "nexis:agent:openai/reviewer",
+            "memberType": "agent",
+            "displayName": "Reviewer",
+            "avatarUrl": null,
+            "publicKey": null,
+            "capabilities": { "tools": ["code-review", "summarize"], "maxContextTokens": 32000 },
+            "createdAt": "2026-01-01T00:00:00Z"
+        }))
+        .unwrap();
+        let output = format_member_profile(&profile);
+        assert!(output.starts_with("nexis:agent:openai/reviewer\n"));
+        assert!(output.contains("display name: Reviewer"));
+        assert!(output.contains("  tools:        code-review, summarize\n"));
+        assert!(output.contains("  max context:  32000 tokens\n"));
+        assert!(!output.contains("avatar"));
+    }
+
+    #[test]
+    fn cli_parses_room_archive_commands() {
+        let cli = Cli::parse_from([
+            "nexis-cli",
+            "export-room",
+            "room_general",
+            "--output",
+            "general.jsonl",
+        ]);
+        match cli.command {
+            Commands::ExportRoom { room_id, output } => {
+                assert_eq!(room_id, "room_general");
+                assert_eq!(output, Some(std::path::PathBuf::from("general.jsonl")));
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        let cli = Cli::parse_from([
+            "nexis-cli",
+            "export",
+            "room_general",
+            "--format",
+            "html",
+            "--until",
+            "2026-01-05",
+        ]);
+        match cli.command {
+            Commands::Export {
+                format,
+                since,
+                until,
+                ..
+            } => {
+                assert_eq!(format, crate::transcript::TranscriptFormat::Html);
+                assert_eq!(since, None);
+                assert_eq!(
+                    until,
+                    Some(crate::transcript::parse_until("2026-01-05").unwrap())
+                );
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        let cli = Cli::parse_from(["nexis-cli", "import-room", "general.jsonl"]);
+        assert!(
+            matches!(cli.command, Commands::ImportRoom { file } if file == std::path::Path::new("general.jsonl"))
+        );
+    }
+
+    #[test]
+    fn invite_bulk_reports_failed_entries() {
+        let cli = Cli::parse_from(["nexis-cli", "invite-bulk", "room_ops", "team.csv"]);
+        assert!(matches!(
+            cli.command,
+            Commands::InviteBulk { ref room_id, ref file }
+                if room_id == "room_ops" && file == std::path::Path::new("team.csv")
+        ));
+
+        let report: BulkInviteResponse = serde_json::from_value(json!({
+            "roomId": "room_ops",
+            "invited": 1,
+            "failed": 1,
+            "results": [
+                { "entry": "alice", "memberId": "alice", "status": "invited" },
+                { "entry": "bob", "memberId": "bob", "status": "already_member" },
+                { "entry": "x@example.com", "status": "failed", "error": "no member has this email address" }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            format_bulk_invite(&report),
+            "invited 1 member(s) to room_ops, 1 already in the room, 1 failed\n  \
+             x@example.com: no member has this email address"
+        );
+    }
+
+    #[test]
+    fn admin_list_rooms_encodes_its_filters() {
+        let cli = Cli::parse_from([
+            "nexis-cli",
+            "admin",
+            "list-rooms",
+            "--search",
+            "eng ops",
+            "--sort",
+            "activity",
+        ]);
+        let Commands::Admin {
+            command:
+                AdminCommands::ListRooms {
+                    limit,
+                    offset,
+                    search,
+                    sort,
+                },
+        } = cli.command
+        else {
+            panic!("expected admin list-rooms");
+        };
+        assert_eq!(
+            crate::admin::room_list_path(limit, offset, search.as_deref(), sort),
+            "/v1/rooms?limit=100&offset=0&q=eng+ops&sort=activity"
+        );
+    }
+
+    #[test]
+    fn slow_mode_refusals_report_the_wait() {
+        let body = json!({
+            "error": "slow mode is on: one message every 30s; try again in 12s",
+            "code": "SLOW_MODE",
+            "retryAfterSecs": 12,
+        });
+        let err = slow_mode_error(CliError::HttpStatus {
+            status: 429,
+            body: body.to_string(),
+        });
+        assert!(matches!(
+            err,
+            CliError::SlowMode {
+                retry_after_secs: 12
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "slow mode is on in this room; try again in 12s"
+        );
+
+        let other = json!({ "error": "quota", "code": "QUOTA_EXCEEDED" }).to_string();
+        let err = slow_mode_error(CliError::HttpStatus {
+            status: 429,
+            body: other,
+        });
+        assert!(matches!(err, CliError::HttpStatus { status: 429, .. }));
+
+        let cli = Cli::parse_from(["nexis-cli", "admin", "slow-mode", "room_1", "0"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Admin {
+                command: AdminCommands::SlowMode { seconds: 0, .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn admin_revoke_token_takes_a_token_or_a_member() {
+        let cli = Cli::parse_from([
+            "nexis-cli",
+            "admin",
+            "revoke-token",
+            "--member",
+            "nexis:human:bob",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Commands::Admin {
+                command: AdminCommands::RevokeToken { token: None, member: Some(ref member) }
+            } if member == "nexis:human:bob"
+        ));
+        assert!(Cli::try_parse_from(["nexis-cli", "admin", "revoke-token"]).is_err());
+        assert!(Cli::try_parse_from([
+            "nexis-cli",
+            "admin",
+            "revoke-token",
+            "nrt_abc",
+            "--member",
+            "nexis:human:bob",
+        ])
+        .is_err());
+
+        let cli = Cli::parse_from(["nexis-cli", "admin", "tenant", "suspend", "tenant_1"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Admin {
+                command: AdminCommands::Tenant {
+                    command: TenantCommands::Suspend { ref tenant_id }
+                }
+            } if tenant_id == "tenant_1"
+        ));
+    }
+
+    #[tokio::test]
+    async fn import_room_rejects_invalid_archive_before_upload() {
+        let client = CliClient::new("http://127.0.0.1:9");
+        let err = client
+            .import_room("{\"type\":\"room\"}".to_string())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, CliError::InvalidArgument(message) if message.starts_with("invalid archive"))
+        );
+    }
+
+    #[tokio::test]
+    async fn login_stores_tokens_on_the_profile() {
+        if !network_tests_enabled() {
+            eprintln!("skipping network test: set NEXIS_RUN_NETWORK_TESTS=1 to enable");
+            return;
+        }
+
+        let server = MockServer::start_async().await;
+        let login_mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/v1/auth/login")
+                    .json_body(json!({"memberId": "nexis:human:alice", "secret": "s3cret"}));
+                then.status(200).json_body(json!({
+                    "memberId": "nexis:human:alice",
+                    "accessToken": "jwt",
+                    "refreshToken": "refresh",
+                    "tokenType": "Bearer",
+                    "expiresIn": 3600
+                }));
+            })
+            .await;
+        let logout_mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/v1/auth/logout")
+                    .header("authorization", "Bearer jwt")
+                    .json_body(json!({"refreshToken": "refresh"}));
+                then.status(204);
+            })
+            .await;
+
+        let path = temp_dir("login").join("config.toml");
+        let mut config = CliConfig::default();
+        let connection = config.resolve(Some("dev"), Some(&server.base_url()), |_| None);
+        let output = super::login(
+            &mut config,
+            &path,
+            &connection,
+            "nexis:human:alice",
+            "s3cret",
+            OutputFormat::Text,
+        )
+        .await
+        .unwrap();
+        login_mock.assert_async().await;
+        assert_eq!(output, "logged in as nexis:human:alice (profile dev)");
+        let saved = CliConfig::load(&path).unwrap();
+        let profile = saved.profile("dev").unwrap();
+        assert_eq!(profile.token.as_deref(), Some("jwt"));
+        assert_eq!(profile.refresh_token.as_deref(), Some("refresh"));
+        assert_eq!(profile.member.as_deref(), Some("nexis:human:alice"));
+
+        let mut config = saved;
+        super::logout(&mut config, &path, &connection, OutputFormat::Text)
+            .await
+            .unwrap();
+        logout_mock.assert_async().await;
+        assert_eq!(
+            CliConfig::load(&path)
+                .unwrap()
+                .profile("dev")
+                .unwrap()
+                .token,
+            None
+        );
+    }
+
     #[tokio::test]
     async fn send_message_surfaces_http_status_error() {
         if !network_tests_enabled() {
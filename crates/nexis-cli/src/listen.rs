@@ -0,0 +1,369 @@
+//! `nexis-cli listen`: follow a room's WebSocket events as they happen.
+//!
+//! The connection is re-established with exponential backoff whenever it
+//! drops; the backoff resets once the gateway confirms the subscription.
+//! With `--encoding msgpack` the gateway sends binary MessagePack frames,
+//! which are decoded into the same events as JSON frames.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use colored::{Color, Colorize};
+use futures::{SinkExt, StreamExt};
+use nexis_protocol::codec::Encoding;
+use serde_json::Value;
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{client::IntoClientRequest, protocol::Message},
+};
+
+use crate::{CliError, OutputFormat};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const SENDER_COLORS: &[Color] = &[
+    Color::Cyan,
+    Color::Magenta,
+    Color::Yellow,
+    Color::Green,
+    Color::Blue,
+    Color::BrightRed,
+];
+
+/// Gateway WebSocket endpoint for an HTTP(S) server base URL.
+pub fn websocket_url(server: &str) -> String {
+    let base = server.trim_end_matches('/');
+    let base = if let Some(rest) = base.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = base.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        base.to_string()
+    };
+    format!("{base}/ws")
+}
+
+/// `url` asking the gateway to frame events with `encoding`.
+pub fn with_encoding(url: &str, encoding: Encoding) -> String {
+    if encoding == Encoding::default() {
+        return url.to_string();
+    }
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{url}{separator}encoding={encoding}")
+}
+
+/// Reconnect delay that doubles on every attempt, up to `max`.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    current: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    pub fn new(max: Duration) -> Self {
+        Self {
+            current: INITIAL_BACKOFF.min(max),
+            max,
+        }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.max);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.current = INITIAL_BACKOFF.min(self.max);
+    }
+}
+
+/// Stable color for a sender, so each member keeps theirs across events.
+fn sender_color(sender: &str) -> Color {
+    let hash = sender.bytes().fold(0usize, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte.into())
+    });
+    SENDER_COLORS[hash % SENDER_COLORS.len()]
+}
+
+fn timestamp(created_at: Option<&str>) -> String {
+    created_at
+        .and_then(|raw| raw.parse::<DateTime<Utc>>().ok())
+        .map(|time| time.with_timezone(&Local))
+        .unwrap_or_else(Local::now)
+        .format("%H:%M:%S")
+        .to_string()
+}
+
+fn is_control_frame(event: &Value) -> bool {
+    matches!(event["type"].as_str(), Some("subscribed" | "unsubscribed"))
+}
+
+/// Human-readable line for one event frame; `None` for subscription acks
+/// and frames that are not JSON events.
+pub fn format_event(frame: &str) -> Option<String> {
+    let event: Value = serde_json::from_str(frame).ok()?;
+    if is_control_frame(&event) {
+        return None;
+    }
+    let line = match event["type"].as_str()? {
+        "message" => {
+            let message = &event["message"];
+            let sender = message["sender"].as_str().unwrap_or("unknown");
+            let mut line = format!(
+                "{} {}: {}",
+                timestamp(message["created_at"].as_str()).dimmed(),
+                sender.color(sender_color(sender)).bold(),
+                message["text"].as_str().unwrap_or_default()
+            );
+            if let Some(reply_to) = message["reply_to"].as_str() {
+                line.push_str(&format!(" {}", format!("(reply to {reply_to})").dimmed()));
+            }
+            line
+        }
+        "message_delta" => {
+            let sender = event["sender"].as_str().unwrap_or("unknown");
+            format!(
+                "{} {} {}",
+                timestamp(None).dimmed(),
+                format!("{sender} …").color(sender_color(sender)),
+                event["delta"].as_str().unwrap_or_default().dimmed()
+            )
+        }
+        "read_marker" => {
+            let member = event["memberId"].as_str().unwrap_or("unknown");
+            format!(
+                "{} {} read up to {}",
+                timestamp(None).dimmed(),
+                member.color(sender_color(member)),
+                event["messageId"].as_str().unwrap_or("?")
+            )
+        }
+        other => format!("{} {} {}", timestamp(None).dimmed(), other.italic(), event),
+    };
+    Some(line)
+}
+
+/// What a room subscription reports to its caller.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedEvent {
+    /// The gateway confirmed the subscription.
+    Subscribed,
+    /// A room event, as sent by the gateway.
+    Event(Value),
+    /// The connection dropped; the next attempt starts after `retry_in`.
+    Disconnected { reason: String, retry_in: Duration },
+}
+
+/// Follow `room_id` forever as the holder of `token`, reconnecting with
+/// backoff, and hand every [`FeedEvent`] to `on_event`. Callers stop it by
+/// dropping the future.
+pub async fn subscribe_room(
+    url: &str,
+    token: Option<&str>,
+    room_id: &str,
+    max_backoff: Duration,
+    mut on_event: impl FnMut(FeedEvent),
+) {
+    let mut backoff = Backoff::new(max_backoff);
+    loop {
+        let reason = match follow(url, token, room_id, &mut backoff, &mut on_event).await {
+            Ok(()) => "connection closed".to_string(),
+            Err(err) => err.to_string(),
+        };
+        let retry_in = backoff.next_delay();
+        on_event(FeedEvent::Disconnected { reason, retry_in });
+        tokio::time::sleep(retry_in).await;
+    }
+}
+
+/// Print `room_id`'s events until interrupted, reconnecting on drops.
+pub async fn listen(
+    url: &str,
+    token: Option<&str>,
+    room_id: &str,
+    format: OutputFormat,
+    max_backoff: Duration,
+) -> Result<String, CliError> {
+    let feed = subscribe_room(url, token, room_id, max_backoff, |event| match event {
+        FeedEvent::Subscribed => eprintln!("{} {room_id}", "listening to".green()),
+        FeedEvent::Event(event) => match format {
+            OutputFormat::Text => {
+                if let Some(line) = format_event(&event.to_string()) {
+                    println!("{line}");
+                }
+            }
+            OutputFormat::Json => println!("{event}"),
+        },
+        FeedEvent::Disconnected { reason, retry_in } => eprintln!(
+            "{} {reason}; reconnecting in {:.1}s",
+            "disconnected:".yellow(),
+            retry_in.as_secs_f32()
+        ),
+    });
+    tokio::select! {
+        _ = feed => unreachable!("room feeds only end when dropped"),
+        _ = tokio::signal::ctrl_c() => Ok(format!("stopped listening to {room_id}")),
+    }
+}
+
+/// One connection: subscribe, then report events until the socket closes.
+async fn follow(
+    url: &str,
+    token: Option<&str>,
+    room_id: &str,
+    backoff: &mut Backoff,
+    on_event: &mut impl FnMut(FeedEvent),
+) -> Result<(), CliError> {
+    let mut request = url
+        .into_client_request()
+        .map_err(|err| CliError::WebSocket(err.to_string()))?;
+    // The gateway only lets authenticated connections subscribe.
+    if let Some(token) = token {
+        let bearer = format!("Bearer {token}")
+            .parse()
+            .map_err(|_| CliError::InvalidArgument("token is not a valid header".to_string()))?;
+        request.headers_mut().insert("authorization", bearer);
+    }
+    let (mut ws, _) = connect_async(request)
+        .await
+        .map_err(|err| CliError::WebSocket(err.to_string()))?;
+    let subscribe = serde_json::json!({ "type": "subscribe", "roomId": room_id });
+    ws.send(Message::Text(subscribe.to_string().into()))
+        .await
+        .map_err(|err| CliError::WebSocket(err.to_string()))?;
+
+    while let Some(frame) = ws.next().await {
+        let event = match frame.map_err(|err| CliError::WebSocket(err.to_string()))? {
+            Message::Text(text) => serde_json::from_str::<Value>(&text).ok(),
+            Message::Binary(bytes) => Encoding::MessagePack.decode::<Value>(&bytes).ok(),
+            Message::Close(_) => return Ok(()),
+            _ => continue,
+        };
+        let Some(event) = event else {
+            continue;
+        };
+        if event["type"] == "subscribed" {
+            backoff.reset();
+            on_event(FeedEvent::Subscribed);
+        } else if !is_control_frame(&event) {
+            on_event(FeedEvent::Event(event));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn subscribe_room_reports_events_and_drops() {
+        if !matches!(std::env::var("NEXIS_RUN_NETWORK_TESTS"), Ok(value) if value == "1") {
+            eprintln!("skipping network test: set NEXIS_RUN_NETWORK_TESTS=1 to enable");
+            return;
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let Some(Ok(Message::Text(subscribe))) = ws.next().await else {
+                panic!("expected a subscribe frame");
+            };
+            let subscribe: Value = serde_json::from_str(&subscribe).unwrap();
+            assert_eq!(subscribe["roomId"], "room_1");
+            for frame in [
+                r#"{"type":"subscribed","roomId":"room_1"}"#,
+                r#"{"type":"message","roomId":"room_1","message":{"sender":"a","text":"hi"}}"#,
+            ] {
+                ws.send(Message::Text(frame.into())).await.unwrap();
+            }
+            ws.close(None).await.unwrap();
+        });
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let feed = tokio::spawn(async move {
+            subscribe_room(&url, None, "room_1", Duration::from_secs(1), |event| {
+                let _ = tx.send(event);
+            })
+            .await
+        });
+        assert_eq!(rx.recv().await, Some(FeedEvent::Subscribed));
+        assert!(
+            matches!(rx.recv().await, Some(FeedEvent::Event(event)) if event["message"]["text"] == "hi")
+        );
+        assert!(matches!(
+            rx.recv().await,
+            Some(FeedEvent::Disconnected { retry_in, .. }) if retry_in == Duration::from_millis(500)
+        ));
+        feed.abort();
+    }
+
+    #[test]
+    fn websocket_url_follows_the_server_scheme() {
+        assert_eq!(
+            websocket_url("http://127.0.0.1:8080"),
+            "ws://127.0.0.1:8080/ws"
+        );
+        assert_eq!(websocket_url("https://nexis.ai/"), "wss://nexis.ai/ws");
+    }
+
+    #[test]
+    fn with_encoding_only_asks_for_binary_frames() {
+        let url = "ws://127.0.0.1:8080/ws";
+        assert_eq!(with_encoding(url, Encoding::Json), url);
+        assert_eq!(
+            with_encoding(url, Encoding::MessagePack),
+            "ws://127.0.0.1:8080/ws?encoding=msgpack"
+        );
+        assert_eq!(
+            with_encoding("ws://host/ws?token=t", Encoding::MessagePack),
+            "ws://host/ws?token=t&encoding=msgpack"
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap_and_resets() {
+        let mut backoff = Backoff::new(Duration::from_secs(2));
+        let delays: Vec<_> = (0..4).map(|_| backoff.next_delay()).collect();
+        assert_eq!(
+            delays,
+            [500, 1000, 2000, 2000].map(Duration::from_millis).to_vec()
+        );
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn format_event_renders_messages_and_skips_acks() {
+        let line = format_event(
+            r#"{"type":"message","roomId":"room_1","message":{"id":"msg_1","sender":"nexis:human:alice@example.com","text":"hello","reply_to":"msg_0","created_at":"2026-01-01T12:00:00Z"}}"#,
+        )
+        .unwrap();
+        assert!(line.contains("nexis:human:alice@example.com"));
+        assert!(line.contains(": hello"));
+        assert!(line.contains("reply to msg_0"));
+
+        let line = format_event(
+            r#"{"type":"read_marker","roomId":"room_1","memberId":"nexis:human:bob@example.com","messageId":"msg_1"}"#,
+        )
+        .unwrap();
+        assert!(line.contains("read up to msg_1"));
+
+        let line = format_event(
+            r#"{"type":"message_delta","roomId":"room_1","messageId":"msg_2","sender":"nexis:ai:assistant","delta":"Hel"}"#,
+        )
+        .unwrap();
+        assert!(line.contains("nexis:ai:assistant …"));
+        assert!(line.contains("Hel"));
+        assert!(format_event(r#"{"type":"presence","memberId":"bob"}"#)
+            .unwrap()
+            .contains("presence"));
+        assert_eq!(
+            format_event(r#"{"type":"subscribed","roomId":"room_1"}"#),
+            None
+        );
+        assert_eq!(format_event("ping"), None);
+    }
+}
@@ -1,30 +1,50 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::io::{self, Write};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use clap::Parser;
 use colored::Colorize;
 use futures::StreamExt;
-use nexis_cli::{CliClient, CliError, RoomInfoResponse};
-use nexis_runtime::{AIProvider, AnthropicProvider, GenerateRequest, OpenAIProvider, StreamChunk};
+use nexis_cli::listen::{format_event, subscribe_room, websocket_url, FeedEvent};
+use nexis_cli::profile::{CliConfig, Connection};
+use nexis_cli::{
+    login, logout, read_secret, CliClient, CliError, OutputFormat, RoomInfoResponse, SearchResponse,
+};
+use nexis_context::{counter_for_model, ContextWindow, Message as ContextMessage, PromptAssembler};
+use nexis_protocol::RoomId;
+use nexis_runtime::{
+    AIProvider, AnthropicProvider, GenerateRequest, OpenAICompatibleProvider, OpenAIProvider,
+    StreamChunk,
+};
 use rustyline::completion::{Completer, Pair};
 use rustyline::highlight::Highlighter;
 use rustyline::hint::Hinter;
 use rustyline::validate::Validator;
-use rustyline::{Context, Editor, Helper};
+use rustyline::{Context, Editor, ExternalPrinter, Helper};
+use tokio::task::JoinHandle;
+
+/// Longest wait between reconnects of the joined room's live feed.
+const FEED_MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// How often a streaming `@ai` reply is pushed to the room.
+const STREAM_FLUSH_INTERVAL: Duration = Duration::from_millis(250);
 
 const REPL_COMMANDS: &[&str] = &[
     "login",
     "logout",
     "create-room",
     "join-room",
+    "create-invite",
+    "approve-join",
     "send",
     "reply",
     "invite-member",
     "list-rooms",
     "list-members",
     "search",
+    "similar",
+    "regen",
     "help",
     "@ai",
     "exit",
@@ -36,20 +56,67 @@ enum ReplCommand {
     Login(String),
     Logout,
     CreateRoom(String),
-    JoinRoom(String),
+    JoinRoom(String, Option<String>),
+    CreateInvite(String, Option<u64>),
+    ApproveJoin(String, String),
     Send(String),
     Reply(String, String),
     InviteMember(String, String),
-    ListRooms,
+    /// Rooms the member belongs to, optionally only those whose name
+    /// contains the filter.
+    ListRooms(Option<String>),
     ListMembers,
     Search(String),
+    Similar(String),
+    Regen(String),
     Help,
-    Ai(String),
+    Ai(AiCommand),
     Exit,
     Empty,
     Unknown(String),
 }
 
+/// `@ai [--model <model>] [--provider <provider>] <message>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AiCommand {
+    prompt: String,
+    /// Overrides `NEXIS_AI_MODEL`.
+    model: Option<String>,
+    /// Overrides `NEXIS_AI_PROVIDER`.
+    provider: Option<String>,
+}
+
+const AI_USAGE: &str = "usage: @ai [--model <model>] [--provider <provider>] <message>";
+
+fn parse_ai_command(tail: &str) -> ReplCommand {
+    let mut command = AiCommand {
+        prompt: String::new(),
+        model: None,
+        provider: None,
+    };
+    let mut rest = tail.trim();
+    while let Some(flag) = rest.strip_prefix("--") {
+        let (name, tail) = flag.split_once(char::is_whitespace).unwrap_or((flag, ""));
+        let tail = tail.trim_start();
+        let (value, tail) = tail.split_once(char::is_whitespace).unwrap_or((tail, ""));
+        let slot = match name {
+            "model" => &mut command.model,
+            "provider" => &mut command.provider,
+            _ => return ReplCommand::Unknown(format!("unknown @ai option --{name}; {AI_USAGE}")),
+        };
+        if value.is_empty() {
+            return ReplCommand::Unknown(AI_USAGE.to_string());
+        }
+        *slot = Some(value.to_string());
+        rest = tail.trim_start();
+    }
+    if rest.is_empty() {
+        return ReplCommand::Unknown(AI_USAGE.to_string());
+    }
+    command.prompt = rest.to_string();
+    ReplCommand::Ai(command)
+}
+
 #[derive(Default)]
 struct ReplHelper;
 
@@ -103,21 +170,16 @@ fn parse_command(line: &str) -> ReplCommand {
         return ReplCommand::Logout;
     }
     if line == "list-rooms" {
-        return ReplCommand::ListRooms;
+        return ReplCommand::ListRooms(None);
     }
     if line == "list-members" {
         return ReplCommand::ListMembers;
     }
-    if let Some(message) = line.strip_prefix("@ai ") {
-        let message = message.trim();
-        return if message.is_empty() {
-            ReplCommand::Unknown("usage: @ai <message>".to_string())
-        } else {
-            ReplCommand::Ai(message.to_string())
-        };
+    if let Some(tail) = line.strip_prefix("@ai ") {
+        return parse_ai_command(tail);
     }
     if line == "@ai" {
-        return ReplCommand::Unknown("usage: @ai <message>".to_string());
+        return ReplCommand::Unknown(AI_USAGE.to_string());
     }
 
     let mut parts = line.splitn(2, char::is_whitespace);
@@ -129,12 +191,40 @@ fn parse_command(line: &str) -> ReplCommand {
         "login" => ReplCommand::Unknown("usage: login <member_id>".to_string()),
         "create-room" if !tail.is_empty() => ReplCommand::CreateRoom(tail.to_string()),
         "create-room" => ReplCommand::Unknown("usage: create-room <name>".to_string()),
-        "join-room" if !tail.is_empty() => ReplCommand::JoinRoom(tail.to_string()),
-        "join-room" => ReplCommand::Unknown("usage: join-room <room_id>".to_string()),
+        "join-room" => {
+            let mut parts = tail.split_whitespace();
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(room_id), token, None) => {
+                    ReplCommand::JoinRoom(room_id.to_string(), token.map(str::to_string))
+                }
+                _ => ReplCommand::Unknown("usage: join-room <room_id> [token]".to_string()),
+            }
+        }
+        "create-invite" => {
+            let mut parts = tail.split_whitespace();
+            match (
+                parts.next(),
+                parts.next().map(str::parse::<u64>),
+                parts.next(),
+            ) {
+                (Some(room_id), None, None) => ReplCommand::CreateInvite(room_id.to_string(), None),
+                (Some(room_id), Some(Ok(secs)), None) if secs > 0 => {
+                    ReplCommand::CreateInvite(room_id.to_string(), Some(secs))
+                }
+                _ => ReplCommand::Unknown(
+                    "usage: create-invite <room_id> [expires_secs]".to_string(),
+                ),
+            }
+        }
+        "list-rooms" => ReplCommand::ListRooms(Some(tail.to_string())),
         "send" if !tail.is_empty() => ReplCommand::Send(tail.to_string()),
         "send" => ReplCommand::Unknown("usage: send <message>".to_string()),
         "search" if !tail.is_empty() => ReplCommand::Search(tail.to_string()),
         "search" => ReplCommand::Unknown("usage: search <query>".to_string()),
+        "similar" if !tail.is_empty() => ReplCommand::Similar(tail.to_string()),
+        "similar" => ReplCommand::Unknown("usage: similar <message_id>".to_string()),
+        "regen" if !tail.is_empty() => ReplCommand::Regen(tail.to_string()),
+        "regen" => ReplCommand::Unknown("usage: regen <message_id>".to_string()),
         "reply" => {
             let mut parts = tail.splitn(2, char::is_whitespace);
             let message_id = parts.next().unwrap_or_default();
@@ -145,6 +235,15 @@ fn parse_command(line: &str) -> ReplCommand {
                 ReplCommand::Reply(message_id.to_string(), message.to_string())
             }
         }
+        "approve-join" => {
+            let mut parts = tail.split_whitespace();
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(room_id), Some(member_id), None) => {
+                    ReplCommand::ApproveJoin(room_id.to_string(), member_id.to_string())
+                }
+                _ => ReplCommand::Unknown("usage: approve-join <room_id> <member_id>".to_string()),
+            }
+        }
         "invite-member" => {
             let mut parts = tail.splitn(2, char::is_whitespace);
             let room_id = parts.next().unwrap_or_default();
@@ -162,38 +261,98 @@ fn parse_command(line: &str) -> ReplCommand {
 fn help_text() -> String {
     [
         "Commands:",
-        "  login <member_id>      Login as a member",
+        "  login <member_id>      Log in with the member's secret",
         "  logout                 Logout current member",
         "  create-room <name>     Create a room",
-        "  join-room <room_id>    Join existing room",
+        "  join-room <room_id> [token]  Join a room and follow its messages",
+        "  create-invite <room_id> [expires_secs]  Create a single-use room invitation",
+        "  approve-join <room_id> <member_id>  Approve a request to join a room",
         "  send <message>         Send message to current room",
         "  reply <message_id> <message>  Reply to a message",
         "  invite-member <room_id> <member_id>  Invite member to room",
-        "  list-rooms             List known rooms",
+        "  list-rooms [filter]    List your rooms, optionally by name",
         "  list-members           List members in current room",
         "  search <query>         Semantic search for messages",
-        "  @ai <message>          Ask AI and stream response",
+        "  similar <message_id>   Find messages similar to a message",
+        "  regen <message_id>     Generate an alternative to an AI reply",
+        "  @ai <message>          Ask AI with room context and stream response",
+        "    [--model <model>] [--provider <provider>]  Override the model or provider",
         "  help                   Show this help",
         "  exit | quit            Exit REPL",
     ]
     .join("\n")
 }
 
-#[derive(Debug)]
+/// Prints above the prompt without garbling the line being typed.
+type FeedPrinter = Arc<Mutex<Box<dyn ExternalPrinter + Send>>>;
+
 struct ReplState {
     member_id: Option<String>,
     current_room: Option<String>,
     known_rooms: BTreeMap<String, String>,
     client: CliClient,
+    connection: Connection,
+    printer: Option<FeedPrinter>,
+    feed: Option<JoinHandle<()>>,
+    /// Message this REPL is streaming into the room, whose chunks the feed
+    /// skips since they are already printed.
+    own_stream: Arc<Mutex<Option<String>>>,
 }
 
 impl ReplState {
-    fn new(server: String) -> Self {
+    fn new(connection: &Connection, printer: Option<FeedPrinter>) -> Self {
         Self {
-            member_id: None,
+            member_id: connection.member.clone(),
             current_room: None,
             known_rooms: BTreeMap::new(),
-            client: CliClient::new(server),
+            client: CliClient::for_connection(connection),
+            connection: connection.clone(),
+            printer,
+            feed: None,
+            own_stream: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Show `room_id`'s live messages above the prompt, replacing the feed
+    /// of the previously joined room.
+    fn follow_room(&mut self, room_id: &str) {
+        self.stop_feed();
+        let Some(printer) = self.printer.clone() else {
+            return;
+        };
+        let url = websocket_url(&self.connection.server);
+        let token = self.connection.token.clone();
+        let room_id = room_id.to_string();
+        let own_stream = self.own_stream.clone();
+        self.feed = Some(tokio::spawn(async move {
+            subscribe_room(
+                &url,
+                token.as_deref(),
+                &room_id,
+                FEED_MAX_BACKOFF,
+                |event| {
+                    let line = match event {
+                        FeedEvent::Subscribed => None,
+                        FeedEvent::Event(event) if is_own_delta(&event, &own_stream) => None,
+                        FeedEvent::Event(event) => format_event(&event.to_string()),
+                        FeedEvent::Disconnected { reason, retry_in } => Some(format!(
+                            "{} {reason}; reconnecting in {:.1}s",
+                            "feed disconnected:".yellow(),
+                            retry_in.as_secs_f32()
+                        )),
+                    };
+                    if let (Some(line), Ok(mut printer)) = (line, printer.lock()) {
+                        let _ = printer.print(line);
+                    }
+                },
+            )
+            .await;
+        }));
+    }
+
+    fn stop_feed(&mut self) {
+        if let Some(feed) = self.feed.take() {
+            feed.abort();
         }
     }
 }
@@ -202,12 +361,16 @@ impl ReplState {
 async fn main() {
     if std::env::args().count() > 1 {
         let cli = nexis_cli::Cli::parse();
+        let format = cli.output;
         match nexis_cli::run(cli).await {
             Ok(output) => {
                 println!("{output}");
             }
             Err(err) => {
-                eprintln!("error: {err}");
+                match format {
+                    OutputFormat::Text => eprintln!("error: {err}"),
+                    OutputFormat::Json => eprintln!("{}", nexis_cli::error_json(&err)),
+                }
                 std::process::exit(1);
             }
         }
@@ -225,9 +388,19 @@ async fn main() {
     let history = history_file();
     let _ = editor.load_history(&history);
 
-    let server =
-        std::env::var("NEXIS_SERVER").unwrap_or_else(|_| "http://127.0.0.1:8080".to_string());
-    let mut state = ReplState::new(server);
+    let config = match CliConfig::load(&CliConfig::default_path()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{} {err}", "warning:".yellow());
+            CliConfig::default()
+        }
+    };
+    let connection = config.resolve(None, None, |key| std::env::var(key).ok());
+    let printer = editor
+        .create_external_printer()
+        .ok()
+        .map(|printer| -> FeedPrinter { Arc::new(Mutex::new(Box::new(printer))) });
+    let mut state = ReplState::new(&connection, printer);
     println!(
         "{}",
         "Nexis CLI interactive mode. Type `help`.".bright_green()
@@ -269,10 +442,31 @@ async fn main() {
 async fn run_repl_command(state: &mut ReplState, command: ReplCommand) -> Result<bool, CliError> {
     match command {
         ReplCommand::Login(member_id) => {
+            let secret = read_secret("secret: ")?;
+            let path = CliConfig::default_path();
+            let mut config = CliConfig::load(&path)?;
+            login(
+                &mut config,
+                &path,
+                &state.connection,
+                &member_id,
+                &secret,
+                OutputFormat::Text,
+            )
+            .await?;
+            state.connection.token = config
+                .profile(&state.connection.profile)
+                .and_then(|profile| profile.token.clone());
+            state.client = CliClient::for_connection(&state.connection);
             state.member_id = Some(member_id.clone());
             println!("{} {}", "logged in as".green(), member_id.cyan());
         }
         ReplCommand::Logout => {
+            let path = CliConfig::default_path();
+            let mut config = CliConfig::load(&path)?;
+            logout(&mut config, &path, &state.connection, OutputFormat::Text).await?;
+            state.connection.token = None;
+            state.client = CliClient::for_connection(&state.connection);
             state.member_id = None;
             println!("{}", "logged out".green());
         }
@@ -282,6 +476,7 @@ async fn run_repl_command(state: &mut ReplState, command: ReplCommand) -> Result
                 .known_rooms
                 .insert(created.id.clone(), created.name.clone());
             state.current_room = Some(created.id.clone());
+            state.follow_room(&created.id);
             println!(
                 "{} {} ({})",
                 "room created:".green(),
@@ -289,12 +484,43 @@ async fn run_repl_command(state: &mut ReplState, command: ReplCommand) -> Result
                 created.name
             );
         }
-        ReplCommand::JoinRoom(room_id) => {
+        ReplCommand::JoinRoom(room_id, token) => {
+            let joined = state.client.join_room(&room_id, token.as_deref()).await?;
+            if !joined.is_joined() {
+                println!(
+                    "{} {}",
+                    "join request awaiting approval for".yellow(),
+                    joined.room_id.cyan()
+                );
+                return Ok(false);
+            }
             let room = state.client.get_room(&room_id).await?;
             state.known_rooms.insert(room.id.clone(), room.name.clone());
             state.current_room = Some(room.id.clone());
+            state.follow_room(&room.id);
             println!("{} {}", "joined room".green(), room.id.cyan());
         }
+        ReplCommand::CreateInvite(room_id, expires_in_secs) => {
+            let invitation = state
+                .client
+                .create_invitation(&room_id, expires_in_secs)
+                .await?;
+            println!(
+                "{} {} (expires {})",
+                "invitation:".green(),
+                invitation.token.cyan(),
+                invitation.expires_at.to_rfc3339()
+            );
+        }
+        ReplCommand::ApproveJoin(room_id, member_id) => {
+            let approved = state.client.approve_join(&room_id, &member_id).await?;
+            println!(
+                "{} {} -> {}",
+                "approved".green(),
+                approved.member_id.cyan(),
+                approved.room_id.cyan()
+            );
+        }
         ReplCommand::Send(message) => {
             let member_id = state.member_id.as_deref().ok_or_else(|| {
                 CliError::InvalidArgument("login required before `send`".to_string())
@@ -343,18 +569,28 @@ async fn run_repl_command(state: &mut ReplState, command: ReplCommand) -> Result
                 room_id.cyan()
             );
         }
-        ReplCommand::ListRooms => {
-            if state.known_rooms.is_empty() {
-                println!("{}", "no known rooms yet".yellow());
-            } else {
-                for (room_id, room_name) in &state.known_rooms {
-                    let marker = if state.current_room.as_deref() == Some(room_id.as_str()) {
-                        "*"
-                    } else {
-                        " "
-                    };
-                    println!("{marker} {room_id} ({room_name})");
-                }
+        ReplCommand::ListRooms(search) => {
+            let rooms = state
+                .client
+                .list_rooms(100, 0, search.as_deref(), None)
+                .await?;
+            if rooms.rooms.is_empty() {
+                println!("{}", "no rooms found".yellow());
+            }
+            for room in &rooms.rooms {
+                state.known_rooms.insert(room.id.clone(), room.name.clone());
+                let marker = if state.current_room.as_deref() == Some(room.id.as_str()) {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{marker} {} ({})", room.id, room.name);
+            }
+            if rooms.total > rooms.rooms.len() {
+                println!(
+                    "{}",
+                    format!("... and {} more", rooms.total - rooms.rooms.len()).yellow()
+                );
             }
         }
         ReplCommand::ListMembers => {
@@ -368,45 +604,109 @@ async fn run_repl_command(state: &mut ReplState, command: ReplCommand) -> Result
             let room_id = state
                 .current_room
                 .as_ref()
-                .and_then(|r| r.parse::<uuid::Uuid>().ok());
+                .and_then(|r| r.parse::<RoomId>().ok());
             let response = state.client.search(&query, 10, room_id, None).await?;
+            print_search_results(
+                &format!("Search results for: {}", response.query),
+                &response,
+            );
+        }
+        ReplCommand::Similar(message_id) => {
+            let response = state
+                .client
+                .similar(&message_id, 10, state.current_room.as_deref(), None)
+                .await?;
+            print_search_results(
+                &format!("Messages similar to {message_id}: {}", response.query),
+                &response,
+            );
+        }
+        ReplCommand::Regen(message_id) => {
+            let regenerated = state.client.regenerate_message(&message_id).await?;
             println!(
-                "{}",
-                format!("Search results for: {}", response.query).bright_blue()
+                "{} {} (generation {} of {})",
+                "AI:".bright_magenta(),
+                regenerated.message_id.cyan(),
+                regenerated.branch.index + 1,
+                regenerated.branch.origin_id
             );
-            if response.results.is_empty() {
-                println!("{}", "No results found.".yellow());
-            } else {
-                for (i, result) in response.results.iter().enumerate() {
-                    println!(
-                        "{}. {} [score: {:.3}]",
-                        (i + 1).to_string().cyan(),
-                        result.content.chars().take(80).collect::<String>(),
-                        result.score
-                    );
-                    if let Some(room_id) = result.room_id {
-                        println!("   {}", format!("Room: {}", room_id).dimmed());
-                    }
-                }
-                println!("{}", format!("Total: {} results", response.total).green());
-            }
+            println!("{}", regenerated.content);
         }
         ReplCommand::Help => {
             println!("{}", help_text().bright_blue());
         }
-        ReplCommand::Ai(prompt) => {
+        ReplCommand::Ai(command) => {
             let room_id = state.current_room.as_deref().ok_or_else(|| {
                 CliError::InvalidArgument("join-room required before `@ai`".to_string())
             })?;
-            let reply = stream_ai_response(&prompt).await?;
+            let model = command
+                .model
+                .clone()
+                .or_else(|| std::env::var("NEXIS_AI_MODEL").ok());
+            if let Some(model) = &command.model {
+                // Gateways without room settings don't restrict models.
+                if let Ok(settings) = state.client.room_settings(room_id).await {
+                    if !settings.model_allowed(model) {
+                        return Err(CliError::InvalidArgument(format!(
+                            "model {model} is not allowed in this room (allowed: {})",
+                            settings.allowed_models.join(", ")
+                        )));
+                    }
+                }
+            }
+            let provider = ai_provider(command.provider.as_deref())?;
+            let room = state.client.get_room(room_id).await?;
+            let assembled = ai_prompt_assembler(model.as_deref().unwrap_or_default()).assemble(
+                None,
+                room_history(&room),
+                &command.prompt,
+            );
             let ai_sender = std::env::var("NEXIS_AI_MEMBER")
                 .unwrap_or_else(|_| "nexis:ai:assistant".to_string());
-            let _ = state
-                .client
-                .send_message(room_id.to_string(), ai_sender, reply)
-                .await?;
+            // Streams are sent as the logged-in member. Gateways without
+            // message streams, and sessions without a member, still get the
+            // whole reply.
+            let stream_sender = state.member_id.clone();
+            let mut stream = match &stream_sender {
+                Some(_) => match state.client.open_stream(room_id).await {
+                    Ok(stream_id) => Some(RoomStream::new(&state.client, room_id, stream_id)),
+                    Err(err) => {
+                        println!("{} not streaming to the room: {err}", "warning:".yellow());
+                        None
+                    }
+                },
+                None => None,
+            };
+            if let (Some(stream), Ok(mut own)) = (&stream, state.own_stream.lock()) {
+                *own = Some(stream.stream_id.clone());
+            }
+            let reply =
+                stream_ai_response(provider, model, &assembled.prompt, stream.as_mut()).await;
+            if let Ok(mut own) = state.own_stream.lock() {
+                *own = None;
+            }
+            let stream_id = stream.map(|stream| stream.stream_id);
+            let reply = match (reply, &stream_id) {
+                (Ok(reply), _) => reply,
+                (Err(err), Some(stream_id)) => {
+                    let _ = state.client.abort_stream(room_id, stream_id).await;
+                    return Err(err);
+                }
+                (Err(err), None) => return Err(err),
+            };
+            let room_id = room_id.to_string();
+            let _ = match (stream_id, stream_sender) {
+                (Some(stream_id), Some(sender)) => {
+                    state
+                        .client
+                        .finish_stream(room_id, stream_id, sender, reply)
+                        .await?
+                }
+                _ => state.client.send_message(room_id, ai_sender, reply).await?,
+            };
         }
         ReplCommand::Exit => {
+            state.stop_feed();
             println!("{}", "bye".bright_green());
             return Ok(true);
         }
@@ -420,6 +720,27 @@ async fn run_repl_command(state: &mut ReplState, command: ReplCommand) -> Result
     Ok(false)
 }
 
+fn print_search_results(heading: &str, response: &SearchResponse) {
+    println!("{}", heading.bright_blue());
+    if response.results.is_empty() {
+        println!("{}", "No results found.".yellow());
+        return;
+    }
+    for (i, result) in response.results.iter().enumerate() {
+        println!(
+            "{}. {} [score: {:.3}]",
+            (i + 1).to_string().cyan(),
+            result.content.chars().take(80).collect::<String>(),
+            result.score
+        );
+        println!("   {}", format!("Id: {}", result.id).dimmed());
+        if let Some(room_id) = &result.room_id {
+            println!("   {}", format!("Room: {}", room_id).dimmed());
+        }
+    }
+    println!("{}", format!("Total: {} results", response.total).green());
+}
+
 fn print_members(room: &RoomInfoResponse, current_member: Option<&str>) {
     let mut members = BTreeSet::new();
     if let Some(member) = current_member {
@@ -439,24 +760,122 @@ fn print_members(room: &RoomInfoResponse, current_member: Option<&str>) {
     }
 }
 
-async fn stream_ai_response(prompt: &str) -> Result<String, CliError> {
-    let provider_name = std::env::var("NEXIS_AI_PROVIDER").unwrap_or_else(|_| "openai".to_string());
-    let provider: Arc<dyn AIProvider> = match provider_name.as_str() {
-        "openai" => Arc::new(OpenAIProvider::from_env()),
-        "anthropic" => Arc::new(AnthropicProvider::from_env()),
-        other => {
-            return Err(CliError::InvalidArgument(format!(
-                "unsupported AI provider `{other}`"
-            )));
+fn ai_prompt_assembler(model: &str) -> PromptAssembler {
+    let window = std::env::var("NEXIS_AI_CONTEXT_TOKENS")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .map(ContextWindow::new)
+        .unwrap_or_else(|| ContextWindow::for_model(model));
+    PromptAssembler::new(window).with_token_counter(counter_for_model(model))
+}
+
+fn room_history(room: &RoomInfoResponse) -> Vec<ContextMessage> {
+    room.messages
+        .iter()
+        .map(|message| {
+            let context_message = if message.sender.starts_with("nexis:ai:") {
+                ContextMessage::assistant(message.text.clone())
+            } else {
+                ContextMessage::user(message.text.clone())
+            };
+            context_message.with_author(message.sender.clone())
+        })
+        .collect()
+}
+
+/// Whether `event` is a chunk of the message this REPL is streaming.
+fn is_own_delta(event: &serde_json::Value, own_stream: &Mutex<Option<String>>) -> bool {
+    event["type"] == "message_delta"
+        && own_stream.lock().is_ok_and(|own| {
+            own.as_deref()
+                .is_some_and(|stream_id| event["messageId"] == stream_id)
+        })
+}
+
+/// Pushes a streaming reply to the room in batches, so a fast model doesn't
+/// cost a request per token.
+struct RoomStream<'a> {
+    client: &'a CliClient,
+    room_id: &'a str,
+    stream_id: String,
+    pending: String,
+    flushed_at: Instant,
+    /// Set once an append failed; the rest of the reply is only printed.
+    failed: bool,
+}
+
+impl<'a> RoomStream<'a> {
+    fn new(client: &'a CliClient, room_id: &'a str, stream_id: String) -> Self {
+        Self {
+            client,
+            room_id,
+            stream_id,
+            pending: String::new(),
+            flushed_at: Instant::now(),
+            failed: false,
+        }
+    }
+
+    async fn push(&mut self, text: &str) {
+        self.pending.push_str(text);
+        if self.flushed_at.elapsed() >= STREAM_FLUSH_INTERVAL {
+            self.flush().await;
+        }
+    }
+
+    async fn flush(&mut self) {
+        if self.pending.is_empty() || self.failed {
+            return;
+        }
+        if let Err(err) = self
+            .client
+            .append_stream(self.room_id, &self.stream_id, &self.pending)
+            .await
+        {
+            self.failed = true;
+            println!();
+            println!(
+                "{} stopped streaming to the room: {err}",
+                "warning:".yellow()
+            );
         }
+        self.pending.clear();
+        self.flushed_at = Instant::now();
+    }
+}
+
+/// The provider named `name`, or by `NEXIS_AI_PROVIDER` (default `openai`).
+fn ai_provider(name: Option<&str>) -> Result<Arc<dyn AIProvider>, CliError> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => std::env::var("NEXIS_AI_PROVIDER").unwrap_or_else(|_| "openai".to_string()),
     };
+    match name.as_str() {
+        "openai" => Ok(Arc::new(OpenAIProvider::from_env())),
+        "anthropic" => Ok(Arc::new(AnthropicProvider::from_env())),
+        "ollama" => Ok(Arc::new(OpenAICompatibleProvider::ollama_from_env())),
+        "vllm" => Ok(Arc::new(OpenAICompatibleProvider::vllm_from_env())),
+        other => Err(CliError::InvalidArgument(format!(
+            "unsupported AI provider `{other}`"
+        ))),
+    }
+}
 
+/// Print the reply to `prompt` as it is generated, and push it to `room`
+/// when streaming to the room.
+async fn stream_ai_response(
+    provider: Arc<dyn AIProvider>,
+    model: Option<String>,
+    prompt: &str,
+    mut room: Option<&mut RoomStream<'_>>,
+) -> Result<String, CliError> {
     let request = GenerateRequest {
         prompt: prompt.to_string(),
-        model: std::env::var("NEXIS_AI_MODEL").ok(),
+        model,
         max_tokens: Some(300),
         temperature: Some(0.7),
         metadata: None,
+        images: Vec::new(),
     };
 
     let mut stream = provider
@@ -472,10 +891,16 @@ async fn stream_ai_response(prompt: &str) -> Result<String, CliError> {
                 response.push_str(&text);
                 print!("{text}");
                 let _ = io::stdout().flush();
+                if let Some(room) = room.as_deref_mut() {
+                    room.push(&text).await;
+                }
             }
             StreamChunk::Done => {}
         }
     }
+    if let Some(room) = room {
+        room.flush().await;
+    }
     println!();
     Ok(response)
 }
@@ -497,7 +922,11 @@ fn complete_candidates(prefix: &str) -> BTreeSet<&'static str> {
 
 #[cfg(test)]
 mod tests {
-    use super::{complete_candidates, help_text, parse_command, ReplCommand};
+    use super::{
+        complete_candidates, help_text, parse_command, room_history, AiCommand, ReplCommand,
+    };
+    use nexis_cli::{RoomInfoResponse, StoredMessage};
+    use nexis_context::MessageRole;
 
     #[test]
     fn parse_send_uses_message_tail() {
@@ -508,7 +937,96 @@ mod tests {
     #[test]
     fn parse_ai_command() {
         let command = parse_command("@ai summarize this");
-        assert_eq!(command, ReplCommand::Ai("summarize this".to_string()));
+        assert_eq!(
+            command,
+            ReplCommand::Ai(AiCommand {
+                prompt: "summarize this".to_string(),
+                model: None,
+                provider: None,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_ai_command_with_overrides() {
+        let command = parse_command("@ai --model  gpt-4o --provider anthropic summarize  this");
+        assert_eq!(
+            command,
+            ReplCommand::Ai(AiCommand {
+                prompt: "summarize  this".to_string(),
+                model: Some("gpt-4o".to_string()),
+                provider: Some("anthropic".to_string()),
+            })
+        );
+        assert!(matches!(
+            parse_command("@ai --model gpt-4o"),
+            ReplCommand::Unknown(_)
+        ));
+        assert!(matches!(
+            parse_command("@ai --temperature 1 hi"),
+            ReplCommand::Unknown(_)
+        ));
+    }
+
+    #[test]
+    fn parse_list_rooms_takes_an_optional_filter() {
+        assert_eq!(parse_command("list-rooms"), ReplCommand::ListRooms(None));
+        assert_eq!(
+            parse_command("list-rooms  eng ops "),
+            ReplCommand::ListRooms(Some("eng ops".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_similar_requires_message_id() {
+        assert_eq!(
+            parse_command("similar msg_1"),
+            ReplCommand::Similar("msg_1".to_string())
+        );
+        assert_eq!(
+            parse_command("similar"),
+            ReplCommand::Unknown("usage: similar <message_id>".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_regen_requires_message_id() {
+        assert_eq!(
+            parse_command("regen msg_1"),
+            ReplCommand::Regen("msg_1".to_string())
+        );
+        assert_eq!(
+            parse_command("regen"),
+            ReplCommand::Unknown("usage: regen <message_id>".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_join_commands() {
+        assert_eq!(
+            parse_command("join-room room_1"),
+            ReplCommand::JoinRoom("room_1".to_string(), None)
+        );
+        assert_eq!(
+            parse_command("join-room room_1 inv_abc"),
+            ReplCommand::JoinRoom("room_1".to_string(), Some("inv_abc".to_string()))
+        );
+        assert_eq!(
+            parse_command("create-invite room_1 3600"),
+            ReplCommand::CreateInvite("room_1".to_string(), Some(3600))
+        );
+        assert_eq!(
+            parse_command("create-invite room_1 soon"),
+            ReplCommand::Unknown("usage: create-invite <room_id> [expires_secs]".to_string())
+        );
+        assert_eq!(
+            parse_command("approve-join room_1 alice"),
+            ReplCommand::ApproveJoin("room_1".to_string(), "alice".to_string())
+        );
+        assert_eq!(
+            parse_command("approve-join room_1"),
+            ReplCommand::Unknown("usage: approve-join <room_id> <member_id>".to_string())
+        );
     }
 
     #[test]
@@ -539,14 +1057,46 @@ mod tests {
             "logout",
             "create-room <name>",
             "join-room <room_id>",
+            "create-invite <room_id>",
+            "approve-join <room_id>",
             "send <message>",
             "reply <message_id>",
             "invite-member <room_id>",
             "list-rooms",
             "list-members",
+            "regen <message_id>",
             "@ai <message>",
         ] {
             assert!(help.contains(command), "help text missing `{command}`");
         }
     }
+
+    #[test]
+    fn room_history_maps_ai_senders_to_assistant() {
+        let room = RoomInfoResponse {
+            id: "room_1".to_string(),
+            name: "general".to_string(),
+            topic: None,
+            messages: vec![
+                StoredMessage {
+                    id: "msg_1".to_string(),
+                    sender: "nexis:human:alice".to_string(),
+                    text: "hello".to_string(),
+                    reply_to: None,
+                },
+                StoredMessage {
+                    id: "msg_2".to_string(),
+                    sender: "nexis:ai:assistant".to_string(),
+                    text: "hi alice".to_string(),
+                    reply_to: None,
+                },
+            ],
+        };
+
+        let history = room_history(&room);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].role, MessageRole::User);
+        assert_eq!(history[0].author.as_deref(), Some("nexis:human:alice"));
+        assert_eq!(history[1].role, MessageRole::Assistant);
+    }
 }
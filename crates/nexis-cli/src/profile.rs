@@ -0,0 +1,275 @@
+//! Named connection profiles stored in `~/.config/nexis/config.toml`.
+//!
+//! A profile holds the server URL, member id and tokens for one deployment.
+//! Settings resolve as flag > environment > profile > built-in default, so
+//! a profile only fills in what the invocation leaves open.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::CliError;
+
+pub const DEFAULT_SERVER: &str = "http://127.0.0.1:8080";
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Keys accepted by `config set` and `config get`.
+pub const PROFILE_KEYS: &[&str] = &["server", "member", "token", "refresh-token"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+}
+
+impl Profile {
+    pub fn get(&self, key: &str) -> Result<Option<&str>, CliError> {
+        let value = match key {
+            "server" => &self.server,
+            "member" => &self.member,
+            "token" => &self.token,
+            "refresh-token" => &self.refresh_token,
+            other => return Err(unknown_key(other)),
+        };
+        Ok(value.as_deref())
+    }
+
+    /// Set `key`; an empty value clears it.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), CliError> {
+        let slot = match key {
+            "server" => &mut self.server,
+            "member" => &mut self.member,
+            "token" => &mut self.token,
+            "refresh-token" => &mut self.refresh_token,
+            other => return Err(unknown_key(other)),
+        };
+        *slot = (!value.trim().is_empty()).then(|| value.trim().to_string());
+        Ok(())
+    }
+}
+
+fn unknown_key(key: &str) -> CliError {
+    CliError::InvalidArgument(format!(
+        "unknown profile key `{key}` (expected one of: {})",
+        PROFILE_KEYS.join(", ")
+    ))
+}
+
+/// Contents of the CLI config file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CliConfig {
+    /// Profile used when neither `--profile` nor `NEXIS_PROFILE` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_profile: Option<String>,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Settings for one invocation after applying the precedence rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connection {
+    pub profile: String,
+    pub server: String,
+    pub member: Option<String>,
+    pub token: Option<String>,
+}
+
+impl CliConfig {
+    /// Config file location: `NEXIS_CLI_CONFIG`, then
+    /// `$XDG_CONFIG_HOME/nexis/config.toml`, then `~/.config/nexis/config.toml`.
+    pub fn default_path() -> PathBuf {
+        if let Ok(path) = std::env::var("NEXIS_CLI_CONFIG") {
+            return PathBuf::from(path);
+        }
+        let base = std::env::var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .unwrap_or_else(|_| PathBuf::from("."));
+        base.join("nexis").join("config.toml")
+    }
+
+    /// Read `path`; a missing file is an empty config.
+    pub fn load(path: &Path) -> Result<Self, CliError> {
+        let raw = match fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(CliError::Config(format!(
+                    "failed to read {}: {err}",
+                    path.display()
+                )))
+            }
+        };
+        toml::from_str(&raw)
+            .map_err(|err| CliError::Config(format!("invalid {}: {err}", path.display())))
+    }
+
+    /// Write the config, readable by the owner only since it holds tokens.
+    pub fn save(&self, path: &Path) -> Result<(), CliError> {
+        let write_error = |err: std::io::Error| {
+            CliError::Config(format!("failed to write {}: {err}", path.display()))
+        };
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).map_err(write_error)?;
+        }
+        let raw = toml::to_string_pretty(self).map_err(|err| CliError::Config(err.to_string()))?;
+        let tmp = path.with_extension("toml.tmp");
+        fs::write(&tmp, raw).map_err(write_error)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600)).map_err(write_error)?;
+        }
+        fs::rename(&tmp, path).map_err(write_error)
+    }
+
+    /// Name of the profile in effect: `flag`, then `NEXIS_PROFILE`, then the
+    /// current profile, then `default`.
+    pub fn profile_name(&self, flag: Option<&str>, env: impl Fn(&str) -> Option<String>) -> String {
+        flag.map(str::to_string)
+            .or_else(|| env("NEXIS_PROFILE"))
+            .or_else(|| self.current_profile.clone())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+
+    pub fn profile_mut(&mut self, name: &str) -> &mut Profile {
+        self.profiles.entry(name.to_string()).or_default()
+    }
+
+    /// Resolve the connection settings for one invocation.
+    pub fn resolve(
+        &self,
+        profile_flag: Option<&str>,
+        server_flag: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Connection {
+        let name = self.profile_name(profile_flag, &env);
+        let profile = self.profile(&name).cloned().unwrap_or_default();
+        Connection {
+            server: server_flag
+                .map(str::to_string)
+                .or_else(|| env("NEXIS_SERVER"))
+                .or(profile.server)
+                .unwrap_or_else(|| DEFAULT_SERVER.to_string()),
+            member: env("NEXIS_MEMBER").or(profile.member),
+            token: env("NEXIS_TOKEN").or(profile.token),
+            profile: name,
+        }
+    }
+
+    /// Remember a freshly issued token pair on `profile`.
+    pub fn store_tokens(&mut self, profile: &str, token: String, refresh_token: Option<String>) {
+        let profile = self.profile_mut(profile);
+        profile.token = Some(token);
+        if refresh_token.is_some() {
+            profile.refresh_token = refresh_token;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |key| {
+            vars.iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn flags_beat_env_which_beats_the_profile() {
+        let mut config = CliConfig {
+            current_profile: Some("staging".to_string()),
+            ..CliConfig::default()
+        };
+        config
+            .profile_mut("staging")
+            .set("server", "https://staging.nexis.ai")
+            .unwrap();
+        config
+            .profile_mut("staging")
+            .set("token", "staging-jwt")
+            .unwrap();
+        config
+            .profile_mut("prod")
+            .set("server", "https://nexis.ai")
+            .unwrap();
+
+        let connection = config.resolve(None, None, env(&[]));
+        assert_eq!(connection.profile, "staging");
+        assert_eq!(connection.server, "https://staging.nexis.ai");
+        assert_eq!(connection.token.as_deref(), Some("staging-jwt"));
+
+        let connection = config.resolve(
+            None,
+            None,
+            env(&[("NEXIS_PROFILE", "prod"), ("NEXIS_TOKEN", "env-jwt")]),
+        );
+        assert_eq!(connection.server, "https://nexis.ai");
+        assert_eq!(connection.token.as_deref(), Some("env-jwt"));
+
+        let connection = config.resolve(
+            Some("prod"),
+            Some("http://localhost:9000"),
+            env(&[("NEXIS_SERVER", "http://env:8080")]),
+        );
+        assert_eq!(connection.profile, "prod");
+        assert_eq!(connection.server, "http://localhost:9000");
+
+        let connection = CliConfig::default().resolve(None, None, env(&[]));
+        assert_eq!(connection.profile, DEFAULT_PROFILE);
+        assert_eq!(connection.server, DEFAULT_SERVER);
+        assert!(config.profile_mut("prod").set("password", "x").is_err());
+    }
+
+    #[test]
+    fn config_round_trips_through_the_file() {
+        let dir = std::env::temp_dir().join(format!("nexis-cli-profile-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("nexis").join("config.toml");
+        assert_eq!(CliConfig::load(&path).unwrap(), CliConfig::default());
+
+        let mut config = CliConfig {
+            current_profile: Some("prod".to_string()),
+            ..CliConfig::default()
+        };
+        config
+            .profile_mut("prod")
+            .set("server", "https://nexis.ai")
+            .unwrap();
+        config.store_tokens("prod", "jwt".to_string(), Some("refresh".to_string()));
+        config.save(&path).unwrap();
+
+        let loaded = CliConfig::load(&path).unwrap();
+        assert_eq!(loaded, config);
+        assert_eq!(
+            loaded
+                .profile("prod")
+                .unwrap()
+                .get("refresh-token")
+                .unwrap(),
+            Some("refresh")
+        );
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(raw.contains("[profiles.prod]"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
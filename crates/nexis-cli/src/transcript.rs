@@ -0,0 +1,380 @@
+//! `nexis-cli export`: a room's messages as a readable transcript.
+//!
+//! Messages are fetched page by page from `GET /v1/rooms/:id/messages`.
+//! Markdown and HTML transcripts nest each reply under the message it
+//! answers; JSONL keeps the messages flat, in order, with `reply_to` intact.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{CliClient, CliError};
+
+/// Messages requested per page.
+pub const PAGE_SIZE: usize = 200;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TranscriptFormat {
+    #[default]
+    Jsonl,
+    Markdown,
+    Html,
+}
+
+/// Reference to an uploaded file, as stored on a message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: String,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptMessage {
+    pub id: String,
+    pub sender: String,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePage {
+    pub messages: Vec<TranscriptMessage>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Optional `[since, until)` window on message creation time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Parse an RFC 3339 instant, or a date meaning the start of that day (UTC).
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    parse_bound(value, false)
+}
+
+/// Like [`parse_since`], but a bare date includes the whole of that day.
+pub fn parse_until(value: &str) -> Result<DateTime<Utc>, String> {
+    parse_bound(value, true)
+}
+
+fn parse_bound(value: &str, end_of_day: bool) -> Result<DateTime<Utc>, String> {
+    if let Ok(instant) = DateTime::parse_from_rfc3339(value) {
+        return Ok(instant.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("`{value}` is not a date (YYYY-MM-DD) or RFC 3339 time"))?;
+    let date = if end_of_day {
+        date.succ_opt()
+            .ok_or_else(|| format!("`{value}` is out of range"))?
+    } else {
+        date
+    };
+    Ok(date.and_time(chrono::NaiveTime::MIN).and_utc())
+}
+
+/// Every message of `room_id` within `range`, oldest first.
+pub async fn fetch_transcript(
+    client: &CliClient,
+    room_id: &str,
+    range: DateRange,
+) -> Result<Vec<TranscriptMessage>, CliError> {
+    let mut messages = Vec::new();
+    let mut after = None;
+    loop {
+        let page = client
+            .message_page(room_id, after.as_deref(), range, PAGE_SIZE)
+            .await?;
+        messages.extend(page.messages);
+        match page.next_cursor {
+            Some(cursor) => after = Some(cursor),
+            None => return Ok(messages),
+        }
+    }
+}
+
+/// Render `messages` of `room_id`. `server` is used to link attachments.
+pub fn render_transcript(
+    room_id: &str,
+    messages: &[TranscriptMessage],
+    format: TranscriptFormat,
+    server: &str,
+) -> Result<String, CliError> {
+    match format {
+        TranscriptFormat::Jsonl => messages
+            .iter()
+            .map(|message| {
+                serde_json::to_string(message).map_err(|err| CliError::Decode(err.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|lines| lines.join("\n")),
+        TranscriptFormat::Markdown => Ok(markdown(room_id, messages, server)),
+        TranscriptFormat::Html => Ok(html(room_id, messages, server)),
+    }
+}
+
+/// Messages in display order with their reply depth. Replies follow their
+/// parent, oldest first; a reply whose parent is outside the transcript
+/// starts a thread of its own.
+fn threaded(messages: &[TranscriptMessage]) -> Vec<(usize, &TranscriptMessage)> {
+    let ids: HashSet<&str> = messages.iter().map(|message| message.id.as_str()).collect();
+    let mut roots = Vec::new();
+    let mut replies: HashMap<&str, Vec<&TranscriptMessage>> = HashMap::new();
+    for message in messages {
+        match message
+            .reply_to
+            .as_deref()
+            .filter(|parent| ids.contains(parent))
+        {
+            Some(parent) => replies.entry(parent).or_default().push(message),
+            None => roots.push(message),
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(messages.len());
+    let mut stack: Vec<(usize, &TranscriptMessage)> = roots
+        .into_iter()
+        .rev()
+        .map(|message| (0, message))
+        .collect();
+    while let Some((depth, message)) = stack.pop() {
+        ordered.push((depth, message));
+        if let Some(children) = replies.get(message.id.as_str()) {
+            stack.extend(children.iter().rev().map(|child| (depth + 1, *child)));
+        }
+    }
+    ordered
+}
+
+fn attachment_url(server: &str, attachment: &Attachment) -> String {
+    format!(
+        "{}/v1/uploads/{}/content",
+        server.trim_end_matches('/'),
+        attachment.id
+    )
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+fn timestamp(message: &TranscriptMessage) -> String {
+    message.created_at.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+fn markdown(room_id: &str, messages: &[TranscriptMessage], server: &str) -> String {
+    let mut out = format!("# Transcript of {room_id}\n");
+    if messages.is_empty() {
+        out.push_str("\nNo messages.\n");
+        return out;
+    }
+    out.push('\n');
+    for (depth, message) in threaded(messages) {
+        let indent = "  ".repeat(depth);
+        let _ = write!(
+            out,
+            "{indent}- **{}** · {}",
+            message.sender,
+            timestamp(message)
+        );
+        for line in message.text.lines() {
+            let _ = write!(out, "\n{indent}  {line}");
+        }
+        for attachment in &message.attachments {
+            let _ = write!(
+                out,
+                "\n{indent}  - [{}]({}) ({})",
+                attachment.file_name.replace(['[', ']'], ""),
+                attachment_url(server, attachment),
+                human_size(attachment.size)
+            );
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn html(room_id: &str, messages: &[TranscriptMessage], server: &str) -> String {
+    let title = escape_html(&format!("Transcript of {room_id}"));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>\nbody {{ font-family: sans-serif; max-width: 48rem; margin: 2rem auto; }}\n\
+         ul {{ list-style: none; padding-left: 1.5rem; }}\n\
+         .meta {{ color: #666; font-size: 0.85em; }}\n\
+         .text {{ white-space: pre-wrap; margin: 0.25rem 0 0.75rem; }}\n</style>\n\
+         </head>\n<body>\n<h1>{title}</h1>\n"
+    );
+    if messages.is_empty() {
+        out.push_str("<p>No messages.</p>\n</body>\n</html>\n");
+        return out;
+    }
+
+    out.push_str("<ul>\n");
+    let mut previous: Option<usize> = None;
+    for (depth, message) in threaded(messages) {
+        match previous {
+            // A reply is always exactly one level below its parent.
+            Some(previous) if depth > previous => out.push_str("<ul>\n"),
+            Some(previous) => {
+                out.push_str("</li>\n");
+                for _ in depth..previous {
+                    out.push_str("</ul>\n</li>\n");
+                }
+            }
+            None => {}
+        }
+        let _ = write!(
+            out,
+            "<li id=\"{id}\">\n<div class=\"meta\"><strong>{sender}</strong> · \
+             <time datetime=\"{datetime}\">{time}</time></div>\n\
+             <div class=\"text\">{text}</div>\n",
+            id = escape_html(&message.id),
+            sender = escape_html(&message.sender),
+            datetime = message.created_at.to_rfc3339(),
+            time = timestamp(message),
+            text = escape_html(&message.text),
+        );
+        for attachment in &message.attachments {
+            let _ = writeln!(
+                out,
+                "<div class=\"attachment\"><a href=\"{}\">{}</a> ({})</div>",
+                escape_html(&attachment_url(server, attachment)),
+                escape_html(&attachment.file_name),
+                human_size(attachment.size)
+            );
+        }
+        previous = Some(depth);
+    }
+    out.push_str("</li>\n");
+    for _ in 0..previous.unwrap_or_default() {
+        out.push_str("</ul>\n</li>\n");
+    }
+    out.push_str("</ul>\n</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, reply_to: Option<&str>, minute: u32) -> TranscriptMessage {
+        TranscriptMessage {
+            id: id.to_string(),
+            sender: "nexis:human:alice".to_string(),
+            text: format!("text of {id}"),
+            reply_to: reply_to.map(str::to_string),
+            created_at: NaiveDate::from_ymd_opt(2026, 1, 5)
+                .unwrap()
+                .and_hms_opt(9, minute, 0)
+                .unwrap()
+                .and_utc(),
+            attachments: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn replies_nest_under_their_parent() {
+        let mut messages = vec![
+            message("a", None, 0),
+            message("b", None, 1),
+            message("c", Some("a"), 2),
+            message("d", Some("c"), 3),
+            // Parent filtered out of the transcript.
+            message("e", Some("gone"), 4),
+            message("f", Some("a"), 5),
+        ];
+        messages[3].attachments.push(Attachment {
+            id: "upl_1".to_string(),
+            file_name: "notes.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            size: 2048,
+        });
+        let order: Vec<(usize, &str)> = threaded(&messages)
+            .into_iter()
+            .map(|(depth, message)| (depth, message.id.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            [(0, "a"), (1, "c"), (2, "d"), (1, "f"), (0, "b"), (0, "e")]
+        );
+
+        let markdown = render_transcript(
+            "room_1",
+            &messages,
+            TranscriptFormat::Markdown,
+            "http://nexis.test/",
+        )
+        .unwrap();
+        assert!(markdown.contains(
+            "    - **nexis:human:alice** · 2026-01-05 09:03 UTC\n      text of d\n      \
+             - [notes.pdf](http://nexis.test/v1/uploads/upl_1/content) (2.0 KB)\n"
+        ));
+
+        let html = render_transcript("room_1", &messages, TranscriptFormat::Html, "").unwrap();
+        assert_eq!(html.matches("<ul>").count(), 3);
+        assert_eq!(html.matches("</ul>").count(), 3);
+        assert_eq!(html.matches("<li ").count(), html.matches("</li>").count());
+
+        let jsonl = render_transcript("room_1", &messages, TranscriptFormat::Jsonl, "").unwrap();
+        let first: TranscriptMessage = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(first, messages[0]);
+    }
+
+    #[test]
+    fn date_bounds_accept_dates_and_instants() {
+        assert_eq!(
+            parse_since("2026-01-05").unwrap().to_rfc3339(),
+            "2026-01-05T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_until("2026-01-05").unwrap().to_rfc3339(),
+            "2026-01-06T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_until("2026-01-05T10:30:00+02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2026-01-05T08:30:00+00:00"
+        );
+        assert!(parse_since("yesterday").is_err());
+    }
+}
@@ -0,0 +1,26 @@
+//! Error types for the client crate.
+
+use thiserror::Error;
+
+/// Unified error type for client operations.
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The request never got a response.
+    #[error("request failed: {0}")]
+    Http(String),
+    /// The gateway answered with a non-success status.
+    #[error("gateway returned {status}: {message}")]
+    Status { status: u16, message: String },
+    /// A body or frame did not have the expected shape.
+    #[error("cannot decode response: {0}")]
+    Decode(String),
+    /// The WebSocket could not be opened or failed mid-stream.
+    #[error("websocket error: {0}")]
+    WebSocket(String),
+    /// The gateway closed the WebSocket.
+    #[error("websocket closed")]
+    Closed,
+}
+
+/// Standard result type for client operations.
+pub type ClientResult<T> = Result<T, ClientError>;
@@ -0,0 +1,307 @@
+//! HTTP half of the client.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::{ClientError, ClientResult};
+use crate::transport::{HttpTransport, Method};
+use crate::types::{
+    CreateRoomRequest, HistoryPage, HistoryQuery, LoginRequest, LoginResponse, Room, RoomInfo,
+    SearchRequest, SearchResponse, SendMessageRequest, SendMessageResponse,
+};
+use crate::ws::RoomSocket;
+
+/// Client for one gateway, optionally acting as one member.
+#[derive(Debug, Clone)]
+pub struct Client {
+    base_url: String,
+    token: Option<String>,
+    transport: HttpTransport,
+}
+
+impl Client {
+    /// Client for the gateway at `base_url`, e.g. `https://api.nexis.ai`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+            transport: HttpTransport::new(),
+        }
+    }
+
+    /// Send `token` as the bearer token of every request.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn set_token(&mut self, token: Option<String>) {
+        self.token = token;
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Gateway WebSocket endpoint for the base URL.
+    pub fn websocket_url(&self) -> String {
+        let base = &self.base_url;
+        if let Some(rest) = base.strip_prefix("https://") {
+            format!("wss://{rest}/ws")
+        } else if let Some(rest) = base.strip_prefix("http://") {
+            format!("ws://{rest}/ws")
+        } else {
+            format!("{base}/ws")
+        }
+    }
+
+    /// Trade a member's secret for tokens; pass the access token to
+    /// [`Client::with_token`] to act as that member.
+    pub async fn login(
+        &self,
+        member_id: impl Into<String>,
+        secret: impl Into<String>,
+    ) -> ClientResult<LoginResponse> {
+        let body = LoginRequest {
+            member_id: member_id.into(),
+            secret: secret.into(),
+        };
+        self.call(Method::Post, "/v1/auth/login", Some(&body)).await
+    }
+
+    pub async fn create_room(&self, request: &CreateRoomRequest) -> ClientResult<Room> {
+        self.call(Method::Post, "/v1/rooms", Some(request)).await
+    }
+
+    pub async fn get_room(&self, id: &str) -> ClientResult<RoomInfo> {
+        let path = format!("/v1/rooms/{}", encode_component(id));
+        self.call(Method::Get, &path, None::<&()>).await
+    }
+
+    pub async fn delete_room(&self, id: &str) -> ClientResult<()> {
+        let path = format!("/v1/rooms/{}", encode_component(id));
+        self.request(Method::Delete, &path, None::<&()>).await?;
+        Ok(())
+    }
+
+    pub async fn send_message(
+        &self,
+        request: &SendMessageRequest,
+    ) -> ClientResult<SendMessageResponse> {
+        self.call(Method::Post, "/v1/messages", Some(request)).await
+    }
+
+    /// One page of a room's messages; pass `next_cursor` back as `after` for
+    /// the next one.
+    pub async fn list_messages(
+        &self,
+        room_id: &str,
+        query: &HistoryQuery,
+    ) -> ClientResult<HistoryPage> {
+        let mut params = Vec::new();
+        if let Some(after) = &query.after {
+            params.push(("after", after.clone()));
+        }
+        if let Some(limit) = query.limit {
+            params.push(("limit", limit.to_string()));
+        }
+        if let Some(since) = query.since {
+            params.push(("since", since.to_rfc3339()));
+        }
+        if let Some(until) = query.until {
+            params.push(("until", until.to_rfc3339()));
+        }
+        let mut path = format!("/v1/rooms/{}/messages", encode_component(room_id));
+        for (index, (name, value)) in params.iter().enumerate() {
+            let separator = if index == 0 { '?' } else { '&' };
+            path.push_str(&format!("{separator}{name}={}", encode_component(value)));
+        }
+        self.call(Method::Get, &path, None::<&()>).await
+    }
+
+    pub async fn search(&self, request: &SearchRequest) -> ClientResult<SearchResponse> {
+        self.call(Method::Post, "/v1/search", Some(request)).await
+    }
+
+    /// Open the gateway WebSocket, authenticated with the client's token
+    /// where the transport allows it.
+    pub async fn connect(&self) -> ClientResult<RoomSocket> {
+        RoomSocket::connect(&self.websocket_url(), self.token()).await
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&impl Serialize>,
+    ) -> ClientResult<T> {
+        let body = self.request(method, path, body).await?;
+        serde_json::from_str(&body).map_err(|err| ClientError::Decode(err.to_string()))
+    }
+
+    /// Body of a successful response to `method path`.
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&impl Serialize>,
+    ) -> ClientResult<String> {
+        let body = body
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|err| ClientError::Decode(err.to_string()))?;
+        let url = format!("{}{path}", self.base_url);
+        let response = self
+            .transport
+            .send(method, &url, self.token(), body)
+            .await?;
+        if (200..300).contains(&response.status) {
+            return Ok(response.body);
+        }
+        // Gateway errors are `{"error": "..."}`; anything else is shown as is.
+        let message = serde_json::from_str::<serde_json::Value>(&response.body)
+            .ok()
+            .and_then(|value| value["error"].as_str().map(str::to_string))
+            .unwrap_or(response.body);
+        Err(ClientError::Status {
+            status: response.status,
+            message,
+        })
+    }
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+fn encode_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn create_room_sends_bearer_token_and_body() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/v1/rooms")
+                    .header("authorization", "Bearer token-1")
+                    .json_body(json!({ "name": "general" }));
+                then.status(201)
+                    .json_body(json!({ "id": "room_1", "name": "general" }));
+            })
+            .await;
+
+        let client = Client::new(server.base_url()).with_token("token-1");
+        let room = client
+            .create_room(&CreateRoomRequest {
+                name: "general".into(),
+                topic: None,
+            })
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(
+            room,
+            Room {
+                id: "room_1".into(),
+                name: "general".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn gateway_errors_carry_status_and_message() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/rooms/room_missing");
+                then.status(404)
+                    .json_body(json!({ "error": "room not found", "code": "NOT_FOUND" }));
+            })
+            .await;
+
+        let err = Client::new(server.base_url())
+            .get_room("room_missing")
+            .await
+            .unwrap_err();
+
+        match err {
+            ClientError::Status { status, message } => {
+                assert_eq!(status, 404);
+                assert_eq!(message, "room not found");
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn list_messages_encodes_the_query() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/v1/rooms/room_1/messages")
+                    .query_param("after", "msg_1")
+                    .query_param("limit", "2")
+                    .query_param("since", "2026-01-01T00:00:00+00:00");
+                then.status(200).json_body(json!({
+                    "messages": [{
+                        "id": "msg_2",
+                        "sender": "nexis:human:alice@example.com",
+                        "text": "hello",
+                        "created_at": "2026-01-01T12:00:00Z"
+                    }],
+                    "nextCursor": "msg_2"
+                }));
+            })
+            .await;
+
+        let page = Client::new(server.base_url())
+            .list_messages(
+                "room_1",
+                &HistoryQuery {
+                    after: Some("msg_1".into()),
+                    limit: Some(2),
+                    since: Some("2026-01-01T00:00:00Z".parse().unwrap()),
+                    until: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(page.messages[0].text, "hello");
+        assert_eq!(page.next_cursor.as_deref(), Some("msg_2"));
+    }
+
+    #[test]
+    fn websocket_url_follows_the_scheme() {
+        assert_eq!(
+            Client::new("https://api.nexis.ai/").websocket_url(),
+            "wss://api.nexis.ai/ws"
+        );
+        assert_eq!(
+            Client::new("http://localhost:8080").websocket_url(),
+            "ws://localhost:8080/ws"
+        );
+    }
+}
@@ -0,0 +1,34 @@
+//! Nexis Client - typed access to a Nexis gateway
+//!
+//! This crate provides:
+//! - The gateway's HTTP request and response bodies as Rust types
+//! - [`Client`] for rooms, messages, history, search and login over HTTP
+//! - [`RoomSocket`] for room subscriptions over the gateway WebSocket
+//!
+//! Transports are picked by feature. `native` (the default) uses reqwest and
+//! tokio-tungstenite and needs a tokio runtime. `wasm` uses the browser's
+//! `fetch` and `WebSocket` through gloo-net, so the crate builds for
+//! `wasm32-unknown-unknown` and web frontends share these types instead of
+//! re-declaring them in TypeScript:
+//!
+//! ```toml
+//! nexis-client = { version = "0.1", default-features = false, features = ["wasm"] }
+//! ```
+//!
+//! Browsers cannot set headers on a WebSocket handshake, so sockets opened
+//! from `wasm` builds are anonymous even when the client holds a token.
+
+#[cfg(not(any(feature = "native", feature = "wasm")))]
+compile_error!("enable the `native` or `wasm` feature of nexis-client");
+
+pub mod error;
+pub mod http;
+mod transport;
+pub mod types;
+pub mod ws;
+
+pub use error::{ClientError, ClientResult};
+pub use http::Client;
+pub use nexis_protocol as protocol;
+pub use types::*;
+pub use ws::RoomSocket;
@@ -0,0 +1,36 @@
+//! HTTP and WebSocket transports, one pair per target.
+//!
+//! Both expose the same crate-private surface so [`crate::Client`] and
+//! [`crate::RoomSocket`] stay transport-agnostic. With both features on,
+//! `wasm` wins.
+
+#[cfg(all(feature = "native", not(feature = "wasm")))]
+mod native;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+#[cfg(all(feature = "native", not(feature = "wasm")))]
+pub(crate) use native::{HttpTransport, Socket};
+#[cfg(feature = "wasm")]
+pub(crate) use wasm::{HttpTransport, Socket};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Method {
+    Get,
+    Post,
+    Delete,
+}
+
+/// Status and body of an HTTP response.
+#[derive(Debug, Clone)]
+pub(crate) struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Data frame received on a WebSocket; control frames never surface.
+#[derive(Debug, Clone)]
+pub(crate) enum Frame {
+    Text(String),
+    Binary,
+}
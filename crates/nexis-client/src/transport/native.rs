@@ -0,0 +1,101 @@
+//! reqwest and tokio-tungstenite transports.
+
+use futures::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{header::AUTHORIZATION, HeaderValue};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+use super::{Frame, Method, Response};
+use crate::error::{ClientError, ClientResult};
+
+#[derive(Debug, Clone)]
+pub(crate) struct HttpTransport {
+    http: reqwest::Client,
+}
+
+impl HttpTransport {
+    pub(crate) fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub(crate) async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        token: Option<&str>,
+        body: Option<String>,
+    ) -> ClientResult<Response> {
+        let mut request = match method {
+            Method::Get => self.http.get(url),
+            Method::Post => self.http.post(url),
+            Method::Delete => self.http.delete(url),
+        };
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(body) = body {
+            request = request
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|err| ClientError::Http(err.to_string()))?;
+        let status = response.status().as_u16();
+        let body = response
+            .text()
+            .await
+            .map_err(|err| ClientError::Http(err.to_string()))?;
+        Ok(Response { status, body })
+    }
+}
+
+pub(crate) struct Socket {
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+fn socket_error(err: impl ToString) -> ClientError {
+    ClientError::WebSocket(err.to_string())
+}
+
+impl Socket {
+    pub(crate) async fn connect(url: &str, token: Option<&str>) -> ClientResult<Self> {
+        let mut request = url.into_client_request().map_err(socket_error)?;
+        if let Some(token) = token {
+            let value = HeaderValue::from_str(&format!("Bearer {token}")).map_err(socket_error)?;
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+        let (stream, _) = connect_async(request).await.map_err(socket_error)?;
+        Ok(Self { stream })
+    }
+
+    pub(crate) async fn send(&mut self, text: String) -> ClientResult<()> {
+        self.stream
+            .send(Message::Text(text.into()))
+            .await
+            .map_err(socket_error)
+    }
+
+    /// Next data frame; `None` once the gateway closes the connection.
+    pub(crate) async fn next(&mut self) -> Option<ClientResult<Frame>> {
+        loop {
+            match self.stream.next().await? {
+                Ok(Message::Text(text)) => return Some(Ok(Frame::Text(text.to_string()))),
+                Ok(Message::Binary(_)) => return Some(Ok(Frame::Binary)),
+                Ok(Message::Close(_)) => return None,
+                // Pings are answered by tungstenite itself.
+                Ok(_) => {}
+                Err(err) => return Some(Err(socket_error(err))),
+            }
+        }
+    }
+
+    pub(crate) async fn close(mut self) -> ClientResult<()> {
+        self.stream.close(None).await.map_err(socket_error)
+    }
+}
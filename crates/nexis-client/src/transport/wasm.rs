@@ -0,0 +1,89 @@
+//! Browser `fetch` and `WebSocket` transports through gloo-net.
+
+use futures::{SinkExt, StreamExt};
+use gloo_net::http::Request;
+use gloo_net::websocket::{futures::WebSocket, Message, WebSocketError};
+
+use super::{Frame, Method, Response};
+use crate::error::{ClientError, ClientResult};
+
+#[derive(Debug, Clone)]
+pub(crate) struct HttpTransport;
+
+impl HttpTransport {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+
+    pub(crate) async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        token: Option<&str>,
+        body: Option<String>,
+    ) -> ClientResult<Response> {
+        let mut request = match method {
+            Method::Get => Request::get(url),
+            Method::Post => Request::post(url),
+            Method::Delete => Request::delete(url),
+        };
+        if let Some(token) = token {
+            request = request.header("Authorization", &format!("Bearer {token}"));
+        }
+        let request = match body {
+            Some(body) => request
+                .header("Content-Type", "application/json")
+                .body(body),
+            None => request.build(),
+        }
+        .map_err(|err| ClientError::Http(err.to_string()))?;
+        let response = request
+            .send()
+            .await
+            .map_err(|err| ClientError::Http(err.to_string()))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|err| ClientError::Http(err.to_string()))?;
+        Ok(Response { status, body })
+    }
+}
+
+pub(crate) struct Socket {
+    stream: WebSocket,
+}
+
+fn socket_error(err: impl ToString) -> ClientError {
+    ClientError::WebSocket(err.to_string())
+}
+
+impl Socket {
+    /// Browsers cannot add headers to the handshake, so `_token` goes unused
+    /// and the connection is anonymous.
+    pub(crate) async fn connect(url: &str, _token: Option<&str>) -> ClientResult<Self> {
+        let stream = WebSocket::open(url).map_err(socket_error)?;
+        Ok(Self { stream })
+    }
+
+    pub(crate) async fn send(&mut self, text: String) -> ClientResult<()> {
+        self.stream
+            .send(Message::Text(text))
+            .await
+            .map_err(socket_error)
+    }
+
+    /// Next data frame; `None` once the gateway closes the connection.
+    pub(crate) async fn next(&mut self) -> Option<ClientResult<Frame>> {
+        match self.stream.next().await? {
+            Ok(Message::Text(text)) => Some(Ok(Frame::Text(text))),
+            Ok(Message::Bytes(_)) => Some(Ok(Frame::Binary)),
+            Err(WebSocketError::ConnectionClose(_)) => None,
+            Err(err) => Some(Err(socket_error(err))),
+        }
+    }
+
+    pub(crate) async fn close(self) -> ClientResult<()> {
+        self.stream.close(None, None).map_err(socket_error)
+    }
+}
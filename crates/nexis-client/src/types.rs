@@ -0,0 +1,251 @@
+//! Request, response and event bodies of the gateway API.
+//!
+//! Field names follow the gateway's JSON exactly; see
+//! `docs/en/api/reference.md` or the gateway's `/v1/openapi.json`.
+
+use chrono::{DateTime, Utc};
+use nexis_protocol::{AttachmentRef, DelegatedTask, RoomId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Body of `POST /v1/auth/login`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginRequest {
+    pub member_id: String,
+    pub secret: String,
+}
+
+/// Tokens issued by `POST /v1/auth/login`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginResponse {
+    pub member_id: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Access token lifetime in seconds.
+    pub expires_in: u64,
+}
+
+/// Body of `POST /v1/rooms`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateRoomRequest {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+}
+
+/// Room as returned by `POST /v1/rooms`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Room {
+    pub id: String,
+    pub name: String,
+}
+
+/// Room with its messages, as returned by `GET /v1/rooms/{id}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub topic: Option<String>,
+    #[serde(default)]
+    pub messages: Vec<StoredMessage>,
+}
+
+/// Body of `POST /v1/messages`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendMessageRequest {
+    pub room_id: String,
+    pub sender: String,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    /// Client-assigned id; required for signed messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Client timestamp; required for signed messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Base64 Ed25519 signature over the NIP-002 form of the message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Ids of files previously sent to `POST /v1/uploads`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<String>,
+}
+
+/// Id the gateway stored a message under.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SendMessageResponse {
+    pub id: String,
+}
+
+/// A message as the gateway stores and delivers it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredMessage {
+    pub id: String,
+    pub sender: String,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<AttachmentRef>,
+}
+
+/// Query of `GET /v1/rooms/{id}/messages`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryQuery {
+    /// Cursor from the previous page.
+    pub after: Option<String>,
+    pub limit: Option<usize>,
+    /// Only messages created at or after this instant.
+    pub since: Option<DateTime<Utc>>,
+    /// Only messages created before this instant.
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// One page of a room's messages, oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPage {
+    pub messages: Vec<StoredMessage>,
+    /// Absent on the last page.
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Body of `POST /v1/search`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchRequest {
+    pub query: String,
+    pub limit: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<RoomId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+impl SearchRequest {
+    /// Search for `query` with the gateway's default limit of 10.
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            limit: 10,
+            min_score: None,
+            room_id: None,
+            content_type: None,
+        }
+    }
+}
+
+/// Results of `POST /v1/search`, best first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub query: String,
+    pub results: Vec<SearchResultItem>,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResultItem {
+    pub id: Uuid,
+    pub score: f32,
+    pub content: String,
+    #[serde(default)]
+    pub room_id: Option<RoomId>,
+}
+
+/// Frame a client sends on the gateway WebSocket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientFrame {
+    Subscribe {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        /// Last message the client saw; messages after it are replayed.
+        #[serde(rename = "lastEventId", skip_serializing_if = "Option::is_none")]
+        last_event_id: Option<String>,
+    },
+    Unsubscribe {
+        #[serde(rename = "roomId")]
+        room_id: String,
+    },
+    /// The client has seen every message of the room up to `event_id`.
+    Ack {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "eventId")]
+        event_id: String,
+    },
+}
+
+/// Frame the gateway sends on the WebSocket: a room event or a reply to a
+/// [`ClientFrame`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    /// A message was sent to a subscribed room.
+    Message {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        message: StoredMessage,
+    },
+    /// A member's read marker moved forward.
+    ReadMarker {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "memberId")]
+        member_id: String,
+        #[serde(rename = "messageId")]
+        message_id: String,
+    },
+    /// A delegated task was opened or changed status.
+    Task {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        task: DelegatedTask,
+    },
+    /// A member's presence in the room changed.
+    Presence {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "memberId")]
+        member_id: String,
+        status: String,
+    },
+    /// The subscription is live; `replayed` missed messages came before it.
+    Subscribed {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(default)]
+        replayed: usize,
+    },
+    Unsubscribed {
+        #[serde(rename = "roomId")]
+        room_id: String,
+    },
+    Acked {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "eventId")]
+        event_id: String,
+    },
+    /// Too many messages were missed to replay them all; page through the
+    /// room's history instead.
+    ReplayTruncated {
+        #[serde(rename = "roomId")]
+        room_id: String,
+    },
+    /// The gateway could not act on a frame.
+    Error { message: String },
+    /// An event this version of the client does not know.
+    #[serde(other)]
+    Unknown,
+}
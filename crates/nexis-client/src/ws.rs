@@ -0,0 +1,141 @@
+//! WebSocket half of the client.
+
+use crate::error::{ClientError, ClientResult};
+use crate::transport::{Frame, Socket};
+use crate::types::{ClientFrame, ServerEvent};
+
+/// Connection to the gateway WebSocket, carrying JSON frames.
+pub struct RoomSocket {
+    socket: Socket,
+}
+
+impl RoomSocket {
+    /// Connect to `url`, e.g. from [`crate::Client::websocket_url`].
+    pub async fn connect(url: &str, token: Option<&str>) -> ClientResult<Self> {
+        Ok(Self {
+            socket: Socket::connect(url, token).await?,
+        })
+    }
+
+    pub async fn send(&mut self, frame: &ClientFrame) -> ClientResult<()> {
+        let text =
+            serde_json::to_string(frame).map_err(|err| ClientError::Decode(err.to_string()))?;
+        self.socket.send(text).await
+    }
+
+    /// Follow a room; with `last_event_id`, messages after it are replayed
+    /// before [`ServerEvent::Subscribed`].
+    pub async fn subscribe(
+        &mut self,
+        room_id: impl Into<String>,
+        last_event_id: Option<String>,
+    ) -> ClientResult<()> {
+        self.send(&ClientFrame::Subscribe {
+            room_id: room_id.into(),
+            last_event_id,
+        })
+        .await
+    }
+
+    pub async fn unsubscribe(&mut self, room_id: impl Into<String>) -> ClientResult<()> {
+        self.send(&ClientFrame::Unsubscribe {
+            room_id: room_id.into(),
+        })
+        .await
+    }
+
+    /// Record that every message of the room up to `event_id` was seen.
+    pub async fn ack(
+        &mut self,
+        room_id: impl Into<String>,
+        event_id: impl Into<String>,
+    ) -> ClientResult<()> {
+        self.send(&ClientFrame::Ack {
+            room_id: room_id.into(),
+            event_id: event_id.into(),
+        })
+        .await
+    }
+
+    /// Next event from the gateway; `None` once the connection closes.
+    pub async fn next_event(&mut self) -> Option<ClientResult<ServerEvent>> {
+        let event = match self.socket.next().await? {
+            Ok(Frame::Text(text)) => {
+                serde_json::from_str(&text).map_err(|err| ClientError::Decode(err.to_string()))
+            }
+            Ok(Frame::Binary) => Err(ClientError::Decode(
+                "unexpected binary frame on a JSON connection".into(),
+            )),
+            Err(err) => Err(err),
+        };
+        Some(event)
+    }
+
+    pub async fn close(self) -> ClientResult<()> {
+        self.socket.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use serde_json::Value;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    use super::*;
+    use crate::Client;
+
+    #[tokio::test]
+    async fn subscribe_sends_the_frame_and_events_are_typed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let Some(Ok(Message::Text(subscribe))) = ws.next().await else {
+                panic!("expected a subscribe frame");
+            };
+            let subscribe: Value = serde_json::from_str(&subscribe).unwrap();
+            for frame in [
+                r#"{"type":"message","roomId":"room_1","message":{"id":"msg_1","sender":"nexis:human:alice@example.com","text":"hi","created_at":"2026-01-01T12:00:00Z"}}"#,
+                r#"{"type":"subscribed","roomId":"room_1","replayed":1}"#,
+                r#"{"type":"typing","roomId":"room_1"}"#,
+            ] {
+                ws.send(Message::Text(frame.into())).await.unwrap();
+            }
+            ws.close(None).await.unwrap();
+            subscribe
+        });
+
+        let client = Client::new(format!("http://{addr}"));
+        let mut socket = client.connect().await.unwrap();
+        socket
+            .subscribe("room_1", Some("msg_0".into()))
+            .await
+            .unwrap();
+
+        let Some(Ok(ServerEvent::Message { room_id, message })) = socket.next_event().await else {
+            panic!("expected a message event");
+        };
+        assert_eq!(room_id, "room_1");
+        assert_eq!(message.text, "hi");
+        assert_eq!(
+            socket.next_event().await.unwrap().unwrap(),
+            ServerEvent::Subscribed {
+                room_id: "room_1".into(),
+                replayed: 1
+            }
+        );
+        assert_eq!(
+            socket.next_event().await.unwrap().unwrap(),
+            ServerEvent::Unknown
+        );
+        assert!(socket.next_event().await.is_none());
+
+        let subscribe = server.await.unwrap();
+        assert_eq!(subscribe["type"], "subscribe");
+        assert_eq!(subscribe["roomId"], "room_1");
+        assert_eq!(subscribe["lastEventId"], "msg_0");
+    }
+}
@@ -34,6 +34,7 @@ impl AIProvider for FastMockProvider {
             content: "Summarized content".to_string(),
             model: Some("mock".to_string()),
             finish_reason: Some("stop".to_string()),
+            usage: None,
         })
     }
 
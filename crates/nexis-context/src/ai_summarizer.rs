@@ -85,6 +85,7 @@ impl ContextSummarizer for AISummarizer {
             max_tokens: Some(self.config.max_summary_tokens as u32),
             temperature: Some(0.3), // Lower temperature for more consistent summaries
             metadata: None,
+            images: Vec::new(),
         };
 
         match self.provider.generate(request).await {
@@ -158,6 +159,7 @@ mod tests {
                 content: self.response.clone(),
                 model: Some("mock-model".to_string()),
                 finish_reason: Some("stop".to_string()),
+                usage: None,
             })
         }
 
@@ -0,0 +1,312 @@
+//! Per-room cache of recent conversation history
+//!
+//! Every AI call assembles its prompt from the room's recent messages.
+//! [`ConversationCache`] keeps the newest messages of each room in a ring
+//! buffer, so a room's history is loaded from storage once instead of on
+//! every call. Callers keep it current with [`ConversationCache::append`] as
+//! messages arrive and [`ConversationCache::invalidate`] when a room's
+//! history changes any other way, such as deletions or an import; the next
+//! [`ConversationCache::get_or_load`] loads the room again.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use crate::context::Message;
+use crate::error::ContextResult;
+
+#[cfg(feature = "metrics")]
+use crate::metrics::record_cache_lookup;
+
+/// Lookups and contents of a [`ConversationCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct CacheStats {
+    /// Reads served from the cache
+    pub hits: u64,
+    /// Reads that found the room cold
+    pub misses: u64,
+    /// Rooms dropped because their history changed
+    pub invalidations: u64,
+    /// Rooms currently cached
+    pub rooms: usize,
+    /// Messages currently cached across all rooms
+    pub messages: usize,
+}
+
+impl CacheStats {
+    /// Share of reads served from the cache, `0.0` before any read
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Room {
+    /// Newest messages with their ids, oldest first; `None` while cold
+    window: Option<VecDeque<(String, Message)>>,
+    /// Bumped whenever the room changes, so a load that raced with a
+    /// change is not cached
+    version: u64,
+}
+
+/// Ring buffer of the newest messages of each room
+#[derive(Debug)]
+pub struct ConversationCache {
+    capacity: usize,
+    rooms: RwLock<HashMap<String, Room>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl Default for ConversationCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl ConversationCache {
+    /// Default number of messages kept per room
+    pub const DEFAULT_CAPACITY: usize = 200;
+
+    /// Keep the newest `capacity` messages of each room; at least one
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            rooms: RwLock::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    /// Messages kept per room
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Cached history of `room_id`, oldest first, or `None` if it is cold
+    pub async fn get(&self, room_id: &str) -> Option<Vec<Message>> {
+        let messages = self
+            .rooms
+            .read()
+            .await
+            .get(room_id)
+            .and_then(|room| room.window.as_ref())
+            .map(|window| window.iter().map(|(_, message)| message.clone()).collect());
+        self.record_lookup(messages.is_some());
+        messages
+    }
+
+    /// Cached history of `room_id`, loading and caching it on a miss
+    ///
+    /// `load` returns the room's history as `(message id, message)` pairs,
+    /// oldest first; only the newest `capacity` are kept.
+    pub async fn get_or_load<F, Fut>(&self, room_id: &str, load: F) -> ContextResult<Vec<Message>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = ContextResult<Vec<(String, Message)>>>,
+    {
+        if let Some(messages) = self.get(room_id).await {
+            return Ok(messages);
+        }
+
+        let version = self.version(room_id).await;
+        let loaded = load().await?;
+        let window = self.window_of(loaded);
+        let messages = window.iter().map(|(_, message)| message.clone()).collect();
+        let mut rooms = self.rooms.write().await;
+        let room = rooms.entry(room_id.to_string()).or_default();
+        if room.version == version && room.window.is_none() {
+            room.window = Some(window);
+        }
+        Ok(messages)
+    }
+
+    /// Cache `messages` as the history of `room_id`, e.g. at startup
+    pub async fn warm(&self, room_id: &str, messages: Vec<(String, Message)>) {
+        let window = self.window_of(messages);
+        let mut rooms = self.rooms.write().await;
+        let room = rooms.entry(room_id.to_string()).or_default();
+        room.version += 1;
+        room.window = Some(window);
+    }
+
+    /// Add a new message to the end of `room_id`'s history
+    ///
+    /// Dropped if the room is cold; it is part of whatever the next load
+    /// returns. A message already cached is not added twice.
+    pub async fn append(&self, room_id: &str, id: impl Into<String>, message: Message) {
+        let id = id.into();
+        let mut rooms = self.rooms.write().await;
+        let room = rooms.entry(room_id.to_string()).or_default();
+        room.version += 1;
+        let Some(window) = room.window.as_mut() else {
+            return;
+        };
+        if window.iter().any(|(cached, _)| *cached == id) {
+            return;
+        }
+        if window.len() == self.capacity {
+            window.pop_front();
+        }
+        window.push_back((id, message));
+    }
+
+    /// Drop the cached history of `room_id` after it changed
+    pub async fn invalidate(&self, room_id: &str) {
+        let mut rooms = self.rooms.write().await;
+        let Some(room) = rooms.get_mut(room_id) else {
+            return;
+        };
+        room.version += 1;
+        if room.window.take().is_some() {
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Forget `room_id` entirely, e.g. once the room is deleted
+    pub async fn remove(&self, room_id: &str) {
+        self.rooms.write().await.remove(room_id);
+    }
+
+    pub async fn stats(&self) -> CacheStats {
+        let rooms = self.rooms.read().await;
+        let windows = rooms.values().filter_map(|room| room.window.as_ref());
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            rooms: windows.clone().count(),
+            messages: windows.map(VecDeque::len).sum(),
+        }
+    }
+
+    async fn version(&self, room_id: &str) -> u64 {
+        self.rooms
+            .read()
+            .await
+            .get(room_id)
+            .map_or(0, |room| room.version)
+    }
+
+    fn window_of(&self, messages: Vec<(String, Message)>) -> VecDeque<(String, Message)> {
+        let skip = messages.len().saturating_sub(self.capacity);
+        messages.into_iter().skip(skip).collect()
+    }
+
+    fn record_lookup(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        record_cache_lookup(hit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ContextError;
+
+    fn history(count: usize) -> Vec<(String, Message)> {
+        (0..count)
+            .map(|i| (format!("msg_{i}"), Message::user(format!("message {i}"))))
+            .collect()
+    }
+
+    fn contents(messages: &[Message]) -> Vec<&str> {
+        messages
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn loads_once_and_keeps_the_newest_messages() {
+        let cache = ConversationCache::new(3);
+        let loaded = cache
+            .get_or_load("general", || async { Ok(history(5)) })
+            .await
+            .unwrap();
+        assert_eq!(contents(&loaded), ["message 2", "message 3", "message 4"]);
+
+        let cached = cache
+            .get_or_load("general", || async {
+                Err(ContextError::NotFound(
+                    "history was loaded again".to_string(),
+                ))
+            })
+            .await
+            .unwrap();
+        assert_eq!(contents(&cached), contents(&loaded));
+
+        cache
+            .append(
+                "general",
+                "msg_5",
+                Message::assistant("message 5".to_string()),
+            )
+            .await;
+        cache
+            .append(
+                "general",
+                "msg_5",
+                Message::assistant("message 5".to_string()),
+            )
+            .await;
+        let cached = cache.get("general").await.unwrap();
+        assert_eq!(contents(&cached), ["message 3", "message 4", "message 5"]);
+
+        let stats = cache.stats().await;
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert_eq!((stats.rooms, stats.messages), (1, 3));
+        assert!((stats.hit_rate() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn invalidated_rooms_load_again() {
+        let cache = ConversationCache::new(10);
+        cache.warm("general", history(2)).await;
+        cache
+            .append("random", "msg_0", Message::user("dropped".to_string()))
+            .await;
+        assert!(cache.get("random").await.is_none());
+
+        cache.invalidate("general").await;
+        assert!(cache.get("general").await.is_none());
+        let reloaded = cache
+            .get_or_load("general", || async { Ok(history(1)) })
+            .await
+            .unwrap();
+        assert_eq!(contents(&reloaded), ["message 0"]);
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.invalidations, 1);
+        assert_eq!(stats.misses, 3);
+    }
+
+    #[tokio::test]
+    async fn a_load_that_raced_with_a_new_message_is_not_cached() {
+        let cache = ConversationCache::new(10);
+        let loaded = cache
+            .get_or_load("general", || async {
+                // A message arrives while the history is being read.
+                cache
+                    .append("general", "msg_2", Message::user("late".to_string()))
+                    .await;
+                Ok(history(2))
+            })
+            .await
+            .unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert!(cache.get("general").await.is_none());
+    }
+}
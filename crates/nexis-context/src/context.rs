@@ -20,6 +20,9 @@ pub struct Message {
     pub content: String,
     pub created_at: DateTime<Utc>,
     pub token_count: Option<usize>,
+    /// Display name of the sender, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
 }
 
 impl Message {
@@ -30,9 +33,16 @@ impl Message {
             content,
             created_at: Utc::now(),
             token_count: None,
+            author: None,
         }
     }
 
+    /// Attribute the message to a named sender
+    pub fn with_author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
     pub fn user(content: String) -> Self {
         Self::new(MessageRole::User, content)
     }
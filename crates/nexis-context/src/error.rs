@@ -25,6 +25,13 @@ pub enum ContextError {
 
     #[error("Summarization not available")]
     SummarizationNotAvailable,
+
+    #[error("Invalid memory: {0}")]
+    InvalidMemory(String),
+
+    #[cfg(feature = "sqlx")]
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
 }
 
 /// Result type for context operations
@@ -5,17 +5,26 @@
 //! - Token counting (optional, feature-gated)
 //! - Conversation context tracking
 //! - Context summarization (when window overflows)
+//! - Prompt assembly from conversation history
+//! - Durable per-room conversation memories
+//! - Per-room cache of recent history for prompt assembly
 //!
 //! ## Features
 //!
 //! - `token-counting` - Enable accurate token counting using tokenizers
+//! - `tiktoken` - Enable tiktoken-based token counting for OpenAI models
 //! - `ai-summarizer` - Enable AI-powered summarization using nexis-runtime
 //! - `metrics` - Enable Prometheus metrics for monitoring
+//! - `sqlx` - Enable the PostgreSQL-backed memory store
 
+pub mod cache;
 pub mod context;
 pub mod error;
 pub mod manager;
+pub mod memory;
+pub mod prompt;
 pub mod summarizer;
+pub mod tokens;
 pub mod window;
 
 #[cfg(feature = "ai-summarizer")]
@@ -29,18 +38,29 @@ pub mod metrics;
 
 #[cfg(feature = "metrics")]
 pub use metrics::{
-    record_summarization_failure, record_summarization_overflow,
+    record_cache_lookup, record_summarization_failure, record_summarization_overflow,
     record_summarization_success, record_truncation, record_window_utilization,
-    set_active_contexts, CONTEXTS_ACTIVE, MESSAGES_SUMMARIZED, MESSAGES_TRUNCATED,
-    OVERFLOW_EVENTS, SUMMARIZATION_ATTEMPTS, SUMMARIZATION_LATENCY, TOKEN_SAVINGS,
+    set_active_contexts, CONTEXTS_ACTIVE, CONVERSATION_CACHE_LOOKUPS, MESSAGES_SUMMARIZED,
+    MESSAGES_TRUNCATED, OVERFLOW_EVENTS, SUMMARIZATION_ATTEMPTS, SUMMARIZATION_LATENCY,
+    TOKEN_SAVINGS,
 };
 
+pub use cache::{CacheStats, ConversationCache};
 pub use context::{ConversationContext, Message, MessageRole};
 pub use error::{ContextError, ContextResult};
 pub use manager::ContextManager;
+pub use memory::{InMemoryMemoryStore, MemoryEntry, MemoryKind, MemoryQuery, MemoryStore};
+pub use prompt::{AssembledPrompt, PromptAssembler};
 pub use summarizer::{ContextSummarizer, SummarizerConfig, NoOpSummarizer, MockSummarizer};
+pub use tokens::{counter_for_model, HeuristicTokenCounter, TokenCounter};
 pub use window::{ContextWindow, OverflowStrategy};
 
+#[cfg(feature = "tiktoken")]
+pub use tokens::TiktokenCounter;
+
+#[cfg(feature = "sqlx")]
+pub use memory::SqlxMemoryStore;
+
 /// Prelude for common imports
 pub mod prelude {
     pub use crate::context::ConversationContext;
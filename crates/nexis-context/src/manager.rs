@@ -11,6 +11,7 @@ use crate::context::{ConversationContext, Message};
 use crate::error::{ContextError, ContextResult};
 use crate::window::{ContextWindow, OverflowStrategy};
 use crate::summarizer::{ContextSummarizer, SummarizerConfig};
+use crate::tokens::{HeuristicTokenCounter, TokenCounter};
 
 #[cfg(feature = "metrics")]
 use crate::metrics::{
@@ -24,6 +25,7 @@ pub struct ContextManager {
     window: ContextWindow,
     summarizer: Option<Arc<dyn ContextSummarizer>>,
     summarizer_config: SummarizerConfig,
+    token_counter: Arc<dyn TokenCounter>,
 }
 
 impl ContextManager {
@@ -34,6 +36,7 @@ impl ContextManager {
             window,
             summarizer: None,
             summarizer_config: SummarizerConfig::default(),
+            token_counter: Arc::new(HeuristicTokenCounter),
         }
     }
 
@@ -44,6 +47,7 @@ impl ContextManager {
             window,
             summarizer: Some(summarizer),
             summarizer_config: SummarizerConfig::default(),
+            token_counter: Arc::new(HeuristicTokenCounter),
         }
     }
 
@@ -58,9 +62,16 @@ impl ContextManager {
             window,
             summarizer: Some(summarizer),
             summarizer_config: config,
+            token_counter: Arc::new(HeuristicTokenCounter),
         }
     }
 
+    /// Use a specific token counter for window budgeting
+    pub fn with_token_counter(mut self, token_counter: Arc<dyn TokenCounter>) -> Self {
+        self.token_counter = token_counter;
+        self
+    }
+
     /// Create a new context
     pub async fn create_context(&self, room_id: Option<Uuid>) -> ContextResult<Uuid> {
         let context = ConversationContext::new(room_id);
@@ -91,7 +102,7 @@ impl ContextManager {
             .ok_or_else(|| ContextError::NotFound(context_id.to_string()))?;
 
         // Check window overflow
-        let estimated_tokens = estimate_tokens(&message.content);
+        let estimated_tokens = self.token_counter.count(&message.content);
         let new_total = context.total_tokens() + estimated_tokens;
 
         if new_total > self.window.available_tokens() {
@@ -138,6 +149,7 @@ impl ContextManager {
         // If no summarizer configured, fall back to truncation
         let Some(ref summarizer) = self.summarizer else {
             debug!("No summarizer configured, falling back to truncation");
+            #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
             let truncated = self.truncate_oldest_with_count(context, tokens_to_free);
             #[cfg(feature = "metrics")]
             record_truncation(truncated);
@@ -175,6 +187,7 @@ impl ContextManager {
                 // On failure, restore the messages and fall back to truncation
                 warn!(error = ?e, "Summarization failed, falling back to truncation");
                 context.messages = [messages_to_summarize, context.messages.clone()].concat();
+                #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
                 let truncated = self.truncate_oldest_with_count(context, tokens_to_free);
                 
                 #[cfg(feature = "metrics")]
@@ -223,11 +236,6 @@ impl ContextManager {
     }
 }
 
-/// Simple token estimation (approximately 4 chars per token)
-fn estimate_tokens(text: &str) -> usize {
-    (text.len() / 4).max(1)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -309,6 +317,68 @@ mod tests {
         assert!(context.messages.len() < 10);
     }
 
+    /// Counts whitespace-separated words so budgets are easy to reason about
+    #[derive(Debug)]
+    struct WordCounter;
+
+    impl TokenCounter for WordCounter {
+        fn name(&self) -> &'static str {
+            "words"
+        }
+
+        fn count(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+    }
+
+    fn words(count: usize) -> String {
+        vec!["w"; count].join(" ")
+    }
+
+    #[tokio::test]
+    async fn test_message_exactly_filling_window_is_kept() {
+        let window = ContextWindow::new(10).with_reserved_tokens(0);
+        let manager = ContextManager::new(window).with_token_counter(Arc::new(WordCounter));
+        let id = manager.create_context(None).await.unwrap();
+
+        manager.add_message(id, Message::user(words(6))).await.unwrap();
+        manager.add_message(id, Message::user(words(4))).await.unwrap();
+
+        let context = manager.get_context(id).await.unwrap();
+        assert_eq!(context.messages.len(), 2);
+        assert_eq!(context.total_tokens(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_one_token_over_window_truncates_oldest() {
+        let window = ContextWindow::new(10).with_reserved_tokens(0);
+        let manager = ContextManager::new(window).with_token_counter(Arc::new(WordCounter));
+        let id = manager.create_context(None).await.unwrap();
+
+        manager.add_message(id, Message::user(words(6))).await.unwrap();
+        manager.add_message(id, Message::user(words(4))).await.unwrap();
+        manager.add_message(id, Message::user(words(1))).await.unwrap();
+
+        let context = manager.get_context(id).await.unwrap();
+        assert_eq!(context.messages.len(), 2);
+        assert_eq!(context.total_tokens(), 5);
+        assert_eq!(context.messages[0].token_count, Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_fail_strategy_rejects_message_over_window() {
+        let window = ContextWindow::new(10)
+            .with_reserved_tokens(2)
+            .with_overflow_strategy(OverflowStrategy::Fail);
+        let manager = ContextManager::new(window).with_token_counter(Arc::new(WordCounter));
+        let id = manager.create_context(None).await.unwrap();
+
+        manager.add_message(id, Message::user(words(8))).await.unwrap();
+        let result = manager.add_message(id, Message::user(words(1))).await;
+
+        assert!(matches!(result, Err(ContextError::WindowFull)));
+    }
+
     #[tokio::test]
     async fn test_context_count() {
         let manager = ContextManager::new(ContextWindow::default());
@@ -0,0 +1,518 @@
+//! Durable per-room conversation memory
+//!
+//! Memories are short facts or decisions extracted from a conversation that
+//! should outlive the sliding window. They are scoped to a room and optionally
+//! to a single member, and the highest-ranked ones are injected into prompt
+//! assembly via [`crate::PromptAssembler::assemble_with_memories`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::error::{ContextError, ContextResult};
+
+/// Kind of information a memory records
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryKind {
+    /// Something that is true about the room, project or a member
+    Fact,
+    /// An outcome the participants agreed on
+    Decision,
+    /// How a member likes things done
+    Preference,
+    /// Anything else worth remembering
+    Note,
+}
+
+impl MemoryKind {
+    /// Stable string form used for storage and rendering
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fact => "fact",
+            Self::Decision => "decision",
+            Self::Preference => "preference",
+            Self::Note => "note",
+        }
+    }
+}
+
+impl fmt::Display for MemoryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MemoryKind {
+    type Err = ContextError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "fact" => Ok(Self::Fact),
+            "decision" => Ok(Self::Decision),
+            "preference" => Ok(Self::Preference),
+            "note" => Ok(Self::Note),
+            other => Err(ContextError::InvalidMemory(format!(
+                "unknown memory kind: {other}"
+            ))),
+        }
+    }
+}
+
+/// A durable memory recorded for a room
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemoryEntry {
+    pub id: Uuid,
+    pub room_id: String,
+    /// Member the memory is about; `None` for room-wide memories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member_id: Option<String>,
+    pub kind: MemoryKind,
+    pub content: String,
+    /// Ranking weight in `0.0..=1.0`; higher memories are injected first
+    pub importance: f32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl MemoryEntry {
+    /// Default importance for new memories
+    pub const DEFAULT_IMPORTANCE: f32 = 0.5;
+
+    pub fn new(room_id: impl Into<String>, kind: MemoryKind, content: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            room_id: room_id.into(),
+            member_id: None,
+            kind,
+            content: content.into(),
+            importance: Self::DEFAULT_IMPORTANCE,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn fact(room_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self::new(room_id, MemoryKind::Fact, content)
+    }
+
+    pub fn decision(room_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self::new(room_id, MemoryKind::Decision, content)
+    }
+
+    /// Scope the memory to a single member
+    pub fn with_member(mut self, member_id: impl Into<String>) -> Self {
+        self.member_id = Some(member_id.into());
+        self
+    }
+
+    /// Set the ranking weight, clamped to `0.0..=1.0`
+    pub fn with_importance(mut self, importance: f32) -> Self {
+        self.importance = importance.clamp(0.0, 1.0);
+        self
+    }
+
+    fn validate(&self) -> ContextResult<()> {
+        if self.room_id.trim().is_empty() {
+            return Err(ContextError::InvalidMemory(
+                "room_id is required".to_string(),
+            ));
+        }
+        if self.content.trim().is_empty() {
+            return Err(ContextError::InvalidMemory(
+                "content is required".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Filter for reading memories back out of a store
+///
+/// Results are ordered by importance, then newest first. When `member_id` is
+/// set, room-wide memories are returned alongside that member's own.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryQuery {
+    pub room_id: String,
+    pub member_id: Option<String>,
+    pub kind: Option<MemoryKind>,
+    /// Case-insensitive substring match on content
+    pub text: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl MemoryQuery {
+    pub fn new(room_id: impl Into<String>) -> Self {
+        Self {
+            room_id: room_id.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_member(mut self, member_id: impl Into<String>) -> Self {
+        self.member_id = Some(member_id.into());
+        self
+    }
+
+    pub fn with_kind(mut self, kind: MemoryKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Check whether an entry satisfies this query
+    pub fn matches(&self, entry: &MemoryEntry) -> bool {
+        if entry.room_id != self.room_id {
+            return false;
+        }
+        if let (Some(member_id), Some(entry_member)) = (&self.member_id, &entry.member_id) {
+            if member_id != entry_member {
+                return false;
+            }
+        }
+        if self.kind.is_some_and(|kind| kind != entry.kind) {
+            return false;
+        }
+        if let Some(text) = &self.text {
+            if !entry.content.to_lowercase().contains(&text.to_lowercase()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Storage backend for conversation memories
+#[async_trait]
+pub trait MemoryStore: Send + Sync {
+    /// Record a new memory
+    async fn append(&self, entry: MemoryEntry) -> ContextResult<MemoryEntry>;
+
+    /// Read memories matching a query
+    async fn query(&self, query: &MemoryQuery) -> ContextResult<Vec<MemoryEntry>>;
+
+    /// Remove a memory, returning whether it existed
+    async fn delete(&self, id: Uuid) -> ContextResult<bool>;
+
+    /// The `k` highest-ranked memories for a room, optionally including a member's own
+    async fn top_k(
+        &self,
+        room_id: &str,
+        member_id: Option<&str>,
+        k: usize,
+    ) -> ContextResult<Vec<MemoryEntry>> {
+        let mut query = MemoryQuery::new(room_id).with_limit(k);
+        query.member_id = member_id.map(str::to_string);
+        self.query(&query).await
+    }
+}
+
+/// Process-local memory store, mainly for tests and single-node deployments
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryMemoryStore {
+    rooms: Arc<RwLock<HashMap<String, Vec<MemoryEntry>>>>,
+}
+
+impl InMemoryMemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Total number of memories across all rooms
+    pub async fn len(&self) -> usize {
+        self.rooms.read().await.values().map(Vec::len).sum()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
+
+#[async_trait]
+impl MemoryStore for InMemoryMemoryStore {
+    async fn append(&self, entry: MemoryEntry) -> ContextResult<MemoryEntry> {
+        entry.validate()?;
+        self.rooms
+            .write()
+            .await
+            .entry(entry.room_id.clone())
+            .or_default()
+            .push(entry.clone());
+        Ok(entry)
+    }
+
+    async fn query(&self, query: &MemoryQuery) -> ContextResult<Vec<MemoryEntry>> {
+        let rooms = self.rooms.read().await;
+        let mut entries: Vec<MemoryEntry> = rooms
+            .get(&query.room_id)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|entry| query.matches(entry))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        entries.sort_by(|a, b| {
+            b.importance
+                .total_cmp(&a.importance)
+                .then_with(|| b.created_at.cmp(&a.created_at))
+        });
+        if let Some(limit) = query.limit {
+            entries.truncate(limit);
+        }
+        Ok(entries)
+    }
+
+    async fn delete(&self, id: Uuid) -> ContextResult<bool> {
+        let mut rooms = self.rooms.write().await;
+        for entries in rooms.values_mut() {
+            if let Some(index) = entries.iter().position(|entry| entry.id == id) {
+                entries.remove(index);
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// SQL schema for the `conversation_memories` table
+pub const MEMORIES_TABLE_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS conversation_memories (
+    id UUID PRIMARY KEY,
+    room_id TEXT NOT NULL,
+    member_id TEXT,
+    kind TEXT NOT NULL,
+    content TEXT NOT NULL,
+    importance REAL NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);"#;
+
+/// Index for ranked per-room memory lookups
+pub const MEMORIES_ROOM_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_conversation_memories_room
+    ON conversation_memories(room_id, importance DESC, created_at DESC);"#;
+
+/// PostgreSQL-backed memory store
+#[cfg(feature = "sqlx")]
+#[derive(Debug, Clone)]
+pub struct SqlxMemoryStore {
+    pool: sqlx::PgPool,
+}
+
+#[cfg(feature = "sqlx")]
+impl SqlxMemoryStore {
+    /// Build a store over an existing pool
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create the memories table and index if they do not exist
+    pub async fn initialize_schema(&self) -> ContextResult<()> {
+        sqlx::query(MEMORIES_TABLE_SCHEMA)
+            .execute(&self.pool)
+            .await?;
+        sqlx::query(MEMORIES_ROOM_INDEX).execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlx")]
+fn memory_from_row(row: &sqlx::postgres::PgRow) -> ContextResult<MemoryEntry> {
+    use sqlx::Row;
+
+    let kind: String = row.try_get("kind")?;
+    Ok(MemoryEntry {
+        id: row.try_get("id")?,
+        room_id: row.try_get("room_id")?,
+        member_id: row.try_get("member_id")?,
+        kind: kind.parse()?,
+        content: row.try_get("content")?,
+        importance: row.try_get("importance")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+#[cfg(feature = "sqlx")]
+#[async_trait]
+impl MemoryStore for SqlxMemoryStore {
+    async fn append(&self, entry: MemoryEntry) -> ContextResult<MemoryEntry> {
+        entry.validate()?;
+        sqlx::query(
+            "INSERT INTO conversation_memories (id, room_id, member_id, kind, content, importance, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(entry.id)
+        .bind(&entry.room_id)
+        .bind(&entry.member_id)
+        .bind(entry.kind.as_str())
+        .bind(&entry.content)
+        .bind(entry.importance)
+        .bind(entry.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(entry)
+    }
+
+    async fn query(&self, query: &MemoryQuery) -> ContextResult<Vec<MemoryEntry>> {
+        let rows = sqlx::query(
+            "SELECT id, room_id, member_id, kind, content, importance, created_at FROM conversation_memories \
+             WHERE room_id = $1 \
+             AND ($2::TEXT IS NULL OR member_id IS NULL OR member_id = $2) \
+             AND ($3::TEXT IS NULL OR kind = $3) \
+             AND ($4::TEXT IS NULL OR content ILIKE '%' || $4 || '%') \
+             ORDER BY importance DESC, created_at DESC \
+             LIMIT $5",
+        )
+        .bind(&query.room_id)
+        .bind(&query.member_id)
+        .bind(query.kind.map(|kind| kind.as_str()))
+        .bind(&query.text)
+        .bind(query.limit.map(|limit| limit as i64))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(memory_from_row).collect()
+    }
+
+    async fn delete(&self, id: Uuid) -> ContextResult<bool> {
+        let result = sqlx::query("DELETE FROM conversation_memories WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn top_k_ranks_by_importance_then_recency() {
+        let store = InMemoryMemoryStore::new();
+        store
+            .append(MemoryEntry::fact("room_1", "uses postgres").with_importance(0.2))
+            .await
+            .unwrap();
+        store
+            .append(MemoryEntry::decision("room_1", "ship on friday").with_importance(0.9))
+            .await
+            .unwrap();
+        store
+            .append(MemoryEntry::fact("room_1", "deploys to eu-west").with_importance(0.2))
+            .await
+            .unwrap();
+        store
+            .append(MemoryEntry::fact("room_2", "other room").with_importance(1.0))
+            .await
+            .unwrap();
+
+        let top = store.top_k("room_1", None, 2).await.unwrap();
+
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].content, "ship on friday");
+        assert_eq!(top[1].content, "deploys to eu-west");
+    }
+
+    #[tokio::test]
+    async fn member_queries_include_room_wide_memories() {
+        let store = InMemoryMemoryStore::new();
+        store
+            .append(MemoryEntry::fact("room_1", "room-wide"))
+            .await
+            .unwrap();
+        store
+            .append(
+                MemoryEntry::new("room_1", MemoryKind::Preference, "prefers tabs")
+                    .with_member("alice"),
+            )
+            .await
+            .unwrap();
+        store
+            .append(
+                MemoryEntry::new("room_1", MemoryKind::Preference, "prefers spaces")
+                    .with_member("bob"),
+            )
+            .await
+            .unwrap();
+
+        let alice = store.top_k("room_1", Some("alice"), 10).await.unwrap();
+        let contents: Vec<_> = alice.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents.len(), 2);
+        assert!(contents.contains(&"room-wide"));
+        assert!(contents.contains(&"prefers tabs"));
+
+        assert_eq!(store.top_k("room_1", None, 10).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn query_filters_by_kind_and_text() {
+        let store = InMemoryMemoryStore::new();
+        store
+            .append(MemoryEntry::fact("room_1", "API is REST"))
+            .await
+            .unwrap();
+        store
+            .append(MemoryEntry::decision("room_1", "Adopt gRPC for the api"))
+            .await
+            .unwrap();
+
+        let decisions = store
+            .query(&MemoryQuery::new("room_1").with_kind(MemoryKind::Decision))
+            .await
+            .unwrap();
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].kind, MemoryKind::Decision);
+
+        let api = store
+            .query(&MemoryQuery::new("room_1").with_text("api"))
+            .await
+            .unwrap();
+        assert_eq!(api.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn append_rejects_blank_content_and_delete_removes() {
+        let store = InMemoryMemoryStore::new();
+        assert!(matches!(
+            store.append(MemoryEntry::fact("room_1", "  ")).await,
+            Err(ContextError::InvalidMemory(_))
+        ));
+
+        let entry = store
+            .append(MemoryEntry::fact("room_1", "keep me"))
+            .await
+            .unwrap();
+        assert!(store.delete(entry.id).await.unwrap());
+        assert!(!store.delete(entry.id).await.unwrap());
+        assert!(store.is_empty().await);
+    }
+
+    #[test]
+    fn kind_round_trips_through_strings() {
+        for kind in [
+            MemoryKind::Fact,
+            MemoryKind::Decision,
+            MemoryKind::Preference,
+            MemoryKind::Note,
+        ] {
+            assert_eq!(kind.as_str().parse::<MemoryKind>().unwrap(), kind);
+        }
+        assert!("opinion".parse::<MemoryKind>().is_err());
+    }
+}
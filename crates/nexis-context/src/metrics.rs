@@ -67,6 +67,14 @@ lazy_static! {
     /// Token savings from summarization
     pub static ref TOKEN_SAVINGS: Gauge =
         register_gauge!("nexis_context_token_savings_total", "Total tokens saved through summarization").unwrap();
+
+    // ============================================================================
+    // Conversation Cache Metrics
+    // ============================================================================
+
+    /// Conversation cache reads by result (hit or miss)
+    pub static ref CONVERSATION_CACHE_LOOKUPS: CounterVec =
+        register_counter_vec!("nexis_context_cache_lookups_total", "Conversation cache lookups", &["result"]).unwrap();
 }
 
 /// Record a summarization success
@@ -97,6 +105,12 @@ pub fn set_active_contexts(count: usize) {
     CONTEXTS_ACTIVE.set(count as f64);
 }
 
+/// Record a conversation cache read
+pub fn record_cache_lookup(hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    CONVERSATION_CACHE_LOOKUPS.with_label_values(&[result]).inc();
+}
+
 /// Record window utilization
 pub fn record_window_utilization(utilization_percent: f64) {
     CONTEXT_WINDOW_UTILIZATION.observe(utilization_percent);
@@ -115,5 +129,6 @@ mod tests {
         record_summarization_overflow();
         record_summarization_success(10, 1.5);
         record_summarization_failure();
+        record_cache_lookup(true);
     }
 }
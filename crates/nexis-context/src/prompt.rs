@@ -0,0 +1,302 @@
+//! Prompt assembly from conversation history
+
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::context::{ConversationContext, Message, MessageRole};
+use crate::memory::MemoryEntry;
+use crate::tokens::{HeuristicTokenCounter, TokenCounter};
+use crate::window::ContextWindow;
+
+/// Assembles provider prompts from recent history within a context window
+#[derive(Debug, Clone)]
+pub struct PromptAssembler {
+    window: ContextWindow,
+    system_prompt: Option<String>,
+    token_counter: Arc<dyn TokenCounter>,
+}
+
+impl Default for PromptAssembler {
+    fn default() -> Self {
+        Self::new(ContextWindow::default())
+    }
+}
+
+/// Result of assembling a prompt
+#[derive(Debug, Clone)]
+pub struct AssembledPrompt {
+    /// Context holding the history messages that fit the budget
+    pub context: ConversationContext,
+    /// Memories that fit the budget, in the order they were rendered
+    pub memories: Vec<MemoryEntry>,
+    /// Rendered prompt to send to the provider
+    pub prompt: String,
+    /// Estimated tokens used by the rendered prompt
+    pub estimated_tokens: usize,
+}
+
+impl PromptAssembler {
+    /// Create an assembler for the given window
+    pub fn new(window: ContextWindow) -> Self {
+        Self {
+            window,
+            system_prompt: None,
+            token_counter: Arc::new(HeuristicTokenCounter),
+        }
+    }
+
+    /// Use a specific token counter for budgeting
+    pub fn with_token_counter(mut self, token_counter: Arc<dyn TokenCounter>) -> Self {
+        self.token_counter = token_counter;
+        self
+    }
+
+    /// Prepend a system prompt to every assembled prompt
+    pub fn with_system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+
+    /// Get the context window
+    pub fn window(&self) -> &ContextWindow {
+        &self.window
+    }
+
+    /// Build a context from the newest history messages that fit alongside `prompt`
+    pub fn build_context(
+        &self,
+        room_id: Option<Uuid>,
+        history: Vec<Message>,
+        prompt: &str,
+    ) -> ConversationContext {
+        self.select(room_id, history, Vec::new(), prompt).0
+    }
+
+    /// Fit memories first, in ranked order, then the newest history into the budget
+    fn select(
+        &self,
+        room_id: Option<Uuid>,
+        history: Vec<Message>,
+        memories: Vec<MemoryEntry>,
+        prompt: &str,
+    ) -> (ConversationContext, Vec<MemoryEntry>) {
+        let fixed = self.token_counter.count(prompt)
+            + self
+                .system_prompt
+                .as_deref()
+                .map_or(0, |system| self.token_counter.count(system));
+        let mut budget = self.window.available_tokens().saturating_sub(fixed);
+
+        let mut kept_memories = Vec::new();
+        for memory in memories {
+            let tokens = self.token_counter.count(&memory_line(&memory));
+            if tokens > budget {
+                break;
+            }
+            budget -= tokens;
+            kept_memories.push(memory);
+        }
+
+        let mut kept = Vec::new();
+        for mut message in history.into_iter().rev() {
+            let tokens = message
+                .token_count
+                .unwrap_or_else(|| self.token_counter.count(&message.content));
+            if tokens > budget {
+                break;
+            }
+            budget -= tokens;
+            message.token_count = Some(tokens);
+            kept.push(message);
+        }
+
+        let mut context = ConversationContext::new(room_id);
+        for message in kept.into_iter().rev() {
+            context.add_message(message);
+        }
+        (context, kept_memories)
+    }
+
+    /// Render a context and the user's prompt into a single transcript
+    pub fn render(&self, context: &ConversationContext, prompt: &str) -> String {
+        self.render_with_memories(context, &[], prompt)
+    }
+
+    /// Render memories, a context and the user's prompt into a single transcript
+    pub fn render_with_memories(
+        &self,
+        context: &ConversationContext,
+        memories: &[MemoryEntry],
+        prompt: &str,
+    ) -> String {
+        let mut rendered = String::new();
+        if let Some(system_prompt) = &self.system_prompt {
+            rendered.push_str(system_prompt);
+            rendered.push_str("\n\n");
+        }
+
+        if !memories.is_empty() {
+            rendered.push_str("Relevant memories:\n");
+            for memory in memories {
+                rendered.push_str(&memory_line(memory));
+                rendered.push('\n');
+            }
+            rendered.push('\n');
+        }
+
+        if !context.messages.is_empty() {
+            rendered.push_str("Conversation so far:\n");
+            for message in &context.messages {
+                rendered.push_str(&format!("{}: {}\n", speaker(message), message.content));
+            }
+            rendered.push_str("\nRequest:\n");
+        }
+
+        rendered.push_str(prompt);
+        rendered
+    }
+
+    /// Build the context and render the prompt in one step
+    pub fn assemble(
+        &self,
+        room_id: Option<Uuid>,
+        history: Vec<Message>,
+        prompt: &str,
+    ) -> AssembledPrompt {
+        self.assemble_with_memories(room_id, history, Vec::new(), prompt)
+    }
+
+    /// Like [`Self::assemble`], injecting ranked memories ahead of the history
+    ///
+    /// Memories are budgeted before history so durable facts survive when the
+    /// sliding window is full; callers usually pass [`crate::MemoryStore::top_k`].
+    pub fn assemble_with_memories(
+        &self,
+        room_id: Option<Uuid>,
+        history: Vec<Message>,
+        memories: Vec<MemoryEntry>,
+        prompt: &str,
+    ) -> AssembledPrompt {
+        let (context, memories) = self.select(room_id, history, memories, prompt);
+        let prompt = self.render_with_memories(&context, &memories, prompt);
+        let estimated_tokens = self.token_counter.count(&prompt);
+        AssembledPrompt {
+            context,
+            memories,
+            prompt,
+            estimated_tokens,
+        }
+    }
+}
+
+fn memory_line(memory: &MemoryEntry) -> String {
+    match &memory.member_id {
+        Some(member_id) => format!("- {} ({member_id}): {}", memory.kind, memory.content),
+        None => format!("- {}: {}", memory.kind, memory.content),
+    }
+}
+
+fn speaker(message: &Message) -> &str {
+    match (&message.author, message.role) {
+        (Some(author), _) => author,
+        (None, MessageRole::User) => "user",
+        (None, MessageRole::Assistant) => "assistant",
+        (None, MessageRole::System) => "system",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryKind;
+
+    fn history(count: usize) -> Vec<Message> {
+        (0..count)
+            .map(|i| Message::user(format!("message number {i} with some padding text")))
+            .collect()
+    }
+
+    #[test]
+    fn keeps_all_history_when_it_fits() {
+        let assembler = PromptAssembler::new(ContextWindow::default());
+        let context = assembler.build_context(None, history(3), "summarize");
+        assert_eq!(context.messages.len(), 3);
+    }
+
+    #[test]
+    fn drops_oldest_history_when_over_budget() {
+        let window = ContextWindow::new(40).with_reserved_tokens(0);
+        let assembler = PromptAssembler::new(window);
+
+        let context = assembler.build_context(None, history(10), "summarize");
+
+        assert!(context.messages.len() < 10);
+        assert!(context.total_tokens() + HeuristicTokenCounter.count("summarize") <= 40);
+        assert_eq!(
+            context.messages.last().unwrap().content,
+            "message number 9 with some padding text"
+        );
+    }
+
+    #[test]
+    fn render_includes_authors_and_prompt() {
+        let assembler = PromptAssembler::default().with_system_prompt("Be brief.");
+        let history = vec![
+            Message::user("hi there".to_string()).with_author("nexis:human:alice"),
+            Message::assistant("hello".to_string()),
+        ];
+
+        let assembled = assembler.assemble(None, history, "what did alice say?");
+
+        assert!(assembled.prompt.starts_with("Be brief.\n\n"));
+        assert!(assembled.prompt.contains("nexis:human:alice: hi there\n"));
+        assert!(assembled.prompt.contains("assistant: hello\n"));
+        assert!(assembled.prompt.ends_with("Request:\nwhat did alice say?"));
+        assert!(assembled.estimated_tokens > 0);
+    }
+
+    #[test]
+    fn render_without_history_is_just_the_prompt() {
+        let assembler = PromptAssembler::default();
+        let assembled = assembler.assemble(None, Vec::new(), "hello");
+        assert_eq!(assembled.prompt, "hello");
+    }
+
+    #[test]
+    fn memories_render_before_history() {
+        let assembler = PromptAssembler::default();
+        let memories = vec![
+            MemoryEntry::decision("room_1", "ship on friday"),
+            MemoryEntry::new("room_1", MemoryKind::Preference, "prefers tabs").with_member("alice"),
+        ];
+
+        let assembled = assembler.assemble_with_memories(
+            None,
+            vec![Message::user("when do we ship?".to_string())],
+            memories,
+            "answer",
+        );
+
+        assert_eq!(assembled.memories.len(), 2);
+        assert!(assembled.prompt.starts_with(
+            "Relevant memories:\n- decision: ship on friday\n- preference (alice): prefers tabs\n\nConversation so far:\n"
+        ));
+    }
+
+    #[test]
+    fn memories_take_budget_before_history() {
+        let window = ContextWindow::new(40).with_reserved_tokens(0);
+        let assembler = PromptAssembler::new(window);
+        let memories = vec![MemoryEntry::fact(
+            "room_1",
+            "the deploy target is eu-west-1",
+        )];
+
+        let without = assembler.assemble(None, history(10), "summarize");
+        let with = assembler.assemble_with_memories(None, history(10), memories, "summarize");
+
+        assert_eq!(with.memories.len(), 1);
+        assert!(with.context.messages.len() < without.context.messages.len());
+    }
+}
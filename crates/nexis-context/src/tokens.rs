@@ -0,0 +1,146 @@
+//! Token counting backends
+
+use std::fmt;
+use std::sync::Arc;
+
+/// Counts tokens in text for budgeting a context window
+pub trait TokenCounter: Send + Sync + fmt::Debug {
+    /// Backend name
+    fn name(&self) -> &'static str;
+
+    /// Count the tokens in `text`
+    fn count(&self, text: &str) -> usize;
+}
+
+/// Fallback counter estimating one token per four characters, rounded up
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenCounter;
+
+impl TokenCounter for HeuristicTokenCounter {
+    fn name(&self) -> &'static str {
+        "heuristic"
+    }
+
+    fn count(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+}
+
+/// BPE counter backed by tiktoken encodings
+#[cfg(feature = "tiktoken")]
+pub struct TiktokenCounter {
+    bpe: &'static tiktoken_rs::CoreBPE,
+    encoding: &'static str,
+}
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenCounter {
+    /// Counter using the `cl100k_base` encoding (GPT-3.5 / GPT-4)
+    pub fn cl100k() -> Self {
+        Self {
+            bpe: tiktoken_rs::cl100k_base_singleton(),
+            encoding: "cl100k_base",
+        }
+    }
+
+    /// Counter using the `o200k_base` encoding (GPT-4o / o-series)
+    pub fn o200k() -> Self {
+        Self {
+            bpe: tiktoken_rs::o200k_base_singleton(),
+            encoding: "o200k_base",
+        }
+    }
+
+    /// Counter for a known OpenAI model name
+    pub fn for_model(model: &str) -> Option<Self> {
+        match tiktoken_rs::tokenizer::get_tokenizer(model)? {
+            tiktoken_rs::tokenizer::Tokenizer::O200kBase => Some(Self::o200k()),
+            tiktoken_rs::tokenizer::Tokenizer::Cl100kBase => Some(Self::cl100k()),
+            _ => None,
+        }
+    }
+
+    /// Name of the underlying encoding
+    pub fn encoding(&self) -> &'static str {
+        self.encoding
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl fmt::Debug for TiktokenCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TiktokenCounter")
+            .field("encoding", &self.encoding)
+            .finish()
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl TokenCounter for TiktokenCounter {
+    fn name(&self) -> &'static str {
+        "tiktoken"
+    }
+
+    fn count(&self, text: &str) -> usize {
+        self.bpe.encode_with_special_tokens(text).len()
+    }
+}
+
+/// Pick the most accurate counter available for a model name
+///
+/// Uses tiktoken for OpenAI models when the `tiktoken` feature is enabled and
+/// falls back to [`HeuristicTokenCounter`] otherwise.
+pub fn counter_for_model(model: &str) -> Arc<dyn TokenCounter> {
+    #[cfg(feature = "tiktoken")]
+    if let Some(counter) = TiktokenCounter::for_model(model) {
+        return Arc::new(counter);
+    }
+
+    let _ = model;
+    Arc::new(HeuristicTokenCounter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heuristic_rounds_up_partial_tokens() {
+        let counter = HeuristicTokenCounter;
+        assert_eq!(counter.count(""), 0);
+        assert_eq!(counter.count("a"), 1);
+        assert_eq!(counter.count("abcd"), 1);
+        assert_eq!(counter.count("abcde"), 2);
+        assert_eq!(counter.count("abcdefgh"), 2);
+    }
+
+    #[test]
+    fn heuristic_counts_characters_not_bytes() {
+        let counter = HeuristicTokenCounter;
+        assert_eq!(counter.count("ééé"), 1);
+    }
+
+    #[test]
+    fn unknown_models_use_heuristic() {
+        assert_eq!(counter_for_model("claude-3-5-sonnet").name(), "heuristic");
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn openai_models_use_tiktoken() {
+        let counter = counter_for_model("gpt-4o");
+        assert_eq!(counter.name(), "tiktoken");
+        assert_eq!(counter.count("hello world"), 2);
+
+        assert_eq!(
+            TiktokenCounter::for_model("gpt-4").unwrap().encoding(),
+            "cl100k_base"
+        );
+        assert_eq!(
+            TiktokenCounter::for_model("gpt-4o-mini")
+                .unwrap()
+                .encoding(),
+            "o200k_base"
+        );
+    }
+}
@@ -31,6 +31,36 @@ impl ContextWindow {
         }
     }
 
+    /// Window sized for a model's context length, reserving room for the reply
+    pub fn for_model(model: &str) -> Self {
+        let model = model.to_ascii_lowercase();
+        let max_tokens = if model.starts_with("claude") {
+            200_000
+        } else if model.starts_with("gpt-4o")
+            || model.starts_with("gpt-4-turbo")
+            || model.starts_with("gpt-4.1")
+            || model.starts_with("o1")
+            || model.starts_with("o3")
+            || model.starts_with("o4")
+        {
+            128_000
+        } else if model.starts_with("gpt-4-32k") {
+            32_768
+        } else if model.starts_with("gpt-4") {
+            8_192
+        } else if model.starts_with("gpt-3.5-turbo") {
+            16_385
+        } else {
+            return Self::default();
+        };
+
+        Self {
+            max_tokens,
+            reserved_tokens: (max_tokens / 8).max(Self::default().reserved_tokens),
+            ..Default::default()
+        }
+    }
+
     pub fn available_tokens(&self) -> usize {
         self.max_tokens.saturating_sub(self.reserved_tokens)
     }
@@ -56,3 +86,28 @@ pub enum OverflowStrategy {
     /// Fail with error
     Fail,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_model_uses_known_context_sizes() {
+        assert_eq!(ContextWindow::for_model("gpt-4o-mini").max_tokens, 128_000);
+        assert_eq!(ContextWindow::for_model("gpt-4").max_tokens, 8_192);
+        assert_eq!(
+            ContextWindow::for_model("claude-3-5-sonnet").max_tokens,
+            200_000
+        );
+    }
+
+    #[test]
+    fn for_model_falls_back_to_default_window() {
+        let window = ContextWindow::for_model("local-llama");
+        assert_eq!(window.max_tokens, ContextWindow::default().max_tokens);
+        assert_eq!(
+            window.available_tokens(),
+            ContextWindow::default().available_tokens()
+        );
+    }
+}
@@ -0,0 +1,283 @@
+//! Room archive format for export and import.
+//!
+//! An archive is JSON Lines: a `header` record, one `room` record, one
+//! `member` record per member and one `message` record per message in
+//! chronological order. Every record carries a `type` tag.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub const ARCHIVE_FORMAT: &str = "nexis-room-archive";
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Media type used when serving archives over HTTP.
+pub const ARCHIVE_CONTENT_TYPE: &str = "application/x-ndjson";
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("line {line}: {source}")]
+    Json {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("archive must start with a header record")]
+    MissingHeader,
+    #[error("unsupported archive format `{format}` version {version}")]
+    Unsupported { format: String, version: u32 },
+    #[error("archive must contain exactly one room record")]
+    InvalidRoom,
+    #[error("line {line}: duplicate message id `{id}`")]
+    DuplicateMessage { line: usize, id: String },
+    #[error("line {line}: {reason}")]
+    InvalidRecord { line: usize, reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedRoom {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedMessage {
+    pub id: String,
+    pub sender: String,
+    pub text: String,
+    #[serde(rename = "replyTo", default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// One line of an archive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ArchiveRecord {
+    Header {
+        format: String,
+        version: u32,
+        #[serde(rename = "exportedAt")]
+        exported_at: DateTime<Utc>,
+    },
+    Room(ArchivedRoom),
+    Member {
+        #[serde(rename = "memberId")]
+        member_id: String,
+    },
+    Message(ArchivedMessage),
+}
+
+/// A complete room snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoomArchive {
+    pub exported_at: DateTime<Utc>,
+    pub room: ArchivedRoom,
+    pub members: Vec<String>,
+    pub messages: Vec<ArchivedMessage>,
+}
+
+impl RoomArchive {
+    pub fn new(room: ArchivedRoom) -> Self {
+        Self {
+            exported_at: Utc::now(),
+            room,
+            members: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    pub fn with_members(mut self, members: Vec<String>) -> Self {
+        self.members = members;
+        self
+    }
+
+    pub fn with_messages(mut self, messages: Vec<ArchivedMessage>) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = ArchiveRecord> + '_ {
+        let header = ArchiveRecord::Header {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            exported_at: self.exported_at,
+        };
+        std::iter::once(header)
+            .chain(std::iter::once(ArchiveRecord::Room(self.room.clone())))
+            .chain(self.members.iter().map(|member_id| ArchiveRecord::Member {
+                member_id: member_id.clone(),
+            }))
+            .chain(self.messages.iter().cloned().map(ArchiveRecord::Message))
+    }
+
+    /// Serialize to JSON Lines, one record per line with a trailing newline.
+    pub fn to_jsonl(&self) -> String {
+        let mut out = String::new();
+        for record in self.records() {
+            out.push_str(&serde_json::to_string(&record).expect("archive records serialize"));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Parse and validate an archive. Blank lines are ignored.
+    pub fn from_jsonl(input: &str) -> Result<Self, ArchiveError> {
+        let mut lines = input
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        let exported_at = match lines.next().map(parse_record).transpose()? {
+            Some((
+                _,
+                ArchiveRecord::Header {
+                    format,
+                    version,
+                    exported_at,
+                },
+            )) => {
+                if format != ARCHIVE_FORMAT || version > ARCHIVE_VERSION {
+                    return Err(ArchiveError::Unsupported { format, version });
+                }
+                exported_at
+            }
+            _ => return Err(ArchiveError::MissingHeader),
+        };
+
+        let mut room = None;
+        let mut members = Vec::new();
+        let mut messages: Vec<ArchivedMessage> = Vec::new();
+        let mut message_ids = HashSet::new();
+        for entry in lines {
+            let (line, record) = parse_record(entry)?;
+            match record {
+                ArchiveRecord::Header { .. } => {
+                    return Err(ArchiveError::InvalidRecord {
+                        line,
+                        reason: "unexpected second header".to_string(),
+                    })
+                }
+                ArchiveRecord::Room(_) if room.is_some() => return Err(ArchiveError::InvalidRoom),
+                ArchiveRecord::Room(record) => room = Some(record),
+                ArchiveRecord::Member { member_id } => {
+                    if member_id.trim().is_empty() {
+                        return Err(ArchiveError::InvalidRecord {
+                            line,
+                            reason: "member id cannot be empty".to_string(),
+                        });
+                    }
+                    if !members.contains(&member_id) {
+                        members.push(member_id);
+                    }
+                }
+                ArchiveRecord::Message(message) => {
+                    if !message_ids.insert(message.id.clone()) {
+                        return Err(ArchiveError::DuplicateMessage {
+                            line,
+                            id: message.id,
+                        });
+                    }
+                    if let Some(previous) = messages.last() {
+                        if message.created_at < previous.created_at {
+                            return Err(ArchiveError::InvalidRecord {
+                                line,
+                                reason: "messages must be in chronological order".to_string(),
+                            });
+                        }
+                    }
+                    messages.push(message);
+                }
+            }
+        }
+
+        let room = room.ok_or(ArchiveError::InvalidRoom)?;
+        if room.id.trim().is_empty() || room.name.trim().is_empty() {
+            return Err(ArchiveError::InvalidRoom);
+        }
+
+        Ok(Self {
+            exported_at,
+            room,
+            members,
+            messages,
+        })
+    }
+}
+
+fn parse_record((line, text): (usize, &str)) -> Result<(usize, ArchiveRecord), ArchiveError> {
+    serde_json::from_str(text)
+        .map(|record| (line, record))
+        .map_err(|source| ArchiveError::Json { line, source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> RoomArchive {
+        let start = Utc::now();
+        RoomArchive::new(ArchivedRoom {
+            id: "room_1".to_string(),
+            name: "general".to_string(),
+            topic: Some("chat".to_string()),
+        })
+        .with_members(vec!["nexis:human:alice".to_string()])
+        .with_messages(vec![
+            ArchivedMessage {
+                id: "msg_1".to_string(),
+                sender: "nexis:human:alice".to_string(),
+                text: "hello".to_string(),
+                reply_to: None,
+                created_at: start,
+            },
+            ArchivedMessage {
+                id: "msg_2".to_string(),
+                sender: "nexis:ai:assistant".to_string(),
+                text: "hi".to_string(),
+                reply_to: Some("msg_1".to_string()),
+                created_at: start + chrono::Duration::seconds(1),
+            },
+        ])
+    }
+
+    #[test]
+    fn archive_round_trips_through_jsonl() {
+        let archive = sample();
+        let jsonl = archive.to_jsonl();
+        assert_eq!(jsonl.lines().count(), 5);
+        assert!(jsonl.starts_with("{\"type\":\"header\""));
+
+        let parsed = RoomArchive::from_jsonl(&jsonl).unwrap();
+        assert_eq!(parsed, archive);
+    }
+
+    #[test]
+    fn invalid_archives_are_rejected() {
+        let jsonl = sample().to_jsonl();
+        let mut lines: Vec<&str> = jsonl.lines().collect();
+
+        assert!(matches!(
+            RoomArchive::from_jsonl(&lines[1..].join("\n")),
+            Err(ArchiveError::MissingHeader)
+        ));
+
+        let duplicate = lines[3];
+        lines.push(duplicate);
+        assert!(matches!(
+            RoomArchive::from_jsonl(&lines.join("\n")),
+            Err(ArchiveError::DuplicateMessage { line: 6, .. })
+        ));
+
+        assert!(matches!(
+            RoomArchive::from_jsonl("{\"type\":\"header\",\"format\":\"nexis-room-archive\",\"version\":99,\"exportedAt\":\"2024-01-01T00:00:00Z\"}"),
+            Err(ArchiveError::Unsupported { version: 99, .. })
+        ));
+    }
+}
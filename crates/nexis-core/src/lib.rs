@@ -3,6 +3,7 @@
 //! This crate re-exports protocol types from `nexis-protocol` and provides
 //! domain-specific extensions for the Nexis system.
 
+pub mod archive;
 pub mod context;
 pub mod identity;
 pub mod message;
@@ -11,6 +12,8 @@ pub mod permission;
 #[cfg(feature = "multi-tenant")]
 pub mod tenant;
 
-pub use nexis_protocol::{Action, MemberId, MemberIdError, Message, MessageContent, Permissions};
+pub use nexis_protocol::{
+    Action, MemberId, MemberIdError, Message, MessageContent, Permissions, RoomId, RoomIdError,
+};
 
 pub const CORE_VERSION: &str = env!("CARGO_PKG_VERSION");
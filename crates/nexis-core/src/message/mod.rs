@@ -5,7 +5,7 @@ pub use nexis_protocol::{Message, MessageContent};
 #[derive(Debug, Clone)]
 pub struct MessageBuilder {
     id: String,
-    room_id: String,
+    room_id: nexis_protocol::RoomId,
     sender: nexis_protocol::MemberId,
     content: MessageContent,
     reply_to: Option<String>,
@@ -14,7 +14,7 @@ pub struct MessageBuilder {
 impl MessageBuilder {
     pub fn new(
         id: String,
-        room_id: String,
+        room_id: nexis_protocol::RoomId,
         sender: nexis_protocol::MemberId,
         content: MessageContent,
     ) -> Self {
@@ -43,6 +43,7 @@ impl MessageBuilder {
             reply_to: self.reply_to,
             created_at: chrono::Utc::now(),
             updated_at: None,
+            signature: None,
         }
     }
 }
@@ -34,6 +34,12 @@ impl PermissionChecker {
         self.permissions.can_access_room(room_id)
     }
 
+    /// Rooms access is limited to; `None` when it extends to every room.
+    pub fn allowed_rooms(&self) -> Option<&[String]> {
+        let rooms = &self.permissions.allowed_rooms;
+        (!rooms.iter().any(|room| room == "*")).then_some(rooms.as_slice())
+    }
+
     pub fn effective_permissions(&self, room_id: &str) -> HashSet<Action> {
         let mut actions = HashSet::new();
         if !self.can_access_room(room_id) {
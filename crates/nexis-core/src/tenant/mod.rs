@@ -19,6 +19,7 @@ pub enum TenantError {
     NotFound(TenantId),
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct TenantId(Uuid);
@@ -54,6 +55,41 @@ impl std::fmt::Display for TenantId {
     }
 }
 
+/// Per-tenant resource limits. `None` means unlimited.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TenantQuota {
+    #[serde(default)]
+    pub max_rooms: Option<u64>,
+    #[serde(default)]
+    pub max_messages_per_day: Option<u64>,
+    #[serde(default)]
+    pub max_vector_documents: Option<u64>,
+}
+
+impl TenantQuota {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_rooms(mut self, max_rooms: u64) -> Self {
+        self.max_rooms = Some(max_rooms);
+        self
+    }
+
+    pub fn with_max_messages_per_day(mut self, max_messages: u64) -> Self {
+        self.max_messages_per_day = Some(max_messages);
+        self
+    }
+
+    pub fn with_max_vector_documents(mut self, max_documents: u64) -> Self {
+        self.max_vector_documents = Some(max_documents);
+        self
+    }
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Tenant {
@@ -62,6 +98,8 @@ pub struct Tenant {
     pub slug: String,
     #[serde(default)]
     pub is_active: bool,
+    #[serde(default)]
+    pub quota: TenantQuota,
 }
 
 impl Tenant {
@@ -73,6 +111,7 @@ impl Tenant {
             name,
             slug,
             is_active: true,
+            quota: TenantQuota::default(),
         })
     }
 
@@ -86,6 +125,11 @@ impl Tenant {
         self
     }
 
+    pub fn with_quota(mut self, quota: TenantQuota) -> Self {
+        self.quota = quota;
+        self
+    }
+
     fn validate_name(name: &str) -> Result<(), TenantError> {
         if name.trim().is_empty() {
             return Err(TenantError::EmptyName);
@@ -166,4 +210,16 @@ mod tests {
         assert_eq!(json["slug"], "test");
         assert_eq!(json["isActive"], true);
     }
+
+    #[test]
+    fn tenant_quota_defaults_to_unlimited() {
+        let tenant = Tenant::new("Test".to_string(), "test".to_string()).unwrap();
+        assert_eq!(tenant.quota, TenantQuota::unlimited());
+
+        let json =
+            serde_json::to_value(tenant.with_quota(TenantQuota::unlimited().with_max_rooms(5)))
+                .unwrap();
+        assert_eq!(json["quota"]["maxRooms"], 5);
+        assert!(json["quota"]["maxMessagesPerDay"].is_null());
+    }
 }
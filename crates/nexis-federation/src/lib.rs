@@ -12,6 +12,9 @@ use serde_json::Value;
 use sha2::Sha256;
 use thiserror::Error;
 
+#[cfg(feature = "matrix")]
+pub mod matrix;
+
 type HmacSha256 = Hmac<Sha256>;
 
 /// A unique identifier for an active federation connection.
@@ -0,0 +1,783 @@
+//! Matrix bridge relaying messages between a Matrix room and a Nexis room.
+//!
+//! The bridge logs in as a regular Matrix user (access token), joins the room
+//! through the client-server API and long-polls `/sync`. Matrix senders appear
+//! in Nexis as `nexis:human:matrix_<localpart>@<server>` members; Nexis messages
+//! are posted to Matrix by the bridge user with the Nexis sender as prefix.
+//! Reply threading is preserved through a bounded event-id mapping.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::StreamExt;
+use nexis_runtime::{RoomMessage, RoomTransport};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// Prefix of Nexis member ids that represent Matrix users.
+pub const MATRIX_MEMBER_PREFIX: &str = "nexis:human:matrix_";
+
+/// Default Nexis member used for membership notices.
+pub const DEFAULT_BRIDGE_MEMBER: &str = "nexis:system:matrix-bridge";
+
+const DEFAULT_SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+const SYNC_RETRY_DELAY: Duration = Duration::from_secs(5);
+const DEFAULT_EVENT_MAP_CAPACITY: usize = 10_000;
+
+/// Errors produced by the Matrix bridge.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum MatrixBridgeError {
+    #[error("matrix request failed: {0}")]
+    Http(String),
+    #[error("matrix api error {status} {errcode}: {error}")]
+    Api {
+        status: u16,
+        errcode: String,
+        error: String,
+    },
+    #[error("unexpected matrix response: {0}")]
+    Protocol(String),
+    #[error("nexis transport error: {0}")]
+    Nexis(String),
+}
+
+/// Configuration for one Matrix room to Nexis room bridge.
+#[derive(Debug, Clone)]
+pub struct MatrixBridgeConfig {
+    pub homeserver_url: String,
+    pub access_token: String,
+    /// Matrix room id (`!abc:server`) or alias (`#room:server`).
+    pub matrix_room: String,
+    pub nexis_room_id: String,
+    pub bridge_member: String,
+    pub sync_timeout: Duration,
+    pub relay_membership: bool,
+}
+
+impl MatrixBridgeConfig {
+    /// Creates a bridge configuration with default sync and membership settings.
+    pub fn new(
+        homeserver_url: impl Into<String>,
+        access_token: impl Into<String>,
+        matrix_room: impl Into<String>,
+        nexis_room_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            homeserver_url: homeserver_url.into().trim_end_matches('/').to_string(),
+            access_token: access_token.into(),
+            matrix_room: matrix_room.into(),
+            nexis_room_id: nexis_room_id.into(),
+            bridge_member: DEFAULT_BRIDGE_MEMBER.to_string(),
+            sync_timeout: DEFAULT_SYNC_TIMEOUT,
+            relay_membership: true,
+        }
+    }
+
+    /// Sets the Nexis member that posts membership notices.
+    pub fn with_bridge_member(mut self, member: impl Into<String>) -> Self {
+        self.bridge_member = member.into();
+        self
+    }
+
+    /// Sets the `/sync` long-poll timeout.
+    pub fn with_sync_timeout(mut self, timeout: Duration) -> Self {
+        self.sync_timeout = timeout;
+        self
+    }
+
+    /// Enables or disables relaying Matrix join/leave/invite/ban events.
+    pub fn with_relay_membership(mut self, enabled: bool) -> Self {
+        self.relay_membership = enabled;
+        self
+    }
+}
+
+/// A Matrix room event from a `/sync` timeline.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MatrixEvent {
+    pub event_id: String,
+    pub sender: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(default)]
+    pub content: Value,
+    #[serde(default)]
+    pub state_key: Option<String>,
+}
+
+/// Timeline events and the token for the next `/sync`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncBatch {
+    pub next_batch: String,
+    pub events: Vec<MatrixEvent>,
+}
+
+/// What a Matrix event becomes on the Nexis side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgedEvent {
+    /// A chat message from a Matrix user.
+    Message {
+        sender: String,
+        text: String,
+        in_reply_to: Option<String>,
+    },
+    /// A membership change, rendered as a notice.
+    Membership { notice: String },
+}
+
+/// Maps a Matrix user id such as `@alice:example.org` to a Nexis member id.
+pub fn matrix_member_id(matrix_user: &str) -> String {
+    let user = matrix_user.trim_start_matches('@');
+    let identifier = match user.split_once(':') {
+        Some((localpart, server)) => format!("{localpart}@{}", server.replace(':', "_")),
+        None => user.to_string(),
+    };
+    format!("{MATRIX_MEMBER_PREFIX}{identifier}")
+}
+
+/// Short display name for a Nexis member id (`nexis:human:alice` -> `alice`).
+pub fn nexis_display_name(member_id: &str) -> &str {
+    member_id
+        .strip_prefix("nexis:")
+        .and_then(|rest| rest.split_once(':'))
+        .map_or(member_id, |(_, identifier)| identifier)
+}
+
+/// Removes the quoted reply fallback Matrix clients prepend to reply bodies.
+pub fn strip_reply_fallback(body: &str) -> &str {
+    if !body.starts_with("> ") {
+        return body;
+    }
+    body.split_once("\n\n").map_or(body, |(_, rest)| rest)
+}
+
+/// Translates a Matrix timeline event into a Nexis-side action.
+///
+/// Returns `None` for events the bridge does not relay.
+pub fn translate_event(event: &MatrixEvent) -> Option<BridgedEvent> {
+    match event.event_type.as_str() {
+        "m.room.message" => {
+            let msgtype = event.content.get("msgtype")?.as_str()?;
+            let body = event.content.get("body")?.as_str()?;
+            let in_reply_to = event
+                .content
+                .pointer("/m.relates_to/m.in_reply_to/event_id")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            let body = if in_reply_to.is_some() {
+                strip_reply_fallback(body)
+            } else {
+                body
+            };
+            let text = match msgtype {
+                "m.text" | "m.notice" => body.to_string(),
+                "m.emote" => format!("* {} {body}", event.sender),
+                _ => return None,
+            };
+            Some(BridgedEvent::Message {
+                sender: matrix_member_id(&event.sender),
+                text,
+                in_reply_to,
+            })
+        }
+        "m.room.member" => {
+            let target = event.state_key.as_deref().unwrap_or(&event.sender);
+            let notice = match event.content.get("membership")?.as_str()? {
+                "join" => format!("{target} joined the Matrix room"),
+                "leave" if target == event.sender => format!("{target} left the Matrix room"),
+                "leave" => format!(
+                    "{target} was removed from the Matrix room by {}",
+                    event.sender
+                ),
+                "invite" => format!(
+                    "{target} was invited to the Matrix room by {}",
+                    event.sender
+                ),
+                "ban" => format!(
+                    "{target} was banned from the Matrix room by {}",
+                    event.sender
+                ),
+                _ => return None,
+            };
+            Some(BridgedEvent::Membership { notice })
+        }
+        _ => None,
+    }
+}
+
+/// Bounded two-way mapping between Nexis message ids and Matrix event ids.
+#[derive(Debug)]
+pub struct EventIdMap {
+    capacity: usize,
+    nexis_to_matrix: HashMap<String, String>,
+    matrix_to_nexis: HashMap<String, String>,
+    order: VecDeque<String>,
+}
+
+impl EventIdMap {
+    /// Creates a map that forgets the oldest pairs beyond `capacity`.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            nexis_to_matrix: HashMap::new(),
+            matrix_to_nexis: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Records that a Nexis message and a Matrix event are the same message.
+    pub fn insert(&mut self, nexis_id: impl Into<String>, matrix_event_id: impl Into<String>) {
+        let nexis_id = nexis_id.into();
+        let matrix_event_id = matrix_event_id.into();
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                if let Some(event_id) = self.nexis_to_matrix.remove(&oldest) {
+                    self.matrix_to_nexis.remove(&event_id);
+                }
+            }
+        }
+        self.order.push_back(nexis_id.clone());
+        self.matrix_to_nexis
+            .insert(matrix_event_id.clone(), nexis_id.clone());
+        self.nexis_to_matrix.insert(nexis_id, matrix_event_id);
+    }
+
+    /// Looks up the Matrix event for a Nexis message id.
+    pub fn matrix_for(&self, nexis_id: &str) -> Option<&str> {
+        self.nexis_to_matrix.get(nexis_id).map(String::as_str)
+    }
+
+    /// Looks up the Nexis message for a Matrix event id.
+    pub fn nexis_for(&self, matrix_event_id: &str) -> Option<&str> {
+        self.matrix_to_nexis
+            .get(matrix_event_id)
+            .map(String::as_str)
+    }
+
+    /// Number of tracked pairs.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Returns true when no pairs are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+/// Minimal Matrix client-server API client.
+#[derive(Debug, Clone)]
+pub struct MatrixClient {
+    http: reqwest::Client,
+    homeserver_url: String,
+    access_token: String,
+    txn_prefix: String,
+    txn_counter: Arc<AtomicU64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MatrixErrorBody {
+    #[serde(default)]
+    errcode: String,
+    #[serde(default)]
+    error: String,
+}
+
+impl MatrixClient {
+    /// Creates a client for a homeserver base URL such as `https://matrix.org`.
+    pub fn new(homeserver_url: impl Into<String>, access_token: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            homeserver_url: homeserver_url.into().trim_end_matches('/').to_string(),
+            access_token: access_token.into(),
+            txn_prefix: format!("nexis{}", chrono::Utc::now().timestamp_millis()),
+            txn_counter: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Returns the Matrix user id the access token belongs to.
+    pub async fn whoami(&self) -> Result<String, MatrixBridgeError> {
+        let body = self
+            .call(self.http.get(self.url("/account/whoami")))
+            .await?;
+        string_field(&body, "user_id")
+    }
+
+    /// Joins a room by id or alias and returns the room id.
+    pub async fn join(&self, room: &str) -> Result<String, MatrixBridgeError> {
+        let path = format!("/join/{}", encode_path_segment(room));
+        let body = self
+            .call(self.http.post(self.url(&path)).json(&json!({})))
+            .await?;
+        string_field(&body, "room_id")
+    }
+
+    /// Long-polls `/sync` and returns timeline events for `room_id`.
+    pub async fn sync(
+        &self,
+        room_id: &str,
+        since: Option<&str>,
+        timeout: Duration,
+    ) -> Result<SyncBatch, MatrixBridgeError> {
+        let filter = json!({
+            "room": {"rooms": [room_id], "timeline": {"limit": 50}},
+            "presence": {"types": []},
+            "account_data": {"types": []}
+        })
+        .to_string();
+        let timeout_ms = timeout.as_millis().to_string();
+        let mut query = vec![
+            ("filter", filter.as_str()),
+            ("timeout", timeout_ms.as_str()),
+        ];
+        if let Some(since) = since {
+            query.push(("since", since));
+        }
+
+        let request = self
+            .http
+            .get(self.url("/sync"))
+            .query(&query)
+            .timeout(timeout + Duration::from_secs(30));
+        let body = self.call(request).await?;
+
+        let next_batch = string_field(&body, "next_batch")?;
+        let events = body
+            .pointer(&format!(
+                "/rooms/join/{}/timeline/events",
+                room_id.replace('~', "~0").replace('/', "~1")
+            ))
+            .cloned()
+            .map(|events| {
+                serde_json::from_value::<Vec<Value>>(events)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|event| serde_json::from_value(event).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(SyncBatch { next_batch, events })
+    }
+
+    /// Sends an `m.text` message, optionally as a reply, and returns its event id.
+    pub async fn send_text(
+        &self,
+        room_id: &str,
+        body: &str,
+        in_reply_to: Option<&str>,
+    ) -> Result<String, MatrixBridgeError> {
+        let txn_id = format!(
+            "{}-{}",
+            self.txn_prefix,
+            self.txn_counter.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut content = json!({ "msgtype": "m.text", "body": body });
+        if let Some(event_id) = in_reply_to {
+            content["m.relates_to"] = json!({ "m.in_reply_to": { "event_id": event_id } });
+        }
+        let path = format!(
+            "/rooms/{}/send/m.room.message/{txn_id}",
+            encode_path_segment(room_id)
+        );
+        let body = self
+            .call(self.http.put(self.url(&path)).json(&content))
+            .await?;
+        string_field(&body, "event_id")
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/_matrix/client/v3{path}", self.homeserver_url)
+    }
+
+    async fn call(&self, request: reqwest::RequestBuilder) -> Result<Value, MatrixBridgeError> {
+        let response = request
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .map_err(|err| MatrixBridgeError::Http(err.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body: MatrixErrorBody = response.json().await.unwrap_or(MatrixErrorBody {
+                errcode: String::new(),
+                error: String::new(),
+            });
+            return Err(MatrixBridgeError::Api {
+                status: status.as_u16(),
+                errcode: body.errcode,
+                error: body.error,
+            });
+        }
+        response
+            .json()
+            .await
+            .map_err(|err| MatrixBridgeError::Protocol(err.to_string()))
+    }
+}
+
+fn string_field(body: &Value, field: &str) -> Result<String, MatrixBridgeError> {
+    body.get(field)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| MatrixBridgeError::Protocol(format!("missing `{field}`")))
+}
+
+fn encode_path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            other => format!("%{other:02X}"),
+        })
+        .collect()
+}
+
+/// Relays messages between one Matrix room and one Nexis room.
+pub struct MatrixBridge {
+    config: MatrixBridgeConfig,
+    matrix: MatrixClient,
+    nexis: Arc<dyn RoomTransport>,
+    event_ids: Mutex<EventIdMap>,
+}
+
+impl MatrixBridge {
+    /// Creates a bridge that reaches Nexis through `nexis`.
+    pub fn new(config: MatrixBridgeConfig, nexis: Arc<dyn RoomTransport>) -> Self {
+        let matrix = MatrixClient::new(&config.homeserver_url, &config.access_token);
+        Self {
+            config,
+            matrix,
+            nexis,
+            event_ids: Mutex::new(EventIdMap::new(DEFAULT_EVENT_MAP_CAPACITY)),
+        }
+    }
+
+    /// Joins the Matrix room and relays in both directions until the Nexis
+    /// subscription ends. Matrix history from before startup is not replayed.
+    pub async fn run(&self) -> Result<(), MatrixBridgeError> {
+        let own_user = self.matrix.whoami().await?;
+        let room_id = self.matrix.join(&self.config.matrix_room).await?;
+        let mut since = self
+            .matrix
+            .sync(&room_id, None, Duration::ZERO)
+            .await?
+            .next_batch;
+        let mut nexis_messages = self
+            .nexis
+            .subscribe(&self.config.nexis_room_id)
+            .await
+            .map_err(|err| MatrixBridgeError::Nexis(err.to_string()))?;
+
+        tracing::info!(
+            matrix_room = %room_id,
+            nexis_room = %self.config.nexis_room_id,
+            "matrix bridge started"
+        );
+
+        loop {
+            tokio::select! {
+                message = nexis_messages.next() => {
+                    let message = match message {
+                        Some(Ok(message)) => message,
+                        Some(Err(err)) => {
+                            tracing::warn!("nexis subscription error: {}", err);
+                            continue;
+                        }
+                        None => return Ok(()),
+                    };
+                    if let Err(err) = self.relay_to_matrix(&room_id, &message).await {
+                        tracing::warn!(message_id = %message.id, "failed to relay to matrix: {}", err);
+                    }
+                }
+                batch = self.matrix.sync(&room_id, Some(&since), self.config.sync_timeout) => {
+                    match batch {
+                        Ok(batch) => {
+                            since = batch.next_batch;
+                            for event in batch.events {
+                                if event.sender == own_user {
+                                    continue;
+                                }
+                                if let Err(err) = self.relay_to_nexis(&event).await {
+                                    tracing::warn!(event_id = %event.event_id, "failed to relay to nexis: {}", err);
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            tracing::warn!("matrix sync failed: {}", err);
+                            tokio::time::sleep(SYNC_RETRY_DELAY).await;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns true for Nexis messages that originated on the Matrix side.
+    fn is_bridged(&self, message: &RoomMessage) -> bool {
+        message.sender.starts_with(MATRIX_MEMBER_PREFIX)
+            || message.sender == self.config.bridge_member
+    }
+
+    async fn relay_to_matrix(
+        &self,
+        room_id: &str,
+        message: &RoomMessage,
+    ) -> Result<(), MatrixBridgeError> {
+        if self.is_bridged(message) {
+            return Ok(());
+        }
+
+        let in_reply_to = message.reply_to.as_deref().and_then(|reply_to| {
+            self.event_ids
+                .lock()
+                .expect("event id map poisoned")
+                .matrix_for(reply_to)
+                .map(str::to_string)
+        });
+        let body = format!("{}: {}", nexis_display_name(&message.sender), message.text);
+        let event_id = self
+            .matrix
+            .send_text(room_id, &body, in_reply_to.as_deref())
+            .await?;
+        self.event_ids
+            .lock()
+            .expect("event id map poisoned")
+            .insert(message.id.clone(), event_id);
+        Ok(())
+    }
+
+    async fn relay_to_nexis(&self, event: &MatrixEvent) -> Result<(), MatrixBridgeError> {
+        let (sender, text, reply_to) = match translate_event(event) {
+            Some(BridgedEvent::Message {
+                sender,
+                text,
+                in_reply_to,
+            }) => {
+                let reply_to = in_reply_to.and_then(|event_id| {
+                    self.event_ids
+                        .lock()
+                        .expect("event id map poisoned")
+                        .nexis_for(&event_id)
+                        .map(str::to_string)
+                });
+                (sender, text, reply_to)
+            }
+            Some(BridgedEvent::Membership { notice }) if self.config.relay_membership => {
+                (self.config.bridge_member.clone(), notice, None)
+            }
+            _ => return Ok(()),
+        };
+
+        let message_id = self
+            .nexis
+            .post_message(
+                &self.config.nexis_room_id,
+                &sender,
+                &text,
+                reply_to.as_deref(),
+            )
+            .await
+            .map_err(|err| MatrixBridgeError::Nexis(err.to_string()))?;
+        self.event_ids
+            .lock()
+            .expect("event id map poisoned")
+            .insert(message_id, event.event_id.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use nexis_runtime::{AgentRuntimeError, RoomMessageStream};
+
+    fn event(event_type: &str, sender: &str, content: Value) -> MatrixEvent {
+        MatrixEvent {
+            event_id: "$event1".to_string(),
+            sender: sender.to_string(),
+            event_type: event_type.to_string(),
+            content,
+            state_key: None,
+        }
+    }
+
+    #[test]
+    fn matrix_users_map_to_valid_member_ids() {
+        let member = matrix_member_id("@alice:example.org:8448");
+        assert_eq!(member, "nexis:human:matrix_alice@example.org_8448");
+        assert!(member.parse::<nexis_protocol::MemberId>().is_ok());
+        assert_eq!(nexis_display_name("nexis:agent:helper"), "helper");
+        assert_eq!(nexis_display_name("plain"), "plain");
+    }
+
+    #[test]
+    fn messages_and_replies_are_translated() {
+        let plain = event(
+            "m.room.message",
+            "@bob:example.org",
+            json!({"msgtype": "m.text", "body": "hello"}),
+        );
+        assert_eq!(
+            translate_event(&plain),
+            Some(BridgedEvent::Message {
+                sender: "nexis:human:matrix_bob@example.org".to_string(),
+                text: "hello".to_string(),
+                in_reply_to: None,
+            })
+        );
+
+        let reply = event(
+            "m.room.message",
+            "@bob:example.org",
+            json!({
+                "msgtype": "m.text",
+                "body": "> <@alice:example.org> original\n\nanswer",
+                "m.relates_to": {"m.in_reply_to": {"event_id": "$orig"}}
+            }),
+        );
+        assert_eq!(
+            translate_event(&reply),
+            Some(BridgedEvent::Message {
+                sender: "nexis:human:matrix_bob@example.org".to_string(),
+                text: "answer".to_string(),
+                in_reply_to: Some("$orig".to_string()),
+            })
+        );
+
+        let image = event(
+            "m.room.message",
+            "@bob:example.org",
+            json!({"msgtype": "m.image", "body": "cat.png"}),
+        );
+        assert_eq!(translate_event(&image), None);
+    }
+
+    #[test]
+    fn membership_events_become_notices() {
+        let mut kick = event(
+            "m.room.member",
+            "@mod:example.org",
+            json!({"membership": "leave"}),
+        );
+        kick.state_key = Some("@bob:example.org".to_string());
+        assert_eq!(
+            translate_event(&kick),
+            Some(BridgedEvent::Membership {
+                notice: "@bob:example.org was removed from the Matrix room by @mod:example.org"
+                    .to_string()
+            })
+        );
+
+        let mut join = event(
+            "m.room.member",
+            "@bob:example.org",
+            json!({"membership": "join"}),
+        );
+        join.state_key = Some("@bob:example.org".to_string());
+        assert!(matches!(
+            translate_event(&join),
+            Some(BridgedEvent::Membership { notice }) if notice == "@bob:example.org joined the Matrix room"
+        ));
+    }
+
+    #[test]
+    fn event_id_map_is_bounded() {
+        let mut map = EventIdMap::new(2);
+        map.insert("msg_1", "$1");
+        map.insert("msg_2", "$2");
+        map.insert("msg_3", "$3");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.matrix_for("msg_1"), None);
+        assert_eq!(map.nexis_for("$1"), None);
+        assert_eq!(map.matrix_for("msg_3"), Some("$3"));
+        assert_eq!(map.nexis_for("$2"), Some("msg_2"));
+    }
+
+    #[derive(Default)]
+    struct RecordingTransport {
+        posted: Mutex<Vec<(String, String, Option<String>)>>,
+    }
+
+    #[async_trait]
+    impl RoomTransport for RecordingTransport {
+        async fn subscribe(&self, _room_id: &str) -> Result<RoomMessageStream, AgentRuntimeError> {
+            Ok(Box::pin(futures::stream::empty()))
+        }
+
+        async fn post_message(
+            &self,
+            _room_id: &str,
+            sender: &str,
+            text: &str,
+            reply_to: Option<&str>,
+        ) -> Result<String, AgentRuntimeError> {
+            let mut posted = self.posted.lock().unwrap();
+            posted.push((
+                sender.to_string(),
+                text.to_string(),
+                reply_to.map(str::to_string),
+            ));
+            Ok(format!("msg_{}", posted.len()))
+        }
+    }
+
+    #[tokio::test]
+    async fn matrix_replies_thread_onto_nexis_messages() {
+        let transport = Arc::new(RecordingTransport::default());
+        let bridge = MatrixBridge::new(
+            MatrixBridgeConfig::new(
+                "http://localhost:8008",
+                "token",
+                "!room:example.org",
+                "room_1",
+            ),
+            transport.clone(),
+        );
+        bridge
+            .event_ids
+            .lock()
+            .unwrap()
+            .insert("msg_nexis", "$orig");
+
+        let mut reply = event(
+            "m.room.message",
+            "@bob:example.org",
+            json!({
+                "msgtype": "m.text",
+                "body": "> <@alice:example.org> hi\n\nhello back",
+                "m.relates_to": {"m.in_reply_to": {"event_id": "$orig"}}
+            }),
+        );
+        reply.event_id = "$reply".to_string();
+        bridge.relay_to_nexis(&reply).await.unwrap();
+
+        let posted = transport.posted.lock().unwrap().clone();
+        assert_eq!(
+            posted,
+            vec![(
+                "nexis:human:matrix_bob@example.org".to_string(),
+                "hello back".to_string(),
+                Some("msg_nexis".to_string()),
+            )]
+        );
+        assert_eq!(
+            bridge.event_ids.lock().unwrap().matrix_for("msg_1"),
+            Some("$reply")
+        );
+
+        // Messages that came from Matrix are never echoed back.
+        let echoed = RoomMessage {
+            id: "msg_1".to_string(),
+            room_id: "room_1".to_string(),
+            sender: "nexis:human:matrix_bob@example.org".to_string(),
+            text: "hello back".to_string(),
+            reply_to: None,
+        };
+        assert!(bridge.is_bridged(&echoed));
+    }
+}
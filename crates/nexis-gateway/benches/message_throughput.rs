@@ -8,12 +8,15 @@ use tower::ServiceExt;
 fn authorization_header() -> String {
     let now = chrono::Utc::now().timestamp() as usize;
     let claims = Claims {
-        sub: "bench-user".to_string(),
+        sub: "nexis:agent:bench".to_string(),
         exp: now + 3600,
         iat: now,
         iss: "nexis".to_string(),
         aud: "nexis".to_string(),
-        member_type: "human".to_string(),
+        member_type: "agent".to_string(),
+        jti: String::new(),
+        #[cfg(feature = "multi-tenant")]
+        tenant_id: None,
     };
 
     let token = jsonwebtoken::encode(
@@ -104,7 +107,7 @@ fn benchmark_message_throughput(c: &mut Criterion) {
                                 .clone()
                                 .oneshot(send_message_request(
                                     &room_id,
-                                    "bench",
+                                    "nexis:agent:bench",
                                     &format!("message-{idx}"),
                                     &auth,
                                 ))
@@ -35,7 +35,7 @@ fn send_message_request(room_id: &str, index: usize) -> Request<Body> {
         .body(Body::from(
             serde_json::json!({
                 "roomId": room_id,
-                "sender": "bench",
+                "sender": "nexis:agent:bench",
                 "text": format!("message-{index}"),
             })
             .to_string(),
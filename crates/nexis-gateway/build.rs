@@ -0,0 +1,13 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    {
+        // Use the bundled protoc so builds don't depend on a system install.
+        if std::env::var_os("PROTOC").is_none() {
+            std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+        }
+        tonic_build::configure()
+            .compile_protos(&["proto/nexis/v1/control_plane.proto"], &["proto"])?;
+    }
+    Ok(())
+}
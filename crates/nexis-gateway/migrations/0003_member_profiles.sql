@@ -0,0 +1,9 @@
+-- Directory entries are keyed by their Nexis member id and need not have an email
+ALTER TABLE members ALTER COLUMN email DROP NOT NULL;
+ALTER TABLE members ADD COLUMN IF NOT EXISTS display_name TEXT;
+ALTER TABLE members ADD COLUMN IF NOT EXISTS avatar_url TEXT;
+ALTER TABLE members ADD COLUMN IF NOT EXISTS public_key TEXT;
+ALTER TABLE members ADD COLUMN IF NOT EXISTS capabilities TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_members_email ON members (email);
+CREATE INDEX IF NOT EXISTS idx_rooms_created_at ON rooms (created_at);
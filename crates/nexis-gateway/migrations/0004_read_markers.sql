@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS read_markers (
+    room_id TEXT NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+    member_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (room_id, member_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_read_markers_member ON read_markers (member_id);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS tenants (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    max_rooms BIGINT,
+    max_messages_per_day BIGINT,
+    max_vector_documents BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_rooms_tenant ON rooms (tenant_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_messages_tenant_room ON messages (tenant_id, room_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_members_tenant ON members (tenant_id);
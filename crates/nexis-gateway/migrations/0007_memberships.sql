@@ -0,0 +1,9 @@
+-- Members invited to or joined into a room
+CREATE TABLE IF NOT EXISTS memberships (
+    room_id TEXT NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+    member_id TEXT NOT NULL,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (room_id, member_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_memberships_member ON memberships (member_id);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS index_outbox (
+    message_id TEXT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    room_id TEXT NOT NULL,
+    tenant_id TEXT,
+    content TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_index_outbox_created_at ON index_outbox (created_at);
@@ -0,0 +1,8 @@
+-- Capabilities become a structured declaration; earlier free-form tags are kept as tool names
+ALTER TABLE members ALTER COLUMN capabilities DROP DEFAULT;
+ALTER TABLE members ALTER COLUMN capabilities TYPE JSONB USING
+    CASE
+        WHEN cardinality(capabilities) = 0 THEN '{}'::jsonb
+        ELSE jsonb_build_object('tools', to_jsonb(capabilities))
+    END;
+ALTER TABLE members ALTER COLUMN capabilities SET DEFAULT '{}'::jsonb;
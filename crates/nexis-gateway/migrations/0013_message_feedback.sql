@@ -0,0 +1,12 @@
+-- One rating per AI message and rater
+CREATE TABLE IF NOT EXISTS message_feedback (
+    message_id TEXT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    room_id TEXT NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+    member_id TEXT NOT NULL,
+    rating TEXT NOT NULL CHECK (rating IN ('up', 'down')),
+    comment TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (message_id, member_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_feedback_room_id ON message_feedback (room_id);
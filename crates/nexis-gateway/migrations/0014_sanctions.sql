@@ -0,0 +1,11 @@
+-- Bans and mutes in rooms, one of each kind per member
+CREATE TABLE IF NOT EXISTS sanctions (
+    room_id TEXT NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+    member_id TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('ban', 'mute')),
+    reason TEXT,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,
+    PRIMARY KEY (room_id, member_id, kind)
+);
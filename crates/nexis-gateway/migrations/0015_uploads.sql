@@ -0,0 +1,13 @@
+-- Files uploaded for attachments; the blobs themselves live in the blob store
+CREATE TABLE IF NOT EXISTS uploads (
+    id TEXT PRIMARY KEY,
+    file_name TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    uploaded_by TEXT NOT NULL,
+    tenant_id TEXT,
+    room_id TEXT REFERENCES rooms(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_uploads_room_id ON uploads (room_id);
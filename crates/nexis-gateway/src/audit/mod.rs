@@ -0,0 +1,565 @@
+//! Audit trail for security-relevant actions.
+//!
+//! Every [`AuditEvent`] records who did what to which resource and whether it
+//! succeeded. Events are written to a pluggable [`AuditSink`] (tracing, a
+//! JSONL file or a database table) and the most recent ones are kept in
+//! memory so administrators can page through them via `GET /v1/audit`.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, RwLock};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::config::{AuditConfig, AuditSinkKind, NexisConfig};
+
+/// SQL schema for the `audit_log` table used by the database sink.
+pub const AUDIT_LOG_TABLE_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS audit_log (
+    id TEXT PRIMARY KEY,
+    member_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    resource TEXT NOT NULL,
+    result TEXT NOT NULL,
+    reason TEXT,
+    occurred_at TIMESTAMPTZ NOT NULL
+);"#;
+
+/// Index for paging the audit log by time.
+pub const AUDIT_LOG_OCCURRED_AT_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_audit_log_occurred_at ON audit_log(occurred_at);"#;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("audit sink I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to encode audit event: {0}")]
+    Encode(#[from] serde_json::Error),
+    #[cfg(feature = "persistence-sqlx")]
+    #[error("audit database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("audit sink `{0}` requires the persistence-sqlx feature")]
+    Unsupported(&'static str),
+}
+
+/// Kinds of audited actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum AuditAction {
+    #[serde(rename = "room.created")]
+    RoomCreated,
+    #[serde(rename = "room.deleted")]
+    RoomDeleted,
+    #[serde(rename = "room.imported")]
+    RoomImported,
+    #[serde(rename = "room.moderation_changed")]
+    RoomModerationChanged,
+    #[serde(rename = "room.settings_changed")]
+    RoomSettingsChanged,
+    #[serde(rename = "room.ai_budget_overridden")]
+    RoomAiBudgetOverridden,
+    #[serde(rename = "room.ai_budget_restored")]
+    RoomAiBudgetRestored,
+    #[serde(rename = "member.invited")]
+    MemberInvited,
+    #[serde(rename = "member.joined")]
+    MemberJoined,
+    #[serde(rename = "member.join_requested")]
+    JoinRequested,
+    #[serde(rename = "member.join_rejected")]
+    JoinRequestRejected,
+    #[serde(rename = "member.kicked")]
+    MemberKicked,
+    #[serde(rename = "member.banned")]
+    MemberBanned,
+    #[serde(rename = "member.unbanned")]
+    MemberUnbanned,
+    #[serde(rename = "member.muted")]
+    MemberMuted,
+    #[serde(rename = "member.unmuted")]
+    MemberUnmuted,
+    #[serde(rename = "invitation.created")]
+    InvitationCreated,
+    #[serde(rename = "invitation.revoked")]
+    InvitationRevoked,
+    #[serde(rename = "member.signing_key_registered")]
+    SigningKeyRegistered,
+    #[serde(rename = "member.profile_created")]
+    MemberProfileCreated,
+    #[serde(rename = "member.profile_updated")]
+    MemberProfileUpdated,
+    #[serde(rename = "member.profile_deleted")]
+    MemberProfileDeleted,
+    #[serde(rename = "permission.changed")]
+    PermissionChanged,
+    #[serde(rename = "message.deleted")]
+    MessageDeleted,
+    #[serde(rename = "message.flagged")]
+    MessageFlagged,
+    #[serde(rename = "message.rejected")]
+    MessageRejected,
+    #[serde(rename = "message.spam_detected")]
+    SpamDetected,
+    #[serde(rename = "token.issued")]
+    TokenIssued,
+    #[serde(rename = "token.revoked")]
+    TokenRevoked,
+    #[serde(rename = "webhook.registered")]
+    WebhookRegistered,
+    #[serde(rename = "webhook.secret_rotated")]
+    WebhookSecretRotated,
+    #[serde(rename = "webhook.disabled")]
+    WebhookDisabled,
+    #[serde(rename = "schedule.created")]
+    ScheduleCreated,
+    #[serde(rename = "schedule.deleted")]
+    ScheduleDeleted,
+    #[serde(rename = "search.reindexed")]
+    SearchReindexed,
+    #[serde(rename = "tenant.created")]
+    TenantCreated,
+    #[serde(rename = "tenant.suspended")]
+    TenantSuspended,
+    #[serde(rename = "tenant.resumed")]
+    TenantResumed,
+    #[serde(rename = "tenant.quota_changed")]
+    TenantQuotaChanged,
+    #[serde(rename = "retention.changed")]
+    RetentionChanged,
+}
+
+impl AuditAction {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::RoomCreated => "room.created",
+            Self::RoomDeleted => "room.deleted",
+            Self::RoomImported => "room.imported",
+            Self::RoomModerationChanged => "room.moderation_changed",
+            Self::RoomSettingsChanged => "room.settings_changed",
+            Self::RoomAiBudgetOverridden => "room.ai_budget_overridden",
+            Self::RoomAiBudgetRestored => "room.ai_budget_restored",
+            Self::MemberInvited => "member.invited",
+            Self::MemberJoined => "member.joined",
+            Self::JoinRequested => "member.join_requested",
+            Self::JoinRequestRejected => "member.join_rejected",
+            Self::MemberKicked => "member.kicked",
+            Self::MemberBanned => "member.banned",
+            Self::MemberUnbanned => "member.unbanned",
+            Self::MemberMuted => "member.muted",
+            Self::MemberUnmuted => "member.unmuted",
+            Self::InvitationCreated => "invitation.created",
+            Self::InvitationRevoked => "invitation.revoked",
+            Self::SigningKeyRegistered => "member.signing_key_registered",
+            Self::MemberProfileCreated => "member.profile_created",
+            Self::MemberProfileUpdated => "member.profile_updated",
+            Self::MemberProfileDeleted => "member.profile_deleted",
+            Self::PermissionChanged => "permission.changed",
+            Self::MessageDeleted => "message.deleted",
+            Self::MessageFlagged => "message.flagged",
+            Self::MessageRejected => "message.rejected",
+            Self::SpamDetected => "message.spam_detected",
+            Self::TokenIssued => "token.issued",
+            Self::TokenRevoked => "token.revoked",
+            Self::WebhookRegistered => "webhook.registered",
+            Self::WebhookSecretRotated => "webhook.secret_rotated",
+            Self::WebhookDisabled => "webhook.disabled",
+            Self::ScheduleCreated => "schedule.created",
+            Self::ScheduleDeleted => "schedule.deleted",
+            Self::SearchReindexed => "search.reindexed",
+            Self::TenantCreated => "tenant.created",
+            Self::TenantSuspended => "tenant.suspended",
+            Self::TenantResumed => "tenant.resumed",
+            Self::TenantQuotaChanged => "tenant.quota_changed",
+            Self::RetentionChanged => "retention.changed",
+        }
+    }
+}
+
+impl std::fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whether an audited action went through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditResult {
+    Success,
+    /// The caller was not allowed to perform the action.
+    Denied,
+    /// The action was allowed but could not be completed.
+    Failed,
+}
+
+impl AuditResult {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Denied => "denied",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// One entry in the audit trail.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    pub id: String,
+    pub member_id: String,
+    pub action: AuditAction,
+    /// Affected resource, e.g. `room:room_1` or `room:room_1/webhook:wh_2`.
+    pub resource: String,
+    pub result: AuditResult,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl AuditEvent {
+    /// A successful `action` by `member_id` on `resource`.
+    pub fn new(
+        member_id: impl Into<String>,
+        action: AuditAction,
+        resource: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: format!("audit_{}", Uuid::new_v4().simple()),
+            member_id: member_id.into(),
+            action,
+            resource: resource.into(),
+            result: AuditResult::Success,
+            reason: None,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    pub fn with_result(mut self, result: AuditResult, reason: impl Into<String>) -> Self {
+        self.result = result;
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+/// Destination for audit events.
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    async fn record(&self, event: &AuditEvent) -> Result<(), AuditError>;
+}
+
+/// Emits each event as a structured `tracing` record with target `audit`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingAuditSink;
+
+#[async_trait]
+impl AuditSink for TracingAuditSink {
+    async fn record(&self, event: &AuditEvent) -> Result<(), AuditError> {
+        tracing::info!(
+            target: "audit",
+            audit_id = %event.id,
+            member_id = %event.member_id,
+            action = %event.action,
+            resource = %event.resource,
+            result = event.result.as_str(),
+            reason = ?event.reason,
+            "audit event"
+        );
+        Ok(())
+    }
+}
+
+/// Appends each event as one JSON line to a file.
+pub struct FileAuditSink {
+    path: PathBuf,
+    file: Mutex<tokio::fs::File>,
+}
+
+impl FileAuditSink {
+    /// Open `path` for appending, creating it if needed.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, AuditError> {
+        let path = path.as_ref().to_path_buf();
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl AuditSink for FileAuditSink {
+    async fn record(&self, event: &AuditEvent) -> Result<(), AuditError> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        let mut file = self.file.lock().await;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Inserts each event into the `audit_log` table.
+#[cfg(feature = "persistence-sqlx")]
+pub struct SqlxAuditSink {
+    pool: crate::db::DatabasePool,
+}
+
+#[cfg(feature = "persistence-sqlx")]
+impl SqlxAuditSink {
+    /// Create the sink, making sure the `audit_log` table exists.
+    pub async fn new(pool: crate::db::DatabasePool) -> Result<Self, AuditError> {
+        sqlx::query(AUDIT_LOG_TABLE_SCHEMA).execute(&pool).await?;
+        sqlx::query(AUDIT_LOG_OCCURRED_AT_INDEX)
+            .execute(&pool)
+            .await?;
+        Ok(Self { pool })
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+#[async_trait]
+impl AuditSink for SqlxAuditSink {
+    async fn record(&self, event: &AuditEvent) -> Result<(), AuditError> {
+        sqlx::query(
+            "INSERT INTO audit_log (id, member_id, action, resource, result, reason, occurred_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(&event.id)
+        .bind(&event.member_id)
+        .bind(event.action.as_str())
+        .bind(&event.resource)
+        .bind(event.result.as_str())
+        .bind(&event.reason)
+        .bind(event.occurred_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Filters and paging for [`AuditLog::query`].
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    pub member_id: Option<String>,
+    pub action: Option<AuditAction>,
+    /// Matches resources equal to or nested under this value.
+    pub resource: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, event: &AuditEvent) -> bool {
+        self.member_id
+            .as_deref()
+            .is_none_or(|member_id| event.member_id == member_id)
+            && self.action.is_none_or(|action| event.action == action)
+            && self.resource.as_deref().is_none_or(|resource| {
+                event.resource == resource
+                    || event
+                        .resource
+                        .strip_prefix(resource)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+    }
+}
+
+/// A page of audit events, newest first.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditPage {
+    pub events: Vec<AuditEvent>,
+    /// Number of retained events matching the filters.
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+struct Inner {
+    sink: Arc<dyn AuditSink>,
+    recent: RwLock<VecDeque<AuditEvent>>,
+    retained: usize,
+}
+
+/// Records audit events to a sink and keeps a bounded window for queries.
+#[derive(Clone)]
+pub struct AuditLog {
+    inner: Arc<Inner>,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new(Arc::new(TracingAuditSink), AuditConfig::default().retained)
+    }
+}
+
+impl AuditLog {
+    pub fn new(sink: Arc<dyn AuditSink>, retained: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                sink,
+                recent: RwLock::new(VecDeque::new()),
+                retained: retained.max(1),
+            }),
+        }
+    }
+
+    /// Build the audit log described by `config.audit`.
+    pub async fn from_config(config: &NexisConfig) -> Result<Self, AuditError> {
+        let audit = &config.audit;
+        let sink: Arc<dyn AuditSink> = match audit.sink {
+            AuditSinkKind::Tracing => Arc::new(TracingAuditSink),
+            AuditSinkKind::File => Arc::new(FileAuditSink::open(&audit.path).await?),
+            #[cfg(feature = "persistence-sqlx")]
+            AuditSinkKind::Database => {
+                let url = config.database.url.as_deref().unwrap_or_default();
+                let pool = sqlx::postgres::PgPoolOptions::new()
+                    .max_connections(config.database.max_connections)
+                    .connect(url)
+                    .await?;
+                Arc::new(SqlxAuditSink::new(pool).await?)
+            }
+            #[cfg(not(feature = "persistence-sqlx"))]
+            AuditSinkKind::Database => return Err(AuditError::Unsupported("database")),
+        };
+        Ok(Self::new(sink, audit.retained))
+    }
+
+    /// Record `event`. Sink failures are logged rather than surfaced so an
+    /// unavailable audit backend never turns a completed action into an error.
+    pub async fn record(&self, event: AuditEvent) {
+        if let Err(err) = self.inner.sink.record(&event).await {
+            tracing::error!(
+                target: "audit",
+                audit_id = %event.id,
+                action = %event.action,
+                error = %err,
+                "failed to write audit event"
+            );
+        }
+
+        let mut recent = self.inner.recent.write().await;
+        if recent.len() == self.inner.retained {
+            recent.pop_front();
+        }
+        recent.push_back(event);
+    }
+
+    /// Page through retained events matching `query`, newest first.
+    pub async fn query(&self, query: &AuditQuery) -> AuditPage {
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let offset = query.offset.unwrap_or(0);
+
+        let recent = self.inner.recent.read().await;
+        let matching: Vec<&AuditEvent> = recent
+            .iter()
+            .rev()
+            .filter(|event| query.matches(event))
+            .collect();
+        AuditPage {
+            total: matching.len(),
+            events: matching
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .cloned()
+                .collect(),
+            limit,
+            offset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn query_filters_and_pages_newest_first() {
+        let log = AuditLog::new(Arc::new(TracingAuditSink), 3);
+        for (member, action, resource) in [
+            ("alice", AuditAction::RoomCreated, "room:a"),
+            ("bob", AuditAction::MemberInvited, "room:a/member:carol"),
+            ("alice", AuditAction::RoomCreated, "room:ab"),
+            ("alice", AuditAction::RoomDeleted, "room:a"),
+        ] {
+            log.record(AuditEvent::new(member, action, resource)).await;
+        }
+
+        // The oldest event fell out of the retained window.
+        let all = log.query(&AuditQuery::default()).await;
+        assert_eq!(all.total, 3);
+        assert_eq!(all.events[0].action, AuditAction::RoomDeleted);
+
+        let room_a = log
+            .query(&AuditQuery {
+                resource: Some("room:a".to_string()),
+                ..AuditQuery::default()
+            })
+            .await;
+        let resources: Vec<_> = room_a.events.iter().map(|e| e.resource.as_str()).collect();
+        assert_eq!(resources, ["room:a", "room:a/member:carol"]);
+
+        let page = log
+            .query(&AuditQuery {
+                member_id: Some("alice".to_string()),
+                limit: Some(1),
+                offset: Some(1),
+                ..AuditQuery::default()
+            })
+            .await;
+        assert_eq!(page.total, 2);
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(page.events[0].resource, "room:ab");
+    }
+
+    #[tokio::test]
+    async fn file_sink_appends_json_lines() {
+        let path = std::env::temp_dir().join(format!("nexis-audit-{}.jsonl", Uuid::new_v4()));
+        let sink = FileAuditSink::open(&path).await.unwrap();
+        let denied = AuditEvent::new("mallory", AuditAction::RoomDeleted, "room:x")
+            .with_result(AuditResult::Denied, "not a room member");
+        sink.record(&AuditEvent::new(
+            "alice",
+            AuditAction::TokenIssued,
+            "member:alice",
+        ))
+        .await
+        .unwrap();
+        sink.record(&denied).await.unwrap();
+
+        let contents = tokio::fs::read_to_string(&path).await.unwrap();
+        let _ = tokio::fs::remove_file(&path).await;
+        let lines: Vec<AuditEvent> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1], denied);
+        assert!(contents.contains(r#""action":"token.issued""#));
+        assert!(contents.contains(r#""result":"denied""#));
+    }
+}
@@ -11,11 +11,16 @@ use axum::{
 };
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[cfg(feature = "multi-tenant")]
 mod tenant;
+mod tokens;
 #[cfg(feature = "multi-tenant")]
-pub use tenant::{TenantContext, TenantError, TenantExtractor, TenantStore};
+pub use tenant::{TenantContext, TenantError, TenantExtractor};
+pub use tokens::{
+    InMemoryRevocationList, RefreshGrant, RefreshTokenStore, RevocationList, TokenPair,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
@@ -25,6 +30,9 @@ pub struct Claims {
     pub iss: String,
     pub aud: String,
     pub member_type: String,
+    /// Token id used for revocation; empty for tokens minted before ids existed.
+    #[serde(default)]
+    pub jti: String,
     #[cfg(feature = "multi-tenant")]
     #[serde(default)]
     pub tenant_id: Option<String>,
@@ -36,6 +44,8 @@ pub enum AuthError {
     InvalidToken,
     #[error("Token expired")]
     TokenExpired,
+    #[error("Token revoked")]
+    TokenRevoked,
     #[error("Missing authorization header")]
     MissingHeader,
     #[error("Invalid header format")]
@@ -52,6 +62,9 @@ pub struct JwtConfig {
     pub issuer: String,
     pub audience: String,
     pub expiry_seconds: u64,
+    pub refresh_expiry_seconds: u64,
+    revocations: Arc<dyn RevocationList>,
+    refresh_tokens: RefreshTokenStore,
 }
 
 impl JwtConfig {
@@ -62,6 +75,74 @@ impl JwtConfig {
             issuer,
             audience,
             expiry_seconds: 3600,
+            refresh_expiry_seconds: 30 * 24 * 3600,
+            revocations: Arc::new(InMemoryRevocationList::default()),
+            refresh_tokens: RefreshTokenStore::default(),
+        }
+    }
+
+    /// Check access tokens against `revocations` instead of the in-memory list.
+    pub fn with_revocation_list(mut self, revocations: Arc<dyn RevocationList>) -> Self {
+        self.revocations = revocations;
+        self
+    }
+
+    /// Issue an access token together with a refresh token starting a new
+    /// rotation family.
+    pub fn issue_tokens(
+        &self,
+        member_id: &str,
+        member_type: &str,
+        tenant_id: Option<&str>,
+    ) -> Result<TokenPair, AuthError> {
+        let access_token = self.generate_token_with_tenant(member_id, member_type, tenant_id)?;
+        let refresh_token = self.refresh_tokens.issue(
+            member_id,
+            member_type,
+            tenant_id,
+            self.refresh_expiry_seconds,
+        );
+        Ok(self.token_pair(access_token, refresh_token))
+    }
+
+    /// Exchange a refresh token for a fresh pair, rotating the refresh token.
+    pub fn refresh(&self, refresh_token: &str) -> Result<(RefreshGrant, TokenPair), AuthError> {
+        let (grant, refresh_token) = self
+            .refresh_tokens
+            .rotate(refresh_token, self.refresh_expiry_seconds)?;
+        let access_token = self.generate_token_with_tenant(
+            &grant.member_id,
+            &grant.member_type,
+            grant.tenant_id.as_deref(),
+        )?;
+        Ok((grant, self.token_pair(access_token, refresh_token)))
+    }
+
+    /// Deny the access token described by `claims` for the rest of its lifetime.
+    pub fn revoke(&self, claims: &Claims) {
+        if !claims.jti.is_empty() {
+            self.revocations.revoke(&claims.jti, claims.exp);
+        }
+    }
+
+    /// Revoke a refresh token and every token rotated from the same family.
+    /// Returns whether the token was known.
+    pub fn revoke_refresh_token(&self, refresh_token: &str) -> bool {
+        self.refresh_tokens.revoke(refresh_token)
+    }
+
+    /// Revoke every refresh token issued to `member_id`; returns the number
+    /// of token families revoked.
+    pub fn revoke_member_refresh_tokens(&self, member_id: &str) -> usize {
+        self.refresh_tokens.revoke_member(member_id)
+    }
+
+    fn token_pair(&self, access_token: String, refresh_token: String) -> TokenPair {
+        TokenPair {
+            access_token,
+            refresh_token,
+            token_type: "Bearer",
+            expires_in: self.expiry_seconds,
         }
     }
 
@@ -84,6 +165,7 @@ impl JwtConfig {
             iss: self.issuer.clone(),
             aud: self.audience.clone(),
             member_type: member_type.to_string(),
+            jti: uuid::Uuid::new_v4().to_string(),
             tenant_id: tenant_id.map(|s| s.to_string()),
         };
 
@@ -105,6 +187,7 @@ impl JwtConfig {
             iss: self.issuer.clone(),
             aud: self.audience.clone(),
             member_type: member_type.to_string(),
+            jti: uuid::Uuid::new_v4().to_string(),
         };
 
         encode(&Header::default(), &claims, &self.encoding_key).map_err(|_| AuthError::InvalidToken)
@@ -115,7 +198,7 @@ impl JwtConfig {
         validation.set_issuer(&[&self.issuer]);
         validation.set_audience(&[&self.audience]);
 
-        decode::<Claims>(token, &self.decoding_key, &validation)
+        let claims = decode::<Claims>(token, &self.decoding_key, &validation)
             .map(|data| data.claims)
             .map_err(|e| {
                 if e.kind() == &jsonwebtoken::errors::ErrorKind::ExpiredSignature {
@@ -123,7 +206,12 @@ impl JwtConfig {
                 } else {
                     AuthError::InvalidToken
                 }
-            })
+            })?;
+
+        if !claims.jti.is_empty() && self.revocations.is_revoked(&claims.jti) {
+            return Err(AuthError::TokenRevoked);
+        }
+        Ok(claims)
     }
 
     #[cfg(test)]
@@ -133,6 +221,20 @@ impl JwtConfig {
     }
 }
 
+#[cfg(test)]
+pub(crate) fn fallback_jwt_config() -> JwtConfig {
+    JwtConfig::new("test-secret", "test".to_string(), "test".to_string())
+}
+
+#[cfg(not(test))]
+pub(crate) fn fallback_jwt_config() -> JwtConfig {
+    JwtConfig::new(
+        &std::env::var("JWT_SECRET").unwrap_or_else(|_| "default_secret".to_string()),
+        std::env::var("JWT_ISSUER").unwrap_or_else(|_| "nexis".to_string()),
+        std::env::var("JWT_AUDIENCE").unwrap_or_else(|_| "nexis".to_string()),
+    )
+}
+
 pub struct AuthenticatedUser {
     pub member_id: String,
     pub member_type: String,
@@ -164,17 +266,20 @@ where
 
         let token = &header_value[7..];
 
-        // Use test config in test environment, production config otherwise
-        #[cfg(test)]
-        let config = JwtConfig::new("test-secret", "test".to_string(), "test".to_string());
+        // Prefer the config installed by the router, then fall back to the
+        // test config in tests and the environment otherwise.
+        let config = match parts.extensions.get::<JwtConfig>() {
+            Some(config) => config.clone(),
+            None => fallback_jwt_config(),
+        };
 
-        #[cfg(not(test))]
-        let config = JwtConfig::new(
-            &std::env::var("JWT_SECRET").unwrap_or_else(|_| "default_secret".to_string()),
-            std::env::var("JWT_ISSUER").unwrap_or_else(|_| "nexis".to_string()),
-            std::env::var("JWT_AUDIENCE").unwrap_or_else(|_| "nexis".to_string()),
-        );
+        Self::from_token(&config, token)
+    }
+}
 
+impl AuthenticatedUser {
+    /// Member holding the access token `token`, verified with `config`.
+    pub fn from_token(config: &JwtConfig, token: &str) -> Result<Self, StatusCode> {
         let claims = config
             .verify_token(token)
             .map_err(|_| StatusCode::UNAUTHORIZED)?;
@@ -257,6 +362,42 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn revoked_token_is_rejected() {
+        let config = JwtConfig::new(
+            "test_secret_key_that_is_long_enough",
+            "nexis-test".to_string(),
+            "nexis".to_string(),
+        );
+
+        let token = config.generate_token("user1", "human").unwrap();
+        let claims = config.verify_token(&token).unwrap();
+        config.clone().revoke(&claims);
+
+        assert!(matches!(
+            config.verify_token(&token),
+            Err(AuthError::TokenRevoked)
+        ));
+    }
+
+    #[test]
+    fn refresh_issues_new_pair_for_same_member() {
+        let config = JwtConfig::new(
+            "test_secret_key_that_is_long_enough",
+            "nexis-test".to_string(),
+            "nexis".to_string(),
+        );
+
+        let pair = config.issue_tokens("user1", "human", None).unwrap();
+        let (grant, refreshed) = config.refresh(&pair.refresh_token).unwrap();
+
+        assert_eq!(grant.member_id, "user1");
+        assert_ne!(refreshed.refresh_token, pair.refresh_token);
+        let claims = config.verify_token(&refreshed.access_token).unwrap();
+        assert_eq!(claims.sub, "user1");
+        assert!(config.refresh(&pair.refresh_token).is_err());
+    }
+
     #[cfg(feature = "multi-tenant")]
     mod multi_tenant_tests {
         use super::*;
@@ -3,13 +3,12 @@
 //! This module provides tenant-aware authentication for multi-tenant deployments.
 
 use axum::{
-    extract::{FromRef, FromRequestParts},
+    extract::FromRequestParts,
     http::{request::Parts, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
-use std::sync::Arc;
 
 #[derive(Debug, thiserror::Error)]
 pub enum TenantError {
@@ -89,7 +88,7 @@ pub fn extract_tenant_from_headers(headers: &HeaderMap) -> Result<TenantExtracto
 }
 
 pub fn tenant_extractor(claims: &crate::auth::Claims) -> Option<TenantContext> {
-    claims.tenant_id.as_ref().map(|tid| TenantContext::new(tid))
+    claims.tenant_id.as_ref().map(TenantContext::new)
 }
 
 #[derive(Debug, Clone)]
@@ -120,48 +119,6 @@ impl TenantGuard {
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct TenantStore {
-    tenants: Arc<std::sync::RwLock<Vec<String>>>,
-}
-
-impl TenantStore {
-    pub fn new() -> Self {
-        Self {
-            tenants: Arc::new(std::sync::RwLock::new(Vec::new())),
-        }
-    }
-
-    pub fn with_tenants(tenants: Vec<String>) -> Self {
-        Self {
-            tenants: Arc::new(std::sync::RwLock::new(tenants)),
-        }
-    }
-
-    pub fn register_tenant(&self, tenant_id: String) {
-        let mut tenants = self.tenants.write().unwrap();
-        if !tenants.contains(&tenant_id) {
-            tenants.push(tenant_id);
-        }
-    }
-
-    pub fn tenant_exists(&self, tenant_id: &str) -> bool {
-        let tenants = self.tenants.read().unwrap();
-        tenants.iter().any(|t| t == tenant_id)
-    }
-
-    pub fn list_tenants(&self) -> Vec<String> {
-        let tenants = self.tenants.read().unwrap();
-        tenants.clone()
-    }
-}
-
-impl FromRef<()> for TenantStore {
-    fn from_ref(_: &()) -> Self {
-        Self::new()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -191,33 +148,4 @@ mod tests {
         let result = guard.check_access("tenant_456");
         assert!(result.is_err());
     }
-
-    #[test]
-    fn tenant_store_registers_and_checks_tenants() {
-        let store = TenantStore::new();
-        assert!(!store.tenant_exists("tenant_123"));
-
-        store.register_tenant("tenant_123".to_string());
-        assert!(store.tenant_exists("tenant_123"));
-    }
-
-    #[test]
-    fn tenant_store_list_tenants_returns_all() {
-        let store =
-            TenantStore::with_tenants(vec!["tenant_123".to_string(), "tenant_456".to_string()]);
-        let tenants = store.list_tenants();
-        assert_eq!(tenants.len(), 2);
-        assert!(tenants.contains(&"tenant_123".to_string()));
-        assert!(tenants.contains(&"tenant_456".to_string()));
-    }
-
-    #[test]
-    fn tenant_store_prevents_duplicates() {
-        let store = TenantStore::new();
-        store.register_tenant("tenant_123".to_string());
-        store.register_tenant("tenant_123".to_string());
-
-        let tenants = store.list_tenants();
-        assert_eq!(tenants.len(), 1);
-    }
 }
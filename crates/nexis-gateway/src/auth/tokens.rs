@@ -0,0 +1,239 @@
+//! Refresh tokens and the access-token revocation list.
+//!
+//! Refresh tokens are opaque random strings; only their SHA-256 digest is
+//! kept server-side. Every refresh rotates the token, and presenting an
+//! already-rotated token revokes its whole family so a stolen token stops
+//! working as soon as either party uses it again.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::AuthError;
+
+const REFRESH_TOKEN_PREFIX: &str = "nrt_";
+
+/// Deny list of access-token ids (`jti`) that must no longer be accepted.
+pub trait RevocationList: Send + Sync {
+    /// Deny `jti` until `expires_at` (unix seconds), when the token lapses anyway.
+    fn revoke(&self, jti: &str, expires_at: usize);
+
+    fn is_revoked(&self, jti: &str) -> bool;
+}
+
+/// Process-local [`RevocationList`]; entries are dropped once they expire.
+#[derive(Debug, Default)]
+pub struct InMemoryRevocationList {
+    entries: Mutex<HashMap<String, usize>>,
+}
+
+impl RevocationList for InMemoryRevocationList {
+    fn revoke(&self, jti: &str, expires_at: usize) {
+        let now = now();
+        let mut entries = self.entries.lock().expect("revocation list poisoned");
+        entries.retain(|_, expiry| *expiry > now);
+        entries.insert(jti.to_string(), expires_at);
+    }
+
+    fn is_revoked(&self, jti: &str) -> bool {
+        self.entries
+            .lock()
+            .expect("revocation list poisoned")
+            .get(jti)
+            .is_some_and(|expiry| *expiry > now())
+    }
+}
+
+/// Access/refresh token pair handed to clients.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    #[schema(value_type = String, example = "Bearer")]
+    pub token_type: &'static str,
+    /// Access token lifetime in seconds.
+    pub expires_in: u64,
+}
+
+/// Identity a refresh token was issued for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefreshGrant {
+    pub member_id: String,
+    pub member_type: String,
+    pub tenant_id: Option<String>,
+    family: String,
+}
+
+#[derive(Debug, Clone)]
+struct RefreshRecord {
+    grant: RefreshGrant,
+    expires_at: usize,
+    rotated: bool,
+}
+
+/// In-memory store of issued refresh tokens, shared by clones.
+#[derive(Debug, Clone, Default)]
+pub struct RefreshTokenStore {
+    records: Arc<Mutex<HashMap<String, RefreshRecord>>>,
+}
+
+impl RefreshTokenStore {
+    /// Issue a refresh token starting a new family.
+    pub fn issue(
+        &self,
+        member_id: &str,
+        member_type: &str,
+        tenant_id: Option<&str>,
+        ttl_seconds: u64,
+    ) -> String {
+        let grant = RefreshGrant {
+            member_id: member_id.to_string(),
+            member_type: member_type.to_string(),
+            tenant_id: tenant_id.map(str::to_string),
+            family: random_hex(),
+        };
+        self.insert(grant, ttl_seconds)
+    }
+
+    /// Consume `token` and return its grant along with a replacement token in
+    /// the same family.
+    ///
+    /// Reusing a token that was already rotated revokes the entire family.
+    pub fn rotate(
+        &self,
+        token: &str,
+        ttl_seconds: u64,
+    ) -> Result<(RefreshGrant, String), AuthError> {
+        let grant = {
+            let mut records = self.records.lock().expect("refresh store poisoned");
+            let record = records
+                .get_mut(&digest(token))
+                .ok_or(AuthError::InvalidToken)?;
+            if record.rotated {
+                let RefreshGrant {
+                    member_id, family, ..
+                } = record.grant.clone();
+                records.retain(|_, record| record.grant.family != family);
+                tracing::warn!(
+                    member_id = %member_id,
+                    "Refresh token reuse detected; revoked token family"
+                );
+                return Err(AuthError::TokenRevoked);
+            }
+            if record.expires_at <= now() {
+                return Err(AuthError::TokenExpired);
+            }
+            record.rotated = true;
+            record.grant.clone()
+        };
+        let replacement = self.insert(grant.clone(), ttl_seconds);
+        Ok((grant, replacement))
+    }
+
+    /// Revoke the family `token` belongs to; returns whether it was known.
+    pub fn revoke(&self, token: &str) -> bool {
+        let mut records = self.records.lock().expect("refresh store poisoned");
+        let Some(family) = records
+            .get(&digest(token))
+            .map(|record| record.grant.family.clone())
+        else {
+            return false;
+        };
+        records.retain(|_, record| record.grant.family != family);
+        true
+    }
+
+    /// Revoke every family issued to `member_id`; returns how many there were.
+    pub fn revoke_member(&self, member_id: &str) -> usize {
+        let mut records = self.records.lock().expect("refresh store poisoned");
+        let families: HashSet<String> = records
+            .values()
+            .filter(|record| record.grant.member_id == member_id)
+            .map(|record| record.grant.family.clone())
+            .collect();
+        records.retain(|_, record| !families.contains(&record.grant.family));
+        families.len()
+    }
+
+    fn insert(&self, grant: RefreshGrant, ttl_seconds: u64) -> String {
+        let token = format!("{REFRESH_TOKEN_PREFIX}{}", random_hex());
+        let now = now();
+        let mut records = self.records.lock().expect("refresh store poisoned");
+        records.retain(|_, record| record.expires_at > now);
+        records.insert(
+            digest(&token),
+            RefreshRecord {
+                grant,
+                expires_at: now + ttl_seconds as usize,
+                rotated: false,
+            },
+        );
+        token
+    }
+}
+
+fn digest(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn random_hex() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+pub(super) fn now() -> usize {
+    chrono::Utc::now().timestamp() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revocation_list_denies_until_expiry() {
+        let list = InMemoryRevocationList::default();
+        list.revoke("live", now() + 60);
+        list.revoke("lapsed", now() - 1);
+
+        assert!(list.is_revoked("live"));
+        assert!(!list.is_revoked("lapsed"));
+        assert!(!list.is_revoked("unknown"));
+    }
+
+    #[test]
+    fn refresh_token_rotates_and_detects_reuse() {
+        let store = RefreshTokenStore::default();
+        let first = store.issue("nexis:human:alice", "human", None, 60);
+
+        let (grant, second) = store.rotate(&first, 60).unwrap();
+        assert_eq!(grant.member_id, "nexis:human:alice");
+        assert_ne!(first, second);
+
+        assert!(matches!(
+            store.rotate(&first, 60),
+            Err(AuthError::TokenRevoked)
+        ));
+        // Reuse revoked the whole family, including the newest token.
+        assert!(matches!(
+            store.rotate(&second, 60),
+            Err(AuthError::InvalidToken)
+        ));
+    }
+
+    #[test]
+    fn revoked_refresh_token_cannot_rotate() {
+        let store = RefreshTokenStore::default();
+        let token = store.issue("nexis:human:alice", "human", None, 60);
+        store.revoke(&token);
+
+        assert!(matches!(
+            store.rotate(&token, 60),
+            Err(AuthError::InvalidToken)
+        ));
+    }
+}
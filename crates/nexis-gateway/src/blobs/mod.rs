@@ -0,0 +1,288 @@
+//! Blob storage for uploaded files.
+//!
+//! Uploads are streamed into a [`BlobStore`] under an opaque key. The gateway
+//! ships a local filesystem store and an S3-compatible store; which one is
+//! used is chosen by `[uploads] store` in the gateway config.
+//!
+//! Downloads go through short-lived signed URLs so that clients which cannot
+//! attach a bearer token (`<img>` tags, media players) can still fetch a file.
+//! The signature is a hex HMAC-SHA256 over the upload id and expiry time,
+//! keyed with the gateway's JWT secret.
+
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::{BlobStoreKind, UploadsConfig};
+
+mod s3;
+
+pub use s3::S3BlobStore;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Domain separator so download signatures can't be confused with other
+/// HMACs keyed by the same secret.
+const DOWNLOAD_SIGNATURE_CONTEXT: &[u8] = b"nexis-download-v1:";
+
+/// Chunked request body handed to [`BlobStore::put`].
+pub type BlobStream<'a> = Pin<Box<dyn Stream<Item = Result<Bytes, BlobError>> + Send + 'a>>;
+
+#[derive(Debug, Error)]
+pub enum BlobError {
+    #[error("blob exceeds the {limit} byte limit")]
+    TooLarge { limit: usize },
+    #[error("invalid blob key: {0}")]
+    InvalidKey(String),
+    #[error("blob I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("blob backend error: {0}")]
+    Backend(String),
+    #[error("upload stream error: {0}")]
+    Stream(String),
+}
+
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// Store `body` under `key`, failing with [`BlobError::TooLarge`] once
+    /// more than `limit` bytes arrive. Returns the stored size.
+    ///
+    /// Nothing is left behind under `key` when the upload fails.
+    async fn put(
+        &self,
+        key: &str,
+        content_type: &str,
+        body: BlobStream<'_>,
+        limit: usize,
+    ) -> Result<u64, BlobError>;
+
+    /// Read the blob stored under `key`, if any.
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, BlobError>;
+
+    /// Remove the blob stored under `key`. Missing keys are not an error.
+    async fn delete(&self, key: &str) -> Result<(), BlobError>;
+}
+
+/// Build the store selected by `config`.
+pub fn from_config(config: &UploadsConfig) -> Result<Box<dyn BlobStore>, BlobError> {
+    match config.store {
+        BlobStoreKind::Local => Ok(Box::new(LocalBlobStore::new(config.path.clone()))),
+        BlobStoreKind::S3 => Ok(Box::new(S3BlobStore::new(config.s3.clone())?)),
+    }
+}
+
+/// Keys are generated by the gateway; anything that could escape the store
+/// root is rejected outright.
+fn validate_key(key: &str) -> Result<(), BlobError> {
+    let valid = !key.is_empty()
+        && key.len() <= 128
+        && key
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-');
+    if valid {
+        Ok(())
+    } else {
+        Err(BlobError::InvalidKey(key.to_string()))
+    }
+}
+
+/// Read all of `body`, failing once it grows past `limit` bytes.
+async fn collect_limited(mut body: BlobStream<'_>, limit: usize) -> Result<Vec<u8>, BlobError> {
+    let mut buffer = Vec::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk?;
+        if buffer.len() + chunk.len() > limit {
+            return Err(BlobError::TooLarge { limit });
+        }
+        buffer.extend_from_slice(&chunk);
+    }
+    Ok(buffer)
+}
+
+/// Stores each blob as a file named after its key inside `root`.
+#[derive(Debug, Clone)]
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    async fn write(
+        &self,
+        path: &Path,
+        mut body: BlobStream<'_>,
+        limit: usize,
+    ) -> Result<u64, BlobError> {
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut written = 0usize;
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk?;
+            written += chunk.len();
+            if written > limit {
+                return Err(BlobError::TooLarge { limit });
+            }
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok(written as u64)
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn put(
+        &self,
+        key: &str,
+        _content_type: &str,
+        body: BlobStream<'_>,
+        limit: usize,
+    ) -> Result<u64, BlobError> {
+        validate_key(key)?;
+        tokio::fs::create_dir_all(&self.root).await?;
+        // Write to a side file so readers never see a partial blob.
+        let partial = self.root.join(format!("{key}.part"));
+        match self.write(&partial, body, limit).await {
+            Ok(size) => {
+                tokio::fs::rename(&partial, self.root.join(key)).await?;
+                Ok(size)
+            }
+            Err(err) => {
+                let _ = tokio::fs::remove_file(&partial).await;
+                Err(err)
+            }
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, BlobError> {
+        validate_key(key)?;
+        match tokio::fs::read(self.root.join(key)).await {
+            Ok(contents) => Ok(Some(Bytes::from(contents))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BlobError> {
+        validate_key(key)?;
+        match tokio::fs::remove_file(self.root.join(key)).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+fn download_mac(secret: &str, id: &str, expires: i64) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any length");
+    mac.update(DOWNLOAD_SIGNATURE_CONTEXT);
+    mac.update(id.as_bytes());
+    mac.update(b".");
+    mac.update(expires.to_string().as_bytes());
+    mac
+}
+
+/// Signature for a download URL of upload `id` valid until `expires`
+/// (unix seconds).
+pub fn sign_download(secret: &str, id: &str, expires: i64) -> String {
+    hex::encode(download_mac(secret, id, expires).finalize().into_bytes())
+}
+
+/// Check a signature produced by [`sign_download`] in constant time.
+///
+/// Expiry is the caller's concern; this only checks authenticity.
+pub fn verify_download(secret: &str, id: &str, expires: i64, signature: &str) -> bool {
+    let Ok(expected) = hex::decode(signature) else {
+        return false;
+    };
+    download_mac(secret, id, expires)
+        .verify_slice(&expected)
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(chunks: &[&'static [u8]]) -> BlobStream<'static> {
+        let chunks = chunks
+            .iter()
+            .map(|chunk| Ok(Bytes::from_static(chunk)))
+            .collect::<Vec<_>>();
+        Box::pin(futures::stream::iter(chunks))
+    }
+
+    #[tokio::test]
+    async fn local_store_round_trips_and_rejects_oversized_blobs() {
+        let root = std::env::temp_dir().join(format!("nexis-blobs-{}", uuid::Uuid::new_v4()));
+        let store = LocalBlobStore::new(&root);
+
+        let size = store
+            .put("upl_1", "text/plain", stream(&[b"hello ", b"world"]), 64)
+            .await
+            .unwrap();
+        assert_eq!(size, 11);
+        assert_eq!(
+            store.get("upl_1").await.unwrap().unwrap(),
+            Bytes::from_static(b"hello world")
+        );
+
+        let err = store
+            .put("upl_2", "text/plain", stream(&[b"0123", b"4567"]), 6)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BlobError::TooLarge { limit: 6 }));
+        assert!(store.get("upl_2").await.unwrap().is_none());
+        assert!(!root.join("upl_2.part").exists());
+
+        assert!(matches!(
+            store.get("../etc/passwd").await,
+            Err(BlobError::InvalidKey(_))
+        ));
+
+        store.delete("upl_1").await.unwrap();
+        store.delete("upl_1").await.unwrap();
+        assert!(store.get("upl_1").await.unwrap().is_none());
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn download_signatures_bind_id_and_expiry() {
+        let signature = sign_download("secret", "upl_1", 1_700_000_000);
+        assert!(verify_download(
+            "secret",
+            "upl_1",
+            1_700_000_000,
+            &signature
+        ));
+        assert!(!verify_download(
+            "secret",
+            "upl_2",
+            1_700_000_000,
+            &signature
+        ));
+        assert!(!verify_download(
+            "secret",
+            "upl_1",
+            1_700_000_001,
+            &signature
+        ));
+        assert!(!verify_download(
+            "other",
+            "upl_1",
+            1_700_000_000,
+            &signature
+        ));
+        assert!(!verify_download("secret", "upl_1", 1_700_000_000, "zz"));
+    }
+}
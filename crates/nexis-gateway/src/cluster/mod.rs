@@ -0,0 +1,180 @@
+//! Event fan-out across gateway instances.
+//!
+//! A single gateway delivers room events to its WebSocket clients through an
+//! in-process channel. When several instances serve the same rooms, an
+//! [`EventBus`] carries every event to the other instances as well; each
+//! event is wrapped in an [`Envelope`] naming the instance that published it,
+//! so instances can skip their own events when they come back. A
+//! [`ConnectionRegistry`] records which instance holds each member's
+//! connections.
+
+#[cfg(feature = "redis")]
+mod redis;
+mod registry;
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::config::ClusterConfig;
+
+#[cfg(feature = "redis")]
+pub use self::redis::{RedisConnectionRegistry, RedisEventBus};
+pub use registry::{
+    ConnectionLocation, ConnectionRegistry, DeliveryRoute, InMemoryConnectionRegistry,
+};
+
+/// Envelopes buffered for local subscribers before the slowest one lags.
+const BUS_CAPACITY: usize = 1_024;
+
+/// Error returned when an event cannot be handed to the bus.
+#[derive(Debug, Error)]
+pub enum ClusterError {
+    /// `[cluster]` names a bus this build cannot use or cannot parse.
+    #[error("invalid cluster config: {0}")]
+    Config(String),
+    /// Too many events are waiting to be published.
+    #[error("cluster event backlog is full")]
+    Backlogged,
+    /// The bus connection task has stopped.
+    #[error("cluster event bus is closed")]
+    Closed,
+    /// A Redis command failed.
+    #[cfg(feature = "redis")]
+    #[error("redis error: {0}")]
+    Redis(#[from] ::redis::RedisError),
+}
+
+/// Event as it travels between instances.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Instance that published the event.
+    pub origin: String,
+    /// The event itself, as delivered to WebSocket clients.
+    pub event: serde_json::Value,
+}
+
+/// Channel shared by every gateway instance of a deployment.
+pub trait EventBus: Send + Sync {
+    /// Send an encoded [`Envelope`] to every instance, this one included.
+    fn publish(&self, payload: String) -> Result<(), ClusterError>;
+    /// Envelopes published by any instance from now on.
+    fn subscribe(&self) -> broadcast::Receiver<String>;
+}
+
+/// [`EventBus`] within one process, for instances sharing a runtime.
+#[derive(Debug, Clone)]
+pub struct InProcessEventBus {
+    events: broadcast::Sender<String>,
+}
+
+impl Default for InProcessEventBus {
+    fn default() -> Self {
+        Self {
+            events: broadcast::channel(BUS_CAPACITY).0,
+        }
+    }
+}
+
+impl InProcessEventBus {
+    /// Create a bus with no subscribers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EventBus for InProcessEventBus {
+    fn publish(&self, payload: String) -> Result<(), ClusterError> {
+        // No receivers simply means no instance is listening yet.
+        let _ = self.events.send(payload);
+        Ok(())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.events.subscribe()
+    }
+}
+
+/// Id this instance stamps on the events it publishes.
+pub fn instance_id(config: &ClusterConfig) -> String {
+    config
+        .instance_id
+        .clone()
+        .unwrap_or_else(|| format!("gw_{}", Uuid::new_v4().simple()))
+}
+
+/// Open the bus selected by `[cluster]`; `None` keeps the gateway in
+/// single-node mode.
+///
+/// Connecting happens in the background, so this must be called from within
+/// a Tokio runtime.
+pub fn from_config(config: &ClusterConfig) -> Result<Option<Arc<dyn EventBus>>, ClusterError> {
+    let Some(url) = config.redis_url.as_deref() else {
+        return Ok(None);
+    };
+    #[cfg(feature = "redis")]
+    {
+        let bus = RedisEventBus::connect(url, &config.channel)?;
+        Ok(Some(Arc::new(bus)))
+    }
+    #[cfg(not(feature = "redis"))]
+    {
+        let _ = url;
+        Err(ClusterError::Config(
+            "cluster.redis_url is set but the gateway was built without the redis feature"
+                .to_string(),
+        ))
+    }
+}
+
+/// Open the connection registry selected by `[cluster]`: Redis when a URL
+/// is set and the `redis` feature is enabled, process memory otherwise.
+pub fn registry_from_config(config: &ClusterConfig) -> Arc<dyn ConnectionRegistry> {
+    #[cfg(feature = "redis")]
+    if let Some(url) = config.redis_url.as_deref() {
+        match RedisConnectionRegistry::new(url, &config.channel, config.registry_ttl()) {
+            Ok(registry) => return Arc::new(registry),
+            Err(err) => tracing::error!("Connection registry unavailable: {}", err),
+        }
+    }
+    #[cfg(not(feature = "redis"))]
+    let _ = config;
+    Arc::new(InMemoryConnectionRegistry::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn in_process_bus_delivers_to_every_subscriber() {
+        let bus = InProcessEventBus::new();
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+
+        let envelope = Envelope {
+            origin: "gw_a".to_string(),
+            event: serde_json::json!({ "type": "message", "roomId": "room_1" }),
+        };
+        bus.publish(serde_json::to_string(&envelope).unwrap())
+            .unwrap();
+
+        for receiver in [&mut first, &mut second] {
+            let payload = receiver.recv().await.unwrap();
+            assert_eq!(
+                serde_json::from_str::<Envelope>(&payload).unwrap(),
+                envelope
+            );
+        }
+    }
+
+    #[test]
+    fn single_node_mode_needs_no_bus() {
+        let config = ClusterConfig::default();
+        assert!(from_config(&config).unwrap().is_none());
+        assert!(instance_id(&config).starts_with("gw_"));
+    }
+}
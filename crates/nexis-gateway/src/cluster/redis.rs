@@ -0,0 +1,233 @@
+//! Redis pub/sub [`EventBus`] and hash-backed [`ConnectionRegistry`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use tokio::sync::{broadcast, mpsc, OnceCell};
+use uuid::Uuid;
+
+use super::{ClusterError, ConnectionLocation, ConnectionRegistry, EventBus, BUS_CAPACITY};
+
+/// Wait before reconnecting after the Redis connection drops.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// [`EventBus`] over a Redis pub/sub channel.
+///
+/// A background task owns the connections: it publishes queued envelopes and
+/// forwards everything received on the channel to local subscribers,
+/// reconnecting whenever Redis goes away. Envelopes published while Redis is
+/// unreachable are dropped.
+#[derive(Debug, Clone)]
+pub struct RedisEventBus {
+    outgoing: mpsc::Sender<String>,
+    incoming: broadcast::Sender<String>,
+}
+
+impl RedisEventBus {
+    /// Start relaying through `channel` on the Redis server at `url`, e.g.
+    /// `redis://127.0.0.1:6379/0`.
+    pub fn connect(url: &str, channel: &str) -> Result<Self, ClusterError> {
+        let client = redis::Client::open(url)
+            .map_err(|err| ClusterError::Config(format!("cluster.redis_url: {err}")))?;
+        let (outgoing, receiver) = mpsc::channel(BUS_CAPACITY);
+        let incoming = broadcast::channel(BUS_CAPACITY).0;
+        tokio::spawn(relay(
+            client,
+            channel.to_string(),
+            receiver,
+            incoming.clone(),
+        ));
+        Ok(Self { outgoing, incoming })
+    }
+}
+
+impl EventBus for RedisEventBus {
+    fn publish(&self, payload: String) -> Result<(), ClusterError> {
+        self.outgoing.try_send(payload).map_err(|err| match err {
+            mpsc::error::TrySendError::Full(_) => ClusterError::Backlogged,
+            mpsc::error::TrySendError::Closed(_) => ClusterError::Closed,
+        })
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.incoming.subscribe()
+    }
+}
+
+/// Keep a session open until every [`RedisEventBus`] handle is dropped.
+async fn relay(
+    client: redis::Client,
+    channel: String,
+    mut outgoing: mpsc::Receiver<String>,
+    incoming: broadcast::Sender<String>,
+) {
+    loop {
+        match session(&client, &channel, &mut outgoing, &incoming).await {
+            Ok(()) => return,
+            Err(err) => {
+                tracing::warn!(error = %err, "Redis event bus disconnected; reconnecting");
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
+
+async fn session(
+    client: &redis::Client,
+    channel: &str,
+    outgoing: &mut mpsc::Receiver<String>,
+    incoming: &broadcast::Sender<String>,
+) -> redis::RedisResult<()> {
+    let mut publisher = client.get_multiplexed_async_connection().await?;
+    let mut subscriber = client.get_async_pubsub().await?;
+    subscriber.subscribe(channel).await?;
+    let mut messages = subscriber.on_message();
+    tracing::info!(channel, "Subscribed to Redis event bus");
+
+    loop {
+        tokio::select! {
+            payload = outgoing.recv() => {
+                let Some(payload) = payload else {
+                    return Ok(());
+                };
+                publisher.publish::<_, _, ()>(channel, payload).await?;
+            }
+            message = messages.next() => {
+                let Some(message) = message else {
+                    return Err((redis::ErrorKind::IoError, "subscription closed").into());
+                };
+                match message.get_payload::<String>() {
+                    // No receivers simply means nobody is subscribed right now.
+                    Ok(payload) => {
+                        let _ = incoming.send(payload);
+                    }
+                    Err(err) => tracing::warn!(error = %err, "Dropped malformed bus message"),
+                }
+            }
+        }
+    }
+}
+
+/// [`ConnectionRegistry`] in Redis, shared by every gateway instance.
+///
+/// Each member's connections live in one hash, keyed by connection id.
+/// Instances also keep a liveness key alive through
+/// [`ConnectionRegistry::heartbeat`]; connections of an instance whose key
+/// expired are treated as gone and pruned on lookup.
+#[derive(Clone)]
+pub struct RedisConnectionRegistry {
+    client: redis::Client,
+    connection: Arc<OnceCell<ConnectionManager>>,
+    prefix: String,
+    ttl: Duration,
+}
+
+impl RedisConnectionRegistry {
+    /// Use the Redis server at `url`, naming keys `<prefix>:...`. An
+    /// instance counts as alive for `ttl` after its last heartbeat. The
+    /// connection is opened on first use.
+    pub fn new(url: &str, prefix: &str, ttl: Duration) -> Result<Self, ClusterError> {
+        let client = redis::Client::open(url)
+            .map_err(|err| ClusterError::Config(format!("cluster.redis_url: {err}")))?;
+        Ok(Self {
+            client,
+            connection: Arc::new(OnceCell::new()),
+            prefix: prefix.to_string(),
+            ttl,
+        })
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager, ClusterError> {
+        let connection = self
+            .connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await?;
+        Ok(connection.clone())
+    }
+
+    fn member_key(&self, member_id: &str) -> String {
+        format!("{}:connections:{}", self.prefix, member_id)
+    }
+
+    fn instance_key(&self, instance_id: &str) -> String {
+        format!("{}:instances:{}", self.prefix, instance_id)
+    }
+}
+
+#[async_trait]
+impl ConnectionRegistry for RedisConnectionRegistry {
+    async fn register(
+        &self,
+        member_id: &str,
+        location: &ConnectionLocation,
+    ) -> Result<(), ClusterError> {
+        let encoded =
+            serde_json::to_string(location).map_err(|err| ClusterError::Config(err.to_string()))?;
+        self.connection()
+            .await?
+            .hset::<_, _, _, ()>(
+                self.member_key(member_id),
+                location.connection_id.to_string(),
+                encoded,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn unregister(&self, member_id: &str, connection_id: Uuid) -> Result<(), ClusterError> {
+        self.connection()
+            .await?
+            .hdel::<_, _, ()>(self.member_key(member_id), connection_id.to_string())
+            .await?;
+        Ok(())
+    }
+
+    async fn locate(&self, member_id: &str) -> Result<Vec<ConnectionLocation>, ClusterError> {
+        let mut connection = self.connection().await?;
+        let key = self.member_key(member_id);
+        let stored: HashMap<String, String> = connection.hgetall(&key).await?;
+
+        let mut alive = HashMap::new();
+        let mut locations = Vec::new();
+        let mut stale = Vec::new();
+        for (field, encoded) in stored {
+            let Ok(location) = serde_json::from_str::<ConnectionLocation>(&encoded) else {
+                stale.push(field);
+                continue;
+            };
+            let live = match alive.get(&location.instance_id) {
+                Some(live) => *live,
+                None => {
+                    let live: bool = connection
+                        .exists(self.instance_key(&location.instance_id))
+                        .await?;
+                    alive.insert(location.instance_id.clone(), live);
+                    live
+                }
+            };
+            if live {
+                locations.push(location);
+            } else {
+                stale.push(field);
+            }
+        }
+        if !stale.is_empty() {
+            connection.hdel::<_, _, ()>(&key, stale).await?;
+        }
+        locations.sort_by_key(|location| location.connected_at);
+        Ok(locations)
+    }
+
+    async fn heartbeat(&self, instance_id: &str) -> Result<(), ClusterError> {
+        self.connection()
+            .await?
+            .set_ex::<_, _, ()>(self.instance_key(instance_id), 1, self.ttl.as_secs().max(1))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+//! Which gateway instance holds each member's WebSocket connections.
+//!
+//! Instances record the connections they accept in a [`ConnectionRegistry`]
+//! shared by the deployment, so a delivery meant for a few members can be
+//! sent to the instances holding them instead of to every instance.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::ClusterError;
+
+/// Where one WebSocket connection of a member lives.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionLocation {
+    /// Gateway instance holding the connection.
+    pub instance_id: String,
+    /// Connection id, unique across instances.
+    pub connection_id: Uuid,
+    /// When the connection was accepted.
+    pub connected_at: DateTime<Utc>,
+}
+
+/// Members grouped by the instances their connections live on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryRoute {
+    /// Member ids to deliver to, keyed by instance id. A member connected to
+    /// several instances is listed under each of them.
+    pub instances: BTreeMap<String, Vec<String>>,
+    /// Members with no live connection.
+    pub offline: Vec<String>,
+}
+
+/// Deployment-wide record of live WebSocket connections.
+#[async_trait]
+pub trait ConnectionRegistry: Send + Sync {
+    /// Record a connection of `member_id`.
+    async fn register(
+        &self,
+        member_id: &str,
+        location: &ConnectionLocation,
+    ) -> Result<(), ClusterError>;
+    /// Forget a connection once it closes.
+    async fn unregister(&self, member_id: &str, connection_id: Uuid) -> Result<(), ClusterError>;
+    /// Live connections of `member_id`, oldest first.
+    async fn locate(&self, member_id: &str) -> Result<Vec<ConnectionLocation>, ClusterError>;
+    /// Mark `instance_id` as alive. Registries shared between instances drop
+    /// the connections of instances that stop calling this.
+    async fn heartbeat(&self, instance_id: &str) -> Result<(), ClusterError>;
+
+    /// Group `member_ids` by the instances holding their connections.
+    async fn route(&self, member_ids: &[String]) -> Result<DeliveryRoute, ClusterError> {
+        let mut route = DeliveryRoute::default();
+        for member_id in member_ids {
+            let locations = self.locate(member_id).await?;
+            if locations.is_empty() {
+                route.offline.push(member_id.clone());
+                continue;
+            }
+            for location in locations {
+                let members = route.instances.entry(location.instance_id).or_default();
+                if !members.contains(member_id) {
+                    members.push(member_id.clone());
+                }
+            }
+        }
+        Ok(route)
+    }
+}
+
+/// Process-local [`ConnectionRegistry`] for single-node deployments.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryConnectionRegistry {
+    connections: Arc<RwLock<HashMap<String, Vec<ConnectionLocation>>>>,
+}
+
+impl InMemoryConnectionRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ConnectionRegistry for InMemoryConnectionRegistry {
+    async fn register(
+        &self,
+        member_id: &str,
+        location: &ConnectionLocation,
+    ) -> Result<(), ClusterError> {
+        self.connections
+            .write()
+            .await
+            .entry(member_id.to_string())
+            .or_default()
+            .push(location.clone());
+        Ok(())
+    }
+
+    async fn unregister(&self, member_id: &str, connection_id: Uuid) -> Result<(), ClusterError> {
+        let mut connections = self.connections.write().await;
+        if let Some(locations) = connections.get_mut(member_id) {
+            locations.retain(|location| location.connection_id != connection_id);
+            if locations.is_empty() {
+                connections.remove(member_id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn locate(&self, member_id: &str) -> Result<Vec<ConnectionLocation>, ClusterError> {
+        Ok(self
+            .connections
+            .read()
+            .await
+            .get(member_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn heartbeat(&self, _instance_id: &str) -> Result<(), ClusterError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(instance_id: &str) -> ConnectionLocation {
+        ConnectionLocation {
+            instance_id: instance_id.to_string(),
+            connection_id: Uuid::new_v4(),
+            connected_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn route_groups_members_by_instance() {
+        let registry = InMemoryConnectionRegistry::new();
+        let alice_phone = location("gw_a");
+        registry.register("alice", &alice_phone).await.unwrap();
+        registry.register("alice", &location("gw_b")).await.unwrap();
+        registry.register("bob", &location("gw_b")).await.unwrap();
+
+        let members = ["alice", "bob", "carol"].map(str::to_string);
+        let route = registry.route(&members).await.unwrap();
+        assert_eq!(route.instances["gw_a"], ["alice"]);
+        assert_eq!(route.instances["gw_b"], ["alice", "bob"]);
+        assert_eq!(route.offline, ["carol"]);
+
+        registry
+            .unregister("alice", alice_phone.connection_id)
+            .await
+            .unwrap();
+        let route = registry.route(&members[..1]).await.unwrap();
+        assert_eq!(route.instances.keys().collect::<Vec<_>>(), ["gw_b"]);
+    }
+}
@@ -9,6 +9,7 @@ use axum::{
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::auth::AuthenticatedUser;
@@ -21,105 +22,106 @@ const MAX_RATE_LIMIT_SUBJECT_LEN: usize = 64;
 const API_VERSION_HEADER: &str = "x-api-version";
 const SUPPORTED_API_VERSION: &str = "1";
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct CreateMeetingRoomRequest {
     name: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct CreateMeetingRoomResponse {
     room_id: String,
     name: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct MeetingParticipantRequest {
     user_id: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct MeetingParticipantResponse {
     room_id: String,
     user_id: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct CreateDocumentRequest {
     title: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct CreateDocumentResponse {
     document_id: String,
     title: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct SyncDocumentRequest {
     content: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct DocumentContentResponse {
     document_id: String,
     content: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct CreateTaskRequest {
     title: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct CreateTaskResponse {
     task_id: String,
     title: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct AssignTaskRequest {
     assignee_id: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct TaskAssignmentResponse {
     task_id: String,
     assignee_id: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct CompleteTaskResponse {
     task_id: String,
     status: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct CreateCalendarEventRequest {
     title: String,
     starts_at: DateTime<Utc>,
     ends_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct CreateCalendarEventResponse {
     event_id: String,
     title: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct ConflictCheckRequest {
     starts_at: DateTime<Utc>,
     ends_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct ConflictCheckResponse {
     has_conflicts: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct CollaborationErrorResponse {
     error: String,
+    #[schema(value_type = String)]
     code: &'static str,
 }
 
@@ -250,6 +252,7 @@ fn bad_request_response(message: impl Into<String>) -> Response {
     CollaborationError::BadRequest(message.into()).into_response()
 }
 
+#[allow(clippy::result_large_err)]
 fn validate_required_text(field: &str, value: &str, max_len: usize) -> Result<String, Response> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
@@ -286,10 +289,12 @@ fn validate_identifier(field: &str, value: &str, max_len: usize) -> Result<Strin
     Ok(trimmed.to_string())
 }
 
+#[allow(clippy::result_large_err)]
 fn validate_path_id(field: &str, value: &str) -> Result<String, Response> {
     validate_identifier(field, value, MAX_IDENTIFIER_LEN).map_err(bad_request_response)
 }
 
+#[allow(clippy::result_large_err)]
 fn validate_time_window(starts_at: DateTime<Utc>, ends_at: DateTime<Utc>) -> Result<(), Response> {
     if starts_at >= ends_at {
         return Err(bad_request_response(
@@ -343,6 +348,19 @@ where
         )
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/collaboration/meetings/rooms",
+    tag = "collaboration",
+    summary = "Create a meeting room",
+    params(("x-api-version" = String, Header, description = "Must be `1`")),
+    request_body = CreateMeetingRoomRequest,
+    responses(
+        (status = 201, description = "Meeting room created", body = CreateMeetingRoomResponse),
+        (status = 400, description = "Invalid request or missing `X-API-Version` header", body = CollaborationErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = CollaborationErrorResponse),
+    )
+)]
 async fn create_meeting_room(
     _ctx: CollaborationRequestContext,
     Json(payload): Json<CreateMeetingRoomRequest>,
@@ -361,6 +379,22 @@ async fn create_meeting_room(
     (StatusCode::CREATED, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/collaboration/meetings/rooms/{room_id}/join",
+    tag = "collaboration",
+    summary = "Join a meeting room",
+    params(
+        ("x-api-version" = String, Header, description = "Must be `1`"),
+        ("room_id" = String, Path, description = "Meeting room id"),
+    ),
+    request_body = MeetingParticipantRequest,
+    responses(
+        (status = 200, description = "Participant joined", body = MeetingParticipantResponse),
+        (status = 400, description = "Invalid request or missing `X-API-Version` header", body = CollaborationErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = CollaborationErrorResponse),
+    )
+)]
 async fn join_meeting_room(
     _ctx: CollaborationRequestContext,
     Path(room_id): Path<String>,
@@ -381,6 +415,22 @@ async fn join_meeting_room(
     (StatusCode::OK, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/collaboration/meetings/rooms/{room_id}/leave",
+    tag = "collaboration",
+    summary = "Leave a meeting room",
+    params(
+        ("x-api-version" = String, Header, description = "Must be `1`"),
+        ("room_id" = String, Path, description = "Meeting room id"),
+    ),
+    request_body = MeetingParticipantRequest,
+    responses(
+        (status = 200, description = "Participant left", body = MeetingParticipantResponse),
+        (status = 400, description = "Invalid request or missing `X-API-Version` header", body = CollaborationErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = CollaborationErrorResponse),
+    )
+)]
 async fn leave_meeting_room(
     _ctx: CollaborationRequestContext,
     Path(room_id): Path<String>,
@@ -401,6 +451,19 @@ async fn leave_meeting_room(
     (StatusCode::OK, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/collaboration/documents",
+    tag = "collaboration",
+    summary = "Create a shared document",
+    params(("x-api-version" = String, Header, description = "Must be `1`")),
+    request_body = CreateDocumentRequest,
+    responses(
+        (status = 201, description = "Document created", body = CreateDocumentResponse),
+        (status = 400, description = "Invalid request or missing `X-API-Version` header", body = CollaborationErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = CollaborationErrorResponse),
+    )
+)]
 async fn create_document(
     _ctx: CollaborationRequestContext,
     Json(payload): Json<CreateDocumentRequest>,
@@ -419,6 +482,22 @@ async fn create_document(
     (StatusCode::CREATED, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/collaboration/documents/{document_id}/sync",
+    tag = "collaboration",
+    summary = "Replace a document's content",
+    params(
+        ("x-api-version" = String, Header, description = "Must be `1`"),
+        ("document_id" = String, Path, description = "Document id"),
+    ),
+    request_body = SyncDocumentRequest,
+    responses(
+        (status = 200, description = "Document synced", body = DocumentContentResponse),
+        (status = 400, description = "Invalid request or missing `X-API-Version` header", body = CollaborationErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = CollaborationErrorResponse),
+    )
+)]
 async fn sync_document(
     _ctx: CollaborationRequestContext,
     Path(document_id): Path<String>,
@@ -442,6 +521,21 @@ async fn sync_document(
     (StatusCode::OK, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/collaboration/documents/{document_id}/content",
+    tag = "collaboration",
+    summary = "Get a document's content",
+    params(
+        ("x-api-version" = String, Header, description = "Must be `1`"),
+        ("document_id" = String, Path, description = "Document id"),
+    ),
+    responses(
+        (status = 200, description = "The document's content", body = DocumentContentResponse),
+        (status = 400, description = "Invalid request or missing `X-API-Version` header", body = CollaborationErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = CollaborationErrorResponse),
+    )
+)]
 async fn get_document_content(
     _ctx: CollaborationRequestContext,
     Path(document_id): Path<String>,
@@ -460,6 +554,19 @@ async fn get_document_content(
     (StatusCode::OK, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/collaboration/tasks",
+    tag = "collaboration",
+    summary = "Create a collaboration task",
+    params(("x-api-version" = String, Header, description = "Must be `1`")),
+    request_body = CreateTaskRequest,
+    responses(
+        (status = 201, description = "Task created", body = CreateTaskResponse),
+        (status = 400, description = "Invalid request or missing `X-API-Version` header", body = CollaborationErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = CollaborationErrorResponse),
+    )
+)]
 async fn create_task(
     _ctx: CollaborationRequestContext,
     Json(payload): Json<CreateTaskRequest>,
@@ -478,6 +585,22 @@ async fn create_task(
     (StatusCode::CREATED, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/collaboration/tasks/{task_id}/assign",
+    tag = "collaboration",
+    summary = "Assign a collaboration task",
+    params(
+        ("x-api-version" = String, Header, description = "Must be `1`"),
+        ("task_id" = String, Path, description = "Task id"),
+    ),
+    request_body = AssignTaskRequest,
+    responses(
+        (status = 200, description = "Task assigned", body = TaskAssignmentResponse),
+        (status = 400, description = "Invalid request or missing `X-API-Version` header", body = CollaborationErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = CollaborationErrorResponse),
+    )
+)]
 async fn assign_task(
     _ctx: CollaborationRequestContext,
     Path(task_id): Path<String>,
@@ -502,6 +625,21 @@ async fn assign_task(
     (StatusCode::OK, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/collaboration/tasks/{task_id}/complete",
+    tag = "collaboration",
+    summary = "Complete a collaboration task",
+    params(
+        ("x-api-version" = String, Header, description = "Must be `1`"),
+        ("task_id" = String, Path, description = "Task id"),
+    ),
+    responses(
+        (status = 200, description = "Task completed", body = CompleteTaskResponse),
+        (status = 400, description = "Invalid request or missing `X-API-Version` header", body = CollaborationErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = CollaborationErrorResponse),
+    )
+)]
 async fn complete_task(_ctx: CollaborationRequestContext, Path(task_id): Path<String>) -> Response {
     let _domain_type_marker: Option<nexis_task::TaskStatus> = None;
     let task_id = match validate_path_id("task_id", &task_id) {
@@ -517,6 +655,19 @@ async fn complete_task(_ctx: CollaborationRequestContext, Path(task_id): Path<St
     (StatusCode::OK, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/collaboration/calendar/events",
+    tag = "collaboration",
+    summary = "Create a calendar event",
+    params(("x-api-version" = String, Header, description = "Must be `1`")),
+    request_body = CreateCalendarEventRequest,
+    responses(
+        (status = 201, description = "Event created", body = CreateCalendarEventResponse),
+        (status = 400, description = "Invalid request or missing `X-API-Version` header", body = CollaborationErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = CollaborationErrorResponse),
+    )
+)]
 async fn create_calendar_event(
     _ctx: CollaborationRequestContext,
     Json(payload): Json<CreateCalendarEventRequest>,
@@ -541,6 +692,19 @@ async fn create_calendar_event(
     (StatusCode::CREATED, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/collaboration/calendar/conflicts",
+    tag = "collaboration",
+    summary = "Check a time range for calendar conflicts",
+    params(("x-api-version" = String, Header, description = "Must be `1`")),
+    request_body = ConflictCheckRequest,
+    responses(
+        (status = 200, description = "Whether the range conflicts", body = ConflictCheckResponse),
+        (status = 400, description = "Invalid request or missing `X-API-Version` header", body = CollaborationErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = CollaborationErrorResponse),
+    )
+)]
 async fn check_calendar_conflicts(
     _ctx: CollaborationRequestContext,
     Json(payload): Json<ConflictCheckRequest>,
@@ -0,0 +1,2169 @@
+//! Gateway configuration.
+//!
+//! [`NexisConfig`] is read from a TOML file, then environment variables are
+//! applied on top, then the result is validated once at startup. Every
+//! section and field is optional in the file; missing values fall back to
+//! the same defaults the gateway used before the file existed.
+//!
+//! ```toml
+//! [server]
+//! bind_addr = "0.0.0.0:8080"
+//!
+//! [cors]
+//! allow_origins = ["https://app.example.com"]
+//!
+//! [auth]
+//! jwt_secret = "change-me"
+//! jwt_issuer = "nexis"
+//!
+//! [auth.member_credentials]
+//! "nexis:human:alice@example.com" = "<argon2 PHC hash of alice's secret>"
+//!
+//! [providers]
+//! default = "openai"
+//! openai = { api_key = "sk-..." }
+//!
+//! [audit]
+//! sink = "file"
+//! path = "/var/log/nexis/audit.jsonl"
+//!
+//! [uploads]
+//! store = "local"
+//! path = "/var/lib/nexis/uploads"
+//!
+//! [moderation]
+//! provider = "keywords"
+//! action = "reject"
+//! rules = [{ category = "spam", pattern = "(?i)buy now" }]
+//!
+//! [costs]
+//! monthly_budget_usd = 20.0
+//! room_daily_max_calls = 200
+//! prices = { "gpt-4o" = { input_per_million = 2.5, output_per_million = 10.0 } }
+//!
+//! [scheduler]
+//! path = "/var/lib/nexis/schedules.json"
+//! tick_secs = 30
+//!
+//! [cluster]
+//! redis_url = "redis://redis:6379/0"
+//!
+//! [websocket]
+//! replay_limit = 500
+//! ping_interval_secs = 30
+//! ```
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use argon2::password_hash::PasswordHash;
+use argon2::{Argon2, PasswordVerifier};
+use axum::http::HeaderValue;
+use nexis_runtime::{CostTracker, PriceTable, RoomBudgets, RoomLimits};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::auth::JwtConfig;
+
+/// Environment variable naming the config file.
+pub const CONFIG_PATH_ENV: &str = "NEXIS_CONFIG";
+/// File loaded from the working directory when [`CONFIG_PATH_ENV`] is unset.
+pub const DEFAULT_CONFIG_PATH: &str = "nexis.toml";
+/// Development-only JWT secret used when none is configured.
+pub const DEV_JWT_SECRET: &str = "default_secret";
+/// argon2 hash with the default parameters that matches no secret, checked
+/// in place of a member's hash when the member has none.
+const UNKNOWN_MEMBER_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$3MCAbRyQ/QwMNhsHjOU3bw$i0yJKTFIvvvO99/6U11EmmGG8wM8FkWqDQ59XJCb6cg";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse config file {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: Box<toml::de::Error>,
+    },
+    #[error("invalid value for {name}: `{value}`")]
+    InvalidEnv { name: &'static str, value: String },
+    #[error("invalid configuration: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NexisConfig {
+    pub server: ServerConfig,
+    pub cors: CorsConfig,
+    pub auth: AuthConfig,
+    pub database: DatabaseConfig,
+    pub vector: VectorConfig,
+    pub providers: ProvidersConfig,
+    pub rate_limits: RateLimitConfig,
+    pub audit: AuditConfig,
+    pub generation_log: GenerationLogConfig,
+    pub tenants: TenantsConfig,
+    pub oidc: OidcConfig,
+    pub uploads: UploadsConfig,
+    pub costs: CostsConfig,
+    pub moderation: ModerationConfig,
+    pub spam: SpamConfig,
+    pub scheduler: SchedulerConfig,
+    pub cluster: ClusterConfig,
+    pub websocket: WebSocketConfig,
+    pub notifications: NotificationsConfig,
+    pub email: EmailConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_addr: SocketAddr,
+    /// How long shutdown waits for connections and queues to drain.
+    pub drain_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            drain_timeout_secs: 30,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_secs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Allowed origins; `"*"` allows any origin.
+    pub allow_origins: Vec<String>,
+    pub allow_credentials: bool,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allow_origins: vec![
+                "http://localhost:5173".to_string(),
+                "http://127.0.0.1:5173".to_string(),
+            ],
+            allow_credentials: true,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub jwt_secret: String,
+    pub jwt_issuer: String,
+    pub jwt_audience: String,
+    pub token_expiry_secs: u64,
+    /// Lifetime of refresh tokens issued alongside access tokens.
+    pub refresh_token_expiry_secs: u64,
+    /// Member ids allowed to call admin endpoints such as `GET /v1/audit`.
+    pub admin_members: Vec<String>,
+    /// Members allowed to log in with `POST /v1/auth/login`, mapped to the
+    /// argon2 PHC hash of their secret.
+    pub member_credentials: BTreeMap<String, String>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: DEV_JWT_SECRET.to_string(),
+            jwt_issuer: "nexis".to_string(),
+            jwt_audience: "nexis".to_string(),
+            token_expiry_secs: 3600,
+            refresh_token_expiry_secs: 30 * 24 * 3600,
+            admin_members: Vec::new(),
+            member_credentials: BTreeMap::new(),
+        }
+    }
+}
+
+impl AuthConfig {
+    pub fn uses_dev_secret(&self) -> bool {
+        self.jwt_secret == DEV_JWT_SECRET
+    }
+
+    pub fn is_admin(&self, member_id: &str) -> bool {
+        self.admin_members.iter().any(|admin| admin == member_id)
+    }
+
+    /// Whether `secret` is the login secret configured for `member_id`.
+    ///
+    /// Unknown members are checked against a stand-in hash, so the answer
+    /// takes as long whether or not the member exists.
+    pub fn verify_member_secret(&self, member_id: &str, secret: &str) -> bool {
+        let stored = self
+            .member_credentials
+            .get(member_id)
+            .and_then(|hash| PasswordHash::new(hash).ok());
+        let unknown = PasswordHash::new(UNKNOWN_MEMBER_HASH).expect("stand-in hash is valid");
+        let verified = Argon2::default()
+            .verify_password(secret.as_bytes(), stored.as_ref().unwrap_or(&unknown))
+            .is_ok();
+        stored.is_some() && verified
+    }
+
+    pub fn jwt_config(&self) -> JwtConfig {
+        let mut config = JwtConfig::new(
+            &self.jwt_secret,
+            self.jwt_issuer.clone(),
+            self.jwt_audience.clone(),
+        );
+        config.expiry_seconds = self.token_expiry_secs;
+        config.refresh_expiry_seconds = self.refresh_token_expiry_secs;
+        config
+    }
+}
+
+impl std::fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthConfig")
+            .field("jwt_secret", &"<redacted>")
+            .field("jwt_issuer", &self.jwt_issuer)
+            .field("jwt_audience", &self.jwt_audience)
+            .field("token_expiry_secs", &self.token_expiry_secs)
+            .field("refresh_token_expiry_secs", &self.refresh_token_expiry_secs)
+            .field("admin_members", &self.admin_members)
+            .field(
+                "member_credentials",
+                &self.member_credentials.keys().collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// Postgres connection URL; rooms stay in memory when unset.
+    pub url: Option<String>,
+    pub max_connections: u32,
+    /// Newest messages of each room loaded back into memory at startup.
+    pub restore_messages: usize,
+    /// Apply pending migrations at startup; when off, run
+    /// `nexis-gateway migrate` before starting the gateway.
+    pub auto_migrate: bool,
+    /// Record every stored message in an index outbox for an indexing relay
+    /// to drain.
+    pub index_outbox: bool,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            max_connections: 10,
+            restore_messages: 500,
+            auto_migrate: true,
+            index_outbox: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VectorBackend {
+    #[default]
+    Memory,
+    Qdrant,
+}
+
+impl FromStr for VectorBackend {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "memory" => Ok(Self::Memory),
+            "qdrant" => Ok(Self::Qdrant),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How indexed messages are spread over vector collections.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CollectionRouting {
+    /// One collection for everything.
+    #[default]
+    Single,
+    /// One collection per tenant.
+    PerTenant,
+    /// One collection per room, dropped with the room.
+    PerRoom,
+}
+
+impl FromStr for CollectionRouting {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "single" => Ok(Self::Single),
+            "per-tenant" => Ok(Self::PerTenant),
+            "per-room" => Ok(Self::PerRoom),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VectorConfig {
+    pub backend: VectorBackend,
+    pub url: String,
+    /// Collection name, or the prefix of the routed collections.
+    pub collection: String,
+    pub dimension: usize,
+    pub routing: CollectionRouting,
+}
+
+impl Default for VectorConfig {
+    fn default() -> Self {
+        Self {
+            backend: VectorBackend::Memory,
+            url: "http://localhost:6334".to_string(),
+            collection: "nexis_vectors".to_string(),
+            dimension: 1536,
+            routing: CollectionRouting::Single,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    OpenAI,
+    Anthropic,
+    /// A local Ollama server's OpenAI-compatible API.
+    Ollama,
+    /// A vLLM server's OpenAI-compatible API.
+    Vllm,
+}
+
+impl ProviderKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::OpenAI => "openai",
+            Self::Anthropic => "anthropic",
+            Self::Ollama => "ollama",
+            Self::Vllm => "vllm",
+        }
+    }
+
+    /// Whether the provider can be used without an API key.
+    pub fn is_local(self) -> bool {
+        matches!(self, Self::Ollama | Self::Vllm)
+    }
+
+    pub fn default_base_url(self) -> &'static str {
+        match self {
+            Self::OpenAI => "https://api.openai.com/v1",
+            Self::Anthropic => "https://api.anthropic.com/v1",
+            Self::Ollama => "http://localhost:11434/v1",
+            Self::Vllm => "http://localhost:8000/v1",
+        }
+    }
+
+    pub fn default_model(self) -> &'static str {
+        match self {
+            Self::OpenAI => "gpt-4o-mini",
+            Self::Anthropic => "claude-3-5-sonnet-20241022",
+            Self::Ollama => "llama3.1",
+            // Empty: whichever model the server lists first.
+            Self::Vllm => "",
+        }
+    }
+}
+
+impl FromStr for ProviderKind {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "openai" => Ok(Self::OpenAI),
+            "anthropic" => Ok(Self::Anthropic),
+            "ollama" => Ok(Self::Ollama),
+            "vllm" => Ok(Self::Vllm),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProviderConfig {
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+}
+
+impl std::fmt::Debug for ProviderConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderConfig")
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .field("base_url", &self.base_url)
+            .field("model", &self.model)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProvidersConfig {
+    /// Provider used for room AI; AI endpoints are disabled when unset.
+    pub default: Option<ProviderKind>,
+    pub openai: ProviderConfig,
+    pub anthropic: ProviderConfig,
+    pub ollama: ProviderConfig,
+    pub vllm: ProviderConfig,
+    /// Models AI requests may pick with `model`; any model when empty.
+    pub allowed_models: Vec<String>,
+    /// Replaces `allowed_models` for a tenant's rooms.
+    pub tenant_models: BTreeMap<String, Vec<String>>,
+}
+
+impl ProvidersConfig {
+    pub fn get(&self, kind: ProviderKind) -> &ProviderConfig {
+        match kind {
+            ProviderKind::OpenAI => &self.openai,
+            ProviderKind::Anthropic => &self.anthropic,
+            ProviderKind::Ollama => &self.ollama,
+            ProviderKind::Vllm => &self.vllm,
+        }
+    }
+
+    fn get_mut(&mut self, kind: ProviderKind) -> &mut ProviderConfig {
+        match kind {
+            ProviderKind::OpenAI => &mut self.openai,
+            ProviderKind::Anthropic => &mut self.anthropic,
+            ProviderKind::Ollama => &mut self.ollama,
+            ProviderKind::Vllm => &mut self.vllm,
+        }
+    }
+
+    /// Models AI requests in `tenant`'s rooms may pick; any model when empty.
+    pub fn allowed_models(&self, tenant: Option<&str>) -> &[String] {
+        tenant
+            .and_then(|tenant| self.tenant_models.get(tenant))
+            .unwrap_or(&self.allowed_models)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Maximum number of write requests processed concurrently.
+    pub max_concurrent_writes: usize,
+    /// Maximum message text size in bytes.
+    pub max_message_bytes: usize,
+    /// Maximum size of a message's `metadata` object, in bytes of JSON.
+    pub max_metadata_bytes: usize,
+    /// Message text longer than this many bytes is stored as a `text/plain`
+    /// attachment and replaced by a preview; 0 keeps all text inline.
+    pub paste_attachment_bytes: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_writes: 2_048,
+            max_message_bytes: 32 * 1024,
+            max_metadata_bytes: 4 * 1024,
+            paste_attachment_bytes: 8 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditSinkKind {
+    #[default]
+    Tracing,
+    File,
+    Database,
+}
+
+impl FromStr for AuditSinkKind {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "tracing" => Ok(Self::Tracing),
+            "file" => Ok(Self::File),
+            "database" => Ok(Self::Database),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    pub sink: AuditSinkKind,
+    /// JSONL file written by the `file` sink.
+    pub path: PathBuf,
+    /// Number of recent events kept in memory for `GET /v1/audit`.
+    pub retained: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            sink: AuditSinkKind::Tracing,
+            path: PathBuf::from("nexis-audit.jsonl"),
+            retained: 10_000,
+        }
+    }
+}
+
+/// Logging AI prompts and responses for debugging.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenerationLogConfig {
+    /// Record every generation; off by default since prompts carry room
+    /// history.
+    pub enabled: bool,
+    /// Number of recent generations kept in memory for `GET /v1/generations`.
+    pub retained: usize,
+    /// Regular expressions whose matches are replaced with `[REDACTED]` in
+    /// logged prompts and responses.
+    pub redact_patterns: Vec<String>,
+}
+
+impl Default for GenerationLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retained: 1_000,
+            redact_patterns: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TenantStoreKind {
+    #[default]
+    Memory,
+    Database,
+}
+
+impl FromStr for TenantStoreKind {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "memory" => Ok(Self::Memory),
+            "database" => Ok(Self::Database),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Tenant registry used by the `multi-tenant` build.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantsConfig {
+    pub store: TenantStoreKind,
+}
+
+/// ID-token claim that becomes the `nexis:human:*` identifier.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OidcMemberClaim {
+    /// Verified `email` claim.
+    #[default]
+    Email,
+    /// Issuer-scoped `sub` claim.
+    Subject,
+}
+
+impl FromStr for OidcMemberClaim {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "email" => Ok(Self::Email),
+            "subject" | "sub" => Ok(Self::Subject),
+            _ => Err(()),
+        }
+    }
+}
+
+/// OpenID Connect login used by the `oidc` build; disabled while
+/// `issuer_url` is unset.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OidcConfig {
+    pub issuer_url: Option<String>,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    /// Callback URL registered with the issuer, ending in `/v1/auth/oidc/callback`.
+    pub redirect_url: String,
+    pub scopes: Vec<String>,
+    pub member_claim: OidcMemberClaim,
+}
+
+impl Default for OidcConfig {
+    fn default() -> Self {
+        Self {
+            issuer_url: None,
+            client_id: String::new(),
+            client_secret: None,
+            redirect_url: String::new(),
+            scopes: vec![
+                "openid".to_string(),
+                "email".to_string(),
+                "profile".to_string(),
+            ],
+            member_claim: OidcMemberClaim::Email,
+        }
+    }
+}
+
+impl std::fmt::Debug for OidcConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OidcConfig")
+            .field("issuer_url", &self.issuer_url)
+            .field("client_id", &self.client_id)
+            .field(
+                "client_secret",
+                &self.client_secret.as_ref().map(|_| "<redacted>"),
+            )
+            .field("redirect_url", &self.redirect_url)
+            .field("scopes", &self.scopes)
+            .field("member_claim", &self.member_claim)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobStoreKind {
+    #[default]
+    Local,
+    S3,
+}
+
+impl FromStr for BlobStoreKind {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "s3" => Ok(Self::S3),
+            _ => Err(()),
+        }
+    }
+}
+
+/// S3-compatible bucket used by the `s3` blob store.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct S3Config {
+    /// Service endpoint, e.g. `https://s3.us-east-1.amazonaws.com` or a MinIO URL.
+    pub endpoint: Option<String>,
+    pub bucket: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: Option<String>,
+    /// Address objects as `endpoint/bucket/key` instead of `bucket.endpoint/key`.
+    pub path_style: bool,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            bucket: String::new(),
+            region: "us-east-1".to_string(),
+            access_key_id: String::new(),
+            secret_access_key: None,
+            path_style: true,
+        }
+    }
+}
+
+impl std::fmt::Debug for S3Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("S3Config")
+            .field("endpoint", &self.endpoint)
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("access_key_id", &self.access_key_id)
+            .field(
+                "secret_access_key",
+                &self.secret_access_key.as_ref().map(|_| "<redacted>"),
+            )
+            .field("path_style", &self.path_style)
+            .finish()
+    }
+}
+
+/// File uploads and the blob store holding them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UploadsConfig {
+    pub store: BlobStoreKind,
+    /// Directory written by the `local` store.
+    pub path: PathBuf,
+    /// Largest accepted file, in bytes.
+    pub max_bytes: usize,
+    /// Accepted MIME types; `type/*` accepts a whole family.
+    pub allowed_mime_types: Vec<String>,
+    /// Lifetime of signed download URLs.
+    pub url_ttl_secs: u64,
+    pub s3: S3Config,
+}
+
+impl Default for UploadsConfig {
+    fn default() -> Self {
+        Self {
+            store: BlobStoreKind::Local,
+            path: PathBuf::from("nexis-uploads"),
+            max_bytes: 25 * 1024 * 1024,
+            allowed_mime_types: [
+                "image/*",
+                "audio/*",
+                "video/*",
+                "text/plain",
+                "text/markdown",
+                "text/csv",
+                "application/pdf",
+                "application/json",
+                "application/zip",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            url_ttl_secs: 15 * 60,
+            s3: S3Config::default(),
+        }
+    }
+}
+
+impl UploadsConfig {
+    /// Whether `mime_type` is on the allow list.
+    pub fn allows_mime_type(&self, mime_type: &str) -> bool {
+        let mime_type = mime_type.trim().to_ascii_lowercase();
+        self.allowed_mime_types
+            .iter()
+            .any(|allowed| match allowed.strip_suffix("/*") {
+                Some(family) => mime_type
+                    .strip_prefix(family)
+                    .is_some_and(|rest| rest.starts_with('/') && rest.len() > 1),
+                None => *allowed == mime_type,
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationProviderKind {
+    /// Messages are not checked.
+    #[default]
+    None,
+    /// Local regular expressions from `moderation.rules`.
+    Keywords,
+    /// OpenAI's moderation endpoint, using the OpenAI provider settings.
+    #[serde(rename = "openai")]
+    OpenAI,
+}
+
+impl FromStr for ModerationProviderKind {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "keywords" => Ok(Self::Keywords),
+            "openai" => Ok(Self::OpenAI),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What happens to a message that trips a moderated category.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationAction {
+    /// Deliver the message unchanged.
+    Allow,
+    /// Deliver the message with the triggered categories attached.
+    #[default]
+    Flag,
+    /// Refuse the message.
+    Reject,
+}
+
+impl ModerationAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Flag => "flag",
+            Self::Reject => "reject",
+        }
+    }
+}
+
+impl FromStr for ModerationAction {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "allow" => Ok(Self::Allow),
+            "flag" => Ok(Self::Flag),
+            "reject" => Ok(Self::Reject),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Pattern used by the `keywords` moderation provider.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeywordRule {
+    pub category: String,
+    /// Regular expression matched against message text.
+    pub pattern: String,
+}
+
+/// Content moderation applied to messages before they are stored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModerationConfig {
+    pub provider: ModerationProviderKind,
+    /// Default action; rooms can override it through the API.
+    pub action: ModerationAction,
+    /// Categories that trigger `action`; empty means every category.
+    pub categories: Vec<String>,
+    pub rules: Vec<KeywordRule>,
+    /// Model used by the `openai` provider.
+    pub openai_model: String,
+    /// Deliver messages unmoderated when the provider fails, rather than
+    /// refusing them.
+    pub fail_open: bool,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            provider: ModerationProviderKind::None,
+            action: ModerationAction::Flag,
+            categories: Vec::new(),
+            rules: Vec::new(),
+            openai_model: "omni-moderation-latest".to_string(),
+            fail_open: true,
+        }
+    }
+}
+
+/// Spam heuristics applied per member and room to messages being sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpamConfig {
+    pub enabled: bool,
+    /// Span of recent messages the repeat and link checks look at.
+    pub window_secs: u64,
+    /// Identical messages a member may send to a room within `window_secs`.
+    pub max_repeats: usize,
+    /// Links a member may post to a room within `window_secs`.
+    pub max_links: usize,
+    /// Messages a member may send to a room within `burst_secs`.
+    pub burst_messages: usize,
+    pub burst_secs: u64,
+    /// Detections within `score_window_secs` that get a member muted.
+    pub mute_score: usize,
+    pub score_window_secs: u64,
+    /// How long automatic mutes last.
+    pub mute_secs: u64,
+}
+
+impl Default for SpamConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_secs: 60,
+            max_repeats: 3,
+            max_links: 10,
+            burst_messages: 10,
+            burst_secs: 10,
+            mute_score: 3,
+            score_window_secs: 600,
+            mute_secs: 600,
+        }
+    }
+}
+
+/// AI spending limits and the prices used to compute spending.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CostsConfig {
+    /// Monthly USD budget per member; unlimited when unset.
+    pub monthly_budget_usd: Option<f64>,
+    /// Per-member overrides of `monthly_budget_usd`.
+    pub member_budgets_usd: BTreeMap<String, f64>,
+    /// AI calls per room per UTC day; unlimited when unset.
+    pub room_daily_max_calls: Option<u64>,
+    /// Input plus output tokens per room per UTC day; unlimited when unset.
+    pub room_daily_max_tokens: Option<u64>,
+    /// Per-room overrides of both daily limits.
+    pub room_daily_limits: BTreeMap<String, RoomLimits>,
+    /// USD per million tokens, keyed by model name or model name prefix.
+    pub prices: PriceTable,
+}
+
+impl CostsConfig {
+    pub fn tracker(&self) -> CostTracker {
+        let mut tracker = CostTracker::new(self.prices.clone());
+        if let Some(budget) = self.monthly_budget_usd {
+            tracker = tracker.with_monthly_budget(budget);
+        }
+        for (member, budget) in &self.member_budgets_usd {
+            tracker = tracker.with_member_budget(member.clone(), *budget);
+        }
+        tracker
+    }
+
+    pub fn room_budgets(&self) -> RoomBudgets {
+        let mut budgets = RoomBudgets::new(RoomLimits {
+            max_calls: self.room_daily_max_calls,
+            max_tokens: self.room_daily_max_tokens,
+        });
+        for (room_id, limits) in &self.room_daily_limits {
+            budgets = budgets.with_room_limits(room_id.clone(), *limits);
+        }
+        budgets
+    }
+}
+
+/// Scheduled per-room jobs such as daily summaries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulerConfig {
+    /// Run due jobs; schedules can still be managed while this is off.
+    pub enabled: bool,
+    /// JSON file schedules are kept in; they only live in memory when unset.
+    pub path: Option<PathBuf>,
+    /// How often the scheduler looks for due jobs.
+    pub tick_secs: u64,
+    /// Run jobs that came due while the gateway was down once at startup,
+    /// rather than skipping them to their next run.
+    pub catch_up: bool,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: None,
+            tick_secs: 30,
+            catch_up: true,
+        }
+    }
+}
+
+impl SchedulerConfig {
+    pub fn tick(&self) -> Duration {
+        Duration::from_secs(self.tick_secs)
+    }
+}
+
+/// Event fan-out between gateway instances serving the same rooms.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClusterConfig {
+    /// Redis server relaying room events; unset runs a single node.
+    pub redis_url: Option<String>,
+    /// Pub/sub channel shared by every instance.
+    pub channel: String,
+    /// Name this instance publishes under; generated at startup when unset.
+    pub instance_id: Option<String>,
+    /// Seconds an instance's connections stay routable after its last
+    /// heartbeat; instances heartbeat three times per period.
+    pub registry_ttl_secs: u64,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            redis_url: None,
+            channel: "nexis:room-events".to_string(),
+            instance_id: None,
+            registry_ttl_secs: 30,
+        }
+    }
+}
+
+impl ClusterConfig {
+    pub fn registry_ttl(&self) -> Duration {
+        Duration::from_secs(self.registry_ttl_secs)
+    }
+}
+
+/// Delivery to WebSocket clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebSocketConfig {
+    /// Most missed messages replayed to a client resuming a room
+    /// subscription; older ones are skipped. `0` turns resuming off.
+    pub replay_limit: usize,
+    /// Seconds between the pings sent to every client. `0` turns
+    /// heartbeats off.
+    pub ping_interval_secs: u64,
+    /// Pings a client may leave unanswered before its connection is closed.
+    pub max_missed_pongs: u32,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            replay_limit: 500,
+            ping_interval_secs: 30,
+            max_missed_pongs: 3,
+        }
+    }
+}
+
+impl WebSocketConfig {
+    /// Interval between heartbeat pings, unless heartbeats are off.
+    pub fn ping_interval(&self) -> Option<Duration> {
+        (self.ping_interval_secs > 0).then(|| Duration::from_secs(self.ping_interval_secs))
+    }
+}
+
+/// Notifying members of messages and mentions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsConfig {
+    /// Notify members at all; preferences can still be managed while this
+    /// is off.
+    pub enabled: bool,
+    /// How often batched notifications are checked for a due digest.
+    pub digest_tick_secs: u64,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            digest_tick_secs: 60,
+        }
+    }
+}
+
+impl NotificationsConfig {
+    pub fn digest_tick(&self) -> Duration {
+        Duration::from_secs(self.digest_tick_secs)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailProviderKind {
+    /// No email is sent.
+    #[default]
+    None,
+    /// Emails are written to the log instead of being sent.
+    Console,
+    /// Emails are sent through `email.smtp_url`; needs the `email` feature.
+    Smtp,
+}
+
+impl FromStr for EmailProviderKind {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "console" => Ok(Self::Console),
+            "smtp" => Ok(Self::Smtp),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Outgoing email: invitations, mention notifications and digests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailConfig {
+    pub provider: EmailProviderKind,
+    /// `smtp://` or `smtps://` URL, with credentials if the server needs them.
+    pub smtp_url: Option<String>,
+    /// Sender address, such as `Nexis <nexis@example.com>`.
+    pub from: String,
+    /// Sender addresses overriding `from` for rooms of these tenants.
+    pub tenant_from: BTreeMap<String, String>,
+    /// Replacements for the built-in templates.
+    pub templates: EmailTemplatesConfig,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            provider: EmailProviderKind::None,
+            smtp_url: None,
+            from: "Nexis <nexis@localhost>".to_string(),
+            tenant_from: BTreeMap::new(),
+            templates: EmailTemplatesConfig::default(),
+        }
+    }
+}
+
+impl EmailConfig {
+    /// Sender address for email about rooms of `tenant`.
+    pub fn from_address(&self, tenant: Option<&str>) -> &str {
+        tenant
+            .and_then(|tenant| self.tenant_from.get(tenant))
+            .unwrap_or(&self.from)
+    }
+}
+
+/// Email templates; `{{name}}` placeholders are filled in when sending.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailTemplatesConfig {
+    /// Invitation to a room: `room_name`, `room_id`, `token`, `invited_by`,
+    /// `expires_at`.
+    pub invitation: Option<EmailTemplateConfig>,
+    /// A single notification: `sender`, `room_id`, `excerpt`, `sent_at`.
+    pub mention: Option<EmailTemplateConfig>,
+    /// A single notification of a message that doesn't mention the member;
+    /// same placeholders as `mention`.
+    pub message: Option<EmailTemplateConfig>,
+    /// Several notifications: `count` and `notifications`, one line each.
+    pub digest: Option<EmailTemplateConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailTemplateConfig {
+    pub subject: String,
+    pub body: String,
+}
+
+impl NexisConfig {
+    /// Load the config file named by `NEXIS_CONFIG` (or `./nexis.toml` when
+    /// present), apply process environment overrides and validate.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let path = std::env::var_os(CONFIG_PATH_ENV)
+            .map(PathBuf::from)
+            .or_else(|| {
+                let default = PathBuf::from(DEFAULT_CONFIG_PATH);
+                default.is_file().then_some(default)
+            });
+        Self::load(path.as_deref(), |name| std::env::var(name).ok())
+    }
+
+    /// Load from an optional file, apply overrides from `env` and validate.
+    pub fn load(
+        path: Option<&Path>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply_env(env)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let raw = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&raw).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source: Box::new(source),
+        })
+    }
+
+    /// Apply environment variable overrides. Variable names match the ones
+    /// the gateway and providers read individually.
+    pub fn apply_env(&mut self, env: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        if let Some(value) = env("NEXIS_BIND_ADDR") {
+            self.server.bind_addr = parse_env("NEXIS_BIND_ADDR", value)?;
+        }
+        if let Some(value) = env("NEXIS_DRAIN_TIMEOUT_SECS") {
+            self.server.drain_timeout_secs = parse_env("NEXIS_DRAIN_TIMEOUT_SECS", value)?;
+        }
+        if let Some(value) = env("NEXIS_CORS_ALLOW_ORIGINS") {
+            self.cors.allow_origins = value
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(value) = env("NEXIS_CORS_ALLOW_CREDENTIALS") {
+            self.cors.allow_credentials = parse_flag("NEXIS_CORS_ALLOW_CREDENTIALS", value)?;
+        }
+
+        if let Some(value) = env("JWT_SECRET") {
+            self.auth.jwt_secret = value;
+        }
+        if let Some(value) = env("JWT_ISSUER") {
+            self.auth.jwt_issuer = value;
+        }
+        if let Some(value) = env("JWT_AUDIENCE") {
+            self.auth.jwt_audience = value;
+        }
+
+        if let Some(value) = env("NEXIS_ADMIN_MEMBERS") {
+            self.auth.admin_members = value
+                .split(',')
+                .map(str::trim)
+                .filter(|member| !member.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+
+        if let Some(value) = env("DATABASE_URL") {
+            self.database.url = Some(value);
+        }
+        if let Some(value) = env("NEXIS_DATABASE_AUTO_MIGRATE") {
+            self.database.auto_migrate = parse_flag("NEXIS_DATABASE_AUTO_MIGRATE", value)?;
+        }
+        if let Some(value) = env("NEXIS_DATABASE_INDEX_OUTBOX") {
+            self.database.index_outbox = parse_flag("NEXIS_DATABASE_INDEX_OUTBOX", value)?;
+        }
+
+        if let Some(value) = env("NEXIS_VECTOR_BACKEND") {
+            self.vector.backend = parse_env("NEXIS_VECTOR_BACKEND", value)?;
+        }
+        if let Some(value) = env("QDRANT_URL") {
+            self.vector.url = value;
+        }
+        if let Some(value) = env("NEXIS_VECTOR_ROUTING") {
+            self.vector.routing = parse_env("NEXIS_VECTOR_ROUTING", value)?;
+        }
+
+        if let Some(value) = env("NEXIS_AI_PROVIDER") {
+            self.providers.default = Some(parse_env("NEXIS_AI_PROVIDER", value)?);
+        }
+        for (kind, prefix) in [
+            (ProviderKind::OpenAI, "OPENAI"),
+            (ProviderKind::Anthropic, "ANTHROPIC"),
+            (ProviderKind::Ollama, "OLLAMA"),
+            (ProviderKind::Vllm, "VLLM"),
+        ] {
+            let provider = self.providers.get_mut(kind);
+            if let Some(value) = env(&format!("{prefix}_API_KEY")) {
+                provider.api_key = Some(value);
+            }
+            if let Some(value) = env(&format!("{prefix}_API_BASE")) {
+                provider.base_url = Some(value);
+            }
+            if let Some(value) = env(&format!("{prefix}_DEFAULT_MODEL")) {
+                provider.model = Some(value);
+            }
+        }
+        if let Some(value) = env("NEXIS_AI_ALLOWED_MODELS") {
+            self.providers.allowed_models = value
+                .split(',')
+                .map(str::trim)
+                .filter(|model| !model.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+
+        if let Some(value) = env("NEXIS_AUDIT_SINK") {
+            self.audit.sink = parse_env("NEXIS_AUDIT_SINK", value)?;
+        }
+        if let Some(value) = env("NEXIS_AUDIT_PATH") {
+            self.audit.path = PathBuf::from(value);
+        }
+        if let Some(value) = env("NEXIS_GENERATION_LOG_ENABLED") {
+            self.generation_log.enabled = parse_flag("NEXIS_GENERATION_LOG_ENABLED", value)?;
+        }
+        if let Some(value) = env("NEXIS_TENANT_STORE") {
+            self.tenants.store = parse_env("NEXIS_TENANT_STORE", value)?;
+        }
+
+        if let Some(value) = env("NEXIS_OIDC_ISSUER_URL") {
+            self.oidc.issuer_url = Some(value);
+        }
+        if let Some(value) = env("NEXIS_OIDC_CLIENT_ID") {
+            self.oidc.client_id = value;
+        }
+        if let Some(value) = env("NEXIS_OIDC_CLIENT_SECRET") {
+            self.oidc.client_secret = Some(value);
+        }
+        if let Some(value) = env("NEXIS_OIDC_REDIRECT_URL") {
+            self.oidc.redirect_url = value;
+        }
+        if let Some(value) = env("NEXIS_OIDC_MEMBER_CLAIM") {
+            self.oidc.member_claim = parse_env("NEXIS_OIDC_MEMBER_CLAIM", value)?;
+        }
+
+        if let Some(value) = env("NEXIS_UPLOAD_STORE") {
+            self.uploads.store = parse_env("NEXIS_UPLOAD_STORE", value)?;
+        }
+        if let Some(value) = env("NEXIS_UPLOAD_PATH") {
+            self.uploads.path = PathBuf::from(value);
+        }
+        if let Some(value) = env("NEXIS_UPLOAD_MAX_BYTES") {
+            self.uploads.max_bytes = parse_env("NEXIS_UPLOAD_MAX_BYTES", value)?;
+        }
+        if let Some(value) = env("NEXIS_S3_ENDPOINT") {
+            self.uploads.s3.endpoint = Some(value);
+        }
+        if let Some(value) = env("NEXIS_S3_BUCKET") {
+            self.uploads.s3.bucket = value;
+        }
+        if let Some(value) = env("NEXIS_S3_REGION") {
+            self.uploads.s3.region = value;
+        }
+        if let Some(value) = env("NEXIS_S3_ACCESS_KEY_ID") {
+            self.uploads.s3.access_key_id = value;
+        }
+        if let Some(value) = env("NEXIS_S3_SECRET_ACCESS_KEY") {
+            self.uploads.s3.secret_access_key = Some(value);
+        }
+
+        if let Some(value) = env("NEXIS_MODERATION_PROVIDER") {
+            self.moderation.provider = parse_env("NEXIS_MODERATION_PROVIDER", value)?;
+        }
+        if let Some(value) = env("NEXIS_MODERATION_ACTION") {
+            self.moderation.action = parse_env("NEXIS_MODERATION_ACTION", value)?;
+        }
+        if let Some(value) = env("NEXIS_SPAM_ENABLED") {
+            self.spam.enabled = parse_flag("NEXIS_SPAM_ENABLED", value)?;
+        }
+
+        if let Some(value) = env("NEXIS_MONTHLY_BUDGET_USD") {
+            self.costs.monthly_budget_usd = Some(parse_env("NEXIS_MONTHLY_BUDGET_USD", value)?);
+        }
+        if let Some(value) = env("NEXIS_ROOM_DAILY_MAX_CALLS") {
+            self.costs.room_daily_max_calls = Some(parse_env("NEXIS_ROOM_DAILY_MAX_CALLS", value)?);
+        }
+        if let Some(value) = env("NEXIS_ROOM_DAILY_MAX_TOKENS") {
+            self.costs.room_daily_max_tokens =
+                Some(parse_env("NEXIS_ROOM_DAILY_MAX_TOKENS", value)?);
+        }
+
+        if let Some(value) = env("NEXIS_SCHEDULER_ENABLED") {
+            self.scheduler.enabled = parse_flag("NEXIS_SCHEDULER_ENABLED", value)?;
+        }
+        if let Some(value) = env("NEXIS_SCHEDULES_PATH") {
+            self.scheduler.path = Some(PathBuf::from(value));
+        }
+
+        if let Some(value) = env("NEXIS_CLUSTER_REDIS_URL") {
+            self.cluster.redis_url = Some(value);
+        }
+        if let Some(value) = env("NEXIS_INSTANCE_ID") {
+            self.cluster.instance_id = Some(value);
+        }
+
+        if let Some(value) = env("NEXIS_WS_REPLAY_LIMIT") {
+            self.websocket.replay_limit = parse_env("NEXIS_WS_REPLAY_LIMIT", value)?;
+        }
+        if let Some(value) = env("NEXIS_WS_PING_INTERVAL_SECS") {
+            self.websocket.ping_interval_secs = parse_env("NEXIS_WS_PING_INTERVAL_SECS", value)?;
+        }
+        if let Some(value) = env("NEXIS_WS_MAX_MISSED_PONGS") {
+            self.websocket.max_missed_pongs = parse_env("NEXIS_WS_MAX_MISSED_PONGS", value)?;
+        }
+
+        if let Some(value) = env("NEXIS_NOTIFICATIONS_ENABLED") {
+            self.notifications.enabled = parse_flag("NEXIS_NOTIFICATIONS_ENABLED", value)?;
+        }
+
+        if let Some(value) = env("NEXIS_EMAIL_PROVIDER") {
+            self.email.provider = parse_env("NEXIS_EMAIL_PROVIDER", value)?;
+        }
+        if let Some(value) = env("NEXIS_SMTP_URL") {
+            self.email.smtp_url = Some(value);
+        }
+        if let Some(value) = env("NEXIS_EMAIL_FROM") {
+            self.email.from = value;
+        }
+
+        Ok(())
+    }
+
+    /// Check the whole config and report every problem at once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.cors.allow_origins.is_empty() {
+            problems.push("cors.allow_origins must not be empty".to_string());
+        }
+        for origin in &self.cors.allow_origins {
+            if origin != "*" && HeaderValue::from_str(origin).is_err() {
+                problems.push(format!(
+                    "cors.allow_origins contains invalid origin `{origin}`"
+                ));
+            }
+        }
+        if self.cors.allow_credentials && self.cors.allow_origins.iter().any(|o| o == "*") {
+            problems.push("cors.allow_credentials cannot be combined with origin `*`".to_string());
+        }
+
+        if self.auth.jwt_secret.trim().is_empty() {
+            problems.push("auth.jwt_secret must not be empty".to_string());
+        }
+        if self.auth.jwt_issuer.trim().is_empty() {
+            problems.push("auth.jwt_issuer must not be empty".to_string());
+        }
+        if self.auth.jwt_audience.trim().is_empty() {
+            problems.push("auth.jwt_audience must not be empty".to_string());
+        }
+        if self.auth.token_expiry_secs == 0 {
+            problems.push("auth.token_expiry_secs must be greater than zero".to_string());
+        }
+        if self.auth.refresh_token_expiry_secs <= self.auth.token_expiry_secs {
+            problems.push(
+                "auth.refresh_token_expiry_secs must be greater than auth.token_expiry_secs"
+                    .to_string(),
+            );
+        }
+        for (member_id, hash) in &self.auth.member_credentials {
+            let is_argon2 = PasswordHash::new(hash)
+                .is_ok_and(|hash| argon2::Algorithm::new(hash.algorithm.as_str()).is_ok());
+            if !is_argon2 {
+                problems.push(format!(
+                    "auth.member_credentials for `{member_id}` must be an argon2 PHC hash"
+                ));
+            }
+        }
+
+        if let Some(url) = &self.database.url {
+            if !(url.starts_with("postgres://") || url.starts_with("postgresql://")) {
+                problems.push("database.url must be a postgres:// URL".to_string());
+            }
+        }
+        if self.database.max_connections == 0 {
+            problems.push("database.max_connections must be greater than zero".to_string());
+        }
+
+        if self.vector.backend == VectorBackend::Qdrant
+            && !(self.vector.url.starts_with("http://") || self.vector.url.starts_with("https://"))
+        {
+            problems.push("vector.url must be an http(s) URL for the qdrant backend".to_string());
+        }
+        if self.vector.collection.trim().is_empty() {
+            problems.push("vector.collection must not be empty".to_string());
+        }
+        if self.vector.dimension == 0 {
+            problems.push("vector.dimension must be greater than zero".to_string());
+        }
+
+        if let Some(kind) = self.providers.default.filter(|kind| !kind.is_local()) {
+            let has_key = self
+                .providers
+                .get(kind)
+                .api_key
+                .as_deref()
+                .is_some_and(|key| !key.trim().is_empty());
+            if !has_key {
+                problems.push(format!(
+                    "providers.{}.api_key is required when it is the default provider",
+                    kind.as_str()
+                ));
+            }
+        }
+        let models = self
+            .providers
+            .tenant_models
+            .values()
+            .chain([&self.providers.allowed_models]);
+        if models.flatten().any(|model| model.trim().is_empty()) {
+            problems.push("providers.allowed_models must not contain empty models".to_string());
+        }
+
+        if self.rate_limits.max_concurrent_writes == 0 {
+            problems
+                .push("rate_limits.max_concurrent_writes must be greater than zero".to_string());
+        }
+        if self.rate_limits.max_message_bytes == 0 {
+            problems.push("rate_limits.max_message_bytes must be greater than zero".to_string());
+        }
+        if self.rate_limits.max_metadata_bytes == 0 {
+            problems.push("rate_limits.max_metadata_bytes must be greater than zero".to_string());
+        }
+
+        if self.audit.sink == AuditSinkKind::File && self.audit.path.as_os_str().is_empty() {
+            problems.push("audit.path must be set for the file sink".to_string());
+        }
+        if self.audit.sink == AuditSinkKind::Database && self.database.url.is_none() {
+            problems.push("audit.sink = \"database\" requires database.url".to_string());
+        }
+        if self.audit.retained == 0 {
+            problems.push("audit.retained must be greater than zero".to_string());
+        }
+        if self.tenants.store == TenantStoreKind::Database && self.database.url.is_none() {
+            problems.push("tenants.store = \"database\" requires database.url".to_string());
+        }
+        if let Some(issuer_url) = &self.oidc.issuer_url {
+            if !(issuer_url.starts_with("https://") || issuer_url.starts_with("http://")) {
+                problems.push("oidc.issuer_url must be an http(s) URL".to_string());
+            }
+            if self.oidc.client_id.trim().is_empty() {
+                problems.push("oidc.client_id is required when oidc.issuer_url is set".to_string());
+            }
+            if self.oidc.redirect_url.trim().is_empty() {
+                problems
+                    .push("oidc.redirect_url is required when oidc.issuer_url is set".to_string());
+            }
+            if !self.oidc.scopes.iter().any(|scope| scope == "openid") {
+                problems.push("oidc.scopes must include \"openid\"".to_string());
+            }
+        }
+
+        if self.uploads.max_bytes == 0 {
+            problems.push("uploads.max_bytes must be greater than zero".to_string());
+        }
+        if self.uploads.url_ttl_secs == 0 {
+            problems.push("uploads.url_ttl_secs must be greater than zero".to_string());
+        }
+        if self.uploads.allowed_mime_types.is_empty() {
+            problems.push("uploads.allowed_mime_types must not be empty".to_string());
+        }
+        if self.uploads.store == BlobStoreKind::Local && self.uploads.path.as_os_str().is_empty() {
+            problems.push("uploads.path must be set for the local store".to_string());
+        }
+        if self.uploads.store == BlobStoreKind::S3 {
+            let s3 = &self.uploads.s3;
+            if !s3
+                .endpoint
+                .as_deref()
+                .is_some_and(|url| url.starts_with("https://") || url.starts_with("http://"))
+            {
+                problems.push("uploads.s3.endpoint must be an http(s) URL".to_string());
+            }
+            if s3.bucket.trim().is_empty() {
+                problems.push("uploads.s3.bucket must not be empty".to_string());
+            }
+            if s3.access_key_id.trim().is_empty() || s3.secret_access_key.is_none() {
+                problems.push(
+                    "uploads.s3.access_key_id and uploads.s3.secret_access_key are required"
+                        .to_string(),
+                );
+            }
+        }
+
+        match self.moderation.provider {
+            ModerationProviderKind::None => {}
+            ModerationProviderKind::Keywords if self.moderation.rules.is_empty() => {
+                problems.push(
+                    "moderation.rules must not be empty for the keywords provider".to_string(),
+                );
+            }
+            ModerationProviderKind::Keywords => {}
+            ModerationProviderKind::OpenAI if self.providers.openai.api_key.is_none() => {
+                problems.push(
+                    "providers.openai.api_key is required for the openai moderation provider"
+                        .to_string(),
+                );
+            }
+            ModerationProviderKind::OpenAI => {}
+        }
+        for rule in &self.moderation.rules {
+            if rule.category.trim().is_empty() {
+                problems.push("moderation.rules entries need a category".to_string());
+            }
+            if let Err(err) = regex::Regex::new(&rule.pattern) {
+                problems.push(format!(
+                    "moderation.rules pattern for {} is invalid: {err}",
+                    rule.category
+                ));
+            }
+        }
+        let spam = &self.spam;
+        for (name, value) in [
+            ("window_secs", spam.window_secs),
+            ("burst_secs", spam.burst_secs),
+            ("score_window_secs", spam.score_window_secs),
+            ("mute_secs", spam.mute_secs),
+            ("mute_score", spam.mute_score as u64),
+        ] {
+            if spam.enabled && value == 0 {
+                problems.push(format!("spam.{name} must be greater than zero"));
+            }
+        }
+
+        let valid_usd = |value: f64| value.is_finite() && value >= 0.0;
+        if !self.costs.monthly_budget_usd.is_none_or(valid_usd) {
+            problems.push("costs.monthly_budget_usd must be a non-negative amount".to_string());
+        }
+        for (member, budget) in &self.costs.member_budgets_usd {
+            if !valid_usd(*budget) {
+                problems.push(format!(
+                    "costs.member_budgets_usd.{member} must be a non-negative amount"
+                ));
+            }
+        }
+        for (model, price) in self.costs.prices.iter() {
+            if !valid_usd(price.input_per_million) || !valid_usd(price.output_per_million) {
+                problems.push(format!("costs.prices.{model} must be non-negative"));
+            }
+        }
+
+        if self.scheduler.tick_secs == 0 {
+            problems.push("scheduler.tick_secs must be greater than zero".to_string());
+        }
+        if self
+            .scheduler
+            .path
+            .as_ref()
+            .is_some_and(|path| path.as_os_str().is_empty())
+        {
+            problems.push("scheduler.path must not be empty when set".to_string());
+        }
+
+        if self.cluster.channel.trim().is_empty() {
+            problems.push("cluster.channel must not be empty".to_string());
+        }
+        if self.cluster.registry_ttl_secs == 0 {
+            problems.push("cluster.registry_ttl_secs must be greater than zero".to_string());
+        }
+        if self.websocket.ping_interval_secs > 0 && self.websocket.max_missed_pongs == 0 {
+            problems.push(
+                "websocket.max_missed_pongs must be greater than zero when heartbeats are on"
+                    .to_string(),
+            );
+        }
+
+        if self.generation_log.retained == 0 {
+            problems.push("generation_log.retained must be greater than zero".to_string());
+        }
+        for pattern in &self.generation_log.redact_patterns {
+            if regex::Regex::new(pattern).is_err() {
+                problems.push(format!(
+                    "generation_log.redact_patterns has an invalid pattern `{pattern}`"
+                ));
+            }
+        }
+
+        if self.notifications.digest_tick_secs == 0 {
+            problems.push("notifications.digest_tick_secs must be greater than zero".to_string());
+        }
+
+        if self.email.from.trim().is_empty() {
+            problems.push("email.from must not be empty".to_string());
+        }
+        for (tenant, from) in &self.email.tenant_from {
+            if from.trim().is_empty() {
+                problems.push(format!("email.tenant_from.{tenant} must not be empty"));
+            }
+        }
+        if self.email.provider == EmailProviderKind::Smtp
+            && self
+                .email
+                .smtp_url
+                .as_deref()
+                .is_none_or(|url| url.trim().is_empty())
+        {
+            problems.push("email.smtp_url is required with the smtp provider".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+fn parse_env<T: FromStr>(name: &'static str, value: String) -> Result<T, ConfigError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ConfigError::InvalidEnv { name, value })
+}
+
+fn parse_flag(name: &'static str, value: String) -> Result<bool, ConfigError> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(ConfigError::InvalidEnv { name, value }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn toml_file_is_merged_with_defaults_and_env_overrides() {
+        let path = std::env::temp_dir().join(format!("nexis-config-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"
+[server]
+bind_addr = "127.0.0.1:9000"
+
+[auth]
+jwt_secret = "file-secret"
+
+[providers]
+default = "anthropic"
+anthropic = { api_key = "file-key" }
+
+[rate_limits]
+max_concurrent_writes = 16
+"#,
+        )
+        .unwrap();
+
+        let config = NexisConfig::load(
+            Some(&path),
+            env(&[
+                ("JWT_SECRET", "env-secret"),
+                ("QDRANT_URL", "http://qdrant:6334"),
+                ("NEXIS_VECTOR_ROUTING", "per-room"),
+                ("NEXIS_AI_ALLOWED_MODELS", "gpt-4o, claude-3-5-haiku"),
+            ]),
+        )
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.server.bind_addr, "127.0.0.1:9000".parse().unwrap());
+        assert_eq!(config.auth.jwt_secret, "env-secret");
+        assert_eq!(config.auth.jwt_issuer, "nexis");
+        assert_eq!(config.providers.default, Some(ProviderKind::Anthropic));
+        assert_eq!(
+            config.providers.anthropic.api_key.as_deref(),
+            Some("file-key")
+        );
+        assert_eq!(config.rate_limits.max_concurrent_writes, 16);
+        assert_eq!(config.rate_limits.max_message_bytes, 32 * 1024);
+        assert_eq!(config.vector.url, "http://qdrant:6334");
+        assert_eq!(config.vector.routing, CollectionRouting::PerRoom);
+        assert_eq!(
+            config.providers.allowed_models(Some("acme")),
+            ["gpt-4o", "claude-3-5-haiku"]
+        );
+    }
+
+    #[test]
+    fn local_providers_need_no_api_key() {
+        let config = NexisConfig::load(
+            None,
+            env(&[
+                ("NEXIS_AI_PROVIDER", "ollama"),
+                ("OLLAMA_API_BASE", "http://ollama:11434/v1"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.providers.default, Some(ProviderKind::Ollama));
+        assert_eq!(
+            config.providers.ollama.base_url.as_deref(),
+            Some("http://ollama:11434/v1")
+        );
+        assert_eq!(config.providers.ollama.api_key, None);
+        assert!(NexisConfig::load(None, env(&[("NEXIS_AI_PROVIDER", "vllm")])).is_ok());
+    }
+
+    #[test]
+    fn validation_reports_every_problem() {
+        let err = NexisConfig::load(
+            None,
+            env(&[
+                ("NEXIS_AI_PROVIDER", "openai"),
+                ("DATABASE_URL", "mysql://db"),
+                ("NEXIS_CORS_ALLOW_ORIGINS", "*"),
+            ]),
+        )
+        .unwrap_err();
+        let ConfigError::Invalid(problems) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(problems.len(), 3, "{problems:?}");
+
+        assert!(matches!(
+            NexisConfig::load(None, env(&[("NEXIS_BIND_ADDR", "not-an-addr")])),
+            Err(ConfigError::InvalidEnv {
+                name: "NEXIS_BIND_ADDR",
+                ..
+            })
+        ));
+        assert!(toml::from_str::<NexisConfig>("[server]\nport = 1").is_err());
+    }
+
+    #[test]
+    fn member_credentials_are_argon2_hashes() {
+        let mut config: NexisConfig = toml::from_str(
+            r#"
+[auth.member_credentials]
+"nexis:human:alice@example.com" = "$argon2id$v=19$m=19456,t=2,p=1$bmV4aXMtdGVzdC1zYWx0IQ$flgXTwWKpa2ibWVefh0eEPMbiIW/9RMP02kMMjgoGvE"
+"#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert!(config
+            .auth
+            .verify_member_secret("nexis:human:alice@example.com", "secret"));
+        assert!(!config
+            .auth
+            .verify_member_secret("nexis:human:alice@example.com", "Secret"));
+        assert!(!config
+            .auth
+            .verify_member_secret("nexis:human:bob@example.com", "secret"));
+        assert!(!format!("{:?}", config.auth).contains("argon2id"));
+
+        config.auth.member_credentials.insert(
+            "nexis:human:bob@example.com".to_string(),
+            "secret".to_string(),
+        );
+        // Unsalted SHA-256 digests of secrets are no longer accepted.
+        config.auth.member_credentials.insert(
+            "nexis:human:carol@example.com".to_string(),
+            "2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b".to_string(),
+        );
+        let Err(ConfigError::Invalid(problems)) = config.validate() else {
+            panic!("plain-text secrets and digests should be rejected");
+        };
+        assert!(problems[0].contains("nexis:human:bob@example.com"));
+        assert!(problems[1].contains("nexis:human:carol@example.com"));
+    }
+
+    #[test]
+    fn oidc_requires_client_and_redirect_once_enabled() {
+        let err = NexisConfig::load(
+            None,
+            env(&[("NEXIS_OIDC_ISSUER_URL", "https://accounts.google.com")]),
+        )
+        .unwrap_err();
+        let ConfigError::Invalid(problems) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(problems.len(), 2, "{problems:?}");
+
+        let config = NexisConfig::load(
+            None,
+            env(&[
+                ("NEXIS_OIDC_ISSUER_URL", "https://accounts.google.com"),
+                ("NEXIS_OIDC_CLIENT_ID", "nexis"),
+                ("NEXIS_OIDC_CLIENT_SECRET", "shh"),
+                (
+                    "NEXIS_OIDC_REDIRECT_URL",
+                    "https://nexis.example.com/v1/auth/oidc/callback",
+                ),
+                ("NEXIS_OIDC_MEMBER_CLAIM", "sub"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.oidc.member_claim, OidcMemberClaim::Subject);
+        assert!(!format!("{:?}", config.oidc).contains("shh"));
+    }
+
+    #[test]
+    fn uploads_validate_s3_settings_and_match_mime_families() {
+        let err = NexisConfig::load(None, env(&[("NEXIS_UPLOAD_STORE", "s3")])).unwrap_err();
+        let ConfigError::Invalid(problems) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(problems.len(), 3, "{problems:?}");
+
+        let config = NexisConfig::load(
+            None,
+            env(&[
+                ("NEXIS_UPLOAD_STORE", "s3"),
+                ("NEXIS_UPLOAD_MAX_BYTES", "1024"),
+                ("NEXIS_S3_ENDPOINT", "http://minio:9000"),
+                ("NEXIS_S3_BUCKET", "nexis"),
+                ("NEXIS_S3_ACCESS_KEY_ID", "minio"),
+                ("NEXIS_S3_SECRET_ACCESS_KEY", "minio-secret"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.uploads.store, BlobStoreKind::S3);
+        assert_eq!(config.uploads.max_bytes, 1024);
+        assert!(!format!("{:?}", config.uploads).contains("minio-secret"));
+
+        let uploads = UploadsConfig::default();
+        assert!(uploads.allows_mime_type("image/png"));
+        assert!(uploads.allows_mime_type("Application/PDF"));
+        assert!(!uploads.allows_mime_type("image/"));
+        assert!(!uploads.allows_mime_type("imagex/png"));
+        assert!(!uploads.allows_mime_type("application/x-msdownload"));
+    }
+
+    #[test]
+    fn costs_parse_prices_and_reject_negative_budgets() {
+        let mut config: NexisConfig = toml::from_str(
+            r#"
+            [costs]
+            monthly_budget_usd = 20.0
+            member_budgets_usd = { alice = 50.0 }
+            prices = { "gpt-4o" = { input_per_million = 2.5, output_per_million = 10.0 } }
+            room_daily_max_calls = 100
+            room_daily_limits = { support = { max_tokens = 50000 } }
+            "#,
+        )
+        .unwrap();
+        let tracker = config.costs.tracker();
+        assert_eq!(tracker.budget_for("alice"), Some(50.0));
+        assert_eq!(tracker.budget_for("bob"), Some(20.0));
+        assert!(tracker.prices().price("gpt-4o-2024-08-06").is_some());
+        let room_budgets = config.costs.room_budgets();
+        assert_eq!(room_budgets.limits_for("general").max_calls, Some(100));
+        assert_eq!(
+            room_budgets.limits_for("support"),
+            RoomLimits {
+                max_calls: None,
+                max_tokens: Some(50_000),
+            }
+        );
+
+        config
+            .apply_env(env(&[("NEXIS_MONTHLY_BUDGET_USD", "-1")]))
+            .unwrap();
+        config
+            .costs
+            .member_budgets_usd
+            .insert("bob".to_string(), f64::NAN);
+        let ConfigError::Invalid(problems) = config.validate().unwrap_err() else {
+            panic!("expected validation error");
+        };
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("costs.monthly_budget_usd")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("costs.member_budgets_usd.bob")));
+    }
+
+    #[test]
+    fn generation_log_is_off_by_default_and_checks_redact_patterns() {
+        let mut config: NexisConfig = toml::from_str(
+            r#"
+            [generation_log]
+            redact_patterns = ["sk-[A-Za-z0-9]+", "(unclosed"]
+            "#,
+        )
+        .unwrap();
+        assert!(!config.generation_log.enabled);
+        config
+            .apply_env(env(&[("NEXIS_GENERATION_LOG_ENABLED", "true")]))
+            .unwrap();
+        assert!(config.generation_log.enabled);
+
+        let ConfigError::Invalid(problems) = config.validate().unwrap_err() else {
+            panic!("expected validation error");
+        };
+        assert_eq!(
+            problems,
+            ["generation_log.redact_patterns has an invalid pattern `(unclosed`"]
+        );
+    }
+
+    #[test]
+    fn spam_guard_is_off_by_default_and_checks_its_thresholds() {
+        let mut config: NexisConfig = toml::from_str(
+            r#"
+            [spam]
+            max_links = 4
+            burst_secs = 0
+            "#,
+        )
+        .unwrap();
+        assert!(!config.spam.enabled);
+        assert_eq!(config.spam.max_links, 4);
+        config.validate().unwrap();
+
+        config
+            .apply_env(env(&[("NEXIS_SPAM_ENABLED", "true")]))
+            .unwrap();
+        assert!(config.spam.enabled);
+        let ConfigError::Invalid(problems) = config.validate().unwrap_err() else {
+            panic!("expected validation error");
+        };
+        assert_eq!(problems, ["spam.burst_secs must be greater than zero"]);
+    }
+
+    #[test]
+    fn moderation_rules_must_compile_and_openai_needs_a_key() {
+        let config: NexisConfig = toml::from_str(
+            r#"
+            [moderation]
+            provider = "keywords"
+            action = "reject"
+            rules = [{ category = "spam", pattern = "(?i)buy now" }]
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.moderation.action, ModerationAction::Reject);
+
+        let err = NexisConfig::load(
+            None,
+            env(&[
+                ("NEXIS_MODERATION_PROVIDER", "openai"),
+                ("NEXIS_MODERATION_ACTION", "flag"),
+            ]),
+        )
+        .unwrap_err();
+        let ConfigError::Invalid(problems) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("openai moderation provider")));
+
+        let mut config = NexisConfig::default();
+        config.moderation.provider = ModerationProviderKind::Keywords;
+        config.moderation.rules.push(KeywordRule {
+            category: "spam".to_string(),
+            pattern: "(unclosed".to_string(),
+        });
+        let ConfigError::Invalid(problems) = config.validate().unwrap_err() else {
+            panic!("expected validation error");
+        };
+        assert!(problems[0].contains("moderation.rules pattern for spam"));
+    }
+
+    #[test]
+    fn scheduler_reads_env_overrides_and_needs_a_tick() {
+        let config = NexisConfig::load(
+            None,
+            env(&[
+                ("NEXIS_SCHEDULER_ENABLED", "off"),
+                ("NEXIS_SCHEDULES_PATH", "/var/lib/nexis/schedules.json"),
+            ]),
+        )
+        .unwrap();
+        assert!(!config.scheduler.enabled);
+        assert!(config.scheduler.catch_up);
+        assert_eq!(
+            config.scheduler.path.as_deref(),
+            Some(Path::new("/var/lib/nexis/schedules.json"))
+        );
+
+        let config: NexisConfig = toml::from_str("[scheduler]\ntick_secs = 0\n").unwrap();
+        let ConfigError::Invalid(problems) = config.validate().unwrap_err() else {
+            panic!("expected validation error");
+        };
+        assert_eq!(
+            problems,
+            vec!["scheduler.tick_secs must be greater than zero"]
+        );
+    }
+
+    #[test]
+    fn email_reads_env_overrides_and_needs_a_server() {
+        let config = NexisConfig::load(
+            None,
+            env(&[
+                ("NEXIS_NOTIFICATIONS_ENABLED", "false"),
+                ("NEXIS_EMAIL_PROVIDER", "smtp"),
+                ("NEXIS_SMTP_URL", "smtps://mail.example.com"),
+                ("NEXIS_EMAIL_FROM", "Nexis <nexis@example.com>"),
+            ]),
+        )
+        .unwrap();
+        assert!(!config.notifications.enabled);
+        assert_eq!(config.email.provider, EmailProviderKind::Smtp);
+        assert_eq!(
+            config.email.smtp_url.as_deref(),
+            Some("smtps://mail.example.com")
+        );
+
+        let config: NexisConfig = toml::from_str(
+            "[email]\nprovider = \"smtp\"\n[email.tenant_from]\nacme = \"Acme <chat@acme.test>\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.email.from_address(Some("acme")), "Acme <chat@acme.test>");
+        assert_eq!(config.email.from_address(Some("other")), "Nexis <nexis@localhost>");
+        let ConfigError::Invalid(problems) = config.validate().unwrap_err() else {
+            panic!("expected validation error");
+        };
+        assert_eq!(
+            problems,
+            vec!["email.smtp_url is required with the smtp provider"]
+        );
+    }
+
+    #[test]
+    fn cluster_reads_env_overrides_and_needs_a_channel() {
+        let config = NexisConfig::load(
+            None,
+            env(&[
+                ("NEXIS_CLUSTER_REDIS_URL", "redis://redis:6379/0"),
+                ("NEXIS_INSTANCE_ID", "gw-1"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            config.cluster.redis_url.as_deref(),
+            Some("redis://redis:6379/0")
+        );
+        assert_eq!(config.cluster.instance_id.as_deref(), Some("gw-1"));
+        assert_eq!(config.cluster.channel, "nexis:room-events");
+
+        let config: NexisConfig = toml::from_str("[cluster]\nchannel = \" \"\n").unwrap();
+        let ConfigError::Invalid(problems) = config.validate().unwrap_err() else {
+            panic!("expected validation error");
+        };
+        assert_eq!(problems, vec!["cluster.channel must not be empty"]);
+    }
+
+    #[test]
+    fn websocket_replay_limit_reads_env_override() {
+        assert_eq!(NexisConfig::default().websocket.replay_limit, 500);
+        let config = NexisConfig::load(None, env(&[("NEXIS_WS_REPLAY_LIMIT", "0")])).unwrap();
+        assert_eq!(config.websocket.replay_limit, 0);
+        assert!(NexisConfig::load(None, env(&[("NEXIS_WS_REPLAY_LIMIT", "-1")])).is_err());
+    }
+
+    #[test]
+    fn websocket_heartbeat_settings_are_validated() {
+        let config = NexisConfig::load(
+            None,
+            env(&[
+                ("NEXIS_WS_PING_INTERVAL_SECS", "5"),
+                ("NEXIS_WS_MAX_MISSED_PONGS", "2"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.websocket.ping_interval(), Some(Duration::from_secs(5)));
+        assert_eq!(config.websocket.max_missed_pongs, 2);
+
+        let config: NexisConfig =
+            toml::from_str("[websocket]\nping_interval_secs = 0\nmax_missed_pongs = 0\n").unwrap();
+        assert_eq!(config.websocket.ping_interval(), None);
+        assert!(config.validate().is_ok());
+
+        let config: NexisConfig = toml::from_str("[websocket]\nmax_missed_pongs = 0\n").unwrap();
+        let ConfigError::Invalid(problems) = config.validate().unwrap_err() else {
+            panic!("expected validation error");
+        };
+        assert_eq!(
+            problems,
+            vec!["websocket.max_missed_pongs must be greater than zero when heartbeats are on"]
+        );
+    }
+}
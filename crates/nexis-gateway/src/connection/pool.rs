@@ -6,7 +6,7 @@ use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::hash::{Hash, Hasher};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, OwnedSemaphorePermit, RwLock, Semaphore};
 use uuid::Uuid;
 
@@ -290,36 +290,61 @@ impl ShardedConnectionManager {
         distribution
     }
 
-    /// Clean up inactive connections (optional maintenance)
-    pub async fn cleanup_inactive(&self, max_idle_secs: u64) -> usize {
+    /// Mark a connection as alive, e.g. when it answers a heartbeat ping
+    pub async fn record_pong(&self, id: ConnectionId) -> bool {
+        let shard = &self.shards[self.shard_index(id)];
+        let mut connections = shard.connections.write().await;
+        if let Some(conn) = connections.get_mut(&id) {
+            conn.last_activity = Instant::now();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Remove connections silent for longer than `timeout` and free their
+    /// pool slots, returning the removed connections
+    pub async fn reap_unresponsive(&self, timeout: Duration) -> Vec<Connection> {
         let now = Instant::now();
-        let mut removed = 0;
+        let mut reaped = Vec::new();
 
         for (shard_idx, shard) in self.shards.iter().enumerate() {
             let mut connections = shard.connections.write().await;
-            let inactive: Vec<ConnectionId> = connections
+            let stale: Vec<ConnectionId> = connections
                 .iter()
-                .filter(|(_, conn)| {
-                    now.duration_since(conn.last_activity).as_secs() > max_idle_secs
-                })
+                .filter(|(_, conn)| now.duration_since(conn.last_activity) > timeout)
                 .map(|(id, _)| *id)
                 .collect();
+            if stale.is_empty() {
+                continue;
+            }
 
-            for id in inactive {
-                connections.remove(&id);
-                let mut permits = shard.permits.write().await;
+            let mut permits = shard.permits.write().await;
+            for id in stale {
+                let Some(conn) = connections.remove(&id) else {
+                    continue;
+                };
                 permits.remove(&id);
-                self.active_connections.fetch_sub(1, Ordering::Relaxed);
-                removed += 1;
+                let count = self.active_connections.fetch_sub(1, Ordering::Relaxed) - 1;
+                record_pool_connection_removed(count);
                 tracing::debug!(
                     connection_id = %id,
                     shard = shard_idx,
-                    "Removed inactive connection"
+                    total = count,
+                    "Reaped unresponsive connection"
                 );
+                reaped.push(conn);
             }
         }
 
-        removed
+        reaped
+    }
+
+    /// Clean up inactive connections (optional maintenance)
+    pub async fn cleanup_inactive(&self, max_idle_secs: u64) -> usize {
+        self.reap_unresponsive(Duration::from_secs(max_idle_secs))
+            .await
+            .len()
     }
 }
 
@@ -440,4 +465,24 @@ mod tests {
         assert_eq!(msg.room_id, Some("room_1".to_string()));
         assert_eq!(msg.payload, "hello");
     }
+
+    #[tokio::test]
+    async fn sharded_manager_reaps_unresponsive_connections() {
+        let manager = ShardedConnectionManager::with_max_connections(2);
+        let silent = manager.add_connection("alice".to_string()).await;
+        let alive = manager.add_connection("bob".to_string()).await;
+        assert!(manager.try_add_connection("charlie".to_string()).await.is_none());
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(manager.record_pong(alive).await);
+
+        let reaped = manager.reap_unresponsive(Duration::from_millis(20)).await;
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(reaped[0].id, silent);
+        assert_eq!(manager.connection_count(), 1);
+        assert!(!manager.record_pong(silent).await);
+
+        // The reaped connection's slot is free again.
+        assert!(manager.try_add_connection("charlie".to_string()).await.is_some());
+    }
 }
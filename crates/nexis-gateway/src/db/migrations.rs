@@ -0,0 +1,213 @@
+//! Versioned schema migrations.
+//!
+//! Each [`Migration`] is one SQL file under `crates/nexis-gateway/migrations`,
+//! compiled into the binary. Applied versions are recorded in
+//! `schema_migrations`, so [`run`] only executes the ones a database has not
+//! seen yet. Every migration is written to be safe against databases created
+//! by the older `CREATE TABLE IF NOT EXISTS` bootstrap.
+
+#[cfg(feature = "persistence-sqlx")]
+use sqlx::Row;
+
+use super::{DatabasePool, RepositoryError};
+
+/// One schema change, applied in a transaction of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    /// Position in [`MIGRATIONS`]; versions only ever grow.
+    pub version: i64,
+    /// Short name, taken from the file name.
+    pub description: &'static str,
+    /// Statements to execute.
+    pub sql: &'static str,
+}
+
+/// Every migration, oldest first.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial",
+        sql: include_str!("../../migrations/0001_initial.sql"),
+    },
+    Migration {
+        version: 2,
+        description: "add_tenant_id",
+        sql: include_str!("../../migrations/0002_add_tenant_id.sql"),
+    },
+    Migration {
+        version: 3,
+        description: "member_profiles",
+        sql: include_str!("../../migrations/0003_member_profiles.sql"),
+    },
+    Migration {
+        version: 4,
+        description: "read_markers",
+        sql: include_str!("../../migrations/0004_read_markers.sql"),
+    },
+    Migration {
+        version: 5,
+        description: "audit_log",
+        sql: include_str!("../../migrations/0005_audit_log.sql"),
+    },
+    Migration {
+        version: 6,
+        description: "tenants",
+        sql: include_str!("../../migrations/0006_tenants.sql"),
+    },
+    Migration {
+        version: 7,
+        description: "memberships",
+        sql: include_str!("../../migrations/0007_memberships.sql"),
+    },
+    Migration {
+        version: 8,
+        description: "reactions",
+        sql: include_str!("../../migrations/0008_reactions.sql"),
+    },
+    Migration {
+        version: 9,
+        description: "webhooks",
+        sql: include_str!("../../migrations/0009_webhooks.sql"),
+    },
+    Migration {
+        version: 10,
+        description: "index_outbox",
+        sql: include_str!("../../migrations/0010_index_outbox.sql"),
+    },
+    Migration {
+        version: 11,
+        description: "member_capabilities",
+        sql: include_str!("../../migrations/0011_member_capabilities.sql"),
+    },
+    Migration {
+        version: 12,
+        description: "room_settings",
+        sql: include_str!("../../migrations/0012_room_settings.sql"),
+    },
+    Migration {
+        version: 13,
+        description: "message_feedback",
+        sql: include_str!("../../migrations/0013_message_feedback.sql"),
+    },
+    Migration {
+        version: 14,
+        description: "sanctions",
+        sql: include_str!("../../migrations/0014_sanctions.sql"),
+    },
+    Migration {
+        version: 15,
+        description: "uploads",
+        sql: include_str!("../../migrations/0015_uploads.sql"),
+    },
+];
+
+/// SQL schema for the table recording applied migrations.
+pub const SCHEMA_MIGRATIONS_TABLE_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS schema_migrations (
+    version BIGINT PRIMARY KEY,
+    description TEXT NOT NULL,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);"#;
+
+/// Advisory lock key held while a migration runs, so gateways starting
+/// together do not apply the same migration twice.
+#[cfg(feature = "persistence-sqlx")]
+const MIGRATION_LOCK_KEY: i64 = 0x6e65_7869_735f_6d67;
+
+/// Version a fully migrated database reports.
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Apply every pending migration and return the ones that ran.
+#[cfg(feature = "persistence-sqlx")]
+pub async fn run(pool: &DatabasePool) -> Result<Vec<Migration>, RepositoryError> {
+    sqlx::query(SCHEMA_MIGRATIONS_TABLE_SCHEMA)
+        .execute(pool)
+        .await?;
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS {
+        let mut tx = pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *tx)
+            .await?;
+        let done = sqlx::query("SELECT 1 FROM schema_migrations WHERE version = $1")
+            .bind(migration.version)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        if done {
+            continue;
+        }
+
+        sqlx::raw_sql(migration.sql).execute(&mut *tx).await?;
+        sqlx::query("INSERT INTO schema_migrations (version, description) VALUES ($1, $2)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        tracing::info!(
+            version = migration.version,
+            "Applied migration {}",
+            migration.description
+        );
+        applied.push(*migration);
+    }
+    Ok(applied)
+}
+
+/// Apply every pending migration and return the ones that ran.
+#[cfg(not(feature = "persistence-sqlx"))]
+pub async fn run(_pool: &DatabasePool) -> Result<Vec<Migration>, RepositoryError> {
+    Err(RepositoryError::SqlxDisabled)
+}
+
+/// Highest applied migration version; `None` before the first migration.
+#[cfg(feature = "persistence-sqlx")]
+pub async fn current_version(pool: &DatabasePool) -> Result<Option<i64>, RepositoryError> {
+    let exists: bool = sqlx::query("SELECT to_regclass('schema_migrations') IS NOT NULL AS found")
+        .fetch_one(pool)
+        .await?
+        .get("found");
+    if !exists {
+        return Ok(None);
+    }
+    Ok(
+        sqlx::query("SELECT MAX(version) AS version FROM schema_migrations")
+            .fetch_one(pool)
+            .await?
+            .get("version"),
+    )
+}
+
+/// Highest applied migration version; `None` before the first migration.
+#[cfg(not(feature = "persistence-sqlx"))]
+pub async fn current_version(_pool: &DatabasePool) -> Result<Option<i64>, RepositoryError> {
+    Err(RepositoryError::SqlxDisabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_are_numbered_in_order() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(
+                migration.version,
+                index as i64 + 1,
+                "{}",
+                migration.description
+            );
+            assert!(
+                !migration.sql.trim().is_empty(),
+                "{}",
+                migration.description
+            );
+        }
+        assert_eq!(latest_version(), MIGRATIONS.len() as i64);
+    }
+}
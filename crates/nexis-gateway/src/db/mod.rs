@@ -2,17 +2,18 @@
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use nexis_protocol::{Capabilities, RoomId};
 #[cfg(feature = "persistence-sqlx")]
 use sqlx::{postgres::PgPoolOptions, PgPool, Row};
 use thiserror::Error;
-#[cfg(any(test, feature = "persistence-sqlx"))]
 use uuid::Uuid;
 
-#[cfg(test)]
+use crate::config::NexisConfig;
+
+pub mod migrations;
+
 use std::collections::HashMap;
-#[cfg(test)]
 use std::sync::Arc;
-#[cfg(test)]
 use tokio::sync::RwLock;
 
 /// Database connection pool type used by gateway persistence.
@@ -52,6 +53,20 @@ CREATE TABLE IF NOT EXISTS members (
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );"#;
 
+/// SQL schema for the `read_markers` table.
+pub const READ_MARKERS_TABLE_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS read_markers (
+    room_id TEXT NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+    member_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (room_id, member_id)
+);"#;
+
+/// Index for listing a member's read markers across rooms.
+pub const READ_MARKERS_MEMBER_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_read_markers_member ON read_markers(member_id);"#;
+
 /// Index for room listing by creation time.
 pub const ROOMS_CREATED_AT_INDEX: &str = r#"
 CREATE INDEX IF NOT EXISTS idx_rooms_created_at ON rooms(created_at);"#;
@@ -64,6 +79,29 @@ CREATE INDEX IF NOT EXISTS idx_messages_room_created_at ON messages(room_id, cre
 pub const MEMBERS_EMAIL_INDEX: &str = r#"
 CREATE INDEX IF NOT EXISTS idx_members_email ON members(email);"#;
 
+/// Adds directory profile columns to the `members` table.
+///
+/// Directory entries are keyed by their Nexis member id and need not have an
+/// email, so the column becomes nullable.
+pub const MEMBER_PROFILE_MIGRATIONS: &[&str] = &[
+    "ALTER TABLE members ALTER COLUMN email DROP NOT NULL",
+    "ALTER TABLE members ADD COLUMN IF NOT EXISTS display_name TEXT",
+    "ALTER TABLE members ADD COLUMN IF NOT EXISTS avatar_url TEXT",
+    "ALTER TABLE members ADD COLUMN IF NOT EXISTS public_key TEXT",
+    "ALTER TABLE members ADD COLUMN IF NOT EXISTS capabilities TEXT[] NOT NULL DEFAULT '{}'",
+];
+
+/// Adds tenant ownership columns and tenant lookup indexes to existing tables.
+#[cfg(feature = "multi-tenant")]
+pub const TENANT_SCHEMA_MIGRATIONS: &[&str] = &[
+    "ALTER TABLE rooms ADD COLUMN IF NOT EXISTS tenant_id TEXT",
+    "ALTER TABLE messages ADD COLUMN IF NOT EXISTS tenant_id TEXT",
+    "ALTER TABLE members ADD COLUMN IF NOT EXISTS tenant_id TEXT",
+    "CREATE INDEX IF NOT EXISTS idx_rooms_tenant ON rooms(tenant_id, created_at)",
+    "CREATE INDEX IF NOT EXISTS idx_messages_tenant_room ON messages(tenant_id, room_id, created_at)",
+    "CREATE INDEX IF NOT EXISTS idx_members_tenant ON members(tenant_id)",
+];
+
 /// Error type returned by repository operations.
 #[derive(Debug, Error)]
 pub enum RepositoryError {
@@ -74,6 +112,18 @@ pub enum RepositoryError {
     /// SQLx persistence feature is disabled.
     #[error("persistence-sqlx feature is disabled")]
     SqlxDisabled,
+    /// A record with the same ID already exists.
+    #[error("{0} already exists")]
+    AlreadyExists(String),
+    /// The database has not been migrated to the schema this build expects.
+    #[error(
+        "database schema is at version {current}, expected {expected}; run `nexis-gateway migrate`"
+    )]
+    SchemaOutdated { current: i64, expected: i64 },
+    /// The referenced room does not exist within the caller's tenant.
+    #[cfg(feature = "multi-tenant")]
+    #[error("room {room_id} not found in tenant {tenant_id}")]
+    RoomNotInTenant { tenant_id: String, room_id: String },
 }
 
 /// Domain model for a room.
@@ -117,8 +167,16 @@ pub struct Member {
     pub id: String,
     /// Member type (`human`, `ai`, `agent`, ...).
     pub member_type: String,
-    /// Unique email.
-    pub email: String,
+    /// Unique email, if known.
+    pub email: Option<String>,
+    /// Name shown to other members.
+    pub display_name: Option<String>,
+    /// Profile picture URL.
+    pub avatar_url: Option<String>,
+    /// Base64 X25519 public key used to encrypt messages for this member.
+    pub public_key: Option<String>,
+    /// Capabilities advertised by agent members.
+    pub capabilities: Capabilities,
     /// Creation timestamp.
     pub created_at: DateTime<Utc>,
     /// Tenant ID (multi-tenant only).
@@ -126,6 +184,146 @@ pub struct Member {
     pub tenant_id: Option<String>,
 }
 
+impl Member {
+    /// Directory entry for `id` with an empty profile.
+    pub fn new(id: impl Into<String>, member_type: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            member_type: member_type.into(),
+            email: None,
+            display_name: None,
+            avatar_url: None,
+            public_key: None,
+            capabilities: Capabilities::default(),
+            created_at: Utc::now(),
+            #[cfg(feature = "multi-tenant")]
+            tenant_id: None,
+        }
+    }
+}
+
+/// Last message a member has read in a room.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadMarker {
+    /// Room the marker belongs to.
+    pub room_id: String,
+    /// Member who read the messages.
+    pub member_id: String,
+    /// Last read message ID.
+    pub message_id: String,
+    /// When the marker last moved.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Settings of one room beyond its name and topic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoomSettingsRecord {
+    /// Room the settings belong to.
+    pub room_id: String,
+    /// Settings document as written by the gateway.
+    pub settings: serde_json::Value,
+    /// When the settings last changed.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A member's place in a room.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MembershipRecord {
+    /// Room the member belongs to.
+    pub room_id: String,
+    /// Member invited to or joined into the room.
+    pub member_id: String,
+    /// When the member was added.
+    pub joined_at: DateTime<Utc>,
+}
+
+/// A ban or mute on a member of a room.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanctionRecord {
+    /// Room the sanction applies in.
+    pub room_id: String,
+    /// Sanctioned member.
+    pub member_id: String,
+    /// `ban` or `mute`.
+    pub kind: String,
+    /// Reason shown to the room.
+    pub reason: Option<String>,
+    /// Admin, or the spam guard, who imposed it.
+    pub created_by: String,
+    /// When it was imposed.
+    pub created_at: DateTime<Utc>,
+    /// When it lapses; unset for sanctions that last until lifted.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Metadata of an uploaded file; the blob itself is kept under `id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadRecord {
+    /// Upload id messages attach it by.
+    pub id: String,
+    /// File name shown on downloads.
+    pub file_name: String,
+    /// Content type the file is served with.
+    pub mime_type: String,
+    /// Size in bytes.
+    pub size: u64,
+    /// Member who uploaded the file.
+    pub uploaded_by: String,
+    /// Tenant of the uploader.
+    pub tenant_id: Option<String>,
+    /// Room the file was first posted in; unset until it is attached.
+    pub room_id: Option<String>,
+    /// When the file was uploaded.
+    pub created_at: DateTime<Utc>,
+}
+
+/// One member's rating of an AI message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedbackRecord {
+    /// Rated message.
+    pub message_id: String,
+    /// Room of the message.
+    pub room_id: String,
+    /// Member who rated the message.
+    pub member_id: String,
+    /// `up` or `down`.
+    pub rating: String,
+    /// Optional free-text comment.
+    pub comment: Option<String>,
+    /// When the member last rated the message.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Stored message still waiting to be handed to the indexing pipeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxEntry {
+    /// Message the entry was written with.
+    pub message_id: String,
+    /// Room of the message.
+    pub room_id: String,
+    /// Tenant owning the message, if any.
+    pub tenant_id: Option<String>,
+    /// Text to index.
+    pub content: String,
+    /// When the message was stored.
+    pub created_at: DateTime<Utc>,
+}
+
+impl OutboxEntry {
+    fn for_message(message: &Message) -> Self {
+        Self {
+            message_id: message.id.clone(),
+            room_id: message.room_id.clone(),
+            #[cfg(feature = "multi-tenant")]
+            tenant_id: message.tenant_id.clone(),
+            #[cfg(not(feature = "multi-tenant"))]
+            tenant_id: None,
+            content: message.content.clone(),
+            created_at: message.created_at,
+        }
+    }
+}
+
 /// Create a PostgreSQL connection pool for gateway persistence.
 #[cfg(feature = "persistence-sqlx")]
 pub async fn init_pool(database_url: &str) -> Result<DatabasePool, RepositoryError> {
@@ -141,26 +339,167 @@ pub async fn init_pool(_database_url: &str) -> Result<DatabasePool, RepositoryEr
     Err(RepositoryError::SqlxDisabled)
 }
 
-/// Initialize required tables if they do not exist.
+/// Bring the schema up to date by applying every pending migration.
 #[cfg(feature = "persistence-sqlx")]
 pub async fn initialize_schema(pool: &DatabasePool) -> Result<(), RepositoryError> {
-    sqlx::query(ROOMS_TABLE_SCHEMA).execute(pool).await?;
-    sqlx::query(MESSAGES_TABLE_SCHEMA).execute(pool).await?;
-    sqlx::query(MEMBERS_TABLE_SCHEMA).execute(pool).await?;
-    sqlx::query(ROOMS_CREATED_AT_INDEX).execute(pool).await?;
-    sqlx::query(MESSAGES_ROOM_CREATED_AT_INDEX)
-        .execute(pool)
-        .await?;
-    sqlx::query(MEMBERS_EMAIL_INDEX).execute(pool).await?;
+    migrations::run(pool).await?;
     Ok(())
 }
 
-/// Initialize required tables if they do not exist.
+/// Bring the schema up to date by applying every pending migration.
 #[cfg(not(feature = "persistence-sqlx"))]
 pub async fn initialize_schema(_pool: &DatabasePool) -> Result<(), RepositoryError> {
     Err(RepositoryError::SqlxDisabled)
 }
 
+/// Room and message repositories the gateway writes through to.
+#[derive(Clone)]
+pub struct Storage {
+    /// Room records.
+    pub rooms: Arc<dyn RoomRepository>,
+    /// Message records.
+    pub messages: Arc<dyn MessageRepository>,
+    /// Every stored room with its newest messages, read back when the
+    /// storage was opened.
+    pub restored: Vec<(Room, Vec<Message>)>,
+    /// Pool behind the repositories; unset when they live in memory.
+    pub database: Option<DatabasePool>,
+    /// Index outbox filled by `messages`, when `database.index_outbox` is on.
+    pub outbox: Option<Arc<dyn IndexOutbox>>,
+    /// Room settings records.
+    pub room_settings: Arc<dyn RoomSettingsRepository>,
+    /// Every stored room's settings, read back when the storage was opened.
+    pub restored_settings: Vec<RoomSettingsRecord>,
+    /// Room memberships.
+    pub memberships: Arc<dyn MembershipRepository>,
+    /// Every stored membership, read back when the storage was opened.
+    pub restored_memberships: Vec<MembershipRecord>,
+    /// Bans and mutes.
+    pub sanctions: Arc<dyn SanctionRepository>,
+    /// Every stored ban and mute, read back when the storage was opened.
+    pub restored_sanctions: Vec<SanctionRecord>,
+    /// Upload metadata.
+    pub uploads: Arc<dyn UploadRepository>,
+    /// Every stored upload, read back when the storage was opened.
+    pub restored_uploads: Vec<UploadRecord>,
+    /// Ratings of AI messages.
+    pub feedback: Arc<dyn FeedbackRepository>,
+    /// Every stored rating, read back when the storage was opened.
+    pub restored_feedback: Vec<FeedbackRecord>,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            rooms: Arc::new(InMemoryRoomRepository::new()),
+            messages: Arc::new(InMemoryMessageRepository::new()),
+            restored: Vec::new(),
+            database: None,
+            outbox: None,
+            room_settings: Arc::new(InMemoryRoomSettingsRepository::new()),
+            restored_settings: Vec::new(),
+            memberships: Arc::new(InMemoryMembershipRepository::new()),
+            restored_memberships: Vec::new(),
+            sanctions: Arc::new(InMemorySanctionRepository::new()),
+            restored_sanctions: Vec::new(),
+            uploads: Arc::new(InMemoryUploadRepository::new()),
+            restored_uploads: Vec::new(),
+            feedback: Arc::new(InMemoryFeedbackRepository::new()),
+            restored_feedback: Vec::new(),
+        }
+    }
+}
+
+impl Storage {
+    /// Open the storage selected by `[database]`: PostgreSQL when a URL is
+    /// set and the `persistence-sqlx` feature is enabled, process memory
+    /// otherwise.
+    pub async fn from_config(config: &NexisConfig) -> Result<Self, RepositoryError> {
+        let database = &config.database;
+        #[cfg(feature = "persistence-sqlx")]
+        if let Some(url) = database.url.as_deref() {
+            let pool = PgPoolOptions::new()
+                .max_connections(database.max_connections)
+                .connect(url)
+                .await?;
+            if database.auto_migrate {
+                initialize_schema(&pool).await?;
+            } else {
+                let current = migrations::current_version(&pool).await?.unwrap_or(0);
+                let expected = migrations::latest_version();
+                if current < expected {
+                    return Err(RepositoryError::SchemaOutdated { current, expected });
+                }
+            }
+            let mut messages = SqlxMessageRepository::new(pool.clone());
+            if database.index_outbox {
+                messages = messages.with_index_outbox();
+            }
+            let messages = Arc::new(messages);
+            let mut storage = Self::restore(
+                Arc::new(SqlxRoomRepository::new(pool.clone())),
+                messages.clone(),
+                database.restore_messages,
+            )
+            .await?;
+            let room_settings = Arc::new(SqlxRoomSettingsRepository::new(pool.clone()));
+            storage.restored_settings = room_settings.list().await?;
+            storage.room_settings = room_settings;
+            let memberships = Arc::new(SqlxMembershipRepository::new(pool.clone()));
+            storage.restored_memberships = memberships.list().await?;
+            storage.memberships = memberships;
+            let sanctions = Arc::new(SqlxSanctionRepository::new(pool.clone()));
+            storage.restored_sanctions = sanctions.list().await?;
+            storage.sanctions = sanctions;
+            let uploads = Arc::new(SqlxUploadRepository::new(pool.clone()));
+            storage.restored_uploads = uploads.list().await?;
+            storage.uploads = uploads;
+            let feedback = Arc::new(SqlxFeedbackRepository::new(pool.clone()));
+            storage.restored_feedback = feedback.list().await?;
+            storage.feedback = feedback;
+            storage.database = Some(pool);
+            if database.index_outbox {
+                storage.outbox = Some(messages);
+            }
+            return Ok(storage);
+        }
+        #[cfg(not(feature = "persistence-sqlx"))]
+        if database.url.is_some() {
+            tracing::warn!(
+                "database.url is set but persistence-sqlx is disabled; rooms stay in memory"
+            );
+        }
+        if database.index_outbox {
+            let messages = Arc::new(InMemoryMessageRepository::new().with_index_outbox());
+            return Ok(Self {
+                messages: messages.clone(),
+                outbox: Some(messages),
+                ..Self::default()
+            });
+        }
+        Ok(Self::default())
+    }
+
+    /// Read back every room and the newest `per_room` messages of each.
+    pub async fn restore(
+        rooms: Arc<dyn RoomRepository>,
+        messages: Arc<dyn MessageRepository>,
+        per_room: usize,
+    ) -> Result<Self, RepositoryError> {
+        let mut restored = Vec::new();
+        for room in rooms.list().await? {
+            let recent = messages.list_recent_by_room(&room.id, per_room).await?;
+            restored.push((room, recent));
+        }
+        Ok(Self {
+            rooms,
+            messages,
+            restored,
+            ..Self::default()
+        })
+    }
+}
+
 /// Persistence operations for rooms.
 #[async_trait]
 pub trait RoomRepository: Send + Sync {
@@ -170,6 +509,10 @@ pub trait RoomRepository: Send + Sync {
     async fn get(&self, id: &str) -> Result<Option<Room>, RepositoryError>;
     /// List all rooms.
     async fn list(&self) -> Result<Vec<Room>, RepositoryError>;
+    /// Persist `room` under its own ID, failing if the ID is taken.
+    async fn insert(&self, room: &Room) -> Result<(), RepositoryError>;
+    /// Remove a room. Returns `false` if absent.
+    async fn delete(&self, id: &str) -> Result<bool, RepositoryError>;
 
     /// Create room with tenant context (multi-tenant).
     #[cfg(feature = "multi-tenant")]
@@ -201,6 +544,31 @@ pub trait MessageRepository: Send + Sync {
     async fn get(&self, id: &str) -> Result<Option<Message>, RepositoryError>;
     /// List all messages in a room.
     async fn list_by_room(&self, room_id: &str) -> Result<Vec<Message>, RepositoryError>;
+    /// Persist `message` under its own ID, failing if the ID is taken.
+    async fn insert(&self, message: &Message) -> Result<(), RepositoryError>;
+    /// The `limit` newest messages in a room, oldest first.
+    async fn list_recent_by_room(
+        &self,
+        room_id: &str,
+        limit: usize,
+    ) -> Result<Vec<Message>, RepositoryError>;
+    /// Drop every message of a deleted room.
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError>;
+    /// Drop the messages `ids`, returning how many existed.
+    async fn delete_messages(&self, ids: &[String]) -> Result<usize, RepositoryError>;
+    /// Clear the content of the messages `ids`, keeping them in place.
+    /// Returns how many existed.
+    async fn redact_messages(&self, ids: &[String]) -> Result<usize, RepositoryError>;
+    /// Drop, or clear when `redact` is set, the room's messages created
+    /// before `cutoff` and the oldest beyond its newest `keep`, returning
+    /// their ids. Messages already cleared are neither counted nor touched.
+    async fn prune_room(
+        &self,
+        room_id: &str,
+        cutoff: Option<DateTime<Utc>>,
+        keep: Option<usize>,
+        redact: bool,
+    ) -> Result<Vec<String>, RepositoryError>;
 
     /// Create message with tenant context (multi-tenant).
     #[cfg(feature = "multi-tenant")]
@@ -234,6 +602,14 @@ pub trait MemberRepository: Send + Sync {
     async fn create(&self, member_type: &str, email: &str) -> Result<Member, RepositoryError>;
     /// Load one member by ID.
     async fn get(&self, id: &str) -> Result<Option<Member>, RepositoryError>;
+    /// Persist `member` under its own ID, failing if the ID is taken.
+    async fn insert(&self, member: &Member) -> Result<(), RepositoryError>;
+    /// Replace the profile of an existing member. Returns `false` if absent.
+    async fn update(&self, member: &Member) -> Result<bool, RepositoryError>;
+    /// Remove a member. Returns `false` if absent.
+    async fn delete(&self, id: &str) -> Result<bool, RepositoryError>;
+    /// List all members ordered by ID.
+    async fn list(&self) -> Result<Vec<Member>, RepositoryError>;
 
     /// Create member with tenant context (multi-tenant).
     #[cfg(feature = "multi-tenant")]
@@ -252,6 +628,111 @@ pub trait MemberRepository: Send + Sync {
     ) -> Result<Option<Member>, RepositoryError>;
 }
 
+/// Persistence operations for read markers.
+#[async_trait]
+pub trait ReadMarkerRepository: Send + Sync {
+    /// Insert or move the marker for `(room_id, member_id)`.
+    async fn set(&self, marker: &ReadMarker) -> Result<(), RepositoryError>;
+    /// Load the marker of one member in one room.
+    async fn get(
+        &self,
+        room_id: &str,
+        member_id: &str,
+    ) -> Result<Option<ReadMarker>, RepositoryError>;
+    /// List a member's markers across all rooms.
+    async fn list_for_member(&self, member_id: &str) -> Result<Vec<ReadMarker>, RepositoryError>;
+    /// Drop every marker of a deleted room.
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError>;
+}
+
+/// Persistence operations for room settings.
+#[async_trait]
+pub trait RoomSettingsRepository: Send + Sync {
+    /// Insert or replace the settings of a room.
+    async fn set(&self, record: &RoomSettingsRecord) -> Result<(), RepositoryError>;
+    /// Load the settings of one room.
+    async fn get(&self, room_id: &str) -> Result<Option<RoomSettingsRecord>, RepositoryError>;
+    /// List the settings of every room that has any.
+    async fn list(&self) -> Result<Vec<RoomSettingsRecord>, RepositoryError>;
+    /// Drop the settings of a deleted room.
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError>;
+}
+
+/// Persistence operations for room memberships.
+#[async_trait]
+pub trait MembershipRepository: Send + Sync {
+    /// Add a member to a room; `false` when already a member.
+    async fn add(&self, record: &MembershipRecord) -> Result<bool, RepositoryError>;
+    /// Take a member out of a room; `false` when not a member.
+    async fn remove(&self, room_id: &str, member_id: &str) -> Result<bool, RepositoryError>;
+    /// Every membership of every room, oldest first within a room.
+    async fn list(&self) -> Result<Vec<MembershipRecord>, RepositoryError>;
+    /// Drop the memberships of a deleted room.
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError>;
+}
+
+/// Persistence operations for bans and mutes.
+#[async_trait]
+pub trait SanctionRepository: Send + Sync {
+    /// Store a sanction, replacing the member's sanction of the same kind.
+    async fn set(&self, record: &SanctionRecord) -> Result<(), RepositoryError>;
+    /// Drop the member's `kind` sanction; `false` when there was none.
+    async fn remove(
+        &self,
+        room_id: &str,
+        member_id: &str,
+        kind: &str,
+    ) -> Result<bool, RepositoryError>;
+    /// Every sanction of every room, oldest first within a room.
+    async fn list(&self) -> Result<Vec<SanctionRecord>, RepositoryError>;
+    /// Drop the sanctions of a deleted room.
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError>;
+}
+
+/// Persistence operations for upload metadata.
+#[async_trait]
+pub trait UploadRepository: Send + Sync {
+    /// Store a new upload.
+    async fn insert(&self, record: &UploadRecord) -> Result<(), RepositoryError>;
+    /// Record the room an upload was posted in; `false` when it is unknown or
+    /// was already posted.
+    async fn set_room(&self, id: &str, room_id: &str) -> Result<bool, RepositoryError>;
+    /// Every stored upload, oldest first.
+    async fn list(&self) -> Result<Vec<UploadRecord>, RepositoryError>;
+    /// Drop the uploads posted in a deleted room.
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError>;
+}
+
+/// Persistence operations for ratings of AI messages.
+#[async_trait]
+pub trait FeedbackRepository: Send + Sync {
+    /// Insert or replace a member's rating of a message.
+    async fn set(&self, record: &FeedbackRecord) -> Result<(), RepositoryError>;
+    /// Remove a member's rating of a message; `false` when there was none.
+    async fn delete(&self, message_id: &str, member_id: &str) -> Result<bool, RepositoryError>;
+    /// Every rating of one message, oldest first.
+    async fn list_for_message(
+        &self,
+        message_id: &str,
+    ) -> Result<Vec<FeedbackRecord>, RepositoryError>;
+    /// Every rating, oldest first.
+    async fn list(&self) -> Result<Vec<FeedbackRecord>, RepositoryError>;
+    /// Drop the ratings of a deleted room.
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError>;
+}
+
+/// Messages stored but not yet handed to the indexing pipeline.
+///
+/// Entries are written together with their message, so a crash between
+/// storing and indexing leaves the entry behind instead of losing it.
+#[async_trait]
+pub trait IndexOutbox: Send + Sync {
+    /// Oldest entries first, at most `limit`.
+    async fn pending(&self, limit: usize) -> Result<Vec<OutboxEntry>, RepositoryError>;
+    /// Remove entries that were handed off.
+    async fn acknowledge(&self, message_ids: &[String]) -> Result<(), RepositoryError>;
+}
+
 /// SQLx/PostgreSQL implementation of [`RoomRepository`].
 #[cfg(feature = "persistence-sqlx")]
 #[derive(Debug, Clone)]
@@ -267,11 +748,23 @@ impl SqlxRoomRepository {
     }
 }
 
+#[cfg(feature = "persistence-sqlx")]
+fn room_from_row(row: &sqlx::postgres::PgRow) -> Room {
+    Room {
+        id: row.get("id"),
+        name: row.get("name"),
+        topic: row.get("topic"),
+        created_at: row.get("created_at"),
+        #[cfg(feature = "multi-tenant")]
+        tenant_id: row.try_get("tenant_id").unwrap_or(None),
+    }
+}
+
 #[cfg(feature = "persistence-sqlx")]
 #[async_trait]
 impl RoomRepository for SqlxRoomRepository {
     async fn create(&self, name: &str, topic: Option<&str>) -> Result<Room, RepositoryError> {
-        let id = format!("room_{}", Uuid::new_v4().simple());
+        let id = RoomId::generate().to_string();
         let row = sqlx::query(
             "INSERT INTO rooms (id, name, topic) VALUES ($1, $2, $3) RETURNING id, name, topic, created_at",
         )
@@ -308,22 +801,54 @@ impl RoomRepository for SqlxRoomRepository {
     }
 
     async fn list(&self) -> Result<Vec<Room>, RepositoryError> {
-        let rows =
-            sqlx::query("SELECT id, name, topic, created_at FROM rooms ORDER BY created_at ASC")
-                .fetch_all(&self.pool)
-                .await?;
+        #[cfg(feature = "multi-tenant")]
+        let columns = "id, name, topic, created_at, tenant_id";
+        #[cfg(not(feature = "multi-tenant"))]
+        let columns = "id, name, topic, created_at";
+        let rows = sqlx::query(&format!(
+            "SELECT {columns} FROM rooms ORDER BY created_at ASC"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(rows
-            .into_iter()
-            .map(|row| Room {
-                id: row.get("id"),
-                name: row.get("name"),
-                topic: row.get("topic"),
-                created_at: row.get("created_at"),
-                #[cfg(feature = "multi-tenant")]
-                tenant_id: None,
-            })
-            .collect())
+        Ok(rows.iter().map(room_from_row).collect())
+    }
+
+    async fn insert(&self, room: &Room) -> Result<(), RepositoryError> {
+        #[cfg(feature = "multi-tenant")]
+        let tenant_id = room.tenant_id.as_deref();
+        #[cfg(not(feature = "multi-tenant"))]
+        let tenant_id: Option<&str> = None;
+        let query = if tenant_id.is_some() {
+            "INSERT INTO rooms (id, name, topic, created_at, tenant_id) VALUES ($1, $2, $3, $4, $5)"
+        } else {
+            "INSERT INTO rooms (id, name, topic, created_at) VALUES ($1, $2, $3, $4)"
+        };
+        let mut query = sqlx::query(query)
+            .bind(&room.id)
+            .bind(&room.name)
+            .bind(&room.topic)
+            .bind(room.created_at);
+        if let Some(tenant_id) = tenant_id {
+            query = query.bind(tenant_id);
+        }
+
+        match query.execute(&self.pool).await {
+            Ok(_) => Ok(()),
+            Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+                Err(RepositoryError::AlreadyExists(format!("room {}", room.id)))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, RepositoryError> {
+        let result = sqlx::query("DELETE FROM rooms WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
     }
 
     #[cfg(feature = "multi-tenant")]
@@ -333,7 +858,7 @@ impl RoomRepository for SqlxRoomRepository {
         name: &str,
         topic: Option<&str>,
     ) -> Result<Room, RepositoryError> {
-        let id = format!("room_{}", Uuid::new_v4().simple());
+        let id = RoomId::generate().to_string();
         let row = sqlx::query(
             "INSERT INTO rooms (id, name, topic, tenant_id) VALUES ($1, $2, $3, $4) RETURNING id, name, topic, created_at, tenant_id",
         )
@@ -399,13 +924,37 @@ impl RoomRepository for SqlxRoomRepository {
 #[derive(Debug, Clone)]
 pub struct SqlxMessageRepository {
     pool: DatabasePool,
+    index_outbox: bool,
 }
 
 #[cfg(feature = "persistence-sqlx")]
 impl SqlxMessageRepository {
     /// Build a repository over an existing pool.
     pub fn new(pool: DatabasePool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            index_outbox: false,
+        }
+    }
+
+    /// Write an `index_outbox` row in the same transaction as every
+    /// inserted message.
+    pub fn with_index_outbox(mut self) -> Self {
+        self.index_outbox = true;
+        self
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+fn message_from_row(row: &sqlx::postgres::PgRow) -> Message {
+    Message {
+        id: row.get("id"),
+        room_id: row.get("room_id"),
+        sender_id: row.get("sender_id"),
+        content: row.get("content"),
+        created_at: row.get("created_at"),
+        #[cfg(feature = "multi-tenant")]
+        tenant_id: row.try_get("tenant_id").unwrap_or(None),
     }
 }
 
@@ -481,6 +1030,133 @@ impl MessageRepository for SqlxMessageRepository {
             .collect())
     }
 
+    async fn insert(&self, message: &Message) -> Result<(), RepositoryError> {
+        #[cfg(feature = "multi-tenant")]
+        let tenant_id = message.tenant_id.as_deref();
+        #[cfg(not(feature = "multi-tenant"))]
+        let tenant_id: Option<&str> = None;
+        let query = if tenant_id.is_some() {
+            "INSERT INTO messages (id, room_id, sender_id, content, created_at, tenant_id) VALUES ($1, $2, $3, $4, $5, $6)"
+        } else {
+            "INSERT INTO messages (id, room_id, sender_id, content, created_at) VALUES ($1, $2, $3, $4, $5)"
+        };
+        let mut query = sqlx::query(query)
+            .bind(&message.id)
+            .bind(&message.room_id)
+            .bind(&message.sender_id)
+            .bind(&message.content)
+            .bind(message.created_at);
+        if let Some(tenant_id) = tenant_id {
+            query = query.bind(tenant_id);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        match query.execute(&mut *tx).await {
+            Ok(_) => {}
+            Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+                return Err(RepositoryError::AlreadyExists(format!(
+                    "message {}",
+                    message.id
+                )))
+            }
+            Err(err) => return Err(err.into()),
+        }
+        if self.index_outbox {
+            sqlx::query(
+                "INSERT INTO index_outbox (message_id, room_id, tenant_id, content, created_at) VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(&message.id)
+            .bind(&message.room_id)
+            .bind(tenant_id)
+            .bind(&message.content)
+            .bind(message.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list_recent_by_room(
+        &self,
+        room_id: &str,
+        limit: usize,
+    ) -> Result<Vec<Message>, RepositoryError> {
+        #[cfg(feature = "multi-tenant")]
+        let columns = "id, room_id, sender_id, content, created_at, tenant_id";
+        #[cfg(not(feature = "multi-tenant"))]
+        let columns = "id, room_id, sender_id, content, created_at";
+        let rows = sqlx::query(&format!(
+            "SELECT * FROM (SELECT {columns} FROM messages WHERE room_id = $1 ORDER BY created_at DESC LIMIT $2) recent ORDER BY created_at ASC"
+        ))
+        .bind(room_id)
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(message_from_row).collect())
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        sqlx::query("DELETE FROM messages WHERE room_id = $1")
+            .bind(room_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_messages(&self, ids: &[String]) -> Result<usize, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        if self.index_outbox {
+            sqlx::query("DELETE FROM index_outbox WHERE message_id = ANY($1)")
+                .bind(ids)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let deleted = sqlx::query("DELETE FROM messages WHERE id = ANY($1)")
+            .bind(ids)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted as usize)
+    }
+
+    async fn redact_messages(&self, ids: &[String]) -> Result<usize, RepositoryError> {
+        let redacted = sqlx::query("UPDATE messages SET content = '' WHERE id = ANY($1)")
+            .bind(ids)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(redacted as usize)
+    }
+
+    async fn prune_room(
+        &self,
+        room_id: &str,
+        cutoff: Option<DateTime<Utc>>,
+        keep: Option<usize>,
+        redact: bool,
+    ) -> Result<Vec<String>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT id FROM messages WHERE room_id = $1 AND content <> '' AND (created_at < $2 OR ($3::BIGINT IS NOT NULL AND id NOT IN (SELECT id FROM messages WHERE room_id = $1 AND content <> '' ORDER BY created_at DESC LIMIT $3))) ORDER BY created_at ASC",
+        )
+        .bind(room_id)
+        .bind(cutoff)
+        .bind(keep.map(|keep| i64::try_from(keep).unwrap_or(i64::MAX)))
+        .fetch_all(&self.pool)
+        .await?;
+        let ids: Vec<String> = rows.iter().map(|row| row.get("id")).collect();
+        if !ids.is_empty() {
+            if redact {
+                self.redact_messages(&ids).await?;
+            } else {
+                self.delete_messages(&ids).await?;
+            }
+        }
+        Ok(ids)
+    }
+
     #[cfg(feature = "multi-tenant")]
     async fn create_tenant(
         &self,
@@ -490,16 +1166,21 @@ impl MessageRepository for SqlxMessageRepository {
         content: &str,
     ) -> Result<Message, RepositoryError> {
         let id = format!("msg_{}", Uuid::new_v4().simple());
+        // Only insert when the room belongs to the same tenant.
         let row = sqlx::query(
-            "INSERT INTO messages (id, room_id, sender_id, content, tenant_id) VALUES ($1, $2, $3, $4, $5) RETURNING id, room_id, sender_id, content, created_at, tenant_id",
+            "INSERT INTO messages (id, room_id, sender_id, content, tenant_id) SELECT $1, $2, $3, $4, $5 WHERE EXISTS (SELECT 1 FROM rooms WHERE id = $2 AND tenant_id = $5) RETURNING id, room_id, sender_id, content, created_at, tenant_id",
         )
         .bind(&id)
         .bind(room_id)
         .bind(sender_id)
         .bind(content)
         .bind(tenant_id)
-        .fetch_one(&self.pool)
-        .await?;
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| RepositoryError::RoomNotInTenant {
+            tenant_id: tenant_id.to_string(),
+            room_id: room_id.to_string(),
+        })?;
 
         Ok(Message {
             id: row.get("id"),
@@ -563,6 +1244,38 @@ impl MessageRepository for SqlxMessageRepository {
     }
 }
 
+#[cfg(feature = "persistence-sqlx")]
+#[async_trait]
+impl IndexOutbox for SqlxMessageRepository {
+    async fn pending(&self, limit: usize) -> Result<Vec<OutboxEntry>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT message_id, room_id, tenant_id, content, created_at FROM index_outbox ORDER BY created_at ASC LIMIT $1",
+        )
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| OutboxEntry {
+                message_id: row.get("message_id"),
+                room_id: row.get("room_id"),
+                tenant_id: row.get("tenant_id"),
+                content: row.get("content"),
+                created_at: row.get("created_at"),
+            })
+            .collect())
+    }
+
+    async fn acknowledge(&self, message_ids: &[String]) -> Result<(), RepositoryError> {
+        sqlx::query("DELETE FROM index_outbox WHERE message_id = ANY($1)")
+            .bind(message_ids)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
 /// SQLx/PostgreSQL implementation of [`MemberRepository`].
 #[cfg(feature = "persistence-sqlx")]
 #[derive(Debug, Clone)]
@@ -578,44 +1291,125 @@ impl SqlxMemberRepository {
     }
 }
 
+#[cfg(feature = "persistence-sqlx")]
+const MEMBER_COLUMNS: &str =
+    r#"id, "type", email, display_name, avatar_url, public_key, capabilities, created_at"#;
+
+#[cfg(feature = "persistence-sqlx")]
+fn member_from_row(row: &sqlx::postgres::PgRow) -> Member {
+    Member {
+        id: row.get("id"),
+        member_type: row.get("type"),
+        email: row.get("email"),
+        display_name: row.get("display_name"),
+        avatar_url: row.get("avatar_url"),
+        public_key: row.get("public_key"),
+        capabilities: row
+            .get::<sqlx::types::Json<Capabilities>, _>("capabilities")
+            .0,
+        created_at: row.get("created_at"),
+        #[cfg(feature = "multi-tenant")]
+        tenant_id: row.try_get("tenant_id").unwrap_or(None),
+    }
+}
+
 #[cfg(feature = "persistence-sqlx")]
 #[async_trait]
 impl MemberRepository for SqlxMemberRepository {
     async fn create(&self, member_type: &str, email: &str) -> Result<Member, RepositoryError> {
         let id = format!("member_{}", Uuid::new_v4().simple());
-        let row = sqlx::query(
-            r#"INSERT INTO members (id, "type", email) VALUES ($1, $2, $3) RETURNING id, "type", email, created_at"#,
-        )
+        let row = sqlx::query(&format!(
+            r#"INSERT INTO members (id, "type", email) VALUES ($1, $2, $3) RETURNING {MEMBER_COLUMNS}"#
+        ))
         .bind(&id)
         .bind(member_type)
         .bind(email)
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(Member {
-            id: row.get("id"),
-            member_type: row.get("type"),
-            email: row.get("email"),
-            created_at: row.get("created_at"),
-            #[cfg(feature = "multi-tenant")]
-            tenant_id: None,
-        })
+        Ok(member_from_row(&row))
     }
 
     async fn get(&self, id: &str) -> Result<Option<Member>, RepositoryError> {
-        let row = sqlx::query(r#"SELECT id, "type", email, created_at FROM members WHERE id = $1"#)
+        let row = sqlx::query(&format!(
+            "SELECT {MEMBER_COLUMNS} FROM members WHERE id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(member_from_row))
+    }
+
+    async fn insert(&self, member: &Member) -> Result<(), RepositoryError> {
+        #[cfg(feature = "multi-tenant")]
+        let tenant_id = member.tenant_id.as_deref();
+        #[cfg(not(feature = "multi-tenant"))]
+        let tenant_id: Option<&str> = None;
+        let query = if tenant_id.is_some() {
+            r#"INSERT INTO members (id, "type", email, display_name, avatar_url, public_key, capabilities, created_at, tenant_id)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#
+        } else {
+            r#"INSERT INTO members (id, "type", email, display_name, avatar_url, public_key, capabilities, created_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#
+        };
+        let mut query = sqlx::query(query)
+            .bind(&member.id)
+            .bind(&member.member_type)
+            .bind(&member.email)
+            .bind(&member.display_name)
+            .bind(&member.avatar_url)
+            .bind(&member.public_key)
+            .bind(sqlx::types::Json(&member.capabilities))
+            .bind(member.created_at);
+        if let Some(tenant_id) = tenant_id {
+            query = query.bind(tenant_id);
+        }
+
+        match query.execute(&self.pool).await {
+            Ok(_) => Ok(()),
+            Err(sqlx::Error::Database(err)) if err.is_unique_violation() => Err(
+                RepositoryError::AlreadyExists(format!("member {}", member.id)),
+            ),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn update(&self, member: &Member) -> Result<bool, RepositoryError> {
+        let result = sqlx::query(
+            "UPDATE members SET email = $2, display_name = $3, avatar_url = $4, public_key = $5, capabilities = $6 WHERE id = $1",
+        )
+        .bind(&member.id)
+        .bind(&member.email)
+        .bind(&member.display_name)
+        .bind(&member.avatar_url)
+        .bind(&member.public_key)
+        .bind(sqlx::types::Json(&member.capabilities))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, RepositoryError> {
+        let result = sqlx::query("DELETE FROM members WHERE id = $1")
             .bind(id)
-            .fetch_optional(&self.pool)
+            .execute(&self.pool)
             .await?;
 
-        Ok(row.map(|row| Member {
-            id: row.get("id"),
-            member_type: row.get("type"),
-            email: row.get("email"),
-            created_at: row.get("created_at"),
-            #[cfg(feature = "multi-tenant")]
-            tenant_id: None,
-        }))
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list(&self) -> Result<Vec<Member>, RepositoryError> {
+        #[cfg(feature = "multi-tenant")]
+        let columns = format!("{MEMBER_COLUMNS}, tenant_id");
+        #[cfg(not(feature = "multi-tenant"))]
+        let columns = MEMBER_COLUMNS;
+        let rows = sqlx::query(&format!("SELECT {columns} FROM members ORDER BY id"))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(member_from_row).collect())
     }
 
     #[cfg(feature = "multi-tenant")]
@@ -626,9 +1420,9 @@ impl MemberRepository for SqlxMemberRepository {
         email: &str,
     ) -> Result<Member, RepositoryError> {
         let id = format!("member_{}", Uuid::new_v4().simple());
-        let row = sqlx::query(
-            r#"INSERT INTO members (id, "type", email, tenant_id) VALUES ($1, $2, $3, $4) RETURNING id, "type", email, created_at, tenant_id"#,
-        )
+        let row = sqlx::query(&format!(
+            r#"INSERT INTO members (id, "type", email, tenant_id) VALUES ($1, $2, $3, $4) RETURNING {MEMBER_COLUMNS}, tenant_id"#
+        ))
         .bind(&id)
         .bind(member_type)
         .bind(email)
@@ -636,13 +1430,7 @@ impl MemberRepository for SqlxMemberRepository {
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(Member {
-            id: row.get("id"),
-            member_type: row.get("type"),
-            email: row.get("email"),
-            created_at: row.get("created_at"),
-            tenant_id: row.get("tenant_id"),
-        })
+        Ok(member_from_row(&row))
     }
 
     #[cfg(feature = "multi-tenant")]
@@ -651,36 +1439,36 @@ impl MemberRepository for SqlxMemberRepository {
         tenant_id: &str,
         id: &str,
     ) -> Result<Option<Member>, RepositoryError> {
-        let row = sqlx::query(
-            r#"SELECT id, "type", email, created_at, tenant_id FROM members WHERE id = $1 AND tenant_id = $2"#,
-        )
+        let row = sqlx::query(&format!(
+            "SELECT {MEMBER_COLUMNS}, tenant_id FROM members WHERE id = $1 AND tenant_id = $2"
+        ))
         .bind(id)
         .bind(tenant_id)
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(row.map(|row| Member {
-            id: row.get("id"),
-            member_type: row.get("type"),
-            email: row.get("email"),
-            created_at: row.get("created_at"),
-            tenant_id: row.get("tenant_id"),
-        }))
+        Ok(row.as_ref().map(member_from_row))
     }
 }
 
-#[cfg(test)]
+/// Process-local [`RoomRepository`] used when no database is configured.
 #[derive(Debug, Default, Clone)]
-struct InMemoryRoomRepository {
+pub struct InMemoryRoomRepository {
     rooms: Arc<RwLock<HashMap<String, Room>>>,
 }
 
-#[cfg(test)]
+impl InMemoryRoomRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
 #[async_trait]
 impl RoomRepository for InMemoryRoomRepository {
     async fn create(&self, name: &str, topic: Option<&str>) -> Result<Room, RepositoryError> {
         let room = Room {
-            id: format!("room_{}", Uuid::new_v4().simple()),
+            id: RoomId::generate().to_string(),
             name: name.to_string(),
             topic: topic.map(std::string::ToString::to_string),
             created_at: Utc::now(),
@@ -711,6 +1499,19 @@ impl RoomRepository for InMemoryRoomRepository {
         Ok(rooms)
     }
 
+    async fn insert(&self, room: &Room) -> Result<(), RepositoryError> {
+        let mut rooms = self.rooms.write().await;
+        if rooms.contains_key(&room.id) {
+            return Err(RepositoryError::AlreadyExists(format!("room {}", room.id)));
+        }
+        rooms.insert(room.id.clone(), room.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, RepositoryError> {
+        Ok(self.rooms.write().await.remove(id).is_some())
+    }
+
     #[cfg(feature = "multi-tenant")]
     async fn create_tenant(
         &self,
@@ -719,7 +1520,7 @@ impl RoomRepository for InMemoryRoomRepository {
         topic: Option<&str>,
     ) -> Result<Room, RepositoryError> {
         let room = Room {
-            id: format!("room_{}", Uuid::new_v4().simple()),
+            id: RoomId::generate().to_string(),
             name: name.to_string(),
             topic: topic.map(std::string::ToString::to_string),
             created_at: Utc::now(),
@@ -759,13 +1560,26 @@ impl RoomRepository for InMemoryRoomRepository {
     }
 }
 
-#[cfg(test)]
+/// Process-local [`MessageRepository`] used when no database is configured.
 #[derive(Debug, Default, Clone)]
-struct InMemoryMessageRepository {
+pub struct InMemoryMessageRepository {
     messages: Arc<RwLock<HashMap<String, Message>>>,
+    outbox: Option<Arc<RwLock<Vec<OutboxEntry>>>>,
+}
+
+impl InMemoryMessageRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an outbox entry for every inserted message.
+    pub fn with_index_outbox(mut self) -> Self {
+        self.outbox = Some(Arc::default());
+        self
+    }
 }
 
-#[cfg(test)]
 #[async_trait]
 impl MessageRepository for InMemoryMessageRepository {
     async fn create(
@@ -808,7 +1622,104 @@ impl MessageRepository for InMemoryMessageRepository {
         Ok(messages)
     }
 
-    #[cfg(feature = "multi-tenant")]
+    async fn insert(&self, message: &Message) -> Result<(), RepositoryError> {
+        let mut messages = self.messages.write().await;
+        if messages.contains_key(&message.id) {
+            return Err(RepositoryError::AlreadyExists(format!(
+                "message {}",
+                message.id
+            )));
+        }
+        // Queue the entry while the message lock is held, so no reader sees
+        // the message without it.
+        if let Some(outbox) = &self.outbox {
+            outbox.write().await.push(OutboxEntry::for_message(message));
+        }
+        messages.insert(message.id.clone(), message.clone());
+        Ok(())
+    }
+
+    async fn list_recent_by_room(
+        &self,
+        room_id: &str,
+        limit: usize,
+    ) -> Result<Vec<Message>, RepositoryError> {
+        let mut messages = self.list_by_room(room_id).await?;
+        let skip = messages.len().saturating_sub(limit);
+        messages.drain(..skip);
+        Ok(messages)
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        let mut messages = self.messages.write().await;
+        messages.retain(|_, message| message.room_id != room_id);
+        if let Some(outbox) = &self.outbox {
+            outbox
+                .write()
+                .await
+                .retain(|entry| entry.room_id != room_id);
+        }
+        Ok(())
+    }
+
+    async fn delete_messages(&self, ids: &[String]) -> Result<usize, RepositoryError> {
+        let mut messages = self.messages.write().await;
+        let deleted = ids
+            .iter()
+            .filter(|id| messages.remove(id.as_str()).is_some())
+            .count();
+        if let Some(outbox) = &self.outbox {
+            outbox
+                .write()
+                .await
+                .retain(|entry| !ids.contains(&entry.message_id));
+        }
+        Ok(deleted)
+    }
+
+    async fn redact_messages(&self, ids: &[String]) -> Result<usize, RepositoryError> {
+        let mut messages = self.messages.write().await;
+        let mut redacted = 0;
+        for id in ids {
+            if let Some(message) = messages.get_mut(id) {
+                message.content.clear();
+                redacted += 1;
+            }
+        }
+        Ok(redacted)
+    }
+
+    async fn prune_room(
+        &self,
+        room_id: &str,
+        cutoff: Option<DateTime<Utc>>,
+        keep: Option<usize>,
+        redact: bool,
+    ) -> Result<Vec<String>, RepositoryError> {
+        let live: Vec<Message> = self
+            .list_by_room(room_id)
+            .await?
+            .into_iter()
+            .filter(|message| !message.content.is_empty())
+            .collect();
+        let excess = keep.map_or(0, |keep| live.len().saturating_sub(keep));
+        let ids: Vec<String> = live
+            .into_iter()
+            .enumerate()
+            .filter(|(position, message)| {
+                *position < excess || cutoff.is_some_and(|cutoff| message.created_at < cutoff)
+            })
+            .map(|(_, message)| message.id)
+            .collect();
+        if redact {
+            self.redact_messages(&ids).await?;
+        } else {
+            self.delete_messages(&ids).await?;
+        }
+        Ok(ids)
+    }
+
+    #[cfg(feature = "multi-tenant")]
     async fn create_tenant(
         &self,
         tenant_id: &str,
@@ -868,24 +1779,517 @@ impl MessageRepository for InMemoryMessageRepository {
     }
 }
 
-#[cfg(test)]
+#[async_trait]
+impl IndexOutbox for InMemoryMessageRepository {
+    async fn pending(&self, limit: usize) -> Result<Vec<OutboxEntry>, RepositoryError> {
+        let Some(outbox) = &self.outbox else {
+            return Ok(Vec::new());
+        };
+        Ok(outbox.read().await.iter().take(limit).cloned().collect())
+    }
+
+    async fn acknowledge(&self, message_ids: &[String]) -> Result<(), RepositoryError> {
+        if let Some(outbox) = &self.outbox {
+            outbox
+                .write()
+                .await
+                .retain(|entry| !message_ids.contains(&entry.message_id));
+        }
+        Ok(())
+    }
+}
+
+/// SQLx/PostgreSQL implementation of [`ReadMarkerRepository`].
+#[cfg(feature = "persistence-sqlx")]
+#[derive(Debug, Clone)]
+pub struct SqlxReadMarkerRepository {
+    pool: DatabasePool,
+}
+
+#[cfg(feature = "persistence-sqlx")]
+impl SqlxReadMarkerRepository {
+    /// Build a repository over an existing pool.
+    pub fn new(pool: DatabasePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+fn read_marker_from_row(row: &sqlx::postgres::PgRow) -> ReadMarker {
+    ReadMarker {
+        room_id: row.get("room_id"),
+        member_id: row.get("member_id"),
+        message_id: row.get("message_id"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+#[async_trait]
+impl ReadMarkerRepository for SqlxReadMarkerRepository {
+    async fn set(&self, marker: &ReadMarker) -> Result<(), RepositoryError> {
+        sqlx::query(
+            "INSERT INTO read_markers (room_id, member_id, message_id, updated_at) VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (room_id, member_id) DO UPDATE SET message_id = EXCLUDED.message_id, updated_at = EXCLUDED.updated_at",
+        )
+        .bind(&marker.room_id)
+        .bind(&marker.member_id)
+        .bind(&marker.message_id)
+        .bind(marker.updated_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        room_id: &str,
+        member_id: &str,
+    ) -> Result<Option<ReadMarker>, RepositoryError> {
+        let row = sqlx::query(
+            "SELECT room_id, member_id, message_id, updated_at FROM read_markers WHERE room_id = $1 AND member_id = $2",
+        )
+        .bind(room_id)
+        .bind(member_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(read_marker_from_row))
+    }
+
+    async fn list_for_member(&self, member_id: &str) -> Result<Vec<ReadMarker>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT room_id, member_id, message_id, updated_at FROM read_markers WHERE member_id = $1 ORDER BY room_id",
+        )
+        .bind(member_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(read_marker_from_row).collect())
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        sqlx::query("DELETE FROM read_markers WHERE room_id = $1")
+            .bind(room_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// SQLx/PostgreSQL implementation of [`RoomSettingsRepository`].
+#[cfg(feature = "persistence-sqlx")]
+#[derive(Debug, Clone)]
+pub struct SqlxRoomSettingsRepository {
+    pool: DatabasePool,
+}
+
+#[cfg(feature = "persistence-sqlx")]
+impl SqlxRoomSettingsRepository {
+    /// Build a repository over an existing pool.
+    pub fn new(pool: DatabasePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+fn room_settings_from_row(row: &sqlx::postgres::PgRow) -> RoomSettingsRecord {
+    RoomSettingsRecord {
+        room_id: row.get("room_id"),
+        settings: row
+            .get::<sqlx::types::Json<serde_json::Value>, _>("settings")
+            .0,
+        updated_at: row.get("updated_at"),
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+#[async_trait]
+impl RoomSettingsRepository for SqlxRoomSettingsRepository {
+    async fn set(&self, record: &RoomSettingsRecord) -> Result<(), RepositoryError> {
+        sqlx::query(
+            "INSERT INTO room_settings (room_id, settings, updated_at) VALUES ($1, $2, $3) \
+             ON CONFLICT (room_id) DO UPDATE SET settings = EXCLUDED.settings, updated_at = EXCLUDED.updated_at",
+        )
+        .bind(&record.room_id)
+        .bind(sqlx::types::Json(&record.settings))
+        .bind(record.updated_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get(&self, room_id: &str) -> Result<Option<RoomSettingsRecord>, RepositoryError> {
+        let row = sqlx::query(
+            "SELECT room_id, settings, updated_at FROM room_settings WHERE room_id = $1",
+        )
+        .bind(room_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(room_settings_from_row))
+    }
+
+    async fn list(&self) -> Result<Vec<RoomSettingsRecord>, RepositoryError> {
+        let rows =
+            sqlx::query("SELECT room_id, settings, updated_at FROM room_settings ORDER BY room_id")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows.iter().map(room_settings_from_row).collect())
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        sqlx::query("DELETE FROM room_settings WHERE room_id = $1")
+            .bind(room_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// SQLx/PostgreSQL implementation of [`MembershipRepository`].
+#[cfg(feature = "persistence-sqlx")]
+#[derive(Debug, Clone)]
+pub struct SqlxMembershipRepository {
+    pool: DatabasePool,
+}
+
+#[cfg(feature = "persistence-sqlx")]
+impl SqlxMembershipRepository {
+    /// Build a repository over an existing pool.
+    pub fn new(pool: DatabasePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+#[async_trait]
+impl MembershipRepository for SqlxMembershipRepository {
+    async fn add(&self, record: &MembershipRecord) -> Result<bool, RepositoryError> {
+        let result = sqlx::query(
+            "INSERT INTO memberships (room_id, member_id, joined_at) VALUES ($1, $2, $3) \
+             ON CONFLICT (room_id, member_id) DO NOTHING",
+        )
+        .bind(&record.room_id)
+        .bind(&record.member_id)
+        .bind(record.joined_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn remove(&self, room_id: &str, member_id: &str) -> Result<bool, RepositoryError> {
+        let result = sqlx::query("DELETE FROM memberships WHERE room_id = $1 AND member_id = $2")
+            .bind(room_id)
+            .bind(member_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list(&self) -> Result<Vec<MembershipRecord>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT room_id, member_id, joined_at FROM memberships \
+             ORDER BY room_id, joined_at, member_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| MembershipRecord {
+                room_id: row.get("room_id"),
+                member_id: row.get("member_id"),
+                joined_at: row.get("joined_at"),
+            })
+            .collect())
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        sqlx::query("DELETE FROM memberships WHERE room_id = $1")
+            .bind(room_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// SQLx/PostgreSQL implementation of [`SanctionRepository`].
+#[cfg(feature = "persistence-sqlx")]
+#[derive(Debug, Clone)]
+pub struct SqlxSanctionRepository {
+    pool: DatabasePool,
+}
+
+#[cfg(feature = "persistence-sqlx")]
+impl SqlxSanctionRepository {
+    /// Build a repository over an existing pool.
+    pub fn new(pool: DatabasePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+#[async_trait]
+impl SanctionRepository for SqlxSanctionRepository {
+    async fn set(&self, record: &SanctionRecord) -> Result<(), RepositoryError> {
+        sqlx::query(
+            "INSERT INTO sanctions (room_id, member_id, kind, reason, created_by, created_at, expires_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7) \
+             ON CONFLICT (room_id, member_id, kind) DO UPDATE SET reason = EXCLUDED.reason, \
+             created_by = EXCLUDED.created_by, created_at = EXCLUDED.created_at, \
+             expires_at = EXCLUDED.expires_at",
+        )
+        .bind(&record.room_id)
+        .bind(&record.member_id)
+        .bind(&record.kind)
+        .bind(&record.reason)
+        .bind(&record.created_by)
+        .bind(record.created_at)
+        .bind(record.expires_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove(
+        &self,
+        room_id: &str,
+        member_id: &str,
+        kind: &str,
+    ) -> Result<bool, RepositoryError> {
+        let result = sqlx::query(
+            "DELETE FROM sanctions WHERE room_id = $1 AND member_id = $2 AND kind = $3",
+        )
+        .bind(room_id)
+        .bind(member_id)
+        .bind(kind)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list(&self) -> Result<Vec<SanctionRecord>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT room_id, member_id, kind, reason, created_by, created_at, expires_at \
+             FROM sanctions ORDER BY room_id, created_at, member_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| SanctionRecord {
+                room_id: row.get("room_id"),
+                member_id: row.get("member_id"),
+                kind: row.get("kind"),
+                reason: row.get("reason"),
+                created_by: row.get("created_by"),
+                created_at: row.get("created_at"),
+                expires_at: row.get("expires_at"),
+            })
+            .collect())
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        sqlx::query("DELETE FROM sanctions WHERE room_id = $1")
+            .bind(room_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// SQLx/PostgreSQL implementation of [`UploadRepository`].
+#[cfg(feature = "persistence-sqlx")]
+#[derive(Debug, Clone)]
+pub struct SqlxUploadRepository {
+    pool: DatabasePool,
+}
+
+#[cfg(feature = "persistence-sqlx")]
+impl SqlxUploadRepository {
+    /// Build a repository over an existing pool.
+    pub fn new(pool: DatabasePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+#[async_trait]
+impl UploadRepository for SqlxUploadRepository {
+    async fn insert(&self, record: &UploadRecord) -> Result<(), RepositoryError> {
+        sqlx::query(
+            "INSERT INTO uploads (id, file_name, mime_type, size, uploaded_by, tenant_id, room_id, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(&record.id)
+        .bind(&record.file_name)
+        .bind(&record.mime_type)
+        .bind(record.size as i64)
+        .bind(&record.uploaded_by)
+        .bind(&record.tenant_id)
+        .bind(&record.room_id)
+        .bind(record.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn set_room(&self, id: &str, room_id: &str) -> Result<bool, RepositoryError> {
+        let result =
+            sqlx::query("UPDATE uploads SET room_id = $2 WHERE id = $1 AND room_id IS NULL")
+                .bind(id)
+                .bind(room_id)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list(&self) -> Result<Vec<UploadRecord>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT id, file_name, mime_type, size, uploaded_by, tenant_id, room_id, created_at \
+             FROM uploads ORDER BY created_at, id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| UploadRecord {
+                id: row.get("id"),
+                file_name: row.get("file_name"),
+                mime_type: row.get("mime_type"),
+                size: row.get::<i64, _>("size") as u64,
+                uploaded_by: row.get("uploaded_by"),
+                tenant_id: row.get("tenant_id"),
+                room_id: row.get("room_id"),
+                created_at: row.get("created_at"),
+            })
+            .collect())
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        sqlx::query("DELETE FROM uploads WHERE room_id = $1")
+            .bind(room_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// SQLx/PostgreSQL implementation of [`FeedbackRepository`].
+#[cfg(feature = "persistence-sqlx")]
+#[derive(Debug, Clone)]
+pub struct SqlxFeedbackRepository {
+    pool: DatabasePool,
+}
+
+#[cfg(feature = "persistence-sqlx")]
+impl SqlxFeedbackRepository {
+    /// Build a repository over an existing pool.
+    pub fn new(pool: DatabasePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+fn feedback_from_row(row: &sqlx::postgres::PgRow) -> FeedbackRecord {
+    FeedbackRecord {
+        message_id: row.get("message_id"),
+        room_id: row.get("room_id"),
+        member_id: row.get("member_id"),
+        rating: row.get("rating"),
+        comment: row.get("comment"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+#[async_trait]
+impl FeedbackRepository for SqlxFeedbackRepository {
+    async fn set(&self, record: &FeedbackRecord) -> Result<(), RepositoryError> {
+        sqlx::query(
+            "INSERT INTO message_feedback (message_id, room_id, member_id, rating, comment, updated_at) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (message_id, member_id) DO UPDATE SET rating = EXCLUDED.rating, \
+             comment = EXCLUDED.comment, updated_at = EXCLUDED.updated_at",
+        )
+        .bind(&record.message_id)
+        .bind(&record.room_id)
+        .bind(&record.member_id)
+        .bind(&record.rating)
+        .bind(&record.comment)
+        .bind(record.updated_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, message_id: &str, member_id: &str) -> Result<bool, RepositoryError> {
+        let result =
+            sqlx::query("DELETE FROM message_feedback WHERE message_id = $1 AND member_id = $2")
+                .bind(message_id)
+                .bind(member_id)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_for_message(
+        &self,
+        message_id: &str,
+    ) -> Result<Vec<FeedbackRecord>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT message_id, room_id, member_id, rating, comment, updated_at \
+             FROM message_feedback WHERE message_id = $1 ORDER BY updated_at, member_id",
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(feedback_from_row).collect())
+    }
+
+    async fn list(&self) -> Result<Vec<FeedbackRecord>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT message_id, room_id, member_id, rating, comment, updated_at \
+             FROM message_feedback ORDER BY updated_at, message_id, member_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(feedback_from_row).collect())
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        sqlx::query("DELETE FROM message_feedback WHERE room_id = $1")
+            .bind(room_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Process-local [`MemberRepository`] used when no database is configured.
 #[derive(Debug, Default, Clone)]
-struct InMemoryMemberRepository {
+pub struct InMemoryMemberRepository {
     members: Arc<RwLock<HashMap<String, Member>>>,
 }
 
-#[cfg(test)]
+impl InMemoryMemberRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
 #[async_trait]
 impl MemberRepository for InMemoryMemberRepository {
     async fn create(&self, member_type: &str, email: &str) -> Result<Member, RepositoryError> {
-        let member = Member {
-            id: format!("member_{}", Uuid::new_v4().simple()),
-            member_type: member_type.to_string(),
-            email: email.to_string(),
-            created_at: Utc::now(),
-            #[cfg(feature = "multi-tenant")]
-            tenant_id: None,
-        };
+        let mut member = Member::new(format!("member_{}", Uuid::new_v4().simple()), member_type);
+        member.email = Some(email.to_string());
         self.members
             .write()
             .await
@@ -897,6 +2301,47 @@ impl MemberRepository for InMemoryMemberRepository {
         Ok(self.members.read().await.get(id).cloned())
     }
 
+    async fn insert(&self, member: &Member) -> Result<(), RepositoryError> {
+        let mut members = self.members.write().await;
+        if members.contains_key(&member.id) {
+            return Err(RepositoryError::AlreadyExists(format!(
+                "member {}",
+                member.id
+            )));
+        }
+        members.insert(member.id.clone(), member.clone());
+        Ok(())
+    }
+
+    async fn update(&self, member: &Member) -> Result<bool, RepositoryError> {
+        let mut members = self.members.write().await;
+        let Some(existing) = members.get_mut(&member.id) else {
+            return Ok(false);
+        };
+        existing.email = member.email.clone();
+        existing.display_name = member.display_name.clone();
+        existing.avatar_url = member.avatar_url.clone();
+        existing.public_key = member.public_key.clone();
+        existing.capabilities = member.capabilities.clone();
+        Ok(true)
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, RepositoryError> {
+        Ok(self.members.write().await.remove(id).is_some())
+    }
+
+    async fn list(&self) -> Result<Vec<Member>, RepositoryError> {
+        let mut members = self
+            .members
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        members.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(members)
+    }
+
     #[cfg(feature = "multi-tenant")]
     async fn create_tenant(
         &self,
@@ -904,13 +2349,9 @@ impl MemberRepository for InMemoryMemberRepository {
         member_type: &str,
         email: &str,
     ) -> Result<Member, RepositoryError> {
-        let member = Member {
-            id: format!("member_{}", Uuid::new_v4().simple()),
-            member_type: member_type.to_string(),
-            email: email.to_string(),
-            created_at: Utc::now(),
-            tenant_id: Some(tenant_id.to_string()),
-        };
+        let mut member = Member::new(format!("member_{}", Uuid::new_v4().simple()), member_type);
+        member.email = Some(email.to_string());
+        member.tenant_id = Some(tenant_id.to_string());
         self.members
             .write()
             .await
@@ -934,12 +2375,331 @@ impl MemberRepository for InMemoryMemberRepository {
     }
 }
 
+/// Process-local [`ReadMarkerRepository`] used when no database is configured.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryReadMarkerRepository {
+    markers: Arc<RwLock<HashMap<(String, String), ReadMarker>>>,
+}
+
+impl InMemoryReadMarkerRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ReadMarkerRepository for InMemoryReadMarkerRepository {
+    async fn set(&self, marker: &ReadMarker) -> Result<(), RepositoryError> {
+        self.markers.write().await.insert(
+            (marker.room_id.clone(), marker.member_id.clone()),
+            marker.clone(),
+        );
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        room_id: &str,
+        member_id: &str,
+    ) -> Result<Option<ReadMarker>, RepositoryError> {
+        Ok(self
+            .markers
+            .read()
+            .await
+            .get(&(room_id.to_string(), member_id.to_string()))
+            .cloned())
+    }
+
+    async fn list_for_member(&self, member_id: &str) -> Result<Vec<ReadMarker>, RepositoryError> {
+        let mut markers = self
+            .markers
+            .read()
+            .await
+            .values()
+            .filter(|marker| marker.member_id == member_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        markers.sort_by(|a, b| a.room_id.cmp(&b.room_id));
+        Ok(markers)
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        self.markers
+            .write()
+            .await
+            .retain(|(room, _), _| room != room_id);
+        Ok(())
+    }
+}
+
+/// Process-local [`RoomSettingsRepository`] used when no database is configured.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryRoomSettingsRepository {
+    settings: Arc<RwLock<HashMap<String, RoomSettingsRecord>>>,
+}
+
+impl InMemoryRoomSettingsRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RoomSettingsRepository for InMemoryRoomSettingsRepository {
+    async fn set(&self, record: &RoomSettingsRecord) -> Result<(), RepositoryError> {
+        self.settings
+            .write()
+            .await
+            .insert(record.room_id.clone(), record.clone());
+        Ok(())
+    }
+
+    async fn get(&self, room_id: &str) -> Result<Option<RoomSettingsRecord>, RepositoryError> {
+        Ok(self.settings.read().await.get(room_id).cloned())
+    }
+
+    async fn list(&self) -> Result<Vec<RoomSettingsRecord>, RepositoryError> {
+        let mut records = self
+            .settings
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        records.sort_by(|a, b| a.room_id.cmp(&b.room_id));
+        Ok(records)
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        self.settings.write().await.remove(room_id);
+        Ok(())
+    }
+}
+
+/// Process-local [`MembershipRepository`] used when no database is configured.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryMembershipRepository {
+    memberships: Arc<RwLock<Vec<MembershipRecord>>>,
+}
+
+impl InMemoryMembershipRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl MembershipRepository for InMemoryMembershipRepository {
+    async fn add(&self, record: &MembershipRecord) -> Result<bool, RepositoryError> {
+        let mut memberships = self.memberships.write().await;
+        if memberships.iter().any(|existing| {
+            existing.room_id == record.room_id && existing.member_id == record.member_id
+        }) {
+            return Ok(false);
+        }
+        memberships.push(record.clone());
+        Ok(true)
+    }
+
+    async fn remove(&self, room_id: &str, member_id: &str) -> Result<bool, RepositoryError> {
+        let mut memberships = self.memberships.write().await;
+        let before = memberships.len();
+        memberships
+            .retain(|existing| existing.room_id != room_id || existing.member_id != member_id);
+        Ok(memberships.len() < before)
+    }
+
+    async fn list(&self) -> Result<Vec<MembershipRecord>, RepositoryError> {
+        let mut records = self.memberships.read().await.clone();
+        records.sort_by(|a, b| a.room_id.cmp(&b.room_id));
+        Ok(records)
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        self.memberships
+            .write()
+            .await
+            .retain(|record| record.room_id != room_id);
+        Ok(())
+    }
+}
+
+/// Process-local [`SanctionRepository`] used when no database is configured.
+#[derive(Debug, Default, Clone)]
+pub struct InMemorySanctionRepository {
+    sanctions: Arc<RwLock<Vec<SanctionRecord>>>,
+}
+
+impl InMemorySanctionRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SanctionRepository for InMemorySanctionRepository {
+    async fn set(&self, record: &SanctionRecord) -> Result<(), RepositoryError> {
+        let mut sanctions = self.sanctions.write().await;
+        sanctions.retain(|existing| {
+            existing.room_id != record.room_id
+                || existing.member_id != record.member_id
+                || existing.kind != record.kind
+        });
+        sanctions.push(record.clone());
+        Ok(())
+    }
+
+    async fn remove(
+        &self,
+        room_id: &str,
+        member_id: &str,
+        kind: &str,
+    ) -> Result<bool, RepositoryError> {
+        let mut sanctions = self.sanctions.write().await;
+        let before = sanctions.len();
+        sanctions.retain(|existing| {
+            existing.room_id != room_id || existing.member_id != member_id || existing.kind != kind
+        });
+        Ok(sanctions.len() < before)
+    }
+
+    async fn list(&self) -> Result<Vec<SanctionRecord>, RepositoryError> {
+        let mut records = self.sanctions.read().await.clone();
+        records.sort_by(|a, b| a.room_id.cmp(&b.room_id));
+        Ok(records)
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        self.sanctions
+            .write()
+            .await
+            .retain(|record| record.room_id != room_id);
+        Ok(())
+    }
+}
+
+/// Process-local [`UploadRepository`] used when no database is configured.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryUploadRepository {
+    uploads: Arc<RwLock<Vec<UploadRecord>>>,
+}
+
+impl InMemoryUploadRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl UploadRepository for InMemoryUploadRepository {
+    async fn insert(&self, record: &UploadRecord) -> Result<(), RepositoryError> {
+        self.uploads.write().await.push(record.clone());
+        Ok(())
+    }
+
+    async fn set_room(&self, id: &str, room_id: &str) -> Result<bool, RepositoryError> {
+        let mut uploads = self.uploads.write().await;
+        match uploads
+            .iter_mut()
+            .find(|record| record.id == id && record.room_id.is_none())
+        {
+            Some(record) => {
+                record.room_id = Some(room_id.to_string());
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<UploadRecord>, RepositoryError> {
+        Ok(self.uploads.read().await.clone())
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        self.uploads
+            .write()
+            .await
+            .retain(|record| record.room_id.as_deref() != Some(room_id));
+        Ok(())
+    }
+}
+
+/// Process-local [`FeedbackRepository`] used when no database is configured.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryFeedbackRepository {
+    feedback: Arc<RwLock<Vec<FeedbackRecord>>>,
+}
+
+impl InMemoryFeedbackRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl FeedbackRepository for InMemoryFeedbackRepository {
+    async fn set(&self, record: &FeedbackRecord) -> Result<(), RepositoryError> {
+        let mut feedback = self.feedback.write().await;
+        feedback.retain(|existing| {
+            existing.message_id != record.message_id || existing.member_id != record.member_id
+        });
+        feedback.push(record.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, message_id: &str, member_id: &str) -> Result<bool, RepositoryError> {
+        let mut feedback = self.feedback.write().await;
+        let before = feedback.len();
+        feedback.retain(|existing| {
+            existing.message_id != message_id || existing.member_id != member_id
+        });
+        Ok(feedback.len() < before)
+    }
+
+    async fn list_for_message(
+        &self,
+        message_id: &str,
+    ) -> Result<Vec<FeedbackRecord>, RepositoryError> {
+        Ok(self
+            .feedback
+            .read()
+            .await
+            .iter()
+            .filter(|record| record.message_id == message_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn list(&self) -> Result<Vec<FeedbackRecord>, RepositoryError> {
+        Ok(self.feedback.read().await.clone())
+    }
+
+    async fn delete_room(&self, room_id: &str) -> Result<(), RepositoryError> {
+        self.feedback
+            .write()
+            .await
+            .retain(|record| record.room_id != room_id);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        InMemoryMemberRepository, InMemoryMessageRepository, InMemoryRoomRepository,
-        MemberRepository, MessageRepository, RoomRepository,
+        Capabilities, FeedbackRecord, FeedbackRepository, InMemoryFeedbackRepository,
+        InMemoryMemberRepository, InMemoryMembershipRepository, InMemoryMessageRepository,
+        InMemoryReadMarkerRepository, InMemoryRoomRepository, InMemorySanctionRepository,
+        InMemoryUploadRepository, Member, MemberRepository, MembershipRecord, MembershipRepository,
+        Message, MessageRepository, ReadMarker, ReadMarkerRepository, RepositoryError,
+        RoomRepository, SanctionRecord, SanctionRepository, UploadRecord, UploadRepository,
     };
+    use chrono::Utc;
 
     #[tokio::test]
     async fn room_repository_create_get_and_list() {
@@ -977,6 +2737,55 @@ mod tests {
         assert_eq!(room_messages[0].id, first.id);
     }
 
+    #[tokio::test]
+    async fn message_repository_inserts_and_lists_recent_messages() {
+        let repository = InMemoryMessageRepository::new();
+        let start = Utc::now();
+        for (offset, id) in ["msg_1", "msg_2", "msg_3"].into_iter().enumerate() {
+            let message = Message {
+                id: id.to_string(),
+                room_id: "room_1".to_string(),
+                sender_id: "member_1".to_string(),
+                content: id.to_string(),
+                created_at: start + chrono::Duration::seconds(offset as i64),
+                #[cfg(feature = "multi-tenant")]
+                tenant_id: None,
+            };
+            repository.insert(&message).await.unwrap();
+        }
+        let duplicate = repository.get("msg_1").await.unwrap().unwrap();
+        assert!(matches!(
+            repository.insert(&duplicate).await,
+            Err(RepositoryError::AlreadyExists(_))
+        ));
+
+        let recent = repository.list_recent_by_room("room_1", 2).await.unwrap();
+        let ids: Vec<&str> = recent.iter().map(|message| message.id.as_str()).collect();
+        assert_eq!(ids, ["msg_2", "msg_3"]);
+
+        let redacted = repository
+            .redact_messages(&["msg_2".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(redacted, 1);
+        assert!(repository
+            .get("msg_2")
+            .await
+            .unwrap()
+            .unwrap()
+            .content
+            .is_empty());
+        let deleted = repository
+            .delete_messages(&["msg_1".to_string(), "msg_9".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(repository.list_by_room("room_1").await.unwrap().len(), 2);
+
+        repository.delete_room("room_1").await.unwrap();
+        assert!(repository.list_by_room("room_1").await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn member_repository_create_and_get() {
         let repository = InMemoryMemberRepository::default();
@@ -988,7 +2797,214 @@ mod tests {
         let loaded = repository.get(&created.id).await.unwrap().unwrap();
 
         assert_eq!(loaded.member_type, "human");
-        assert_eq!(loaded.email, "alice@example.com");
+        assert_eq!(loaded.email.as_deref(), Some("alice@example.com"));
+    }
+
+    #[tokio::test]
+    async fn member_repository_directory_crud() {
+        let repository = InMemoryMemberRepository::new();
+
+        let mut member = Member::new("nexis:agent:openai/gpt-4o", "agent");
+        member.capabilities = Capabilities::new().with_tool("code-review");
+        repository.insert(&member).await.unwrap();
+        assert!(matches!(
+            repository.insert(&member).await,
+            Err(RepositoryError::AlreadyExists(_))
+        ));
+
+        member.display_name = Some("Reviewer".to_string());
+        assert!(repository.update(&member).await.unwrap());
+        let loaded = repository.get(&member.id).await.unwrap().unwrap();
+        assert_eq!(loaded.display_name.as_deref(), Some("Reviewer"));
+        assert_eq!(
+            loaded.capabilities,
+            Capabilities::new().with_tool("code-review")
+        );
+        assert_eq!(repository.list().await.unwrap(), vec![loaded]);
+
+        assert!(repository.delete(&member.id).await.unwrap());
+        assert!(!repository.delete(&member.id).await.unwrap());
+        assert!(!repository.update(&member).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn read_marker_repository_upserts_per_room_and_member() {
+        let repository = InMemoryReadMarkerRepository::new();
+        let marker = |room: &str, message: &str| ReadMarker {
+            room_id: room.to_string(),
+            member_id: "alice".to_string(),
+            message_id: message.to_string(),
+            updated_at: chrono::Utc::now(),
+        };
+
+        repository.set(&marker("room_1", "msg_1")).await.unwrap();
+        repository.set(&marker("room_1", "msg_2")).await.unwrap();
+        repository.set(&marker("room_2", "msg_9")).await.unwrap();
+
+        let loaded = repository.get("room_1", "alice").await.unwrap().unwrap();
+        assert_eq!(loaded.message_id, "msg_2");
+        assert_eq!(repository.get("room_1", "bob").await.unwrap(), None);
+        assert_eq!(repository.list_for_member("alice").await.unwrap().len(), 2);
+
+        repository.delete_room("room_1").await.unwrap();
+        let remaining = repository.list_for_member("alice").await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].room_id, "room_2");
+    }
+
+    #[tokio::test]
+    async fn feedback_repository_keeps_one_rating_per_rater() {
+        let repository = InMemoryFeedbackRepository::new();
+        let rating = |room: &str, message: &str, member: &str, rating: &str| FeedbackRecord {
+            message_id: message.to_string(),
+            room_id: room.to_string(),
+            member_id: member.to_string(),
+            rating: rating.to_string(),
+            comment: None,
+            updated_at: chrono::Utc::now(),
+        };
+
+        repository
+            .set(&rating("room_1", "msg_1", "alice", "down"))
+            .await
+            .unwrap();
+        repository
+            .set(&rating("room_1", "msg_1", "bob", "up"))
+            .await
+            .unwrap();
+        repository
+            .set(&rating("room_1", "msg_1", "alice", "up"))
+            .await
+            .unwrap();
+        repository
+            .set(&rating("room_2", "msg_2", "alice", "down"))
+            .await
+            .unwrap();
+
+        let ratings = repository.list_for_message("msg_1").await.unwrap();
+        assert_eq!(ratings.len(), 2);
+        assert!(ratings.iter().all(|rating| rating.rating == "up"));
+        assert!(repository.delete("msg_1", "bob").await.unwrap());
+        assert!(!repository.delete("msg_1", "bob").await.unwrap());
+
+        repository.delete_room("room_1").await.unwrap();
+        let remaining = repository.list().await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].message_id, "msg_2");
+    }
+
+    #[tokio::test]
+    async fn sanction_repository_keeps_one_of_each_kind_per_member() {
+        let repository = InMemorySanctionRepository::new();
+        let sanction = |room: &str, member: &str, kind: &str, reason: &str| SanctionRecord {
+            room_id: room.to_string(),
+            member_id: member.to_string(),
+            kind: kind.to_string(),
+            reason: Some(reason.to_string()),
+            created_by: "admin".to_string(),
+            created_at: Utc::now(),
+            expires_at: None,
+        };
+
+        repository
+            .set(&sanction("room_1", "alice", "mute", "noise"))
+            .await
+            .unwrap();
+        repository
+            .set(&sanction("room_1", "alice", "ban", "spam"))
+            .await
+            .unwrap();
+        repository
+            .set(&sanction("room_1", "alice", "mute", "more noise"))
+            .await
+            .unwrap();
+        repository
+            .set(&sanction("room_2", "bob", "ban", "spam"))
+            .await
+            .unwrap();
+
+        let stored = repository.list().await.unwrap();
+        assert_eq!(stored.len(), 3);
+        let mute = stored.iter().find(|record| record.kind == "mute").unwrap();
+        assert_eq!(mute.reason.as_deref(), Some("more noise"));
+
+        assert!(repository.remove("room_1", "alice", "mute").await.unwrap());
+        assert!(!repository.remove("room_1", "alice", "mute").await.unwrap());
+        repository.delete_room("room_1").await.unwrap();
+        let remaining = repository.list().await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].member_id, "bob");
+    }
+
+    #[tokio::test]
+    async fn upload_repository_records_the_first_room_an_upload_is_posted_in() {
+        let repository = InMemoryUploadRepository::new();
+        let upload = |id: &str| UploadRecord {
+            id: id.to_string(),
+            file_name: "notes.txt".to_string(),
+            mime_type: "text/plain".to_string(),
+            size: 5,
+            uploaded_by: "alice".to_string(),
+            tenant_id: None,
+            room_id: None,
+            created_at: Utc::now(),
+        };
+        repository.insert(&upload("upl_1")).await.unwrap();
+        repository.insert(&upload("upl_2")).await.unwrap();
+
+        assert!(repository.set_room("upl_1", "room_1").await.unwrap());
+        assert!(!repository.set_room("upl_1", "room_2").await.unwrap());
+        assert!(!repository.set_room("upl_missing", "room_1").await.unwrap());
+        let stored = repository.list().await.unwrap();
+        assert_eq!(stored[0].room_id.as_deref(), Some("room_1"));
+        assert_eq!(stored[1].room_id, None);
+
+        repository.delete_room("room_1").await.unwrap();
+        let remaining = repository.list().await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, "upl_2");
+    }
+
+    #[tokio::test]
+    async fn membership_repository_adds_each_member_once() {
+        let repository = InMemoryMembershipRepository::new();
+        let membership = |room: &str, member: &str| MembershipRecord {
+            room_id: room.to_string(),
+            member_id: member.to_string(),
+            joined_at: chrono::Utc::now(),
+        };
+
+        assert!(repository
+            .add(&membership("room_2", "alice"))
+            .await
+            .unwrap());
+        assert!(repository.add(&membership("room_1", "bob")).await.unwrap());
+        assert!(repository
+            .add(&membership("room_1", "alice"))
+            .await
+            .unwrap());
+        assert!(!repository.add(&membership("room_1", "bob")).await.unwrap());
+
+        let members: Vec<_> = repository
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|record| (record.room_id, record.member_id))
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("room_1".to_string(), "bob".to_string()),
+                ("room_1".to_string(), "alice".to_string()),
+                ("room_2".to_string(), "alice".to_string()),
+            ]
+        );
+        assert!(repository.remove("room_1", "bob").await.unwrap());
+        assert!(!repository.remove("room_1", "bob").await.unwrap());
+
+        repository.delete_room("room_1").await.unwrap();
+        assert_eq!(repository.list().await.unwrap().len(), 1);
     }
 
     #[cfg(feature = "multi-tenant")]
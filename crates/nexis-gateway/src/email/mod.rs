@@ -0,0 +1,286 @@
+//! Outgoing email.
+//!
+//! An [`EmailSender`] delivers a finished [`EmailMessage`]:
+//! [`ConsoleEmailSender`] writes it to the log and `SmtpEmailSender` (`email`
+//! feature) hands it to an SMTP server. [`Mailer`] fills in the `[email]`
+//! templates and picks the sender address configured for the room's tenant.
+
+#[cfg(feature = "email")]
+mod smtp;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::config::{EmailConfig, EmailProviderKind, EmailTemplateConfig};
+
+#[cfg(feature = "email")]
+pub use smtp::SmtpEmailSender;
+
+#[derive(Debug, Error)]
+pub enum EmailError {
+    /// `[email]` names a provider this build cannot use or cannot parse.
+    #[error("invalid email config: {0}")]
+    Config(String),
+    #[error("invalid email address: {0}")]
+    Address(String),
+    #[error("failed to send email: {0}")]
+    Send(String),
+}
+
+/// A rendered email, ready to send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailMessage {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers email.
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    async fn send(&self, message: EmailMessage) -> Result<(), EmailError>;
+}
+
+/// Writes email to the log instead of sending it, for development.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleEmailSender;
+
+#[async_trait]
+impl EmailSender for ConsoleEmailSender {
+    async fn send(&self, message: EmailMessage) -> Result<(), EmailError> {
+        tracing::info!(
+            from = %message.from,
+            to = %message.to,
+            subject = %message.subject,
+            "Email not sent (console provider):\n{}",
+            message.body
+        );
+        Ok(())
+    }
+}
+
+/// Whether `address` looks like an email address, with or without a
+/// display name.
+pub fn is_address(address: &str) -> bool {
+    let address = match (address.find('<'), address.strip_suffix('>')) {
+        (Some(start), Some(rest)) => &rest[start + 1..],
+        _ => address,
+    };
+    !address.chars().any(char::is_whitespace)
+        && address
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))
+}
+
+/// Kinds of email the gateway sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTemplate {
+    /// Invitation to a room.
+    Invitation,
+    /// A notification of a message mentioning the member.
+    Mention,
+    /// A notification of any other message.
+    Message,
+    /// Several notifications at once.
+    Digest,
+}
+
+impl EmailTemplate {
+    /// Built-in subject and body.
+    fn default_text(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Invitation => (
+                "You're invited to {{room_name}}",
+                "{{invited_by}} invited you to the room {{room_name}} on Nexis.\n\n\
+                 Join it with the invitation token {{token}}, for example with\n\
+                 `join-room {{room_id}} {{token}}` in nexis-cli. The token can be\n\
+                 used once and expires at {{expires_at}}.\n",
+            ),
+            Self::Mention => (
+                "{{sender}} mentioned you",
+                "{{sender}} mentioned you in {{room_id}} at {{sent_at}}:\n\n{{excerpt}}\n",
+            ),
+            Self::Message => (
+                "New message from {{sender}}",
+                "{{sender}} wrote in {{room_id}} at {{sent_at}}:\n\n{{excerpt}}\n",
+            ),
+            Self::Digest => (
+                "{{count}} new notifications",
+                "You have {{count}} new notifications:\n\n{{notifications}}\n",
+            ),
+        }
+    }
+}
+
+/// Replace each `{{name}}` in `template` with its value; unknown names are
+/// left as they are.
+pub fn render(template: &str, values: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        let placeholder = &rest[start..start + end + 2];
+        let name = placeholder[2..placeholder.len() - 2].trim();
+        match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => rendered.push_str(value),
+            None => rendered.push_str(placeholder),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Sends templated email through an [`EmailSender`].
+pub struct Mailer {
+    sender: Arc<dyn EmailSender>,
+    config: EmailConfig,
+}
+
+impl Mailer {
+    pub fn new(sender: Arc<dyn EmailSender>, config: EmailConfig) -> Self {
+        Self { sender, config }
+    }
+
+    fn template(&self, template: EmailTemplate) -> (&str, &str) {
+        let templates = &self.config.templates;
+        let configured = match template {
+            EmailTemplate::Invitation => templates.invitation.as_ref(),
+            EmailTemplate::Mention => templates.mention.as_ref(),
+            EmailTemplate::Message => templates.message.as_ref(),
+            EmailTemplate::Digest => templates.digest.as_ref(),
+        };
+        match configured {
+            Some(EmailTemplateConfig { subject, body }) => (subject, body),
+            None => template.default_text(),
+        }
+    }
+
+    /// Render `template` with `values` and send it to `to`, from the address
+    /// configured for `tenant`.
+    pub async fn send(
+        &self,
+        template: EmailTemplate,
+        to: &str,
+        tenant: Option<&str>,
+        values: &[(&str, &str)],
+    ) -> Result<(), EmailError> {
+        if !is_address(to) {
+            return Err(EmailError::Address(to.to_string()));
+        }
+        let (subject, body) = self.template(template);
+        let message = EmailMessage {
+            from: self.config.from_address(tenant).to_string(),
+            to: to.to_string(),
+            subject: render(subject, values),
+            body: render(body, values),
+        };
+        self.sender.send(message).await
+    }
+}
+
+/// Mailer for `[email]`; `None` when the provider is `none`.
+pub fn from_config(config: &EmailConfig) -> Result<Option<Mailer>, EmailError> {
+    let sender: Arc<dyn EmailSender> = match config.provider {
+        EmailProviderKind::None => return Ok(None),
+        EmailProviderKind::Console => Arc::new(ConsoleEmailSender),
+        #[cfg(feature = "email")]
+        EmailProviderKind::Smtp => {
+            let url = config.smtp_url.as_deref().unwrap_or_default();
+            Arc::new(SmtpEmailSender::from_url(url)?)
+        }
+        #[cfg(not(feature = "email"))]
+        EmailProviderKind::Smtp => {
+            return Err(EmailError::Config(
+                "email.provider is smtp but the gateway was built without the email feature"
+                    .to_string(),
+            ))
+        }
+    };
+    Ok(Some(Mailer::new(sender, config.clone())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Outbox(Mutex<Vec<EmailMessage>>);
+
+    #[async_trait]
+    impl EmailSender for Outbox {
+        async fn send(&self, message: EmailMessage) -> Result<(), EmailError> {
+            self.0.lock().unwrap().push(message);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn render_fills_known_placeholders_only() {
+        assert_eq!(
+            render("Hi {{ name }}, {{unknown}} {{name}}!", &[("name", "bob")]),
+            "Hi bob, {{unknown}} bob!"
+        );
+        assert_eq!(render("open {{", &[]), "open {{");
+    }
+
+    #[test]
+    fn addresses_may_carry_a_display_name() {
+        assert!(is_address("bob@example.com"));
+        assert!(is_address("Bob <bob@example.com>"));
+        assert!(!is_address("bob"));
+        assert!(!is_address("bob @example.com"));
+    }
+
+    #[tokio::test]
+    async fn mailer_uses_configured_templates_and_tenant_sender() {
+        let outbox = Arc::new(Outbox::default());
+        let mut config = EmailConfig::default();
+        config
+            .tenant_from
+            .insert("acme".to_string(), "Acme <chat@acme.test>".to_string());
+        config.templates.mention = Some(EmailTemplateConfig {
+            subject: "Ping from {{sender}}".to_string(),
+            body: "{{excerpt}}".to_string(),
+        });
+        let mailer = Mailer::new(outbox.clone(), config);
+
+        mailer
+            .send(
+                EmailTemplate::Mention,
+                "bob@example.com",
+                Some("acme"),
+                &[("sender", "alice"), ("excerpt", "@bob look")],
+            )
+            .await
+            .unwrap();
+        mailer
+            .send(
+                EmailTemplate::Invitation,
+                "carol@example.com",
+                None,
+                &[("room_name", "ops")],
+            )
+            .await
+            .unwrap();
+        assert!(mailer
+            .send(EmailTemplate::Digest, "nobody", None, &[])
+            .await
+            .is_err());
+
+        let sent = outbox.0.lock().unwrap();
+        assert_eq!(sent[0].from, "Acme <chat@acme.test>");
+        assert_eq!(sent[0].subject, "Ping from alice");
+        assert_eq!(sent[0].body, "@bob look");
+        assert_eq!(sent[1].from, "Nexis <nexis@localhost>");
+        assert_eq!(sent[1].subject, "You're invited to ops");
+        assert_eq!(sent.len(), 2);
+    }
+}
@@ -0,0 +1,50 @@
+//! Email over SMTP.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use super::{EmailError, EmailMessage, EmailSender};
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Hands email to an SMTP server.
+pub struct SmtpEmailSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpEmailSender {
+    /// Send through the server at an `smtp://` or `smtps://` URL.
+    pub fn from_url(url: &str) -> Result<Self, EmailError> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(url)
+            .map_err(|err| EmailError::Config(format!("email.smtp_url: {err}")))?
+            .timeout(Some(SEND_TIMEOUT))
+            .build();
+        Ok(Self { transport })
+    }
+}
+
+fn mailbox(address: &str) -> Result<Mailbox, EmailError> {
+    address
+        .parse()
+        .map_err(|err| EmailError::Address(format!("{address}: {err}")))
+}
+
+#[async_trait]
+impl EmailSender for SmtpEmailSender {
+    async fn send(&self, message: EmailMessage) -> Result<(), EmailError> {
+        let email = Message::builder()
+            .from(mailbox(&message.from)?)
+            .to(mailbox(&message.to)?)
+            .subject(message.subject)
+            .body(message.body)
+            .map_err(|err| EmailError::Send(err.to_string()))?;
+        self.transport
+            .send(email)
+            .await
+            .map_err(|err| EmailError::Send(err.to_string()))?;
+        Ok(())
+    }
+}
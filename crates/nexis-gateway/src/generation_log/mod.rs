@@ -0,0 +1,404 @@
+//! Log of AI generations for debugging prompts.
+//!
+//! When `[generation_log] enabled` is on, every request the gateway sends to
+//! an AI provider is recorded with the response or error it got back, after
+//! passing prompts and completions through a [`Redactor`]. The most recent
+//! entries are kept in memory so administrators can search them by member,
+//! room and time via `GET /v1/generations`, and re-run one against another
+//! provider or model via `POST /v1/generations/:id/replay`.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use nexis_runtime::{GenerateRequest, GenerateResponse};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::config::GenerationLogConfig;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+/// What redacted text is replaced with.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Scrubs sensitive text from prompts and completions before they are logged.
+pub trait Redactor: Send + Sync {
+    fn redact(&self, text: &str) -> String;
+}
+
+/// Replaces every match of a set of regular expressions with [`REDACTED`].
+#[derive(Debug, Clone, Default)]
+pub struct PatternRedactor {
+    patterns: Vec<Regex>,
+}
+
+impl PatternRedactor {
+    pub fn new(patterns: &[String]) -> Result<Self, regex::Error> {
+        Ok(Self {
+            patterns: patterns
+                .iter()
+                .map(|pattern| Regex::new(pattern))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl Redactor for PatternRedactor {
+    fn redact(&self, text: &str) -> String {
+        self.patterns
+            .iter()
+            .fold(text.to_string(), |text, pattern| {
+                pattern.replace_all(&text, REDACTED).into_owned()
+            })
+    }
+}
+
+/// The parts of a [`GenerateRequest`] that are logged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggedRequest {
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Number of images sent along; the images themselves are not logged.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub images: usize,
+}
+
+impl From<&GenerateRequest> for LoggedRequest {
+    fn from(request: &GenerateRequest) -> Self {
+        Self {
+            prompt: request.prompt.clone(),
+            model: request.model.clone(),
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            images: request.images.len(),
+        }
+    }
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+/// The parts of a [`GenerateResponse`] that are logged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggedResponse {
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_tokens: Option<u32>,
+}
+
+impl From<&GenerateResponse> for LoggedResponse {
+    fn from(response: &GenerateResponse) -> Self {
+        Self {
+            content: response.content.clone(),
+            model: response.model.clone(),
+            finish_reason: response.finish_reason.clone(),
+            input_tokens: response.usage.map(|usage| usage.input_tokens),
+            output_tokens: response.usage.map(|usage| usage.output_tokens),
+        }
+    }
+}
+
+/// One request sent to an AI provider and what came back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationLogEntry {
+    pub id: String,
+    /// Member the generation was made for.
+    pub member_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+    pub provider: String,
+    pub request: LoggedRequest,
+    /// Set when the provider answered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<LoggedResponse>,
+    /// Set when the provider failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub latency_ms: u64,
+    /// Id of the entry this one replayed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay_of: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl GenerationLogEntry {
+    /// A request to `provider` on behalf of `member_id`, before it is sent.
+    /// The room and the replayed entry are read from the `roomId` and
+    /// `replayOf` the request metadata carries, if any.
+    pub fn new(
+        member_id: impl Into<String>,
+        provider: impl Into<String>,
+        request: &GenerateRequest,
+    ) -> Self {
+        let metadata = |key: &str| {
+            request
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get(key))
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+        };
+        Self {
+            id: format!("gen_{}", Uuid::new_v4().simple()),
+            member_id: member_id.into(),
+            room_id: metadata("roomId"),
+            provider: provider.into(),
+            request: LoggedRequest::from(request),
+            response: None,
+            error: None,
+            latency_ms: 0,
+            replay_of: metadata("replayOf"),
+            occurred_at: Utc::now(),
+        }
+    }
+
+    /// What the provider answered, `latency` after the request was sent.
+    pub fn with_result(
+        mut self,
+        result: Result<&GenerateResponse, String>,
+        latency: Duration,
+    ) -> Self {
+        match result {
+            Ok(response) => self.response = Some(LoggedResponse::from(response)),
+            Err(error) => self.error = Some(error),
+        }
+        self.latency_ms = u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+}
+
+/// Filters and paging for [`GenerationLog::query`].
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct GenerationLogQuery {
+    pub member_id: Option<String>,
+    pub room_id: Option<String>,
+    pub provider: Option<String>,
+    /// Only generations at or after this instant.
+    pub since: Option<DateTime<Utc>>,
+    /// Only generations before this instant.
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl GenerationLogQuery {
+    fn matches(&self, entry: &GenerationLogEntry) -> bool {
+        self.member_id
+            .as_deref()
+            .is_none_or(|member_id| entry.member_id == member_id)
+            && self
+                .room_id
+                .as_deref()
+                .is_none_or(|room_id| entry.room_id.as_deref() == Some(room_id))
+            && self
+                .provider
+                .as_deref()
+                .is_none_or(|provider| entry.provider == provider)
+            && self.since.is_none_or(|since| entry.occurred_at >= since)
+            && self.until.is_none_or(|until| entry.occurred_at < until)
+    }
+}
+
+/// A page of logged generations, newest first.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationLogPage {
+    pub generations: Vec<GenerationLogEntry>,
+    /// Number of retained generations matching the filters.
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+struct Inner {
+    redactor: Arc<dyn Redactor>,
+    recent: RwLock<VecDeque<GenerationLogEntry>>,
+    retained: usize,
+}
+
+/// Keeps a bounded window of redacted AI generations.
+#[derive(Clone)]
+pub struct GenerationLog {
+    inner: Arc<Inner>,
+}
+
+impl GenerationLog {
+    pub fn new(redactor: Arc<dyn Redactor>, retained: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                redactor,
+                recent: RwLock::new(VecDeque::new()),
+                retained: retained.max(1),
+            }),
+        }
+    }
+
+    /// Build the log described by `config`, or `None` when it is disabled.
+    pub fn from_config(config: &GenerationLogConfig) -> Result<Option<Self>, regex::Error> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let redactor = PatternRedactor::new(&config.redact_patterns)?;
+        Ok(Some(Self::new(Arc::new(redactor), config.retained)))
+    }
+
+    /// Redact `entry` and keep it, dropping the oldest entry when full.
+    pub async fn record(&self, mut entry: GenerationLogEntry) {
+        let redactor = &self.inner.redactor;
+        entry.request.prompt = redactor.redact(&entry.request.prompt);
+        if let Some(response) = &mut entry.response {
+            response.content = redactor.redact(&response.content);
+        }
+        if let Some(error) = &mut entry.error {
+            *error = redactor.redact(error);
+        }
+
+        let mut recent = self.inner.recent.write().await;
+        if recent.len() == self.inner.retained {
+            recent.pop_front();
+        }
+        recent.push_back(entry);
+    }
+
+    pub async fn get(&self, id: &str) -> Option<GenerationLogEntry> {
+        self.inner
+            .recent
+            .read()
+            .await
+            .iter()
+            .find(|entry| entry.id == id)
+            .cloned()
+    }
+
+    /// Page through retained generations matching `query`, newest first.
+    pub async fn query(&self, query: &GenerationLogQuery) -> GenerationLogPage {
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let offset = query.offset.unwrap_or(0);
+
+        let recent = self.inner.recent.read().await;
+        let matching: Vec<&GenerationLogEntry> = recent
+            .iter()
+            .rev()
+            .filter(|entry| query.matches(entry))
+            .collect();
+        GenerationLogPage {
+            total: matching.len(),
+            generations: matching
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .cloned()
+                .collect(),
+            limit,
+            offset,
+        }
+    }
+
+    /// Forget the generations made in a deleted room.
+    pub async fn remove_room(&self, room_id: &str) {
+        self.inner
+            .recent
+            .write()
+            .await
+            .retain(|entry| entry.room_id.as_deref() != Some(room_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(prompt: &str, room_id: &str) -> GenerateRequest {
+        GenerateRequest {
+            prompt: prompt.to_string(),
+            model: None,
+            max_tokens: None,
+            temperature: None,
+            metadata: Some(serde_json::json!({ "roomId": room_id })),
+            images: Vec::new(),
+        }
+    }
+
+    fn response(content: &str) -> GenerateResponse {
+        GenerateResponse {
+            content: content.to_string(),
+            model: Some("echo-model".to_string()),
+            finish_reason: None,
+            usage: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn record_redacts_and_query_filters_newest_first() {
+        let redactor = PatternRedactor::new(&[r"sk-[A-Za-z0-9]+".to_string()]).unwrap();
+        let log = GenerationLog::new(Arc::new(redactor), 3);
+        for (member, prompt, room) in [
+            ("alice", "first", "room_a"),
+            ("alice", "key sk-abc123 please", "room_a"),
+            ("bob", "third", "room_b"),
+            ("alice", "fourth", "room_b"),
+        ] {
+            let entry = GenerationLogEntry::new(member, "echo", &request(prompt, room))
+                .with_result(Ok(&response(prompt)), Duration::from_millis(5));
+            log.record(entry).await;
+        }
+
+        // The oldest entry fell out of the retained window.
+        let all = log.query(&GenerationLogQuery::default()).await;
+        assert_eq!(all.total, 3);
+        assert_eq!(all.generations[0].request.prompt, "fourth");
+
+        let room_a = log
+            .query(&GenerationLogQuery {
+                room_id: Some("room_a".to_string()),
+                ..GenerationLogQuery::default()
+            })
+            .await;
+        assert_eq!(room_a.total, 1);
+        let entry = &room_a.generations[0];
+        assert_eq!(entry.request.prompt, "key [REDACTED] please");
+        assert_eq!(
+            entry.response.as_ref().unwrap().content,
+            "key [REDACTED] please"
+        );
+        assert_eq!(log.get(&entry.id).await.as_ref(), Some(entry));
+
+        let bob = log
+            .query(&GenerationLogQuery {
+                member_id: Some("bob".to_string()),
+                until: Some(Utc::now()),
+                ..GenerationLogQuery::default()
+            })
+            .await;
+        assert_eq!(bob.total, 1);
+
+        log.remove_room("room_b").await;
+        assert_eq!(log.query(&GenerationLogQuery::default()).await.total, 1);
+    }
+}
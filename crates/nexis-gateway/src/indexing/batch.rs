@@ -0,0 +1,614 @@
+//! Batched indexing queue with bounded depth and overflow policies
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use nexis_protocol::RoomId;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{oneshot, Mutex, Notify, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, error, warn, Instrument};
+use uuid::Uuid;
+
+use super::queue::{IndexTask, QueueStats};
+use super::service::{IndexingError, IndexingService};
+use crate::metrics::{
+    record_indexing_flush, record_indexing_overflow, record_indexing_queue_depth,
+};
+
+/// What to do when a message arrives while the queue is at its maximum depth
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Evict the oldest queued message to make room
+    DropOldest,
+    /// Refuse the new message with [`IndexingError::QueueFull`]
+    Reject,
+    /// Wait until the worker frees up space
+    #[default]
+    Block,
+}
+
+impl OverflowPolicy {
+    /// Metric label for this policy
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DropOldest => "drop_oldest",
+            Self::Reject => "reject",
+            Self::Block => "block",
+        }
+    }
+}
+
+/// Batching configuration for the indexing queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchConfig {
+    /// Flush as soon as this many messages are queued
+    pub max_batch_size: usize,
+    /// Flush whatever is queued after this interval
+    pub flush_interval_ms: u64,
+    /// Maximum number of messages waiting to be indexed
+    pub max_queue_depth: usize,
+    /// Behaviour when the queue is full
+    pub overflow_policy: OverflowPolicy,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 32,
+            flush_interval_ms: 500,
+            max_queue_depth: 1024,
+            overflow_policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+impl BatchConfig {
+    /// Set the batch size that triggers a flush
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
+
+    /// Set the periodic flush interval
+    pub fn with_flush_interval_ms(mut self, flush_interval_ms: u64) -> Self {
+        self.flush_interval_ms = flush_interval_ms.max(1);
+        self
+    }
+
+    /// Set the maximum queue depth
+    pub fn with_max_queue_depth(mut self, max_queue_depth: usize) -> Self {
+        self.max_queue_depth = max_queue_depth.max(1);
+        self
+    }
+
+    /// Set the overflow policy
+    pub fn with_overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+}
+
+struct Shared {
+    tasks: Mutex<VecDeque<IndexTask>>,
+    capacity: Semaphore,
+    flush: Notify,
+    stats: Mutex<QueueStats>,
+}
+
+/// Background indexing queue that accumulates messages and indexes them in batches
+pub struct BatchingIndexingQueue {
+    shared: Arc<Shared>,
+    config: BatchConfig,
+    /// Taken by the first [`Self::shutdown`], so a queue shared behind an
+    /// `Arc` can be stopped.
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl BatchingIndexingQueue {
+    /// Create a new batching queue and spawn its flush worker
+    pub fn new(service: Arc<dyn IndexingService>, config: BatchConfig) -> Self {
+        let config = BatchConfig {
+            max_batch_size: config.max_batch_size.max(1),
+            flush_interval_ms: config.flush_interval_ms.max(1),
+            max_queue_depth: config.max_queue_depth.max(1),
+            ..config
+        };
+        let shared = Arc::new(Shared {
+            tasks: Mutex::new(VecDeque::with_capacity(config.max_queue_depth)),
+            capacity: Semaphore::new(config.max_queue_depth),
+            flush: Notify::new(),
+            stats: Mutex::new(QueueStats::default()),
+        });
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
+
+        let state = shared.clone();
+        let max_batch_size = config.max_batch_size;
+        let flush_interval = Duration::from_millis(config.flush_interval_ms);
+
+        let worker = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval_at(
+                tokio::time::Instant::now() + flush_interval,
+                flush_interval,
+            );
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_rx => {
+                        state.flush_pending(service.as_ref(), max_batch_size).await;
+                        break;
+                    }
+                    _ = state.flush.notified() => {}
+                    _ = ticker.tick() => {}
+                }
+                state.flush_pending(service.as_ref(), max_batch_size).await;
+            }
+            debug!("Batching indexing queue worker stopped");
+        });
+
+        Self {
+            shared,
+            config,
+            shutdown: Mutex::new(Some(shutdown_tx)),
+            worker: Mutex::new(Some(worker)),
+        }
+    }
+
+    /// Enqueue a task, applying the overflow policy if the queue is full
+    pub async fn enqueue(&self, task: IndexTask) -> Result<(), IndexingError> {
+        let policy = self.config.overflow_policy;
+
+        let depth = match policy {
+            OverflowPolicy::Block => {
+                if self.shared.capacity.available_permits() == 0 {
+                    record_indexing_overflow(policy.as_str());
+                }
+                self.shared
+                    .capacity
+                    .acquire()
+                    .await
+                    .map_err(|_| IndexingError::InvalidMessage("Queue closed".to_string()))?
+                    .forget();
+                self.push(task).await
+            }
+            OverflowPolicy::Reject => match self.shared.capacity.try_acquire() {
+                Ok(permit) => {
+                    permit.forget();
+                    self.push(task).await
+                }
+                Err(_) => {
+                    record_indexing_overflow(policy.as_str());
+                    self.shared.stats.lock().await.rejected += 1;
+                    return Err(IndexingError::QueueFull(self.config.max_queue_depth));
+                }
+            },
+            OverflowPolicy::DropOldest => {
+                let mut tasks = self.shared.tasks.lock().await;
+                match self.shared.capacity.try_acquire() {
+                    Ok(permit) => permit.forget(),
+                    Err(_) => {
+                        record_indexing_overflow(policy.as_str());
+                        match tasks.pop_front() {
+                            // The new task takes over the dropped task's slot.
+                            Some(dropped) => {
+                                warn!(task_id = %dropped.id, "Indexing queue full, dropping oldest task");
+                                self.shared.stats.lock().await.dropped += 1;
+                            }
+                            // Every slot belongs to a batch being indexed, so
+                            // there is nothing to drop; wait for the flush.
+                            None => {
+                                drop(tasks);
+                                self.shared
+                                    .capacity
+                                    .acquire()
+                                    .await
+                                    .map_err(|_| {
+                                        IndexingError::InvalidMessage("Queue closed".to_string())
+                                    })?
+                                    .forget();
+                                tasks = self.shared.tasks.lock().await;
+                            }
+                        }
+                    }
+                }
+                tasks.push_back(task);
+                tasks.len()
+            }
+        };
+
+        record_indexing_queue_depth(depth);
+        if depth >= self.config.max_batch_size {
+            self.shared.flush.notify_one();
+        }
+        Ok(())
+    }
+
+    /// Enqueue a message for batched indexing, returning the task ID
+    pub async fn index_message(
+        &self,
+        message: String,
+        room_id: RoomId,
+        metadata: serde_json::Value,
+    ) -> Result<Uuid, IndexingError> {
+        let task = IndexTask::new(message, room_id, metadata);
+        let task_id = task.id;
+        self.enqueue(task).await?;
+        Ok(task_id)
+    }
+
+    /// Ask the worker to flush queued messages without waiting for the interval
+    pub fn flush(&self) {
+        self.shared.flush.notify_one();
+    }
+
+    /// Stop the worker after a final flush and wait for it to finish.
+    ///
+    /// Tasks that still need a retry after the final flush are dropped, and
+    /// so are tasks enqueued afterwards. Later calls return at once.
+    pub async fn shutdown(&self) {
+        if let Some(shutdown) = self.shutdown.lock().await.take() {
+            let _ = shutdown.send(());
+        }
+        let Some(worker) = self.worker.lock().await.take() else {
+            return;
+        };
+        if let Err(err) = worker.await {
+            error!(error = %err, "Batching indexing queue worker panicked");
+        }
+    }
+
+    /// Number of messages waiting to be indexed
+    pub async fn len(&self) -> usize {
+        self.shared.tasks.lock().await.len()
+    }
+
+    /// Check if no messages are waiting
+    pub async fn is_empty(&self) -> bool {
+        self.shared.tasks.lock().await.is_empty()
+    }
+
+    /// Get queue statistics
+    pub async fn stats(&self) -> QueueStats {
+        let stats = self.shared.stats.lock().await.clone();
+        let pending = self.len().await;
+        QueueStats { pending, ..stats }
+    }
+
+    /// Get the batching configuration
+    pub fn config(&self) -> &BatchConfig {
+        &self.config
+    }
+
+    async fn push(&self, task: IndexTask) -> usize {
+        let mut tasks = self.shared.tasks.lock().await;
+        tasks.push_back(task);
+        tasks.len()
+    }
+}
+
+impl Shared {
+    async fn take_batch(&self, max_batch_size: usize) -> Vec<IndexTask> {
+        let mut tasks = self.tasks.lock().await;
+        let count = tasks.len().min(max_batch_size);
+        tasks.drain(..count).collect()
+    }
+
+    /// Flush queued tasks batch by batch, stopping early if a batch needs retrying
+    async fn flush_pending(&self, service: &dyn IndexingService, max_batch_size: usize) {
+        loop {
+            let batch = self.take_batch(max_batch_size).await;
+            if batch.is_empty() {
+                break;
+            }
+
+            let span = tracing::info_span!("indexing.batch", batch_size = batch.len());
+            for id in batch.iter().filter_map(IndexTask::origin_span_id) {
+                span.follows_from(id);
+            }
+            let started = Instant::now();
+            let results = service.index_batch(&batch).instrument(span).await;
+            record_indexing_flush(batch.len(), started);
+
+            let mut retry = Vec::new();
+            let mut completed = 0u64;
+            let mut failed = 0u64;
+            for (mut task, result) in batch.into_iter().zip(results) {
+                match result {
+                    Ok(_) => completed += 1,
+                    Err(IndexingError::EmbeddingError(e)) => {
+                        task.increment_attempt();
+                        if task.can_retry() {
+                            warn!(task_id = %task.id, error = %e, "Embedding error, will retry");
+                            retry.push(task);
+                        } else {
+                            error!(task_id = %task.id, "Task failed after max retries");
+                            failed += 1;
+                        }
+                    }
+                    Err(e) => {
+                        error!(task_id = %task.id, error = %e, "Indexing task failed");
+                        failed += 1;
+                    }
+                }
+            }
+
+            let retried = retry.len();
+            let depth = {
+                let mut tasks = self.tasks.lock().await;
+                for task in retry.into_iter().rev() {
+                    tasks.push_front(task);
+                }
+                // Retried tasks keep their slot; release capacity for everything else.
+                self.capacity.add_permits((completed + failed) as usize);
+                tasks.len()
+            };
+            record_indexing_queue_depth(depth);
+
+            let mut stats = self.stats.lock().await;
+            stats.completed += completed;
+            stats.failed += failed;
+            stats.retries += retried as u64;
+            stats.batches += 1;
+            drop(stats);
+
+            if retried > 0 {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexing::service::IndexingResult;
+    use async_trait::async_trait;
+    use nexis_vector::SearchResult;
+
+    #[derive(Default)]
+    struct RecordingService {
+        batches: std::sync::Mutex<Vec<Vec<String>>>,
+    }
+
+    impl RecordingService {
+        fn batches(&self) -> Vec<Vec<String>> {
+            self.batches.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl IndexingService for RecordingService {
+        async fn index_message(
+            &self,
+            _message: &str,
+            _room_id: RoomId,
+            _metadata: serde_json::Value,
+        ) -> IndexingResult<Uuid> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn index_batch(&self, tasks: &[IndexTask]) -> Vec<IndexingResult<Uuid>> {
+            self.batches
+                .lock()
+                .unwrap()
+                .push(tasks.iter().map(|task| task.message.clone()).collect());
+            tasks.iter().map(|_| Ok(Uuid::new_v4())).collect()
+        }
+
+        async fn search(&self, _query: &str, _limit: usize) -> IndexingResult<Vec<SearchResult>> {
+            Ok(Vec::new())
+        }
+
+        async fn search_in_room(
+            &self,
+            _query: &str,
+            _room_id: RoomId,
+            _limit: usize,
+        ) -> IndexingResult<Vec<SearchResult>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn task(message: &str) -> IndexTask {
+        IndexTask::new(
+            message.to_string(),
+            RoomId::generate(),
+            serde_json::json!({}),
+        )
+    }
+
+    async fn wait_for_batches(service: &RecordingService, count: usize) -> Vec<Vec<String>> {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                let batches = service.batches();
+                if batches.len() >= count {
+                    return batches;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("batches were not flushed in time")
+    }
+
+    #[tokio::test]
+    async fn shutdown_flushes_pending_tasks() {
+        let service = Arc::new(RecordingService::default());
+        let config = BatchConfig::default()
+            .with_max_batch_size(10)
+            .with_flush_interval_ms(60_000);
+        let queue = Arc::new(BatchingIndexingQueue::new(service.clone(), config));
+
+        queue.enqueue(task("a")).await.unwrap();
+        queue.shutdown().await;
+        queue.shutdown().await;
+
+        assert_eq!(service.batches(), vec![vec!["a".to_string()]]);
+    }
+
+    #[tokio::test]
+    async fn flushes_when_batch_size_reached() {
+        let service = Arc::new(RecordingService::default());
+        let config = BatchConfig::default()
+            .with_max_batch_size(2)
+            .with_flush_interval_ms(60_000);
+        let queue = BatchingIndexingQueue::new(service.clone(), config);
+
+        queue.enqueue(task("a")).await.unwrap();
+        queue.enqueue(task("b")).await.unwrap();
+
+        let batches = wait_for_batches(&service, 1).await;
+        assert_eq!(batches[0], vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn flushes_partial_batch_on_interval() {
+        let service = Arc::new(RecordingService::default());
+        let config = BatchConfig::default()
+            .with_max_batch_size(100)
+            .with_flush_interval_ms(20);
+        let queue = BatchingIndexingQueue::new(service.clone(), config);
+
+        queue.enqueue(task("only")).await.unwrap();
+
+        let batches = wait_for_batches(&service, 1).await;
+        assert_eq!(batches[0], vec!["only".to_string()]);
+
+        let stats = queue.stats().await;
+        assert_eq!(stats.completed, 1);
+        assert_eq!(stats.pending, 0);
+    }
+
+    #[tokio::test]
+    async fn reject_policy_refuses_when_full() {
+        let service = Arc::new(RecordingService::default());
+        let config = BatchConfig::default()
+            .with_max_batch_size(100)
+            .with_flush_interval_ms(60_000)
+            .with_max_queue_depth(2)
+            .with_overflow_policy(OverflowPolicy::Reject);
+        let queue = BatchingIndexingQueue::new(service, config);
+
+        queue.enqueue(task("a")).await.unwrap();
+        queue.enqueue(task("b")).await.unwrap();
+        let err = queue.enqueue(task("c")).await.unwrap_err();
+
+        assert!(matches!(err, IndexingError::QueueFull(2)));
+        assert_eq!(queue.len().await, 2);
+        assert_eq!(queue.stats().await.rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_policy_evicts_head() {
+        let service = Arc::new(RecordingService::default());
+        let config = BatchConfig::default()
+            .with_max_batch_size(100)
+            .with_flush_interval_ms(60_000)
+            .with_max_queue_depth(2)
+            .with_overflow_policy(OverflowPolicy::DropOldest);
+        let queue = BatchingIndexingQueue::new(service.clone(), config);
+
+        queue.enqueue(task("a")).await.unwrap();
+        queue.enqueue(task("b")).await.unwrap();
+        queue.enqueue(task("c")).await.unwrap();
+        assert_eq!(queue.stats().await.dropped, 1);
+
+        queue.flush();
+        let batches = wait_for_batches(&service, 1).await;
+        assert_eq!(batches[0], vec!["b".to_string(), "c".to_string()]);
+    }
+
+    /// Holds every batch until `release` is notified.
+    #[derive(Default)]
+    struct GatedService {
+        started: Notify,
+        release: Notify,
+    }
+
+    #[async_trait]
+    impl IndexingService for GatedService {
+        async fn index_message(
+            &self,
+            _message: &str,
+            _room_id: RoomId,
+            _metadata: serde_json::Value,
+        ) -> IndexingResult<Uuid> {
+            Ok(Uuid::new_v4())
+        }
+
+        async fn index_batch(&self, tasks: &[IndexTask]) -> Vec<IndexingResult<Uuid>> {
+            self.started.notify_one();
+            self.release.notified().await;
+            tasks.iter().map(|_| Ok(Uuid::new_v4())).collect()
+        }
+
+        async fn search(&self, _query: &str, _limit: usize) -> IndexingResult<Vec<SearchResult>> {
+            Ok(Vec::new())
+        }
+
+        async fn search_in_room(
+            &self,
+            _query: &str,
+            _room_id: RoomId,
+            _limit: usize,
+        ) -> IndexingResult<Vec<SearchResult>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_policy_waits_while_every_slot_is_being_flushed() {
+        let service = Arc::new(GatedService::default());
+        let config = BatchConfig::default()
+            .with_max_batch_size(100)
+            .with_flush_interval_ms(60_000)
+            .with_max_queue_depth(1)
+            .with_overflow_policy(OverflowPolicy::DropOldest);
+        let queue = BatchingIndexingQueue::new(service.clone(), config);
+
+        queue.enqueue(task("a")).await.unwrap();
+        queue.flush();
+        service.started.notified().await;
+
+        let blocked =
+            tokio::time::timeout(Duration::from_millis(50), queue.enqueue(task("b"))).await;
+        assert!(blocked.is_err());
+        assert_eq!(queue.stats().await.dropped, 0);
+
+        service.release.notify_one();
+        tokio::time::timeout(Duration::from_secs(2), queue.enqueue(task("b")))
+            .await
+            .expect("enqueue should go through once the batch is indexed")
+            .unwrap();
+        assert_eq!(queue.len().await, 1);
+        assert_eq!(queue.stats().await.dropped, 0);
+    }
+
+    #[tokio::test]
+    async fn block_policy_waits_for_capacity() {
+        let service = Arc::new(RecordingService::default());
+        let config = BatchConfig::default()
+            .with_max_batch_size(100)
+            .with_flush_interval_ms(60_000)
+            .with_max_queue_depth(1)
+            .with_overflow_policy(OverflowPolicy::Block);
+        let queue = BatchingIndexingQueue::new(service.clone(), config);
+
+        queue.enqueue(task("a")).await.unwrap();
+        let blocked =
+            tokio::time::timeout(Duration::from_millis(50), queue.enqueue(task("b"))).await;
+        assert!(blocked.is_err());
+
+        queue.flush();
+        tokio::time::timeout(Duration::from_secs(2), queue.enqueue(task("b")))
+            .await
+            .expect("enqueue should unblock after flush")
+            .unwrap();
+        assert_eq!(queue.len().await, 1);
+    }
+}
@@ -0,0 +1,206 @@
+//! Splitting long messages into overlapping chunks before embedding
+//!
+//! Embedding a pasted document as a single vector blurs everything it says
+//! into one point, and providers truncate input past their context size.
+//! [`Chunker`] splits such text into chunks of at most
+//! [`ChunkerConfig::max_tokens`], ending chunks at sentence or line breaks
+//! where it can and repeating the last [`ChunkerConfig::overlap_tokens`] of
+//! each chunk at the start of the next, so a passage cut at a boundary is
+//! still found whole in one of them.
+//!
+//! Tokens are approximated by whitespace-separated words.
+
+/// Chunk size and overlap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkerConfig {
+    /// Longest chunk, in words; text up to this long stays in one piece
+    pub max_tokens: usize,
+    /// Words repeated from the end of one chunk at the start of the next;
+    /// kept below `max_tokens`
+    pub overlap_tokens: usize,
+}
+
+impl Default for ChunkerConfig {
+    fn default() -> Self {
+        Self {
+            max_tokens: 256,
+            overlap_tokens: 32,
+        }
+    }
+}
+
+/// Splits text into overlapping chunks
+#[derive(Debug, Clone, Default)]
+pub struct Chunker {
+    config: ChunkerConfig,
+}
+
+/// One whitespace-separated word of the input
+struct Word {
+    start: usize,
+    end: usize,
+    /// Whether a sentence or line ends after this word
+    boundary: bool,
+}
+
+impl Chunker {
+    /// Create a chunker; `max_tokens` is at least one and the overlap is
+    /// always shorter than it
+    pub fn new(config: ChunkerConfig) -> Self {
+        let max_tokens = config.max_tokens.max(1);
+        Self {
+            config: ChunkerConfig {
+                max_tokens,
+                overlap_tokens: config.overlap_tokens.min(max_tokens - 1),
+            },
+        }
+    }
+
+    /// Chunk size and overlap in use
+    pub fn config(&self) -> &ChunkerConfig {
+        &self.config
+    }
+
+    /// Split `text` into chunks, in order
+    ///
+    /// Text of at most `max_tokens` words comes back unchanged as the only
+    /// chunk. Chunks are slices of `text`, so line breaks and spacing inside
+    /// them are kept.
+    pub fn chunk(&self, text: &str) -> Vec<String> {
+        let words = Self::words(text);
+        let (max, overlap) = (self.config.max_tokens, self.config.overlap_tokens);
+        if words.len() <= max {
+            return vec![text.to_string()];
+        }
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        loop {
+            let mut end = (start + max).min(words.len());
+            if end < words.len() {
+                // End at the last sentence break that still moves past the
+                // overlap, so the next chunk starts further on.
+                if let Some(boundary) = (start + overlap + 1..end)
+                    .rev()
+                    .find(|&i| words[i - 1].boundary)
+                {
+                    end = boundary;
+                }
+            }
+            chunks.push(text[words[start].start..words[end - 1].end].to_string());
+            if end == words.len() {
+                return chunks;
+            }
+
+            // Start the next chunk at a sentence within the overlap if there
+            // is one, otherwise exactly `overlap` words back.
+            let back = end - overlap;
+            start = (back..end)
+                .find(|&i| i > 0 && words[i - 1].boundary)
+                .unwrap_or(back);
+        }
+    }
+
+    fn words(text: &str) -> Vec<Word> {
+        let mut words: Vec<Word> = Vec::new();
+        let mut start = None;
+        for (i, c) in text.char_indices() {
+            match (c.is_whitespace(), start) {
+                (false, None) => start = Some(i),
+                (true, Some(begin)) => {
+                    words.push(Word::new(text, begin, i));
+                    start = None;
+                }
+                _ => {}
+            }
+            if c == '\n' {
+                if let Some(last) = words.last_mut() {
+                    if start.is_none() {
+                        last.boundary = true;
+                    }
+                }
+            }
+        }
+        if let Some(begin) = start {
+            words.push(Word::new(text, begin, text.len()));
+        }
+        words
+    }
+}
+
+impl Word {
+    fn new(text: &str, start: usize, end: usize) -> Self {
+        let word = text[start..end].trim_end_matches(['"', '\'', ')', ']']);
+        Self {
+            start,
+            end,
+            boundary: word.ends_with(['.', '!', '?', ':', ';']),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunker(max_tokens: usize, overlap_tokens: usize) -> Chunker {
+        Chunker::new(ChunkerConfig {
+            max_tokens,
+            overlap_tokens,
+        })
+    }
+
+    #[test]
+    fn short_text_is_one_chunk() {
+        let text = "  Hello there,\nworld. ";
+        assert_eq!(chunker(3, 1).chunk(text), vec![text.to_string()]);
+        assert_eq!(Chunker::default().chunk(""), vec![String::new()]);
+    }
+
+    #[test]
+    fn long_text_is_split_with_overlap() {
+        let text = (1..=10)
+            .map(|i| format!("w{i}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let chunks = chunker(4, 1).chunk(&text);
+        assert_eq!(
+            chunks,
+            ["w1 w2 w3 w4", "w4 w5 w6 w7", "w7 w8 w9 w10"].map(String::from)
+        );
+
+        let chunks = chunker(4, 0).chunk(&text);
+        assert_eq!(
+            chunks,
+            ["w1 w2 w3 w4", "w5 w6 w7 w8", "w9 w10"].map(String::from)
+        );
+    }
+
+    #[test]
+    fn chunks_end_at_sentences_and_keep_line_breaks() {
+        let text = "One two three. Four five\nsix seven eight nine.\nTen eleven.";
+        let chunks = chunker(6, 2).chunk(text);
+        assert_eq!(
+            chunks,
+            [
+                "One two three. Four five",
+                "Four five\nsix seven eight nine.",
+                "eight nine.\nTen eleven.",
+            ]
+            .map(String::from)
+        );
+        for chunk in &chunks {
+            assert!(chunk.split_whitespace().count() <= 6);
+        }
+    }
+
+    #[test]
+    fn overlap_is_kept_shorter_than_a_chunk() {
+        let chunker = chunker(2, 5);
+        assert_eq!(chunker.config().overlap_tokens, 1);
+        assert_eq!(
+            chunker.chunk("a b c d"),
+            ["a b", "b c", "c d"].map(String::from)
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! Near-duplicate detection at index time
+//!
+//! Reposted or bot-spammed text would otherwise fill search results with
+//! copies of the same message. Before a message is stored, its embedding is
+//! compared with the room's recent documents; a match at or above
+//! [`DedupConfig::threshold`] cosine similarity is handled by the room's
+//! [`DuplicatePolicy`].
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use nexis_protocol::RoomId;
+use nexis_vector::{Document, SearchResult, Vector};
+use serde::{Deserialize, Serialize};
+
+/// Tag added to duplicates stored under [`DuplicatePolicy::Tag`]
+pub const DUPLICATE_TAG: &str = "duplicate";
+
+/// What to do with a message that duplicates a recent one in its room
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Index it like any other message, without checking
+    #[default]
+    Keep,
+    /// Don't index it; the earlier document's id is returned instead
+    Skip,
+    /// Don't index it, but count it in the earlier document's `duplicates`
+    /// metadata field
+    Merge,
+    /// Index it tagged [`DUPLICATE_TAG`], with the earlier document's id in
+    /// its `duplicate_of` metadata field
+    Tag,
+}
+
+impl DuplicatePolicy {
+    /// Metric label for this policy
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Keep => "keep",
+            Self::Skip => "skip",
+            Self::Merge => "merge",
+            Self::Tag => "tag",
+        }
+    }
+}
+
+/// Near-duplicate detection settings
+#[derive(Debug, Clone)]
+pub struct DedupConfig {
+    /// Cosine similarity at or above which a message duplicates another
+    pub threshold: f32,
+    /// How far back documents are compared; older ones never match
+    pub window: chrono::Duration,
+    /// Policy for rooms without one of their own
+    pub policy: DuplicatePolicy,
+    /// Policies of individual rooms
+    pub room_policies: HashMap<RoomId, DuplicatePolicy>,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 0.97,
+            window: chrono::Duration::days(1),
+            policy: DuplicatePolicy::Keep,
+            room_policies: HashMap::new(),
+        }
+    }
+}
+
+impl DedupConfig {
+    /// Handle duplicates in `room_id` with `policy`
+    pub fn with_room_policy(mut self, room_id: RoomId, policy: DuplicatePolicy) -> Self {
+        self.room_policies.insert(room_id, policy);
+        self
+    }
+
+    /// Policy applying to messages in `room_id`
+    pub fn policy_for(&self, room_id: Option<&RoomId>) -> DuplicatePolicy {
+        room_id
+            .and_then(|room_id| self.room_policies.get(room_id))
+            .copied()
+            .unwrap_or(self.policy)
+    }
+
+    /// The first of `candidates`, best match first, that `vector`
+    /// duplicates and that was created within the window before `now`
+    pub(super) fn original(
+        &self,
+        vector: &Vector,
+        candidates: Vec<SearchResult>,
+        now: DateTime<Utc>,
+    ) -> Option<Document> {
+        let since = now - self.window;
+        candidates
+            .into_iter()
+            .map(|candidate| candidate.document)
+            .find(|document| {
+                document.created_at >= since
+                    && vector.cosine_similarity(&document.vector) >= self.threshold
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nexis_vector::DocumentMetadata;
+
+    fn candidate(data: Vec<f32>, age: chrono::Duration, now: DateTime<Utc>) -> SearchResult {
+        let mut document = Document::new(Vector::new(data), String::new(), DocumentMetadata::new());
+        document.created_at = now - age;
+        SearchResult {
+            document,
+            score: 1.0,
+            explanation: None,
+        }
+    }
+
+    #[test]
+    fn originals_are_recent_and_similar_enough() {
+        let config = DedupConfig::default();
+        let now = Utc::now();
+        let vector = Vector::new(vec![1.0, 0.0]);
+
+        let stale = candidate(vec![1.0, 0.0], chrono::Duration::days(2), now);
+        let different = candidate(vec![1.0, 1.0], chrono::Duration::minutes(5), now);
+        let recent = candidate(vec![2.0, 0.01], chrono::Duration::hours(1), now);
+        let recent_id = recent.document.id;
+
+        let original = config.original(&vector, vec![stale, different, recent], now);
+        assert_eq!(original.map(|document| document.id), Some(recent_id));
+        assert!(config.original(&vector, Vec::new(), now).is_none());
+    }
+
+    #[test]
+    fn rooms_fall_back_to_the_default_policy() {
+        let room = RoomId::generate();
+        let config = DedupConfig {
+            policy: DuplicatePolicy::Tag,
+            ..DedupConfig::default()
+        }
+        .with_room_policy(room.clone(), DuplicatePolicy::Skip);
+
+        assert_eq!(config.policy_for(Some(&room)), DuplicatePolicy::Skip);
+        assert_eq!(
+            config.policy_for(Some(&RoomId::generate())),
+            DuplicatePolicy::Tag
+        );
+        assert_eq!(config.policy_for(None), DuplicatePolicy::Tag);
+    }
+}
@@ -5,11 +5,26 @@
 //! - Async embedding generation
 //! - Vector storage integration
 //! - Background task queue
+//! - Batched flushing with bounded queue depth
+//! - Outbox relay for messages stored before they were queued
+//! - Chunking of long messages before embedding
+//! - Near-duplicate detection with per-room policies
 
+mod batch;
+mod chunker;
+mod dedup;
+mod outbox;
 mod queue;
 mod retry;
 mod service;
 
+pub use batch::{BatchConfig, BatchingIndexingQueue, OverflowPolicy};
+pub use chunker::{Chunker, ChunkerConfig};
+pub use dedup::{DedupConfig, DuplicatePolicy, DUPLICATE_TAG};
+pub use outbox::{OutboxRelay, RelayStats};
 pub use queue::{IndexTask, IndexingQueue, QueueStats, SyncIndexingQueue, TaskStatus};
 pub use retry::{RetryConfig, RetryPolicy};
-pub use service::{IndexingError, IndexingService, MessageIndexer};
+pub use service::{
+    DocumentQuota, IndexableContent, IndexerConfig, IndexingError, IndexingResult, IndexingService,
+    MessageIndexer,
+};
@@ -0,0 +1,296 @@
+//! Relay from the message outbox into the indexing queue
+//!
+//! Messages stored through a repository built with an index outbox get an
+//! outbox row in the same write. [`OutboxRelay`] moves those rows into an
+//! [`IndexingQueue`], so a crash between storing a message and queueing it
+//! delays indexing instead of losing it.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use nexis_protocol::RoomId;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use super::queue::{IndexTask, IndexingQueue};
+use super::service::{IndexingError, IndexingResult};
+use crate::db::{IndexOutbox, OutboxEntry};
+use crate::server::ShutdownController;
+
+const DEFAULT_BATCH_SIZE: usize = 100;
+/// Message ids remembered for deduplication.
+const RECENT_CAPACITY: usize = 10_000;
+
+/// Outcome of one relay pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RelayStats {
+    /// Entries handed to the indexing queue.
+    pub enqueued: usize,
+    /// Entries acknowledged without queueing because their message was
+    /// already queued.
+    pub duplicates: usize,
+    /// Entries acknowledged without queueing because their room id is not a
+    /// valid [`RoomId`].
+    pub skipped: usize,
+}
+
+/// Message ids queued recently, oldest evicted first.
+#[derive(Debug, Default)]
+struct RecentIds {
+    order: VecDeque<String>,
+    ids: HashSet<String>,
+}
+
+impl RecentIds {
+    fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+
+    fn insert(&mut self, id: String) {
+        if !self.ids.insert(id.clone()) {
+            return;
+        }
+        self.order.push_back(id);
+        if self.order.len() > RECENT_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Moves outbox entries into an [`IndexingQueue`].
+///
+/// Entries are acknowledged only once queued, so delivery is at least once;
+/// entries whose message id was already queued are acknowledged without
+/// being queued again.
+pub struct OutboxRelay {
+    outbox: Arc<dyn IndexOutbox>,
+    queue: Arc<IndexingQueue>,
+    batch_size: usize,
+    recent: Mutex<RecentIds>,
+}
+
+impl OutboxRelay {
+    /// Create a relay from `outbox` into `queue`
+    pub fn new(outbox: Arc<dyn IndexOutbox>, queue: Arc<IndexingQueue>) -> Self {
+        Self {
+            outbox,
+            queue,
+            batch_size: DEFAULT_BATCH_SIZE,
+            recent: Mutex::new(RecentIds::default()),
+        }
+    }
+
+    /// Set how many entries one pass reads
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Queue one batch of pending entries and acknowledge them.
+    pub async fn relay_once(&self) -> IndexingResult<RelayStats> {
+        let entries = self
+            .outbox
+            .pending(self.batch_size)
+            .await
+            .map_err(|e| IndexingError::StorageError(e.to_string()))?;
+
+        let mut stats = RelayStats::default();
+        let mut handled = Vec::with_capacity(entries.len());
+        let mut result = Ok(());
+        let mut recent = self.recent.lock().await;
+        for entry in entries {
+            if recent.contains(&entry.message_id) {
+                stats.duplicates += 1;
+                handled.push(entry.message_id);
+                continue;
+            }
+            let Some(task) = index_task(&entry) else {
+                tracing::warn!(
+                    message_id = %entry.message_id,
+                    room_id = %entry.room_id,
+                    "Skipped outbox entry with an invalid room id"
+                );
+                stats.skipped += 1;
+                handled.push(entry.message_id);
+                continue;
+            };
+            if let Err(e) = self.queue.enqueue(task).await {
+                // Leave this and later entries for the next pass.
+                result = Err(e);
+                break;
+            }
+            recent.insert(entry.message_id.clone());
+            stats.enqueued += 1;
+            handled.push(entry.message_id);
+        }
+        drop(recent);
+
+        if !handled.is_empty() {
+            self.outbox
+                .acknowledge(&handled)
+                .await
+                .map_err(|e| IndexingError::StorageError(e.to_string()))?;
+        }
+        result.map(|()| stats)
+    }
+
+    /// Relay every `interval` until `shutdown` is triggered.
+    pub fn spawn(
+        self: Arc<Self>,
+        interval: Duration,
+        shutdown: ShutdownController,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let stopped = shutdown.triggered();
+            tokio::pin!(stopped);
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    _ = interval.tick() => {
+                        match self.relay_once().await {
+                            Ok(stats) if stats.enqueued > 0 => {
+                                tracing::debug!(enqueued = stats.enqueued, "Relayed outbox entries");
+                            }
+                            Ok(_) => {}
+                            Err(e) => tracing::warn!(error = %e, "Outbox relay failed"),
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Indexing task for an outbox entry; `None` if its room id is malformed.
+fn index_task(entry: &OutboxEntry) -> Option<IndexTask> {
+    let room_id = entry.room_id.parse::<RoomId>().ok()?;
+    let task = IndexTask::new(
+        entry.content.clone(),
+        room_id,
+        serde_json::json!({ "messageId": entry.message_id }),
+    );
+    Some(match &entry.tenant_id {
+        Some(tenant_id) => task.with_tenant(tenant_id.clone()),
+        None => task,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{InMemoryMessageRepository, Message, MessageRepository, RepositoryError};
+    use crate::indexing::IndexingService;
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use nexis_vector::prelude::SearchResult;
+    use uuid::Uuid;
+
+    #[derive(Default)]
+    struct RecordingService {
+        indexed: std::sync::Mutex<Vec<serde_json::Value>>,
+    }
+
+    #[async_trait]
+    impl IndexingService for RecordingService {
+        async fn index_message(
+            &self,
+            _message: &str,
+            _room_id: RoomId,
+            metadata: serde_json::Value,
+        ) -> IndexingResult<Uuid> {
+            self.indexed.lock().unwrap().push(metadata);
+            Ok(Uuid::new_v4())
+        }
+
+        async fn search(&self, _query: &str, _limit: usize) -> IndexingResult<Vec<SearchResult>> {
+            Ok(Vec::new())
+        }
+
+        async fn search_in_room(
+            &self,
+            _query: &str,
+            _room_id: RoomId,
+            _limit: usize,
+        ) -> IndexingResult<Vec<SearchResult>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn message(id: &str) -> Message {
+        Message {
+            id: id.to_string(),
+            room_id: RoomId::generate().to_string(),
+            sender_id: "member_1".to_string(),
+            content: format!("text of {id}"),
+            created_at: Utc::now(),
+            #[cfg(feature = "multi-tenant")]
+            tenant_id: None,
+        }
+    }
+
+    /// Outbox whose acknowledgements are lost, as after a crash between
+    /// queueing and acknowledging.
+    struct UnacknowledgedOutbox(Vec<OutboxEntry>);
+
+    #[async_trait]
+    impl IndexOutbox for UnacknowledgedOutbox {
+        async fn pending(&self, limit: usize) -> Result<Vec<OutboxEntry>, RepositoryError> {
+            Ok(self.0.iter().take(limit).cloned().collect())
+        }
+
+        async fn acknowledge(&self, _message_ids: &[String]) -> Result<(), RepositoryError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn relay_queues_each_stored_message_once() {
+        let repository = Arc::new(InMemoryMessageRepository::new().with_index_outbox());
+        let service = Arc::new(RecordingService::default());
+        let queue = Arc::new(IndexingQueue::new(service.clone(), 16));
+        let relay = OutboxRelay::new(repository.clone(), queue.clone());
+
+        repository.insert(&message("msg_1")).await.unwrap();
+        repository.insert(&message("msg_2")).await.unwrap();
+        let stats = relay.relay_once().await.unwrap();
+        assert_eq!(stats.enqueued, 2);
+        assert!(repository.pending(10).await.unwrap().is_empty());
+        assert_eq!(relay.relay_once().await.unwrap(), RelayStats::default());
+
+        for _ in 0..50 {
+            if queue.stats().await.completed == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let indexed = service.indexed.lock().unwrap();
+        assert_eq!(indexed.len(), 2);
+        assert!(indexed
+            .iter()
+            .any(|metadata| metadata["messageId"] == "msg_1"));
+    }
+
+    #[tokio::test]
+    async fn relay_skips_entries_it_already_queued() {
+        let repository = InMemoryMessageRepository::new().with_index_outbox();
+        repository.insert(&message("msg_1")).await.unwrap();
+        let outbox = Arc::new(UnacknowledgedOutbox(repository.pending(10).await.unwrap()));
+        let service = Arc::new(RecordingService::default());
+        let relay = OutboxRelay::new(outbox, Arc::new(IndexingQueue::new(service, 16)));
+
+        assert_eq!(relay.relay_once().await.unwrap().enqueued, 1);
+        assert_eq!(
+            relay.relay_once().await.unwrap(),
+            RelayStats {
+                enqueued: 0,
+                duplicates: 1,
+                skipped: 0,
+            }
+        );
+    }
+}
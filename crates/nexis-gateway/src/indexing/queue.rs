@@ -3,9 +3,10 @@
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 
+use nexis_protocol::RoomId;
 use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, Mutex};
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument, Span};
 use uuid::Uuid;
 
 use super::service::{IndexingError, IndexingService};
@@ -18,28 +19,42 @@ pub struct IndexTask {
     /// Message content to index
     pub message: String,
     /// Room ID
-    pub room_id: Uuid,
+    pub room_id: RoomId,
+    /// Tenant that owns the room, recorded on the indexed document
+    #[serde(default)]
+    pub tenant_id: Option<String>,
     /// Custom metadata
     pub metadata: serde_json::Value,
     /// Number of retry attempts
     pub attempts: u32,
     /// Maximum retries before giving up
     pub max_retries: u32,
+    /// Span that enqueued the task, so background indexing joins its trace
+    #[serde(skip)]
+    origin: Option<Span>,
 }
 
 impl IndexTask {
     /// Create a new indexing task
-    pub fn new(message: String, room_id: Uuid, metadata: serde_json::Value) -> Self {
+    pub fn new(message: String, room_id: RoomId, metadata: serde_json::Value) -> Self {
         Self {
             id: Uuid::new_v4(),
             message,
             room_id,
+            tenant_id: None,
             metadata,
             attempts: 0,
             max_retries: 3,
+            origin: Some(Span::current()),
         }
     }
 
+    /// Scope the indexed document to a tenant
+    pub fn with_tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
     /// Set maximum retries
     pub fn with_max_retries(mut self, max_retries: u32) -> Self {
         self.max_retries = max_retries;
@@ -55,6 +70,21 @@ impl IndexTask {
     pub fn can_retry(&self) -> bool {
         self.attempts < self.max_retries
     }
+
+    /// ID of the span that enqueued this task, if it is still being recorded
+    pub fn origin_span_id(&self) -> Option<tracing::Id> {
+        self.origin.as_ref().and_then(Span::id)
+    }
+
+    /// Span covering the processing of this task, parented to its origin
+    pub fn span(&self) -> Span {
+        tracing::info_span!(
+            parent: self.origin_span_id(),
+            "indexing.task",
+            task_id = %self.id,
+            attempt = self.attempts
+        )
+    }
 }
 
 /// Task status for tracking
@@ -81,6 +111,15 @@ pub struct QueueStats {
     pub failed: u64,
     /// Number of retries
     pub retries: u64,
+    /// Number of tasks evicted by the drop-oldest overflow policy
+    #[serde(default)]
+    pub dropped: u64,
+    /// Number of tasks refused by the reject overflow policy
+    #[serde(default)]
+    pub rejected: u64,
+    /// Number of batches flushed
+    #[serde(default)]
+    pub batches: u64,
 }
 
 /// Background task queue for indexing
@@ -105,10 +144,7 @@ impl IndexingQueue {
             while let Some(mut task) = receiver.recv().await {
                 debug!(task_id = %task.id, attempt = task.attempts, "Processing indexing task");
 
-                match service
-                    .index_message(&task.message, task.room_id, task.metadata.clone())
-                    .await
-                {
+                match service.index_task(&task).instrument(task.span()).await {
                     Ok(doc_id) => {
                         info!(task_id = %task.id, doc_id = %doc_id, "Indexing task completed");
                         let mut stats = stats_clone.lock().await;
@@ -179,7 +215,7 @@ impl IndexingQueue {
     pub async fn index_message(
         &self,
         message: String,
-        room_id: Uuid,
+        room_id: RoomId,
         metadata: serde_json::Value,
     ) -> Result<Uuid, IndexingError> {
         let task = IndexTask::new(message, room_id, metadata);
@@ -253,8 +289,12 @@ mod tests {
 
     #[test]
     fn index_task_creation() {
-        let room_id = Uuid::new_v4();
-        let task = IndexTask::new("Hello world".to_string(), room_id, serde_json::json!({}));
+        let room_id = RoomId::generate();
+        let task = IndexTask::new(
+            "Hello world".to_string(),
+            room_id.clone(),
+            serde_json::json!({}),
+        );
 
         assert!(!task.message.is_empty());
         assert_eq!(task.room_id, room_id);
@@ -263,7 +303,11 @@ mod tests {
 
     #[test]
     fn index_task_retry_logic() {
-        let mut task = IndexTask::new("Test".to_string(), Uuid::new_v4(), serde_json::json!({}));
+        let mut task = IndexTask::new(
+            "Test".to_string(),
+            RoomId::generate(),
+            serde_json::json!({}),
+        );
         task.max_retries = 2;
 
         assert!(task.can_retry());
@@ -279,7 +323,11 @@ mod tests {
 
         assert!(queue.is_empty().await);
 
-        let task = IndexTask::new("Test".to_string(), Uuid::new_v4(), serde_json::json!({}));
+        let task = IndexTask::new(
+            "Test".to_string(),
+            RoomId::generate(),
+            serde_json::json!({}),
+        );
         queue.push(task).await;
 
         assert_eq!(queue.len().await, 1);
@@ -303,7 +351,11 @@ mod tests {
 
     #[test]
     fn retry_respects_max_retries() {
-        let mut task = IndexTask::new("Test".to_string(), Uuid::new_v4(), serde_json::json!({}));
+        let mut task = IndexTask::new(
+            "Test".to_string(),
+            RoomId::generate(),
+            serde_json::json!({}),
+        );
         task.max_retries = 3;
 
         assert!(task.can_retry());
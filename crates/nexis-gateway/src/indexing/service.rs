@@ -1,14 +1,23 @@
 //! Indexing service implementation
 
 use async_trait::async_trait;
-use nexis_runtime::{EmbeddingProvider, EmbeddingRequest};
+use nexis_protocol::MessageContent;
+use nexis_protocol::RoomId;
+use nexis_runtime::{BatchEmbeddingRequest, EmbeddingProvider, EmbeddingRequest};
 use nexis_vector::prelude::*;
 use nexis_vector::DocumentMetadata;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::debug;
 use uuid::Uuid;
 
+use super::chunker::{Chunker, ChunkerConfig};
+use super::dedup::{DedupConfig, DuplicatePolicy, DUPLICATE_TAG};
+use super::queue::IndexTask;
 use super::retry::{with_retry, RetryConfig};
+use crate::config::VectorConfig;
+use crate::metrics::record_indexing_duplicate;
+use crate::search::CollectionRouter;
 
 /// Indexing service for processing messages into vector storage
 #[async_trait]
@@ -17,10 +26,26 @@ pub trait IndexingService: Send + Sync {
     async fn index_message(
         &self,
         message: &str,
-        room_id: Uuid,
+        room_id: RoomId,
         metadata: serde_json::Value,
     ) -> IndexingResult<Uuid>;
 
+    /// Index a queued task. Implementations that store tenant-scoped
+    /// documents should override this to honour `task.tenant_id`.
+    async fn index_task(&self, task: &IndexTask) -> IndexingResult<Uuid> {
+        self.index_message(&task.message, task.room_id.clone(), task.metadata.clone())
+            .await
+    }
+
+    /// Index a batch of messages, returning one result per task in input order
+    async fn index_batch(&self, tasks: &[IndexTask]) -> Vec<IndexingResult<Uuid>> {
+        let mut results = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            results.push(self.index_task(task).await);
+        }
+        results
+    }
+
     /// Search for similar messages
     async fn search(&self, query: &str, limit: usize) -> IndexingResult<Vec<SearchResult>>;
 
@@ -28,7 +53,7 @@ pub trait IndexingService: Send + Sync {
     async fn search_in_room(
         &self,
         query: &str,
-        room_id: Uuid,
+        room_id: RoomId,
         limit: usize,
     ) -> IndexingResult<Vec<SearchResult>>;
 }
@@ -39,9 +64,15 @@ pub struct IndexerConfig {
     /// Vector dimension
     pub dimension: usize,
     /// Room ID for indexing
-    pub default_room_id: Option<Uuid>,
+    pub default_room_id: Option<RoomId>,
     /// Retry configuration for embedding calls
     pub retry_config: RetryConfig,
+    /// How long indexed documents live; `None` keeps them until deleted
+    pub document_ttl: Option<chrono::Duration>,
+    /// Chunk size and overlap for splitting long messages before embedding
+    pub chunker: ChunkerConfig,
+    /// How messages that repeat a recent one in their room are handled
+    pub dedup: DedupConfig,
 }
 
 impl Default for IndexerConfig {
@@ -50,31 +81,95 @@ impl Default for IndexerConfig {
             dimension: 1536,
             default_room_id: None,
             retry_config: RetryConfig::default(),
+            document_ttl: None,
+            chunker: ChunkerConfig::default(),
+            dedup: DedupConfig::default(),
         }
     }
 }
 
+/// Nearest documents compared when looking for a duplicate
+const DUPLICATE_CANDIDATES: usize = 8;
+
+/// Admission check for documents written on behalf of a tenant, used to
+/// enforce per-tenant vector document quotas.
+#[async_trait]
+pub trait DocumentQuota: Send + Sync {
+    /// Reserve `documents` more documents for `tenant_id`, or refuse with
+    /// [`IndexingError::Rejected`].
+    async fn reserve(&self, tenant_id: &str, documents: u64) -> IndexingResult<()>;
+}
+
 /// Message indexer that combines embedding and vector storage
+///
+/// Messages longer than [`IndexerConfig::chunker`] allows are stored as one
+/// document per chunk, each recording the message's first chunk as its
+/// parent; the id returned for the message is that first chunk's.
+///
+/// Messages that fit in one chunk are checked against the room's recent
+/// documents first, as configured by [`IndexerConfig::dedup`].
 pub struct MessageIndexer {
-    vector_store: Arc<dyn VectorStore>,
+    collections: Arc<CollectionRouter>,
     embedding_provider: Arc<dyn EmbeddingProvider>,
+    chunker: Chunker,
     config: IndexerConfig,
+    document_quota: Option<Arc<dyn DocumentQuota>>,
 }
 
 impl MessageIndexer {
-    /// Create a new message indexer
+    /// Create a new message indexer writing to a single vector store
     pub fn new(
         vector_store: Arc<dyn VectorStore>,
         embedding_provider: Arc<dyn EmbeddingProvider>,
         config: IndexerConfig,
+    ) -> Self {
+        Self::with_collections(
+            Arc::new(CollectionRouter::single(vector_store)),
+            embedding_provider,
+            config,
+        )
+    }
+
+    /// Create a message indexer writing to the collections `[vector]`
+    /// configures; search them through [`Self::collections`]
+    pub fn from_config(
+        vector: &VectorConfig,
+        embedding_provider: Arc<dyn EmbeddingProvider>,
+        config: IndexerConfig,
+    ) -> VectorResult<Self> {
+        Ok(Self::with_collections(
+            Arc::new(CollectionRouter::from_config(vector)?),
+            embedding_provider,
+            config,
+        ))
+    }
+
+    /// Create a message indexer writing to routed collections
+    pub fn with_collections(
+        collections: Arc<CollectionRouter>,
+        embedding_provider: Arc<dyn EmbeddingProvider>,
+        config: IndexerConfig,
     ) -> Self {
         Self {
-            vector_store,
+            collections,
             embedding_provider,
+            chunker: Chunker::new(config.chunker.clone()),
             config,
+            document_quota: None,
         }
     }
 
+    /// Collections the indexer writes to, for a search service to share
+    pub fn collections(&self) -> Arc<CollectionRouter> {
+        self.collections.clone()
+    }
+
+    /// Check tenant-scoped documents against `quota` before storing them
+    pub fn with_document_quota(mut self, quota: Arc<dyn DocumentQuota>) -> Self {
+        self.document_quota = Some(quota);
+        self
+    }
+
     /// Create with default configuration
     pub fn with_defaults(
         vector_store: Arc<dyn VectorStore>,
@@ -83,6 +178,294 @@ impl MessageIndexer {
         Self::new(vector_store, embedding_provider, IndexerConfig::default())
     }
 
+    /// Index structured message content, recording its kind in the document metadata
+    pub async fn index_content(
+        &self,
+        content: &MessageContent,
+        room_id: RoomId,
+        metadata: serde_json::Value,
+    ) -> IndexingResult<Uuid> {
+        let indexable = IndexableContent::from_message_content(content)?;
+        debug!(
+            content_type = indexable.content_type,
+            "Indexing message content for room: {}", room_id
+        );
+
+        let store = self.store_for(None, &room_id).await?;
+        let mut doc_metadata = DocumentMetadata::new()
+            .with_room(room_id)
+            .with_content_type(indexable.content_type)
+            .with_extra("custom", metadata);
+        for tag in indexable.tags {
+            doc_metadata = doc_metadata.with_tag(tag);
+        }
+
+        self.store_chunks(store, &indexable.text, doc_metadata)
+            .await
+    }
+
+    async fn index_text(
+        &self,
+        message: &str,
+        room_id: RoomId,
+        tenant_id: Option<&str>,
+        metadata: serde_json::Value,
+    ) -> IndexingResult<Uuid> {
+        debug!("Indexing message for room: {}", room_id);
+        let store = self.store_for(tenant_id, &room_id).await?;
+
+        let mut metadata = DocumentMetadata::new()
+            .with_room(room_id)
+            .with_content_type("text")
+            .with_extra("custom", metadata);
+        if let Some(tenant_id) = tenant_id {
+            metadata = metadata.with_tenant(tenant_id);
+        }
+
+        self.store_chunks(store, message, metadata).await
+    }
+
+    /// Embed and store `text`, split into chunks if it is long; the id of
+    /// its first document.
+    async fn store_chunks(
+        &self,
+        store: Arc<dyn VectorStore>,
+        text: &str,
+        metadata: DocumentMetadata,
+    ) -> IndexingResult<Uuid> {
+        let chunks = self.chunker.chunk(text);
+        if chunks.len() == 1 {
+            let vector = Vector::new(self.generate_embedding(text).await?);
+            let mut metadata = metadata;
+            let policy = self.config.dedup.policy_for(metadata.room_id.as_ref());
+            if policy != DuplicatePolicy::Keep {
+                if let Some(original) = self.find_duplicate(&store, &vector, &metadata).await? {
+                    debug!(original = %original.id, policy = policy.as_str(), "Duplicate message");
+                    record_indexing_duplicate(policy.as_str());
+                    match policy {
+                        DuplicatePolicy::Skip => return Ok(original.id),
+                        DuplicatePolicy::Merge => {
+                            return self.merge_duplicate(&store, original).await
+                        }
+                        DuplicatePolicy::Tag | DuplicatePolicy::Keep => {
+                            metadata = metadata
+                                .with_tag(DUPLICATE_TAG)
+                                .with_extra("duplicate_of", serde_json::json!(original.id));
+                        }
+                    }
+                }
+            }
+
+            self.reserve_documents(metadata.tenant_id.as_deref(), 1)
+                .await?;
+            let doc = self.document(vector, text.to_string(), metadata);
+            return store
+                .upsert(doc)
+                .await
+                .map_err(|e| IndexingError::StorageError(e.to_string()));
+        }
+
+        debug!(chunks = chunks.len(), "Indexing long message in chunks");
+        self.reserve_documents(metadata.tenant_id.as_deref(), chunks.len())
+            .await?;
+        let embeddings = self.generate_embeddings(chunks.clone()).await?;
+        let docs = self.documents(chunks, embeddings, metadata);
+        let id = docs[0].id;
+        let stored = store
+            .upsert_batch(docs)
+            .await
+            .map_err(|e| IndexingError::StorageError(e.to_string()))?;
+        match stored.failed.into_iter().next() {
+            Some((_, reason)) => Err(IndexingError::StorageError(reason)),
+            None => Ok(id),
+        }
+    }
+
+    /// A recent document in the same room and tenant that `vector` nearly
+    /// duplicates
+    async fn find_duplicate(
+        &self,
+        store: &Arc<dyn VectorStore>,
+        vector: &Vector,
+        metadata: &DocumentMetadata,
+    ) -> IndexingResult<Option<Document>> {
+        let mut filter = SearchFilter::new();
+        if let Some(room_id) = metadata.room_id.clone() {
+            filter = filter.with_room(room_id);
+        }
+        if let Some(tenant_id) = metadata.tenant_id.clone() {
+            filter = filter.with_tenant(tenant_id);
+        }
+        let query = SearchQuery::new(vector.clone())
+            .with_limit(DUPLICATE_CANDIDATES)
+            .with_filter(filter);
+        let candidates = store
+            .search(query)
+            .await
+            .map_err(|e| IndexingError::StorageError(e.to_string()))?;
+        Ok(self
+            .config
+            .dedup
+            .original(vector, candidates, chrono::Utc::now()))
+    }
+
+    /// Count a duplicate in `original`'s metadata instead of storing it
+    async fn merge_duplicate(
+        &self,
+        store: &Arc<dyn VectorStore>,
+        mut original: Document,
+    ) -> IndexingResult<Uuid> {
+        let duplicates = original
+            .metadata
+            .extra
+            .get("duplicates")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0);
+        original
+            .metadata
+            .extra
+            .insert("duplicates".to_string(), serde_json::json!(duplicates + 1));
+        original.updated_at = chrono::Utc::now();
+        store
+            .upsert(original)
+            .await
+            .map_err(|e| IndexingError::StorageError(e.to_string()))
+    }
+
+    async fn store_batch(&self, tasks: &[&IndexTask]) -> Vec<IndexingResult<Uuid>> {
+        if tasks.is_empty() {
+            return Vec::new();
+        }
+
+        let chunks: Vec<Vec<String>> = tasks
+            .iter()
+            .map(|task| self.chunker.chunk(&task.message))
+            .collect();
+        let texts = chunks.iter().flatten().cloned().collect();
+        let embeddings = match self.generate_embeddings(texts).await {
+            Ok(embeddings) => embeddings,
+            Err(e) => {
+                let message = e.to_string();
+                return tasks
+                    .iter()
+                    .map(|_| Err(IndexingError::EmbeddingError(message.clone())))
+                    .collect();
+            }
+        };
+
+        // One batch per collection the documents are routed to; a task
+        // fails if any of its chunks does.
+        let mut ids = Vec::with_capacity(tasks.len());
+        let mut parents: HashMap<Uuid, Uuid> = HashMap::new();
+        let mut batches: HashMap<String, Vec<Document>> = HashMap::new();
+        let mut embeddings = embeddings.into_iter();
+        for (task, chunks) in tasks.iter().zip(chunks) {
+            let mut metadata = DocumentMetadata::new()
+                .with_room(task.room_id.clone())
+                .with_content_type("text")
+                .with_extra("custom", task.metadata.clone());
+            if let Some(tenant_id) = &task.tenant_id {
+                metadata = metadata.with_tenant(tenant_id.clone());
+            }
+            let task_embeddings = embeddings.by_ref().take(chunks.len()).collect();
+            let docs = self.documents(chunks, task_embeddings, metadata);
+            let id = docs[0].id;
+            ids.push(id);
+            parents.extend(docs.iter().map(|doc| (doc.id, id)));
+            let collection = self
+                .collections
+                .collection_name(task.tenant_id.as_deref(), &task.room_id);
+            batches.entry(collection).or_default().extend(docs);
+        }
+
+        let mut failures: HashMap<Uuid, String> = HashMap::new();
+        for (collection, documents) in batches {
+            let stored = match self.collections.open_collection(&collection).await {
+                Ok(store) => store.upsert_batch(documents.clone()).await,
+                Err(e) => Err(e),
+            };
+            match stored {
+                Ok(batch) => failures.extend(
+                    batch
+                        .failed
+                        .into_iter()
+                        .map(|(id, reason)| (parents.get(&id).copied().unwrap_or(id), reason)),
+                ),
+                Err(e) => {
+                    let message = e.to_string();
+                    failures.extend(
+                        documents
+                            .iter()
+                            .map(|doc| (parents[&doc.id], message.clone())),
+                    );
+                }
+            }
+        }
+
+        ids.into_iter()
+            .map(|id| match failures.remove(&id) {
+                Some(reason) => Err(IndexingError::StorageError(reason)),
+                None => Ok(id),
+            })
+            .collect()
+    }
+
+    /// Documents for the chunks of one message, in order, with their
+    /// embeddings; chunks of a split message record the first as parent.
+    fn documents(
+        &self,
+        chunks: Vec<String>,
+        embeddings: Vec<Vec<f32>>,
+        metadata: DocumentMetadata,
+    ) -> Vec<Document> {
+        let split = chunks.len() > 1;
+        let mut docs: Vec<Document> = chunks
+            .into_iter()
+            .zip(embeddings)
+            .map(|(chunk, embedding)| {
+                self.document(Vector::new(embedding), chunk, metadata.clone())
+            })
+            .collect();
+        if split {
+            let parent = docs[0].id;
+            for (index, doc) in (0..).zip(docs.iter_mut()) {
+                doc.metadata = std::mem::take(&mut doc.metadata).with_chunk(parent, index);
+            }
+        }
+        docs
+    }
+
+    /// New document, expiring after the configured TTL
+    fn document(&self, vector: Vector, content: String, metadata: DocumentMetadata) -> Document {
+        let doc = Document::new(vector, content, metadata);
+        match self.config.document_ttl {
+            Some(ttl) => doc.with_ttl(ttl),
+            None => doc,
+        }
+    }
+
+    async fn store_for(
+        &self,
+        tenant_id: Option<&str>,
+        room_id: &RoomId,
+    ) -> IndexingResult<Arc<dyn VectorStore>> {
+        self.collections
+            .store_for(tenant_id, room_id)
+            .await
+            .map_err(|e| IndexingError::StorageError(e.to_string()))
+    }
+
+    async fn reserve_documents(
+        &self,
+        tenant_id: Option<&str>,
+        documents: usize,
+    ) -> IndexingResult<()> {
+        match (&self.document_quota, tenant_id) {
+            (Some(quota), Some(tenant_id)) => quota.reserve(tenant_id, documents as u64).await,
+            _ => Ok(()),
+        }
+    }
+
     async fn generate_embedding(&self, text: &str) -> IndexingResult<Vec<f32>> {
         let text = text.to_string();
         let embedding = with_retry(
@@ -101,31 +484,96 @@ impl MessageIndexer {
 
         Ok(embedding.embedding)
     }
+
+    async fn generate_embeddings(&self, texts: Vec<String>) -> IndexingResult<Vec<Vec<f32>>> {
+        let expected = texts.len();
+        let response = with_retry(
+            || {
+                let texts = texts.clone();
+                let provider = self.embedding_provider.clone();
+                async move {
+                    provider
+                        .embed_batch(BatchEmbeddingRequest::new(texts))
+                        .await
+                }
+            },
+            &self.config.retry_config,
+        )
+        .await
+        .map_err(|e| IndexingError::EmbeddingError(e.to_string()))?;
+
+        if response.embeddings.len() != expected {
+            return Err(IndexingError::EmbeddingError(format!(
+                "expected {expected} embeddings, got {}",
+                response.embeddings.len()
+            )));
+        }
+
+        Ok(response.embeddings)
+    }
 }
 
 #[async_trait]
 impl IndexingService for MessageIndexer {
+    #[tracing::instrument(name = "indexing.index_message", skip_all, fields(room_id = %room_id))]
     async fn index_message(
         &self,
         message: &str,
-        room_id: Uuid,
+        room_id: RoomId,
         metadata: serde_json::Value,
     ) -> IndexingResult<Uuid> {
-        debug!("Indexing message for room: {}", room_id);
+        self.index_text(message, room_id, None, metadata).await
+    }
 
-        let embedding = self.generate_embedding(message).await?;
-        let vector = Vector::new(embedding);
+    #[tracing::instrument(name = "indexing.index_task", skip_all, fields(room_id = %task.room_id))]
+    async fn index_task(&self, task: &IndexTask) -> IndexingResult<Uuid> {
+        self.index_text(
+            &task.message,
+            task.room_id.clone(),
+            task.tenant_id.as_deref(),
+            task.metadata.clone(),
+        )
+        .await
+    }
 
-        let metadata = DocumentMetadata::new()
-            .with_room(room_id)
-            .with_extra("custom", metadata);
+    #[tracing::instrument(name = "indexing.index_batch", skip_all, fields(batch_size = tasks.len()))]
+    async fn index_batch(&self, tasks: &[IndexTask]) -> Vec<IndexingResult<Uuid>> {
+        if tasks.is_empty() {
+            return Vec::new();
+        }
+        debug!(batch_size = tasks.len(), "Indexing message batch");
 
-        let doc = Document::new(vector, message.to_string(), metadata);
+        // Tasks in rooms checked for duplicates are indexed one at a time,
+        // so repeats within the batch are caught too.
+        let mut settled = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            let result =
+                if self.config.dedup.policy_for(Some(&task.room_id)) != DuplicatePolicy::Keep {
+                    Some(self.index_task(task).await)
+                } else {
+                    let documents = self.chunker.chunk(&task.message).len();
+                    self.reserve_documents(task.tenant_id.as_deref(), documents)
+                        .await
+                        .err()
+                        .map(Err)
+                };
+            settled.push(result);
+        }
+        let admitted: Vec<&IndexTask> = tasks
+            .iter()
+            .zip(&settled)
+            .filter(|(_, result)| result.is_none())
+            .map(|(task, _)| task)
+            .collect();
 
-        self.vector_store
-            .upsert(doc)
-            .await
-            .map_err(|e| IndexingError::StorageError(e.to_string()))
+        let mut stored = self.store_batch(&admitted).await.into_iter();
+        settled
+            .into_iter()
+            .map(|result| match result {
+                Some(result) => result,
+                None => stored.next().expect("one result per admitted task"),
+            })
+            .collect()
     }
 
     async fn search(&self, query: &str, limit: usize) -> IndexingResult<Vec<SearchResult>> {
@@ -135,8 +583,8 @@ impl IndexingService for MessageIndexer {
         let query_vector = Vector::new(embedding);
         let search_query = SearchQuery::new(query_vector).with_limit(limit);
 
-        self.vector_store
-            .search(search_query)
+        self.collections
+            .search(None, None, search_query)
             .await
             .map_err(|e| IndexingError::StorageError(e.to_string()))
     }
@@ -144,7 +592,7 @@ impl IndexingService for MessageIndexer {
     async fn search_in_room(
         &self,
         query: &str,
-        room_id: Uuid,
+        room_id: RoomId,
         limit: usize,
     ) -> IndexingResult<Vec<SearchResult>> {
         debug!("Searching in room {} for: {}", room_id, query);
@@ -153,15 +601,91 @@ impl IndexingService for MessageIndexer {
         let query_vector = Vector::new(embedding);
         let search_query = SearchQuery::new(query_vector)
             .with_limit(limit)
-            .with_filter(SearchFilter::new().with_room(room_id));
+            .with_filter(SearchFilter::new().with_room(room_id.clone()));
 
-        self.vector_store
-            .search(search_query)
+        self.collections
+            .search(None, Some(&room_id), search_query)
             .await
             .map_err(|e| IndexingError::StorageError(e.to_string()))
     }
 }
 
+/// Searchable text extracted from structured message content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexableContent {
+    /// Text to embed and store
+    pub text: String,
+    /// Content kind, matching the protocol `type` tag
+    pub content_type: &'static str,
+    /// Extra tags to attach (e.g. `language:rust`)
+    pub tags: Vec<String>,
+}
+
+impl IndexableContent {
+    /// Extract indexable text from message content
+    pub fn from_message_content(content: &MessageContent) -> IndexingResult<Self> {
+        let (text, content_type, tags) = match content {
+            MessageContent::Text { text } => (text.clone(), "text", Vec::new()),
+            MessageContent::Markdown { markdown } => (markdown.clone(), "markdown", Vec::new()),
+            MessageContent::Code { code, language } => match language {
+                Some(language) => (
+                    format!("[{language}]\n{code}"),
+                    "code",
+                    vec![format!("language:{}", language.to_ascii_lowercase())],
+                ),
+                None => (code.clone(), "code", Vec::new()),
+            },
+            MessageContent::Tool { tool_name, input } => (
+                format!("{tool_name} {input}"),
+                "tool",
+                vec![format!("tool:{tool_name}")],
+            ),
+            MessageContent::ToolCall {
+                name, arguments, ..
+            } => (
+                format!("{name} {arguments}"),
+                "toolcall",
+                vec![format!("tool:{name}")],
+            ),
+            MessageContent::Data { .. } => {
+                return Err(IndexingError::InvalidMessage(
+                    "data content is not indexable".to_string(),
+                ))
+            }
+            MessageContent::Encrypted { .. } => {
+                return Err(IndexingError::InvalidMessage(
+                    "encrypted content is not indexable".to_string(),
+                ))
+            }
+            MessageContent::Attachment(attachment) => (
+                attachment.file_name.clone(),
+                "attachment",
+                vec![format!("mime:{}", attachment.mime_type)],
+            ),
+            MessageContent::Media { alt_text, .. } => match alt_text {
+                Some(alt_text) => (alt_text.clone(), "media", Vec::new()),
+                None => {
+                    return Err(IndexingError::InvalidMessage(
+                        "media content without alt text is not indexable".to_string(),
+                    ))
+                }
+            },
+        };
+
+        if text.trim().is_empty() {
+            return Err(IndexingError::InvalidMessage(
+                "message content is empty".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            text,
+            content_type,
+            tags,
+        })
+    }
+}
+
 // Error types
 
 /// Indexing error type
@@ -175,6 +699,12 @@ pub enum IndexingError {
 
     #[error("Invalid message: {0}")]
     InvalidMessage(String),
+
+    #[error("Indexing queue is full (max depth {0})")]
+    QueueFull(usize),
+
+    #[error("Indexing rejected: {0}")]
+    Rejected(String),
 }
 
 /// Indexing result type
@@ -183,6 +713,8 @@ pub type IndexingResult<T> = Result<T, IndexingError>;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::CollectionRouting;
+    use crate::search::InMemoryCollections;
     use nexis_runtime::MockEmbeddingProvider;
     use nexis_vector::InMemoryVectorStore;
 
@@ -192,7 +724,7 @@ mod tests {
         let embedding = Arc::new(MockEmbeddingProvider::new(1536));
         let indexer = MessageIndexer::with_defaults(store, embedding);
 
-        let room_id = Uuid::new_v4();
+        let room_id = RoomId::generate();
         let metadata = serde_json::json!({"sender": "test"});
 
         let result = indexer
@@ -207,9 +739,9 @@ mod tests {
         let embedding = Arc::new(MockEmbeddingProvider::new(1536));
         let indexer = MessageIndexer::with_defaults(store, embedding);
 
-        let room_id = Uuid::new_v4();
+        let room_id = RoomId::generate();
         indexer
-            .index_message("Test message", room_id, serde_json::json!({}))
+            .index_message("Test message", room_id.clone(), serde_json::json!({}))
             .await
             .unwrap();
 
@@ -223,13 +755,351 @@ mod tests {
         let embedding = Arc::new(MockEmbeddingProvider::new(1536));
         let indexer = MessageIndexer::with_defaults(store, embedding);
 
-        let room_id = Uuid::new_v4();
+        let room_id = RoomId::generate();
         indexer
-            .index_message("Test message", room_id, serde_json::json!({}))
+            .index_message("Test message", room_id.clone(), serde_json::json!({}))
             .await
             .unwrap();
 
         let results = indexer.search_in_room("Test", room_id, 10).await;
         assert!(results.is_ok());
     }
+
+    #[tokio::test]
+    async fn long_messages_are_indexed_in_chunks() {
+        let store = Arc::new(InMemoryVectorStore::new(1536));
+        let embedding = Arc::new(MockEmbeddingProvider::new(1536));
+        let config = IndexerConfig {
+            chunker: ChunkerConfig {
+                max_tokens: 4,
+                overlap_tokens: 1,
+            },
+            ..IndexerConfig::default()
+        };
+        let indexer = MessageIndexer::new(store.clone(), embedding, config);
+        let room_id = RoomId::generate();
+
+        let id = indexer
+            .index_message("one two three", room_id.clone(), serde_json::json!({}))
+            .await
+            .unwrap();
+        let short = store.get(id).await.unwrap();
+        assert_eq!(short.metadata.parent_message_id, None);
+        assert_eq!(short.metadata.chunk_index, None);
+
+        let task = IndexTask::new(
+            "w1 w2 w3 w4 w5 w6 w7 w8 w9 w10".to_string(),
+            room_id.clone(),
+            serde_json::json!({}),
+        );
+        let results = indexer.index_batch(&[task]).await;
+        let parent = *results[0].as_ref().unwrap();
+        assert_eq!(store.count().await.unwrap(), 4);
+
+        let query = SearchQuery::new(Vector::new(vec![0.1; 1536]))
+            .with_filter(SearchFilter::new().with_room(room_id));
+        let mut chunks: Vec<Document> = store
+            .search(query)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|result| result.document)
+            .filter(|doc| doc.metadata.parent_message_id == Some(parent))
+            .collect();
+        chunks.sort_by_key(|doc| doc.metadata.chunk_index);
+        let contents: Vec<&str> = chunks.iter().map(|doc| doc.content.as_str()).collect();
+        assert_eq!(contents, ["w1 w2 w3 w4", "w4 w5 w6 w7", "w7 w8 w9 w10"]);
+        assert_eq!(chunks[0].id, parent);
+        assert_eq!(chunks[2].metadata.chunk_index, Some(2));
+    }
+
+    #[tokio::test]
+    async fn duplicate_messages_follow_the_room_policy() {
+        let store = Arc::new(InMemoryVectorStore::new(1536));
+        let embedding = Arc::new(MockEmbeddingProvider::new(1536));
+        let rooms: Vec<RoomId> = (0..4).map(|_| RoomId::generate()).collect();
+        let config = IndexerConfig {
+            dedup: DedupConfig::default()
+                .with_room_policy(rooms[0].clone(), DuplicatePolicy::Skip)
+                .with_room_policy(rooms[1].clone(), DuplicatePolicy::Merge)
+                .with_room_policy(rooms[2].clone(), DuplicatePolicy::Tag),
+            ..IndexerConfig::default()
+        };
+        let indexer = MessageIndexer::new(store.clone(), embedding, config);
+        let index = |room: usize| {
+            indexer.index_message("Buy now!", rooms[room].clone(), serde_json::json!({}))
+        };
+
+        let original = index(0).await.unwrap();
+        assert_eq!(index(0).await.unwrap(), original);
+
+        let first = index(1).await.unwrap();
+        assert_eq!(index(1).await.unwrap(), first);
+        assert_eq!(index(1).await.unwrap(), first);
+        let merged = store.get(first).await.unwrap();
+        assert_eq!(merged.metadata.extra["duplicates"], 2);
+
+        let first = index(2).await.unwrap();
+        let tagged = store.get(index(2).await.unwrap()).await.unwrap();
+        assert_eq!(tagged.metadata.tags, vec![DUPLICATE_TAG.to_string()]);
+        assert_eq!(
+            tagged.metadata.extra["duplicate_of"],
+            serde_json::json!(first)
+        );
+
+        // Rooms without a policy keep every copy.
+        index(3).await.unwrap();
+        index(3).await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 6);
+
+        let tasks: Vec<IndexTask> = (0..3)
+            .map(|_| {
+                IndexTask::new(
+                    "Buy now!".to_string(),
+                    rooms[0].clone(),
+                    serde_json::json!({}),
+                )
+            })
+            .collect();
+        let results = indexer.index_batch(&tasks).await;
+        assert!(results
+            .iter()
+            .all(|result| result.as_ref().ok() == Some(&original)));
+        assert_eq!(store.count().await.unwrap(), 6);
+    }
+
+    #[test]
+    fn indexable_content_includes_code_language() {
+        let content = MessageContent::Code {
+            code: "fn main() {}".to_string(),
+            language: Some("Rust".to_string()),
+        };
+
+        let indexable = IndexableContent::from_message_content(&content).unwrap();
+        assert_eq!(indexable.content_type, "code");
+        assert!(indexable.text.contains("[Rust]"));
+        assert!(indexable.text.contains("fn main() {}"));
+        assert_eq!(indexable.tags, vec!["language:rust".to_string()]);
+    }
+
+    #[test]
+    fn indexable_content_stringifies_tool_input() {
+        let content = MessageContent::Tool {
+            tool_name: "web_search".to_string(),
+            input: serde_json::json!({"query": "nexis"}),
+        };
+
+        let indexable = IndexableContent::from_message_content(&content).unwrap();
+        assert_eq!(indexable.content_type, "tool");
+        assert!(indexable.text.starts_with("web_search "));
+        assert!(indexable.text.contains("\"query\":\"nexis\""));
+    }
+
+    #[test]
+    fn indexable_content_rejects_data() {
+        let content = MessageContent::Data {
+            data: serde_json::json!({"k": 1}),
+            mime_type: None,
+        };
+
+        assert!(matches!(
+            IndexableContent::from_message_content(&content),
+            Err(IndexingError::InvalidMessage(_))
+        ));
+    }
+
+    #[test]
+    fn indexable_content_rejects_encrypted() {
+        let content = MessageContent::Encrypted {
+            algorithm: "x25519-chacha20poly1305".to_string(),
+            key_id: "key_1".to_string(),
+            nonce: "bm9uY2U=".to_string(),
+            ciphertext: "c2VjcmV0".to_string(),
+            recipients: Vec::new(),
+        };
+
+        assert!(matches!(
+            IndexableContent::from_message_content(&content),
+            Err(IndexingError::InvalidMessage(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_index_content_is_filterable_by_content_type() {
+        let store = Arc::new(InMemoryVectorStore::new(1536));
+        let embedding = Arc::new(MockEmbeddingProvider::new(1536));
+        let indexer = MessageIndexer::with_defaults(store.clone(), embedding);
+
+        let room_id = RoomId::generate();
+        indexer
+            .index_content(
+                &MessageContent::Code {
+                    code: "SELECT 1".to_string(),
+                    language: Some("sql".to_string()),
+                },
+                room_id.clone(),
+                serde_json::json!({}),
+            )
+            .await
+            .unwrap();
+        indexer
+            .index_message("plain text", room_id, serde_json::json!({}))
+            .await
+            .unwrap();
+
+        let query = SearchQuery::new(Vector::new(vec![0.1; 1536]))
+            .with_filter(SearchFilter::new().with_content_type("code"));
+        let results = store.search(query).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].document.metadata.content_type.as_deref(),
+            Some("code")
+        );
+        assert!(results[0]
+            .document
+            .metadata
+            .tags
+            .contains(&"language:sql".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_index_batch_stores_every_message() {
+        let store = Arc::new(InMemoryVectorStore::new(1536));
+        let embedding = Arc::new(MockEmbeddingProvider::new(1536));
+        let indexer = MessageIndexer::with_defaults(store.clone(), embedding);
+
+        let room_id = RoomId::generate();
+        let tasks: Vec<IndexTask> = (0..3)
+            .map(|i| {
+                IndexTask::new(
+                    format!("message {i}"),
+                    room_id.clone(),
+                    serde_json::json!({}),
+                )
+            })
+            .collect();
+
+        let results = indexer.index_batch(&tasks).await;
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(store.count().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_index_task_records_tenant() {
+        let store = Arc::new(InMemoryVectorStore::new(1536));
+        let embedding = Arc::new(MockEmbeddingProvider::new(1536));
+        let indexer = MessageIndexer::with_defaults(store.clone(), embedding);
+
+        let task = IndexTask::new(
+            "quarterly plan".to_string(),
+            RoomId::generate(),
+            serde_json::json!({}),
+        )
+        .with_tenant("acme");
+        let id = indexer.index_task(&task).await.unwrap();
+
+        let doc = store.get(id).await.unwrap();
+        assert_eq!(doc.metadata.tenant_id.as_deref(), Some("acme"));
+    }
+
+    struct DenyTenant(&'static str);
+
+    #[async_trait]
+    impl DocumentQuota for DenyTenant {
+        async fn reserve(&self, tenant_id: &str, _documents: u64) -> IndexingResult<()> {
+            if tenant_id == self.0 {
+                Err(IndexingError::Rejected(format!(
+                    "{tenant_id} is over quota"
+                )))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_batch_skips_tasks_over_quota() {
+        let store = Arc::new(InMemoryVectorStore::new(1536));
+        let embedding = Arc::new(MockEmbeddingProvider::new(1536));
+        let indexer = MessageIndexer::with_defaults(store.clone(), embedding)
+            .with_document_quota(Arc::new(DenyTenant("globex")));
+
+        let room_id = RoomId::generate();
+        let task = |tenant: Option<&str>| {
+            let task = IndexTask::new("hello".to_string(), room_id.clone(), serde_json::json!({}));
+            match tenant {
+                Some(tenant) => task.with_tenant(tenant),
+                None => task,
+            }
+        };
+        let tasks = vec![task(Some("acme")), task(Some("globex")), task(None)];
+
+        let results = indexer.index_batch(&tasks).await;
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(IndexingError::Rejected(_))));
+        assert!(results[2].is_ok());
+        assert_eq!(store.count().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_indexed_documents_expire_after_ttl() {
+        let store = Arc::new(InMemoryVectorStore::new(1536));
+        let embedding = Arc::new(MockEmbeddingProvider::new(1536));
+        let config = IndexerConfig {
+            document_ttl: Some(chrono::Duration::days(30)),
+            ..IndexerConfig::default()
+        };
+        let indexer = MessageIndexer::new(store.clone(), embedding, config);
+
+        let id = indexer
+            .index_message("Test message", RoomId::generate(), serde_json::json!({}))
+            .await
+            .unwrap();
+
+        let doc = store.get(id).await.unwrap();
+        assert_eq!(
+            doc.expires_at,
+            Some(doc.created_at + chrono::Duration::days(30))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_index_batch_routes_documents_per_room() {
+        let collections = Arc::new(CollectionRouter::new(
+            CollectionRouting::PerRoom,
+            "nexis_vectors",
+            Arc::new(InMemoryCollections::new(1536)),
+        ));
+        let embedding = Arc::new(MockEmbeddingProvider::new(1536));
+        let indexer = MessageIndexer::with_collections(
+            collections.clone(),
+            embedding,
+            IndexerConfig::default(),
+        );
+
+        let rooms = [RoomId::generate(), RoomId::generate()];
+        let tasks: Vec<IndexTask> = [&rooms[0], &rooms[0], &rooms[1]]
+            .into_iter()
+            .enumerate()
+            .map(|(i, room)| {
+                IndexTask::new(format!("message {i}"), room.clone(), serde_json::json!({}))
+            })
+            .collect();
+        assert!(indexer.index_batch(&tasks).await.iter().all(Result::is_ok));
+
+        for (room, expected) in rooms.iter().zip([2, 1]) {
+            let store = collections.store_for(None, room).await.unwrap();
+            assert_eq!(store.count().await.unwrap(), expected);
+        }
+        assert_eq!(indexer.search("message", 10).await.unwrap().len(), 3);
+        assert_eq!(
+            indexer
+                .search_in_room("message", rooms[1].clone(), 10)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
 }
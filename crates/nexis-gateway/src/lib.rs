@@ -7,27 +7,66 @@
 //! - Connection management
 //! - Message indexing and semantic search
 //! - Metrics and monitoring
+//! - Outbound room webhooks
+//! - Member notifications of messages and mentions
+//! - Templated invitation and notification email, over SMTP with the
+//!   `email` feature
+//! - TOML configuration with environment overrides
+//! - Audit trail for security-relevant actions
+//! - Optional log of AI prompts and responses, with redaction and replay
+//! - Tenant management and quotas (`multi-tenant` feature)
+//! - OpenID Connect login (`oidc` feature)
+//! - File uploads backed by local or S3-compatible blob storage
+//! - Content moderation of messages before they are stored
+//! - Spam heuristics with automatic temporary mutes
+//! - Scheduled per-room jobs such as daily summaries and inactivity reminders
+//! - gRPC control-plane API on the HTTP port (`grpc` feature)
+//! - OpenAPI document generated from the HTTP handlers
 
+pub mod audit;
 pub mod auth;
+pub mod blobs;
+pub mod cluster;
 pub mod collaboration;
+pub mod config;
 pub mod connection;
 pub mod db;
+pub mod email;
+pub mod generation_log;
 pub mod indexing;
 pub mod metrics;
+pub mod moderation;
+pub mod notifications;
 pub mod observability;
+#[cfg(feature = "oidc")]
+pub mod oidc;
 pub mod router;
+pub mod scheduler;
 pub mod search;
 pub mod server;
+pub mod spam;
+#[cfg(feature = "multi-tenant")]
+pub mod tenants;
+pub mod webhooks;
 
+pub use audit::{AuditAction, AuditEvent, AuditLog, AuditSink};
 #[allow(unused_imports)]
 pub use auth::{AuthError, AuthenticatedUser, Claims, JwtConfig};
+pub use config::{ConfigError, NexisConfig};
 pub use indexing::{IndexingService, MessageIndexer};
 pub use metrics::{export as export_metrics, init_metrics};
-pub use router::build_routes;
-pub use search::{SearchRequest, SearchResponse, SearchService, SemanticSearchService};
+pub use router::{build_routes, build_routes_with_ai, build_routes_with_config};
+pub use search::{
+    CollectionRouter, LexicalSearchService, SearchRequest, SearchResponse, SearchService,
+    SemanticSearchService,
+};
 
 #[cfg(feature = "multi-tenant")]
 pub use auth::{TenantContext, TenantError, TenantExtractor};
+#[cfg(feature = "oidc")]
+pub use oidc::{OidcClient, OidcError};
+#[cfg(feature = "multi-tenant")]
+pub use tenants::{TenantDirectory, TenantStore};
 
 /// Gateway version
 pub const GATEWAY_VERSION: &str = env!("CARGO_PKG_VERSION");
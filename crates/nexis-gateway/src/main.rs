@@ -7,13 +7,20 @@ use axum::http::{HeaderValue, Method, Request, StatusCode};
 use axum::middleware::{self, Next};
 use axum::response::{IntoResponse, Redirect, Response};
 use axum::Router;
-use std::net::SocketAddr;
-use std::sync::OnceLock;
+use std::future::IntoFuture;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
+use nexis_gateway::audit::AuditLog;
+use nexis_gateway::config::{CorsConfig, NexisConfig, ProviderKind};
+use nexis_gateway::db::{init_pool, migrations, Storage};
+use nexis_gateway::indexing::BatchConfig;
+use nexis_gateway::search::KeywordIndexing;
+use nexis_gateway::server::{shutdown_signal, ShutdownController};
 use nexis_gateway::{init_metrics, observability, router};
+use nexis_runtime::{AIProvider, AnthropicProvider, OpenAICompatibleProvider, OpenAIProvider};
 
 #[derive(Debug)]
 struct GatewaySecurityConfig {
@@ -45,24 +52,7 @@ fn security_config() -> &'static GatewaySecurityConfig {
     })
 }
 
-fn configured_origins() -> Vec<HeaderValue> {
-    let raw = std::env::var("NEXIS_CORS_ALLOW_ORIGINS")
-        .unwrap_or_else(|_| "http://localhost:5173,http://127.0.0.1:5173".to_string());
-
-    raw.split(',')
-        .map(str::trim)
-        .filter(|origin| !origin.is_empty())
-        .filter_map(|origin| match HeaderValue::from_str(origin) {
-            Ok(value) => Some(value),
-            Err(_) => {
-                tracing::warn!("Skipping invalid CORS origin '{}'", origin);
-                None
-            }
-        })
-        .collect()
-}
-
-fn build_cors_layer() -> CorsLayer {
+fn build_cors_layer(config: &CorsConfig) -> CorsLayer {
     let mut cors = CorsLayer::new()
         .allow_methods([
             Method::GET,
@@ -83,20 +73,18 @@ fn build_cors_layer() -> CorsLayer {
         )])
         .max_age(Duration::from_secs(3600));
 
-    let origins = configured_origins();
-    if origins.iter().any(|value| value.as_bytes() == b"*") {
+    // Origins were validated when the config was loaded.
+    if config.allow_origins.iter().any(|origin| origin == "*") {
         cors = cors.allow_origin(Any);
-    } else if !origins.is_empty() {
-        cors = cors.allow_origin(AllowOrigin::list(origins));
     } else {
-        tracing::warn!("No valid CORS origins found; defaulting to localhost dev origins");
-        cors = cors.allow_origin(AllowOrigin::list([
-            HeaderValue::from_static("http://localhost:5173"),
-            HeaderValue::from_static("http://127.0.0.1:5173"),
-        ]));
+        let origins = config
+            .allow_origins
+            .iter()
+            .filter_map(|origin| HeaderValue::from_str(origin).ok());
+        cors = cors.allow_origin(AllowOrigin::list(origins));
     }
 
-    if env_flag("NEXIS_CORS_ALLOW_CREDENTIALS", true) {
+    if config.allow_credentials {
         cors = cors.allow_credentials(true);
     }
 
@@ -171,66 +159,150 @@ async fn security_headers_middleware(request: Request<axum::body::Body>, next: N
     response
 }
 
+fn configured_ai_provider(config: &NexisConfig) -> Option<Arc<dyn AIProvider>> {
+    let kind = config.providers.default?;
+    let settings = config.providers.get(kind);
+    // Validation guarantees the default provider has an API key, unless it
+    // is a local server that needs none.
+    let api_key = settings.api_key.clone().unwrap_or_default();
+    let base_url = settings
+        .base_url
+        .clone()
+        .unwrap_or_else(|| kind.default_base_url().to_string());
+    let model = settings
+        .model
+        .clone()
+        .unwrap_or_else(|| kind.default_model().to_string());
+
+    let provider: Arc<dyn AIProvider> = match kind {
+        ProviderKind::OpenAI => Arc::new(OpenAIProvider::new(api_key, base_url, model)),
+        ProviderKind::Anthropic => Arc::new(AnthropicProvider::new(api_key, base_url, model)),
+        ProviderKind::Ollama | ProviderKind::Vllm => Arc::new(
+            OpenAICompatibleProvider::new(kind.as_str(), base_url, model)
+                .with_api_key(settings.api_key.clone()),
+        ),
+    };
+    tracing::info!("AI provider enabled: {}", provider.name());
+    Some(provider)
+}
+
+/// Apply pending schema migrations to `[database] url`.
+async fn migrate(config: &NexisConfig) -> anyhow::Result<()> {
+    let url = config
+        .database
+        .url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("database.url (DATABASE_URL) is required to migrate"))?;
+    let pool = init_pool(url).await?;
+    let applied = migrations::run(&pool).await?;
+    tracing::info!(
+        "Applied {} migration(s); schema is at version {}",
+        applied.len(),
+        migrations::current_version(&pool).await?.unwrap_or(0)
+    );
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize tracing + export config
-    observability::init_tracing()?;
+    let _tracing = observability::init_tracing()?;
 
     tracing::info!("Starting Nexus Gateway v{}", env!("CARGO_PKG_VERSION"));
     init_metrics();
 
+    // Load and validate configuration before binding anything
+    let config = Arc::new(NexisConfig::from_env()?);
+    if config.auth.uses_dev_secret() {
+        tracing::warn!("auth.jwt_secret is not set; using the development secret");
+    }
+
+    match std::env::args().nth(1).as_deref() {
+        None => {}
+        Some("migrate") => return migrate(&config).await,
+        Some(other) => anyhow::bail!("unknown command `{other}`; expected `migrate`"),
+    }
+
     // Build router
+    let shutdown = ShutdownController::new();
+    let ai_provider = configured_ai_provider(&config);
+    let audit = AuditLog::from_config(&config).await?;
+    let storage = Storage::from_config(&config).await?;
+    let indexing = KeywordIndexing::new(BatchConfig::default());
+    indexing.drain_on(&shutdown);
+    if let Some(outbox) = storage.outbox.clone() {
+        // Picks up messages stored before a crash kept them from the queue
+        indexing.relay_outbox(outbox, Duration::from_secs(1), &shutdown);
+    }
+    #[cfg(feature = "multi-tenant")]
+    let routes = router::build_routes_with_tenants(
+        config.clone(),
+        ai_provider,
+        audit,
+        shutdown.clone(),
+        storage,
+        indexing,
+        nexis_gateway::TenantDirectory::from_config(&config).await?,
+    );
+    #[cfg(not(feature = "multi-tenant"))]
+    let routes = router::build_routes_with_config(
+        config.clone(),
+        ai_provider,
+        audit,
+        shutdown.clone(),
+        storage,
+        indexing,
+    );
     let app = Router::new()
-        .merge(router::build_routes())
+        .merge(routes)
         .layer(middleware::from_fn(security_headers_middleware))
         .layer(middleware::from_fn(enforce_https_middleware))
-        .layer(build_cors_layer())
+        .layer(build_cors_layer(&config.cors))
         .layer(TraceLayer::new_for_http());
 
     // Start server
-    let addr: SocketAddr = std::env::var("NEXIS_BIND_ADDR")
-        .unwrap_or_else(|_| "0.0.0.0:8080".into())
-        .parse()?;
+    let addr = config.server.bind_addr;
 
     tracing::info!("Listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    
-    // Graceful shutdown setup
-    let shutdown = async {
-        use tokio::signal;
-        
-        let ctrl_c = async {
-            signal::ctrl_c()
-                .await
-                .expect("failed to install Ctrl+C handler");
-        };
-        
-        #[cfg(unix)]
-        let terminate = async {
-            signal::unix::signal(signal::unix::SignalKind::terminate())
-                .expect("failed to install signal handler")
-                .recv()
-                .await;
-        };
-        
-        #[cfg(not(unix))]
-        let terminate = std::future::pending::<()>();
-        
-        tokio::select! {
-            _ = ctrl_c => tracing::info!("Received Ctrl+C"),
-            _ = terminate => tracing::info!("Received SIGTERM"),
-        }
-        
-        tracing::info!("Shutdown signal received, stopping gracefully...");
-    };
-    
-    // Run server with graceful shutdown
+
+    // Stop accepting on the first signal; in-flight requests keep running
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown.triggered())
+        .into_future();
+    tokio::pin!(server);
+
     tokio::select! {
-        _ = axum::serve(listener, app) => {},
-        _ = shutdown => {},
+        result = &mut server => {
+            result?;
+            return Ok(());
+        }
+        _ = shutdown_signal() => {}
     }
-    
+
+    let drain_timeout = config.server.drain_timeout();
+    tracing::info!(
+        "Shutdown signal received, draining for up to {}s",
+        drain_timeout.as_secs()
+    );
+    shutdown.trigger();
+
+    // WebSocket clients get a close frame, then queues flush via drain hooks
+    let drained = tokio::time::timeout(drain_timeout, async {
+        if let Err(err) = server.await {
+            tracing::error!("Server error during shutdown: {}", err);
+        }
+        shutdown.drain().await;
+    })
+    .await;
+    if drained.is_err() {
+        tracing::warn!(
+            "Drain timeout elapsed with {} connection(s) still open",
+            shutdown.active_connections()
+        );
+    }
+
     tracing::info!("Server stopped");
     Ok(())
 }
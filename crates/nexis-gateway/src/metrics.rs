@@ -2,6 +2,8 @@
 //!
 //! Exposes metrics for monitoring gateway performance and health.
 
+use std::time::Instant;
+
 use lazy_static::lazy_static;
 use prometheus::{
     register_counter, register_counter_vec, register_gauge, register_gauge_vec, register_histogram,
@@ -25,6 +27,14 @@ lazy_static! {
     pub static ref CONNECTION_ERRORS: CounterVec =
         register_counter_vec!("nexis_connection_errors", "Connection errors by type", &["error_type"]).unwrap();
 
+    /// Room events skipped because a WebSocket subscriber fell behind the broadcast
+    pub static ref WS_BROADCAST_LAGGED_TOTAL: Counter =
+        register_counter!("nexis_ws_broadcast_lagged_total", "Room events skipped by lagging WebSocket subscribers").unwrap();
+
+    /// WebSocket connections closed because they stopped answering pings
+    pub static ref WS_CONNECTIONS_REAPED_TOTAL: Counter =
+        register_counter!("nexis_ws_connections_reaped_total", "WebSocket connections closed after missing heartbeats").unwrap();
+
     // ============================================================================
     // Message Metrics
     // ============================================================================
@@ -111,6 +121,18 @@ lazy_static! {
     pub static ref OPERATION_ERRORS_TOTAL: CounterVec =
         register_counter_vec!("nexis_operation_errors_total", "Operation errors by operation and type", &["operation", "error_type"]).unwrap();
 
+    // ============================================================================
+    // Spam Metrics
+    // ============================================================================
+
+    /// Spam signals tripped by sent messages
+    pub static ref SPAM_DETECTIONS_TOTAL: CounterVec =
+        register_counter_vec!("nexis_spam_detections_total", "Spam signals detected by signal", &["signal"]).unwrap();
+
+    /// Members muted by the spam guard
+    pub static ref SPAM_MUTES_TOTAL: Counter =
+        register_counter!("nexis_spam_mutes_total", "Members automatically muted for spam").unwrap();
+
     // ============================================================================
     // HTTP Metrics
     // ============================================================================
@@ -131,6 +153,48 @@ lazy_static! {
     pub static ref HTTP_RESPONSES: CounterVec =
         register_counter_vec!("nexis_http_responses_total", "HTTP responses by status code", &["method", "path", "status"]).unwrap();
 
+    // ============================================================================
+    // Indexing Metrics
+    // ============================================================================
+
+    /// Messages waiting in the indexing queue
+    pub static ref INDEXING_QUEUE_DEPTH: Gauge =
+        register_gauge!("nexis_indexing_queue_depth", "Messages waiting in the indexing queue").unwrap();
+
+    /// Indexing batch flush latency
+    pub static ref INDEXING_FLUSH_LATENCY: Histogram = register_histogram!(
+        "nexis_indexing_flush_latency_seconds",
+        "Indexing batch flush latency in seconds",
+        vec![0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    ).unwrap();
+
+    /// Indexing batch sizes
+    pub static ref INDEXING_BATCH_SIZE: Histogram = register_histogram!(
+        "nexis_indexing_batch_size",
+        "Number of messages per indexing flush",
+        vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0]
+    ).unwrap();
+
+    /// Messages dropped or rejected by the indexing overflow policy
+    pub static ref INDEXING_OVERFLOW_TOTAL: CounterVec =
+        register_counter_vec!("nexis_indexing_overflow_total", "Indexing queue overflow events by policy", &["policy"]).unwrap();
+
+    /// Near-duplicate messages caught at index time, by the policy applied
+    pub static ref INDEXING_DUPLICATES_TOTAL: CounterVec =
+        register_counter_vec!("nexis_indexing_duplicates_total", "Near-duplicate messages detected at index time by policy", &["policy"]).unwrap();
+
+    // ============================================================================
+    // Search Metrics
+    // ============================================================================
+
+    /// Semantic search latency by outcome
+    pub static ref SEARCH_LATENCY: HistogramVec = register_histogram_vec!(
+        "nexis_search_latency_seconds",
+        "Semantic search latency in seconds",
+        &["outcome"],
+        vec![0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]
+    ).unwrap();
+
     // ============================================================================
     // System Metrics
     // ============================================================================
@@ -185,6 +249,82 @@ pub fn export() -> String {
     String::from_utf8(buffer).unwrap()
 }
 
+// ============================================================================
+// HTTP and WebSocket Metrics Helpers
+// ============================================================================
+
+/// Record a finished HTTP request against its route template
+pub fn record_http_request(method: &str, route: &str, status: u16, started: Instant) {
+    let status = status.to_string();
+    HTTP_REQUESTS_TOTAL
+        .with_label_values(&[method, route])
+        .inc();
+    HTTP_RESPONSES
+        .with_label_values(&[method, route, &status])
+        .inc();
+    HTTP_LATENCY
+        .with_label_values(&[method, route])
+        .observe(started.elapsed().as_secs_f64());
+}
+
+/// Record an accepted WebSocket connection
+pub fn record_ws_connection_opened() {
+    CONNECTIONS_TOTAL.inc();
+    CONNECTIONS_ACTIVE.inc();
+}
+
+/// Record a closed WebSocket connection
+pub fn record_ws_connection_closed() {
+    CONNECTIONS_ACTIVE.dec();
+}
+
+/// Record a WebSocket connection closed for missing heartbeats
+pub fn record_ws_connection_reaped() {
+    WS_CONNECTIONS_REAPED_TOTAL.inc();
+}
+
+/// Record room events skipped by a lagging WebSocket subscriber
+pub fn record_broadcast_lag(skipped: u64) {
+    WS_BROADCAST_LAGGED_TOTAL.inc_by(skipped as f64);
+}
+
+// ============================================================================
+// Provider and Search Metrics Helpers
+// ============================================================================
+
+/// Record an AI provider call; `error_type` is `None` on success
+pub fn record_ai_request(provider: &str, started: Instant, error_type: Option<&str>) {
+    AI_REQUESTS_TOTAL.with_label_values(&[provider]).inc();
+    AI_LATENCY
+        .with_label_values(&[provider])
+        .observe(started.elapsed().as_secs_f64());
+    if let Some(error_type) = error_type {
+        AI_ERRORS.with_label_values(&[provider, error_type]).inc();
+    }
+}
+
+/// Record a semantic search call
+pub fn record_search(started: Instant, succeeded: bool) {
+    let outcome = if succeeded { "ok" } else { "error" };
+    SEARCH_LATENCY
+        .with_label_values(&[outcome])
+        .observe(started.elapsed().as_secs_f64());
+}
+
+// ============================================================================
+// Spam Metrics Helpers
+// ============================================================================
+
+/// Record a spam signal a message tripped
+pub fn record_spam_detection(signal: &str) {
+    SPAM_DETECTIONS_TOTAL.with_label_values(&[signal]).inc();
+}
+
+/// Record an automatic mute for spam
+pub fn record_spam_mute() {
+    SPAM_MUTES_TOTAL.inc();
+}
+
 // ============================================================================
 // Connection Pool Metrics Helpers
 // ============================================================================
@@ -228,6 +368,31 @@ pub fn record_pool_message_dropped() {
     POOL_MESSAGES_DROPPED.inc();
 }
 
+// ============================================================================
+// Indexing Metrics Helpers
+// ============================================================================
+
+/// Record the current indexing queue depth
+pub fn record_indexing_queue_depth(depth: usize) {
+    INDEXING_QUEUE_DEPTH.set(depth as f64);
+}
+
+/// Record a completed indexing flush
+pub fn record_indexing_flush(batch_size: usize, started: Instant) {
+    INDEXING_BATCH_SIZE.observe(batch_size as f64);
+    INDEXING_FLUSH_LATENCY.observe(started.elapsed().as_secs_f64());
+}
+
+/// Record an indexing queue overflow handled by the given policy
+pub fn record_indexing_overflow(policy: &str) {
+    INDEXING_OVERFLOW_TOTAL.with_label_values(&[policy]).inc();
+}
+
+/// Record a near-duplicate message handled by the given policy
+pub fn record_indexing_duplicate(policy: &str) {
+    INDEXING_DUPLICATES_TOTAL.with_label_values(&[policy]).inc();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
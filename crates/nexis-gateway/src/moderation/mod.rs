@@ -0,0 +1,235 @@
+//! Content moderation for outgoing messages.
+//!
+//! A [`ModerationService`] labels message text with the categories it trips.
+//! What happens next is decided by a [`ModerationPolicy`]: the gateway-wide
+//! one from `[moderation]`, or a per-room override set through the API.
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::config::{KeywordRule, ModerationAction, ModerationProviderKind, NexisConfig};
+
+mod openai;
+
+pub use openai::OpenAIModerator;
+
+#[derive(Debug, Error)]
+pub enum ModerationError {
+    #[error("invalid moderation rule for {category}: {source}")]
+    InvalidRule {
+        category: String,
+        #[source]
+        source: regex::Error,
+    },
+    #[error("moderation provider is not configured: {0}")]
+    NotConfigured(&'static str),
+    #[error("moderation transport error: {0}")]
+    Transport(String),
+    #[error("moderation provider returned {status}: {body}")]
+    HttpStatus { status: u16, body: String },
+    #[error("moderation response decode error: {0}")]
+    Decode(String),
+}
+
+/// Categories a piece of text tripped; empty when it is clean.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModerationResult {
+    pub categories: Vec<String>,
+}
+
+impl ModerationResult {
+    pub fn is_flagged(&self) -> bool {
+        !self.categories.is_empty()
+    }
+}
+
+#[async_trait]
+pub trait ModerationService: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn check(&self, text: &str) -> Result<ModerationResult, ModerationError>;
+}
+
+/// Build the service selected by `[moderation] provider`, if any.
+pub fn from_config(
+    config: &NexisConfig,
+) -> Result<Option<Box<dyn ModerationService>>, ModerationError> {
+    match config.moderation.provider {
+        ModerationProviderKind::None => Ok(None),
+        ModerationProviderKind::Keywords => Ok(Some(Box::new(KeywordModerator::new(
+            &config.moderation.rules,
+        )?))),
+        ModerationProviderKind::OpenAI => {
+            let openai = &config.providers.openai;
+            let api_key = openai
+                .api_key
+                .clone()
+                .ok_or(ModerationError::NotConfigured("providers.openai.api_key"))?;
+            let base_url = openai.base_url.clone().unwrap_or_else(|| {
+                crate::config::ProviderKind::OpenAI
+                    .default_base_url()
+                    .to_string()
+            });
+            Ok(Some(Box::new(OpenAIModerator::new(
+                api_key,
+                base_url,
+                config.moderation.openai_model.clone(),
+            ))))
+        }
+    }
+}
+
+/// Flags text matching any configured regular expression.
+#[derive(Debug, Clone)]
+pub struct KeywordModerator {
+    rules: Vec<(String, Regex)>,
+}
+
+impl KeywordModerator {
+    pub fn new(rules: &[KeywordRule]) -> Result<Self, ModerationError> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                Regex::new(&rule.pattern)
+                    .map(|pattern| (rule.category.clone(), pattern))
+                    .map_err(|source| ModerationError::InvalidRule {
+                        category: rule.category.clone(),
+                        source,
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+}
+
+#[async_trait]
+impl ModerationService for KeywordModerator {
+    fn name(&self) -> &'static str {
+        "keywords"
+    }
+
+    async fn check(&self, text: &str) -> Result<ModerationResult, ModerationError> {
+        let mut categories = Vec::new();
+        for (category, pattern) in &self.rules {
+            if !categories.contains(category) && pattern.is_match(text) {
+                categories.push(category.clone());
+            }
+        }
+        Ok(ModerationResult { categories })
+    }
+}
+
+/// How a room treats flagged messages.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ModerationPolicy {
+    pub action: ModerationAction,
+    /// Categories that trigger `action`; empty means every category.
+    #[serde(default)]
+    pub categories: Vec<String>,
+}
+
+/// Outcome of applying a [`ModerationPolicy`] to a [`ModerationResult`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModerationDecision {
+    Allow,
+    Flag(Vec<String>),
+    Reject(Vec<String>),
+}
+
+impl ModerationPolicy {
+    pub fn from_config(config: &NexisConfig) -> Self {
+        Self {
+            action: config.moderation.action,
+            categories: config.moderation.categories.clone(),
+        }
+    }
+
+    pub fn decide(&self, result: &ModerationResult) -> ModerationDecision {
+        let matched: Vec<String> = result
+            .categories
+            .iter()
+            .filter(|category| self.categories.is_empty() || self.categories.contains(category))
+            .cloned()
+            .collect();
+        if matched.is_empty() {
+            return ModerationDecision::Allow;
+        }
+        match self.action {
+            ModerationAction::Allow => ModerationDecision::Allow,
+            ModerationAction::Flag => ModerationDecision::Flag(matched),
+            ModerationAction::Reject => ModerationDecision::Reject(matched),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(category: &str, pattern: &str) -> KeywordRule {
+        KeywordRule {
+            category: category.to_string(),
+            pattern: pattern.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn keyword_moderator_reports_each_category_once() {
+        let moderator = KeywordModerator::new(&[
+            rule("spam", r"(?i)\bbuy now\b"),
+            rule("spam", r"(?i)free money"),
+            rule("harassment", r"(?i)\bidiot\b"),
+        ])
+        .unwrap();
+
+        let result = moderator
+            .check("BUY NOW for free money, idiot")
+            .await
+            .unwrap();
+        assert_eq!(result.categories, vec!["spam", "harassment"]);
+        assert!(!moderator
+            .check("see you at standup")
+            .await
+            .unwrap()
+            .is_flagged());
+
+        assert!(matches!(
+            KeywordModerator::new(&[rule("spam", "(unclosed")]),
+            Err(ModerationError::InvalidRule { .. })
+        ));
+    }
+
+    #[test]
+    fn policy_only_acts_on_selected_categories() {
+        let result = ModerationResult {
+            categories: vec!["spam".to_string(), "violence".to_string()],
+        };
+        let policy = ModerationPolicy {
+            action: ModerationAction::Reject,
+            categories: vec!["violence".to_string()],
+        };
+        assert_eq!(
+            policy.decide(&result),
+            ModerationDecision::Reject(vec!["violence".to_string()])
+        );
+
+        let policy = ModerationPolicy {
+            action: ModerationAction::Flag,
+            categories: vec!["hate".to_string()],
+        };
+        assert_eq!(policy.decide(&result), ModerationDecision::Allow);
+
+        let policy = ModerationPolicy::default();
+        assert_eq!(
+            policy.decide(&result),
+            ModerationDecision::Flag(vec!["spam".to_string(), "violence".to_string()])
+        );
+        assert_eq!(
+            policy.decide(&ModerationResult::default()),
+            ModerationDecision::Allow
+        );
+    }
+}
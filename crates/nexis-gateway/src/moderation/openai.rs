@@ -0,0 +1,137 @@
+//! OpenAI moderation endpoint (`POST /moderations`).
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{ModerationError, ModerationResult, ModerationService};
+
+/// Moderation sits on the message send path, so it must answer quickly.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct OpenAIModerator {
+    client: reqwest::Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+impl OpenAIModerator {
+    pub fn new(
+        api_key: impl Into<String>,
+        base_url: impl Into<String>,
+        model: impl Into<String>,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("reqwest client should build"),
+            api_key: api_key.into(),
+            base_url: base_url.into(),
+            model: model.into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ModerationResponse {
+    results: Vec<ModerationResultBody>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModerationResultBody {
+    /// Category name (`harassment`, `self-harm/intent`, ...) to whether it
+    /// tripped.
+    categories: BTreeMap<String, bool>,
+}
+
+impl ModerationResponse {
+    fn into_result(self) -> Result<ModerationResult, ModerationError> {
+        let first = self
+            .results
+            .into_iter()
+            .next()
+            .ok_or_else(|| ModerationError::Decode("missing moderation result".to_string()))?;
+        Ok(ModerationResult {
+            categories: first
+                .categories
+                .into_iter()
+                .filter_map(|(category, flagged)| flagged.then_some(category))
+                .collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl ModerationService for OpenAIModerator {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    #[tracing::instrument(name = "moderation.openai.check", skip_all, fields(otel.kind = "client"))]
+    async fn check(&self, text: &str) -> Result<ModerationResult, ModerationError> {
+        let response = self
+            .client
+            .post(format!(
+                "{}/moderations",
+                self.base_url.trim_end_matches('/')
+            ))
+            .bearer_auth(&self.api_key)
+            .json(&json!({ "model": self.model, "input": text }))
+            .send()
+            .await
+            .map_err(|err| ModerationError::Transport(err.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ModerationError::HttpStatus {
+                status: status.as_u16(),
+                body,
+            });
+        }
+
+        response
+            .json::<ModerationResponse>()
+            .await
+            .map_err(|err| ModerationError::Decode(err.to_string()))?
+            .into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flagged_categories_are_extracted_from_the_first_result() {
+        let response: ModerationResponse = serde_json::from_value(json!({
+            "id": "modr-1",
+            "model": "omni-moderation-latest",
+            "results": [{
+                "flagged": true,
+                "categories": {
+                    "harassment": true,
+                    "hate": false,
+                    "self-harm/intent": true
+                },
+                "category_scores": { "harassment": 0.91, "hate": 0.01, "self-harm/intent": 0.7 }
+            }]
+        }))
+        .unwrap();
+        assert_eq!(
+            response.into_result().unwrap().categories,
+            vec!["harassment", "self-harm/intent"]
+        );
+
+        let empty: ModerationResponse = serde_json::from_value(json!({ "results": [] })).unwrap();
+        assert!(matches!(
+            empty.into_result(),
+            Err(ModerationError::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,815 @@
+//! Member notifications for room messages and mentions.
+//!
+//! Every member keeps [`NotificationPreferences`]: a level (`all`,
+//! `mentions` or `none`) with per-room overrides, the channels to deliver
+//! on and whether deliveries are batched into digests. Notifications always
+//! reach the member's WebSocket connections right away; the webhook and email
+//! channels deliver them one by one or as an hourly or daily digest.
+//! Preferences and pending digests are kept in memory.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::config::NotificationsConfig;
+use crate::email::{self, EmailTemplate, Mailer};
+use crate::server::ShutdownController;
+
+/// Notifications buffered for WebSocket connections before the slowest lags.
+const LIVE_CAPACITY: usize = 1_024;
+/// Characters of the message kept in a notification.
+const EXCERPT_CHARS: usize = 200;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum NotificationError {
+    #[error("invalid notification preferences: {0}")]
+    InvalidPreferences(String),
+    #[error("delivery failed: {0}")]
+    Delivery(String),
+}
+
+/// Which messages a member is notified of.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationLevel {
+    /// Every message in the room.
+    All,
+    /// Messages mentioning the member with `@<member id>`.
+    #[default]
+    Mentions,
+    /// Nothing.
+    None,
+}
+
+/// How often the webhook and email channels deliver.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestMode {
+    /// Each notification on its own, as it happens.
+    #[default]
+    Immediate,
+    /// Notifications of the past hour in one delivery.
+    Hourly,
+    /// Notifications of the past day in one delivery.
+    Daily,
+}
+
+impl DigestMode {
+    /// Time a digest collects notifications for; `None` when not batching.
+    pub fn period(self) -> Option<chrono::Duration> {
+        match self {
+            Self::Immediate => None,
+            Self::Hourly => Some(chrono::Duration::hours(1)),
+            Self::Daily => Some(chrono::Duration::days(1)),
+        }
+    }
+}
+
+/// Where a member's notifications are delivered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NotificationChannels {
+    /// Push `notification` frames to the member's WebSocket connections.
+    #[serde(default = "enabled")]
+    pub websocket: bool,
+    /// `POST` notifications as JSON to this URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// Email notifications to this address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Default for NotificationChannels {
+    fn default() -> Self {
+        Self {
+            websocket: true,
+            webhook_url: None,
+            email: None,
+        }
+    }
+}
+
+/// A member's notification settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NotificationPreferences {
+    #[serde(default)]
+    pub level: NotificationLevel,
+    /// Levels overriding `level` in single rooms, by room id.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rooms: BTreeMap<String, NotificationLevel>,
+    #[serde(default)]
+    pub channels: NotificationChannels,
+    #[serde(default)]
+    pub digest: DigestMode,
+}
+
+impl NotificationPreferences {
+    /// Level that applies in `room_id`.
+    pub fn level_for(&self, room_id: &str) -> NotificationLevel {
+        self.rooms.get(room_id).copied().unwrap_or(self.level)
+    }
+
+    /// Why the preferences cannot be used, if they cannot.
+    pub fn validate(&self) -> Result<(), NotificationError> {
+        if let Some(url) = &self.channels.webhook_url {
+            let valid = reqwest::Url::parse(url).is_ok_and(|url| {
+                matches!(url.scheme(), "http" | "https") && url.host_str().is_some()
+            });
+            if !valid {
+                return Err(NotificationError::InvalidPreferences(
+                    "webhookUrl must be an absolute http(s) url".to_string(),
+                ));
+            }
+        }
+        if let Some(email) = &self.channels.email {
+            if !email::is_address(email) {
+                return Err(NotificationError::InvalidPreferences(
+                    "email must be an email address".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a member is notified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// A message in a room the member follows.
+    Message,
+    /// A message mentioning the member.
+    Mention,
+}
+
+/// One notification for one member.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    pub id: String,
+    pub member_id: String,
+    pub kind: NotificationKind,
+    pub room_id: String,
+    pub message_id: String,
+    pub sender: String,
+    /// Start of the message text.
+    pub excerpt: String,
+    pub created_at: DateTime<Utc>,
+    /// Tenant of the room, which picks the email sender address.
+    #[serde(skip)]
+    pub tenant_id: Option<String>,
+}
+
+/// A message notifications are raised for.
+#[derive(Debug, Clone, Copy)]
+pub struct MessageNotice<'a> {
+    pub room_id: &'a str,
+    pub message_id: &'a str,
+    pub sender: &'a str,
+    pub text: &'a str,
+    /// Tenant of the room, if any.
+    pub tenant: Option<&'a str>,
+}
+
+/// Whether `sender` names `member_id`, either exactly or as the last
+/// segment of a `nexis:<kind>:<name>` id.
+pub fn is_member(sender: &str, member_id: &str) -> bool {
+    sender == member_id
+        || sender
+            .rsplit_once(':')
+            .is_some_and(|(_, name)| name == member_id)
+}
+
+/// Whether `text` mentions `member_id` with `@<member id>`, or with
+/// `@<name>` for a `nexis:<kind>:<name>` id, the name ignoring case.
+pub fn mentions(text: &str, member_id: &str) -> bool {
+    let name = member_id
+        .rsplit_once(':')
+        .map_or(member_id, |(_, name)| name);
+    text.split_whitespace()
+        .filter_map(|word| word.strip_prefix('@'))
+        .map(|word| word.trim_end_matches(|c: char| ",.;:!?)".contains(c)))
+        .any(|mention| {
+            !mention.is_empty()
+                && (is_member(mention, member_id) || mention.eq_ignore_ascii_case(name))
+        })
+}
+
+/// A channel notifications are delivered on besides WebSocket connections.
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// Name used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Deliver `notifications`, one or a digest, to `member_id` if its
+    /// preferences enable this channel.
+    async fn deliver(
+        &self,
+        member_id: &str,
+        preferences: &NotificationPreferences,
+        notifications: &[Notification],
+    ) -> Result<(), NotificationError>;
+}
+
+/// `POST`s `{"memberId": ..., "notifications": [...]}` to the member's
+/// webhook URL.
+#[derive(Debug, Clone)]
+pub struct WebhookChannel {
+    client: reqwest::Client,
+}
+
+impl Default for WebhookChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebhookChannel {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(DELIVERY_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for WebhookChannel {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn deliver(
+        &self,
+        member_id: &str,
+        preferences: &NotificationPreferences,
+        notifications: &[Notification],
+    ) -> Result<(), NotificationError> {
+        let Some(url) = &preferences.channels.webhook_url else {
+            return Ok(());
+        };
+        let body = serde_json::json!({
+            "memberId": member_id,
+            "notifications": notifications,
+        });
+        let response = self
+            .client
+            .post(url)
+            .header(crate::webhooks::EVENT_HEADER, "notification")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|err| NotificationError::Delivery(err.to_string()))?;
+        if !response.status().is_success() {
+            return Err(NotificationError::Delivery(format!(
+                "webhook answered {}",
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Mails notifications to the member's email address, one at a time with
+/// the `mention` or `message` template and as a `digest` otherwise.
+pub struct EmailChannel {
+    mailer: Arc<Mailer>,
+}
+
+impl EmailChannel {
+    pub fn new(mailer: Arc<Mailer>) -> Self {
+        Self { mailer }
+    }
+}
+
+fn email_time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    async fn deliver(
+        &self,
+        _member_id: &str,
+        preferences: &NotificationPreferences,
+        notifications: &[Notification],
+    ) -> Result<(), NotificationError> {
+        let (Some(address), Some(first)) = (&preferences.channels.email, notifications.first())
+        else {
+            return Ok(());
+        };
+        let tenant = first.tenant_id.as_deref();
+        let result = if let [notification] = notifications {
+            let template = match notification.kind {
+                NotificationKind::Mention => EmailTemplate::Mention,
+                NotificationKind::Message => EmailTemplate::Message,
+            };
+            let sent_at = email_time(notification.created_at);
+            let values = [
+                ("sender", notification.sender.as_str()),
+                ("room_id", notification.room_id.as_str()),
+                ("message_id", notification.message_id.as_str()),
+                ("excerpt", notification.excerpt.as_str()),
+                ("sent_at", sent_at.as_str()),
+            ];
+            self.mailer.send(template, address, tenant, &values).await
+        } else {
+            let count = notifications.len().to_string();
+            let lines = notifications
+                .iter()
+                .map(|notification| {
+                    format!(
+                        "[{}] {} in {}:\n{}\n",
+                        email_time(notification.created_at),
+                        notification.sender,
+                        notification.room_id,
+                        notification.excerpt
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let values = [("count", count.as_str()), ("notifications", lines.as_str())];
+            self.mailer
+                .send(EmailTemplate::Digest, address, tenant, &values)
+                .await
+        };
+        result.map_err(|err| NotificationError::Delivery(err.to_string()))
+    }
+}
+
+/// Notifications waiting for a member's next digest.
+#[derive(Debug, Clone)]
+struct PendingDigest {
+    since: DateTime<Utc>,
+    notifications: Vec<Notification>,
+}
+
+/// Decides who is notified of a message and delivers the notifications.
+#[derive(Clone)]
+pub struct NotificationService {
+    enabled: bool,
+    preferences: Arc<RwLock<HashMap<String, NotificationPreferences>>>,
+    pending: Arc<RwLock<HashMap<String, PendingDigest>>>,
+    live: broadcast::Sender<Notification>,
+    channels: Vec<Arc<dyn NotificationChannel>>,
+    email: bool,
+}
+
+impl Default for NotificationService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotificationService {
+    /// Notify over WebSocket connections and webhooks.
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            preferences: Arc::new(RwLock::new(HashMap::new())),
+            pending: Arc::new(RwLock::new(HashMap::new())),
+            live: broadcast::channel(LIVE_CAPACITY).0,
+            channels: vec![Arc::new(WebhookChannel::new())],
+            email: false,
+        }
+    }
+
+    /// Service for `[notifications]`, mailing notifications through
+    /// `mailer` when email is configured.
+    pub fn from_config(config: &NotificationsConfig, mailer: Option<Arc<Mailer>>) -> Self {
+        let service = Self::new().with_enabled(config.enabled);
+        match mailer {
+            Some(mailer) => service.with_email_channel(Arc::new(EmailChannel::new(mailer))),
+            None => service,
+        }
+    }
+
+    /// Raise no notifications while `enabled` is off.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Also deliver on `channel`.
+    pub fn with_channel(mut self, channel: Arc<dyn NotificationChannel>) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    /// Deliver email through `channel`; members may only set an email
+    /// address once one is configured.
+    pub fn with_email_channel(mut self, channel: Arc<dyn NotificationChannel>) -> Self {
+        self.email = true;
+        self.with_channel(channel)
+    }
+
+    pub async fn preferences(&self, member_id: &str) -> NotificationPreferences {
+        self.preferences
+            .read()
+            .await
+            .get(member_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Replace a member's preferences. A pending digest is delivered on the
+    /// next tick if the member stops batching.
+    pub async fn set_preferences(
+        &self,
+        member_id: &str,
+        preferences: NotificationPreferences,
+    ) -> Result<(), NotificationError> {
+        preferences.validate()?;
+        if preferences.channels.email.is_some() && !self.email {
+            return Err(NotificationError::InvalidPreferences(
+                "email notifications are not configured".to_string(),
+            ));
+        }
+        self.preferences
+            .write()
+            .await
+            .insert(member_id.to_string(), preferences);
+        Ok(())
+    }
+
+    /// Notifications as they are raised, for WebSocket connections.
+    pub fn subscribe(&self) -> broadcast::Receiver<Notification> {
+        self.live.subscribe()
+    }
+
+    /// Notify `members` of a message, skipping its sender. Returns the
+    /// notifications raised.
+    pub async fn notify_message(
+        &self,
+        message: MessageNotice<'_>,
+        members: &[String],
+    ) -> Vec<Notification> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let now = Utc::now();
+        let excerpt: String = message.text.chars().take(EXCERPT_CHARS).collect();
+        let mut raised = Vec::new();
+        for member_id in members {
+            if is_member(message.sender, member_id) {
+                continue;
+            }
+            let preferences = self.preferences(member_id).await;
+            let kind = if mentions(message.text, member_id) {
+                NotificationKind::Mention
+            } else {
+                NotificationKind::Message
+            };
+            match (preferences.level_for(message.room_id), kind) {
+                (NotificationLevel::None, _)
+                | (NotificationLevel::Mentions, NotificationKind::Message) => continue,
+                _ => {}
+            }
+            let notification = Notification {
+                id: format!("ntf_{}", Uuid::new_v4().simple()),
+                member_id: member_id.clone(),
+                kind,
+                room_id: message.room_id.to_string(),
+                message_id: message.message_id.to_string(),
+                sender: message.sender.to_string(),
+                excerpt: excerpt.clone(),
+                created_at: now,
+                tenant_id: message.tenant.map(str::to_string),
+            };
+            if preferences.channels.websocket {
+                // No receivers simply means nobody is connected right now.
+                let _ = self.live.send(notification.clone());
+            }
+            self.dispatch(&preferences, notification.clone()).await;
+            raised.push(notification);
+        }
+        raised
+    }
+
+    /// Deliver a notification on the other channels now, or queue it for
+    /// the member's next digest.
+    async fn dispatch(&self, preferences: &NotificationPreferences, notification: Notification) {
+        if preferences.channels.webhook_url.is_none() && preferences.channels.email.is_none() {
+            return;
+        }
+        if preferences.digest.period().is_some() {
+            self.pending
+                .write()
+                .await
+                .entry(notification.member_id.clone())
+                .or_insert_with(|| PendingDigest {
+                    since: notification.created_at,
+                    notifications: Vec::new(),
+                })
+                .notifications
+                .push(notification);
+            return;
+        }
+        let service = self.clone();
+        let preferences = preferences.clone();
+        tokio::spawn(async move {
+            let member_id = notification.member_id.clone();
+            service
+                .deliver(&member_id, &preferences, &[notification])
+                .await;
+        });
+    }
+
+    async fn deliver(
+        &self,
+        member_id: &str,
+        preferences: &NotificationPreferences,
+        notifications: &[Notification],
+    ) {
+        for channel in &self.channels {
+            if let Err(err) = channel.deliver(member_id, preferences, notifications).await {
+                tracing::warn!(
+                    member_id,
+                    channel = channel.name(),
+                    "Failed to deliver notifications: {}",
+                    err
+                );
+            }
+        }
+    }
+
+    /// Deliver the digests due at `now`. Returns the members they went to.
+    pub async fn flush_digests(&self, now: DateTime<Utc>) -> Vec<String> {
+        let preferences = self.preferences.read().await.clone();
+        let due: Vec<(String, PendingDigest)> = {
+            let mut pending = self.pending.write().await;
+            let members: Vec<String> = pending
+                .iter()
+                .filter(|(member_id, digest)| {
+                    let period = preferences
+                        .get(*member_id)
+                        .and_then(|preferences| preferences.digest.period());
+                    period.is_none_or(|period| now - digest.since >= period)
+                })
+                .map(|(member_id, _)| member_id.clone())
+                .collect();
+            members
+                .into_iter()
+                .filter_map(|member_id| pending.remove_entry(&member_id))
+                .collect()
+        };
+
+        let mut delivered = Vec::with_capacity(due.len());
+        for (member_id, digest) in due {
+            let preferences = preferences.get(&member_id).cloned().unwrap_or_default();
+            self.deliver(&member_id, &preferences, &digest.notifications)
+                .await;
+            delivered.push(member_id);
+        }
+        delivered
+    }
+
+    /// Deliver due digests every `tick` until `shutdown` is triggered.
+    pub fn spawn(self, tick: Duration, shutdown: ShutdownController) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let stopped = shutdown.triggered();
+            tokio::pin!(stopped);
+            let mut interval = tokio::time::interval(tick);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    _ = interval.tick() => {
+                        let delivered = self.flush_digests(Utc::now()).await;
+                        if !delivered.is_empty() {
+                            tracing::debug!(members = delivered.len(), "Delivered notification digests");
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        delivered: Mutex<Vec<(String, usize)>>,
+    }
+
+    #[async_trait]
+    impl NotificationChannel for RecordingChannel {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        async fn deliver(
+            &self,
+            member_id: &str,
+            _preferences: &NotificationPreferences,
+            notifications: &[Notification],
+        ) -> Result<(), NotificationError> {
+            self.delivered
+                .lock()
+                .unwrap()
+                .push((member_id.to_string(), notifications.len()));
+            Ok(())
+        }
+    }
+
+    fn message(text: &str) -> MessageNotice<'_> {
+        MessageNotice {
+            room_id: "room_1",
+            message_id: "msg_1",
+            sender: "nexis:human:alice",
+            text,
+            tenant: None,
+        }
+    }
+
+    fn members() -> Vec<String> {
+        ["alice", "bob", "carol"].map(String::from).to_vec()
+    }
+
+    #[test]
+    fn mentions_match_member_ids_and_their_last_segment() {
+        assert!(mentions("hey @bob, look", "bob"));
+        assert!(mentions("@Bob!", "nexis:human:bob"));
+        assert!(mentions("ping @nexis:human:bob", "nexis:human:bob"));
+        assert!(!mentions("hey @bobby", "bob"));
+        assert!(!mentions("mail bob@example.com", "bob"));
+    }
+
+    #[tokio::test]
+    async fn levels_decide_who_is_notified() {
+        let service = NotificationService::new();
+        service
+            .set_preferences(
+                "carol",
+                NotificationPreferences {
+                    level: NotificationLevel::All,
+                    rooms: BTreeMap::from([("room_2".to_string(), NotificationLevel::None)]),
+                    ..NotificationPreferences::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // The sender is skipped; bob only hears of mentions by default.
+        let raised = service.notify_message(message("hello"), &members()).await;
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].member_id, "carol");
+
+        let raised = service
+            .notify_message(message("@bob hello"), &members())
+            .await;
+        let kinds: Vec<_> = raised
+            .iter()
+            .map(|n| (n.member_id.as_str(), n.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("bob", NotificationKind::Mention),
+                ("carol", NotificationKind::Message)
+            ]
+        );
+
+        let other_room = MessageNotice {
+            room_id: "room_2",
+            ..message("hello")
+        };
+        assert!(service
+            .notify_message(other_room, &members())
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn digests_batch_until_their_period_passes() {
+        let channel = Arc::new(RecordingChannel::default());
+        let service = NotificationService::new().with_channel(channel.clone());
+        let preferences = NotificationPreferences {
+            level: NotificationLevel::All,
+            channels: NotificationChannels {
+                webhook_url: Some("https://hooks.example.com/bob".to_string()),
+                ..NotificationChannels::default()
+            },
+            digest: DigestMode::Hourly,
+            ..NotificationPreferences::default()
+        };
+        service.set_preferences("bob", preferences).await.unwrap();
+        let mut live = service.subscribe();
+
+        for _ in 0..3 {
+            service
+                .notify_message(message("hello"), &["bob".to_string()])
+                .await;
+        }
+        assert_eq!(live.recv().await.unwrap().member_id, "bob");
+
+        assert!(service.flush_digests(Utc::now()).await.is_empty());
+        let later = Utc::now() + chrono::Duration::hours(1);
+        assert_eq!(service.flush_digests(later).await, ["bob"]);
+        assert_eq!(*channel.delivered.lock().unwrap(), [("bob".to_string(), 3)]);
+    }
+
+    #[tokio::test]
+    async fn email_needs_a_configured_channel() {
+        let preferences = NotificationPreferences {
+            channels: NotificationChannels {
+                email: Some("bob@example.com".to_string()),
+                ..NotificationChannels::default()
+            },
+            ..NotificationPreferences::default()
+        };
+        let service = NotificationService::new();
+        assert!(service
+            .set_preferences("bob", preferences.clone())
+            .await
+            .is_err());
+
+        let service = service.with_email_channel(Arc::new(RecordingChannel::default()));
+        service.set_preferences("bob", preferences).await.unwrap();
+
+        let invalid = NotificationPreferences {
+            channels: NotificationChannels {
+                webhook_url: Some("ftp://example.com".to_string()),
+                ..NotificationChannels::default()
+            },
+            ..NotificationPreferences::default()
+        };
+        assert!(service.set_preferences("bob", invalid).await.is_err());
+    }
+
+    #[derive(Default)]
+    struct Outbox(Mutex<Vec<email::EmailMessage>>);
+
+    #[async_trait]
+    impl email::EmailSender for Outbox {
+        async fn send(&self, message: email::EmailMessage) -> Result<(), email::EmailError> {
+            self.0.lock().unwrap().push(message);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn email_channel_mails_single_notifications_and_digests() {
+        let outbox = Arc::new(Outbox::default());
+        let mut config = crate::config::EmailConfig::default();
+        config
+            .tenant_from
+            .insert("acme".to_string(), "chat@acme.test".to_string());
+        let channel = EmailChannel::new(Arc::new(Mailer::new(outbox.clone(), config)));
+        let preferences = NotificationPreferences {
+            channels: NotificationChannels {
+                email: Some("bob@example.com".to_string()),
+                ..NotificationChannels::default()
+            },
+            ..NotificationPreferences::default()
+        };
+        let service = NotificationService::new();
+        service
+            .set_preferences("bob", NotificationPreferences::default())
+            .await
+            .unwrap();
+        let notice = MessageNotice {
+            tenant: Some("acme"),
+            ..message("@bob hello")
+        };
+        let raised = service.notify_message(notice, &members()).await;
+
+        channel.deliver("bob", &preferences, &raised).await.unwrap();
+        let digest = [raised[0].clone(), raised[0].clone()];
+        channel.deliver("bob", &preferences, &digest).await.unwrap();
+
+        let sent = outbox.0.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].from, "chat@acme.test");
+        assert_eq!(sent[0].to, "bob@example.com");
+        assert_eq!(sent[0].subject, "nexis:human:alice mentioned you");
+        assert!(sent[0].body.contains("@bob hello"));
+        assert_eq!(sent[1].subject, "2 new notifications");
+    }
+}
@@ -24,11 +24,29 @@ impl TraceExportConfig {
     }
 }
 
-pub fn init_tracing() -> Result<()> {
+/// Keeps the trace exporter alive; dropping it flushes pending spans.
+#[must_use = "dropping the guard shuts down trace export"]
+pub struct TracingGuard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("failed to flush OpenTelemetry spans: {err}");
+            }
+        }
+    }
+}
+
+pub fn init_tracing() -> Result<TracingGuard> {
     let trace_export = TraceExportConfig::from_env()?;
-    let env_filter = EnvFilter::new(
-        std::env::var("RUST_LOG").unwrap_or_else(|_| "nexis_gateway=info,tower_http=info".into()),
-    );
+    let env_filter = EnvFilter::new(std::env::var("RUST_LOG").unwrap_or_else(|_| {
+        "nexis_gateway=info,nexis_runtime=info,nexis_vector=info,tower_http=info".into()
+    }));
 
     let json_logs = std::env::var("NEXIS_LOG_FORMAT")
         .map(|v| v.eq_ignore_ascii_case("json"))
@@ -37,6 +55,20 @@ pub fn init_tracing() -> Result<()> {
         .map(|v| !v.eq_ignore_ascii_case("false"))
         .unwrap_or(true);
 
+    #[cfg(feature = "otel")]
+    let provider = if trace_export.exporter == "otlp" {
+        Some(otlp_tracer_provider(trace_export.endpoint.as_deref())?)
+    } else {
+        None
+    };
+    #[cfg(feature = "otel")]
+    let otel_layer = provider.as_ref().map(|provider| {
+        use opentelemetry::trace::TracerProvider as _;
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME))
+    });
+    #[cfg(not(feature = "otel"))]
+    let otel_layer = None::<tracing_subscriber::layer::Identity>;
+
     let fmt_layer = tracing_subscriber::fmt::layer()
         .json()
         .with_current_span(with_span_list)
@@ -48,11 +80,13 @@ pub fn init_tracing() -> Result<()> {
     if json_logs {
         tracing_subscriber::registry()
             .with(env_filter)
+            .with(otel_layer)
             .with(fmt_layer)
             .init();
     } else {
         tracing_subscriber::registry()
             .with(env_filter)
+            .with(otel_layer)
             .with(tracing_subscriber::fmt::layer())
             .init();
     }
@@ -64,14 +98,83 @@ pub fn init_tracing() -> Result<()> {
         "tracing initialized"
     );
 
-    if trace_export.exporter == "otlp" && trace_export.endpoint.is_none() {
-        tracing::warn!(
-            target: "observability",
-            "NEXIS_OTEL_EXPORTER=otlp is set without NEXIS_OTEL_EXPORT_ENDPOINT"
-        );
+    if trace_export.exporter == "otlp" {
+        if !cfg!(feature = "otel") {
+            tracing::warn!(
+                target: "observability",
+                "NEXIS_OTEL_EXPORTER=otlp is set but this build lacks the `otel` feature; spans will not be exported"
+            );
+        } else if trace_export.endpoint.is_none() {
+            tracing::warn!(
+                target: "observability",
+                "NEXIS_OTEL_EXPORTER=otlp is set without NEXIS_OTEL_EXPORT_ENDPOINT; using the OTLP default endpoint"
+            );
+        }
     }
 
-    Ok(())
+    Ok(TracingGuard {
+        #[cfg(feature = "otel")]
+        provider,
+    })
+}
+
+#[cfg(feature = "otel")]
+const SERVICE_NAME: &str = "nexis-gateway";
+
+/// Build an OTLP/HTTP span exporter and register the W3C trace-context
+/// propagator used for incoming and outgoing requests.
+#[cfg(feature = "otel")]
+fn otlp_tracer_provider(
+    endpoint: Option<&str>,
+) -> Result<opentelemetry_sdk::trace::SdkTracerProvider> {
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{
+        propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource,
+    };
+
+    let mut exporter = opentelemetry_otlp::SpanExporter::builder().with_http();
+    if let Some(endpoint) = endpoint {
+        exporter = exporter.with_endpoint(endpoint);
+    }
+    let exporter = exporter
+        .build()
+        .map_err(|err| anyhow!("failed to build OTLP span exporter: {err}"))?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+        .build();
+
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    opentelemetry::global::set_tracer_provider(provider.clone());
+    Ok(provider)
+}
+
+/// Continue the trace carried by incoming `traceparent`/`tracestate`
+/// headers, if any, by making it the parent of `span`.
+#[cfg(feature = "otel")]
+pub fn set_parent_from_headers(span: &tracing::Span, headers: &axum::http::HeaderMap) {
+    use opentelemetry::propagation::Extractor;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    struct HeaderExtractor<'a>(&'a axum::http::HeaderMap);
+
+    impl Extractor for HeaderExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(|name| name.as_str()).collect()
+        }
+    }
+
+    let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(headers))
+    });
+    if let Err(err) = span.set_parent(parent) {
+        tracing::debug!(target: "observability", error = %err, "failed to attach trace parent");
+    }
 }
 
 #[cfg(test)]
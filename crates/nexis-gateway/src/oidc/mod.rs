@@ -0,0 +1,368 @@
+//! OpenID Connect login for the gateway.
+//!
+//! [`OidcClient`] runs the authorization-code flow with PKCE against the
+//! configured issuer: it discovers the issuer's endpoints, builds the login
+//! redirect, and on callback exchanges the code and validates the returned ID
+//! token before mapping it to a `nexis:human:*` member id. Nexis JWTs are then
+//! minted by the router, so deployments never store passwords.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use nexis_protocol::{MemberId, MemberType};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::{OnceCell, RwLock};
+
+use crate::config::{OidcConfig, OidcMemberClaim};
+
+/// How long a login started with [`OidcClient::authorization_url`] may take
+/// to come back through the callback.
+const LOGIN_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    #[error("OIDC discovery failed: {0}")]
+    Discovery(String),
+    #[error("unknown or expired login state")]
+    InvalidState,
+    #[error("token exchange failed: {0}")]
+    TokenExchange(String),
+    #[error("invalid ID token: {0}")]
+    InvalidIdToken(String),
+    #[error("ID token has no usable `{0}` claim")]
+    MissingClaim(&'static str),
+}
+
+/// Subset of the issuer's discovery document the login flow needs.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// Validated ID token claims.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: Option<bool>,
+    #[serde(default)]
+    nonce: Option<String>,
+}
+
+impl IdTokenClaims {
+    /// Map the identity to a human member id using `claim`.
+    ///
+    /// Emails are lowercased and only accepted when the issuer has not marked
+    /// them unverified.
+    pub fn member_id(&self, claim: OidcMemberClaim) -> Result<MemberId, OidcError> {
+        let identifier = match claim {
+            OidcMemberClaim::Email => self
+                .email
+                .as_deref()
+                .filter(|_| self.email_verified != Some(false))
+                .map(str::to_ascii_lowercase)
+                .ok_or(OidcError::MissingClaim("email"))?,
+            OidcMemberClaim::Subject => self.sub.clone(),
+        };
+        MemberId::new(MemberType::Human, identifier.trim()).map_err(|_| {
+            OidcError::MissingClaim(match claim {
+                OidcMemberClaim::Email => "email",
+                OidcMemberClaim::Subject => "sub",
+            })
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+struct PendingLogin {
+    nonce: String,
+    code_verifier: String,
+    started: Instant,
+}
+
+/// Authorization-code client for a single issuer.
+pub struct OidcClient {
+    config: OidcConfig,
+    issuer_url: String,
+    http: reqwest::Client,
+    metadata: OnceCell<ProviderMetadata>,
+    jwks: RwLock<Option<JwkSet>>,
+    pending: Mutex<HashMap<String, PendingLogin>>,
+}
+
+impl OidcClient {
+    /// Build a client from config; `None` while `oidc.issuer_url` is unset.
+    pub fn from_config(config: &OidcConfig) -> Option<Self> {
+        let issuer_url = config.issuer_url.as_deref()?.trim_end_matches('/');
+        Some(Self {
+            config: config.clone(),
+            issuer_url: issuer_url.to_string(),
+            http: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+            jwks: RwLock::new(None),
+            pending: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn member_claim(&self) -> OidcMemberClaim {
+        self.config.member_claim
+    }
+
+    /// Start a login and return the issuer URL to redirect the browser to.
+    pub async fn authorization_url(&self) -> Result<String, OidcError> {
+        let metadata = self.metadata().await?;
+        let state = random_token();
+        let nonce = random_token();
+        let code_verifier = random_token();
+
+        let mut url = reqwest::Url::parse(&metadata.authorization_endpoint)
+            .map_err(|err| OidcError::Discovery(err.to_string()))?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_url)
+            .append_pair("scope", &self.config.scopes.join(" "))
+            .append_pair("state", &state)
+            .append_pair("nonce", &nonce)
+            .append_pair("code_challenge", &pkce_challenge(&code_verifier))
+            .append_pair("code_challenge_method", "S256");
+
+        let mut pending = self.pending.lock().expect("oidc login state poisoned");
+        pending.retain(|_, login| login.started.elapsed() < LOGIN_TTL);
+        pending.insert(
+            state,
+            PendingLogin {
+                nonce,
+                code_verifier,
+                started: Instant::now(),
+            },
+        );
+        Ok(url.into())
+    }
+
+    /// Finish the login identified by `state`, returning the ID token claims.
+    pub async fn complete(&self, code: &str, state: &str) -> Result<IdTokenClaims, OidcError> {
+        let login = self
+            .pending
+            .lock()
+            .expect("oidc login state poisoned")
+            .remove(state)
+            .filter(|login| login.started.elapsed() < LOGIN_TTL)
+            .ok_or(OidcError::InvalidState)?;
+        let metadata = self.metadata().await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_url.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", login.code_verifier.as_str()),
+        ];
+        if let Some(secret) = &self.config.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|err| OidcError::TokenExchange(err.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(OidcError::TokenExchange(format!("{status}: {body}")));
+        }
+        let tokens: TokenResponse = response
+            .json()
+            .await
+            .map_err(|err| OidcError::TokenExchange(err.to_string()))?;
+
+        let claims = self.verify_id_token(&tokens.id_token, metadata).await?;
+        if claims.nonce.as_deref() != Some(login.nonce.as_str()) {
+            return Err(OidcError::InvalidIdToken("nonce mismatch".to_string()));
+        }
+        Ok(claims)
+    }
+
+    async fn metadata(&self) -> Result<&ProviderMetadata, OidcError> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!("{}/.well-known/openid-configuration", self.issuer_url);
+                let metadata: ProviderMetadata = self
+                    .http
+                    .get(&url)
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status)
+                    .map_err(|err| OidcError::Discovery(err.to_string()))?
+                    .json()
+                    .await
+                    .map_err(|err| OidcError::Discovery(err.to_string()))?;
+                if metadata.issuer.trim_end_matches('/') != self.issuer_url {
+                    return Err(OidcError::Discovery(format!(
+                        "issuer mismatch: expected {}, got {}",
+                        self.issuer_url, metadata.issuer
+                    )));
+                }
+                Ok(metadata)
+            })
+            .await
+    }
+
+    async fn verify_id_token(
+        &self,
+        id_token: &str,
+        metadata: &ProviderMetadata,
+    ) -> Result<IdTokenClaims, OidcError> {
+        let header =
+            decode_header(id_token).map_err(|err| OidcError::InvalidIdToken(err.to_string()))?;
+        let key = match header.alg {
+            // Symmetric ID tokens are signed with the client secret.
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                let secret = self.config.client_secret.as_deref().ok_or_else(|| {
+                    OidcError::InvalidIdToken("HMAC-signed token without a client secret".into())
+                })?;
+                DecodingKey::from_secret(secret.as_bytes())
+            }
+            _ => self.signing_key(metadata, header.kid.as_deref()).await?,
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|err| OidcError::InvalidIdToken(err.to_string()))
+    }
+
+    /// Find the JWK for `kid`, refetching the key set once in case the
+    /// issuer rotated its keys.
+    async fn signing_key(
+        &self,
+        metadata: &ProviderMetadata,
+        kid: Option<&str>,
+    ) -> Result<DecodingKey, OidcError> {
+        for refresh in [false, true] {
+            if refresh || self.jwks.read().await.is_none() {
+                let jwks: JwkSet = self
+                    .http
+                    .get(&metadata.jwks_uri)
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status)
+                    .map_err(|err| OidcError::Discovery(err.to_string()))?
+                    .json()
+                    .await
+                    .map_err(|err| OidcError::Discovery(err.to_string()))?;
+                *self.jwks.write().await = Some(jwks);
+            }
+
+            let jwks = self.jwks.read().await;
+            let jwk = jwks.as_ref().and_then(|jwks| match kid {
+                Some(kid) => jwks.find(kid),
+                None => jwks.keys.first(),
+            });
+            if let Some(jwk) = jwk {
+                return DecodingKey::from_jwk(jwk)
+                    .map_err(|err| OidcError::InvalidIdToken(err.to_string()));
+            }
+        }
+        Err(OidcError::InvalidIdToken(
+            "no matching signing key".to_string(),
+        ))
+    }
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// S256 code challenge from RFC 7636.
+fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(email: Option<&str>, email_verified: Option<bool>) -> IdTokenClaims {
+        IdTokenClaims {
+            sub: "10769150350006150715113082367".to_string(),
+            email: email.map(str::to_string),
+            email_verified,
+            nonce: None,
+        }
+    }
+
+    #[test]
+    fn pkce_challenge_matches_rfc_7636_example() {
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn member_id_is_derived_from_configured_claim() {
+        let verified = claims(Some("Alice@Example.com"), Some(true));
+        assert_eq!(
+            verified
+                .member_id(OidcMemberClaim::Email)
+                .unwrap()
+                .to_string(),
+            "nexis:human:alice@example.com"
+        );
+        assert_eq!(
+            verified
+                .member_id(OidcMemberClaim::Subject)
+                .unwrap()
+                .to_string(),
+            "nexis:human:10769150350006150715113082367"
+        );
+
+        let unverified = claims(Some("alice@example.com"), Some(false));
+        assert!(matches!(
+            unverified.member_id(OidcMemberClaim::Email),
+            Err(OidcError::MissingClaim("email"))
+        ));
+        assert!(claims(None, None)
+            .member_id(OidcMemberClaim::Email)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn unknown_state_is_rejected_before_contacting_issuer() {
+        let client = OidcClient::from_config(&OidcConfig {
+            issuer_url: Some("http://127.0.0.1:9".to_string()),
+            client_id: "nexis".to_string(),
+            redirect_url: "http://localhost/v1/auth/oidc/callback".to_string(),
+            ..OidcConfig::default()
+        })
+        .unwrap();
+
+        assert!(matches!(
+            client.complete("code", "forged").await,
+            Err(OidcError::InvalidState)
+        ));
+    }
+}
@@ -0,0 +1,750 @@
+//! Operator endpoints under `/v1/admin`, restricted to `auth.admin_members`.
+//!
+//! `POST /v1/admin/tokens/revoke` revokes an access token, a refresh token
+//! family, or every refresh token of a member. `POST /v1/admin/reindex`
+//! rebuilds the in-memory keyword index from the stored messages.
+//! `GET /v1/admin/members/:id/connections` and
+//! `POST /v1/admin/connections/route` look up which gateway instances hold
+//! members' WebSocket connections. `/v1/admin/retention` lists, sets, and
+//! removes the maximum age of vector documents per room and per tenant.
+//! `DELETE /v1/admin/rooms/:id` deletes a room, as `DELETE /v1/rooms/:id`
+//! does for admins.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Extension, Json, Router,
+};
+use nexis_protocol::RoomId;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{require_admin, ErrorResponse, SharedState};
+use crate::audit::{AuditAction, AuditEvent, AuditResult};
+use crate::auth::{AuthError, AuthenticatedUser, JwtConfig};
+use crate::cluster::{ClusterError, ConnectionLocation, DeliveryRoute};
+use crate::search::RetentionPolicy;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/admin/tokens/revoke", post(revoke_token))
+        .route("/v1/admin/reindex", post(reindex))
+        .route("/v1/admin/members/:id/connections", get(member_connections))
+        .route("/v1/admin/connections/route", post(route_connections))
+        .route("/v1/admin/rooms/:id", delete(delete_room))
+        .route("/v1/admin/retention", get(list_retention))
+        .route(
+            "/v1/admin/retention/rooms/:id",
+            put(set_room_retention).delete(remove_room_retention),
+        )
+        .route(
+            "/v1/admin/retention/tenants/:id",
+            put(set_tenant_retention).delete(remove_tenant_retention),
+        )
+}
+
+/// Exactly one of `token` and `memberId`.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RevokeTokenRequest {
+    /// An access token, or a refresh token (`nrt_...`).
+    #[serde(default)]
+    token: Option<String>,
+    /// Revoke every refresh token issued to this member.
+    #[serde(default)]
+    member_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RevokeTokenResponse {
+    /// Access tokens plus refresh token families revoked.
+    revoked: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ReindexResponse {
+    indexed: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct MemberConnectionsResponse {
+    member_id: String,
+    connections: Vec<ConnectionLocation>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RouteConnectionsRequest {
+    member_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RoomRetention {
+    room_id: String,
+    max_age_days: u32,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct TenantRetention {
+    tenant_id: String,
+    max_age_days: u32,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RetentionResponse {
+    rooms: Vec<RoomRetention>,
+    tenants: Vec<TenantRetention>,
+}
+
+/// Members accepted by one route lookup.
+const MAX_ROUTE_MEMBERS: usize = 1_000;
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/tokens/revoke",
+    tag = "admin",
+    summary = "Revoke an access token, a refresh token family, or every refresh token of a member",
+    request_body = RevokeTokenRequest,
+    responses(
+        (status = 200, description = "Number of tokens revoked", body = RevokeTokenResponse),
+        (status = 400, description = "Neither or both of token and memberId given", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.admin_revoke_token",
+    skip_all,
+    fields(member_id = %user.member_id)
+)]
+async fn revoke_token(
+    State(state): State<SharedState>,
+    Extension(jwt): Extension<JwtConfig>,
+    user: AuthenticatedUser,
+    Json(payload): Json<RevokeTokenRequest>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+
+    let (resource, revoked) = match (payload.token, payload.member_id) {
+        (Some(token), None) if token.starts_with("nrt_") => (
+            "refresh_token".to_string(),
+            usize::from(jwt.revoke_refresh_token(&token)),
+        ),
+        (Some(token), None) => match jwt.verify_token(&token) {
+            Ok(claims) => {
+                jwt.revoke(&claims);
+                (format!("member:{}", claims.sub), 1)
+            }
+            // Nothing left to deny.
+            Err(AuthError::TokenExpired | AuthError::TokenRevoked) => {
+                ("access_token".to_string(), 0)
+            }
+            Err(_) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse::bad_request(
+                        "token is not an access token or refresh token issued by this gateway",
+                    )),
+                )
+                    .into_response()
+            }
+        },
+        (None, Some(member_id)) if !member_id.trim().is_empty() => {
+            let revoked = jwt.revoke_member_refresh_tokens(&member_id);
+            (format!("member:{member_id}"), revoked)
+        }
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request(
+                    "exactly one of token and memberId is required",
+                )),
+            )
+                .into_response()
+        }
+    };
+
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::TokenRevoked,
+            resource,
+        ))
+        .await;
+    (StatusCode::OK, Json(RevokeTokenResponse { revoked })).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/reindex",
+    tag = "admin",
+    summary = "Rebuild the in-memory keyword search index from stored messages",
+    responses(
+        (status = 200, description = "Number of messages indexed", body = ReindexResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 409, description = "Search is served by an external index", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.admin_reindex", skip_all, fields(member_id = %user.member_id))]
+async fn reindex(State(state): State<SharedState>, user: AuthenticatedUser) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    let Some(index) = state.lexical_index.clone() else {
+        state
+            .audit
+            .record(
+                AuditEvent::new(&user.member_id, AuditAction::SearchReindexed, "search")
+                    .with_result(AuditResult::Failed, "external search service"),
+            )
+            .await;
+        return (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::conflict(
+                "search is served by an external index, which the gateway does not write to",
+            )),
+        )
+            .into_response();
+    };
+
+    let indexed = {
+        let rooms = state.rooms.read().await;
+        let messages = state.room_messages.read().await;
+        index.clear();
+        let mut indexed = 0;
+        for (room_id, messages) in messages.iter() {
+            let tenant = rooms.get(room_id).and_then(|room| room.tenant());
+            for message in messages {
+                state.index_message(room_id, tenant, message);
+                indexed += 1;
+            }
+        }
+        indexed
+    };
+
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::SearchReindexed,
+            "search",
+        ))
+        .await;
+    (StatusCode::OK, Json(ReindexResponse { indexed })).into_response()
+}
+
+fn registry_error_response(err: ClusterError) -> Response {
+    tracing::error!("Connection registry lookup failed: {}", err);
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(ErrorResponse::service_unavailable(
+            "connection registry unavailable",
+        )),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/members/{id}/connections",
+    tag = "admin",
+    summary = "List the gateway instances holding a member's WebSocket connections",
+    params(("id" = String, Path, description = "Member id")),
+    responses(
+        (status = 200, description = "The member's live connections", body = MemberConnectionsResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 503, description = "The connection registry is unreachable", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.admin_member_connections",
+    skip(state, user),
+    fields(member_id = %id)
+)]
+async fn member_connections(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    match state.connections.locate(&id).await {
+        Ok(connections) => (
+            StatusCode::OK,
+            Json(MemberConnectionsResponse {
+                member_id: id,
+                connections,
+            }),
+        )
+            .into_response(),
+        Err(err) => registry_error_response(err),
+    }
+}
+
+/// Group members by the gateway instances their connections live on.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/connections/route",
+    tag = "admin",
+    summary = "Group members by the gateway instances holding their WebSocket connections",
+    request_body = RouteConnectionsRequest,
+    responses(
+        (status = 200, description = "Members per instance, and those offline", body = DeliveryRoute),
+        (status = 400, description = "Too many members", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 503, description = "The connection registry is unreachable", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.admin_route_connections", skip_all)]
+async fn route_connections(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Json(payload): Json<RouteConnectionsRequest>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if payload.member_ids.len() > MAX_ROUTE_MEMBERS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(format!(
+                "at most {MAX_ROUTE_MEMBERS} memberIds are allowed"
+            ))),
+        )
+            .into_response();
+    }
+    match state.connections.route(&payload.member_ids).await {
+        Ok(route) => (StatusCode::OK, Json(route)).into_response(),
+        Err(err) => registry_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/retention",
+    tag = "admin",
+    summary = "List the vector document retention of rooms and tenants",
+    responses(
+        (status = 200, description = "Every room and tenant retention policy", body = RetentionResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.admin_list_retention", skip_all)]
+async fn list_retention(State(state): State<SharedState>, user: AuthenticatedUser) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    let mut rooms: Vec<RoomRetention> = state
+        .retention
+        .rooms()
+        .await
+        .into_iter()
+        .map(|(room_id, policy)| RoomRetention {
+            room_id: room_id.to_string(),
+            max_age_days: policy.max_age_days,
+        })
+        .collect();
+    rooms.sort_by(|a, b| a.room_id.cmp(&b.room_id));
+    let mut tenants: Vec<TenantRetention> = state
+        .retention
+        .tenants()
+        .await
+        .into_iter()
+        .map(|(tenant_id, policy)| TenantRetention {
+            tenant_id,
+            max_age_days: policy.max_age_days,
+        })
+        .collect();
+    tenants.sort_by(|a, b| a.tenant_id.cmp(&b.tenant_id));
+    (StatusCode::OK, Json(RetentionResponse { rooms, tenants })).into_response()
+}
+
+fn invalid_retention() -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::bad_request("maxAgeDays must be at least 1")),
+    )
+        .into_response()
+}
+
+fn retention_not_found(message: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found(message)),
+    )
+        .into_response()
+}
+
+async fn record_retention_change(state: &SharedState, user: &AuthenticatedUser, resource: String) {
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::RetentionChanged,
+            resource,
+        ))
+        .await;
+}
+
+/// Room id of an existing room, if `id` names one.
+async fn existing_room(state: &SharedState, id: &str) -> Option<RoomId> {
+    if !state.rooms.read().await.contains_key(id) {
+        return None;
+    }
+    id.parse().ok()
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/admin/retention/rooms/{id}",
+    tag = "admin",
+    summary = "Set how long a room's vector documents are kept",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = RetentionPolicy,
+    responses(
+        (status = 200, description = "The room's retention policy", body = RetentionPolicy),
+        (status = 400, description = "maxAgeDays is 0", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.admin_set_room_retention", skip(state, user, policy))]
+async fn set_room_retention(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(policy): Json<RetentionPolicy>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if policy.max_age_days == 0 {
+        return invalid_retention();
+    }
+    let Some(room_id) = existing_room(&state, &id).await else {
+        return retention_not_found("room not found");
+    };
+    state.retention.set_room(room_id, policy).await;
+    record_retention_change(&state, &user, format!("room:{id}")).await;
+    (StatusCode::OK, Json(policy)).into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/admin/retention/rooms/{id}",
+    tag = "admin",
+    summary = "Keep a room's vector documents until they expire",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 204, description = "Retention policy removed"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "The room has no retention policy", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.admin_remove_room_retention", skip(state, user))]
+async fn remove_room_retention(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    let removed = match id.parse::<RoomId>() {
+        Ok(room_id) => state.retention.remove_room(&room_id).await,
+        Err(_) => None,
+    };
+    if removed.is_none() {
+        return retention_not_found("room has no retention policy");
+    }
+    record_retention_change(&state, &user, format!("room:{id}")).await;
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/admin/rooms/{id}",
+    tag = "admin",
+    summary = "Delete a room",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 204, description = "Room deleted"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+async fn delete_room(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    super::delete_room(State(state), user, Path(id))
+        .await
+        .into_response()
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/admin/retention/tenants/{id}",
+    tag = "admin",
+    summary = "Set how long a tenant's vector documents are kept",
+    params(("id" = String, Path, description = "Tenant id")),
+    request_body = RetentionPolicy,
+    responses(
+        (status = 200, description = "The tenant's retention policy", body = RetentionPolicy),
+        (status = 400, description = "maxAgeDays is 0", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.admin_set_tenant_retention", skip(state, user, policy))]
+async fn set_tenant_retention(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(policy): Json<RetentionPolicy>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if policy.max_age_days == 0 {
+        return invalid_retention();
+    }
+    state.retention.set_tenant(id.clone(), policy).await;
+    record_retention_change(&state, &user, format!("tenant:{id}")).await;
+    (StatusCode::OK, Json(policy)).into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/admin/retention/tenants/{id}",
+    tag = "admin",
+    summary = "Keep a tenant's vector documents until they expire",
+    params(("id" = String, Path, description = "Tenant id")),
+    responses(
+        (status = 204, description = "Retention policy removed"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "The tenant has no retention policy", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.admin_remove_tenant_retention", skip(state, user))]
+async fn remove_tenant_retention(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if state.retention.remove_tenant(&id).await.is_none() {
+        return retention_not_found("tenant has no retention policy");
+    }
+    record_retention_change(&state, &user, format!("tenant:{id}")).await;
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn admins_revoke_tokens_and_rebuild_the_search_index() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let jwt = crate::auth::fallback_jwt_config();
+        let state = AppState {
+            config: Arc::new(config),
+            jwt: Some(jwt.clone()),
+            ..AppState::default()
+        };
+        let index = state.lexical_index.clone().unwrap();
+        let app = routes(state);
+
+        let victim = jwt.issue_tokens("nexis:human:bob", "human", None).unwrap();
+        jwt.issue_tokens("nexis:human:bob", "human", None).unwrap();
+        let response = app
+            .clone()
+            .oneshot(request(
+                "bob",
+                "POST",
+                "/v1/admin/tokens/revoke",
+                json!({ "memberId": "nexis:human:bob" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                "/v1/admin/tokens/revoke",
+                json!({ "token": victim.access_token }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["revoked"], 1);
+        assert!(matches!(
+            jwt.verify_token(&victim.access_token),
+            Err(crate::auth::AuthError::TokenRevoked)
+        ));
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                "/v1/admin/tokens/revoke",
+                json!({ "memberId": "nexis:human:bob" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["revoked"], 2);
+        assert!(jwt.refresh(&victim.refresh_token).is_err());
+        for body in [
+            json!({}),
+            json!({ "token": "garbage" }),
+            json!({ "token": victim.access_token, "memberId": "nexis:human:bob" }),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request(
+                    "admin",
+                    "POST",
+                    "/v1/admin/tokens/revoke",
+                    body.clone(),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{body}");
+        }
+
+        let room_id = new_room(&app, "admin", "general").await;
+        app.clone()
+            .oneshot(request(
+                "admin", "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": "nexis:human:admin", "text": "release notes" }),
+            ))
+            .await
+            .unwrap();
+        index.clear();
+        let response = app
+            .oneshot(request("admin", "POST", "/v1/admin/reindex", Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["indexed"], 1);
+        assert_eq!(index.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn admins_configure_vector_retention_per_room_and_tenant() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let retention = state.retention.clone();
+        let app = routes(state);
+
+        let room_id = new_room(&app, "admin", "legal").await;
+        let room_uri = format!("/v1/admin/retention/rooms/{room_id}");
+
+        let policy = json!({ "maxAgeDays": 90 });
+        let response = app
+            .clone()
+            .oneshot(request("bob", "PUT", &room_uri, policy.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PUT",
+                &room_uri,
+                json!({ "maxAgeDays": 0 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PUT",
+                "/v1/admin/retention/rooms/room_missing",
+                policy.clone(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        for uri in [room_uri.as_str(), "/v1/admin/retention/tenants/acme"] {
+            let response = app
+                .clone()
+                .oneshot(request("admin", "PUT", uri, policy.clone()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{uri}");
+        }
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", "/v1/admin/retention", Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(
+            json_body(response).await,
+            json!({
+                "rooms": [{ "roomId": room_id, "maxAgeDays": 90 }],
+                "tenants": [{ "tenantId": "acme", "maxAgeDays": 90 }],
+            })
+        );
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "DELETE",
+                "/v1/admin/retention/tenants/acme",
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(retention.tenant("acme").await.is_none());
+
+        let room_uri = format!("/v1/admin/rooms/{room_id}");
+        let response = app
+            .clone()
+            .oneshot(request("bob", "DELETE", &room_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request("admin", "DELETE", &room_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(retention.rooms().await.is_empty());
+    }
+}
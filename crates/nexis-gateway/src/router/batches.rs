@@ -0,0 +1,429 @@
+//! Batch generation.
+//!
+//! `POST /v1/generate/batch` takes up to [`MAX_BATCH_ITEMS`] prompts, answers
+//! `202 Accepted` with a batch id straight away and works through the prompts
+//! in the background, a few at a time. `GET /v1/generate/batch/:id` reports
+//! each prompt's reply or error as they come in, so bulk summarisation or
+//! classification jobs need no orchestration of their own. Every prompt
+//! counts toward the caller's AI spending like a single request would, and
+//! once the budget runs out the remaining prompts fail. Batches are kept in
+//! memory, up to [`RETAINED_BATCHES`] of them.
+
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use nexis_runtime::GenerateRequest;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{
+    caller_tenant, error_codes, generate_ai_reply, record_operation_error,
+    record_operation_success, settings, ErrorResponse, SharedState,
+};
+use crate::auth::AuthenticatedUser;
+
+/// Most prompts one batch may carry.
+const MAX_BATCH_ITEMS: usize = 100;
+/// Prompts of one batch generated at the same time, unless asked otherwise.
+const DEFAULT_CONCURRENCY: usize = 4;
+const MAX_CONCURRENCY: usize = 16;
+/// Batches kept for polling; the oldest finished ones are dropped first.
+const RETAINED_BATCHES: usize = 1_000;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/generate/batch", post(create_batch))
+        .route("/v1/generate/batch/:id", get(get_batch))
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct BatchItemRequest {
+    prompt: String,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+    #[serde(default)]
+    temperature: Option<f32>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct CreateBatchRequest {
+    requests: Vec<BatchItemRequest>,
+    /// Provider to ask instead of the default one.
+    #[serde(default)]
+    provider: Option<String>,
+    /// Prompts generated at the same time; 4 by default, at most 16.
+    #[serde(default)]
+    concurrency: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum BatchStatus {
+    Running,
+    Completed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum ItemStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+/// One prompt of a batch and, once generated, its reply or error.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct BatchItem {
+    index: usize,
+    status: ItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Batch {
+    batch_id: String,
+    #[serde(skip)]
+    member_id: String,
+    provider: String,
+    status: BatchStatus,
+    total: usize,
+    succeeded: usize,
+    failed: usize,
+    items: Vec<BatchItem>,
+    created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl Batch {
+    fn finish_item(&mut self, index: usize, item: BatchItem) {
+        match item.status {
+            ItemStatus::Succeeded => self.succeeded += 1,
+            ItemStatus::Failed => self.failed += 1,
+            ItemStatus::Pending => {}
+        }
+        self.items[index] = item;
+        if self.succeeded + self.failed == self.total {
+            self.status = BatchStatus::Completed;
+            self.completed_at = Some(Utc::now());
+        }
+    }
+}
+
+fn bad_request(message: impl Into<String>) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::bad_request(message)),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/generate/batch",
+    tag = "ai",
+    summary = "Generate replies to many prompts in the background",
+    request_body = CreateBatchRequest,
+    responses(
+        (status = 202, description = "Batch accepted; poll it by id", body = Batch),
+        (status = 400, description = "Invalid batch, provider or model", body = ErrorResponse),
+        (status = 503, description = "No AI provider is configured", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.create_generation_batch", skip(state, user, payload))]
+async fn create_batch(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Json(payload): Json<CreateBatchRequest>,
+) -> Response {
+    let started = Instant::now();
+    let operation = "create_generation_batch";
+    if state.ai_provider.is_none() {
+        record_operation_error(operation, "unavailable", started);
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: "AI provider not configured".to_string(),
+                code: Some(error_codes::AI_UNAVAILABLE),
+            }),
+        )
+            .into_response();
+    }
+    if payload.requests.is_empty() || payload.requests.len() > MAX_BATCH_ITEMS {
+        record_operation_error(operation, "validation", started);
+        return bad_request(format!(
+            "a batch needs between 1 and {MAX_BATCH_ITEMS} requests"
+        ));
+    }
+    if let Some(index) = payload
+        .requests
+        .iter()
+        .position(|item| item.prompt.trim().is_empty())
+    {
+        record_operation_error(operation, "validation", started);
+        return bad_request(format!("requests[{index}].prompt is required"));
+    }
+    let room_settings = settings::RoomSettings::default();
+    if let Some(model) = payload.requests.iter().find_map(|item| {
+        item.model.as_deref().filter(|model| {
+            !settings::model_allowed(&state, &room_settings, caller_tenant(&user), model)
+        })
+    }) {
+        record_operation_error(operation, "validation", started);
+        return bad_request(format!("model {model} is not allowed"));
+    }
+    let concurrency = payload.concurrency.unwrap_or(DEFAULT_CONCURRENCY);
+    if !(1..=MAX_CONCURRENCY).contains(&concurrency) {
+        record_operation_error(operation, "validation", started);
+        return bad_request(format!(
+            "concurrency must be between 1 and {MAX_CONCURRENCY}"
+        ));
+    }
+    let Some(provider) = state.ai_provider_named(payload.provider.as_deref()) else {
+        record_operation_error(operation, "validation", started);
+        return bad_request(format!(
+            "unknown AI provider: {}",
+            payload.provider.unwrap_or_default()
+        ));
+    };
+
+    let batch_id = format!("batch_{}", uuid::Uuid::new_v4().simple());
+    let total = payload.requests.len();
+    let batch = Batch {
+        batch_id: batch_id.clone(),
+        member_id: user.member_id.clone(),
+        provider: provider.name().to_string(),
+        status: BatchStatus::Running,
+        total,
+        succeeded: 0,
+        failed: 0,
+        items: (0..total)
+            .map(|index| BatchItem {
+                index,
+                status: ItemStatus::Pending,
+                content: None,
+                model: None,
+                error: None,
+            })
+            .collect(),
+        created_at: Utc::now(),
+        completed_at: None,
+    };
+    {
+        let mut batches = state.generation_batches.write().await;
+        evict_finished(&mut batches);
+        batches.insert(batch_id.clone(), batch.clone());
+    }
+
+    let worker_state = state.clone();
+    tokio::spawn(async move {
+        let state = worker_state;
+        futures::stream::iter(payload.requests.into_iter().enumerate())
+            .for_each_concurrent(concurrency, |(index, item)| {
+                let (state, user, provider, batch_id) = (&state, &user, &provider, &batch_id);
+                async move {
+                    let request = GenerateRequest {
+                        prompt: item.prompt,
+                        model: item.model.clone(),
+                        max_tokens: item.max_tokens,
+                        temperature: item.temperature,
+                        metadata: Some(serde_json::json!({ "batchId": batch_id })),
+                        images: Vec::new(),
+                    };
+                    let item_started = Instant::now();
+                    let result = generate_ai_reply(
+                        state,
+                        user,
+                        provider.as_ref(),
+                        request,
+                        "generation_batch_item",
+                        item_started,
+                    )
+                    .await;
+                    let item = match result {
+                        Ok(generated) => {
+                            record_operation_success("generation_batch_item", item_started);
+                            BatchItem {
+                                index,
+                                status: ItemStatus::Succeeded,
+                                content: Some(generated.content),
+                                model: generated.model.or(item.model),
+                                error: None,
+                            }
+                        }
+                        Err(response) => BatchItem {
+                            index,
+                            status: ItemStatus::Failed,
+                            content: None,
+                            model: item.model,
+                            error: Some(item_error(response.status())),
+                        },
+                    };
+                    if let Some(batch) = state.generation_batches.write().await.get_mut(batch_id) {
+                        batch.finish_item(index, item);
+                    }
+                }
+            })
+            .await;
+    });
+
+    record_operation_success(operation, started);
+    (StatusCode::ACCEPTED, Json(batch)).into_response()
+}
+
+/// Why an item failed, from the response a single request would have got.
+fn item_error(status: StatusCode) -> String {
+    match status {
+        StatusCode::PAYMENT_REQUIRED => "monthly AI budget exhausted".to_string(),
+        _ => "AI provider request failed".to_string(),
+    }
+}
+
+/// Make room for one more batch by dropping the oldest finished ones.
+fn evict_finished(batches: &mut std::collections::HashMap<String, Batch>) {
+    while batches.len() >= RETAINED_BATCHES {
+        let oldest = batches
+            .values()
+            .filter(|batch| batch.status == BatchStatus::Completed)
+            .min_by_key(|batch| batch.created_at)
+            .map(|batch| batch.batch_id.clone());
+        match oldest {
+            Some(id) => batches.remove(&id),
+            None => break,
+        };
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/generate/batch/{id}",
+    tag = "ai",
+    summary = "Poll a generation batch",
+    params(("id" = String, Path, description = "Batch id")),
+    responses(
+        (status = 200, description = "The batch and every reply so far", body = Batch),
+        (status = 404, description = "Batch not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.get_generation_batch",
+    skip(state, user),
+    fields(batch_id = %id)
+)]
+async fn get_batch(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    let batches = state.generation_batches.read().await;
+    match batches.get(&id) {
+        Some(batch)
+            if batch.member_id == user.member_id || state.config.auth.is_admin(&user.member_id) =>
+        {
+            (StatusCode::OK, Json(batch.clone())).into_response()
+        }
+        _ => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("batch not found")),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn generation_batches_run_in_the_background_and_can_be_polled() {
+        let app = routes(AppState::default().with_ai_provider(Arc::new(EchoProvider)));
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "POST",
+                "/v1/generate/batch",
+                json!({ "requests": [{ "prompt": "  " }] }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "POST",
+                "/v1/generate/batch",
+                json!({
+                    "requests": [
+                        { "prompt": "summarise one" },
+                        { "prompt": "summarise two", "maxTokens": 20 },
+                        { "prompt": "summarise three" }
+                    ],
+                    "concurrency": 2
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let accepted = json_body(response).await;
+        assert_eq!(accepted["total"], 3);
+        assert_eq!(accepted["provider"], "echo");
+        let batch_uri = format!(
+            "/v1/generate/batch/{}",
+            accepted["batchId"].as_str().unwrap()
+        );
+
+        let mut batch = Value::Null;
+        for _ in 0..50 {
+            let response = app
+                .clone()
+                .oneshot(request("alice", "GET", &batch_uri, json!({})))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            batch = json_body(response).await;
+            if batch["status"] == "completed" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(batch["status"], "completed", "{batch}");
+        assert_eq!(batch["succeeded"], 3);
+        assert_eq!(batch["items"][1]["status"], "succeeded");
+        assert_eq!(batch["items"][1]["content"], "summarise two");
+
+        let response = app
+            .clone()
+            .oneshot(request("bob", "GET", &batch_uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,549 @@
+//! Regenerated AI replies.
+//!
+//! Every reply `POST /v1/rooms/:id/ai` posts remembers the prompt, provider
+//! and model it was generated with. `POST /v1/messages/:id/regenerate` asks
+//! again, with the room history that preceded the first reply, and posts the
+//! answer as its sibling: it replies to the same message and carries a
+//! `branch` naming the first reply and its place among the generations.
+//! `GET /v1/messages/:id/generations` lists them in order. Generations are
+//! kept in memory and, like replies, are not restored from storage.
+
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use nexis_context::Message as ContextMessage;
+use nexis_runtime::GenerateRequest;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{
+    context_message, error_codes, find_visible_message, generate_ai_reply, post_ai_message,
+    record_operation_error, record_operation_success, sanctions, ErrorResponse, SharedState,
+    StoredMessage, AI_MEMBER_ID,
+};
+use crate::auth::AuthenticatedUser;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/messages/:id/regenerate", post(regenerate_message))
+        .route("/v1/messages/:id/generations", get(list_generations))
+}
+
+/// Place of a regenerated AI reply among the generations of the same reply.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct MessageBranch {
+    /// Id of the first generation.
+    origin_id: String,
+    /// Position among the generations; the first one is 0.
+    index: usize,
+}
+
+/// What an AI reply was generated from, and the generations so far.
+#[derive(Debug, Clone)]
+pub(super) struct Generation {
+    room_id: String,
+    prompt: String,
+    provider: String,
+    model: Option<String>,
+    max_tokens: Option<u32>,
+    /// Ids of the generations in order, the first one first.
+    message_ids: Vec<String>,
+}
+
+impl Generation {
+    pub(super) fn new(
+        room_id: &str,
+        message_id: &str,
+        prompt: String,
+        provider: &str,
+        model: Option<String>,
+        max_tokens: Option<u32>,
+    ) -> Self {
+        Self {
+            room_id: room_id.to_string(),
+            prompt,
+            provider: provider.to_string(),
+            model,
+            max_tokens,
+            message_ids: vec![message_id.to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RegenerateResponse {
+    message_id: String,
+    content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    branch: MessageBranch,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct GenerationsResponse {
+    origin_id: String,
+    /// Every generation still in the room, the first one first.
+    messages: Vec<StoredMessage>,
+}
+
+/// Remember the AI reply `generation` was generated for.
+pub(super) async fn record(state: &SharedState, generation: Generation) {
+    state
+        .generations
+        .write()
+        .await
+        .insert(generation.message_ids[0].clone(), generation);
+}
+
+/// Forget the generations of a deleted room.
+pub(super) async fn remove_room(state: &SharedState, room_id: &str) {
+    state
+        .generations
+        .write()
+        .await
+        .retain(|_, generation| generation.room_id != room_id);
+}
+
+/// Prompt and model `message` was generated from, when the gateway
+/// generated it.
+pub(super) async fn generation_inputs(
+    state: &SharedState,
+    message: &StoredMessage,
+) -> Option<(String, Option<String>)> {
+    state
+        .generations
+        .read()
+        .await
+        .get(origin_id(message))
+        .map(|generation| (generation.prompt.clone(), generation.model.clone()))
+}
+
+fn origin_id(message: &StoredMessage) -> &str {
+    message
+        .branch
+        .as_ref()
+        .map_or(&message.id, |branch| &branch.origin_id)
+}
+
+fn message_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found("message not found")),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/messages/{id}/regenerate",
+    tag = "ai",
+    summary = "Generate an alternative to an AI reply and post it as a sibling",
+    params(("id" = String, Path, description = "Id of the AI reply or of one of its generations")),
+    responses(
+        (status = 200, description = "Alternative generated and posted to the room", body = RegenerateResponse),
+        (status = 400, description = "The message is not a regenerable AI reply", body = ErrorResponse),
+        (status = 402, description = "Monthly AI budget exhausted", body = ErrorResponse),
+        (status = 403, description = "The caller is banned from or muted in the room", body = ErrorResponse),
+        (status = 404, description = "Message not found", body = ErrorResponse),
+        (status = 502, description = "The AI provider failed", body = ErrorResponse),
+        (status = 503, description = "The reply's AI provider is no longer configured", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.regenerate_message",
+    skip(state, user),
+    fields(message_id = %id)
+)]
+async fn regenerate_message(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    let started = Instant::now();
+    let operation = "regenerate_message";
+    let Some((room_id, message)) = find_visible_message(&state, &user, &id).await else {
+        record_operation_error(operation, "not_found", started);
+        return message_not_found();
+    };
+    // The alternative is posted on the caller's behalf.
+    if let Err(response) = sanctions::check_can_send(&state, &room_id, &user.member_id).await {
+        record_operation_error(operation, "sanctioned", started);
+        return response;
+    }
+    let origin_id = origin_id(&message).to_string();
+    let Some(generation) = state.generations.read().await.get(&origin_id).cloned() else {
+        record_operation_error(operation, "validation", started);
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(
+                "only AI replies generated by the gateway can be regenerated",
+            )),
+        )
+            .into_response();
+    };
+    let Some(provider) = state.ai_provider_named(Some(&generation.provider)) else {
+        record_operation_error(operation, "unavailable", started);
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: format!("AI provider {} not configured", generation.provider),
+                code: Some(error_codes::AI_UNAVAILABLE),
+            }),
+        )
+            .into_response();
+    };
+
+    // The conversation as it stood when the first reply was generated.
+    let history: Vec<ContextMessage> = state
+        .room_messages
+        .read()
+        .await
+        .get(&room_id)
+        .map(|messages| {
+            messages
+                .iter()
+                .take_while(|message| message.id != origin_id)
+                .filter(|message| message.branch.is_none())
+                .map(context_message)
+                .collect()
+        })
+        .unwrap_or_default();
+    let assembled = state
+        .prompt_assembler
+        .assemble(None, history, &generation.prompt);
+    let request = GenerateRequest {
+        prompt: assembled.prompt,
+        model: generation.model.clone(),
+        max_tokens: generation.max_tokens,
+        temperature: None,
+        metadata: Some(serde_json::json!({ "roomId": room_id.clone() })),
+        images: Vec::new(),
+    };
+    let generated = match generate_ai_reply(
+        &state,
+        &user,
+        provider.as_ref(),
+        request,
+        operation,
+        started,
+    )
+    .await
+    {
+        Ok(generated) => generated,
+        Err(response) => return response,
+    };
+
+    let mut reply = StoredMessage {
+        reply_to: message.reply_to,
+        ..StoredMessage::new(AI_MEMBER_ID, generated.content.clone())
+    };
+    let index = {
+        let mut generations = state.generations.write().await;
+        let Some(generation) = generations.get_mut(&origin_id) else {
+            // The room was deleted meanwhile.
+            record_operation_error(operation, "not_found", started);
+            return message_not_found();
+        };
+        generation.message_ids.push(reply.id.clone());
+        generation.message_ids.len() - 1
+    };
+    let branch = MessageBranch {
+        origin_id: origin_id.clone(),
+        index,
+    };
+    reply.branch = Some(branch.clone());
+    let response = RegenerateResponse {
+        message_id: reply.id.clone(),
+        content: generated.content,
+        model: generated.model,
+        branch,
+    };
+    let reply_id = reply.id.clone();
+    if let Err(response) = post_ai_message(&state, &user, room_id, reply, operation, started).await
+    {
+        if let Some(generation) = state.generations.write().await.get_mut(&origin_id) {
+            generation.message_ids.retain(|id| id != &reply_id);
+        }
+        return response;
+    }
+    record_operation_success(operation, started);
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/messages/{id}/generations",
+    tag = "ai",
+    summary = "List the alternative generations of an AI reply",
+    params(("id" = String, Path, description = "Id of the AI reply or of one of its generations")),
+    responses(
+        (status = 200, description = "Generations in order, the first one first", body = GenerationsResponse),
+        (status = 404, description = "Message not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.list_generations",
+    skip(state, user),
+    fields(message_id = %id)
+)]
+async fn list_generations(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    let Some((room_id, message)) = find_visible_message(&state, &user, &id).await else {
+        return message_not_found();
+    };
+    let origin_id = origin_id(&message).to_string();
+    let message_ids = state
+        .generations
+        .read()
+        .await
+        .get(&origin_id)
+        .map(|generation| generation.message_ids.clone())
+        .unwrap_or_else(|| vec![message.id.clone()]);
+    let messages = state
+        .room_messages
+        .read()
+        .await
+        .get(&room_id)
+        .map(|messages| {
+            message_ids
+                .iter()
+                .filter_map(|id| messages.iter().find(|message| &message.id == id))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    (
+        StatusCode::OK,
+        Json(GenerationsResponse {
+            origin_id,
+            messages,
+        }),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn regenerated_ai_replies_are_siblings_with_branch_metadata() {
+        let app = routes(AppState::default().with_ai_provider(Arc::new(EchoProvider)));
+
+        let room_id = new_room(&app, "nexis:human:alice", "branches").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &format!("/v1/rooms/{room_id}/ai"),
+                json!({ "prompt": "name a colour" }),
+            ))
+            .await
+            .unwrap();
+        let origin_id = json_body(response).await["messageId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": "too late" }),
+            ))
+            .await
+            .unwrap();
+        let human_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &format!("/v1/messages/{origin_id}/regenerate"),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let first = json_body(response).await;
+        assert_eq!(
+            first["branch"],
+            json!({ "originId": origin_id, "index": 1 })
+        );
+        let content = first["content"].as_str().unwrap();
+        assert!(content.contains("name a colour"));
+        assert!(!content.contains("too late"), "{content}");
+
+        // Regenerating a regeneration adds another sibling of the first reply.
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &format!(
+                    "/v1/messages/{}/regenerate",
+                    first["messageId"].as_str().unwrap()
+                ),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["branch"]["index"], 2);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "GET",
+                &format!(
+                    "/v1/messages/{}/generations",
+                    first["messageId"].as_str().unwrap()
+                ),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        let generations = json_body(response).await;
+        assert_eq!(generations["originId"], origin_id.as_str());
+        let messages = generations["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["id"], origin_id.as_str());
+        assert!(messages[0].get("branch").is_none());
+        assert_eq!(messages[1]["id"], first["messageId"]);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &format!("/v1/messages/{human_id}/regenerate"),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                "/v1/messages/msg_missing/regenerate",
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn only_members_of_the_room_regenerate_its_replies() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(
+            AppState {
+                config: Arc::new(config),
+                ..AppState::default()
+            }
+            .with_ai_provider(Arc::new(EchoProvider)),
+        );
+        let room_id = invite_only_room(&app, "admin", "branches").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &format!("/v1/rooms/{room_id}/ai"),
+                json!({ "prompt": "name a colour" }),
+            ))
+            .await
+            .unwrap();
+        let origin_id = json_body(response).await["messageId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        for (method, uri) in [
+            ("POST", format!("/v1/messages/{origin_id}/regenerate")),
+            ("GET", format!("/v1/messages/{origin_id}/generations")),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request("alice", method, &uri, json!({})))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn muted_members_cannot_regenerate_replies() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(
+            AppState {
+                config: Arc::new(config),
+                ..AppState::default()
+            }
+            .with_ai_provider(Arc::new(EchoProvider)),
+        );
+        let room_id = new_room(&app, "admin", "branches").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "POST",
+                &format!("/v1/rooms/{room_id}/ai"),
+                json!({ "prompt": "name a colour" }),
+            ))
+            .await
+            .unwrap();
+        let origin_id = json_body(response).await["messageId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        app.clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &format!("/v1/rooms/{room_id}/members/alice/mute"),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(request(
+                "alice",
+                "POST",
+                &format!("/v1/messages/{origin_id}/regenerate"),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(json_body(response).await["code"], "MEMBER_MUTED");
+    }
+}
@@ -0,0 +1,204 @@
+//! Delivery of room events published by other gateway instances.
+
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+
+use super::{context_message, AppState, RoomEvent};
+use crate::cluster::Envelope;
+
+/// Deliver events from the cluster bus to this instance's WebSocket
+/// subscribers and keep this instance alive in the connection registry until
+/// shutdown. Does nothing in single-node mode.
+pub(super) fn spawn_relay(state: &AppState) {
+    let Some(bus) = state.cluster.clone() else {
+        return;
+    };
+    spawn_heartbeat(state);
+    let mut incoming = bus.subscribe();
+    let state = state.clone();
+    tokio::spawn(async move {
+        let stopped = state.shutdown.triggered();
+        tokio::pin!(stopped);
+        loop {
+            let payload = tokio::select! {
+                _ = &mut stopped => break,
+                received = incoming.recv() => match received {
+                    Ok(payload) => payload,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Cluster relay lagged by {} events", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            };
+            state.receive_remote(&payload).await;
+        }
+    });
+}
+
+/// Refresh this instance's liveness in the connection registry three times
+/// per `cluster.registry_ttl_secs`.
+fn spawn_heartbeat(state: &AppState) {
+    let state = state.clone();
+    let period = state.config.cluster.registry_ttl() / 3;
+    tokio::spawn(async move {
+        let stopped = state.shutdown.triggered();
+        tokio::pin!(stopped);
+        let mut interval = tokio::time::interval(period.max(Duration::from_secs(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = &mut stopped => break,
+                _ = interval.tick() => {
+                    if let Err(err) = state.connections.heartbeat(&state.instance_id).await {
+                        tracing::warn!("Connection registry heartbeat failed: {}", err);
+                    }
+                }
+            }
+        }
+    });
+}
+
+impl AppState {
+    /// Send `event` to the other instances; a no-op in single-node mode.
+    pub(super) fn publish_remote(&self, event: &RoomEvent) {
+        let Some(bus) = &self.cluster else {
+            return;
+        };
+        let envelope = Envelope {
+            origin: self.instance_id.to_string(),
+            event: match serde_json::to_value(event) {
+                Ok(event) => event,
+                Err(err) => {
+                    tracing::warn!("Failed to encode room event for the cluster: {}", err);
+                    return;
+                }
+            },
+        };
+        let payload = match serde_json::to_string(&envelope) {
+            Ok(payload) => payload,
+            Err(err) => {
+                tracing::warn!("Failed to encode room event for the cluster: {}", err);
+                return;
+            }
+        };
+        if let Err(err) = bus.publish(payload) {
+            tracing::warn!("Failed to publish room event to the cluster: {}", err);
+        }
+    }
+
+    /// Apply an event another instance published: cache and index its
+    /// message, then hand it to local subscribers. Webhooks already fired on
+    /// the publishing instance.
+    async fn receive_remote(&self, payload: &str) {
+        let envelope = match serde_json::from_str::<Envelope>(payload) {
+            Ok(envelope) => envelope,
+            Err(err) => {
+                tracing::warn!("Dropped malformed cluster event: {}", err);
+                return;
+            }
+        };
+        if envelope.origin == *self.instance_id {
+            return;
+        }
+        let event = match serde_json::from_value::<RoomEvent>(envelope.event) {
+            Ok(event) => event,
+            Err(err) => {
+                tracing::warn!("Dropped unknown cluster event: {}", err);
+                return;
+            }
+        };
+
+        if let RoomEvent::Message { room_id, message } = &event {
+            // Rooms created elsewhere are only known here after a restart.
+            if let Some(messages) = self.room_messages.write().await.get_mut(room_id) {
+                if !messages.iter().any(|stored| stored.id == message.id) {
+                    messages.push(message.clone());
+                }
+            }
+            self.conversations
+                .append(room_id, &message.id, context_message(message))
+                .await;
+            if self.lexical_index.is_some() {
+                let tenant = self.room_tenant(room_id).await;
+                self.index_message(room_id, tenant.as_deref(), message);
+            }
+        }
+        // No receivers simply means nobody is subscribed right now.
+        let _ = self.room_events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cluster::EventBus;
+    use crate::db::Storage;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn messages_reach_subscribers_on_other_instances() {
+        let storage = Storage::default();
+        let bus: Arc<dyn EventBus> = Arc::new(crate::cluster::InProcessEventBus::new());
+        let instance = |name: &str, storage: Storage| {
+            let mut state = AppState::default().with_storage(storage);
+            state.cluster = Some(bus.clone());
+            state.instance_id = name.into();
+            state
+        };
+
+        let first = routes(instance("gw_a", storage.clone()));
+        let room_id = new_room(&first, "nexis:human:alice", "general").await;
+
+        let restored = Storage::restore(storage.rooms.clone(), storage.messages.clone(), 10)
+            .await
+            .unwrap();
+        let second = instance("gw_b", restored);
+        let mut events = second.room_events.subscribe();
+        spawn_relay(&second);
+        let second = routes(second);
+
+        let response = first
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": "hello" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let event = tokio::time::timeout(std::time::Duration::from_secs(1), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let RoomEvent::Message {
+            room_id: event_room,
+            message,
+        } = event
+        else {
+            panic!("expected a message event");
+        };
+        assert_eq!(event_room, room_id);
+        assert_eq!(message.text, "hello");
+
+        let response = second
+            .oneshot(request(
+                "nexis:human:alice",
+                "GET",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        let room = json_body(response).await;
+        assert_eq!(room["messages"][0]["text"], "hello");
+    }
+}
@@ -0,0 +1,268 @@
+//! AI spending for the current month.
+//!
+//! `GET /v1/costs` reports totals per member and provider to admins;
+//! `GET /v1/members/:id/costs` lets a member check their own spending
+//! against their budget.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use nexis_runtime::CostTotals;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::{require_admin, ErrorResponse, SharedState};
+use crate::auth::AuthenticatedUser;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/costs", get(cost_summary))
+        .route("/v1/members/:id/costs", get(member_costs))
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct Totals {
+    requests: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    cost_usd: f64,
+}
+
+impl From<CostTotals> for Totals {
+    fn from(totals: CostTotals) -> Self {
+        Self {
+            requests: totals.requests,
+            input_tokens: totals.input_tokens,
+            output_tokens: totals.output_tokens,
+            cost_usd: totals.cost_usd,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct MemberCosts {
+    member_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    budget_usd: Option<f64>,
+    #[serde(flatten)]
+    totals: Totals,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ProviderCosts {
+    provider: String,
+    #[serde(flatten)]
+    totals: Totals,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct CostSummaryResponse {
+    period: String,
+    total: Totals,
+    members: Vec<MemberCosts>,
+    providers: Vec<ProviderCosts>,
+    unpriced_requests: u64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/costs",
+    tag = "ai",
+    summary = "AI spending for the current month per member and provider (admin only)",
+    responses(
+        (status = 200, description = "Spending per member and provider", body = CostSummaryResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.cost_summary", skip(state, user), fields(member_id = %user.member_id))]
+async fn cost_summary(State(state): State<SharedState>, user: AuthenticatedUser) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+
+    let summary = state.costs.summary();
+    let members = summary
+        .members
+        .into_iter()
+        .map(|(member_id, totals)| MemberCosts {
+            budget_usd: state.costs.budget_for(&member_id),
+            member_id,
+            totals: totals.into(),
+        })
+        .collect();
+    let providers = summary
+        .providers
+        .into_iter()
+        .map(|(provider, totals)| ProviderCosts {
+            provider,
+            totals: totals.into(),
+        })
+        .collect();
+    (
+        StatusCode::OK,
+        Json(CostSummaryResponse {
+            period: summary.period,
+            total: summary.total.into(),
+            members,
+            providers,
+            unpriced_requests: summary.unpriced_requests,
+        }),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/members/{id}/costs",
+    tag = "ai",
+    summary = "The member's AI token usage, cost and budget for the current month",
+    params(("id" = String, Path, description = "Member id")),
+    responses(
+        (status = 200, description = "The member's spending", body = MemberCosts),
+        (status = 403, description = "Only the member or an admin can read its costs", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.member_costs", skip(state, user), fields(member_id = %id))]
+async fn member_costs(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if user.member_id != id && !state.config.auth.is_admin(&user.member_id) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::forbidden(
+                "members can only view their own costs",
+            )),
+        )
+            .into_response();
+    }
+
+    (
+        StatusCode::OK,
+        Json(MemberCosts {
+            budget_usd: state.costs.budget_for(&id),
+            totals: state.costs.member_totals(&id).into(),
+            member_id: id,
+        }),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn room_ai_records_costs_and_enforces_monthly_budgets() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        config.costs.monthly_budget_usd = Some(1.0);
+        config
+            .costs
+            .member_budgets_usd
+            .insert("bob".to_string(), 10.0);
+        config
+            .costs
+            .prices
+            .insert("echo-model", nexis_runtime::ModelPrice::new(1000.0, 1000.0));
+        let app = routes(
+            AppState {
+                costs: Arc::new(config.costs.tracker()),
+                config: Arc::new(config),
+                ..AppState::default()
+            }
+            .with_ai_provider(Arc::new(EchoProvider)),
+        );
+
+        let room_id = new_room(&app, "alice", "ai").await;
+        let ai_uri = format!("/v1/rooms/{room_id}/ai");
+        let prompt = json!({ "prompt": "summarize" });
+
+        let response = app
+            .clone()
+            .oneshot(request("alice", "POST", &ai_uri, prompt.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .clone()
+            .oneshot(request("alice", "POST", &ai_uri, prompt.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        let rejected = json_body(response).await;
+        assert_eq!(rejected["code"], "BUDGET_EXCEEDED");
+        assert!(rejected["error"]
+            .as_str()
+            .unwrap()
+            .contains("spent $1.50 of $1.00"));
+        let response = app
+            .clone()
+            .oneshot(request("bob", "POST", &ai_uri, prompt))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "GET",
+                "/v1/members/alice/costs",
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let own = json_body(response).await;
+        assert_eq!(own["requests"], 1);
+        assert_eq!(own["inputTokens"], 1000);
+        assert_eq!(own["costUsd"], 1.5);
+        assert_eq!(own["budgetUsd"], 1.0);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "GET",
+                "/v1/members/bob/costs",
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request("alice", "GET", "/v1/costs", Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .oneshot(request("admin", "GET", "/v1/costs", Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let summary = json_body(response).await;
+        assert_eq!(summary["total"]["requests"], 2);
+        assert_eq!(summary["total"]["costUsd"], 3.0);
+        assert_eq!(summary["members"][1]["memberId"], "bob");
+        assert_eq!(summary["members"][1]["budgetUsd"], 10.0);
+        assert_eq!(summary["providers"][0]["provider"], "echo");
+        assert_eq!(summary["providers"][0]["outputTokens"], 1000);
+    }
+}
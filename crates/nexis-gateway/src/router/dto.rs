@@ -0,0 +1,138 @@
+//! Mapping between domain models and the shapes the router serves.
+//!
+//! Rooms and messages reach the router from storage, from room archives and
+//! from handlers; every conversion into or out of the API types lives here so
+//! the field mapping is written once.
+
+use chrono::{DateTime, Utc};
+use nexis_core::archive::ArchivedMessage;
+use uuid::Uuid;
+
+use super::{MessageRecord, Room, RoomRecord, StoredMessage};
+
+impl From<RoomRecord> for Room {
+    fn from(record: RoomRecord) -> Self {
+        Self {
+            id: record.id,
+            name: record.name,
+            topic: record.topic,
+            #[cfg(feature = "multi-tenant")]
+            tenant_id: record.tenant_id,
+        }
+    }
+}
+
+impl Room {
+    /// Storage form of the room, created at `created_at`.
+    pub(super) fn to_record(&self, created_at: DateTime<Utc>) -> RoomRecord {
+        RoomRecord {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            topic: self.topic.clone(),
+            created_at,
+            #[cfg(feature = "multi-tenant")]
+            tenant_id: self.tenant_id.clone(),
+        }
+    }
+}
+
+impl StoredMessage {
+    /// A new unsigned message from `sender` with a fresh id and timestamp.
+    pub(super) fn new(sender: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            id: format!("msg_{}", Uuid::new_v4().simple()),
+            sender: sender.into(),
+            text: text.into(),
+            reply_to: None,
+            created_at: Utc::now(),
+            signature: None,
+            attachments: Vec::new(),
+            metadata: None,
+            moderation: None,
+            deleted: false,
+            branch: None,
+            feedback: None,
+        }
+    }
+
+    /// Drop the content of a message removed by retention, keeping its id,
+    /// sender and time.
+    pub(super) fn tombstone(&mut self) {
+        self.text.clear();
+        self.signature = None;
+        self.attachments.clear();
+        self.metadata = None;
+        self.moderation = None;
+        self.deleted = true;
+    }
+
+    /// Storage form of the message as posted to `room_id`.
+    pub(super) fn to_record(&self, room_id: &str, tenant: Option<&str>) -> MessageRecord {
+        #[cfg(not(feature = "multi-tenant"))]
+        let _ = tenant;
+        MessageRecord {
+            id: self.id.clone(),
+            room_id: room_id.to_string(),
+            sender_id: self.sender.clone(),
+            content: self.text.clone(),
+            created_at: self.created_at,
+            #[cfg(feature = "multi-tenant")]
+            tenant_id: tenant.map(str::to_string),
+        }
+    }
+}
+
+/// Stored records keep the text, sender and time of a message; replies,
+/// signatures, attachments, metadata and moderation labels are not
+/// restored, and tombstones come back as empty messages.
+impl From<MessageRecord> for StoredMessage {
+    fn from(record: MessageRecord) -> Self {
+        Self {
+            id: record.id,
+            sender: record.sender_id,
+            text: record.content,
+            reply_to: None,
+            created_at: record.created_at,
+            signature: None,
+            attachments: Vec::new(),
+            metadata: None,
+            moderation: None,
+            deleted: false,
+            branch: None,
+            feedback: None,
+        }
+    }
+}
+
+/// Archives carry replies but not signatures, attachments, metadata or
+/// moderation labels.
+impl From<&StoredMessage> for ArchivedMessage {
+    fn from(message: &StoredMessage) -> Self {
+        Self {
+            id: message.id.clone(),
+            sender: message.sender.clone(),
+            text: message.text.clone(),
+            reply_to: message.reply_to.clone(),
+            created_at: message.created_at,
+        }
+    }
+}
+
+impl From<ArchivedMessage> for StoredMessage {
+    fn from(message: ArchivedMessage) -> Self {
+        Self {
+            id: message.id,
+            sender: message.sender,
+            text: message.text,
+            reply_to: message.reply_to,
+            created_at: message.created_at,
+            signature: None,
+            attachments: Vec::new(),
+            metadata: None,
+            moderation: None,
+            deleted: false,
+            branch: None,
+            feedback: None,
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! MessagePack content negotiation for high-traffic endpoints.
+//!
+//! `POST /v1/messages` also accepts `Content-Type: application/msgpack`
+//! bodies, and `GET /v1/rooms/:id` and `GET /v1/rooms/:id/messages` answer in
+//! MessagePack when the `Accept` header prefers it. Error responses stay
+//! JSON. WebSocket clients pick their framing with `/ws?encoding=msgpack`.
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::{header, request::Parts, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use nexis_protocol::codec::Encoding;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::ErrorResponse;
+
+/// Encoding the client asked for in its `Accept` header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) struct Accept(pub(super) Encoding);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Accept
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(
+            parts
+                .headers
+                .get(header::ACCEPT)
+                .and_then(|value| value.to_str().ok())
+                .map(Encoding::negotiate)
+                .unwrap_or_default(),
+        ))
+    }
+}
+
+impl Accept {
+    /// `body` with `status`, in the negotiated encoding.
+    pub(super) fn respond<T: Serialize>(self, status: StatusCode, body: &T) -> Response {
+        match self.0 {
+            Encoding::Json => (status, Json(body)).into_response(),
+            encoding => match encoding.encode(body) {
+                Ok(bytes) => (
+                    status,
+                    [(
+                        header::CONTENT_TYPE,
+                        HeaderValue::from_static(encoding.content_type()),
+                    )],
+                    bytes,
+                )
+                    .into_response(),
+                Err(err) => {
+                    tracing::error!("Failed to encode {} response: {}", encoding, err);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ErrorResponse::internal_error()),
+                    )
+                        .into_response()
+                }
+            },
+        }
+    }
+}
+
+/// Request body decoded from JSON or MessagePack, by its `Content-Type`.
+#[derive(Debug, Clone)]
+pub(super) struct Body<T>(pub(super) T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Body<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let encoding = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Encoding::from_media_type);
+        if encoding != Some(Encoding::MessagePack) {
+            return Json::<T>::from_request(req, state)
+                .await
+                .map(|Json(value)| Self(value))
+                .map_err(IntoResponse::into_response);
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Encoding::MessagePack
+            .decode(&bytes)
+            .map(Self)
+            .map_err(|err| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse::bad_request(err.to_string())),
+                )
+                    .into_response()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn message_pack_clients_get_binary_frames_and_bodies() {
+        use crate::auth::JwtConfig;
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+
+        let token = JwtConfig::test_token("nexis:human:alice");
+        let addr = serve(routes(AppState::default())).await;
+        let client = reqwest::Client::new();
+        let created: Value = client
+            .post(format!("http://{addr}/v1/rooms"))
+            .bearer_auth(&token)
+            .json(&json!({ "name": "general" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let room_id = created["id"].as_str().unwrap().to_string();
+
+        // Browsers can't set headers on the handshake, so the token rides
+        // in the query.
+        let (mut ws, _) = connect_async(format!("ws://{addr}/ws?encoding=msgpack&token={token}"))
+            .await
+            .unwrap();
+        let subscribe = Encoding::MessagePack
+            .encode(&json!({ "type": "subscribe", "roomId": room_id.clone() }))
+            .unwrap();
+        ws.send(WsMessage::Binary(subscribe.into())).await.unwrap();
+        async fn next_event<S>(ws: &mut S) -> Value
+        where
+            S: futures::Stream<Item = Result<WsMessage, tokio_tungstenite::tungstenite::Error>>
+                + Unpin,
+        {
+            let frame = tokio::time::timeout(std::time::Duration::from_secs(2), ws.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            match frame {
+                WsMessage::Binary(bytes) => Encoding::MessagePack.decode(&bytes).unwrap(),
+                other => panic!("unexpected frame: {other:?}"),
+            }
+        }
+        assert_eq!(next_event(&mut ws).await["type"], "subscribed");
+
+        let body = Encoding::MessagePack
+            .encode(&json!({
+                "roomId": room_id.clone(),
+                "sender": "nexis:human:alice",
+                "text": "packed",
+            }))
+            .unwrap();
+        let sent = client
+            .post(format!("http://{addr}/v1/messages"))
+            .bearer_auth(&token)
+            .header("content-type", "application/msgpack")
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(sent.status().as_u16(), 201);
+        let event = next_event(&mut ws).await;
+        assert_eq!(event["type"], "message");
+        assert_eq!(event["message"]["text"], "packed");
+
+        let history = client
+            .get(format!("http://{addr}/v1/rooms/{room_id}/messages"))
+            .bearer_auth(&token)
+            .header("accept", "application/msgpack")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(history.headers()["content-type"], "application/msgpack");
+        let page: Value = Encoding::MessagePack
+            .decode(&history.bytes().await.unwrap())
+            .unwrap();
+        assert_eq!(page["messages"][0]["text"], "packed");
+
+        let malformed = client
+            .post(format!("http://{addr}/v1/messages"))
+            .bearer_auth(&token)
+            .header("content-type", "application/msgpack")
+            .body(vec![0xc1])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(malformed.status().as_u16(), 400);
+    }
+}
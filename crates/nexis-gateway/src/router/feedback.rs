@@ -0,0 +1,651 @@
+//! Ratings of AI replies.
+//!
+//! Members rate an AI message thumbs up or down with
+//! `POST /v1/messages/:id/feedback`, optionally with a comment; a member's
+//! later rating replaces their earlier one. Ratings are stored per rater and
+//! summed up in the message's `feedback`. Admins export them with
+//! `GET /v1/feedback/export` as JSONL, one rated reply per line with the
+//! prompt it answered when the gateway generated it, for fine-tuning and
+//! evaluation pipelines.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::{
+    branches, find_visible_message, record_operation_error, record_operation_success,
+    require_admin, storage_error_response, validation, ErrorResponse, SharedState, StoredMessage,
+};
+use crate::auth::AuthenticatedUser;
+use crate::db::{FeedbackRecord, RepositoryError};
+
+/// Longest comment a rating may carry, in characters.
+const MAX_COMMENT_CHARS: usize = 2_000;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route(
+            "/v1/messages/:id/feedback",
+            post(set_feedback).get(get_feedback).delete(delete_feedback),
+        )
+        .route("/v1/feedback/export", get(export_feedback))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum Rating {
+    Up,
+    Down,
+}
+
+impl Rating {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Down => "down",
+        }
+    }
+
+    fn parse(rating: &str) -> Option<Self> {
+        match rating {
+            "up" => Some(Self::Up),
+            "down" => Some(Self::Down),
+            _ => None,
+        }
+    }
+}
+
+/// Ratings of an AI message so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub(super) struct FeedbackSummary {
+    up: u32,
+    down: u32,
+}
+
+impl FeedbackSummary {
+    fn add(&mut self, record: &FeedbackRecord) {
+        match Rating::parse(&record.rating) {
+            Some(Rating::Up) => self.up += 1,
+            Some(Rating::Down) => self.down += 1,
+            None => {}
+        }
+    }
+}
+
+/// Summaries of the messages rated in `records`, by message id.
+pub(super) fn summaries(records: &[FeedbackRecord]) -> HashMap<&str, FeedbackSummary> {
+    let mut summaries = HashMap::<&str, FeedbackSummary>::new();
+    for record in records {
+        summaries
+            .entry(record.message_id.as_str())
+            .or_default()
+            .add(record);
+    }
+    summaries
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct FeedbackRequest {
+    rating: Rating,
+    #[serde(default)]
+    comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct FeedbackEntry {
+    member_id: String,
+    rating: Rating,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct MessageFeedbackResponse {
+    message_id: String,
+    #[serde(flatten)]
+    summary: FeedbackSummary,
+    /// Every rating, oldest first.
+    feedback: Vec<FeedbackEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+struct ExportParams {
+    /// Only ratings of messages in this room.
+    #[serde(default)]
+    room_id: Option<String>,
+    /// Only ratings given or changed at or after this instant.
+    #[serde(default)]
+    since: Option<DateTime<Utc>>,
+}
+
+/// One line of the feedback export.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FeedbackExample {
+    message_id: String,
+    room_id: String,
+    /// Prompt the reply answered, when the gateway generated it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prompt: Option<String>,
+    completion: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    rating: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    member_id: String,
+    rated_at: DateTime<Utc>,
+}
+
+fn message_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found("message not found")),
+    )
+        .into_response()
+}
+
+/// The ratings of a message, after updating the summary it carries.
+async fn message_feedback(
+    state: &SharedState,
+    room_id: &str,
+    message_id: &str,
+) -> Result<MessageFeedbackResponse, RepositoryError> {
+    let records = state.feedback.list_for_message(message_id).await?;
+    let summary = summaries(&records).remove(message_id).unwrap_or_default();
+    if let Some(message) = state
+        .room_messages
+        .write()
+        .await
+        .get_mut(room_id)
+        .and_then(|messages| messages.iter_mut().find(|message| message.id == message_id))
+    {
+        message.feedback = (!records.is_empty()).then_some(summary);
+    }
+    let feedback = records
+        .into_iter()
+        .filter_map(|record| {
+            Some(FeedbackEntry {
+                rating: Rating::parse(&record.rating)?,
+                member_id: record.member_id,
+                comment: record.comment,
+                updated_at: record.updated_at,
+            })
+        })
+        .collect();
+    Ok(MessageFeedbackResponse {
+        message_id: message_id.to_string(),
+        summary,
+        feedback,
+    })
+}
+
+fn is_ai_message(message: &StoredMessage) -> bool {
+    message.sender.starts_with("nexis:ai:")
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/messages/{id}/feedback",
+    tag = "ai",
+    summary = "Rate an AI message thumbs up or down",
+    params(("id" = String, Path, description = "Message id")),
+    request_body = FeedbackRequest,
+    responses(
+        (status = 200, description = "Rating stored; the message's ratings", body = MessageFeedbackResponse),
+        (status = 400, description = "The message was not written by an AI member", body = ErrorResponse),
+        (status = 404, description = "Message not found", body = ErrorResponse),
+        (status = 422, description = "Comment too long"),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.set_feedback",
+    skip(state, user, payload),
+    fields(message_id = %id, member_id = %user.member_id)
+)]
+async fn set_feedback(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(payload): Json<FeedbackRequest>,
+) -> Response {
+    let started = Instant::now();
+    let operation = "set_feedback";
+    let comment = payload
+        .comment
+        .map(|comment| comment.trim().to_string())
+        .filter(|comment| !comment.is_empty());
+    let mut validator = validation::Validator::new();
+    if comment
+        .as_ref()
+        .is_some_and(|comment| comment.chars().count() > MAX_COMMENT_CHARS)
+    {
+        validator.reject(
+            "comment",
+            format!("must be at most {MAX_COMMENT_CHARS} characters"),
+        );
+    }
+    if let Err(response) = validator.finish(Some(())) {
+        record_operation_error(operation, "validation", started);
+        return response;
+    }
+    let Some((room_id, message)) = find_visible_message(&state, &user, &id).await else {
+        record_operation_error(operation, "not_found", started);
+        return message_not_found();
+    };
+    if !is_ai_message(&message) {
+        record_operation_error(operation, "validation", started);
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(
+                "only AI-generated messages can be rated",
+            )),
+        )
+            .into_response();
+    }
+
+    let record = FeedbackRecord {
+        message_id: message.id,
+        room_id,
+        member_id: user.member_id,
+        rating: payload.rating.as_str().to_string(),
+        comment,
+        updated_at: Utc::now(),
+    };
+    if let Err(err) = state.feedback.set(&record).await {
+        record_operation_error(operation, "storage", started);
+        return storage_error_response(err);
+    }
+    match message_feedback(&state, &record.room_id, &record.message_id).await {
+        Ok(response) => {
+            record_operation_success(operation, started);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(err) => {
+            record_operation_error(operation, "storage", started);
+            storage_error_response(err)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/messages/{id}/feedback",
+    tag = "ai",
+    summary = "List the ratings of a message",
+    params(("id" = String, Path, description = "Message id")),
+    responses(
+        (status = 200, description = "The message's ratings", body = MessageFeedbackResponse),
+        (status = 404, description = "Message not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.get_feedback", skip(state, user), fields(message_id = %id))]
+async fn get_feedback(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    let Some((room_id, message)) = find_visible_message(&state, &user, &id).await else {
+        return message_not_found();
+    };
+    match message_feedback(&state, &room_id, &message.id).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(err) => storage_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/messages/{id}/feedback",
+    tag = "ai",
+    summary = "Withdraw the caller's rating of a message",
+    params(("id" = String, Path, description = "Message id")),
+    responses(
+        (status = 204, description = "Rating withdrawn"),
+        (status = 404, description = "Message not found or not rated by the caller", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.delete_feedback",
+    skip(state, user),
+    fields(message_id = %id, member_id = %user.member_id)
+)]
+async fn delete_feedback(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    let Some((room_id, message)) = find_visible_message(&state, &user, &id).await else {
+        return message_not_found();
+    };
+    match state.feedback.delete(&message.id, &user.member_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::not_found("no rating of this message by you")),
+            )
+                .into_response()
+        }
+        Err(err) => return storage_error_response(err),
+    }
+    match message_feedback(&state, &room_id, &message.id).await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => storage_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/feedback/export",
+    tag = "admin",
+    summary = "Export rated AI replies as a JSONL dataset (admin only)",
+    params(ExportParams),
+    responses(
+        (status = 200, description = "One rated reply per line, oldest rating first", body = String, content_type = "application/x-ndjson"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.export_feedback", skip(state, user, params))]
+async fn export_feedback(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Query(params): Query<ExportParams>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    let records = match state.feedback.list().await {
+        Ok(records) => records,
+        Err(err) => return storage_error_response(err),
+    };
+
+    let mut lines = Vec::new();
+    {
+        let rooms = state.rooms.read().await;
+        let messages = state.room_messages.read().await;
+        for record in records {
+            if params
+                .room_id
+                .as_ref()
+                .is_some_and(|room_id| room_id != &record.room_id)
+                || params.since.is_some_and(|since| record.updated_at < since)
+                || !rooms
+                    .get(&record.room_id)
+                    .is_some_and(|room| room.is_visible_to(&user))
+            {
+                continue;
+            }
+            // Replies no longer held, or removed by retention, have nothing
+            // to learn from.
+            let Some(message) = messages.get(&record.room_id).and_then(|messages| {
+                messages
+                    .iter()
+                    .find(|message| message.id == record.message_id && !message.deleted)
+            }) else {
+                continue;
+            };
+            lines.push((record, message.clone()));
+        }
+    }
+
+    let mut body = String::new();
+    for (record, message) in lines {
+        let (prompt, model) = branches::generation_inputs(&state, &message).await.unzip();
+        let example = FeedbackExample {
+            message_id: record.message_id,
+            room_id: record.room_id,
+            prompt,
+            completion: message.text,
+            model: model.flatten(),
+            rating: record.rating,
+            comment: record.comment,
+            member_id: record.member_id,
+            rated_at: record.updated_at,
+        };
+        if let Ok(line) = serde_json::to_string(&example) {
+            body.push_str(&line);
+            body.push('\n');
+        }
+    }
+    (
+        StatusCode::OK,
+        [
+            ("content-type", "application/x-ndjson"),
+            (
+                "content-disposition",
+                "attachment; filename=\"feedback.jsonl\"",
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Forget the ratings of a deleted room.
+pub(super) async fn remove_room(state: &SharedState, room_id: &str) {
+    if let Err(err) = state.feedback.delete_room(room_id).await {
+        tracing::warn!("Failed to drop feedback of room {}: {}", room_id, err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn ai_messages_collect_feedback_and_export_it_as_jsonl() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(
+            AppState {
+                config: Arc::new(config),
+                ..AppState::default()
+            }
+            .with_ai_provider(Arc::new(EchoProvider)),
+        );
+
+        let room_id = new_room(&app, "admin", "rated").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &format!("/v1/rooms/{room_id}/ai"),
+                json!({ "prompt": "summarize" }),
+            ))
+            .await
+            .unwrap();
+        let message_id = json_body(response).await["messageId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let feedback_uri = format!("/v1/messages/{message_id}/feedback");
+
+        for (member, body) in [
+            ("nexis:human:alice", json!({ "rating": "down" })),
+            ("bob", json!({ "rating": "down", "comment": "too short" })),
+            (
+                "nexis:human:alice",
+                json!({ "rating": "up", "comment": "  " }),
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request(member, "POST", &feedback_uri, body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = app
+            .clone()
+            .oneshot(request("bob", "GET", &feedback_uri, json!({})))
+            .await
+            .unwrap();
+        let feedback = json_body(response).await;
+        assert_eq!(
+            (feedback["up"].clone(), feedback["down"].clone()),
+            (json!(1), json!(1))
+        );
+        assert_eq!(feedback["feedback"].as_array().unwrap().len(), 2);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "bob",
+                "GET",
+                &format!("/v1/rooms/{room_id}"),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            json_body(response).await["messages"][0]["feedback"],
+            json!({ "up": 1, "down": 1 })
+        );
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", "/v1/feedback/export", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let lines: Vec<Value> = text_body(response)
+            .await
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["memberId"], "bob");
+        assert_eq!(lines[0]["comment"], "too short");
+        assert_eq!(lines[0]["prompt"], "summarize");
+        assert!(lines[0]["completion"]
+            .as_str()
+            .unwrap()
+            .contains("summarize"));
+        assert_eq!(lines[1]["rating"], "up");
+        assert!(lines[1].get("comment").is_none());
+        let response = app
+            .clone()
+            .oneshot(request("bob", "GET", "/v1/feedback/export", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(request("bob", "DELETE", &feedback_uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app
+            .clone()
+            .oneshot(request("bob", "DELETE", &feedback_uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": "hi" }),
+            ))
+            .await
+            .unwrap();
+        let human_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let response = app
+            .clone()
+            .oneshot(request(
+                "bob",
+                "POST",
+                &format!("/v1/messages/{human_id}/feedback"),
+                json!({ "rating": "up" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .oneshot(request(
+                "bob",
+                "POST",
+                &feedback_uri,
+                json!({ "rating": "up", "comment": "x".repeat(2_001) }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn only_members_of_the_room_rate_its_messages() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(
+            AppState {
+                config: Arc::new(config),
+                ..AppState::default()
+            }
+            .with_ai_provider(Arc::new(EchoProvider)),
+        );
+        let room_id = invite_only_room(&app, "admin", "rated").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &format!("/v1/rooms/{room_id}/ai"),
+                json!({ "prompt": "summarize" }),
+            ))
+            .await
+            .unwrap();
+        let message_id = json_body(response).await["messageId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let feedback_uri = format!("/v1/messages/{message_id}/feedback");
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "POST",
+                &feedback_uri,
+                json!({ "rating": "up" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app
+            .oneshot(request("alice", "GET", &feedback_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,372 @@
+//! Searching and replaying logged AI generations.
+//!
+//! With `[generation_log] enabled` on, admins page through recent prompts
+//! and responses with `GET /v1/generations` and re-run one against another
+//! provider or model with `POST /v1/generations/:id/replay` to compare the
+//! outputs. Replays send the logged, redacted prompt, are not posted to any
+//! room and count toward the caller's AI spending; they are logged too, with
+//! `replayOf` naming the entry they replayed.
+
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use nexis_runtime::GenerateRequest;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{
+    error_codes, generate_ai_reply, record_operation_error, record_operation_success,
+    require_admin, ErrorResponse, SharedState,
+};
+use crate::auth::AuthenticatedUser;
+use crate::generation_log::{GenerationLog, GenerationLogEntry, GenerationLogQuery};
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/generations", get(list_generations))
+        .route("/v1/generations/:id", get(get_generation))
+        .route("/v1/generations/:id/replay", post(replay_generation))
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct ReplayRequest {
+    /// Provider to ask instead of the one the generation was made with.
+    #[serde(default)]
+    provider: Option<String>,
+    /// Model to ask instead of the one the generation requested.
+    #[serde(default)]
+    model: Option<String>,
+}
+
+/// What one provider made of a logged prompt.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ReplayOutput {
+    provider: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ReplayResponse {
+    generation_id: String,
+    original: ReplayOutput,
+    replay: ReplayOutput,
+}
+
+/// The generation log, or the response to send when it is disabled.
+#[allow(clippy::result_large_err)]
+fn enabled_log(state: &SharedState) -> Result<&GenerationLog, Response> {
+    state.generation_log.as_ref().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::service_unavailable(
+                "generation log is not enabled",
+            )),
+        )
+            .into_response()
+    })
+}
+
+async fn find_generation(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    id: &str,
+) -> Result<GenerationLogEntry, Response> {
+    require_admin(state, user).await?;
+    enabled_log(state)?.get(id).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("generation not found")),
+        )
+            .into_response()
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/generations",
+    tag = "ai",
+    summary = "Search recently logged AI generations, newest first (admin only)",
+    params(GenerationLogQuery),
+    responses(
+        (status = 200, description = "A page of logged generations", body = crate::generation_log::GenerationLogPage),
+        (status = 403, description = "Caller is not an admin", body = ErrorResponse),
+        (status = 503, description = "The generation log is not enabled", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.list_generation_log", skip(state, user, query))]
+async fn list_generations(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Query(query): Query<GenerationLogQuery>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    match enabled_log(&state) {
+        Ok(log) => (StatusCode::OK, Json(log.query(&query).await)).into_response(),
+        Err(response) => response,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/generations/{id}",
+    tag = "ai",
+    summary = "Get a logged AI generation (admin only)",
+    params(("id" = String, Path, description = "Generation id")),
+    responses(
+        (status = 200, description = "The logged generation", body = GenerationLogEntry),
+        (status = 403, description = "Caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Generation not found", body = ErrorResponse),
+        (status = 503, description = "The generation log is not enabled", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.get_generation",
+    skip(state, user),
+    fields(generation_id = %id)
+)]
+async fn get_generation(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    match find_generation(&state, &user, &id).await {
+        Ok(entry) => (StatusCode::OK, Json(entry)).into_response(),
+        Err(response) => response,
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/generations/{id}/replay",
+    tag = "ai",
+    summary = "Re-run a logged AI generation and compare the outputs (admin only)",
+    params(("id" = String, Path, description = "Generation id")),
+    request_body = ReplayRequest,
+    responses(
+        (status = 200, description = "The original and the replayed output", body = ReplayResponse),
+        (status = 402, description = "Monthly AI budget exhausted", body = ErrorResponse),
+        (status = 403, description = "Caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Generation not found", body = ErrorResponse),
+        (status = 502, description = "The AI provider failed", body = ErrorResponse),
+        (status = 503, description = "The generation log or the AI provider is not available", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.replay_generation",
+    skip(state, user, payload),
+    fields(generation_id = %id)
+)]
+async fn replay_generation(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    payload: Option<Json<ReplayRequest>>,
+) -> Response {
+    let started = Instant::now();
+    let operation = "replay_generation";
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let entry = match find_generation(&state, &user, &id).await {
+        Ok(entry) => entry,
+        Err(response) => {
+            record_operation_error(operation, "not_found", started);
+            return response;
+        }
+    };
+    let provider_name = payload.provider.unwrap_or_else(|| entry.provider.clone());
+    let Some(provider) = state.ai_provider_named(Some(&provider_name)) else {
+        record_operation_error(operation, "unavailable", started);
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: format!("AI provider {provider_name} not configured"),
+                code: Some(error_codes::AI_UNAVAILABLE),
+            }),
+        )
+            .into_response();
+    };
+
+    let mut metadata = serde_json::json!({ "replayOf": entry.id });
+    if let Some(room_id) = &entry.room_id {
+        metadata["roomId"] = serde_json::json!(room_id);
+    }
+    let request = GenerateRequest {
+        prompt: entry.request.prompt.clone(),
+        model: payload.model.or_else(|| entry.request.model.clone()),
+        max_tokens: entry.request.max_tokens,
+        temperature: entry.request.temperature,
+        metadata: Some(metadata),
+        images: Vec::new(),
+    };
+    let requested_model = request.model.clone();
+    let generated = match generate_ai_reply(
+        &state,
+        &user,
+        provider.as_ref(),
+        request,
+        operation,
+        started,
+    )
+    .await
+    {
+        Ok(generated) => generated,
+        Err(response) => return response,
+    };
+
+    let original = ReplayOutput {
+        provider: entry.provider,
+        model: entry
+            .response
+            .as_ref()
+            .and_then(|response| response.model.clone())
+            .or(entry.request.model),
+        content: entry.response.map(|response| response.content),
+        error: entry.error,
+    };
+    let replay = ReplayOutput {
+        provider: provider.name().to_string(),
+        model: generated.model.or(requested_model),
+        content: Some(generated.content),
+        error: None,
+    };
+    record_operation_success(operation, started);
+    (
+        StatusCode::OK,
+        Json(ReplayResponse {
+            generation_id: entry.id,
+            original,
+            replay,
+        }),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn generation_log_redacts_prompts_and_replays_them() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        config.generation_log.enabled = true;
+        config.generation_log.redact_patterns = vec![r"secret-\d+".to_string()];
+        let app = routes(
+            AppState {
+                generation_log: GenerationLog::from_config(&config.generation_log).unwrap(),
+                config: Arc::new(config),
+                ..AppState::default()
+            }
+            .with_ai_provider(Arc::new(EchoProvider)),
+        );
+
+        let room_id = new_room(&app, "admin", "logged").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &format!("/v1/rooms/{room_id}/ai"),
+                json!({ "prompt": "use secret-42 to log in" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let list_uri = format!("/v1/generations?roomId={room_id}");
+        let response = app
+            .clone()
+            .oneshot(request("alice", "GET", &list_uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", &list_uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let page = json_body(response).await;
+        assert_eq!(page["total"], 1);
+        let generation = &page["generations"][0];
+        assert_eq!(generation["memberId"], "admin");
+        assert_eq!(generation["provider"], "echo");
+        let prompt = generation["request"]["prompt"].as_str().unwrap();
+        assert!(prompt.contains("use [REDACTED] to log in"), "{prompt}");
+        assert!(!generation.to_string().contains("secret-42"));
+        let generation_id = generation["id"].as_str().unwrap().to_string();
+
+        let replay_uri = format!("/v1/generations/{generation_id}/replay");
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &replay_uri,
+                json!({ "provider": "missing" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &replay_uri,
+                json!({ "model": "echo-large" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let replay = json_body(response).await;
+        assert_eq!(replay["generationId"], generation_id.as_str());
+        assert_eq!(replay["original"]["content"], prompt);
+        assert_eq!(replay["replay"]["provider"], "echo");
+        assert_eq!(replay["replay"]["content"], prompt);
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", &list_uri, json!({})))
+            .await
+            .unwrap();
+        let page = json_body(response).await;
+        assert_eq!(page["total"], 2);
+        assert_eq!(page["generations"][0]["replayOf"], generation_id.as_str());
+        assert_eq!(page["generations"][0]["request"]["model"], "echo-large");
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "GET",
+                "/v1/generations/gen_missing",
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
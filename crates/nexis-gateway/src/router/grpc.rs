@@ -0,0 +1,496 @@
+//! gRPC surface of the control plane (`grpc` feature).
+//!
+//! `nexis.v1.ControlPlane`, defined in `proto/nexis/v1/control_plane.proto`,
+//! is served on the HTTP port alongside the REST API. Each RPC is answered by
+//! the HTTP handler it mirrors, called in-process with the caller's
+//! `authorization` metadata, so validation, permissions, moderation, quotas
+//! and auditing are the same on both surfaces. HTTP errors come back as the
+//! matching gRPC status with the error message as its description.
+
+use std::collections::HashSet;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{metadata::MetadataMap, server::NamedService, Code, Status};
+use tower::ServiceExt;
+
+use super::{RoomEvent, SharedState, StoredMessage};
+use crate::metrics::record_broadcast_lag;
+use nexis_protocol::AttachmentRef;
+
+/// Types generated from `control_plane.proto`, including the client.
+pub mod proto {
+    tonic::include_proto!("nexis.v1");
+}
+
+use proto::control_plane_server::{ControlPlane, ControlPlaneServer};
+
+/// Largest history page the HTTP API hands out.
+const HISTORY_PAGE_SIZE: usize = 500;
+const STREAM_BUFFER: usize = 64;
+
+/// Routes `nexis.v1.ControlPlane` calls to a service answering them with
+/// `http`.
+pub(super) fn routes(state: SharedState, http: Router) -> Router {
+    let server = ControlPlaneServer::new(ControlPlaneService { state, http });
+    Router::new().route_service(
+        &format!("/{}/*rest", ControlPlaneServer::<ControlPlaneService>::NAME),
+        server,
+    )
+}
+
+#[derive(Clone)]
+struct ControlPlaneService {
+    state: SharedState,
+    http: Router,
+}
+
+impl ControlPlaneService {
+    /// Calls the HTTP API as the gRPC caller and decodes a successful JSON
+    /// response.
+    async fn call<T: DeserializeOwned>(
+        &self,
+        metadata: &MetadataMap,
+        method: Method,
+        uri: &str,
+        body: Option<Value>,
+    ) -> Result<T, Status> {
+        call(&self.http, metadata, method, uri, body).await
+    }
+}
+
+async fn call<T: DeserializeOwned>(
+    http: &Router,
+    metadata: &MetadataMap,
+    method: Method,
+    uri: &str,
+    body: Option<Value>,
+) -> Result<T, Status> {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(authorization) = metadata
+        .get(header::AUTHORIZATION.as_str())
+        .and_then(|value| value.to_str().ok())
+    {
+        request = request.header(header::AUTHORIZATION, authorization);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let request = request
+        .body(body)
+        .map_err(|err| Status::invalid_argument(err.to_string()))?;
+
+    let response = match http.clone().oneshot(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .map_err(|err| Status::internal(err.to_string()))?;
+    if !status.is_success() {
+        let message = serde_json::from_slice::<Value>(&bytes)
+            .ok()
+            .and_then(|body| body["error"].as_str().map(str::to_string))
+            .unwrap_or_else(|| status.to_string());
+        return Err(Status::new(status_code(status), message));
+    }
+    serde_json::from_slice(&bytes).map_err(|err| {
+        tracing::error!("Unexpected response from {}: {}", uri, err);
+        Status::internal("unexpected response from the control plane")
+    })
+}
+
+/// gRPC code for an HTTP error status.
+fn status_code(status: StatusCode) -> Code {
+    match status {
+        StatusCode::BAD_REQUEST
+        | StatusCode::PAYLOAD_TOO_LARGE
+        | StatusCode::UNPROCESSABLE_ENTITY => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::CONFLICT => Code::AlreadyExists,
+        StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+        StatusCode::NOT_IMPLEMENTED => Code::Unimplemented,
+        StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        StatusCode::GATEWAY_TIMEOUT => Code::DeadlineExceeded,
+        _ => Code::Internal,
+    }
+}
+
+/// Percent-encodes `value` for use in a path segment or query string.
+fn encode_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryPage {
+    messages: Vec<StoredMessage>,
+    #[serde(default)]
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchHit {
+    id: String,
+    score: f32,
+    content: String,
+    #[serde(default)]
+    room_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResults {
+    query: String,
+    results: Vec<SearchHit>,
+    total: u32,
+}
+
+impl From<StoredMessage> for proto::Message {
+    fn from(message: StoredMessage) -> Self {
+        Self {
+            id: message.id,
+            sender: message.sender,
+            text: message.text,
+            reply_to: message.reply_to,
+            created_at: message.created_at.to_rfc3339(),
+            signature: message.signature,
+            attachments: message.attachments.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<AttachmentRef> for proto::Attachment {
+    fn from(attachment: AttachmentRef) -> Self {
+        Self {
+            id: attachment.id,
+            file_name: attachment.file_name,
+            mime_type: attachment.mime_type,
+            size: attachment.size,
+        }
+    }
+}
+
+/// Sends a room's history, then its live messages, to one gRPC stream.
+struct HistoryStream {
+    http: Router,
+    metadata: MetadataMap,
+    /// History URI without the page cursor.
+    base: String,
+    room_id: String,
+    tx: mpsc::Sender<Result<proto::Message, Status>>,
+}
+
+impl HistoryStream {
+    /// Returns `None` once the client has gone away or the stream ended.
+    async fn forward(
+        self,
+        mut page: HistoryPage,
+        events: Option<broadcast::Receiver<RoomEvent>>,
+    ) -> Option<()> {
+        // Messages sent while paging show up in both history and events.
+        let mut sent = HashSet::new();
+        loop {
+            for message in page.messages {
+                if events.is_some() {
+                    sent.insert(message.id.clone());
+                }
+                self.tx.send(Ok(message.into())).await.ok()?;
+            }
+            let Some(cursor) = page.next_cursor else {
+                break;
+            };
+            let uri = format!("{}&after={}", self.base, encode_component(&cursor));
+            match call(&self.http, &self.metadata, Method::GET, &uri, None).await {
+                Ok(next) => page = next,
+                Err(status) => {
+                    let _ = self.tx.send(Err(status)).await;
+                    return None;
+                }
+            }
+        }
+
+        let mut events = events?;
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("gRPC history stream lagged by {} events", skipped);
+                    record_broadcast_lag(skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            };
+            let RoomEvent::Message { room_id, message } = event else {
+                continue;
+            };
+            if room_id != self.room_id || sent.remove(&message.id) {
+                continue;
+            }
+            self.tx.send(Ok(message.into())).await.ok()?;
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl ControlPlane for ControlPlaneService {
+    async fn create_room(
+        &self,
+        request: tonic::Request<proto::CreateRoomRequest>,
+    ) -> Result<tonic::Response<proto::Room>, Status> {
+        let (metadata, _, payload) = request.into_parts();
+        let mut body = json!({ "name": payload.name, "topic": payload.topic });
+        if let Some(tenant_id) = payload.tenant_id {
+            body["tenant_id"] = Value::String(tenant_id);
+        }
+        let room: Value = self
+            .call(&metadata, Method::POST, "/v1/rooms", Some(body))
+            .await?;
+        Ok(tonic::Response::new(proto::Room {
+            id: room["id"].as_str().unwrap_or_default().to_string(),
+            name: room["name"].as_str().unwrap_or_default().to_string(),
+        }))
+    }
+
+    async fn send_message(
+        &self,
+        request: tonic::Request<proto::SendMessageRequest>,
+    ) -> Result<tonic::Response<proto::SendMessageResponse>, Status> {
+        let (metadata, _, payload) = request.into_parts();
+        let body = json!({
+            "roomId": payload.room_id,
+            "sender": payload.sender,
+            "text": payload.text,
+            "replyTo": payload.reply_to,
+            "id": payload.id,
+            "createdAt": payload.created_at,
+            "signature": payload.signature,
+            "attachments": payload.attachments,
+        });
+        let sent: Value = self
+            .call(&metadata, Method::POST, "/v1/messages", Some(body))
+            .await?;
+        Ok(tonic::Response::new(proto::SendMessageResponse {
+            id: sent["id"].as_str().unwrap_or_default().to_string(),
+        }))
+    }
+
+    type StreamRoomHistoryStream = ReceiverStream<Result<proto::Message, Status>>;
+
+    async fn stream_room_history(
+        &self,
+        request: tonic::Request<proto::StreamRoomHistoryRequest>,
+    ) -> Result<tonic::Response<Self::StreamRoomHistoryStream>, Status> {
+        let (metadata, _, payload) = request.into_parts();
+        let room = encode_component(&payload.room_id);
+        let mut base = format!("/v1/rooms/{room}/messages?limit={HISTORY_PAGE_SIZE}");
+        for (name, value) in [("since", &payload.since), ("until", &payload.until)] {
+            if let Some(value) = value {
+                base.push_str(&format!("&{name}={}", encode_component(value)));
+            }
+        }
+
+        // Subscribe before reading history so nothing sent meanwhile is missed,
+        // and fetch the first page up front so a bad request fails the call.
+        let events = payload.follow.then(|| self.state.room_events.subscribe());
+        let first: HistoryPage = self.call(&metadata, Method::GET, &base, None).await?;
+
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        let history = HistoryStream {
+            http: self.http.clone(),
+            metadata,
+            base,
+            room_id: payload.room_id,
+            tx,
+        };
+        let shutdown = self.state.shutdown.triggered();
+        tokio::spawn(async move {
+            let closed = history.tx.clone();
+            tokio::select! {
+                _ = history.forward(first, events) => {}
+                _ = closed.closed() => {}
+                _ = shutdown => {}
+            }
+        });
+        Ok(tonic::Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn search(
+        &self,
+        request: tonic::Request<proto::SearchRequest>,
+    ) -> Result<tonic::Response<proto::SearchResponse>, Status> {
+        let (metadata, _, payload) = request.into_parts();
+        let mut body = json!({
+            "query": payload.query,
+            "min_score": payload.min_score,
+            "room_id": payload.room_id,
+            "content_type": payload.content_type,
+        });
+        if let Some(limit) = payload.limit {
+            body["limit"] = json!(limit);
+        }
+        let results: SearchResults = self
+            .call(&metadata, Method::POST, "/v1/search", Some(body))
+            .await?;
+        Ok(tonic::Response::new(proto::SearchResponse {
+            query: results.query,
+            results: results
+                .results
+                .into_iter()
+                .map(|hit| proto::SearchHit {
+                    id: hit.id,
+                    score: hit.score,
+                    content: hit.content,
+                    room_id: hit.room_id,
+                })
+                .collect(),
+            total: results.total,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tonic::transport::Channel;
+
+    use super::proto::control_plane_client::ControlPlaneClient;
+    use super::*;
+    use crate::auth::JwtConfig;
+    use crate::router::build_routes;
+
+    async fn client() -> ControlPlaneClient<Channel> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, build_routes()).await.unwrap();
+        });
+        ControlPlaneClient::connect(format!("http://{addr}"))
+            .await
+            .unwrap()
+    }
+
+    fn authorized<T>(message: T) -> tonic::Request<T> {
+        let mut request = tonic::Request::new(message);
+        let token = JwtConfig::test_token("nexis:human:alice");
+        request
+            .metadata_mut()
+            .insert("authorization", format!("Bearer {token}").parse().unwrap());
+        request
+    }
+
+    fn text(room_id: &str, text: &str) -> proto::SendMessageRequest {
+        proto::SendMessageRequest {
+            room_id: room_id.to_string(),
+            sender: "nexis:human:alice".to_string(),
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    async fn next_text(history: &mut tonic::Streaming<proto::Message>) -> String {
+        history.next().await.unwrap().unwrap().text
+    }
+
+    #[tokio::test]
+    async fn rooms_messages_and_search_are_served_over_grpc() {
+        let mut client = client().await;
+        let room = client
+            .create_room(authorized(proto::CreateRoomRequest {
+                name: "general".to_string(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(room.name, "general");
+
+        for body in ["first", "second about rust"] {
+            client
+                .send_message(authorized(text(&room.id, body)))
+                .await
+                .unwrap();
+        }
+
+        let mut history = client
+            .stream_room_history(authorized(proto::StreamRoomHistoryRequest {
+                room_id: room.id.clone(),
+                follow: true,
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(next_text(&mut history).await, "first");
+        assert_eq!(next_text(&mut history).await, "second about rust");
+
+        client
+            .send_message(authorized(text(&room.id, "third")))
+            .await
+            .unwrap();
+        assert_eq!(next_text(&mut history).await, "third");
+
+        let found = client
+            .search(authorized(proto::SearchRequest {
+                query: "rust".to_string(),
+                room_id: Some(room.id.clone()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(found.total, 1);
+        assert_eq!(found.results[0].content, "second about rust");
+    }
+
+    #[tokio::test]
+    async fn http_errors_map_to_grpc_status_codes() {
+        let mut client = client().await;
+        let unauthenticated = client
+            .create_room(tonic::Request::new(proto::CreateRoomRequest {
+                name: "general".to_string(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(unauthenticated.code(), Code::Unauthenticated);
+
+        let missing = client
+            .send_message(authorized(text("room_missing", "hello")))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), Code::NotFound);
+
+        let invalid = client
+            .stream_room_history(authorized(proto::StreamRoomHistoryRequest {
+                room_id: "room_missing".to_string(),
+                since: Some("yesterday".to_string()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(invalid.code(), Code::InvalidArgument);
+    }
+}
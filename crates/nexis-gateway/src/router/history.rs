@@ -0,0 +1,221 @@
+//! Paged message history: `GET /v1/rooms/:id/messages`.
+//!
+//! Messages come oldest first. Each page's `nextCursor` is the id of its last
+//! message; passing it back as `after` continues from there, so messages sent
+//! while a client pages through the room are picked up rather than shifting
+//! an offset.
+//!
+//! Pages are sent as MessagePack when the `Accept` header prefers it.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::encoding::Accept;
+use super::{ensure_room_access, joins, ErrorResponse, SharedState, StoredMessage};
+use crate::auth::AuthenticatedUser;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new().route("/v1/rooms/:id/messages", get(list_messages))
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+struct HistoryParams {
+    /// Cursor from the previous page.
+    #[serde(default)]
+    after: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+    /// Only messages created at or after this instant.
+    #[serde(default)]
+    since: Option<DateTime<Utc>>,
+    /// Only messages created before this instant.
+    #[serde(default)]
+    until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct HistoryPage {
+    messages: Vec<StoredMessage>,
+    /// Absent on the last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/messages",
+    tag = "messages",
+    summary = "Page through a room's messages, oldest first, optionally within a since/until window",
+    params(("id" = String, Path, description = "Room id"), HistoryParams),
+    responses(
+        (status = 200, description = "A page of messages, as JSON or MessagePack per `Accept`", content(
+            (HistoryPage = "application/json"),
+            (HistoryPage = "application/msgpack"),
+        )),
+        (status = 400, description = "Bad limit or cursor", body = ErrorResponse),
+        (status = 403, description = "The room isn't open and the caller is not a member", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.list_messages",
+    skip(state, user, params),
+    fields(room_id = %id)
+)]
+async fn list_messages(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Query(params): Query<HistoryParams>,
+    accept: Accept,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(format!(
+                "limit must be between 1 and {MAX_PAGE_SIZE}"
+            ))),
+        )
+            .into_response();
+    }
+
+    let messages = state.room_messages.read().await;
+    let messages = messages.get(&id).map(Vec::as_slice).unwrap_or_default();
+    let start = match params.after.as_deref() {
+        None => 0,
+        Some(after) => match messages.iter().position(|message| message.id == after) {
+            Some(index) => index + 1,
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse::bad_request(
+                        "after does not name a message in this room",
+                    )),
+                )
+                    .into_response()
+            }
+        },
+    };
+    let mut matching = messages[start..].iter().filter(|message| {
+        params.since.is_none_or(|since| message.created_at >= since)
+            && params.until.is_none_or(|until| message.created_at < until)
+    });
+    let page: Vec<StoredMessage> = matching.by_ref().take(limit).cloned().collect();
+    let next_cursor = if matching.next().is_some() {
+        page.last().map(|message| message.id.clone())
+    } else {
+        None
+    };
+
+    accept.respond(
+        StatusCode::OK,
+        &HistoryPage {
+            messages: page,
+            next_cursor,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn message_history_pages_with_a_cursor() {
+        let app = routes(AppState::default());
+
+        let room_id = new_room(&app, "nexis:human:alice", "general").await;
+        for text in ["one", "two", "three"] {
+            app.clone()
+                .oneshot(request(
+                    "nexis:human:alice",
+                    "POST",
+                    "/v1/messages",
+                    json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": text }),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let uri = format!("/v1/rooms/{room_id}/messages?limit=2");
+        let response = app
+            .clone()
+            .oneshot(request("nexis:human:alice", "GET", &uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let page = json_body(response).await;
+        assert_eq!(page["messages"][0]["text"], "one");
+        assert_eq!(page["messages"][1]["text"], "two");
+        let cursor = page["nextCursor"].as_str().unwrap().to_string();
+
+        let uri = format!("/v1/rooms/{room_id}/messages?limit=2&after={cursor}");
+        let page = json_body(
+            app.clone()
+                .oneshot(request("nexis:human:alice", "GET", &uri, Value::Null))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(page["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(page["messages"][0]["text"], "three");
+        assert!(page.get("nextCursor").is_none());
+
+        let uri = format!(
+            "/v1/rooms/{room_id}/messages?since=2000-01-01T00:00:00Z&until=2000-01-02T00:00:00Z"
+        );
+        let page = json_body(
+            app.clone()
+                .oneshot(request("nexis:human:alice", "GET", &uri, Value::Null))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert!(page["messages"].as_array().unwrap().is_empty());
+
+        for query in ["after=msg_missing", "limit=0"] {
+            let uri = format!("/v1/rooms/{room_id}/messages?{query}");
+            let response = app
+                .clone()
+                .oneshot(request("nexis:human:alice", "GET", &uri, Value::Null))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{query}");
+        }
+        let response = app
+            .oneshot(request(
+                "nexis:human:alice",
+                "GET",
+                "/v1/rooms/room_missing/messages",
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
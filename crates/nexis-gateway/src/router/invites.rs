@@ -0,0 +1,490 @@
+//! Inviting many members to a room at once.
+//!
+//! `POST /v1/rooms/:id/invite/bulk` takes a JSON list of member ids and
+//! email addresses, or the same list uploaded as CSV (`text/csv`) with one
+//! entry in the first column of each row. Email addresses are looked up in
+//! the member directory. Each entry gets its own result, so a bad line does
+//! not stop the rest of the list. Only admins and members of the room can
+//! invite in bulk, and members banned from the room can't be invited.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::joins::is_member;
+use super::members::{member_tenant, repository_error_response};
+use super::sanctions::banned_members;
+use super::{caller_tenant, ensure_room_access, member_invited, ErrorResponse, SharedState};
+use crate::auth::AuthenticatedUser;
+
+/// Most entries one request can invite.
+const MAX_BULK_INVITES: usize = 1_000;
+
+/// First cells that mark a CSV header row rather than a member.
+const CSV_HEADERS: &[&str] = &["member", "member_id", "memberid", "id", "email"];
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new().route("/v1/rooms/:id/invite/bulk", post(invite_bulk))
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+struct BulkInviteRequest {
+    /// Member ids or email addresses of directory members.
+    members: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum InviteStatus {
+    Invited,
+    /// The member was already in the room; nothing changed.
+    AlreadyMember,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct InviteResult {
+    /// The entry as it was given.
+    entry: String,
+    /// Member the entry resolved to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    member_id: Option<String>,
+    status: InviteStatus,
+    /// Why a failed entry could not be invited.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct BulkInviteResponse {
+    room_id: String,
+    /// Entries that added a member to the room.
+    invited: usize,
+    failed: usize,
+    /// One result per entry, in the order given.
+    results: Vec<InviteResult>,
+}
+
+/// Entries of a JSON or CSV request body, trimmed and without blanks.
+fn parse_entries(headers: &HeaderMap, body: &str) -> Result<Vec<String>, String> {
+    let is_csv = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/csv"));
+    let entries = if is_csv {
+        csv_entries(body)
+    } else {
+        serde_json::from_str::<BulkInviteRequest>(body)
+            .map_err(|err| format!("invalid request body: {err}"))?
+            .members
+    };
+    Ok(entries
+        .into_iter()
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect())
+}
+
+/// First cell of each CSV row, skipping a header row.
+fn csv_entries(body: &str) -> Vec<String> {
+    let mut entries: Vec<String> = body.lines().map(first_cell).collect();
+    if entries
+        .first()
+        .is_some_and(|first| CSV_HEADERS.contains(&first.to_lowercase().as_str()))
+    {
+        entries.remove(0);
+    }
+    entries
+}
+
+/// The first cell of a CSV row, unquoted.
+fn first_cell(row: &str) -> String {
+    let row = row.trim_start();
+    let Some(quoted) = row.strip_prefix('"') else {
+        return row.split(',').next().unwrap_or_default().trim().to_string();
+    };
+    let mut cell = String::new();
+    let mut chars = quoted.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => break,
+            c => cell.push(c),
+        }
+    }
+    cell
+}
+
+/// Email addresses have an `@` but, unlike member ids, no `:`.
+fn is_email(entry: &str) -> bool {
+    entry.contains('@') && !entry.contains(':')
+}
+
+fn failed(entry: String, member_id: Option<String>, error: &str) -> InviteResult {
+    InviteResult {
+        entry,
+        member_id,
+        status: InviteStatus::Failed,
+        error: Some(error.to_string()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/invite/bulk",
+    tag = "rooms",
+    summary = "Add many members to the room",
+    description = "Takes member ids or email addresses as JSON, or as CSV with one entry \
+                   in the first column of each row.",
+    params(("id" = String, Path, description = "Room id")),
+    request_body(content(
+        (BulkInviteRequest = "application/json"),
+        (String = "text/csv"),
+    )),
+    responses(
+        (status = 200, description = "Result of each entry", body = BulkInviteResponse),
+        (status = 400, description = "Unreadable body, or no or too many entries", body = ErrorResponse),
+        (status = 403, description = "The caller is neither an admin nor a member of the room", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.invite_bulk",
+    skip(state, user, headers, body),
+    fields(room_id = %id)
+)]
+async fn invite_bulk(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if !state.config.auth.is_admin(&user.member_id)
+        && !is_member(&state, &id, &user.member_id).await
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::forbidden(
+                "only admins and members of the room can invite",
+            )),
+        )
+            .into_response();
+    }
+
+    let entries = match parse_entries(&headers, &body) {
+        Ok(entries) if entries.is_empty() => Err("no members given".to_string()),
+        Ok(entries) if entries.len() > MAX_BULK_INVITES => Err(format!(
+            "at most {MAX_BULK_INVITES} members can be invited at once"
+        )),
+        other => other,
+    };
+    let entries = match entries {
+        Ok(entries) => entries,
+        Err(message) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request(message)),
+            )
+                .into_response()
+        }
+    };
+
+    let directory = match state.members.list().await {
+        Ok(directory) => directory,
+        Err(err) => return repository_error_response(err),
+    };
+    let tenants: HashMap<&str, Option<&str>> = directory
+        .iter()
+        .map(|member| (member.id.as_str(), member_tenant(member)))
+        .collect();
+    let by_email: HashMap<String, &str> = directory
+        .iter()
+        .filter(|member| member_tenant(member) == caller_tenant(&user))
+        .filter_map(|member| {
+            let email = member.email.as_deref()?;
+            Some((email.to_lowercase(), member.id.as_str()))
+        })
+        .collect();
+
+    let banned = banned_members(&state, &id).await;
+
+    let Ok(_permit) = state.write_gate.clone().acquire_owned().await else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::service_unavailable("service unavailable")),
+        )
+            .into_response();
+    };
+
+    let mut results = Vec::with_capacity(entries.len());
+    let mut newly_invited = Vec::new();
+    for entry in entries {
+        let member_id = if is_email(&entry) {
+            match by_email.get(&entry.to_lowercase()) {
+                Some(member_id) => member_id.to_string(),
+                None => {
+                    results.push(failed(entry, None, "no member has this email address"));
+                    continue;
+                }
+            }
+        } else {
+            entry.clone()
+        };
+        // Directory members of other tenants can't join this tenant's rooms.
+        if tenants
+            .get(member_id.as_str())
+            .is_some_and(|tenant| *tenant != caller_tenant(&user))
+        {
+            results.push(failed(entry, Some(member_id), "member not found"));
+            continue;
+        }
+        if banned.contains(&member_id) {
+            results.push(failed(
+                entry,
+                Some(member_id),
+                "member is banned from the room",
+            ));
+            continue;
+        }
+
+        let status = match state.add_member(&id, &member_id).await {
+            Ok(true) => {
+                newly_invited.push(member_id.clone());
+                InviteStatus::Invited
+            }
+            Ok(false) => InviteStatus::AlreadyMember,
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to store membership of {} in room {}: {}",
+                    member_id,
+                    id,
+                    err
+                );
+                results.push(failed(
+                    entry,
+                    Some(member_id),
+                    "membership could not be saved",
+                ));
+                continue;
+            }
+        };
+        results.push(InviteResult {
+            entry,
+            member_id: Some(member_id),
+            status,
+            error: None,
+        });
+    }
+
+    for member_id in &newly_invited {
+        member_invited(&state, &user, &id, member_id).await;
+    }
+
+    let response = BulkInviteResponse {
+        room_id: id,
+        invited: newly_invited.len(),
+        failed: results
+            .iter()
+            .filter(|result| result.status == InviteStatus::Failed)
+            .count(),
+        results,
+    };
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[test]
+    fn csv_uploads_take_the_first_column_without_a_header() {
+        let body = "Email,Name\nalice@example.com,Alice\n\n\"nexis:human:bob\",\"Bob, Jr.\"\n  carol@example.com\n";
+        assert_eq!(
+            csv_entries(body),
+            [
+                "alice@example.com",
+                "",
+                "nexis:human:bob",
+                "carol@example.com"
+            ]
+        );
+        assert_eq!(csv_entries("nexis:human:dave"), ["nexis:human:dave"]);
+        assert_eq!(first_cell("\"say \"\"hi\"\"\",x"), "say \"hi\"");
+    }
+
+    #[test]
+    fn entries_are_read_from_json_or_csv() {
+        let mut headers = HeaderMap::new();
+        let json = r#"{ "members": [" alice ", "", "bob@example.com"] }"#;
+        assert_eq!(
+            parse_entries(&headers, json).unwrap(),
+            ["alice", "bob@example.com"]
+        );
+        assert!(parse_entries(&headers, "alice\nbob").is_err());
+
+        headers.insert(
+            header::CONTENT_TYPE,
+            "text/csv; charset=utf-8".parse().unwrap(),
+        );
+        assert_eq!(
+            parse_entries(&headers, "id\nalice\n\nbob").unwrap(),
+            ["alice", "bob"]
+        );
+
+        assert!(is_email("bob@example.com"));
+        assert!(!is_email("nexis:human:bob@example.com"));
+    }
+
+    #[tokio::test]
+    async fn invitations_are_emailed_when_email_is_configured() {
+        use crate::config::EmailConfig;
+        use crate::email::{ConsoleEmailSender, Mailer};
+
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let mailer = Mailer::new(Arc::new(ConsoleEmailSender), EmailConfig::default());
+        let state = AppState {
+            config: Arc::new(config),
+            mailer: Some(Arc::new(mailer)),
+            ..AppState::default()
+        };
+        let app = routes(state.clone());
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                "/v1/rooms",
+                json!({ "name": "ops" }),
+            ))
+            .await
+            .unwrap();
+        let room = json_body(response).await;
+        let invitations_uri = format!("/v1/rooms/{}/invitations", room["id"].as_str().unwrap());
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &invitations_uri,
+                json!({ "email": "not an address" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &invitations_uri,
+                json!({ "email": "bob@example.com" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(state.invitations.read().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn members_are_invited_in_bulk_from_json_or_csv() {
+        use crate::db::Member;
+
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let mut bob = Member::new("nexis:human:bob", "human");
+        bob.email = Some("bob@example.com".to_string());
+        state.members.insert(&bob).await.unwrap();
+        let app = routes(state.clone());
+        let call = |member: &str, uri: &str, content_type: &str, body: String| {
+            raw_request(member, "POST", uri, content_type, body)
+        };
+
+        let response = app
+            .clone()
+            .oneshot(call(
+                "admin",
+                "/v1/rooms",
+                "application/json",
+                json!({ "name": "ops" }).to_string(),
+            ))
+            .await
+            .unwrap();
+        let room_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let uri = format!("/v1/rooms/{room_id}/invite/bulk");
+
+        let body = json!({ "members": ["alice", "BOB@example.com", "nobody@example.com"] });
+        let response = app
+            .clone()
+            .oneshot(call("admin", &uri, "application/json", body.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let report = json_body(response).await;
+        assert_eq!(report["invited"], 2);
+        assert_eq!(report["failed"], 1);
+        assert_eq!(report["results"][1]["memberId"], "nexis:human:bob");
+        assert_eq!(report["results"][2]["status"], "failed");
+
+        // Members of the room can invite too; outsiders can't.
+        let csv = "member\nalice\ncarol\n".to_string();
+        let response = app
+            .clone()
+            .oneshot(call("dave", &uri, "text/csv", csv.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(call("alice", &uri, "text/csv", csv))
+            .await
+            .unwrap();
+        let report = json_body(response).await;
+        assert_eq!(report["results"][0]["status"], "already_member");
+        assert_eq!(report["results"][1]["status"], "invited");
+        assert_eq!(
+            state.room_members.read().await[&room_id],
+            ["admin", "alice", "nexis:human:bob", "carol"]
+        );
+
+        let response = app
+            .clone()
+            .oneshot(call("admin", &uri, "text/csv", "email\n".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,989 @@
+//! Joining rooms under their join policy.
+//!
+//! `POST /v1/rooms/:id/join` adds the caller to the room's members as the
+//! room's [`JoinPolicy`] allows: open rooms take anyone who can see them,
+//! invite-only rooms need an invitation token, and approval-required rooms
+//! queue a join request for an admin to approve unless a token is presented.
+//! Invitations are single-use and expire; like join requests they are kept
+//! in memory. An invitation created with an `email` address is also mailed
+//! there through `[email]`.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::sanctions;
+use super::settings::{room_settings, JoinPolicy};
+use super::{
+    ensure_room_access, require_admin, storage_error_response, ErrorResponse, SharedState,
+};
+use crate::audit::{AuditAction, AuditEvent, AuditResult};
+use crate::auth::AuthenticatedUser;
+use crate::email::{self, EmailTemplate};
+
+/// Lifetime of invitations created without `expiresInSecs`.
+const DEFAULT_INVITATION_SECS: u64 = 7 * 24 * 3_600;
+/// Longest lifetime an invitation can be given.
+const MAX_INVITATION_SECS: u64 = 30 * 24 * 3_600;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/rooms/:id/join", post(join_room))
+        .route(
+            "/v1/rooms/:id/invitations",
+            get(list_invitations).post(create_invitation),
+        )
+        .route(
+            "/v1/rooms/:id/invitations/:token",
+            delete(revoke_invitation),
+        )
+        .route("/v1/rooms/:id/join-requests", get(list_join_requests))
+        .route(
+            "/v1/rooms/:id/join-requests/:member_id",
+            delete(reject_join_request),
+        )
+        .route(
+            "/v1/rooms/:id/join-requests/:member_id/approve",
+            post(approve_join_request),
+        )
+}
+
+/// Single-use token that lets its holder join a room.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Invitation {
+    token: String,
+    room_id: String,
+    /// Member the invitation is for; anyone holding the token may use it
+    /// when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    member_id: Option<String>,
+    created_by: String,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+impl Invitation {
+    fn admits(&self, room_id: &str, member_id: &str, now: DateTime<Utc>) -> bool {
+        self.room_id == room_id
+            && self.expires_at > now
+            && self
+                .member_id
+                .as_deref()
+                .is_none_or(|invited| invited == member_id)
+    }
+}
+
+/// Request to join an approval-required room, waiting for an admin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct JoinRequest {
+    member_id: String,
+    requested_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct JoinRoomRequest {
+    /// Invitation token; required for invite-only rooms.
+    #[serde(default)]
+    token: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum JoinStatus {
+    /// The caller is a member of the room.
+    Joined,
+    /// The caller's join request waits for approval.
+    Pending,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct JoinRoomResponse {
+    room_id: String,
+    member_id: String,
+    status: JoinStatus,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct CreateInvitationRequest {
+    /// Seconds until the invitation expires; a week when unset.
+    #[serde(default)]
+    expires_in_secs: Option<u64>,
+    /// Restrict the invitation to one member.
+    #[serde(default)]
+    member_id: Option<String>,
+    /// Email the invitation to this address.
+    #[serde(default)]
+    email: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct ListInvitationsResponse {
+    invitations: Vec<Invitation>,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct ListJoinRequestsResponse {
+    requests: Vec<JoinRequest>,
+    total: usize,
+}
+
+pub(super) async fn is_member(state: &SharedState, room_id: &str, member_id: &str) -> bool {
+    state
+        .room_members
+        .read()
+        .await
+        .get(room_id)
+        .is_some_and(|members| members.iter().any(|member| member == member_id))
+}
+
+/// Refuse `user` reading or posting in a room that isn't open unless they
+/// are a member of it or an admin. Call after checking the room is visible.
+pub(super) async fn ensure_participant(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    room_id: &str,
+) -> Result<(), Response> {
+    if state.config.auth.is_admin(&user.member_id)
+        || room_settings(state, room_id).await.join_policy == JoinPolicy::Open
+        || is_member(state, room_id, &user.member_id).await
+    {
+        return Ok(());
+    }
+    Err(forbidden("you are not a member of this room"))
+}
+
+/// Take the invitation `token` if it admits `member_id` to the room.
+async fn redeem(state: &SharedState, room_id: &str, member_id: &str, token: &str) -> bool {
+    let now = Utc::now();
+    let mut invitations = state.invitations.write().await;
+    invitations.retain(|_, invitation| invitation.expires_at > now);
+    match invitations.get(token) {
+        Some(invitation) if invitation.admits(room_id, member_id, now) => {
+            invitations.remove(token);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Drop the invitations and join requests of a deleted room.
+pub(super) async fn remove_room(state: &SharedState, room_id: &str) {
+    state
+        .invitations
+        .write()
+        .await
+        .retain(|_, invitation| invitation.room_id != room_id);
+    state.join_requests.write().await.remove(room_id);
+}
+
+fn joined(room_id: String, member_id: String) -> Response {
+    let response = JoinRoomResponse {
+        room_id,
+        member_id,
+        status: JoinStatus::Joined,
+    };
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+fn forbidden(message: &str) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(ErrorResponse::forbidden(message)),
+    )
+        .into_response()
+}
+
+fn join_request_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found("join request not found")),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/join",
+    tag = "rooms",
+    summary = "Join a room as its join policy allows",
+    params(("id" = String, Path, description = "Room id")),
+    request_body(content = JoinRoomRequest, description = "Optional invitation token"),
+    responses(
+        (status = 200, description = "The caller is a member", body = JoinRoomResponse),
+        (status = 202, description = "Join request queued for approval", body = JoinRoomResponse),
+        (status = 403, description = "Invitation missing, invalid or expired, or the caller is banned", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.join_room",
+    skip(state, user, payload),
+    fields(room_id = %id, member_id = %user.member_id)
+)]
+async fn join_room(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    payload: Option<Json<JoinRoomRequest>>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    let member_id = user.member_id.clone();
+    if is_member(&state, &id, &member_id).await {
+        return joined(id, member_id);
+    }
+
+    let resource = format!("room:{id}/member:{member_id}");
+    if sanctions::is_banned(&state, &id, &member_id).await {
+        state
+            .audit
+            .record(
+                AuditEvent::new(&member_id, AuditAction::MemberJoined, resource)
+                    .with_result(AuditResult::Denied, "member is banned"),
+            )
+            .await;
+        return forbidden("you are banned from this room");
+    }
+    let token = payload.and_then(|Json(payload)| payload.token);
+    if let Some(token) = token {
+        if !redeem(&state, &id, &member_id, &token).await {
+            state
+                .audit
+                .record(
+                    AuditEvent::new(&member_id, AuditAction::MemberJoined, resource)
+                        .with_result(AuditResult::Denied, "invalid invitation"),
+                )
+                .await;
+            return forbidden("invitation is invalid or expired");
+        }
+        if let Err(err) = state.add_member(&id, &member_id).await {
+            return storage_error_response(err);
+        }
+        state
+            .audit
+            .record(AuditEvent::new(
+                &member_id,
+                AuditAction::MemberJoined,
+                resource,
+            ))
+            .await;
+        return joined(id, member_id);
+    }
+
+    match room_settings(&state, &id).await.join_policy {
+        JoinPolicy::Open => {
+            if let Err(err) = state.add_member(&id, &member_id).await {
+                return storage_error_response(err);
+            }
+            state
+                .audit
+                .record(AuditEvent::new(
+                    &member_id,
+                    AuditAction::MemberJoined,
+                    resource,
+                ))
+                .await;
+            joined(id, member_id)
+        }
+        JoinPolicy::InviteOnly => forbidden("room is invite-only; an invitation is required"),
+        JoinPolicy::ApprovalRequired => {
+            let mut requests = state.join_requests.write().await;
+            let pending = requests.entry(id.clone()).or_default();
+            if !pending.iter().any(|request| request.member_id == member_id) {
+                pending.push(JoinRequest {
+                    member_id: member_id.clone(),
+                    requested_at: Utc::now(),
+                });
+                drop(requests);
+                state
+                    .audit
+                    .record(AuditEvent::new(
+                        &member_id,
+                        AuditAction::JoinRequested,
+                        resource,
+                    ))
+                    .await;
+            }
+            let response = JoinRoomResponse {
+                room_id: id,
+                member_id,
+                status: JoinStatus::Pending,
+            };
+            (StatusCode::ACCEPTED, Json(response)).into_response()
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/invitations",
+    tag = "rooms",
+    summary = "Create a single-use invitation to a room (admin only)",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = CreateInvitationRequest,
+    responses(
+        (status = 201, description = "Invitation created", body = Invitation),
+        (status = 400, description = "Invalid expiry or email address, or email is not configured", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+        (status = 503, description = "The invitation email could not be sent", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.create_invitation",
+    skip(state, user, payload),
+    fields(room_id = %id, member_id = %user.member_id)
+)]
+async fn create_invitation(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    payload: Option<Json<CreateInvitationRequest>>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let expires_in = payload.expires_in_secs.unwrap_or(DEFAULT_INVITATION_SECS);
+    if expires_in == 0 || expires_in > MAX_INVITATION_SECS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(format!(
+                "expiresInSecs must be between 1 and {MAX_INVITATION_SECS}"
+            ))),
+        )
+            .into_response();
+    }
+    let email = payload.email.filter(|email| !email.trim().is_empty());
+    if let Some(address) = &email {
+        let message = if state.mailer.is_none() {
+            Some("email is not configured")
+        } else if !email::is_address(address) {
+            Some("email must be an email address")
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request(message)),
+            )
+                .into_response();
+        }
+    }
+
+    let created_at = Utc::now();
+    let invitation = Invitation {
+        token: format!("inv_{}", Uuid::new_v4().simple()),
+        room_id: id.clone(),
+        member_id: payload.member_id.filter(|member| !member.trim().is_empty()),
+        created_by: user.member_id.clone(),
+        created_at,
+        expires_at: created_at + chrono::Duration::seconds(expires_in as i64),
+    };
+    state
+        .invitations
+        .write()
+        .await
+        .insert(invitation.token.clone(), invitation.clone());
+    if let Some(address) = &email {
+        if let Err(err) = send_invitation(&state, &invitation, address).await {
+            tracing::warn!("Failed to email invitation to room {}: {}", id, err);
+            state.invitations.write().await.remove(&invitation.token);
+            state
+                .audit
+                .record(
+                    AuditEvent::new(
+                        &user.member_id,
+                        AuditAction::InvitationCreated,
+                        format!("room:{id}"),
+                    )
+                    .with_result(AuditResult::Failed, err.to_string()),
+                )
+                .await;
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse::service_unavailable(
+                    "the invitation email could not be sent",
+                )),
+            )
+                .into_response();
+        }
+    }
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::InvitationCreated,
+            format!("room:{id}"),
+        ))
+        .await;
+    (StatusCode::CREATED, Json(invitation)).into_response()
+}
+
+/// Mail `invitation` to `address`, from the sender of the room's tenant.
+async fn send_invitation(
+    state: &SharedState,
+    invitation: &Invitation,
+    address: &str,
+) -> Result<(), email::EmailError> {
+    let Some(mailer) = &state.mailer else {
+        return Err(email::EmailError::Config(
+            "email is not configured".to_string(),
+        ));
+    };
+    let (room_name, tenant) = state
+        .rooms
+        .read()
+        .await
+        .get(&invitation.room_id)
+        .map(|room| (room.name.clone(), room.tenant().map(str::to_string)))
+        .unwrap_or_else(|| (invitation.room_id.clone(), None));
+    let expires_at = invitation
+        .expires_at
+        .format("%Y-%m-%d %H:%M UTC")
+        .to_string();
+    let values = [
+        ("room_name", room_name.as_str()),
+        ("room_id", invitation.room_id.as_str()),
+        ("token", invitation.token.as_str()),
+        ("invited_by", invitation.created_by.as_str()),
+        ("expires_at", expires_at.as_str()),
+    ];
+    mailer
+        .send(
+            EmailTemplate::Invitation,
+            address,
+            tenant.as_deref(),
+            &values,
+        )
+        .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/invitations",
+    tag = "rooms",
+    summary = "List a room's unused invitations (admin only)",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "Invitations that can still be used", body = ListInvitationsResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.list_invitations", skip(state, user), fields(room_id = %id))]
+async fn list_invitations(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+
+    let now = Utc::now();
+    let mut invitations: Vec<Invitation> = state
+        .invitations
+        .read()
+        .await
+        .values()
+        .filter(|invitation| invitation.room_id == id && invitation.expires_at > now)
+        .cloned()
+        .collect();
+    invitations.sort_by_key(|invitation| invitation.created_at);
+    let total = invitations.len();
+    (
+        StatusCode::OK,
+        Json(ListInvitationsResponse { invitations, total }),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/rooms/{id}/invitations/{token}",
+    tag = "rooms",
+    summary = "Revoke an invitation (admin only)",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("token" = String, Path, description = "Invitation token"),
+    ),
+    responses(
+        (status = 204, description = "Invitation revoked"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room or invitation not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.revoke_invitation", skip_all, fields(room_id = %id))]
+async fn revoke_invitation(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, token)): Path<(String, String)>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+
+    let mut invitations = state.invitations.write().await;
+    if invitations
+        .get(&token)
+        .is_none_or(|invitation| invitation.room_id != id)
+    {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("invitation not found")),
+        )
+            .into_response();
+    }
+    invitations.remove(&token);
+    drop(invitations);
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::InvitationRevoked,
+            format!("room:{id}"),
+        ))
+        .await;
+    (StatusCode::NO_CONTENT, ()).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/join-requests",
+    tag = "rooms",
+    summary = "List join requests waiting for approval (admin only)",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "Pending join requests, oldest first", body = ListJoinRequestsResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.list_join_requests", skip(state, user), fields(room_id = %id))]
+async fn list_join_requests(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+
+    let requests = state
+        .join_requests
+        .read()
+        .await
+        .get(&id)
+        .cloned()
+        .unwrap_or_default();
+    let total = requests.len();
+    (
+        StatusCode::OK,
+        Json(ListJoinRequestsResponse { requests, total }),
+    )
+        .into_response()
+}
+
+/// Take `member_id`'s pending request to join the room.
+async fn take_join_request(state: &SharedState, room_id: &str, member_id: &str) -> bool {
+    let mut requests = state.join_requests.write().await;
+    let Some(pending) = requests.get_mut(room_id) else {
+        return false;
+    };
+    let before = pending.len();
+    pending.retain(|request| request.member_id != member_id);
+    before != pending.len()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/join-requests/{member_id}/approve",
+    tag = "rooms",
+    summary = "Approve a join request (admin only)",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("member_id" = String, Path, description = "Member who asked to join"),
+    ),
+    responses(
+        (status = 200, description = "The member joined the room", body = JoinRoomResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room or join request not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.approve_join_request",
+    skip(state, user),
+    fields(room_id = %id, member_id = %member_id)
+)]
+async fn approve_join_request(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, member_id)): Path<(String, String)>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if !take_join_request(&state, &id, &member_id).await {
+        return join_request_not_found();
+    }
+
+    if let Err(err) = state.add_member(&id, &member_id).await {
+        return storage_error_response(err);
+    }
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::MemberJoined,
+            format!("room:{id}/member:{member_id}"),
+        ))
+        .await;
+    joined(id, member_id)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/rooms/{id}/join-requests/{member_id}",
+    tag = "rooms",
+    summary = "Reject a join request (admin only)",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("member_id" = String, Path, description = "Member who asked to join"),
+    ),
+    responses(
+        (status = 204, description = "Join request rejected"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room or join request not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.reject_join_request",
+    skip(state, user),
+    fields(room_id = %id, member_id = %member_id)
+)]
+async fn reject_join_request(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, member_id)): Path<(String, String)>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if !take_join_request(&state, &id, &member_id).await {
+        return join_request_not_found();
+    }
+
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::JoinRequestRejected,
+            format!("room:{id}/member:{member_id}"),
+        ))
+        .await;
+    (StatusCode::NO_CONTENT, ()).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn invitation(member_id: Option<&str>, expires_in: i64) -> Invitation {
+        let now = Utc::now();
+        Invitation {
+            token: "inv_1".to_string(),
+            room_id: "room_1".to_string(),
+            member_id: member_id.map(str::to_string),
+            created_by: "admin".to_string(),
+            created_at: now,
+            expires_at: now + chrono::Duration::seconds(expires_in),
+        }
+    }
+
+    #[test]
+    fn invitations_admit_only_their_room_member_and_lifetime() {
+        let now = Utc::now();
+        assert!(invitation(None, 60).admits("room_1", "alice", now));
+        assert!(!invitation(None, 60).admits("room_2", "alice", now));
+        assert!(!invitation(None, -1).admits("room_1", "alice", now));
+        assert!(invitation(Some("alice"), 60).admits("room_1", "alice", now));
+        assert!(!invitation(Some("alice"), 60).admits("room_1", "bob", now));
+    }
+
+    #[tokio::test]
+    async fn rooms_are_joined_as_their_join_policy_allows() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let app = routes(state.clone());
+        let is_member = |room_id: String, member: &'static str| {
+            let state = state.clone();
+            async move {
+                state.room_members.read().await[&room_id]
+                    .iter()
+                    .any(|member_id| member_id == member)
+            }
+        };
+
+        let room_id = new_room(&app, "admin", "ops").await;
+        let join_uri = format!("/v1/rooms/{room_id}/join");
+        let set_policy = |policy: &str| {
+            request(
+                "admin",
+                "PATCH",
+                &format!("/v1/rooms/{room_id}/settings"),
+                json!({ "joinPolicy": policy }),
+            )
+        };
+
+        // Open rooms take anyone.
+        let response = app
+            .clone()
+            .oneshot(request("alice", "POST", &join_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["status"], "joined");
+        assert!(is_member(room_id.clone(), "alice").await);
+
+        // Invite-only rooms need a token, and each token is used once.
+        app.clone()
+            .oneshot(set_policy("invite_only"))
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(request("nexis:human:bob", "POST", &join_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let invitations_uri = format!("/v1/rooms/{room_id}/invitations");
+        let response = app
+            .clone()
+            .oneshot(request("alice", "POST", &invitations_uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &invitations_uri,
+                json!({ "expiresInSecs": 0 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        // Without `[email]` invitations cannot be mailed.
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &invitations_uri,
+                json!({ "email": "bob@example.com" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &invitations_uri,
+                json!({ "expiresInSecs": 3600 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let token = json_body(response).await["token"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:bob",
+                "POST",
+                &join_uri,
+                json!({ "token": token }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(is_member(room_id.clone(), "nexis:human:bob").await);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "carol",
+                "POST",
+                &join_uri,
+                json!({ "token": token }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // Outsiders can't invite themselves in, nor read or post there.
+        let response = app
+            .clone()
+            .oneshot(request(
+                "carol",
+                "POST",
+                &format!("/v1/rooms/{room_id}/invite"),
+                json!({ "memberId": "carol" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(!is_member(room_id.clone(), "carol").await);
+        for uri in [
+            format!("/v1/rooms/{room_id}"),
+            format!("/v1/rooms/{room_id}/messages"),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request("carol", "GET", &uri, Value::Null))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            let response = app
+                .clone()
+                .oneshot(request("nexis:human:bob", "GET", &uri, Value::Null))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let message = |member: &str| {
+            request(
+                member,
+                "POST",
+                "/v1/messages",
+                json!({
+                    "roomId": room_id,
+                    "sender": member,
+                    "text": "hi",
+                }),
+            )
+        };
+        let response = app
+            .clone()
+            .oneshot(message("nexis:human:carol"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(message("nexis:human:bob"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        // Approval-required rooms queue a request for an admin.
+        app.clone()
+            .oneshot(set_policy("approval_required"))
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(request("carol", "POST", &join_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(json_body(response).await["status"], "pending");
+        assert!(!is_member(room_id.clone(), "carol").await);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "GET",
+                &format!("/v1/rooms/{room_id}/join-requests"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        let requests = json_body(response).await;
+        assert_eq!(requests["total"], 1);
+        assert_eq!(requests["requests"][0]["memberId"], "carol");
+        let approve_uri = format!("/v1/rooms/{room_id}/join-requests/carol/approve");
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", &approve_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(is_member(room_id.clone(), "carol").await);
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", &approve_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", "/v1/audit", Value::Null))
+            .await
+            .unwrap();
+        let actions: Vec<Value> = json_body(response).await["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|event| event["action"].clone())
+            .collect();
+        for action in [
+            "member.joined",
+            "member.join_requested",
+            "invitation.created",
+        ] {
+            assert!(actions.contains(&json!(action)));
+        }
+    }
+}
@@ -0,0 +1,770 @@
+//! Member directory endpoints.
+//!
+//! Profiles are keyed by Nexis member id and stored through the configured
+//! [`MemberRepository`](crate::db::MemberRepository). Any authenticated
+//! member can look profiles up; only the member itself or an admin can
+//! create, change or remove one.
+//!
+//! Agent and AI members declare [`Capabilities`] on their profile, either
+//! with the rest of it or through `/v1/members/:id/capabilities`. Listing
+//! members with capability filters returns only the agents that cover them,
+//! which is how work is routed to an agent that can handle it.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use nexis_protocol::{e2e::decode_public_key, Capabilities, MemberId, MemberType};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::{caller_tenant, ErrorResponse, SharedState};
+use crate::audit::{AuditAction, AuditEvent};
+use crate::auth::AuthenticatedUser;
+use crate::db::{Member, RepositoryError};
+
+const MAX_DISPLAY_NAME_CHARS: usize = 128;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/members", get(list_members).post(create_member))
+        .route(
+            "/v1/members/:id",
+            get(get_member).put(update_member).delete(delete_member),
+        )
+        .route(
+            "/v1/members/:id/capabilities",
+            get(get_capabilities).put(register_capabilities),
+        )
+}
+
+/// Editable profile fields.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ProfileFields {
+    display_name: Option<String>,
+    avatar_url: Option<String>,
+    public_key: Option<String>,
+    #[serde(default)]
+    capabilities: Capabilities,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+struct CreateMemberRequest {
+    id: String,
+    #[serde(flatten)]
+    profile: ProfileFields,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct MemberProfile {
+    id: String,
+    member_type: String,
+    display_name: Option<String>,
+    avatar_url: Option<String>,
+    public_key: Option<String>,
+    capabilities: Capabilities,
+    created_at: DateTime<Utc>,
+}
+
+impl From<Member> for MemberProfile {
+    fn from(member: Member) -> Self {
+        Self {
+            id: member.id,
+            member_type: member.member_type,
+            display_name: member.display_name,
+            avatar_url: member.avatar_url,
+            public_key: member.public_key,
+            capabilities: member.capabilities,
+            created_at: member.created_at,
+        }
+    }
+}
+
+/// Capability filter of `GET /v1/members`. Lists are comma-separated, e.g.
+/// `?contentTypes=code&tools=run_tests,lint`.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+struct CapabilityQuery {
+    content_types: Option<String>,
+    tools: Option<String>,
+    languages: Option<String>,
+    max_context_tokens: Option<u32>,
+}
+
+impl CapabilityQuery {
+    fn required(&self) -> Capabilities {
+        let names = |list: &Option<String>| {
+            list.iter()
+                .flat_map(|list| list.split(','))
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        Capabilities {
+            content_types: names(&self.content_types),
+            tools: names(&self.tools),
+            max_context_tokens: self.max_context_tokens,
+            languages: names(&self.languages),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct ListMembersResponse {
+    members: Vec<MemberProfile>,
+    total: usize,
+}
+
+pub(super) fn member_tenant(member: &Member) -> Option<&str> {
+    #[cfg(feature = "multi-tenant")]
+    return member.tenant_id.as_deref();
+    #[cfg(not(feature = "multi-tenant"))]
+    {
+        let _ = member;
+        None
+    }
+}
+
+fn bad_request(message: impl Into<String>) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::bad_request(message)),
+    )
+        .into_response()
+}
+
+fn member_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found("member not found")),
+    )
+        .into_response()
+}
+
+pub(super) fn repository_error_response(err: RepositoryError) -> Response {
+    match err {
+        RepositoryError::AlreadyExists(_) => (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::conflict(err.to_string())),
+        )
+            .into_response(),
+        other => {
+            tracing::error!("Member repository error: {}", other);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::internal_error()),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Only the member itself or an admin may change a profile.
+fn ensure_can_edit(state: &SharedState, user: &AuthenticatedUser, id: &str) -> Option<Response> {
+    (user.member_id != id && !state.config.auth.is_admin(&user.member_id)).then(|| {
+        (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::forbidden(
+                "members can only manage their own profile",
+            )),
+        )
+            .into_response()
+    })
+}
+
+/// Check `profile` and copy it onto `member`.
+fn apply_profile(
+    member: &mut Member,
+    member_type: MemberType,
+    profile: ProfileFields,
+) -> Option<Response> {
+    let display_name = profile
+        .display_name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    if display_name
+        .as_ref()
+        .is_some_and(|name| name.chars().count() > MAX_DISPLAY_NAME_CHARS)
+    {
+        return Some(bad_request(format!(
+            "displayName must be at most {MAX_DISPLAY_NAME_CHARS} characters"
+        )));
+    }
+    if profile
+        .avatar_url
+        .as_deref()
+        .is_some_and(|url| !(url.starts_with("https://") || url.starts_with("http://")))
+    {
+        return Some(bad_request("avatarUrl must be an http(s) URL"));
+    }
+    if let Some(Err(err)) = profile.public_key.as_deref().map(decode_public_key) {
+        return Some(bad_request(err.to_string()));
+    }
+    if let Some(response) = check_capabilities(member_type, &profile.capabilities) {
+        return Some(response);
+    }
+
+    member.display_name = display_name;
+    member.avatar_url = profile.avatar_url;
+    member.public_key = profile.public_key;
+    member.capabilities = profile.capabilities;
+    None
+}
+
+/// Only agent and AI members may declare capabilities, and every declared
+/// name must be valid.
+fn check_capabilities(member_type: MemberType, capabilities: &Capabilities) -> Option<Response> {
+    if !capabilities.is_empty() && !matches!(member_type, MemberType::Agent | MemberType::Ai) {
+        return Some(bad_request(
+            "only agent and ai members can declare capabilities",
+        ));
+    }
+    capabilities
+        .validate()
+        .err()
+        .map(|err| bad_request(err.to_string()))
+}
+
+/// Load `id` if it exists and is visible from the caller's tenant.
+async fn visible_member(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    id: &str,
+) -> Result<Member, Response> {
+    match state.members.get(id).await {
+        Ok(Some(member)) if member_tenant(&member) == caller_tenant(user) => Ok(member),
+        Ok(_) => Err(member_not_found()),
+        Err(err) => Err(repository_error_response(err)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/members",
+    tag = "members",
+    summary = "List member profiles visible to the caller, optionally only agents declaring the given capabilities",
+    params(CapabilityQuery),
+    responses((status = 200, description = "Matching member profiles", body = ListMembersResponse))
+)]
+#[tracing::instrument(name = "gateway.list_members", skip(state, user, query))]
+async fn list_members(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Query(query): Query<CapabilityQuery>,
+) -> Response {
+    let required = query.required();
+    match state.members.list().await {
+        Ok(members) => {
+            let members = members
+                .into_iter()
+                .filter(|member| member_tenant(member) == caller_tenant(&user))
+                .filter(|member| required.is_empty() || member.capabilities.satisfies(&required))
+                .map(MemberProfile::from)
+                .collect::<Vec<_>>();
+            let total = members.len();
+            (StatusCode::OK, Json(ListMembersResponse { members, total })).into_response()
+        }
+        Err(err) => repository_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/members",
+    tag = "members",
+    summary = "Create a member profile",
+    request_body = CreateMemberRequest,
+    responses(
+        (status = 201, description = "Profile created", body = MemberProfile),
+        (status = 400, description = "Invalid profile", body = ErrorResponse),
+        (status = 403, description = "Only the member itself or an admin can create the profile", body = ErrorResponse),
+        (status = 409, description = "The member already has a profile", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.create_member",
+    skip(state, user, payload),
+    fields(member_id = %payload.id)
+)]
+async fn create_member(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Json(payload): Json<CreateMemberRequest>,
+) -> Response {
+    let Ok(member_id) = payload.id.parse::<MemberId>() else {
+        return bad_request("id must be a valid member id");
+    };
+    if let Some(response) = ensure_can_edit(&state, &user, &payload.id) {
+        return response;
+    }
+
+    let member_type = member_id.member_type();
+    let mut member = Member::new(member_id.to_string(), member_type.as_str());
+    if let Some(response) = apply_profile(&mut member, member_type, payload.profile) {
+        return response;
+    }
+    #[cfg(feature = "multi-tenant")]
+    {
+        member.tenant_id = caller_tenant(&user).map(str::to_string);
+    }
+
+    if let Err(err) = state.members.insert(&member).await {
+        return repository_error_response(err);
+    }
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::MemberProfileCreated,
+            format!("member:{}", member.id),
+        ))
+        .await;
+    (StatusCode::CREATED, Json(MemberProfile::from(member))).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/members/{id}",
+    tag = "members",
+    summary = "Get a member profile",
+    params(("id" = String, Path, description = "Member id")),
+    responses(
+        (status = 200, description = "The member's profile", body = MemberProfile),
+        (status = 404, description = "Member not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.get_member", skip(state, user), fields(member_id = %id))]
+async fn get_member(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    match visible_member(&state, &user, &id).await {
+        Ok(member) => (StatusCode::OK, Json(MemberProfile::from(member))).into_response(),
+        Err(response) => response,
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/members/{id}",
+    tag = "members",
+    summary = "Replace a member's profile fields",
+    params(("id" = String, Path, description = "Member id")),
+    request_body = ProfileFields,
+    responses(
+        (status = 200, description = "The updated profile", body = MemberProfile),
+        (status = 400, description = "Invalid profile", body = ErrorResponse),
+        (status = 403, description = "Only the member itself or an admin can change the profile", body = ErrorResponse),
+        (status = 404, description = "Member not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.update_member",
+    skip(state, user, profile),
+    fields(member_id = %id)
+)]
+async fn update_member(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(profile): Json<ProfileFields>,
+) -> Response {
+    if let Some(response) = ensure_can_edit(&state, &user, &id) {
+        return response;
+    }
+    let mut member = match visible_member(&state, &user, &id).await {
+        Ok(member) => member,
+        Err(response) => return response,
+    };
+    let Ok(member_type) = id.parse::<MemberId>().map(|id| id.member_type()) else {
+        return bad_request("id must be a valid member id");
+    };
+    if let Some(response) = apply_profile(&mut member, member_type, profile) {
+        return response;
+    }
+
+    match state.members.update(&member).await {
+        Ok(true) => {
+            state
+                .audit
+                .record(AuditEvent::new(
+                    &user.member_id,
+                    AuditAction::MemberProfileUpdated,
+                    format!("member:{id}"),
+                ))
+                .await;
+            (StatusCode::OK, Json(MemberProfile::from(member))).into_response()
+        }
+        Ok(false) => member_not_found(),
+        Err(err) => repository_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/members/{id}",
+    tag = "members",
+    summary = "Remove a member profile",
+    params(("id" = String, Path, description = "Member id")),
+    responses(
+        (status = 204, description = "Profile removed"),
+        (status = 403, description = "Only the member itself or an admin can remove the profile", body = ErrorResponse),
+        (status = 404, description = "Member not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.delete_member", skip(state, user), fields(member_id = %id))]
+async fn delete_member(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Some(response) = ensure_can_edit(&state, &user, &id) {
+        return response;
+    }
+    if let Err(response) = visible_member(&state, &user, &id).await {
+        return response;
+    }
+
+    match state.members.delete(&id).await {
+        Ok(true) => {
+            state
+                .audit
+                .record(AuditEvent::new(
+                    &user.member_id,
+                    AuditAction::MemberProfileDeleted,
+                    format!("member:{id}"),
+                ))
+                .await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => member_not_found(),
+        Err(err) => repository_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/members/{id}/capabilities",
+    tag = "members",
+    summary = "Get the capabilities a member declares",
+    params(("id" = String, Path, description = "Member id")),
+    responses(
+        (status = 200, description = "The member's capabilities", body = Capabilities),
+        (status = 404, description = "Member not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.get_member_capabilities",
+    skip(state, user),
+    fields(member_id = %id)
+)]
+async fn get_capabilities(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    match visible_member(&state, &user, &id).await {
+        Ok(member) => (StatusCode::OK, Json(member.capabilities)).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// Replace the capabilities of an existing profile, leaving the rest of it
+/// untouched.
+#[utoipa::path(
+    put,
+    path = "/v1/members/{id}/capabilities",
+    tag = "members",
+    summary = "Replace an agent member's capabilities",
+    params(("id" = String, Path, description = "Member id")),
+    request_body = Capabilities,
+    responses(
+        (status = 200, description = "The member's new capabilities", body = Capabilities),
+        (status = 400, description = "Invalid capabilities, or the member is not an agent", body = ErrorResponse),
+        (status = 403, description = "Only the member itself or an admin can change its capabilities", body = ErrorResponse),
+        (status = 404, description = "Member not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.register_member_capabilities",
+    skip(state, user, capabilities),
+    fields(member_id = %id)
+)]
+async fn register_capabilities(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(capabilities): Json<Capabilities>,
+) -> Response {
+    if let Some(response) = ensure_can_edit(&state, &user, &id) {
+        return response;
+    }
+    let mut member = match visible_member(&state, &user, &id).await {
+        Ok(member) => member,
+        Err(response) => return response,
+    };
+    let Ok(member_type) = id.parse::<MemberId>().map(|id| id.member_type()) else {
+        return bad_request("id must be a valid member id");
+    };
+    if let Some(response) = check_capabilities(member_type, &capabilities) {
+        return response;
+    }
+    member.capabilities = capabilities;
+
+    match state.members.update(&member).await {
+        Ok(true) => {
+            state
+                .audit
+                .record(AuditEvent::new(
+                    &user.member_id,
+                    AuditAction::MemberProfileUpdated,
+                    format!("member:{id}"),
+                ))
+                .await;
+            (StatusCode::OK, Json(member.capabilities)).into_response()
+        }
+        Ok(false) => member_not_found(),
+        Err(err) => repository_error_response(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::router::build_routes;
+    use crate::router::test_support::*;
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn agents_register_capabilities_and_members_filter_by_them() {
+        let app = build_routes();
+        for agent in ["nexis:agent:reviewer", "nexis:agent:translator"] {
+            let response = app
+                .clone()
+                .oneshot(request(
+                    agent,
+                    "POST",
+                    "/v1/members",
+                    json!({ "id": agent }),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+
+        let reviewer = "/v1/members/nexis:agent:reviewer/capabilities";
+        let capabilities = json!({
+            "contentTypes": ["text", "code"],
+            "tools": ["run_tests"],
+            "maxContextTokens": 32000
+        });
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:agent:translator",
+                "PUT",
+                reviewer,
+                capabilities.clone(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:agent:reviewer",
+                "PUT",
+                reviewer,
+                json!({ "tools": [" "] }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:agent:reviewer",
+                "PUT",
+                reviewer,
+                capabilities.clone(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:agent:translator",
+                "PUT",
+                "/v1/members/nexis:agent:translator/capabilities",
+                json!({ "languages": ["en", "de", "fr"] }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(request("nexis:human:alice", "GET", reviewer, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await, capabilities);
+
+        let matching = |query: &'static str| {
+            let app = app.clone();
+            let request = request(
+                "nexis:human:alice",
+                "GET",
+                &format!("/v1/members?{query}"),
+                Value::Null,
+            );
+            async move {
+                let listed = json_body(app.oneshot(request).await.unwrap()).await;
+                listed["members"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|member| member["id"].as_str().unwrap().to_string())
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(
+            matching("contentTypes=code&maxContextTokens=16000").await,
+            vec!["nexis:agent:reviewer"]
+        );
+        assert_eq!(
+            matching("languages=de-CH,fr").await,
+            vec!["nexis:agent:translator"]
+        );
+        assert!(matching("tools=run_tests&languages=de").await.is_empty());
+        assert_eq!(matching("").await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn member_directory_crud_and_permissions() {
+        let app = build_routes();
+        let agent = "nexis:agent:openai/reviewer";
+        // Identifiers may contain `/`, so it is percent-encoded in the path.
+        let path = "/v1/members/nexis:agent:openai%2Freviewer";
+        let profile = json!({
+            "id": agent,
+            "displayName": "Reviewer",
+            "avatarUrl": "https://example.com/reviewer.png",
+            "capabilities": { "tools": ["code-review"] }
+        });
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                "/v1/members",
+                profile.clone(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request(agent, "POST", "/v1/members", profile.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = app
+            .clone()
+            .oneshot(request(agent, "POST", "/v1/members", profile))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                "/v1/members",
+                json!({ "id": "nexis:human:alice", "capabilities": { "tools": ["code-review"] } }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(request("nexis:human:alice", "GET", path, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let loaded = json_body(response).await;
+        assert_eq!(loaded["memberType"], "agent");
+        assert_eq!(loaded["displayName"], "Reviewer");
+        assert_eq!(loaded["capabilities"], json!({ "tools": ["code-review"] }));
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                agent,
+                "PUT",
+                path,
+                json!({ "displayName": "Senior Reviewer", "publicKey": "c2hvcnQ=" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(request(
+                agent,
+                "PUT",
+                path,
+                json!({ "displayName": "Senior Reviewer" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "GET",
+                "/v1/members",
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        let listed = json_body(response).await;
+        assert_eq!(listed["total"], 1);
+        assert_eq!(listed["members"][0]["displayName"], "Senior Reviewer");
+        assert_eq!(listed["members"][0]["capabilities"], json!({}));
+
+        let response = app
+            .clone()
+            .oneshot(request("nexis:human:alice", "DELETE", path, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request(agent, "DELETE", path, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app
+            .oneshot(request(agent, "GET", path, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
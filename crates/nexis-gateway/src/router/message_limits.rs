@@ -0,0 +1,283 @@
+//! Size limits on message text and metadata, and long pastes.
+//!
+//! `rate_limits.max_message_bytes` and `rate_limits.max_metadata_bytes` cap
+//! a message's text and its `metadata` object; a room's `maxMessageBytes`
+//! and `maxMetadataBytes` settings replace them there. Anything larger is
+//! refused with `413 PAYLOAD_TOO_LARGE`, naming the field, the limit and
+//! the size sent.
+//!
+//! Text longer than `rate_limits.paste_attachment_bytes` is kept as a
+//! `text/plain` upload attached to the message, whose text becomes a short
+//! preview. Signed and streamed messages keep their text as sent, and so
+//! does every message while file uploads are unavailable.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use nexis_protocol::AttachmentRef;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use super::settings::room_settings;
+use super::{error_codes, uploads, SharedState};
+use crate::auth::AuthenticatedUser;
+
+/// Largest text limit a room may set, in bytes.
+pub(super) const MAX_ROOM_MESSAGE_BYTES: usize = 1024 * 1024;
+/// Largest metadata limit a room may set, in bytes.
+pub(super) const MAX_ROOM_METADATA_BYTES: usize = 64 * 1024;
+/// Characters of a long paste kept in the message text.
+const PREVIEW_CHARS: usize = 280;
+const PASTE_FILE_NAME: &str = "paste.txt";
+
+/// Limits in force in one room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct MessageLimits {
+    pub(super) max_message_bytes: usize,
+    pub(super) max_metadata_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TooLargeResponse {
+    error: String,
+    code: &'static str,
+    /// Request field over its limit: `text` or `metadata`.
+    field: &'static str,
+    limit_bytes: usize,
+    size_bytes: usize,
+}
+
+/// Limits for messages sent to the room.
+pub(super) async fn room_limits(state: &SharedState, room_id: &str) -> MessageLimits {
+    let settings = room_settings(state, room_id).await;
+    let defaults = &state.config.rate_limits;
+    MessageLimits {
+        max_message_bytes: settings
+            .max_message_bytes
+            .unwrap_or(defaults.max_message_bytes),
+        max_metadata_bytes: settings
+            .max_metadata_bytes
+            .unwrap_or(defaults.max_metadata_bytes),
+    }
+}
+
+impl MessageLimits {
+    /// Refuse `text` or `metadata` when over their limit.
+    #[allow(clippy::result_large_err)]
+    pub(super) fn check(
+        &self,
+        text: &str,
+        metadata: Option<&Map<String, Value>>,
+    ) -> Result<(), Response> {
+        if text.len() > self.max_message_bytes {
+            return Err(too_large("text", self.max_message_bytes, text.len()));
+        }
+        let metadata_bytes = metadata.map_or(0, |metadata| {
+            serde_json::to_vec(metadata).map_or(usize::MAX, |json| json.len())
+        });
+        if metadata_bytes > self.max_metadata_bytes {
+            return Err(too_large(
+                "metadata",
+                self.max_metadata_bytes,
+                metadata_bytes,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Store `text` as an attachment when it is a long paste, returning the
+/// preview to send in its place along with the attachment.
+pub(super) async fn stash_paste(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    text: &str,
+) -> Option<(String, AttachmentRef)> {
+    let threshold = state.config.rate_limits.paste_attachment_bytes;
+    if threshold == 0 || text.len() <= threshold {
+        return None;
+    }
+    let attachment = uploads::store_text(state, user, PASTE_FILE_NAME, text.to_string()).await?;
+    Some((preview(text), attachment))
+}
+
+/// The start of `text`, cut at a whitespace within [`PREVIEW_CHARS`] when
+/// there is one.
+fn preview(text: &str) -> String {
+    let text = text.trim_start();
+    let Some((end, _)) = text.char_indices().nth(PREVIEW_CHARS) else {
+        return text.to_string();
+    };
+    let head = &text[..end];
+    let head = head
+        .rfind(char::is_whitespace)
+        .map_or(head, |space| &head[..space]);
+    format!("{}…", head.trim_end())
+}
+
+fn too_large(field: &'static str, limit_bytes: usize, size_bytes: usize) -> Response {
+    let response = TooLargeResponse {
+        error: format!("{field} exceeds the maximum of {limit_bytes} bytes ({size_bytes} sent)"),
+        code: error_codes::PAYLOAD_TOO_LARGE,
+        field,
+        limit_bytes,
+        size_bytes,
+    };
+    (StatusCode::PAYLOAD_TOO_LARGE, Json(response)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blobs::LocalBlobStore;
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    #[test]
+    fn previews_end_on_a_word_boundary() {
+        assert_eq!(preview("  short paste"), "short paste");
+
+        let long = "word ".repeat(100);
+        let cut = preview(&long);
+        assert!(cut.ends_with("word…"));
+        assert!(cut.chars().count() <= PREVIEW_CHARS + 1);
+
+        let unbroken = "é".repeat(PREVIEW_CHARS * 2);
+        assert_eq!(preview(&unbroken).chars().count(), PREVIEW_CHARS + 1);
+    }
+
+    #[tokio::test]
+    async fn oversized_fields_are_named_with_their_limit() {
+        let limits = MessageLimits {
+            max_message_bytes: 8,
+            max_metadata_bytes: 16,
+        };
+        assert!(limits.check("hello", None).is_ok());
+
+        let response = limits.check("hello world", None).unwrap_err();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = json_body(response).await;
+        assert_eq!(body["code"], "PAYLOAD_TOO_LARGE");
+        assert_eq!(body["field"], "text");
+        assert_eq!(body["limitBytes"], 8);
+        assert_eq!(body["sizeBytes"], 11);
+
+        let mut metadata = Map::new();
+        metadata.insert("client".to_string(), Value::from("nexis-web 1.2"));
+        let response = limits.check("hello", Some(&metadata)).unwrap_err();
+        let body = json_body(response).await;
+        assert_eq!(body["field"], "metadata");
+        assert_eq!(body["sizeBytes"], 26);
+    }
+
+    #[tokio::test]
+    async fn message_limits_apply_per_room_and_long_pastes_become_attachments() {
+        let root = std::env::temp_dir().join(format!("nexis-pastes-{}", Uuid::new_v4()));
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        config.rate_limits.max_message_bytes = 4_096;
+        config.rate_limits.max_metadata_bytes = 32;
+        config.rate_limits.paste_attachment_bytes = 1_024;
+        let state = AppState {
+            config: Arc::new(config),
+            blobs: Some(Arc::new(LocalBlobStore::new(&root))),
+            ..AppState::default()
+        };
+        let app = routes(state.clone());
+
+        let room_id = new_room(&app, "admin", "logs").await;
+        let settings_uri = format!("/v1/rooms/{room_id}/settings");
+        let send = |text: &str, metadata: Value| {
+            let body = json!({
+                "roomId": room_id,
+                "sender": "nexis:human:alice",
+                "text": text,
+                "metadata": metadata,
+            });
+            app.clone()
+                .oneshot(request("nexis:human:alice", "POST", "/v1/messages", body))
+        };
+        let paste = "2026-10-17 12:00:00 INFO request served in 3ms\n".repeat(50);
+
+        let response = send("hi", json!({ "client": "nexis-web 1.2" }))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = send("hi", json!({ "client": "nexis-web 1.2", "build": 4711 }))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let rejected = json_body(response).await;
+        assert_eq!(rejected["code"], "PAYLOAD_TOO_LARGE");
+        assert_eq!(rejected["field"], "metadata");
+        assert_eq!(rejected["limitBytes"], 32);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PATCH",
+                &settings_uri,
+                json!({ "maxMessageBytes": 100, "maxMetadataBytes": 64 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["maxMessageBytes"], 100);
+        let response = send(&paste, json!(null)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let rejected = json_body(response).await;
+        assert_eq!(rejected["field"], "text");
+        assert_eq!(rejected["limitBytes"], 100);
+        assert_eq!(rejected["sizeBytes"], paste.len());
+        let response = send("hi", json!({ "client": "nexis-web 1.2", "build": 4711 }))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PATCH",
+                &settings_uri,
+                json!({ "maxMessageBytes": null }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = send(&paste, json!(null)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let messages = state.room_messages.read().await;
+        let message = messages[&room_id]
+            .iter()
+            .find(|message| message.id == id)
+            .unwrap();
+        assert!(message.text.ends_with('…'));
+        assert!(paste.starts_with(message.text.trim_end_matches('…')));
+        assert_eq!(message.attachments.len(), 1);
+        assert_eq!(message.attachments[0].file_name, "paste.txt");
+        assert_eq!(message.attachments[0].mime_type, "text/plain");
+        assert_eq!(message.attachments[0].size, paste.len() as u64);
+        assert_eq!(
+            messages[&room_id][0].metadata.as_ref().unwrap()["client"],
+            "nexis-web 1.2"
+        );
+        drop(messages);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
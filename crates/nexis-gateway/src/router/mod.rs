@@ -1,68 +1,606 @@
 //! Message routing for Nexus Gateway
 
 use axum::{
-    extract::ws::{Message, WebSocket, WebSocketUpgrade},
-    extract::{Path, Query, State},
+    extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+    extract::{DefaultBodyLimit, MatchedPath, Path, Query, State},
     http::{HeaderValue, Request, StatusCode},
     middleware::{self, Next},
-    response::{Html, IntoResponse, Response},
+    response::{IntoResponse, Response},
     routing::{get, post},
-    Json, Router,
+    Extension, Json, Router,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::{mpsc, RwLock, Semaphore};
+use tokio::sync::{broadcast, mpsc, RwLock, Semaphore};
 use tracing::Instrument;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-use crate::auth::AuthenticatedUser;
+use crate::audit::{AuditAction, AuditEvent, AuditLog, AuditQuery, AuditResult};
+use crate::auth::{AuthenticatedUser, JwtConfig};
+use crate::blobs::{BlobStore, LocalBlobStore};
+use crate::cluster::{
+    ConnectionLocation, ConnectionRegistry, EventBus, InMemoryConnectionRegistry,
+};
+use crate::config::{NexisConfig, ProviderKind};
+use crate::db::{
+    migrations, DatabasePool, FeedbackRepository, InMemoryMemberRepository,
+    InMemoryReadMarkerRepository, MemberRepository, MembershipRecord, MembershipRepository,
+    Message as MessageRecord, MessageRepository, ReadMarkerRepository, RepositoryError,
+    Room as RoomRecord, RoomRepository, RoomSettingsRepository, SanctionRepository, Storage,
+    UploadRepository,
+};
+use crate::email::Mailer;
+use crate::generation_log::{GenerationLog, GenerationLogEntry};
+use crate::indexing::{BatchingIndexingQueue, IndexTask};
 use crate::metrics::{
-    export as export_metrics, HTTP_LATENCY, HTTP_REQUESTS_TOTAL, HTTP_RESPONSES, MESSAGES_SENT,
-    OPERATION_ERRORS_TOTAL, OPERATION_LATENCY, OPERATION_THROUGHPUT_TOTAL, ROOMS_ACTIVE,
-    ROOMS_CREATED_TOTAL,
+    export as export_metrics, record_ai_request, record_broadcast_lag, record_http_request,
+    record_search, record_ws_connection_closed, record_ws_connection_opened,
+    record_ws_connection_reaped, MESSAGES_SENT, OPERATION_ERRORS_TOTAL, OPERATION_LATENCY,
+    OPERATION_THROUGHPUT_TOTAL, ROOMS_ACTIVE, ROOMS_CREATED_TOTAL,
+};
+use crate::moderation::ModerationService;
+use crate::notifications::{MessageNotice, NotificationService};
+use crate::scheduler::Scheduler;
+use crate::search::{
+    KeywordIndexing, LexicalSearchService, RetentionPolicies, SearchError, SearchRequest,
+    SearchService,
+};
+use crate::server::ShutdownController;
+use crate::spam::SpamGuard;
+use crate::webhooks::{WebhookError, WebhookEvent, WebhookService};
+use nexis_context::{ContextWindow, ConversationCache, Message as ContextMessage, PromptAssembler};
+use nexis_core::archive::{ArchivedMessage, ArchivedRoom, RoomArchive, ARCHIVE_CONTENT_TYPE};
+use nexis_core::permission::{Action, PermissionChecker, Permissions};
+use nexis_protocol::codec::Encoding;
+use nexis_protocol::signing::VerifyingKey;
+use nexis_protocol::{AttachmentRef, DelegatedTask, MessageContent, RoomId};
+use nexis_runtime::transcription::openai::DEFAULT_TRANSCRIPTION_MODEL;
+use nexis_runtime::{
+    AIProvider, AnthropicProvider, CostTracker, GenerateRequest, GenerateResponse,
+    OpenAICompatibleProvider, OpenAIProvider, OpenAITranscriptionProvider, ProviderError,
+    RoomBudgets, TranscriptionProvider,
 };
-use crate::search::{SearchError, SearchRequest, SearchService};
+use validation::Validator;
 
 #[cfg(feature = "multi-tenant")]
-use crate::auth::TenantStore;
+use crate::tenants::{TenantAccessError, TenantDirectory};
+
+mod admin;
+mod batches;
+mod branches;
+mod cluster;
+mod costs;
+mod dto;
+mod encoding;
+mod feedback;
+mod generation_log;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+mod history;
+mod invites;
+mod joins;
+mod members;
+mod message_limits;
+mod moderation;
+mod notifications;
+#[cfg(feature = "oidc")]
+mod oidc;
+mod openapi;
+mod read_markers;
+mod room_budgets;
+mod sanctions;
+mod schedules;
+mod session;
+mod settings;
+mod signing_keys;
+mod similar;
+mod slow_mode;
+mod spam;
+mod streams;
+mod tasks;
+#[cfg(feature = "multi-tenant")]
+mod tenant_admin;
+#[cfg(test)]
+mod test_support;
+mod transcripts;
+mod uploads;
+mod validation;
 
 #[derive(Clone)]
 struct AppState {
+    config: Arc<NexisConfig>,
+    /// Installed as a request extension; unset means the environment-derived
+    /// fallback config is installed instead.
+    jwt: Option<JwtConfig>,
     rooms: Arc<RwLock<HashMap<String, Room>>>,
     room_messages: Arc<RwLock<HashMap<String, Vec<StoredMessage>>>>,
+    /// Durable copies of `rooms` and `room_messages`; new rooms and messages
+    /// are written here before they join the in-memory working set.
+    room_store: Arc<dyn RoomRepository>,
+    message_store: Arc<dyn MessageRepository>,
+    /// Pool behind the stores; `/readyz` reports its schema version.
+    database: Option<DatabasePool>,
+    /// Members of each room; written to `membership_store` before they
+    /// change here.
     room_members: Arc<RwLock<HashMap<String, Vec<String>>>>,
+    membership_store: Arc<dyn MembershipRepository>,
+    /// Registered Ed25519 keys; messages from these members must be signed.
+    signing_keys: Arc<RwLock<HashMap<String, VerifyingKey>>>,
+    /// Member directory backing `/v1/members`.
+    members: Arc<dyn MemberRepository>,
+    read_markers: Arc<dyn ReadMarkerRepository>,
+    /// Unset when the configured blob store could not be built.
+    blobs: Option<Arc<dyn BlobStore>>,
+    /// Upload metadata by id; written to `upload_store` before it changes
+    /// here.
+    uploads: Arc<RwLock<HashMap<String, uploads::Upload>>>,
+    upload_store: Arc<dyn UploadRepository>,
+    /// Delegated tasks by id, across all rooms.
+    tasks: Arc<RwLock<HashMap<String, DelegatedTask>>>,
+    /// Last message each member acknowledged over WebSocket, keyed by
+    /// member and room; resumed subscriptions replay from here.
+    delivery_cursors: Arc<RwLock<HashMap<(String, String), String>>>,
     write_gate: Arc<Semaphore>,
     search_service: Option<Arc<dyn SearchService>>,
+    /// Keyword index that serves search when no search service is
+    /// configured; stored messages are added to it as they are published.
+    lexical_index: Option<Arc<LexicalSearchService>>,
+    /// Queue published messages are added to `lexical_index` through;
+    /// without one they are indexed as they are published.
+    indexing_queue: Option<Arc<BatchingIndexingQueue>>,
+    ai_provider: Option<Arc<dyn AIProvider>>,
+    /// Every provider with an API key in `[providers]`, by name, for AI
+    /// requests and rooms that pick one.
+    ai_providers: Arc<HashMap<String, Arc<dyn AIProvider>>>,
+    /// Speech-to-text for `/v1/rooms/:id/transcribe`.
+    transcriber: Option<Arc<dyn TranscriptionProvider>>,
+    /// Monthly AI spending per member and provider.
+    costs: Arc<CostTracker>,
+    /// AI calls and tokens per room today.
+    room_budgets: Arc<RoomBudgets>,
+    /// Unset when `[moderation] provider` is `none` or failed to build.
+    moderation: Option<Arc<dyn ModerationService>>,
+    /// Room settings, including overrides of the configured moderation
+    /// policy; written to `settings_store` before they change here.
+    room_settings: Arc<RwLock<HashMap<String, settings::RoomSettings>>>,
+    settings_store: Arc<dyn RoomSettingsRepository>,
+    /// Unused invitations by token.
+    invitations: Arc<RwLock<HashMap<String, joins::Invitation>>>,
+    /// Pending requests to join approval-required rooms, by room.
+    join_requests: Arc<RwLock<HashMap<String, Vec<joins::JoinRequest>>>>,
+    /// Bans and mutes by room, expired ones included until they are swept;
+    /// written to `sanction_store` before they change here.
+    sanctions: Arc<RwLock<HashMap<String, Vec<sanctions::Sanction>>>>,
+    sanction_store: Arc<dyn SanctionRepository>,
+    /// When each member last sent to each room, for slow mode; keyed by
+    /// room and member.
+    last_sent: Arc<std::sync::Mutex<HashMap<(String, String), Instant>>>,
+    /// Recent activity of members in rooms, weighed for spam.
+    spam: Arc<SpamGuard>,
+    /// Messages being streamed into rooms, by the id they will be sent with.
+    streams: Arc<RwLock<HashMap<String, streams::MessageStream>>>,
+    /// AI replies that can be regenerated, by the id of the first generation.
+    generations: Arc<RwLock<HashMap<String, branches::Generation>>>,
+    /// Batch generation jobs by id, for polling.
+    generation_batches: Arc<RwLock<HashMap<String, batches::Batch>>>,
+    /// Ratings of AI messages, summed up on the messages themselves.
+    feedback: Arc<dyn FeedbackRepository>,
+    /// Vector document retention per room and tenant, applied by a
+    /// [`crate::search::RetentionSweeper`] sharing it.
+    retention: Arc<RetentionPolicies>,
+    /// Scheduled room jobs; they only run once the runner is spawned.
+    scheduler: Scheduler,
+    prompt_assembler: PromptAssembler,
+    /// Recent history of each room, loaded from `room_messages` on the
+    /// room's first AI request.
+    conversations: Arc<ConversationCache>,
+    room_events: broadcast::Sender<RoomEvent>,
+    /// Carries room events to other gateway instances; unset on a single node.
+    cluster: Option<Arc<dyn EventBus>>,
+    /// Origin stamped on events this instance publishes to the cluster.
+    instance_id: Arc<str>,
+    /// Instances holding each member's WebSocket connections.
+    connections: Arc<dyn ConnectionRegistry>,
+    webhooks: WebhookService,
+    notifications: NotificationService,
+    /// Sends invitation and notification email; unset without `[email]`.
+    mailer: Option<Arc<Mailer>>,
+    audit: AuditLog,
+    /// Prompts and responses of recent generations; unset unless
+    /// `[generation_log] enabled` is on.
+    generation_log: Option<GenerationLog>,
+    shutdown: ShutdownController,
     #[cfg(feature = "multi-tenant")]
-    tenant_store: TenantStore,
+    tenants: TenantDirectory,
+    #[cfg(feature = "oidc")]
+    oidc: Option<Arc<crate::oidc::OidcClient>>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
+        let config = NexisConfig::default();
+        let lexical_index = Arc::new(LexicalSearchService::new());
+        let storage = Storage::default();
+        let instance_id = crate::cluster::instance_id(&config.cluster).into();
         Self {
+            write_gate: Arc::new(Semaphore::new(config.rate_limits.max_concurrent_writes)),
+            blobs: Some(Arc::new(LocalBlobStore::new(config.uploads.path.clone()))),
+            config: Arc::new(config),
+            jwt: None,
             rooms: Arc::new(RwLock::new(HashMap::new())),
             room_messages: Arc::new(RwLock::new(HashMap::new())),
+            room_store: storage.rooms,
+            message_store: storage.messages,
+            database: storage.database,
             room_members: Arc::new(RwLock::new(HashMap::new())),
-            write_gate: Arc::new(Semaphore::new(2_048)),
-            search_service: None,
+            membership_store: storage.memberships,
+            signing_keys: Arc::new(RwLock::new(HashMap::new())),
+            members: Arc::new(InMemoryMemberRepository::new()),
+            read_markers: Arc::new(InMemoryReadMarkerRepository::new()),
+            uploads: Arc::new(RwLock::new(HashMap::new())),
+            upload_store: storage.uploads,
+            tasks: Arc::new(RwLock::new(HashMap::new())),
+            delivery_cursors: Arc::new(RwLock::new(HashMap::new())),
+            search_service: Some(lexical_index.clone()),
+            lexical_index: Some(lexical_index),
+            indexing_queue: None,
+            ai_provider: None,
+            ai_providers: Arc::new(HashMap::new()),
+            transcriber: None,
+            costs: Arc::new(CostTracker::default()),
+            room_budgets: Arc::new(RoomBudgets::default()),
+            moderation: None,
+            room_settings: Arc::new(RwLock::new(HashMap::new())),
+            settings_store: storage.room_settings,
+            invitations: Arc::new(RwLock::new(HashMap::new())),
+            join_requests: Arc::new(RwLock::new(HashMap::new())),
+            sanctions: Arc::new(RwLock::new(HashMap::new())),
+            sanction_store: storage.sanctions,
+            last_sent: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            spam: Arc::new(SpamGuard::new()),
+            streams: Arc::new(RwLock::new(HashMap::new())),
+            generations: Arc::new(RwLock::new(HashMap::new())),
+            generation_batches: Arc::new(RwLock::new(HashMap::new())),
+            feedback: storage.feedback,
+            retention: Arc::new(RetentionPolicies::new()),
+            scheduler: Scheduler::default(),
+            prompt_assembler: PromptAssembler::new(ContextWindow::default()),
+            conversations: Arc::new(ConversationCache::default()),
+            room_events: broadcast::channel(ROOM_EVENT_CAPACITY).0,
+            cluster: None,
+            instance_id,
+            connections: Arc::new(InMemoryConnectionRegistry::new()),
+            webhooks: WebhookService::new(),
+            notifications: NotificationService::new(),
+            mailer: None,
+            audit: AuditLog::default(),
+            generation_log: None,
+            shutdown: ShutdownController::new(),
             #[cfg(feature = "multi-tenant")]
-            tenant_store: TenantStore::new(),
+            tenants: TenantDirectory::default(),
+            #[cfg(feature = "oidc")]
+            oidc: None,
         }
     }
 }
 
 impl AppState {
+    fn with_config(mut self, config: Arc<NexisConfig>) -> Self {
+        self.write_gate = Arc::new(Semaphore::new(config.rate_limits.max_concurrent_writes));
+        self.jwt = Some(config.auth.jwt_config());
+        self.transcriber = configured_transcriber(&config);
+        self.ai_providers = Arc::new(configured_ai_providers(&config));
+        self.costs = Arc::new(config.costs.tracker());
+        self.room_budgets = Arc::new(config.costs.room_budgets());
+        self.scheduler = Scheduler::from_config(&config.scheduler);
+        self.moderation = match crate::moderation::from_config(&config) {
+            Ok(service) => service.map(Arc::from),
+            Err(err) => {
+                tracing::error!("Moderation unavailable: {}", err);
+                None
+            }
+        };
+        self.blobs = match crate::blobs::from_config(&config.uploads) {
+            Ok(store) => Some(Arc::from(store)),
+            Err(err) => {
+                tracing::error!("Blob store unavailable: {}", err);
+                None
+            }
+        };
+        #[cfg(feature = "oidc")]
+        {
+            self.oidc = crate::oidc::OidcClient::from_config(&config.oidc).map(Arc::new);
+        }
+        self.instance_id = crate::cluster::instance_id(&config.cluster).into();
+        self.cluster = match crate::cluster::from_config(&config.cluster) {
+            Ok(bus) => bus,
+            Err(err) => {
+                tracing::error!("Cluster event bus unavailable: {}", err);
+                None
+            }
+        };
+        self.connections = crate::cluster::registry_from_config(&config.cluster);
+        self.mailer = match crate::email::from_config(&config.email) {
+            Ok(mailer) => mailer.map(Arc::new),
+            Err(err) => {
+                tracing::error!("Email unavailable: {}", err);
+                None
+            }
+        };
+        self.notifications =
+            NotificationService::from_config(&config.notifications, self.mailer.clone());
+        self.generation_log = match GenerationLog::from_config(&config.generation_log) {
+            Ok(log) => log,
+            Err(err) => {
+                tracing::error!("Generation log unavailable: {}", err);
+                None
+            }
+        };
+        self.config = config;
+        self
+    }
+
+    fn with_audit(mut self, audit: AuditLog) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    fn with_shutdown(mut self, shutdown: ShutdownController) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Write rooms and messages through to `storage`, starting from the
+    /// rooms and messages it restored.
+    fn with_storage(mut self, storage: Storage) -> Self {
+        let mut rooms = HashMap::new();
+        let mut room_messages = HashMap::new();
+        let feedback = feedback::summaries(&storage.restored_feedback);
+        for (record, messages) in storage.restored {
+            let room = Room::from(record);
+            let mut messages: Vec<StoredMessage> =
+                messages.into_iter().map(StoredMessage::from).collect();
+            for message in &mut messages {
+                message.feedback = feedback.get(message.id.as_str()).copied();
+                self.index_message(&room.id, room.tenant(), message);
+            }
+            room_messages.insert(room.id.clone(), messages);
+            rooms.insert(room.id.clone(), room);
+        }
+        ROOMS_ACTIVE.set(rooms.len() as f64);
+        self.rooms = Arc::new(RwLock::new(rooms));
+        self.room_messages = Arc::new(RwLock::new(room_messages));
+        let room_settings = storage
+            .restored_settings
+            .into_iter()
+            .map(|record| {
+                (
+                    record.room_id.clone(),
+                    settings::RoomSettings::from_record(record),
+                )
+            })
+            .collect();
+        self.room_settings = Arc::new(RwLock::new(room_settings));
+        let mut room_members: HashMap<String, Vec<String>> = HashMap::new();
+        for record in storage.restored_memberships {
+            room_members
+                .entry(record.room_id)
+                .or_default()
+                .push(record.member_id);
+        }
+        self.room_members = Arc::new(RwLock::new(room_members));
+        self.sanctions = Arc::new(RwLock::new(sanctions::restore(storage.restored_sanctions)));
+        self.uploads = Arc::new(RwLock::new(uploads::restore(storage.restored_uploads)));
+        self.room_store = storage.rooms;
+        self.message_store = storage.messages;
+        self.settings_store = storage.room_settings;
+        self.membership_store = storage.memberships;
+        self.sanction_store = storage.sanctions;
+        self.upload_store = storage.uploads;
+        self.feedback = storage.feedback;
+        self.database = storage.database;
+        self
+    }
+
+    #[cfg(feature = "multi-tenant")]
+    fn with_tenants(mut self, tenants: TenantDirectory) -> Self {
+        self.tenants = tenants;
+        self
+    }
+
     fn with_search_service(mut self, service: Arc<dyn SearchService>) -> Self {
         self.search_service = Some(service);
+        self.lexical_index = None;
+        self.indexing_queue = None;
         self
     }
+
+    /// Search `indexing.index`, adding published messages to it through
+    /// `indexing.queue`. Call before [`with_storage`](Self::with_storage) so
+    /// restored messages land in the same index.
+    fn with_keyword_indexing(mut self, indexing: KeywordIndexing) -> Self {
+        self.search_service = Some(indexing.index.clone());
+        self.lexical_index = Some(indexing.index);
+        self.indexing_queue = Some(indexing.queue);
+        self
+    }
+
+    fn with_retention(mut self, retention: Arc<RetentionPolicies>) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    fn with_ai_provider(mut self, provider: Arc<dyn AIProvider>) -> Self {
+        self.ai_provider = Some(provider);
+        self
+    }
+
+    /// The AI provider called `name`, or the default one without a name.
+    fn ai_provider_named(&self, name: Option<&str>) -> Option<Arc<dyn AIProvider>> {
+        let Some(name) = name else {
+            return self.ai_provider.clone();
+        };
+        self.ai_provider
+            .as_ref()
+            .filter(|provider| provider.name() == name)
+            .or_else(|| self.ai_providers.get(name))
+            .cloned()
+    }
+
+    /// Add a stored message to the keyword index, if search falls back to it.
+    fn index_message(&self, room_id: &str, tenant: Option<&str>, message: &StoredMessage) {
+        let Some(index) = &self.lexical_index else {
+            return;
+        };
+        let mut metadata = nexis_vector::DocumentMetadata::new()
+            .with_content_type("text")
+            .with_extra("sender", serde_json::json!(message.sender));
+        if let Ok(room_id) = room_id.parse::<RoomId>() {
+            metadata = metadata.with_room(room_id);
+        }
+        if let Some(tenant) = tenant {
+            metadata = metadata.with_tenant(tenant);
+        }
+        // Client-assigned ids of signed messages need not be UUIDs.
+        let id = crate::search::document_id(&message.id).unwrap_or_else(Uuid::new_v4);
+        index.index(id, message.text.clone(), metadata.with_message(id));
+    }
+
+    /// Add a published message to the keyword index through the indexing
+    /// queue, or right away without one.
+    async fn index_published(&self, room_id: &str, tenant: Option<&str>, message: &StoredMessage) {
+        let (Some(queue), Ok(room)) = (&self.indexing_queue, room_id.parse::<RoomId>()) else {
+            self.index_message(room_id, tenant, message);
+            return;
+        };
+        let task = IndexTask::new(
+            message.text.clone(),
+            room,
+            serde_json::json!({ "messageId": message.id, "sender": message.sender }),
+        );
+        let task = match tenant {
+            Some(tenant) => task.with_tenant(tenant),
+            None => task,
+        };
+        if let Err(err) = queue.enqueue(task).await {
+            tracing::warn!(message_id = %message.id, error = %err, "Indexing queue refused message; indexing it directly");
+            self.index_message(room_id, tenant, message);
+        }
+    }
+
+    /// Tenant owning a room, if the room exists and has one.
+    async fn room_tenant(&self, room_id: &str) -> Option<String> {
+        self.rooms
+            .read()
+            .await
+            .get(room_id)
+            .and_then(|room| room.tenant().map(str::to_string))
+    }
+
+    /// Persist a new room before it joins `rooms`.
+    async fn store_room(&self, room: &Room) -> Result<(), RepositoryError> {
+        self.room_store.insert(&room.to_record(Utc::now())).await
+    }
+
+    /// Persist `member_id` joining a room before it joins `room_members`;
+    /// `false` when they were already a member.
+    async fn add_member(&self, room_id: &str, member_id: &str) -> Result<bool, RepositoryError> {
+        let mut members = self.room_members.write().await;
+        let room_members = members.entry(room_id.to_string()).or_default();
+        if room_members.iter().any(|member| member == member_id) {
+            return Ok(false);
+        }
+        let record = MembershipRecord {
+            room_id: room_id.to_string(),
+            member_id: member_id.to_string(),
+            joined_at: Utc::now(),
+        };
+        self.membership_store.add(&record).await?;
+        room_members.push(record.member_id);
+        Ok(true)
+    }
+
+    /// Take `member_id` out of a room, in storage first; `false` when they
+    /// were not a member.
+    async fn remove_member(&self, room_id: &str, member_id: &str) -> Result<bool, RepositoryError> {
+        let mut members = self.room_members.write().await;
+        let Some(room_members) = members.get_mut(room_id) else {
+            return Ok(false);
+        };
+        if !room_members.iter().any(|member| member == member_id) {
+            return Ok(false);
+        }
+        self.membership_store.remove(room_id, member_id).await?;
+        room_members.retain(|member| member != member_id);
+        Ok(true)
+    }
+
+    /// Persist a message before it joins `room_messages`, recording the room
+    /// its attachments were posted in. Look `tenant` up before taking the
+    /// `room_messages` lock.
+    async fn store_message(
+        &self,
+        room_id: &str,
+        tenant: Option<&str>,
+        message: &StoredMessage,
+    ) -> Result<(), RepositoryError> {
+        self.message_store
+            .insert(&message.to_record(room_id, tenant))
+            .await?;
+        uploads::posted(self, room_id, &message.attachments).await;
+        Ok(())
+    }
+
+    /// Fan a room event out to subscribed WebSocket clients, webhooks and
+    /// the other gateway instances, and notify the room's members of
+    /// messages.
+    async fn publish(&self, event: RoomEvent) {
+        if let RoomEvent::Message { room_id, message } = &event {
+            self.conversations
+                .append(room_id, &message.id, context_message(message))
+                .await;
+            let tenant = self.room_tenant(room_id).await;
+            if self.lexical_index.is_some() {
+                self.index_published(room_id, tenant.as_deref(), message)
+                    .await;
+            }
+            self.webhooks
+                .dispatch(
+                    room_id,
+                    WebhookEvent::MessageCreated,
+                    serde_json::json!({ "message": message }),
+                )
+                .await;
+            let members = self
+                .room_members
+                .read()
+                .await
+                .get(room_id)
+                .cloned()
+                .unwrap_or_default();
+            let notice = MessageNotice {
+                room_id,
+                message_id: &message.id,
+                sender: &message.sender,
+                text: &message.text,
+                tenant: tenant.as_deref(),
+            };
+            self.notifications.notify_message(notice, &members).await;
+        }
+        self.publish_remote(&event);
+        // No receivers simply means nobody is subscribed right now.
+        let _ = self.room_events.send(event);
+    }
 }
 
 type SharedState = AppState;
-const MAX_MESSAGE_TEXT_LEN: usize = 32 * 1024;
-const OPENAPI_JSON: &str = include_str!("openapi.json");
+const MAX_ARCHIVE_BYTES: usize = 64 * 1024 * 1024;
+const AI_MEMBER_ID: &str = "nexis:ai:assistant";
+const ROOM_EVENT_CAPACITY: usize = 1_024;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Room {
@@ -75,7 +613,83 @@ struct Room {
     tenant_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl Room {
+    fn tenant(&self) -> Option<&str> {
+        #[cfg(feature = "multi-tenant")]
+        return self.tenant_id.as_deref();
+        #[cfg(not(feature = "multi-tenant"))]
+        None
+    }
+
+    /// Rooms are only visible inside the tenant that owns them. Callers
+    /// without a tenant claim see only rooms that have no tenant.
+    fn is_visible_to(&self, user: &AuthenticatedUser) -> bool {
+        self.tenant() == caller_tenant(user)
+    }
+}
+
+/// Tenant the caller acts in; always `None` without the `multi-tenant` feature.
+fn caller_tenant(user: &AuthenticatedUser) -> Option<&str> {
+    #[cfg(feature = "multi-tenant")]
+    return user
+        .tenant_context
+        .as_ref()
+        .map(|tenant| tenant.tenant_id.as_str());
+    #[cfg(not(feature = "multi-tenant"))]
+    {
+        let _ = user;
+        None
+    }
+}
+
+#[cfg(feature = "multi-tenant")]
+fn tenant_access_response(err: TenantAccessError) -> Response {
+    let (status, code) = match &err {
+        TenantAccessError::Suspended(_) => (StatusCode::FORBIDDEN, error_codes::TENANT_SUSPENDED),
+        TenantAccessError::QuotaExceeded { .. } => {
+            (StatusCode::TOO_MANY_REQUESTS, error_codes::QUOTA_EXCEEDED)
+        }
+        TenantAccessError::Store(store_err) => {
+            tracing::error!("Tenant store error: {}", store_err);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::internal_error()),
+            )
+                .into_response();
+        }
+    };
+    (
+        status,
+        Json(ErrorResponse {
+            error: err.to_string(),
+            code: Some(code),
+        }),
+    )
+        .into_response()
+}
+
+/// Reject a new room when `tenant` already has as many rooms as its quota allows.
+#[cfg(feature = "multi-tenant")]
+async fn check_room_quota(
+    state: &SharedState,
+    rooms: &HashMap<String, Room>,
+    tenant: Option<&str>,
+) -> Result<(), Response> {
+    let Some(tenant) = tenant else {
+        return Ok(());
+    };
+    let current = rooms
+        .values()
+        .filter(|room| room.tenant() == Some(tenant))
+        .count();
+    state
+        .tenants
+        .check_rooms(tenant, current)
+        .await
+        .map_err(tenant_access_response)
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct CreateRoomRequest {
     name: String,
     #[serde(default)]
@@ -85,13 +699,13 @@ struct CreateRoomRequest {
     tenant_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct CreateRoomResponse {
     id: String,
     name: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct SendMessageRequest {
     #[serde(rename = "roomId")]
     room_id: String,
@@ -99,23 +713,174 @@ struct SendMessageRequest {
     text: String,
     #[serde(rename = "replyTo", default)]
     reply_to: Option<String>,
+    /// Client-assigned id; required for signed messages.
+    #[serde(default)]
+    id: Option<String>,
+    /// Client timestamp; required for signed messages.
+    #[serde(rename = "createdAt", default)]
+    created_at: Option<DateTime<Utc>>,
+    /// Base64 Ed25519 signature over the NIP-002 form of the message.
+    #[serde(default)]
+    signature: Option<String>,
+    /// Ids of files previously sent to `POST /v1/uploads`.
+    #[serde(default)]
+    attachments: Vec<String>,
+    /// Free-form JSON object stored with the message, such as client hints.
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    metadata: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Stream opened with `POST /v1/rooms/:id/streams` whose finished text
+    /// this is; the message takes the stream's id.
+    #[serde(rename = "streamId", default)]
+    stream_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct SendMessageResponse {
     id: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct StoredMessage {
     id: String,
     sender: String,
     text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     reply_to: Option<String>,
+    created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<AttachmentRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    metadata: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Set when moderation flagged the message but let it through.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    moderation: Option<moderation::MessageModeration>,
+    /// Set when message retention tombstoned the message.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    deleted: bool,
+    /// Set on regenerated AI replies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<branches::MessageBranch>,
+    /// Ratings of an AI message, once it has any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    feedback: Option<feedback::FeedbackSummary>,
+}
+
+/// Event pushed to WebSocket clients subscribed to a room.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RoomEvent {
+    Message {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        message: StoredMessage,
+    },
+    /// A member's read marker moved forward.
+    ReadMarker {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "memberId")]
+        member_id: String,
+        #[serde(rename = "messageId")]
+        message_id: String,
+    },
+    /// A delegated task was opened or changed status.
+    Task {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        task: DelegatedTask,
+    },
+    /// A member's presence in the room changed.
+    Presence {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "memberId")]
+        member_id: String,
+        status: PresenceStatus,
+    },
+    /// The next chunk of a message still being streamed; the finished
+    /// message follows as a `message` event with the same id.
+    MessageDelta {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "messageId")]
+        message_id: String,
+        sender: String,
+        delta: String,
+    },
+    /// A member was kicked, banned or muted, or a ban or mute was lifted.
+    Sanction {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "memberId")]
+        member_id: String,
+        action: sanctions::SanctionAction,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+        #[serde(default, rename = "expiresAt", skip_serializing_if = "Option::is_none")]
+        expires_at: Option<DateTime<Utc>>,
+    },
+}
+
+impl RoomEvent {
+    fn room_id(&self) -> &str {
+        match self {
+            Self::Message { room_id, .. }
+            | Self::ReadMarker { room_id, .. }
+            | Self::Task { room_id, .. }
+            | Self::Presence { room_id, .. }
+            | Self::MessageDelta { room_id, .. }
+            | Self::Sanction { room_id, .. } => room_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PresenceStatus {
+    /// The member's last WebSocket connection closed or was reaped.
+    Offline,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct WebSocketParams {
+    /// Framing of every frame on the connection.
+    #[serde(default)]
+    encoding: Encoding,
+    /// Access token of clients that can't send an `Authorization` header,
+    /// such as browsers.
+    #[serde(default)]
+    token: Option<String>,
+}
+
+/// Control frame sent by WebSocket clients.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientFrame {
+    Subscribe {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        /// Last message the client saw; messages after it are replayed.
+        #[serde(default, rename = "lastEventId")]
+        last_event_id: Option<String>,
+    },
+    Unsubscribe {
+        #[serde(rename = "roomId")]
+        room_id: String,
+    },
+    /// The client has seen every message of the room up to `event_id`.
+    Ack {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "eventId")]
+        event_id: String,
+    },
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct RoomInfoResponse {
     id: String,
     name: String,
@@ -127,25 +892,85 @@ struct RoomInfoResponse {
     tenant_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+struct RoomAiRequest {
+    prompt: String,
+    /// Provider to ask instead of the room's or the default one.
+    #[serde(default)]
+    provider: Option<String>,
+    /// Model to use; must be allowed in the room when an allow-list is
+    /// configured.
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(rename = "maxTokens", default)]
+    max_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct RoomAiResponse {
+    #[serde(rename = "messageId")]
+    message_id: String,
+    content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(rename = "contextMessages")]
+    context_messages: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct InviteMemberRequest {
     #[serde(rename = "memberId")]
     member_id: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct InviteMemberResponse {
     room_id: String,
     member_id: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+struct RegisterWebhookRequest {
+    url: String,
+    #[serde(default)]
+    events: Vec<WebhookEvent>,
+}
+
+/// Webhook as returned on create and rotate, the only times the secret is shown.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct WebhookWithSecretResponse {
+    #[serde(flatten)]
+    webhook: crate::webhooks::Webhook,
+    secret: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct ListWebhooksResponse {
+    webhooks: Vec<crate::webhooks::Webhook>,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct ListDeliveriesResponse {
+    deliveries: Vec<crate::webhooks::DeliveryAttempt>,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct ImportRoomResponse {
+    id: String,
+    name: String,
+    members: usize,
+    messages: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct ListRoomsResponse {
     rooms: Vec<RoomSummary>,
     total: usize,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct RoomSummary {
     id: String,
     name: String,
@@ -155,15 +980,35 @@ struct RoomSummary {
     member_count: Option<usize>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct ListRoomsQuery {
     #[serde(default)]
     limit: Option<usize>,
     #[serde(default)]
     offset: Option<usize>,
+    /// Only rooms whose name contains this text, ignoring case.
+    #[serde(default)]
+    q: Option<String>,
+    #[serde(default)]
+    sort: RoomSort,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// Order of `GET /v1/rooms` results; ties are broken by room id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum RoomSort {
+    /// By name, ignoring case.
+    #[default]
+    Name,
+    /// Most members first.
+    Members,
+    /// Most recent message first; rooms without messages last.
+    Activity,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct SearchQueryParams {
     q: String,
     #[serde(default = "default_limit")]
@@ -171,10 +1016,15 @@ struct SearchQueryParams {
     #[serde(default)]
     min_score: Option<f32>,
     #[serde(default)]
-    room_id: Option<Uuid>,
+    room_id: Option<RoomId>,
+    #[serde(default)]
+    content_type: Option<String>,
+    /// Also search for corrected spellings and synonyms of the query.
+    #[serde(default)]
+    expand: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 struct SearchApiRequest {
     query: String,
     #[serde(default = "default_limit")]
@@ -182,42 +1032,75 @@ struct SearchApiRequest {
     #[serde(default)]
     min_score: Option<f32>,
     #[serde(default)]
-    room_id: Option<Uuid>,
+    room_id: Option<RoomId>,
+    #[serde(default)]
+    content_type: Option<String>,
+    /// Also search for corrected spellings and synonyms of the query.
+    #[serde(default)]
+    expand: bool,
 }
 
 fn default_limit() -> usize {
     10
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct SearchApiResponse {
     query: String,
     results: Vec<SearchResultItem>,
     total: usize,
+    /// Terms searched for alongside the query when it was expanded.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    expanded_terms: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct SearchResultItem {
     id: Uuid,
+    /// Vector similarity to the query.
     score: f32,
+    /// Relevance from the re-ranking stage, when one ordered the results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rerank_score: Option<f32>,
     content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    room_id: Option<Uuid>,
+    room_id: Option<RoomId>,
 }
 
 mod error_codes {
     pub const BAD_REQUEST: &str = "BAD_REQUEST";
+    pub const VALIDATION_FAILED: &str = "VALIDATION_FAILED";
+    pub const UNAUTHORIZED: &str = "UNAUTHORIZED";
+    pub const INVALID_SIGNATURE: &str = "INVALID_SIGNATURE";
+    pub const FORBIDDEN: &str = "FORBIDDEN";
     pub const NOT_FOUND: &str = "NOT_FOUND";
+    pub const CONFLICT: &str = "CONFLICT";
     pub const INTERNAL_ERROR: &str = "INTERNAL_ERROR";
     pub const SERVICE_UNAVAILABLE: &str = "SERVICE_UNAVAILABLE";
+    pub const PAYLOAD_TOO_LARGE: &str = "PAYLOAD_TOO_LARGE";
+    pub const UNSUPPORTED_MEDIA_TYPE: &str = "UNSUPPORTED_MEDIA_TYPE";
     pub const INVALID_QUERY: &str = "INVALID_QUERY";
     pub const SEARCH_UNAVAILABLE: &str = "SEARCH_UNAVAILABLE";
+    pub const AI_UNAVAILABLE: &str = "AI_UNAVAILABLE";
+    pub const AI_PROVIDER_ERROR: &str = "AI_PROVIDER_ERROR";
+    pub const BUDGET_EXCEEDED: &str = "BUDGET_EXCEEDED";
+    pub const ROOM_BUDGET_EXCEEDED: &str = "ROOM_BUDGET_EXCEEDED";
+    pub const CONTENT_REJECTED: &str = "CONTENT_REJECTED";
+    pub const MEMBER_BANNED: &str = "MEMBER_BANNED";
+    pub const MEMBER_MUTED: &str = "MEMBER_MUTED";
+    pub const SLOW_MODE: &str = "SLOW_MODE";
+    pub const TOO_MANY_STREAMS: &str = "TOO_MANY_STREAMS";
+    #[cfg(feature = "multi-tenant")]
+    pub const TENANT_SUSPENDED: &str = "TENANT_SUSPENDED";
+    #[cfg(feature = "multi-tenant")]
+    pub const QUOTA_EXCEEDED: &str = "QUOTA_EXCEEDED";
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 struct ErrorResponse {
     error: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
     code: Option<&'static str>,
 }
 
@@ -229,6 +1112,27 @@ impl ErrorResponse {
         }
     }
 
+    fn unauthorized(message: impl Into<String>) -> Self {
+        Self {
+            error: message.into(),
+            code: Some(error_codes::UNAUTHORIZED),
+        }
+    }
+
+    fn invalid_signature(message: impl Into<String>) -> Self {
+        Self {
+            error: message.into(),
+            code: Some(error_codes::INVALID_SIGNATURE),
+        }
+    }
+
+    fn forbidden(message: impl Into<String>) -> Self {
+        Self {
+            error: message.into(),
+            code: Some(error_codes::FORBIDDEN),
+        }
+    }
+
     fn not_found(message: impl Into<String>) -> Self {
         Self {
             error: message.into(),
@@ -236,6 +1140,13 @@ impl ErrorResponse {
         }
     }
 
+    fn conflict(message: impl Into<String>) -> Self {
+        Self {
+            error: message.into(),
+            code: Some(error_codes::CONFLICT),
+        }
+    }
+
     fn internal_error() -> Self {
         Self {
             error: "An internal error occurred. Please try again later.".to_string(),
@@ -249,6 +1160,29 @@ impl ErrorResponse {
             code: Some(error_codes::SERVICE_UNAVAILABLE),
         }
     }
+
+    fn payload_too_large(message: impl Into<String>) -> Self {
+        Self {
+            error: message.into(),
+            code: Some(error_codes::PAYLOAD_TOO_LARGE),
+        }
+    }
+
+    fn unsupported_media_type(message: impl Into<String>) -> Self {
+        Self {
+            error: message.into(),
+            code: Some(error_codes::UNSUPPORTED_MEDIA_TYPE),
+        }
+    }
+}
+
+impl From<WebhookError> for ErrorResponse {
+    fn from(err: WebhookError) -> Self {
+        match err {
+            WebhookError::InvalidUrl(_) => Self::bad_request(err.to_string()),
+            WebhookError::NotFound => Self::not_found(err.to_string()),
+        }
+    }
 }
 
 impl From<SearchError> for ErrorResponse {
@@ -259,99 +1193,293 @@ impl From<SearchError> for ErrorResponse {
                 error: "Invalid search query".to_string(),
                 code: Some("INVALID_QUERY"),
             },
-            SearchError::EmbeddingError(_) | SearchError::VectorError(_) => Self::internal_error(),
+            SearchError::EmbeddingError(_)
+            | SearchError::VectorError(_)
+            | SearchError::RerankError(_)
+            | SearchError::ExpansionError(_) => Self::internal_error(),
+            SearchError::NotFound(_) => Self::not_found("message not found"),
         }
     }
 }
 
 /// Build the main router for the gateway
 pub fn build_routes() -> Router {
-    let state = AppState::default();
-
-    Router::new()
-        .route("/health", get(health_check))
-        .route("/metrics", get(metrics_handler))
-        .route("/openapi.json", get(openapi_json))
-        .route("/docs", get(swagger_ui))
-        .route("/ws", get(websocket_handler))
-        .route("/v1/rooms", get(list_rooms).post(create_room))
-        .route("/v1/rooms/:id", get(get_room).delete(delete_room))
-        .route("/v1/rooms/:id/invite", post(invite_member))
-        .route("/v1/messages", post(send_message))
-        .route("/v1/search", get(search_messages_get).post(search_messages))
-        .merge(crate::collaboration::routes())
-        .layer(middleware::from_fn(correlation_id_middleware))
-        .with_state(state)
+    routes(AppState::default())
 }
 
 /// Build router with search service
 pub fn build_routes_with_search(search_service: Arc<dyn SearchService>) -> Router {
-    let state = AppState::default().with_search_service(search_service);
+    routes(AppState::default().with_search_service(search_service))
+}
+
+/// Build router with a search service whose vector documents are swept by
+/// a [`crate::search::RetentionSweeper`] reading `retention`; the admin
+/// retention endpoints edit it.
+pub fn build_routes_with_retention(
+    search_service: Arc<dyn SearchService>,
+    retention: Arc<RetentionPolicies>,
+) -> Router {
+    routes(
+        AppState::default()
+            .with_search_service(search_service)
+            .with_retention(retention),
+    )
+}
+
+/// Build router with an AI provider for room-aware generation
+pub fn build_routes_with_ai(ai_provider: Arc<dyn AIProvider>) -> Router {
+    routes(AppState::default().with_ai_provider(ai_provider))
+}
+
+/// Build router from a validated gateway config and an optional AI provider.
+///
+/// Rooms and messages are written through to `storage` and start out as the
+/// ones it restored; messages are searched through the keyword index in
+/// `indexing` and added to it through its queue. Security-relevant actions
+/// are written to `audit`. WebSocket connections close with a going-away
+/// frame when `shutdown` is triggered and are tracked by it until they
+/// finish. Scheduled room jobs run in the background until
+/// then, unless `[scheduler] enabled` is off, and so do deliveries of events
+/// other instances publish and connection registry heartbeats when
+/// `[cluster]` names an event bus, and notification digests unless
+/// `[notifications] enabled` is off.
+pub fn build_routes_with_config(
+    config: Arc<NexisConfig>,
+    ai_provider: Option<Arc<dyn AIProvider>>,
+    audit: AuditLog,
+    shutdown: ShutdownController,
+    storage: Storage,
+    indexing: KeywordIndexing,
+) -> Router {
+    let state = AppState::default()
+        .with_config(config)
+        .with_audit(audit)
+        .with_shutdown(shutdown)
+        .with_keyword_indexing(indexing)
+        .with_storage(storage);
+    let state = match ai_provider {
+        Some(provider) => state.with_ai_provider(provider),
+        None => state,
+    };
+    if state.config.scheduler.enabled {
+        schedules::spawn_runner(&state);
+    }
+    cluster::spawn_relay(&state);
+    notifications::spawn_digests(&state);
+    routes(state)
+}
+
+/// Like [`build_routes_with_config`], with tenants resolved and their quotas
+/// enforced through `tenants`.
+#[cfg(feature = "multi-tenant")]
+pub fn build_routes_with_tenants(
+    config: Arc<NexisConfig>,
+    ai_provider: Option<Arc<dyn AIProvider>>,
+    audit: AuditLog,
+    shutdown: ShutdownController,
+    storage: Storage,
+    indexing: KeywordIndexing,
+    tenants: TenantDirectory,
+) -> Router {
+    let state = AppState::default()
+        .with_config(config)
+        .with_audit(audit)
+        .with_shutdown(shutdown)
+        .with_keyword_indexing(indexing)
+        .with_storage(storage)
+        .with_tenants(tenants);
+    let state = match ai_provider {
+        Some(provider) => state.with_ai_provider(provider),
+        None => state,
+    };
+    if state.config.scheduler.enabled {
+        schedules::spawn_runner(&state);
+    }
+    cluster::spawn_relay(&state);
+    notifications::spawn_digests(&state);
+    routes(state)
+}
 
-    Router::new()
+fn routes(state: AppState) -> Router {
+    // Always install a config so revocations are shared across requests.
+    let jwt = state
+        .jwt
+        .clone()
+        .unwrap_or_else(crate::auth::fallback_jwt_config);
+    let router = Router::new()
         .route("/health", get(health_check))
+        .route("/readyz", get(readiness_check))
         .route("/metrics", get(metrics_handler))
-        .route("/openapi.json", get(openapi_json))
-        .route("/docs", get(swagger_ui))
         .route("/ws", get(websocket_handler))
         .route("/v1/rooms", get(list_rooms).post(create_room))
         .route("/v1/rooms/:id", get(get_room).delete(delete_room))
+        .route(
+            "/v1/rooms/import",
+            post(import_room).layer(DefaultBodyLimit::max(MAX_ARCHIVE_BYTES)),
+        )
+        .route("/v1/rooms/:id/export", get(export_room))
         .route("/v1/rooms/:id/invite", post(invite_member))
+        .route("/v1/rooms/:id/ai", post(room_ai))
+        .route(
+            "/v1/rooms/:id/webhooks",
+            get(list_webhooks).post(register_webhook),
+        )
+        .route(
+            "/v1/rooms/:id/webhooks/:webhook_id/rotate",
+            post(rotate_webhook_secret),
+        )
+        .route(
+            "/v1/rooms/:id/webhooks/:webhook_id/disable",
+            post(disable_webhook),
+        )
+        .route(
+            "/v1/rooms/:id/webhooks/:webhook_id/deliveries",
+            get(list_webhook_deliveries),
+        )
         .route("/v1/messages", post(send_message))
         .route("/v1/search", get(search_messages_get).post(search_messages))
-        .merge(crate::collaboration::routes())
+        .route("/v1/audit", get(list_audit_events))
+        .merge(session::routes())
+        .merge(admin::routes())
+        .merge(members::routes())
+        .merge(history::routes())
+        .merge(read_markers::routes())
+        .merge(signing_keys::routes())
+        .merge(uploads::routes(state.config.uploads.max_bytes))
+        .merge(transcripts::routes())
+        .merge(costs::routes())
+        .merge(room_budgets::routes())
+        .merge(moderation::routes())
+        .merge(settings::routes())
+        .merge(joins::routes())
+        .merge(invites::routes())
+        .merge(sanctions::routes())
+        .merge(notifications::routes())
+        .merge(similar::routes())
+        .merge(schedules::routes())
+        .merge(streams::routes())
+        .merge(branches::routes())
+        .merge(feedback::routes())
+        .merge(generation_log::routes())
+        .merge(batches::routes())
+        .merge(tasks::routes())
+        .merge(openapi::routes())
+        .merge(crate::collaboration::routes());
+    #[cfg(feature = "oidc")]
+    let router = router.merge(oidc::routes());
+    #[cfg(feature = "multi-tenant")]
+    let router = router
+        .merge(tenant_admin::routes())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            tenant_admin::tenant_admission,
+        ));
+    #[cfg(feature = "grpc")]
+    let grpc_state = state.clone();
+    let router = router
         .layer(middleware::from_fn(correlation_id_middleware))
         .with_state(state)
+        .layer(Extension(jwt));
+    #[cfg(feature = "grpc")]
+    let router = router.clone().merge(grpc::routes(grpc_state, router));
+    router
 }
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    summary = "Health check",
+    security(()),
+    responses((status = 200, description = "The gateway is up", body = String))
+)]
 async fn health_check() -> &'static str {
     "OK"
 }
 
-async fn metrics_handler() -> impl IntoResponse {
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ReadinessResponse {
+    status: &'static str,
+    /// Applied migration version; absent when rooms live in memory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema_version: Option<i64>,
+    latest_schema_version: i64,
+}
+
+/// Readiness probe: fails while the database is unreachable or behind the
+/// schema this build expects.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    summary = "Readiness check",
+    security(()),
+    responses(
+        (status = 200, description = "Ready to serve traffic", body = ReadinessResponse),
+        (status = 503, description = "Database unreachable or behind the expected schema", body = ReadinessResponse),
+    )
+)]
+async fn readiness_check(State(state): State<SharedState>) -> Response {
+    let latest_schema_version = migrations::latest_version();
+    let Some(pool) = &state.database else {
+        return (
+            StatusCode::OK,
+            Json(ReadinessResponse {
+                status: "ready",
+                schema_version: None,
+                latest_schema_version,
+            }),
+        )
+            .into_response();
+    };
+
+    let (status, code, schema_version) = match migrations::current_version(pool).await {
+        Ok(version) if version.unwrap_or(0) >= latest_schema_version => {
+            ("ready", StatusCode::OK, version)
+        }
+        Ok(version) => (
+            "migrations_pending",
+            StatusCode::SERVICE_UNAVAILABLE,
+            version,
+        ),
+        Err(err) => {
+            tracing::warn!("Readiness check failed: {}", err);
+            (
+                "database_unavailable",
+                StatusCode::SERVICE_UNAVAILABLE,
+                None,
+            )
+        }
+    };
     (
-        StatusCode::OK,
-        [("content-type", "text/plain; version=0.0.4; charset=utf-8")],
-        export_metrics(),
+        code,
+        Json(ReadinessResponse {
+            status,
+            schema_version,
+            latest_schema_version,
+        }),
     )
+        .into_response()
 }
 
-async fn openapi_json() -> impl IntoResponse {
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    summary = "Prometheus metrics",
+    security(()),
+    responses((status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain"))
+)]
+async fn metrics_handler() -> impl IntoResponse {
     (
         StatusCode::OK,
-        [("content-type", "application/json; charset=utf-8")],
-        OPENAPI_JSON,
+        [("content-type", "text/plain; version=0.0.4; charset=utf-8")],
+        export_metrics(),
     )
 }
 
-async fn swagger_ui() -> impl IntoResponse {
-    const SWAGGER_HTML: &str = r##"<!doctype html>
-<html>
-  <head>
-    <meta charset="utf-8" />
-    <meta name="viewport" content="width=device-width, initial-scale=1" />
-    <title>Nexis Gateway API Docs</title>
-    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
-  </head>
-  <body>
-    <div id="swagger-ui"></div>
-    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
-    <script>
-      window.ui = SwaggerUIBundle({
-        url: "/openapi.json",
-        dom_id: "#swagger-ui",
-        deepLinking: true,
-        docExpansion: "list"
-      });
-    </script>
-  </body>
-</html>
-"##;
-
-    Html(SWAGGER_HTML)
-}
-
 fn record_operation_success(operation: &str, start: Instant) {
     OPERATION_THROUGHPUT_TOTAL
         .with_label_values(&[operation])
@@ -370,10 +1498,94 @@ fn record_operation_error(operation: &str, error_type: &str, start: Instant) {
         .observe(start.elapsed().as_secs_f64());
 }
 
+/// Log a storage failure and answer with a generic `500`.
+fn storage_error_response(err: RepositoryError) -> Response {
+    tracing::error!("Storage error: {}", err);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::internal_error()),
+    )
+        .into_response()
+}
+
+fn configured_ai_providers(config: &NexisConfig) -> HashMap<String, Arc<dyn AIProvider>> {
+    let mut providers = HashMap::new();
+    for kind in [
+        ProviderKind::OpenAI,
+        ProviderKind::Anthropic,
+        ProviderKind::Ollama,
+        ProviderKind::Vllm,
+    ] {
+        let settings = config.providers.get(kind);
+        // Local servers need no key; naming one enables it.
+        let enabled = if kind.is_local() {
+            settings.base_url.is_some() || config.providers.default == Some(kind)
+        } else {
+            settings.api_key.is_some()
+        };
+        if !enabled {
+            continue;
+        }
+        let api_key = settings.api_key.clone().unwrap_or_default();
+        let base_url = settings
+            .base_url
+            .clone()
+            .unwrap_or_else(|| kind.default_base_url().to_string());
+        let model = settings
+            .model
+            .clone()
+            .unwrap_or_else(|| kind.default_model().to_string());
+        let provider: Arc<dyn AIProvider> = match kind {
+            ProviderKind::OpenAI => Arc::new(OpenAIProvider::new(api_key, base_url, model)),
+            ProviderKind::Anthropic => Arc::new(AnthropicProvider::new(api_key, base_url, model)),
+            ProviderKind::Ollama | ProviderKind::Vllm => Arc::new(
+                OpenAICompatibleProvider::new(kind.as_str(), base_url, model)
+                    .with_api_key(settings.api_key.clone()),
+            ),
+        };
+        providers.insert(kind.as_str().to_string(), provider);
+    }
+    providers
+}
+
+/// Whisper via the OpenAI settings, whenever an OpenAI key is configured.
+fn configured_transcriber(config: &NexisConfig) -> Option<Arc<dyn TranscriptionProvider>> {
+    let settings = &config.providers.openai;
+    let api_key = settings.api_key.clone()?;
+    let base_url = settings
+        .base_url
+        .clone()
+        .unwrap_or_else(|| ProviderKind::OpenAI.default_base_url().to_string());
+    Some(Arc::new(OpenAITranscriptionProvider::new(
+        api_key,
+        base_url,
+        DEFAULT_TRANSCRIPTION_MODEL,
+    )))
+}
+
+fn provider_error_type(err: &ProviderError) -> &'static str {
+    match err {
+        ProviderError::MockQueueEmpty | ProviderError::Message(_) => "provider",
+        ProviderError::Transport(_) => "transport",
+        ProviderError::HttpStatus { .. } => "http_status",
+        ProviderError::Decode(_) => "decode",
+        ProviderError::RetryExhausted { .. } => "retry_exhausted",
+        ProviderError::Unsupported(_) => "unsupported",
+        ProviderError::Cancelled => "cancelled",
+        ProviderError::Overloaded(_) => "overloaded",
+    }
+}
+
 async fn correlation_id_middleware(request: Request<axum::body::Body>, next: Next) -> Response {
     let started = Instant::now();
     let method = request.method().to_string();
     let path = request.uri().path().to_string();
+    // Label metrics by route template so room and webhook ids stay out of them.
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", MatchedPath::as_str)
+        .to_string();
 
     let correlation_id = request
         .headers()
@@ -385,8 +1597,12 @@ async fn correlation_id_middleware(request: Request<axum::body::Body>, next: Nex
         "gateway.http.request",
         correlation_id = %correlation_id,
         method = %method,
-        path = %path
+        path = %path,
+        route = %route,
+        otel.kind = "server"
     );
+    #[cfg(feature = "otel")]
+    crate::observability::set_parent_from_headers(&span, request.headers());
     let mut response = next.run(request).instrument(span).await;
     response.headers_mut().insert(
         "x-correlation-id",
@@ -394,16 +1610,7 @@ async fn correlation_id_middleware(request: Request<axum::body::Body>, next: Nex
             .unwrap_or_else(|_| HeaderValue::from_static("invalid-correlation-id")),
     );
 
-    let status = response.status().as_u16().to_string();
-    HTTP_REQUESTS_TOTAL
-        .with_label_values(&[&method, &path])
-        .inc();
-    HTTP_RESPONSES
-        .with_label_values(&[&method, &path, &status])
-        .inc();
-    HTTP_LATENCY
-        .with_label_values(&[&method, &path])
-        .observe(started.elapsed().as_secs_f64());
+    record_http_request(&method, &route, response.status().as_u16(), started);
 
     if response.status().is_server_error() {
         OPERATION_ERRORS_TOTAL
@@ -415,18 +1622,80 @@ async fn correlation_id_middleware(request: Request<axum::body::Body>, next: Nex
 }
 
 /// WebSocket handler
-async fn websocket_handler(ws: WebSocketUpgrade) -> Response {
-    ws.on_upgrade(handle_socket)
+///
+/// Connections opened with a bearer token, or a `token` query parameter, are
+/// recorded in the connection registry under the token's member until they
+/// close. When a member's last connection closes, the rooms it was
+/// subscribed to get a `presence` event with status `offline`.
+async fn websocket_handler(
+    State(state): State<SharedState>,
+    Extension(jwt): Extension<JwtConfig>,
+    user: Option<AuthenticatedUser>,
+    Query(params): Query<WebSocketParams>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let user = user.or_else(|| {
+        let token = params.token.as_deref()?;
+        AuthenticatedUser::from_token(&jwt, token).ok()
+    });
+    if state.shutdown.is_triggered() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::service_unavailable(
+                "server is shutting down",
+            )),
+        )
+            .into_response();
+    }
+    let guard = state.shutdown.track();
+    ws.on_upgrade(move |socket| async move {
+        record_ws_connection_opened();
+        let member_id = user.as_ref().map(|user| user.member_id.clone());
+        let location = ConnectionLocation {
+            instance_id: state.instance_id.to_string(),
+            connection_id: Uuid::new_v4(),
+            connected_at: Utc::now(),
+        };
+        if let Some(member_id) = &member_id {
+            if let Err(err) = state.connections.register(member_id, &location).await {
+                tracing::warn!("Failed to register connection of {}: {}", member_id, err);
+            }
+        }
+        let subscribed = handle_socket(socket, state.clone(), user, params.encoding).await;
+        if let Some(member_id) = &member_id {
+            if let Err(err) = state
+                .connections
+                .unregister(member_id, location.connection_id)
+                .await
+            {
+                tracing::warn!("Failed to unregister connection of {}: {}", member_id, err);
+            }
+            announce_offline(&state, member_id, subscribed).await;
+        }
+        record_ws_connection_closed();
+        drop(guard);
+    })
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/rooms",
+    tag = "rooms",
+    summary = "Create room",
+    request_body = CreateRoomRequest,
+    responses(
+        (status = 201, description = "Room created", body = CreateRoomResponse),
+        (status = 400, description = "Invalid room", body = ErrorResponse),
+    )
+)]
 #[tracing::instrument(
     name = "gateway.create_room",
-    skip(state, _user, payload),
+    skip(state, user, payload),
     fields(room_name = %payload.name)
 )]
 async fn create_room(
     State(state): State<SharedState>,
-    _user: AuthenticatedUser,
+    user: AuthenticatedUser,
     Json(payload): Json<CreateRoomRequest>,
 ) -> impl IntoResponse {
     let started = Instant::now();
@@ -441,13 +1710,30 @@ async fn create_room(
     }
 
     #[cfg(feature = "multi-tenant")]
-    let tenant_id = payload.tenant_id.clone();
-
-    #[cfg(not(feature = "multi-tenant"))]
-    let _tenant_id: Option<String> = None;
+    let tenant_id = match (caller_tenant(&user), payload.tenant_id.as_deref()) {
+        (Some(own), Some(requested)) if own != requested => {
+            record_operation_error(operation, "forbidden", started);
+            return crate::auth::TenantError::CrossTenantAccess {
+                user_tenant: own.to_string(),
+                resource_tenant: requested.to_string(),
+            }
+            .into_response();
+        }
+        (None, Some(_)) => {
+            record_operation_error(operation, "forbidden", started);
+            return (
+                StatusCode::FORBIDDEN,
+                Json(ErrorResponse::forbidden(
+                    "a tenant-scoped token is required to create tenant rooms",
+                )),
+            )
+                .into_response();
+        }
+        (own, _) => own.map(str::to_string),
+    };
 
     let room = Room {
-        id: format!("room_{}", Uuid::new_v4().simple()),
+        id: RoomId::generate().to_string(),
         name: payload.name,
         topic: payload.topic,
         #[cfg(feature = "multi-tenant")]
@@ -469,67 +1755,220 @@ async fn create_room(
     };
 
     let mut rooms = state.rooms.write().await;
+    #[cfg(feature = "multi-tenant")]
+    if let Err(response) = check_room_quota(&state, &rooms, room.tenant()).await {
+        record_operation_error(operation, "quota", started);
+        return response;
+    }
+    if let Err(err) = state.store_room(&room).await {
+        record_operation_error(operation, "storage", started);
+        return storage_error_response(err);
+    }
+    // The creator is the room's first member.
+    if let Err(err) = state.add_member(&room.id, &user.member_id).await {
+        if let Err(cleanup) = state.room_store.delete(&room.id).await {
+            tracing::warn!("Failed to drop stored room {}: {}", room.id, cleanup);
+        }
+        record_operation_error(operation, "storage", started);
+        return storage_error_response(err);
+    }
+    let resource = format!("room:{}", room.id);
     rooms.insert(room.id.clone(), room);
     ROOMS_CREATED_TOTAL.inc();
     ROOMS_ACTIVE.set(rooms.len() as f64);
+    drop(rooms);
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::RoomCreated,
+            resource,
+        ))
+        .await;
     record_operation_success(operation, started);
 
     (StatusCode::CREATED, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/messages",
+    tag = "messages",
+    summary = "Send message",
+    request_body(content(
+        (SendMessageRequest = "application/json"),
+        (SendMessageRequest = "application/msgpack"),
+    )),
+    responses(
+        (status = 201, description = "Message stored and published", body = SendMessageResponse),
+        (status = 400, description = "Invalid message", body = ErrorResponse),
+        (status = 403, description = "Not a member of a room that isn't open, banned from or muted in the room, a sender other than the caller, or a bad signature", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+        (status = 409, description = "A message with this id already exists", body = ErrorResponse),
+        (status = 422, description = "Rejected by moderation", body = ErrorResponse),
+        (status = 429, description = "Slow mode is on and the sender must wait"),
+    )
+)]
 #[tracing::instrument(
     name = "gateway.send_message",
-    skip(state, _user, payload),
+    skip(state, user, payload),
     fields(room_id = %payload.room_id, sender = %payload.sender)
 )]
 async fn send_message(
     State(state): State<SharedState>,
-    _user: AuthenticatedUser,
-    Json(payload): Json<SendMessageRequest>,
+    user: AuthenticatedUser,
+    encoding::Body(payload): encoding::Body<SendMessageRequest>,
 ) -> impl IntoResponse {
     let started = Instant::now();
     let operation = "send_message";
-    if payload.room_id.trim().is_empty()
-        || payload.sender.trim().is_empty()
-        || payload.text.trim().is_empty()
+    let mut validator = Validator::new();
+    let room_id = validator.field("roomId", payload.room_id.parse::<RoomId>());
+    let sender = validator.field("sender", validation::member_id(&payload.sender));
+    let content = validator.field(
+        "text",
+        validation::text_content(&payload.text, !payload.attachments.is_empty()),
+    );
+    // Signatures only cover the text, so they can't vouch for attachments
+    // or metadata.
+    if payload.signature.is_some() && !payload.attachments.is_empty() {
+        validator.reject("attachments", "signed messages cannot carry attachments");
+    }
+    if payload.signature.is_some() && payload.metadata.is_some() {
+        validator.reject("metadata", "signed messages cannot carry metadata");
+    }
+    if let Some(stream_id) = &payload.stream_id {
+        if payload.signature.is_some() {
+            validator.reject("streamId", "signed messages cannot be streamed");
+        } else if let Err(message) =
+            streams::check_finish(&state, &user, &payload.room_id, stream_id, &payload.sender).await
+        {
+            validator.reject("streamId", message);
+        }
+    }
+    let ((room_id, sender), content) = match validator.finish(room_id.zip(sender).zip(content)) {
+        Ok(parsed) => parsed,
+        Err(response) => {
+            record_operation_error(operation, "validation", started);
+            return response;
+        }
+    };
+
+    if visible_room(&state, &user, room_id.as_str())
+        .await
+        .is_none()
     {
-        record_operation_error(operation, "validation", started);
+        record_operation_error(operation, "room_not_found", started);
         return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::bad_request(
-                "roomId, sender, and text are required",
-            )),
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("room not found")),
         )
             .into_response();
     }
-    if payload.text.len() > MAX_MESSAGE_TEXT_LEN {
-        record_operation_error(operation, "validation", started);
+    // Messages are attributed to their sender, so only admins may post on
+    // someone else's behalf.
+    if payload.sender != user.member_id && !state.config.auth.is_admin(&user.member_id) {
+        record_operation_error(operation, "forged_sender", started);
         return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::bad_request(
-                "text exceeds maximum length of 32768 characters",
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::forbidden(
+                "sender must be the authenticated member",
             )),
         )
             .into_response();
     }
-
-    let rooms = state.rooms.read().await;
-    if !rooms.contains_key(&payload.room_id) {
-        record_operation_error(operation, "room_not_found", started);
-        return (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse::not_found("room not found")),
-        )
-            .into_response();
+    if let Err(response) = joins::ensure_participant(&state, &user, room_id.as_str()).await {
+        record_operation_error(operation, "not_a_member", started);
+        return response;
+    }
+    if let Err(response) =
+        sanctions::check_can_send(&state, room_id.as_str(), &user.member_id).await
+    {
+        record_operation_error(operation, "sanctioned", started);
+        return response;
+    }
+    // Held until the message is stored; a refused message hands the slot back.
+    let slow_mode = match slow_mode::reserve(&state, &user, room_id.as_str()).await {
+        Ok(reservation) => reservation,
+        Err(response) => {
+            record_operation_error(operation, "slow_mode", started);
+            return response;
+        }
+    };
+    if let Err(response) = message_limits::room_limits(&state, room_id.as_str())
+        .await
+        .check(&payload.text, payload.metadata.as_ref())
+    {
+        record_operation_error(operation, "too_large", started);
+        return response;
+    }
+    if let Err(response) = spam::screen(&state, &user, room_id.as_str(), &payload.text).await {
+        record_operation_error(operation, "spam", started);
+        return response;
     }
-    drop(rooms);
 
-    let message = StoredMessage {
-        id: format!("msg_{}", Uuid::new_v4().simple()),
-        sender: payload.sender,
-        text: payload.text,
+    let attachments =
+        match uploads::resolve_attachments(&state, &user, room_id.as_str(), &payload.attachments)
+            .await
+        {
+            Ok(attachments) => attachments,
+            Err(response) => {
+                record_operation_error(operation, "validation", started);
+                return response;
+            }
+        };
+    let signed = match signing_keys::verify_message_signature(&state, &payload).await {
+        Ok(signed) => signed,
+        Err(response) => {
+            record_operation_error(operation, "signature", started);
+            return response;
+        }
+    };
+    let moderation =
+        match moderation::moderate(&state, &user, room_id.as_str(), &payload.text).await {
+            Ok(moderation) => moderation,
+            Err(response) => {
+                // The chunks were shown unchecked; a stream whose text is
+                // rejected can't be finished with other text instead.
+                if let (Some(stream_id), StatusCode::UNPROCESSABLE_ENTITY) =
+                    (&payload.stream_id, response.status())
+                {
+                    streams::close(&state, stream_id).await;
+                }
+                record_operation_error(operation, "moderation", started);
+                return response;
+            }
+        };
+    let (id, created_at) = match (&signed, &payload.stream_id) {
+        (Some(signed), _) => (signed.id.clone(), signed.created_at),
+        (None, Some(stream_id)) => (stream_id.clone(), Utc::now()),
+        (None, None) => (format!("msg_{}", Uuid::new_v4().simple()), Utc::now()),
+    };
+    let mut message = StoredMessage {
+        id,
+        sender: sender.to_string(),
+        text: match content {
+            Some(MessageContent::Text { text }) => text,
+            _ => String::new(),
+        },
         reply_to: payload.reply_to,
+        created_at,
+        signature: payload.signature,
+        attachments,
+        metadata: payload.metadata,
+        moderation,
+        deleted: false,
+        branch: None,
+        feedback: None,
     };
+    // Long pastes go to file storage; signed and streamed text stays as sent.
+    if message.signature.is_none() && payload.stream_id.is_none() {
+        if let Some((preview, attachment)) =
+            message_limits::stash_paste(&state, &user, &message.text).await
+        {
+            message.text = preview;
+            message.attachments.push(attachment);
+        }
+    }
     let response = SendMessageResponse {
         id: message.id.clone(),
     };
@@ -544,33 +1983,87 @@ async fn send_message(
     };
 
     let mut messages = state.room_messages.write().await;
-    messages.entry(payload.room_id).or_default().push(message);
+    let room_messages = messages.entry(room_id.to_string()).or_default();
+    // Client-assigned ids make a replayed signed message detectable.
+    if signed.is_some() && room_messages.iter().any(|stored| stored.id == message.id) {
+        record_operation_error(operation, "duplicate", started);
+        return (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::conflict("message id already exists in room")),
+        )
+            .into_response();
+    }
+    // Only messages that are about to be stored count towards the quota.
+    #[cfg(feature = "multi-tenant")]
+    if let Some(tenant_id) = caller_tenant(&user) {
+        if let Err(err) = state.tenants.consume_message(tenant_id).await {
+            record_operation_error(operation, "quota", started);
+            return tenant_access_response(err);
+        }
+    }
+    if let Err(err) = state
+        .store_message(room_id.as_str(), caller_tenant(&user), &message)
+        .await
+    {
+        record_operation_error(operation, "storage", started);
+        return storage_error_response(err);
+    }
+    room_messages.push(message.clone());
+    drop(messages);
+    if let Some(reservation) = slow_mode {
+        reservation.keep();
+    }
+    if let Some(stream_id) = &payload.stream_id {
+        streams::close(&state, stream_id).await;
+    }
+    state
+        .publish(RoomEvent::Message {
+            room_id: room_id.into(),
+            message,
+        })
+        .await;
     MESSAGES_SENT.inc();
     record_operation_success(operation, started);
 
     (StatusCode::CREATED, Json(response)).into_response()
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}",
+    tag = "rooms",
+    summary = "Get room details",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room and its messages, as JSON or MessagePack per `Accept`", content(
+            (RoomInfoResponse = "application/json"),
+            (RoomInfoResponse = "application/msgpack"),
+        )),
+        (status = 403, description = "The room isn't open and the caller is not a member", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
 #[tracing::instrument(
     name = "gateway.get_room",
-    skip(state, _user),
+    skip(state, user),
     fields(room_id = %id)
 )]
 async fn get_room(
     State(state): State<SharedState>,
-    _user: AuthenticatedUser,
+    user: AuthenticatedUser,
     Path(id): Path<String>,
+    accept: encoding::Accept,
 ) -> impl IntoResponse {
-    let rooms = state.rooms.read().await;
-    let Some(room) = rooms.get(&id) else {
+    let Some(room) = visible_room(&state, &user, &id).await else {
         return (
             StatusCode::NOT_FOUND,
             Json(ErrorResponse::not_found("room not found")),
         )
             .into_response();
     };
-    let room = room.clone();
-    drop(rooms);
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
+    }
 
     let messages = state
         .room_messages
@@ -594,17 +2087,31 @@ async fn get_room(
         tenant_id,
     };
 
-    (StatusCode::OK, Json(response)).into_response()
+    accept.respond(StatusCode::OK, &response)
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/invite",
+    tag = "rooms",
+    summary = "Add a member to the room",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = InviteMemberRequest,
+    responses(
+        (status = 200, description = "Member invited", body = InviteMemberResponse),
+        (status = 400, description = "Missing member id", body = ErrorResponse),
+        (status = 403, description = "The caller is neither an admin nor a member of the room, or the member is banned from it", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
 #[tracing::instrument(
     name = "gateway.invite_member",
-    skip(state, _user, payload),
+    skip(state, user, payload),
     fields(room_id = %id, member_id = %payload.member_id)
 )]
 async fn invite_member(
     State(state): State<SharedState>,
-    _user: AuthenticatedUser,
+    user: AuthenticatedUser,
     Path(id): Path<String>,
     Json(payload): Json<InviteMemberRequest>,
 ) -> impl IntoResponse {
@@ -616,17 +2123,56 @@ async fn invite_member(
             .into_response();
     }
 
-    let rooms = state.rooms.read().await;
-    if !rooms.contains_key(&id) {
+    let resource = format!("room:{id}/member:{}", payload.member_id);
+    if visible_room(&state, &user, &id).await.is_none() {
+        state
+            .audit
+            .record(
+                AuditEvent::new(&user.member_id, AuditAction::MemberInvited, resource)
+                    .with_result(AuditResult::Failed, "room not found"),
+            )
+            .await;
         return (
             StatusCode::NOT_FOUND,
             Json(ErrorResponse::not_found("room not found")),
         )
             .into_response();
     }
-    drop(rooms);
+
+    if !state.config.auth.is_admin(&user.member_id)
+        && !joins::is_member(&state, &id, &user.member_id).await
+    {
+        state
+            .audit
+            .record(
+                AuditEvent::new(&user.member_id, AuditAction::MemberInvited, resource)
+                    .with_result(AuditResult::Denied, "not a member of the room"),
+            )
+            .await;
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::forbidden(
+                "only admins and members of the room can invite",
+            )),
+        )
+            .into_response();
+    }
 
     let member_id = payload.member_id.clone();
+    if sanctions::is_banned(&state, &id, &member_id).await {
+        state
+            .audit
+            .record(
+                AuditEvent::new(&user.member_id, AuditAction::MemberInvited, resource)
+                    .with_result(AuditResult::Denied, "member is banned"),
+            )
+            .await;
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::forbidden("member is banned from the room")),
+        )
+            .into_response();
+    }
     let Ok(_permit) = state.write_gate.clone().acquire_owned().await else {
         return (
             StatusCode::SERVICE_UNAVAILABLE,
@@ -635,10 +2181,12 @@ async fn invite_member(
             .into_response();
     };
 
-    let mut members = state.room_members.write().await;
-    let room_members = members.entry(id.clone()).or_default();
-    if !room_members.contains(&member_id) {
-        room_members.push(member_id.clone());
+    let newly_invited = match state.add_member(&id, &member_id).await {
+        Ok(newly_invited) => newly_invited,
+        Err(err) => return storage_error_response(err),
+    };
+    if newly_invited {
+        member_invited(&state, &user, &id, &member_id).await;
     }
 
     let response = InviteMemberResponse {
@@ -649,591 +2197,4157 @@ async fn invite_member(
     (StatusCode::OK, Json(response)).into_response()
 }
 
-#[tracing::instrument(
-    name = "gateway.search_messages.post",
-    skip(state, _user, payload),
-    fields(limit = payload.limit)
-)]
-async fn search_messages(
-    State(state): State<SharedState>,
-    _user: AuthenticatedUser,
-    Json(payload): Json<SearchApiRequest>,
-) -> impl IntoResponse {
-    let Some(search_service) = state.search_service.as_ref() else {
-        return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "Search service not configured".to_string(),
-                code: Some(error_codes::SEARCH_UNAVAILABLE),
-            }),
+/// Audit and announce that `user` added `member_id` to room `id`.
+async fn member_invited(state: &SharedState, user: &AuthenticatedUser, id: &str, member_id: &str) {
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::MemberInvited,
+            format!("room:{id}/member:{member_id}"),
+        ))
+        .await;
+    state
+        .webhooks
+        .dispatch(
+            id,
+            WebhookEvent::MemberInvited,
+            serde_json::json!({ "memberId": member_id }),
         )
-            .into_response();
+        .await;
+}
+
+/// Look up a room the caller is allowed to see.
+async fn visible_room(state: &SharedState, user: &AuthenticatedUser, id: &str) -> Option<Room> {
+    state
+        .rooms
+        .read()
+        .await
+        .get(id)
+        .filter(|room| room.is_visible_to(user))
+        .cloned()
+}
+
+/// Room and copy of a message in a room the caller can see and take part in.
+async fn find_visible_message(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    id: &str,
+) -> Option<(String, StoredMessage)> {
+    let found = {
+        let rooms = state.rooms.read().await;
+        let messages = state.room_messages.read().await;
+        messages
+            .iter()
+            .filter(|(room_id, _)| {
+                rooms
+                    .get(room_id.as_str())
+                    .is_some_and(|room| room.is_visible_to(user))
+            })
+            .find_map(|(room_id, messages)| {
+                messages
+                    .iter()
+                    .find(|message| message.id == id)
+                    .map(|message| (room_id.clone(), message.clone()))
+            })
     };
+    let (room_id, message) = found?;
+    joins::ensure_participant(state, user, &room_id)
+        .await
+        .ok()
+        .map(|()| (room_id, message))
+}
 
-    if payload.query.trim().is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Query cannot be empty".to_string(),
-                code: Some(error_codes::INVALID_QUERY),
-            }),
+/// Returns a 404 response when `id` does not name a room visible to `user`.
+async fn ensure_room_access(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    id: &str,
+) -> Result<(), Response> {
+    if visible_room(state, user, id).await.is_some() {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("room not found")),
         )
-            .into_response();
+            .into_response())
     }
+}
 
-    let mut request = SearchRequest::new(&payload.query).with_limit(payload.limit);
+fn webhook_error_response(err: WebhookError) -> Response {
+    let status = match err {
+        WebhookError::InvalidUrl(_) => StatusCode::BAD_REQUEST,
+        WebhookError::NotFound => StatusCode::NOT_FOUND,
+    };
+    (status, Json(ErrorResponse::from(err))).into_response()
+}
 
-    if let Some(min_score) = payload.min_score {
-        request = request.with_min_score(min_score);
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/webhooks",
+    tag = "webhooks",
+    summary = "Register a webhook for message.created and member.invited events",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = RegisterWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook registered; the secret is only shown here and on rotation", body = WebhookWithSecretResponse),
+        (status = 400, description = "Invalid webhook URL", body = ErrorResponse),
+        (status = 403, description = "The caller is not a member of the room", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.register_webhook",
+    skip(state, user, payload),
+    fields(room_id = %id)
+)]
+async fn register_webhook(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(payload): Json<RegisterWebhookRequest>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
     }
-
-    if let Some(room_id) = payload.room_id {
-        request = request.in_room(room_id);
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
     }
 
-    match search_service.search(request).await {
-        Ok(response) => {
-            let items: Vec<SearchResultItem> = response
-                .results
-                .into_iter()
-                .filter_map(|r| {
-                    r.content.map(|content| SearchResultItem {
-                        id: r.id,
-                        score: r.score,
-                        content,
-                        room_id: r.room_id,
-                    })
-                })
-                .collect();
-            let total = items.len();
-            let api_response = SearchApiResponse {
-                query: response.query,
-                results: items,
-                total,
-            };
-            (StatusCode::OK, Json(api_response)).into_response()
+    let result = state
+        .webhooks
+        .register(&id, &payload.url, payload.events)
+        .await;
+    let resource = match &result {
+        Ok(webhook) => format!("room:{id}/webhook:{}", webhook.id),
+        Err(_) => format!("room:{id}"),
+    };
+    audit_webhook_change(
+        &state,
+        &user,
+        AuditAction::WebhookRegistered,
+        resource,
+        &result,
+    )
+    .await;
+    match result {
+        Ok(webhook) => {
+            let secret = webhook.secret().to_string();
+            (
+                StatusCode::CREATED,
+                Json(WebhookWithSecretResponse { webhook, secret }),
+            )
+                .into_response()
         }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse::from(e)),
-        )
-            .into_response(),
+        Err(err) => webhook_error_response(err),
     }
 }
 
-#[tracing::instrument(
-    name = "gateway.search_messages.get",
-    skip(state, _user, params),
-    fields(limit = params.limit)
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/webhooks",
+    tag = "webhooks",
+    summary = "List webhooks registered for a room",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room's webhooks", body = ListWebhooksResponse),
+        (status = 403, description = "The caller is not a member of the room", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
 )]
-async fn search_messages_get(
+#[tracing::instrument(name = "gateway.list_webhooks", skip(state, user), fields(room_id = %id))]
+async fn list_webhooks(
     State(state): State<SharedState>,
-    _user: AuthenticatedUser,
-    Query(params): Query<SearchQueryParams>,
-) -> impl IntoResponse {
-    let Some(search_service) = state.search_service.as_ref() else {
-        return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "Search service not configured".to_string(),
-                code: Some(error_codes::SEARCH_UNAVAILABLE),
-            }),
-        )
-            .into_response();
-    };
-
-    if params.q.trim().is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Query parameter 'q' is required".to_string(),
-                code: Some(error_codes::INVALID_QUERY),
-            }),
-        )
-            .into_response();
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
     }
 
-    let mut request = SearchRequest::new(&params.q).with_limit(params.limit);
+    let webhooks = state.webhooks.list(&id).await;
+    let total = webhooks.len();
+    (
+        StatusCode::OK,
+        Json(ListWebhooksResponse { webhooks, total }),
+    )
+        .into_response()
+}
 
-    if let Some(min_score) = params.min_score {
-        request = request.with_min_score(min_score);
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/webhooks/{webhook_id}/rotate",
+    tag = "webhooks",
+    summary = "Rotate a webhook signing secret (admin only)",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("webhook_id" = String, Path, description = "Webhook id"),
+    ),
+    responses(
+        (status = 200, description = "The webhook with its new secret", body = WebhookWithSecretResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room or webhook not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.rotate_webhook_secret",
+    skip(state, user),
+    fields(room_id = %id, webhook_id = %webhook_id)
+)]
+async fn rotate_webhook_secret(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, webhook_id)): Path<(String, String)>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
     }
-
-    if let Some(room_id) = params.room_id {
-        request = request.in_room(room_id);
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
+    }
+    // Rotating hands out the new secret, so it is left to admins.
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
     }
 
-    match search_service.search(request).await {
-        Ok(response) => {
-            let items: Vec<SearchResultItem> = response
-                .results
-                .into_iter()
-                .filter_map(|r| {
-                    r.content.map(|content| SearchResultItem {
-                        id: r.id,
-                        score: r.score,
-                        content,
-                        room_id: r.room_id,
-                    })
-                })
-                .collect();
-            let total = items.len();
-            let api_response = SearchApiResponse {
-                query: response.query,
-                results: items,
-                total,
-            };
-            (StatusCode::OK, Json(api_response)).into_response()
+    let result = state.webhooks.rotate_secret(&id, &webhook_id).await;
+    let resource = format!("room:{id}/webhook:{webhook_id}");
+    audit_webhook_change(
+        &state,
+        &user,
+        AuditAction::WebhookSecretRotated,
+        resource,
+        &result,
+    )
+    .await;
+    match result {
+        Ok(webhook) => {
+            let secret = webhook.secret().to_string();
+            (
+                StatusCode::OK,
+                Json(WebhookWithSecretResponse { webhook, secret }),
+            )
+                .into_response()
         }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse::from(e)),
-        )
-            .into_response(),
+        Err(err) => webhook_error_response(err),
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/webhooks/{webhook_id}/disable",
+    tag = "webhooks",
+    summary = "Disable a webhook (admin only)",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("webhook_id" = String, Path, description = "Webhook id"),
+    ),
+    responses(
+        (status = 200, description = "The disabled webhook", body = crate::webhooks::Webhook),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room or webhook not found", body = ErrorResponse),
+    )
+)]
 #[tracing::instrument(
-    name = "gateway.list_rooms",
-    skip(state, _user, query),
-    fields(limit = ?query.limit, offset = ?query.offset)
+    name = "gateway.disable_webhook",
+    skip(state, user),
+    fields(room_id = %id, webhook_id = %webhook_id)
 )]
-async fn list_rooms(
+async fn disable_webhook(
     State(state): State<SharedState>,
-    _user: AuthenticatedUser,
-    Query(query): Query<ListRoomsQuery>,
-) -> impl IntoResponse {
-    let limit = query.limit.unwrap_or(100).min(1000);
-    let offset = query.offset.unwrap_or(0);
-
-    let rooms = state.rooms.read().await;
-    let members = state.room_members.read().await;
-
-    let all_rooms: Vec<RoomSummary> = rooms
-        .values()
-        .skip(offset)
-        .take(limit)
-        .map(|room| {
-            let member_count = members.get(&room.id).map(|m| m.len());
-            RoomSummary {
-                id: room.id.clone(),
-                name: room.name.clone(),
-                topic: room.topic.clone(),
-                member_count,
-            }
-        })
-        .collect();
+    user: AuthenticatedUser,
+    Path((id, webhook_id)): Path<(String, String)>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
 
-    let total = rooms.len();
+    let result = state.webhooks.disable(&id, &webhook_id).await;
+    let resource = format!("room:{id}/webhook:{webhook_id}");
+    audit_webhook_change(
+        &state,
+        &user,
+        AuditAction::WebhookDisabled,
+        resource,
+        &result,
+    )
+    .await;
+    match result {
+        Ok(webhook) => (StatusCode::OK, Json(webhook)).into_response(),
+        Err(err) => webhook_error_response(err),
+    }
+}
 
-    let response = ListRoomsResponse {
-        rooms: all_rooms,
-        total,
+async fn audit_webhook_change<T>(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    action: AuditAction,
+    resource: String,
+    result: &Result<T, WebhookError>,
+) {
+    let event = AuditEvent::new(&user.member_id, action, resource);
+    let event = match result {
+        Ok(_) => event,
+        Err(err) => event.with_result(AuditResult::Failed, err.to_string()),
     };
+    state.audit.record(event).await;
+}
 
-    (StatusCode::OK, Json(response)).into_response()
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/webhooks/{webhook_id}/deliveries",
+    tag = "webhooks",
+    summary = "Delivery attempt history, most recent first",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("webhook_id" = String, Path, description = "Webhook id"),
+    ),
+    responses(
+        (status = 200, description = "Recent delivery attempts", body = ListDeliveriesResponse),
+        (status = 403, description = "The caller is not a member of the room", body = ErrorResponse),
+        (status = 404, description = "Room or webhook not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.list_webhook_deliveries",
+    skip(state, user),
+    fields(room_id = %id, webhook_id = %webhook_id)
+)]
+async fn list_webhook_deliveries(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, webhook_id)): Path<(String, String)>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
+    }
+
+    match state.webhooks.deliveries(&id, &webhook_id).await {
+        Ok(deliveries) => {
+            let total = deliveries.len();
+            (
+                StatusCode::OK,
+                Json(ListDeliveriesResponse { deliveries, total }),
+            )
+                .into_response()
+        }
+        Err(err) => webhook_error_response(err),
+    }
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/ai",
+    tag = "ai",
+    summary = "Generate an AI reply using recent room history as context",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = RoomAiRequest,
+    responses(
+        (status = 200, description = "Reply generated and posted to the room", body = RoomAiResponse),
+        (status = 400, description = "Empty prompt, unknown provider or a model not allowed in the room", body = ErrorResponse),
+        (status = 402, description = "Monthly AI budget exhausted", body = ErrorResponse),
+        (status = 403, description = "The caller is not a member of the room, or is banned or muted in it", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+        (status = 502, description = "The AI provider failed", body = ErrorResponse),
+        (status = 503, description = "No AI provider configured", body = ErrorResponse),
+    )
+)]
 #[tracing::instrument(
-    name = "gateway.delete_room",
-    skip(state, _user),
+    name = "gateway.room_ai",
+    skip(state, user, payload),
     fields(room_id = %id)
 )]
-async fn delete_room(
+async fn room_ai(
     State(state): State<SharedState>,
-    _user: AuthenticatedUser,
+    user: AuthenticatedUser,
     Path(id): Path<String>,
+    Json(payload): Json<RoomAiRequest>,
 ) -> impl IntoResponse {
-    let Ok(_permit) = state.write_gate.clone().acquire_owned().await else {
+    let started = Instant::now();
+    let operation = "room_ai";
+    if state.ai_provider.is_none() {
+        record_operation_error(operation, "unavailable", started);
         return (
             StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::service_unavailable("service unavailable")),
+            Json(ErrorResponse {
+                error: "AI provider not configured".to_string(),
+                code: Some(error_codes::AI_UNAVAILABLE),
+            }),
         )
             .into_response();
     };
 
-    let mut rooms = state.rooms.write().await;
-    if rooms.remove(&id).is_none() {
+    if payload.prompt.trim().is_empty() {
+        record_operation_error(operation, "validation", started);
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request("prompt is required")),
+        )
+            .into_response();
+    }
+
+    let Some(room) = visible_room(&state, &user, &id).await else {
+        record_operation_error(operation, "room_not_found", started);
         return (
             StatusCode::NOT_FOUND,
             Json(ErrorResponse::not_found("room not found")),
         )
             .into_response();
+    };
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        record_operation_error(operation, "not_a_member", started);
+        return response;
+    }
+    // The reply is posted on the caller's behalf.
+    if let Err(response) = sanctions::check_can_send(&state, &id, &user.member_id).await {
+        record_operation_error(operation, "sanctioned", started);
+        return response;
     }
-    drop(rooms);
 
-    let mut messages = state.room_messages.write().await;
-    messages.remove(&id);
-    drop(messages);
+    let room_settings = settings::room_settings(&state, &id).await;
+    let provider_name = payload
+        .provider
+        .as_deref()
+        .or(room_settings.ai_provider.as_deref());
+    let Some(provider) = state.ai_provider_named(provider_name) else {
+        record_operation_error(operation, "validation", started);
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(format!(
+                "unknown AI provider: {}",
+                provider_name.unwrap_or_default()
+            ))),
+        )
+            .into_response();
+    };
+    if let Some(model) = &payload.model {
+        if !settings::model_allowed(&state, &room_settings, room.tenant(), model) {
+            record_operation_error(operation, "validation", started);
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request(format!(
+                    "model {model} is not allowed in this room"
+                ))),
+            )
+                .into_response();
+        }
+    }
 
-    let mut members = state.room_members.write().await;
-    members.remove(&id);
+    let history = room_history(&state, &id).await;
+    let prompt = payload.prompt.trim().to_string();
+    let assembled = state.prompt_assembler.assemble(None, history, &prompt);
+    // The room's model only suits the room's provider.
+    let requested_model = match (payload.model, &payload.provider) {
+        (Some(model), _) => Some(model),
+        (None, Some(provider)) if room_settings.ai_provider.as_ref() != Some(provider) => None,
+        (None, _) => room_settings.ai_model,
+    };
+    let context_messages = assembled.context.messages.len();
+
+    let request = GenerateRequest {
+        prompt: assembled.prompt,
+        model: requested_model.clone(),
+        max_tokens: payload.max_tokens,
+        temperature: None,
+        metadata: Some(serde_json::json!({ "roomId": id.clone() })),
+        images: Vec::new(),
+    };
+    let generated = match generate_ai_reply(
+        &state,
+        &user,
+        provider.as_ref(),
+        request,
+        operation,
+        started,
+    )
+    .await
+    {
+        Ok(generated) => generated,
+        Err(response) => return response,
+    };
 
-    (StatusCode::NO_CONTENT, ()).into_response()
-}
+    let message = StoredMessage::new(AI_MEMBER_ID, generated.content.clone());
+    let response = RoomAiResponse {
+        message_id: message.id.clone(),
+        content: generated.content,
+        model: generated.model,
+        context_messages,
+    };
+    let generation = branches::Generation::new(
+        &id,
+        &message.id,
+        prompt,
+        provider.name(),
+        requested_model,
+        payload.max_tokens,
+    );
+    if let Err(response) = post_ai_message(&state, &user, id, message, operation, started).await {
+        return response;
+    }
+    branches::record(&state, generation).await;
+    record_operation_success(operation, started);
 
-/// Handle WebSocket connection
-async fn handle_socket(socket: WebSocket) {
-    use futures::{SinkExt, StreamExt};
+    (StatusCode::OK, Json(response)).into_response()
+}
 
-    let (mut sender, mut receiver) = socket.split();
-    let (tx, mut rx) = mpsc::channel::<Message>(256);
+/// Ask `provider` for a reply on `user`'s behalf, within their AI budget
+/// and that of the room named by the request's `roomId` metadata, and
+/// record the request and its cost.
+async fn generate_ai_reply(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    provider: &dyn AIProvider,
+    request: GenerateRequest,
+    operation: &str,
+    started: Instant,
+) -> Result<GenerateResponse, Response> {
+    if let Err(err) = state.costs.check_budget(&user.member_id) {
+        record_operation_error(operation, "budget", started);
+        return Err((
+            StatusCode::PAYMENT_REQUIRED,
+            Json(ErrorResponse {
+                error: err.to_string(),
+                code: Some(error_codes::BUDGET_EXCEEDED),
+            }),
+        )
+            .into_response());
+    }
+    let room_id = request
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("roomId"))
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string);
+    if let Some(room_id) = &room_id {
+        room_budgets::check(state, user, room_id, operation, started).await?;
+    }
 
-    let writer = tokio::spawn(async move {
-        while let Some(message) = rx.recv().await {
-            if sender.send(message).await.is_err() {
-                break;
-            }
+    let requested_model = request.model.clone();
+    let entry = state
+        .generation_log
+        .as_ref()
+        .map(|_| GenerationLogEntry::new(&user.member_id, provider.name(), &request));
+    let provider_started = Instant::now();
+    let result = provider.generate(request).await;
+    record_ai_request(
+        provider.name(),
+        provider_started,
+        result.as_ref().err().map(provider_error_type),
+    );
+    if let (Some(log), Some(entry)) = (&state.generation_log, entry) {
+        let result = result.as_ref().map_err(ToString::to_string);
+        log.record(entry.with_result(result, provider_started.elapsed()))
+            .await;
+    }
+    let generated = match result {
+        Ok(generated) => generated,
+        Err(err) => {
+            tracing::error!("AI provider error: {}", err);
+            record_operation_error(operation, "provider", started);
+            return Err((
+                StatusCode::BAD_GATEWAY,
+                Json(ErrorResponse {
+                    error: "AI provider request failed".to_string(),
+                    code: Some(error_codes::AI_PROVIDER_ERROR),
+                }),
+            )
+                .into_response());
         }
-    });
+    };
+    if let Some(room_id) = &room_id {
+        state.room_budgets.record(room_id, generated.usage);
+    }
+    if let Some(usage) = generated.usage {
+        let model = generated
+            .model
+            .as_deref()
+            .or(requested_model.as_deref())
+            .unwrap_or("unknown");
+        state
+            .costs
+            .record(&user.member_id, provider.name(), model, usage);
+    }
+    Ok(generated)
+}
 
-    while let Some(msg) = receiver.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                tracing::debug!("Received: {}", text);
-                if tx.send(Message::Text(text)).await.is_err() {
-                    break;
-                }
-            }
-            Ok(Message::Close(_)) => {
-                tracing::debug!("Client disconnected");
-                break;
-            }
-            Err(e) => {
-                tracing::error!("WebSocket error: {}", e);
-                break;
-            }
-            _ => {}
-        }
+/// Store an AI reply and publish it to room `room_id`.
+async fn post_ai_message(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    room_id: String,
+    message: StoredMessage,
+    operation: &str,
+    started: Instant,
+) -> Result<(), Response> {
+    let Ok(_permit) = state.write_gate.clone().acquire_owned().await else {
+        record_operation_error(operation, "unavailable", started);
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::service_unavailable("service unavailable")),
+        )
+            .into_response());
+    };
+
+    if let Err(err) = state
+        .store_message(&room_id, caller_tenant(user), &message)
+        .await
+    {
+        record_operation_error(operation, "storage", started);
+        return Err(storage_error_response(err));
     }
+    state
+        .room_messages
+        .write()
+        .await
+        .entry(room_id.clone())
+        .or_default()
+        .push(message.clone());
+    state.publish(RoomEvent::Message { room_id, message }).await;
+    MESSAGES_SENT.inc();
+    Ok(())
+}
 
-    writer.abort();
+/// Recent history of room `room_id` for prompt assembly.
+async fn room_history(state: &SharedState, room_id: &str) -> Vec<ContextMessage> {
+    let load = || async {
+        Ok(state
+            .room_messages
+            .read()
+            .await
+            .get(room_id)
+            .map(|messages| {
+                messages
+                    .iter()
+                    .map(|message| (message.id.clone(), context_message(message)))
+                    .collect()
+            })
+            .unwrap_or_default())
+    };
+    // Loading from `room_messages` cannot fail.
+    state
+        .conversations
+        .get_or_load(room_id, load)
+        .await
+        .unwrap_or_default()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use axum::body::Body;
-    use axum::http::{Request, StatusCode};
-    use serde_json::{json, Value};
-    use tower::ServiceExt;
+fn context_message(message: &StoredMessage) -> ContextMessage {
+    let context_message = if message.sender.starts_with("nexis:ai:") {
+        ContextMessage::assistant(message.text.clone())
+    } else {
+        ContextMessage::user(message.text.clone())
+    };
+    context_message.with_author(message.sender.clone())
+}
 
-    #[tokio::test]
-    async fn health_check_returns_ok() {
-        let app = build_routes();
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/health")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
+/// Rooms the caller may see search results from: the visible rooms they are
+/// a member of, or every visible room for admins.
+async fn search_permissions(state: &SharedState, user: &AuthenticatedUser) -> PermissionChecker {
+    let is_admin = state.config.auth.is_admin(&user.member_id);
+    let rooms = state.rooms.read().await;
+    let members = state.room_members.read().await;
+    let rooms = rooms
+        .values()
+        .filter(|room| room.is_visible_to(user))
+        .filter(|room| {
+            is_admin
+                || members
+                    .get(&room.id)
+                    .is_some_and(|members| members.contains(&user.member_id))
+        })
+        .map(|room| room.id.clone())
+        .collect();
+    PermissionChecker::new(Permissions::new(rooms, vec![Action::Read]))
+}
 
-        assert_eq!(response.status(), StatusCode::OK);
+/// Tenant recorded on an indexed document, read back from its metadata.
+fn result_tenant(result: &crate::search::SearchResultItem) -> Option<&str> {
+    result
+        .metadata
+        .get("tenant_id")
+        .and_then(serde_json::Value::as_str)
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/search",
+    tag = "search",
+    summary = "Search messages (JSON body)",
+    request_body = SearchApiRequest,
+    responses(
+        (status = 200, description = "Matching messages", body = SearchApiResponse),
+        (status = 400, description = "Empty query", body = ErrorResponse),
+        (status = 503, description = "Search is not configured", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.search_messages.post",
+    skip(state, user, payload),
+    fields(limit = payload.limit)
+)]
+async fn search_messages(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Json(payload): Json<SearchApiRequest>,
+) -> impl IntoResponse {
+    let Some(search_service) = state.search_service.as_ref() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: "Search service not configured".to_string(),
+                code: Some(error_codes::SEARCH_UNAVAILABLE),
+            }),
+        )
+            .into_response();
+    };
+
+    if payload.query.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Query cannot be empty".to_string(),
+                code: Some(error_codes::INVALID_QUERY),
+            }),
+        )
+            .into_response();
     }
 
-    #[tokio::test]
-    async fn openapi_endpoint_returns_json() {
-        let app = build_routes();
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/openapi.json")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
+    let mut request = SearchRequest::new(&payload.query).with_limit(payload.limit);
 
-        assert_eq!(response.status(), StatusCode::OK);
-        let content_type = response
-            .headers()
-            .get("content-type")
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("");
-        assert!(content_type.starts_with("application/json"));
+    if let Some(min_score) = payload.min_score {
+        request = request.with_min_score(min_score);
+    }
 
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+    if let Some(room_id) = payload.room_id {
+        request = request.in_room(room_id);
+    }
+
+    if let Some(content_type) = payload.content_type.clone() {
+        request = request.with_content_type(content_type);
+    }
+
+    if payload.expand {
+        request = request.with_expansion();
+    }
+
+    if let Some(tenant_id) = caller_tenant(&user) {
+        request = request.for_tenant(tenant_id);
+    }
+    request = request.with_permissions(search_permissions(&state, &user).await);
+
+    let search_started = Instant::now();
+    let result = search_service.search(request).await;
+    record_search(search_started, result.is_ok());
+    match result {
+        Ok(response) => {
+            let items: Vec<SearchResultItem> = response
+                .results
+                .into_iter()
+                .filter(|r| result_tenant(r) == caller_tenant(&user))
+                .filter_map(|r| {
+                    r.content.map(|content| SearchResultItem {
+                        id: r.id,
+                        score: r.score,
+                        rerank_score: r.rerank_score,
+                        content,
+                        room_id: r.room_id,
+                    })
+                })
+                .collect();
+            let total = items.len();
+            let api_response = SearchApiResponse {
+                query: response.query,
+                results: items,
+                total,
+                expanded_terms: response.expanded_terms,
+            };
+            (StatusCode::OK, Json(api_response)).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::from(e)),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/search",
+    tag = "search",
+    summary = "Search messages (query params)",
+    params(SearchQueryParams),
+    responses(
+        (status = 200, description = "Matching messages", body = SearchApiResponse),
+        (status = 400, description = "Empty query", body = ErrorResponse),
+        (status = 503, description = "Search is not configured", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.search_messages.get",
+    skip(state, user, params),
+    fields(limit = params.limit)
+)]
+async fn search_messages_get(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Query(params): Query<SearchQueryParams>,
+) -> impl IntoResponse {
+    let Some(search_service) = state.search_service.as_ref() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: "Search service not configured".to_string(),
+                code: Some(error_codes::SEARCH_UNAVAILABLE),
+            }),
+        )
+            .into_response();
+    };
+
+    if params.q.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Query parameter 'q' is required".to_string(),
+                code: Some(error_codes::INVALID_QUERY),
+            }),
+        )
+            .into_response();
+    }
+
+    let mut request = SearchRequest::new(&params.q).with_limit(params.limit);
+
+    if let Some(min_score) = params.min_score {
+        request = request.with_min_score(min_score);
+    }
+
+    if let Some(room_id) = params.room_id {
+        request = request.in_room(room_id);
+    }
+
+    if let Some(content_type) = params.content_type.clone() {
+        request = request.with_content_type(content_type);
+    }
+
+    if params.expand {
+        request = request.with_expansion();
+    }
+
+    if let Some(tenant_id) = caller_tenant(&user) {
+        request = request.for_tenant(tenant_id);
+    }
+    request = request.with_permissions(search_permissions(&state, &user).await);
+
+    let search_started = Instant::now();
+    let result = search_service.search(request).await;
+    record_search(search_started, result.is_ok());
+    match result {
+        Ok(response) => {
+            let items: Vec<SearchResultItem> = response
+                .results
+                .into_iter()
+                .filter(|r| result_tenant(r) == caller_tenant(&user))
+                .filter_map(|r| {
+                    r.content.map(|content| SearchResultItem {
+                        id: r.id,
+                        score: r.score,
+                        rerank_score: r.rerank_score,
+                        content,
+                        room_id: r.room_id,
+                    })
+                })
+                .collect();
+            let total = items.len();
+            let api_response = SearchApiResponse {
+                query: response.query,
+                results: items,
+                total,
+                expanded_terms: response.expanded_terms,
+            };
+            (StatusCode::OK, Json(api_response)).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::from(e)),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms",
+    tag = "rooms",
+    summary = "List rooms",
+    description = "Rooms the caller is a member of; admins see every room.",
+    params(ListRoomsQuery),
+    responses((status = 200, description = "Rooms visible to the caller", body = ListRoomsResponse))
+)]
+#[tracing::instrument(
+    name = "gateway.list_rooms",
+    skip(state, user, query),
+    fields(limit = ?query.limit, offset = ?query.offset, sort = ?query.sort)
+)]
+async fn list_rooms(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Query(query): Query<ListRoomsQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(100).min(1000);
+    let offset = query.offset.unwrap_or(0);
+    let is_admin = state.config.auth.is_admin(&user.member_id);
+    let name_filter = query
+        .q
+        .as_deref()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+        .map(str::to_lowercase);
+
+    let mut visible: Vec<RoomSummary> = {
+        let rooms = state.rooms.read().await;
+        let members = state.room_members.read().await;
+        rooms
+            .values()
+            .filter(|room| room.is_visible_to(&user))
+            .filter(|room| {
+                is_admin
+                    || members
+                        .get(&room.id)
+                        .is_some_and(|members| members.contains(&user.member_id))
+            })
+            .filter(|room| {
+                name_filter
+                    .as_ref()
+                    .is_none_or(|filter| room.name.to_lowercase().contains(filter))
+            })
+            .map(|room| RoomSummary {
+                id: room.id.clone(),
+                name: room.name.clone(),
+                topic: room.topic.clone(),
+                member_count: members.get(&room.id).map(|m| m.len()),
+            })
+            .collect()
+    };
+
+    match query.sort {
+        RoomSort::Name => visible.sort_by(|a, b| {
+            a.name
+                .to_lowercase()
+                .cmp(&b.name.to_lowercase())
+                .then_with(|| a.id.cmp(&b.id))
+        }),
+        RoomSort::Members => visible.sort_by(|a, b| {
+            b.member_count
+                .unwrap_or(0)
+                .cmp(&a.member_count.unwrap_or(0))
+                .then_with(|| a.id.cmp(&b.id))
+        }),
+        RoomSort::Activity => {
+            let messages = state.room_messages.read().await;
+            let last_message = |room: &RoomSummary| {
+                messages
+                    .get(&room.id)
+                    .and_then(|messages| messages.last())
+                    .map(|message| message.created_at)
+            };
+            visible.sort_by(|a, b| {
+                last_message(b)
+                    .cmp(&last_message(a))
+                    .then_with(|| a.id.cmp(&b.id))
+            });
+        }
+    }
+
+    let total = visible.len();
+    let response = ListRoomsResponse {
+        rooms: visible.into_iter().skip(offset).take(limit).collect(),
+        total,
+    };
+
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+/// Returns a 403 response unless `user` is listed in `auth.admin_members`.
+async fn require_admin(state: &SharedState, user: &AuthenticatedUser) -> Result<(), Response> {
+    if state.config.auth.is_admin(&user.member_id) {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::forbidden("admin access required")),
+        )
+            .into_response())
+    }
+}
+
+/// Page through recent audit events. Restricted to `auth.admin_members`.
+#[utoipa::path(
+    get,
+    path = "/v1/audit",
+    tag = "admin",
+    summary = "Page through recent audit events, newest first (admin only)",
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "A page of audit events", body = crate::audit::AuditPage),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.list_audit_events",
+    skip(state, user, query),
+    fields(member_id = %user.member_id)
+)]
+async fn list_audit_events(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Query(query): Query<AuditQuery>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+
+    (StatusCode::OK, Json(state.audit.query(&query).await)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/export",
+    tag = "rooms",
+    summary = "Export room metadata, members and message history as a JSONL archive",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room archive", body = String, content_type = "application/x-ndjson"),
+        (status = 403, description = "The caller is not a member of the room", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.export_room", skip(state, user), fields(room_id = %id))]
+async fn export_room(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    let started = Instant::now();
+    let operation = "export_room";
+    let Some(room) = visible_room(&state, &user, &id).await else {
+        record_operation_error(operation, "room_not_found", started);
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("room not found")),
+        )
+            .into_response();
+    };
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        record_operation_error(operation, "not_a_member", started);
+        return response;
+    }
+    let members = state
+        .room_members
+        .read()
+        .await
+        .get(&id)
+        .cloned()
+        .unwrap_or_default();
+    let messages = state
+        .room_messages
+        .read()
+        .await
+        .get(&id)
+        .map(|messages| messages.iter().map(ArchivedMessage::from).collect())
+        .unwrap_or_default();
+
+    let archive = RoomArchive::new(ArchivedRoom {
+        id: room.id,
+        name: room.name,
+        topic: room.topic,
+    })
+    .with_members(members)
+    .with_messages(messages);
+    record_operation_success(operation, started);
+
+    (
+        StatusCode::OK,
+        [
+            ("content-type", ARCHIVE_CONTENT_TYPE.to_string()),
+            (
+                "content-disposition",
+                format!("attachment; filename=\"{id}.jsonl\""),
+            ),
+        ],
+        archive.to_jsonl(),
+    )
+        .into_response()
+}
+
+/// Recreate a room from an export archive, keeping room, message and member ids.
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/import",
+    tag = "rooms",
+    summary = "Import a room from a JSONL archive, keeping room, member and message ids",
+    request_body(content = String, content_type = "application/x-ndjson"),
+    responses(
+        (status = 201, description = "Room imported", body = ImportRoomResponse),
+        (status = 400, description = "Malformed archive", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 409, description = "A room with the archived id already exists", body = ErrorResponse),
+        (status = 422, description = "Archive failed validation", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.import_room", skip(state, user, body))]
+async fn import_room(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    body: String,
+) -> Response {
+    let started = Instant::now();
+    let operation = "import_room";
+    if let Err(response) = require_admin(&state, &user).await {
+        record_operation_error(operation, "forbidden", started);
+        return response;
+    }
+    let archive = match RoomArchive::from_jsonl(&body) {
+        Ok(archive) => archive,
+        Err(err) => {
+            record_operation_error(operation, "validation", started);
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request(format!(
+                    "invalid archive: {err}"
+                ))),
+            )
+                .into_response();
+        }
+    };
+    // Messages can't be put in the name of someone outside the tenant.
+    let tenant_members = match tenant_members(&state, &user).await {
+        Ok(members) => members,
+        Err(err) => {
+            record_operation_error(operation, "storage", started);
+            return storage_error_response(err);
+        }
+    };
+    let mut validator = Validator::new();
+    validator.field("room.id", archive.room.id.parse::<RoomId>());
+    for (index, message) in archive.messages.iter().enumerate() {
+        let field = format!("messages[{index}].sender");
+        if validator
+            .field(field.clone(), validation::member_id(&message.sender))
+            .is_some()
+            && !tenant_members.contains(&message.sender)
+        {
+            validator.reject(field, "must be a member of the tenant");
+        }
+    }
+    if let Err(response) = validator.finish(Some(())) {
+        record_operation_error(operation, "validation", started);
+        return response;
+    }
+
+    let Ok(_permit) = state.write_gate.clone().acquire_owned().await else {
+        record_operation_error(operation, "unavailable", started);
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::service_unavailable("service unavailable")),
+        )
+            .into_response();
+    };
+
+    let mut rooms = state.rooms.write().await;
+    if rooms.contains_key(&archive.room.id) {
+        record_operation_error(operation, "conflict", started);
+        return (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::conflict("room already exists")),
+        )
+            .into_response();
+    }
+    #[cfg(feature = "multi-tenant")]
+    if let Err(response) = check_room_quota(&state, &rooms, caller_tenant(&user)).await {
+        record_operation_error(operation, "quota", started);
+        return response;
+    }
+
+    // The importer keeps access to the room they brought in.
+    let mut members = archive.members;
+    if !members.contains(&user.member_id) {
+        members.push(user.member_id.clone());
+    }
+    let room_id = archive.room.id.clone();
+    let response = ImportRoomResponse {
+        id: room_id.clone(),
+        name: archive.room.name.clone(),
+        members: members.len(),
+        messages: archive.messages.len(),
+    };
+    let room = Room {
+        id: archive.room.id,
+        name: archive.room.name,
+        topic: archive.room.topic,
+        #[cfg(feature = "multi-tenant")]
+        tenant_id: caller_tenant(&user).map(str::to_string),
+    };
+    let messages: Vec<StoredMessage> = archive
+        .messages
+        .into_iter()
+        .map(StoredMessage::from)
+        .collect();
+    if let Err(err) =
+        store_imported_room(&state, &room, &members, &messages, caller_tenant(&user)).await
+    {
+        record_operation_error(operation, "storage", started);
+        return storage_error_response(err);
+    }
+    rooms.insert(room_id.clone(), room);
+    ROOMS_CREATED_TOTAL.inc();
+    ROOMS_ACTIVE.set(rooms.len() as f64);
+    drop(rooms);
+
+    state
+        .room_members
+        .write()
+        .await
+        .insert(room_id.clone(), members);
+    for message in &messages {
+        state.index_message(&room_id, caller_tenant(&user), message);
+    }
+    state.conversations.invalidate(&room_id).await;
+    state.room_messages.write().await.insert(room_id, messages);
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::RoomImported,
+            format!("room:{}", response.id),
+        ))
+        .await;
+    record_operation_success(operation, started);
+
+    (StatusCode::CREATED, Json(response)).into_response()
+}
+
+/// Members that messages may be imported in the name of: the caller, the
+/// gateway's AI member, the tenant's profiles in the member directory and
+/// the members of the rooms the caller can see, which are the tenant's.
+async fn tenant_members(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+) -> Result<HashSet<String>, RepositoryError> {
+    let mut known: HashSet<String> = state
+        .members
+        .list()
+        .await?
+        .into_iter()
+        .filter(|member| members::member_tenant(member) == caller_tenant(user))
+        .map(|member| member.id)
+        .collect();
+    let rooms = state.rooms.read().await;
+    let room_members = state.room_members.read().await;
+    known.extend(
+        rooms
+            .values()
+            .filter(|room| room.is_visible_to(user))
+            .filter_map(|room| room_members.get(&room.id))
+            .flatten()
+            .cloned(),
+    );
+    known.extend([user.member_id.clone(), AI_MEMBER_ID.to_string()]);
+    Ok(known)
+}
+
+/// Persist an imported room with its members and messages, removing the
+/// stored room again if any of them fails.
+async fn store_imported_room(
+    state: &SharedState,
+    room: &Room,
+    members: &[String],
+    messages: &[StoredMessage],
+    tenant: Option<&str>,
+) -> Result<(), RepositoryError> {
+    state.store_room(room).await?;
+    let stored = async {
+        let joined_at = Utc::now();
+        for member_id in members {
+            let record = MembershipRecord {
+                room_id: room.id.clone(),
+                member_id: member_id.clone(),
+                joined_at,
+            };
+            state.membership_store.add(&record).await?;
+        }
+        for message in messages {
+            state.store_message(&room.id, tenant, message).await?;
+        }
+        Ok::<_, RepositoryError>(())
+    };
+    let Err(err) = stored.await else {
+        return Ok(());
+    };
+    if let Err(cleanup) = state.membership_store.delete_room(&room.id).await {
+        tracing::warn!(
+            "Failed to drop stored memberships of room {}: {}",
+            room.id,
+            cleanup
+        );
+    }
+    if let Err(cleanup) = state.message_store.delete_room(&room.id).await {
+        tracing::warn!(
+            "Failed to drop stored messages of room {}: {}",
+            room.id,
+            cleanup
+        );
+    }
+    if let Err(cleanup) = state.room_store.delete(&room.id).await {
+        tracing::warn!("Failed to drop stored room {}: {}", room.id, cleanup);
+    }
+    Err(err)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/rooms/{id}",
+    tag = "rooms",
+    summary = "Delete room (admin only)",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 204, description = "Room deleted"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.delete_room",
+    skip(state, user),
+    fields(room_id = %id)
+)]
+async fn delete_room(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let resource = format!("room:{id}");
+    let denied = match visible_room(&state, &user, &id).await {
+        None => Some((
+            "room not found",
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::not_found("room not found")),
+            )
+                .into_response(),
+        )),
+        // Deleting drops the room for everyone in it, so it is left to admins.
+        Some(_) => require_admin(&state, &user)
+            .await
+            .err()
+            .map(|response| ("admin access required", response)),
+    };
+    if let Some((reason, response)) = denied {
+        state
+            .audit
+            .record(
+                AuditEvent::new(&user.member_id, AuditAction::RoomDeleted, resource)
+                    .with_result(AuditResult::Failed, reason),
+            )
+            .await;
+        return response;
+    }
+    let Ok(_permit) = state.write_gate.clone().acquire_owned().await else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::service_unavailable("service unavailable")),
+        )
+            .into_response();
+    };
+
+    let mut rooms = state.rooms.write().await;
+    let visible = rooms.get(&id).is_some_and(|room| room.is_visible_to(&user));
+    if visible {
+        if let Err(err) = state.room_store.delete(&id).await {
+            drop(rooms);
+            state
+                .audit
+                .record(
+                    AuditEvent::new(&user.member_id, AuditAction::RoomDeleted, resource)
+                        .with_result(AuditResult::Failed, "storage error"),
+                )
+                .await;
+            return storage_error_response(err);
+        }
+    }
+    let removed = if visible { rooms.remove(&id) } else { None };
+    if removed.is_none() {
+        drop(rooms);
+        state
+            .audit
+            .record(
+                AuditEvent::new(&user.member_id, AuditAction::RoomDeleted, resource)
+                    .with_result(AuditResult::Failed, "room not found"),
+            )
+            .await;
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("room not found")),
+        )
+            .into_response();
+    }
+    drop(rooms);
+
+    let mut messages = state.room_messages.write().await;
+    messages.remove(&id);
+    drop(messages);
+    state.conversations.remove(&id).await;
+
+    let mut members = state.room_members.write().await;
+    members.remove(&id);
+    drop(members);
+
+    if let Err(err) = state.membership_store.delete_room(&id).await {
+        tracing::warn!("Failed to drop memberships of room {}: {}", id, err);
+    }
+    if let Err(err) = state.message_store.delete_room(&id).await {
+        tracing::warn!("Failed to drop stored messages of room {}: {}", id, err);
+    }
+    if let Err(err) = state.read_markers.delete_room(&id).await {
+        tracing::warn!("Failed to drop read markers of room {}: {}", id, err);
+    }
+    if let (Some(index), Ok(room_id)) = (&state.lexical_index, id.parse::<RoomId>()) {
+        index.remove_room(&room_id);
+    }
+    if let (Some(search), Ok(room_id)) = (&state.search_service, id.parse::<RoomId>()) {
+        if let Err(err) = search.remove_room(&room_id).await {
+            tracing::warn!("Failed to drop vector documents of room {}: {}", id, err);
+        }
+    }
+    if let Ok(room_id) = id.parse::<RoomId>() {
+        state.retention.remove_room(&room_id).await;
+    }
+    settings::remove_room(&state, &id).await;
+    joins::remove_room(&state, &id).await;
+    sanctions::remove_room(&state, &id).await;
+    uploads::remove_room(&state, &id).await;
+    slow_mode::remove_room(&state, &id).await;
+    spam::remove_room(&state, &id);
+    streams::remove_room(&state, &id).await;
+    branches::remove_room(&state, &id).await;
+    feedback::remove_room(&state, &id).await;
+    if let Some(log) = &state.generation_log {
+        log.remove_room(&id).await;
+    }
+    if let Err(err) = state.scheduler.delete_room(&id).await {
+        tracing::warn!("Failed to drop scheduled jobs of room {}: {}", id, err);
+    }
+    state
+        .delivery_cursors
+        .write()
+        .await
+        .retain(|(_, room_id), _| room_id != &id);
+
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::RoomDeleted,
+            resource,
+        ))
+        .await;
+
+    (StatusCode::NO_CONTENT, ()).into_response()
+}
+
+/// Handle WebSocket connection
+///
+/// Clients subscribe to rooms with `{"type":"subscribe","roomId":"..."}` and
+/// then receive a `message` event for every message stored in those rooms, a
+/// `message_delta` event for each chunk of a message still being streamed
+/// and a `read_marker` event whenever a member's read marker moves.
+/// Members banned from a room can't subscribe to it, and a connection stops
+/// receiving a room's events once its member is kicked or banned from it.
+/// Authenticated clients also receive their `notification` events, whatever
+/// rooms they subscribed to. Any other text frame is echoed back.
+///
+/// Authenticated clients acknowledge what they have seen with
+/// `{"type":"ack","roomId":"...","eventId":"<message id>"}` in rooms the
+/// connection is subscribed to; other acks get an `error` frame. Subscribing
+/// again, on this or a later connection, first replays the messages stored
+/// after the acknowledged one (or after `lastEventId` when the subscribe
+/// frame names one), at most `websocket.replay_limit` of them.
+///
+/// The server pings every client each `websocket.ping_interval_secs` and
+/// closes connections that leave `websocket.max_missed_pongs` pings in a row
+/// unanswered. Returns the rooms the connection was subscribed to.
+///
+/// Connections opened with `?encoding=msgpack` get every frame as a binary
+/// MessagePack frame and may send their control frames the same way.
+async fn handle_socket(
+    socket: WebSocket,
+    state: SharedState,
+    user: Option<AuthenticatedUser>,
+    encoding: Encoding,
+) -> HashSet<String> {
+    use futures::{SinkExt, StreamExt};
+
+    let (mut sender, mut receiver) = socket.split();
+    let (tx, mut rx) = mpsc::channel::<Message>(256);
+    let subscriptions = Arc::new(RwLock::new(HashSet::<String>::new()));
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            if sender.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let forwarder = {
+        let tx = tx.clone();
+        let subscriptions = Arc::clone(&subscriptions);
+        let member_id = user.as_ref().map(|user| user.member_id.clone());
+        let mut events = state.room_events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("WebSocket subscriber lagged by {} events", skipped);
+                        record_broadcast_lag(skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if !subscriptions.read().await.contains(event.room_id()) {
+                    continue;
+                }
+                if let RoomEvent::Sanction {
+                    room_id,
+                    member_id: sanctioned,
+                    action,
+                    ..
+                } = &event
+                {
+                    if action.removes() && member_id.as_ref() == Some(sanctioned) {
+                        subscriptions.write().await.remove(room_id);
+                    }
+                }
+                let Some(frame) = ws_frame(encoding, &event) else {
+                    continue;
+                };
+                if tx.send(frame).await.is_err() {
+                    break;
+                }
+            }
+        })
+    };
+
+    let notifier = user.as_ref().map(|user| {
+        let tx = tx.clone();
+        let member_id = user.member_id.clone();
+        let mut notifications = state.notifications.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let notification = match notifications.recv().await {
+                    Ok(notification) => notification,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Notification subscriber lagged by {} events", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if notification.member_id != member_id {
+                    continue;
+                }
+                let frame = serde_json::json!({
+                    "type": "notification",
+                    "notification": notification,
+                });
+                let Some(frame) = ws_frame(encoding, &frame) else {
+                    continue;
+                };
+                if tx.send(frame).await.is_err() {
+                    break;
+                }
+            }
+        })
+    });
+
+    let shutdown = state.shutdown.triggered();
+    tokio::pin!(shutdown);
+    let mut heartbeat = state.config.websocket.ping_interval().map(|period| {
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        ticker
+    });
+    let mut missed_pongs = 0;
+
+    loop {
+        let msg = tokio::select! {
+            msg = receiver.next() => msg,
+            _ = &mut shutdown => {
+                let frame = CloseFrame {
+                    code: close_code::AWAY,
+                    reason: "server shutting down".into(),
+                };
+                let _ = tx.send(Message::Close(Some(frame))).await;
+                break;
+            }
+            () = next_heartbeat(&mut heartbeat) => {
+                if missed_pongs >= state.config.websocket.max_missed_pongs {
+                    tracing::debug!("Closing WebSocket after {} missed pongs", missed_pongs);
+                    record_ws_connection_reaped();
+                    let frame = CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "heartbeat timeout".into(),
+                    };
+                    let _ = tx.send(Message::Close(Some(frame))).await;
+                    break;
+                }
+                missed_pongs += 1;
+                if tx.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+        };
+        let Some(msg) = msg else {
+            break;
+        };
+        let frame = match msg {
+            Ok(Message::Text(text)) => {
+                tracing::debug!("Received: {}", text);
+                match serde_json::from_str::<ClientFrame>(&text) {
+                    Ok(frame) => frame,
+                    Err(_) => {
+                        if tx.send(Message::Text(text)).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+            }
+            Ok(Message::Binary(bytes)) if encoding == Encoding::MessagePack => {
+                match encoding.decode::<ClientFrame>(&bytes) {
+                    Ok(frame) => frame,
+                    Err(err) => {
+                        let reply = serde_json::json!({
+                            "type": "error",
+                            "message": format!("unreadable frame: {err}"),
+                        });
+                        if let Some(reply) = ws_frame(encoding, &reply) {
+                            if tx.send(reply).await.is_err() {
+                                break;
+                            }
+                        }
+                        continue;
+                    }
+                }
+            }
+            Ok(Message::Pong(_)) => {
+                missed_pongs = 0;
+                continue;
+            }
+            Ok(Message::Close(_)) => {
+                tracing::debug!("Client disconnected");
+                break;
+            }
+            Err(e) => {
+                tracing::error!("WebSocket error: {}", e);
+                break;
+            }
+            _ => continue,
+        };
+        let reply = match frame {
+            ClientFrame::Subscribe {
+                room_id,
+                last_event_id,
+            } => {
+                let subscriber = match check_subscribe(&state, user.as_ref(), &room_id).await {
+                    Ok(subscriber) => subscriber,
+                    Err(message) => {
+                        let reply = serde_json::json!({
+                            "type": "error",
+                            "roomId": room_id,
+                            "message": message,
+                        });
+                        if let Some(reply) = ws_frame(encoding, &reply) {
+                            if tx.send(reply).await.is_err() {
+                                break;
+                            }
+                        }
+                        continue;
+                    }
+                };
+                // Live events for the room wait on this lock, so none slip
+                // between the replayed messages and live delivery. A message
+                // stored while the replay is taken may arrive twice; clients
+                // drop repeated ids.
+                let mut subscribed = subscriptions.write().await;
+                let frames =
+                    subscribe_frames(&state, subscriber, &room_id, last_event_id, encoding).await;
+                let mut delivered = true;
+                for frame in frames {
+                    if tx.send(frame).await.is_err() {
+                        delivered = false;
+                        break;
+                    }
+                }
+                if !delivered {
+                    break;
+                }
+                subscribed.insert(room_id);
+                continue;
+            }
+            ClientFrame::Unsubscribe { room_id } => {
+                subscriptions.write().await.remove(&room_id);
+                serde_json::json!({ "type": "unsubscribed", "roomId": room_id })
+            }
+            ClientFrame::Ack { room_id, event_id } => {
+                // Cursors are only kept for rooms this connection follows.
+                let subscribed = subscriptions.read().await.contains(&room_id);
+                match &user {
+                    Some(_) if !subscribed => serde_json::json!({
+                        "type": "error",
+                        "roomId": room_id,
+                        "message": "not subscribed to this room",
+                    }),
+                    Some(user) => {
+                        state
+                            .delivery_cursors
+                            .write()
+                            .await
+                            .insert((user.member_id.clone(), room_id.clone()), event_id.clone());
+                        serde_json::json!({ "type": "acked", "roomId": room_id, "eventId": event_id })
+                    }
+                    None => serde_json::json!({
+                        "type": "error",
+                        "message": "acknowledgements need an authenticated connection",
+                    }),
+                }
+            }
+        };
+        if let Some(reply) = ws_frame(encoding, &reply) {
+            if tx.send(reply).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    // Let the writer flush queued frames (including any close frame) once
+    // every sender is gone.
+    forwarder.abort();
+    let _ = forwarder.await;
+    if let Some(notifier) = notifier {
+        notifier.abort();
+        let _ = notifier.await;
+    }
+    drop(tx);
+    let _ = writer.await;
+
+    // The forwarder held the only other handle.
+    Arc::try_unwrap(subscriptions)
+        .map(RwLock::into_inner)
+        .unwrap_or_default()
+}
+
+/// Wait for the next heartbeat tick; never resolves with heartbeats off.
+async fn next_heartbeat(heartbeat: &mut Option<tokio::time::Interval>) {
+    match heartbeat {
+        Some(ticker) => {
+            ticker.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Tell `rooms` that `member_id` went offline, unless the member still has a
+/// connection open on any instance.
+async fn announce_offline(state: &SharedState, member_id: &str, rooms: HashSet<String>) {
+    if rooms.is_empty() {
+        return;
+    }
+    match state.connections.locate(member_id).await {
+        Ok(connections) if connections.is_empty() => {}
+        Ok(_) => return,
+        Err(err) => {
+            tracing::warn!("Failed to look up connections of {}: {}", member_id, err);
+            return;
+        }
+    }
+    for room_id in rooms {
+        state
+            .publish(RoomEvent::Presence {
+                room_id,
+                member_id: member_id.to_string(),
+                status: PresenceStatus::Offline,
+            })
+            .await;
+    }
+}
+
+/// Frames answering a subscribe: the `subscribed` acknowledgement, then the
+/// messages the member missed since `last_event_id` or their acknowledged
+/// cursor. When the cursor is unknown or more messages were missed than
+/// `websocket.replay_limit` allows, only the newest are replayed and a
+/// `replay_truncated` frame says so.
+async fn subscribe_frames(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    room_id: &str,
+    last_event_id: Option<String>,
+    encoding: Encoding,
+) -> Vec<Message> {
+    let subscribed = |replayed: usize| {
+        let reply =
+            serde_json::json!({ "type": "subscribed", "roomId": room_id, "replayed": replayed });
+        ws_frame(encoding, &reply).into_iter().collect::<Vec<_>>()
+    };
+    let limit = state.config.websocket.replay_limit;
+    if limit == 0 {
+        return subscribed(0);
+    }
+    let cursor = match last_event_id {
+        Some(id) => Some(id),
+        None => state
+            .delivery_cursors
+            .read()
+            .await
+            .get(&(user.member_id.clone(), room_id.to_string()))
+            .cloned(),
+    };
+    let Some(cursor) = cursor else {
+        return subscribed(0);
+    };
+
+    let messages = state.room_messages.read().await;
+    let messages = messages.get(room_id).map(Vec::as_slice).unwrap_or_default();
+    let (mut missed, mut truncated) = match messages.iter().position(|m| m.id == cursor) {
+        Some(index) => (&messages[index + 1..], false),
+        None => (messages, true),
+    };
+    if missed.len() > limit {
+        missed = &missed[missed.len() - limit..];
+        truncated = true;
+    }
+
+    let mut frames = subscribed(missed.len());
+    if truncated {
+        let notice = serde_json::json!({ "type": "replay_truncated", "roomId": room_id });
+        frames.extend(ws_frame(encoding, &notice));
+    }
+    frames.extend(missed.iter().filter_map(|message| {
+        let event = RoomEvent::Message {
+            room_id: room_id.to_string(),
+            message: message.clone(),
+        };
+        ws_frame(encoding, &event)
+    }));
+    frames
+}
+
+/// Why `user` may not subscribe to the room, if they may not: anonymous
+/// connections never may, and members need to be able to read the room.
+async fn check_subscribe<'a>(
+    state: &SharedState,
+    user: Option<&'a AuthenticatedUser>,
+    room_id: &str,
+) -> Result<&'a AuthenticatedUser, &'static str> {
+    let Some(user) = user else {
+        return Err("subscriptions need an authenticated connection");
+    };
+    if visible_room(state, user, room_id).await.is_none() {
+        return Err("room not found");
+    }
+    if joins::ensure_participant(state, user, room_id)
+        .await
+        .is_err()
+    {
+        return Err("you are not a member of this room");
+    }
+    if sanctions::is_banned(state, room_id, &user.member_id).await {
+        return Err("you are banned from this room");
+    }
+    Ok(user)
+}
+
+/// WebSocket frame carrying `value`: text for JSON, binary for MessagePack.
+fn ws_frame<T: Serialize>(encoding: Encoding, value: &T) -> Option<Message> {
+    match encoding {
+        Encoding::Json => serde_json::to_string(value).ok().map(Message::Text),
+        encoding => match encoding.encode(value) {
+            Ok(bytes) => Some(Message::Binary(bytes)),
+            Err(err) => {
+                tracing::warn!("Failed to encode WebSocket frame: {}", err);
+                None
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::*;
+    use super::*;
+    use crate::indexing::BatchConfig;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn health_check_returns_ok() {
+        let app = build_routes();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/health")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn readiness_reports_the_latest_schema_version() {
+        let response = build_routes()
+            .oneshot(
+                Request::builder()
+                    .uri("/readyz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_body(response).await;
+        assert_eq!(body["status"], "ready");
+        assert!(body.get("schemaVersion").is_none());
+        assert_eq!(body["latestSchemaVersion"], migrations::latest_version());
+    }
+
+    async fn openapi_document(uri: &str) -> Value {
+        let response = build_routes()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        assert!(content_type.starts_with("application/json"));
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn openapi_endpoint_returns_generated_document() {
+        let payload = openapi_document("/v1/openapi.json").await;
+
+        assert!(payload["openapi"].as_str().unwrap().starts_with("3.1"));
+        for path in [
+            "/health",
+            "/v1/rooms",
+            "/v1/rooms/{id}",
+            "/v1/rooms/{id}/messages",
+            "/v1/messages",
+            "/v1/search",
+            "/v1/auth/login",
+            "/v1/uploads",
+            "/v1/rooms/{id}/tasks/{task_id}/claim",
+            "/v1/collaboration/documents",
+        ] {
+            assert!(payload["paths"].get(path).is_some(), "{path} undocumented");
+        }
+        let schemas = &payload["components"]["schemas"];
+        for schema in [
+            "SendMessageRequest",
+            "StoredMessage",
+            "ErrorResponse",
+            "DelegatedTask",
+        ] {
+            assert!(schemas.get(schema).is_some(), "{schema} missing");
+        }
+        assert_eq!(
+            payload["components"]["securitySchemes"]["bearer"]["scheme"],
+            "bearer"
+        );
+        assert_eq!(payload["paths"]["/health"]["get"]["security"], json!([{}]));
+    }
+
+    #[tokio::test]
+    async fn openapi_document_follows_serde_renames() {
+        let payload = openapi_document("/v1/openapi.json").await;
+
+        let message = &payload["components"]["schemas"]["SendMessageRequest"]["properties"];
+        assert!(message.get("roomId").is_some());
+        assert!(message.get("room_id").is_none());
+    }
+
+    #[tokio::test]
+    async fn legacy_openapi_path_serves_the_same_document() {
+        assert_eq!(
+            openapi_document("/openapi.json").await,
+            openapi_document("/v1/openapi.json").await
+        );
+    }
+
+    #[tokio::test]
+    async fn docs_endpoint_returns_swagger_html() {
+        for uri in ["/v1/docs", "/docs"] {
+            let response = build_routes()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let html = String::from_utf8(body.to_vec()).unwrap();
+            assert!(html.contains("SwaggerUIBundle"));
+            assert!(html.contains("/v1/openapi.json"));
+        }
+    }
+
+    #[tokio::test]
+    async fn metrics_endpoint_returns_prometheus_payload() {
+        ROOMS_CREATED_TOTAL.inc();
+        let app = build_routes();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let payload = String::from_utf8(body.to_vec()).unwrap();
+        assert!(payload.contains("nexis_rooms_created_total"));
+    }
+
+    #[tokio::test]
+    async fn http_metrics_are_labelled_by_route_template() {
+        let app = build_routes();
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/v1/rooms/room_metrics_probe")
+                    .header(
+                        "authorization",
+                        format!("Bearer {}", JwtConfig::test_token("test-user")),
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let payload = String::from_utf8(body.to_vec()).unwrap();
+        assert!(payload
+            .contains(r#"nexis_http_latency_seconds_bucket{method="GET",path="/v1/rooms/:id""#));
+        assert!(payload.contains(r#"path="/v1/rooms/:id",status="404""#));
+        assert!(!payload.contains("room_metrics_probe"));
+    }
+
+    #[tokio::test]
+    async fn response_contains_correlation_id_header() {
+        let app = build_routes();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/health")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key("x-correlation-id"));
+    }
+
+    #[tokio::test]
+    async fn create_room_returns_201_and_room_identity() {
+        let token = JwtConfig::test_token("test-user");
+        let before_rooms_created = ROOMS_CREATED_TOTAL.get();
+        let before_throughput = OPERATION_THROUGHPUT_TOTAL
+            .with_label_values(&["create_room"])
+            .get();
+
+        let app = build_routes();
+        let response = app
+            .oneshot(request_with_token(
+                &token,
+                "POST",
+                "/v1/rooms",
+                json!({
+                    "name": "general",
+                    "topic": "team"
+                }),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let payload = json_body(response).await;
+        assert_eq!(payload["name"], "general");
+        assert!(payload["id"].as_str().unwrap().starts_with("room_"));
+        assert!(ROOMS_CREATED_TOTAL.get() > before_rooms_created);
+        assert!(
+            OPERATION_THROUGHPUT_TOTAL
+                .with_label_values(&["create_room"])
+                .get()
+                > before_throughput
+        );
+    }
+
+    #[tokio::test]
+    async fn configured_router_uses_config_jwt_and_limits() {
+        let mut config = NexisConfig::default();
+        config.auth.jwt_secret = "configured-secret".to_string();
+        config.rate_limits.max_message_bytes = 4;
+        let token = config
+            .auth
+            .jwt_config()
+            .generate_token("nexis:human:alice", "human")
+            .unwrap();
+        let app = build_routes_with_config(
+            Arc::new(config),
+            None,
+            AuditLog::default(),
+            ShutdownController::new(),
+            Storage::default(),
+            KeywordIndexing::new(BatchConfig::default()),
+        );
+
+        let create_room = |token: String| {
+            request_with_token(&token, "POST", "/v1/rooms", json!({ "name": "general" }))
+        };
+
+        let response = app
+            .clone()
+            .oneshot(create_room(JwtConfig::test_token("nexis:human:alice")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .clone()
+            .oneshot(create_room(token.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let room_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let response = app
+            .oneshot(request_with_token(
+                &token,
+                "POST",
+                "/v1/messages",
+                json!({
+                    "roomId": room_id,
+                    "sender": "nexis:human:alice",
+                    "text": "hello"
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn audit_log_records_room_actions_for_admins() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["nexis:human:admin".to_string()];
+        let jwt = config.auth.jwt_config();
+        let alice = jwt.generate_token("nexis:human:alice", "human").unwrap();
+        let admin = jwt.generate_token("nexis:human:admin", "human").unwrap();
+        let app = build_routes_with_config(
+            Arc::new(config),
+            None,
+            AuditLog::default(),
+            ShutdownController::new(),
+            Storage::default(),
+            KeywordIndexing::new(BatchConfig::default()),
+        );
+
+        let response = app
+            .clone()
+            .oneshot(request_with_token(
+                &alice,
+                "POST",
+                "/v1/rooms",
+                json!({ "name": "general" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let room_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let response = app
+            .clone()
+            .oneshot(request_with_token(
+                &alice,
+                "DELETE",
+                "/v1/rooms/room_missing",
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let list_audit = |token: &str, query: &str| {
+            request_with_token(token, "GET", &format!("/v1/audit{query}"), json!({}))
+        };
+
+        let response = app.clone().oneshot(list_audit(&alice, "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app.clone().oneshot(list_audit(&admin, "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let page = json_body(response).await;
+        assert_eq!(page["total"], 2);
+        assert_eq!(page["events"][0]["action"], "room.deleted");
+        assert_eq!(page["events"][0]["result"], "failed");
+        assert_eq!(page["events"][1]["action"], "room.created");
+        assert_eq!(page["events"][1]["memberId"], "nexis:human:alice");
+        assert_eq!(page["events"][1]["resource"], format!("room:{room_id}"));
+
+        let response = app
+            .oneshot(list_audit(&admin, "?action=room.created&limit=1"))
+            .await
+            .unwrap();
+        let page = json_body(response).await;
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["limit"], 1);
+    }
+
+    #[tokio::test]
+    async fn published_messages_are_indexed_through_the_queue() {
+        let indexing = KeywordIndexing::new(BatchConfig::default().with_flush_interval_ms(60_000));
+        let (index, queue) = (indexing.index.clone(), indexing.queue.clone());
+        let app = routes(AppState::default().with_keyword_indexing(indexing));
+
+        let room_id = new_room(&app, "nexis:human:alice", "general").await;
+        let response = app
+            .oneshot(request("nexis:human:alice", "POST", 
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": "release notes" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(queue.len().await, 1);
+        assert!(index.is_empty());
+
+        queue.flush();
+        tokio::time::timeout(std::time::Duration::from_secs(2), async {
+            while index.is_empty() {
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("message was not indexed in time");
+        let response = index.search(SearchRequest::new("release")).await.unwrap();
+        assert_eq!(response.results[0].metadata["sender"], "nexis:human:alice");
+    }
+
+    #[tokio::test]
+    async fn rooms_and_messages_are_restored_from_storage() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["nexis:human:admin".to_string()];
+        let config = Arc::new(config);
+        let storage = Storage::default();
+        let app = routes(
+            AppState {
+                config: config.clone(),
+                ..AppState::default()
+            }
+            .with_storage(storage.clone()),
+        );
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                "/v1/rooms",
+                json!({ "name": "general", "topic": "team" }),
+            ))
+            .await
+            .unwrap();
+        let room_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        for text in ["one", "two", "three"] {
+            let response = app
+                .clone()
+                .oneshot(request(
+                    "nexis:human:alice",
+                    "POST",
+                    "/v1/messages",
+                    json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": text }),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+
+        // A restarted gateway sees the stored room with its newest messages.
+        let restored = Storage::restore(storage.rooms.clone(), storage.messages.clone(), 2)
+            .await
+            .unwrap();
+        let app = routes(
+            AppState {
+                config,
+                ..AppState::default()
+            }
+            .with_storage(restored),
+        );
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "GET",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let room = json_body(response).await;
+        assert_eq!(room["topic"], "team");
+        let texts: Vec<&str> = room["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| message["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(texts, ["two", "three"]);
+
+        let response = app
+            .oneshot(request(
+                "nexis:human:admin",
+                "DELETE",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(storage.rooms.list().await.unwrap().is_empty());
+        assert!(storage
+            .messages
+            .list_by_room(&room_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn create_room_validation_error_records_metric() {
+        let token = JwtConfig::test_token("test-user");
+        let before_errors = OPERATION_ERRORS_TOTAL
+            .with_label_values(&["create_room", "validation"])
+            .get();
+
+        let app = build_routes();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/rooms")
+                    .header("content-type", "application/json")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::from(
+                        json!({
+                            "name": "   "
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(
+            OPERATION_ERRORS_TOTAL
+                .with_label_values(&["create_room", "validation"])
+                .get()
+                > before_errors
+        );
+    }
+
+    #[tokio::test]
+    async fn send_message_returns_404_for_unknown_room() {
+        let token = JwtConfig::test_token("nexis:human:alice");
+
+        let app = build_routes();
+        let response = app
+            .oneshot(request_with_token(
+                &token,
+                "POST",
+                "/v1/messages",
+                json!({
+                    "roomId": "room_missing",
+                    "sender": "nexis:human:alice",
+                    "text": "hello"
+                }),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn send_message_rejects_invalid_fields_with_details() {
+        let token = JwtConfig::test_token("test-user");
+
+        let app = build_routes();
+        let response = app
+            .oneshot(request_with_token(
+                &token,
+                "POST",
+                "/v1/messages",
+                json!({
+                    "roomId": "general",
+                    "sender": "alice",
+                    "text": "  "
+                }),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = json_body(response).await;
+        assert_eq!(body["code"], "VALIDATION_FAILED");
+        let fields: Vec<&str> = body["details"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|detail| detail["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(fields, ["roomId", "sender", "text"]);
+    }
+
+    #[tokio::test]
+    async fn only_admins_send_messages_as_someone_else() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let room_id = new_room(&app, "nexis:human:alice", "general").await;
+        let send = |member: &str, sender: &str| {
+            let body = json!({ "roomId": room_id, "sender": sender, "text": "hi" });
+            app.clone()
+                .oneshot(request(member, "POST", "/v1/messages", body))
+        };
+
+        for sender in ["nexis:human:bob", "nexis:ai:assistant"] {
+            let response = send("nexis:human:alice", sender).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{sender}");
+        }
+        let response = send("nexis:human:alice", "nexis:human:alice")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = send("admin", "nexis:ai:assistant").await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[test]
+    fn room_ids_need_the_room_prefix_and_a_short_slug() {
+        assert!("room_0f3a9c".parse::<RoomId>().is_ok());
+        assert!("room_release-notes".parse::<RoomId>().is_ok());
+        assert!("general".parse::<RoomId>().is_err());
+        assert!("room_".parse::<RoomId>().is_err());
+        assert!("room_a/b".parse::<RoomId>().is_err());
+        assert!(format!("room_{}", "a".repeat(65))
+            .parse::<RoomId>()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn get_room_returns_messages_after_send() {
+        let token = JwtConfig::test_token("nexis:human:alice");
+
+        let app = build_routes();
+
+        let create_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/rooms")
+                    .header("content-type", "application/json")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::from(
+                        json!({
+                            "name": "general"
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(create_response.status(), StatusCode::CREATED);
+        let create_body = axum::body::to_bytes(create_response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let create_payload: Value = serde_json::from_slice(&create_body).unwrap();
+        let room_id = create_payload["id"].as_str().unwrap().to_string();
+
+        let send_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/messages")
+                    .header("content-type", "application/json")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::from(
+                        json!({
+                            "roomId": room_id.clone(),
+                            "sender": "nexis:human:alice",
+                            "text": "hello"
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(send_response.status(), StatusCode::CREATED);
+
+        let get_response = app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/v1/rooms/{}", room_id.clone()))
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(get_response.status(), StatusCode::OK);
+        let get_body = axum::body::to_bytes(get_response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let get_payload: Value = serde_json::from_slice(&get_body).unwrap();
+        assert_eq!(get_payload["id"], room_id);
+        assert_eq!(get_payload["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(get_payload["messages"][0]["text"], "hello");
+    }
+
+    #[cfg(feature = "multi-tenant")]
+    mod multi_tenant_tests {
+        use super::*;
+
+        fn tenant_token(member_id: &str, tenant_id: &str) -> String {
+            JwtConfig::new("test-secret", "test".to_string(), "test".to_string())
+                .generate_token_with_tenant(member_id, "human", Some(tenant_id))
+                .unwrap()
+        }
+
+        #[tokio::test]
+        async fn create_room_with_tenant_includes_tenant_id() {
+            let app = build_routes();
+            let token = tenant_token("alice", "tenant_123");
+            let response = app
+                .clone()
+                .oneshot(request_with_token(
+                    &token,
+                    "POST",
+                    "/v1/rooms",
+                    json!({ "name": "tenant-room", "tenant_id": "tenant_123" }),
+                ))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::CREATED);
+            let payload = json_body(response).await;
+            let room_id = payload["id"].as_str().unwrap();
+            assert!(room_id.starts_with("room_"));
+
+            let response = app
+                .oneshot(request_with_token(
+                    &token,
+                    "GET",
+                    &format!("/v1/rooms/{room_id}"),
+                    json!({}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(json_body(response).await["tenant_id"], "tenant_123");
+        }
+
+        #[tokio::test]
+        async fn create_room_in_other_tenant_is_forbidden() {
+            let app = build_routes();
+            let response = app
+                .oneshot(request_with_token(
+                    &tenant_token("alice", "acme"),
+                    "POST",
+                    "/v1/rooms",
+                    json!({ "name": "sneaky", "tenant_id": "globex" }),
+                ))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+
+        #[tokio::test]
+        async fn rooms_are_isolated_between_tenants() {
+            let app = build_routes();
+            let acme = tenant_token("alice", "acme");
+            let globex = tenant_token("bob", "globex");
+
+            let response = app
+                .clone()
+                .oneshot(request_with_token(
+                    &acme,
+                    "POST",
+                    "/v1/rooms",
+                    json!({ "name": "plans" }),
+                ))
+                .await
+                .unwrap();
+            let room_id = json_body(response).await["id"]
+                .as_str()
+                .unwrap()
+                .to_string();
+
+            for (method, uri, body) in [
+                ("GET", format!("/v1/rooms/{room_id}"), json!({})),
+                ("GET", format!("/v1/rooms/{room_id}/export"), json!({})),
+                (
+                    "POST",
+                    "/v1/messages".to_string(),
+                    json!({ "roomId": room_id, "sender": "nexis:human:bob", "text": "hi" }),
+                ),
+                (
+                    "POST",
+                    format!("/v1/rooms/{room_id}/invite"),
+                    json!({ "memberId": "bob" }),
+                ),
+                ("DELETE", format!("/v1/rooms/{room_id}"), json!({})),
+            ] {
+                let response = app
+                    .clone()
+                    .oneshot(request_with_token(&globex, method, &uri, body))
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::NOT_FOUND, "{method} {uri}");
+            }
+
+            let response = app
+                .clone()
+                .oneshot(request_with_token(&globex, "GET", "/v1/rooms", json!({})))
+                .await
+                .unwrap();
+            let listed = json_body(response).await;
+            assert_eq!(listed["total"], 0);
+            assert!(listed["rooms"].as_array().unwrap().is_empty());
+
+            let response = app
+                .oneshot(request_with_token(&acme, "GET", "/v1/rooms", json!({})))
+                .await
+                .unwrap();
+            let listed = json_body(response).await;
+            assert_eq!(listed["total"], 1);
+            assert_eq!(listed["rooms"][0]["id"], room_id);
+        }
+
+        #[tokio::test]
+        async fn tenant_quotas_and_suspension_are_enforced() {
+            let mut config = NexisConfig::default();
+            config.auth.admin_members = vec!["admin".to_string()];
+            let jwt = config.auth.jwt_config();
+            let admin = jwt.generate_token("admin", "human").unwrap();
+            let alice = jwt
+                .generate_token_with_tenant("nexis:human:alice", "human", Some("acme"))
+                .unwrap();
+            let app = build_routes_with_tenants(
+                Arc::new(config),
+                None,
+                AuditLog::default(),
+                ShutdownController::new(),
+                Storage::default(),
+                KeywordIndexing::new(BatchConfig::default()),
+                TenantDirectory::default(),
+            );
+
+            let response = app
+                .clone()
+                .oneshot(request_with_token(
+                    &alice,
+                    "POST",
+                    "/v1/admin/tenants",
+                    json!({ "name": "Acme", "slug": "acme" }),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+            let response = app
+                .clone()
+                .oneshot(request_with_token(
+                    &admin,
+                    "POST",
+                    "/v1/admin/tenants",
+                    json!({
+                        "name": "Acme",
+                        "slug": "acme",
+                        "quota": { "maxRooms": 1, "maxMessagesPerDay": 1 }
+                    }),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+            let tenant_id = json_body(response).await["id"]
+                .as_str()
+                .unwrap()
+                .to_string();
+
+            let response = app
+                .clone()
+                .oneshot(request_with_token(
+                    &alice,
+                    "POST",
+                    "/v1/rooms",
+                    json!({ "name": "one" }),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+            let room_id = json_body(response).await["id"]
+                .as_str()
+                .unwrap()
+                .to_string();
+
+            let response = app
+                .clone()
+                .oneshot(request_with_token(
+                    &alice,
+                    "POST",
+                    "/v1/rooms",
+                    json!({ "name": "two" }),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(json_body(response).await["code"], "QUOTA_EXCEEDED");
+
+            // Refused messages leave the day's quota untouched.
+            let refused = json!({
+                "roomId": room_id,
+                "sender": "nexis:human:alice",
+                "text": "hi",
+                "attachments": ["upl_missing"],
+            });
+            let response = app
+                .clone()
+                .oneshot(request_with_token(&alice, "POST", "/v1/messages", refused))
+                .await
+                .unwrap();
+            assert!(response.status().is_client_error());
+            assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+            let message = json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": "hi" });
+            let response = app
+                .clone()
+                .oneshot(request_with_token(
+                    &alice,
+                    "POST",
+                    "/v1/messages",
+                    message.clone(),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+            let response = app
+                .clone()
+                .oneshot(request_with_token(&alice, "POST", "/v1/messages", message))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+            let response = app
+                .clone()
+                .oneshot(request_with_token(
+                    &admin,
+                    "GET",
+                    &format!("/v1/admin/tenants/{tenant_id}"),
+                    json!({}),
+                ))
+                .await
+                .unwrap();
+            let details = json_body(response).await;
+            assert_eq!(details["rooms"], 1);
+            assert_eq!(details["usage"]["messagesToday"], 1);
+
+            let response = app
+                .clone()
+                .oneshot(request_with_token(
+                    &admin,
+                    "POST",
+                    &format!("/v1/admin/tenants/{tenant_id}/suspend"),
+                    json!({}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(json_body(response).await["isActive"], false);
+
+            let response = app
+                .clone()
+                .oneshot(request_with_token(&alice, "GET", "/v1/rooms", json!({})))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            assert_eq!(json_body(response).await["code"], "TENANT_SUSPENDED");
+
+            let response = app
+                .oneshot(request_with_token(
+                    &admin,
+                    "POST",
+                    &format!("/v1/admin/tenants/{tenant_id}/resume"),
+                    json!({}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn room_ai_returns_503_without_provider() {
+        let token = JwtConfig::test_token("test-user");
+
+        let app = build_routes();
+        let response = app
+            .oneshot(request_with_token(
+                &token,
+                "POST",
+                "/v1/rooms/room_missing/ai",
+                json!({ "prompt": "hi" }),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn room_ai_overrides_provider_and_model_within_the_allow_list() {
+        #[derive(Debug)]
+        struct ModelEcho;
+
+        #[async_trait::async_trait]
+        impl AIProvider for ModelEcho {
+            fn name(&self) -> &'static str {
+                "other"
+            }
+
+            async fn generate(
+                &self,
+                req: GenerateRequest,
+            ) -> Result<nexis_runtime::GenerateResponse, nexis_runtime::ProviderError> {
+                Ok(nexis_runtime::GenerateResponse {
+                    content: format!("other:{}", req.model.clone().unwrap_or_default()),
+                    model: req.model,
+                    finish_reason: Some("stop".to_string()),
+                    usage: None,
+                })
+            }
+
+            async fn generate_stream(
+                &self,
+                _req: GenerateRequest,
+            ) -> Result<nexis_runtime::ProviderStream, nexis_runtime::ProviderError> {
+                Err(nexis_runtime::ProviderError::Message(
+                    "streaming not supported".to_string(),
+                ))
+            }
+        }
+
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        config.providers.allowed_models = vec!["small".to_string(), "large".to_string()];
+        let providers: HashMap<String, Arc<dyn AIProvider>> = HashMap::from([(
+            "other".to_string(),
+            Arc::new(ModelEcho) as Arc<dyn AIProvider>,
+        )]);
+        let app = routes(
+            AppState {
+                config: Arc::new(config),
+                ai_providers: Arc::new(providers),
+                ..AppState::default()
+            }
+            .with_ai_provider(Arc::new(EchoProvider)),
+        );
+
+        let room_id = new_room(&app, "admin", "ai").await;
+        let ai_uri = format!("/v1/rooms/{room_id}/ai");
+        let ask = |body: Value| app.clone().oneshot(request("admin", "POST", &ai_uri, body));
+
+        let response = ask(json!({ "prompt": "hi", "provider": "other", "model": "large" }))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let reply = json_body(response).await;
+        assert_eq!(reply["content"], "other:large");
+        assert_eq!(reply["model"], "large");
+
+        let response = ask(json!({ "prompt": "hi", "model": "huge" }))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = ask(json!({ "prompt": "hi", "provider": "missing" }))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // The room's own list replaces the configured one.
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PATCH",
+                &format!("/v1/rooms/{room_id}/settings"),
+                json!({ "aiProvider": "other", "aiModels": ["huge"] }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = ask(json!({ "prompt": "hi", "model": "huge" }))
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["content"], "other:huge");
+        let response = ask(json!({ "prompt": "hi", "model": "small" }))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn room_ai_reuses_cached_history_as_messages_arrive() {
+        let state = AppState::default().with_ai_provider(Arc::new(EchoProvider));
+        let conversations = state.conversations.clone();
+        let app = routes(state);
+
+        let room_id = new_room(&app, "nexis:human:alice", "ai").await;
+        let ai_uri = format!("/v1/rooms/{room_id}/ai");
+        let mut replies = Vec::new();
+        for text in ["deploy is at noon", "moved to 3pm"] {
+            let message = json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": text });
+            app.clone()
+                .oneshot(request(
+                    "nexis:human:alice",
+                    "POST",
+                    "/v1/messages",
+                    message,
+                ))
+                .await
+                .unwrap();
+            let response = app
+                .clone()
+                .oneshot(request(
+                    "nexis:human:alice",
+                    "POST",
+                    &ai_uri,
+                    json!({ "prompt": "when?" }),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            replies.push(json_body(response).await);
+        }
+
+        let content = replies[1]["content"].as_str().unwrap();
+        assert!(content.contains("deploy is at noon"));
+        assert!(content.contains("moved to 3pm"));
+        // The first message, the first AI reply and the second message.
+        assert_eq!(replies[1]["contextMessages"], 3);
+        let stats = conversations.stats().await;
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert_eq!(stats.messages, 4);
+    }
+
+    #[tokio::test]
+    async fn search_only_returns_results_from_visible_rooms() {
+        use crate::search::SemanticSearchService;
+        use nexis_vector::prelude::*;
+        use nexis_vector::InMemoryVectorStore;
+
+        let store = Arc::new(InMemoryVectorStore::new(16));
+        let search = SemanticSearchService::new(
+            store.clone(),
+            Arc::new(nexis_runtime::MockEmbeddingProvider::new(16)),
+        );
+        let app = build_routes_with_search(Arc::new(search));
+        let token = JwtConfig::test_token("alice");
+        let response = app
+            .clone()
+            .oneshot(request_with_token(
+                &token,
+                "POST",
+                "/v1/rooms",
+                json!({ "name": "visible" }),
+            ))
+            .await
+            .unwrap();
+        let room_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let visible_room = room_id.parse::<RoomId>().unwrap();
+        for (content, room) in [
+            ("deploy plan", visible_room),
+            ("deploy secrets", RoomId::generate()),
+        ] {
+            store
+                .upsert(Document::new(
+                    Vector::new(vec![0.1; 16]),
+                    content.to_string(),
+                    DocumentMetadata::new().with_room(room),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let search = |token: String| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(request_with_token(
+                        &token,
+                        "GET",
+                        "/v1/search?q=deploy",
+                        json!({}),
+                    ))
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                json_body(response).await
+            }
+        };
+        let results = search(token).await;
+        assert_eq!(results["total"], 1);
+        assert_eq!(results["results"][0]["content"], "deploy plan");
+
+        // Seeing a room is not enough; only its members search it.
+        let results = search(JwtConfig::test_token("bob")).await;
+        assert_eq!(results["total"], 0);
+    }
+
+    #[tokio::test]
+    async fn search_falls_back_to_keywords_without_a_search_service() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["nexis:human:admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+
+        let room_id = new_room(&app, "nexis:human:alice", "ops").await;
+        for text in ["Deploy to staging at noon", "lunch?", "staging deploy done"] {
+            let response = app
+                .clone()
+                .oneshot(request(
+                    "nexis:human:alice",
+                    "POST",
+                    "/v1/messages",
+                    json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": text }),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "GET",
+                "/v1/search?q=deploy%20staging",
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let results = json_body(response).await;
+        assert_eq!(results["total"], 2);
+        assert_eq!(results["results"][0]["room_id"], room_id);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:admin",
+                "DELETE",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                "/v1/search",
+                json!({ "query": "deploy" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["total"], 0);
+    }
+
+    #[tokio::test]
+    async fn room_list_is_scoped_to_membership_and_filtered() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["nexis:human:admin".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let app = routes(state);
+        let list = |member: &'static str, uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(request(member, "GET", uri, Value::Null))
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let page = json_body(response).await;
+                let names: Vec<String> = page["rooms"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|room| room["name"].as_str().unwrap().to_string())
+                    .collect();
+                (names, page["total"].as_u64().unwrap())
+            }
+        };
+
+        let mut room_ids = HashMap::new();
+        for (name, members) in [
+            ("general", &["nexis:human:alice", "nexis:human:bob"][..]),
+            ("Gardening", &["nexis:human:alice"][..]),
+            ("random", &["nexis:human:bob"][..]),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request(
+                    "nexis:human:admin",
+                    "POST",
+                    "/v1/rooms",
+                    json!({ "name": name }),
+                ))
+                .await
+                .unwrap();
+            let room_id = json_body(response).await["id"]
+                .as_str()
+                .unwrap()
+                .to_string();
+            for member in members {
+                app.clone()
+                    .oneshot(request(
+                        "nexis:human:admin",
+                        "POST",
+                        &format!("/v1/rooms/{room_id}/invite"),
+                        json!({ "memberId": member }),
+                    ))
+                    .await
+                    .unwrap();
+            }
+            room_ids.insert(name, room_id);
+        }
+        app.clone()
+            .oneshot(request(
+                "nexis:human:bob",
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_ids["random"], "sender": "nexis:human:bob", "text": "hi" }),
+            ))
+            .await
+            .unwrap();
+
+        // Members only see their own rooms, by name unless asked otherwise.
+        assert_eq!(
+            list("nexis:human:alice", "/v1/rooms").await,
+            (vec!["Gardening".to_string(), "general".to_string()], 2)
+        );
+        assert_eq!(list("nexis:human:carol", "/v1/rooms").await.1, 0);
+        assert_eq!(
+            list("nexis:human:alice", "/v1/rooms?q=GEN").await.0,
+            ["general"]
+        );
+
+        // Admins see every room.
+        assert_eq!(
+            list("nexis:human:admin", "/v1/rooms?sort=members&limit=1").await,
+            (vec!["general".to_string()], 3)
+        );
+        assert_eq!(
+            list("nexis:human:admin", "/v1/rooms?sort=activity").await.0[0],
+            "random"
+        );
+        assert_eq!(
+            list("nexis:human:admin", "/v1/rooms?offset=1&limit=1")
+                .await
+                .0,
+            ["general"]
+        );
+    }
+
+    #[tokio::test]
+    async fn room_members_are_stored_and_restored() {
+        let storage = Storage::default();
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["nexis:human:admin".to_string()];
+        let config = Arc::new(config);
+        let app = |storage: Storage| {
+            routes(
+                AppState {
+                    config: config.clone(),
+                    ..AppState::default()
+                }
+                .with_storage(storage),
+            )
+        };
+        let rooms_of = |app: Router, member: &'static str| async move {
+            let response = app
+                .oneshot(request(member, "GET", "/v1/rooms", Value::Null))
+                .await
+                .unwrap();
+            json_body(response).await["total"].clone()
+        };
+
+        // Creators are members of their rooms.
+        let first = app(storage.clone());
+        let room_id = new_room(&first, "nexis:human:carol", "book club").await;
+        assert_eq!(rooms_of(first.clone(), "nexis:human:carol").await, 1);
+        let response = first
+            .clone()
+            .oneshot(request(
+                "nexis:human:carol",
+                "POST",
+                &format!("/v1/rooms/{room_id}/invite"),
+                json!({ "memberId": "nexis:human:dave" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // A restarted gateway reads the memberships back.
+        let restored = Storage {
+            restored_memberships: storage.memberships.list().await.unwrap(),
+            memberships: storage.memberships.clone(),
+            ..Storage::restore(storage.rooms.clone(), storage.messages.clone(), 10)
+                .await
+                .unwrap()
+        };
+        assert_eq!(restored.restored_memberships.len(), 2);
+        let second = app(restored);
+        assert_eq!(rooms_of(second.clone(), "nexis:human:carol").await, 1);
+        assert_eq!(rooms_of(second.clone(), "nexis:human:dave").await, 1);
+
+        // Deleting the room drops its memberships.
+        second
+            .oneshot(request(
+                "nexis:human:admin",
+                "DELETE",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert!(storage.memberships.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn room_ai_includes_room_history_in_prompt() {
+        let token = JwtConfig::test_token("nexis:human:alice");
+
+        let app = build_routes_with_ai(Arc::new(EchoProvider));
+
+        let create_response = app
+            .clone()
+            .oneshot(request_with_token(
+                &token,
+                "POST",
+                "/v1/rooms",
+                json!({ "name": "general" }),
+            ))
+            .await
+            .unwrap();
+        let create_payload = json_body(create_response).await;
+        let room_id = create_payload["id"].as_str().unwrap().to_string();
+
+        app.clone()
+            .oneshot(request_with_token(
+                &token,
+                "POST",
+                "/v1/messages",
+                json!({
+                    "roomId": room_id.clone(),
+                    "sender": "nexis:human:alice",
+                    "text": "deploy is at noon"
+                }),
+            ))
+            .await
+            .unwrap();
+
+        let ai_response = app
+            .clone()
+            .oneshot(request_with_token(
+                &token,
+                "POST",
+                &format!("/v1/rooms/{room_id}/ai"),
+                json!({ "prompt": "when is the deploy?" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(ai_response.status(), StatusCode::OK);
+        let ai_payload = json_body(ai_response).await;
+        let content = ai_payload["content"].as_str().unwrap();
+        assert!(content.contains("nexis:human:alice: deploy is at noon"));
+        assert!(content.ends_with("when is the deploy?"));
+        assert_eq!(ai_payload["contextMessages"], 1);
+
+        let get_response = app
+            .oneshot(request_with_token(
+                &token,
+                "GET",
+                &format!("/v1/rooms/{room_id}"),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        let get_payload = json_body(get_response).await;
+        assert_eq!(get_payload["messages"][1]["sender"], AI_MEMBER_ID);
+    }
+
+    #[tokio::test]
+    async fn websocket_clients_are_closed_on_shutdown() {
+        use futures::StreamExt;
+        use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+
+        let shutdown = ShutdownController::new();
+        let addr = serve(routes(AppState::default().with_shutdown(shutdown.clone()))).await;
+
+        let mut ws = connect_ws(addr, None).await;
+        tokio::time::timeout(std::time::Duration::from_secs(2), async {
+            while shutdown.active_connections() == 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("connection is tracked");
+
+        shutdown.trigger();
+        match ws.next().await.unwrap().unwrap() {
+            WsMessage::Close(Some(frame)) => {
+                assert_eq!(u16::from(frame.code), close_code::AWAY);
+                assert_eq!(frame.reason.as_str(), "server shutting down");
+            }
+            other => panic!("unexpected frame: {other:?}"),
+        }
+        tokio::time::timeout(std::time::Duration::from_secs(2), shutdown.drain())
+            .await
+            .expect("connections drain after close");
+
+        match connect_async(format!("ws://{addr}/ws")).await {
+            Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+                assert_eq!(response.status().as_u16(), 503);
+            }
+            other => panic!(
+                "expected upgrade to be refused, got {:?}",
+                other.map(|_| ())
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn websocket_subscribers_receive_room_messages() {
+        let token = JwtConfig::test_token("nexis:human:alice");
+        let addr = serve(build_routes()).await;
+
+        let client = reqwest::Client::new();
+        let created: Value = client
+            .post(format!("http://{addr}/v1/rooms"))
+            .bearer_auth(&token)
+            .json(&json!({ "name": "general" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let room_id = created["id"].as_str().unwrap().to_string();
+
+        let mut ws = connect_ws(addr, Some("nexis:human:alice")).await;
+        send_json(
+            &mut ws,
+            json!({ "type": "subscribe", "roomId": room_id.clone() }),
+        )
+        .await;
+        assert_eq!(next_json(&mut ws).await["type"], "subscribed");
+
+        let status = client
+            .post(format!("http://{addr}/v1/messages"))
+            .bearer_auth(&token)
+            .json(&json!({ "roomId": room_id.clone(), "sender": "nexis:human:alice", "text": "hello" }))
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status.as_u16(), 201);
+
+        let event = next_json(&mut ws).await;
+        assert_eq!(event["type"], "message");
+        assert_eq!(event["roomId"], room_id);
+        assert_eq!(event["message"]["sender"], "nexis:human:alice");
+        assert_eq!(event["message"]["text"], "hello");
+    }
+
+    #[tokio::test]
+    async fn websocket_subscribers_resume_after_their_last_event() {
+        let mut config = NexisConfig::default();
+        config.websocket.replay_limit = 2;
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let token = JwtConfig::test_token("nexis:human:alice");
+        let addr = serve(routes(state)).await;
+
+        let client = reqwest::Client::new();
+        let created: Value = client
+            .post(format!("http://{addr}/v1/rooms"))
+            .bearer_auth(&token)
+            .json(&json!({ "name": "general" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let room_id = created["id"].as_str().unwrap().to_string();
+        let post = |text: &'static str| {
+            let request = client
+                .post(format!("http://{addr}/v1/messages"))
+                .bearer_auth(&token)
+                .json(&json!({ "roomId": room_id.clone(), "sender": "nexis:human:alice", "text": text }));
+            async move {
+                let sent: Value = request.send().await.unwrap().json().await.unwrap();
+                sent["id"].as_str().unwrap().to_string()
+            }
+        };
+        let first = post("one").await;
+        post("two").await;
+        let third = post("three").await;
+        post("four").await;
+
+        // Three messages were missed but only the newest two fit the window.
+        let mut ws = connect_ws(addr, Some("nexis:human:alice")).await;
+        send_json(
+            &mut ws,
+            json!({ "type": "ack", "roomId": room_id.clone(), "eventId": first }),
+        )
+        .await;
+        let refused = next_json(&mut ws).await;
+        assert_eq!(refused["type"], "error");
+        assert_eq!(refused["message"], "not subscribed to this room");
+        send_json(
+            &mut ws,
+            json!({ "type": "subscribe", "roomId": room_id.clone(), "lastEventId": first }),
+        )
+        .await;
+        let subscribed = next_json(&mut ws).await;
+        assert_eq!(subscribed["type"], "subscribed");
+        assert_eq!(subscribed["replayed"], 2);
+        assert_eq!(next_json(&mut ws).await["type"], "replay_truncated");
+        assert_eq!(next_json(&mut ws).await["message"]["text"], "three");
+        assert_eq!(next_json(&mut ws).await["message"]["text"], "four");
+
+        send_json(
+            &mut ws,
+            json!({ "type": "ack", "roomId": room_id.clone(), "eventId": third }),
+        )
+        .await;
+        assert_eq!(next_json(&mut ws).await["type"], "acked");
+        ws.close(None).await.unwrap();
+
+        // A later connection resumes from the acknowledged message.
+        post("five").await;
+        let mut ws = connect_ws(addr, Some("nexis:human:alice")).await;
+        send_json(
+            &mut ws,
+            json!({ "type": "subscribe", "roomId": room_id.clone() }),
+        )
+        .await;
+        assert_eq!(next_json(&mut ws).await["replayed"], 2);
+        assert_eq!(next_json(&mut ws).await["message"]["text"], "four");
+        assert_eq!(next_json(&mut ws).await["message"]["text"], "five");
+
+        post("six").await;
+        let live = next_json(&mut ws).await;
+        assert_eq!(live["type"], "message");
+        assert_eq!(live["message"]["text"], "six");
+    }
+
+    #[tokio::test]
+    async fn websocket_subscriptions_need_a_member_of_the_room() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let room_id = invite_only_room(&app, "admin", "ops").await;
+        let addr = serve(app).await;
+        let subscribe = json!({ "type": "subscribe", "roomId": room_id.clone() });
+
+        for (member, message) in [
+            (None, "subscriptions need an authenticated connection"),
+            (Some("alice"), "you are not a member of this room"),
+        ] {
+            let mut ws = connect_ws(addr, member).await;
+            send_json(&mut ws, subscribe.clone()).await;
+            let reply = next_json(&mut ws).await;
+            assert_eq!(reply["type"], "error", "{member:?}");
+            assert_eq!(reply["message"], message);
+        }
+        let mut ws = connect_ws(addr, Some("alice")).await;
+        send_json(
+            &mut ws,
+            json!({ "type": "subscribe", "roomId": "room_missing" }),
+        )
+        .await;
+        assert_eq!(next_json(&mut ws).await["message"], "room not found");
+
+        let mut ws = connect_ws(addr, Some("admin")).await;
+        send_json(&mut ws, subscribe).await;
+        assert_eq!(next_json(&mut ws).await["type"], "subscribed");
+    }
+
+    #[tokio::test]
+    async fn websocket_connections_missing_heartbeats_are_reaped() {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let mut config = NexisConfig::default();
+        config.websocket.ping_interval_secs = 1;
+        config.websocket.max_missed_pongs = 1;
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let registry = state.connections.clone();
+        let app = routes(state);
+        let room_id = new_room(&app, "alice", "ops").await;
+        let addr = serve(app).await;
+        let subscribe = json!({ "type": "subscribe", "roomId": room_id.clone() }).to_string();
+
+        let mut silent = connect_ws(addr, Some("alice")).await;
+        silent
+            .send(WsMessage::Text(subscribe.clone().into()))
+            .await
+            .unwrap();
+        let subscribed = silent.next().await.unwrap().unwrap();
+        assert!(matches!(subscribed, WsMessage::Text(_)));
+        assert_eq!(registry.locate("alice").await.unwrap().len(), 1);
+
+        // Reading the stream answers pings, so this connection stays open
+        // while the silent one is reaped.
+        let mut watcher = connect_ws(addr, Some("bob")).await;
+        watcher
+            .send(WsMessage::Text(subscribe.into()))
+            .await
+            .unwrap();
+        let presence = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            let mut pings = 0;
+            loop {
+                match watcher.next().await.unwrap().unwrap() {
+                    WsMessage::Ping(_) => pings += 1,
+                    WsMessage::Text(text) => {
+                        let event: Value = serde_json::from_str(&text).unwrap();
+                        if event["type"] == "presence" {
+                            return (event, pings);
+                        }
+                    }
+                    other => panic!("unexpected frame: {other:?}"),
+                }
+            }
+        })
+        .await
+        .unwrap();
+        let (event, pings) = presence;
+        assert!(pings >= 1);
+        assert_eq!(event["roomId"], room_id);
+        assert_eq!(event["memberId"], "alice");
+        assert_eq!(event["status"], "offline");
+        assert!(registry.locate("alice").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn authenticated_websocket_connections_are_registered() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            instance_id: "gw_a".into(),
+            ..AppState::default()
+        };
+        let registry = state.connections.clone();
+        let addr = serve(routes(state)).await;
+
+        let mut ws = connect_ws(addr, Some("alice")).await;
+        // Anonymous connections are not recorded.
+        let _anonymous = connect_ws(addr, None).await;
+
+        let client = reqwest::Client::new();
+        let admin = JwtConfig::test_token("admin");
+        let located: Value = client
+            .get(format!("http://{addr}/v1/admin/members/alice/connections"))
+            .bearer_auth(&admin)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(located["memberId"], "alice");
+        assert_eq!(located["connections"][0]["instanceId"], "gw_a");
+
+        let route = client
+            .post(format!("http://{addr}/v1/admin/connections/route"))
+            .bearer_auth(&admin)
+            .json(&json!({ "memberIds": ["alice", "bob"] }))
+            .send()
             .await
             .unwrap();
-        let payload: Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(payload["openapi"], "3.0.3");
+        assert_eq!(route.status().as_u16(), 200);
+        let route: Value = route.json().await.unwrap();
+        assert_eq!(route["instances"], json!({ "gw_a": ["alice"] }));
+        assert_eq!(route["offline"], json!(["bob"]));
+
+        let status = client
+            .post(format!("http://{addr}/v1/admin/connections/route"))
+            .bearer_auth(JwtConfig::test_token("alice"))
+            .json(&json!({ "memberIds": ["bob"] }))
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status.as_u16(), 403);
+
+        ws.close(None).await.unwrap();
+        for _ in 0..50 {
+            if registry.locate("alice").await.unwrap().is_empty() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("closed connection is still registered");
     }
 
     #[tokio::test]
-    async fn docs_endpoint_returns_swagger_html() {
-        let app = build_routes();
-        let response = app
-            .oneshot(Request::builder().uri("/docs").body(Body::empty()).unwrap())
-            .await
-            .unwrap();
+    async fn webhook_management_endpoints() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["test-user".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let call = |method: &str, uri: String, body: Option<Value>| {
+            let request = request("test-user", method, &uri, body.unwrap_or(Value::Null));
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                (response.status(), json_body(response).await)
+            }
+        };
+
+        let (status, room) = call("POST", "/v1/rooms".into(), Some(json!({"name": "hooks"}))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let room_id = room["id"].as_str().unwrap().to_string();
+        let base = format!("/v1/rooms/{room_id}/webhooks");
+
+        let (status, _) = call("POST", base.clone(), Some(json!({"url": "not a url"}))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = call(
+            "POST",
+            "/v1/rooms/missing/webhooks".into(),
+            Some(json!({"url": "https://example.com/hook"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
 
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-            .await
-            .unwrap();
-        let html = String::from_utf8(body.to_vec()).unwrap();
-        assert!(html.contains("SwaggerUIBundle"));
-        assert!(html.contains("/openapi.json"));
+        let (status, created) = call(
+            "POST",
+            base.clone(),
+            Some(json!({"url": "https://example.com/hook", "events": ["member.invited"]})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["events"], json!(["member.invited"]));
+        let webhook_id = created["id"].as_str().unwrap().to_string();
+        let secret = created["secret"].as_str().unwrap().to_string();
+
+        let (status, listed) = call("GET", base.clone(), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed["total"], 1);
+        assert!(listed["webhooks"][0].get("secret").is_none());
+
+        let (status, rotated) = call("POST", format!("{base}/{webhook_id}/rotate"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_ne!(rotated["secret"].as_str().unwrap(), secret);
+
+        let (status, disabled) = call("POST", format!("{base}/{webhook_id}/disable"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(disabled["enabled"], false);
+
+        let (status, deliveries) =
+            call("GET", format!("{base}/{webhook_id}/deliveries"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(deliveries["total"], 0);
+
+        let (status, _) = call("POST", format!("{base}/wh_missing/disable"), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
-    async fn metrics_endpoint_returns_prometheus_payload() {
-        ROOMS_CREATED_TOTAL.inc();
-        let app = build_routes();
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/metrics")
-                    .body(Body::empty())
-                    .unwrap(),
+    async fn exported_rooms_can_be_reimported() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["test-user".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let call = |method: &str, uri: String, body: Body| {
+            let request = raw_request("test-user", method, &uri, "application/json", body);
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                (response.status(), text_body(response).await)
+            }
+        };
+        let json_body = |value: Value| Body::from(value.to_string());
+
+        // Alice and bob stay members of the tenant once the archived room
+        // is gone.
+        let lobby_id = new_room(&app, "test-user", "lobby").await;
+        for member_id in ["nexis:human:alice", "nexis:human:bob"] {
+            call(
+                "POST",
+                format!("/v1/rooms/{lobby_id}/invite"),
+                json_body(json!({ "memberId": member_id })),
             )
-            .await
-            .unwrap();
+            .await;
+        }
 
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        let (_, created) = call(
+            "POST",
+            "/v1/rooms".into(),
+            json_body(json!({"name": "archive"})),
+        )
+        .await;
+        let room_id = serde_json::from_str::<Value>(&created).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        call(
+            "POST",
+            format!("/v1/rooms/{room_id}/invite"),
+            json_body(json!({"memberId": "nexis:human:bob"})),
+        )
+        .await;
+        let (_, first) = call(
+            "POST",
+            "/v1/messages".into(),
+            json_body(json!({"roomId": room_id, "sender": "nexis:human:alice", "text": "first"})),
+        )
+        .await;
+        let first_id = serde_json::from_str::<Value>(&first).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        call(
+            "POST",
+            "/v1/messages".into(),
+            json_body(json!({
+                "roomId": room_id,
+                "sender": "nexis:human:bob",
+                "text": "second",
+                "replyTo": first_id
+            })),
+        )
+        .await;
+
+        let (status, archive) =
+            call("GET", format!("/v1/rooms/{room_id}/export"), Body::empty()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(archive.lines().count(), 6);
+
+        let (status, _) = call(
+            "POST",
+            "/v1/rooms/import".into(),
+            Body::from(archive.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, _) = call("DELETE", format!("/v1/rooms/{room_id}"), Body::empty()).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, imported) = call(
+            "POST",
+            "/v1/rooms/import".into(),
+            Body::from(archive.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let imported: Value = serde_json::from_str(&imported).unwrap();
+        assert_eq!(imported["id"], room_id);
+        assert_eq!(imported["members"], 2);
+        assert_eq!(imported["messages"], 2);
+
+        let (_, room) = call("GET", format!("/v1/rooms/{room_id}"), Body::empty()).await;
+        let room: Value = serde_json::from_str(&room).unwrap();
+        assert_eq!(room["messages"][0]["id"], first_id);
+        assert_eq!(room["messages"][1]["text"], "second");
+        assert_eq!(room["messages"][1]["reply_to"], first_id);
+
+        let (status, _) = call("POST", "/v1/rooms/import".into(), Body::from("garbage")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let tampered = archive.replace(r#""sender":"nexis:human:bob""#, r#""sender":"bob""#);
+        let (status, rejected) =
+            call("POST", "/v1/rooms/import".into(), Body::from(tampered)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let rejected: Value = serde_json::from_str(&rejected).unwrap();
+        assert_eq!(rejected["details"][0]["field"], "messages[1].sender");
+
+        // Messages can't be put in the name of strangers, nor imported by
+        // anyone but an admin.
+        let (status, _) = call("DELETE", format!("/v1/rooms/{room_id}"), Body::empty()).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let forged = archive.replace("nexis:human:bob", "nexis:human:mallory");
+        let (status, rejected) = call("POST", "/v1/rooms/import".into(), Body::from(forged)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let rejected: Value = serde_json::from_str(&rejected).unwrap();
+        assert_eq!(rejected["details"][0]["field"], "messages[1].sender");
+        assert_eq!(
+            rejected["details"][0]["message"],
+            "must be a member of the tenant"
+        );
+        let response = app
+            .clone()
+            .oneshot(raw_request(
+                "nexis:human:alice",
+                "POST",
+                "/v1/rooms/import",
+                "application/json",
+                Body::from(archive),
+            ))
             .await
             .unwrap();
-        let payload = String::from_utf8(body.to_vec()).unwrap();
-        assert!(payload.contains("nexis_rooms_created_total"));
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
     }
 
     #[tokio::test]
-    async fn response_contains_correlation_id_header() {
-        let app = build_routes();
+    async fn importers_become_members_of_the_imported_room() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string(), "importer".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let app = routes(state.clone());
+        let room_id = invite_only_room(&app, "admin", "archive").await;
         let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/health")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+            .clone()
+            .oneshot(request(
+                "admin",
+                "GET",
+                &format!("/v1/rooms/{room_id}/export"),
+                Value::Null,
+            ))
             .await
             .unwrap();
-
-        assert_eq!(response.status(), StatusCode::OK);
-        assert!(response.headers().contains_key("x-correlation-id"));
-    }
-
-    #[tokio::test]
-    async fn create_room_returns_201_and_room_identity() {
-        use crate::auth::JwtConfig;
-        let token = JwtConfig::test_token("test-user");
-        let before_rooms_created = ROOMS_CREATED_TOTAL.get();
-        let before_throughput = OPERATION_THROUGHPUT_TOTAL
-            .with_label_values(&["create_room"])
-            .get();
-
-        let app = build_routes();
+        let archive = text_body(response).await;
         let response = app
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/v1/rooms")
-                    .header("content-type", "application/json")
-                    .header("authorization", format!("Bearer {}", token))
-                    .body(Body::from(
-                        json!({
-                            "name": "general",
-                            "topic": "team"
-                        })
-                        .to_string(),
-                    ))
-                    .unwrap(),
-            )
+            .clone()
+            .oneshot(request(
+                "admin",
+                "DELETE",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
 
-        assert_eq!(response.status(), StatusCode::CREATED);
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        let response = app
+            .clone()
+            .oneshot(raw_request(
+                "importer",
+                "POST",
+                "/v1/rooms/import",
+                "application/json",
+                Body::from(archive),
+            ))
             .await
             .unwrap();
-        let payload: Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(payload["name"], "general");
-        assert!(payload["id"].as_str().unwrap().starts_with("room_"));
-        assert!(ROOMS_CREATED_TOTAL.get() > before_rooms_created);
-        assert!(
-            OPERATION_THROUGHPUT_TOTAL
-                .with_label_values(&["create_room"])
-                .get()
-                > before_throughput
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(json_body(response).await["members"], 2);
+        assert_eq!(
+            state.room_members.read().await[&room_id],
+            vec!["admin".to_string(), "importer".to_string()]
         );
     }
 
     #[tokio::test]
-    async fn create_room_validation_error_records_metric() {
-        use crate::auth::JwtConfig;
-        let token = JwtConfig::test_token("test-user");
-        let before_errors = OPERATION_ERRORS_TOTAL
-            .with_label_values(&["create_room", "validation"])
-            .get();
-
-        let app = build_routes();
+    async fn only_admins_delete_rooms() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let room_id = new_room(&app, "admin", "general").await;
         let response = app
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/v1/rooms")
-                    .header("content-type", "application/json")
-                    .header("authorization", format!("Bearer {}", token))
-                    .body(Body::from(
-                        json!({
-                            "name": "   "
-                        })
-                        .to_string(),
-                    ))
-                    .unwrap(),
-            )
+            .clone()
+            .oneshot(request(
+                "bob",
+                "POST",
+                &format!("/v1/rooms/{room_id}/join"),
+                json!({}),
+            ))
             .await
             .unwrap();
+        assert!(response.status().is_success());
 
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-        assert!(
-            OPERATION_ERRORS_TOTAL
-                .with_label_values(&["create_room", "validation"])
-                .get()
-                > before_errors
-        );
-    }
-
-    #[tokio::test]
-    async fn send_message_returns_404_for_unknown_room() {
-        use crate::auth::JwtConfig;
-        let token = JwtConfig::test_token("test-user");
+        let uri = format!("/v1/rooms/{room_id}");
+        for member in ["mallory", "bob"] {
+            let response = app
+                .clone()
+                .oneshot(request(member, "DELETE", &uri, Value::Null))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{member}");
+        }
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", &uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
 
-        let app = build_routes();
         let response = app
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/v1/messages")
-                    .header("content-type", "application/json")
-                    .header("authorization", format!("Bearer {}", token))
-                    .body(Body::from(
-                        json!({
-                            "roomId": "room_missing",
-                            "sender": "alice",
-                            "text": "hello"
-                        })
-                        .to_string(),
-                    ))
-                    .unwrap(),
-            )
+            .clone()
+            .oneshot(request(
+                "admin",
+                "GET",
+                "/v1/audit?action=room.deleted",
+                Value::Null,
+            ))
             .await
             .unwrap();
+        let page = json_body(response).await;
+        assert_eq!(page["total"], 2);
+        assert_eq!(page["events"][0]["result"], "failed");
+        assert_eq!(page["events"][0]["memberId"], "bob");
 
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app
+            .clone()
+            .oneshot(request("admin", "DELETE", &uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
     }
 
     #[tokio::test]
-    async fn get_room_returns_messages_after_send() {
-        use crate::auth::JwtConfig;
-        let token = JwtConfig::test_token("test-user");
-
-        let app = build_routes();
+    async fn room_endpoints_need_a_member_of_the_room() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(
+            AppState {
+                config: Arc::new(config),
+                ..AppState::default()
+            }
+            .with_ai_provider(Arc::new(EchoProvider)),
+        );
+        let room_id = invite_only_room(&app, "admin", "ops").await;
+
+        for (method, uri, body) in [
+            ("GET", format!("/v1/rooms/{room_id}/export"), Value::Null),
+            (
+                "POST",
+                format!("/v1/rooms/{room_id}/webhooks"),
+                json!({ "url": "https://example.com/hook" }),
+            ),
+            (
+                "POST",
+                format!("/v1/rooms/{room_id}/ai"),
+                json!({ "prompt": "summarize" }),
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request("alice", method, &uri, body.clone()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{uri}");
+            let response = app
+                .clone()
+                .oneshot(request("admin", method, &uri, body))
+                .await
+                .unwrap();
+            assert!(response.status().is_success(), "{uri}");
+        }
 
-        let create_response = app
+        // Webhook secrets and delivery history stay with the room's members,
+        // and only admins rotate or disable a webhook.
+        let response = app
             .clone()
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/v1/rooms")
-                    .header("content-type", "application/json")
-                    .header("authorization", format!("Bearer {}", token))
-                    .body(Body::from(
-                        json!({
-                            "name": "general"
-                        })
-                        .to_string(),
-                    ))
-                    .unwrap(),
-            )
+            .oneshot(request(
+                "admin",
+                "POST",
+                &format!("/v1/rooms/{room_id}/webhooks"),
+                json!({ "url": "https://example.com/hook" }),
+            ))
             .await
             .unwrap();
-        assert_eq!(create_response.status(), StatusCode::CREATED);
-        let create_body = axum::body::to_bytes(create_response.into_body(), usize::MAX)
+        let webhook_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let base = format!("/v1/rooms/{room_id}/webhooks");
+        for (method, uri) in [
+            ("GET", base.clone()),
+            ("GET", format!("{base}/{webhook_id}/deliveries")),
+            ("POST", format!("{base}/{webhook_id}/rotate")),
+            ("POST", format!("{base}/{webhook_id}/disable")),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request("alice", method, &uri, Value::Null))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{uri}");
+        }
+        let room_id = new_room(&app, "admin", "hooks").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "POST",
+                &format!("/v1/rooms/{room_id}/webhooks"),
+                json!({ "url": "https://example.com/hook" }),
+            ))
             .await
             .unwrap();
-        let create_payload: Value = serde_json::from_slice(&create_body).unwrap();
-        let room_id = create_payload["id"].as_str().unwrap().to_string();
-
-        let send_response = app
+        let webhook_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let response = app
             .clone()
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/v1/messages")
-                    .header("content-type", "application/json")
-                    .header("authorization", format!("Bearer {}", token))
-                    .body(Body::from(
-                        json!({
-                            "roomId": room_id.clone(),
-                            "sender": "alice",
-                            "text": "hello"
-                        })
-                        .to_string(),
-                    ))
-                    .unwrap(),
-            )
+            .oneshot(request(
+                "alice",
+                "POST",
+                &format!("/v1/rooms/{room_id}/webhooks/{webhook_id}/rotate"),
+                Value::Null,
+            ))
             .await
             .unwrap();
-        assert_eq!(send_response.status(), StatusCode::CREATED);
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
-        let get_response = app
-            .oneshot(
-                Request::builder()
-                    .method("GET")
-                    .uri(format!("/v1/rooms/{}", room_id.clone()))
-                    .header("authorization", format!("Bearer {}", token))
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+        // `@ai` replies are posted on the caller's behalf, so muted members
+        // can't ask for them.
+        let room_id = new_room(&app, "admin", "general").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &format!("/v1/rooms/{room_id}/members/alice/mute"),
+                json!({}),
+            ))
             .await
             .unwrap();
-        assert_eq!(get_response.status(), StatusCode::OK);
-        let get_body = axum::body::to_bytes(get_response.into_body(), usize::MAX)
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "POST",
+                &format!("/v1/rooms/{room_id}/ai"),
+                json!({ "prompt": "summarize" }),
+            ))
             .await
             .unwrap();
-        let get_payload: Value = serde_json::from_slice(&get_body).unwrap();
-        assert_eq!(get_payload["id"], room_id);
-        assert_eq!(get_payload["messages"].as_array().unwrap().len(), 1);
-        assert_eq!(get_payload["messages"][0]["text"], "hello");
-    }
-
-    #[cfg(feature = "multi-tenant")]
-    mod multi_tenant_tests {
-        use super::*;
-
-        #[tokio::test]
-        async fn create_room_with_tenant_includes_tenant_id() {
-            let app = build_routes();
-            let response = app
-                .oneshot(
-                    Request::builder()
-                        .method("POST")
-                        .uri("/v1/rooms")
-                        .header("content-type", "application/json")
-                        .body(Body::from(
-                            json!({
-                                "name": "tenant-room",
-                                "tenant_id": "tenant_123"
-                            })
-                            .to_string(),
-                        ))
-                        .unwrap(),
-                )
-                .await
-                .unwrap();
-
-            assert_eq!(response.status(), StatusCode::CREATED);
-            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-                .await
-                .unwrap();
-            let payload: Value = serde_json::from_slice(&body).unwrap();
-            assert!(payload["id"].as_str().unwrap().starts_with("room_"));
-        }
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(json_body(response).await["code"], "MEMBER_MUTED");
     }
 }
@@ -0,0 +1,374 @@
+//! Message moderation and per-room moderation policies.
+//!
+//! Message text is checked by the configured [`ModerationService`] before it
+//! is stored. The room's policy decides whether tripped categories reject the
+//! message or only flag it; rooms without an override in their settings use
+//! `[moderation]`.
+//!
+//! [`ModerationService`]: crate::moderation::ModerationService
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::settings::{room_settings, update_settings};
+use super::{
+    ensure_room_access, error_codes, joins, require_admin, storage_error_response, ErrorResponse,
+    SharedState,
+};
+use crate::audit::{AuditAction, AuditEvent, AuditResult};
+use crate::auth::AuthenticatedUser;
+use crate::moderation::{ModerationDecision, ModerationPolicy};
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new().route(
+        "/v1/rooms/:id/moderation",
+        get(get_room_moderation).put(set_room_moderation),
+    )
+}
+
+/// Attached to messages that tripped moderation but were let through.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct MessageModeration {
+    pub(super) flagged: bool,
+    pub(super) categories: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RoomModerationResponse {
+    room_id: String,
+    /// Name of the active moderation service; absent when moderation is off.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    provider: Option<&'static str>,
+    #[serde(flatten)]
+    policy: ModerationPolicy,
+    /// Whether the room overrides the gateway-wide policy.
+    overridden: bool,
+}
+
+async fn room_policy(state: &SharedState, room_id: &str) -> (ModerationPolicy, bool) {
+    match room_settings(state, room_id).await.moderation {
+        Some(policy) => (policy, true),
+        None => (ModerationPolicy::from_config(&state.config), false),
+    }
+}
+
+async fn moderation_response(state: &SharedState, room_id: String) -> RoomModerationResponse {
+    let (policy, overridden) = room_policy(state, &room_id).await;
+    RoomModerationResponse {
+        room_id,
+        provider: state.moderation.as_ref().map(|service| service.name()),
+        policy,
+        overridden,
+    }
+}
+
+/// Check `text` against the room's policy before it is stored.
+///
+/// Returns the metadata to attach when the message is flagged, or the
+/// response to send when it is rejected or cannot be checked.
+pub(super) async fn moderate(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    room_id: &str,
+    text: &str,
+) -> Result<Option<MessageModeration>, Response> {
+    let Some(service) = state.moderation.as_ref() else {
+        return Ok(None);
+    };
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let result = match service.check(text).await {
+        Ok(result) => result,
+        Err(err) if state.config.moderation.fail_open => {
+            tracing::warn!(
+                provider = service.name(),
+                "Moderation check failed: {}",
+                err
+            );
+            return Ok(None);
+        }
+        Err(err) => {
+            tracing::error!(
+                provider = service.name(),
+                "Moderation check failed: {}",
+                err
+            );
+            return Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse::service_unavailable(
+                    "moderation service unavailable",
+                )),
+            )
+                .into_response());
+        }
+    };
+
+    let (policy, _) = room_policy(state, room_id).await;
+    match policy.decide(&result) {
+        ModerationDecision::Allow => Ok(None),
+        ModerationDecision::Flag(categories) => {
+            state
+                .audit
+                .record(
+                    AuditEvent::new(
+                        &user.member_id,
+                        AuditAction::MessageFlagged,
+                        format!("room:{room_id}"),
+                    )
+                    .with_result(AuditResult::Success, categories.join(",")),
+                )
+                .await;
+            Ok(Some(MessageModeration {
+                flagged: true,
+                categories,
+            }))
+        }
+        ModerationDecision::Reject(categories) => {
+            let reason = categories.join(",");
+            state
+                .audit
+                .record(
+                    AuditEvent::new(
+                        &user.member_id,
+                        AuditAction::MessageRejected,
+                        format!("room:{room_id}"),
+                    )
+                    .with_result(AuditResult::Denied, reason.clone()),
+                )
+                .await;
+            Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ErrorResponse {
+                    error: format!("message rejected by moderation: {reason}"),
+                    code: Some(error_codes::CONTENT_REJECTED),
+                }),
+            )
+                .into_response())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/moderation",
+    tag = "rooms",
+    summary = "The room's effective moderation policy",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room's policy", body = RoomModerationResponse),
+        (status = 403, description = "The caller is not a member of the room", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.get_room_moderation", skip(state, user), fields(room_id = %id))]
+async fn get_room_moderation(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
+    }
+    (StatusCode::OK, Json(moderation_response(&state, id).await)).into_response()
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/rooms/{id}/moderation",
+    tag = "rooms",
+    summary = "Override the moderation policy for a room (admin only)",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = ModerationPolicy,
+    responses(
+        (status = 200, description = "The room's new policy", body = RoomModerationResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.set_room_moderation",
+    skip(state, user, policy),
+    fields(room_id = %id, member_id = %user.member_id)
+)]
+async fn set_room_moderation(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(policy): Json<ModerationPolicy>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+
+    let reason = policy.action.as_str();
+    if let Err(err) =
+        update_settings(&state, &id, |settings| settings.moderation = Some(policy)).await
+    {
+        return storage_error_response(err);
+    }
+    state
+        .audit
+        .record(
+            AuditEvent::new(
+                &user.member_id,
+                AuditAction::RoomModerationChanged,
+                format!("room:{id}"),
+            )
+            .with_result(AuditResult::Success, reason),
+        )
+        .await;
+    (StatusCode::OK, Json(moderation_response(&state, id).await)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn send_message_applies_room_moderation_policy() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        config.moderation.provider = crate::config::ModerationProviderKind::Keywords;
+        config.moderation.action = crate::config::ModerationAction::Reject;
+        config.moderation.rules = vec![crate::config::KeywordRule {
+            category: "spam".to_string(),
+            pattern: r"(?i)\bbuy now\b".to_string(),
+        }];
+        let app = routes(AppState {
+            moderation: crate::moderation::from_config(&config)
+                .unwrap()
+                .map(Arc::from),
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+
+        let room_id = new_room(&app, "admin", "deals").await;
+        let spam = json!({ "roomId": room_id, "sender": "nexis:human:admin", "text": "BUY NOW!" });
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", "/v1/messages", spam.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(response).await["code"], "CONTENT_REJECTED");
+
+        let moderation_uri = format!("/v1/rooms/{room_id}/moderation");
+        let policy = json!({ "action": "flag", "categories": ["spam"] });
+        let response = app
+            .clone()
+            .oneshot(request("alice", "PUT", &moderation_uri, policy.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request("admin", "PUT", &moderation_uri, policy))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let room_policy = json_body(response).await;
+        assert_eq!(room_policy["provider"], "keywords");
+        assert_eq!(room_policy["action"], "flag");
+        assert_eq!(room_policy["overridden"], true);
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", "/v1/messages", spam))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": "nexis:human:admin", "text": "lunch?" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "GET",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        let room = json_body(response).await;
+        assert_eq!(room["messages"][0]["moderation"]["flagged"], true);
+        assert_eq!(room["messages"][0]["moderation"]["categories"][0], "spam");
+        assert!(room["messages"][1].get("moderation").is_none());
+
+        let response = app
+            .oneshot(request("admin", "GET", "/v1/audit", Value::Null))
+            .await
+            .unwrap();
+        let actions: Vec<Value> = json_body(response).await["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|event| event["action"].clone())
+            .collect();
+        for action in [
+            "message.rejected",
+            "room.moderation_changed",
+            "message.flagged",
+        ] {
+            assert!(actions.contains(&json!(action)), "missing {action}");
+        }
+    }
+
+    #[tokio::test]
+    async fn only_members_read_a_closed_room_moderation_policy() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let room_id = invite_only_room(&app, "admin", "ops").await;
+        let moderation_uri = format!("/v1/rooms/{room_id}/moderation");
+
+        let response = app
+            .clone()
+            .oneshot(request("alice", "GET", &moderation_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .oneshot(request("admin", "GET", &moderation_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
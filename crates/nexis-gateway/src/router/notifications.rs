@@ -0,0 +1,327 @@
+//! The caller's notification preferences.
+//!
+//! Members manage their own preferences; see [`crate::notifications`] for
+//! how they decide who is notified and where.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{ensure_room_access, joins, ErrorResponse, SharedState};
+use crate::auth::AuthenticatedUser;
+use crate::notifications::{NotificationError, NotificationLevel, NotificationPreferences};
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route(
+            "/v1/notifications/preferences",
+            get(get_preferences).put(set_preferences),
+        )
+        .route(
+            "/v1/rooms/:id/notifications",
+            put(set_room_level).delete(clear_room_level),
+        )
+}
+
+/// Deliver notification digests until the gateway shuts down, unless
+/// `[notifications] enabled` is off.
+pub(super) fn spawn_digests(state: &SharedState) {
+    let config = &state.config.notifications;
+    if config.enabled {
+        state
+            .notifications
+            .clone()
+            .spawn(config.digest_tick(), state.shutdown.clone());
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct PreferencesResponse {
+    member_id: String,
+    #[serde(flatten)]
+    preferences: NotificationPreferences,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct RoomLevelRequest {
+    level: NotificationLevel,
+}
+
+fn preferences_response(member_id: String, preferences: NotificationPreferences) -> Response {
+    let response = PreferencesResponse {
+        member_id,
+        preferences,
+    };
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+fn notification_error_response(err: NotificationError) -> Response {
+    let message = err.to_string();
+    match err {
+        NotificationError::InvalidPreferences(_) => (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(message)),
+        )
+            .into_response(),
+        NotificationError::Delivery(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal_error()),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/notifications/preferences",
+    tag = "notifications",
+    summary = "The caller's notification preferences",
+    responses(
+        (status = 200, description = "The caller's preferences", body = PreferencesResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.get_notification_preferences", skip_all)]
+async fn get_preferences(State(state): State<SharedState>, user: AuthenticatedUser) -> Response {
+    let preferences = state.notifications.preferences(&user.member_id).await;
+    preferences_response(user.member_id, preferences)
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/notifications/preferences",
+    tag = "notifications",
+    summary = "Replace the caller's notification preferences",
+    request_body = NotificationPreferences,
+    responses(
+        (status = 200, description = "The caller's new preferences", body = PreferencesResponse),
+        (status = 400, description = "Invalid preferences", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.set_notification_preferences",
+    skip_all,
+    fields(member_id = %user.member_id)
+)]
+async fn set_preferences(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Json(preferences): Json<NotificationPreferences>,
+) -> Response {
+    if let Err(err) = state
+        .notifications
+        .set_preferences(&user.member_id, preferences.clone())
+        .await
+    {
+        return notification_error_response(err);
+    }
+    preferences_response(user.member_id, preferences)
+}
+
+/// Change the caller's preferences with `change`.
+async fn update_preferences(
+    state: &SharedState,
+    user: AuthenticatedUser,
+    change: impl FnOnce(&mut NotificationPreferences),
+) -> Response {
+    let mut preferences = state.notifications.preferences(&user.member_id).await;
+    change(&mut preferences);
+    if let Err(err) = state
+        .notifications
+        .set_preferences(&user.member_id, preferences.clone())
+        .await
+    {
+        return notification_error_response(err);
+    }
+    preferences_response(user.member_id, preferences)
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/rooms/{id}/notifications",
+    tag = "notifications",
+    summary = "Override the caller's notification level in a room",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = RoomLevelRequest,
+    responses(
+        (status = 200, description = "The caller's new preferences", body = PreferencesResponse),
+        (status = 403, description = "The caller is not a member of the room", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.set_room_notification_level",
+    skip(state, user, payload),
+    fields(room_id = %id)
+)]
+async fn set_room_level(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(payload): Json<RoomLevelRequest>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
+    }
+    update_preferences(&state, user, |preferences| {
+        preferences.rooms.insert(id, payload.level);
+    })
+    .await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/rooms/{id}/notifications",
+    tag = "notifications",
+    summary = "Drop the caller's notification level override in a room",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The caller's new preferences", body = PreferencesResponse),
+        (status = 403, description = "The caller is not a member of the room", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.clear_room_notification_level",
+    skip(state, user),
+    fields(room_id = %id)
+)]
+async fn clear_room_level(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
+    }
+    update_preferences(&state, user, |preferences| {
+        preferences.rooms.remove(&id);
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auth::JwtConfig;
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn members_are_notified_of_mentions_over_websocket() {
+        let addr = serve(routes(AppState::default())).await;
+        let client = reqwest::Client::new();
+        let alice = JwtConfig::test_token("nexis:human:alice");
+        let bob = JwtConfig::test_token("bob");
+
+        let created: Value = client
+            .post(format!("http://{addr}/v1/rooms"))
+            .bearer_auth(&alice)
+            .json(&json!({ "name": "general" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let room_id = created["id"].as_str().unwrap().to_string();
+        for member in ["nexis:human:alice", "bob"] {
+            client
+                .post(format!("http://{addr}/v1/rooms/{room_id}/invite"))
+                .bearer_auth(&alice)
+                .json(&json!({ "memberId": member }))
+                .send()
+                .await
+                .unwrap();
+        }
+
+        // Preferences default to mentions only and are validated.
+        let preferences: Value = client
+            .get(format!("http://{addr}/v1/notifications/preferences"))
+            .bearer_auth(&bob)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(preferences["level"], "mentions");
+        assert_eq!(preferences["channels"]["websocket"], true);
+        let response = client
+            .put(format!("http://{addr}/v1/notifications/preferences"))
+            .bearer_auth(&bob)
+            .json(&json!({ "channels": { "webhookUrl": "not a url" } }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let preferences: Value = client
+            .put(format!("http://{addr}/v1/rooms/{room_id}/notifications"))
+            .bearer_auth(&bob)
+            .json(&json!({ "level": "all" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(preferences["rooms"][&room_id], "all");
+
+        let mut ws = connect_ws(addr, Some("bob")).await;
+
+        // The echo shows the connection is listening for notifications.
+        send_json(&mut ws, json!({ "type": "hello" })).await;
+        assert_eq!(next_json(&mut ws).await, json!({ "type": "hello" }));
+
+        client
+            .post(format!("http://{addr}/v1/messages"))
+            .bearer_auth(&alice)
+            .json(&json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": "@bob can you look?" }))
+            .send()
+            .await
+            .unwrap();
+        let frame = next_json(&mut ws).await;
+        assert_eq!(frame["type"], "notification");
+        assert_eq!(frame["notification"]["kind"], "mention");
+        assert_eq!(frame["notification"]["memberId"], "bob");
+        assert_eq!(frame["notification"]["excerpt"], "@bob can you look?");
+    }
+
+    #[tokio::test]
+    async fn room_levels_are_only_set_in_rooms_the_caller_belongs_to() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let room_id = invite_only_room(&app, "admin", "ops").await;
+        let uri = format!("/v1/rooms/{room_id}/notifications");
+
+        for (method, body) in [("PUT", json!({ "level": "all" })), ("DELETE", Value::Null)] {
+            let response = app
+                .clone()
+                .oneshot(request("alice", method, &uri, body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{method}");
+        }
+    }
+}
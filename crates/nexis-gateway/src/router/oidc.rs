@@ -0,0 +1,319 @@
+//! OpenID Connect login endpoints (`oidc` feature).
+//!
+//! `GET /v1/auth/oidc/login` redirects the browser to the configured issuer;
+//! the issuer sends it back to `GET /v1/auth/oidc/callback`, which answers
+//! with a Nexis access/refresh token pair for the mapped member.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::{ErrorResponse, SharedState};
+use crate::audit::{AuditAction, AuditEvent};
+use crate::auth::{JwtConfig, TokenPair};
+use crate::oidc::OidcError;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/auth/oidc/login", get(oidc_login))
+        .route("/v1/auth/oidc/callback", get(oidc_callback))
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CallbackQuery {
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct OidcLoginResponse {
+    member_id: String,
+    #[serde(flatten)]
+    tokens: TokenPair,
+}
+
+fn not_configured() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found("OIDC login is not configured")),
+    )
+        .into_response()
+}
+
+fn oidc_error_response(err: OidcError) -> Response {
+    match err {
+        OidcError::InvalidState => (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(err.to_string())),
+        )
+            .into_response(),
+        OidcError::InvalidIdToken(_) | OidcError::MissingClaim(_) => (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::unauthorized(err.to_string())),
+        )
+            .into_response(),
+        OidcError::Discovery(_) | OidcError::TokenExchange(_) => {
+            tracing::warn!("OIDC issuer request failed: {}", err);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse::service_unavailable(
+                    "identity provider unavailable",
+                )),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/auth/oidc/login",
+    tag = "auth",
+    summary = "Redirect to the configured OpenID Connect issuer to sign in",
+    security(()),
+    responses(
+        (status = 303, description = "Redirect to the issuer's authorization endpoint"),
+        (status = 404, description = "OIDC login is not configured", body = ErrorResponse),
+        (status = 503, description = "The issuer could not be reached", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.oidc_login", skip_all)]
+async fn oidc_login(State(state): State<SharedState>) -> Response {
+    let Some(client) = state.oidc.clone() else {
+        return not_configured();
+    };
+    match client.authorization_url().await {
+        Ok(url) => Redirect::to(&url).into_response(),
+        Err(err) => oidc_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/auth/oidc/callback",
+    tag = "auth",
+    summary = "Complete the authorization-code flow and return memberId plus a Nexis token pair",
+    security(()),
+    params(CallbackQuery),
+    responses(
+        (status = 200, description = "A fresh token pair", body = OidcLoginResponse),
+        (status = 400, description = "Missing code or unknown state", body = ErrorResponse),
+        (status = 401, description = "The issuer refused the login or returned an invalid ID token", body = ErrorResponse),
+        (status = 404, description = "OIDC login is not configured", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.oidc_callback", skip_all)]
+async fn oidc_callback(
+    State(state): State<SharedState>,
+    Extension(jwt): Extension<JwtConfig>,
+    Query(query): Query<CallbackQuery>,
+) -> Response {
+    let Some(client) = state.oidc.clone() else {
+        return not_configured();
+    };
+    if let Some(error) = query.error {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::unauthorized(format!(
+                "identity provider denied login: {error}"
+            ))),
+        )
+            .into_response();
+    }
+    let (Some(code), Some(login_state)) = (query.code, query.state) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request("code and state are required")),
+        )
+            .into_response();
+    };
+
+    let member_id = match client
+        .complete(&code, &login_state)
+        .await
+        .and_then(|claims| claims.member_id(client.member_claim()))
+    {
+        Ok(member_id) => member_id.to_string(),
+        Err(err) => return oidc_error_response(err),
+    };
+
+    let tokens = match jwt.issue_tokens(&member_id, "human", None) {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            tracing::error!("Failed to mint tokens for OIDC login: {}", err);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::internal_error()),
+            )
+                .into_response();
+        }
+    };
+    state
+        .audit
+        .record(AuditEvent::new(
+            &member_id,
+            AuditAction::TokenIssued,
+            format!("member:{member_id}"),
+        ))
+        .await;
+
+    (
+        StatusCode::OK,
+        Json(OidcLoginResponse { member_id, tokens }),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLog;
+    use crate::config::NexisConfig;
+    use crate::db::Storage;
+    use crate::indexing::BatchConfig;
+    use crate::router::test_support::*;
+    use crate::router::{build_routes, build_routes_with_config};
+    use crate::search::KeywordIndexing;
+    use crate::server::ShutdownController;
+    use axum::extract::Form;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use chrono::Utc;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    const CLIENT_SECRET: &str = "oidc-client-secret";
+
+    /// Minimal issuer serving discovery and an HS256-signing token endpoint.
+    async fn spawn_issuer(nonce: Arc<Mutex<String>>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let discovery = json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{issuer}/authorize"),
+            "token_endpoint": format!("{issuer}/token"),
+            "jwks_uri": format!("{issuer}/jwks"),
+        });
+        let token_issuer = issuer.clone();
+        let app = Router::new()
+            .route(
+                "/.well-known/openid-configuration",
+                get(move || async move { Json(discovery) }),
+            )
+            .route(
+                "/token",
+                post(
+                    move |Form(form): Form<HashMap<String, String>>| async move {
+                        if form.get("code").map(String::as_str) != Some("good-code")
+                            || !form.contains_key("code_verifier")
+                        {
+                            return StatusCode::BAD_REQUEST.into_response();
+                        }
+                        let now = Utc::now().timestamp();
+                        let claims = json!({
+                            "iss": token_issuer,
+                            "aud": "nexis",
+                            "sub": "alice-subject",
+                            "email": "Alice@Example.com",
+                            "email_verified": true,
+                            "nonce": nonce.lock().unwrap().clone(),
+                            "iat": now,
+                            "exp": now + 300,
+                        });
+                        let id_token = jsonwebtoken::encode(
+                            &jsonwebtoken::Header::default(),
+                            &claims,
+                            &jsonwebtoken::EncodingKey::from_secret(CLIENT_SECRET.as_bytes()),
+                        )
+                        .unwrap();
+                        Json(json!({ "id_token": id_token, "token_type": "Bearer" }))
+                            .into_response()
+                    },
+                ),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        issuer
+    }
+
+    fn query_param(url: &str, name: &str) -> String {
+        reqwest::Url::parse(url)
+            .unwrap()
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn oidc_login_mints_nexis_tokens() {
+        let nonce = Arc::new(Mutex::new(String::new()));
+        let issuer = spawn_issuer(nonce.clone()).await;
+        let mut config = NexisConfig::default();
+        config.oidc.issuer_url = Some(issuer.clone());
+        config.oidc.client_id = "nexis".to_string();
+        config.oidc.client_secret = Some(CLIENT_SECRET.to_string());
+        config.oidc.redirect_url = "http://localhost/v1/auth/oidc/callback".to_string();
+        let jwt = config.auth.jwt_config();
+        let app = build_routes_with_config(
+            Arc::new(config),
+            None,
+            AuditLog::default(),
+            ShutdownController::new(),
+            Storage::default(),
+            KeywordIndexing::new(BatchConfig::default()),
+        );
+
+        let response = app
+            .clone()
+            .oneshot(anonymous_request("GET", "/v1/auth/oidc/login", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = response.headers()["location"].to_str().unwrap().to_string();
+        assert!(location.starts_with(&format!("{issuer}/authorize?")));
+        assert_eq!(query_param(&location, "code_challenge_method"), "S256");
+        *nonce.lock().unwrap() = query_param(&location, "nonce");
+        let state = query_param(&location, "state");
+
+        let callback = |code: &str| {
+            let uri = format!("/v1/auth/oidc/callback?code={code}&state={state}");
+            anonymous_request("GET", &uri, json!({}))
+        };
+        let response = app.clone().oneshot(callback("good-code")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let login = json_body(response).await;
+        assert_eq!(login["memberId"], "nexis:human:alice@example.com");
+        let claims = jwt
+            .verify_token(login["accessToken"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(claims.sub, "nexis:human:alice@example.com");
+        assert!(login["refreshToken"].is_string());
+
+        // Login state is single-use.
+        let response = app.clone().oneshot(callback("good-code")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn oidc_endpoints_are_404_when_unconfigured() {
+        let response = build_routes()
+            .oneshot(anonymous_request("GET", "/v1/auth/oidc/login", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,262 @@
+//! OpenAPI document for the HTTP API, generated from handler annotations.
+//!
+//! Every handler carries a `#[utoipa::path]` attribute and every request and
+//! response body derives `ToSchema`, so the document follows the structs it
+//! describes. It is served at `/v1/openapi.json`, with Swagger UI at
+//! `/v1/docs`; `/openapi.json` and `/docs` remain as aliases.
+
+use std::sync::OnceLock;
+
+use axum::{
+    http::StatusCode,
+    response::{Html, IntoResponse},
+    routing::get,
+    Router,
+};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use super::SharedState;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/openapi.json", get(openapi_json))
+        .route("/v1/docs", get(swagger_ui))
+        .route("/openapi.json", get(openapi_json))
+        .route("/docs", get(swagger_ui))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Nexis Gateway API",
+        description = "Control plane API for room management, messaging, and semantic search."
+    ),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    paths(
+        super::health_check,
+        super::readiness_check,
+        super::metrics_handler,
+        super::list_rooms,
+        super::create_room,
+        super::get_room,
+        super::delete_room,
+        super::import_room,
+        super::export_room,
+        super::invite_member,
+        super::room_ai,
+        super::list_webhooks,
+        super::register_webhook,
+        super::rotate_webhook_secret,
+        super::disable_webhook,
+        super::list_webhook_deliveries,
+        super::send_message,
+        super::search_messages_get,
+        super::search_messages,
+        super::list_audit_events,
+        super::session::login,
+        super::session::refresh_token,
+        super::session::logout,
+        super::admin::revoke_token,
+        super::admin::reindex,
+        super::admin::member_connections,
+        super::admin::route_connections,
+        super::admin::delete_room,
+        super::admin::list_retention,
+        super::admin::set_room_retention,
+        super::admin::remove_room_retention,
+        super::admin::set_tenant_retention,
+        super::admin::remove_tenant_retention,
+        super::members::list_members,
+        super::members::create_member,
+        super::members::get_member,
+        super::members::update_member,
+        super::members::delete_member,
+        super::members::get_capabilities,
+        super::members::register_capabilities,
+        super::history::list_messages,
+        super::read_markers::mark_read,
+        super::read_markers::unread_counts,
+        super::signing_keys::register_signing_key,
+        super::signing_keys::get_signing_key,
+        super::uploads::create_upload,
+        super::uploads::get_upload,
+        super::uploads::download_upload,
+        super::transcripts::transcribe_upload,
+        super::costs::cost_summary,
+        super::costs::member_costs,
+        super::room_budgets::get_room_budget,
+        super::room_budgets::override_room_budget,
+        super::room_budgets::restore_room_budget,
+        super::moderation::get_room_moderation,
+        super::moderation::set_room_moderation,
+        super::settings::get_room_settings,
+        super::settings::update_room_settings,
+        super::invites::invite_bulk,
+        super::joins::join_room,
+        super::joins::create_invitation,
+        super::joins::list_invitations,
+        super::joins::revoke_invitation,
+        super::joins::list_join_requests,
+        super::joins::approve_join_request,
+        super::joins::reject_join_request,
+        super::sanctions::list_sanctions,
+        super::sanctions::kick_member,
+        super::sanctions::ban_member,
+        super::sanctions::unban_member,
+        super::sanctions::mute_member,
+        super::sanctions::unmute_member,
+        super::notifications::get_preferences,
+        super::notifications::set_preferences,
+        super::notifications::set_room_level,
+        super::notifications::clear_room_level,
+        super::similar::similar_messages,
+        super::schedules::list_schedules,
+        super::schedules::create_schedule,
+        super::schedules::delete_schedule,
+        super::schedules::get_retention,
+        super::schedules::set_retention,
+        super::schedules::delete_retention,
+        super::streams::open_stream,
+        super::streams::append_stream,
+        super::streams::abort_stream,
+        super::branches::regenerate_message,
+        super::branches::list_generations,
+        super::feedback::set_feedback,
+        super::feedback::get_feedback,
+        super::feedback::delete_feedback,
+        super::feedback::export_feedback,
+        super::generation_log::list_generations,
+        super::generation_log::get_generation,
+        super::generation_log::replay_generation,
+        super::batches::create_batch,
+        super::batches::get_batch,
+        super::tasks::create_task,
+        super::tasks::list_tasks,
+        super::tasks::get_task,
+        super::tasks::claim_task,
+        super::tasks::complete_task,
+        super::tasks::cancel_task,
+        crate::collaboration::create_meeting_room,
+        crate::collaboration::join_meeting_room,
+        crate::collaboration::leave_meeting_room,
+        crate::collaboration::create_document,
+        crate::collaboration::sync_document,
+        crate::collaboration::get_document_content,
+        crate::collaboration::create_task,
+        crate::collaboration::assign_task,
+        crate::collaboration::complete_task,
+        crate::collaboration::create_calendar_event,
+        crate::collaboration::check_calendar_conflicts,
+    ),
+    tags(
+        (name = "health", description = "Liveness, readiness, and metrics"),
+        (name = "auth", description = "Sessions and tokens"),
+        (name = "rooms", description = "Rooms, membership, and archives"),
+        (name = "messages", description = "Sending, reading, and tracking messages"),
+        (name = "search", description = "Keyword and semantic search"),
+        (name = "members", description = "Member profiles, capabilities, and signing keys"),
+        (name = "uploads", description = "File attachments"),
+        (name = "ai", description = "AI replies and transcription"),
+        (name = "webhooks", description = "Outbound room webhooks"),
+        (name = "notifications", description = "Member notification preferences"),
+        (name = "schedules", description = "Scheduled room jobs"),
+        (name = "tasks", description = "Tasks delegated to room members"),
+        (name = "admin", description = "Operator endpoints"),
+        (name = "collaboration", description = "Meetings, documents, tasks, and calendars"),
+    )
+)]
+struct ApiDoc;
+
+#[cfg(feature = "oidc")]
+#[derive(OpenApi)]
+#[openapi(paths(super::oidc::oidc_login, super::oidc::oidc_callback))]
+struct OidcApi;
+
+#[cfg(feature = "multi-tenant")]
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        super::tenant_admin::list_tenants,
+        super::tenant_admin::create_tenant,
+        super::tenant_admin::get_tenant,
+        super::tenant_admin::suspend_tenant,
+        super::tenant_admin::resume_tenant,
+        super::tenant_admin::update_quota,
+    ),
+    tags((name = "tenants", description = "Tenant administration"))
+)]
+struct TenantApi;
+
+/// Registers the bearer JWT scheme every authenticated operation uses.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(
+                    HttpBuilder::new()
+                        .scheme(HttpAuthScheme::Bearer)
+                        .bearer_format("JWT")
+                        .build(),
+                ),
+            );
+    }
+}
+
+/// The document for the routes this build serves.
+fn document() -> utoipa::openapi::OpenApi {
+    #[allow(unused_mut)]
+    let mut doc = ApiDoc::openapi();
+    #[cfg(feature = "oidc")]
+    doc.merge(OidcApi::openapi());
+    #[cfg(feature = "multi-tenant")]
+    doc.merge(TenantApi::openapi());
+    doc
+}
+
+async fn openapi_json() -> impl IntoResponse {
+    static DOCUMENT: OnceLock<String> = OnceLock::new();
+    let json = DOCUMENT.get_or_init(|| {
+        document()
+            .to_pretty_json()
+            .expect("OpenAPI document serializes")
+    });
+    (
+        StatusCode::OK,
+        [("content-type", "application/json; charset=utf-8")],
+        json.as_str(),
+    )
+}
+
+async fn swagger_ui() -> impl IntoResponse {
+    const SWAGGER_HTML: &str = r##"<!doctype html>
+<html>
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>Nexis Gateway API Docs</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+  </head>
+  <body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+    <script>
+      window.ui = SwaggerUIBundle({
+        url: "/v1/openapi.json",
+        dom_id: "#swagger-ui",
+        deepLinking: true,
+        docExpansion: "list"
+      });
+    </script>
+  </body>
+</html>
+"##;
+
+    Html(SWAGGER_HTML)
+}
@@ -0,0 +1,442 @@
+//! Read receipts and unread counts.
+//!
+//! Each member has at most one marker per room naming the last message they
+//! have read. Markers only move forward; every move is pushed to WebSocket
+//! subscribers of the room as a `read_marker` event.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{ensure_room_access, joins, ErrorResponse, RoomEvent, SharedState, StoredMessage};
+use crate::auth::AuthenticatedUser;
+use crate::db::{ReadMarker, RepositoryError};
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/rooms/:id/read", post(mark_read))
+        .route("/v1/members/:id/unread", get(unread_counts))
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct MarkReadRequest {
+    message_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ReadMarkerResponse {
+    room_id: String,
+    member_id: String,
+    message_id: String,
+    unread: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RoomUnread {
+    room_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_read_message_id: Option<String>,
+    unread: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct UnreadCountsResponse {
+    member_id: String,
+    rooms: Vec<RoomUnread>,
+    total: usize,
+}
+
+fn repository_error_response(err: RepositoryError) -> Response {
+    tracing::error!("Read marker repository error: {}", err);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::internal_error()),
+    )
+        .into_response()
+}
+
+/// Messages after `last_read` that `member_id` did not send themselves.
+///
+/// A marker pointing at a message that no longer exists counts as unset.
+fn count_unread(messages: &[StoredMessage], member_id: &str, last_read: Option<&str>) -> usize {
+    let start = last_read
+        .and_then(|id| messages.iter().position(|message| message.id == id))
+        .map_or(0, |index| index + 1);
+    messages[start..]
+        .iter()
+        .filter(|message| message.sender != member_id)
+        .count()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/read",
+    tag = "messages",
+    summary = "Move the caller's read marker forward and publish a read_marker event",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = MarkReadRequest,
+    responses(
+        (status = 200, description = "The caller's marker after the move", body = ReadMarkerResponse),
+        (status = 403, description = "The caller is not a member of the room", body = ErrorResponse),
+        (status = 404, description = "Room or message not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.mark_read",
+    skip(state, user, payload),
+    fields(room_id = %id, member_id = %user.member_id)
+)]
+async fn mark_read(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(payload): Json<MarkReadRequest>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
+    }
+
+    let current = match state.read_markers.get(&id, &user.member_id).await {
+        Ok(marker) => marker,
+        Err(err) => return repository_error_response(err),
+    };
+
+    let (message_id, moved, unread) = {
+        let messages = state.room_messages.read().await;
+        let messages = messages.get(&id).map(Vec::as_slice).unwrap_or_default();
+        let position = |message_id: &str| messages.iter().position(|m| m.id == message_id);
+        let Some(requested) = position(&payload.message_id) else {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::not_found("message not found in room")),
+            )
+                .into_response();
+        };
+        // Receipts arriving out of order must not move the marker backwards.
+        let message_id = match current.as_ref() {
+            Some(marker)
+                if position(&marker.message_id).is_some_and(|index| index >= requested) =>
+            {
+                marker.message_id.clone()
+            }
+            _ => payload.message_id.clone(),
+        };
+        let moved = current
+            .as_ref()
+            .is_none_or(|marker| marker.message_id != message_id);
+        let unread = count_unread(messages, &user.member_id, Some(&message_id));
+        (message_id, moved, unread)
+    };
+
+    if moved {
+        let marker = ReadMarker {
+            room_id: id.clone(),
+            member_id: user.member_id.clone(),
+            message_id: message_id.clone(),
+            updated_at: Utc::now(),
+        };
+        if let Err(err) = state.read_markers.set(&marker).await {
+            return repository_error_response(err);
+        }
+        state
+            .publish(RoomEvent::ReadMarker {
+                room_id: id.clone(),
+                member_id: user.member_id.clone(),
+                message_id: message_id.clone(),
+            })
+            .await;
+    }
+
+    (
+        StatusCode::OK,
+        Json(ReadMarkerResponse {
+            room_id: id,
+            member_id: user.member_id,
+            message_id,
+            unread,
+        }),
+    )
+        .into_response()
+}
+
+/// Unread counts for every room the member was invited to, has posted in or
+/// has read in.
+#[utoipa::path(
+    get,
+    path = "/v1/members/{id}/unread",
+    tag = "members",
+    summary = "Unread message counts per room for a member",
+    params(("id" = String, Path, description = "Member id")),
+    responses(
+        (status = 200, description = "Unread counts", body = UnreadCountsResponse),
+        (status = 403, description = "Only the member can read its own counts", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.unread_counts", skip(state, user), fields(member_id = %id))]
+async fn unread_counts(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if user.member_id != id && !state.config.auth.is_admin(&user.member_id) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::forbidden(
+                "members can only read their own unread counts",
+            )),
+        )
+            .into_response();
+    }
+
+    let markers = match state.read_markers.list_for_member(&id).await {
+        Ok(markers) => markers,
+        Err(err) => return repository_error_response(err),
+    };
+
+    let rooms = state.rooms.read().await;
+    let members = state.room_members.read().await;
+    let messages = state.room_messages.read().await;
+
+    let mut room_ids = rooms
+        .values()
+        .filter(|room| room.is_visible_to(&user))
+        .filter(|room| {
+            members
+                .get(&room.id)
+                .is_some_and(|members| members.contains(&id))
+                || markers.iter().any(|marker| marker.room_id == room.id)
+                || messages
+                    .get(&room.id)
+                    .is_some_and(|messages| messages.iter().any(|m| m.sender == id))
+        })
+        .map(|room| room.id.clone())
+        .collect::<Vec<_>>();
+    room_ids.sort();
+
+    let rooms = room_ids
+        .into_iter()
+        .map(|room_id| {
+            let last_read = markers
+                .iter()
+                .find(|marker| marker.room_id == room_id)
+                .map(|marker| marker.message_id.clone());
+            let unread = count_unread(
+                messages
+                    .get(&room_id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+                &id,
+                last_read.as_deref(),
+            );
+            RoomUnread {
+                room_id,
+                last_read_message_id: last_read,
+                unread,
+            }
+        })
+        .collect::<Vec<_>>();
+    let total = rooms.iter().map(|room| room.unread).sum();
+
+    (
+        StatusCode::OK,
+        Json(UnreadCountsResponse {
+            member_id: id,
+            rooms,
+            total,
+        }),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn read_markers_move_forward_and_drive_unread_counts() {
+        let state = AppState::default();
+        let mut events = state.room_events.subscribe();
+        let app = routes(state);
+
+        let room_id = new_room(&app, "nexis:human:bob", "general").await;
+        let mut message_ids = Vec::new();
+        for (sender, text) in [
+            ("nexis:human:bob", "one"),
+            ("nexis:human:bob", "two"),
+            ("nexis:human:alice", "mine"),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request(
+                    sender,
+                    "POST",
+                    "/v1/messages",
+                    json!({ "roomId": room_id, "sender": sender, "text": text }),
+                ))
+                .await
+                .unwrap();
+            message_ids.push(
+                json_body(response).await["id"]
+                    .as_str()
+                    .unwrap()
+                    .to_string(),
+            );
+        }
+        while events.try_recv().is_ok() {}
+
+        let read_uri = format!("/v1/rooms/{room_id}/read");
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &read_uri,
+                json!({ "messageId": "msg_missing" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &read_uri,
+                json!({ "messageId": message_ids[0] }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        // Alice's own message never counts as unread.
+        assert_eq!(json_body(response).await["unread"], 1);
+        let event = serde_json::to_value(events.try_recv().unwrap()).unwrap();
+        assert_eq!(event["type"], "read_marker");
+        assert_eq!(event["memberId"], "nexis:human:alice");
+        assert_eq!(event["messageId"], message_ids[0].as_str());
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &read_uri,
+                json!({ "messageId": message_ids[1] }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["unread"], 0);
+        assert!(events.try_recv().is_ok());
+
+        // A stale receipt keeps the newer marker and publishes nothing.
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &read_uri,
+                json!({ "messageId": message_ids[0] }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            json_body(response).await["messageId"],
+            message_ids[1].as_str()
+        );
+        assert!(events.try_recv().is_err());
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "GET",
+                "/v1/members/nexis:human:bob/unread",
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:bob",
+                "POST",
+                "/v1/rooms/missing/read",
+                json!({ "messageId": message_ids[0] }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .oneshot(request(
+                "nexis:human:bob",
+                "GET",
+                "/v1/members/nexis:human:bob/unread",
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let unread = json_body(response).await;
+        assert_eq!(unread["total"], 1);
+        assert_eq!(unread["rooms"][0]["roomId"], room_id.as_str());
+        assert!(unread["rooms"][0].get("lastReadMessageId").is_none());
+    }
+
+    #[tokio::test]
+    async fn only_members_mark_a_closed_room_read() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let room_id = invite_only_room(&app, "admin", "ops").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": "nexis:human:admin", "text": "hi" }),
+            ))
+            .await
+            .unwrap();
+        let message_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let response = app
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &format!("/v1/rooms/{room_id}/read"),
+                json!({ "messageId": message_id }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,340 @@
+//! Daily AI budgets per room.
+//!
+//! `[costs] room_daily_max_calls` and `room_daily_max_tokens` cap how much AI
+//! a room uses per UTC day, on top of each member's monthly budget. Every AI
+//! request made in a room counts, whoever makes it. Once a cap is reached
+//! the assistant says so in the room and further requests fail with
+//! `429 ROOM_BUDGET_EXCEEDED`. `GET /v1/rooms/:id/ai-budget` reports the
+//! room's use today; admins can lift the caps for the rest of the day with
+//! `POST /v1/rooms/:id/ai-budget/override` and restore them with `DELETE`.
+
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use nexis_runtime::RoomUsage;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::{
+    ensure_room_access, error_codes, joins, post_ai_message, record_operation_error, require_admin,
+    ErrorResponse, SharedState, StoredMessage, AI_MEMBER_ID,
+};
+use crate::audit::{AuditAction, AuditEvent};
+use crate::auth::AuthenticatedUser;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/rooms/:id/ai-budget", get(get_room_budget))
+        .route(
+            "/v1/rooms/:id/ai-budget/override",
+            post(override_room_budget).delete(restore_room_budget),
+        )
+}
+
+/// Reject an AI request in `room_id` once the room's daily budget is used
+/// up, telling the room the first time that happens each day.
+pub(super) async fn check(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    room_id: &str,
+    operation: &str,
+    started: Instant,
+) -> Result<(), Response> {
+    let Err(err) = state.room_budgets.check(room_id) else {
+        return Ok(());
+    };
+    record_operation_error(operation, "room_budget", started);
+    if err.first_rejection {
+        let notice = StoredMessage::new(AI_MEMBER_ID, err.notice());
+        // The request fails either way; a notice that can't be posted is
+        // only logged.
+        if post_ai_message(state, user, room_id.to_string(), notice, operation, started)
+            .await
+            .is_err()
+        {
+            tracing::warn!(room_id, "Failed to post the room's AI budget notice");
+        }
+    }
+    Err((
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(ErrorResponse {
+            error: err.to_string(),
+            code: Some(error_codes::ROOM_BUDGET_EXCEEDED),
+        }),
+    )
+        .into_response())
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RoomBudgetResponse {
+    room_id: String,
+    /// UTC day the usage covers, as `YYYY-MM-DD`.
+    day: String,
+    calls: u64,
+    /// Input plus output tokens reported by the providers.
+    tokens: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_calls: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u64>,
+    /// Whether an admin lifted the limits for the rest of the day.
+    overridden: bool,
+}
+
+impl RoomBudgetResponse {
+    fn new(room_id: String, usage: RoomUsage) -> Self {
+        Self {
+            room_id,
+            day: usage.day,
+            calls: usage.calls,
+            tokens: usage.tokens,
+            max_calls: usage.limits.max_calls,
+            max_tokens: usage.limits.max_tokens,
+            overridden: usage.overridden,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/ai-budget",
+    tag = "ai",
+    summary = "The room's AI calls and tokens today and its daily limits",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room's AI use today", body = RoomBudgetResponse),
+        (status = 403, description = "The caller is not a member of the room", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.get_room_budget", skip(state, user), fields(room_id = %id))]
+async fn get_room_budget(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
+    }
+    let usage = state.room_budgets.usage(&id);
+    (StatusCode::OK, Json(RoomBudgetResponse::new(id, usage))).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/ai-budget/override",
+    tag = "ai",
+    summary = "Lift the room's daily AI limits for the rest of the day (admin only)",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room's AI use today", body = RoomBudgetResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.override_room_budget",
+    skip(state, user),
+    fields(room_id = %id, member_id = %user.member_id)
+)]
+async fn override_room_budget(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    set_override(state, user, id, true).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/rooms/{id}/ai-budget/override",
+    tag = "ai",
+    summary = "Put the room's daily AI limits back in force (admin only)",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room's AI use today", body = RoomBudgetResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.restore_room_budget",
+    skip(state, user),
+    fields(room_id = %id, member_id = %user.member_id)
+)]
+async fn restore_room_budget(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    set_override(state, user, id, false).await
+}
+
+async fn set_override(
+    state: SharedState,
+    user: AuthenticatedUser,
+    id: String,
+    overridden: bool,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+
+    let usage = state.room_budgets.set_override(&id, overridden);
+    let action = if overridden {
+        AuditAction::RoomAiBudgetOverridden
+    } else {
+        AuditAction::RoomAiBudgetRestored
+    };
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            action,
+            format!("room:{id}"),
+        ))
+        .await;
+    (StatusCode::OK, Json(RoomBudgetResponse::new(id, usage))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn room_ai_budget_posts_a_notice_and_admins_can_override() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        config.costs.room_daily_max_calls = Some(1);
+        let state = AppState {
+            room_budgets: Arc::new(config.costs.room_budgets()),
+            config: Arc::new(config),
+            ..AppState::default()
+        }
+        .with_ai_provider(Arc::new(EchoProvider));
+        let room_messages = state.room_messages.clone();
+        let app = routes(state);
+
+        let room_id = new_room(&app, "alice", "ai").await;
+        let ai_uri = format!("/v1/rooms/{room_id}/ai");
+        let budget_uri = format!("/v1/rooms/{room_id}/ai-budget");
+        let override_uri = format!("{budget_uri}/override");
+        let prompt = json!({ "prompt": "summarize" });
+
+        let response = app
+            .clone()
+            .oneshot(request("alice", "POST", &ai_uri, prompt.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        for member in ["bob", "alice"] {
+            let response = app
+                .clone()
+                .oneshot(request(member, "POST", &ai_uri, prompt.clone()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+            let rejected = json_body(response).await;
+            assert_eq!(rejected["code"], "ROOM_BUDGET_EXCEEDED");
+            assert!(rejected["error"]
+                .as_str()
+                .unwrap()
+                .contains("used 1 of 1 calls"));
+        }
+        // The room is told once, not on every rejected request.
+        let notices: Vec<String> = room_messages.read().await[&room_id]
+            .iter()
+            .filter(|message| message.text.contains("daily AI budget"))
+            .map(|message| message.sender.clone())
+            .collect();
+        assert_eq!(notices, vec![AI_MEMBER_ID.to_string()]);
+
+        let response = app
+            .clone()
+            .oneshot(request("alice", "GET", &budget_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let budget = json_body(response).await;
+        assert_eq!(budget["calls"], 1);
+        assert_eq!(budget["tokens"], 1500);
+        assert_eq!(budget["maxCalls"], 1);
+        assert_eq!(budget["overridden"], false);
+
+        let response = app
+            .clone()
+            .oneshot(request("alice", "POST", &override_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", &override_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["overridden"], true);
+        let response = app
+            .clone()
+            .oneshot(request("alice", "POST", &ai_uri, prompt.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "DELETE", &override_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["calls"], 2);
+        let response = app
+            .oneshot(request("alice", "POST", &ai_uri, prompt))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn only_members_read_a_closed_room_budget() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let room_id = invite_only_room(&app, "admin", "ops").await;
+        let budget_uri = format!("/v1/rooms/{room_id}/ai-budget");
+
+        let response = app
+            .clone()
+            .oneshot(request("alice", "GET", &budget_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .oneshot(request("admin", "GET", &budget_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
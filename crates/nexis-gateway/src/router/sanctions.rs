@@ -0,0 +1,1063 @@
+//! Kicking, banning and muting members of a room.
+//!
+//! Admins can remove a member from a room (kick), keep them out of it for a
+//! while or for good (ban), or leave them reading it without letting them
+//! write (mute). Banned members can't send messages, join or be invited
+//! back, or subscribe to the room over WebSocket; muted members can't send
+//! messages. Every action is audited and announced to the room as a
+//! `sanction` event, and connections of a kicked or banned member stop
+//! receiving the room's events once it arrives. Bans and mutes are stored, so
+//! they outlast a restart, and lapse when they expire.
+
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{
+    ensure_room_access, error_codes, require_admin, storage_error_response, ErrorResponse,
+    RoomEvent, SharedState,
+};
+use crate::audit::{AuditAction, AuditEvent};
+use crate::auth::AuthenticatedUser;
+use crate::db::{RepositoryError, SanctionRecord};
+
+/// Longest a ban or mute can be given a duration for; longer ones are
+/// given without one.
+const MAX_SANCTION_SECS: u64 = 365 * 24 * 3_600;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/rooms/:id/sanctions", get(list_sanctions))
+        .route("/v1/rooms/:id/members/:member_id/kick", post(kick_member))
+        .route(
+            "/v1/rooms/:id/members/:member_id/ban",
+            post(ban_member).delete(unban_member),
+        )
+        .route(
+            "/v1/rooms/:id/members/:member_id/mute",
+            post(mute_member).delete(unmute_member),
+        )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum SanctionKind {
+    /// Out of the room; can't rejoin, be invited or subscribe.
+    Ban,
+    /// Can read the room but not send to it.
+    Mute,
+}
+
+impl SanctionKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Ban => "ban",
+            Self::Mute => "mute",
+        }
+    }
+
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "ban" => Some(Self::Ban),
+            "mute" => Some(Self::Mute),
+            _ => None,
+        }
+    }
+}
+
+/// A ban or mute in force in a room.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Sanction {
+    member_id: String,
+    kind: SanctionKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    created_by: String,
+    created_at: DateTime<Utc>,
+    /// Unset for sanctions that last until they are lifted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl Sanction {
+    fn in_force(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+
+    fn from_record(record: SanctionRecord) -> Option<Self> {
+        let Some(kind) = SanctionKind::parse(&record.kind) else {
+            tracing::warn!(
+                room_id = %record.room_id,
+                "Ignoring sanction of unknown kind {}",
+                record.kind
+            );
+            return None;
+        };
+        Some(Self {
+            member_id: record.member_id,
+            kind,
+            reason: record.reason,
+            created_by: record.created_by,
+            created_at: record.created_at,
+            expires_at: record.expires_at,
+        })
+    }
+
+    fn to_record(&self, room_id: &str) -> SanctionRecord {
+        SanctionRecord {
+            room_id: room_id.to_string(),
+            member_id: self.member_id.clone(),
+            kind: self.kind.as_str().to_string(),
+            reason: self.reason.clone(),
+            created_by: self.created_by.clone(),
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+        }
+    }
+}
+
+/// The stored sanctions still in force, by room.
+pub(super) fn restore(records: Vec<SanctionRecord>) -> HashMap<String, Vec<Sanction>> {
+    let now = Utc::now();
+    let mut sanctions: HashMap<String, Vec<Sanction>> = HashMap::new();
+    for record in records {
+        let room_id = record.room_id.clone();
+        if let Some(sanction) = Sanction::from_record(record).filter(|s| s.in_force(now)) {
+            sanctions.entry(room_id).or_default().push(sanction);
+        }
+    }
+    sanctions
+}
+
+/// What a `sanction` room event announces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum SanctionAction {
+    Kicked,
+    Banned,
+    Unbanned,
+    Muted,
+    Unmuted,
+}
+
+impl SanctionAction {
+    /// Whether the member loses the room's events.
+    pub(super) fn removes(self) -> bool {
+        matches!(self, Self::Kicked | Self::Banned)
+    }
+
+    fn audit_action(self) -> AuditAction {
+        match self {
+            Self::Kicked => AuditAction::MemberKicked,
+            Self::Banned => AuditAction::MemberBanned,
+            Self::Unbanned => AuditAction::MemberUnbanned,
+            Self::Muted => AuditAction::MemberMuted,
+            Self::Unmuted => AuditAction::MemberUnmuted,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct KickRequest {
+    /// Shown to the room with the `sanction` event.
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct SanctionRequest {
+    /// Seconds until the ban or mute lapses; it lasts until lifted when
+    /// unset.
+    #[serde(default)]
+    duration_secs: Option<u64>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ListSanctionsResponse {
+    room_id: String,
+    sanctions: Vec<Sanction>,
+    total: usize,
+}
+
+/// The `kind` sanction on `member_id` in the room, if one is in force.
+async fn find(
+    state: &SharedState,
+    room_id: &str,
+    member_id: &str,
+    kind: SanctionKind,
+) -> Option<Sanction> {
+    let now = Utc::now();
+    state
+        .sanctions
+        .read()
+        .await
+        .get(room_id)?
+        .iter()
+        .find(|sanction| {
+            sanction.member_id == member_id && sanction.kind == kind && sanction.in_force(now)
+        })
+        .cloned()
+}
+
+pub(super) async fn is_banned(state: &SharedState, room_id: &str, member_id: &str) -> bool {
+    find(state, room_id, member_id, SanctionKind::Ban)
+        .await
+        .is_some()
+}
+
+/// Members banned from the room.
+pub(super) async fn banned_members(state: &SharedState, room_id: &str) -> HashSet<String> {
+    let now = Utc::now();
+    state
+        .sanctions
+        .read()
+        .await
+        .get(room_id)
+        .into_iter()
+        .flatten()
+        .filter(|sanction| sanction.kind == SanctionKind::Ban && sanction.in_force(now))
+        .map(|sanction| sanction.member_id.clone())
+        .collect()
+}
+
+/// Refuse messages from members banned from or muted in the room.
+pub(super) async fn check_can_send(
+    state: &SharedState,
+    room_id: &str,
+    member_id: &str,
+) -> Result<(), Response> {
+    let (code, verb, sanction) =
+        if let Some(ban) = find(state, room_id, member_id, SanctionKind::Ban).await {
+            (error_codes::MEMBER_BANNED, "banned from", ban)
+        } else if let Some(mute) = find(state, room_id, member_id, SanctionKind::Mute).await {
+            (error_codes::MEMBER_MUTED, "muted in", mute)
+        } else {
+            return Ok(());
+        };
+    let error = match sanction.expires_at {
+        Some(expires_at) => format!("you are {verb} this room until {}", expires_at.to_rfc3339()),
+        None => format!("you are {verb} this room"),
+    };
+    let response = ErrorResponse {
+        error,
+        code: Some(code),
+    };
+    Err((StatusCode::FORBIDDEN, Json(response)).into_response())
+}
+
+/// Drop the sanctions of a deleted room.
+pub(super) async fn remove_room(state: &SharedState, room_id: &str) {
+    state.sanctions.write().await.remove(room_id);
+    if let Err(err) = state.sanction_store.delete_room(room_id).await {
+        tracing::warn!("Failed to drop sanctions of room {}: {}", room_id, err);
+    }
+}
+
+/// Put `sanction` in force, in storage first, replacing one of the same
+/// kind.
+async fn impose(
+    state: &SharedState,
+    room_id: &str,
+    sanction: Sanction,
+) -> Result<(), RepositoryError> {
+    let now = Utc::now();
+    let mut sanctions = state.sanctions.write().await;
+    state
+        .sanction_store
+        .set(&sanction.to_record(room_id))
+        .await?;
+    let room_sanctions = sanctions.entry(room_id.to_string()).or_default();
+    room_sanctions.retain(|existing| {
+        existing.in_force(now)
+            && !(existing.member_id == sanction.member_id && existing.kind == sanction.kind)
+    });
+    room_sanctions.push(sanction);
+    Ok(())
+}
+
+/// Lift the `kind` sanction on `member_id`, in storage first; `false` if
+/// none was in force.
+async fn lift(
+    state: &SharedState,
+    room_id: &str,
+    member_id: &str,
+    kind: SanctionKind,
+) -> Result<bool, RepositoryError> {
+    let now = Utc::now();
+    let mut sanctions = state.sanctions.write().await;
+    let Some(room_sanctions) = sanctions.get_mut(room_id) else {
+        return Ok(false);
+    };
+    let lifted = room_sanctions.iter().any(|sanction| {
+        sanction.member_id == member_id && sanction.kind == kind && sanction.in_force(now)
+    });
+    if lifted {
+        state
+            .sanction_store
+            .remove(room_id, member_id, kind.as_str())
+            .await?;
+    }
+    room_sanctions.retain(|sanction| {
+        sanction.in_force(now) && !(sanction.member_id == member_id && sanction.kind == kind)
+    });
+    Ok(lifted)
+}
+
+/// Audit `action` and announce it to the room.
+async fn announce(
+    state: &SharedState,
+    actor: &str,
+    room_id: &str,
+    member_id: &str,
+    action: SanctionAction,
+    reason: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+) {
+    state
+        .audit
+        .record(AuditEvent::new(
+            actor,
+            action.audit_action(),
+            format!("room:{room_id}/member:{member_id}"),
+        ))
+        .await;
+    state
+        .publish(RoomEvent::Sanction {
+            room_id: room_id.to_string(),
+            member_id: member_id.to_string(),
+            action,
+            reason,
+            expires_at,
+        })
+        .await;
+}
+
+/// Mute `member_id` for `secs` on behalf of `muted_by`, such as the spam
+/// guard, announced like a mute by an admin.
+pub(super) async fn mute_for(
+    state: &SharedState,
+    room_id: &str,
+    member_id: &str,
+    muted_by: &str,
+    secs: u64,
+    reason: String,
+) {
+    let created_at = Utc::now();
+    let secs = secs.min(MAX_SANCTION_SECS) as i64;
+    let sanction = Sanction {
+        member_id: member_id.to_string(),
+        kind: SanctionKind::Mute,
+        reason: Some(reason),
+        created_by: muted_by.to_string(),
+        created_at,
+        expires_at: Some(created_at + chrono::Duration::seconds(secs)),
+    };
+    if let Err(err) = impose(state, room_id, sanction.clone()).await {
+        tracing::warn!(
+            "Failed to store mute of {} in room {}: {}",
+            member_id,
+            room_id,
+            err
+        );
+        return;
+    }
+    announce(
+        state,
+        muted_by,
+        room_id,
+        member_id,
+        SanctionAction::Muted,
+        sanction.reason,
+        sanction.expires_at,
+    )
+    .await;
+}
+
+/// Reason without surrounding whitespace, or none when blank.
+fn reason(reason: Option<String>) -> Option<String> {
+    reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty())
+}
+
+/// Ban or mute `member_id` as `payload` asks.
+async fn sanction(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    room_id: &str,
+    member_id: &str,
+    kind: SanctionKind,
+    payload: SanctionRequest,
+) -> Response {
+    if let Err(response) = require_admin(state, user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(state, user, room_id).await {
+        return response;
+    }
+    let expires_in = match payload.duration_secs {
+        Some(secs) if secs == 0 || secs > MAX_SANCTION_SECS => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request(format!(
+                    "durationSecs must be between 1 and {MAX_SANCTION_SECS}"
+                ))),
+            )
+                .into_response()
+        }
+        other => other,
+    };
+    if member_id == user.member_id {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request("you can't sanction yourself")),
+        )
+            .into_response();
+    }
+
+    let created_at = Utc::now();
+    let sanction = Sanction {
+        member_id: member_id.to_string(),
+        kind,
+        reason: reason(payload.reason),
+        created_by: user.member_id.clone(),
+        created_at,
+        expires_at: expires_in.map(|secs| created_at + chrono::Duration::seconds(secs as i64)),
+    };
+    if let Err(err) = impose(state, room_id, sanction.clone()).await {
+        return storage_error_response(err);
+    }
+    let action = match kind {
+        SanctionKind::Ban => {
+            // The ban keeps them out even if their membership lingers.
+            if let Err(err) = state.remove_member(room_id, member_id).await {
+                tracing::warn!(
+                    "Failed to drop membership of banned member {} in room {}: {}",
+                    member_id,
+                    room_id,
+                    err
+                );
+            }
+            SanctionAction::Banned
+        }
+        SanctionKind::Mute => SanctionAction::Muted,
+    };
+    announce(
+        state,
+        &user.member_id,
+        room_id,
+        member_id,
+        action,
+        sanction.reason.clone(),
+        sanction.expires_at,
+    )
+    .await;
+    (StatusCode::OK, Json(sanction)).into_response()
+}
+
+/// Lift a ban or mute on `member_id`.
+async fn pardon(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    room_id: &str,
+    member_id: &str,
+    kind: SanctionKind,
+) -> Response {
+    if let Err(response) = require_admin(state, user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(state, user, room_id).await {
+        return response;
+    }
+    let lifted = match lift(state, room_id, member_id, kind).await {
+        Ok(lifted) => lifted,
+        Err(err) => return storage_error_response(err),
+    };
+    if !lifted {
+        let message = match kind {
+            SanctionKind::Ban => "member is not banned",
+            SanctionKind::Mute => "member is not muted",
+        };
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found(message)),
+        )
+            .into_response();
+    }
+    let action = match kind {
+        SanctionKind::Ban => SanctionAction::Unbanned,
+        SanctionKind::Mute => SanctionAction::Unmuted,
+    };
+    announce(
+        state,
+        &user.member_id,
+        room_id,
+        member_id,
+        action,
+        None,
+        None,
+    )
+    .await;
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/sanctions",
+    tag = "rooms",
+    summary = "List the bans and mutes in force in a room (admin only)",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "Bans and mutes in force", body = ListSanctionsResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.list_sanctions", skip(state, user), fields(room_id = %id))]
+async fn list_sanctions(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    let now = Utc::now();
+    let sanctions: Vec<Sanction> = state
+        .sanctions
+        .read()
+        .await
+        .get(&id)
+        .into_iter()
+        .flatten()
+        .filter(|sanction| sanction.in_force(now))
+        .cloned()
+        .collect();
+    let response = ListSanctionsResponse {
+        room_id: id,
+        total: sanctions.len(),
+        sanctions,
+    };
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/members/{member_id}/kick",
+    tag = "rooms",
+    summary = "Remove a member from a room (admin only)",
+    description = "The member can rejoin as the room's join policy allows.",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("member_id" = String, Path, description = "Member to remove"),
+    ),
+    request_body(content = KickRequest, description = "Optional reason"),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found, or the member is not in it", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.kick_member",
+    skip(state, user, payload),
+    fields(room_id = %id, member_id = %member_id)
+)]
+async fn kick_member(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, member_id)): Path<(String, String)>,
+    payload: Option<Json<KickRequest>>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    match state.remove_member(&id, &member_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::not_found("member is not in the room")),
+            )
+                .into_response()
+        }
+        Err(err) => return storage_error_response(err),
+    }
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    announce(
+        &state,
+        &user.member_id,
+        &id,
+        &member_id,
+        SanctionAction::Kicked,
+        reason(payload.reason),
+        None,
+    )
+    .await;
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/members/{member_id}/ban",
+    tag = "rooms",
+    summary = "Ban a member from a room (admin only)",
+    description = "Removes the member from the room and keeps them out of it until the ban \
+                   lapses or is lifted.",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("member_id" = String, Path, description = "Member to ban"),
+    ),
+    request_body(content = SanctionRequest, description = "Optional duration and reason"),
+    responses(
+        (status = 200, description = "Ban in force", body = Sanction),
+        (status = 400, description = "Invalid duration, or the caller named themselves", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.ban_member",
+    skip(state, user, payload),
+    fields(room_id = %id, member_id = %member_id)
+)]
+async fn ban_member(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, member_id)): Path<(String, String)>,
+    payload: Option<Json<SanctionRequest>>,
+) -> Response {
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    sanction(&state, &user, &id, &member_id, SanctionKind::Ban, payload).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/rooms/{id}/members/{member_id}/ban",
+    tag = "rooms",
+    summary = "Lift a member's ban from a room (admin only)",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("member_id" = String, Path, description = "Banned member"),
+    ),
+    responses(
+        (status = 204, description = "Ban lifted"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found, or the member is not banned", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.unban_member",
+    skip(state, user),
+    fields(room_id = %id, member_id = %member_id)
+)]
+async fn unban_member(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, member_id)): Path<(String, String)>,
+) -> Response {
+    pardon(&state, &user, &id, &member_id, SanctionKind::Ban).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/members/{member_id}/mute",
+    tag = "rooms",
+    summary = "Mute a member in a room (admin only)",
+    description = "The member keeps reading the room but can't send to it until the mute \
+                   lapses or is lifted.",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("member_id" = String, Path, description = "Member to mute"),
+    ),
+    request_body(content = SanctionRequest, description = "Optional duration and reason"),
+    responses(
+        (status = 200, description = "Mute in force", body = Sanction),
+        (status = 400, description = "Invalid duration, or the caller named themselves", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.mute_member",
+    skip(state, user, payload),
+    fields(room_id = %id, member_id = %member_id)
+)]
+async fn mute_member(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, member_id)): Path<(String, String)>,
+    payload: Option<Json<SanctionRequest>>,
+) -> Response {
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    sanction(&state, &user, &id, &member_id, SanctionKind::Mute, payload).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/rooms/{id}/members/{member_id}/mute",
+    tag = "rooms",
+    summary = "Unmute a member in a room (admin only)",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("member_id" = String, Path, description = "Muted member"),
+    ),
+    responses(
+        (status = 204, description = "Mute lifted"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found, or the member is not muted", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.unmute_member",
+    skip(state, user),
+    fields(room_id = %id, member_id = %member_id)
+)]
+async fn unmute_member(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, member_id)): Path<(String, String)>,
+) -> Response {
+    pardon(&state, &user, &id, &member_id, SanctionKind::Mute).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::JwtConfig;
+    use crate::config::NexisConfig;
+    use crate::db::Storage;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[test]
+    fn sanctions_lapse_when_they_expire() {
+        let now = Utc::now();
+        let mut sanction = Sanction {
+            member_id: "alice".to_string(),
+            kind: SanctionKind::Mute,
+            reason: None,
+            created_by: "admin".to_string(),
+            created_at: now,
+            expires_at: None,
+        };
+        assert!(sanction.in_force(now));
+        sanction.expires_at = Some(now + chrono::Duration::seconds(60));
+        assert!(sanction.in_force(now));
+        assert!(!sanction.in_force(now + chrono::Duration::seconds(60)));
+
+        assert_eq!(reason(Some("  spam ".to_string())).as_deref(), Some("spam"));
+        assert_eq!(reason(Some(" ".to_string())), None);
+        assert!(SanctionAction::Banned.removes());
+        assert!(!SanctionAction::Muted.removes());
+    }
+
+    #[tokio::test]
+    async fn sanctioned_members_are_kept_from_writing_and_joining() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let mut events = state.room_events.subscribe();
+        let app = routes(state.clone());
+        let alice = "nexis:human:alice";
+        let send = |app: Router, room_id: &str| {
+            let body = json!({ "roomId": room_id, "sender": alice, "text": "hi" });
+            let request = request(alice, "POST", "/v1/messages", body);
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = json_body(response).await;
+                (status, body["code"].clone())
+            }
+        };
+
+        let room_id = new_room(&app, "admin", "ops").await;
+        let member_uri = |action: &str| format!("/v1/rooms/{room_id}/members/{alice}/{action}");
+        let invite = json!({ "memberId": alice });
+        let invite_uri = format!("/v1/rooms/{room_id}/invite");
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", &invite_uri, invite.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Muted members keep their place but can't write.
+        let response = app
+            .clone()
+            .oneshot(request(alice, "POST", &member_uri("mute"), json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let mute = json!({ "durationSecs": 60, "reason": "cool off" });
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", &member_uri("mute"), mute))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            send(app.clone(), &room_id).await,
+            (StatusCode::FORBIDDEN, json!("MEMBER_MUTED"))
+        );
+        let response = app
+            .clone()
+            .oneshot(request("admin", "DELETE", &member_uri("mute"), json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(send(app.clone(), &room_id).await.0, StatusCode::CREATED);
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", &member_uri("kick"), json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(state.room_members.read().await[&room_id], ["admin"]);
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", &member_uri("kick"), json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Banned members can't write, rejoin or be invited back.
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", &member_uri("ban"), json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            send(app.clone(), &room_id).await,
+            (StatusCode::FORBIDDEN, json!("MEMBER_BANNED"))
+        );
+        let join_uri = format!("/v1/rooms/{room_id}/join");
+        let response = app
+            .clone()
+            .oneshot(request(alice, "POST", &join_uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", &invite_uri, invite))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let sanctions_uri = format!("/v1/rooms/{room_id}/sanctions");
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", &sanctions_uri, json!({})))
+            .await
+            .unwrap();
+        let listed = json_body(response).await;
+        assert_eq!(listed["total"], 1);
+        assert_eq!(listed["sanctions"][0]["kind"], "ban");
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "DELETE", &member_uri("ban"), json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app
+            .clone()
+            .oneshot(request("admin", "DELETE", &member_uri("ban"), json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let mut actions = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let RoomEvent::Sanction { action, .. } = event {
+                actions.push(action);
+            }
+        }
+        assert_eq!(
+            actions,
+            [
+                SanctionAction::Muted,
+                SanctionAction::Unmuted,
+                SanctionAction::Kicked,
+                SanctionAction::Banned,
+                SanctionAction::Unbanned,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn bans_and_mutes_are_stored_and_restored() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let config = Arc::new(config);
+        let storage = Storage::default();
+        let app = |storage: Storage| {
+            let state = AppState {
+                config: config.clone(),
+                ..AppState::default()
+            };
+            routes(state.with_storage(storage))
+        };
+        let bob = "nexis:human:bob";
+        let carol = "nexis:human:carol";
+
+        let first = app(storage.clone());
+        let room_id = new_room(&first, "admin", "ops").await;
+        let member_uri =
+            |member: &str, action: &str| format!("/v1/rooms/{room_id}/members/{member}/{action}");
+        for (member, action) in [(bob, "ban"), (carol, "mute")] {
+            let response = first
+                .clone()
+                .oneshot(request(
+                    "admin",
+                    "POST",
+                    &member_uri(member, action),
+                    json!({}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        assert_eq!(storage.sanctions.list().await.unwrap().len(), 2);
+
+        // A restarted gateway keeps both in force.
+        let restored = Storage {
+            restored_sanctions: storage.sanctions.list().await.unwrap(),
+            sanctions: storage.sanctions.clone(),
+            ..Storage::restore(storage.rooms.clone(), storage.messages.clone(), 10)
+                .await
+                .unwrap()
+        };
+        let second = app(restored);
+        let join_uri = format!("/v1/rooms/{room_id}/join");
+        let response = second
+            .clone()
+            .oneshot(request(bob, "POST", &join_uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let sanctions_uri = format!("/v1/rooms/{room_id}/sanctions");
+        let response = second
+            .clone()
+            .oneshot(request("admin", "GET", &sanctions_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["total"], 2);
+
+        // Lifting a sanction, or deleting the room, drops it from storage.
+        let response = second
+            .clone()
+            .oneshot(request(
+                "admin",
+                "DELETE",
+                &member_uri(carol, "mute"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(storage.sanctions.list().await.unwrap().len(), 1);
+        second
+            .oneshot(request(
+                "admin",
+                "DELETE",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert!(storage.sanctions.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn websocket_subscriptions_end_with_kicks_and_bans() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let addr = serve(routes(state)).await;
+
+        let admin = JwtConfig::test_token("admin");
+        let client = reqwest::Client::new();
+        let created: Value = client
+            .post(format!("http://{addr}/v1/rooms"))
+            .bearer_auth(&admin)
+            .json(&json!({ "name": "ops" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let room_id = created["id"].as_str().unwrap().to_string();
+        let moderate = |action: &str| {
+            client
+                .post(format!(
+                    "http://{addr}/v1/rooms/{room_id}/members/bob/{action}"
+                ))
+                .bearer_auth(&admin)
+                .json(&json!({}))
+                .send()
+        };
+        client
+            .post(format!("http://{addr}/v1/rooms/{room_id}/invite"))
+            .bearer_auth(&admin)
+            .json(&json!({ "memberId": "bob" }))
+            .send()
+            .await
+            .unwrap();
+
+        let mut ws = connect_ws(addr, Some("bob")).await;
+        let subscribe = json!({ "type": "subscribe", "roomId": room_id.clone() });
+        send_json(&mut ws, subscribe.clone()).await;
+        let reply = next_json(&mut ws).await;
+        assert_eq!(reply["type"], "subscribed");
+
+        assert_eq!(moderate("kick").await.unwrap().status().as_u16(), 204);
+        let event = next_json(&mut ws).await;
+        assert_eq!(event["type"], "sanction");
+        assert_eq!(event["action"], "kicked");
+        assert_eq!(event["memberId"], "bob");
+
+        // The kick ended the subscription, so the ban isn't delivered; the
+        // next frame is the reply to the refused subscription.
+        assert_eq!(moderate("ban").await.unwrap().status().as_u16(), 200);
+        send_json(&mut ws, subscribe).await;
+        let reply = next_json(&mut ws).await;
+        assert_eq!(reply["type"], "error");
+        assert_eq!(reply["roomId"], room_id);
+    }
+}
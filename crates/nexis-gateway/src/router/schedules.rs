@@ -0,0 +1,1103 @@
+//! Scheduled room jobs and the runner that carries them out.
+//!
+//! Admins manage a room's jobs under `/v1/rooms/:id/schedules`. When a job
+//! comes due, [`RoomJobRunner`] posts its output to the room as
+//! [`SCHEDULER_MEMBER_ID`], like any other message.
+//!
+//! Message retention is a job too: `/v1/rooms/:id/retention` keeps one
+//! hourly [`JobKind::MessageRetention`] per room, which deletes or tombstones
+//! the messages past the room's limits.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use nexis_runtime::GenerateRequest;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{
+    context_message, ensure_room_access, provider_error_type, require_admin, ErrorResponse,
+    RoomEvent, SharedState, StoredMessage,
+};
+use crate::audit::{AuditAction, AuditEvent, AuditResult};
+use crate::auth::AuthenticatedUser;
+use crate::metrics::{record_ai_request, MESSAGES_SENT};
+use crate::scheduler::{
+    JobKind, JobRunner, RetentionAction, Schedule, ScheduledJob, SchedulerError,
+};
+
+/// Sender of messages posted by scheduled jobs.
+pub(super) const SCHEDULER_MEMBER_ID: &str = "nexis:system:scheduler";
+const SUMMARY_PROMPT: &str = "Summarize the conversation above in a few short bullet points, \
+     covering decisions made and open questions.";
+const DEFAULT_REMINDER: &str = "This room has been quiet for a while. Anything to share?";
+/// How often message retention runs.
+const RETENTION_EVERY_SECS: u64 = 3_600;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route(
+            "/v1/rooms/:id/schedules",
+            get(list_schedules).post(create_schedule),
+        )
+        .route("/v1/rooms/:id/schedules/:job_id", delete(delete_schedule))
+        .route(
+            "/v1/rooms/:id/retention",
+            get(get_retention)
+                .put(set_retention)
+                .delete(delete_retention),
+        )
+}
+
+/// Start the scheduler loop for `state`; it stops when the gateway shuts down.
+pub(super) fn spawn_runner(state: &SharedState) {
+    state.scheduler.clone().spawn(
+        Arc::new(RoomJobRunner::new(state.clone())),
+        state.config.scheduler.tick(),
+        state.shutdown.clone(),
+    );
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct CreateScheduleRequest {
+    job: JobKind,
+    schedule: Schedule,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct ListSchedulesResponse {
+    schedules: Vec<ScheduledJob>,
+    total: usize,
+}
+
+/// Limits of a room's message retention; at least one must be set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub(super) struct MessageRetention {
+    /// Messages older than this many seconds are removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_age_secs: Option<u64>,
+    /// Only the newest this many messages are kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_messages: Option<usize>,
+    #[serde(default)]
+    action: RetentionAction,
+}
+
+impl MessageRetention {
+    /// Limits of a retention job; `None` for other jobs.
+    fn of(job: &ScheduledJob) -> Option<Self> {
+        match job.job {
+            JobKind::MessageRetention {
+                max_age_secs,
+                max_messages,
+                action,
+            } => Some(Self {
+                max_age_secs,
+                max_messages,
+                action,
+            }),
+            _ => None,
+        }
+    }
+
+    fn job(&self) -> JobKind {
+        JobKind::MessageRetention {
+            max_age_secs: self.max_age_secs,
+            max_messages: self.max_messages,
+            action: self.action,
+        }
+    }
+
+    pub(super) fn validate(&self) -> Result<(), SchedulerError> {
+        self.job().validate()
+    }
+}
+
+/// The room's message retention job, if it has one.
+async fn retention_job(state: &SharedState, room_id: &str) -> Option<ScheduledJob> {
+    state
+        .scheduler
+        .list(room_id)
+        .await
+        .into_iter()
+        .find(|job| matches!(job.job, JobKind::MessageRetention { .. }))
+}
+
+/// The room's message retention, if it has one.
+pub(super) async fn room_retention(state: &SharedState, room_id: &str) -> Option<MessageRetention> {
+    retention_job(state, room_id)
+        .await
+        .as_ref()
+        .and_then(MessageRetention::of)
+}
+
+/// Replace the room's retention job with one enforcing `retention`, and
+/// audit the change.
+pub(super) async fn replace_retention(
+    state: &SharedState,
+    room_id: &str,
+    retention: &MessageRetention,
+    member_id: &str,
+) -> Result<ScheduledJob, SchedulerError> {
+    let previous = retention_job(state, room_id).await;
+    let schedule = Schedule::Interval {
+        every_secs: RETENTION_EVERY_SECS,
+    };
+    let mut result = state
+        .scheduler
+        .create(room_id, retention.job(), schedule, member_id)
+        .await;
+    if let (Ok(_), Some(previous)) = (&result, previous) {
+        if let Err(err) = state.scheduler.delete(&previous.id).await {
+            result = Err(err);
+        }
+    }
+    audit_retention_change(state, room_id, member_id, &result).await;
+    result
+}
+
+/// Remove the room's retention job and audit the change; `NotFound` when
+/// the room has none.
+pub(super) async fn remove_retention(
+    state: &SharedState,
+    room_id: &str,
+    member_id: &str,
+) -> Result<ScheduledJob, SchedulerError> {
+    let Some(job) = retention_job(state, room_id).await else {
+        return Err(SchedulerError::NotFound);
+    };
+    let result = state.scheduler.delete(&job.id).await;
+    audit_retention_change(state, room_id, member_id, &result).await;
+    result
+}
+
+async fn audit_retention_change(
+    state: &SharedState,
+    room_id: &str,
+    member_id: &str,
+    result: &Result<ScheduledJob, SchedulerError>,
+) {
+    let event = AuditEvent::new(
+        member_id,
+        AuditAction::RetentionChanged,
+        format!("room:{room_id}/messages"),
+    );
+    let event = match result {
+        Ok(_) => event,
+        Err(err) => event.with_result(AuditResult::Failed, err.to_string()),
+    };
+    state.audit.record(event).await;
+}
+
+pub(super) fn scheduler_error_response(err: SchedulerError) -> Response {
+    match err {
+        SchedulerError::InvalidSchedule(message) => (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(message)),
+        )
+            .into_response(),
+        SchedulerError::NotFound => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("scheduled job not found")),
+        )
+            .into_response(),
+        SchedulerError::Store(_) | SchedulerError::Job(_) => {
+            tracing::error!("Scheduler error: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::internal_error()),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/schedules",
+    tag = "schedules",
+    summary = "List a room's scheduled jobs",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room's jobs", body = ListSchedulesResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.list_schedules", skip(state, user), fields(room_id = %id))]
+async fn list_schedules(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+
+    let schedules = state.scheduler.list(&id).await;
+    let total = schedules.len();
+    (
+        StatusCode::OK,
+        Json(ListSchedulesResponse { schedules, total }),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/schedules",
+    tag = "schedules",
+    summary = "Schedule a job in a room",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = CreateScheduleRequest,
+    responses(
+        (status = 201, description = "Job scheduled", body = ScheduledJob),
+        (status = 400, description = "Invalid schedule or job", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.create_schedule",
+    skip(state, user, payload),
+    fields(room_id = %id, member_id = %user.member_id)
+)]
+async fn create_schedule(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(payload): Json<CreateScheduleRequest>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+
+    let result = state
+        .scheduler
+        .create(&id, payload.job, payload.schedule, &user.member_id)
+        .await;
+    let event = match &result {
+        Ok(job) => AuditEvent::new(
+            &user.member_id,
+            AuditAction::ScheduleCreated,
+            format!("room:{id}/schedule:{}", job.id),
+        ),
+        Err(err) => AuditEvent::new(
+            &user.member_id,
+            AuditAction::ScheduleCreated,
+            format!("room:{id}"),
+        )
+        .with_result(AuditResult::Failed, err.to_string()),
+    };
+    state.audit.record(event).await;
+    match result {
+        Ok(job) => (StatusCode::CREATED, Json(job)).into_response(),
+        Err(err) => scheduler_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/rooms/{id}/schedules/{job_id}",
+    tag = "schedules",
+    summary = "Delete a scheduled job",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("job_id" = String, Path, description = "Job id"),
+    ),
+    responses(
+        (status = 204, description = "Job deleted"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room or job not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.delete_schedule",
+    skip(state, user),
+    fields(room_id = %id, job_id = %job_id)
+)]
+async fn delete_schedule(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, job_id)): Path<(String, String)>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if state
+        .scheduler
+        .get(&job_id)
+        .await
+        .is_none_or(|job| job.room_id != id)
+    {
+        return scheduler_error_response(SchedulerError::NotFound);
+    }
+
+    let result = state.scheduler.delete(&job_id).await;
+    let event = AuditEvent::new(
+        &user.member_id,
+        AuditAction::ScheduleDeleted,
+        format!("room:{id}/schedule:{job_id}"),
+    );
+    let event = match &result {
+        Ok(_) => event,
+        Err(err) => event.with_result(AuditResult::Failed, err.to_string()),
+    };
+    state.audit.record(event).await;
+    match result {
+        Ok(_) => (StatusCode::NO_CONTENT, ()).into_response(),
+        Err(err) => scheduler_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/retention",
+    tag = "schedules",
+    summary = "Get a room's message retention",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room's retention job", body = ScheduledJob),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found or without retention", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.get_retention", skip(state, user), fields(room_id = %id))]
+async fn get_retention(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+
+    match retention_job(&state, &id).await {
+        Some(job) => (StatusCode::OK, Json(job)).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("room has no message retention")),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/rooms/{id}/retention",
+    tag = "schedules",
+    summary = "Set a room's message retention",
+    description = "Replaces the room's retention job. The job runs hourly and deletes or \
+        tombstones messages older than `maxAgeSecs` and the oldest beyond `maxMessages`.",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = MessageRetention,
+    responses(
+        (status = 200, description = "Retention set", body = ScheduledJob),
+        (status = 400, description = "Invalid retention limits", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.set_retention",
+    skip(state, user, payload),
+    fields(room_id = %id, member_id = %user.member_id)
+)]
+async fn set_retention(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(payload): Json<MessageRetention>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+
+    match replace_retention(&state, &id, &payload, &user.member_id).await {
+        Ok(job) => (StatusCode::OK, Json(job)).into_response(),
+        Err(err) => scheduler_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/rooms/{id}/retention",
+    tag = "schedules",
+    summary = "Remove a room's message retention",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 204, description = "Retention removed"),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found or without retention", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.delete_retention", skip(state, user), fields(room_id = %id))]
+async fn delete_retention(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    match remove_retention(&state, &id, &user.member_id).await {
+        Ok(_) => (StatusCode::NO_CONTENT, ()).into_response(),
+        Err(SchedulerError::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("room has no message retention")),
+        )
+            .into_response(),
+        Err(err) => scheduler_error_response(err),
+    }
+}
+
+/// Carries out due jobs against the gateway's rooms.
+pub(super) struct RoomJobRunner {
+    state: SharedState,
+}
+
+impl RoomJobRunner {
+    pub(super) fn new(state: SharedState) -> Self {
+        Self { state }
+    }
+
+    /// Messages written by members since the job last ran, or over the past
+    /// day on its first run.
+    async fn messages_since(&self, job: &ScheduledJob, now: DateTime<Utc>) -> Vec<StoredMessage> {
+        let since = job
+            .last_run_at
+            .unwrap_or_else(|| now - chrono::Duration::days(1));
+        self.state
+            .room_messages
+            .read()
+            .await
+            .get(&job.room_id)
+            .map(|messages| {
+                messages
+                    .iter()
+                    .filter(|message| message.created_at > since && message.created_at <= now)
+                    .filter(|message| message.sender != SCHEDULER_MEMBER_ID)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Summary written by the AI provider, billed to the job's creator and
+    /// the room. `None` when no provider is configured, either budget is
+    /// spent or the provider fails.
+    async fn ai_summary(&self, job: &ScheduledJob, messages: &[StoredMessage]) -> Option<String> {
+        let provider = self.state.ai_provider.clone()?;
+        if let Err(err) = self.state.costs.check_budget(&job.created_by) {
+            tracing::warn!(job_id = %job.id, "Skipping AI summary: {}", err);
+            return None;
+        }
+        if let Err(err) = self.state.room_budgets.check(&job.room_id) {
+            tracing::warn!(job_id = %job.id, "Skipping AI summary: {}", err);
+            return None;
+        }
+
+        let history = messages.iter().map(context_message).collect();
+        let assembled = self
+            .state
+            .prompt_assembler
+            .assemble(None, history, SUMMARY_PROMPT);
+        let request = GenerateRequest {
+            prompt: assembled.prompt,
+            model: None,
+            max_tokens: None,
+            temperature: None,
+            metadata: Some(serde_json::json!({ "roomId": job.room_id, "jobId": job.id })),
+            images: Vec::new(),
+        };
+        let started = Instant::now();
+        let result = provider.generate(request).await;
+        record_ai_request(
+            provider.name(),
+            started,
+            result.as_ref().err().map(provider_error_type),
+        );
+        let generated = match result {
+            Ok(generated) => generated,
+            Err(err) => {
+                tracing::warn!(job_id = %job.id, "AI summary failed: {}", err);
+                return None;
+            }
+        };
+        self.state
+            .room_budgets
+            .record(&job.room_id, generated.usage);
+        if let Some(usage) = generated.usage {
+            let model = generated.model.as_deref().unwrap_or("unknown");
+            self.state
+                .costs
+                .record(&job.created_by, provider.name(), model, usage);
+        }
+        Some(generated.content)
+    }
+
+    async fn summary(&self, job: &ScheduledJob, now: DateTime<Utc>) -> Option<String> {
+        let messages = self.messages_since(job, now).await;
+        if messages.is_empty() {
+            return None;
+        }
+        if let Some(summary) = self.ai_summary(job, &messages).await {
+            return Some(summary);
+        }
+
+        let mut senders: Vec<&str> = Vec::new();
+        for message in &messages {
+            if !senders.contains(&message.sender.as_str()) {
+                senders.push(&message.sender);
+            }
+        }
+        Some(format!(
+            "Daily summary: {} message(s) from {}.",
+            messages.len(),
+            senders.join(", ")
+        ))
+    }
+
+    /// Reminder text when the room has been idle for `idle_secs`. The
+    /// reminder counts as activity, so a quiet room is nudged once per idle
+    /// stretch rather than on every run.
+    async fn reminder(
+        &self,
+        job: &ScheduledJob,
+        now: DateTime<Utc>,
+        idle_secs: u64,
+        message: Option<&str>,
+    ) -> Option<String> {
+        let last_activity = self
+            .state
+            .room_messages
+            .read()
+            .await
+            .get(&job.room_id)
+            .and_then(|messages| messages.iter().map(|message| message.created_at).max())
+            .map_or(job.created_at, |last| last.max(job.created_at));
+        let idle = now.signed_duration_since(last_activity);
+        if idle < chrono::Duration::seconds(i64::try_from(idle_secs).unwrap_or(i64::MAX)) {
+            return None;
+        }
+        Some(message.unwrap_or(DEFAULT_REMINDER).to_string())
+    }
+
+    /// Delete or tombstone the room's messages older than `max_age_secs`
+    /// and the oldest beyond the newest `max_messages`, auditing each one.
+    async fn enforce_retention(
+        &self,
+        room_id: &str,
+        now: DateTime<Utc>,
+        max_age_secs: Option<u64>,
+        max_messages: Option<usize>,
+        action: RetentionAction,
+    ) -> Result<(), SchedulerError> {
+        let cutoff = max_age_secs
+            .and_then(|secs| chrono::Duration::try_seconds(i64::try_from(secs).ok()?))
+            .and_then(|max_age| now.checked_sub_signed(max_age));
+        let Ok(_permit) = self.state.write_gate.clone().acquire_owned().await else {
+            return Err(SchedulerError::Job("gateway is shutting down".to_string()));
+        };
+
+        // Prune the store rather than the messages held in memory, which may
+        // be only the newest of the room's history.
+        let mut messages = self.state.room_messages.write().await;
+        let removed = self
+            .state
+            .message_store
+            .prune_room(
+                room_id,
+                cutoff,
+                max_messages,
+                action == RetentionAction::Tombstone,
+            )
+            .await
+            .map_err(|err| SchedulerError::Store(err.to_string()))?;
+        if removed.is_empty() {
+            return Ok(());
+        }
+        let ids: HashSet<&str> = removed.iter().map(String::as_str).collect();
+        if let Some(room_messages) = messages.get_mut(room_id) {
+            match action {
+                RetentionAction::Delete => {
+                    room_messages.retain(|message| !ids.contains(message.id.as_str()))
+                }
+                RetentionAction::Tombstone => room_messages
+                    .iter_mut()
+                    .filter(|message| ids.contains(message.id.as_str()))
+                    .for_each(StoredMessage::tombstone),
+            }
+        }
+        drop(messages);
+        self.state.conversations.invalidate(room_id).await;
+
+        for id in &removed {
+            if let Some(index) = &self.state.lexical_index {
+                if let Some(id) = crate::search::document_id(id) {
+                    index.remove(id);
+                }
+            }
+            self.state
+                .audit
+                .record(AuditEvent::new(
+                    SCHEDULER_MEMBER_ID,
+                    AuditAction::MessageDeleted,
+                    format!("room:{room_id}/message:{id}"),
+                ))
+                .await;
+        }
+        tracing::info!(
+            room_id,
+            removed = removed.len(),
+            ?action,
+            "Enforced message retention"
+        );
+        Ok(())
+    }
+
+    async fn post(&self, room_id: &str, text: String) -> Result<(), SchedulerError> {
+        let message = StoredMessage::new(SCHEDULER_MEMBER_ID, text);
+        let tenant = self.state.room_tenant(room_id).await;
+        let Ok(_permit) = self.state.write_gate.clone().acquire_owned().await else {
+            return Err(SchedulerError::Job("gateway is shutting down".to_string()));
+        };
+        self.state
+            .store_message(room_id, tenant.as_deref(), &message)
+            .await
+            .map_err(|err| SchedulerError::Store(err.to_string()))?;
+        self.state
+            .room_messages
+            .write()
+            .await
+            .entry(room_id.to_string())
+            .or_default()
+            .push(message.clone());
+        self.state
+            .publish(RoomEvent::Message {
+                room_id: room_id.to_string(),
+                message,
+            })
+            .await;
+        MESSAGES_SENT.inc();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl JobRunner for RoomJobRunner {
+    #[tracing::instrument(
+        name = "gateway.run_scheduled_job",
+        skip_all,
+        fields(job_id = %job.id, room_id = %job.room_id)
+    )]
+    async fn run(&self, job: &ScheduledJob, now: DateTime<Utc>) -> Result<(), SchedulerError> {
+        if !self.state.rooms.read().await.contains_key(&job.room_id) {
+            return Err(SchedulerError::Job("room no longer exists".to_string()));
+        }
+        let text = match &job.job {
+            JobKind::DailySummary => self.summary(job, now).await,
+            JobKind::InactivityReminder { idle_secs, message } => {
+                self.reminder(job, now, *idle_secs, message.as_deref())
+                    .await
+            }
+            JobKind::MessageRetention {
+                max_age_secs,
+                max_messages,
+                action,
+            } => {
+                return self
+                    .enforce_retention(&job.room_id, now, *max_age_secs, *max_messages, *action)
+                    .await;
+            }
+        };
+        match text {
+            Some(text) => self.post(&job.room_id, text).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn scheduled_jobs_are_managed_by_admins_and_post_to_the_room() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let app = routes(state.clone());
+
+        let room_id = new_room(&app, "admin", "standup").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": "nexis:human:admin", "text": "shipped the fix" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let schedules_uri = format!("/v1/rooms/{room_id}/schedules");
+        let summary = json!({
+            "job": { "type": "daily_summary" },
+            "schedule": { "type": "interval", "everySecs": 3600 }
+        });
+        let response = app
+            .clone()
+            .oneshot(request("alice", "POST", &schedules_uri, summary.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &schedules_uri,
+                json!({
+                    "job": { "type": "daily_summary" },
+                    "schedule": { "type": "daily", "at": "25:00" }
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "POST", &schedules_uri, summary))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let job = json_body(response).await;
+        assert_eq!(job["roomId"], room_id.as_str());
+        assert_eq!(job["createdBy"], "admin");
+        let job_id = job["id"].as_str().unwrap().to_string();
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &schedules_uri,
+                json!({
+                    "job": { "type": "inactivity_reminder", "idleSecs": 600, "message": "Status?" },
+                    "schedule": { "type": "interval", "everySecs": 1800 }
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", &schedules_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["total"], 2);
+
+        let runner = RoomJobRunner::new(state.clone());
+        let later = Utc::now() + chrono::Duration::hours(2);
+        assert_eq!(state.scheduler.run_due(later, &runner).await, 2);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "GET",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        let mut posted: Vec<String> = json_body(response).await["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|message| message["sender"] == SCHEDULER_MEMBER_ID)
+            .map(|message| message["text"].as_str().unwrap().to_string())
+            .collect();
+        posted.sort();
+        assert_eq!(
+            posted,
+            vec![
+                "Daily summary: 1 message(s) from nexis:human:admin.",
+                "Status?"
+            ]
+        );
+
+        let job_uri = format!("{schedules_uri}/{job_id}");
+        let response = app
+            .clone()
+            .oneshot(request("admin", "DELETE", &job_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app
+            .clone()
+            .oneshot(request("admin", "DELETE", &job_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .oneshot(request("admin", "GET", "/v1/audit", Value::Null))
+            .await
+            .unwrap();
+        let actions: Vec<Value> = json_body(response).await["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|event| event["action"].clone())
+            .collect();
+        assert!(actions.contains(&json!("schedule.created")));
+        assert!(actions.contains(&json!("schedule.deleted")));
+    }
+
+    #[tokio::test]
+    async fn message_retention_removes_old_messages_and_audits_them() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let app = routes(state.clone());
+
+        let room_id = new_room(&app, "admin", "ops").await;
+        for text in ["first", "second", "third"] {
+            let response = app
+                .clone()
+                .oneshot(request(
+                    "admin",
+                    "POST",
+                    "/v1/messages",
+                    json!({ "roomId": room_id, "sender": "nexis:human:admin", "text": text }),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+
+        let retention_uri = format!("/v1/rooms/{room_id}/retention");
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", &retention_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app
+            .clone()
+            .oneshot(request("admin", "PUT", &retention_uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "PUT",
+                &retention_uri,
+                json!({ "maxMessages": 2 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PUT",
+                &retention_uri,
+                json!({ "maxMessages": 2 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        // Setting it again replaces the job rather than adding a second one.
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PUT",
+                &retention_uri,
+                json!({ "maxMessages": 1, "action": "tombstone" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.scheduler.list(&room_id).await.len(), 1);
+
+        let runner = RoomJobRunner::new(state.clone());
+        let later = Utc::now() + chrono::Duration::hours(2);
+        assert_eq!(state.scheduler.run_due(later, &runner).await, 1);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "GET",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        let messages = json_body(response).await["messages"].clone();
+        let texts: Vec<&str> = messages
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| message["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(texts, ["", "", "third"]);
+        assert_eq!(messages[0]["deleted"], true);
+        assert!(messages[2].get("deleted").is_none());
+        let stored = state.message_store.list_by_room(&room_id).await.unwrap();
+        assert_eq!(stored[0].content, "");
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PUT",
+                &retention_uri,
+                json!({ "maxAgeSecs": 3600 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let much_later = later + chrono::Duration::hours(2);
+        assert_eq!(state.scheduler.run_due(much_later, &runner).await, 1);
+        assert!(state.room_messages.read().await[&room_id]
+            .iter()
+            .all(|message| message.deleted));
+        assert!(state
+            .message_store
+            .list_by_room(&room_id)
+            .await
+            .unwrap()
+            .iter()
+            .all(|message| message.content.is_empty()));
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "DELETE", &retention_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(state.scheduler.list(&room_id).await.is_empty());
+
+        let response = app
+            .oneshot(request("admin", "GET", "/v1/audit", Value::Null))
+            .await
+            .unwrap();
+        let events = json_body(response).await["events"].clone();
+        let events = events.as_array().unwrap();
+        let deleted = events
+            .iter()
+            .filter(|event| event["action"] == "message.deleted")
+            .count();
+        assert_eq!(deleted, 3);
+        assert!(events
+            .iter()
+            .any(|event| event["action"] == "retention.changed"));
+    }
+
+    #[tokio::test]
+    async fn message_retention_prunes_messages_no_longer_held_in_memory() {
+        use crate::db::Message;
+
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let app = routes(state.clone());
+        let room_id = new_room(&app, "admin", "ops").await;
+        // Stored before the gateway started and not restored into memory.
+        let now = Utc::now();
+        for (id, age_hours) in [("msg_old", 3), ("msg_recent", 0)] {
+            state
+                .message_store
+                .insert(&Message {
+                    id: id.to_string(),
+                    room_id: room_id.clone(),
+                    sender_id: "nexis:human:admin".to_string(),
+                    content: format!("{id} text"),
+                    created_at: now - chrono::Duration::hours(age_hours),
+                    #[cfg(feature = "multi-tenant")]
+                    tenant_id: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let response = app
+            .oneshot(request(
+                "admin",
+                "PUT",
+                &format!("/v1/rooms/{room_id}/retention"),
+                json!({ "maxAgeSecs": 3600 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let job = state.scheduler.list(&room_id).await.remove(0);
+        RoomJobRunner::new(state.clone())
+            .run(&job, now)
+            .await
+            .unwrap();
+
+        let stored: Vec<String> = state
+            .message_store
+            .list_by_room(&room_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|message| message.id)
+            .collect();
+        assert_eq!(stored, ["msg_recent"]);
+    }
+}
@@ -0,0 +1,339 @@
+//! Login, token refresh and logout endpoints.
+//!
+//! `POST /v1/auth/login` trades a member id and the secret configured under
+//! `[auth.member_credentials]` for an access/refresh pair.
+//! `POST /v1/auth/refresh` exchanges a refresh token for a new access/refresh
+//! pair, rotating the refresh token. `POST /v1/auth/logout` revokes the
+//! caller's access token and, when supplied, its refresh token family.
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Extension, Json, Router,
+};
+use nexis_protocol::MemberId;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{ErrorResponse, SharedState};
+use crate::audit::{AuditAction, AuditEvent, AuditResult};
+use crate::auth::{AuthError, AuthenticatedUser, JwtConfig, TokenPair};
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/auth/login", post(login))
+        .route("/v1/auth/refresh", post(refresh_token))
+        .route("/v1/auth/logout", post(logout))
+}
+
+#[derive(Clone, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct LoginRequest {
+    member_id: String,
+    secret: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct LoginResponse {
+    member_id: String,
+    #[serde(flatten)]
+    tokens: TokenPair,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct LogoutRequest {
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/auth/login",
+    tag = "auth",
+    summary = "Log in with a member id and the secret configured under [auth.member_credentials]",
+    security(()),
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "A fresh token pair", body = LoginResponse),
+        (status = 401, description = "Unknown member or wrong secret", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.login", skip_all, fields(member_id = %payload.member_id))]
+async fn login(
+    State(state): State<SharedState>,
+    Extension(jwt): Extension<JwtConfig>,
+    Json(payload): Json<LoginRequest>,
+) -> Response {
+    let member_id = payload.member_id;
+    // argon2 is slow on purpose, so it runs off the async workers.
+    let verified = {
+        let config = state.config.clone();
+        let member_id = member_id.clone();
+        tokio::task::spawn_blocking(move || {
+            config
+                .auth
+                .verify_member_secret(&member_id, &payload.secret)
+        })
+        .await
+        .unwrap_or(false)
+    };
+    let member_type = match member_id.parse::<MemberId>() {
+        Ok(id) if verified => id.member_type(),
+        // Unknown members and wrong secrets get the same answer.
+        _ => {
+            state
+                .audit
+                .record(
+                    AuditEvent::new(
+                        &member_id,
+                        AuditAction::TokenIssued,
+                        format!("member:{member_id}"),
+                    )
+                    .with_result(AuditResult::Denied, "invalid member id or secret"),
+                )
+                .await;
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse::unauthorized("invalid member id or secret")),
+            )
+                .into_response();
+        }
+    };
+
+    let tokens = match jwt.issue_tokens(&member_id, member_type.as_str(), None) {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            tracing::error!("Failed to mint tokens for login: {}", err);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::internal_error()),
+            )
+                .into_response();
+        }
+    };
+    state
+        .audit
+        .record(AuditEvent::new(
+            &member_id,
+            AuditAction::TokenIssued,
+            format!("member:{member_id}"),
+        ))
+        .await;
+
+    (StatusCode::OK, Json(LoginResponse { member_id, tokens })).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/auth/refresh",
+    tag = "auth",
+    summary = "Exchange a refresh token for a new access/refresh pair; the old refresh token is rotated out",
+    security(()),
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "A fresh token pair", body = TokenPair),
+        (status = 401, description = "Invalid, expired or revoked refresh token", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.refresh_token", skip_all)]
+async fn refresh_token(
+    State(state): State<SharedState>,
+    Extension(jwt): Extension<JwtConfig>,
+    Json(payload): Json<RefreshRequest>,
+) -> Response {
+    match jwt.refresh(&payload.refresh_token) {
+        Ok((grant, pair)) => {
+            state
+                .audit
+                .record(AuditEvent::new(
+                    &grant.member_id,
+                    AuditAction::TokenIssued,
+                    format!("member:{}", grant.member_id),
+                ))
+                .await;
+            (StatusCode::OK, Json(pair)).into_response()
+        }
+        Err(
+            err @ (AuthError::InvalidToken | AuthError::TokenExpired | AuthError::TokenRevoked),
+        ) => (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::unauthorized(format!(
+                "refresh failed: {err}"
+            ))),
+        )
+            .into_response(),
+        Err(err) => {
+            tracing::error!("Failed to refresh token: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::internal_error()),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/auth/logout",
+    tag = "auth",
+    summary = "Revoke the caller's access token and, if given, its refresh token family",
+    request_body(content = Option<LogoutRequest>),
+    responses(
+        (status = 204, description = "Tokens revoked"),
+        (status = 401, description = "Missing or invalid access token", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.logout", skip_all, fields(member_id = %user.member_id))]
+async fn logout(
+    State(state): State<SharedState>,
+    Extension(jwt): Extension<JwtConfig>,
+    user: AuthenticatedUser,
+    payload: Option<Json<LogoutRequest>>,
+) -> Response {
+    jwt.revoke(&user.claims);
+    if let Some(refresh_token) = payload.and_then(|Json(payload)| payload.refresh_token) {
+        jwt.revoke_refresh_token(&refresh_token);
+    }
+
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::TokenRevoked,
+            format!("member:{}", user.member_id),
+        ))
+        .await;
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn refresh_rotates_tokens_and_logout_revokes_them() {
+        let jwt = crate::auth::fallback_jwt_config();
+        let app = routes(AppState {
+            jwt: Some(jwt.clone()),
+            ..AppState::default()
+        });
+        let pair = jwt
+            .issue_tokens("nexis:human:alice", "human", None)
+            .unwrap();
+
+        let refresh = |token: &str| {
+            anonymous_request("POST", "/v1/auth/refresh", json!({ "refreshToken": token }))
+        };
+        let response = app
+            .clone()
+            .oneshot(refresh(&pair.refresh_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let refreshed = json_body(response).await;
+        let access_token = refreshed["accessToken"].as_str().unwrap().to_string();
+        let refresh_token = refreshed["refreshToken"].as_str().unwrap().to_string();
+        assert_eq!(refreshed["tokenType"], "Bearer");
+        assert_ne!(refresh_token, pair.refresh_token);
+
+        let response = app
+            .clone()
+            .oneshot(refresh(&pair.refresh_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let list_rooms = |token: &str| request_with_token(token, "GET", "/v1/rooms", json!({}));
+        let response = app
+            .clone()
+            .oneshot(list_rooms(&access_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(request_with_token(
+                &access_token,
+                "POST",
+                "/v1/auth/logout",
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = app
+            .clone()
+            .oneshot(list_rooms(&access_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn login_trades_a_member_secret_for_tokens() {
+        let mut config = NexisConfig::default();
+        config.auth.member_credentials.insert(
+            "nexis:human:alice@example.com".to_string(),
+            // argon2id of "secret"
+            "$argon2id$v=19$m=19456,t=2,p=1$bmV4aXMtdGVzdC1zYWx0IQ$flgXTwWKpa2ibWVefh0eEPMbiIW/9RMP02kMMjgoGvE".to_string(),
+        );
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let login = |member_id: &str, secret: &str| {
+            let body = json!({ "memberId": member_id, "secret": secret });
+            anonymous_request("POST", "/v1/auth/login", body)
+        };
+
+        for (member_id, secret) in [
+            ("nexis:human:alice@example.com", "wrong"),
+            ("nexis:human:bob@example.com", "secret"),
+            ("alice", "secret"),
+        ] {
+            let response = app.clone().oneshot(login(member_id, secret)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{member_id}");
+        }
+
+        let response = app
+            .clone()
+            .oneshot(login("nexis:human:alice@example.com", "secret"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let login = json_body(response).await;
+        assert_eq!(login["memberId"], "nexis:human:alice@example.com");
+        assert_eq!(login["tokenType"], "Bearer");
+        assert!(login["refreshToken"].is_string());
+
+        let response = app
+            .oneshot(request_with_token(
+                login["accessToken"].as_str().unwrap(),
+                "GET",
+                "/v1/rooms",
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,597 @@
+//! Room settings beyond name and topic.
+//!
+//! A room's description, `@ai` defaults and allowed models, moderation
+//! override, join policy, slow mode and message size limits are kept as
+//! one [`RoomSettings`] document, written to the
+//! [`RoomSettingsRepository`] before it takes effect. Message retention is
+//! part of the settings API but lives on as the room's retention job; see
+//! [`super::schedules`].
+//!
+//! [`RoomSettingsRepository`]: crate::db::RoomSettingsRepository
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::Utc;
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::ToSchema;
+
+use super::message_limits::{MAX_ROOM_MESSAGE_BYTES, MAX_ROOM_METADATA_BYTES};
+use super::schedules::{self, MessageRetention};
+use super::slow_mode::MAX_SLOW_MODE_SECS;
+use super::{
+    ensure_room_access, joins, require_admin, storage_error_response, ErrorResponse, SharedState,
+};
+use crate::audit::{AuditAction, AuditEvent, AuditResult};
+use crate::auth::AuthenticatedUser;
+use crate::db::{RepositoryError, RoomSettingsRecord};
+use crate::moderation::ModerationPolicy;
+use crate::scheduler::SchedulerError;
+
+/// Longest accepted room description, in characters.
+const MAX_DESCRIPTION_CHARS: usize = 2_000;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new().route(
+        "/v1/rooms/:id/settings",
+        get(get_room_settings).patch(update_room_settings),
+    )
+}
+
+/// Who may join a room.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum JoinPolicy {
+    /// Anyone who can see the room.
+    #[default]
+    Open,
+    /// Only invited members.
+    InviteOnly,
+    /// Anyone, once a moderator approves the request.
+    ApprovalRequired,
+}
+
+/// Stored settings of a room.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct RoomSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) description: Option<String>,
+    /// AI provider `@ai` requests in the room are meant for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) ai_provider: Option<String>,
+    /// Model `@ai` requests use when they don't name one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) ai_model: Option<String>,
+    /// Models `@ai` requests may pick; replaces the configured allow-list.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(super) ai_models: Vec<String>,
+    /// Overrides the `[moderation]` policy in the room.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) moderation: Option<ModerationPolicy>,
+    #[serde(default)]
+    pub(super) join_policy: JoinPolicy,
+    /// Seconds each member waits between messages; off when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) slow_mode_secs: Option<u64>,
+    /// Replaces `rate_limits.max_message_bytes` in the room.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) max_message_bytes: Option<usize>,
+    /// Replaces `rate_limits.max_metadata_bytes` in the room.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) max_metadata_bytes: Option<usize>,
+}
+
+impl RoomSettings {
+    /// Settings stored in `record`, or the defaults when the document no
+    /// longer parses.
+    pub(super) fn from_record(record: RoomSettingsRecord) -> Self {
+        serde_json::from_value(record.settings).unwrap_or_else(|err| {
+            tracing::warn!(
+                room_id = %record.room_id,
+                "Ignoring unreadable room settings: {}",
+                err
+            );
+            Self::default()
+        })
+    }
+
+    fn to_record(&self, room_id: &str) -> RoomSettingsRecord {
+        RoomSettingsRecord {
+            room_id: room_id.to_string(),
+            settings: serde_json::to_value(self).unwrap_or_default(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// The room's settings, or the defaults when it has none.
+pub(super) async fn room_settings(state: &SharedState, room_id: &str) -> RoomSettings {
+    state
+        .room_settings
+        .read()
+        .await
+        .get(room_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Models AI requests in a room with `settings`, owned by `tenant`, may
+/// pick: the room's `aiModels`, or else the configured allow-list. Any
+/// model is allowed when this is empty.
+fn allowed_models<'a>(
+    state: &'a SharedState,
+    settings: &'a RoomSettings,
+    tenant: Option<&str>,
+) -> &'a [String] {
+    if settings.ai_models.is_empty() {
+        state.config.providers.allowed_models(tenant)
+    } else {
+        &settings.ai_models
+    }
+}
+
+/// Whether AI requests in a room with `settings` may pick `model`.
+pub(super) fn model_allowed(
+    state: &SharedState,
+    settings: &RoomSettings,
+    tenant: Option<&str>,
+    model: &str,
+) -> bool {
+    let allowed = allowed_models(state, settings, tenant);
+    allowed.is_empty() || allowed.iter().any(|allowed| allowed == model)
+}
+
+/// Apply `change` to the room's settings and persist them before they
+/// take effect.
+pub(super) async fn update_settings(
+    state: &SharedState,
+    room_id: &str,
+    change: impl FnOnce(&mut RoomSettings),
+) -> Result<RoomSettings, RepositoryError> {
+    let mut all = state.room_settings.write().await;
+    let mut settings = all.get(room_id).cloned().unwrap_or_default();
+    change(&mut settings);
+    state
+        .settings_store
+        .set(&settings.to_record(room_id))
+        .await?;
+    all.insert(room_id.to_string(), settings.clone());
+    Ok(settings)
+}
+
+/// Drop the settings of a deleted room.
+pub(super) async fn remove_room(state: &SharedState, room_id: &str) {
+    state.room_settings.write().await.remove(room_id);
+    if let Err(err) = state.settings_store.delete_room(room_id).await {
+        tracing::warn!("Failed to drop settings of room {}: {}", room_id, err);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RoomSettingsResponse {
+    room_id: String,
+    #[serde(flatten)]
+    settings: RoomSettings,
+    /// Message retention, enforced by the room's retention job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retention: Option<MessageRetention>,
+    /// Models AI requests in the room may pick, from `aiModels` or the
+    /// configured allow-list; any model when absent.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    allowed_models: Vec<String>,
+}
+
+/// Settings to change; absent fields stay as they are and `null` clears
+/// a field.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct UpdateRoomSettingsRequest {
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    ai_provider: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    ai_model: Option<Option<String>>,
+    /// An empty list falls back to the configured allow-list.
+    #[serde(default)]
+    ai_models: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<ModerationPolicy>)]
+    moderation: Option<Option<ModerationPolicy>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<MessageRetention>)]
+    retention: Option<Option<MessageRetention>>,
+    #[serde(default)]
+    join_policy: Option<JoinPolicy>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<u64>)]
+    slow_mode_secs: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<usize>)]
+    max_message_bytes: Option<Option<usize>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<usize>)]
+    max_metadata_bytes: Option<Option<usize>>,
+}
+
+/// Tell a field set to `null` (`Some(None)`) from an absent one (`None`).
+fn nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+impl UpdateRoomSettingsRequest {
+    /// Why the request is rejected, checked before anything changes.
+    fn validate(&self, state: &SharedState) -> Result<(), String> {
+        if let Some(Some(description)) = &self.description {
+            if description.chars().count() > MAX_DESCRIPTION_CHARS {
+                return Err(format!(
+                    "description must be at most {MAX_DESCRIPTION_CHARS} characters"
+                ));
+            }
+        }
+        if let Some(Some(provider)) = &self.ai_provider {
+            if state.ai_provider_named(Some(provider)).is_none() {
+                return Err(format!("unknown AI provider: {provider}"));
+            }
+        }
+        if let Some(Some(model)) = &self.ai_model {
+            if model.trim().is_empty() {
+                return Err("aiModel must not be empty".to_string());
+            }
+        }
+        if let Some(models) = &self.ai_models {
+            if models.iter().any(|model| model.trim().is_empty()) {
+                return Err("aiModels must not contain empty models".to_string());
+            }
+        }
+        if let Some(Some(secs)) = self.slow_mode_secs {
+            if secs == 0 || secs > MAX_SLOW_MODE_SECS {
+                return Err(format!(
+                    "slowModeSecs must be between 1 and {MAX_SLOW_MODE_SECS}"
+                ));
+            }
+        }
+        if let Some(Some(bytes)) = self.max_message_bytes {
+            if bytes == 0 || bytes > MAX_ROOM_MESSAGE_BYTES {
+                return Err(format!(
+                    "maxMessageBytes must be between 1 and {MAX_ROOM_MESSAGE_BYTES}"
+                ));
+            }
+        }
+        if let Some(Some(bytes)) = self.max_metadata_bytes {
+            if bytes == 0 || bytes > MAX_ROOM_METADATA_BYTES {
+                return Err(format!(
+                    "maxMetadataBytes must be between 1 and {MAX_ROOM_METADATA_BYTES}"
+                ));
+            }
+        }
+        if let Some(Some(retention)) = &self.retention {
+            if let Err(SchedulerError::InvalidSchedule(message)) = retention.validate() {
+                return Err(message);
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether any field stored in [`RoomSettings`] changes.
+    fn changes_settings(&self) -> bool {
+        self.description.is_some()
+            || self.ai_provider.is_some()
+            || self.ai_model.is_some()
+            || self.ai_models.is_some()
+            || self.moderation.is_some()
+            || self.join_policy.is_some()
+            || self.slow_mode_secs.is_some()
+            || self.max_message_bytes.is_some()
+            || self.max_metadata_bytes.is_some()
+    }
+
+    fn apply(self, settings: &mut RoomSettings) {
+        if let Some(description) = self.description {
+            settings.description = description;
+        }
+        if let Some(provider) = self.ai_provider {
+            settings.ai_provider = provider;
+        }
+        if let Some(model) = self.ai_model {
+            settings.ai_model = model;
+        }
+        if let Some(models) = self.ai_models {
+            settings.ai_models = models;
+        }
+        if let Some(moderation) = self.moderation {
+            settings.moderation = moderation;
+        }
+        if let Some(join_policy) = self.join_policy {
+            settings.join_policy = join_policy;
+        }
+        if let Some(slow_mode_secs) = self.slow_mode_secs {
+            settings.slow_mode_secs = slow_mode_secs;
+        }
+        if let Some(max_message_bytes) = self.max_message_bytes {
+            settings.max_message_bytes = max_message_bytes;
+        }
+        if let Some(max_metadata_bytes) = self.max_metadata_bytes {
+            settings.max_metadata_bytes = max_metadata_bytes;
+        }
+    }
+}
+
+async fn settings_response(state: &SharedState, room_id: String) -> RoomSettingsResponse {
+    let settings = room_settings(state, &room_id).await;
+    let tenant = state.room_tenant(&room_id).await;
+    RoomSettingsResponse {
+        allowed_models: allowed_models(state, &settings, tenant.as_deref()).to_vec(),
+        settings,
+        retention: schedules::room_retention(state, &room_id).await,
+        room_id,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/settings",
+    tag = "rooms",
+    summary = "The room's settings",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room's settings", body = RoomSettingsResponse),
+        (status = 403, description = "The caller is not a member of the room", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.get_room_settings", skip(state, user), fields(room_id = %id))]
+async fn get_room_settings(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        return response;
+    }
+    (StatusCode::OK, Json(settings_response(&state, id).await)).into_response()
+}
+
+#[utoipa::path(
+    patch,
+    path = "/v1/rooms/{id}/settings",
+    tag = "rooms",
+    summary = "Change the room's settings (admin only)",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = UpdateRoomSettingsRequest,
+    responses(
+        (status = 200, description = "The room's new settings", body = RoomSettingsResponse),
+        (status = 400, description = "Invalid settings", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.update_room_settings",
+    skip(state, user, payload),
+    fields(room_id = %id, member_id = %user.member_id)
+)]
+async fn update_room_settings(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateRoomSettingsRequest>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        return response;
+    }
+    if let Err(message) = payload.validate(&state) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(message)),
+        )
+            .into_response();
+    }
+
+    let retention = payload.retention.clone();
+    if payload.changes_settings() {
+        let result = update_settings(&state, &id, |settings| payload.apply(settings)).await;
+        let event = AuditEvent::new(
+            &user.member_id,
+            AuditAction::RoomSettingsChanged,
+            format!("room:{id}"),
+        );
+        let event = match &result {
+            Ok(_) => event,
+            Err(err) => event.with_result(AuditResult::Failed, err.to_string()),
+        };
+        state.audit.record(event).await;
+        if let Err(err) = result {
+            return storage_error_response(err);
+        }
+    }
+
+    let result = match &retention {
+        Some(Some(retention)) => {
+            schedules::replace_retention(&state, &id, retention, &user.member_id)
+                .await
+                .map(|_| ())
+        }
+        Some(None) => match schedules::remove_retention(&state, &id, &user.member_id).await {
+            Ok(_) | Err(SchedulerError::NotFound) => Ok(()),
+            Err(err) => Err(err),
+        },
+        None => Ok(()),
+    };
+    if let Err(err) = result {
+        return schedules::scheduler_error_response(err);
+    }
+
+    (StatusCode::OK, Json(settings_response(&state, id).await)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::NexisConfig;
+    use crate::db::Storage;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn room_settings_are_changed_by_admins_and_restored() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let config = Arc::new(config);
+        let storage = Storage::default();
+        let state = AppState {
+            config: config.clone(),
+            ..AppState::default()
+        }
+        .with_storage(storage.clone())
+        .with_ai_provider(Arc::new(EchoProvider));
+        let app = routes(state);
+
+        let room_id = new_room(&app, "admin", "ops").await;
+        let settings_uri = format!("/v1/rooms/{room_id}/settings");
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", &settings_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let settings = json_body(response).await;
+        assert_eq!(settings["joinPolicy"], "open");
+        assert!(settings.get("description").is_none());
+
+        let changes = json!({
+            "description": "Incident coordination",
+            "aiProvider": "echo",
+            "aiModel": "echo-large",
+            "moderation": { "action": "flag" },
+            "joinPolicy": "invite_only",
+            "retention": { "maxAgeSecs": 86400 },
+        });
+        let response = app
+            .clone()
+            .oneshot(request("alice", "PATCH", &settings_uri, changes.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        for invalid in [
+            json!({ "aiProvider": "missing" }),
+            json!({ "retention": {} }),
+            json!({ "color": "red" }),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request("admin", "PATCH", &settings_uri, invalid))
+                .await
+                .unwrap();
+            assert!(response.status().is_client_error());
+        }
+        let response = app
+            .clone()
+            .oneshot(request("admin", "PATCH", &settings_uri, changes))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let settings = json_body(response).await;
+        assert_eq!(settings["description"], "Incident coordination");
+        assert_eq!(settings["aiModel"], "echo-large");
+        assert_eq!(settings["joinPolicy"], "invite_only");
+        assert_eq!(settings["retention"]["maxAgeSecs"], 86400);
+        // The room is closed now, so outsiders can't read its settings.
+        let response = app
+            .clone()
+            .oneshot(request("alice", "GET", &settings_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "GET",
+                &format!("/v1/rooms/{room_id}/moderation"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        let moderation = json_body(response).await;
+        assert_eq!(moderation["action"], "flag");
+        assert_eq!(moderation["overridden"], true);
+
+        // `null` clears a field; absent fields are left alone.
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PATCH",
+                &settings_uri,
+                json!({ "description": null, "retention": null }),
+            ))
+            .await
+            .unwrap();
+        let settings = json_body(response).await;
+        assert!(settings.get("description").is_none());
+        assert!(settings.get("retention").is_none());
+        assert_eq!(settings["aiModel"], "echo-large");
+
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", "/v1/audit", Value::Null))
+            .await
+            .unwrap();
+        let actions: Vec<Value> = json_body(response).await["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|event| event["action"].clone())
+            .collect();
+        assert!(actions.contains(&json!("room.settings_changed")));
+        assert!(actions.contains(&json!("retention.changed")));
+
+        // A restarted gateway reads the settings back.
+        let restored = Storage {
+            restored_settings: storage.room_settings.list().await.unwrap(),
+            ..Storage::restore(storage.rooms.clone(), storage.messages.clone(), 10)
+                .await
+                .unwrap()
+        };
+        let app = routes(
+            AppState {
+                config,
+                ..AppState::default()
+            }
+            .with_storage(restored),
+        );
+        let response = app
+            .clone()
+            .oneshot(request("admin", "GET", &settings_uri, Value::Null))
+            .await
+            .unwrap();
+        let settings = json_body(response).await;
+        assert_eq!(settings["joinPolicy"], "invite_only");
+        assert_eq!(settings["moderation"]["action"], "flag");
+    }
+}
@@ -0,0 +1,321 @@
+//! Member signing keys and signed-message verification.
+//!
+//! Members register an Ed25519 public key under
+//! `/v1/members/:id/signing-key`. Once a key is registered, every message
+//! sent as that member must carry a signature that verifies against it.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::put,
+    Json, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use nexis_protocol::signing::{decode_verifying_key, encode_verifying_key};
+use nexis_protocol::{MemberId, Message, MessageContent, RoomId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{ErrorResponse, SendMessageRequest, SharedState};
+use crate::audit::{AuditAction, AuditEvent};
+use crate::auth::AuthenticatedUser;
+
+/// How far a signed message's `createdAt` may drift from server time.
+const MAX_CLOCK_SKEW_SECS: i64 = 300;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new().route(
+        "/v1/members/:id/signing-key",
+        put(register_signing_key).get(get_signing_key),
+    )
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct SigningKeyBody {
+    public_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct SigningKeyResponse {
+    member_id: String,
+    public_key: String,
+}
+
+/// Identity of a message whose signature checked out.
+pub(super) struct SignedMessage {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/members/{id}/signing-key",
+    tag = "members",
+    summary = "Register the Ed25519 key a member signs messages with",
+    params(("id" = String, Path, description = "Member id")),
+    request_body = SigningKeyBody,
+    responses(
+        (status = 204, description = "Key registered"),
+        (status = 400, description = "Not a valid Ed25519 public key", body = ErrorResponse),
+        (status = 403, description = "The caller is not this member", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.register_signing_key",
+    skip(state, user, payload),
+    fields(member_id = %id)
+)]
+async fn register_signing_key(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(payload): Json<SigningKeyBody>,
+) -> Response {
+    if user.member_id != id && !state.config.auth.is_admin(&user.member_id) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::forbidden(
+                "members can only register their own signing key",
+            )),
+        )
+            .into_response();
+    }
+    let key = match decode_verifying_key(&payload.public_key) {
+        Ok(key) => key,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request(err.to_string())),
+            )
+                .into_response();
+        }
+    };
+
+    state.signing_keys.write().await.insert(id.clone(), key);
+    state
+        .audit
+        .record(AuditEvent::new(
+            &user.member_id,
+            AuditAction::SigningKeyRegistered,
+            format!("member:{id}"),
+        ))
+        .await;
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/members/{id}/signing-key",
+    tag = "members",
+    summary = "Get a member's registered signing key",
+    params(("id" = String, Path, description = "Member id")),
+    responses(
+        (status = 200, description = "The member's public key", body = SigningKeyResponse),
+        (status = 404, description = "No key registered", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.get_signing_key", skip(state, _user), fields(member_id = %id))]
+async fn get_signing_key(
+    State(state): State<SharedState>,
+    _user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    match state.signing_keys.read().await.get(&id) {
+        Some(key) => (
+            StatusCode::OK,
+            Json(SigningKeyResponse {
+                public_key: encode_verifying_key(key),
+                member_id: id,
+            }),
+        )
+            .into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("no signing key registered")),
+        )
+            .into_response(),
+    }
+}
+
+fn signature_rejected(message: impl Into<String>) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(ErrorResponse::invalid_signature(message)),
+    )
+        .into_response()
+}
+
+fn signature_malformed(message: impl Into<String>) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::bad_request(message)),
+    )
+        .into_response()
+}
+
+/// Check `payload` against the sender's registered key.
+///
+/// Returns `Ok(None)` for unsigned messages from members without a key and
+/// the client-assigned identity for messages whose signature verifies.
+pub(super) async fn verify_message_signature(
+    state: &SharedState,
+    payload: &SendMessageRequest,
+) -> Result<Option<SignedMessage>, Response> {
+    let key = state
+        .signing_keys
+        .read()
+        .await
+        .get(&payload.sender)
+        .copied();
+    let (key, signature) = match (key, &payload.signature) {
+        (None, None) => return Ok(None),
+        (None, Some(_)) => return Err(signature_rejected("sender has no registered signing key")),
+        (Some(_), None) => {
+            return Err(signature_rejected(format!(
+                "messages from {} must be signed",
+                payload.sender
+            )))
+        }
+        (Some(key), Some(signature)) => (key, signature),
+    };
+
+    let (Some(id), Some(created_at)) = (payload.id.clone(), payload.created_at) else {
+        return Err(signature_malformed(
+            "signed messages require id and createdAt",
+        ));
+    };
+    if (Utc::now() - created_at).abs() > Duration::seconds(MAX_CLOCK_SKEW_SECS) {
+        return Err(signature_malformed("createdAt is too far from server time"));
+    }
+    let Ok(sender) = payload.sender.parse::<MemberId>() else {
+        return Err(signature_malformed("sender is not a valid member id"));
+    };
+    let Ok(room_id) = payload.room_id.parse::<RoomId>() else {
+        return Err(signature_malformed("roomId is not a valid room id"));
+    };
+
+    let mut message = Message::new(
+        id.clone(),
+        room_id,
+        sender,
+        MessageContent::Text {
+            text: payload.text.clone(),
+        },
+        created_at,
+    );
+    message.reply_to = payload.reply_to.clone();
+    message.signature = Some(signature.clone());
+    message
+        .verify(&key)
+        .map_err(|err| signature_rejected(format!("invalid message signature: {err}")))?;
+
+    Ok(Some(SignedMessage { id, created_at }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::JwtConfig;
+    use crate::router::build_routes;
+    use crate::router::test_support::*;
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn registered_signing_key_requires_valid_signatures() {
+        use nexis_protocol::signing::{encode_verifying_key, SigningKey};
+
+        let app = build_routes();
+        let alice = "nexis:human:alice";
+        let token = JwtConfig::test_token(alice);
+        let key = SigningKey::from_bytes(&[7; 32]);
+
+        let response = app
+            .clone()
+            .oneshot(request_with_token(
+                &token,
+                "POST",
+                "/v1/rooms",
+                json!({ "name": "signed" }),
+            ))
+            .await
+            .unwrap();
+        let room_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let response = app
+            .clone()
+            .oneshot(request_with_token(
+                &token,
+                "PUT",
+                "/v1/members/nexis:human:bob/signing-key",
+                json!({ "publicKey": encode_verifying_key(&key.verifying_key()) }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request_with_token(
+                &token,
+                "PUT",
+                &format!("/v1/members/{alice}/signing-key"),
+                json!({ "publicKey": encode_verifying_key(&key.verifying_key()) }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = app
+            .clone()
+            .oneshot(request_with_token(
+                &token,
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": alice, "text": "unsigned" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let mut signed = nexis_protocol::Message::new(
+            "msg_signed_1".to_string(),
+            room_id.parse().unwrap(),
+            alice.parse().unwrap(),
+            nexis_protocol::MessageContent::Text {
+                text: "hello".to_string(),
+            },
+            Utc::now(),
+        );
+        signed.sign(&key);
+        let send_signed = |text: &str| {
+            request_with_token(
+                &token,
+                "POST",
+                "/v1/messages",
+                json!({
+                    "roomId": room_id,
+                    "sender": alice,
+                    "text": text,
+                    "id": signed.id,
+                    "createdAt": signed.created_at,
+                    "signature": signed.signature,
+                }),
+            )
+        };
+
+        let response = app.clone().oneshot(send_signed("tampered")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.clone().oneshot(send_signed("hello")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(json_body(response).await["id"], "msg_signed_1");
+        let response = app.clone().oneshot(send_signed("hello")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+}
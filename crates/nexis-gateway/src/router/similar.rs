@@ -0,0 +1,292 @@
+//! More-like-this lookups: `GET /v1/messages/:id/similar`.
+//!
+//! `id` is either an indexed document id, as returned by `/v1/search`, whose
+//! stored vector is reused, or a gateway message id, whose text is embedded.
+//! The source message and exact copies of it are left out of the results.
+
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use nexis_protocol::RoomId;
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use super::{
+    caller_tenant, default_limit, error_codes, find_visible_message, result_tenant,
+    search_permissions, ErrorResponse, SearchApiResponse, SearchResultItem, SharedState,
+};
+use crate::auth::AuthenticatedUser;
+use crate::metrics::record_search;
+use crate::search::{SearchError, SimilarRequest};
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new().route("/v1/messages/:id/similar", get(similar_messages))
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SimilarQueryParams {
+    #[serde(default = "default_limit")]
+    limit: usize,
+    #[serde(default)]
+    min_score: Option<f32>,
+    #[serde(default)]
+    room_id: Option<String>,
+}
+
+/// Text of a stored message in a room the caller takes part in.
+async fn message_text(state: &SharedState, user: &AuthenticatedUser, id: &str) -> Option<String> {
+    find_visible_message(state, user, id)
+        .await
+        .map(|(_, message)| message.text)
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/messages/{id}/similar",
+    tag = "search",
+    summary = "Find messages semantically similar to a stored message",
+    params(("id" = String, Path, description = "Message id"), SimilarQueryParams),
+    responses(
+        (status = 200, description = "Similar messages, best first", body = SearchApiResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+        (status = 404, description = "Message not found", body = ErrorResponse),
+        (status = 503, description = "Semantic search is unavailable", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.similar_messages",
+    skip(state, user, params),
+    fields(message_id = %id, limit = params.limit)
+)]
+async fn similar_messages(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Query(params): Query<SimilarQueryParams>,
+) -> Response {
+    let Some(search_service) = state.search_service.as_ref() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: "Search service not configured".to_string(),
+                code: Some(error_codes::SEARCH_UNAVAILABLE),
+            }),
+        )
+            .into_response();
+    };
+
+    let mut request = match Uuid::parse_str(&id) {
+        Ok(document_id) => SimilarRequest::for_document(document_id),
+        Err(_) => match message_text(&state, &user, &id).await {
+            Some(text) if !text.trim().is_empty() => SimilarRequest::for_text(text),
+            _ => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(ErrorResponse::not_found("message not found")),
+                )
+                    .into_response()
+            }
+        },
+    }
+    .with_limit(params.limit)
+    .with_permissions(search_permissions(&state, &user).await);
+
+    if let Some(min_score) = params.min_score {
+        request = request.with_min_score(min_score);
+    }
+    if let Some(room_id) = params.room_id.as_deref() {
+        let Ok(room_id) = room_id.parse::<RoomId>() else {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request("room_id is not a valid room id")),
+            )
+                .into_response();
+        };
+        request = request.in_room(room_id);
+    }
+    if let Some(tenant_id) = caller_tenant(&user) {
+        request = request.for_tenant(tenant_id);
+    }
+
+    let search_started = Instant::now();
+    let result = search_service.similar(request).await;
+    record_search(
+        search_started,
+        !matches!(
+            result,
+            Err(SearchError::EmbeddingError(_)
+                | SearchError::VectorError(_)
+                | SearchError::RerankError(_)
+                | SearchError::ExpansionError(_))
+        ),
+    );
+    match result {
+        Ok(response) => {
+            let results: Vec<SearchResultItem> = response
+                .results
+                .into_iter()
+                .filter(|r| result_tenant(r) == caller_tenant(&user))
+                .filter_map(|r| {
+                    r.content.map(|content| SearchResultItem {
+                        id: r.id,
+                        score: r.score,
+                        rerank_score: r.rerank_score,
+                        content,
+                        room_id: r.room_id,
+                    })
+                })
+                .collect();
+            let total = results.len();
+            (
+                StatusCode::OK,
+                Json(SearchApiResponse {
+                    query: response.query,
+                    results,
+                    total,
+                    expanded_terms: Vec::new(),
+                }),
+            )
+                .into_response()
+        }
+        Err(SearchError::NotFound(_)) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found("message not found")),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::from(e)),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::build_routes_with_search;
+    use crate::router::test_support::*;
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn similar_messages_excludes_source_and_hides_other_rooms() {
+        use crate::search::SemanticSearchService;
+        use nexis_vector::prelude::*;
+        use nexis_vector::InMemoryVectorStore;
+
+        let store = Arc::new(InMemoryVectorStore::new(4));
+        let search = SemanticSearchService::new(
+            store.clone(),
+            Arc::new(nexis_runtime::MockEmbeddingProvider::new(4)),
+        );
+        let app = build_routes_with_search(Arc::new(search));
+        let call = |uri: String| request("alice", "GET", &uri, json!({}));
+        let room_id = new_room(&app, "alice", "ops").await;
+        let room = room_id.parse::<RoomId>().unwrap();
+        let mut ids = Vec::new();
+        for (content, room, vector) in [
+            ("deploy friday", room.clone(), [1.0, 0.0, 0.0, 0.0]),
+            ("release monday", room, [0.9, 0.1, 0.0, 0.0]),
+            ("deploy keys", RoomId::generate(), [0.95, 0.05, 0.0, 0.0]),
+        ] {
+            ids.push(
+                store
+                    .upsert(Document::new(
+                        Vector::new(vector.to_vec()),
+                        content.to_string(),
+                        DocumentMetadata::new().with_room(room),
+                    ))
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let response = app
+            .clone()
+            .oneshot(call(format!(
+                "/v1/messages/{}/similar?min_score=0.5&room_id={room_id}",
+                ids[0]
+            )))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let similar = json_body(response).await;
+        assert_eq!(similar["query"], "deploy friday");
+        assert_eq!(similar["total"], 1);
+        assert_eq!(similar["results"][0]["content"], "release monday");
+
+        let response = app
+            .clone()
+            .oneshot(call(format!("/v1/messages/{}/similar", ids[2])))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app
+            .oneshot(call("/v1/messages/msg_missing/similar".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn similar_messages_reads_seed_messages_only_for_room_members() {
+        use crate::config::NexisConfig;
+        use crate::router::{routes, AppState};
+        use crate::search::SemanticSearchService;
+        use nexis_vector::InMemoryVectorStore;
+
+        let search = SemanticSearchService::new(
+            Arc::new(InMemoryVectorStore::new(4)),
+            Arc::new(nexis_runtime::MockEmbeddingProvider::new(4)),
+        );
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["nexis:human:admin".to_string()];
+        let app = routes(
+            AppState {
+                config: Arc::new(config),
+                ..AppState::default()
+            }
+            .with_search_service(Arc::new(search)),
+        );
+        let room_id = invite_only_room(&app, "nexis:human:admin", "legal").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:admin",
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": "nexis:human:admin", "text": "settlement terms" }),
+            ))
+            .await
+            .unwrap();
+        let message_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let uri = format!("/v1/messages/{message_id}/similar");
+
+        let response = app
+            .clone()
+            .oneshot(request("alice", "GET", &uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app
+            .oneshot(request("nexis:human:admin", "GET", &uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["query"], "settlement terms");
+    }
+}
@@ -0,0 +1,293 @@
+//! Slow mode: a per-member pause between messages in a room.
+//!
+//! With `slowModeSecs` set in a room's settings, each member may send one
+//! message per that many seconds there. A message sent sooner is refused
+//! with `429 SLOW_MODE`, a `Retry-After` header and the seconds left in
+//! `retryAfterSecs`. Only messages that are stored start a pause, so one
+//! refused for its size or by moderation can be fixed and resent at once.
+//! Admins moderate rooms and are never held back. The time of each member's
+//! last message is kept in memory.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use super::settings::room_settings;
+use super::{error_codes, SharedState};
+use crate::auth::AuthenticatedUser;
+
+/// Longest pause slow mode can impose, in seconds.
+pub(super) const MAX_SLOW_MODE_SECS: u64 = 6 * 3_600;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlowModeErrorResponse {
+    error: String,
+    code: &'static str,
+    /// Seconds until the member may send again.
+    retry_after_secs: u64,
+}
+
+/// When members last sent to rooms, keyed by room and member.
+type LastSent = Arc<Mutex<HashMap<(String, String), Instant>>>;
+
+/// A member's claim on their next message in a slow-mode room, taken before
+/// the message is checked. Dropping it hands the slot back, so only messages
+/// that are [kept](Reservation::keep) start a pause.
+pub(super) struct Reservation {
+    last_sent: LastSent,
+    key: (String, String),
+    reserved_at: Instant,
+    previous: Option<Instant>,
+    kept: bool,
+}
+
+impl Reservation {
+    /// Start the member's pause: their message was stored.
+    pub(super) fn keep(mut self) {
+        self.kept = true;
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if self.kept {
+            return;
+        }
+        let mut last_sent = self.last_sent.lock().expect("slow mode lock poisoned");
+        // Leave the entry alone if a later message has claimed it since.
+        if last_sent.get(&self.key) != Some(&self.reserved_at) {
+            return;
+        }
+        match self.previous {
+            Some(previous) => last_sent.insert(self.key.clone(), previous),
+            None => last_sent.remove(&self.key),
+        };
+    }
+}
+
+/// Refuse `user` sending to the room while its slow mode still holds them
+/// back, with the `429` response to return; otherwise claim their next
+/// message there in the same step, so concurrent sends can't both slip
+/// through. `None` when slow mode doesn't apply to them.
+pub(super) async fn reserve(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    room_id: &str,
+) -> Result<Option<Reservation>, Response> {
+    let Some(secs) = room_settings(state, room_id).await.slow_mode_secs else {
+        return Ok(None);
+    };
+    if state.config.auth.is_admin(&user.member_id) {
+        return Ok(None);
+    }
+    let pause = Duration::from_secs(secs);
+    let key = (room_id.to_string(), user.member_id.clone());
+    let now = Instant::now();
+    let mut last_sent = state.last_sent.lock().expect("slow mode lock poisoned");
+    let previous = last_sent.get(&key).copied();
+    if let Some(remaining) = previous
+        .and_then(|sent| (sent + pause).checked_duration_since(now))
+        .filter(|remaining| !remaining.is_zero())
+    {
+        return Err(slow_mode_response(secs, remaining));
+    }
+    last_sent.insert(key.clone(), now);
+    Ok(Some(Reservation {
+        last_sent: state.last_sent.clone(),
+        key,
+        reserved_at: now,
+        previous,
+        kept: false,
+    }))
+}
+
+/// Forget when members last sent to a deleted room.
+pub(super) async fn remove_room(state: &SharedState, room_id: &str) {
+    state
+        .last_sent
+        .lock()
+        .expect("slow mode lock poisoned")
+        .retain(|(room, _), _| room != room_id);
+}
+
+fn slow_mode_response(secs: u64, remaining: Duration) -> Response {
+    // Round up so clients that wait this long are let through.
+    let retry_after_secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    let response = SlowModeErrorResponse {
+        error: format!(
+            "slow mode is on: one message every {secs}s; try again in {retry_after_secs}s"
+        ),
+        code: error_codes::SLOW_MODE,
+        retry_after_secs,
+    };
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after_secs.to_string())],
+        Json(response),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn waits_are_rounded_up_to_whole_seconds() {
+        let response = slow_mode_response(30, Duration::from_millis(12_300));
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "13");
+        let body = json_body(response).await;
+        assert_eq!(body["code"], "SLOW_MODE");
+        assert_eq!(body["retryAfterSecs"], 13);
+    }
+
+    #[tokio::test]
+    async fn slow_mode_spaces_out_messages_from_non_admins() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["nexis:human:admin".to_string()];
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let app = routes(state);
+
+        let room_id = new_room(&app, "nexis:human:admin", "ops").await;
+        let settings_uri = format!("/v1/rooms/{room_id}/settings");
+        let send = |member: &str| {
+            let body = json!({ "roomId": room_id, "sender": member, "text": "hi" });
+            app.clone()
+                .oneshot(request(member, "POST", "/v1/messages", body))
+        };
+        let set_slow_mode = |secs: Value| {
+            let body = json!({ "slowModeSecs": secs });
+            app.clone()
+                .oneshot(request("nexis:human:admin", "PATCH", &settings_uri, body))
+        };
+
+        let response = set_slow_mode(json!(0)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = set_slow_mode(json!(60)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["slowModeSecs"], 60);
+
+        assert_eq!(
+            send("nexis:human:alice").await.unwrap().status(),
+            StatusCode::CREATED
+        );
+        let response = send("nexis:human:alice").await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()["retry-after"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=60).contains(&retry_after));
+        let rejected = json_body(response).await;
+        assert_eq!(rejected["code"], "SLOW_MODE");
+        assert_eq!(rejected["retryAfterSecs"], retry_after);
+
+        // Other members and admins aren't held back by alice's pause.
+        assert_eq!(
+            send("nexis:human:bob").await.unwrap().status(),
+            StatusCode::CREATED
+        );
+        assert_eq!(
+            send("nexis:human:admin").await.unwrap().status(),
+            StatusCode::CREATED
+        );
+        assert_eq!(
+            send("nexis:human:admin").await.unwrap().status(),
+            StatusCode::CREATED
+        );
+
+        let response = set_slow_mode(json!(null)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            send("nexis:human:alice").await.unwrap().status(),
+            StatusCode::CREATED
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_messages_do_not_start_the_pause() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let room_id = new_room(&app, "admin", "ops").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PATCH",
+                &format!("/v1/rooms/{room_id}/settings"),
+                json!({ "slowModeSecs": 60, "maxMessageBytes": 16 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let send = |text: &str| {
+            let body = json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": text });
+            app.clone()
+                .oneshot(request("nexis:human:alice", "POST", "/v1/messages", body))
+        };
+
+        let response = send("far too long for this room").await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(send("short").await.unwrap().status(), StatusCode::CREATED);
+        let response = send("short").await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn concurrent_sends_share_one_slot() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let room_id = new_room(&app, "admin", "ops").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PATCH",
+                &format!("/v1/rooms/{room_id}/settings"),
+                json!({ "slowModeSecs": 60 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let send = || {
+            let body = json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": "hi" });
+            app.clone()
+                .oneshot(request("nexis:human:alice", "POST", "/v1/messages", body))
+        };
+
+        let sent = futures::future::join_all((0..8).map(|_| send())).await;
+        let created = sent
+            .iter()
+            .filter(|response| response.as_ref().unwrap().status() == StatusCode::CREATED)
+            .count();
+        assert_eq!(created, 1);
+    }
+}
@@ -0,0 +1,209 @@
+//! Screening messages with the spam guard.
+//!
+//! With `[spam] enabled`, each message a member sends or transcribes, and
+//! each stream they open, is weighed by the
+//! [`SpamGuard`](crate::spam::SpamGuard) before it is stored. Signals it
+//! trips are counted in `nexis_spam_detections_total` and audited as
+//! `message.spam_detected`, and the message still goes through. Once a
+//! member's strikes reach `mute_score`, they are muted in the room for
+//! `mute_secs` by [`SPAM_GUARD_MEMBER_ID`] and the message is refused like
+//! any other from a muted member. Admins are never screened.
+
+use std::time::Instant;
+
+use axum::response::Response;
+
+use super::{sanctions, SharedState};
+use crate::audit::{AuditAction, AuditEvent, AuditResult};
+use crate::auth::AuthenticatedUser;
+use crate::metrics;
+
+/// Member automatic mutes are attributed to.
+pub(super) const SPAM_GUARD_MEMBER_ID: &str = "nexis:system:spam-guard";
+
+/// Weigh `text` from `user` to the room, muting them when it tips their
+/// score over; the `403 MEMBER_MUTED` response to return then.
+pub(super) async fn screen(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    room_id: &str,
+    text: &str,
+) -> Result<(), Response> {
+    let config = &state.config.spam;
+    if !config.enabled || state.config.auth.is_admin(&user.member_id) {
+        return Ok(());
+    }
+    let verdict = state
+        .spam
+        .check(config, room_id, &user.member_id, text, Instant::now());
+    if verdict.signals.is_empty() {
+        return Ok(());
+    }
+
+    let signals = verdict
+        .signals
+        .iter()
+        .map(|signal| signal.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+    for signal in &verdict.signals {
+        metrics::record_spam_detection(signal.as_str());
+    }
+    tracing::info!(
+        room_id,
+        member_id = %user.member_id,
+        signals = %signals,
+        score = verdict.score,
+        "Spam detected"
+    );
+    let result = if verdict.mute {
+        AuditResult::Denied
+    } else {
+        AuditResult::Success
+    };
+    state
+        .audit
+        .record(
+            AuditEvent::new(
+                &user.member_id,
+                AuditAction::SpamDetected,
+                format!("room:{room_id}"),
+            )
+            .with_result(result, format!("{signals} (score {})", verdict.score)),
+        )
+        .await;
+    if !verdict.mute {
+        return Ok(());
+    }
+
+    metrics::record_spam_mute();
+    sanctions::mute_for(
+        state,
+        room_id,
+        &user.member_id,
+        SPAM_GUARD_MEMBER_ID,
+        config.mute_secs,
+        format!("automatic mute for spam: {signals}"),
+    )
+    .await;
+    sanctions::check_can_send(state, room_id, &user.member_id).await
+}
+
+/// Forget what members sent to a deleted room.
+pub(super) fn remove_room(state: &SharedState, room_id: &str) {
+    state.spam.remove_room(room_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditQuery;
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn spam_guard_mutes_members_who_keep_repeating_themselves() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        config.spam.enabled = true;
+        config.spam.max_repeats = 1;
+        config.spam.mute_score = 2;
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        };
+        let app = routes(state.clone());
+
+        let room_id = new_room(&app, "admin", "deals").await;
+        let send = |member: &str, text: &str| {
+            let body = json!({ "roomId": room_id, "sender": "nexis:ai:bot", "text": text });
+            app.clone()
+                .oneshot(request(member, "POST", "/v1/messages", body))
+        };
+
+        // Admins are never screened.
+        for _ in 0..3 {
+            let response = send("admin", "buy now").await.unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+        // The first repeat is a strike; the second tips the bot into a mute.
+        for _ in 0..2 {
+            let response = send("nexis:ai:bot", "buy now").await.unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+        let response = send("nexis:ai:bot", "buy now").await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(json_body(response).await["code"], "MEMBER_MUTED");
+        let response = send("nexis:ai:bot", "something else").await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "GET",
+                &format!("/v1/rooms/{room_id}/sanctions"),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        let listed = json_body(response).await;
+        assert_eq!(listed["sanctions"][0]["memberId"], "nexis:ai:bot");
+        assert_eq!(listed["sanctions"][0]["kind"], "mute");
+        assert_eq!(listed["sanctions"][0]["createdBy"], SPAM_GUARD_MEMBER_ID);
+        assert!(listed["sanctions"][0]["expiresAt"].is_string());
+
+        let detections = state
+            .audit
+            .query(&AuditQuery {
+                action: Some(AuditAction::SpamDetected),
+                ..AuditQuery::default()
+            })
+            .await
+            .events;
+        let mut detections: Vec<_> = detections
+            .iter()
+            .map(|event| {
+                assert_eq!(event.member_id, "nexis:ai:bot");
+                (event.reason.clone().unwrap_or_default(), event.result)
+            })
+            .collect();
+        detections.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            detections,
+            [
+                ("repeat (score 1)".to_string(), AuditResult::Success),
+                ("repeat (score 2)".to_string(), AuditResult::Denied),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn spam_guard_counts_opened_streams_towards_bursts() {
+        let mut config = NexisConfig::default();
+        config.spam.enabled = true;
+        config.spam.burst_messages = 1;
+        config.spam.mute_score = 1;
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+
+        let room_id = new_room(&app, "alice", "live").await;
+        let open = || {
+            let uri = format!("/v1/rooms/{room_id}/streams");
+            app.clone()
+                .oneshot(request("alice", "POST", &uri, Value::Null))
+        };
+        let response = open().await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = open().await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(json_body(response).await["code"], "MEMBER_MUTED");
+    }
+}
@@ -0,0 +1,539 @@
+//! Messages streamed into a room while they are written.
+//!
+//! A member opens a stream, e.g. while an `@ai` reply is generated, which
+//! reserves the id of the message to come. Each appended chunk is pushed to
+//! WebSocket subscribers of the room as a `message_delta` event from the
+//! member but not stored; the finished text is sent with `POST /v1/messages`
+//! and `streamId`, and arrives as a `message` event with the reserved id.
+//! Opening and appending are held to the same membership and sanction
+//! checks as sending. Open streams are kept in memory and lapse when idle.
+
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{
+    ensure_room_access, error_codes, joins, message_limits, sanctions, spam, ErrorResponse,
+    RoomEvent, SharedState,
+};
+use crate::auth::AuthenticatedUser;
+
+/// Streams not appended to for this long are dropped.
+const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Streams a member may have open at once, across rooms.
+const MAX_OPEN_STREAMS_PER_MEMBER: usize = 4;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/rooms/:id/streams", post(open_stream))
+        .route("/v1/rooms/:id/streams/:stream_id", delete(abort_stream))
+        .route(
+            "/v1/rooms/:id/streams/:stream_id/append",
+            post(append_stream),
+        )
+}
+
+/// A message being streamed into a room.
+#[derive(Debug, Clone)]
+pub(super) struct MessageStream {
+    room_id: String,
+    /// Member that opened the stream and that the message is sent as; only
+    /// it may append or finish it.
+    sender: String,
+    /// Bytes appended so far, held to the room's message size limit.
+    bytes: usize,
+    updated_at: Instant,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct OpenStreamResponse {
+    /// Id of the message being streamed; pass it as `streamId` when sending
+    /// the finished message.
+    stream_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct AppendStreamRequest {
+    delta: String,
+}
+
+fn stream_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found("stream not found")),
+    )
+        .into_response()
+}
+
+/// Why the caller can't send the message of stream `stream_id` as `sender`
+/// in `room_id`, if it can't: streamed messages are sent as the member that
+/// opened the stream.
+pub(super) async fn check_finish(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    room_id: &str,
+    stream_id: &str,
+    sender: &str,
+) -> Result<(), &'static str> {
+    match state.streams.read().await.get(stream_id) {
+        Some(stream)
+            if stream.room_id == room_id
+                && stream.sender == user.member_id
+                && stream.updated_at.elapsed() < STREAM_IDLE_TIMEOUT =>
+        {
+            if stream.sender == sender {
+                Ok(())
+            } else {
+                Err("must be sent as the member that opened the stream")
+            }
+        }
+        _ => Err("no open stream with this id in the room"),
+    }
+}
+
+/// Close a stream once its message is sent.
+pub(super) async fn close(state: &SharedState, stream_id: &str) {
+    state.streams.write().await.remove(stream_id);
+}
+
+/// Drop the open streams of a deleted room.
+pub(super) async fn remove_room(state: &SharedState, room_id: &str) {
+    state
+        .streams
+        .write()
+        .await
+        .retain(|_, stream| stream.room_id != room_id);
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/streams",
+    tag = "messages",
+    summary = "Open a stream for a message that is still being written",
+    params(("id" = String, Path, description = "Room id")),
+    responses(
+        (status = 201, description = "Stream opened", body = OpenStreamResponse),
+        (status = 403, description = "The caller is not a member of the room, or is banned or muted in it", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+        (status = 429, description = "The caller has too many streams open", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.open_stream",
+    skip(state, user),
+    fields(room_id = %id, member_id = %user.member_id)
+)]
+async fn open_stream(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = check_can_stream(&state, &user, &id).await {
+        return response;
+    }
+    // Opening a stream counts towards the sender's burst; the finished text
+    // is screened when it is sent.
+    if let Err(response) = spam::screen(&state, &user, &id, "").await {
+        return response;
+    }
+
+    let stream_id = format!("msg_{}", Uuid::new_v4().simple());
+    let mut streams = state.streams.write().await;
+    streams.retain(|_, stream| stream.updated_at.elapsed() < STREAM_IDLE_TIMEOUT);
+    let open = streams
+        .values()
+        .filter(|stream| stream.sender == user.member_id)
+        .count();
+    if open >= MAX_OPEN_STREAMS_PER_MEMBER {
+        let response = ErrorResponse {
+            error: format!(
+                "at most {MAX_OPEN_STREAMS_PER_MEMBER} streams may be open at once; \
+                 finish or abandon one first"
+            ),
+            code: Some(error_codes::TOO_MANY_STREAMS),
+        };
+        return (StatusCode::TOO_MANY_REQUESTS, Json(response)).into_response();
+    }
+    streams.insert(
+        stream_id.clone(),
+        MessageStream {
+            room_id: id,
+            sender: user.member_id,
+            bytes: 0,
+            updated_at: Instant::now(),
+        },
+    );
+    (StatusCode::CREATED, Json(OpenStreamResponse { stream_id })).into_response()
+}
+
+/// Refuse `user` streaming into room `id` unless they could send to it.
+async fn check_can_stream(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    id: &str,
+) -> Result<(), Response> {
+    ensure_room_access(state, user, id).await?;
+    joins::ensure_participant(state, user, id).await?;
+    sanctions::check_can_send(state, id, &user.member_id).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/streams/{stream_id}/append",
+    tag = "messages",
+    summary = "Push the next chunk of a streamed message to the room",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("stream_id" = String, Path, description = "Stream id"),
+    ),
+    request_body = AppendStreamRequest,
+    responses(
+        (status = 202, description = "Chunk pushed to the room's subscribers"),
+        (status = 400, description = "The streamed message grew too long", body = ErrorResponse),
+        (status = 403, description = "The caller is no longer a member of the room, or is banned or muted in it", body = ErrorResponse),
+        (status = 404, description = "Room or stream not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.append_stream",
+    skip(state, user, payload),
+    fields(room_id = %id, stream_id = %stream_id)
+)]
+async fn append_stream(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, stream_id)): Path<(String, String)>,
+    Json(payload): Json<AppendStreamRequest>,
+) -> Response {
+    if let Err(response) = check_can_stream(&state, &user, &id).await {
+        return response;
+    }
+    let max_bytes = message_limits::room_limits(&state, &id)
+        .await
+        .max_message_bytes;
+    let sender = {
+        let mut streams = state.streams.write().await;
+        let Some(stream) = streams.get_mut(&stream_id).filter(|stream| {
+            stream.room_id == id
+                && stream.sender == user.member_id
+                && stream.updated_at.elapsed() < STREAM_IDLE_TIMEOUT
+        }) else {
+            return stream_not_found();
+        };
+        if stream.bytes + payload.delta.len() > max_bytes {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request(format!(
+                    "streamed message exceeds maximum length of {max_bytes} bytes"
+                ))),
+            )
+                .into_response();
+        }
+        stream.bytes += payload.delta.len();
+        stream.updated_at = Instant::now();
+        stream.sender.clone()
+    };
+    if !payload.delta.is_empty() {
+        state
+            .publish(RoomEvent::MessageDelta {
+                room_id: id,
+                message_id: stream_id,
+                sender,
+                delta: payload.delta,
+            })
+            .await;
+    }
+    StatusCode::ACCEPTED.into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/rooms/{id}/streams/{stream_id}",
+    tag = "messages",
+    summary = "Abandon a streamed message without sending it",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("stream_id" = String, Path, description = "Stream id"),
+    ),
+    responses(
+        (status = 204, description = "Stream closed"),
+        (status = 404, description = "Room or stream not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.abort_stream",
+    skip(state, user),
+    fields(room_id = %id, stream_id = %stream_id)
+)]
+async fn abort_stream(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, stream_id)): Path<(String, String)>,
+) -> Response {
+    let mut streams = state.streams.write().await;
+    match streams.get(&stream_id) {
+        Some(stream) if stream.room_id == id && stream.sender == user.member_id => {
+            streams.remove(&stream_id);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        _ => stream_not_found(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MAX_OPEN_STREAMS_PER_MEMBER;
+    use crate::auth::JwtConfig;
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    const ALICE: &str = "nexis:human:alice";
+
+    #[tokio::test]
+    async fn streamed_messages_reach_subscribers_chunk_by_chunk() {
+        let addr = serve(routes(AppState::default())).await;
+        let client = reqwest::Client::new();
+        let alice = JwtConfig::test_token("nexis:human:alice");
+        let bob = JwtConfig::test_token("bob");
+
+        let created: Value = client
+            .post(format!("http://{addr}/v1/rooms"))
+            .bearer_auth(&alice)
+            .json(&json!({ "name": "general" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let room_id = created["id"].as_str().unwrap().to_string();
+
+        let mut ws = connect_ws(addr, Some("bob")).await;
+        send_json(
+            &mut ws,
+            json!({ "type": "subscribe", "roomId": room_id.clone() }),
+        )
+        .await;
+        assert_eq!(next_json(&mut ws).await["type"], "subscribed");
+
+        let opened: Value = client
+            .post(format!("http://{addr}/v1/rooms/{room_id}/streams"))
+            .bearer_auth(&alice)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let stream_id = opened["streamId"].as_str().unwrap().to_string();
+        let append_uri = format!("http://{addr}/v1/rooms/{room_id}/streams/{stream_id}/append");
+        for delta in ["Hello", " world"] {
+            let response = client
+                .post(&append_uri)
+                .bearer_auth(&alice)
+                .json(&json!({ "delta": delta }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+            let event = next_json(&mut ws).await;
+            assert_eq!(event["type"], "message_delta");
+            assert_eq!(event["messageId"], stream_id.as_str());
+            assert_eq!(event["sender"], "nexis:human:alice");
+            assert_eq!(event["delta"], delta);
+        }
+
+        // Only the member that opened the stream may use it, and the
+        // message is sent as them.
+        let response = client
+            .post(&append_uri)
+            .bearer_auth(&bob)
+            .json(&json!({ "delta": "!" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        let finish = |token: &str, sender: &str| {
+            client
+                .post(format!("http://{addr}/v1/messages"))
+                .bearer_auth(token)
+                .json(&json!({
+                    "roomId": room_id.clone(),
+                    "sender": sender,
+                    "text": "Hello world",
+                    "streamId": stream_id.clone(),
+                }))
+                .send()
+        };
+        let response = finish(&alice, "nexis:ai:assistant").await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+        let response = finish(&bob, "nexis:human:alice").await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = finish(&alice, "nexis:human:alice").await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::CREATED);
+        let sent: Value = response.json().await.unwrap();
+        assert_eq!(sent["id"], stream_id.as_str());
+        let event = next_json(&mut ws).await;
+        assert_eq!(event["type"], "message");
+        assert_eq!(event["message"]["id"], stream_id.as_str());
+        assert_eq!(event["message"]["sender"], "nexis:human:alice");
+        assert_eq!(event["message"]["text"], "Hello world");
+
+        // The stream closes with its message.
+        let response = finish(&alice, "nexis:human:alice").await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn streams_are_held_to_the_checks_on_sending() {
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let open = |room_id: &str| {
+            request(
+                ALICE,
+                "POST",
+                &format!("/v1/rooms/{room_id}/streams"),
+                Value::Null,
+            )
+        };
+
+        let private = invite_only_room(&app, "admin", "private").await;
+        let response = app.clone().oneshot(open(&private)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let room_id = new_room(&app, "admin", "general").await;
+        let response = app.clone().oneshot(open(&room_id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let stream_id = json_body(response).await["streamId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &format!("/v1/rooms/{room_id}/members/{ALICE}/mute"),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                ALICE,
+                "POST",
+                &format!("/v1/rooms/{room_id}/streams/{stream_id}/append"),
+                json!({ "delta": "still here" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(json_body(response).await["code"], "MEMBER_MUTED");
+        let response = app.oneshot(open(&room_id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn members_may_only_hold_a_few_streams_open() {
+        let app = routes(AppState::default());
+        let room_id = new_room(&app, ALICE, "general").await;
+        let uri = format!("/v1/rooms/{room_id}/streams");
+        let open = |member: &str| request(member, "POST", &uri, Value::Null);
+
+        let mut stream_ids = Vec::new();
+        for _ in 0..MAX_OPEN_STREAMS_PER_MEMBER {
+            let response = app.clone().oneshot(open(ALICE)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+            stream_ids.push(json_body(response).await["streamId"].clone());
+        }
+        let response = app.clone().oneshot(open(ALICE)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(json_body(response).await["code"], "TOO_MANY_STREAMS");
+        let response = app.clone().oneshot(open("bob")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                ALICE,
+                "DELETE",
+                &format!("{uri}/{}", stream_ids[0].as_str().unwrap()),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app.oneshot(open(ALICE)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn streams_whose_text_is_rejected_are_closed() {
+        let mut config = NexisConfig::default();
+        config.moderation.provider = crate::config::ModerationProviderKind::Keywords;
+        config.moderation.action = crate::config::ModerationAction::Reject;
+        config.moderation.rules = vec![crate::config::KeywordRule {
+            category: "spam".to_string(),
+            pattern: r"(?i)\bbuy now\b".to_string(),
+        }];
+        let app = routes(AppState {
+            moderation: crate::moderation::from_config(&config)
+                .unwrap()
+                .map(Arc::from),
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+        let room_id = new_room(&app, ALICE, "deals").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                ALICE,
+                "POST",
+                &format!("/v1/rooms/{room_id}/streams"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        let stream_id = json_body(response).await["streamId"].clone();
+        let finish = |text: &str| {
+            request(
+                ALICE,
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": ALICE, "text": text, "streamId": stream_id }),
+            )
+        };
+
+        let response = app.clone().oneshot(finish("BUY NOW!")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(response).await["code"], "CONTENT_REJECTED");
+        let response = app.oneshot(finish("never mind")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(response).await["details"][0]["field"], "streamId");
+    }
+}
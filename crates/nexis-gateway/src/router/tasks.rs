@@ -0,0 +1,644 @@
+//! Task delegation between room members (NIP-004).
+//!
+//! Any member of a room can open a task there, optionally assigning it to a
+//! member or requiring capabilities. A member whose directory profile
+//! declares those capabilities claims the task and later completes or fails
+//! it; the requester or an admin may cancel it until then. Every change is
+//! pushed to WebSocket subscribers of the room as a `task` event. Members
+//! banned from or muted in the room can't open or change tasks there. Tasks
+//! are kept in memory only.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use nexis_protocol::{
+    Capabilities, DelegatedTask, MemberId, RoomId, TaskRequest, TaskResult, TaskStatus,
+    TaskTransitionError,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::{ensure_room_access, joins, sanctions, ErrorResponse, RoomEvent, SharedState};
+use crate::auth::AuthenticatedUser;
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/rooms/:id/tasks", get(list_tasks).post(create_task))
+        .route("/v1/rooms/:id/tasks/:task_id", get(get_task))
+        .route("/v1/rooms/:id/tasks/:task_id/claim", post(claim_task))
+        .route("/v1/rooms/:id/tasks/:task_id/complete", post(complete_task))
+        .route("/v1/rooms/:id/tasks/:task_id/cancel", post(cancel_task))
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListTasksQuery {
+    status: Option<TaskStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct ListTasksResponse {
+    tasks: Vec<DelegatedTask>,
+    total: usize,
+}
+
+fn bad_request(message: impl Into<String>) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::bad_request(message)),
+    )
+        .into_response()
+}
+
+fn task_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found("task not found")),
+    )
+        .into_response()
+}
+
+fn transition_error_response(err: TaskTransitionError) -> Response {
+    match err {
+        TaskTransitionError::NotOpen(_)
+        | TaskTransitionError::NotClaimed(_)
+        | TaskTransitionError::Finished(_) => (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::conflict(err.to_string())),
+        )
+            .into_response(),
+        TaskTransitionError::NotAssignee(_)
+        | TaskTransitionError::NotClaimant(_)
+        | TaskTransitionError::MissingCapabilities => (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::forbidden(err.to_string())),
+        )
+            .into_response(),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn caller_id(user: &AuthenticatedUser) -> Result<MemberId, Response> {
+    user.member_id
+        .parse()
+        .map_err(|_| bad_request("caller is not a valid member id"))
+}
+
+/// Capabilities the caller declares in the member directory; members
+/// without a profile declare none.
+async fn caller_capabilities(state: &SharedState, user: &AuthenticatedUser) -> Capabilities {
+    match state.members.get(&user.member_id).await {
+        Ok(member) => member.map(|member| member.capabilities).unwrap_or_default(),
+        Err(err) => {
+            tracing::warn!("Member lookup failed, assuming no capabilities: {}", err);
+            Capabilities::default()
+        }
+    }
+}
+
+/// Apply `change` to task `task_id` of room `room_id`, then tell the room.
+async fn update_task(
+    state: &SharedState,
+    room_id: &str,
+    task_id: &str,
+    change: impl FnOnce(&mut DelegatedTask) -> Result<(), TaskTransitionError>,
+) -> Response {
+    let task = {
+        let mut tasks = state.tasks.write().await;
+        let Some(task) = tasks
+            .get_mut(task_id)
+            .filter(|task| task.room_id.as_str() == room_id)
+        else {
+            return task_not_found();
+        };
+        if let Err(err) = change(task) {
+            return transition_error_response(err);
+        }
+        task.clone()
+    };
+    publish_task(state, &task).await;
+    (StatusCode::OK, Json(task)).into_response()
+}
+
+async fn publish_task(state: &SharedState, task: &DelegatedTask) {
+    state
+        .publish(RoomEvent::Task {
+            room_id: task.room_id.to_string(),
+            task: task.clone(),
+        })
+        .await;
+}
+
+/// Refuse `user` reading room `id`'s tasks unless they take part in it.
+async fn check_can_read(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    id: &str,
+) -> Result<(), Response> {
+    ensure_room_access(state, user, id).await?;
+    joins::ensure_participant(state, user, id).await
+}
+
+/// Refuse `user` opening or changing room `id`'s tasks unless they could
+/// send to it.
+async fn check_can_write(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    id: &str,
+) -> Result<(), Response> {
+    check_can_read(state, user, id).await?;
+    sanctions::check_can_send(state, id, &user.member_id).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/tasks",
+    tag = "tasks",
+    summary = "Delegate a task to room members with the required capabilities",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = TaskRequest,
+    responses(
+        (status = 201, description = "Task created", body = DelegatedTask),
+        (status = 400, description = "Invalid task", body = ErrorResponse),
+        (status = 403, description = "Not a member of a room that isn't open, or banned from or muted in the room", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.create_task",
+    skip(state, user, request),
+    fields(room_id = %id, member_id = %user.member_id)
+)]
+async fn create_task(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(request): Json<TaskRequest>,
+) -> Response {
+    if let Err(response) = check_can_write(&state, &user, &id).await {
+        return response;
+    }
+    let requester = match caller_id(&user) {
+        Ok(requester) => requester,
+        Err(response) => return response,
+    };
+    if let Err(err) = request.validate() {
+        return bad_request(err.to_string());
+    }
+    let Ok(room_id) = id.parse::<RoomId>() else {
+        return bad_request("room id is not valid");
+    };
+
+    let task = DelegatedTask::new(room_id, requester, request);
+    state
+        .tasks
+        .write()
+        .await
+        .insert(task.id.clone(), task.clone());
+    publish_task(&state, &task).await;
+    (StatusCode::CREATED, Json(task)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/tasks",
+    tag = "tasks",
+    summary = "List a room's tasks",
+    params(("id" = String, Path, description = "Room id"), ListTasksQuery),
+    responses(
+        (status = 200, description = "The room's tasks", body = ListTasksResponse),
+        (status = 403, description = "The room isn't open and the caller is not a member", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.list_tasks", skip(state, user, query), fields(room_id = %id))]
+async fn list_tasks(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Query(query): Query<ListTasksQuery>,
+) -> Response {
+    if let Err(response) = check_can_read(&state, &user, &id).await {
+        return response;
+    }
+
+    let mut tasks: Vec<DelegatedTask> = state
+        .tasks
+        .read()
+        .await
+        .values()
+        .filter(|task| task.room_id.as_str() == id)
+        .filter(|task| query.status.is_none_or(|status| task.status == status))
+        .cloned()
+        .collect();
+    tasks.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+    let total = tasks.len();
+    (StatusCode::OK, Json(ListTasksResponse { tasks, total })).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/rooms/{id}/tasks/{task_id}",
+    tag = "tasks",
+    summary = "Get a task",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("task_id" = String, Path, description = "Task id"),
+    ),
+    responses(
+        (status = 200, description = "The task", body = DelegatedTask),
+        (status = 403, description = "The room isn't open and the caller is not a member", body = ErrorResponse),
+        (status = 404, description = "Room or task not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.get_task",
+    skip(state, user),
+    fields(room_id = %id, task_id = %task_id)
+)]
+async fn get_task(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, task_id)): Path<(String, String)>,
+) -> Response {
+    if let Err(response) = check_can_read(&state, &user, &id).await {
+        return response;
+    }
+
+    match state
+        .tasks
+        .read()
+        .await
+        .get(&task_id)
+        .filter(|task| task.room_id.as_str() == id)
+    {
+        Some(task) => (StatusCode::OK, Json(task.clone())).into_response(),
+        None => task_not_found(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/tasks/{task_id}/claim",
+    tag = "tasks",
+    summary = "Claim a pending task",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("task_id" = String, Path, description = "Task id"),
+    ),
+    responses(
+        (status = 200, description = "The claimed task", body = DelegatedTask),
+        (status = 403, description = "The caller lacks the required capabilities, is not a member, or is banned or muted", body = ErrorResponse),
+        (status = 404, description = "Room or task not found", body = ErrorResponse),
+        (status = 409, description = "The task is not pending", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.claim_task",
+    skip(state, user),
+    fields(room_id = %id, task_id = %task_id, member_id = %user.member_id)
+)]
+async fn claim_task(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, task_id)): Path<(String, String)>,
+) -> Response {
+    if let Err(response) = check_can_write(&state, &user, &id).await {
+        return response;
+    }
+    let member = match caller_id(&user) {
+        Ok(member) => member,
+        Err(response) => return response,
+    };
+    let capabilities = caller_capabilities(&state, &user).await;
+
+    update_task(&state, &id, &task_id, |task| {
+        task.claim(&member, &capabilities)
+    })
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/tasks/{task_id}/complete",
+    tag = "tasks",
+    summary = "Report the result of a claimed task",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("task_id" = String, Path, description = "Task id"),
+    ),
+    request_body = TaskResult,
+    responses(
+        (status = 200, description = "The completed task", body = DelegatedTask),
+        (status = 403, description = "The caller is not the task's assignee, or is banned or muted", body = ErrorResponse),
+        (status = 404, description = "Room or task not found", body = ErrorResponse),
+        (status = 409, description = "The task is not claimed", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.complete_task",
+    skip(state, user, result),
+    fields(room_id = %id, task_id = %task_id, member_id = %user.member_id)
+)]
+async fn complete_task(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, task_id)): Path<(String, String)>,
+    Json(result): Json<TaskResult>,
+) -> Response {
+    if let Err(response) = check_can_write(&state, &user, &id).await {
+        return response;
+    }
+    let member = match caller_id(&user) {
+        Ok(member) => member,
+        Err(response) => return response,
+    };
+
+    update_task(&state, &id, &task_id, |task| task.finish(&member, result)).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/tasks/{task_id}/cancel",
+    tag = "tasks",
+    summary = "Cancel a task",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("task_id" = String, Path, description = "Task id"),
+    ),
+    responses(
+        (status = 200, description = "The cancelled task", body = DelegatedTask),
+        (status = 403, description = "The caller did not create the task, or is banned or muted", body = ErrorResponse),
+        (status = 404, description = "Room or task not found", body = ErrorResponse),
+        (status = 409, description = "The task is already finished", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.cancel_task",
+    skip(state, user),
+    fields(room_id = %id, task_id = %task_id, member_id = %user.member_id)
+)]
+async fn cancel_task(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path((id, task_id)): Path<(String, String)>,
+) -> Response {
+    if let Err(response) = check_can_write(&state, &user, &id).await {
+        return response;
+    }
+    let is_admin = state.config.auth.is_admin(&user.member_id);
+    let requester = {
+        let tasks = state.tasks.read().await;
+        match tasks
+            .get(&task_id)
+            .filter(|task| task.room_id.as_str() == id)
+        {
+            Some(task) => task.requester.to_string(),
+            None => return task_not_found(),
+        }
+    };
+    if requester != user.member_id && !is_admin {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::forbidden(
+                "only the requester or an admin can cancel a task",
+            )),
+        )
+            .into_response();
+    }
+
+    update_task(&state, &id, &task_id, DelegatedTask::cancel).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn delegated_tasks_are_claimed_by_capable_agents_and_completed() {
+        let state = AppState::default();
+        let mut events = state.room_events.subscribe();
+        let app = routes(state);
+
+        let room_id = new_room(&app, "nexis:human:alice", "releases").await;
+        for (agent, capabilities) in [
+            ("nexis:agent:reviewer", json!({ "tools": ["run_tests"] })),
+            ("nexis:agent:chatter", json!({})),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request(
+                    agent,
+                    "POST",
+                    "/v1/members",
+                    json!({ "id": agent, "capabilities": capabilities }),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+        while events.try_recv().is_ok() {}
+
+        let tasks_uri = format!("/v1/rooms/{room_id}/tasks");
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &tasks_uri,
+                json!({ "title": " " }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &tasks_uri,
+                json!({
+                    "title": "Run the release tests",
+                    "requires": { "tools": ["run_tests"] }
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let task = json_body(response).await;
+        assert_eq!(task["status"], "open");
+        assert_eq!(task["requester"], "nexis:human:alice");
+        let task_uri = format!("{tasks_uri}/{}", task["id"].as_str().unwrap());
+        let event = serde_json::to_value(events.try_recv().unwrap()).unwrap();
+        assert_eq!(event["type"], "task");
+        assert_eq!(event["task"]["id"], task["id"]);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:agent:chatter",
+                "POST",
+                &format!("{task_uri}/claim"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:agent:reviewer",
+                "POST",
+                &format!("{task_uri}/claim"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(response).await["claimedBy"],
+            "nexis:agent:reviewer"
+        );
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:agent:reviewer",
+                "POST",
+                &format!("{task_uri}/claim"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:bob",
+                "POST",
+                &format!("{task_uri}/cancel"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:agent:reviewer",
+                "POST",
+                &format!("{task_uri}/complete"),
+                json!({ "success": true, "output": "All 42 tests pass" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(request("nexis:human:alice", "GET", &task_uri, Value::Null))
+            .await
+            .unwrap();
+        let task = json_body(response).await;
+        assert_eq!(task["status"], "completed");
+        assert_eq!(task["result"]["output"], "All 42 tests pass");
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "GET",
+                &format!("{tasks_uri}?status=open"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["total"], 0);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                &format!("{task_uri}/cancel"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn tasks_are_kept_to_members_who_may_send() {
+        const ADMIN: &str = "nexis:human:admin";
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec![ADMIN.to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            ..AppState::default()
+        });
+
+        let private_room = invite_only_room(&app, ADMIN, "private").await;
+        let tasks_uri = format!("/v1/rooms/{private_room}/tasks");
+        let response = app
+            .clone()
+            .oneshot(request(
+                ADMIN,
+                "POST",
+                &tasks_uri,
+                json!({ "title": "Rotate the keys" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let task_uri = format!(
+            "{tasks_uri}/{}",
+            json_body(response).await["id"].as_str().unwrap()
+        );
+        for (method, uri, body) in [
+            ("GET", tasks_uri.clone(), Value::Null),
+            ("POST", tasks_uri.clone(), json!({ "title": "Peek" })),
+            ("GET", task_uri.clone(), Value::Null),
+            ("POST", format!("{task_uri}/claim"), Value::Null),
+            ("POST", format!("{task_uri}/cancel"), Value::Null),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request("nexis:human:mallory", method, &uri, body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{method} {uri}");
+        }
+
+        let open_room = new_room(&app, ADMIN, "open").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                ADMIN,
+                "POST",
+                &format!("/v1/rooms/{open_room}/members/nexis:human:bob/mute"),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        let response = app
+            .oneshot(request(
+                "nexis:human:bob",
+                "POST",
+                &format!("/v1/rooms/{open_room}/tasks"),
+                json!({ "title": "Spam" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
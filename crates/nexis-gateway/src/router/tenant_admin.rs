@@ -0,0 +1,347 @@
+//! Tenant administration endpoints and per-request tenant admission.
+//!
+//! Admin routes live under `/v1/admin/tenants` and are restricted to
+//! `auth.admin_members`. Every request carrying a tenant-scoped token passes
+//! through [`tenant_admission`], which turns suspended tenants away with 403.
+
+use axum::{
+    extract::{Path, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use nexis_core::tenant::{Tenant, TenantId, TenantQuota};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{caller_tenant, require_admin, tenant_access_response, ErrorResponse, SharedState};
+use crate::audit::{AuditAction, AuditEvent};
+use crate::auth::AuthenticatedUser;
+use crate::tenants::{TenantStoreError, TenantUsage};
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/admin/tenants", get(list_tenants).post(create_tenant))
+        .route("/v1/admin/tenants/:id", get(get_tenant))
+        .route("/v1/admin/tenants/:id/suspend", post(suspend_tenant))
+        .route("/v1/admin/tenants/:id/resume", post(resume_tenant))
+        .route("/v1/admin/tenants/:id/quota", put(update_quota))
+}
+
+/// Reject requests from suspended tenants before they reach a handler.
+pub(super) async fn tenant_admission(
+    State(state): State<SharedState>,
+    user: Option<AuthenticatedUser>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(tenant_id) = user.as_ref().and_then(caller_tenant) {
+        if let Err(err) = state.tenants.admit(tenant_id).await {
+            return tenant_access_response(err);
+        }
+    }
+    next.run(request).await
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+struct CreateTenantRequest {
+    name: String,
+    slug: String,
+    #[serde(default)]
+    quota: TenantQuota,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct ListTenantsResponse {
+    tenants: Vec<Tenant>,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct TenantDetailsResponse {
+    #[serde(flatten)]
+    tenant: Tenant,
+    rooms: usize,
+    usage: TenantUsage,
+}
+
+fn store_error_response(err: TenantStoreError) -> Response {
+    match err {
+        TenantStoreError::SlugTaken(_) => (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::conflict(err.to_string())),
+        )
+            .into_response(),
+        TenantStoreError::NotFound(_) => tenant_not_found(),
+        other => {
+            tracing::error!("Tenant store error: {}", other);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::internal_error()),
+            )
+                .into_response()
+        }
+    }
+}
+
+fn tenant_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found("tenant not found")),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/tenants",
+    tag = "tenants",
+    summary = "List tenants",
+    responses(
+        (status = 200, description = "All tenants", body = ListTenantsResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.list_tenants", skip(state, user))]
+async fn list_tenants(State(state): State<SharedState>, user: AuthenticatedUser) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+
+    match state.tenants.store().list().await {
+        Ok(tenants) => {
+            let total = tenants.len();
+            (StatusCode::OK, Json(ListTenantsResponse { tenants, total })).into_response()
+        }
+        Err(err) => store_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/tenants",
+    tag = "tenants",
+    summary = "Create a tenant",
+    request_body = CreateTenantRequest,
+    responses(
+        (status = 201, description = "Tenant created", body = Tenant),
+        (status = 400, description = "Invalid name or slug", body = ErrorResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 409, description = "The slug is taken", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.create_tenant",
+    skip(state, user, payload),
+    fields(slug = %payload.slug)
+)]
+async fn create_tenant(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Json(payload): Json<CreateTenantRequest>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+
+    let tenant = match Tenant::new(payload.name, payload.slug) {
+        Ok(tenant) => tenant.with_quota(payload.quota),
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request(err.to_string())),
+            )
+                .into_response();
+        }
+    };
+
+    match state.tenants.store().create(tenant).await {
+        Ok(tenant) => {
+            state
+                .audit
+                .record(AuditEvent::new(
+                    &user.member_id,
+                    AuditAction::TenantCreated,
+                    format!("tenant:{}", tenant.id),
+                ))
+                .await;
+            (StatusCode::CREATED, Json(tenant)).into_response()
+        }
+        Err(err) => store_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/tenants/{id}",
+    tag = "tenants",
+    summary = "Get a tenant with its room count and usage",
+    params(("id" = String, Path, description = "Tenant id")),
+    responses(
+        (status = 200, description = "The tenant", body = TenantDetailsResponse),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Tenant not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.get_tenant", skip(state, user), fields(tenant_id = %id))]
+async fn get_tenant(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    let Ok(id) = TenantId::parse(&id) else {
+        return tenant_not_found();
+    };
+
+    let tenant = match state.tenants.store().get(id).await {
+        Ok(Some(tenant)) => tenant,
+        Ok(None) => return store_error_response(TenantStoreError::NotFound(id)),
+        Err(err) => return store_error_response(err),
+    };
+    let rooms = state
+        .rooms
+        .read()
+        .await
+        .values()
+        .filter(|room| room.tenant() == Some(tenant.slug.as_str()))
+        .count();
+    let usage = state.tenants.usage(&tenant.slug).await;
+
+    (
+        StatusCode::OK,
+        Json(TenantDetailsResponse {
+            tenant,
+            rooms,
+            usage,
+        }),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/tenants/{id}/suspend",
+    tag = "tenants",
+    summary = "Suspend a tenant",
+    params(("id" = String, Path, description = "Tenant id")),
+    responses(
+        (status = 200, description = "The suspended tenant", body = Tenant),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Tenant not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.suspend_tenant", skip(state, user), fields(tenant_id = %id))]
+async fn suspend_tenant(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    set_tenant_active(state, user, id, false).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/tenants/{id}/resume",
+    tag = "tenants",
+    summary = "Resume a suspended tenant",
+    params(("id" = String, Path, description = "Tenant id")),
+    responses(
+        (status = 200, description = "The resumed tenant", body = Tenant),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Tenant not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.resume_tenant", skip(state, user), fields(tenant_id = %id))]
+async fn resume_tenant(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    set_tenant_active(state, user, id, true).await
+}
+
+async fn set_tenant_active(
+    state: SharedState,
+    user: AuthenticatedUser,
+    id: String,
+    active: bool,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    let Ok(id) = TenantId::parse(&id) else {
+        return tenant_not_found();
+    };
+
+    match state.tenants.set_active(id, active).await {
+        Ok(tenant) => {
+            let action = if active {
+                AuditAction::TenantResumed
+            } else {
+                AuditAction::TenantSuspended
+            };
+            state
+                .audit
+                .record(AuditEvent::new(
+                    &user.member_id,
+                    action,
+                    format!("tenant:{id}"),
+                ))
+                .await;
+            (StatusCode::OK, Json(tenant)).into_response()
+        }
+        Err(err) => store_error_response(err),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/admin/tenants/{id}/quota",
+    tag = "tenants",
+    summary = "Replace a tenant's quota",
+    params(("id" = String, Path, description = "Tenant id")),
+    request_body = TenantQuota,
+    responses(
+        (status = 200, description = "The updated tenant", body = Tenant),
+        (status = 403, description = "The caller is not an admin", body = ErrorResponse),
+        (status = 404, description = "Tenant not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.update_tenant_quota",
+    skip(state, user, quota),
+    fields(tenant_id = %id)
+)]
+async fn update_quota(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(quota): Json<TenantQuota>,
+) -> Response {
+    if let Err(response) = require_admin(&state, &user).await {
+        return response;
+    }
+    let Ok(id) = TenantId::parse(&id) else {
+        return tenant_not_found();
+    };
+
+    match state.tenants.set_quota(id, quota).await {
+        Ok(tenant) => {
+            state
+                .audit
+                .record(AuditEvent::new(
+                    &user.member_id,
+                    AuditAction::TenantQuotaChanged,
+                    format!("tenant:{id}"),
+                ))
+                .await;
+            (StatusCode::OK, Json(tenant)).into_response()
+        }
+        Err(err) => store_error_response(err),
+    }
+}
@@ -0,0 +1,211 @@
+//! Request builders, response readers and providers shared by router tests.
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use axum::Router;
+use futures::{SinkExt, StreamExt};
+use nexis_runtime::{
+    AIProvider, GenerateRequest, GenerateResponse, ProviderError, ProviderStream, TokenUsage,
+};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tower::ServiceExt;
+
+use crate::auth::JwtConfig;
+
+/// JSON request carrying `token` as its bearer token; a `null` body is sent
+/// empty.
+pub(crate) fn request_with_token(
+    token: &str,
+    method: &str,
+    uri: &str,
+    body: Value,
+) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {token}"))
+        .body(if body.is_null() {
+            Body::empty()
+        } else {
+            Body::from(body.to_string())
+        })
+        .unwrap()
+}
+
+/// JSON request made by `member` with a test token.
+pub(crate) fn request(member: &str, method: &str, uri: &str, body: Value) -> Request<Body> {
+    request_with_token(&JwtConfig::test_token(member), method, uri, body)
+}
+
+/// Request made by `member` with a test token and a `content_type` body.
+pub(crate) fn raw_request(
+    member: &str,
+    method: &str,
+    uri: &str,
+    content_type: &str,
+    body: impl Into<Body>,
+) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", content_type)
+        .header(
+            "authorization",
+            format!("Bearer {}", JwtConfig::test_token(member)),
+        )
+        .body(body.into())
+        .unwrap()
+}
+
+/// JSON request without credentials.
+pub(crate) fn anonymous_request(method: &str, uri: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// Response body as text.
+pub(crate) async fn text_body(response: Response) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+/// Response body as JSON, or `null` when it is empty or not JSON.
+pub(crate) async fn json_body(response: Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap_or(Value::Null)
+}
+
+/// Create a room named `name` as `member` and return its id.
+pub(crate) async fn new_room(app: &Router, member: &str, name: &str) -> String {
+    let response = app
+        .clone()
+        .oneshot(request(
+            member,
+            "POST",
+            "/v1/rooms",
+            json!({ "name": name }),
+        ))
+        .await
+        .unwrap();
+    json_body(response).await["id"]
+        .as_str()
+        .expect("room was not created")
+        .to_string()
+}
+
+/// Create a room named `name` as `admin`, which must be a configured admin,
+/// and make it invite-only so that only members and admins take part.
+pub(crate) async fn invite_only_room(app: &Router, admin: &str, name: &str) -> String {
+    let room_id = new_room(app, admin, name).await;
+    let response = app
+        .clone()
+        .oneshot(request(
+            admin,
+            "PATCH",
+            &format!("/v1/rooms/{room_id}/settings"),
+            json!({ "joinPolicy": "invite_only" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(
+        response.status(),
+        StatusCode::OK,
+        "room was not made invite-only"
+    );
+    room_id
+}
+
+/// Client side of a gateway WebSocket.
+pub(crate) type WsClient = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Serve `app` on a free local port and return its address.
+pub(crate) async fn serve(app: Router) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    addr
+}
+
+/// Open a WebSocket to the gateway at `addr`, authenticated as `member`
+/// when there is one.
+pub(crate) async fn connect_ws(addr: SocketAddr, member: Option<&str>) -> WsClient {
+    let mut request = format!("ws://{addr}/ws").into_client_request().unwrap();
+    if let Some(member) = member {
+        let bearer = format!("Bearer {}", JwtConfig::test_token(member));
+        request
+            .headers_mut()
+            .insert("authorization", bearer.parse().unwrap());
+    }
+    connect_async(request).await.unwrap().0
+}
+
+/// Send `frame` as a text frame.
+pub(crate) async fn send_json(ws: &mut WsClient, frame: Value) {
+    ws.send(WsMessage::Text(frame.to_string().into()))
+        .await
+        .unwrap();
+}
+
+/// Next text frame as JSON, failing after two seconds.
+pub(crate) async fn next_json(ws: &mut WsClient) -> Value {
+    let frame = tokio::time::timeout(Duration::from_secs(2), ws.next())
+        .await
+        .expect("no frame within two seconds")
+        .unwrap()
+        .unwrap();
+    match frame {
+        WsMessage::Text(text) => serde_json::from_str(&text).unwrap(),
+        other => panic!("unexpected frame: {other:?}"),
+    }
+}
+
+/// Provider answering every prompt with the prompt itself.
+#[derive(Debug)]
+pub(crate) struct EchoProvider;
+
+#[async_trait]
+impl AIProvider for EchoProvider {
+    fn name(&self) -> &'static str {
+        "echo"
+    }
+
+    async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+        Ok(GenerateResponse {
+            content: req.prompt,
+            model: Some("echo-model".to_string()),
+            finish_reason: Some("stop".to_string()),
+            usage: Some(TokenUsage {
+                input_tokens: 1000,
+                output_tokens: 500,
+            }),
+        })
+    }
+
+    async fn generate_stream(
+        &self,
+        _req: GenerateRequest,
+    ) -> Result<ProviderStream, ProviderError> {
+        Err(ProviderError::Message(
+            "streaming not supported".to_string(),
+        ))
+    }
+}
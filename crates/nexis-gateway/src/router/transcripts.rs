@@ -0,0 +1,545 @@
+//! Voice-note transcription.
+//!
+//! A member uploads an audio file through `POST /v1/uploads`, then asks for
+//! it to be transcribed into a room. The transcript is posted as a regular
+//! message from that member, with the recording attached, and counts as
+//! one of their messages under the room's slow mode. The room's message
+//! size limit applies to the transcript, and a long one is attached as a
+//! text file the way long pastes are.
+
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use nexis_runtime::TranscriptionRequest;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{
+    caller_tenant, ensure_room_access, error_codes, joins, message_limits, moderation,
+    provider_error_type, record_operation_error, record_operation_success, sanctions, slow_mode,
+    spam, storage_error_response, uploads, ErrorResponse, RoomEvent, SharedState, StoredMessage,
+};
+use crate::auth::AuthenticatedUser;
+use crate::metrics::{record_ai_request, MESSAGES_SENT};
+
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new().route("/v1/rooms/:id/transcribe", post(transcribe_upload))
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct TranscribeRequest {
+    upload_id: String,
+    /// ISO-639-1 hint passed to the provider.
+    #[serde(default)]
+    language: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct TranscribeResponse {
+    message_id: String,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_secs: Option<f32>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/rooms/{id}/transcribe",
+    tag = "ai",
+    summary = "Transcribe an uploaded audio file and post the text to the room",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = TranscribeRequest,
+    responses(
+        (status = 201, description = "Transcript posted", body = TranscribeResponse),
+        (status = 403, description = "The caller is not a member of the room, or is banned or muted in it", body = ErrorResponse),
+        (status = 404, description = "Room or upload not found", body = ErrorResponse),
+        (status = 413, description = "The transcript is longer than the room allows"),
+        (status = 415, description = "The upload is not audio", body = ErrorResponse),
+        (status = 429, description = "Slow mode is on and the sender must wait"),
+        (status = 502, description = "The transcription provider failed", body = ErrorResponse),
+        (status = 503, description = "Transcription is unavailable", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.transcribe_upload",
+    skip(state, user, payload),
+    fields(room_id = %id, upload_id = %payload.upload_id)
+)]
+async fn transcribe_upload(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(payload): Json<TranscribeRequest>,
+) -> Response {
+    let started = Instant::now();
+    let operation = "transcribe_upload";
+    let (Some(transcriber), Some(blobs)) = (state.transcriber.clone(), state.blobs.clone()) else {
+        record_operation_error(operation, "unavailable", started);
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: "transcription is not configured".to_string(),
+                code: Some(error_codes::AI_UNAVAILABLE),
+            }),
+        )
+            .into_response();
+    };
+    if let Err(response) = ensure_room_access(&state, &user, &id).await {
+        record_operation_error(operation, "room_not_found", started);
+        return response;
+    }
+    if let Err(response) = joins::ensure_participant(&state, &user, &id).await {
+        record_operation_error(operation, "not_a_member", started);
+        return response;
+    }
+    if let Err(response) = sanctions::check_can_send(&state, &id, &user.member_id).await {
+        record_operation_error(operation, "sanctioned", started);
+        return response;
+    }
+    // Held until the transcript is stored; a refused one hands the slot back.
+    let slow_mode = match slow_mode::reserve(&state, &user, &id).await {
+        Ok(reservation) => reservation,
+        Err(response) => {
+            record_operation_error(operation, "slow_mode", started);
+            return response;
+        }
+    };
+    // Only the uploader may transcribe, since the message is posted as them.
+    let attachment = match uploads::resolve_attachments(
+        &state,
+        &user,
+        &id,
+        std::slice::from_ref(&payload.upload_id),
+    )
+    .await
+    {
+        Ok(mut attachments) => attachments.remove(0),
+        Err(response) => {
+            record_operation_error(operation, "validation", started);
+            return response;
+        }
+    };
+    if !attachment.mime_type.starts_with("audio/") {
+        record_operation_error(operation, "validation", started);
+        return (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Json(ErrorResponse::unsupported_media_type(
+                "only audio uploads can be transcribed",
+            )),
+        )
+            .into_response();
+    }
+
+    let audio = match blobs.get(&attachment.id).await {
+        Ok(Some(audio)) => audio,
+        Ok(None) => {
+            record_operation_error(operation, "blob_missing", started);
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::not_found("upload content not found")),
+            )
+                .into_response();
+        }
+        Err(err) => {
+            tracing::error!("Blob store error: {}", err);
+            record_operation_error(operation, "blob_store", started);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::internal_error()),
+            )
+                .into_response();
+        }
+    };
+
+    let mut request = TranscriptionRequest::new(
+        audio.to_vec(),
+        attachment.file_name.clone(),
+        attachment.mime_type.clone(),
+    );
+    request.language = payload.language;
+    let provider_started = Instant::now();
+    let result = transcriber.transcribe(request).await;
+    record_ai_request(
+        transcriber.name(),
+        provider_started,
+        result.as_ref().err().map(provider_error_type),
+    );
+    let transcript = match result {
+        Ok(transcript) => transcript,
+        Err(err) => {
+            tracing::error!("Transcription provider error: {}", err);
+            record_operation_error(operation, "provider", started);
+            return (
+                StatusCode::BAD_GATEWAY,
+                Json(ErrorResponse {
+                    error: "transcription request failed".to_string(),
+                    code: Some(error_codes::AI_PROVIDER_ERROR),
+                }),
+            )
+                .into_response();
+        }
+    };
+
+    if let Err(response) = message_limits::room_limits(&state, &id)
+        .await
+        .check(&transcript.text, None)
+    {
+        record_operation_error(operation, "too_large", started);
+        return response;
+    }
+    if let Err(response) = spam::screen(&state, &user, &id, &transcript.text).await {
+        record_operation_error(operation, "spam", started);
+        return response;
+    }
+    let moderation = match moderation::moderate(&state, &user, &id, &transcript.text).await {
+        Ok(moderation) => moderation,
+        Err(response) => {
+            record_operation_error(operation, "moderation", started);
+            return response;
+        }
+    };
+
+    let mut message = StoredMessage {
+        attachments: vec![attachment],
+        moderation,
+        ..StoredMessage::new(user.member_id.clone(), transcript.text.clone())
+    };
+    // Long transcripts go to file storage like long pastes.
+    if let Some((preview, attachment)) =
+        message_limits::stash_paste(&state, &user, &message.text).await
+    {
+        message.text = preview;
+        message.attachments.push(attachment);
+    }
+    let response = TranscribeResponse {
+        message_id: message.id.clone(),
+        text: transcript.text,
+        language: transcript.language,
+        duration_secs: transcript.duration_secs,
+    };
+
+    let Ok(_permit) = state.write_gate.clone().acquire_owned().await else {
+        record_operation_error(operation, "unavailable", started);
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::service_unavailable("service unavailable")),
+        )
+            .into_response();
+    };
+    // Only transcripts that are about to be stored count towards the quota.
+    #[cfg(feature = "multi-tenant")]
+    if let Some(tenant_id) = super::caller_tenant(&user) {
+        if let Err(err) = state.tenants.consume_message(tenant_id).await {
+            record_operation_error(operation, "quota", started);
+            return super::tenant_access_response(err);
+        }
+    }
+    if let Err(err) = state
+        .store_message(&id, caller_tenant(&user), &message)
+        .await
+    {
+        record_operation_error(operation, "storage", started);
+        return storage_error_response(err);
+    }
+    state
+        .room_messages
+        .write()
+        .await
+        .entry(id.clone())
+        .or_default()
+        .push(message.clone());
+    if let Some(reservation) = slow_mode {
+        reservation.keep();
+    }
+    state
+        .publish(RoomEvent::Message {
+            room_id: id,
+            message,
+        })
+        .await;
+    MESSAGES_SENT.inc();
+    record_operation_success(operation, started);
+
+    (StatusCode::CREATED, Json(response)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blobs::LocalBlobStore;
+    use crate::config::NexisConfig;
+    use crate::router::test_support::*;
+    use crate::router::{build_routes, routes, AppState};
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn transcribe_posts_voice_note_transcript_as_uploader() {
+        use nexis_runtime::transcription::{MockTranscriptionProvider, TranscriptionResponse};
+
+        let root = std::env::temp_dir().join(format!("nexis-uploads-{}", Uuid::new_v4()));
+        let transcriber = Arc::new(MockTranscriptionProvider::new());
+        transcriber.enqueue(Ok(TranscriptionResponse::new(
+            "buy milk on the way home",
+            "whisper-1",
+        )));
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        let app = routes(AppState {
+            config: Arc::new(config),
+            blobs: Some(Arc::new(LocalBlobStore::new(&root))),
+            transcriber: Some(transcriber.clone()),
+            ..AppState::default()
+        });
+        let upload = |mime_type: &str, file_name: &str| {
+            let body = format!(
+                "--XBOUNDARY\r\nContent-Disposition: form-data; name=\"file\"; \
+                 filename=\"{file_name}\"\r\nContent-Type: {mime_type}\r\n\r\nOggS\r\n\
+                 --XBOUNDARY--\r\n"
+            );
+            let content_type = "multipart/form-data; boundary=XBOUNDARY";
+            raw_request("alice", "POST", "/v1/uploads", content_type, body)
+        };
+
+        let room_id = new_room(&app, "alice", "voice").await;
+        let response = app
+            .clone()
+            .oneshot(upload("audio/ogg", "note.ogg"))
+            .await
+            .unwrap();
+        let audio_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let response = app
+            .clone()
+            .oneshot(upload("text/plain", "note.txt"))
+            .await
+            .unwrap();
+        let text_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let uri = format!("/v1/rooms/{room_id}/transcribe");
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "POST",
+                &uri,
+                json!({ "uploadId": text_id }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "bob",
+                "POST",
+                &uri,
+                json!({ "uploadId": audio_id }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "POST",
+                &uri,
+                json!({ "uploadId": audio_id, "language": "en" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created = json_body(response).await;
+        assert_eq!(created["text"], "buy milk on the way home");
+        let requests = transcriber.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].audio, b"OggS");
+        assert_eq!(requests[0].mime_type, "audio/ogg");
+        assert_eq!(requests[0].language.as_deref(), Some("en"));
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "GET",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        let room = json_body(response).await;
+        assert_eq!(room["messages"][0]["id"], created["messageId"]);
+        assert_eq!(room["messages"][0]["sender"], "alice");
+        assert_eq!(room["messages"][0]["text"], "buy milk on the way home");
+        assert_eq!(room["messages"][0]["attachments"][0]["id"], audio_id);
+
+        // Transcripts count as messages in slow mode; a refused one isn't
+        // sent to the provider.
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PATCH",
+                &format!("/v1/rooms/{room_id}/settings"),
+                json!({ "slowModeSecs": 60 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        transcriber.enqueue(Ok(TranscriptionResponse::new("and eggs", "whisper-1")));
+        for expected in [StatusCode::CREATED, StatusCode::TOO_MANY_REQUESTS] {
+            let response = app
+                .clone()
+                .oneshot(request(
+                    "alice",
+                    "POST",
+                    &uri,
+                    json!({ "uploadId": audio_id }),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected);
+        }
+        assert_eq!(transcriber.requests().len(), 2);
+
+        // Muted members can't post transcripts, and the provider isn't asked.
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "POST",
+                &format!("/v1/rooms/{room_id}/members/alice/mute"),
+                json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .oneshot(request(
+                "alice",
+                "POST",
+                &uri,
+                json!({ "uploadId": audio_id }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(transcriber.requests().len(), 2);
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn transcripts_keep_to_the_room_message_limits() {
+        use nexis_runtime::transcription::{MockTranscriptionProvider, TranscriptionResponse};
+
+        let root = std::env::temp_dir().join(format!("nexis-uploads-{}", Uuid::new_v4()));
+        let transcriber = Arc::new(MockTranscriptionProvider::new());
+        let mut config = NexisConfig::default();
+        config.auth.admin_members = vec!["admin".to_string()];
+        config.rate_limits.paste_attachment_bytes = 32;
+        let app = routes(AppState {
+            config: Arc::new(config),
+            blobs: Some(Arc::new(LocalBlobStore::new(&root))),
+            transcriber: Some(transcriber.clone()),
+            ..AppState::default()
+        });
+        let body = "--XBOUNDARY\r\nContent-Disposition: form-data; name=\"file\"; \
+                    filename=\"note.ogg\"\r\nContent-Type: audio/ogg\r\n\r\nOggS\r\n\
+                    --XBOUNDARY--\r\n";
+        let content_type = "multipart/form-data; boundary=XBOUNDARY";
+        let response = app
+            .clone()
+            .oneshot(raw_request(
+                "alice",
+                "POST",
+                "/v1/uploads",
+                content_type,
+                body.to_string(),
+            ))
+            .await
+            .unwrap();
+        let audio_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let room_id = new_room(&app, "admin", "voice").await;
+        let transcribe = || {
+            app.clone().oneshot(request(
+                "alice",
+                "POST",
+                &format!("/v1/rooms/{room_id}/transcribe"),
+                json!({ "uploadId": audio_id }),
+            ))
+        };
+        let transcript = "remember to buy milk, eggs and bread on the way home";
+
+        transcriber.enqueue(Ok(TranscriptionResponse::new(transcript, "whisper-1")));
+        let response = transcribe().await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "alice",
+                "GET",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        let room = json_body(response).await;
+        let attachments = room["messages"][0]["attachments"].as_array().unwrap();
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0]["id"], audio_id);
+        assert_eq!(attachments[1]["mimeType"], "text/plain");
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "admin",
+                "PATCH",
+                &format!("/v1/rooms/{room_id}/settings"),
+                json!({ "maxMessageBytes": 16 }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        transcriber.enqueue(Ok(TranscriptionResponse::new(transcript, "whisper-1")));
+        let response = transcribe().await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(json_body(response).await["field"], "text");
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn transcribe_is_unavailable_without_a_provider() {
+        let response = build_routes()
+            .oneshot(request(
+                "alice",
+                "POST",
+                "/v1/rooms/room_1/transcribe",
+                json!({ "uploadId": "upl_1" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
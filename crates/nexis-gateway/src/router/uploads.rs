@@ -0,0 +1,862 @@
+//! File uploads and signed downloads.
+//!
+//! `POST /v1/uploads` streams a multipart `file` field into the configured
+//! [`BlobStore`](crate::blobs::BlobStore) and returns an upload id that
+//! messages can reference in `attachments`. Downloads use short-lived signed
+//! URLs so they work without a bearer token. Upload metadata is stored
+//! alongside messages; once a file is posted, its metadata is only shown to
+//! the uploader and those who can read the room it was posted in.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use bytes::Bytes;
+use chrono::{DateTime, Duration, Utc};
+use futures::StreamExt;
+use nexis_protocol::AttachmentRef;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::{caller_tenant, joins, storage_error_response, ErrorResponse, SharedState};
+use crate::auth::AuthenticatedUser;
+use crate::blobs::{sign_download, verify_download, BlobError};
+use crate::db::{RepositoryError, UploadRecord};
+
+/// Room for multipart boundaries and part headers on top of the file itself.
+const MULTIPART_OVERHEAD_BYTES: usize = 64 * 1024;
+const MAX_FILE_NAME_CHARS: usize = 255;
+const FALLBACK_MIME_TYPE: &str = "application/octet-stream";
+const TEXT_MIME_TYPE: &str = "text/plain";
+
+pub(super) fn routes(max_bytes: usize) -> Router<SharedState> {
+    Router::new()
+        .route(
+            "/v1/uploads",
+            post(create_upload).layer(DefaultBodyLimit::max(
+                max_bytes.saturating_add(MULTIPART_OVERHEAD_BYTES),
+            )),
+        )
+        .route("/v1/uploads/:id", get(get_upload))
+        .route("/v1/uploads/:id/content", get(download_upload))
+}
+
+/// Metadata for a stored file; the blob itself lives under `id`.
+#[derive(Debug, Clone)]
+pub(super) struct Upload {
+    id: String,
+    file_name: String,
+    mime_type: String,
+    size: u64,
+    uploaded_by: String,
+    tenant_id: Option<String>,
+    /// Room the file was first posted in; it can't be attached elsewhere.
+    room_id: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl Upload {
+    fn from_record(record: UploadRecord) -> Self {
+        Self {
+            id: record.id,
+            file_name: record.file_name,
+            mime_type: record.mime_type,
+            size: record.size,
+            uploaded_by: record.uploaded_by,
+            tenant_id: record.tenant_id,
+            room_id: record.room_id,
+            created_at: record.created_at,
+        }
+    }
+
+    fn to_record(&self) -> UploadRecord {
+        UploadRecord {
+            id: self.id.clone(),
+            file_name: self.file_name.clone(),
+            mime_type: self.mime_type.clone(),
+            size: self.size,
+            uploaded_by: self.uploaded_by.clone(),
+            tenant_id: self.tenant_id.clone(),
+            room_id: self.room_id.clone(),
+            created_at: self.created_at,
+        }
+    }
+
+    fn attachment(&self) -> AttachmentRef {
+        AttachmentRef {
+            id: self.id.clone(),
+            file_name: self.file_name.clone(),
+            mime_type: self.mime_type.clone(),
+            size: self.size,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct UploadResponse {
+    id: String,
+    file_name: String,
+    mime_type: String,
+    size: u64,
+    uploaded_by: String,
+    created_at: DateTime<Utc>,
+    download_url: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Multipart form accepted by `POST /v1/uploads`, for the API document.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct UploadForm {
+    /// The file; its part's `Content-Type` and file name are kept.
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DownloadQuery {
+    expires: i64,
+    signature: String,
+}
+
+fn error(status: StatusCode, body: ErrorResponse) -> Response {
+    (status, Json(body)).into_response()
+}
+
+fn upload_not_found() -> Response {
+    error(
+        StatusCode::NOT_FOUND,
+        ErrorResponse::not_found("upload not found"),
+    )
+}
+
+fn blob_error_response(err: BlobError) -> Response {
+    match err {
+        BlobError::TooLarge { .. } => error(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorResponse::payload_too_large(err.to_string()),
+        ),
+        BlobError::Stream(_) => error(
+            StatusCode::BAD_REQUEST,
+            ErrorResponse::bad_request(err.to_string()),
+        ),
+        other => {
+            tracing::error!("Blob store error: {}", other);
+            error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorResponse::internal_error(),
+            )
+        }
+    }
+}
+
+/// Strip any client-side directory and characters that would break a
+/// `Content-Disposition` header.
+fn sanitize_file_name(name: Option<&str>) -> String {
+    let name = name
+        .unwrap_or_default()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(MAX_FILE_NAME_CHARS)
+        .collect::<String>();
+    let name = name.trim();
+    if name.is_empty() {
+        "upload".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn upload_response(state: &SharedState, upload: &Upload) -> UploadResponse {
+    let expires_at = Utc::now() + Duration::seconds(state.config.uploads.url_ttl_secs as i64);
+    let signature = sign_download(
+        &state.config.auth.jwt_secret,
+        &upload.id,
+        expires_at.timestamp(),
+    );
+    UploadResponse {
+        id: upload.id.clone(),
+        file_name: upload.file_name.clone(),
+        mime_type: upload.mime_type.clone(),
+        size: upload.size,
+        uploaded_by: upload.uploaded_by.clone(),
+        created_at: upload.created_at,
+        download_url: format!(
+            "/v1/uploads/{}/content?expires={}&signature={signature}",
+            upload.id,
+            expires_at.timestamp()
+        ),
+        expires_at,
+    }
+}
+
+/// The stored uploads by id.
+pub(super) fn restore(records: Vec<UploadRecord>) -> HashMap<String, Upload> {
+    records
+        .into_iter()
+        .map(|record| (record.id.clone(), Upload::from_record(record)))
+        .collect()
+}
+
+/// Persist a new upload before it joins `uploads`.
+async fn insert(state: &SharedState, upload: Upload) -> Result<(), RepositoryError> {
+    state.upload_store.insert(&upload.to_record()).await?;
+    state
+        .uploads
+        .write()
+        .await
+        .insert(upload.id.clone(), upload);
+    Ok(())
+}
+
+/// Resolve attachment ids on a message outgoing to `room_id`.
+///
+/// Members can only attach files they uploaded themselves, and only in the
+/// room a file was first posted in.
+pub(super) async fn resolve_attachments(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    room_id: &str,
+    ids: &[String],
+) -> Result<Vec<AttachmentRef>, Response> {
+    let uploads = state.uploads.read().await;
+    let mut attachments = Vec::with_capacity(ids.len());
+    for id in ids {
+        match uploads.get(id) {
+            Some(upload)
+                if upload.uploaded_by == user.member_id
+                    && upload.tenant_id.as_deref() == caller_tenant(user) =>
+            {
+                if upload
+                    .room_id
+                    .as_deref()
+                    .is_some_and(|posted_in| posted_in != room_id)
+                {
+                    return Err(error(
+                        StatusCode::BAD_REQUEST,
+                        ErrorResponse::bad_request(format!(
+                            "attachment {id} was posted in another room"
+                        )),
+                    ));
+                }
+                attachments.push(upload.attachment());
+            }
+            _ => {
+                return Err(error(
+                    StatusCode::BAD_REQUEST,
+                    ErrorResponse::bad_request(format!("unknown attachment {id}")),
+                ))
+            }
+        }
+    }
+    Ok(attachments)
+}
+
+/// Record `room_id` as the room the stored message's attachments were
+/// posted in, for the ones not posted before.
+pub(super) async fn posted(state: &SharedState, room_id: &str, attachments: &[AttachmentRef]) {
+    if attachments.is_empty() {
+        return;
+    }
+    let mut uploads = state.uploads.write().await;
+    for attachment in attachments {
+        let Some(upload) = uploads.get_mut(&attachment.id) else {
+            continue;
+        };
+        if upload.room_id.is_some() {
+            continue;
+        }
+        match state.upload_store.set_room(&upload.id, room_id).await {
+            Ok(_) => upload.room_id = Some(room_id.to_string()),
+            Err(err) => tracing::warn!(
+                "Failed to record room {} of upload {}: {}",
+                room_id,
+                upload.id,
+                err
+            ),
+        }
+    }
+}
+
+/// Drop the uploads posted in a deleted room, files included.
+pub(super) async fn remove_room(state: &SharedState, room_id: &str) {
+    let removed: Vec<String> = {
+        let mut uploads = state.uploads.write().await;
+        let removed = uploads
+            .values()
+            .filter(|upload| upload.room_id.as_deref() == Some(room_id))
+            .map(|upload| upload.id.clone())
+            .collect::<Vec<_>>();
+        for id in &removed {
+            uploads.remove(id);
+        }
+        removed
+    };
+    if let Err(err) = state.upload_store.delete_room(room_id).await {
+        tracing::warn!("Failed to drop uploads of room {}: {}", room_id, err);
+    }
+    if let Some(blobs) = &state.blobs {
+        for id in removed {
+            if let Err(err) = blobs.delete(&id).await {
+                tracing::warn!("Failed to delete file of upload {}: {}", id, err);
+            }
+        }
+    }
+}
+
+/// Store `text` as a `text/plain` upload by `user` for a message to
+/// attach. `None` when uploads are unavailable, don't accept plain text,
+/// or the store refuses it.
+pub(super) async fn store_text(
+    state: &SharedState,
+    user: &AuthenticatedUser,
+    file_name: &str,
+    text: String,
+) -> Option<AttachmentRef> {
+    let blobs = state.blobs.clone()?;
+    let uploads = &state.config.uploads;
+    if !uploads.allows_mime_type(TEXT_MIME_TYPE) {
+        return None;
+    }
+    let id = format!("upl_{}", Uuid::new_v4().simple());
+    let body = futures::stream::once(async move { Ok(Bytes::from(text)) });
+    let size = match blobs
+        .put(&id, TEXT_MIME_TYPE, Box::pin(body), uploads.max_bytes)
+        .await
+    {
+        Ok(size) => size,
+        Err(err) => {
+            tracing::warn!("Failed to store text upload {}: {}", id, err);
+            return None;
+        }
+    };
+    let upload = Upload {
+        id: id.clone(),
+        file_name: file_name.to_string(),
+        mime_type: TEXT_MIME_TYPE.to_string(),
+        size,
+        uploaded_by: user.member_id.clone(),
+        tenant_id: caller_tenant(user).map(str::to_string),
+        room_id: None,
+        created_at: Utc::now(),
+    };
+    let attachment = upload.attachment();
+    if let Err(err) = insert(state, upload).await {
+        tracing::warn!("Failed to store text upload {}: {}", id, err);
+        return None;
+    }
+    Some(attachment)
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/uploads",
+    tag = "uploads",
+    summary = "Upload a file to attach to messages",
+    request_body(content = UploadForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 201, description = "File stored", body = UploadResponse),
+        (status = 400, description = "Missing or malformed `file` part", body = ErrorResponse),
+        (status = 413, description = "The file is too large", body = ErrorResponse),
+        (status = 415, description = "Files of this type are not accepted", body = ErrorResponse),
+        (status = 503, description = "File uploads are unavailable", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(
+    name = "gateway.create_upload",
+    skip(state, user, multipart),
+    fields(member_id = %user.member_id)
+)]
+async fn create_upload(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    mut multipart: Multipart,
+) -> Response {
+    let Some(blobs) = state.blobs.clone() else {
+        return error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            ErrorResponse::service_unavailable("file uploads are unavailable"),
+        );
+    };
+    let uploads = &state.config.uploads;
+
+    let field = loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.name() == Some("file") => break field,
+            Ok(Some(_)) => continue,
+            Ok(None) => {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    ErrorResponse::bad_request("multipart field `file` is required"),
+                )
+            }
+            Err(err) => {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    ErrorResponse::bad_request(err.body_text()),
+                )
+            }
+        }
+    };
+
+    let mime_type = field
+        .content_type()
+        .unwrap_or(FALLBACK_MIME_TYPE)
+        .trim()
+        .to_ascii_lowercase();
+    if !uploads.allows_mime_type(&mime_type) {
+        return error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorResponse::unsupported_media_type(format!(
+                "files of type {mime_type} are not accepted"
+            )),
+        );
+    }
+    let file_name = sanitize_file_name(field.file_name());
+
+    let id = format!("upl_{}", Uuid::new_v4().simple());
+    let body = field.map(|chunk| chunk.map_err(|err| BlobError::Stream(err.body_text())));
+    let size = match blobs
+        .put(&id, &mime_type, Box::pin(body), uploads.max_bytes)
+        .await
+    {
+        Ok(size) => size,
+        Err(err) => return blob_error_response(err),
+    };
+
+    let upload = Upload {
+        id: id.clone(),
+        file_name,
+        mime_type,
+        size,
+        uploaded_by: user.member_id.clone(),
+        tenant_id: caller_tenant(&user).map(str::to_string),
+        room_id: None,
+        created_at: Utc::now(),
+    };
+    let response = upload_response(&state, &upload);
+    if let Err(err) = insert(&state, upload).await {
+        if let Err(cleanup) = blobs.delete(&id).await {
+            tracing::warn!("Failed to delete file of upload {}: {}", id, cleanup);
+        }
+        return storage_error_response(err);
+    }
+    (StatusCode::CREATED, Json(response)).into_response()
+}
+
+/// Upload metadata with a freshly signed download URL.
+///
+/// Others than the uploader only see a file once it is posted, and only if
+/// they can read the room it was posted in.
+#[utoipa::path(
+    get,
+    path = "/v1/uploads/{id}",
+    tag = "uploads",
+    summary = "Get an upload's metadata and a fresh download link",
+    params(("id" = String, Path, description = "Upload id")),
+    responses(
+        (status = 200, description = "The upload", body = UploadResponse),
+        (status = 403, description = "The caller is not a member of the room the file was posted in", body = ErrorResponse),
+        (status = 404, description = "Upload not found", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.get_upload", skip(state, user), fields(upload_id = %id))]
+async fn get_upload(
+    State(state): State<SharedState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Response {
+    let upload = state.uploads.read().await.get(&id).cloned();
+    let Some(upload) = upload.filter(|upload| upload.tenant_id.as_deref() == caller_tenant(&user))
+    else {
+        return upload_not_found();
+    };
+    if upload.uploaded_by != user.member_id {
+        let Some(room_id) = upload.room_id.as_deref() else {
+            return upload_not_found();
+        };
+        if let Err(response) = joins::ensure_participant(&state, &user, room_id).await {
+            return response;
+        }
+    }
+    (StatusCode::OK, Json(upload_response(&state, &upload))).into_response()
+}
+
+/// Serve a file to anyone holding a valid, unexpired signed URL.
+#[utoipa::path(
+    get,
+    path = "/v1/uploads/{id}/content",
+    tag = "uploads",
+    summary = "Download a file through a signed link",
+    security(()),
+    params(("id" = String, Path, description = "Upload id"), DownloadQuery),
+    responses(
+        (status = 200, description = "The file's contents", content_type = "application/octet-stream"),
+        (status = 403, description = "The link is invalid or has expired", body = ErrorResponse),
+        (status = 404, description = "Upload not found", body = ErrorResponse),
+        (status = 503, description = "File uploads are unavailable", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "gateway.download_upload", skip(state, query), fields(upload_id = %id))]
+async fn download_upload(
+    State(state): State<SharedState>,
+    Path(id): Path<String>,
+    Query(query): Query<DownloadQuery>,
+) -> Response {
+    if query.expires < Utc::now().timestamp()
+        || !verify_download(
+            &state.config.auth.jwt_secret,
+            &id,
+            query.expires,
+            &query.signature,
+        )
+    {
+        return error(
+            StatusCode::FORBIDDEN,
+            ErrorResponse::forbidden("download link is invalid or has expired"),
+        );
+    }
+    let Some(upload) = state.uploads.read().await.get(&id).cloned() else {
+        return upload_not_found();
+    };
+    let Some(blobs) = state.blobs.clone() else {
+        return error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            ErrorResponse::service_unavailable("file uploads are unavailable"),
+        );
+    };
+
+    let contents = match blobs.get(&id).await {
+        Ok(Some(contents)) => contents,
+        Ok(None) => return upload_not_found(),
+        Err(err) => return blob_error_response(err),
+    };
+    // Media renders in place; everything else is saved rather than opened.
+    let disposition = if ["image/", "audio/", "video/"]
+        .iter()
+        .any(|family| upload.mime_type.starts_with(family))
+    {
+        "inline"
+    } else {
+        "attachment"
+    };
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, upload.mime_type.clone()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("{disposition}; filename=\"{}\"", upload.file_name),
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        contents,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blobs::LocalBlobStore;
+    use crate::config::NexisConfig;
+    use crate::db::Storage;
+    use crate::router::test_support::*;
+    use crate::router::{routes, AppState};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn uploads_attach_to_messages_and_download_with_signed_urls() {
+        let root = std::env::temp_dir().join(format!("nexis-uploads-{}", Uuid::new_v4()));
+        let mut config = NexisConfig::default();
+        config.uploads.max_bytes = 16;
+        let app = routes(AppState {
+            config: Arc::new(config),
+            blobs: Some(Arc::new(LocalBlobStore::new(&root))),
+            ..AppState::default()
+        });
+        let upload = |member: &str, mime_type: &str, contents: &str| {
+            let body = format!(
+                "--XBOUNDARY\r\nContent-Disposition: form-data; name=\"file\"; \
+                 filename=\"../notes.txt\"\r\nContent-Type: {mime_type}\r\n\r\n{contents}\r\n\
+                 --XBOUNDARY--\r\n"
+            );
+            let content_type = "multipart/form-data; boundary=XBOUNDARY";
+            raw_request(member, "POST", "/v1/uploads", content_type, body)
+        };
+
+        let response = app
+            .clone()
+            .oneshot(upload(
+                "nexis:human:alice",
+                "application/x-msdownload",
+                "MZ",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let response = app
+            .clone()
+            .oneshot(upload(
+                "nexis:human:alice",
+                "text/plain",
+                "far more than sixteen bytes",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = app
+            .clone()
+            .oneshot(upload("nexis:human:alice", "text/plain", "hello"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let uploaded = json_body(response).await;
+        assert_eq!(uploaded["fileName"], "notes.txt");
+        assert_eq!(uploaded["size"], 5);
+        let upload_id = uploaded["id"].as_str().unwrap().to_string();
+        let download_url = uploaded["downloadUrl"].as_str().unwrap().to_string();
+
+        let room_id = new_room(&app, "nexis:human:alice", "files").await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:bob",
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": "nexis:human:bob", "text": "", "attachments": [upload_id] }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "POST",
+                "/v1/messages",
+                json!({ "roomId": room_id, "sender": "nexis:human:alice", "text": "", "attachments": [upload_id] }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "nexis:human:alice",
+                "GET",
+                &format!("/v1/rooms/{room_id}"),
+                Value::Null,
+            ))
+            .await
+            .unwrap();
+        let room = json_body(response).await;
+        assert_eq!(room["messages"][0]["attachments"][0]["id"], upload_id);
+        assert_eq!(
+            room["messages"][0]["attachments"][0]["mimeType"],
+            "text/plain"
+        );
+
+        let response = app
+            .clone()
+            .oneshot(Request::get(&download_url).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/plain");
+        assert_eq!(
+            response.headers()["content-disposition"],
+            "attachment; filename=\"notes.txt\""
+        );
+        assert_eq!(text_body(response).await, "hello");
+
+        let tampered = download_url.replace(&upload_id, "upl_other");
+        let response = app
+            .clone()
+            .oneshot(Request::get(&tampered).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let expired = format!(
+            "/v1/uploads/{upload_id}/content?expires=1&signature={}",
+            crate::blobs::sign_download(&NexisConfig::default().auth.jwt_secret, &upload_id, 1)
+        );
+        let response = app
+            .oneshot(Request::get(&expired).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn posted_uploads_are_only_shown_in_their_room_and_survive_restarts() {
+        let root = std::env::temp_dir().join(format!("nexis-uploads-{}", Uuid::new_v4()));
+        let mut config = NexisConfig::default();
+        let admin = "nexis:human:admin";
+        config.auth.admin_members = vec![admin.to_string()];
+        let config = Arc::new(config);
+        let storage = Storage::default();
+        let app = |storage: Storage| {
+            let state = AppState {
+                config: config.clone(),
+                blobs: Some(Arc::new(LocalBlobStore::new(&root))),
+                ..AppState::default()
+            };
+            routes(state.with_storage(storage))
+        };
+        let status = |app: Router, member: &'static str, method: &str, uri: String, body: Value| {
+            let request = request(member, method, &uri, body);
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+
+        let first = app(storage.clone());
+        let body = "--XBOUNDARY\r\nContent-Disposition: form-data; name=\"file\"; \
+                    filename=\"plan.txt\"\r\nContent-Type: text/plain\r\n\r\nsecret\r\n\
+                    --XBOUNDARY--\r\n";
+        let response = first
+            .clone()
+            .oneshot(raw_request(
+                admin,
+                "POST",
+                "/v1/uploads",
+                "multipart/form-data; boundary=XBOUNDARY",
+                body.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let upload_id = json_body(response).await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let upload_uri = format!("/v1/uploads/{upload_id}");
+
+        let room_id = invite_only_room(&first, admin, "plans").await;
+        let invite = json!({ "memberId": "nexis:human:alice" });
+        let invite_uri = format!("/v1/rooms/{room_id}/invite");
+        assert_eq!(
+            status(first.clone(), admin, "POST", invite_uri, invite).await,
+            StatusCode::OK
+        );
+        // Nobody else sees a file before it is posted.
+        assert_eq!(
+            status(
+                first.clone(),
+                "nexis:human:alice",
+                "GET",
+                upload_uri.clone(),
+                Value::Null
+            )
+            .await,
+            StatusCode::NOT_FOUND
+        );
+        let message = |room_id: &str| json!({ "roomId": room_id, "sender": admin, "text": "", "attachments": [upload_id] });
+        assert_eq!(
+            status(
+                first.clone(),
+                admin,
+                "POST",
+                "/v1/messages".to_string(),
+                message(&room_id)
+            )
+            .await,
+            StatusCode::CREATED
+        );
+        // Posted files stay in the room they were posted in.
+        let other_room = new_room(&first, admin, "elsewhere").await;
+        assert_eq!(
+            status(
+                first.clone(),
+                admin,
+                "POST",
+                "/v1/messages".to_string(),
+                message(&other_room)
+            )
+            .await,
+            StatusCode::BAD_REQUEST
+        );
+
+        // A restarted gateway still knows the file and where it was posted.
+        let restored = Storage {
+            restored_memberships: storage.memberships.list().await.unwrap(),
+            memberships: storage.memberships.clone(),
+            restored_settings: storage.room_settings.list().await.unwrap(),
+            room_settings: storage.room_settings.clone(),
+            restored_uploads: storage.uploads.list().await.unwrap(),
+            uploads: storage.uploads.clone(),
+            ..Storage::restore(storage.rooms.clone(), storage.messages.clone(), 10)
+                .await
+                .unwrap()
+        };
+        let second = app(restored);
+        assert_eq!(
+            status(
+                second.clone(),
+                admin,
+                "GET",
+                upload_uri.clone(),
+                Value::Null
+            )
+            .await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(
+                second.clone(),
+                "nexis:human:alice",
+                "GET",
+                upload_uri.clone(),
+                Value::Null
+            )
+            .await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(
+                second.clone(),
+                "nexis:human:bob",
+                "GET",
+                upload_uri.clone(),
+                Value::Null
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+
+        // Deleting the room drops the file with it.
+        assert_eq!(
+            status(
+                second.clone(),
+                admin,
+                "DELETE",
+                format!("/v1/rooms/{room_id}"),
+                Value::Null
+            )
+            .await,
+            StatusCode::NO_CONTENT
+        );
+        assert!(storage.uploads.list().await.unwrap().is_empty());
+        assert_eq!(
+            status(second, admin, "GET", upload_uri, Value::Null).await,
+            StatusCode::NOT_FOUND
+        );
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
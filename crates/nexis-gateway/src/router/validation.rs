@@ -0,0 +1,114 @@
+//! Request validation shared by the handlers.
+//!
+//! Handlers parse raw request fields into protocol types before touching
+//! state. Every field that fails is collected, and the request is rejected
+//! once with `422 Unprocessable Entity` listing each field and why it was
+//! refused.
+
+use std::fmt;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use nexis_protocol::{MemberId, MessageContent};
+use serde::Serialize;
+
+use super::error_codes;
+
+/// One rejected request field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(super) struct FieldError {
+    /// JSON name of the field, e.g. `roomId` or `messages[2].sender`.
+    pub(super) field: String,
+    pub(super) message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ValidationErrorResponse {
+    error: String,
+    code: &'static str,
+    details: Vec<FieldError>,
+}
+
+/// Collects field errors while a handler parses its request.
+#[derive(Debug, Default)]
+pub(super) struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the parsed value, or record why `field` was rejected.
+    pub(super) fn field<T, E: fmt::Display>(
+        &mut self,
+        field: impl Into<String>,
+        parsed: Result<T, E>,
+    ) -> Option<T> {
+        match parsed {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.reject(field, err.to_string());
+                None
+            }
+        }
+    }
+
+    pub(super) fn reject(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// The values parsed from the request, or the 422 response to return
+    /// when any field was rejected.
+    #[allow(clippy::result_large_err)]
+    pub(super) fn finish<T>(self, parsed: Option<T>) -> Result<T, Response> {
+        if let (true, Some(parsed)) = (self.errors.is_empty(), parsed) {
+            return Ok(parsed);
+        }
+        let error = match self.errors.as_slice() {
+            [only] => format!("{}: {}", only.field, only.message),
+            errors => format!("{} fields are invalid", errors.len()),
+        };
+        Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ValidationErrorResponse {
+                error,
+                code: error_codes::VALIDATION_FAILED,
+                details: self.errors,
+            }),
+        )
+            .into_response())
+    }
+}
+
+/// Parse a sender into a [`MemberId`], e.g. `nexis:human:alice@example.com`.
+pub(super) fn member_id(value: &str) -> Result<MemberId, String> {
+    value
+        .parse::<MemberId>()
+        .map_err(|err| format!("must be a member id like 'nexis:human:alice': {err}"))
+}
+
+/// Parse message text into [`MessageContent::Text`]. Text may be empty only
+/// when the message carries attachments, which yields `None`.
+pub(super) fn text_content(
+    text: &str,
+    has_attachments: bool,
+) -> Result<Option<MessageContent>, String> {
+    if text.trim().is_empty() {
+        return if has_attachments {
+            Ok(None)
+        } else {
+            Err("must not be empty unless attachments are sent".to_string())
+        };
+    }
+    Ok(Some(MessageContent::Text {
+        text: text.to_string(),
+    }))
+}
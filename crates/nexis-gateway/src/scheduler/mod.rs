@@ -0,0 +1,626 @@
+//! Scheduled per-room jobs.
+//!
+//! A [`ScheduledJob`] runs a [`JobKind`] in one room on a [`Schedule`]. The
+//! [`Scheduler`] keeps every job in a [`ScheduleStore`] so schedules survive
+//! restarts, and the loop started by [`Scheduler::spawn`] hands due jobs to a
+//! [`JobRunner`] once per tick.
+//!
+//! Runs missed while the gateway was down are collapsed: with catch-up on, a
+//! job that came due runs once on the first tick after startup; with it off,
+//! the job skips straight to its next future run. Either way the next run is
+//! computed from the time the job actually ran, so a failing job does not
+//! retry until its following slot.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::config::SchedulerConfig;
+use crate::server::ShutdownController;
+
+/// Shortest accepted interval between two runs of the same job.
+pub const MIN_INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Error)]
+pub enum SchedulerError {
+    #[error("invalid schedule: {0}")]
+    InvalidSchedule(String),
+    #[error("scheduled job not found")]
+    NotFound,
+    #[error("schedule store error: {0}")]
+    Store(String),
+    #[error("job failed: {0}")]
+    Job(String),
+}
+
+/// When a job runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Schedule {
+    /// Once a day at `at`, written `HH:MM` in UTC.
+    Daily { at: String },
+    /// Every `every_secs` seconds, counted from the previous run.
+    Interval {
+        #[serde(rename = "everySecs")]
+        every_secs: u64,
+    },
+}
+
+impl Schedule {
+    pub fn validate(&self) -> Result<(), SchedulerError> {
+        match self {
+            Self::Daily { at } => parse_time_of_day(at).map(|_| ()),
+            Self::Interval { every_secs } if *every_secs < MIN_INTERVAL_SECS => {
+                Err(SchedulerError::InvalidSchedule(format!(
+                    "everySecs must be at least {MIN_INTERVAL_SECS}"
+                )))
+            }
+            Self::Interval { .. } => Ok(()),
+        }
+    }
+
+    /// First run strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Self::Daily { at } => {
+                // Validated when the job was created.
+                let at = parse_time_of_day(at).unwrap_or(NaiveTime::MIN);
+                let today = after.date_naive().and_time(at).and_utc();
+                if today > after {
+                    today
+                } else {
+                    today + chrono::Duration::days(1)
+                }
+            }
+            Self::Interval { every_secs } => {
+                after + chrono::Duration::seconds(i64::try_from(*every_secs).unwrap_or(i64::MAX))
+            }
+        }
+    }
+}
+
+fn parse_time_of_day(value: &str) -> Result<NaiveTime, SchedulerError> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").map_err(|_| {
+        SchedulerError::InvalidSchedule(format!("`{value}` is not a HH:MM time of day"))
+    })
+}
+
+/// What a job does when it runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum JobKind {
+    /// Post a summary of the messages written since the previous run.
+    DailySummary,
+    /// Post a reminder when nobody has written in the room for `idle_secs`.
+    InactivityReminder {
+        #[serde(rename = "idleSecs")]
+        idle_secs: u64,
+        /// Reminder text; a generic nudge is posted when unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+    /// Remove messages older than `max_age_secs`, and the oldest ones beyond
+    /// the newest `max_messages`.
+    MessageRetention {
+        #[serde(
+            rename = "maxAgeSecs",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        max_age_secs: Option<u64>,
+        #[serde(
+            rename = "maxMessages",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        max_messages: Option<usize>,
+        #[serde(default)]
+        action: RetentionAction,
+    },
+}
+
+/// What message retention does to a message it removes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionAction {
+    /// Delete the message.
+    #[default]
+    Delete,
+    /// Keep the message's id, sender and time but drop its content, so
+    /// replies and read markers still point somewhere.
+    Tombstone,
+}
+
+impl JobKind {
+    pub fn validate(&self) -> Result<(), SchedulerError> {
+        match self {
+            Self::InactivityReminder { idle_secs: 0, .. } => Err(SchedulerError::InvalidSchedule(
+                "idleSecs must be greater than zero".to_string(),
+            )),
+            Self::MessageRetention {
+                max_age_secs: None,
+                max_messages: None,
+                ..
+            } => Err(SchedulerError::InvalidSchedule(
+                "one of maxAgeSecs and maxMessages is required".to_string(),
+            )),
+            Self::MessageRetention {
+                max_age_secs: Some(0),
+                ..
+            }
+            | Self::MessageRetention {
+                max_messages: Some(0),
+                ..
+            } => Err(SchedulerError::InvalidSchedule(
+                "maxAgeSecs and maxMessages must be greater than zero".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledJob {
+    pub id: String,
+    pub room_id: String,
+    pub job: JobKind,
+    pub schedule: Schedule,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub next_run_at: DateTime<Utc>,
+}
+
+/// Runs due jobs; implemented by the gateway router.
+#[async_trait]
+pub trait JobRunner: Send + Sync {
+    async fn run(&self, job: &ScheduledJob, now: DateTime<Utc>) -> Result<(), SchedulerError>;
+}
+
+/// Durable home of the job list, written as a whole after every change.
+#[async_trait]
+pub trait ScheduleStore: Send + Sync {
+    async fn load(&self) -> Result<Vec<ScheduledJob>, SchedulerError>;
+    async fn save(&self, jobs: &[ScheduledJob]) -> Result<(), SchedulerError>;
+}
+
+/// Keeps nothing; schedules are lost on restart.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct InMemoryScheduleStore;
+
+#[async_trait]
+impl ScheduleStore for InMemoryScheduleStore {
+    async fn load(&self) -> Result<Vec<ScheduledJob>, SchedulerError> {
+        Ok(Vec::new())
+    }
+
+    async fn save(&self, _jobs: &[ScheduledJob]) -> Result<(), SchedulerError> {
+        Ok(())
+    }
+}
+
+/// Keeps the job list in a JSON file, replaced atomically on every save.
+pub struct FileScheduleStore {
+    path: PathBuf,
+    /// Serializes writers so an older snapshot never replaces a newer one.
+    write: Mutex<()>,
+}
+
+impl FileScheduleStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            write: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl ScheduleStore for FileScheduleStore {
+    async fn load(&self) -> Result<Vec<ScheduledJob>, SchedulerError> {
+        match tokio::fs::read(&self.path).await {
+            Ok(raw) => serde_json::from_slice(&raw)
+                .map_err(|err| SchedulerError::Store(format!("{}: {err}", self.path.display()))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(SchedulerError::Store(format!(
+                "{}: {err}",
+                self.path.display()
+            ))),
+        }
+    }
+
+    async fn save(&self, jobs: &[ScheduledJob]) -> Result<(), SchedulerError> {
+        let store_error =
+            |err: std::io::Error| SchedulerError::Store(format!("{}: {err}", self.path.display()));
+        let raw = serde_json::to_vec_pretty(jobs)
+            .map_err(|err| SchedulerError::Store(err.to_string()))?;
+        let _guard = self.write.lock().await;
+        if let Some(parent) = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(store_error)?;
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        tokio::fs::write(&tmp, raw).await.map_err(store_error)?;
+        tokio::fs::rename(&tmp, &self.path)
+            .await
+            .map_err(store_error)
+    }
+}
+
+/// Shared handle to the scheduled jobs of every room.
+#[derive(Clone)]
+pub struct Scheduler {
+    jobs: Arc<RwLock<BTreeMap<String, ScheduledJob>>>,
+    store: Arc<dyn ScheduleStore>,
+    catch_up: bool,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new(Arc::new(InMemoryScheduleStore), true)
+    }
+}
+
+impl Scheduler {
+    pub fn new(store: Arc<dyn ScheduleStore>, catch_up: bool) -> Self {
+        Self {
+            jobs: Arc::new(RwLock::new(BTreeMap::new())),
+            store,
+            catch_up,
+        }
+    }
+
+    /// Scheduler backed by `[scheduler] path`, or by memory when it is unset.
+    pub fn from_config(config: &SchedulerConfig) -> Self {
+        let store: Arc<dyn ScheduleStore> = match &config.path {
+            Some(path) => Arc::new(FileScheduleStore::new(path)),
+            None => Arc::new(InMemoryScheduleStore),
+        };
+        Self::new(store, config.catch_up)
+    }
+
+    /// Read persisted jobs, keeping any created since startup, and apply the
+    /// catch-up policy to runs missed before `now`. Returns how many jobs
+    /// were loaded.
+    pub async fn load(&self, now: DateTime<Utc>) -> Result<usize, SchedulerError> {
+        let loaded = self.store.load().await?;
+        let count = loaded.len();
+        let mut jobs = self.jobs.write().await;
+        for mut job in loaded {
+            if !self.catch_up && job.next_run_at <= now {
+                job.next_run_at = job.schedule.next_after(now);
+            }
+            jobs.entry(job.id.clone()).or_insert(job);
+        }
+        Ok(count)
+    }
+
+    pub async fn create(
+        &self,
+        room_id: impl Into<String>,
+        job: JobKind,
+        schedule: Schedule,
+        created_by: impl Into<String>,
+    ) -> Result<ScheduledJob, SchedulerError> {
+        job.validate()?;
+        schedule.validate()?;
+        let now = Utc::now();
+        let job = ScheduledJob {
+            id: format!("job_{}", Uuid::new_v4().simple()),
+            room_id: room_id.into(),
+            job,
+            next_run_at: schedule.next_after(now),
+            schedule,
+            created_by: created_by.into(),
+            created_at: now,
+            last_run_at: None,
+        };
+
+        let mut jobs = self.jobs.write().await;
+        jobs.insert(job.id.clone(), job.clone());
+        if let Err(err) = self.persist(&jobs).await {
+            jobs.remove(&job.id);
+            return Err(err);
+        }
+        Ok(job)
+    }
+
+    /// Jobs of `room_id`, ordered by their next run.
+    pub async fn list(&self, room_id: &str) -> Vec<ScheduledJob> {
+        let mut jobs: Vec<ScheduledJob> = self
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|job| job.room_id == room_id)
+            .cloned()
+            .collect();
+        jobs.sort_by_key(|job| job.next_run_at);
+        jobs
+    }
+
+    pub async fn get(&self, id: &str) -> Option<ScheduledJob> {
+        self.jobs.read().await.get(id).cloned()
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<ScheduledJob, SchedulerError> {
+        let mut jobs = self.jobs.write().await;
+        let removed = jobs.remove(id).ok_or(SchedulerError::NotFound)?;
+        if let Err(err) = self.persist(&jobs).await {
+            jobs.insert(removed.id.clone(), removed);
+            return Err(err);
+        }
+        Ok(removed)
+    }
+
+    /// Drop every job of a deleted room.
+    pub async fn delete_room(&self, room_id: &str) -> Result<usize, SchedulerError> {
+        let mut jobs = self.jobs.write().await;
+        let before = jobs.len();
+        jobs.retain(|_, job| job.room_id != room_id);
+        let removed = before - jobs.len();
+        if removed > 0 {
+            self.persist(&jobs).await?;
+        }
+        Ok(removed)
+    }
+
+    /// Run every job due at `now` and move it to its next run. Returns how
+    /// many jobs ran, including ones that failed.
+    pub async fn run_due(&self, now: DateTime<Utc>, runner: &dyn JobRunner) -> usize {
+        let due: Vec<ScheduledJob> = self
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|job| job.next_run_at <= now)
+            .cloned()
+            .collect();
+        if due.is_empty() {
+            return 0;
+        }
+
+        for job in &due {
+            if let Err(err) = runner.run(job, now).await {
+                tracing::warn!(
+                    job_id = %job.id,
+                    room_id = %job.room_id,
+                    "Scheduled job failed: {}",
+                    err
+                );
+            }
+        }
+
+        let mut jobs = self.jobs.write().await;
+        for ran in &due {
+            // Deleted while it was running.
+            if let Some(job) = jobs.get_mut(&ran.id) {
+                job.last_run_at = Some(now);
+                job.next_run_at = job.schedule.next_after(now);
+            }
+        }
+        if let Err(err) = self.persist(&jobs).await {
+            tracing::error!("Failed to persist schedules: {}", err);
+        }
+        due.len()
+    }
+
+    async fn persist(&self, jobs: &BTreeMap<String, ScheduledJob>) -> Result<(), SchedulerError> {
+        let jobs: Vec<ScheduledJob> = jobs.values().cloned().collect();
+        self.store.save(&jobs).await
+    }
+
+    /// Load persisted jobs, then run due jobs every `tick` until `shutdown`
+    /// is triggered.
+    pub fn spawn(
+        self,
+        runner: Arc<dyn JobRunner>,
+        tick: Duration,
+        shutdown: ShutdownController,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            match self.load(Utc::now()).await {
+                Ok(count) => tracing::info!("Loaded {} scheduled job(s)", count),
+                Err(err) => tracing::error!("Failed to load schedules: {}", err),
+            }
+
+            let stopped = shutdown.triggered();
+            tokio::pin!(stopped);
+            let mut interval = tokio::time::interval(tick);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    _ = interval.tick() => {
+                        self.run_due(Utc::now(), runner.as_ref()).await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as StdMutex;
+
+    #[derive(Default)]
+    struct RecordingRunner {
+        ran: StdMutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl JobRunner for RecordingRunner {
+        async fn run(&self, job: &ScheduledJob, _now: DateTime<Utc>) -> Result<(), SchedulerError> {
+            self.ran.lock().unwrap().push(job.id.clone());
+            Err(SchedulerError::Job("always fails".to_string()))
+        }
+    }
+
+    fn at(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn schedules_compute_their_next_run() {
+        let daily = Schedule::Daily {
+            at: "09:30".to_string(),
+        };
+        assert_eq!(
+            daily.next_after(at("2026-03-01T08:00:00Z")),
+            at("2026-03-01T09:30:00Z")
+        );
+        assert_eq!(
+            daily.next_after(at("2026-03-01T09:30:00Z")),
+            at("2026-03-02T09:30:00Z")
+        );
+        let interval = Schedule::Interval { every_secs: 3600 };
+        assert_eq!(
+            interval.next_after(at("2026-03-01T08:15:00Z")),
+            at("2026-03-01T09:15:00Z")
+        );
+
+        assert!(Schedule::Daily {
+            at: "25:00".to_string()
+        }
+        .validate()
+        .is_err());
+        assert!(Schedule::Interval { every_secs: 5 }.validate().is_err());
+        assert!(JobKind::InactivityReminder {
+            idle_secs: 0,
+            message: None
+        }
+        .validate()
+        .is_err());
+
+        let parsed: Schedule =
+            serde_json::from_value(serde_json::json!({ "type": "interval", "everySecs": 600 }))
+                .unwrap();
+        assert_eq!(parsed, Schedule::Interval { every_secs: 600 });
+    }
+
+    #[test]
+    fn message_retention_needs_a_positive_limit() {
+        let retention = |max_age_secs, max_messages| JobKind::MessageRetention {
+            max_age_secs,
+            max_messages,
+            action: RetentionAction::Delete,
+        };
+        assert!(retention(None, None).validate().is_err());
+        assert!(retention(Some(0), Some(10)).validate().is_err());
+        assert!(retention(Some(86_400), Some(0)).validate().is_err());
+        assert!(retention(Some(86_400), None).validate().is_ok());
+
+        let parsed: JobKind = serde_json::from_value(serde_json::json!({
+            "type": "message_retention",
+            "maxMessages": 500,
+            "action": "tombstone",
+        }))
+        .unwrap();
+        assert_eq!(
+            parsed,
+            JobKind::MessageRetention {
+                max_age_secs: None,
+                max_messages: Some(500),
+                action: RetentionAction::Tombstone,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn due_jobs_run_once_and_move_to_their_next_slot() {
+        let scheduler = Scheduler::default();
+        let job = scheduler
+            .create(
+                "room_a",
+                JobKind::DailySummary,
+                Schedule::Interval { every_secs: 600 },
+                "admin",
+            )
+            .await
+            .unwrap();
+        let runner = RecordingRunner::default();
+
+        assert_eq!(scheduler.run_due(Utc::now(), &runner).await, 0);
+        let later = job.next_run_at + chrono::Duration::seconds(1);
+        assert_eq!(scheduler.run_due(later, &runner).await, 1);
+        assert_eq!(scheduler.run_due(later, &runner).await, 0);
+
+        let job = scheduler.get(&job.id).await.unwrap();
+        assert_eq!(job.last_run_at, Some(later));
+        assert_eq!(job.next_run_at, later + chrono::Duration::seconds(600));
+        assert_eq!(runner.ran.lock().unwrap().len(), 1);
+
+        assert_eq!(scheduler.delete_room("room_a").await.unwrap(), 1);
+        assert!(scheduler.list("room_a").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn file_store_survives_restart_with_catch_up() {
+        let path = std::env::temp_dir().join(format!("nexis-schedules-{}.json", Uuid::new_v4()));
+        let store = Arc::new(FileScheduleStore::new(&path));
+        let scheduler = Scheduler::new(store.clone(), true);
+        let job = scheduler
+            .create(
+                "room_a",
+                JobKind::InactivityReminder {
+                    idle_secs: 3600,
+                    message: Some("Anyone around?".to_string()),
+                },
+                Schedule::Daily {
+                    at: "09:00".to_string(),
+                },
+                "admin",
+            )
+            .await
+            .unwrap();
+
+        // Restart three days later: the missed runs collapse into one.
+        let now = job.next_run_at + chrono::Duration::days(3);
+        let restarted = Scheduler::new(store.clone(), true);
+        assert_eq!(restarted.load(now).await.unwrap(), 1);
+        assert_eq!(restarted.list("room_a").await, vec![job.clone()]);
+
+        // Without catch-up the missed run is skipped instead.
+        let skipping = Scheduler::new(store, false);
+        skipping.load(now).await.unwrap();
+        let runner = RecordingRunner::default();
+        assert_eq!(skipping.run_due(now, &runner).await, 0);
+        assert!(skipping.get(&job.id).await.unwrap().next_run_at > now);
+
+        assert_eq!(restarted.run_due(now, &runner).await, 1);
+        assert_eq!(restarted.run_due(now, &runner).await, 0);
+
+        restarted.delete(&job.id).await.unwrap();
+        assert!(matches!(
+            restarted.delete(&job.id).await,
+            Err(SchedulerError::NotFound)
+        ));
+        let persisted = FileScheduleStore::new(&path).load().await.unwrap();
+        assert!(persisted.is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+}
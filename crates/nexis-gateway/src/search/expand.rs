@@ -0,0 +1,316 @@
+//! Query expansion and typo normalization
+//!
+//! Short queries miss messages that word things differently ("bug" against
+//! "issue") or were typed with a slip ("deplyo"). Searches with `expand` set
+//! ask a [`QueryExpander`] for extra terms, search for the query together
+//! with them, and return the terms in the response so callers can see what
+//! was searched for.
+//!
+//! [`RuleExpander`] corrects words one edit away from a known term and adds
+//! that term's synonyms from a table; [`LlmExpander`] asks a chat model.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use nexis_runtime::{AIProvider, GenerateRequest};
+
+use super::service::SearchError;
+
+/// Most terms one query is expanded with.
+const MAX_TERMS: usize = 8;
+
+/// Suggests terms to search for alongside a query
+#[async_trait]
+pub trait QueryExpander: Send + Sync {
+    /// Name reported in logs
+    fn name(&self) -> &'static str;
+
+    /// Corrected spellings and synonyms for `query`, lowercase, none of
+    /// them words of the query itself
+    async fn expand(&self, query: &str) -> Result<Vec<String>, SearchError>;
+}
+
+/// Synonym groups [`RuleExpander::default`] starts with.
+const SYNONYMS: &[&[&str]] = &[
+    &["bug", "issue", "defect", "problem"],
+    &["error", "failure", "exception", "crash"],
+    &["deploy", "deployment", "release", "rollout"],
+    &["meeting", "standup", "sync", "call"],
+    &["docs", "documentation", "guide", "manual"],
+    &["login", "signin", "authentication", "auth"],
+    &["config", "configuration", "settings"],
+    &["delete", "remove"],
+    &["fix", "patch", "hotfix"],
+    &["slow", "latency", "lag"],
+    &["invoice", "bill", "payment"],
+];
+
+/// Table-driven expansion: typo correction against known terms, then
+/// synonyms
+pub struct RuleExpander {
+    /// Each known term to the other members of its synonym groups
+    synonyms: HashMap<String, Vec<String>>,
+}
+
+impl Default for RuleExpander {
+    fn default() -> Self {
+        SYNONYMS.iter().fold(Self::empty(), |expander, group| {
+            expander.with_synonyms(group)
+        })
+    }
+}
+
+impl RuleExpander {
+    /// Expander without any synonyms
+    pub fn empty() -> Self {
+        Self {
+            synonyms: HashMap::new(),
+        }
+    }
+
+    /// Treat the words of `group` as synonyms of each other
+    pub fn with_synonyms(mut self, group: &[&str]) -> Self {
+        for word in group {
+            let word = word.to_lowercase();
+            let others = self.synonyms.entry(word.clone()).or_default();
+            for other in group {
+                let other = other.to_lowercase();
+                if other != word && !others.contains(&other) {
+                    others.push(other);
+                }
+            }
+        }
+        self
+    }
+
+    /// `word` itself if it is a known term, else the known term it is a
+    /// likely typo of
+    fn known(&self, word: &str) -> Option<String> {
+        if self.synonyms.contains_key(word) {
+            return Some(word.to_string());
+        }
+        // One-letter slips in short words too often land on another word.
+        if word.chars().count() < 4 {
+            return None;
+        }
+        let mut candidates: Vec<&String> = self
+            .synonyms
+            .keys()
+            .filter(|term| edit_distance(word, term) == 1)
+            .collect();
+        candidates.sort();
+        candidates.first().map(|term| term.to_string())
+    }
+}
+
+#[async_trait]
+impl QueryExpander for RuleExpander {
+    fn name(&self) -> &'static str {
+        "rules"
+    }
+
+    async fn expand(&self, query: &str) -> Result<Vec<String>, SearchError> {
+        let words = query_words(query);
+        let mut terms = Vec::new();
+        for word in &words {
+            let Some(known) = self.known(&collapse_repeats(word)) else {
+                continue;
+            };
+            let synonyms = self.synonyms.get(&known).into_iter().flatten();
+            for term in std::iter::once(&known).chain(synonyms) {
+                if !words.contains(term) && !terms.contains(term) {
+                    terms.push(term.clone());
+                }
+            }
+        }
+        terms.truncate(MAX_TERMS);
+        Ok(terms)
+    }
+}
+
+/// Asks a chat model for corrected spellings and synonyms
+pub struct LlmExpander {
+    provider: Arc<dyn AIProvider>,
+    model: Option<String>,
+}
+
+impl LlmExpander {
+    /// Expand with `provider`'s default model
+    pub fn new(provider: Arc<dyn AIProvider>) -> Self {
+        Self {
+            provider,
+            model: None,
+        }
+    }
+
+    /// Expand with `model` instead of the provider's default
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    fn prompt(query: &str) -> String {
+        format!(
+            "Suggest up to {MAX_TERMS} extra search terms for the chat search query below: \
+             corrections of misspelled words and close synonyms. Reply with one term per \
+             line and nothing else; reply with nothing if the query needs none.\n\n\
+             Query: {query}"
+        )
+    }
+
+    /// Terms from the model's reply, without list markers, duplicates or
+    /// words of the query
+    fn parse(reply: &str, query: &str) -> Vec<String> {
+        let words = query_words(query);
+        let mut terms: Vec<String> = Vec::new();
+        for line in reply.lines() {
+            let term = strip_list_marker(line).to_lowercase();
+            if !term.is_empty() && !words.contains(&term) && !terms.contains(&term) {
+                terms.push(term);
+            }
+        }
+        terms.truncate(MAX_TERMS);
+        terms
+    }
+}
+
+#[async_trait]
+impl QueryExpander for LlmExpander {
+    fn name(&self) -> &'static str {
+        "llm"
+    }
+
+    #[tracing::instrument(name = "search.expand.llm", skip_all)]
+    async fn expand(&self, query: &str) -> Result<Vec<String>, SearchError> {
+        let request = GenerateRequest {
+            prompt: Self::prompt(query),
+            model: self.model.clone(),
+            max_tokens: Some(64),
+            temperature: Some(0.0),
+            metadata: None,
+            images: Vec::new(),
+        };
+        let response = self
+            .provider
+            .generate(request)
+            .await
+            .map_err(|e| SearchError::ExpansionError(e.to_string()))?;
+        Ok(Self::parse(&response.content, query))
+    }
+}
+
+/// `line` without a leading `-`, `*`, `•`, `1.` or `1)`
+fn strip_list_marker(line: &str) -> &str {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix(['-', '*', '•']) {
+        return rest.trim();
+    }
+    let digits = line
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(line.len());
+    match line[digits..].strip_prefix(['.', ')']) {
+        Some(rest) if digits > 0 => rest.trim(),
+        _ => line,
+    }
+}
+
+/// Lowercase words of `query`
+fn query_words(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// `word` with runs of three or more of a letter cut to two ("helllo")
+fn collapse_repeats(word: &str) -> String {
+    let mut collapsed = String::with_capacity(word.len());
+    let mut run = 0;
+    let mut previous = None;
+    for c in word.chars() {
+        run = if Some(c) == previous { run + 1 } else { 1 };
+        previous = Some(c);
+        if run <= 2 {
+            collapsed.push(c);
+        }
+    }
+    collapsed
+}
+
+/// Edits (insertions, deletions, substitutions and swaps of adjacent
+/// letters) turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rules_correct_typos_and_add_synonyms() {
+        let expander = RuleExpander::default();
+
+        let terms = expander.expand("Deplyo failed").await.unwrap();
+        assert_eq!(terms, ["deploy", "deployment", "release", "rollout"]);
+
+        let terms = expander.expand("login bug").await.unwrap();
+        assert!(terms.contains(&"authentication".to_string()));
+        assert!(terms.contains(&"issue".to_string()));
+        assert!(!terms.contains(&"login".to_string()));
+        assert!(terms.len() <= MAX_TERMS);
+
+        assert!(expander.expand("lunch plans").await.unwrap().is_empty());
+        assert_eq!(
+            expander.expand("sloooow").await.unwrap(),
+            ["slow", "latency", "lag"]
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_synonyms_extend_the_table() {
+        let expander = RuleExpander::empty().with_synonyms(&["k8s", "Kubernetes"]);
+        assert_eq!(expander.expand("k8s").await.unwrap(), ["kubernetes"]);
+        assert_eq!(
+            expander.expand("kubernetse").await.unwrap(),
+            ["kubernetes", "k8s"]
+        );
+    }
+
+    #[test]
+    fn llm_terms_are_cleaned_up() {
+        let reply = "1. Release\n- deploy\n\n* rollout\n2fa\nrelease";
+        let terms = LlmExpander::parse(reply, "deploy");
+        assert_eq!(terms, ["release", "rollout", "2fa"]);
+        assert!(LlmExpander::prompt("deplyo").ends_with("Query: deplyo"));
+    }
+
+    #[test]
+    fn edit_distance_counts_swaps_as_one_edit() {
+        assert_eq!(edit_distance("deplyo", "deploy"), 1);
+        assert_eq!(edit_distance("bug", "bugs"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
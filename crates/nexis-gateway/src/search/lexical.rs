@@ -0,0 +1,692 @@
+//! Keyword search over an in-process inverted index
+//!
+//! Used when no vector store or embedding provider is configured, so search
+//! works on a fresh gateway. Documents are split into lowercase alphanumeric
+//! terms and ranked with BM25. Scores are relative to an average-length
+//! document containing each query term once and capped at 1.0, so `min_score`
+//! roughly keeps the meaning it has for semantic search.
+
+use async_trait::async_trait;
+use nexis_protocol::RoomId;
+use nexis_vector::{Document, DocumentMetadata, SearchResult, Vector};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use super::service::{
+    can_read_result, SearchError, SearchRequest, SearchResponse, SearchResultItem, SearchService,
+    SimilarRequest,
+};
+use crate::db::IndexOutbox;
+use crate::indexing::{
+    BatchConfig, BatchingIndexingQueue, IndexTask, IndexingQueue, IndexingResult, IndexingService,
+    OutboxRelay,
+};
+use crate::server::ShutdownController;
+
+/// Tasks the outbox relay may queue ahead of the index.
+const OUTBOX_QUEUE_SIZE: usize = 256;
+
+/// BM25 term-frequency saturation.
+const K1: f32 = 1.2;
+/// BM25 document-length normalization.
+const B: f32 = 0.75;
+
+#[derive(Debug, Clone)]
+struct IndexedDocument {
+    content: String,
+    metadata: DocumentMetadata,
+    /// Number of terms in the document.
+    length: usize,
+}
+
+#[derive(Debug, Default)]
+struct LexicalIndex {
+    documents: HashMap<Uuid, IndexedDocument>,
+    /// Term to the documents containing it and how often.
+    postings: HashMap<String, HashMap<Uuid, u32>>,
+    total_length: usize,
+}
+
+impl LexicalIndex {
+    fn insert(&mut self, id: Uuid, content: String, metadata: DocumentMetadata) {
+        self.remove(id);
+        let terms = tokenize(&content);
+        self.total_length += terms.len();
+        let length = terms.len();
+        for term in terms {
+            *self
+                .postings
+                .entry(term)
+                .or_default()
+                .entry(id)
+                .or_default() += 1;
+        }
+        self.documents.insert(
+            id,
+            IndexedDocument {
+                content,
+                metadata,
+                length,
+            },
+        );
+    }
+
+    fn remove(&mut self, id: Uuid) -> bool {
+        let Some(document) = self.documents.remove(&id) else {
+            return false;
+        };
+        self.total_length -= document.length;
+        for term in tokenize(&document.content) {
+            if let Some(postings) = self.postings.get_mut(&term) {
+                postings.remove(&id);
+                if postings.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+        true
+    }
+
+    fn idf(&self, term: &str) -> f32 {
+        let total = self.documents.len() as f32;
+        let containing = self.postings.get(term).map_or(0, HashMap::len) as f32;
+        (1.0 + (total - containing + 0.5) / (containing + 0.5)).ln()
+    }
+
+    /// Documents matching `query` with scores in `0.0..=1.0`, best first.
+    fn score(&self, query: &str) -> Vec<(Uuid, f32)> {
+        let terms: HashSet<String> = tokenize(query).into_iter().collect();
+        if terms.is_empty() || self.documents.is_empty() {
+            return Vec::new();
+        }
+
+        let average_length = self.total_length as f32 / self.documents.len() as f32;
+        let mut scores: HashMap<Uuid, f32> = HashMap::new();
+        // What a document of average length scores with every term once.
+        let mut reference = 0.0;
+        for term in &terms {
+            let idf = self.idf(term);
+            reference += idf;
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            for (id, frequency) in postings {
+                let length = self.documents[id].length as f32;
+                let frequency = *frequency as f32;
+                let norm = K1 * (1.0 - B + B * length / average_length.max(1.0));
+                *scores.entry(*id).or_default() +=
+                    idf * frequency * (K1 + 1.0) / (frequency + norm);
+            }
+        }
+
+        let mut scored: Vec<(Uuid, f32)> = scores
+            .into_iter()
+            .map(|(id, score)| (id, (score / reference).min(1.0)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scored
+    }
+}
+
+/// Lowercase alphanumeric runs; everything else separates terms.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Keyword search service backed by an in-memory inverted index
+pub struct LexicalSearchService {
+    index: RwLock<LexicalIndex>,
+    default_limit: usize,
+}
+
+impl Default for LexicalSearchService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LexicalSearchService {
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self {
+            index: RwLock::new(LexicalIndex::default()),
+            default_limit: 10,
+        }
+    }
+
+    /// Set default result limit
+    pub fn with_default_limit(mut self, limit: usize) -> Self {
+        self.default_limit = limit;
+        self
+    }
+
+    /// Add or replace a document
+    pub fn index(&self, id: Uuid, content: impl Into<String>, metadata: DocumentMetadata) {
+        self.index
+            .write()
+            .expect("lexical index lock poisoned")
+            .insert(id, content.into(), metadata);
+    }
+
+    /// Remove a document; returns whether it was indexed
+    pub fn remove(&self, id: Uuid) -> bool {
+        self.index
+            .write()
+            .expect("lexical index lock poisoned")
+            .remove(id)
+    }
+
+    /// Remove every document of a room; returns how many were removed
+    pub fn remove_room(&self, room_id: &RoomId) -> usize {
+        let mut index = self.index.write().expect("lexical index lock poisoned");
+        let ids: Vec<Uuid> = index
+            .documents
+            .iter()
+            .filter(|(_, document)| document.metadata.room_id.as_ref() == Some(room_id))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &ids {
+            index.remove(*id);
+        }
+        ids.len()
+    }
+
+    /// Remove every document
+    pub fn clear(&self) {
+        *self.index.write().expect("lexical index lock poisoned") = LexicalIndex::default();
+    }
+
+    /// Number of indexed documents
+    pub fn len(&self) -> usize {
+        self.index
+            .read()
+            .expect("lexical index lock poisoned")
+            .documents
+            .len()
+    }
+
+    /// Whether nothing is indexed
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Rank documents for `request.query`, skipping those matching `exclude`.
+    fn run(&self, request: SearchRequest, exclude: impl Fn(Uuid, &str) -> bool) -> SearchResponse {
+        let limit = request.limit.unwrap_or(self.default_limit);
+        let include_content = request.include_content.unwrap_or(true);
+        let index = self.index.read().expect("lexical index lock poisoned");
+
+        let mut matches = index
+            .score(&request.query)
+            .into_iter()
+            .filter(|(_, score)| request.min_score.is_none_or(|min| *score >= min))
+            .filter_map(|(id, score)| {
+                let document = &index.documents[&id];
+                let metadata = &document.metadata;
+                let keep = request
+                    .room_id
+                    .as_ref()
+                    .is_none_or(|room| metadata.room_id.as_ref() == Some(room))
+                    && request
+                        .content_type
+                        .as_ref()
+                        .is_none_or(|kind| metadata.content_type.as_ref() == Some(kind))
+                    && request
+                        .tenant_id
+                        .as_ref()
+                        .is_none_or(|tenant| metadata.tenant_id.as_ref() == Some(tenant))
+                    && request.permissions.as_ref().is_none_or(|permissions| {
+                        can_read_result(permissions, metadata.room_id.as_ref())
+                    })
+                    && !exclude(id, &document.content);
+                keep.then(|| SearchResultItem {
+                    id,
+                    score,
+                    rerank_score: None,
+                    content: include_content.then(|| document.content.clone()),
+                    room_id: metadata.room_id.clone(),
+                    metadata: metadata.to_json(),
+                })
+            });
+
+        let items: Vec<SearchResultItem> = matches.by_ref().take(limit).collect();
+        let truncated = matches.next().is_some();
+        let response = SearchResponse::new(request.query, items);
+        if truncated {
+            response.with_truncated()
+        } else {
+            response
+        }
+    }
+}
+
+#[async_trait]
+impl SearchService for LexicalSearchService {
+    async fn search(&self, request: SearchRequest) -> Result<SearchResponse, SearchError> {
+        debug!("Keyword search for: {}", request.query);
+
+        if request.query.trim().is_empty() {
+            return Err(SearchError::InvalidQuery(
+                "Query cannot be empty".to_string(),
+            ));
+        }
+
+        Ok(self.run(request, |_, _| false))
+    }
+
+    async fn search_in_room(
+        &self,
+        query: &str,
+        room_id: RoomId,
+        limit: usize,
+    ) -> Result<SearchResponse, SearchError> {
+        let request = SearchRequest::new(query).with_limit(limit).in_room(room_id);
+        self.search(request).await
+    }
+
+    async fn similar(&self, request: SimilarRequest) -> Result<SearchResponse, SearchError> {
+        let source = request.document_id.and_then(|id| {
+            let index = self.index.read().expect("lexical index lock poisoned");
+            index.documents.get(&id).cloned()
+        });
+
+        let text = match (source, request.text) {
+            (Some(document), _) => {
+                // Don't confirm that documents the caller cannot read exist.
+                let readable = request.permissions.as_ref().is_none_or(|permissions| {
+                    can_read_result(permissions, document.metadata.room_id.as_ref())
+                }) && request
+                    .tenant_id
+                    .as_ref()
+                    .is_none_or(|tenant| document.metadata.tenant_id.as_ref() == Some(tenant));
+                if !readable {
+                    return Err(SearchError::NotFound("document".to_string()));
+                }
+                document.content
+            }
+            (None, Some(text)) if !text.trim().is_empty() => text,
+            (None, _) => return Err(SearchError::NotFound("document".to_string())),
+        };
+
+        let search_request = SearchRequest {
+            query: text.clone(),
+            limit: request.limit,
+            min_score: request.min_score,
+            room_id: request.room_id,
+            content_type: None,
+            include_content: None,
+            tenant_id: request.tenant_id,
+            permissions: request.permissions,
+            expand: false,
+        };
+        let document_id = request.document_id;
+        Ok(self.run(search_request, |id, content| {
+            Some(id) == document_id || content == text
+        }))
+    }
+}
+
+/// Document id of a stored message: the UUID in its `msg_` id, if it has
+/// one.
+pub(crate) fn document_id(message_id: &str) -> Option<Uuid> {
+    Uuid::parse_str(message_id.strip_prefix("msg_").unwrap_or(message_id)).ok()
+}
+
+/// [`IndexingService`] writing into a [`LexicalSearchService`], so stored
+/// messages can be indexed through an indexing queue.
+pub struct LexicalIndexer {
+    index: Arc<LexicalSearchService>,
+}
+
+impl LexicalIndexer {
+    /// Index into `index`
+    pub fn new(index: Arc<LexicalSearchService>) -> Self {
+        Self { index }
+    }
+
+    fn results(&self, request: SearchRequest) -> Vec<SearchResult> {
+        let response = self.index.run(request, |_, _| false);
+        let index = self
+            .index
+            .index
+            .read()
+            .expect("lexical index lock poisoned");
+        response
+            .results
+            .into_iter()
+            .filter_map(|item| {
+                let document = index.documents.get(&item.id)?;
+                let document = Document::with_id(
+                    item.id,
+                    Vector::new(Vec::new()),
+                    document.content.clone(),
+                    document.metadata.clone(),
+                );
+                Some(SearchResult::new(document, item.score))
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl IndexingService for LexicalIndexer {
+    async fn index_message(
+        &self,
+        message: &str,
+        room_id: RoomId,
+        metadata: serde_json::Value,
+    ) -> IndexingResult<Uuid> {
+        self.index_task(&IndexTask::new(message.to_string(), room_id, metadata))
+            .await
+    }
+
+    /// Documents are keyed by the task's `messageId`, falling back to the
+    /// task id, and carry its `sender`.
+    async fn index_task(&self, task: &IndexTask) -> IndexingResult<Uuid> {
+        let id = task
+            .metadata
+            .get("messageId")
+            .and_then(serde_json::Value::as_str)
+            .and_then(document_id)
+            .unwrap_or(task.id);
+        let mut metadata = DocumentMetadata::new().with_content_type("text");
+        if let Some(sender) = task.metadata.get("sender") {
+            metadata = metadata.with_extra("sender", sender.clone());
+        }
+        metadata = metadata.with_room(task.room_id.clone());
+        if let Some(tenant) = &task.tenant_id {
+            metadata = metadata.with_tenant(tenant);
+        }
+        self.index
+            .index(id, task.message.clone(), metadata.with_message(id));
+        Ok(id)
+    }
+
+    async fn search(&self, query: &str, limit: usize) -> IndexingResult<Vec<SearchResult>> {
+        Ok(self.results(SearchRequest::new(query).with_limit(limit)))
+    }
+
+    async fn search_in_room(
+        &self,
+        query: &str,
+        room_id: RoomId,
+        limit: usize,
+    ) -> IndexingResult<Vec<SearchResult>> {
+        Ok(self.results(SearchRequest::new(query).with_limit(limit).in_room(room_id)))
+    }
+}
+
+/// Keyword index together with the batching queue new messages are
+/// indexed through.
+#[derive(Clone)]
+pub struct KeywordIndexing {
+    /// Index searched when no vector store is configured
+    pub index: Arc<LexicalSearchService>,
+    /// Queue filling `index`
+    pub queue: Arc<BatchingIndexingQueue>,
+}
+
+impl KeywordIndexing {
+    /// Create an empty index and a queue batching into it with `config`
+    pub fn new(config: BatchConfig) -> Self {
+        let index = Arc::new(LexicalSearchService::new());
+        let indexer = Arc::new(LexicalIndexer::new(index.clone()));
+        let queue = Arc::new(BatchingIndexingQueue::new(indexer, config));
+        Self { index, queue }
+    }
+
+    /// Flush the queue into the index when `shutdown` drains, so messages
+    /// accepted before shutdown stay searchable.
+    pub fn drain_on(&self, shutdown: &ShutdownController) {
+        let queue = self.queue.clone();
+        shutdown.on_drain(async move { queue.shutdown().await });
+    }
+
+    /// Index the messages recorded in `outbox` every `interval` until
+    /// `shutdown` is triggered; draining waits for the relay to stop.
+    pub fn relay_outbox(
+        &self,
+        outbox: Arc<dyn IndexOutbox>,
+        interval: Duration,
+        shutdown: &ShutdownController,
+    ) {
+        let indexer = Arc::new(LexicalIndexer::new(self.index.clone()));
+        let queue = Arc::new(IndexingQueue::new(indexer, OUTBOX_QUEUE_SIZE));
+        let relay = Arc::new(OutboxRelay::new(outbox, queue));
+        let worker = relay.spawn(interval, shutdown.clone());
+        shutdown.on_drain(async move {
+            if let Err(err) = worker.await {
+                error!(error = %err, "Outbox relay panicked");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nexis_core::permission::{Action, PermissionChecker, Permissions};
+
+    fn service_with(documents: &[(Uuid, &str, &RoomId)]) -> LexicalSearchService {
+        let service = LexicalSearchService::new();
+        for (id, content, room_id) in documents {
+            service.index(
+                *id,
+                *content,
+                DocumentMetadata::new().with_room((*room_id).clone()),
+            );
+        }
+        service
+    }
+
+    #[tokio::test]
+    async fn ranks_documents_by_matching_terms() {
+        let room = RoomId::generate();
+        let (deploy, rollback, lunch) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let service = service_with(&[
+            (deploy, "Deploy finished, the deploy went fine", &room),
+            (rollback, "Rollback the deploy on staging", &room),
+            (lunch, "Lunch at noon?", &room),
+        ]);
+
+        let response = service
+            .search(SearchRequest::new("DEPLOY staging"))
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = response.results.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![rollback, deploy]);
+        assert!(response.results[0].score > response.results[1].score);
+        assert!(response.results[0].score <= 1.0);
+        assert_eq!(
+            response.results[0].content.as_deref(),
+            Some("Rollback the deploy on staging")
+        );
+
+        let strict = service
+            .search(SearchRequest::new("deploy staging").with_min_score(0.5))
+            .await
+            .unwrap();
+        assert_eq!(strict.total, 1);
+        let limited = service
+            .search(SearchRequest::new("deploy").with_limit(1))
+            .await
+            .unwrap();
+        assert!(limited.truncated);
+
+        assert!(service.remove(rollback));
+        let response = service.search(SearchRequest::new("staging")).await.unwrap();
+        assert!(response.results.is_empty());
+        assert!(matches!(
+            service.search(SearchRequest::new("  ")).await,
+            Err(SearchError::InvalidQuery(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn filters_by_room_tenant_and_permissions() {
+        let (open, hidden) = (RoomId::generate(), RoomId::generate());
+        let service = service_with(&[
+            (Uuid::new_v4(), "quarterly roadmap draft", &open),
+            (Uuid::new_v4(), "roadmap for the secret project", &hidden),
+        ]);
+        service.index(
+            Uuid::new_v4(),
+            "tenant roadmap",
+            DocumentMetadata::new()
+                .with_room(open.clone())
+                .with_tenant("acme"),
+        );
+
+        let permissions =
+            PermissionChecker::new(Permissions::new(vec![open.to_string()], vec![Action::Read]));
+        let response = service
+            .search(SearchRequest::new("roadmap").with_permissions(permissions))
+            .await
+            .unwrap();
+        assert_eq!(response.total, 2);
+        assert!(response
+            .results
+            .iter()
+            .all(|r| r.room_id.as_ref() == Some(&open)));
+
+        let response = service
+            .search(SearchRequest::new("roadmap").for_tenant("acme"))
+            .await
+            .unwrap();
+        assert_eq!(response.total, 1);
+        assert_eq!(response.results[0].metadata["tenant_id"], "acme");
+
+        let response = service.search_in_room("roadmap", hidden, 10).await.unwrap();
+        assert_eq!(response.total, 1);
+
+        assert_eq!(service.remove_room(&open), 2);
+        assert_eq!(service.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn queued_messages_are_keyed_by_message_id() {
+        let indexing = KeywordIndexing::new(BatchConfig::default().with_flush_interval_ms(5));
+        let room = RoomId::generate();
+        let id = Uuid::new_v4();
+        let task = IndexTask::new(
+            "release notes".to_string(),
+            room.clone(),
+            serde_json::json!({ "messageId": format!("msg_{id}"), "sender": "nexis:human:alice" }),
+        )
+        .with_tenant("acme");
+        indexing.queue.enqueue(task).await.unwrap();
+
+        tokio::time::timeout(std::time::Duration::from_secs(2), async {
+            while indexing.index.is_empty() {
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("message was not indexed in time");
+        let response = indexing
+            .index
+            .search(SearchRequest::new("release").for_tenant("acme"))
+            .await
+            .unwrap();
+        assert_eq!(response.total, 1);
+        assert_eq!(response.results[0].id, id);
+        assert_eq!(response.results[0].room_id.as_ref(), Some(&room));
+        assert_eq!(response.results[0].metadata["sender"], "nexis:human:alice");
+    }
+
+    #[tokio::test]
+    async fn draining_shutdown_flushes_queued_messages() {
+        let indexing = KeywordIndexing::new(BatchConfig::default().with_flush_interval_ms(60_000));
+        let shutdown = ShutdownController::new();
+        indexing.drain_on(&shutdown);
+        let task = IndexTask::new(
+            "release notes".to_string(),
+            RoomId::generate(),
+            serde_json::json!({ "messageId": format!("msg_{}", Uuid::new_v4()) }),
+        );
+        indexing.queue.enqueue(task).await.unwrap();
+        assert!(indexing.index.is_empty());
+
+        shutdown.drain().await;
+
+        assert!(!indexing.index.is_empty());
+    }
+
+    #[tokio::test]
+    async fn stored_messages_are_relayed_from_the_outbox() {
+        use crate::db::{InMemoryMessageRepository, Message, MessageRepository};
+
+        let indexing = KeywordIndexing::new(BatchConfig::default());
+        let shutdown = ShutdownController::new();
+        let repository = Arc::new(InMemoryMessageRepository::new().with_index_outbox());
+        indexing.relay_outbox(repository.clone(), Duration::from_millis(5), &shutdown);
+        let id = Uuid::new_v4();
+        repository
+            .insert(&Message {
+                id: format!("msg_{id}"),
+                room_id: RoomId::generate().to_string(),
+                sender_id: "nexis:human:alice".to_string(),
+                content: "release notes".to_string(),
+                created_at: chrono::Utc::now(),
+                #[cfg(feature = "multi-tenant")]
+                tenant_id: None,
+            })
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while indexing.index.is_empty() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("outbox entry was not indexed in time");
+        let response = indexing
+            .index
+            .search(SearchRequest::new("release"))
+            .await
+            .unwrap();
+        assert_eq!(response.results[0].id, id);
+
+        shutdown.trigger();
+        shutdown.drain().await;
+        assert!(repository.pending(10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn similar_excludes_the_source_and_its_copies() {
+        let room = RoomId::generate();
+        let (source, copy, related) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let service = service_with(&[
+            (source, "database migration failed", &room),
+            (copy, "database migration failed", &room),
+            (related, "the migration of the database is done", &room),
+            (Uuid::new_v4(), "coffee break", &room),
+        ]);
+
+        let response = service
+            .similar(SimilarRequest::for_document(source))
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = response.results.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![related]);
+
+        let response = service
+            .similar(SimilarRequest::for_text("migration"))
+            .await
+            .unwrap();
+        assert_eq!(response.total, 3);
+        assert!(matches!(
+            service
+                .similar(SimilarRequest::for_document(Uuid::new_v4()))
+                .await,
+            Err(SearchError::NotFound(_))
+        ));
+    }
+}
@@ -4,9 +4,29 @@
 //! - Semantic search across messages
 //! - Room-scoped search
 //! - Search result ranking and filtering
+//! - Similar-message lookups (more-like-this)
+//! - Keyword search fallback when no vector store is configured
+//! - Routing of documents to per-tenant or per-room collections
+//! - Document TTLs and per-room or per-tenant retention
+//! - Optional re-ranking of the top hits by an LLM or cross-encoder
+//! - Query expansion with typo corrections and synonyms
 
+mod expand;
+mod lexical;
+mod rerank;
+mod retention;
+mod routing;
 mod service;
 
+pub use expand::{LlmExpander, QueryExpander, RuleExpander};
+pub(crate) use lexical::document_id;
+pub use lexical::{KeywordIndexing, LexicalIndexer, LexicalSearchService};
+#[cfg(feature = "cross-encoder")]
+pub use rerank::CrossEncoderReranker;
+pub use rerank::{LlmReranker, Reranker};
+pub use retention::{RetentionPolicies, RetentionPolicy, RetentionSweeper, SweepStats};
+pub use routing::{CollectionProvider, CollectionRouter, InMemoryCollections};
 pub use service::{
-    SearchError, SearchRequest, SearchResponse, SearchService, SemanticSearchService,
+    SearchError, SearchRequest, SearchResponse, SearchResultItem, SearchService,
+    SemanticSearchService, SimilarRequest,
 };
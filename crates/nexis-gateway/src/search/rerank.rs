@@ -0,0 +1,268 @@
+//! Re-ranking of semantic search hits
+//!
+//! Vector similarity finds passages near the query but orders them loosely,
+//! which shows most on short or ambiguous queries. With a [`Reranker`]
+//! configured, [`SemanticSearchService`](super::SemanticSearchService) takes
+//! its top vector hits, has the reranker score each one against the query
+//! text, and returns them in that order; each result keeps its vector
+//! `score` and gains a `rerank_score`.
+//!
+//! [`LlmReranker`] asks a chat model to grade the passages. With the
+//! `cross-encoder` feature, [`CrossEncoderReranker`] scores them with a
+//! self-hosted cross-encoder model instead.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use nexis_runtime::{AIProvider, GenerateRequest};
+
+use super::service::SearchError;
+
+/// Passages longer than this many characters are cut before grading.
+const PASSAGE_CHARS: usize = 1_000;
+
+/// Rescores search hits against the query text
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// Name reported in logs
+    fn name(&self) -> &'static str;
+
+    /// Relevance of each of `passages` to `query`, in order, from `0.0`
+    /// (unrelated) to `1.0`
+    async fn rerank(&self, query: &str, passages: &[String]) -> Result<Vec<f32>, SearchError>;
+}
+
+/// Grades passages with a prompt to a chat model
+pub struct LlmReranker {
+    provider: Arc<dyn AIProvider>,
+    model: Option<String>,
+}
+
+impl LlmReranker {
+    /// Grade with `provider`'s default model
+    pub fn new(provider: Arc<dyn AIProvider>) -> Self {
+        Self {
+            provider,
+            model: None,
+        }
+    }
+
+    /// Grade with `model` instead of the provider's default
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    fn prompt(query: &str, passages: &[String]) -> String {
+        let mut prompt = format!(
+            "Rate how relevant each passage is to the search query, from 0 (unrelated) \
+             to 10 (exactly what was searched for). Reply with one line per passage, \
+             as `<passage number>: <rating>`, and nothing else.\n\nQuery: {query}\n"
+        );
+        for (i, passage) in passages.iter().enumerate() {
+            let passage: String = passage.chars().take(PASSAGE_CHARS).collect();
+            prompt.push_str(&format!("\nPassage {}:\n{passage}\n", i + 1));
+        }
+        prompt
+    }
+
+    /// Ratings from the model's reply, scaled to `0.0..=1.0`; passages it
+    /// did not rate score `0.0`
+    fn parse(reply: &str, passages: usize) -> Result<Vec<f32>, SearchError> {
+        let mut scores = vec![0.0; passages];
+        let mut rated = 0;
+        for line in reply.lines() {
+            let Some((number, rating)) = line.split_once(':') else {
+                continue;
+            };
+            let number = number
+                .trim()
+                .trim_start_matches("Passage")
+                .trim()
+                .parse::<usize>();
+            let rating = rating.trim().parse::<f32>();
+            if let (Ok(number @ 1..), Ok(rating)) = (number, rating) {
+                if let Some(score) = scores.get_mut(number - 1) {
+                    *score = (rating / 10.0).clamp(0.0, 1.0);
+                    rated += 1;
+                }
+            }
+        }
+        if rated == 0 && passages > 0 {
+            return Err(SearchError::RerankError(
+                "model reply contained no ratings".to_string(),
+            ));
+        }
+        Ok(scores)
+    }
+}
+
+#[async_trait]
+impl Reranker for LlmReranker {
+    fn name(&self) -> &'static str {
+        "llm"
+    }
+
+    #[tracing::instrument(name = "search.rerank.llm", skip_all, fields(passages = passages.len()))]
+    async fn rerank(&self, query: &str, passages: &[String]) -> Result<Vec<f32>, SearchError> {
+        let request = GenerateRequest {
+            prompt: Self::prompt(query, passages),
+            model: self.model.clone(),
+            max_tokens: Some(8 * passages.len() as u32 + 16),
+            temperature: Some(0.0),
+            metadata: None,
+            images: Vec::new(),
+        };
+        let response = self
+            .provider
+            .generate(request)
+            .await
+            .map_err(|e| SearchError::RerankError(e.to_string()))?;
+        Self::parse(&response.content, passages.len())
+    }
+}
+
+#[cfg(feature = "cross-encoder")]
+pub use cross_encoder::CrossEncoderReranker;
+
+#[cfg(feature = "cross-encoder")]
+mod cross_encoder {
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::{Reranker, SearchError, PASSAGE_CHARS};
+
+    /// Re-ranking sits on the search path, so it must answer quickly.
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Scores passages with a cross-encoder served over HTTP
+    ///
+    /// Speaks the `POST /rerank` API of Hugging Face's
+    /// text-embeddings-inference, which serves models such as
+    /// `cross-encoder/ms-marco-MiniLM-L-6-v2` or `BAAI/bge-reranker-base`
+    /// alongside the gateway.
+    pub struct CrossEncoderReranker {
+        client: reqwest::Client,
+        base_url: String,
+    }
+
+    impl CrossEncoderReranker {
+        pub fn new(base_url: impl Into<String>) -> Self {
+            Self {
+                client: reqwest::Client::builder()
+                    .timeout(REQUEST_TIMEOUT)
+                    .build()
+                    .expect("reqwest client should build"),
+                base_url: base_url.into(),
+            }
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub(super) struct Rank {
+        index: usize,
+        score: f32,
+    }
+
+    /// Scores in passage order
+    pub(super) fn scores(ranks: Vec<Rank>, passages: usize) -> Result<Vec<f32>, SearchError> {
+        let mut scores = vec![None; passages];
+        for rank in ranks {
+            match scores.get_mut(rank.index) {
+                Some(slot) => *slot = Some(rank.score.clamp(0.0, 1.0)),
+                None => {
+                    return Err(SearchError::RerankError(format!(
+                        "score for unknown passage {}",
+                        rank.index
+                    )))
+                }
+            }
+        }
+        scores
+            .into_iter()
+            .collect::<Option<Vec<f32>>>()
+            .ok_or_else(|| SearchError::RerankError("missing passage scores".to_string()))
+    }
+
+    #[async_trait]
+    impl Reranker for CrossEncoderReranker {
+        fn name(&self) -> &'static str {
+            "cross-encoder"
+        }
+
+        #[tracing::instrument(
+            name = "search.rerank.cross_encoder",
+            skip_all,
+            fields(otel.kind = "client", passages = passages.len())
+        )]
+        async fn rerank(&self, query: &str, passages: &[String]) -> Result<Vec<f32>, SearchError> {
+            let texts: Vec<String> = passages
+                .iter()
+                .map(|passage| passage.chars().take(PASSAGE_CHARS).collect())
+                .collect();
+            let response = self
+                .client
+                .post(format!("{}/rerank", self.base_url.trim_end_matches('/')))
+                .json(&json!({
+                    "query": query,
+                    "texts": texts,
+                    "truncate": true,
+                    "raw_scores": false,
+                }))
+                .send()
+                .await
+                .map_err(|e| SearchError::RerankError(e.to_string()))?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err(SearchError::RerankError(format!(
+                    "cross-encoder returned {status}: {body}"
+                )));
+            }
+
+            let ranks = response
+                .json::<Vec<Rank>>()
+                .await
+                .map_err(|e| SearchError::RerankError(e.to_string()))?;
+            scores(ranks, passages.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn llm_ratings_are_scaled_and_matched_to_passages() {
+        let passages = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let prompt = LlmReranker::prompt("deploy steps", &passages);
+        assert!(prompt.contains("Query: deploy steps"));
+        assert!(prompt.contains("Passage 3:\nc"));
+
+        let scores = LlmReranker::parse("Passage 2: 9\n1: 3\nno idea\n7: 10", 3).unwrap();
+        assert_eq!(scores, vec![0.3, 0.9, 0.0]);
+        assert!(matches!(
+            LlmReranker::parse("I cannot rate these.", 3),
+            Err(SearchError::RerankError(_))
+        ));
+    }
+
+    #[cfg(feature = "cross-encoder")]
+    #[test]
+    fn cross_encoder_scores_follow_passage_order() {
+        let ranks: Vec<cross_encoder::Rank> = serde_json::from_value(serde_json::json!([
+            { "index": 1, "score": 0.9 },
+            { "index": 0, "score": 0.2 }
+        ]))
+        .unwrap();
+        assert_eq!(cross_encoder::scores(ranks, 2).unwrap(), vec![0.2, 0.9]);
+
+        let ranks = serde_json::from_value(serde_json::json!([{ "index": 0, "score": 0.5 }]));
+        assert!(cross_encoder::scores(ranks.unwrap(), 2).is_err());
+    }
+}
@@ -0,0 +1,301 @@
+//! Retention of vector documents
+//!
+//! Documents carrying a TTL expire on their own. On top of that, operators
+//! set a maximum age per room or per tenant; [`RetentionSweeper`] removes
+//! expired documents and those past their retention on every pass. Where a
+//! room and its tenant both have a policy, the shorter one wins.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use nexis_protocol::RoomId;
+use nexis_vector::prelude::*;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use utoipa::ToSchema;
+
+use super::CollectionRouter;
+use crate::server::ShutdownController;
+
+/// How long documents are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RetentionPolicy {
+    /// Documents created more than this many days ago are deleted.
+    pub max_age_days: u32,
+}
+
+impl RetentionPolicy {
+    pub fn new(max_age_days: u32) -> Self {
+        Self { max_age_days }
+    }
+
+    /// Documents created before this are past retention at `now`
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - chrono::Duration::days(i64::from(self.max_age_days))
+    }
+}
+
+/// Retention policies per room and per tenant
+#[derive(Debug, Default)]
+pub struct RetentionPolicies {
+    rooms: RwLock<HashMap<RoomId, RetentionPolicy>>,
+    tenants: RwLock<HashMap<String, RetentionPolicy>>,
+}
+
+impl RetentionPolicies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn room(&self, room_id: &RoomId) -> Option<RetentionPolicy> {
+        self.rooms.read().await.get(room_id).copied()
+    }
+
+    /// Set the policy of a room, returning the one it replaced
+    pub async fn set_room(
+        &self,
+        room_id: RoomId,
+        policy: RetentionPolicy,
+    ) -> Option<RetentionPolicy> {
+        self.rooms.write().await.insert(room_id, policy)
+    }
+
+    pub async fn remove_room(&self, room_id: &RoomId) -> Option<RetentionPolicy> {
+        self.rooms.write().await.remove(room_id)
+    }
+
+    pub async fn tenant(&self, tenant_id: &str) -> Option<RetentionPolicy> {
+        self.tenants.read().await.get(tenant_id).copied()
+    }
+
+    /// Set the policy of a tenant, returning the one it replaced
+    pub async fn set_tenant(
+        &self,
+        tenant_id: impl Into<String>,
+        policy: RetentionPolicy,
+    ) -> Option<RetentionPolicy> {
+        self.tenants.write().await.insert(tenant_id.into(), policy)
+    }
+
+    pub async fn remove_tenant(&self, tenant_id: &str) -> Option<RetentionPolicy> {
+        self.tenants.write().await.remove(tenant_id)
+    }
+
+    /// Every room policy
+    pub async fn rooms(&self) -> Vec<(RoomId, RetentionPolicy)> {
+        let rooms = self.rooms.read().await;
+        rooms
+            .iter()
+            .map(|(id, policy)| (id.clone(), *policy))
+            .collect()
+    }
+
+    /// Every tenant policy
+    pub async fn tenants(&self) -> Vec<(String, RetentionPolicy)> {
+        let tenants = self.tenants.read().await;
+        tenants
+            .iter()
+            .map(|(id, policy)| (id.clone(), *policy))
+            .collect()
+    }
+}
+
+/// Outcome of one sweep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SweepStats {
+    /// Documents deleted because their TTL ran out.
+    pub expired: usize,
+    /// Documents deleted because they were past their room's or tenant's
+    /// retention.
+    pub past_retention: usize,
+}
+
+/// Deletes expired documents and documents past their retention
+pub struct RetentionSweeper {
+    collections: Arc<CollectionRouter>,
+    policies: Arc<RetentionPolicies>,
+}
+
+impl RetentionSweeper {
+    pub fn new(collections: Arc<CollectionRouter>, policies: Arc<RetentionPolicies>) -> Self {
+        Self {
+            collections,
+            policies,
+        }
+    }
+
+    /// Run one sweep as of `now`.
+    pub async fn sweep_once(&self, now: DateTime<Utc>) -> VectorResult<SweepStats> {
+        let mut stats = SweepStats::default();
+
+        for store in self.collections.search_stores(None, None).await? {
+            stats.expired += store.delete_expired(now).await?;
+        }
+
+        for (room_id, policy) in self.policies.rooms().await {
+            let filter = FilterBuilder::new()
+                .room(room_id.clone())
+                .created_until(policy.cutoff(now))
+                .build()?;
+            for store in self.collections.search_stores(None, Some(&room_id)).await? {
+                stats.past_retention += store.delete_by_filter(&filter).await?;
+            }
+        }
+
+        for (tenant_id, policy) in self.policies.tenants().await {
+            let filter = FilterBuilder::new()
+                .tenant(tenant_id.clone())
+                .created_until(policy.cutoff(now))
+                .build()?;
+            for store in self
+                .collections
+                .search_stores(Some(&tenant_id), None)
+                .await?
+            {
+                stats.past_retention += store.delete_by_filter(&filter).await?;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Sweep every `interval` until `shutdown` is triggered.
+    pub fn spawn(
+        self: Arc<Self>,
+        interval: Duration,
+        shutdown: ShutdownController,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let stopped = shutdown.triggered();
+            tokio::pin!(stopped);
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    _ = interval.tick() => {
+                        match self.sweep_once(Utc::now()).await {
+                            Ok(stats) if stats != SweepStats::default() => {
+                                tracing::info!(
+                                    expired = stats.expired,
+                                    past_retention = stats.past_retention,
+                                    "Swept vector documents"
+                                );
+                            }
+                            Ok(_) => {}
+                            Err(e) => tracing::warn!(error = %e, "Retention sweep failed"),
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CollectionRouting;
+    use crate::search::InMemoryCollections;
+
+    fn doc(room: &RoomId, tenant: &str, age_days: i64) -> Document {
+        let mut doc = Document::new(
+            Vector::new(vec![1.0, 0.0]),
+            "notes".to_string(),
+            DocumentMetadata::new()
+                .with_room(room.clone())
+                .with_tenant(tenant),
+        );
+        doc.created_at = Utc::now() - chrono::Duration::days(age_days);
+        doc
+    }
+
+    async fn sweeper(
+        routing: CollectionRouting,
+        docs: Vec<Document>,
+    ) -> (
+        RetentionSweeper,
+        Arc<CollectionRouter>,
+        Arc<RetentionPolicies>,
+    ) {
+        let collections = Arc::new(CollectionRouter::new(
+            routing,
+            "nexis_vectors",
+            Arc::new(InMemoryCollections::new(2)),
+        ));
+        for doc in docs {
+            let room = doc.metadata.room_id.clone().unwrap();
+            let tenant = doc.metadata.tenant_id.clone();
+            collections
+                .store_for(tenant.as_deref(), &room)
+                .await
+                .unwrap()
+                .upsert(doc)
+                .await
+                .unwrap();
+        }
+        let policies = Arc::new(RetentionPolicies::new());
+        let sweeper = RetentionSweeper::new(collections.clone(), policies.clone());
+        (sweeper, collections, policies)
+    }
+
+    async fn remaining(collections: &CollectionRouter) -> usize {
+        let mut count = 0;
+        for store in collections.search_stores(None, None).await.unwrap() {
+            count += store.count().await.unwrap();
+        }
+        count
+    }
+
+    #[tokio::test]
+    async fn room_policy_drops_old_documents_of_that_room_only() {
+        let (kept, swept) = (RoomId::generate(), RoomId::generate());
+        let docs = vec![
+            doc(&swept, "acme", 100),
+            doc(&swept, "acme", 10),
+            doc(&kept, "acme", 100),
+        ];
+        let (sweeper, collections, policies) = sweeper(CollectionRouting::PerRoom, docs).await;
+        policies.set_room(swept, RetentionPolicy::new(90)).await;
+
+        let stats = sweeper.sweep_once(Utc::now()).await.unwrap();
+        assert_eq!(stats.past_retention, 1);
+        assert_eq!(remaining(&collections).await, 2);
+    }
+
+    #[tokio::test]
+    async fn shorter_of_room_and_tenant_policy_wins() {
+        let room = RoomId::generate();
+        let docs = vec![doc(&room, "acme", 40), doc(&room, "acme", 5)];
+        let (sweeper, collections, policies) = sweeper(CollectionRouting::PerTenant, docs).await;
+        policies.set_room(room, RetentionPolicy::new(90)).await;
+        policies.set_tenant("acme", RetentionPolicy::new(30)).await;
+
+        sweeper.sweep_once(Utc::now()).await.unwrap();
+        assert_eq!(remaining(&collections).await, 1);
+    }
+
+    #[tokio::test]
+    async fn expired_documents_are_swept() {
+        let room = RoomId::generate();
+        let docs = vec![
+            doc(&room, "acme", 0).with_expires_at(Utc::now() - chrono::Duration::minutes(1)),
+            doc(&room, "acme", 0).with_ttl(chrono::Duration::days(1)),
+        ];
+        let (sweeper, collections, _) = sweeper(CollectionRouting::Single, docs).await;
+
+        let stats = sweeper.sweep_once(Utc::now()).await.unwrap();
+        assert_eq!(
+            stats,
+            SweepStats {
+                expired: 1,
+                past_retention: 0
+            }
+        );
+        assert_eq!(remaining(&collections).await, 1);
+    }
+}
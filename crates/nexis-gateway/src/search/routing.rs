@@ -0,0 +1,464 @@
+//! Routing of vector documents to collections
+//!
+//! With [`CollectionRouting::Single`] every document shares the base
+//! collection. `PerTenant` and `PerRoom` give each tenant or room a collection
+//! of its own, named `<base>__tenant_<tenant>` or `<base>__<room id>` and
+//! created on first write. Searches without the routing key fan out over every
+//! collection under the base name.
+//!
+//! [`CollectionRouter::from_config`] routes as `[vector]` configures, keeping
+//! the collections in memory or, with the `qdrant` feature, on a Qdrant
+//! server.
+
+use async_trait::async_trait;
+use nexis_protocol::RoomId;
+use nexis_vector::prelude::*;
+use nexis_vector::InMemoryVectorStore;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::config::{CollectionRouting, VectorBackend, VectorConfig};
+
+/// Backend that opens and drops named vector collections
+#[async_trait]
+pub trait CollectionProvider: Send + Sync {
+    /// Open the collection `name`, creating it if it doesn't exist
+    async fn open(&self, name: &str) -> VectorResult<Arc<dyn VectorStore>>;
+
+    /// Names of the existing collections
+    async fn list(&self) -> VectorResult<Vec<String>>;
+
+    /// Drop the collection `name` with every document in it
+    async fn drop_collection(&self, name: &str) -> VectorResult<()>;
+}
+
+/// Collections held in memory, each an [`InMemoryVectorStore`]
+pub struct InMemoryCollections {
+    dimension: usize,
+    collections: RwLock<HashMap<String, Arc<InMemoryVectorStore>>>,
+}
+
+impl InMemoryCollections {
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            dimension,
+            collections: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl CollectionProvider for InMemoryCollections {
+    async fn open(&self, name: &str) -> VectorResult<Arc<dyn VectorStore>> {
+        let mut collections = self.collections.write().await;
+        let store = collections
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(InMemoryVectorStore::new(self.dimension)));
+        Ok(store.clone())
+    }
+
+    async fn list(&self) -> VectorResult<Vec<String>> {
+        Ok(self.collections.read().await.keys().cloned().collect())
+    }
+
+    async fn drop_collection(&self, name: &str) -> VectorResult<()> {
+        self.collections.write().await.remove(name);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "qdrant")]
+#[async_trait]
+impl CollectionProvider for nexis_vector::QdrantCollections {
+    async fn open(&self, name: &str) -> VectorResult<Arc<dyn VectorStore>> {
+        Ok(Arc::new(
+            nexis_vector::QdrantCollections::open(self, name).await?,
+        ))
+    }
+
+    async fn list(&self) -> VectorResult<Vec<String>> {
+        nexis_vector::QdrantCollections::list(self).await
+    }
+
+    async fn drop_collection(&self, name: &str) -> VectorResult<()> {
+        nexis_vector::QdrantCollections::drop_collection(self, name).await
+    }
+}
+
+/// A single pre-built store, for services that don't route
+struct FixedCollection {
+    name: String,
+    store: Arc<dyn VectorStore>,
+}
+
+#[async_trait]
+impl CollectionProvider for FixedCollection {
+    async fn open(&self, name: &str) -> VectorResult<Arc<dyn VectorStore>> {
+        if name == self.name {
+            Ok(self.store.clone())
+        } else {
+            Err(VectorError::configuration(format!(
+                "no collection {name} behind a single vector store"
+            )))
+        }
+    }
+
+    async fn list(&self) -> VectorResult<Vec<String>> {
+        Ok(vec![self.name.clone()])
+    }
+
+    async fn drop_collection(&self, _name: &str) -> VectorResult<()> {
+        Err(VectorError::configuration(
+            "a single vector store cannot be dropped",
+        ))
+    }
+}
+
+/// Picks the collection each document is written to and searched in
+pub struct CollectionRouter {
+    routing: CollectionRouting,
+    base: String,
+    provider: Arc<dyn CollectionProvider>,
+    open: RwLock<HashMap<String, Arc<dyn VectorStore>>>,
+}
+
+impl CollectionRouter {
+    /// Route documents under the collection name `base`
+    pub fn new(
+        routing: CollectionRouting,
+        base: impl Into<String>,
+        provider: Arc<dyn CollectionProvider>,
+    ) -> Self {
+        Self {
+            routing,
+            base: base.into(),
+            provider,
+            open: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Route documents as `[vector]` configures, under its collection name
+    pub fn from_config(config: &VectorConfig) -> VectorResult<Self> {
+        let provider: Arc<dyn CollectionProvider> = match config.backend {
+            VectorBackend::Memory => Arc::new(InMemoryCollections::new(config.dimension)),
+            #[cfg(feature = "qdrant")]
+            VectorBackend::Qdrant => Arc::new(nexis_vector::QdrantCollections::new(
+                nexis_vector::QdrantConfig::new(&config.url, &config.collection, config.dimension),
+            )?),
+            #[cfg(not(feature = "qdrant"))]
+            VectorBackend::Qdrant => {
+                return Err(VectorError::configuration(
+                    "the qdrant vector backend needs the gateway's `qdrant` feature",
+                ))
+            }
+        };
+        Ok(Self::new(
+            config.routing,
+            config.collection.clone(),
+            provider,
+        ))
+    }
+
+    /// Keep every document in `store`
+    pub fn single(store: Arc<dyn VectorStore>) -> Self {
+        let provider = FixedCollection {
+            name: "default".to_string(),
+            store,
+        };
+        Self::new(CollectionRouting::Single, "default", Arc::new(provider))
+    }
+
+    pub fn routing(&self) -> CollectionRouting {
+        self.routing
+    }
+
+    /// Collection a document of `tenant` in `room` belongs to
+    pub fn collection_name(&self, tenant: Option<&str>, room: &RoomId) -> String {
+        match self.routing {
+            CollectionRouting::Single => self.base.clone(),
+            CollectionRouting::PerTenant => self.tenant_collection(tenant),
+            CollectionRouting::PerRoom => self.room_collection(room),
+        }
+    }
+
+    fn tenant_collection(&self, tenant: Option<&str>) -> String {
+        match tenant {
+            Some(tenant) => format!("{}__tenant_{}", self.base, encode_name(tenant)),
+            None => self.base.clone(),
+        }
+    }
+
+    fn room_collection(&self, room: &RoomId) -> String {
+        format!("{}__{}", self.base, room.as_str())
+    }
+
+    /// Store for documents of `tenant` in `room`, creating its collection
+    /// on first use
+    pub async fn store_for(
+        &self,
+        tenant: Option<&str>,
+        room: &RoomId,
+    ) -> VectorResult<Arc<dyn VectorStore>> {
+        self.open(&self.collection_name(tenant, room)).await
+    }
+
+    /// Store behind the collection `name`, creating it on first use
+    pub async fn open_collection(&self, name: &str) -> VectorResult<Arc<dyn VectorStore>> {
+        self.open(name).await
+    }
+
+    /// Stores a search restricted to `tenant` and `room` must cover
+    ///
+    /// Never creates a collection: a tenant or room without one has nothing
+    /// to find.
+    pub async fn search_stores(
+        &self,
+        tenant: Option<&str>,
+        room: Option<&RoomId>,
+    ) -> VectorResult<Vec<Arc<dyn VectorStore>>> {
+        let names = match (self.routing, tenant, room) {
+            (CollectionRouting::Single, _, _) => return Ok(vec![self.open(&self.base).await?]),
+            (CollectionRouting::PerTenant, Some(_), _) => vec![self.tenant_collection(tenant)],
+            (CollectionRouting::PerRoom, _, Some(room)) => vec![self.room_collection(room)],
+            _ => self.collections().await?,
+        };
+
+        let existing = self.provider.list().await?;
+        let mut stores = Vec::new();
+        for name in names.iter().filter(|name| existing.contains(name)) {
+            stores.push(self.open(name).await?);
+        }
+        Ok(stores)
+    }
+
+    /// Run `query` over [`Self::search_stores`], merging the results best
+    /// first
+    pub async fn search(
+        &self,
+        tenant: Option<&str>,
+        room: Option<&RoomId>,
+        query: SearchQuery,
+    ) -> VectorResult<Vec<SearchResult>> {
+        let stores = self.search_stores(tenant, room).await?;
+        let mut results = Vec::new();
+        for store in &stores {
+            results.extend(store.search(query.clone()).await?);
+        }
+        if let [first, _, ..] = stores.as_slice() {
+            let metric = first.metric();
+            results.sort_by(|a, b| metric.compare(a.score, b.score));
+            results.truncate(query.limit);
+        }
+        Ok(results)
+    }
+
+    /// Drop the collection of a deleted room; `true` if there was one
+    ///
+    /// Rooms only have a collection of their own with
+    /// [`CollectionRouting::PerRoom`]; otherwise this does nothing.
+    pub async fn remove_room(&self, room: &RoomId) -> VectorResult<bool> {
+        if self.routing != CollectionRouting::PerRoom {
+            return Ok(false);
+        }
+        let name = self.room_collection(room);
+        if !self.provider.list().await?.contains(&name) {
+            return Ok(false);
+        }
+        self.provider.drop_collection(&name).await?;
+        self.open.write().await.remove(&name);
+        Ok(true)
+    }
+
+    /// Existing collections under the base name
+    async fn collections(&self) -> VectorResult<Vec<String>> {
+        let prefix = format!("{}__", self.base);
+        Ok(self
+            .provider
+            .list()
+            .await?
+            .into_iter()
+            .filter(|name| *name == self.base || name.starts_with(&prefix))
+            .collect())
+    }
+
+    async fn open(&self, name: &str) -> VectorResult<Arc<dyn VectorStore>> {
+        if let Some(store) = self.open.read().await.get(name) {
+            return Ok(store.clone());
+        }
+        let mut open = self.open.write().await;
+        if let Some(store) = open.get(name) {
+            return Ok(store.clone());
+        }
+        let store = self.provider.open(name).await?;
+        open.insert(name.to_string(), store.clone());
+        Ok(store)
+    }
+}
+
+/// Encode `value` for a collection name: ASCII letters, digits and `-` stay,
+/// every other byte becomes `_` and two hex digits.
+fn encode_name(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("_{byte:02x}"));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn router(routing: CollectionRouting) -> CollectionRouter {
+        CollectionRouter::new(
+            routing,
+            "nexis_vectors",
+            Arc::new(InMemoryCollections::new(4)),
+        )
+    }
+
+    fn doc(room: &RoomId) -> Document {
+        Document::new(
+            Vector::new(vec![1.0, 0.0, 0.0, 0.0]),
+            "hello".to_string(),
+            DocumentMetadata::new().with_room(room.clone()),
+        )
+    }
+
+    #[test]
+    fn collection_names_follow_the_strategy() {
+        let room = RoomId::from_uuid(uuid::Uuid::nil());
+
+        assert_eq!(
+            router(CollectionRouting::Single).collection_name(Some("acme"), &room),
+            "nexis_vectors"
+        );
+        assert_eq!(
+            router(CollectionRouting::PerTenant).collection_name(Some("acme.eu"), &room),
+            "nexis_vectors__tenant_acme_2eeu"
+        );
+        assert_eq!(
+            router(CollectionRouting::PerTenant).collection_name(None, &room),
+            "nexis_vectors"
+        );
+        assert_eq!(
+            router(CollectionRouting::PerRoom).collection_name(Some("acme"), &room),
+            format!("nexis_vectors__{}", room.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn collections_are_created_on_first_write_only() {
+        let router = router(CollectionRouting::PerRoom);
+        let room = RoomId::generate();
+
+        assert!(router
+            .search_stores(None, Some(&room))
+            .await
+            .unwrap()
+            .is_empty());
+
+        router
+            .store_for(None, &room)
+            .await
+            .unwrap()
+            .upsert(doc(&room))
+            .await
+            .unwrap();
+        let stores = router.search_stores(None, Some(&room)).await.unwrap();
+        assert_eq!(stores.len(), 1);
+        assert_eq!(stores[0].count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn searches_without_a_routing_key_fan_out() {
+        let router = router(CollectionRouting::PerRoom);
+        for room in [RoomId::generate(), RoomId::generate()] {
+            router
+                .store_for(None, &room)
+                .await
+                .unwrap()
+                .upsert(doc(&room))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(router.search_stores(None, None).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn removing_a_room_drops_its_collection() {
+        let router = router(CollectionRouting::PerRoom);
+        let room = RoomId::generate();
+        router.store_for(None, &room).await.unwrap();
+
+        assert!(router.remove_room(&room).await.unwrap());
+        assert!(router
+            .search_stores(None, Some(&room))
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(!router.remove_room(&room).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn configured_routing_and_collection_are_used() {
+        let config = VectorConfig {
+            routing: CollectionRouting::PerRoom,
+            collection: "chat".to_string(),
+            dimension: 4,
+            ..VectorConfig::default()
+        };
+        let router = CollectionRouter::from_config(&config).unwrap();
+        let room = RoomId::generate();
+
+        assert_eq!(router.routing(), CollectionRouting::PerRoom);
+        assert_eq!(
+            router.collection_name(None, &room),
+            format!("chat__{}", room.as_str())
+        );
+        router
+            .store_for(None, &room)
+            .await
+            .unwrap()
+            .upsert(doc(&room))
+            .await
+            .unwrap();
+        assert!(router.remove_room(&room).await.unwrap());
+    }
+
+    #[cfg(not(feature = "qdrant"))]
+    #[test]
+    fn qdrant_routing_needs_the_qdrant_feature() {
+        let config = VectorConfig {
+            backend: VectorBackend::Qdrant,
+            ..VectorConfig::default()
+        };
+        assert!(CollectionRouter::from_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn single_store_is_used_for_everything() {
+        let store: Arc<dyn VectorStore> = Arc::new(InMemoryVectorStore::new(4));
+        let router = CollectionRouter::single(store);
+        let room = RoomId::generate();
+
+        router
+            .store_for(Some("acme"), &room)
+            .await
+            .unwrap()
+            .upsert(doc(&room))
+            .await
+            .unwrap();
+
+        let stores = router.search_stores(None, None).await.unwrap();
+        assert_eq!(stores.len(), 1);
+        assert_eq!(stores[0].count().await.unwrap(), 1);
+        assert!(!router.remove_room(&room).await.unwrap());
+    }
+}
@@ -1,15 +1,22 @@
 //! Search service implementation
 
 use async_trait::async_trait;
+use nexis_core::permission::PermissionChecker;
+use nexis_protocol::RoomId;
 use nexis_runtime::{EmbeddingProvider, EmbeddingRequest};
 use nexis_vector::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::sync::Mutex;
-use tracing::debug;
+use tracing::{debug, warn};
 use uuid::Uuid;
 
+use super::expand::{QueryExpander, RuleExpander};
+use super::rerank::Reranker;
+use super::routing::CollectionRouter;
+use crate::config::VectorConfig;
+
 /// Search request parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchRequest {
@@ -20,9 +27,21 @@ pub struct SearchRequest {
     /// Minimum similarity score (0.0 to 1.0)
     pub min_score: Option<f32>,
     /// Filter to specific room
-    pub room_id: Option<Uuid>,
+    pub room_id: Option<RoomId>,
+    /// Filter to a content type (e.g. "text", "code", "tool")
+    #[serde(default)]
+    pub content_type: Option<String>,
     /// Include full content in results
     pub include_content: Option<bool>,
+    /// Restrict results to documents owned by this tenant
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    /// Drop results from rooms the caller cannot read
+    #[serde(skip)]
+    pub permissions: Option<PermissionChecker>,
+    /// Also search for corrected spellings and synonyms of the query
+    #[serde(default)]
+    pub expand: bool,
 }
 
 impl SearchRequest {
@@ -33,7 +52,11 @@ impl SearchRequest {
             limit: None,
             min_score: None,
             room_id: None,
+            content_type: None,
             include_content: None,
+            tenant_id: None,
+            permissions: None,
+            expand: false,
         }
     }
 
@@ -50,23 +73,120 @@ impl SearchRequest {
     }
 
     /// Filter to specific room
-    pub fn in_room(mut self, room_id: Uuid) -> Self {
+    pub fn in_room(mut self, room_id: RoomId) -> Self {
         self.room_id = Some(room_id);
         self
     }
+
+    /// Filter to a content type
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Restrict results to a tenant
+    pub fn for_tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    /// Restrict results to rooms the caller can read
+    pub fn with_permissions(mut self, permissions: PermissionChecker) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// Expand the query with corrected spellings and synonyms
+    pub fn with_expansion(mut self) -> Self {
+        self.expand = true;
+        self
+    }
+}
+
+/// More-like-this request: neighbours of an indexed document, or of a
+/// message text when the document is not indexed
+#[derive(Debug, Clone, Default)]
+pub struct SimilarRequest {
+    /// Indexed document whose stored vector is reused
+    pub document_id: Option<Uuid>,
+    /// Text embedded when no indexed document is available
+    pub text: Option<String>,
+    /// Maximum number of results
+    pub limit: Option<usize>,
+    /// Minimum similarity score (0.0 to 1.0)
+    pub min_score: Option<f32>,
+    /// Filter to specific room
+    pub room_id: Option<RoomId>,
+    /// Restrict results to documents owned by this tenant
+    pub tenant_id: Option<String>,
+    /// Drop results from rooms the caller cannot read
+    pub permissions: Option<PermissionChecker>,
+}
+
+impl SimilarRequest {
+    /// Find documents similar to an indexed document
+    pub fn for_document(document_id: Uuid) -> Self {
+        Self {
+            document_id: Some(document_id),
+            ..Self::default()
+        }
+    }
+
+    /// Find documents similar to a piece of text
+    pub fn for_text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Set result limit
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set minimum score threshold
+    pub fn with_min_score(mut self, score: f32) -> Self {
+        self.min_score = Some(score);
+        self
+    }
+
+    /// Filter to specific room
+    pub fn in_room(mut self, room_id: RoomId) -> Self {
+        self.room_id = Some(room_id);
+        self
+    }
+
+    /// Restrict results to a tenant
+    pub fn for_tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    /// Restrict results to rooms the caller can read
+    pub fn with_permissions(mut self, permissions: PermissionChecker) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
 }
 
 /// Search result item
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResultItem {
-    /// Document ID
+    /// Document ID; for a long message indexed in chunks, the id of its
+    /// first chunk, whichever chunk matched
     pub id: Uuid,
     /// Similarity score
     pub score: f32,
+    /// Relevance from the re-ranking stage, which ordered the results;
+    /// `None` without a reranker
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerank_score: Option<f32>,
     /// Document content (if included)
     pub content: Option<String>,
     /// Room ID
-    pub room_id: Option<Uuid>,
+    pub room_id: Option<RoomId>,
     /// Custom metadata
     pub metadata: serde_json::Value,
 }
@@ -74,10 +194,15 @@ pub struct SearchResultItem {
 impl From<nexis_vector::SearchResult> for SearchResultItem {
     fn from(result: nexis_vector::SearchResult) -> Self {
         Self {
-            id: result.document.id,
+            id: result
+                .document
+                .metadata
+                .parent_message_id
+                .unwrap_or(result.document.id),
             score: result.score,
+            rerank_score: None,
             content: Some(result.document.content),
-            room_id: result.document.metadata.room_id,
+            room_id: result.document.metadata.room_id.clone(),
             metadata: result.document.metadata.to_json(),
         }
     }
@@ -94,6 +219,9 @@ pub struct SearchResponse {
     pub total: usize,
     /// Whether results were truncated
     pub truncated: bool,
+    /// Terms searched for alongside the query when it was expanded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expanded_terms: Vec<String>,
 }
 
 impl SearchResponse {
@@ -105,6 +233,7 @@ impl SearchResponse {
             results,
             total,
             truncated: false,
+            expanded_terms: Vec::new(),
         }
     }
 
@@ -113,6 +242,12 @@ impl SearchResponse {
         self.truncated = true;
         self
     }
+
+    /// Record the terms the query was expanded with
+    pub fn with_expanded_terms(mut self, terms: Vec<String>) -> Self {
+        self.expanded_terms = terms;
+        self
+    }
 }
 
 /// Search service trait
@@ -125,9 +260,19 @@ pub trait SearchService: Send + Sync {
     async fn search_in_room(
         &self,
         query: &str,
-        room_id: Uuid,
+        room_id: RoomId,
         limit: usize,
     ) -> Result<SearchResponse, SearchError>;
+
+    /// Find documents similar to a document or text, excluding the source
+    /// itself and exact copies of it
+    async fn similar(&self, request: SimilarRequest) -> Result<SearchResponse, SearchError>;
+
+    /// Drop what is indexed for a deleted room, where the index keeps rooms
+    /// apart
+    async fn remove_room(&self, _room_id: &RoomId) -> Result<(), SearchError> {
+        Ok(())
+    }
 }
 
 /// Search error type
@@ -141,27 +286,87 @@ pub enum SearchError {
 
     #[error("Invalid query: {0}")]
     InvalidQuery(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Re-ranking failed: {0}")]
+    RerankError(String),
+
+    #[error("Query expansion failed: {0}")]
+    ExpansionError(String),
+}
+
+/// Extra candidates fetched per requested result because several chunks of
+/// one long message may match and are merged into one result.
+const CHUNK_OVERFETCH: usize = 2;
+
+/// Whether `permissions` allow reading the room a result came from. Results
+/// without a room are only visible to callers with access to every room.
+pub(super) fn can_read_result(permissions: &PermissionChecker, room_id: Option<&RoomId>) -> bool {
+    match room_id {
+        Some(room_id) => permissions.can_access_room(room_id.as_str()),
+        None => permissions.can_access_room("*"),
+    }
 }
 
 /// Semantic search service implementation
 pub struct SemanticSearchService {
-    vector_store: Arc<dyn VectorStore>,
+    collections: Arc<CollectionRouter>,
     embedding_provider: Arc<dyn EmbeddingProvider>,
     default_limit: usize,
     query_embedding_cache: Mutex<QueryEmbeddingCache>,
+    reranking: Option<Reranking>,
+    expander: Arc<dyn QueryExpander>,
+}
+
+/// Re-ranking stage applied to the top vector hits of each search
+struct Reranking {
+    reranker: Arc<dyn Reranker>,
+    top_k: usize,
 }
 
 impl SemanticSearchService {
-    /// Create a new semantic search service
+    /// Create a new semantic search service over a single vector store
     pub fn new(
         vector_store: Arc<dyn VectorStore>,
         embedding_provider: Arc<dyn EmbeddingProvider>,
+    ) -> Self {
+        Self::with_collections(
+            Arc::new(CollectionRouter::single(vector_store)),
+            embedding_provider,
+        )
+    }
+
+    /// Create a search service over the collections `[vector]` configures
+    ///
+    /// In-memory collections are only filled by an indexer sharing them, so
+    /// build those through
+    /// [`crate::indexing::MessageIndexer::from_config`] and
+    /// [`Self::with_collections`] instead.
+    pub fn from_config(
+        config: &VectorConfig,
+        embedding_provider: Arc<dyn EmbeddingProvider>,
+    ) -> VectorResult<Self> {
+        Ok(Self::with_collections(
+            Arc::new(CollectionRouter::from_config(config)?),
+            embedding_provider,
+        ))
+    }
+
+    /// Create a search service over routed collections, usually shared
+    /// with the [`crate::indexing::MessageIndexer`] that fills them
+    pub fn with_collections(
+        collections: Arc<CollectionRouter>,
+        embedding_provider: Arc<dyn EmbeddingProvider>,
     ) -> Self {
         Self {
-            vector_store,
+            collections,
             embedding_provider,
             default_limit: 10,
             query_embedding_cache: Mutex::new(QueryEmbeddingCache::new(256)),
+            reranking: None,
+            expander: Arc::new(RuleExpander::default()),
         }
     }
 
@@ -178,6 +383,38 @@ impl SemanticSearchService {
         self
     }
 
+    /// Rescore the top `top_k` vector hits of each search with `reranker`
+    /// and return the results in its order
+    ///
+    /// Searches asking for more than `top_k` results rerank that many
+    /// instead. If the reranker fails, results keep their vector order.
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>, top_k: usize) -> Self {
+        self.reranking = Some(Reranking {
+            reranker,
+            top_k: top_k.max(1),
+        });
+        self
+    }
+
+    /// Expand queries that ask for it with `expander` instead of the
+    /// built-in [`RuleExpander`]
+    pub fn with_query_expander(mut self, expander: Arc<dyn QueryExpander>) -> Self {
+        self.expander = expander;
+        self
+    }
+
+    /// Terms to search for alongside `query`; none if expansion fails,
+    /// which leaves the query as typed
+    async fn expansion(&self, query: &str) -> Vec<String> {
+        match self.expander.expand(query).await {
+            Ok(terms) => terms,
+            Err(e) => {
+                warn!(expander = self.expander.name(), error = %e, "Query expansion failed");
+                Vec::new()
+            }
+        }
+    }
+
     async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>, SearchError> {
         let cache_key = normalize_query(text);
         if let Some(cached) = self
@@ -249,29 +486,97 @@ fn normalize_query(text: &str) -> String {
     text.trim().to_ascii_lowercase()
 }
 
-#[async_trait]
-impl SearchService for SemanticSearchService {
-    async fn search(&self, request: SearchRequest) -> Result<SearchResponse, SearchError> {
-        debug!("Searching for: {}", request.query);
-
-        if request.query.trim().is_empty() {
-            return Err(SearchError::InvalidQuery(
-                "Query cannot be empty".to_string(),
-            ));
+impl SemanticSearchService {
+    /// Indexed document `id` from the collections of the request's tenant;
+    /// it may live outside the room the results are restricted to.
+    async fn find_document(
+        &self,
+        id: Uuid,
+        request: &SimilarRequest,
+    ) -> Result<Option<Document>, SearchError> {
+        let stores = self
+            .collections
+            .search_stores(request.tenant_id.as_deref(), None)
+            .await
+            .map_err(|e| SearchError::VectorError(e.to_string()))?;
+        for store in stores {
+            match store.get(id).await {
+                Ok(document) => return Ok(Some(document)),
+                Err(VectorError::NotFound { .. }) => {}
+                Err(e) => return Err(SearchError::VectorError(e.to_string())),
+            }
         }
+        Ok(None)
+    }
 
-        let embedding = self.generate_embedding(&request.query).await?;
-        let query_vector = Vector::new(embedding);
+    /// Whether `request` targets nothing the caller may read.
+    fn denied(request: &SearchRequest) -> bool {
+        request.permissions.as_ref().is_some_and(|permissions| {
+            !permissions.can_read()
+                || request
+                    .room_id
+                    .as_ref()
+                    .is_some_and(|room_id| !can_read_result(permissions, Some(room_id)))
+        })
+    }
 
+    /// Query the vector store with `embedding`, applying the filters and
+    /// permissions from `request` and dropping results matching `exclude`.
+    async fn search_vector(
+        &self,
+        request: SearchRequest,
+        embedding: Vec<f32>,
+        exclude: Option<&(dyn Fn(&SearchResultItem) -> bool + Send + Sync)>,
+    ) -> Result<SearchResponse, SearchError> {
         let limit = request.limit.unwrap_or(self.default_limit);
-        let mut search_query = SearchQuery::new(query_vector).with_limit(limit);
+        let query_vector = Vector::new(embedding);
 
+        // The store only returns documents of rooms the caller may read, so
+        // pages aren't cut short by dropping the rest afterwards.
+        let allowed_rooms = match (&request.permissions, &request.room_id) {
+            (Some(permissions), None) => permissions.allowed_rooms().map(|rooms| {
+                rooms
+                    .iter()
+                    .filter_map(|room| room.parse::<RoomId>().ok())
+                    .collect::<Vec<_>>()
+            }),
+            _ => None,
+        };
+        if allowed_rooms.as_ref().is_some_and(Vec::is_empty) {
+            return Ok(SearchResponse::new(request.query, Vec::new()));
+        }
+
+        let mut fetch_limit = limit.saturating_mul(CHUNK_OVERFETCH);
+        if exclude.is_some() {
+            fetch_limit = fetch_limit.saturating_add(1);
+        }
+        let mut search_query = SearchQuery::new(query_vector).with_limit(fetch_limit);
+
+        // Search scores are cosine similarities; out-of-range thresholds
+        // saturate rather than fail the request.
         if let Some(min_score) = request.min_score {
-            search_query = search_query.with_min_score(min_score);
+            search_query = search_query.with_min_score(min_score.clamp(0.0, 1.0));
         }
 
-        if let Some(room_id) = request.room_id {
-            search_query = search_query.with_filter(SearchFilter::new().with_room(room_id));
+        if request.room_id.is_some()
+            || allowed_rooms.is_some()
+            || request.content_type.is_some()
+            || request.tenant_id.is_some()
+        {
+            let mut filter = SearchFilter::new();
+            if let Some(tenant_id) = request.tenant_id.clone() {
+                filter = filter.with_tenant(tenant_id);
+            }
+            if let Some(room_id) = request.room_id.clone() {
+                filter = filter.with_room(room_id);
+            }
+            if let Some(rooms) = allowed_rooms {
+                filter = filter.with_rooms(rooms);
+            }
+            if let Some(content_type) = request.content_type.clone() {
+                filter = filter.with_content_type(content_type);
+            }
+            search_query = search_query.with_filter(filter);
         }
 
         if !request.include_content.unwrap_or(true) {
@@ -279,15 +584,34 @@ impl SearchService for SemanticSearchService {
         }
 
         let results = self
-            .vector_store
-            .search(search_query)
+            .collections
+            .search(
+                request.tenant_id.as_deref(),
+                request.room_id.as_ref(),
+                search_query,
+            )
             .await
             .map_err(|e| SearchError::VectorError(e.to_string()))?;
 
-        let items: Vec<SearchResultItem> =
-            results.into_iter().map(SearchResultItem::from).collect();
+        // Results come best first, so the first hit on a message is its
+        // best-matching chunk; later chunks of the same message are dropped.
+        let fetched = results.len();
+        let mut messages = HashSet::new();
+        let mut items: Vec<SearchResultItem> = results
+            .into_iter()
+            .map(SearchResultItem::from)
+            .filter(|item| messages.insert(item.id))
+            .filter(|item| {
+                request
+                    .permissions
+                    .as_ref()
+                    .is_none_or(|permissions| can_read_result(permissions, item.room_id.as_ref()))
+            })
+            .filter(|item| exclude.is_none_or(|exclude| !exclude(item)))
+            .collect();
+        items.truncate(limit);
 
-        let truncated = items.len() >= limit;
+        let truncated = items.len() >= limit || fetched >= fetch_limit;
         let mut response = SearchResponse::new(request.query, items);
         if truncated {
             response = response.with_truncated();
@@ -296,20 +620,176 @@ impl SearchService for SemanticSearchService {
         Ok(response)
     }
 
+    /// [`Self::search_vector`] over the top `top_k` hits, reordered by
+    /// the reranker's scores.
+    async fn search_reranked(
+        &self,
+        reranking: &Reranking,
+        request: SearchRequest,
+        embedding: Vec<f32>,
+    ) -> Result<SearchResponse, SearchError> {
+        let limit = request.limit.unwrap_or(self.default_limit);
+        let include_content = request.include_content.unwrap_or(true);
+        let candidates = SearchRequest {
+            limit: Some(limit.max(reranking.top_k)),
+            include_content: Some(true),
+            ..request
+        };
+        let mut response = self.search_vector(candidates, embedding, None).await?;
+
+        let passages: Vec<String> = response
+            .results
+            .iter()
+            .map(|item| item.content.clone().unwrap_or_default())
+            .collect();
+        match reranking.reranker.rerank(&response.query, &passages).await {
+            Ok(scores) if scores.len() == passages.len() => {
+                for (item, score) in response.results.iter_mut().zip(scores) {
+                    item.rerank_score = Some(score);
+                }
+                // Stable, so equally rated results keep their vector order.
+                response.results.sort_by(|a, b| {
+                    b.rerank_score
+                        .partial_cmp(&a.rerank_score)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+            }
+            Ok(scores) => warn!(
+                reranker = reranking.reranker.name(),
+                expected = passages.len(),
+                got = scores.len(),
+                "Reranker scored the wrong number of results; keeping vector order"
+            ),
+            Err(e) => warn!(
+                reranker = reranking.reranker.name(),
+                error = %e,
+                "Re-ranking failed; keeping vector order"
+            ),
+        }
+
+        if response.results.len() > limit {
+            response.results.truncate(limit);
+            response.truncated = true;
+        }
+        if !include_content {
+            for item in &mut response.results {
+                item.content = None;
+            }
+        }
+        response.total = response.results.len();
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl SearchService for SemanticSearchService {
+    async fn search(&self, request: SearchRequest) -> Result<SearchResponse, SearchError> {
+        debug!("Searching for: {}", request.query);
+
+        if request.query.trim().is_empty() {
+            return Err(SearchError::InvalidQuery(
+                "Query cannot be empty".to_string(),
+            ));
+        }
+
+        if Self::denied(&request) {
+            debug!("Caller cannot read the requested rooms");
+            return Ok(SearchResponse::new(request.query, Vec::new()));
+        }
+
+        // The extra terms only shape the query embedding; reranking and the
+        // response still use the query as typed.
+        let expanded_terms = if request.expand {
+            self.expansion(&request.query).await
+        } else {
+            Vec::new()
+        };
+        let embedding = if expanded_terms.is_empty() {
+            self.generate_embedding(&request.query).await?
+        } else {
+            let text = format!("{} {}", request.query, expanded_terms.join(" "));
+            self.generate_embedding(&text).await?
+        };
+        let response = match &self.reranking {
+            Some(reranking) => self.search_reranked(reranking, request, embedding).await?,
+            None => self.search_vector(request, embedding, None).await?,
+        };
+        Ok(response.with_expanded_terms(expanded_terms))
+    }
+
     async fn search_in_room(
         &self,
         query: &str,
-        room_id: Uuid,
+        room_id: RoomId,
         limit: usize,
     ) -> Result<SearchResponse, SearchError> {
         let request = SearchRequest::new(query).with_limit(limit).in_room(room_id);
         self.search(request).await
     }
+
+    async fn similar(&self, request: SimilarRequest) -> Result<SearchResponse, SearchError> {
+        let source = match request.document_id {
+            Some(id) => self.find_document(id, &request).await?,
+            None => None,
+        };
+
+        let (embedding, text) = match (source, request.text) {
+            (Some(document), _) => {
+                // Don't confirm that documents the caller cannot read exist.
+                let readable = request.permissions.as_ref().is_none_or(|permissions| {
+                    can_read_result(permissions, document.metadata.room_id.as_ref())
+                }) && request
+                    .tenant_id
+                    .as_ref()
+                    .is_none_or(|tenant| document.metadata.tenant_id.as_ref() == Some(tenant));
+                if !readable {
+                    return Err(SearchError::NotFound("document".to_string()));
+                }
+                (document.vector.data, document.content)
+            }
+            (None, Some(text)) if !text.trim().is_empty() => {
+                (self.generate_embedding(&text).await?, text)
+            }
+            (None, _) => return Err(SearchError::NotFound("document".to_string())),
+        };
+
+        let search_request = SearchRequest {
+            query: text.clone(),
+            limit: request.limit,
+            min_score: request.min_score,
+            room_id: request.room_id,
+            content_type: None,
+            include_content: None,
+            tenant_id: request.tenant_id,
+            permissions: request.permissions,
+            expand: false,
+        };
+        if Self::denied(&search_request) {
+            return Ok(SearchResponse::new(text, Vec::new()));
+        }
+
+        let document_id = request.document_id;
+        let is_source = move |item: &SearchResultItem| {
+            Some(item.id) == document_id || item.content.as_deref() == Some(text.as_str())
+        };
+        self.search_vector(search_request, embedding, Some(&is_source))
+            .await
+    }
+
+    async fn remove_room(&self, room_id: &RoomId) -> Result<(), SearchError> {
+        self.collections
+            .remove_room(room_id)
+            .await
+            .map(|_| ())
+            .map_err(|e| SearchError::VectorError(e.to_string()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::CollectionRouting;
+    use crate::search::InMemoryCollections;
     use async_trait::async_trait;
     use nexis_runtime::{
         BatchEmbeddingRequest, BatchEmbeddingResponse, EmbeddingProvider, EmbeddingRequest,
@@ -326,11 +806,11 @@ mod tests {
 
     #[test]
     fn search_request_builder() {
-        let room_id = Uuid::new_v4();
+        let room_id = RoomId::generate();
         let req = SearchRequest::new("test query")
             .with_limit(5)
             .with_min_score(0.5)
-            .in_room(room_id);
+            .in_room(room_id.clone());
 
         assert_eq!(req.query, "test query");
         assert_eq!(req.limit, Some(5));
@@ -360,12 +840,222 @@ mod tests {
     #[tokio::test]
     async fn search_in_room_uses_room_filter() {
         let service = create_test_service();
-        let room_id = Uuid::new_v4();
+        let room_id = RoomId::generate();
 
         let response = service.search_in_room("test", room_id, 10).await.unwrap();
         assert_eq!(response.total, 0);
     }
 
+    #[tokio::test]
+    async fn search_filters_by_content_type() {
+        let store = Arc::new(InMemoryVectorStore::new(128));
+        let embedding = Arc::new(MockEmbeddingProvider::new(128));
+        for (content, content_type) in [("fn main() {}", "code"), ("hello", "text")] {
+            store
+                .upsert(Document::new(
+                    Vector::new(vec![0.1; 128]),
+                    content.to_string(),
+                    DocumentMetadata::new().with_content_type(content_type),
+                ))
+                .await
+                .unwrap();
+        }
+        let service = SemanticSearchService::new(store, embedding);
+
+        let request = SearchRequest::new("main").with_content_type("code");
+        let response = service.search(request).await.unwrap();
+
+        assert_eq!(response.total, 1);
+        assert_eq!(response.results[0].content.as_deref(), Some("fn main() {}"));
+    }
+
+    #[tokio::test]
+    async fn search_merges_chunk_hits_into_their_message() {
+        let store = Arc::new(InMemoryVectorStore::new(128));
+        let embedding = Arc::new(MockEmbeddingProvider::new(128));
+        let half: Vec<f32> = (0..128).map(|i| if i < 64 { 0.1 } else { 0.0 }).collect();
+        let parent = Uuid::new_v4();
+        let docs = [
+            Document::with_id(
+                parent,
+                Vector::new(half.clone()),
+                "release notes, part one".to_string(),
+                DocumentMetadata::new().with_chunk(parent, 0),
+            ),
+            Document::new(
+                Vector::new(vec![0.1; 128]),
+                "release notes, part two".to_string(),
+                DocumentMetadata::new().with_chunk(parent, 1),
+            ),
+            Document::new(
+                Vector::new(half),
+                "a short message".to_string(),
+                DocumentMetadata::new(),
+            ),
+        ];
+        store.upsert_batch(docs.to_vec()).await.unwrap();
+        let service = SemanticSearchService::new(store, embedding);
+
+        let response = service.search(SearchRequest::new("notes")).await.unwrap();
+
+        assert_eq!(response.total, 2);
+        let best = &response.results[0];
+        assert_eq!(best.id, parent);
+        assert_eq!(best.content.as_deref(), Some("release notes, part two"));
+        assert_eq!(best.metadata["chunk_index"], 1);
+        assert_eq!(response.results[1].id, docs[2].id);
+    }
+
+    #[tokio::test]
+    async fn search_is_scoped_to_tenant() {
+        let store = Arc::new(InMemoryVectorStore::new(128));
+        let embedding = Arc::new(MockEmbeddingProvider::new(128));
+        for tenant in ["acme", "globex"] {
+            store
+                .upsert(Document::new(
+                    Vector::new(vec![0.1; 128]),
+                    format!("{tenant} roadmap"),
+                    DocumentMetadata::new().with_tenant(tenant),
+                ))
+                .await
+                .unwrap();
+        }
+        let service = SemanticSearchService::new(store, embedding);
+
+        let request = SearchRequest::new("roadmap").for_tenant("acme");
+        let response = service.search(request).await.unwrap();
+
+        assert_eq!(response.total, 1);
+        assert_eq!(response.results[0].content.as_deref(), Some("acme roadmap"));
+    }
+
+    #[tokio::test]
+    async fn search_drops_results_from_inaccessible_rooms() {
+        use nexis_core::permission::{Action, Permissions};
+
+        let store = Arc::new(InMemoryVectorStore::new(128));
+        let embedding = Arc::new(MockEmbeddingProvider::new(128));
+        let (open_room, private_room) = (RoomId::generate(), RoomId::generate());
+        for (content, room_id) in [
+            ("standup notes", open_room.clone()),
+            ("salary notes", private_room.clone()),
+        ] {
+            store
+                .upsert(Document::new(
+                    Vector::new(vec![0.1; 128]),
+                    content.to_string(),
+                    DocumentMetadata::new().with_room(room_id),
+                ))
+                .await
+                .unwrap();
+        }
+        let service = SemanticSearchService::new(store, embedding);
+        let reader = PermissionChecker::new(Permissions::new(
+            vec![open_room.to_string()],
+            vec![Action::Read],
+        ));
+
+        let request = SearchRequest::new("notes").with_permissions(reader.clone());
+        let response = service.search(request).await.unwrap();
+        assert_eq!(response.total, 1);
+        assert_eq!(response.results[0].room_id, Some(open_room));
+
+        let request = SearchRequest::new("notes")
+            .in_room(private_room)
+            .with_permissions(reader);
+        assert_eq!(service.search(request).await.unwrap().total, 0);
+
+        let everywhere =
+            PermissionChecker::new(Permissions::new(vec!["*".to_string()], vec![Action::Read]));
+        let request = SearchRequest::new("notes").with_permissions(everywhere);
+        assert_eq!(service.search(request).await.unwrap().total, 2);
+    }
+
+    #[tokio::test]
+    async fn pages_are_filled_from_readable_rooms_only() {
+        use nexis_core::permission::{Action, Permissions};
+
+        let store = Arc::new(InMemoryVectorStore::new(128));
+        let embedding = Arc::new(MockEmbeddingProvider::new(128));
+        let (open_room, private_room) = (RoomId::generate(), RoomId::generate());
+        let rooms =
+            std::iter::repeat_n(&private_room, 20).chain(std::iter::repeat_n(&open_room, 3));
+        for (index, room_id) in rooms.enumerate() {
+            store
+                .upsert(Document::new(
+                    Vector::new(vec![0.1; 128]),
+                    format!("notes {index}"),
+                    DocumentMetadata::new().with_room(room_id.clone()),
+                ))
+                .await
+                .unwrap();
+        }
+        let service = SemanticSearchService::new(store, embedding);
+
+        let reader = PermissionChecker::new(Permissions::new(
+            vec![open_room.to_string()],
+            vec![Action::Read],
+        ));
+        let request = SearchRequest::new("notes")
+            .with_limit(2)
+            .with_permissions(reader);
+        let response = service.search(request).await.unwrap();
+        assert_eq!(response.total, 2);
+        assert!(response
+            .results
+            .iter()
+            .all(|result| result.room_id.as_ref() == Some(&open_room)));
+
+        let outsider = PermissionChecker::new(Permissions::new(Vec::new(), vec![Action::Read]));
+        let request = SearchRequest::new("notes").with_permissions(outsider);
+        assert_eq!(service.search(request).await.unwrap().total, 0);
+    }
+
+    #[tokio::test]
+    async fn similar_reuses_stored_vector_and_excludes_source() {
+        let store = Arc::new(InMemoryVectorStore::new(4));
+        let embedding = Arc::new(CountingEmbeddingProvider::new(4));
+        let room_id = RoomId::generate();
+        let mut ids = Vec::new();
+        for (content, vector) in [
+            ("deploy friday", vec![1.0, 0.0, 0.0, 0.0]),
+            ("deploy friday", vec![1.0, 0.0, 0.0, 0.0]),
+            ("release monday", vec![0.9, 0.1, 0.0, 0.0]),
+            ("lunch order", vec![0.0, 0.0, 1.0, 0.0]),
+        ] {
+            ids.push(
+                store
+                    .upsert(Document::new(
+                        Vector::new(vector),
+                        content.to_string(),
+                        DocumentMetadata::new().with_room(room_id.clone()),
+                    ))
+                    .await
+                    .unwrap(),
+            );
+        }
+        let service = SemanticSearchService::new(store, embedding.clone());
+
+        let request = SimilarRequest::for_document(ids[0])
+            .with_limit(5)
+            .with_min_score(0.5);
+        let response = service.similar(request).await.unwrap();
+        assert_eq!(embedding.calls(), 0);
+        assert_eq!(response.query, "deploy friday");
+        assert_eq!(response.total, 1);
+        assert_eq!(response.results[0].id, ids[2]);
+
+        let missing = service
+            .similar(SimilarRequest::for_document(Uuid::new_v4()))
+            .await;
+        assert!(matches!(missing, Err(SearchError::NotFound(_))));
+
+        let mut request = SimilarRequest::for_text("standup notes").in_room(room_id);
+        request.document_id = Some(Uuid::new_v4());
+        service.similar(request).await.unwrap();
+        assert_eq!(embedding.calls(), 1);
+    }
+
     #[derive(Debug)]
     struct CountingEmbeddingProvider {
         calls: AtomicUsize,
@@ -433,4 +1123,208 @@ mod tests {
 
         assert_eq!(embedding.calls(), 1);
     }
+
+    #[tokio::test]
+    async fn tenant_collections_are_searched_separately() {
+        let collections = Arc::new(CollectionRouter::new(
+            CollectionRouting::PerTenant,
+            "nexis_vectors",
+            Arc::new(InMemoryCollections::new(128)),
+        ));
+        let room = RoomId::generate();
+        for tenant in ["acme", "globex"] {
+            collections
+                .store_for(Some(tenant), &room)
+                .await
+                .unwrap()
+                .upsert(Document::new(
+                    Vector::new(vec![0.1; 128]),
+                    format!("{tenant} roadmap"),
+                    DocumentMetadata::new()
+                        .with_tenant(tenant)
+                        .with_room(room.clone()),
+                ))
+                .await
+                .unwrap();
+        }
+        let embedding = Arc::new(MockEmbeddingProvider::new(128));
+        let service = SemanticSearchService::with_collections(collections, embedding);
+
+        let request = SearchRequest::new("roadmap").for_tenant("globex");
+        let response = service.search(request).await.unwrap();
+        assert_eq!(response.total, 1);
+        assert_eq!(
+            response.results[0].content.as_deref(),
+            Some("globex roadmap")
+        );
+
+        let response = service.search(SearchRequest::new("roadmap")).await.unwrap();
+        assert_eq!(response.total, 2);
+    }
+
+    #[tokio::test]
+    async fn removing_a_room_drops_its_collection() {
+        let collections = Arc::new(CollectionRouter::new(
+            CollectionRouting::PerRoom,
+            "nexis_vectors",
+            Arc::new(InMemoryCollections::new(128)),
+        ));
+        let room = RoomId::generate();
+        collections
+            .store_for(None, &room)
+            .await
+            .unwrap()
+            .upsert(Document::new(
+                Vector::new(vec![0.1; 128]),
+                "standup notes".to_string(),
+                DocumentMetadata::new().with_room(room.clone()),
+            ))
+            .await
+            .unwrap();
+        let embedding = Arc::new(MockEmbeddingProvider::new(128));
+        let service = SemanticSearchService::with_collections(collections, embedding);
+
+        service.remove_room(&room).await.unwrap();
+
+        let response = service.search_in_room("standup", room, 10).await.unwrap();
+        assert_eq!(response.total, 0);
+    }
+
+    #[tokio::test]
+    async fn configured_collections_are_shared_with_the_indexer() {
+        use crate::indexing::{IndexerConfig, IndexingService, MessageIndexer};
+
+        let config = VectorConfig {
+            routing: CollectionRouting::PerRoom,
+            dimension: 128,
+            ..VectorConfig::default()
+        };
+        let embedding = Arc::new(MockEmbeddingProvider::new(128));
+        let indexer =
+            MessageIndexer::from_config(&config, embedding.clone(), IndexerConfig::default())
+                .unwrap();
+        let room = RoomId::generate();
+        indexer
+            .index_message("standup notes", room.clone(), serde_json::json!({}))
+            .await
+            .unwrap();
+        let service = SemanticSearchService::with_collections(indexer.collections(), embedding);
+
+        let response = service
+            .search_in_room("standup notes", room, 10)
+            .await
+            .unwrap();
+        assert_eq!(response.total, 1);
+    }
+
+    /// Rates longer passages as more relevant, or fails.
+    struct LengthReranker {
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Reranker for LengthReranker {
+        fn name(&self) -> &'static str {
+            "length"
+        }
+
+        async fn rerank(&self, _query: &str, passages: &[String]) -> Result<Vec<f32>, SearchError> {
+            if self.fail {
+                return Err(SearchError::RerankError("unavailable".to_string()));
+            }
+            Ok(passages
+                .iter()
+                .map(|passage| passage.len() as f32 / 10.0)
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn reranker_reorders_the_top_hits_and_keeps_both_scores() {
+        let store = Arc::new(InMemoryVectorStore::new(128));
+        let mut ids = HashMap::new();
+        for content in ["a", "bbb", "cc"] {
+            let id = store
+                .upsert(Document::new(
+                    Vector::new(vec![0.1; 128]),
+                    content.to_string(),
+                    DocumentMetadata::new(),
+                ))
+                .await
+                .unwrap();
+            ids.insert(id, content);
+        }
+        let embedding = Arc::new(MockEmbeddingProvider::new(128));
+        let service = SemanticSearchService::new(store.clone(), embedding.clone())
+            .with_reranker(Arc::new(LengthReranker { fail: false }), 5);
+
+        let request = SearchRequest {
+            include_content: Some(false),
+            ..SearchRequest::new("letters").with_limit(2)
+        };
+        let response = service.search(request).await.unwrap();
+
+        let order: Vec<&str> = response.results.iter().map(|item| ids[&item.id]).collect();
+        assert_eq!(order, ["bbb", "cc"]);
+        assert_eq!(response.results[0].rerank_score, Some(0.3));
+        assert!((response.results[0].score - 1.0).abs() < 1e-6);
+        assert!(response.results[0].content.is_none());
+        assert!(response.truncated);
+        assert_eq!(response.total, 2);
+
+        let service = SemanticSearchService::new(store, embedding)
+            .with_reranker(Arc::new(LengthReranker { fail: true }), 5);
+        let response = service
+            .search(SearchRequest::new("letters").with_limit(2))
+            .await
+            .unwrap();
+        assert_eq!(response.total, 2);
+        assert!(response
+            .results
+            .iter()
+            .all(|item| item.rerank_score.is_none()));
+    }
+
+    struct FailingExpander;
+
+    #[async_trait]
+    impl QueryExpander for FailingExpander {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        async fn expand(&self, _query: &str) -> Result<Vec<String>, SearchError> {
+            Err(SearchError::ExpansionError("unavailable".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn expanded_searches_report_their_extra_terms() {
+        let store = Arc::new(InMemoryVectorStore::new(128));
+        let embedding = Arc::new(CountingEmbeddingProvider::new(128));
+        let service = SemanticSearchService::new(store.clone(), embedding.clone());
+
+        let response = service
+            .search(SearchRequest::new("deplyo failed").with_expansion())
+            .await
+            .unwrap();
+        assert_eq!(
+            response.expanded_terms,
+            ["deploy", "deployment", "release", "rollout"]
+        );
+        let response = service
+            .search(SearchRequest::new("deplyo failed"))
+            .await
+            .unwrap();
+        assert!(response.expanded_terms.is_empty());
+        assert_eq!(embedding.calls(), 2);
+
+        let service = SemanticSearchService::new(store, embedding)
+            .with_query_expander(Arc::new(FailingExpander));
+        let response = service
+            .search(SearchRequest::new("deplyo failed").with_expansion())
+            .await
+            .unwrap();
+        assert!(response.expanded_terms.is_empty());
+    }
 }
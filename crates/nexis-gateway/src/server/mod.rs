@@ -1 +1,5 @@
 //! Server module for Nexis Gateway
+
+mod shutdown;
+
+pub use shutdown::{shutdown_signal, ConnectionGuard, ShutdownController};
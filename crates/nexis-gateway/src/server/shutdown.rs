@@ -0,0 +1,186 @@
+//! Graceful shutdown coordination.
+//!
+//! A [`ShutdownController`] is shared by the listener, the WebSocket handlers
+//! and anything else that must finish work before the process exits. Once
+//! triggered, long-lived connections close themselves, the controller waits
+//! for them to go away and then runs the registered drain hooks (for
+//! example flushing an indexing queue).
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{watch, Notify};
+
+type DrainHook = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+struct Inner {
+    triggered: watch::Sender<bool>,
+    active: AtomicUsize,
+    idle: Notify,
+    hooks: Mutex<Vec<DrainHook>>,
+}
+
+/// Cloneable handle used to signal and observe shutdown.
+#[derive(Clone)]
+pub struct ShutdownController {
+    inner: Arc<Inner>,
+}
+
+impl Default for ShutdownController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownController {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                triggered: watch::channel(false).0,
+                active: AtomicUsize::new(0),
+                idle: Notify::new(),
+                hooks: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Start shutting down. Calling this more than once has no effect.
+    pub fn trigger(&self) {
+        self.inner.triggered.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.inner.triggered.borrow()
+    }
+
+    /// Resolve once [`trigger`](Self::trigger) has been called.
+    pub fn triggered(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut receiver = self.inner.triggered.subscribe();
+        async move {
+            // The sender lives in `inner`, which outlives every receiver.
+            let _ = receiver.wait_for(|triggered| *triggered).await;
+        }
+    }
+
+    /// Register a long-lived connection; it counts as active until the
+    /// returned guard is dropped.
+    pub fn track(&self) -> ConnectionGuard {
+        self.inner.active.fetch_add(1, Ordering::SeqCst);
+        ConnectionGuard {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+
+    pub fn active_connections(&self) -> usize {
+        self.inner.active.load(Ordering::SeqCst)
+    }
+
+    /// Run `hook` during [`drain`](Self::drain), after connections close.
+    pub fn on_drain(&self, hook: impl Future<Output = ()> + Send + 'static) {
+        self.inner
+            .hooks
+            .lock()
+            .expect("drain hooks lock poisoned")
+            .push(Box::pin(hook));
+    }
+
+    /// Wait for tracked connections to close, then run drain hooks in
+    /// registration order. Callers bound this with their drain timeout.
+    pub async fn drain(&self) {
+        loop {
+            let idle = self.inner.idle.notified();
+            tokio::pin!(idle);
+            idle.as_mut().enable();
+            if self.active_connections() == 0 {
+                break;
+            }
+            idle.await;
+        }
+
+        let hooks =
+            std::mem::take(&mut *self.inner.hooks.lock().expect("drain hooks lock poisoned"));
+        for hook in hooks {
+            hook.await;
+        }
+    }
+}
+
+/// Marks a connection as active for as long as it is held.
+pub struct ConnectionGuard {
+    inner: Arc<Inner>,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        if self.inner.active.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+/// Resolve on SIGINT (Ctrl+C) or, on Unix, SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received Ctrl+C"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn drain_waits_for_connections_then_runs_hooks() {
+        let controller = ShutdownController::new();
+        let guard = controller.track();
+        let flushed = Arc::new(AtomicBool::new(false));
+        let hook_flag = Arc::clone(&flushed);
+        controller.on_drain(async move {
+            hook_flag.store(true, Ordering::SeqCst);
+        });
+
+        let triggered = controller.triggered();
+        controller.trigger();
+        tokio::time::timeout(Duration::from_secs(1), triggered)
+            .await
+            .expect("trigger is observed");
+
+        let drain = tokio::spawn({
+            let controller = controller.clone();
+            async move { controller.drain().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!drain.is_finished());
+        assert!(!flushed.load(Ordering::SeqCst));
+
+        drop(guard);
+        tokio::time::timeout(Duration::from_secs(1), drain)
+            .await
+            .expect("drain finishes once connections close")
+            .unwrap();
+        assert!(flushed.load(Ordering::SeqCst));
+        assert_eq!(controller.active_connections(), 0);
+    }
+}
@@ -0,0 +1,285 @@
+//! Spam heuristics for message traffic, aimed at misbehaving bots.
+//!
+//! A [`SpamGuard`] remembers what each member recently sent to each room
+//! and looks for three signals: the same text over and over, more links
+//! than a conversation needs, and bursts faster than anyone types. Every
+//! signal a message trips is a strike against its sender in that room;
+//! once the strikes within `score_window_secs` reach `mute_score`, the
+//! guard calls for a mute. Thresholds come from `[spam]`; what a verdict
+//! leads to is up to the caller.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::SpamConfig;
+
+/// Tracked member/room pairs above which idle ones are dropped.
+const PRUNE_ABOVE: usize = 4_096;
+
+/// A pattern of spam the guard looks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpamSignal {
+    /// More than `max_repeats` identical messages within `window_secs`.
+    Repeat,
+    /// More than `max_links` links within `window_secs`.
+    LinkFlood,
+    /// More than `burst_messages` messages within `burst_secs`.
+    Burst,
+}
+
+impl SpamSignal {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Repeat => "repeat",
+            Self::LinkFlood => "link_flood",
+            Self::Burst => "burst",
+        }
+    }
+}
+
+/// What the guard made of one message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpamVerdict {
+    /// Signals the message tripped; empty when it looks fine.
+    pub signals: Vec<SpamSignal>,
+    /// Strikes against the sender in the room, this message's included.
+    pub score: usize,
+    /// Whether the score reached `mute_score`.
+    pub mute: bool,
+}
+
+/// Recent activity of members in rooms, kept in memory.
+#[derive(Debug, Default)]
+pub struct SpamGuard {
+    activity: Mutex<HashMap<(String, String), Activity>>,
+}
+
+#[derive(Debug, Default)]
+struct Activity {
+    sent: VecDeque<Sent>,
+    strikes: VecDeque<Instant>,
+}
+
+#[derive(Debug)]
+struct Sent {
+    at: Instant,
+    /// Digest of the normalized text; `None` for blank text.
+    digest: Option<u64>,
+    links: usize,
+}
+
+impl SpamGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Weigh `text`, sent by `member_id` to `room_id` at `now`, against
+    /// what the member sent there before.
+    pub fn check(
+        &self,
+        config: &SpamConfig,
+        room_id: &str,
+        member_id: &str,
+        text: &str,
+        now: Instant,
+    ) -> SpamVerdict {
+        let window = Duration::from_secs(config.window_secs);
+        let burst = Duration::from_secs(config.burst_secs);
+        let score_window = Duration::from_secs(config.score_window_secs);
+        let within = |at: Instant, span: Duration| now.saturating_duration_since(at) < span;
+
+        let mut activity = self.activity.lock().expect("spam guard poisoned");
+        if activity.len() > PRUNE_ABOVE {
+            activity.retain(|_, member| {
+                member
+                    .sent
+                    .back()
+                    .is_some_and(|sent| within(sent.at, window.max(burst)))
+                    || member
+                        .strikes
+                        .back()
+                        .is_some_and(|at| within(*at, score_window))
+            });
+        }
+        let key = (room_id.to_string(), member_id.to_string());
+        let member = activity.entry(key.clone()).or_default();
+        while member
+            .sent
+            .front()
+            .is_some_and(|sent| !within(sent.at, window.max(burst)))
+        {
+            member.sent.pop_front();
+        }
+        while member
+            .strikes
+            .front()
+            .is_some_and(|at| !within(*at, score_window))
+        {
+            member.strikes.pop_front();
+        }
+        let digest = digest(text);
+        member.sent.push_back(Sent {
+            at: now,
+            digest,
+            links: count_links(text),
+        });
+
+        let mut signals = Vec::new();
+        let recent = || member.sent.iter().filter(|sent| within(sent.at, window));
+        if digest.is_some()
+            && recent().filter(|sent| sent.digest == digest).count() > config.max_repeats
+        {
+            signals.push(SpamSignal::Repeat);
+        }
+        if recent().map(|sent| sent.links).sum::<usize>() > config.max_links {
+            signals.push(SpamSignal::LinkFlood);
+        }
+        let in_burst = member
+            .sent
+            .iter()
+            .filter(|sent| within(sent.at, burst))
+            .count();
+        if in_burst > config.burst_messages {
+            signals.push(SpamSignal::Burst);
+        }
+
+        member.strikes.extend(signals.iter().map(|_| now));
+        let score = member.strikes.len();
+        let mute = score >= config.mute_score;
+        if mute {
+            // Start afresh once the mute lapses.
+            activity.remove(&key);
+        }
+        SpamVerdict {
+            signals,
+            score,
+            mute,
+        }
+    }
+
+    /// Forget activity in a deleted room.
+    pub fn remove_room(&self, room_id: &str) {
+        self.activity
+            .lock()
+            .expect("spam guard poisoned")
+            .retain(|(room, _), _| room != room_id);
+    }
+}
+
+/// Digest of `text` ignoring case and whitespace; `None` when blank.
+fn digest(text: &str) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    let mut words = 0;
+    for word in text.split_whitespace() {
+        word.to_lowercase().hash(&mut hasher);
+        words += 1;
+    }
+    (words > 0).then(|| hasher.finish())
+}
+
+/// Number of web links in `text`.
+fn count_links(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|word| {
+            let word = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+            ["http://", "https://", "www."].iter().any(|prefix| {
+                word.len() > prefix.len() && word[..prefix.len()].eq_ignore_ascii_case(prefix)
+            })
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SpamConfig {
+        SpamConfig {
+            enabled: true,
+            window_secs: 60,
+            max_repeats: 2,
+            max_links: 3,
+            burst_messages: 4,
+            burst_secs: 5,
+            mute_score: 3,
+            score_window_secs: 600,
+            mute_secs: 300,
+        }
+    }
+
+    #[test]
+    fn repeats_links_and_bursts_are_struck_until_a_mute() {
+        let guard = SpamGuard::new();
+        let config = config();
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let check = |text: &str, secs: u64| guard.check(&config, "room_1", "bot", text, at(secs));
+
+        assert!(check("Buy now", 0).signals.is_empty());
+        assert!(check("buy   NOW", 10).signals.is_empty());
+        let verdict = check("buy now", 20);
+        assert_eq!(verdict.signals, [SpamSignal::Repeat]);
+        assert_eq!(verdict.score, 1);
+        assert!(!verdict.mute);
+        // Identical messages drop out of the window.
+        assert!(check("buy now", 75).signals.is_empty());
+
+        assert!(check("see https://a.example and www.b.example", 80)
+            .signals
+            .is_empty());
+        let verdict = check("(https://c.example) http://d.example", 85);
+        assert_eq!(verdict.signals, [SpamSignal::LinkFlood]);
+        assert_eq!(verdict.score, 2);
+
+        // Other members and rooms have their own tallies.
+        let other = guard.check(&config, "room_1", "alice", "buy now", at(86));
+        assert_eq!(other, SpamVerdict::default());
+
+        for text in ["one", "two", "three", "four"] {
+            check(text, 200);
+        }
+        let verdict = check("five", 201);
+        assert_eq!(verdict.signals, [SpamSignal::Burst]);
+        assert_eq!(verdict.score, 3);
+        assert!(verdict.mute);
+
+        // A mute wipes the slate.
+        assert_eq!(check("six", 202).score, 0);
+    }
+
+    #[test]
+    fn strikes_lapse_after_the_score_window() {
+        let guard = SpamGuard::new();
+        let config = config();
+        let start = Instant::now();
+        for (secs, text) in [(0, "x"), (1, "x"), (2, "x")] {
+            guard.check(
+                &config,
+                "room_1",
+                "bot",
+                text,
+                start + Duration::from_secs(secs),
+            );
+        }
+        let later = start + Duration::from_secs(700);
+        let verdict = guard.check(&config, "room_1", "bot", "y", later);
+        assert_eq!(verdict.score, 0);
+
+        guard.remove_room("room_1");
+        assert!(guard.activity.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn blank_text_is_never_a_repeat() {
+        let guard = SpamGuard::new();
+        let config = config();
+        let now = Instant::now();
+        for _ in 0..3 {
+            let verdict = guard.check(&config, "room_1", "bot", " ", now);
+            assert!(!verdict.signals.contains(&SpamSignal::Repeat));
+        }
+    }
+}
@@ -0,0 +1,514 @@
+//! Tenant management and quota enforcement.
+//!
+//! Tenants are kept behind the [`TenantStore`] trait, either in memory or in
+//! PostgreSQL with the `persistence-sqlx` feature. Tokens name their tenant
+//! by slug in the `tenant_id` claim; [`TenantDirectory`] resolves that slug,
+//! rejects suspended tenants and enforces the tenant's [`TenantQuota`].
+//! Tokens naming a tenant that was never registered are not subject to
+//! quotas.
+//!
+//! Daily message and vector document counters are held in memory and start
+//! from zero when the gateway restarts.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use nexis_core::tenant::{Tenant, TenantId, TenantQuota};
+use serde::Serialize;
+use thiserror::Error;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::config::{NexisConfig, TenantStoreKind};
+use crate::indexing::{DocumentQuota, IndexingError, IndexingResult};
+
+/// SQL schema for the `tenants` table.
+pub const TENANTS_TABLE_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS tenants (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    max_rooms BIGINT,
+    max_messages_per_day BIGINT,
+    max_vector_documents BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);"#;
+
+#[derive(Debug, Error)]
+pub enum TenantStoreError {
+    #[error("tenant slug already in use: {0}")]
+    SlugTaken(String),
+    #[error("tenant not found: {0}")]
+    NotFound(TenantId),
+    #[cfg(feature = "persistence-sqlx")]
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{0} tenant store is not available in this build")]
+    Unsupported(&'static str),
+}
+
+/// Quota a request was rejected by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaKind {
+    Rooms,
+    MessagesPerDay,
+    VectorDocuments,
+}
+
+impl QuotaKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Rooms => "rooms",
+            Self::MessagesPerDay => "messages_per_day",
+            Self::VectorDocuments => "vector_documents",
+        }
+    }
+}
+
+impl std::fmt::Display for QuotaKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TenantAccessError {
+    #[error("tenant {0} is suspended")]
+    Suspended(String),
+    #[error("tenant {slug} exceeded its {kind} quota of {limit}")]
+    QuotaExceeded {
+        slug: String,
+        kind: QuotaKind,
+        limit: u64,
+    },
+    #[error(transparent)]
+    Store(#[from] TenantStoreError),
+}
+
+/// Persistence for tenant records.
+#[async_trait]
+pub trait TenantStore: Send + Sync {
+    /// Persist a new tenant. Slugs are unique.
+    async fn create(&self, tenant: Tenant) -> Result<Tenant, TenantStoreError>;
+    async fn get(&self, id: TenantId) -> Result<Option<Tenant>, TenantStoreError>;
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Tenant>, TenantStoreError>;
+    /// All tenants, oldest first.
+    async fn list(&self) -> Result<Vec<Tenant>, TenantStoreError>;
+    /// Save the status and quota of an existing tenant.
+    async fn update(&self, tenant: &Tenant) -> Result<(), TenantStoreError>;
+}
+
+/// Tenant store for tests and single-node deployments.
+#[derive(Debug, Default)]
+pub struct InMemoryTenantStore {
+    tenants: RwLock<Vec<Tenant>>,
+}
+
+impl InMemoryTenantStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TenantStore for InMemoryTenantStore {
+    async fn create(&self, tenant: Tenant) -> Result<Tenant, TenantStoreError> {
+        let mut tenants = self.tenants.write().await;
+        if tenants.iter().any(|existing| existing.slug == tenant.slug) {
+            return Err(TenantStoreError::SlugTaken(tenant.slug));
+        }
+        tenants.push(tenant.clone());
+        Ok(tenant)
+    }
+
+    async fn get(&self, id: TenantId) -> Result<Option<Tenant>, TenantStoreError> {
+        let tenants = self.tenants.read().await;
+        Ok(tenants.iter().find(|tenant| tenant.id == id).cloned())
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Tenant>, TenantStoreError> {
+        let tenants = self.tenants.read().await;
+        Ok(tenants.iter().find(|tenant| tenant.slug == slug).cloned())
+    }
+
+    async fn list(&self) -> Result<Vec<Tenant>, TenantStoreError> {
+        Ok(self.tenants.read().await.clone())
+    }
+
+    async fn update(&self, tenant: &Tenant) -> Result<(), TenantStoreError> {
+        let mut tenants = self.tenants.write().await;
+        let existing = tenants
+            .iter_mut()
+            .find(|existing| existing.id == tenant.id)
+            .ok_or(TenantStoreError::NotFound(tenant.id))?;
+        existing.is_active = tenant.is_active;
+        existing.quota = tenant.quota;
+        Ok(())
+    }
+}
+
+/// Tenant store backed by the `tenants` table.
+#[cfg(feature = "persistence-sqlx")]
+pub struct SqlxTenantStore {
+    pool: crate::db::DatabasePool,
+}
+
+#[cfg(feature = "persistence-sqlx")]
+impl SqlxTenantStore {
+    /// Create the store, making sure the `tenants` table exists.
+    pub async fn new(pool: crate::db::DatabasePool) -> Result<Self, TenantStoreError> {
+        sqlx::query(TENANTS_TABLE_SCHEMA).execute(&pool).await?;
+        Ok(Self { pool })
+    }
+
+    fn tenant_from_row(row: &sqlx::postgres::PgRow) -> Result<Tenant, TenantStoreError> {
+        use sqlx::Row;
+
+        let id: String = row.try_get("id")?;
+        let limit = |column: &str| -> Result<Option<u64>, sqlx::Error> {
+            Ok(row
+                .try_get::<Option<i64>, _>(column)?
+                .map(|value| value.max(0) as u64))
+        };
+        Ok(Tenant {
+            id: TenantId::parse(&id).map_err(|err| {
+                sqlx::Error::Decode(format!("invalid tenant id {id}: {err}").into())
+            })?,
+            name: row.try_get("name")?,
+            slug: row.try_get("slug")?,
+            is_active: row.try_get("is_active")?,
+            quota: TenantQuota {
+                max_rooms: limit("max_rooms")?,
+                max_messages_per_day: limit("max_messages_per_day")?,
+                max_vector_documents: limit("max_vector_documents")?,
+            },
+        })
+    }
+}
+
+#[cfg(feature = "persistence-sqlx")]
+fn limit_column(limit: Option<u64>) -> Option<i64> {
+    limit.map(|value| i64::try_from(value).unwrap_or(i64::MAX))
+}
+
+#[cfg(feature = "persistence-sqlx")]
+#[async_trait]
+impl TenantStore for SqlxTenantStore {
+    async fn create(&self, tenant: Tenant) -> Result<Tenant, TenantStoreError> {
+        let inserted = sqlx::query(
+            "INSERT INTO tenants (id, name, slug, is_active, max_rooms, max_messages_per_day, max_vector_documents) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT (slug) DO NOTHING",
+        )
+        .bind(tenant.id.to_string())
+        .bind(&tenant.name)
+        .bind(&tenant.slug)
+        .bind(tenant.is_active)
+        .bind(limit_column(tenant.quota.max_rooms))
+        .bind(limit_column(tenant.quota.max_messages_per_day))
+        .bind(limit_column(tenant.quota.max_vector_documents))
+        .execute(&self.pool)
+        .await?;
+        if inserted.rows_affected() == 0 {
+            return Err(TenantStoreError::SlugTaken(tenant.slug));
+        }
+        Ok(tenant)
+    }
+
+    async fn get(&self, id: TenantId) -> Result<Option<Tenant>, TenantStoreError> {
+        sqlx::query("SELECT * FROM tenants WHERE id = $1")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?
+            .as_ref()
+            .map(Self::tenant_from_row)
+            .transpose()
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Tenant>, TenantStoreError> {
+        sqlx::query("SELECT * FROM tenants WHERE slug = $1")
+            .bind(slug)
+            .fetch_optional(&self.pool)
+            .await?
+            .as_ref()
+            .map(Self::tenant_from_row)
+            .transpose()
+    }
+
+    async fn list(&self) -> Result<Vec<Tenant>, TenantStoreError> {
+        sqlx::query("SELECT * FROM tenants ORDER BY created_at ASC, id ASC")
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(Self::tenant_from_row)
+            .collect()
+    }
+
+    async fn update(&self, tenant: &Tenant) -> Result<(), TenantStoreError> {
+        let updated = sqlx::query(
+            "UPDATE tenants SET is_active = $2, max_rooms = $3, max_messages_per_day = $4, \
+             max_vector_documents = $5 WHERE id = $1",
+        )
+        .bind(tenant.id.to_string())
+        .bind(tenant.is_active)
+        .bind(limit_column(tenant.quota.max_rooms))
+        .bind(limit_column(tenant.quota.max_messages_per_day))
+        .bind(limit_column(tenant.quota.max_vector_documents))
+        .execute(&self.pool)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(TenantStoreError::NotFound(tenant.id));
+        }
+        Ok(())
+    }
+}
+
+/// In-memory usage counters for one tenant.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TenantUsage {
+    /// UTC day `messages_today` counts towards.
+    pub day: Option<NaiveDate>,
+    pub messages_today: u64,
+    pub vector_documents: u64,
+}
+
+impl TenantUsage {
+    fn roll_over(&mut self, today: NaiveDate) {
+        if self.day != Some(today) {
+            self.day = Some(today);
+            self.messages_today = 0;
+        }
+    }
+}
+
+/// Resolves tenants for requests and enforces their status and quotas.
+#[derive(Clone)]
+pub struct TenantDirectory {
+    store: Arc<dyn TenantStore>,
+    usage: Arc<Mutex<HashMap<String, TenantUsage>>>,
+}
+
+impl Default for TenantDirectory {
+    fn default() -> Self {
+        Self::new(Arc::new(InMemoryTenantStore::new()))
+    }
+}
+
+impl TenantDirectory {
+    pub fn new(store: Arc<dyn TenantStore>) -> Self {
+        Self {
+            store,
+            usage: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Build the store selected by `tenants.store`.
+    pub async fn from_config(config: &NexisConfig) -> Result<Self, TenantStoreError> {
+        let store: Arc<dyn TenantStore> = match config.tenants.store {
+            TenantStoreKind::Memory => Arc::new(InMemoryTenantStore::new()),
+            #[cfg(feature = "persistence-sqlx")]
+            TenantStoreKind::Database => {
+                let url = config.database.url.as_deref().unwrap_or_default();
+                let pool = sqlx::postgres::PgPoolOptions::new()
+                    .max_connections(config.database.max_connections)
+                    .connect(url)
+                    .await?;
+                Arc::new(SqlxTenantStore::new(pool).await?)
+            }
+            #[cfg(not(feature = "persistence-sqlx"))]
+            TenantStoreKind::Database => return Err(TenantStoreError::Unsupported("database")),
+        };
+        Ok(Self::new(store))
+    }
+
+    pub fn store(&self) -> &Arc<dyn TenantStore> {
+        &self.store
+    }
+
+    /// Suspend or reactivate a tenant.
+    pub async fn set_active(&self, id: TenantId, active: bool) -> Result<Tenant, TenantStoreError> {
+        let tenant = self
+            .store
+            .get(id)
+            .await?
+            .ok_or(TenantStoreError::NotFound(id))?
+            .active(active);
+        self.store.update(&tenant).await?;
+        Ok(tenant)
+    }
+
+    pub async fn set_quota(
+        &self,
+        id: TenantId,
+        quota: TenantQuota,
+    ) -> Result<Tenant, TenantStoreError> {
+        let tenant = self
+            .store
+            .get(id)
+            .await?
+            .ok_or(TenantStoreError::NotFound(id))?
+            .with_quota(quota);
+        self.store.update(&tenant).await?;
+        Ok(tenant)
+    }
+
+    /// Current usage counters for the tenant with `slug`.
+    pub async fn usage(&self, slug: &str) -> TenantUsage {
+        let mut usage = self.usage.lock().await;
+        let entry = usage.entry(slug.to_string()).or_default();
+        entry.roll_over(Utc::now().date_naive());
+        entry.clone()
+    }
+
+    /// Resolve the tenant named by a token, rejecting suspended tenants.
+    /// Returns `None` for tenants that were never registered.
+    pub async fn admit(&self, slug: &str) -> Result<Option<Tenant>, TenantAccessError> {
+        match self.store.find_by_slug(slug).await? {
+            Some(tenant) if !tenant.is_active => Err(TenantAccessError::Suspended(tenant.slug)),
+            tenant => Ok(tenant),
+        }
+    }
+
+    /// Check that one more room fits next to the `current` rooms of `slug`.
+    pub async fn check_rooms(&self, slug: &str, current: usize) -> Result<(), TenantAccessError> {
+        let Some(tenant) = self.admit(slug).await? else {
+            return Ok(());
+        };
+        match tenant.quota.max_rooms {
+            Some(limit) if current as u64 >= limit => Err(TenantAccessError::QuotaExceeded {
+                slug: tenant.slug,
+                kind: QuotaKind::Rooms,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Count one message against today's allowance of `slug`.
+    pub async fn consume_message(&self, slug: &str) -> Result<(), TenantAccessError> {
+        let Some(tenant) = self.admit(slug).await? else {
+            return Ok(());
+        };
+        let mut usage = self.usage.lock().await;
+        let entry = usage.entry(tenant.slug.clone()).or_default();
+        entry.roll_over(Utc::now().date_naive());
+        if let Some(limit) = tenant.quota.max_messages_per_day {
+            if entry.messages_today >= limit {
+                return Err(TenantAccessError::QuotaExceeded {
+                    slug: tenant.slug,
+                    kind: QuotaKind::MessagesPerDay,
+                    limit,
+                });
+            }
+        }
+        entry.messages_today += 1;
+        Ok(())
+    }
+
+    /// Reserve room for `count` more vector documents owned by `slug`.
+    pub async fn reserve_documents(&self, slug: &str, count: u64) -> Result<(), TenantAccessError> {
+        let Some(tenant) = self.admit(slug).await? else {
+            return Ok(());
+        };
+        let mut usage = self.usage.lock().await;
+        let entry = usage.entry(tenant.slug.clone()).or_default();
+        if let Some(limit) = tenant.quota.max_vector_documents {
+            if entry.vector_documents + count > limit {
+                return Err(TenantAccessError::QuotaExceeded {
+                    slug: tenant.slug,
+                    kind: QuotaKind::VectorDocuments,
+                    limit,
+                });
+            }
+        }
+        entry.vector_documents += count;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DocumentQuota for TenantDirectory {
+    async fn reserve(&self, tenant_id: &str, documents: u64) -> IndexingResult<()> {
+        self.reserve_documents(tenant_id, documents)
+            .await
+            .map_err(|err| IndexingError::Rejected(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn directory_with(quota: TenantQuota) -> (TenantDirectory, Tenant) {
+        let directory = TenantDirectory::default();
+        let tenant = Tenant::new("Acme".to_string(), "acme".to_string())
+            .unwrap()
+            .with_quota(quota);
+        let tenant = directory.store().create(tenant).await.unwrap();
+        (directory, tenant)
+    }
+
+    #[tokio::test]
+    async fn in_memory_store_rejects_duplicate_slugs() {
+        let (directory, _) = directory_with(TenantQuota::unlimited()).await;
+        let duplicate = Tenant::new("Acme 2".to_string(), "acme".to_string()).unwrap();
+
+        let result = directory.store().create(duplicate).await;
+        assert!(matches!(result, Err(TenantStoreError::SlugTaken(slug)) if slug == "acme"));
+        assert_eq!(directory.store().list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn suspended_tenants_are_rejected_until_resumed() {
+        let (directory, tenant) = directory_with(TenantQuota::unlimited()).await;
+
+        directory.set_active(tenant.id, false).await.unwrap();
+        assert!(matches!(
+            directory.admit("acme").await,
+            Err(TenantAccessError::Suspended(_))
+        ));
+
+        directory.set_active(tenant.id, true).await.unwrap();
+        assert!(directory.admit("acme").await.unwrap().is_some());
+        assert!(directory.admit("unregistered").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn quotas_cap_rooms_messages_and_documents() {
+        let quota = TenantQuota::unlimited()
+            .with_max_rooms(1)
+            .with_max_messages_per_day(2)
+            .with_max_vector_documents(3);
+        let (directory, _) = directory_with(quota).await;
+
+        assert!(directory.check_rooms("acme", 0).await.is_ok());
+        assert!(matches!(
+            directory.check_rooms("acme", 1).await,
+            Err(TenantAccessError::QuotaExceeded {
+                kind: QuotaKind::Rooms,
+                ..
+            })
+        ));
+
+        directory.consume_message("acme").await.unwrap();
+        directory.consume_message("acme").await.unwrap();
+        assert!(matches!(
+            directory.consume_message("acme").await,
+            Err(TenantAccessError::QuotaExceeded {
+                kind: QuotaKind::MessagesPerDay,
+                limit: 2,
+                ..
+            })
+        ));
+
+        directory.reserve_documents("acme", 3).await.unwrap();
+        assert!(directory.reserve_documents("acme", 1).await.is_err());
+
+        let usage = directory.usage("acme").await;
+        assert_eq!(usage.messages_today, 2);
+        assert_eq!(usage.vector_documents, 3);
+    }
+}
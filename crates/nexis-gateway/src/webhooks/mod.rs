@@ -0,0 +1,611 @@
+//! Room-level outbound webhooks.
+//!
+//! Each delivery is a JSON `POST` signed with the webhook secret. Receivers
+//! verify `X-Nexis-Signature`, which is `sha256=<hex>` of an HMAC-SHA256 over
+//! `"{timestamp}.{body}"` with the timestamp taken from `X-Nexis-Timestamp`.
+//! Failed deliveries are retried with exponential backoff and every attempt is
+//! kept in a bounded per-webhook history.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use thiserror::Error;
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub const SIGNATURE_HEADER: &str = "x-nexis-signature";
+pub const TIMESTAMP_HEADER: &str = "x-nexis-timestamp";
+pub const EVENT_HEADER: &str = "x-nexis-event";
+pub const DELIVERY_HEADER: &str = "x-nexis-delivery";
+
+const SECRET_PREFIX: &str = "whsec_";
+const MAX_ATTEMPTS_RETAINED: usize = 100;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum WebhookError {
+    #[error("invalid webhook url: {0}")]
+    InvalidUrl(String),
+    #[error("webhook not found")]
+    NotFound,
+}
+
+/// Event types that can be delivered to a webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum WebhookEvent {
+    #[serde(rename = "message.created")]
+    MessageCreated,
+    #[serde(rename = "member.invited")]
+    MemberInvited,
+}
+
+impl WebhookEvent {
+    pub const ALL: [Self; 2] = [Self::MessageCreated, Self::MemberInvited];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::MessageCreated => "message.created",
+            Self::MemberInvited => "member.invited",
+        }
+    }
+}
+
+impl std::fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A registered webhook. The secret is never serialized.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub id: String,
+    pub room_id: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip)]
+    secret: String,
+}
+
+impl Webhook {
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    pub fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        self.events.contains(&event)
+    }
+}
+
+/// Outcome of a single delivery attempt.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryAttempt {
+    pub delivery_id: String,
+    pub webhook_id: String,
+    pub event: WebhookEvent,
+    pub attempt: u32,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub attempted_at: DateTime<Utc>,
+    pub duration_ms: u64,
+}
+
+/// Exponential backoff between delivery attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the attempt following `attempt` (1-based).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Compute the `X-Nexis-Signature` header value for a payload.
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Verify a signature produced by [`sign_payload`] in constant time.
+pub fn verify_signature(secret: &str, timestamp: i64, body: &[u8], signature: &str) -> bool {
+    let Some(Ok(expected)) = signature.strip_prefix("sha256=").map(hex::decode) else {
+        return false;
+    };
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{SECRET_PREFIX}{}", hex::encode(bytes))
+}
+
+fn validate_url(url: &str) -> Result<(), WebhookError> {
+    let parsed =
+        reqwest::Url::parse(url).map_err(|err| WebhookError::InvalidUrl(err.to_string()))?;
+    match parsed.scheme() {
+        "http" | "https" if parsed.host_str().is_some() => Ok(()),
+        _ => Err(WebhookError::InvalidUrl(
+            "url must be an absolute http(s) url".to_string(),
+        )),
+    }
+}
+
+#[derive(Debug, Default)]
+struct WebhookStore {
+    webhooks: HashMap<String, Webhook>,
+    attempts: HashMap<String, VecDeque<DeliveryAttempt>>,
+}
+
+/// Registry and delivery engine for room webhooks.
+#[derive(Debug, Clone)]
+pub struct WebhookService {
+    store: Arc<RwLock<WebhookStore>>,
+    client: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+impl Default for WebhookService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebhookService {
+    pub fn new() -> Self {
+        Self {
+            store: Arc::default(),
+            client: reqwest::Client::builder()
+                .timeout(DELIVERY_TIMEOUT)
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .expect("reqwest client should build"),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Register a webhook. An empty `events` list subscribes to every event.
+    pub async fn register(
+        &self,
+        room_id: &str,
+        url: &str,
+        events: Vec<WebhookEvent>,
+    ) -> Result<Webhook, WebhookError> {
+        validate_url(url)?;
+        let events = WebhookEvent::ALL
+            .into_iter()
+            .filter(|event| events.is_empty() || events.contains(event))
+            .collect();
+
+        let webhook = Webhook {
+            id: format!("wh_{}", Uuid::new_v4().simple()),
+            room_id: room_id.to_string(),
+            url: url.to_string(),
+            events,
+            enabled: true,
+            created_at: Utc::now(),
+            secret: generate_secret(),
+        };
+        self.store
+            .write()
+            .await
+            .webhooks
+            .insert(webhook.id.clone(), webhook.clone());
+        Ok(webhook)
+    }
+
+    /// Webhooks registered for a room, oldest first.
+    pub async fn list(&self, room_id: &str) -> Vec<Webhook> {
+        let store = self.store.read().await;
+        let mut webhooks: Vec<_> = store
+            .webhooks
+            .values()
+            .filter(|webhook| webhook.room_id == room_id)
+            .cloned()
+            .collect();
+        webhooks.sort_by_key(|webhook| webhook.created_at);
+        webhooks
+    }
+
+    pub async fn get(&self, room_id: &str, webhook_id: &str) -> Result<Webhook, WebhookError> {
+        self.store
+            .read()
+            .await
+            .webhooks
+            .get(webhook_id)
+            .filter(|webhook| webhook.room_id == room_id)
+            .cloned()
+            .ok_or(WebhookError::NotFound)
+    }
+
+    /// Replace the signing secret. Deliveries already in flight keep the old one.
+    pub async fn rotate_secret(
+        &self,
+        room_id: &str,
+        webhook_id: &str,
+    ) -> Result<Webhook, WebhookError> {
+        self.update(room_id, webhook_id, |webhook| {
+            webhook.secret = generate_secret();
+        })
+        .await
+    }
+
+    pub async fn disable(&self, room_id: &str, webhook_id: &str) -> Result<Webhook, WebhookError> {
+        self.update(room_id, webhook_id, |webhook| webhook.enabled = false)
+            .await
+    }
+
+    /// Delivery attempts for a webhook, most recent first.
+    pub async fn deliveries(
+        &self,
+        room_id: &str,
+        webhook_id: &str,
+    ) -> Result<Vec<DeliveryAttempt>, WebhookError> {
+        self.get(room_id, webhook_id).await?;
+        Ok(self
+            .store
+            .read()
+            .await
+            .attempts
+            .get(webhook_id)
+            .map(|attempts| attempts.iter().rev().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    /// Deliver `data` to every enabled webhook of `room_id` subscribed to `event`.
+    ///
+    /// Deliveries run in the background so callers never wait on receivers.
+    pub async fn dispatch(&self, room_id: &str, event: WebhookEvent, data: Value) {
+        let targets: Vec<Webhook> = self
+            .store
+            .read()
+            .await
+            .webhooks
+            .values()
+            .filter(|webhook| {
+                webhook.room_id == room_id && webhook.enabled && webhook.subscribes_to(event)
+            })
+            .cloned()
+            .collect();
+
+        for webhook in targets {
+            let service = self.clone();
+            let data = data.clone();
+            tokio::spawn(async move { service.deliver(webhook, event, data).await });
+        }
+    }
+
+    async fn deliver(&self, webhook: Webhook, event: WebhookEvent, data: Value) {
+        let delivery_id = format!("dlv_{}", Uuid::new_v4().simple());
+        let body = json!({
+            "id": delivery_id,
+            "type": event,
+            "roomId": webhook.room_id,
+            "createdAt": Utc::now(),
+            "data": data,
+        })
+        .to_string();
+
+        for attempt in 1..=self.retry.max_attempts {
+            // Stop retrying once the webhook has been disabled.
+            if attempt > 1 && !self.is_enabled(&webhook.id).await {
+                return;
+            }
+
+            let timestamp = Utc::now().timestamp();
+            let started = std::time::Instant::now();
+            let result = self
+                .client
+                .post(&webhook.url)
+                .header("content-type", "application/json")
+                .header(EVENT_HEADER, event.as_str())
+                .header(DELIVERY_HEADER, &delivery_id)
+                .header(TIMESTAMP_HEADER, timestamp)
+                .header(
+                    SIGNATURE_HEADER,
+                    sign_payload(&webhook.secret, timestamp, body.as_bytes()),
+                )
+                .body(body.clone())
+                .send()
+                .await;
+
+            let (status_code, error, retryable) = match result {
+                Ok(response) if response.status().is_success() => {
+                    (Some(response.status().as_u16()), None, false)
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let retryable = status.is_server_error()
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                        || status == reqwest::StatusCode::REQUEST_TIMEOUT;
+                    (
+                        Some(status.as_u16()),
+                        Some(format!("receiver responded with {status}")),
+                        retryable,
+                    )
+                }
+                Err(err) => (None, Some(err.to_string()), true),
+            };
+            let success = error.is_none();
+
+            self.record(DeliveryAttempt {
+                delivery_id: delivery_id.clone(),
+                webhook_id: webhook.id.clone(),
+                event,
+                attempt,
+                success,
+                status_code,
+                error: error.clone(),
+                attempted_at: Utc::now(),
+                duration_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+            })
+            .await;
+
+            if success || !retryable {
+                if let Some(error) = error {
+                    tracing::warn!(webhook_id = %webhook.id, "webhook delivery failed: {}", error);
+                }
+                return;
+            }
+            if attempt < self.retry.max_attempts {
+                tokio::time::sleep(self.retry.backoff(attempt)).await;
+            }
+        }
+
+        tracing::warn!(
+            webhook_id = %webhook.id,
+            delivery_id = %delivery_id,
+            "webhook delivery abandoned after {} attempts",
+            self.retry.max_attempts
+        );
+    }
+
+    async fn is_enabled(&self, webhook_id: &str) -> bool {
+        self.store
+            .read()
+            .await
+            .webhooks
+            .get(webhook_id)
+            .is_some_and(|webhook| webhook.enabled)
+    }
+
+    async fn record(&self, attempt: DeliveryAttempt) {
+        let mut store = self.store.write().await;
+        let attempts = store
+            .attempts
+            .entry(attempt.webhook_id.clone())
+            .or_default();
+        if attempts.len() == MAX_ATTEMPTS_RETAINED {
+            attempts.pop_front();
+        }
+        attempts.push_back(attempt);
+    }
+
+    async fn update(
+        &self,
+        room_id: &str,
+        webhook_id: &str,
+        apply: impl FnOnce(&mut Webhook),
+    ) -> Result<Webhook, WebhookError> {
+        let mut store = self.store.write().await;
+        let webhook = store
+            .webhooks
+            .get_mut(webhook_id)
+            .filter(|webhook| webhook.room_id == room_id)
+            .ok_or(WebhookError::NotFound)?;
+        apply(webhook);
+        Ok(webhook.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn signatures_round_trip_and_reject_tampering() {
+        let signature = sign_payload("whsec_test", 1_700_000_000, b"{\"a\":1}");
+        assert!(signature.starts_with("sha256="));
+        assert!(verify_signature(
+            "whsec_test",
+            1_700_000_000,
+            b"{\"a\":1}",
+            &signature
+        ));
+        assert!(!verify_signature(
+            "whsec_test",
+            1_700_000_001,
+            b"{\"a\":1}",
+            &signature
+        ));
+        assert!(!verify_signature(
+            "whsec_other",
+            1_700_000_000,
+            b"{\"a\":1}",
+            &signature
+        ));
+        assert!(!verify_signature("whsec_test", 1, b"", "md5=abc"));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn register_rotate_and_disable() {
+        let service = WebhookService::new();
+        assert_eq!(
+            service
+                .register("room", "ftp://example.com", vec![])
+                .await
+                .unwrap_err(),
+            WebhookError::InvalidUrl("url must be an absolute http(s) url".to_string())
+        );
+
+        let webhook = service
+            .register("room", "https://example.com/hook", vec![])
+            .await
+            .unwrap();
+        assert_eq!(webhook.events, WebhookEvent::ALL.to_vec());
+        assert!(webhook.secret().starts_with(SECRET_PREFIX));
+
+        let rotated = service.rotate_secret("room", &webhook.id).await.unwrap();
+        assert_ne!(rotated.secret(), webhook.secret());
+
+        let disabled = service.disable("room", &webhook.id).await.unwrap();
+        assert!(!disabled.enabled);
+        assert_eq!(
+            service
+                .disable("other-room", &webhook.id)
+                .await
+                .unwrap_err(),
+            WebhookError::NotFound
+        );
+        assert_eq!(service.list("room").await.len(), 1);
+    }
+
+    #[derive(Clone)]
+    struct Receiver {
+        secret: Arc<RwLock<String>>,
+        calls: Arc<AtomicUsize>,
+        verified: Arc<AtomicUsize>,
+    }
+
+    async fn receive(
+        State(receiver): State<Receiver>,
+        headers: HeaderMap,
+        body: String,
+    ) -> StatusCode {
+        let call = receiver.calls.fetch_add(1, Ordering::SeqCst);
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        let signature = headers[SIGNATURE_HEADER].to_str().unwrap();
+        let secret = receiver.secret.read().await.clone();
+        if verify_signature(&secret, timestamp, body.as_bytes(), signature) {
+            receiver.verified.fetch_add(1, Ordering::SeqCst);
+        }
+        // Fail the first attempt to exercise retries.
+        if call == 0 {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::NO_CONTENT
+        }
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_signed_retried_and_recorded() {
+        let receiver = Receiver {
+            secret: Arc::default(),
+            calls: Arc::default(),
+            verified: Arc::default(),
+        };
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(receiver.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let service = WebhookService::new().with_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+        });
+        let webhook = service
+            .register(
+                "room",
+                &format!("http://{addr}/hook"),
+                vec![WebhookEvent::MessageCreated],
+            )
+            .await
+            .unwrap();
+        *receiver.secret.write().await = webhook.secret().to_string();
+
+        // Not subscribed, so nothing is sent.
+        service
+            .dispatch("room", WebhookEvent::MemberInvited, json!({}))
+            .await;
+        service
+            .dispatch("room", WebhookEvent::MessageCreated, json!({"text": "hi"}))
+            .await;
+
+        let mut deliveries = Vec::new();
+        for _ in 0..100 {
+            deliveries = service.deliveries("room", &webhook.id).await.unwrap();
+            if deliveries.iter().any(|attempt| attempt.success) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        assert_eq!(deliveries.len(), 2);
+        assert!(deliveries[0].success);
+        assert_eq!(deliveries[0].attempt, 2);
+        assert_eq!(deliveries[1].status_code, Some(503));
+        assert_eq!(deliveries[0].delivery_id, deliveries[1].delivery_id);
+        assert_eq!(receiver.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(receiver.verified.load(Ordering::SeqCst), 2);
+    }
+}
@@ -7,12 +7,15 @@ use tower::ServiceExt;
 fn auth_header() -> String {
     let now = chrono::Utc::now().timestamp() as usize;
     let claims = nexis_gateway::auth::Claims {
-        sub: "integration-user".to_string(),
+        sub: "nexis:human:integration-user".to_string(),
         exp: now + 3600,
         iat: now,
         iss: "nexis".to_string(),
         aud: "nexis".to_string(),
         member_type: "human".to_string(),
+        jti: String::new(),
+        #[cfg(feature = "multi-tenant")]
+        tenant_id: None,
     };
     let token = jsonwebtoken::encode(
         &jsonwebtoken::Header::default(),
@@ -72,7 +75,7 @@ async fn api_create_room_and_send_message_roundtrip() {
                 .body(Body::from(
                     serde_json::json!({
                         "roomId": room_id,
-                        "sender": "integration-user",
+                        "sender": "nexis:human:integration-user",
                         "text": "integration message",
                     })
                     .to_string(),
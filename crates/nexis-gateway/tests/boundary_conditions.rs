@@ -7,12 +7,15 @@ use tower::ServiceExt;
 fn auth_header() -> String {
     let now = chrono::Utc::now().timestamp() as usize;
     let claims = nexis_gateway::auth::Claims {
-        sub: "boundary-user".to_string(),
+        sub: "nexis:human:boundary-user".to_string(),
         exp: now + 3600,
         iat: now,
         iss: "nexis".to_string(),
         aud: "nexis".to_string(),
         member_type: "human".to_string(),
+        jti: String::new(),
+        #[cfg(feature = "multi-tenant")]
+        tenant_id: None,
     };
     let token = jsonwebtoken::encode(
         &jsonwebtoken::Header::default(),
@@ -77,7 +80,7 @@ async fn rejects_oversized_messages() {
                 .body(Body::from(
                     serde_json::json!({
                         "roomId": room_id,
-                        "sender": "boundary-user",
+                        "sender": "nexis:human:boundary-user",
                         "text": oversized_text,
                     })
                     .to_string(),
@@ -87,7 +90,7 @@ async fn rejects_oversized_messages() {
         .await
         .expect("oversized message response should exist");
 
-    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
 }
 
 #[tokio::test]
@@ -111,7 +114,7 @@ async fn supports_concurrent_message_writes() {
                         .body(Body::from(
                             serde_json::json!({
                                 "roomId": room_id,
-                                "sender": format!("sender-{idx}"),
+                                "sender": "nexis:human:boundary-user",
                                 "text": format!("msg-{idx}"),
                             })
                             .to_string(),
@@ -176,7 +179,7 @@ async fn recovers_after_bad_request_and_accepts_next_message() {
                 .body(Body::from(
                     serde_json::json!({
                         "roomId": room_id,
-                        "sender": "boundary-user",
+                        "sender": "nexis:human:boundary-user",
                         "text": "",
                     })
                     .to_string(),
@@ -186,7 +189,7 @@ async fn recovers_after_bad_request_and_accepts_next_message() {
         .await
         .expect("invalid request response should exist");
 
-    assert_eq!(invalid_response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(invalid_response.status(), StatusCode::UNPROCESSABLE_ENTITY);
 
     let valid_response = app
         .clone()
@@ -199,7 +202,7 @@ async fn recovers_after_bad_request_and_accepts_next_message() {
                 .body(Body::from(
                     serde_json::json!({
                         "roomId": room_id,
-                        "sender": "boundary-user",
+                        "sender": "nexis:human:boundary-user",
                         "text": "after-error",
                     })
                     .to_string(),
@@ -20,6 +20,9 @@ fn auth_header_for_subject(subject: &str) -> String {
         iss: "nexis".to_string(),
         aud: "nexis".to_string(),
         member_type: "human".to_string(),
+        jti: String::new(),
+        #[cfg(feature = "multi-tenant")]
+        tenant_id: None,
     };
     let token = jsonwebtoken::encode(
         &jsonwebtoken::Header::default(),
@@ -101,7 +101,7 @@ async fn message_throughput_under_load() {
                         .post("http://127.0.0.1:8080/v1/messages")
                         .json(&serde_json::json!({
                             "roomId": room_id,
-                            "sender": format!("sender-{worker}"),
+                            "sender": format!("nexis:human:sender-{worker}"),
                             "text": format!("msg-{worker}-{i}"),
                         }))
                         .send()
@@ -4,6 +4,7 @@ use async_trait::async_trait;
 use futures::StreamExt;
 use nexis_runtime::{
     AIProvider, GenerateRequest, GenerateResponse, ProviderError, ProviderStream, StreamChunk,
+    TokenUsage,
 };
 use reqwest::StatusCode;
 use reqwest_eventsource::{Event, RequestBuilderExt};
@@ -95,6 +96,7 @@ impl AIProvider for AnthropicProvider {
     }
 
     async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+        req.ensure_text_only(self.name())?;
         let payload = self.payload(req, false);
         let response = self
             .client
@@ -127,10 +129,15 @@ impl AIProvider for AnthropicProvider {
             content,
             model: Some(body.model),
             finish_reason: body.stop_reason,
+            usage: body.usage.map(|usage| TokenUsage {
+                input_tokens: usage.input_tokens,
+                output_tokens: usage.output_tokens,
+            }),
         })
     }
 
     async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
+        req.ensure_text_only(self.name())?;
         let payload = self.payload(req, true);
         let request = self
             .client
@@ -241,6 +248,14 @@ struct AnthropicMessageResponse {
     model: String,
     content: Vec<AnthropicContentBlock>,
     stop_reason: Option<String>,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicUsage {
+    input_tokens: u32,
+    output_tokens: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -288,6 +303,7 @@ mod tests {
             max_tokens: Some(64),
             temperature: Some(0.1),
             metadata: None,
+            images: Vec::new(),
         }
     }
 
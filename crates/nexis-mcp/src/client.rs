@@ -0,0 +1,512 @@
+//! MCP client over stdio or HTTP+SSE.
+//!
+//! Transports only move newline-free JSON-RPC strings; [`McpClient`] matches
+//! responses to requests by id, so a custom transport can be plugged in with
+//! [`McpClient::from_channels`].
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use futures::StreamExt;
+use reqwest_eventsource::{Event, RequestBuilderExt};
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use crate::protocol::{
+    CallToolResult, Implementation, InitializeResult, JsonRpcMessage, JsonRpcRequest,
+    JsonRpcResponse, ListToolsResult, McpToolInfo, MCP_PROTOCOL_VERSION,
+};
+
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, Error)]
+pub enum McpError {
+    #[error("transport error: {0}")]
+    Transport(String),
+    #[error("protocol error: {0}")]
+    Protocol(String),
+    #[error("server error {code}: {message}")]
+    Server { code: i64, message: String },
+    #[error("request timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("connection closed")]
+    Closed,
+}
+
+type PendingRequests = Arc<Mutex<HashMap<u64, oneshot::Sender<JsonRpcResponse>>>>;
+
+/// Connection to a single MCP server.
+pub struct McpClient {
+    outbound: mpsc::Sender<String>,
+    pending: PendingRequests,
+    next_id: AtomicU64,
+    timeout: Duration,
+    server: OnceLock<InitializeResult>,
+    tasks: Vec<JoinHandle<()>>,
+    _child: Option<Child>,
+}
+
+impl std::fmt::Debug for McpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("McpClient")
+            .field("server", &self.server.get().map(|info| &info.server_info))
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl McpClient {
+    /// Build a client over raw message channels.
+    ///
+    /// `outbound` receives serialized requests, and `inbound` yields serialized
+    /// server messages. Call [`McpClient::initialize`] before issuing requests.
+    pub fn from_channels(outbound: mpsc::Sender<String>, inbound: mpsc::Receiver<String>) -> Self {
+        let pending: PendingRequests = Arc::default();
+        let dispatcher = tokio::spawn(dispatch(inbound, outbound.clone(), Arc::clone(&pending)));
+        Self {
+            outbound,
+            pending,
+            next_id: AtomicU64::new(1),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            server: OnceLock::new(),
+            tasks: vec![dispatcher],
+            _child: None,
+        }
+    }
+
+    /// Spawn a server process and speak newline-delimited JSON-RPC over its stdio.
+    pub async fn connect_stdio(mut command: Command) -> Result<Self, McpError> {
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+        let mut child = command
+            .spawn()
+            .map_err(|err| McpError::Transport(format!("failed to spawn server: {err}")))?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| McpError::Transport("server stdin unavailable".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| McpError::Transport("server stdout unavailable".to_string()))?;
+
+        let (out_tx, mut out_rx) = mpsc::channel::<String>(CHANNEL_CAPACITY);
+        let (in_tx, in_rx) = mpsc::channel::<String>(CHANNEL_CAPACITY);
+
+        let writer = tokio::spawn(async move {
+            while let Some(line) = out_rx.recv().await {
+                if stdin.write_all(line.as_bytes()).await.is_err()
+                    || stdin.write_all(b"\n").await.is_err()
+                    || stdin.flush().await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+        let reader = tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if !line.trim().is_empty() && in_tx.send(line).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut client = Self::from_channels(out_tx, in_rx);
+        client.tasks.extend([writer, reader]);
+        client._child = Some(child);
+        client.initialize().await?;
+        Ok(client)
+    }
+
+    /// Connect to a server using the HTTP+SSE transport.
+    ///
+    /// The server announces a POST endpoint in an `endpoint` event; requests are
+    /// posted there and responses arrive as `message` events on the stream.
+    pub async fn connect_sse(url: &str) -> Result<Self, McpError> {
+        let base = reqwest::Url::parse(url).map_err(|err| McpError::Transport(err.to_string()))?;
+        let http = reqwest::Client::new();
+        let mut events = http
+            .get(base.clone())
+            .eventsource()
+            .map_err(|err| McpError::Transport(err.to_string()))?;
+
+        let endpoint = tokio::time::timeout(DEFAULT_REQUEST_TIMEOUT, async {
+            while let Some(event) = events.next().await {
+                match event {
+                    Ok(Event::Message(message)) if message.event == "endpoint" => {
+                        return base
+                            .join(message.data.trim())
+                            .map_err(|err| McpError::Protocol(err.to_string()));
+                    }
+                    Ok(_) => continue,
+                    Err(err) => return Err(McpError::Transport(err.to_string())),
+                }
+            }
+            Err(McpError::Closed)
+        })
+        .await
+        .map_err(|_| McpError::Timeout(DEFAULT_REQUEST_TIMEOUT))??;
+
+        let (out_tx, mut out_rx) = mpsc::channel::<String>(CHANNEL_CAPACITY);
+        let (in_tx, in_rx) = mpsc::channel::<String>(CHANNEL_CAPACITY);
+
+        let writer = tokio::spawn(async move {
+            while let Some(body) = out_rx.recv().await {
+                let sent = http
+                    .post(endpoint.clone())
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(err) = sent {
+                    tracing::warn!("MCP SSE post failed: {}", err);
+                }
+            }
+        });
+        let reader = tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                match event {
+                    Ok(Event::Message(message)) if message.event == "message" => {
+                        if in_tx.send(message.data).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => continue,
+                    Err(err) => {
+                        tracing::warn!("MCP SSE stream ended: {}", err);
+                        events.close();
+                        break;
+                    }
+                }
+            }
+        });
+
+        let mut client = Self::from_channels(out_tx, in_rx);
+        client.tasks.extend([writer, reader]);
+        client.initialize().await?;
+        Ok(client)
+    }
+
+    /// Set the per-request timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Server identity from the handshake, once initialized.
+    pub fn server_info(&self) -> Option<&InitializeResult> {
+        self.server.get()
+    }
+
+    /// Perform the `initialize` handshake.
+    pub async fn initialize(&self) -> Result<&InitializeResult, McpError> {
+        if let Some(server) = self.server.get() {
+            return Ok(server);
+        }
+
+        let result = self
+            .request(
+                "initialize",
+                Some(json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": Implementation::nexis(),
+                })),
+            )
+            .await?;
+        let result: InitializeResult =
+            serde_json::from_value(result).map_err(|err| McpError::Protocol(err.to_string()))?;
+        self.notify("notifications/initialized", None).await?;
+
+        tracing::info!(
+            server = %result.server_info.name,
+            version = %result.server_info.version,
+            "MCP server initialized"
+        );
+        Ok(self.server.get_or_init(|| result))
+    }
+
+    /// List every tool the server exposes, following pagination cursors.
+    pub async fn list_tools(&self) -> Result<Vec<McpToolInfo>, McpError> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = cursor.as_ref().map(|cursor| json!({ "cursor": cursor }));
+            let page: ListToolsResult =
+                serde_json::from_value(self.request("tools/list", params).await?)
+                    .map_err(|err| McpError::Protocol(err.to_string()))?;
+            tools.extend(page.tools);
+            match page.next_cursor {
+                Some(next) if !next.is_empty() => cursor = Some(next),
+                _ => return Ok(tools),
+            }
+        }
+    }
+
+    /// Invoke a tool on the server.
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Value,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .request(
+                "tools/call",
+                Some(json!({ "name": name, "arguments": arguments })),
+            )
+            .await?;
+        serde_json::from_value(result).map_err(|err| McpError::Protocol(err.to_string()))
+    }
+
+    /// Send a request and wait for its response.
+    pub async fn request(&self, method: &str, params: Option<Value>) -> Result<Value, McpError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending
+            .lock()
+            .expect("mcp pending map poisoned")
+            .insert(id, tx);
+
+        let request = JsonRpcRequest::new(id, method, params);
+        if let Err(err) = self.send(&request).await {
+            self.pending
+                .lock()
+                .expect("mcp pending map poisoned")
+                .remove(&id);
+            return Err(err);
+        }
+
+        let response = match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(_)) => return Err(McpError::Closed),
+            Err(_) => {
+                self.pending
+                    .lock()
+                    .expect("mcp pending map poisoned")
+                    .remove(&id);
+                return Err(McpError::Timeout(self.timeout));
+            }
+        };
+
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(McpError::Server {
+                code: error.code,
+                message: error.message,
+            }),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+
+    /// Send a notification, which has no response.
+    pub async fn notify(&self, method: &str, params: Option<Value>) -> Result<(), McpError> {
+        self.send(&JsonRpcRequest::notification(method, params))
+            .await
+    }
+
+    async fn send(&self, request: &JsonRpcRequest) -> Result<(), McpError> {
+        let line =
+            serde_json::to_string(request).map_err(|err| McpError::Protocol(err.to_string()))?;
+        self.outbound.send(line).await.map_err(|_| McpError::Closed)
+    }
+}
+
+impl Drop for McpClient {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+async fn dispatch(
+    mut inbound: mpsc::Receiver<String>,
+    outbound: mpsc::Sender<String>,
+    pending: PendingRequests,
+) {
+    while let Some(line) = inbound.recv().await {
+        let message = match serde_json::from_str::<JsonRpcMessage>(&line) {
+            Ok(message) => message,
+            Err(err) => {
+                tracing::warn!("ignoring malformed MCP message: {}", err);
+                continue;
+            }
+        };
+
+        match message {
+            JsonRpcMessage::Response(response) => {
+                let sender = response.id.as_u64().and_then(|id| {
+                    pending
+                        .lock()
+                        .expect("mcp pending map poisoned")
+                        .remove(&id)
+                });
+                match sender {
+                    Some(sender) => {
+                        let _ = sender.send(response);
+                    }
+                    None => tracing::debug!("dropping MCP response for unknown id {}", response.id),
+                }
+            }
+            JsonRpcMessage::Request(request) => match (request.id, request.method.as_str()) {
+                (Some(id), "ping") => {
+                    let pong = JsonRpcResponse::success(id, json!({}));
+                    if let Ok(line) = serde_json::to_string(&pong) {
+                        let _ = outbound.send(line).await;
+                    }
+                }
+                (_, method) => tracing::debug!("ignoring MCP server message: {}", method),
+            },
+        }
+    }
+
+    // Dropping the senders wakes every waiting request with `Closed`.
+    pending.lock().expect("mcp pending map poisoned").clear();
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::protocol::{error_codes, JsonRpcError};
+
+    /// Start an in-process MCP server that exposes an `echo` and a `fail` tool.
+    pub(crate) fn fake_server() -> McpClient {
+        let (out_tx, mut out_rx) = mpsc::channel::<String>(16);
+        let (in_tx, in_rx) = mpsc::channel::<String>(16);
+
+        tokio::spawn(async move {
+            while let Some(line) = out_rx.recv().await {
+                let JsonRpcMessage::Request(request) = serde_json::from_str(&line).unwrap() else {
+                    continue;
+                };
+                let Some(id) = request.id.clone() else {
+                    continue;
+                };
+                let params = request.params.unwrap_or(Value::Null);
+                let response = match request.method.as_str() {
+                    "initialize" => JsonRpcResponse::success(
+                        id,
+                        json!({
+                            "protocolVersion": MCP_PROTOCOL_VERSION,
+                            "capabilities": {"tools": {}},
+                            "serverInfo": {"name": "fake", "version": "0.0.1"}
+                        }),
+                    ),
+                    "tools/list" if params.get("cursor").is_none() => JsonRpcResponse::success(
+                        id,
+                        json!({
+                            "tools": [{
+                                "name": "echo",
+                                "description": "Echo text back",
+                                "inputSchema": {"type": "object", "properties": {"text": {"type": "string"}}}
+                            }],
+                            "nextCursor": "page-2"
+                        }),
+                    ),
+                    "tools/list" => JsonRpcResponse::success(
+                        id,
+                        json!({
+                            "tools": [{"name": "fail", "inputSchema": {"type": "object"}}]
+                        }),
+                    ),
+                    "tools/call" => {
+                        let result = match params["name"].as_str() {
+                            Some("echo") => CallToolResult::text(
+                                params["arguments"]["text"].as_str().unwrap_or_default(),
+                            ),
+                            _ => CallToolResult::error("tool failed"),
+                        };
+                        JsonRpcResponse::success(id, serde_json::to_value(result).unwrap())
+                    }
+                    "never" => continue,
+                    other => JsonRpcResponse::failure(
+                        id,
+                        JsonRpcError::new(
+                            error_codes::METHOD_NOT_FOUND,
+                            format!("unknown method {other}"),
+                        ),
+                    ),
+                };
+                if in_tx
+                    .send(serde_json::to_string(&response).unwrap())
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        McpClient::from_channels(out_tx, in_rx)
+    }
+
+    #[tokio::test]
+    async fn initialize_and_list_tools_across_pages() {
+        let client = fake_server();
+        let server = client.initialize().await.unwrap();
+        assert_eq!(server.server_info.name, "fake");
+
+        let tools = client.list_tools().await.unwrap();
+        let names: Vec<_> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(names, ["echo", "fail"]);
+    }
+
+    #[tokio::test]
+    async fn call_tool_returns_content() {
+        let client = fake_server();
+        client.initialize().await.unwrap();
+
+        let result = client
+            .call_tool("echo", json!({"text": "hello"}))
+            .await
+            .unwrap();
+        assert_eq!(result.text_content(), "hello");
+        assert!(!result.is_error);
+    }
+
+    #[tokio::test]
+    async fn server_errors_and_timeouts_are_reported() {
+        let client = fake_server().with_timeout(Duration::from_millis(50));
+
+        let err = client.request("unknown", None).await.unwrap_err();
+        assert!(
+            matches!(err, McpError::Server { code, .. } if code == error_codes::METHOD_NOT_FOUND)
+        );
+
+        let err = client.request("never", None).await.unwrap_err();
+        assert!(matches!(err, McpError::Timeout(_)));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stdio_transport_speaks_line_delimited_json_rpc() {
+        let script = r#"
+while IFS= read -r line; do
+  case "$line" in
+    *'"initialize"'*) printf '%s\n' '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2024-11-05","capabilities":{},"serverInfo":{"name":"sh","version":"1"}}}' ;;
+    *'"tools/list"'*) printf '%s\n' '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"date","inputSchema":{"type":"object"}}]}}' ;;
+  esac
+done
+"#;
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(script);
+
+        let client = McpClient::connect_stdio(command).await.unwrap();
+        assert_eq!(client.server_info().unwrap().server_info.name, "sh");
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools[0].name, "date");
+    }
+}
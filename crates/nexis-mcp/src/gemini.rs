@@ -4,6 +4,7 @@ use async_trait::async_trait;
 use futures::StreamExt;
 use nexis_runtime::{
     AIProvider, GenerateRequest, GenerateResponse, ProviderError, ProviderStream, StreamChunk,
+    TokenUsage,
 };
 use reqwest::StatusCode;
 use reqwest_eventsource::{Event, RequestBuilderExt};
@@ -101,6 +102,7 @@ impl AIProvider for GeminiProvider {
     }
 
     async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+        req.ensure_text_only(self.name())?;
         let (model, payload) = self.payload(req);
 
         let response = self
@@ -121,6 +123,10 @@ impl AIProvider for GeminiProvider {
             .await
             .map_err(|err| ProviderError::Decode(err.to_string()))?;
 
+        let usage = body.usage_metadata.map(|usage| TokenUsage {
+            input_tokens: usage.prompt_token_count,
+            output_tokens: usage.candidates_token_count,
+        });
         let first_candidate =
             body.candidates.into_iter().next().ok_or_else(|| {
                 ProviderError::Decode("missing candidate in response".to_string())
@@ -138,10 +144,12 @@ impl AIProvider for GeminiProvider {
             content,
             model: Some(model),
             finish_reason: first_candidate.finish_reason,
+            usage,
         })
     }
 
     async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
+        req.ensure_text_only(self.name())?;
         let (model, payload) = self.payload(req);
         let request = self
             .client
@@ -236,6 +244,16 @@ struct GeminiGenerationConfig {
 struct GeminiGenerateResponse {
     #[serde(default)]
     candidates: Vec<GeminiCandidate>,
+    #[serde(rename = "usageMetadata", default)]
+    usage_metadata: Option<GeminiUsageMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiUsageMetadata {
+    #[serde(rename = "promptTokenCount", default)]
+    prompt_token_count: u32,
+    #[serde(rename = "candidatesTokenCount", default)]
+    candidates_token_count: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -266,7 +284,7 @@ mod tests {
     use futures::StreamExt;
     use httpmock::Method::POST;
     use httpmock::MockServer;
-    use nexis_runtime::{AIProvider, GenerateRequest, StreamChunk};
+    use nexis_runtime::{AIProvider, GenerateRequest, ImageInput, ProviderError, StreamChunk};
     use serde_json::json;
 
     fn network_tests_enabled() -> bool {
@@ -280,9 +298,27 @@ mod tests {
             max_tokens: Some(64),
             temperature: Some(0.1),
             metadata: None,
+            images: Vec::new(),
         }
     }
 
+    #[tokio::test]
+    async fn image_inputs_are_rejected_before_any_request() {
+        let provider = GeminiProvider::new("test-key").with_base_url("http://127.0.0.1:9");
+        assert!(!provider.supports_images());
+        let mut req = request();
+        req.images.push(ImageInput::Url {
+            url: "https://example.com/cat.png".to_string(),
+        });
+
+        let err = provider.generate(req.clone()).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Unsupported(_)), "{err}");
+        assert!(matches!(
+            provider.generate_stream(req).await,
+            Err(ProviderError::Unsupported(_))
+        ));
+    }
+
     #[tokio::test]
     async fn generate_calls_gemini_generate_content_endpoint() {
         if !network_tests_enabled() {
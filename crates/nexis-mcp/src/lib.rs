@@ -1,9 +1,17 @@
 mod anthropic;
+mod client;
 mod gemini;
 mod openai;
+pub mod protocol;
 mod registry;
+mod server;
+mod tool;
 
 pub use anthropic::AnthropicProvider;
+pub use client::{McpClient, McpError};
 pub use gemini::GeminiProvider;
 pub use openai::OpenAIProvider;
+pub use protocol::{CallToolResult, McpToolInfo, ToolContent};
 pub use registry::{create_provider, create_provider_from_env, ProviderKind};
+pub use server::{GatewayBackend, McpServer, NexisBackend, RoomHistoryMessage, SearchHit};
+pub use tool::McpTool;
@@ -0,0 +1,28 @@
+//! `nexis-mcp` - MCP stdio server for Nexis.
+//!
+//! Lets external agent frameworks operate inside Nexis rooms. Configured via
+//! `NEXIS_SERVER` (gateway URL), `NEXIS_MCP_MEMBER` (sender member id) and an
+//! optional `NEXIS_TOKEN` bearer token. Stdout carries the protocol, so
+//! diagnostics go to stderr.
+
+use std::sync::Arc;
+
+use nexis_mcp::{GatewayBackend, McpServer};
+
+#[tokio::main]
+async fn main() {
+    let server_url =
+        std::env::var("NEXIS_SERVER").unwrap_or_else(|_| "http://127.0.0.1:8080".to_string());
+    let member =
+        std::env::var("NEXIS_MCP_MEMBER").unwrap_or_else(|_| "nexis:agent:mcp".to_string());
+
+    let mut backend = GatewayBackend::new(server_url, member);
+    if let Ok(token) = std::env::var("NEXIS_TOKEN") {
+        backend = backend.with_token(token);
+    }
+
+    if let Err(err) = McpServer::new(Arc::new(backend)).serve_stdio().await {
+        eprintln!("nexis-mcp: {err}");
+        std::process::exit(1);
+    }
+}
@@ -4,6 +4,7 @@ use async_trait::async_trait;
 use futures::StreamExt;
 use nexis_runtime::{
     AIProvider, GenerateRequest, GenerateResponse, ProviderError, ProviderStream, StreamChunk,
+    TokenUsage,
 };
 use reqwest::StatusCode;
 use reqwest_eventsource::{Event, RequestBuilderExt};
@@ -88,6 +89,7 @@ impl AIProvider for OpenAIProvider {
     }
 
     async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+        req.ensure_text_only(self.name())?;
         let payload = self.payload(req, false);
         let response = self
             .client
@@ -108,6 +110,10 @@ impl AIProvider for OpenAIProvider {
             .await
             .map_err(|err| ProviderError::Decode(err.to_string()))?;
 
+        let usage = body.usage.map(|usage| TokenUsage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+        });
         let first_choice = body
             .choices
             .into_iter()
@@ -119,10 +125,12 @@ impl AIProvider for OpenAIProvider {
             content,
             model: Some(body.model),
             finish_reason: first_choice.finish_reason,
+            usage,
         })
     }
 
     async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
+        req.ensure_text_only(self.name())?;
         let payload = self.payload(req, true);
         let request = self
             .client
@@ -215,6 +223,14 @@ struct OpenAIChatMessage {
 struct OpenAIChatCompletionResponse {
     model: String,
     choices: Vec<OpenAIChoice>,
+    #[serde(default)]
+    usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -274,6 +290,7 @@ mod tests {
             max_tokens: Some(32),
             temperature: Some(0.2),
             metadata: None,
+            images: Vec::new(),
         }
     }
 
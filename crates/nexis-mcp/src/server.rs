@@ -0,0 +1,598 @@
+//! MCP server exposing Nexis rooms and search to external agents.
+//!
+//! The server speaks newline-delimited JSON-RPC (the MCP stdio transport) and
+//! forwards tool calls to a [`NexisBackend`], usually a [`GatewayBackend`]
+//! pointed at a running gateway.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::client::McpError;
+use crate::protocol::{
+    error_codes, CallToolResult, Implementation, InitializeResult, JsonRpcError, JsonRpcMessage,
+    JsonRpcRequest, JsonRpcResponse, ListToolsResult, McpToolInfo, MCP_PROTOCOL_VERSION,
+};
+
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+const MAX_HISTORY_LIMIT: usize = 500;
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// A message as returned by `read_room_history`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomHistoryMessage {
+    pub id: String,
+    pub sender: String,
+    pub text: String,
+    #[serde(default, rename = "replyTo", skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+}
+
+/// A hit returned by `semantic_search`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub id: String,
+    pub score: f32,
+    pub content: String,
+    #[serde(default, rename = "roomId", skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+}
+
+/// Nexis operations the MCP server exposes as tools.
+#[async_trait]
+pub trait NexisBackend: Send + Sync {
+    /// Post a message and return its id.
+    async fn send_message(
+        &self,
+        room_id: &str,
+        text: &str,
+        reply_to: Option<&str>,
+    ) -> Result<String, McpError>;
+
+    /// Return up to `limit` of the most recent messages, oldest first.
+    async fn room_history(
+        &self,
+        room_id: &str,
+        limit: usize,
+    ) -> Result<Vec<RoomHistoryMessage>, McpError>;
+
+    async fn semantic_search(
+        &self,
+        query: &str,
+        room_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>, McpError>;
+}
+
+/// [`NexisBackend`] backed by the gateway HTTP API.
+#[derive(Debug, Clone)]
+pub struct GatewayBackend {
+    client: reqwest::Client,
+    base_url: String,
+    sender: String,
+    token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GatewayRoom {
+    #[serde(default)]
+    messages: Vec<RoomHistoryMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GatewaySendResponse {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GatewaySearchResponse {
+    results: Vec<GatewaySearchItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GatewaySearchItem {
+    id: String,
+    score: f32,
+    content: String,
+    #[serde(default)]
+    room_id: Option<String>,
+}
+
+impl GatewayBackend {
+    /// Messages are posted as `sender`, which should be the member id of the
+    /// external agent.
+    pub fn new(base_url: impl Into<String>, sender: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(15))
+                .build()
+                .expect("reqwest client should build"),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            sender: sender.into(),
+            token: None,
+        }
+    }
+
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let builder = self
+            .client
+            .request(method, format!("{}{}", self.base_url, path));
+        match &self.token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
+    }
+
+    async fn json<T: for<'de> Deserialize<'de>>(
+        builder: reqwest::RequestBuilder,
+    ) -> Result<T, McpError> {
+        let response = builder
+            .send()
+            .await
+            .map_err(|err| McpError::Transport(err.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(McpError::Transport(format!(
+                "gateway returned {}: {}",
+                status.as_u16(),
+                body
+            )));
+        }
+        response
+            .json::<T>()
+            .await
+            .map_err(|err| McpError::Protocol(err.to_string()))
+    }
+}
+
+#[async_trait]
+impl NexisBackend for GatewayBackend {
+    async fn send_message(
+        &self,
+        room_id: &str,
+        text: &str,
+        reply_to: Option<&str>,
+    ) -> Result<String, McpError> {
+        let payload = json!({
+            "roomId": room_id,
+            "sender": self.sender,
+            "text": text,
+            "replyTo": reply_to,
+        });
+        let response: GatewaySendResponse = Self::json(
+            self.request(reqwest::Method::POST, "/v1/messages")
+                .json(&payload),
+        )
+        .await?;
+        Ok(response.id)
+    }
+
+    async fn room_history(
+        &self,
+        room_id: &str,
+        limit: usize,
+    ) -> Result<Vec<RoomHistoryMessage>, McpError> {
+        let room: GatewayRoom =
+            Self::json(self.request(reqwest::Method::GET, &format!("/v1/rooms/{room_id}"))).await?;
+        let skip = room.messages.len().saturating_sub(limit);
+        Ok(room.messages.into_iter().skip(skip).collect())
+    }
+
+    async fn semantic_search(
+        &self,
+        query: &str,
+        room_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>, McpError> {
+        let payload = json!({ "query": query, "limit": limit, "room_id": room_id });
+        let response: GatewaySearchResponse = Self::json(
+            self.request(reqwest::Method::POST, "/v1/search")
+                .json(&payload),
+        )
+        .await?;
+        Ok(response
+            .results
+            .into_iter()
+            .map(|item| SearchHit {
+                id: item.id,
+                score: item.score,
+                content: item.content,
+                room_id: item.room_id,
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CallToolParams {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendMessageArgs {
+    room_id: String,
+    text: String,
+    #[serde(default)]
+    reply_to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoomHistoryArgs {
+    room_id: String,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SemanticSearchArgs {
+    query: String,
+    #[serde(default)]
+    room_id: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// MCP server answering `initialize`, `tools/list` and `tools/call`.
+#[derive(Clone)]
+pub struct McpServer {
+    backend: Arc<dyn NexisBackend>,
+}
+
+impl std::fmt::Debug for McpServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("McpServer").finish_non_exhaustive()
+    }
+}
+
+impl McpServer {
+    pub fn new(backend: Arc<dyn NexisBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Tools advertised by `tools/list`.
+    pub fn tools() -> Vec<McpToolInfo> {
+        vec![
+            McpToolInfo {
+                name: "send_message".to_string(),
+                description: Some("Post a message to a Nexis room".to_string()),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "room_id": {"type": "string", "description": "Target room id"},
+                        "text": {"type": "string", "description": "Message text"},
+                        "reply_to": {"type": "string", "description": "Optional message id to reply to"}
+                    },
+                    "required": ["room_id", "text"]
+                }),
+            },
+            McpToolInfo {
+                name: "read_room_history".to_string(),
+                description: Some("Read the most recent messages in a Nexis room".to_string()),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "room_id": {"type": "string", "description": "Room id"},
+                        "limit": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": MAX_HISTORY_LIMIT,
+                            "default": DEFAULT_HISTORY_LIMIT
+                        }
+                    },
+                    "required": ["room_id"]
+                }),
+            },
+            McpToolInfo {
+                name: "semantic_search".to_string(),
+                description: Some("Semantic search over Nexis messages".to_string()),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {"type": "string", "description": "Natural language query"},
+                        "room_id": {"type": "string", "description": "Restrict results to one room"},
+                        "limit": {"type": "integer", "minimum": 1, "default": DEFAULT_SEARCH_LIMIT}
+                    },
+                    "required": ["query"]
+                }),
+            },
+        ]
+    }
+
+    /// Handle one request. Notifications produce no response.
+    pub async fn handle(&self, request: JsonRpcRequest) -> Option<JsonRpcResponse> {
+        let id = request.id?;
+        let result = match request.method.as_str() {
+            "initialize" => Ok(json!(InitializeResult {
+                protocol_version: MCP_PROTOCOL_VERSION.to_string(),
+                capabilities: json!({ "tools": {} }),
+                server_info: Implementation::nexis(),
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!(ListToolsResult {
+                tools: Self::tools(),
+                next_cursor: None,
+            })),
+            "tools/call" => self
+                .call_tool(request.params)
+                .await
+                .map(|result| json!(result)),
+            other => Err(JsonRpcError::new(
+                error_codes::METHOD_NOT_FOUND,
+                format!("method not found: {other}"),
+            )),
+        };
+
+        Some(match result {
+            Ok(result) => JsonRpcResponse::success(id, result),
+            Err(error) => JsonRpcResponse::failure(id, error),
+        })
+    }
+
+    /// Serve newline-delimited JSON-RPC until `reader` reaches EOF.
+    pub async fn serve<R, W>(&self, reader: R, mut writer: W) -> std::io::Result<()>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<JsonRpcMessage>(&line) {
+                Ok(JsonRpcMessage::Request(request)) => self.handle(request).await,
+                Ok(JsonRpcMessage::Response(_)) => None,
+                Err(err) => Some(JsonRpcResponse::failure(
+                    Value::Null,
+                    JsonRpcError::new(error_codes::PARSE_ERROR, err.to_string()),
+                )),
+            };
+            if let Some(response) = response {
+                let mut out = serde_json::to_vec(&response)?;
+                out.push(b'\n');
+                writer.write_all(&out).await?;
+                writer.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Serve over the process's stdin and stdout.
+    pub async fn serve_stdio(&self) -> std::io::Result<()> {
+        self.serve(tokio::io::stdin(), tokio::io::stdout()).await
+    }
+
+    async fn call_tool(&self, params: Option<Value>) -> Result<CallToolResult, JsonRpcError> {
+        let params: CallToolParams = parse_params(params.unwrap_or(Value::Null))?;
+        let outcome = match params.name.as_str() {
+            "send_message" => {
+                let args: SendMessageArgs = parse_params(params.arguments)?;
+                if args.room_id.trim().is_empty() || args.text.trim().is_empty() {
+                    return Err(invalid_params("room_id and text are required"));
+                }
+                self.backend
+                    .send_message(&args.room_id, &args.text, args.reply_to.as_deref())
+                    .await
+                    .map(|id| json!({ "id": id }))
+            }
+            "read_room_history" => {
+                let args: RoomHistoryArgs = parse_params(params.arguments)?;
+                if args.room_id.trim().is_empty() {
+                    return Err(invalid_params("room_id is required"));
+                }
+                let limit = args
+                    .limit
+                    .unwrap_or(DEFAULT_HISTORY_LIMIT)
+                    .clamp(1, MAX_HISTORY_LIMIT);
+                self.backend
+                    .room_history(&args.room_id, limit)
+                    .await
+                    .map(|messages| json!({ "messages": messages }))
+            }
+            "semantic_search" => {
+                let args: SemanticSearchArgs = parse_params(params.arguments)?;
+                if args.query.trim().is_empty() {
+                    return Err(invalid_params("query is required"));
+                }
+                let limit = args.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).max(1);
+                self.backend
+                    .semantic_search(&args.query, args.room_id.as_deref(), limit)
+                    .await
+                    .map(|results| json!({ "results": results }))
+            }
+            other => return Err(invalid_params(format!("unknown tool: {other}"))),
+        };
+
+        // Backend failures are tool-level errors, so the calling model sees them.
+        Ok(match outcome {
+            Ok(value) => CallToolResult::text(value.to_string()),
+            Err(err) => CallToolResult::error(err.to_string()),
+        })
+    }
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(value: Value) -> Result<T, JsonRpcError> {
+    serde_json::from_value(value).map_err(|err| invalid_params(err.to_string()))
+}
+
+fn invalid_params(message: impl Into<String>) -> JsonRpcError {
+    JsonRpcError::new(error_codes::INVALID_PARAMS, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::McpClient;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct FakeBackend {
+        messages: Mutex<Vec<RoomHistoryMessage>>,
+    }
+
+    #[async_trait]
+    impl NexisBackend for FakeBackend {
+        async fn send_message(
+            &self,
+            room_id: &str,
+            text: &str,
+            reply_to: Option<&str>,
+        ) -> Result<String, McpError> {
+            if room_id != "room-1" {
+                return Err(McpError::Transport("gateway returned 404".to_string()));
+            }
+            let mut messages = self.messages.lock().unwrap();
+            let id = format!("msg_{}", messages.len() + 1);
+            messages.push(RoomHistoryMessage {
+                id: id.clone(),
+                sender: "agent:mcp".to_string(),
+                text: text.to_string(),
+                reply_to: reply_to.map(str::to_string),
+            });
+            Ok(id)
+        }
+
+        async fn room_history(
+            &self,
+            _room_id: &str,
+            limit: usize,
+        ) -> Result<Vec<RoomHistoryMessage>, McpError> {
+            let messages = self.messages.lock().unwrap();
+            let skip = messages.len().saturating_sub(limit);
+            Ok(messages.iter().skip(skip).cloned().collect())
+        }
+
+        async fn semantic_search(
+            &self,
+            query: &str,
+            room_id: Option<&str>,
+            _limit: usize,
+        ) -> Result<Vec<SearchHit>, McpError> {
+            Ok(vec![SearchHit {
+                id: "hit-1".to_string(),
+                score: 0.9,
+                content: format!("about {query}"),
+                room_id: room_id.map(str::to_string),
+            }])
+        }
+    }
+
+    /// Connect an [`McpClient`] to an in-process server over a duplex pipe.
+    fn connect() -> McpClient {
+        let server = McpServer::new(Arc::new(FakeBackend::default()));
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server_io);
+        tokio::spawn(async move { server.serve(server_read, server_write).await });
+
+        let (client_read, mut client_write) = tokio::io::split(client_io);
+        let (out_tx, mut out_rx) = tokio::sync::mpsc::channel::<String>(16);
+        let (in_tx, in_rx) = tokio::sync::mpsc::channel::<String>(16);
+        tokio::spawn(async move {
+            while let Some(line) = out_rx.recv().await {
+                client_write.write_all(line.as_bytes()).await.unwrap();
+                client_write.write_all(b"\n").await.unwrap();
+            }
+        });
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(client_read).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if in_tx.send(line).await.is_err() {
+                    break;
+                }
+            }
+        });
+        McpClient::from_channels(out_tx, in_rx)
+    }
+
+    #[tokio::test]
+    async fn client_lists_and_calls_nexis_tools() {
+        let client = connect();
+        let info = client.initialize().await.unwrap();
+        assert_eq!(info.server_info.name, "nexis");
+
+        let names: Vec<_> = client
+            .list_tools()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|tool| tool.name)
+            .collect();
+        assert_eq!(
+            names,
+            ["send_message", "read_room_history", "semantic_search"]
+        );
+
+        let sent = client
+            .call_tool("send_message", json!({"room_id": "room-1", "text": "hi"}))
+            .await
+            .unwrap();
+        assert!(!sent.is_error);
+        assert_eq!(sent.text_content(), r#"{"id":"msg_1"}"#);
+
+        let history = client
+            .call_tool("read_room_history", json!({"room_id": "room-1"}))
+            .await
+            .unwrap();
+        let history: Value = serde_json::from_str(&history.text_content()).unwrap();
+        assert_eq!(history["messages"][0]["text"], "hi");
+
+        let search = client
+            .call_tool("semantic_search", json!({"query": "rust"}))
+            .await
+            .unwrap();
+        assert!(search.text_content().contains("about rust"));
+    }
+
+    #[tokio::test]
+    async fn invalid_arguments_and_backend_failures_are_reported() {
+        let client = connect();
+        client.initialize().await.unwrap();
+
+        let err = client
+            .call_tool("send_message", json!({"room_id": "room-1"}))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, McpError::Server { code, .. } if code == error_codes::INVALID_PARAMS)
+        );
+
+        let failed = client
+            .call_tool("send_message", json!({"room_id": "missing", "text": "hi"}))
+            .await
+            .unwrap();
+        assert!(failed.is_error);
+        assert!(failed.text_content().contains("404"));
+    }
+
+    #[tokio::test]
+    async fn serve_reports_parse_errors_and_skips_notifications() {
+        let server = McpServer::new(Arc::new(FakeBackend::default()));
+        let input = b"not json\n{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\n{\"jsonrpc\":\"2.0\",\"id\":7,\"method\":\"ping\"}\n";
+        let mut output = Vec::new();
+        server.serve(&input[..], &mut output).await.unwrap();
+
+        let responses: Vec<JsonRpcResponse> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(
+            responses[0].error.as_ref().unwrap().code,
+            error_codes::PARSE_ERROR
+        );
+        assert_eq!(responses[1].id, json!(7));
+    }
+}
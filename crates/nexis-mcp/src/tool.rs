@@ -0,0 +1,157 @@
+//! Adapts MCP server tools into nexis-runtime [`Tool`]s.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use nexis_runtime::{Tool, ToolDefinition, ToolError, ToolRegistry};
+use serde_json::Value;
+
+use crate::client::{McpClient, McpError};
+use crate::protocol::McpToolInfo;
+
+/// A tool hosted by an MCP server.
+#[derive(Debug, Clone)]
+pub struct McpTool {
+    client: Arc<McpClient>,
+    info: McpToolInfo,
+    name: String,
+}
+
+impl McpTool {
+    pub fn new(client: Arc<McpClient>, info: McpToolInfo) -> Self {
+        let name = info.name.clone();
+        Self { client, info, name }
+    }
+
+    /// Expose the tool as `{prefix}_{name}` to avoid clashes between servers.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.name = format!("{prefix}_{}", self.info.name);
+        self
+    }
+
+    /// Tool metadata as advertised by the server.
+    pub fn info(&self) -> &McpToolInfo {
+        &self.info
+    }
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: self.name.clone(),
+            description: self.info.description.clone().unwrap_or_default(),
+            parameters: self.info.input_schema.clone(),
+        }
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String, ToolError> {
+        let result = self
+            .client
+            .call_tool(&self.info.name, arguments)
+            .await
+            .map_err(|err| match err {
+                McpError::Timeout(timeout) => ToolError::Timeout(timeout.as_millis() as u64),
+                McpError::Server { code, message }
+                    if code == crate::protocol::error_codes::INVALID_PARAMS =>
+                {
+                    ToolError::InvalidParameters(message)
+                }
+                other => ToolError::ExecutionFailed(other.to_string()),
+            })?;
+
+        let content = result.text_content();
+        if result.is_error {
+            Err(ToolError::ExecutionFailed(content))
+        } else {
+            Ok(content)
+        }
+    }
+}
+
+impl McpClient {
+    /// Fetch the server's tools as runtime [`Tool`]s.
+    pub async fn tools(self: &Arc<Self>) -> Result<Vec<McpTool>, McpError> {
+        Ok(self
+            .list_tools()
+            .await?
+            .into_iter()
+            .map(|info| McpTool::new(Arc::clone(self), info))
+            .collect())
+    }
+
+    /// Register every server tool in `registry`, optionally prefixing names.
+    ///
+    /// Returns the number of tools registered.
+    pub async fn register_tools(
+        self: &Arc<Self>,
+        registry: &mut ToolRegistry,
+        prefix: Option<&str>,
+    ) -> Result<usize, McpError> {
+        let tools = self.tools().await?;
+        let count = tools.len();
+        for tool in tools {
+            let tool = match prefix {
+                Some(prefix) => tool.with_prefix(prefix),
+                None => tool,
+            };
+            registry.register(Arc::new(tool));
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::tests::fake_server;
+    use nexis_runtime::ToolCall;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn mcp_tools_run_through_the_tool_registry() {
+        let client = Arc::new(fake_server());
+        client.initialize().await.unwrap();
+
+        let mut registry = ToolRegistry::new();
+        let count = client
+            .register_tools(&mut registry, Some("fake"))
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let mut names: Vec<_> = registry
+            .definitions()
+            .into_iter()
+            .map(|definition| definition.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["fake_echo", "fake_fail"]);
+
+        let result = registry
+            .execute(ToolCall {
+                id: "call_1".to_string(),
+                name: "fake_echo".to_string(),
+                arguments: json!({"text": "hi"}),
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.content, "hi");
+    }
+
+    #[tokio::test]
+    async fn mcp_error_results_become_tool_errors() {
+        let client = Arc::new(fake_server());
+        client.initialize().await.unwrap();
+
+        let tool = client
+            .tools()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|tool| tool.info().name == "fail")
+            .unwrap();
+        let err = tool.execute(json!({})).await.unwrap_err();
+        assert!(matches!(err, ToolError::ExecutionFailed(message) if message == "tool failed"));
+    }
+}
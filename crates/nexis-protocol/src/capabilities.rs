@@ -0,0 +1,229 @@
+//! Capability declarations of agent members.
+//!
+//! An agent advertises the content it understands, the tools it can call,
+//! how much context it accepts and which languages it speaks. The same
+//! [`Capabilities`] shape describes what a task needs, so routing is a
+//! single [`Capabilities::satisfies`] check.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::MessageContent;
+
+/// Longest accepted content type, tool name or language tag.
+pub const MAX_CAPABILITY_NAME_LEN: usize = 64;
+
+/// What an agent member can handle, or what a task requires.
+///
+/// Empty lists and a missing context size place no constraint.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    /// Content types the agent reads, named like the `type` tag of
+    /// [`MessageContent`], e.g. `text` or `code`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub content_types: Vec<String>,
+    /// Tools the agent can call.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    /// Largest prompt the agent accepts, in tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_context_tokens: Option<u32>,
+    /// Languages the agent speaks, as BCP 47 tags such as `en` or `de-CH`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum CapabilitiesError {
+    #[error("invalid {field}: entries cannot be empty")]
+    EmptyName { field: &'static str },
+    #[error("invalid {field}: '{name}' is longer than {MAX_CAPABILITY_NAME_LEN} characters")]
+    NameTooLong { field: &'static str, name: String },
+    #[error("invalid maxContextTokens: must be greater than zero")]
+    ZeroContext,
+}
+
+impl Capabilities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_types.push(content_type.into());
+        self
+    }
+
+    pub fn with_tool(mut self, tool: impl Into<String>) -> Self {
+        self.tools.push(tool.into());
+        self
+    }
+
+    pub fn with_max_context_tokens(mut self, tokens: u32) -> Self {
+        self.max_context_tokens = Some(tokens);
+        self
+    }
+
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.languages.push(language.into());
+        self
+    }
+
+    /// Whether nothing is declared.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Reject empty or overlong names and a zero context size.
+    pub fn validate(&self) -> Result<(), CapabilitiesError> {
+        for (field, names) in [
+            ("contentTypes", &self.content_types),
+            ("tools", &self.tools),
+            ("languages", &self.languages),
+        ] {
+            for name in names {
+                if name.trim().is_empty() {
+                    return Err(CapabilitiesError::EmptyName { field });
+                }
+                if name.len() > MAX_CAPABILITY_NAME_LEN {
+                    return Err(CapabilitiesError::NameTooLong {
+                        field,
+                        name: name.clone(),
+                    });
+                }
+            }
+        }
+        if self.max_context_tokens == Some(0) {
+            return Err(CapabilitiesError::ZeroContext);
+        }
+        Ok(())
+    }
+
+    /// Whether an agent declaring `self` can take on work that needs
+    /// `required`.
+    ///
+    /// Every required content type and tool must be declared. A required
+    /// language also matches a declared primary language (`de` covers
+    /// `de-CH`), and the declared context size must be at least the
+    /// required one. Names compare case-insensitively.
+    pub fn satisfies(&self, required: &Capabilities) -> bool {
+        let covers = |declared: &[String], name: &str| {
+            declared
+                .iter()
+                .any(|declared| declared.eq_ignore_ascii_case(name))
+        };
+        let speaks = |language: &str| {
+            let primary = language.split('-').next().unwrap_or(language);
+            covers(&self.languages, language) || covers(&self.languages, primary)
+        };
+
+        required
+            .content_types
+            .iter()
+            .all(|content_type| covers(&self.content_types, content_type))
+            && required.tools.iter().all(|tool| covers(&self.tools, tool))
+            && required.languages.iter().all(|language| speaks(language))
+            && required.max_context_tokens.is_none_or(|needed| {
+                self.max_context_tokens
+                    .is_some_and(|declared| declared >= needed)
+            })
+    }
+
+    /// Whether the agent reads `content`. Agents that declare no content
+    /// types are assumed to read only text.
+    pub fn accepts(&self, content: &MessageContent) -> bool {
+        let content_type = content.content_type();
+        if self.content_types.is_empty() {
+            return content_type == "text";
+        }
+        self.content_types
+            .iter()
+            .any(|declared| declared.eq_ignore_ascii_case(content_type))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reviewer() -> Capabilities {
+        Capabilities::new()
+            .with_content_type("text")
+            .with_content_type("code")
+            .with_tool("run_tests")
+            .with_max_context_tokens(32_000)
+            .with_language("en")
+            .with_language("de")
+    }
+
+    #[test]
+    fn satisfies_requires_every_declared_need() {
+        let agent = reviewer();
+        assert!(agent.satisfies(&Capabilities::new()));
+        assert!(agent.satisfies(
+            &Capabilities::new()
+                .with_content_type("CODE")
+                .with_tool("run_tests")
+                .with_max_context_tokens(8_000)
+                .with_language("de-CH")
+        ));
+        assert!(!agent.satisfies(&Capabilities::new().with_content_type("media")));
+        assert!(!agent.satisfies(&Capabilities::new().with_tool("deploy")));
+        assert!(!agent.satisfies(&Capabilities::new().with_language("fr")));
+        assert!(!agent.satisfies(&Capabilities::new().with_max_context_tokens(64_000)));
+        assert!(!Capabilities::new().satisfies(&Capabilities::new().with_max_context_tokens(1)));
+    }
+
+    #[test]
+    fn accepts_declared_content_types_and_defaults_to_text() {
+        let code = MessageContent::Code {
+            code: "fn main() {}".to_string(),
+            language: Some("rust".to_string()),
+        };
+        let text = MessageContent::Text {
+            text: "hi".to_string(),
+        };
+        assert!(reviewer().accepts(&code));
+        assert!(Capabilities::new().accepts(&text));
+        assert!(!Capabilities::new().accepts(&code));
+    }
+
+    #[test]
+    fn validate_rejects_blank_long_and_zero_values() {
+        assert!(reviewer().validate().is_ok());
+        assert_eq!(
+            Capabilities::new().with_tool(" ").validate(),
+            Err(CapabilitiesError::EmptyName { field: "tools" })
+        );
+        assert!(matches!(
+            Capabilities::new()
+                .with_language("x".repeat(MAX_CAPABILITY_NAME_LEN + 1))
+                .validate(),
+            Err(CapabilitiesError::NameTooLong {
+                field: "languages",
+                ..
+            })
+        ));
+        assert_eq!(
+            Capabilities::new().with_max_context_tokens(0).validate(),
+            Err(CapabilitiesError::ZeroContext)
+        );
+    }
+
+    #[test]
+    fn serializes_camel_case_and_skips_empty_fields() {
+        let json = serde_json::to_value(
+            Capabilities::new()
+                .with_content_type("text")
+                .with_max_context_tokens(4_096),
+        )
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "contentTypes": ["text"], "maxContextTokens": 4096 })
+        );
+        let parsed: Capabilities = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(parsed.is_empty());
+    }
+}
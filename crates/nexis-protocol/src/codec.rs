@@ -0,0 +1,241 @@
+//! Wire encodings for protocol types.
+//!
+//! JSON is the default everywhere. Clients that push a lot of traffic can
+//! negotiate MessagePack instead, which carries the same field names and
+//! values in a compact binary form: a value encoded with one [`Encoding`]
+//! decodes to the same value with the other.
+
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Media type of JSON bodies.
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+/// Media type of MessagePack bodies.
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// How protocol values are put on the wire.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    #[default]
+    Json,
+    #[serde(rename = "msgpack")]
+    MessagePack,
+}
+
+#[derive(Debug, Error)]
+pub enum CodecError {
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("cannot encode MessagePack: {0}")]
+    MessagePackEncode(#[from] rmp_serde::encode::Error),
+    #[error("invalid MessagePack: {0}")]
+    MessagePackDecode(#[from] rmp_serde::decode::Error),
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("unknown encoding `{0}`: expected json or msgpack")]
+pub struct UnknownEncoding(pub String);
+
+impl Encoding {
+    /// Name used in query strings and CLI flags.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::MessagePack => "msgpack",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => JSON_CONTENT_TYPE,
+            Self::MessagePack => MSGPACK_CONTENT_TYPE,
+        }
+    }
+
+    /// Encoding of a `Content-Type` value, ignoring parameters. Both
+    /// `application/msgpack` and the older `application/x-msgpack` are
+    /// recognized.
+    pub fn from_media_type(value: &str) -> Option<Self> {
+        let media_type = value.split(';').next()?.trim();
+        if media_type.eq_ignore_ascii_case(JSON_CONTENT_TYPE) {
+            Some(Self::Json)
+        } else if media_type.eq_ignore_ascii_case(MSGPACK_CONTENT_TYPE)
+            || media_type.eq_ignore_ascii_case("application/x-msgpack")
+        {
+            Some(Self::MessagePack)
+        } else {
+            None
+        }
+    }
+
+    /// Encoding preferred by an `Accept` header: the supported media type
+    /// with the highest quality, the earliest listed on a tie. Falls back to
+    /// JSON when nothing supported is listed.
+    pub fn negotiate(accept: &str) -> Self {
+        let mut best: Option<(Self, f32)> = None;
+        for range in accept.split(',') {
+            let Some(encoding) = Self::from_media_type(range) else {
+                continue;
+            };
+            let quality = range
+                .split(';')
+                .skip(1)
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+                best = Some((encoding, quality));
+            }
+        }
+        best.map_or(Self::Json, |(encoding, _)| encoding)
+    }
+
+    /// Encode `value`. MessagePack maps keep the serde field names, so
+    /// renamed fields and tagged enums read the same in both encodings.
+    pub fn encode<T: Serialize + ?Sized>(self, value: &T) -> Result<Vec<u8>, CodecError> {
+        Ok(match self {
+            Self::Json => serde_json::to_vec(value)?,
+            Self::MessagePack => rmp_serde::to_vec_named(value)?,
+        })
+    }
+
+    pub fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, CodecError> {
+        Ok(match self {
+            Self::Json => serde_json::from_slice(bytes)?,
+            Self::MessagePack => rmp_serde::from_slice(bytes)?,
+        })
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = UnknownEncoding;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "json" => Ok(Self::Json),
+            "msgpack" | "messagepack" => Ok(Self::MessagePack),
+            other => Err(UnknownEncoding(other.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        AttachmentRef, DelegatedTask, MemberId, Message, MessageContent, RoomId, TaskRequest,
+    };
+
+    fn message(content: MessageContent) -> Message {
+        let mut message = Message::new(
+            "msg_1".to_string(),
+            "room_general".parse::<RoomId>().unwrap(),
+            "nexis:human:alice@example.com".parse::<MemberId>().unwrap(),
+            content,
+            Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap(),
+        );
+        message.metadata = Some(json!({ "client": "cli", "tags": ["a", 1, null] }));
+        message.reply_to = Some("msg_0".to_string());
+        message
+    }
+
+    #[test]
+    fn messages_round_trip_through_message_pack() {
+        let contents = vec![
+            MessageContent::Text {
+                text: "hello".to_string(),
+            },
+            MessageContent::Data {
+                data: json!({ "n": 1.5, "nested": { "ok": true } }),
+                mime_type: None,
+            },
+            MessageContent::ToolCall {
+                tool_call_id: "call_1".to_string(),
+                name: "search".to_string(),
+                arguments: json!({ "query": "rust" }),
+            },
+            MessageContent::Attachment(AttachmentRef {
+                id: "upl_1".to_string(),
+                file_name: "notes.txt".to_string(),
+                mime_type: "text/plain".to_string(),
+                size: 42,
+            }),
+        ];
+        for content in contents {
+            let message = message(content);
+            let bytes = Encoding::MessagePack.encode(&message).unwrap();
+            let decoded: Message = Encoding::MessagePack.decode(&bytes).unwrap();
+            assert_eq!(decoded, message);
+            assert!(bytes.len() < Encoding::Json.encode(&message).unwrap().len());
+        }
+    }
+
+    #[test]
+    fn message_pack_reads_like_json() {
+        let message = message(MessageContent::Text {
+            text: "hi".to_string(),
+        });
+        let bytes = Encoding::MessagePack.encode(&message).unwrap();
+        let as_value: serde_json::Value = Encoding::MessagePack.decode(&bytes).unwrap();
+        assert_eq!(as_value, serde_json::to_value(&message).unwrap());
+        assert_eq!(as_value["roomId"], "room_general");
+        assert_eq!(as_value["content"]["type"], "text");
+    }
+
+    #[test]
+    fn tasks_round_trip_through_message_pack() {
+        let request = TaskRequest::new("Summarize the thread")
+            .with_description("Keep it short")
+            .with_assignee("nexis:agent:summarizer".parse().unwrap());
+        let task = DelegatedTask::new(
+            "room_general".parse().unwrap(),
+            "nexis:human:alice@example.com".parse().unwrap(),
+            request,
+        );
+        let bytes = Encoding::MessagePack.encode(&task).unwrap();
+        let decoded: DelegatedTask = Encoding::MessagePack.decode(&bytes).unwrap();
+        assert_eq!(decoded, task);
+    }
+
+    #[test]
+    fn invalid_message_pack_is_an_error() {
+        let err = Encoding::MessagePack
+            .decode::<Message>(&[0xc1])
+            .unwrap_err();
+        assert!(matches!(err, CodecError::MessagePackDecode(_)));
+    }
+
+    #[test]
+    fn accept_headers_pick_the_preferred_encoding() {
+        assert_eq!(Encoding::negotiate("application/msgpack"), Encoding::MessagePack);
+        assert_eq!(
+            Encoding::negotiate("application/json, application/msgpack"),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::negotiate("application/json;q=0.5, application/x-msgpack"),
+            Encoding::MessagePack
+        );
+        assert_eq!(Encoding::negotiate("application/msgpack;q=0"), Encoding::Json);
+        assert_eq!(Encoding::negotiate("*/*"), Encoding::Json);
+        assert_eq!(
+            Encoding::from_media_type("application/msgpack; charset=binary"),
+            Some(Encoding::MessagePack)
+        );
+        assert_eq!("msgpack".parse::<Encoding>(), Ok(Encoding::MessagePack));
+        assert!("cbor".parse::<Encoding>().is_err());
+    }
+}
@@ -0,0 +1,405 @@
+//! NIP-004: task delegation between members.
+//!
+//! A member hands work to another member by opening a [`TaskRequest`] in a
+//! room, optionally naming the assignee or the [`Capabilities`] the work
+//! needs. A member that may take it claims the task and later finishes it
+//! with a [`TaskResult`]. [`DelegatedTask`] carries the whole record and only
+//! allows the [`TaskStatus`] changes below:
+//!
+//! ```text
+//! open ──claim──▶ claimed ──finish──▶ completed | failed
+//!   └──────┴──cancel──▶ cancelled
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{Capabilities, CapabilitiesError, MemberId, RoomId};
+
+/// Longest accepted task title.
+pub const MAX_TASK_TITLE_LEN: usize = 200;
+
+/// Work one member asks another to do.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRequest {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Only this member may claim the task; anyone in the room may when
+    /// unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<MemberId>,
+    /// Capabilities the claiming member must declare.
+    #[serde(default, skip_serializing_if = "Capabilities::is_empty")]
+    pub requires: Capabilities,
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TaskRequestError {
+    #[error("task title cannot be empty")]
+    EmptyTitle,
+    #[error("task title is longer than {MAX_TASK_TITLE_LEN} characters")]
+    TitleTooLong,
+    #[error("invalid assignee: {0}")]
+    InvalidAssignee(String),
+    #[error(transparent)]
+    Capabilities(#[from] CapabilitiesError),
+}
+
+impl TaskRequest {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            description: None,
+            assignee: None,
+            requires: Capabilities::default(),
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_assignee(mut self, assignee: MemberId) -> Self {
+        self.assignee = Some(assignee);
+        self
+    }
+
+    pub fn requiring(mut self, requires: Capabilities) -> Self {
+        self.requires = requires;
+        self
+    }
+
+    /// Reject blank or overlong titles, malformed assignees and invalid
+    /// capability names.
+    pub fn validate(&self) -> Result<(), TaskRequestError> {
+        if self.title.trim().is_empty() {
+            return Err(TaskRequestError::EmptyTitle);
+        }
+        if self.title.chars().count() > MAX_TASK_TITLE_LEN {
+            return Err(TaskRequestError::TitleTooLong);
+        }
+        if let Some(assignee) = &self.assignee {
+            // Deserializing a `MemberId` does not validate it.
+            assignee
+                .to_string()
+                .parse::<MemberId>()
+                .map_err(|err| TaskRequestError::InvalidAssignee(err.to_string()))?;
+        }
+        self.requires.validate()?;
+        Ok(())
+    }
+}
+
+/// Where a delegated task is in its lifecycle.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    /// Waiting for a member to claim it.
+    Open,
+    /// A member is working on it.
+    Claimed,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Claimed => "claimed",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the task can no longer change.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+impl std::fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Outcome reported by the member that worked on a task.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskResult {
+    pub success: bool,
+    /// Human-readable answer, or the reason the task failed.
+    pub output: String,
+    /// Structured output for members that consume it programmatically.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl TaskResult {
+    pub fn success(output: impl Into<String>) -> Self {
+        Self {
+            success: true,
+            output: output.into(),
+            data: None,
+        }
+    }
+
+    pub fn failure(reason: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            output: reason.into(),
+            data: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Status a task moves to when finished with this result.
+    pub fn status(&self) -> TaskStatus {
+        if self.success {
+            TaskStatus::Completed
+        } else {
+            TaskStatus::Failed
+        }
+    }
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TaskTransitionError {
+    #[error("task is {0}, not open")]
+    NotOpen(TaskStatus),
+    #[error("task is {0}, not claimed")]
+    NotClaimed(TaskStatus),
+    #[error("task is already {0}")]
+    Finished(TaskStatus),
+    #[error("task is assigned to {0}")]
+    NotAssignee(MemberId),
+    #[error("task is claimed by {0}")]
+    NotClaimant(MemberId),
+    #[error("member does not declare the capabilities the task requires")]
+    MissingCapabilities,
+}
+
+/// A task request together with its progress.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DelegatedTask {
+    /// `task_` followed by a UUID.
+    pub id: String,
+    pub room_id: RoomId,
+    pub requester: MemberId,
+    pub request: TaskRequest,
+    pub status: TaskStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claimed_by: Option<MemberId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<TaskResult>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DelegatedTask {
+    /// Open a new task in `room_id` on behalf of `requester`.
+    pub fn new(room_id: RoomId, requester: MemberId, request: TaskRequest) -> Self {
+        let now = Utc::now();
+        Self {
+            id: format!("task_{}", Uuid::new_v4().simple()),
+            room_id,
+            requester,
+            request,
+            status: TaskStatus::Open,
+            claimed_by: None,
+            result: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Whether `member`, declaring `capabilities`, may claim the task now.
+    pub fn claimable_by(&self, member: &MemberId, capabilities: &Capabilities) -> bool {
+        self.check_claim(member, capabilities).is_ok()
+    }
+
+    /// Take an open task. Assigned tasks can only be claimed by their
+    /// assignee, and the claimant must declare what the task requires.
+    pub fn claim(
+        &mut self,
+        member: &MemberId,
+        capabilities: &Capabilities,
+    ) -> Result<(), TaskTransitionError> {
+        self.check_claim(member, capabilities)?;
+        self.status = TaskStatus::Claimed;
+        self.claimed_by = Some(member.clone());
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Report the outcome of a claimed task; only its claimant may.
+    pub fn finish(
+        &mut self,
+        member: &MemberId,
+        result: TaskResult,
+    ) -> Result<(), TaskTransitionError> {
+        if self.status != TaskStatus::Claimed {
+            return Err(TaskTransitionError::NotClaimed(self.status));
+        }
+        match &self.claimed_by {
+            Some(claimant) if claimant != member => {
+                return Err(TaskTransitionError::NotClaimant(claimant.clone()));
+            }
+            _ => {}
+        }
+        self.status = result.status();
+        self.result = Some(result);
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Withdraw a task that has not finished yet. Who may cancel is up to
+    /// the caller.
+    pub fn cancel(&mut self) -> Result<(), TaskTransitionError> {
+        if self.status.is_terminal() {
+            return Err(TaskTransitionError::Finished(self.status));
+        }
+        self.status = TaskStatus::Cancelled;
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    fn check_claim(
+        &self,
+        member: &MemberId,
+        capabilities: &Capabilities,
+    ) -> Result<(), TaskTransitionError> {
+        if self.status != TaskStatus::Open {
+            return Err(TaskTransitionError::NotOpen(self.status));
+        }
+        match &self.request.assignee {
+            Some(assignee) if assignee != member => {
+                return Err(TaskTransitionError::NotAssignee(assignee.clone()));
+            }
+            _ => {}
+        }
+        if !capabilities.satisfies(&self.request.requires) {
+            return Err(TaskTransitionError::MissingCapabilities);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(id: &str) -> MemberId {
+        id.parse().unwrap()
+    }
+
+    fn task(request: TaskRequest) -> DelegatedTask {
+        DelegatedTask::new(
+            "room_general".parse().unwrap(),
+            member("nexis:human:alice"),
+            request,
+        )
+    }
+
+    #[test]
+    fn task_moves_from_open_through_claimed_to_completed() {
+        let agent = member("nexis:agent:reviewer");
+        let mut task = task(TaskRequest::new("Review the release notes"));
+        assert!(task.id.starts_with("task_"));
+        assert_eq!(task.status, TaskStatus::Open);
+
+        assert_eq!(
+            task.finish(&agent, TaskResult::success("done")),
+            Err(TaskTransitionError::NotClaimed(TaskStatus::Open))
+        );
+        task.claim(&agent, &Capabilities::default()).unwrap();
+        assert_eq!(task.claimed_by.as_ref(), Some(&agent));
+        assert_eq!(
+            task.claim(&member("nexis:agent:other"), &Capabilities::default()),
+            Err(TaskTransitionError::NotOpen(TaskStatus::Claimed))
+        );
+        assert_eq!(
+            task.finish(&member("nexis:agent:other"), TaskResult::success("mine")),
+            Err(TaskTransitionError::NotClaimant(agent.clone()))
+        );
+
+        task.finish(&agent, TaskResult::success("Looks good"))
+            .unwrap();
+        assert_eq!(task.status, TaskStatus::Completed);
+        assert_eq!(
+            task.cancel(),
+            Err(TaskTransitionError::Finished(TaskStatus::Completed))
+        );
+    }
+
+    #[test]
+    fn claim_respects_assignee_and_required_capabilities() {
+        let reviewer = member("nexis:agent:reviewer");
+        let required = Capabilities::new().with_tool("run_tests");
+        let assigned = task(
+            TaskRequest::new("Run the test suite")
+                .with_assignee(reviewer.clone())
+                .requiring(required.clone()),
+        );
+
+        assert!(!assigned.claimable_by(&member("nexis:agent:other"), &required));
+        assert!(!assigned.claimable_by(&reviewer, &Capabilities::default()));
+        assert!(assigned.claimable_by(&reviewer, &required));
+
+        let mut failed = assigned.clone();
+        failed.claim(&reviewer, &required).unwrap();
+        failed
+            .finish(&reviewer, TaskResult::failure("tests did not start"))
+            .unwrap();
+        assert_eq!(failed.status, TaskStatus::Failed);
+    }
+
+    #[test]
+    fn validate_rejects_bad_requests() {
+        assert!(TaskRequest::new("Summarize").validate().is_ok());
+        assert_eq!(
+            TaskRequest::new("  ").validate(),
+            Err(TaskRequestError::EmptyTitle)
+        );
+        assert_eq!(
+            TaskRequest::new("x".repeat(MAX_TASK_TITLE_LEN + 1)).validate(),
+            Err(TaskRequestError::TitleTooLong)
+        );
+        let request: TaskRequest =
+            serde_json::from_value(serde_json::json!({ "title": "t", "assignee": "alice" }))
+                .unwrap();
+        assert!(matches!(
+            request.validate(),
+            Err(TaskRequestError::InvalidAssignee(_))
+        ));
+    }
+
+    #[test]
+    fn serializes_camel_case_with_lowercase_status() {
+        let task = task(TaskRequest::new("Translate"));
+        let json = serde_json::to_value(&task).unwrap();
+        assert_eq!(json["roomId"], "room_general");
+        assert_eq!(json["status"], "open");
+        assert_eq!(json["request"], serde_json::json!({ "title": "Translate" }));
+        assert!(json.get("claimedBy").is_none());
+        let parsed: DelegatedTask = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, task);
+    }
+}
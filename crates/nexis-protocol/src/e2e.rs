@@ -0,0 +1,312 @@
+//! X25519 + ChaCha20-Poly1305 helpers for [`MessageContent::Encrypted`].
+//!
+//! The inner content is serialized to JSON and sealed with a random content
+//! key. That key is then wrapped once per recipient: an ephemeral X25519
+//! agreement with the recipient's public key is expanded with HKDF-SHA256
+//! into a wrapping key. Servers relaying the message only see ciphertext.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+use crate::{MemberId, MessageContent, WrappedKey};
+
+/// Algorithm identifier written to encrypted envelopes.
+pub const ALGORITHM: &str = "x25519-chacha20poly1305";
+
+const WRAP_INFO: &[u8] = b"nexis-e2e-v1 key wrap";
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum E2eError {
+    #[error("content is not encrypted")]
+    NotEncrypted,
+    #[error("unsupported algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("encrypted content needs at least one recipient")]
+    NoRecipients,
+    #[error("no wrapped key for {0}")]
+    NoKeyForRecipient(MemberId),
+    #[error("malformed envelope field `{0}`")]
+    Malformed(&'static str),
+    #[error("decryption failed")]
+    Decryption,
+}
+
+/// A member's long-term X25519 key pair.
+pub struct E2eKeyPair {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+impl E2eKeyPair {
+    pub fn generate() -> Self {
+        Self::from_secret(StaticSecret::random_from_rng(OsRng))
+    }
+
+    pub fn from_secret_bytes(bytes: [u8; 32]) -> Self {
+        Self::from_secret(StaticSecret::from(bytes))
+    }
+
+    fn from_secret(secret: StaticSecret) -> Self {
+        let public = PublicKey::from(&secret);
+        Self { secret, public }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.public
+    }
+
+    pub fn key_id(&self) -> String {
+        key_id(&self.public)
+    }
+}
+
+/// Short, stable identifier for a public key.
+pub fn key_id(public_key: &PublicKey) -> String {
+    let digest = Sha256::digest(public_key.as_bytes());
+    STANDARD.encode(&digest[..12])
+}
+
+pub fn encode_public_key(public_key: &PublicKey) -> String {
+    STANDARD.encode(public_key.as_bytes())
+}
+
+pub fn decode_public_key(encoded: &str) -> Result<PublicKey, E2eError> {
+    let bytes: [u8; 32] = decode_field(encoded, "publicKey")?
+        .try_into()
+        .map_err(|_| E2eError::Malformed("publicKey"))?;
+    Ok(PublicKey::from(bytes))
+}
+
+/// Seal `content` so only `recipients` can read it.
+pub fn encrypt(
+    content: &MessageContent,
+    recipients: &[(MemberId, PublicKey)],
+) -> Result<MessageContent, E2eError> {
+    if recipients.is_empty() {
+        return Err(E2eError::NoRecipients);
+    }
+
+    let content_key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    let key_id = uuid::Uuid::new_v4().to_string();
+    let plaintext = serde_json::to_vec(content).expect("message content serializes");
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(&content_key)
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: &plaintext,
+                aad: key_id.as_bytes(),
+            },
+        )
+        .expect("ChaCha20-Poly1305 encryption only fails for oversized input");
+
+    let recipients = recipients
+        .iter()
+        .map(|(member, public_key)| wrap_key(&content_key, member, public_key))
+        .collect();
+
+    Ok(MessageContent::Encrypted {
+        algorithm: ALGORITHM.to_string(),
+        key_id,
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+        recipients,
+    })
+}
+
+/// Open encrypted `content` as `member` holding `keys`.
+pub fn decrypt(
+    content: &MessageContent,
+    member: &MemberId,
+    keys: &E2eKeyPair,
+) -> Result<MessageContent, E2eError> {
+    let MessageContent::Encrypted {
+        algorithm,
+        key_id,
+        nonce,
+        ciphertext,
+        recipients,
+    } = content
+    else {
+        return Err(E2eError::NotEncrypted);
+    };
+    if algorithm != ALGORITHM {
+        return Err(E2eError::UnsupportedAlgorithm(algorithm.clone()));
+    }
+
+    let own_key_id = keys.key_id();
+    let wrapped = recipients
+        .iter()
+        .find(|wrapped| &wrapped.recipient == member && wrapped.key_id == own_key_id)
+        .ok_or_else(|| E2eError::NoKeyForRecipient(member.clone()))?;
+    let content_key = unwrap_key(wrapped, keys)?;
+
+    let plaintext = ChaCha20Poly1305::new(&content_key)
+        .decrypt(
+            &decode_nonce(nonce, "nonce")?,
+            Payload {
+                msg: &decode_field(ciphertext, "ciphertext")?,
+                aad: key_id.as_bytes(),
+            },
+        )
+        .map_err(|_| E2eError::Decryption)?;
+    serde_json::from_slice(&plaintext).map_err(|_| E2eError::Malformed("ciphertext"))
+}
+
+fn wrap_key(content_key: &Key, recipient: &MemberId, public_key: &PublicKey) -> WrappedKey {
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(public_key);
+    let wrapping_key = derive_wrapping_key(shared.as_bytes(), &ephemeral_public, public_key);
+
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let wrapped = ChaCha20Poly1305::new(&wrapping_key)
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: content_key.as_slice(),
+                aad: recipient.to_string().as_bytes(),
+            },
+        )
+        .expect("ChaCha20-Poly1305 encryption only fails for oversized input");
+
+    WrappedKey {
+        recipient: recipient.clone(),
+        key_id: key_id(public_key),
+        ephemeral_public_key: encode_public_key(&ephemeral_public),
+        nonce: STANDARD.encode(nonce),
+        wrapped_key: STANDARD.encode(wrapped),
+    }
+}
+
+fn unwrap_key(wrapped: &WrappedKey, keys: &E2eKeyPair) -> Result<Key, E2eError> {
+    let ephemeral_public = decode_public_key(&wrapped.ephemeral_public_key)
+        .map_err(|_| E2eError::Malformed("ephemeralPublicKey"))?;
+    let shared = keys.secret.diffie_hellman(&ephemeral_public);
+    let wrapping_key = derive_wrapping_key(shared.as_bytes(), &ephemeral_public, &keys.public);
+
+    let content_key = ChaCha20Poly1305::new(&wrapping_key)
+        .decrypt(
+            &decode_nonce(&wrapped.nonce, "nonce")?,
+            Payload {
+                msg: &decode_field(&wrapped.wrapped_key, "wrappedKey")?,
+                aad: wrapped.recipient.to_string().as_bytes(),
+            },
+        )
+        .map_err(|_| E2eError::Decryption)?;
+    if content_key.len() != 32 {
+        return Err(E2eError::Malformed("wrappedKey"));
+    }
+    Ok(*Key::from_slice(&content_key))
+}
+
+fn derive_wrapping_key(shared: &[u8], ephemeral: &PublicKey, recipient: &PublicKey) -> Key {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral.as_bytes());
+    salt[32..].copy_from_slice(recipient.as_bytes());
+
+    let mut key = Key::default();
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(WRAP_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+fn decode_field(value: &str, field: &'static str) -> Result<Vec<u8>, E2eError> {
+    STANDARD
+        .decode(value)
+        .map_err(|_| E2eError::Malformed(field))
+}
+
+fn decode_nonce(value: &str, field: &'static str) -> Result<Nonce, E2eError> {
+    let bytes = decode_field(value, field)?;
+    if bytes.len() != 12 {
+        return Err(E2eError::Malformed(field));
+    }
+    Ok(*Nonce::from_slice(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(id: &str) -> MemberId {
+        id.parse().unwrap()
+    }
+
+    fn text(value: &str) -> MessageContent {
+        MessageContent::Text {
+            text: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn recipients_can_decrypt_and_outsiders_cannot() {
+        let alice = member("nexis:human:alice@example.com");
+        let bob = member("nexis:human:bob@example.com");
+        let alice_keys = E2eKeyPair::generate();
+        let bob_keys = E2eKeyPair::generate();
+
+        let sealed = encrypt(
+            &text("meet at noon"),
+            &[
+                (alice.clone(), alice_keys.public_key()),
+                (bob.clone(), bob_keys.public_key()),
+            ],
+        )
+        .unwrap();
+        let encoded = serde_json::to_string(&sealed).unwrap();
+        assert!(!encoded.contains("meet at noon"));
+
+        assert_eq!(
+            decrypt(&sealed, &alice, &alice_keys).unwrap(),
+            text("meet at noon")
+        );
+        assert_eq!(
+            decrypt(&sealed, &bob, &bob_keys).unwrap(),
+            text("meet at noon")
+        );
+
+        let eve = member("nexis:human:eve@example.com");
+        assert_eq!(
+            decrypt(&sealed, &eve, &E2eKeyPair::generate()),
+            Err(E2eError::NoKeyForRecipient(eve))
+        );
+        // Bob's wrapped key is useless without Bob's secret.
+        assert!(decrypt(&sealed, &bob, &alice_keys).is_err());
+    }
+
+    #[test]
+    fn tampered_ciphertext_fails_authentication() {
+        let bob = member("nexis:human:bob@example.com");
+        let bob_keys = E2eKeyPair::from_secret_bytes([7; 32]);
+        let mut sealed = encrypt(&text("hi"), &[(bob.clone(), bob_keys.public_key())]).unwrap();
+
+        if let MessageContent::Encrypted { key_id, .. } = &mut sealed {
+            key_id.push('x');
+        }
+        assert_eq!(decrypt(&sealed, &bob, &bob_keys), Err(E2eError::Decryption));
+    }
+
+    #[test]
+    fn public_keys_round_trip_through_base64() {
+        let keys = E2eKeyPair::generate();
+        let encoded = encode_public_key(&keys.public_key());
+        assert_eq!(decode_public_key(&encoded).unwrap(), keys.public_key());
+        assert_eq!(
+            decode_public_key("c2hvcnQ="),
+            Err(E2eError::Malformed("publicKey"))
+        );
+        assert!(encrypt(&text("hi"), &[]).is_err());
+        assert_eq!(
+            decrypt(&text("hi"), &member("nexis:human:bob"), &keys),
+            Err(E2eError::NotEncrypted)
+        );
+    }
+}
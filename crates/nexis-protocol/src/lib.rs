@@ -2,13 +2,39 @@
 //!
 //! This crate implements:
 //! - NIP-001: member identity (`MemberId`)
+//! - Room identity (`RoomId`) and how new room ids are generated
 //! - NIP-002: message envelope (`Message`)
+//! - Message signatures over a canonical encoding, with Ed25519 helpers
+//!   behind the `signing` feature
+//! - End-to-end encrypted content (`MessageContent::Encrypted`), with
+//!   X25519/ChaCha20-Poly1305 helpers behind the `e2e` feature
+//! - File attachments (`MessageContent::Attachment`)
+//! - Capability declarations of agent members (`Capabilities`)
+//! - NIP-004: task delegation between members (`DelegatedTask`)
+//! - MessagePack as an alternative wire encoding (`codec::Encoding`), behind
+//!   the `msgpack` feature
+//! - OpenAPI schemas of the types HTTP APIs exchange, behind the `openapi`
+//!   feature
 //! - Permission actions and checks used by protocol-level authorization.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub mod capabilities;
+#[cfg(feature = "msgpack")]
+pub mod codec;
+pub mod delegation;
+#[cfg(feature = "e2e")]
+pub mod e2e;
+#[cfg(feature = "signing")]
+pub mod signing;
+
+pub use capabilities::{Capabilities, CapabilitiesError};
+pub use delegation::{
+    DelegatedTask, TaskRequest, TaskRequestError, TaskResult, TaskStatus, TaskTransitionError,
+};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum MemberType {
@@ -35,6 +61,7 @@ impl std::fmt::Display for MemberType {
     }
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct MemberId(String);
 
@@ -108,6 +135,105 @@ impl std::fmt::Display for MemberId {
     }
 }
 
+/// Longest slug accepted after the `room_` prefix.
+pub const MAX_ROOM_SLUG_LEN: usize = 64;
+
+/// Room identity: `room_` followed by a UUID in simple form for generated
+/// rooms, or by a slug of ASCII letters, digits, `-` and `_` for rooms named
+/// by clients and imports. Serializes as the bare string.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RoomId(String);
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum RoomIdError {
+    #[error("invalid prefix: expected 'room_'")]
+    InvalidPrefix,
+    #[error("invalid length: expected 1 to {MAX_ROOM_SLUG_LEN} characters after 'room_'")]
+    InvalidLength,
+    #[error("invalid character: only ASCII letters, digits, '-' and '_' are allowed")]
+    InvalidCharacter,
+}
+
+impl RoomId {
+    /// A fresh id for a new room, `room_<uuid>`.
+    pub fn generate() -> Self {
+        Self::from_uuid(uuid::Uuid::new_v4())
+    }
+
+    pub fn from_uuid(id: uuid::Uuid) -> Self {
+        Self(format!("room_{}", id.simple()))
+    }
+
+    /// Check `value` without keeping it.
+    pub fn validate(value: &str) -> Result<(), RoomIdError> {
+        let slug = value
+            .strip_prefix("room_")
+            .ok_or(RoomIdError::InvalidPrefix)?;
+        if slug.is_empty() || slug.len() > MAX_ROOM_SLUG_LEN {
+            return Err(RoomIdError::InvalidLength);
+        }
+        if !slug
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_')
+        {
+            return Err(RoomIdError::InvalidCharacter);
+        }
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The part after `room_`.
+    pub fn slug(&self) -> &str {
+        &self.0["room_".len()..]
+    }
+
+    /// The UUID of a generated id; `None` for slugs.
+    pub fn uuid(&self) -> Option<uuid::Uuid> {
+        uuid::Uuid::parse_str(self.slug()).ok()
+    }
+}
+
+impl std::str::FromStr for RoomId {
+    type Err = RoomIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::validate(s)?;
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for RoomId {
+    type Error = RoomIdError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::validate(&value)?;
+        Ok(Self(value))
+    }
+}
+
+impl From<RoomId> for String {
+    fn from(id: RoomId) -> Self {
+        id.0
+    }
+}
+
+impl AsRef<str> for RoomId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RoomId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Action {
@@ -181,6 +307,67 @@ pub enum MessageContent {
         name: String,
         arguments: serde_json::Value,
     },
+    /// End-to-end encrypted content the server relays without reading.
+    Encrypted {
+        algorithm: String,
+        /// Id of the content key, chosen by the sender.
+        #[serde(rename = "keyId")]
+        key_id: String,
+        /// Base64 nonce used with the content key.
+        nonce: String,
+        /// Base64 ciphertext of the serialized inner `MessageContent`.
+        ciphertext: String,
+        /// The content key, wrapped once per recipient.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        recipients: Vec<WrappedKey>,
+    },
+    /// File held in the gateway's blob store.
+    Attachment(AttachmentRef),
+}
+
+impl MessageContent {
+    /// The `type` tag this content is serialized with, e.g. `text` or `code`.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Text { .. } => "text",
+            Self::Markdown { .. } => "markdown",
+            Self::Data { .. } => "data",
+            Self::Media { .. } => "media",
+            Self::Code { .. } => "code",
+            Self::Tool { .. } => "tool",
+            Self::ToolCall { .. } => "toolcall",
+            Self::Encrypted { .. } => "encrypted",
+            Self::Attachment(_) => "attachment",
+        }
+    }
+}
+
+/// Reference to an uploaded file.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentRef {
+    /// Upload id returned by the gateway.
+    pub id: String,
+    pub file_name: String,
+    pub mime_type: String,
+    /// Size in bytes.
+    pub size: u64,
+}
+
+/// Content key of an encrypted message, wrapped for one recipient.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WrappedKey {
+    pub recipient: MemberId,
+    /// Id of the recipient public key the content key was wrapped to.
+    pub key_id: String,
+    /// Base64 sender ephemeral public key used for the key agreement.
+    pub ephemeral_public_key: String,
+    /// Base64 nonce used to wrap the content key.
+    pub nonce: String,
+    /// Base64 wrapped content key.
+    pub wrapped_key: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -190,7 +377,7 @@ pub struct Message {
     pub protocol_version: String,
     pub id: String,
     #[serde(rename = "roomId")]
-    pub room_id: String,
+    pub room_id: RoomId,
     pub sender: MemberId,
     pub content: MessageContent,
     pub metadata: Option<serde_json::Value>,
@@ -200,14 +387,47 @@ pub struct Message {
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt", skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<DateTime<Utc>>,
+    /// Base64 signature over [`Message::signing_payload`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
 }
 
 pub const PROTOCOL_VERSION: &str = "1.0.0";
 
+fn write_canonical_json(value: &serde_json::Value, out: &mut Vec<u8>) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+            out.push(b'{');
+            for (index, key) in keys.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(b',');
+                }
+                serde_json::to_writer(&mut *out, key).expect("string serializes");
+                out.push(b':');
+                write_canonical_json(&fields[key], out);
+            }
+            out.push(b'}');
+        }
+        serde_json::Value::Array(items) => {
+            out.push(b'[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(b',');
+                }
+                write_canonical_json(item, out);
+            }
+            out.push(b']');
+        }
+        scalar => serde_json::to_writer(&mut *out, scalar).expect("scalar serializes"),
+    }
+}
+
 impl Message {
     pub fn new(
         id: String,
-        room_id: String,
+        room_id: RoomId,
         sender: MemberId,
         content: MessageContent,
         created_at: DateTime<Utc>,
@@ -222,15 +442,46 @@ impl Message {
             reply_to: None,
             created_at,
             updated_at: None,
+            signature: None,
+        }
+    }
+
+    /// Canonical bytes covered by the message signature: the JSON encoding
+    /// without `signature`, with object keys sorted and no whitespace.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut value = serde_json::to_value(self).expect("message serializes to JSON");
+        if let serde_json::Value::Object(fields) = &mut value {
+            fields.remove("signature");
         }
+        let mut out = Vec::new();
+        write_canonical_json(&value, &mut out);
+        out
     }
 
     pub fn validate(&self) -> Result<(), String> {
         if self.id.is_empty() {
             return Err("message id cannot be empty".to_string());
         }
-        if self.room_id.is_empty() {
-            return Err("room id cannot be empty".to_string());
+        if let MessageContent::Encrypted {
+            algorithm,
+            nonce,
+            ciphertext,
+            ..
+        } = &self.content
+        {
+            if algorithm.is_empty() || nonce.is_empty() || ciphertext.is_empty() {
+                return Err(
+                    "encrypted content requires algorithm, nonce and ciphertext".to_string()
+                );
+            }
+        }
+        if let MessageContent::Attachment(attachment) = &self.content {
+            if attachment.id.is_empty()
+                || attachment.file_name.is_empty()
+                || attachment.mime_type.is_empty()
+            {
+                return Err("attachment requires id, fileName and mimeType".to_string());
+            }
         }
         Ok(())
     }
@@ -241,7 +492,10 @@ mod tests {
     use chrono::{TimeZone, Utc};
     use serde_json::json;
 
-    use super::{Action, MemberId, MemberIdError, Message, MessageContent, Permissions};
+    use super::{
+        Action, AttachmentRef, MemberId, MemberIdError, Message, MessageContent, Permissions,
+        RoomId, RoomIdError, WrappedKey, MAX_ROOM_SLUG_LEN,
+    };
 
     #[test]
     fn member_id_parses_valid_values() {
@@ -289,7 +543,7 @@ mod tests {
         let message = Message {
             protocol_version: super::PROTOCOL_VERSION.to_string(),
             id: "msg_abc123".to_string(),
-            room_id: "room_xyz".to_string(),
+            room_id: "room_xyz".parse().unwrap(),
             sender,
             content: MessageContent::Text {
                 text: "hello".to_string(),
@@ -298,6 +552,7 @@ mod tests {
             reply_to: Some("msg_def456".to_string()),
             created_at: Utc.with_ymd_and_hms(2026, 2, 14, 12, 0, 0).unwrap(),
             updated_at: None,
+            signature: None,
         };
 
         let encoded = serde_json::to_value(&message).unwrap();
@@ -315,7 +570,7 @@ mod tests {
         let sender = "nexis:human:alice@example.com".parse::<MemberId>().unwrap();
         let mut message = Message::new(
             "".to_string(),
-            "room_xyz".to_string(),
+            "room_xyz".parse().unwrap(),
             sender,
             MessageContent::Text {
                 text: "hello".to_string(),
@@ -326,7 +581,180 @@ mod tests {
         assert!(message.validate().is_err());
 
         message.id = "msg_1".to_string();
-        message.room_id = "".to_string();
+        assert!(message.validate().is_ok());
+
+        let mut encoded = serde_json::to_value(&message).unwrap();
+        encoded["roomId"] = json!("");
+        assert!(serde_json::from_value::<Message>(encoded).is_err());
+    }
+
+    #[test]
+    fn room_id_accepts_generated_ids_and_slugs() {
+        let generated = RoomId::generate();
+        assert!(generated.as_str().starts_with("room_"));
+        assert!(generated.uuid().is_some());
+        assert_eq!(generated.as_str().parse::<RoomId>().unwrap(), generated);
+
+        let slug = "room_release-notes".parse::<RoomId>().unwrap();
+        assert_eq!(slug.slug(), "release-notes");
+        assert_eq!(slug.uuid(), None);
+        assert_eq!(
+            serde_json::to_string(&slug).unwrap(),
+            "\"room_release-notes\""
+        );
+    }
+
+    #[test]
+    fn content_type_matches_the_serialized_tag() {
+        let contents = [
+            MessageContent::Markdown {
+                markdown: "# notes".to_string(),
+            },
+            MessageContent::ToolCall {
+                tool_call_id: "call_1".to_string(),
+                name: "search".to_string(),
+                arguments: serde_json::json!({}),
+            },
+            MessageContent::Attachment(AttachmentRef {
+                id: "upl_1".to_string(),
+                file_name: "notes.txt".to_string(),
+                mime_type: "text/plain".to_string(),
+                size: 5,
+            }),
+        ];
+        for content in contents {
+            let json = serde_json::to_value(&content).unwrap();
+            assert_eq!(json["type"], content.content_type());
+        }
+    }
+
+    #[test]
+    fn room_id_rejects_malformed_values() {
+        assert_eq!(
+            "general".parse::<RoomId>().unwrap_err(),
+            RoomIdError::InvalidPrefix
+        );
+        assert_eq!(
+            "room_".parse::<RoomId>().unwrap_err(),
+            RoomIdError::InvalidLength
+        );
+        let too_long = format!("room_{}", "a".repeat(MAX_ROOM_SLUG_LEN + 1));
+        assert_eq!(
+            too_long.parse::<RoomId>().unwrap_err(),
+            RoomIdError::InvalidLength
+        );
+        assert_eq!(
+            "room_a/b".parse::<RoomId>().unwrap_err(),
+            RoomIdError::InvalidCharacter
+        );
+        assert!(serde_json::from_str::<RoomId>("\"room one\"").is_err());
+    }
+
+    #[test]
+    fn signing_payload_is_canonical_and_excludes_signature() {
+        let sender = "nexis:human:alice@example.com".parse::<MemberId>().unwrap();
+        let mut message = Message::new(
+            "msg_1".to_string(),
+            "room_xyz".parse().unwrap(),
+            sender,
+            MessageContent::Text {
+                text: "hello".to_string(),
+            },
+            Utc.with_ymd_and_hms(2026, 2, 14, 12, 0, 0).unwrap(),
+        );
+        message.metadata = Some(json!({"z": 1, "a": [true, null]}));
+
+        let payload = String::from_utf8(message.signing_payload()).unwrap();
+        assert_eq!(
+            payload,
+            concat!(
+                r#"{"content":{"text":"hello","type":"text"},"createdAt":"2026-02-14T12:00:00Z","#,
+                r#""id":"msg_1","metadata":{"a":[true,null],"z":1},"#,
+                r#""protocolVersion":"1.0.0","roomId":"room_xyz","#,
+                r#""sender":"nexis:human:alice@example.com"}"#
+            )
+        );
+
+        message.signature = Some("c2ln".to_string());
+        assert_eq!(message.signing_payload(), payload.into_bytes());
+    }
+
+    #[test]
+    fn encrypted_content_round_trips_and_requires_ciphertext() {
+        let recipient = "nexis:human:bob@example.com".parse::<MemberId>().unwrap();
+        let content = MessageContent::Encrypted {
+            algorithm: "x25519-chacha20poly1305".to_string(),
+            key_id: "key_1".to_string(),
+            nonce: "bm9uY2U=".to_string(),
+            ciphertext: "c2VjcmV0".to_string(),
+            recipients: vec![WrappedKey {
+                recipient,
+                key_id: "bob-device".to_string(),
+                ephemeral_public_key: "ZXBo".to_string(),
+                nonce: "bm9uY2U=".to_string(),
+                wrapped_key: "a2V5".to_string(),
+            }],
+        };
+
+        let encoded = serde_json::to_value(&content).unwrap();
+        assert_eq!(encoded["type"], "encrypted");
+        assert_eq!(encoded["keyId"], "key_1");
+        assert_eq!(encoded["recipients"][0]["wrappedKey"], "a2V5");
+        let decoded: MessageContent = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, content);
+
+        let sender = "nexis:human:alice@example.com".parse::<MemberId>().unwrap();
+        let mut message = Message::new(
+            "msg_1".to_string(),
+            "room_xyz".parse().unwrap(),
+            sender,
+            content,
+            Utc::now(),
+        );
+        assert!(message.validate().is_ok());
+        if let MessageContent::Encrypted { ciphertext, .. } = &mut message.content {
+            ciphertext.clear();
+        }
+        assert!(message.validate().is_err());
+    }
+
+    #[test]
+    fn attachment_content_is_tagged_and_validated() {
+        let content = MessageContent::Attachment(AttachmentRef {
+            id: "upl_1".to_string(),
+            file_name: "report.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            size: 2048,
+        });
+
+        let encoded = serde_json::to_value(&content).unwrap();
+        assert_eq!(
+            encoded,
+            json!({
+                "type": "attachment",
+                "id": "upl_1",
+                "fileName": "report.pdf",
+                "mimeType": "application/pdf",
+                "size": 2048
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<MessageContent>(encoded).unwrap(),
+            content
+        );
+
+        let sender = "nexis:human:alice@example.com".parse::<MemberId>().unwrap();
+        let mut message = Message::new(
+            "msg_1".to_string(),
+            "room_xyz".parse().unwrap(),
+            sender,
+            content,
+            Utc::now(),
+        );
+        assert!(message.validate().is_ok());
+        if let MessageContent::Attachment(attachment) = &mut message.content {
+            attachment.mime_type.clear();
+        }
         assert!(message.validate().is_err());
     }
 
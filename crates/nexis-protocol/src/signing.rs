@@ -0,0 +1,121 @@
+//! Ed25519 message signatures.
+//!
+//! A signature covers [`Message::signing_payload`], so any change to the
+//! envelope or its content after signing invalidates it.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, Verifier};
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+use thiserror::Error;
+
+use crate::Message;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    #[error("message is not signed")]
+    Missing,
+    #[error("malformed {0}")]
+    Malformed(&'static str),
+    #[error("signature does not match")]
+    Invalid,
+}
+
+impl Message {
+    /// Sign the message, replacing any existing signature.
+    pub fn sign(&mut self, key: &SigningKey) {
+        let signature = key.sign(&self.signing_payload());
+        self.signature = Some(STANDARD.encode(signature.to_bytes()));
+    }
+
+    /// Check the message signature against `key`.
+    pub fn verify(&self, key: &VerifyingKey) -> Result<(), SignatureError> {
+        let encoded = self.signature.as_deref().ok_or(SignatureError::Missing)?;
+        let bytes: [u8; 64] = STANDARD
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(SignatureError::Malformed("signature"))?;
+        key.verify(&self.signing_payload(), &Signature::from_bytes(&bytes))
+            .map_err(|_| SignatureError::Invalid)
+    }
+}
+
+pub fn encode_verifying_key(key: &VerifyingKey) -> String {
+    STANDARD.encode(key.as_bytes())
+}
+
+pub fn decode_verifying_key(encoded: &str) -> Result<VerifyingKey, SignatureError> {
+    let bytes: [u8; 32] = STANDARD
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(SignatureError::Malformed("public key"))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| SignatureError::Malformed("public key"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemberId, MessageContent};
+    use chrono::Utc;
+
+    fn message(text: &str) -> Message {
+        Message::new(
+            "msg_1".to_string(),
+            "room_xyz".parse().unwrap(),
+            "nexis:human:alice@example.com".parse::<MemberId>().unwrap(),
+            MessageContent::Text {
+                text: text.to_string(),
+            },
+            Utc::now(),
+        )
+    }
+
+    #[test]
+    fn signed_message_verifies_until_modified() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let mut signed = message("hello");
+        assert_eq!(
+            signed.verify(&key.verifying_key()),
+            Err(SignatureError::Missing)
+        );
+
+        signed.sign(&key);
+        assert_eq!(signed.verify(&key.verifying_key()), Ok(()));
+
+        let other = SigningKey::from_bytes(&[8; 32]);
+        assert_eq!(
+            signed.verify(&other.verifying_key()),
+            Err(SignatureError::Invalid)
+        );
+
+        signed.content = MessageContent::Text {
+            text: "goodbye".to_string(),
+        };
+        assert_eq!(
+            signed.verify(&key.verifying_key()),
+            Err(SignatureError::Invalid)
+        );
+    }
+
+    #[test]
+    fn verifying_keys_round_trip_through_base64() {
+        let key = SigningKey::from_bytes(&[7; 32]).verifying_key();
+        assert_eq!(
+            decode_verifying_key(&encode_verifying_key(&key)).unwrap(),
+            key
+        );
+        assert_eq!(
+            decode_verifying_key("c2hvcnQ="),
+            Err(SignatureError::Malformed("public key"))
+        );
+
+        let mut garbled = message("hello");
+        garbled.signature = Some("not base64!".to_string());
+        assert_eq!(
+            garbled.verify(&key),
+            Err(SignatureError::Malformed("signature"))
+        );
+    }
+}
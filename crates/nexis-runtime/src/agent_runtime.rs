@@ -0,0 +1,1095 @@
+//! Room-resident agent runtime.
+//!
+//! An [`AgentRuntime`] subscribes to a room through a [`RoomTransport`],
+//! decides which messages to answer with a [`TriggerPolicy`], and posts the
+//! generated replies back to the room under its `nexis:agent:*` member id.
+//! Tool output can be relayed to the room while the tool is still running
+//! with [`relay_tool_output`].
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::{SinkExt, Stream, StreamExt};
+use nexis_protocol::{Capabilities, DelegatedTask, MemberId, MemberType};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+
+use crate::agent::{compose_agent_prompt, AgentConfig};
+use crate::orchestration::TurnOrchestrator;
+use crate::telemetry::TraceContextExt;
+use crate::tool::{ToolCall, ToolError, ToolOutputStream, ToolRegistry, ToolResult};
+use crate::{AIProvider, GenerateRequest, ProviderError, RequestOptions, RoomBudgets};
+
+/// A message observed in a room.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomMessage {
+    pub id: String,
+    #[serde(rename = "roomId")]
+    pub room_id: String,
+    pub sender: String,
+    pub text: String,
+    #[serde(rename = "replyTo", default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+}
+
+/// Agent runtime errors.
+#[derive(Debug, Error)]
+pub enum AgentRuntimeError {
+    #[error("invalid agent member: {0}")]
+    InvalidMember(String),
+    #[error("agent is already running in room {0}")]
+    AlreadyRunning(String),
+    #[error("room transport error: {0}")]
+    Transport(String),
+    #[error(transparent)]
+    Provider(#[from] ProviderError),
+    #[error(transparent)]
+    Tool(#[from] ToolError),
+}
+
+/// Decides which room messages an agent responds to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TriggerPolicy {
+    /// Respond when the message mentions the agent, e.g. `@nexis:agent:helper` or `@helper`.
+    #[default]
+    Mentions,
+    /// Respond when the message contains any keyword (case-insensitive).
+    Keywords(Vec<String>),
+    /// Respond to every message from another member.
+    AllMessages,
+}
+
+impl TriggerPolicy {
+    /// Whether `agent` should respond to `message`. An agent never responds to itself.
+    pub fn should_respond(&self, agent: &MemberId, message: &RoomMessage) -> bool {
+        if message.sender == agent.to_string() {
+            return false;
+        }
+
+        match self {
+            Self::Mentions => is_mentioned(agent, &message.text),
+            Self::Keywords(keywords) => {
+                let text = message.text.to_lowercase();
+                keywords
+                    .iter()
+                    .any(|keyword| !keyword.is_empty() && text.contains(&keyword.to_lowercase()))
+            }
+            Self::AllMessages => true,
+        }
+    }
+}
+
+/// Whether `text` mentions `agent` by full member id or by identifier.
+pub fn is_mentioned(agent: &MemberId, text: &str) -> bool {
+    let full = agent.to_string();
+    text.split_whitespace()
+        .filter_map(|word| word.strip_prefix('@'))
+        .map(|word| word.trim_end_matches(|c: char| ",.;:!?)".contains(c)))
+        .any(|mention| mention == full || mention.eq_ignore_ascii_case(agent.identifier()))
+}
+
+/// Stream of messages delivered by a room subscription.
+pub type RoomMessageStream =
+    Pin<Box<dyn Stream<Item = Result<RoomMessage, AgentRuntimeError>> + Send>>;
+
+/// Connection between an agent and the rooms it lives in.
+#[async_trait]
+pub trait RoomTransport: Send + Sync {
+    /// Subscribe to new messages in a room.
+    async fn subscribe(&self, room_id: &str) -> Result<RoomMessageStream, AgentRuntimeError>;
+
+    /// Post a message to a room, returning the stored message id.
+    async fn post_message(
+        &self,
+        room_id: &str,
+        sender: &str,
+        text: &str,
+        reply_to: Option<&str>,
+    ) -> Result<String, AgentRuntimeError>;
+
+    /// Continue message `message_id` with `text`.
+    ///
+    /// Transports that cannot change posted messages use the default, which
+    /// posts `text` as a reply to it.
+    async fn append_message(
+        &self,
+        room_id: &str,
+        message_id: &str,
+        sender: &str,
+        text: &str,
+    ) -> Result<(), AgentRuntimeError> {
+        self.post_message(room_id, sender, text, Some(message_id))
+            .await
+            .map(|_| ())
+    }
+}
+
+/// Post the output of tool call `call` to `room_id` as it arrives: the first
+/// chunk opens a message and later chunks are appended to it. Returns the
+/// complete result; a chunk that fails ends the output and marks the result
+/// as an error.
+pub async fn relay_tool_output(
+    transport: &dyn RoomTransport,
+    room_id: &str,
+    sender: &str,
+    call: &ToolCall,
+    mut output: ToolOutputStream,
+) -> Result<ToolResult, AgentRuntimeError> {
+    let mut content = String::new();
+    let mut message_id: Option<String> = None;
+    let mut is_error = false;
+
+    while let Some(chunk) = output.next().await {
+        let chunk = match chunk {
+            Ok(chunk) if chunk.is_empty() => continue,
+            Ok(chunk) => chunk,
+            Err(err) => {
+                is_error = true;
+                format!("\n[{} failed: {err}]", call.name)
+            }
+        };
+        match &message_id {
+            Some(id) => {
+                transport
+                    .append_message(room_id, id, sender, &chunk)
+                    .await?
+            }
+            None => {
+                message_id = Some(
+                    transport
+                        .post_message(room_id, sender, &chunk, None)
+                        .await?,
+                )
+            }
+        }
+        content.push_str(&chunk);
+        if is_error {
+            break;
+        }
+    }
+
+    Ok(ToolResult {
+        call_id: call.id.clone(),
+        name: call.name.clone(),
+        content,
+        is_error,
+    })
+}
+
+/// [`RoomTransport`] backed by the gateway WebSocket and REST API.
+#[derive(Debug, Clone)]
+pub struct GatewayTransport {
+    client: reqwest::Client,
+    base_url: String,
+    ws_url: String,
+    token: Option<String>,
+}
+
+impl GatewayTransport {
+    /// Build a transport for a gateway base URL such as `http://127.0.0.1:8080`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        let ws_url = format!(
+            "{}/ws",
+            base_url
+                .replacen("https://", "wss://", 1)
+                .replacen("http://", "ws://", 1)
+        );
+        Self {
+            client: reqwest::Client::new(),
+            base_url,
+            ws_url,
+            token: None,
+        }
+    }
+
+    /// Override the WebSocket endpoint derived from the base URL.
+    pub fn with_ws_url(mut self, ws_url: impl Into<String>) -> Self {
+        self.ws_url = ws_url.into();
+        self
+    }
+
+    /// Authenticate REST calls with a bearer token.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// REST request to `path` on the gateway, authenticated when a token is
+    /// set.
+    pub(crate) fn rest(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}{path}", self.base_url))
+            .with_trace_context();
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum GatewayEvent {
+    Message {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        message: GatewayMessage,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct GatewayMessage {
+    id: String,
+    sender: String,
+    text: String,
+    #[serde(default)]
+    reply_to: Option<String>,
+}
+
+#[async_trait]
+impl RoomTransport for GatewayTransport {
+    async fn subscribe(&self, room_id: &str) -> Result<RoomMessageStream, AgentRuntimeError> {
+        let (mut ws, _) = connect_async(self.ws_url.as_str())
+            .await
+            .map_err(|err| AgentRuntimeError::Transport(err.to_string()))?;
+        let frame = serde_json::json!({ "type": "subscribe", "roomId": room_id }).to_string();
+        ws.send(WsMessage::Text(frame.into()))
+            .await
+            .map_err(|err| AgentRuntimeError::Transport(err.to_string()))?;
+
+        let stream = ws.filter_map(|frame| async move {
+            match frame {
+                Ok(WsMessage::Text(text)) => match serde_json::from_str::<GatewayEvent>(&text) {
+                    Ok(GatewayEvent::Message { room_id, message }) => Some(Ok(RoomMessage {
+                        id: message.id,
+                        room_id,
+                        sender: message.sender,
+                        text: message.text,
+                        reply_to: message.reply_to,
+                    })),
+                    _ => None,
+                },
+                Ok(_) => None,
+                Err(err) => Some(Err(AgentRuntimeError::Transport(err.to_string()))),
+            }
+        });
+        Ok(Box::pin(stream))
+    }
+
+    async fn post_message(
+        &self,
+        room_id: &str,
+        sender: &str,
+        text: &str,
+        reply_to: Option<&str>,
+    ) -> Result<String, AgentRuntimeError> {
+        #[derive(Deserialize)]
+        struct Created {
+            id: String,
+        }
+
+        let response = self
+            .rest(reqwest::Method::POST, "/v1/messages")
+            .json(&serde_json::json!({
+                "roomId": room_id,
+                "sender": sender,
+                "text": text,
+                "replyTo": reply_to,
+            }))
+            .send()
+            .await
+            .map_err(|err| AgentRuntimeError::Transport(err.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AgentRuntimeError::Transport(format!(
+                "post message failed with status {status}: {body}"
+            )));
+        }
+
+        let created: Created = response
+            .json()
+            .await
+            .map_err(|err| AgentRuntimeError::Transport(err.to_string()))?;
+        Ok(created.id)
+    }
+}
+
+/// Configuration for a room-resident agent.
+#[derive(Debug, Clone)]
+pub struct AgentRuntimeConfig {
+    member_id: MemberId,
+    trigger: TriggerPolicy,
+    identity: Option<AgentConfig>,
+    model: Option<String>,
+    max_tokens: Option<u32>,
+    generation_timeout: Option<Duration>,
+    history_limit: usize,
+    orchestrator: Option<Arc<TurnOrchestrator>>,
+    capabilities: Capabilities,
+    room_budgets: Option<Arc<RoomBudgets>>,
+}
+
+impl AgentRuntimeConfig {
+    /// Default number of recent room messages included as context.
+    pub const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+    /// Build a config for an agent member; the id must be a `nexis:agent:*` member.
+    pub fn new(member_id: MemberId) -> Result<Self, AgentRuntimeError> {
+        if member_id.member_type() != MemberType::Agent {
+            return Err(AgentRuntimeError::InvalidMember(format!(
+                "{member_id} is not an agent member"
+            )));
+        }
+
+        Ok(Self {
+            member_id,
+            trigger: TriggerPolicy::default(),
+            identity: None,
+            model: None,
+            max_tokens: None,
+            generation_timeout: None,
+            history_limit: Self::DEFAULT_HISTORY_LIMIT,
+            orchestrator: None,
+            capabilities: Capabilities::default(),
+            room_budgets: None,
+        })
+    }
+
+    /// Set the trigger policy.
+    pub fn with_trigger(mut self, trigger: TriggerPolicy) -> Self {
+        self.trigger = trigger;
+        self
+    }
+
+    /// Prefix prompts with an agent identity from the agent registry.
+    pub fn with_identity(mut self, identity: AgentConfig) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// Request a specific model from the provider.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Cap the length of generated replies.
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Give up on a reply that takes longer than `timeout` to generate.
+    pub fn with_generation_timeout(mut self, timeout: Duration) -> Self {
+        self.generation_timeout = Some(timeout);
+        self
+    }
+
+    /// Set how many recent room messages are kept as context.
+    pub fn with_history_limit(mut self, history_limit: usize) -> Self {
+        self.history_limit = history_limit;
+        self
+    }
+
+    /// Share turn-taking with other agents in the room.
+    ///
+    /// When set, the orchestrator decides whether this agent responds and the
+    /// trigger policy is ignored.
+    pub fn with_orchestrator(mut self, orchestrator: Arc<TurnOrchestrator>) -> Self {
+        self.orchestrator = Some(orchestrator);
+        self
+    }
+
+    /// Declare what the agent can handle; the orchestrator only routes
+    /// messages the capabilities cover.
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Count replies against each room's daily AI budget, and stay quiet in
+    /// rooms that have used theirs up.
+    pub fn with_room_budgets(mut self, room_budgets: Arc<RoomBudgets>) -> Self {
+        self.room_budgets = Some(room_budgets);
+        self
+    }
+
+    /// Agent member id.
+    pub fn member_id(&self) -> &MemberId {
+        &self.member_id
+    }
+
+    /// Active trigger policy.
+    pub fn trigger(&self) -> &TriggerPolicy {
+        &self.trigger
+    }
+
+    /// Declared capabilities.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Model requested from the provider, if any.
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    /// Cap on the length of generated replies, if any.
+    pub fn max_tokens(&self) -> Option<u32> {
+        self.max_tokens
+    }
+
+    /// Longest a reply may take to generate, if limited.
+    pub fn generation_timeout(&self) -> Option<Duration> {
+        self.generation_timeout
+    }
+
+    /// Render the provider prompt for a triggering message and its preceding history.
+    pub fn render_prompt<'a>(
+        &self,
+        history: impl IntoIterator<Item = &'a RoomMessage>,
+        message: &RoomMessage,
+    ) -> String {
+        let mut transcript = String::new();
+        let mut history = history.into_iter().peekable();
+        if history.peek().is_some() {
+            transcript.push_str("Conversation so far:\n");
+            for previous in history {
+                transcript.push_str(&format!("{}: {}\n", previous.sender, previous.text));
+            }
+            transcript.push('\n');
+        }
+        transcript.push_str(&format!(
+            "Reply as {} to:\n{}: {}",
+            self.member_id, message.sender, message.text
+        ));
+
+        match &self.identity {
+            Some(identity) => compose_agent_prompt(identity, &transcript),
+            None => transcript,
+        }
+    }
+
+    /// Render the provider prompt for a task delegated to the agent.
+    pub fn render_task_prompt(&self, task: &DelegatedTask) -> String {
+        let mut prompt = format!(
+            "{} delegated a task to {}.\nTask: {}",
+            task.requester, self.member_id, task.request.title
+        );
+        if let Some(description) = &task.request.description {
+            prompt.push_str(&format!("\nDetails: {description}"));
+        }
+        prompt.push_str("\n\nComplete the task and reply with the result.");
+
+        match &self.identity {
+            Some(identity) => compose_agent_prompt(identity, &prompt),
+            None => prompt,
+        }
+    }
+}
+
+struct RunningAgent {
+    room_id: String,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+/// Runs an agent member inside a room until stopped.
+pub struct AgentRuntime {
+    config: Arc<AgentRuntimeConfig>,
+    provider: Arc<dyn AIProvider>,
+    transport: Arc<dyn RoomTransport>,
+    running: Mutex<Option<RunningAgent>>,
+}
+
+impl AgentRuntime {
+    pub fn new(
+        config: AgentRuntimeConfig,
+        provider: Arc<dyn AIProvider>,
+        transport: Arc<dyn RoomTransport>,
+    ) -> Self {
+        Self {
+            config: Arc::new(config),
+            provider,
+            transport,
+            running: Mutex::new(None),
+        }
+    }
+
+    /// Agent configuration.
+    pub fn config(&self) -> &AgentRuntimeConfig {
+        &self.config
+    }
+
+    /// Subscribe to `room_id` and start responding in the background.
+    pub async fn start(&self, room_id: impl Into<String>) -> Result<(), AgentRuntimeError> {
+        let room_id = room_id.into();
+        if let Some(current) = self.room_id() {
+            return Err(AgentRuntimeError::AlreadyRunning(current));
+        }
+
+        let stream = self.transport.subscribe(&room_id).await?;
+        if let Some(orchestrator) = &self.config.orchestrator {
+            orchestrator
+                .register_with_capabilities(
+                    self.config.member_id.clone(),
+                    self.config.capabilities.clone(),
+                )
+                .await;
+        }
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let task = tokio::spawn(run_agent(
+            Arc::clone(&self.config),
+            Arc::clone(&self.provider),
+            Arc::clone(&self.transport),
+            stream,
+            shutdown_rx,
+        ));
+
+        let mut running = self.running.lock().expect("agent runtime state poisoned");
+        if let Some(current) = running.as_ref().filter(|agent| !agent.task.is_finished()) {
+            task.abort();
+            return Err(AgentRuntimeError::AlreadyRunning(current.room_id.clone()));
+        }
+        *running = Some(RunningAgent {
+            room_id,
+            shutdown,
+            task,
+        });
+        Ok(())
+    }
+
+    /// Stop the agent, returning whether it was running.
+    pub async fn stop(&self) -> bool {
+        let running = self
+            .running
+            .lock()
+            .expect("agent runtime state poisoned")
+            .take();
+        let Some(agent) = running else {
+            return false;
+        };
+
+        let was_running = !agent.task.is_finished();
+        let _ = agent.shutdown.send(());
+        let _ = agent.task.await;
+        if let Some(orchestrator) = &self.config.orchestrator {
+            orchestrator.unregister(&self.config.member_id).await;
+        }
+        was_running
+    }
+
+    /// Whether the agent loop is active.
+    pub fn is_running(&self) -> bool {
+        self.room_id().is_some()
+    }
+
+    /// Run `call` from `registry` in the agent's room, posting its output as
+    /// it is produced.
+    pub async fn run_tool(
+        &self,
+        registry: &ToolRegistry,
+        call: &ToolCall,
+    ) -> Result<ToolResult, AgentRuntimeError> {
+        let room_id = self
+            .room_id()
+            .ok_or_else(|| AgentRuntimeError::Transport("agent is not running".to_string()))?;
+        let output = registry.execute_streaming(call).await?;
+        relay_tool_output(
+            self.transport.as_ref(),
+            &room_id,
+            &self.config.member_id.to_string(),
+            call,
+            output,
+        )
+        .await
+    }
+
+    /// Room the agent is currently running in.
+    pub fn room_id(&self) -> Option<String> {
+        self.running
+            .lock()
+            .expect("agent runtime state poisoned")
+            .as_ref()
+            .filter(|agent| !agent.task.is_finished())
+            .map(|agent| agent.room_id.clone())
+    }
+}
+
+impl Drop for AgentRuntime {
+    fn drop(&mut self) {
+        if let Ok(mut running) = self.running.lock() {
+            if let Some(agent) = running.take() {
+                agent.task.abort();
+            }
+        }
+    }
+}
+
+async fn run_agent(
+    config: Arc<AgentRuntimeConfig>,
+    provider: Arc<dyn AIProvider>,
+    transport: Arc<dyn RoomTransport>,
+    mut stream: RoomMessageStream,
+    mut shutdown: oneshot::Receiver<()>,
+) {
+    let member_id = config.member_id.to_string();
+    let mut history: VecDeque<RoomMessage> = VecDeque::new();
+
+    loop {
+        let message = tokio::select! {
+            _ = &mut shutdown => break,
+            next = stream.next() => match next {
+                Some(Ok(message)) => message,
+                Some(Err(err)) => {
+                    tracing::warn!(member_id = %member_id, "agent subscription error: {}", err);
+                    continue;
+                }
+                None => {
+                    tracing::info!(member_id = %member_id, "agent subscription closed");
+                    break;
+                }
+            },
+        };
+
+        let respond = match &config.orchestrator {
+            Some(orchestrator) => orchestrator
+                .decide(&message)
+                .await
+                .includes(&config.member_id),
+            None => config.trigger.should_respond(&config.member_id, &message),
+        };
+        let budget = match (&config.room_budgets, respond) {
+            (Some(budgets), true) => budgets.check(&message.room_id),
+            _ => Ok(()),
+        };
+        if let Err(exhausted) = &budget {
+            tracing::info!(member_id = %member_id, "agent not replying: {}", exhausted);
+            if exhausted.first_rejection {
+                if let Err(err) = transport
+                    .post_message(&message.room_id, &member_id, &exhausted.notice(), None)
+                    .await
+                {
+                    tracing::warn!(member_id = %member_id, "agent failed to post budget notice: {}", err);
+                }
+            }
+        }
+        if respond && budget.is_ok() {
+            let request = GenerateRequest {
+                prompt: config.render_prompt(&history, &message),
+                model: config.model.clone(),
+                max_tokens: config.max_tokens,
+                temperature: None,
+                metadata: Some(serde_json::json!({
+                    "roomId": message.room_id.clone(),
+                    "memberId": member_id.clone(),
+                    "replyTo": message.id.clone(),
+                })),
+                images: Vec::new(),
+            };
+
+            let mut options = RequestOptions::new();
+            if let Some(timeout) = config.generation_timeout {
+                options = options.with_timeout(timeout);
+            }
+            // Stopping the agent abandons a generation still in progress.
+            let generated = tokio::select! {
+                _ = &mut shutdown => break,
+                generated = provider.generate_with_options(request, options) => generated,
+            };
+            match generated {
+                Ok(generated) => {
+                    if let Some(budgets) = &config.room_budgets {
+                        budgets.record(&message.room_id, generated.usage);
+                    }
+                    if let Err(err) = transport
+                        .post_message(
+                            &message.room_id,
+                            &member_id,
+                            &generated.content,
+                            Some(&message.id),
+                        )
+                        .await
+                    {
+                        tracing::warn!(member_id = %member_id, "agent failed to post reply: {}", err);
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!(member_id = %member_id, "agent generation failed: {}", err);
+                }
+            }
+        }
+
+        history.push_back(message);
+        while history.len() > config.history_limit {
+            history.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GenerateResponse, MockProvider};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use tokio_stream::wrappers::UnboundedReceiverStream;
+
+    /// In-process transport that echoes posted messages back to subscribers.
+    #[derive(Default)]
+    struct ChannelTransport {
+        subscribers: Mutex<Vec<mpsc::UnboundedSender<Result<RoomMessage, AgentRuntimeError>>>>,
+        posted: Mutex<Vec<RoomMessage>>,
+    }
+
+    impl ChannelTransport {
+        fn deliver(&self, message: RoomMessage) {
+            for subscriber in self.subscribers.lock().unwrap().iter() {
+                let _ = subscriber.send(Ok(message.clone()));
+            }
+        }
+
+        fn posted(&self) -> Vec<RoomMessage> {
+            self.posted.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl RoomTransport for ChannelTransport {
+        async fn subscribe(&self, _room_id: &str) -> Result<RoomMessageStream, AgentRuntimeError> {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.subscribers.lock().unwrap().push(tx);
+            Ok(Box::pin(UnboundedReceiverStream::new(rx)))
+        }
+
+        async fn post_message(
+            &self,
+            room_id: &str,
+            sender: &str,
+            text: &str,
+            reply_to: Option<&str>,
+        ) -> Result<String, AgentRuntimeError> {
+            let message = RoomMessage {
+                id: format!("msg_{}", self.posted.lock().unwrap().len()),
+                room_id: room_id.to_string(),
+                sender: sender.to_string(),
+                text: text.to_string(),
+                reply_to: reply_to.map(str::to_string),
+            };
+            self.posted.lock().unwrap().push(message.clone());
+            self.deliver(message.clone());
+            Ok(message.id)
+        }
+    }
+
+    fn agent() -> MemberId {
+        "nexis:agent:helper".parse().unwrap()
+    }
+
+    fn message(id: &str, sender: &str, text: &str) -> RoomMessage {
+        RoomMessage {
+            id: id.to_string(),
+            room_id: "room_1".to_string(),
+            sender: sender.to_string(),
+            text: text.to_string(),
+            reply_to: None,
+        }
+    }
+
+    fn reply(content: &str) -> Result<GenerateResponse, ProviderError> {
+        Ok(GenerateResponse {
+            content: content.to_string(),
+            model: None,
+            finish_reason: Some("stop".to_string()),
+            usage: None,
+        })
+    }
+
+    async fn wait_for_posts(transport: &ChannelTransport, count: usize) {
+        for _ in 0..100 {
+            if transport.posted().len() >= count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {count} posted messages");
+    }
+
+    #[test]
+    fn mentions_match_full_id_or_identifier() {
+        let agent = agent();
+        assert!(is_mentioned(
+            &agent,
+            "hey @nexis:agent:helper can you look?"
+        ));
+        assert!(is_mentioned(&agent, "@Helper, what's up"));
+        assert!(!is_mentioned(&agent, "helper without at sign"));
+        assert!(!is_mentioned(&agent, "@helpers please"));
+    }
+
+    #[test]
+    fn trigger_policies_never_respond_to_self() {
+        let agent = agent();
+        let own = message("m1", "nexis:agent:helper", "@helper deploy");
+        let other = message("m2", "nexis:human:alice", "when do we Deploy?");
+
+        assert!(!TriggerPolicy::AllMessages.should_respond(&agent, &own));
+        assert!(TriggerPolicy::AllMessages.should_respond(&agent, &other));
+
+        let keywords = TriggerPolicy::Keywords(vec!["deploy".to_string()]);
+        assert!(keywords.should_respond(&agent, &other));
+        assert!(!keywords.should_respond(&agent, &own));
+        assert!(!TriggerPolicy::Mentions.should_respond(&agent, &other));
+    }
+
+    #[test]
+    fn config_rejects_non_agent_members() {
+        let human: MemberId = "nexis:human:alice".parse().unwrap();
+        assert!(matches!(
+            AgentRuntimeConfig::new(human),
+            Err(AgentRuntimeError::InvalidMember(_))
+        ));
+    }
+
+    #[test]
+    fn prompt_includes_history_and_trigger() {
+        let config = AgentRuntimeConfig::new(agent()).unwrap();
+        let history = [message("m1", "nexis:human:alice", "deploy is at noon")];
+        let prompt = config.render_prompt(
+            &history,
+            &message("m2", "nexis:human:bob", "@helper when is deploy?"),
+        );
+
+        assert!(prompt.starts_with("Conversation so far:\nnexis:human:alice: deploy is at noon\n"));
+        assert!(prompt.ends_with(
+            "Reply as nexis:agent:helper to:\nnexis:human:bob: @helper when is deploy?"
+        ));
+    }
+
+    #[tokio::test]
+    async fn runtime_replies_to_mentions_as_agent() {
+        let provider = Arc::new(MockProvider::new());
+        provider.enqueue_generate(reply("noon"));
+        let transport = Arc::new(ChannelTransport::default());
+        let runtime = AgentRuntime::new(
+            AgentRuntimeConfig::new(agent()).unwrap(),
+            provider,
+            transport.clone(),
+        );
+
+        runtime.start("room_1").await.unwrap();
+        transport.deliver(message("m1", "nexis:human:alice", "no mention here"));
+        transport.deliver(message("m2", "nexis:human:alice", "@helper when?"));
+        wait_for_posts(&transport, 1).await;
+
+        let posted = transport.posted();
+        assert_eq!(posted.len(), 1);
+        assert_eq!(posted[0].sender, "nexis:agent:helper");
+        assert_eq!(posted[0].text, "noon");
+        assert_eq!(posted[0].reply_to.as_deref(), Some("m2"));
+
+        assert!(runtime.stop().await);
+    }
+
+    #[tokio::test]
+    async fn runtime_stops_replying_once_the_room_budget_is_used_up() {
+        use crate::RoomLimits;
+
+        let provider = Arc::new(MockProvider::new());
+        provider.enqueue_generate(reply("first"));
+        provider.enqueue_generate(reply("second"));
+        let budgets = Arc::new(RoomBudgets::new(RoomLimits {
+            max_calls: Some(1),
+            max_tokens: None,
+        }));
+        let transport = Arc::new(ChannelTransport::default());
+        let runtime = AgentRuntime::new(
+            AgentRuntimeConfig::new(agent())
+                .unwrap()
+                .with_room_budgets(budgets.clone()),
+            provider,
+            transport.clone(),
+        );
+
+        runtime.start("room_1").await.unwrap();
+        for id in ["m1", "m2", "m3"] {
+            transport.deliver(message(id, "nexis:human:alice", "@helper again?"));
+        }
+        wait_for_posts(&transport, 2).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let posted = transport.posted();
+        assert_eq!(posted.len(), 2);
+        assert_eq!(posted[0].text, "first");
+        assert!(posted[1].text.contains("daily AI budget"));
+        assert_eq!(posted[1].reply_to, None);
+        assert_eq!(budgets.usage("room_1").calls, 1);
+
+        assert!(runtime.stop().await);
+    }
+
+    #[tokio::test]
+    async fn orchestrated_agents_take_turns() {
+        use crate::orchestration::{TurnOrchestrator, TurnPolicy};
+
+        let orchestrator = Arc::new(TurnOrchestrator::new(TurnPolicy::RoundRobin));
+        let transport = Arc::new(ChannelTransport::default());
+        let mut runtimes = Vec::new();
+        for name in ["alpha", "beta"] {
+            let provider = Arc::new(MockProvider::new());
+            provider.enqueue_generate(reply(&format!("{name} here")));
+            let config = AgentRuntimeConfig::new(format!("nexis:agent:{name}").parse().unwrap())
+                .unwrap()
+                .with_orchestrator(Arc::clone(&orchestrator));
+            let runtime = AgentRuntime::new(config, provider, transport.clone());
+            runtime.start("room_1").await.unwrap();
+            runtimes.push(runtime);
+        }
+
+        transport.deliver(message("m1", "nexis:human:alice", "hello agents"));
+        wait_for_posts(&transport, 1).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let posted = transport.posted();
+        assert_eq!(posted[0].sender, "nexis:agent:alpha");
+        assert_eq!(posted[0].reply_to.as_deref(), Some("m1"));
+        // beta answers alpha once; alpha's queue is then empty, which ends the exchange
+        assert!(posted.len() <= 2);
+
+        for runtime in &runtimes {
+            assert!(runtime.stop().await);
+        }
+        assert!(orchestrator.agents().await.is_empty());
+    }
+
+    /// Yields its `chunks` argument one string at a time, failing on `"!"`.
+    struct ProgressTool;
+
+    #[async_trait]
+    impl crate::Tool for ProgressTool {
+        fn definition(&self) -> crate::ToolDefinition {
+            crate::ToolDefinition {
+                name: "progress".to_string(),
+                description: "streams its chunks".to_string(),
+                parameters: serde_json::json!({}),
+            }
+        }
+
+        async fn execute(&self, _arguments: serde_json::Value) -> Result<String, ToolError> {
+            unreachable!("only streamed in tests")
+        }
+
+        async fn execute_streaming(
+            &self,
+            arguments: serde_json::Value,
+        ) -> Result<ToolOutputStream, ToolError> {
+            let chunks: Vec<Result<String, ToolError>> = arguments["chunks"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|chunk| match chunk.as_str().unwrap() {
+                    "!" => Err(ToolError::ExecutionFailed("crashed".to_string())),
+                    chunk => Ok(chunk.to_string()),
+                })
+                .collect();
+            Ok(Box::pin(futures::stream::iter(chunks)))
+        }
+    }
+
+    fn progress_call(chunks: &[&str]) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            name: "progress".to_string(),
+            arguments: serde_json::json!({ "chunks": chunks }),
+        }
+    }
+
+    #[tokio::test]
+    async fn tool_output_is_posted_as_it_streams() {
+        let transport = Arc::new(ChannelTransport::default());
+        let runtime = AgentRuntime::new(
+            AgentRuntimeConfig::new(agent()).unwrap(),
+            Arc::new(MockProvider::new()),
+            transport.clone(),
+        );
+        let registry = ToolRegistry::builder()
+            .with_tool(Arc::new(ProgressTool))
+            .build();
+        let call = progress_call(&["compiling\n", "", "running\n", "ok\n"]);
+        assert!(matches!(
+            runtime.run_tool(&registry, &call).await,
+            Err(AgentRuntimeError::Transport(_))
+        ));
+
+        runtime.start("room_1").await.unwrap();
+        let result = runtime.run_tool(&registry, &call).await.unwrap();
+        assert_eq!(result.content, "compiling\nrunning\nok\n");
+        assert!(!result.is_error);
+
+        let posted = transport.posted();
+        assert_eq!(posted.len(), 3);
+        assert_eq!(posted[0].text, "compiling\n");
+        assert_eq!(posted[0].sender, "nexis:agent:helper");
+        assert!(posted[1..]
+            .iter()
+            .all(|chunk| chunk.reply_to.as_deref() == Some(posted[0].id.as_str())));
+        runtime.stop().await;
+    }
+
+    #[tokio::test]
+    async fn failing_chunk_ends_the_relayed_output() {
+        use crate::Tool;
+
+        let transport = ChannelTransport::default();
+        let call = progress_call(&["step 1\n", "!", "never\n"]);
+        let output = ProgressTool
+            .execute_streaming(call.arguments.clone())
+            .await
+            .unwrap();
+
+        let result = relay_tool_output(&transport, "room_1", "nexis:agent:helper", &call, output)
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        assert_eq!(
+            result.content,
+            "step 1\n\n[progress failed: execution failed: crashed]"
+        );
+        assert_eq!(transport.posted().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn runtime_lifecycle_start_stop() {
+        let transport = Arc::new(ChannelTransport::default());
+        let runtime = AgentRuntime::new(
+            AgentRuntimeConfig::new(agent()).unwrap(),
+            Arc::new(MockProvider::new()),
+            transport,
+        );
+
+        assert!(!runtime.is_running());
+        runtime.start("room_1").await.unwrap();
+        assert_eq!(runtime.room_id().as_deref(), Some("room_1"));
+        assert!(matches!(
+            runtime.start("room_2").await,
+            Err(AgentRuntimeError::AlreadyRunning(room)) if room == "room_1"
+        ));
+
+        assert!(runtime.stop().await);
+        assert!(!runtime.is_running());
+        assert!(!runtime.stop().await);
+        runtime.start("room_2").await.unwrap();
+        assert!(runtime.stop().await);
+    }
+}
@@ -0,0 +1,389 @@
+//! Human-in-the-loop approval of tool calls.
+//!
+//! An [`ApprovalPolicy`] on a [`ToolRegistry`](crate::ToolRegistry) names the
+//! tools that may only run once a person agrees, such as code execution or
+//! outbound HTTP. Such calls pause while an [`Approver`] asks for a decision
+//! and fail when it is denied or does not arrive in time. [`RoomApprover`]
+//! asks in a room and waits for a member allowed to invoke tools to reply
+//! `approve <call id>` or `deny <call id>`.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use nexis_protocol::{Action, MemberId, Permissions};
+
+use crate::agent_runtime::RoomTransport;
+use crate::tool::{ToolCall, ToolError};
+
+/// Outcome of an approval request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalDecision {
+    Approved { approver: MemberId },
+    Denied { approver: MemberId },
+}
+
+/// Asks someone whether a paused tool call may run.
+#[async_trait]
+pub trait Approver: Send + Sync {
+    /// Wait for a decision on `call`. The policy gives up after its timeout.
+    async fn request_approval(&self, call: &ToolCall) -> Result<ApprovalDecision, ToolError>;
+}
+
+/// Which tool calls need approval, and how long to wait for it.
+#[derive(Clone)]
+pub struct ApprovalPolicy {
+    tools: HashSet<String>,
+    timeout: Duration,
+    approver: Arc<dyn Approver>,
+}
+
+impl ApprovalPolicy {
+    /// Default time to wait for a decision.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+    /// A policy that asks `approver`; no tool needs approval until named
+    /// with [`Self::require`].
+    pub fn new(approver: Arc<dyn Approver>) -> Self {
+        Self {
+            tools: HashSet::new(),
+            timeout: Self::DEFAULT_TIMEOUT,
+            approver,
+        }
+    }
+
+    /// Require approval before `tool` runs.
+    pub fn require(mut self, tool: impl Into<String>) -> Self {
+        self.tools.insert(tool.into());
+        self
+    }
+
+    /// Give up waiting for a decision after `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Whether calls to `tool` need approval.
+    pub fn requires_approval(&self, tool: &str) -> bool {
+        self.tools.contains(tool)
+    }
+
+    /// Pause `call` until it is approved. Denied and unanswered calls fail.
+    pub async fn authorize(&self, call: &ToolCall) -> Result<(), ToolError> {
+        if !self.requires_approval(&call.name) {
+            return Ok(());
+        }
+        let decision = tokio::time::timeout(self.timeout, self.approver.request_approval(call))
+            .await
+            .map_err(|_| ToolError::ApprovalTimedOut(call.name.clone()))??;
+        match decision {
+            ApprovalDecision::Approved { approver } => {
+                tracing::info!(tool = %call.name, call_id = %call.id, %approver, "tool call approved");
+                Ok(())
+            }
+            ApprovalDecision::Denied { approver } => Err(ToolError::ApprovalDenied {
+                tool: call.name.clone(),
+                approver: approver.to_string(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Debug for ApprovalPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApprovalPolicy")
+            .field("tools", &self.tools)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+/// [`Approver`] that posts the request into a room and takes the first
+/// answer from a member with [`Action::Invoke`] (or [`Action::Admin`]) on
+/// that room.
+pub struct RoomApprover {
+    transport: Arc<dyn RoomTransport>,
+    room_id: String,
+    agent: MemberId,
+    approvers: HashMap<String, Permissions>,
+}
+
+impl RoomApprover {
+    /// Ask in `room_id`, posting as `agent`.
+    pub fn new(
+        transport: Arc<dyn RoomTransport>,
+        room_id: impl Into<String>,
+        agent: MemberId,
+    ) -> Self {
+        Self {
+            transport,
+            room_id: room_id.into(),
+            agent,
+            approvers: HashMap::new(),
+        }
+    }
+
+    /// Let `member` answer with the given permissions. Answers from members
+    /// without Invoke or Admin on the room are ignored.
+    pub fn with_approver(mut self, member: MemberId, permissions: Permissions) -> Self {
+        self.approvers.insert(member.to_string(), permissions);
+        self
+    }
+
+    fn may_approve(&self, member: &str) -> bool {
+        self.approvers.get(member).is_some_and(|permissions| {
+            permissions.can(Action::Invoke) && permissions.can_access_room(&self.room_id)
+        })
+    }
+}
+
+/// `Some(true)` for `approve <id>`, `Some(false)` for `deny <id>`. A bare
+/// `approve` or `deny` counts when replying to the request message.
+fn parse_answer(text: &str, call_id: &str, is_reply: bool) -> Option<bool> {
+    let mut words = text.split_whitespace();
+    let approved = match words.next()?.to_ascii_lowercase().as_str() {
+        "approve" | "/approve" => true,
+        "deny" | "/deny" => false,
+        _ => return None,
+    };
+    match words.next() {
+        Some(id) if id == call_id => Some(approved),
+        None if is_reply => Some(approved),
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl Approver for RoomApprover {
+    async fn request_approval(&self, call: &ToolCall) -> Result<ApprovalDecision, ToolError> {
+        let transport_error = |err: crate::AgentRuntimeError| {
+            ToolError::ExecutionFailed(format!("approval request failed: {err}"))
+        };
+        // Subscribe before asking so no answer is missed.
+        let mut messages = self
+            .transport
+            .subscribe(&self.room_id)
+            .await
+            .map_err(transport_error)?;
+        let text = format!(
+            "Approval needed: {} wants to run `{}` with {}. Reply `approve {}` or `deny {}`.",
+            self.agent, call.name, call.arguments, call.id, call.id
+        );
+        let request_id = self
+            .transport
+            .post_message(&self.room_id, &self.agent.to_string(), &text, None)
+            .await
+            .map_err(transport_error)?;
+
+        while let Some(message) = messages.next().await {
+            let Ok(message) = message else {
+                continue;
+            };
+            let is_reply = message.reply_to.as_deref() == Some(request_id.as_str());
+            let Some(approved) = parse_answer(&message.text, &call.id, is_reply) else {
+                continue;
+            };
+            if !self.may_approve(&message.sender) {
+                tracing::warn!(sender = %message.sender, call_id = %call.id, "ignoring approval answer from member without invoke permission");
+                continue;
+            }
+            let Ok(approver) = message.sender.parse::<MemberId>() else {
+                continue;
+            };
+            return Ok(if approved {
+                ApprovalDecision::Approved { approver }
+            } else {
+                ApprovalDecision::Denied { approver }
+            });
+        }
+        Err(ToolError::ExecutionFailed(
+            "room subscription closed before the call was approved".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_runtime::{AgentRuntimeError, RoomMessage, RoomMessageStream};
+    use crate::tool::{Tool, ToolDefinition, ToolRegistry};
+    use std::sync::Mutex;
+    use tokio::sync::mpsc;
+    use tokio_stream::wrappers::UnboundedReceiverStream;
+
+    struct EchoTool;
+
+    #[async_trait]
+    impl Tool for EchoTool {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "code_execute".to_string(),
+                description: "echo".to_string(),
+                parameters: serde_json::json!({}),
+            }
+        }
+
+        async fn execute(&self, arguments: serde_json::Value) -> Result<String, ToolError> {
+            Ok(arguments.to_string())
+        }
+    }
+
+    /// Answers with a fixed decision, or never when `None`.
+    struct FixedApprover(Option<ApprovalDecision>);
+
+    #[async_trait]
+    impl Approver for FixedApprover {
+        async fn request_approval(&self, _call: &ToolCall) -> Result<ApprovalDecision, ToolError> {
+            match &self.0 {
+                Some(decision) => Ok(decision.clone()),
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    /// Records posts; answers are delivered by the test.
+    #[derive(Default)]
+    struct ScriptedRoom {
+        subscriber: Mutex<Option<mpsc::UnboundedSender<Result<RoomMessage, AgentRuntimeError>>>>,
+        posted: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedRoom {
+        fn answer(&self, sender: &str, text: &str, reply_to: Option<&str>) {
+            let message = RoomMessage {
+                id: format!("msg_{sender}_{text}"),
+                room_id: "room_ops".to_string(),
+                sender: sender.to_string(),
+                text: text.to_string(),
+                reply_to: reply_to.map(str::to_string),
+            };
+            if let Some(subscriber) = self.subscriber.lock().unwrap().as_ref() {
+                let _ = subscriber.send(Ok(message));
+            }
+        }
+    }
+
+    #[async_trait]
+    impl RoomTransport for ScriptedRoom {
+        async fn subscribe(&self, _room_id: &str) -> Result<RoomMessageStream, AgentRuntimeError> {
+            let (tx, rx) = mpsc::unbounded_channel();
+            *self.subscriber.lock().unwrap() = Some(tx);
+            Ok(Box::pin(UnboundedReceiverStream::new(rx)))
+        }
+
+        async fn post_message(
+            &self,
+            _room_id: &str,
+            _sender: &str,
+            text: &str,
+            _reply_to: Option<&str>,
+        ) -> Result<String, AgentRuntimeError> {
+            self.posted.lock().unwrap().push(text.to_string());
+            Ok("msg_request".to_string())
+        }
+    }
+
+    fn member(id: &str) -> MemberId {
+        id.parse().unwrap()
+    }
+
+    fn call() -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            name: "code_execute".to_string(),
+            arguments: serde_json::json!({ "code": "print(1)" }),
+        }
+    }
+
+    fn registry(policy: ApprovalPolicy) -> ToolRegistry {
+        ToolRegistry::builder()
+            .with_tool(Arc::new(EchoTool))
+            .with_approval_policy(policy)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn policy_pauses_only_listed_tools_and_honours_decisions() {
+        let alice = member("nexis:human:alice");
+        let approve = Arc::new(FixedApprover(Some(ApprovalDecision::Approved {
+            approver: alice.clone(),
+        })));
+        let result = registry(ApprovalPolicy::new(approve).require("code_execute"))
+            .execute(call())
+            .await
+            .unwrap();
+        assert_eq!(result.content, r#"{"code":"print(1)"}"#);
+
+        let deny = Arc::new(FixedApprover(Some(ApprovalDecision::Denied {
+            approver: alice,
+        })));
+        let err = registry(ApprovalPolicy::new(deny.clone()).require("code_execute"))
+            .execute(call())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ToolError::ApprovalDenied { ref approver, .. } if approver == "nexis:human:alice")
+        );
+
+        // Tools that are not listed never wait.
+        assert!(registry(ApprovalPolicy::new(deny))
+            .execute(call())
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn unanswered_requests_time_out() {
+        let policy = ApprovalPolicy::new(Arc::new(FixedApprover(None)))
+            .require("code_execute")
+            .with_timeout(Duration::from_millis(20));
+        let err = registry(policy).execute(call()).await.unwrap_err();
+        assert!(matches!(err, ToolError::ApprovalTimedOut(tool) if tool == "code_execute"));
+    }
+
+    #[tokio::test]
+    async fn room_approver_waits_for_a_member_with_invoke_permission() {
+        let room = Arc::new(ScriptedRoom::default());
+        let approver = RoomApprover::new(room.clone(), "room_ops", member("nexis:agent:runner"))
+            .with_approver(
+                member("nexis:human:bob"),
+                Permissions::new(vec!["room_ops".to_string()], vec![Action::Read]),
+            )
+            .with_approver(
+                member("nexis:human:alice"),
+                Permissions::new(vec!["*".to_string()], vec![Action::Invoke]),
+            );
+
+        let call = call();
+        let pending = tokio::spawn(async move { approver.request_approval(&call).await });
+        while room.posted.lock().unwrap().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert!(room.posted.lock().unwrap()[0].contains("approve call_1"));
+
+        room.answer("nexis:human:bob", "approve call_1", None);
+        room.answer("nexis:human:carol", "approve call_1", None);
+        room.answer("nexis:human:alice", "approve call_2", None);
+        room.answer("nexis:human:alice", "deny", Some("msg_request"));
+
+        assert_eq!(
+            pending.await.unwrap().unwrap(),
+            ApprovalDecision::Denied {
+                approver: member("nexis:human:alice")
+            }
+        );
+    }
+
+    #[test]
+    fn answers_name_the_call_or_reply_to_the_request() {
+        assert_eq!(parse_answer("approve call_1", "call_1", false), Some(true));
+        assert_eq!(parse_answer("/DENY call_1", "call_1", false), Some(false));
+        assert_eq!(parse_answer("approve", "call_1", true), Some(true));
+        assert_eq!(parse_answer("approve", "call_1", false), None);
+        assert_eq!(parse_answer("approve call_2", "call_1", true), None);
+        assert_eq!(parse_answer("sounds good", "call_1", true), None);
+    }
+}
@@ -0,0 +1,393 @@
+//! Spreading requests over several backends of the same kind.
+//!
+//! [`LoadBalancingProvider`] sends each request to one of its backends,
+//! e.g. two OpenAI-compatible endpoints, in proportion to their weights.
+//! It keeps the outcome and latency of each backend's most recent requests;
+//! a backend whose error rate crosses [`HealthConfig::max_error_rate`] is
+//! ejected for [`HealthConfig::ejection`] and then given a clean slate.
+//! When every backend is ejected, requests go to all of them again rather
+//! than failing outright.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AIProvider, ErrorCode, GenerateRequest, GenerateResponse, ProviderError, ProviderStream,
+};
+
+/// One backend and its share of the traffic.
+#[derive(Debug, Clone)]
+pub struct Backend {
+    label: String,
+    provider: Arc<dyn AIProvider>,
+    weight: u32,
+}
+
+impl Backend {
+    /// A backend labelled with the provider's name. Weight 0 sends it no
+    /// traffic unless every other backend is ejected.
+    pub fn new(provider: Arc<dyn AIProvider>, weight: u32) -> Self {
+        Self {
+            label: provider.name().to_string(),
+            provider,
+            weight,
+        }
+    }
+
+    /// Label to tell apart backends of the same provider in [`BackendHealth`].
+    pub fn labelled(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+        self
+    }
+}
+
+/// When a backend counts as unhealthy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthConfig {
+    /// Number of recent requests the error rate and latency are taken over.
+    pub window: usize,
+    /// Requests a backend must have served before it can be ejected.
+    pub min_requests: usize,
+    /// Error rate, between 0 and 1, at which a backend is ejected.
+    pub max_error_rate: f64,
+    /// How long an ejected backend receives no traffic.
+    pub ejection: Duration,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            window: 20,
+            min_requests: 5,
+            max_error_rate: 0.5,
+            ejection: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A backend's recent track record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendHealth {
+    pub label: String,
+    pub weight: u32,
+    /// Requests in the current window.
+    pub requests: usize,
+    pub error_rate: f64,
+    pub avg_latency_ms: u64,
+    pub ejected: bool,
+}
+
+#[derive(Debug, Default)]
+struct BackendState {
+    /// `(succeeded, latency)` of the most recent requests.
+    samples: VecDeque<(bool, Duration)>,
+    ejected_until: Option<Instant>,
+    /// Smooth weighted round-robin counter.
+    current_weight: i64,
+}
+
+impl BackendState {
+    fn error_rate(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let errors = self.samples.iter().filter(|(ok, _)| !ok).count();
+        errors as f64 / self.samples.len() as f64
+    }
+
+    fn is_ejected(&mut self, now: Instant) -> bool {
+        match self.ejected_until {
+            Some(until) if until > now => true,
+            Some(_) => {
+                self.ejected_until = None;
+                self.samples.clear();
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+/// [`AIProvider`] that balances requests over weighted backends and ejects
+/// the unhealthy ones.
+#[derive(Debug)]
+pub struct LoadBalancingProvider {
+    backends: Vec<Backend>,
+    health: HealthConfig,
+    state: Mutex<Vec<BackendState>>,
+}
+
+impl LoadBalancingProvider {
+    pub fn new(backends: Vec<Backend>) -> Self {
+        Self::with_health(backends, HealthConfig::default())
+    }
+
+    pub fn with_health(backends: Vec<Backend>, health: HealthConfig) -> Self {
+        let state = backends.iter().map(|_| BackendState::default()).collect();
+        Self {
+            backends,
+            health,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Every backend's recent track record, in the order they were given.
+    pub fn health(&self) -> Vec<BackendHealth> {
+        let now = Instant::now();
+        let mut state = self.state.lock().expect("balancer state poisoned");
+        self.backends
+            .iter()
+            .zip(state.iter_mut())
+            .map(|(backend, state)| {
+                let ejected = state.is_ejected(now);
+                let total: Duration = state.samples.iter().map(|(_, latency)| *latency).sum();
+                let avg_latency_ms = total
+                    .checked_div(state.samples.len() as u32)
+                    .unwrap_or_default()
+                    .as_millis() as u64;
+                BackendHealth {
+                    label: backend.label.clone(),
+                    weight: backend.weight,
+                    requests: state.samples.len(),
+                    error_rate: state.error_rate(),
+                    avg_latency_ms,
+                    ejected,
+                }
+            })
+            .collect()
+    }
+
+    /// Index of the backend to send the next request to.
+    fn pick(&self) -> Result<usize, ProviderError> {
+        if self.backends.is_empty() {
+            return Err(ProviderError::Message(
+                "load balancer has no backends".to_string(),
+            ));
+        }
+        let now = Instant::now();
+        let mut state = self.state.lock().expect("balancer state poisoned");
+        let mut eligible = (0..self.backends.len())
+            .filter(|&index| !state[index].is_ejected(now) && self.backends[index].weight > 0)
+            .collect::<Vec<_>>();
+        if eligible.is_empty() {
+            eligible = (0..self.backends.len()).collect();
+        }
+
+        // Smooth weighted round-robin: the same spread as random weighted
+        // choice, without the bursts.
+        let total = eligible
+            .iter()
+            .map(|&index| i64::from(self.backends[index].weight.max(1)))
+            .sum::<i64>();
+        let mut best = eligible[0];
+        for &index in &eligible {
+            state[index].current_weight += i64::from(self.backends[index].weight.max(1));
+            if state[index].current_weight > state[best].current_weight {
+                best = index;
+            }
+        }
+        state[best].current_weight -= total;
+        Ok(best)
+    }
+
+    fn record(&self, index: usize, succeeded: bool, latency: Duration) {
+        let mut state = self.state.lock().expect("balancer state poisoned");
+        let state = &mut state[index];
+        state.samples.push_back((succeeded, latency));
+        while state.samples.len() > self.health.window.max(1) {
+            state.samples.pop_front();
+        }
+        if !succeeded
+            && state.samples.len() >= self.health.min_requests
+            && state.error_rate() >= self.health.max_error_rate
+        {
+            tracing::warn!(
+                backend = %self.backends[index].label,
+                error_rate = state.error_rate(),
+                "ejecting unhealthy AI backend"
+            );
+            state.ejected_until = Some(Instant::now() + self.health.ejection);
+        }
+    }
+}
+
+/// Errors that say nothing about the backend's health: the request itself
+/// was refused, or the caller gave up on it.
+fn is_caller_error(err: &ProviderError) -> bool {
+    matches!(
+        err,
+        ProviderError::Unsupported(_) | ProviderError::Cancelled
+    ) || matches!(
+        err.code(),
+        Some(ErrorCode::ContextTooLong | ErrorCode::ContentFiltered | ErrorCode::InvalidRequest)
+    )
+}
+
+#[async_trait]
+impl AIProvider for LoadBalancingProvider {
+    fn name(&self) -> &'static str {
+        self.backends
+            .first()
+            .map(|backend| backend.provider.name())
+            .unwrap_or("load_balancer")
+    }
+
+    fn supports_images(&self) -> bool {
+        self.backends
+            .iter()
+            .all(|backend| backend.provider.supports_images())
+    }
+
+    async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+        let index = self.pick()?;
+        let started = Instant::now();
+        let result = self.backends[index].provider.generate(req).await;
+        match &result {
+            Err(err) if is_caller_error(err) => {}
+            _ => self.record(index, result.is_ok(), started.elapsed()),
+        }
+        result
+    }
+
+    /// Only failures to open the stream count against the backend.
+    async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
+        let index = self.pick()?;
+        let started = Instant::now();
+        let result = self.backends[index].provider.generate_stream(req).await;
+        match &result {
+            Err(err) if is_caller_error(err) => {}
+            _ => self.record(index, result.is_ok(), started.elapsed()),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockProvider;
+
+    fn request() -> GenerateRequest {
+        GenerateRequest {
+            prompt: "hello".to_string(),
+            model: None,
+            max_tokens: None,
+            temperature: None,
+            metadata: None,
+            images: Vec::new(),
+        }
+    }
+
+    fn reply(content: &str) -> Result<GenerateResponse, ProviderError> {
+        Ok(GenerateResponse {
+            content: content.to_string(),
+            model: None,
+            finish_reason: None,
+            usage: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn spreads_by_weight_and_ejects_failing_backends() {
+        let primary = Arc::new(MockProvider::new());
+        let secondary = Arc::new(MockProvider::new());
+        for _ in 0..6 {
+            primary.enqueue_generate(reply("primary"));
+        }
+        for _ in 0..2 {
+            secondary.enqueue_generate(Err(ProviderError::HttpStatus {
+                status: 503,
+                body: "overloaded".to_string(),
+            }));
+        }
+        let balancer = LoadBalancingProvider::with_health(
+            vec![
+                Backend::new(primary.clone(), 2).labelled("primary"),
+                Backend::new(secondary.clone(), 1).labelled("secondary"),
+            ],
+            HealthConfig {
+                window: 4,
+                min_requests: 2,
+                max_error_rate: 0.5,
+                ejection: Duration::from_secs(60),
+            },
+        );
+
+        // Two to one: primary, secondary, primary, then secondary fails
+        // again and is ejected.
+        let mut served = Vec::new();
+        for _ in 0..6 {
+            served.push(match balancer.generate(request()).await {
+                Ok(response) => response.content,
+                Err(_) => "error".to_string(),
+            });
+        }
+        assert_eq!(
+            served,
+            ["primary", "error", "primary", "primary", "error", "primary"]
+        );
+
+        let health = balancer.health();
+        assert_eq!(health[0].label, "primary");
+        assert!(!health[0].ejected);
+        assert_eq!(health[0].error_rate, 0.0);
+        assert!(health[1].ejected);
+        assert_eq!(health[1].error_rate, 1.0);
+
+        // Only the healthy backend serves while the other is ejected.
+        assert_eq!(
+            balancer.generate(request()).await.unwrap().content,
+            "primary"
+        );
+        assert_eq!(
+            balancer.generate(request()).await.unwrap().content,
+            "primary"
+        );
+        assert_eq!(
+            balancer.generate(request()).await.unwrap_err(),
+            ProviderError::MockQueueEmpty
+        );
+        assert_eq!(balancer.name(), "mock");
+    }
+
+    #[tokio::test]
+    async fn refused_requests_leave_the_backend_healthy() {
+        let provider = Arc::new(MockProvider::new());
+        for (status, body) in [
+            (400, "bad request"),
+            (413, "payload too large"),
+            (
+                400,
+                r#"{"error":{"message":"This model's maximum context length is 8192 tokens.","type":"invalid_request_error","code":"context_length_exceeded"}}"#,
+            ),
+            (422, "unprocessable"),
+        ] {
+            provider.enqueue_generate(Err(ProviderError::HttpStatus {
+                status,
+                body: body.to_string(),
+            }));
+        }
+        provider.enqueue_generate(Err(ProviderError::Cancelled));
+        let balancer = LoadBalancingProvider::with_health(
+            vec![Backend::new(provider, 1)],
+            HealthConfig {
+                window: 4,
+                min_requests: 2,
+                max_error_rate: 0.5,
+                ejection: Duration::from_secs(60),
+            },
+        );
+
+        for _ in 0..5 {
+            assert!(balancer.generate(request()).await.is_err());
+        }
+        let health = balancer.health();
+        assert!(!health[0].ejected);
+        assert_eq!(health[0].error_rate, 0.0);
+    }
+}
@@ -0,0 +1,406 @@
+//! Response caching for AI providers
+//!
+//! [`CachingProvider`] wraps any [`AIProvider`] and replays stored
+//! [`GenerateResponse`]s for identical requests. The cache key is a SHA-256
+//! hash of the provider, model, prompt, images and sampling parameters, so
+//! `metadata` never affects it.
+//!
+//! Only deterministic requests (`temperature: Some(0.0)`) are cached by
+//! default. A request opts out with `"cache": "bypass"` in its metadata,
+//! which skips both the lookup and the store.
+
+use std::collections::{HashMap, VecDeque};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    AIProvider, GenerateRequest, GenerateResponse, ImageInput, ProviderError, ProviderStream,
+};
+
+#[cfg(feature = "redis")]
+mod redis;
+
+#[cfg(feature = "redis")]
+pub use self::redis::RedisResponseCache;
+
+/// Metadata key read by [`CachingProvider`].
+pub const CACHE_METADATA_KEY: &str = "cache";
+/// Metadata value that skips the cache for one request.
+pub const CACHE_BYPASS: &str = "bypass";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheConfig {
+    /// How long a stored response stays valid.
+    pub ttl: Duration,
+    /// Entry limit for [`MemoryResponseCache`].
+    pub max_entries: NonZeroUsize,
+    /// Responses with more content bytes than this are never stored.
+    pub max_response_bytes: usize,
+    /// Also cache requests with a non-zero or unset temperature.
+    pub cache_nondeterministic: bool,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(3600),
+            max_entries: NonZeroUsize::new(1024).expect("non-zero"),
+            max_response_bytes: 64 * 1024,
+            cache_nondeterministic: false,
+        }
+    }
+}
+
+/// Storage backend for cached responses.
+///
+/// Backends swallow their own failures: a broken cache degrades to a miss
+/// rather than failing the generate call.
+#[async_trait]
+pub trait ResponseCache: Send + Sync + std::fmt::Debug {
+    async fn get(&self, key: &str) -> Option<GenerateResponse>;
+
+    async fn put(&self, key: &str, response: &GenerateResponse, ttl: Duration);
+}
+
+#[derive(Debug)]
+struct MemoryEntry {
+    response: GenerateResponse,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    entries: HashMap<String, MemoryEntry>,
+    /// Keys from least to most recently used.
+    order: VecDeque<String>,
+}
+
+impl MemoryState {
+    fn touch(&mut self, key: &str) {
+        if let Some(position) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(position).expect("position is in bounds");
+            self.order.push_back(key);
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.entries.remove(key);
+        self.order.retain(|k| k != key);
+    }
+}
+
+/// In-process LRU cache bounded by entry count.
+#[derive(Debug)]
+pub struct MemoryResponseCache {
+    capacity: NonZeroUsize,
+    state: Mutex<MemoryState>,
+}
+
+impl MemoryResponseCache {
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(MemoryState::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state
+            .lock()
+            .expect("response cache poisoned")
+            .entries
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl ResponseCache for MemoryResponseCache {
+    async fn get(&self, key: &str) -> Option<GenerateResponse> {
+        let mut state = self.state.lock().expect("response cache poisoned");
+        let expired = match state.entries.get(key) {
+            Some(entry) => entry.expires_at <= Instant::now(),
+            None => return None,
+        };
+        if expired {
+            state.remove(key);
+            return None;
+        }
+        state.touch(key);
+        state.entries.get(key).map(|entry| entry.response.clone())
+    }
+
+    async fn put(&self, key: &str, response: &GenerateResponse, ttl: Duration) {
+        let mut state = self.state.lock().expect("response cache poisoned");
+        let entry = MemoryEntry {
+            response: response.clone(),
+            expires_at: Instant::now() + ttl,
+        };
+        if state.entries.insert(key.to_string(), entry).is_some() {
+            state.touch(key);
+            return;
+        }
+        state.order.push_back(key.to_string());
+        while state.entries.len() > self.capacity.get() {
+            let Some(oldest) = state.order.pop_front() else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+    }
+}
+
+/// Hit and miss counts since the provider was built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// [`AIProvider`] decorator that caches `generate` results.
+///
+/// Streaming calls always go to the inner provider.
+#[derive(Debug)]
+pub struct CachingProvider {
+    inner: Arc<dyn AIProvider>,
+    cache: Arc<dyn ResponseCache>,
+    config: CacheConfig,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CachingProvider {
+    /// Cache `inner` in process memory.
+    pub fn new(inner: Arc<dyn AIProvider>, config: CacheConfig) -> Self {
+        let cache = Arc::new(MemoryResponseCache::new(config.max_entries));
+        Self::with_cache(inner, cache, config)
+    }
+
+    /// Cache `inner` in a caller-supplied backend, e.g. a shared Redis.
+    pub fn with_cache(
+        inner: Arc<dyn AIProvider>,
+        cache: Arc<dyn ResponseCache>,
+        config: CacheConfig,
+    ) -> Self {
+        Self {
+            inner,
+            cache,
+            config,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn is_cacheable(&self, req: &GenerateRequest) -> bool {
+        let bypass = req
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(CACHE_METADATA_KEY))
+            .and_then(|value| value.as_str())
+            == Some(CACHE_BYPASS);
+        !bypass && (self.config.cache_nondeterministic || req.temperature == Some(0.0))
+    }
+}
+
+/// Fields that determine a response; `metadata` is deliberately left out.
+#[derive(Serialize)]
+struct CacheKeyFields<'a> {
+    provider: &'a str,
+    model: Option<&'a str>,
+    prompt: &'a str,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+    images: &'a [ImageInput],
+}
+
+/// Hex SHA-256 cache key for `req` sent to `provider`.
+pub fn cache_key(provider: &str, req: &GenerateRequest) -> String {
+    let fields = CacheKeyFields {
+        provider,
+        model: req.model.as_deref(),
+        prompt: &req.prompt,
+        max_tokens: req.max_tokens,
+        temperature: req.temperature,
+        images: &req.images,
+    };
+    let encoded = serde_json::to_vec(&fields).expect("cache key fields serialize");
+    hex::encode(Sha256::digest(encoded))
+}
+
+#[async_trait]
+impl AIProvider for CachingProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn supports_images(&self) -> bool {
+        self.inner.supports_images()
+    }
+
+    #[tracing::instrument(name = "provider.cache.generate", skip_all, fields(provider = self.inner.name(), cache_hit))]
+    async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+        if !self.is_cacheable(&req) {
+            return self.inner.generate(req).await;
+        }
+
+        let key = cache_key(self.inner.name(), &req);
+        if let Some(response) = self.cache.get(&key).await {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            tracing::Span::current().record("cache_hit", true);
+            return Ok(response);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        tracing::Span::current().record("cache_hit", false);
+
+        let response = self.inner.generate(req).await?;
+        if response.content.len() <= self.config.max_response_bytes {
+            self.cache.put(&key, &response, self.config.ttl).await;
+        }
+        Ok(response)
+    }
+
+    async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
+        self.inner.generate_stream(req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockProvider;
+    use serde_json::json;
+
+    fn request(prompt: &str) -> GenerateRequest {
+        GenerateRequest {
+            prompt: prompt.to_string(),
+            model: Some("gpt-4o-mini".to_string()),
+            max_tokens: Some(64),
+            temperature: Some(0.0),
+            metadata: None,
+            images: Vec::new(),
+        }
+    }
+
+    fn response(content: &str) -> GenerateResponse {
+        GenerateResponse {
+            content: content.to_string(),
+            model: Some("gpt-4o-mini".to_string()),
+            finish_reason: Some("stop".to_string()),
+            usage: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn identical_deterministic_requests_hit_the_cache() {
+        let inner = Arc::new(MockProvider::new());
+        inner.enqueue_generate(Ok(response("first")));
+        inner.enqueue_generate(Ok(response("second")));
+        let provider = CachingProvider::new(inner, CacheConfig::default());
+
+        assert_eq!(
+            provider.generate(request("hi")).await.unwrap().content,
+            "first"
+        );
+        let mut tagged = request("hi");
+        tagged.metadata = Some(json!({ "room": "r1" }));
+        assert_eq!(provider.generate(tagged).await.unwrap().content, "first");
+        assert_eq!(provider.stats(), CacheStats { hits: 1, misses: 1 });
+
+        // A different prompt is a different key.
+        assert_eq!(
+            provider.generate(request("hello")).await.unwrap().content,
+            "second"
+        );
+    }
+
+    #[tokio::test]
+    async fn bypass_and_sampled_requests_skip_the_cache() {
+        let inner = Arc::new(MockProvider::new());
+        for content in ["a", "b", "c"] {
+            inner.enqueue_generate(Ok(response(content)));
+        }
+        let provider = CachingProvider::new(inner, CacheConfig::default());
+
+        provider.generate(request("hi")).await.unwrap();
+        let mut bypass = request("hi");
+        bypass.metadata = Some(json!({ "cache": "bypass" }));
+        assert_eq!(provider.generate(bypass).await.unwrap().content, "b");
+        let mut sampled = request("hi");
+        sampled.temperature = Some(0.7);
+        assert_eq!(provider.generate(sampled).await.unwrap().content, "c");
+        assert_eq!(provider.stats(), CacheStats { hits: 0, misses: 1 });
+    }
+
+    #[tokio::test]
+    async fn errors_and_oversized_responses_are_not_stored() {
+        let inner = Arc::new(MockProvider::new());
+        inner.enqueue_generate(Err(ProviderError::Message("boom".to_string())));
+        inner.enqueue_generate(Ok(response("too long")));
+        inner.enqueue_generate(Ok(response("ok")));
+        let config = CacheConfig {
+            max_response_bytes: 4,
+            ..CacheConfig::default()
+        };
+        let provider = CachingProvider::new(inner, config);
+
+        assert!(provider.generate(request("hi")).await.is_err());
+        assert_eq!(
+            provider.generate(request("hi")).await.unwrap().content,
+            "too long"
+        );
+        assert_eq!(
+            provider.generate(request("hi")).await.unwrap().content,
+            "ok"
+        );
+        assert_eq!(
+            provider.generate(request("hi")).await.unwrap().content,
+            "ok"
+        );
+    }
+
+    #[tokio::test]
+    async fn memory_cache_expires_and_evicts_least_recently_used() {
+        let cache = MemoryResponseCache::new(NonZeroUsize::new(2).unwrap());
+        let ttl = Duration::from_secs(60);
+        cache.put("a", &response("a"), ttl).await;
+        cache.put("b", &response("b"), ttl).await;
+        assert!(cache.get("a").await.is_some());
+        cache.put("c", &response("c"), ttl).await;
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b").await.is_none());
+        assert!(cache.get("a").await.is_some());
+
+        cache.put("d", &response("d"), Duration::ZERO).await;
+        assert!(cache.get("d").await.is_none());
+    }
+
+    #[test]
+    fn cache_key_covers_model_and_parameters() {
+        let base = cache_key("openai", &request("hi"));
+        assert_eq!(base.len(), 64);
+        assert_eq!(base, cache_key("openai", &request("hi")));
+        assert_ne!(base, cache_key("anthropic", &request("hi")));
+        let mut other_model = request("hi");
+        other_model.model = Some("gpt-4o".to_string());
+        assert_ne!(base, cache_key("openai", &other_model));
+        let mut other_limit = request("hi");
+        other_limit.max_tokens = Some(65);
+        assert_ne!(base, cache_key("openai", &other_limit));
+    }
+}
@@ -0,0 +1,76 @@
+//! Redis-backed response cache, shared across gateway replicas.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+
+use super::ResponseCache;
+use crate::GenerateResponse;
+
+const DEFAULT_KEY_PREFIX: &str = "nexis:provider-cache:";
+
+/// Stores responses as JSON under `<prefix><key>` with a Redis expiry.
+#[derive(Clone)]
+pub struct RedisResponseCache {
+    connection: ConnectionManager,
+    key_prefix: String,
+}
+
+impl std::fmt::Debug for RedisResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisResponseCache")
+            .field("key_prefix", &self.key_prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RedisResponseCache {
+    /// Connect to `url`, e.g. `redis://127.0.0.1:6379/0`.
+    pub async fn connect(url: &str) -> Result<Self, redis::RedisError> {
+        let client = redis::Client::open(url)?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(Self {
+            connection,
+            key_prefix: DEFAULT_KEY_PREFIX.to_string(),
+        })
+    }
+
+    pub fn with_key_prefix(mut self, key_prefix: impl Into<String>) -> Self {
+        self.key_prefix = key_prefix.into();
+        self
+    }
+
+    fn redis_key(&self, key: &str) -> String {
+        format!("{}{key}", self.key_prefix)
+    }
+}
+
+#[async_trait]
+impl ResponseCache for RedisResponseCache {
+    async fn get(&self, key: &str) -> Option<GenerateResponse> {
+        let mut connection = self.connection.clone();
+        let stored: Option<String> = match connection.get(self.redis_key(key)).await {
+            Ok(stored) => stored,
+            Err(err) => {
+                tracing::warn!(error = %err, "redis response cache read failed");
+                return None;
+            }
+        };
+        serde_json::from_str(&stored?).ok()
+    }
+
+    async fn put(&self, key: &str, response: &GenerateResponse, ttl: Duration) {
+        let Ok(encoded) = serde_json::to_string(response) else {
+            return;
+        };
+        let mut connection = self.connection.clone();
+        let result: redis::RedisResult<()> = connection
+            .set_ex(self.redis_key(key), encoded, ttl.as_secs().max(1))
+            .await;
+        if let Err(err) = result {
+            tracing::warn!(error = %err, "redis response cache write failed");
+        }
+    }
+}
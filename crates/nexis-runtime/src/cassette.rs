@@ -0,0 +1,415 @@
+//! Recorded provider traffic for offline, deterministic tests.
+//!
+//! [`RecordingProvider`] wraps a real [`AIProvider`] and appends every
+//! request it forwards, with the response, stream chunks or error that came
+//! back, to a JSON cassette file. [`ReplayProvider`] serves a cassette back
+//! without touching the network, so tests of agents and of the gateway's AI
+//! endpoints run the same way every time.
+//!
+//! Requests are matched on everything but `metadata`. Identical requests are
+//! answered in the order they were recorded; once those run out, the last
+//! one is repeated.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    AIProvider, GenerateRequest, GenerateResponse, ProviderError, ProviderStream, StreamChunk,
+};
+
+#[derive(Debug, Error)]
+pub enum CassetteError {
+    #[error("cassette I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid cassette: {0}")]
+    Format(#[from] serde_json::Error),
+}
+
+/// A recorded provider error.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedError {
+    HttpStatus { status: u16, body: String },
+    Unsupported { message: String },
+    Other { message: String },
+}
+
+impl From<&ProviderError> for RecordedError {
+    fn from(err: &ProviderError) -> Self {
+        match err {
+            ProviderError::HttpStatus { status, body } => Self::HttpStatus {
+                status: *status,
+                body: body.clone(),
+            },
+            ProviderError::Unsupported(message) => Self::Unsupported {
+                message: message.clone(),
+            },
+            other => Self::Other {
+                message: other.to_string(),
+            },
+        }
+    }
+}
+
+impl From<RecordedError> for ProviderError {
+    fn from(err: RecordedError) -> Self {
+        match err {
+            RecordedError::HttpStatus { status, body } => Self::HttpStatus { status, body },
+            RecordedError::Unsupported { message } => Self::Unsupported(message),
+            RecordedError::Other { message } => Self::Message(message),
+        }
+    }
+}
+
+/// What a provider answered to one recorded request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedOutcome {
+    Response(GenerateResponse),
+    /// Every chunk of a streamed answer, in order.
+    Stream(Vec<StreamChunk>),
+    Error(RecordedError),
+}
+
+/// One request and what came back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Name of the provider that answered.
+    pub provider: String,
+    /// Whether the request was streamed.
+    #[serde(default)]
+    pub stream: bool,
+    pub request: GenerateRequest,
+    pub outcome: RecordedOutcome,
+}
+
+/// Recorded interactions, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CassetteError> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CassetteError> {
+        let path = path.as_ref();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Whether `recorded` and `req` ask for the same thing, ignoring metadata.
+fn same_request(recorded: &GenerateRequest, req: &GenerateRequest) -> bool {
+    recorded.prompt == req.prompt
+        && recorded.model == req.model
+        && recorded.max_tokens == req.max_tokens
+        && recorded.temperature == req.temperature
+        && recorded.images == req.images
+}
+
+fn chunk_stream(chunks: Vec<StreamChunk>) -> ProviderStream {
+    Box::pin(stream::iter(chunks.into_iter().map(Ok)))
+}
+
+/// [`AIProvider`] decorator that records the traffic of `inner` to a
+/// cassette file, rewriting the file after every request.
+///
+/// Streamed answers are read to the end before they are handed on, so
+/// chunks arrive at once rather than as the provider sends them.
+#[derive(Debug)]
+pub struct RecordingProvider {
+    inner: Arc<dyn AIProvider>,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl RecordingProvider {
+    /// Record into a new cassette at `path`, replacing any file there.
+    pub fn new(inner: Arc<dyn AIProvider>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            cassette: Mutex::new(Cassette::default()),
+        }
+    }
+
+    /// Record after the interactions already in the cassette at `path`.
+    pub fn append(
+        inner: Arc<dyn AIProvider>,
+        path: impl Into<PathBuf>,
+    ) -> Result<Self, CassetteError> {
+        let path = path.into();
+        let cassette = if path.exists() {
+            Cassette::load(&path)?
+        } else {
+            Cassette::default()
+        };
+        Ok(Self {
+            inner,
+            path,
+            cassette: Mutex::new(cassette),
+        })
+    }
+
+    /// The interactions recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().expect("cassette poisoned").clone()
+    }
+
+    fn record(&self, stream: bool, request: GenerateRequest, outcome: RecordedOutcome) {
+        let mut cassette = self.cassette.lock().expect("cassette poisoned");
+        cassette.interactions.push(Interaction {
+            provider: self.inner.name().to_string(),
+            stream,
+            request,
+            outcome,
+        });
+        // Recording is a test aid: a failed write is logged, not surfaced.
+        if let Err(err) = cassette.save(&self.path) {
+            tracing::warn!(path = %self.path.display(), error = %err, "failed to write cassette");
+        }
+    }
+}
+
+#[async_trait]
+impl AIProvider for RecordingProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn supports_images(&self) -> bool {
+        self.inner.supports_images()
+    }
+
+    async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+        let result = self.inner.generate(req.clone()).await;
+        let outcome = match &result {
+            Ok(response) => RecordedOutcome::Response(response.clone()),
+            Err(err) => RecordedOutcome::Error(err.into()),
+        };
+        self.record(false, req, outcome);
+        result
+    }
+
+    async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
+        let mut inner = match self.inner.generate_stream(req.clone()).await {
+            Ok(stream) => stream,
+            Err(err) => {
+                self.record(true, req, RecordedOutcome::Error((&err).into()));
+                return Err(err);
+            }
+        };
+        let mut chunks = Vec::new();
+        while let Some(chunk) = inner.next().await {
+            match chunk {
+                Ok(chunk) => chunks.push(chunk),
+                Err(err) => {
+                    self.record(true, req, RecordedOutcome::Error((&err).into()));
+                    return Err(err);
+                }
+            }
+        }
+        self.record(true, req, RecordedOutcome::Stream(chunks.clone()));
+        Ok(chunk_stream(chunks))
+    }
+}
+
+/// [`AIProvider`] that answers from a [`Cassette`] instead of the network.
+#[derive(Debug)]
+pub struct ReplayProvider {
+    name: &'static str,
+    cassette: Cassette,
+    /// Indexes of the interactions served so far.
+    served: Mutex<HashSet<usize>>,
+}
+
+impl ReplayProvider {
+    pub fn new(cassette: Cassette) -> Self {
+        Self {
+            name: "replay",
+            cassette,
+            served: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CassetteError> {
+        Ok(Self::new(Cassette::load(path)?))
+    }
+
+    /// Report `name`, e.g. the provider the cassette was recorded from, so
+    /// callers that route by provider name find it.
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+
+    fn replay(
+        &self,
+        stream: bool,
+        req: &GenerateRequest,
+    ) -> Result<RecordedOutcome, ProviderError> {
+        let candidates: Vec<usize> = self
+            .cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| {
+                interaction.stream == stream && same_request(&interaction.request, req)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        let mut served = self.served.lock().expect("replay state poisoned");
+        let index = candidates
+            .iter()
+            .copied()
+            .find(|index| !served.contains(index))
+            .or_else(|| candidates.last().copied())
+            .ok_or_else(|| {
+                ProviderError::Message(format!(
+                    "no recorded {} for prompt {:?}",
+                    if stream { "stream" } else { "response" },
+                    req.prompt
+                ))
+            })?;
+        served.insert(index);
+        Ok(self.cassette.interactions[index].outcome.clone())
+    }
+}
+
+#[async_trait]
+impl AIProvider for ReplayProvider {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn supports_images(&self) -> bool {
+        true
+    }
+
+    async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+        match self.replay(false, &req)? {
+            RecordedOutcome::Response(response) => Ok(response),
+            RecordedOutcome::Error(err) => Err(err.into()),
+            RecordedOutcome::Stream(_) => unreachable!("streams are only matched by streams"),
+        }
+    }
+
+    async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
+        match self.replay(true, &req)? {
+            RecordedOutcome::Stream(chunks) => Ok(chunk_stream(chunks)),
+            RecordedOutcome::Error(err) => Err(err.into()),
+            RecordedOutcome::Response(_) => unreachable!("responses are only matched by requests"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockProvider;
+
+    fn request(prompt: &str) -> GenerateRequest {
+        GenerateRequest {
+            prompt: prompt.to_string(),
+            model: Some("gpt-4o-mini".to_string()),
+            max_tokens: None,
+            temperature: None,
+            metadata: None,
+            images: Vec::new(),
+        }
+    }
+
+    fn response(content: &str) -> GenerateResponse {
+        GenerateResponse {
+            content: content.to_string(),
+            model: Some("gpt-4o-mini".to_string()),
+            finish_reason: Some("stop".to_string()),
+            usage: None,
+        }
+    }
+
+    async fn collect(stream: ProviderStream) -> Vec<StreamChunk> {
+        stream.map(|chunk| chunk.unwrap()).collect().await
+    }
+
+    #[tokio::test]
+    async fn recorded_traffic_replays_in_order_without_the_provider() {
+        let path = std::env::temp_dir().join(format!(
+            "nexis-cassette-{}.json",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let inner = Arc::new(MockProvider::new());
+        inner.enqueue_generate(Ok(response("first")));
+        inner.enqueue_generate(Ok(response("second")));
+        inner.enqueue_generate(Err(ProviderError::HttpStatus {
+            status: 429,
+            body: "slow down".to_string(),
+        }));
+        inner.enqueue_stream(Ok(vec![
+            StreamChunk::Delta {
+                text: "streamed".to_string(),
+            },
+            StreamChunk::Done,
+        ]));
+
+        let recorder = RecordingProvider::new(inner, &path);
+        assert_eq!(
+            recorder.generate(request("hi")).await.unwrap().content,
+            "first"
+        );
+        let mut tagged = request("hi");
+        tagged.metadata = Some(serde_json::json!({ "roomId": "room_1" }));
+        assert_eq!(recorder.generate(tagged).await.unwrap().content, "second");
+        assert!(recorder.generate(request("busy")).await.is_err());
+        let streamed = collect(recorder.generate_stream(request("hi")).await.unwrap()).await;
+        assert_eq!(recorder.cassette().interactions.len(), 4);
+
+        let replay = ReplayProvider::open(&path).unwrap().with_name("openai");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.name(), "openai");
+        assert_eq!(
+            replay.generate(request("hi")).await.unwrap().content,
+            "first"
+        );
+        assert_eq!(
+            replay.generate(request("hi")).await.unwrap().content,
+            "second"
+        );
+        // Once the recorded answers run out, the last one repeats.
+        assert_eq!(
+            replay.generate(request("hi")).await.unwrap().content,
+            "second"
+        );
+        assert!(matches!(
+            replay.generate(request("busy")).await,
+            Err(ProviderError::HttpStatus { status: 429, .. })
+        ));
+        assert_eq!(
+            collect(replay.generate_stream(request("hi")).await.unwrap()).await,
+            streamed
+        );
+        assert!(matches!(
+            replay.generate(request("unrecorded")).await,
+            Err(ProviderError::Message(message)) if message.contains("unrecorded")
+        ));
+    }
+}
@@ -0,0 +1,653 @@
+//! Token cost accounting, monthly budgets and daily room budgets
+//!
+//! [`CostTracker`] prices [`TokenUsage`] with a per-model [`PriceTable`] and
+//! keeps running totals per member and per provider for the current calendar
+//! month (UTC). Totals start from zero when the month rolls over.
+//!
+//! Budgets are enforced by the caller: check [`CostTracker::check_budget`]
+//! before a generate call and [`CostTracker::record`] its usage afterwards.
+//!
+//! [`RoomBudgets`] caps the calls and tokens spent in each room per UTC day,
+//! whoever makes them, and lets an admin lift a room's caps until the day
+//! is over.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::TokenUsage;
+
+/// USD prices per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+impl ModelPrice {
+    pub fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+        }
+    }
+
+    /// Cost of `usage` in USD.
+    pub fn cost(&self, usage: TokenUsage) -> f64 {
+        (f64::from(usage.input_tokens) * self.input_per_million
+            + f64::from(usage.output_tokens) * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Prices keyed by model name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PriceTable {
+    prices: BTreeMap<String, ModelPrice>,
+}
+
+impl PriceTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_price(mut self, model: impl Into<String>, price: ModelPrice) -> Self {
+        self.insert(model, price);
+        self
+    }
+
+    pub fn insert(&mut self, model: impl Into<String>, price: ModelPrice) {
+        self.prices.insert(model.into(), price);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prices.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ModelPrice)> {
+        self.prices
+            .iter()
+            .map(|(model, price)| (model.as_str(), price))
+    }
+
+    /// Price for `model`: an exact match, else the longest configured
+    /// prefix, so dated snapshots like `gpt-4o-2024-08-06` use the
+    /// `gpt-4o` price.
+    pub fn price(&self, model: &str) -> Option<ModelPrice> {
+        if let Some(price) = self.prices.get(model) {
+            return Some(*price);
+        }
+        self.prices
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, price)| *price)
+    }
+
+    pub fn cost(&self, model: &str, usage: TokenUsage) -> Option<f64> {
+        self.price(model).map(|price| price.cost(usage))
+    }
+}
+
+/// Accumulated usage for one member or provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct CostTotals {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl CostTotals {
+    fn add(&mut self, usage: TokenUsage, cost_usd: f64) {
+        self.requests += 1;
+        self.input_tokens += u64::from(usage.input_tokens);
+        self.output_tokens += u64::from(usage.output_tokens);
+        self.cost_usd += cost_usd;
+    }
+}
+
+/// Snapshot of one month's totals.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CostSummary {
+    /// Calendar month the totals cover, as `YYYY-MM`.
+    pub period: String,
+    pub total: CostTotals,
+    pub members: BTreeMap<String, CostTotals>,
+    pub providers: BTreeMap<String, CostTotals>,
+    /// Requests for models missing from the price table, counted at zero cost.
+    pub unpriced_requests: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error(
+    "monthly AI budget exhausted for {member}: spent ${spent_usd:.2} of ${budget_usd:.2} in {period}"
+)]
+pub struct BudgetExceeded {
+    pub member: String,
+    pub period: String,
+    pub spent_usd: f64,
+    pub budget_usd: f64,
+}
+
+#[derive(Debug, Default)]
+struct CostState {
+    period: String,
+    members: HashMap<String, CostTotals>,
+    providers: HashMap<String, CostTotals>,
+    unpriced_requests: u64,
+}
+
+impl CostState {
+    /// Start a fresh month once `now` is past the tracked period.
+    fn roll_over(&mut self, now: DateTime<Utc>) {
+        let period = period_of(now);
+        if self.period != period {
+            *self = Self {
+                period,
+                ..Self::default()
+            };
+        }
+    }
+}
+
+fn period_of(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m").to_string()
+}
+
+/// Per-member and per-provider spending for the current month.
+#[derive(Debug, Default)]
+pub struct CostTracker {
+    prices: PriceTable,
+    default_budget_usd: Option<f64>,
+    member_budgets_usd: HashMap<String, f64>,
+    state: Mutex<CostState>,
+}
+
+impl CostTracker {
+    pub fn new(prices: PriceTable) -> Self {
+        Self {
+            prices,
+            ..Self::default()
+        }
+    }
+
+    /// Budget applied to every member without an explicit one.
+    pub fn with_monthly_budget(mut self, budget_usd: f64) -> Self {
+        self.default_budget_usd = Some(budget_usd);
+        self
+    }
+
+    pub fn with_member_budget(mut self, member: impl Into<String>, budget_usd: f64) -> Self {
+        self.member_budgets_usd.insert(member.into(), budget_usd);
+        self
+    }
+
+    pub fn prices(&self) -> &PriceTable {
+        &self.prices
+    }
+
+    pub fn budget_for(&self, member: &str) -> Option<f64> {
+        self.member_budgets_usd
+            .get(member)
+            .copied()
+            .or(self.default_budget_usd)
+    }
+
+    /// Reject `member` once this month's spending has reached their budget.
+    pub fn check_budget(&self, member: &str) -> Result<(), BudgetExceeded> {
+        self.check_budget_at(member, Utc::now())
+    }
+
+    fn check_budget_at(&self, member: &str, now: DateTime<Utc>) -> Result<(), BudgetExceeded> {
+        let Some(budget_usd) = self.budget_for(member) else {
+            return Ok(());
+        };
+        let mut state = self.state.lock().expect("cost tracker poisoned");
+        state.roll_over(now);
+        let spent_usd = state
+            .members
+            .get(member)
+            .map_or(0.0, |totals| totals.cost_usd);
+        if spent_usd >= budget_usd {
+            return Err(BudgetExceeded {
+                member: member.to_string(),
+                period: state.period.clone(),
+                spent_usd,
+                budget_usd,
+            });
+        }
+        Ok(())
+    }
+
+    /// Add one generate call's usage and return its cost in USD.
+    ///
+    /// Models missing from the price table are counted at zero cost.
+    pub fn record(&self, member: &str, provider: &str, model: &str, usage: TokenUsage) -> f64 {
+        self.record_at(member, provider, model, usage, Utc::now())
+    }
+
+    fn record_at(
+        &self,
+        member: &str,
+        provider: &str,
+        model: &str,
+        usage: TokenUsage,
+        now: DateTime<Utc>,
+    ) -> f64 {
+        let mut state = self.state.lock().expect("cost tracker poisoned");
+        state.roll_over(now);
+        let cost = match self.prices.cost(model, usage) {
+            Some(cost) => cost,
+            None => {
+                tracing::debug!(model, "no price configured for model");
+                state.unpriced_requests += 1;
+                0.0
+            }
+        };
+        state
+            .members
+            .entry(member.to_string())
+            .or_default()
+            .add(usage, cost);
+        state
+            .providers
+            .entry(provider.to_string())
+            .or_default()
+            .add(usage, cost);
+        cost
+    }
+
+    /// This month's totals for `member`.
+    pub fn member_totals(&self, member: &str) -> CostTotals {
+        let mut state = self.state.lock().expect("cost tracker poisoned");
+        state.roll_over(Utc::now());
+        state.members.get(member).copied().unwrap_or_default()
+    }
+
+    pub fn summary(&self) -> CostSummary {
+        self.summary_at(Utc::now())
+    }
+
+    fn summary_at(&self, now: DateTime<Utc>) -> CostSummary {
+        let mut state = self.state.lock().expect("cost tracker poisoned");
+        state.roll_over(now);
+        let mut total = CostTotals::default();
+        for totals in state.providers.values() {
+            total.requests += totals.requests;
+            total.input_tokens += totals.input_tokens;
+            total.output_tokens += totals.output_tokens;
+            total.cost_usd += totals.cost_usd;
+        }
+        CostSummary {
+            period: state.period.clone(),
+            total,
+            members: state
+                .members
+                .iter()
+                .map(|(member, totals)| (member.clone(), *totals))
+                .collect(),
+            providers: state
+                .providers
+                .iter()
+                .map(|(provider, totals)| (provider.clone(), *totals))
+                .collect(),
+            unpriced_requests: state.unpriced_requests,
+        }
+    }
+}
+
+/// Daily caps on one room's AI use; `None` leaves that cap off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoomLimits {
+    /// Generate calls per day.
+    pub max_calls: Option<u64>,
+    /// Input plus output tokens per day.
+    pub max_tokens: Option<u64>,
+}
+
+/// One room's AI use for the current day.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RoomUsage {
+    /// Day the usage covers, as `YYYY-MM-DD`.
+    pub day: String,
+    pub calls: u64,
+    pub tokens: u64,
+    pub limits: RoomLimits,
+    /// Whether an admin lifted the limits for the rest of the day.
+    pub overridden: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("daily AI budget exhausted for room {room_id}: used {used} of {limit} {unit} on {day}")]
+pub struct RoomBudgetExceeded {
+    pub room_id: String,
+    pub day: String,
+    /// The cap that was hit: `"calls"` or `"tokens"`.
+    pub unit: &'static str,
+    pub used: u64,
+    pub limit: u64,
+    /// Set on the day's first rejection only, so callers tell the room once.
+    pub first_rejection: bool,
+}
+
+impl RoomBudgetExceeded {
+    /// What to tell the room about it.
+    pub fn notice(&self) -> String {
+        format!(
+            "This room has used up its daily AI budget ({} of {} {}). AI replies resume at \
+             midnight UTC, or sooner if an admin lifts the limit.",
+            self.used, self.limit, self.unit
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct RoomTotals {
+    calls: u64,
+    tokens: u64,
+    overridden: bool,
+    rejected: bool,
+}
+
+#[derive(Debug, Default)]
+struct RoomState {
+    day: String,
+    rooms: HashMap<String, RoomTotals>,
+}
+
+impl RoomState {
+    /// Start a fresh day once `now` is past the tracked one.
+    fn roll_over(&mut self, now: DateTime<Utc>) {
+        let day = now.format("%Y-%m-%d").to_string();
+        if self.day != day {
+            *self = Self {
+                day,
+                ..Self::default()
+            };
+        }
+    }
+}
+
+/// Per-room AI calls and tokens for the current day (UTC).
+///
+/// Like [`CostTracker`], enforced by the caller: check
+/// [`RoomBudgets::check`] before a generate call in a room and
+/// [`RoomBudgets::record`] it afterwards.
+#[derive(Debug, Default)]
+pub struct RoomBudgets {
+    default_limits: RoomLimits,
+    room_limits: HashMap<String, RoomLimits>,
+    state: Mutex<RoomState>,
+}
+
+impl RoomBudgets {
+    /// Apply `limits` to every room without limits of its own.
+    pub fn new(limits: RoomLimits) -> Self {
+        Self {
+            default_limits: limits,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_room_limits(mut self, room_id: impl Into<String>, limits: RoomLimits) -> Self {
+        self.room_limits.insert(room_id.into(), limits);
+        self
+    }
+
+    pub fn limits_for(&self, room_id: &str) -> RoomLimits {
+        self.room_limits
+            .get(room_id)
+            .copied()
+            .unwrap_or(self.default_limits)
+    }
+
+    /// Reject another call in `room_id` once today's calls or tokens have
+    /// reached its limits.
+    pub fn check(&self, room_id: &str) -> Result<(), RoomBudgetExceeded> {
+        self.check_at(room_id, Utc::now())
+    }
+
+    fn check_at(&self, room_id: &str, now: DateTime<Utc>) -> Result<(), RoomBudgetExceeded> {
+        let limits = self.limits_for(room_id);
+        if limits == RoomLimits::default() {
+            return Ok(());
+        }
+        let mut state = self.state.lock().expect("room budgets poisoned");
+        state.roll_over(now);
+        let day = state.day.clone();
+        let totals = state.rooms.entry(room_id.to_string()).or_default();
+        if totals.overridden {
+            return Ok(());
+        }
+        let exhausted = [
+            ("calls", totals.calls, limits.max_calls),
+            ("tokens", totals.tokens, limits.max_tokens),
+        ]
+        .into_iter()
+        .find_map(|(unit, used, limit)| {
+            limit
+                .filter(|limit| used >= *limit)
+                .map(|limit| (unit, used, limit))
+        });
+        let Some((unit, used, limit)) = exhausted else {
+            return Ok(());
+        };
+        let first_rejection = !totals.rejected;
+        totals.rejected = true;
+        Err(RoomBudgetExceeded {
+            room_id: room_id.to_string(),
+            day,
+            unit,
+            used,
+            limit,
+            first_rejection,
+        })
+    }
+
+    /// Count one generate call in `room_id` and the tokens it used, if the
+    /// provider reported them.
+    pub fn record(&self, room_id: &str, usage: Option<TokenUsage>) {
+        self.record_at(room_id, usage, Utc::now());
+    }
+
+    fn record_at(&self, room_id: &str, usage: Option<TokenUsage>, now: DateTime<Utc>) {
+        let mut state = self.state.lock().expect("room budgets poisoned");
+        state.roll_over(now);
+        let totals = state.rooms.entry(room_id.to_string()).or_default();
+        totals.calls += 1;
+        if let Some(usage) = usage {
+            totals.tokens += u64::from(usage.input_tokens) + u64::from(usage.output_tokens);
+        }
+    }
+
+    /// Today's use of `room_id`.
+    pub fn usage(&self, room_id: &str) -> RoomUsage {
+        self.usage_at(room_id, Utc::now())
+    }
+
+    fn usage_at(&self, room_id: &str, now: DateTime<Utc>) -> RoomUsage {
+        let mut state = self.state.lock().expect("room budgets poisoned");
+        state.roll_over(now);
+        let totals = state.rooms.get(room_id).copied().unwrap_or_default();
+        RoomUsage {
+            day: state.day.clone(),
+            calls: totals.calls,
+            tokens: totals.tokens,
+            limits: self.limits_for(room_id),
+            overridden: totals.overridden,
+        }
+    }
+
+    /// Lift `room_id`'s limits for the rest of the day, or put them back.
+    pub fn set_override(&self, room_id: &str, overridden: bool) -> RoomUsage {
+        self.set_override_at(room_id, overridden, Utc::now())
+    }
+
+    fn set_override_at(&self, room_id: &str, overridden: bool, now: DateTime<Utc>) -> RoomUsage {
+        {
+            let mut state = self.state.lock().expect("room budgets poisoned");
+            state.roll_over(now);
+            let totals = state.rooms.entry(room_id.to_string()).or_default();
+            totals.overridden = overridden;
+            totals.rejected = false;
+        }
+        self.usage_at(room_id, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn usage(input_tokens: u32, output_tokens: u32) -> TokenUsage {
+        TokenUsage {
+            input_tokens,
+            output_tokens,
+        }
+    }
+
+    fn prices() -> PriceTable {
+        PriceTable::new()
+            .with_price("gpt-4o", ModelPrice::new(2.5, 10.0))
+            .with_price("gpt-4o-mini", ModelPrice::new(0.15, 0.6))
+    }
+
+    #[test]
+    fn price_table_prefers_exact_then_longest_prefix() {
+        let prices = prices();
+        assert_eq!(prices.price("gpt-4o"), Some(ModelPrice::new(2.5, 10.0)));
+        assert_eq!(
+            prices.price("gpt-4o-mini-2024-07-18"),
+            Some(ModelPrice::new(0.15, 0.6))
+        );
+        assert_eq!(
+            prices.price("gpt-4o-2024-08-06"),
+            Some(ModelPrice::new(2.5, 10.0))
+        );
+        assert_eq!(prices.price("claude-3-5-sonnet"), None);
+
+        let cost = prices.cost("gpt-4o", usage(1_000_000, 500_000)).unwrap();
+        assert!((cost - 7.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tracker_accumulates_per_member_and_provider() {
+        let tracker = CostTracker::new(prices());
+        let now = Utc.with_ymd_and_hms(2026, 3, 14, 12, 0, 0).unwrap();
+        tracker.record_at("alice", "openai", "gpt-4o", usage(1000, 1000), now);
+        tracker.record_at("alice", "openai", "gpt-4o-mini", usage(1000, 0), now);
+        tracker.record_at("bob", "anthropic", "claude-3-5-haiku", usage(10, 10), now);
+
+        let summary = tracker.summary_at(now);
+        assert_eq!(summary.period, "2026-03");
+        assert_eq!(summary.members["alice"].requests, 2);
+        assert_eq!(summary.members["alice"].input_tokens, 2000);
+        assert!((summary.members["alice"].cost_usd - 0.01265).abs() < 1e-9);
+        assert_eq!(summary.members["bob"].cost_usd, 0.0);
+        assert_eq!(summary.providers["openai"].requests, 2);
+        assert_eq!(summary.providers["anthropic"].output_tokens, 10);
+        assert_eq!(summary.total.requests, 3);
+        assert_eq!(summary.unpriced_requests, 1);
+    }
+
+    #[test]
+    fn budgets_reject_until_the_month_rolls_over() {
+        let tracker = CostTracker::new(prices())
+            .with_monthly_budget(1.0)
+            .with_member_budget("bob", 0.0);
+        let march = Utc.with_ymd_and_hms(2026, 3, 31, 23, 0, 0).unwrap();
+        let april = Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap();
+
+        assert!(tracker.check_budget_at("alice", march).is_ok());
+        tracker.record_at("alice", "openai", "gpt-4o", usage(0, 100_000), march);
+        let err = tracker.check_budget_at("alice", march).unwrap_err();
+        assert_eq!(err.period, "2026-03");
+        assert!((err.spent_usd - 1.0).abs() < 1e-9);
+        assert!(err.to_string().contains("spent $1.00 of $1.00"));
+        assert!(tracker.check_budget_at("bob", march).is_err());
+
+        assert!(tracker.check_budget_at("alice", april).is_ok());
+        assert_eq!(tracker.summary_at(april).total, CostTotals::default());
+    }
+
+    #[test]
+    fn room_budgets_cap_calls_and_tokens_per_day() {
+        let budgets = RoomBudgets::new(RoomLimits {
+            max_calls: Some(2),
+            max_tokens: None,
+        })
+        .with_room_limits(
+            "busy",
+            RoomLimits {
+                max_calls: None,
+                max_tokens: Some(1_000),
+            },
+        );
+        let morning = Utc.with_ymd_and_hms(2026, 3, 14, 8, 0, 0).unwrap();
+        let tomorrow = Utc.with_ymd_and_hms(2026, 3, 15, 0, 0, 0).unwrap();
+
+        budgets.record_at("general", None, morning);
+        budgets.record_at("general", Some(usage(10, 10)), morning);
+        let err = budgets.check_at("general", morning).unwrap_err();
+        assert_eq!((err.unit, err.used, err.limit), ("calls", 2, 2));
+        assert_eq!(err.day, "2026-03-14");
+        assert!(err.first_rejection);
+        assert!(err.to_string().contains("used 2 of 2 calls"));
+        assert!(err.notice().contains("(2 of 2 calls)"));
+        assert!(
+            !budgets
+                .check_at("general", morning)
+                .unwrap_err()
+                .first_rejection
+        );
+
+        budgets.record_at("busy", Some(usage(400, 400)), morning);
+        budgets.record_at("busy", Some(usage(400, 400)), morning);
+        budgets.record_at("busy", None, morning);
+        let err = budgets.check_at("busy", morning).unwrap_err();
+        assert_eq!((err.unit, err.used, err.limit), ("tokens", 1_600, 1_000));
+        assert_eq!(budgets.usage_at("busy", morning).calls, 3);
+
+        assert!(budgets.check_at("general", tomorrow).is_ok());
+        assert_eq!(budgets.usage_at("general", tomorrow).calls, 0);
+    }
+
+    #[test]
+    fn room_budget_overrides_last_for_the_day() {
+        let budgets = RoomBudgets::new(RoomLimits {
+            max_calls: Some(1),
+            max_tokens: None,
+        });
+        let noon = Utc.with_ymd_and_hms(2026, 3, 14, 12, 0, 0).unwrap();
+        let tomorrow = Utc.with_ymd_and_hms(2026, 3, 15, 12, 0, 0).unwrap();
+
+        budgets.record_at("general", None, noon);
+        assert!(budgets.check_at("general", noon).is_err());
+        let usage = budgets.set_override_at("general", true, noon);
+        assert!(usage.overridden);
+        assert_eq!(usage.calls, 1);
+        assert!(budgets.check_at("general", noon).is_ok());
+
+        let usage = budgets.set_override_at("general", false, noon);
+        assert!(!usage.overridden);
+        assert!(
+            budgets
+                .check_at("general", noon)
+                .unwrap_err()
+                .first_rejection
+        );
+
+        budgets.set_override_at("general", true, noon);
+        budgets.record_at("general", None, tomorrow);
+        assert!(!budgets.usage_at("general", tomorrow).overridden);
+        assert!(budgets.check_at("general", tomorrow).is_err());
+    }
+}
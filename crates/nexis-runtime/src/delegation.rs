@@ -0,0 +1,312 @@
+//! Agents taking on delegated tasks (NIP-004).
+//!
+//! A [`TaskBoard`] lists, claims and finishes the tasks of a room; the
+//! gateway's implementation is [`GatewayTransport`]. A [`TaskWorker`] picks
+//! the open tasks its agent may claim, generates an answer for each with its
+//! provider and reports the outcome back to the board.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use nexis_protocol::{DelegatedTask, TaskResult, TaskStatus};
+use serde::Deserialize;
+
+use crate::agent_runtime::{AgentRuntimeConfig, AgentRuntimeError, GatewayTransport};
+use crate::{AIProvider, GenerateRequest};
+
+/// Where an agent finds and reports delegated tasks.
+#[async_trait]
+pub trait TaskBoard: Send + Sync {
+    /// Tasks of `room_id` still waiting to be claimed.
+    async fn open_tasks(&self, room_id: &str) -> Result<Vec<DelegatedTask>, AgentRuntimeError>;
+
+    /// Claim a task for the authenticated member.
+    async fn claim(&self, room_id: &str, task_id: &str)
+        -> Result<DelegatedTask, AgentRuntimeError>;
+
+    /// Report the outcome of a claimed task.
+    async fn finish(
+        &self,
+        room_id: &str,
+        task_id: &str,
+        result: &TaskResult,
+    ) -> Result<DelegatedTask, AgentRuntimeError>;
+}
+
+#[async_trait]
+impl TaskBoard for GatewayTransport {
+    async fn open_tasks(&self, room_id: &str) -> Result<Vec<DelegatedTask>, AgentRuntimeError> {
+        #[derive(Deserialize)]
+        struct Listed {
+            tasks: Vec<DelegatedTask>,
+        }
+
+        let request = self
+            .rest(reqwest::Method::GET, &format!("/v1/rooms/{room_id}/tasks"))
+            .query(&[("status", TaskStatus::Open.as_str())]);
+        let listed: Listed = send_json(request, "list tasks").await?;
+        Ok(listed.tasks)
+    }
+
+    async fn claim(
+        &self,
+        room_id: &str,
+        task_id: &str,
+    ) -> Result<DelegatedTask, AgentRuntimeError> {
+        let request = self.rest(
+            reqwest::Method::POST,
+            &format!("/v1/rooms/{room_id}/tasks/{task_id}/claim"),
+        );
+        send_json(request, "claim task").await
+    }
+
+    async fn finish(
+        &self,
+        room_id: &str,
+        task_id: &str,
+        result: &TaskResult,
+    ) -> Result<DelegatedTask, AgentRuntimeError> {
+        let request = self
+            .rest(
+                reqwest::Method::POST,
+                &format!("/v1/rooms/{room_id}/tasks/{task_id}/complete"),
+            )
+            .json(result);
+        send_json(request, "complete task").await
+    }
+}
+
+async fn send_json<T: serde::de::DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+    action: &str,
+) -> Result<T, AgentRuntimeError> {
+    let response = request
+        .send()
+        .await
+        .map_err(|err| AgentRuntimeError::Transport(err.to_string()))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AgentRuntimeError::Transport(format!(
+            "{action} failed with status {status}: {body}"
+        )));
+    }
+    response
+        .json()
+        .await
+        .map_err(|err| AgentRuntimeError::Transport(err.to_string()))
+}
+
+/// Claims and works through delegated tasks on behalf of an agent member.
+pub struct TaskWorker {
+    config: Arc<AgentRuntimeConfig>,
+    provider: Arc<dyn AIProvider>,
+    board: Arc<dyn TaskBoard>,
+}
+
+impl TaskWorker {
+    pub fn new(
+        config: AgentRuntimeConfig,
+        provider: Arc<dyn AIProvider>,
+        board: Arc<dyn TaskBoard>,
+    ) -> Self {
+        Self {
+            config: Arc::new(config),
+            provider,
+            board,
+        }
+    }
+
+    /// Whether the agent may claim `task`, judged by its declared
+    /// capabilities.
+    pub fn can_take(&self, task: &DelegatedTask) -> bool {
+        task.claimable_by(self.config.member_id(), self.config.capabilities())
+    }
+
+    /// Claim `task`, generate an answer and report it. A failed generation
+    /// is reported as a failed task rather than returned as an error.
+    pub async fn work(&self, task: &DelegatedTask) -> Result<DelegatedTask, AgentRuntimeError> {
+        let room_id = task.room_id.as_str();
+        let claimed = self.board.claim(room_id, &task.id).await?;
+
+        let request = GenerateRequest {
+            prompt: self.config.render_task_prompt(&claimed),
+            model: self.config.model().map(str::to_string),
+            max_tokens: self.config.max_tokens(),
+            temperature: None,
+            metadata: Some(serde_json::json!({
+                "roomId": room_id,
+                "memberId": self.config.member_id().to_string(),
+                "taskId": claimed.id.clone(),
+            })),
+            images: Vec::new(),
+        };
+        let result = match self.provider.generate(request).await {
+            Ok(generated) => TaskResult::success(generated.content),
+            Err(err) => {
+                tracing::warn!(task_id = %claimed.id, "task generation failed: {}", err);
+                TaskResult::failure(format!("generation failed: {err}"))
+            }
+        };
+        self.board.finish(room_id, &claimed.id, &result).await
+    }
+
+    /// Work through every open task in `room_id` the agent may claim,
+    /// returning the finished ones. Tasks another member claims first are
+    /// skipped.
+    pub async fn work_room(&self, room_id: &str) -> Result<Vec<DelegatedTask>, AgentRuntimeError> {
+        let mut finished = Vec::new();
+        for task in self.board.open_tasks(room_id).await? {
+            if !self.can_take(&task) {
+                continue;
+            }
+            match self.work(&task).await {
+                Ok(task) => finished.push(task),
+                Err(err) => {
+                    tracing::warn!(task_id = %task.id, "agent could not work on task: {}", err);
+                }
+            }
+        }
+        Ok(finished)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GenerateResponse, MockProvider, ProviderError};
+    use nexis_protocol::{Capabilities, MemberId, TaskRequest, TaskTransitionError};
+    use std::sync::Mutex;
+
+    /// In-memory board acting for a single authenticated member.
+    struct MemoryBoard {
+        member: MemberId,
+        capabilities: Capabilities,
+        tasks: Mutex<Vec<DelegatedTask>>,
+    }
+
+    impl MemoryBoard {
+        fn change(
+            &self,
+            task_id: &str,
+            change: impl FnOnce(&mut DelegatedTask) -> Result<(), TaskTransitionError>,
+        ) -> Result<DelegatedTask, AgentRuntimeError> {
+            let mut tasks = self.tasks.lock().unwrap();
+            let task = tasks
+                .iter_mut()
+                .find(|task| task.id == task_id)
+                .ok_or_else(|| AgentRuntimeError::Transport("task not found".to_string()))?;
+            change(task).map_err(|err| AgentRuntimeError::Transport(err.to_string()))?;
+            Ok(task.clone())
+        }
+    }
+
+    #[async_trait]
+    impl TaskBoard for MemoryBoard {
+        async fn open_tasks(&self, room_id: &str) -> Result<Vec<DelegatedTask>, AgentRuntimeError> {
+            Ok(self
+                .tasks
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|task| task.room_id.as_str() == room_id)
+                .filter(|task| task.status == TaskStatus::Open)
+                .cloned()
+                .collect())
+        }
+
+        async fn claim(
+            &self,
+            _room_id: &str,
+            task_id: &str,
+        ) -> Result<DelegatedTask, AgentRuntimeError> {
+            self.change(task_id, |task| task.claim(&self.member, &self.capabilities))
+        }
+
+        async fn finish(
+            &self,
+            _room_id: &str,
+            task_id: &str,
+            result: &TaskResult,
+        ) -> Result<DelegatedTask, AgentRuntimeError> {
+            self.change(task_id, |task| task.finish(&self.member, result.clone()))
+        }
+    }
+
+    fn member(id: &str) -> MemberId {
+        id.parse().unwrap()
+    }
+
+    fn open(request: TaskRequest) -> DelegatedTask {
+        DelegatedTask::new(
+            "room_general".parse().unwrap(),
+            member("nexis:human:alice"),
+            request,
+        )
+    }
+
+    fn reply(content: &str) -> Result<GenerateResponse, ProviderError> {
+        Ok(GenerateResponse {
+            content: content.to_string(),
+            model: None,
+            finish_reason: Some("stop".to_string()),
+            usage: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn worker_completes_only_tasks_it_can_take() {
+        let agent = member("nexis:agent:reviewer");
+        let capabilities = Capabilities::new().with_tool("run_tests");
+        let board = Arc::new(MemoryBoard {
+            member: agent.clone(),
+            capabilities: capabilities.clone(),
+            tasks: Mutex::new(vec![
+                open(
+                    TaskRequest::new("Run the tests")
+                        .requiring(Capabilities::new().with_tool("run_tests")),
+                ),
+                open(TaskRequest::new("Deploy").requiring(Capabilities::new().with_tool("deploy"))),
+                open(TaskRequest::new("Translate").with_assignee(member("nexis:agent:translator"))),
+            ]),
+        });
+        let provider = Arc::new(MockProvider::new());
+        provider.enqueue_generate(reply("all 42 tests pass"));
+        let worker = TaskWorker::new(
+            AgentRuntimeConfig::new(agent.clone())
+                .unwrap()
+                .with_capabilities(capabilities),
+            provider,
+            board.clone(),
+        );
+
+        let finished = worker.work_room("room_general").await.unwrap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].request.title, "Run the tests");
+        assert_eq!(finished[0].status, TaskStatus::Completed);
+        assert_eq!(finished[0].claimed_by.as_ref(), Some(&agent));
+        assert_eq!(
+            finished[0].result,
+            Some(TaskResult::success("all 42 tests pass"))
+        );
+        assert_eq!(board.open_tasks("room_general").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_generation_fails_the_task() {
+        let agent = member("nexis:agent:helper");
+        let board = Arc::new(MemoryBoard {
+            member: agent.clone(),
+            capabilities: Capabilities::default(),
+            tasks: Mutex::new(vec![open(TaskRequest::new("Summarize the thread"))]),
+        });
+        let provider = Arc::new(MockProvider::new());
+        provider.enqueue_generate(Err(ProviderError::Message("overloaded".to_string())));
+        let worker = TaskWorker::new(AgentRuntimeConfig::new(agent).unwrap(), provider, board);
+
+        let finished = worker.work_room("room_general").await.unwrap();
+        assert_eq!(finished[0].status, TaskStatus::Failed);
+        let result = finished[0].result.as_ref().unwrap();
+        assert!(result.output.contains("overloaded"));
+    }
+}
@@ -12,6 +12,7 @@ use crate::embedding::{
     BatchEmbeddingRequest, BatchEmbeddingResponse, EmbeddingProvider, EmbeddingRequest,
     EmbeddingResponse, EmbeddingUsage, DEFAULT_EMBEDDING_DIMENSION,
 };
+use crate::telemetry::TraceContextExt;
 use crate::ProviderError;
 
 const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
@@ -147,6 +148,7 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
         self.dimension
     }
 
+    #[tracing::instrument(name = "embedding.openai.embed", skip_all, fields(otel.kind = "client"))]
     async fn embed(&self, req: EmbeddingRequest) -> Result<EmbeddingResponse, ProviderError> {
         let model = self.get_model(req.model.as_ref());
         let body = EmbeddingRequestBody {
@@ -156,12 +158,14 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
         };
 
         let mut last_error = None;
+        let mut last_code = None;
         for attempt in 0..=self.max_retries {
             match self.try_embed(&body).await {
                 Ok(response) => return Ok(response),
                 Err(err) => {
                     let retriable = is_retriable(&err);
                     last_error = Some(err.to_string());
+                    last_code = err.code();
                     if retriable && attempt < self.max_retries {
                         tokio::time::sleep(backoff(self.retry_base_delay, attempt)).await;
                         continue;
@@ -171,6 +175,7 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
                             attempts: attempt + 1,
                             last_error: last_error
                                 .unwrap_or_else(|| "unknown retry error".to_string()),
+                            code: last_code,
                         });
                     }
                     return Err(err);
@@ -181,9 +186,11 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
         Err(ProviderError::RetryExhausted {
             attempts: self.max_retries + 1,
             last_error: last_error.unwrap_or_else(|| "unknown retry error".to_string()),
+            code: last_code,
         })
     }
 
+    #[tracing::instrument(name = "embedding.openai.embed_batch", skip_all, fields(otel.kind = "client", batch_size = req.texts.len()))]
     async fn embed_batch(
         &self,
         req: BatchEmbeddingRequest,
@@ -205,12 +212,14 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
         };
 
         let mut last_error = None;
+        let mut last_code = None;
         for attempt in 0..=self.max_retries {
             match self.try_embed_batch(&body).await {
                 Ok(response) => return Ok(response),
                 Err(err) => {
                     let retriable = is_retriable(&err);
                     last_error = Some(err.to_string());
+                    last_code = err.code();
                     if retriable && attempt < self.max_retries {
                         tokio::time::sleep(backoff(self.retry_base_delay, attempt)).await;
                         continue;
@@ -220,6 +229,7 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
                             attempts: attempt + 1,
                             last_error: last_error
                                 .unwrap_or_else(|| "unknown retry error".to_string()),
+                            code: last_code,
                         });
                     }
                     return Err(err);
@@ -230,6 +240,7 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
         Err(ProviderError::RetryExhausted {
             attempts: self.max_retries + 1,
             last_error: last_error.unwrap_or_else(|| "unknown retry error".to_string()),
+            code: last_code,
         })
     }
 }
@@ -242,6 +253,7 @@ impl OpenAIEmbeddingProvider {
         let response = self
             .client
             .post(self.endpoint("/embeddings"))
+            .with_trace_context()
             .bearer_auth(&self.api_key)
             .json(body)
             .send()
@@ -288,6 +300,7 @@ impl OpenAIEmbeddingProvider {
         let response = self
             .client
             .post(self.endpoint("/embeddings"))
+            .with_trace_context()
             .bearer_auth(&self.api_key)
             .json(body)
             .send()
@@ -0,0 +1,244 @@
+//! Machine-readable causes of provider failures.
+//!
+//! [`ProviderError`] keeps what went wrong on the wire: the HTTP status and
+//! body, or the transport error. [`ProviderError::code`] turns that into an
+//! [`ErrorCode`] an agent can branch on, e.g. to shorten its prompt after
+//! [`ErrorCode::ContextTooLong`] or to back off after
+//! [`ErrorCode::RateLimited`]. Error bodies in OpenAI's
+//! (`{"error": {"type", "code", "message"}}`) and Anthropic's
+//! (`{"type": "error", "error": {"type", "message"}}`) formats are
+//! understood; for anything else the status code decides.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ProviderError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The API key is missing, invalid or lacks permission.
+    Auth,
+    /// Too many requests, or the account's quota is used up.
+    RateLimited,
+    /// The requested model does not exist or is not available.
+    ModelNotFound,
+    /// The prompt and requested output exceed the model's context window.
+    ContextTooLong,
+    /// The provider's content filter refused the prompt or the reply.
+    ContentFiltered,
+    /// The provider refused the request as malformed or too large.
+    InvalidRequest,
+    /// The provider failed or is overloaded.
+    Server,
+    /// The provider could not be reached.
+    Network,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auth => "auth",
+            Self::RateLimited => "rate_limited",
+            Self::ModelNotFound => "model_not_found",
+            Self::ContextTooLong => "context_too_long",
+            Self::ContentFiltered => "content_filtered",
+            Self::InvalidRequest => "invalid_request",
+            Self::Server => "server",
+            Self::Network => "network",
+        }
+    }
+
+    /// Classify an HTTP error response by its body, falling back to the
+    /// status code.
+    pub fn from_response(status: u16, body: &str) -> Option<Self> {
+        #[derive(Deserialize)]
+        struct Envelope {
+            error: Detail,
+        }
+
+        #[derive(Default, Deserialize)]
+        struct Detail {
+            #[serde(default, rename = "type")]
+            kind: Option<String>,
+            #[serde(default)]
+            code: Option<serde_json::Value>,
+            #[serde(default)]
+            message: Option<String>,
+        }
+
+        let detail = serde_json::from_str::<Envelope>(body)
+            .map(|envelope| envelope.error)
+            .unwrap_or_default();
+        let kind = detail.kind.as_deref().unwrap_or_default();
+        let code = detail
+            .code
+            .as_ref()
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default();
+        let message = detail.message.unwrap_or_default().to_ascii_lowercase();
+
+        let by_body = match (kind, code) {
+            (_, "context_length_exceeded" | "string_above_max_length") => {
+                Some(Self::ContextTooLong)
+            }
+            (_, "content_filter" | "content_policy_violation") => Some(Self::ContentFiltered),
+            (_, "model_not_found") => Some(Self::ModelNotFound),
+            (_, "invalid_api_key") | ("authentication_error" | "permission_error", _) => {
+                Some(Self::Auth)
+            }
+            (_, "rate_limit_exceeded" | "insufficient_quota") | ("rate_limit_error", _) => {
+                Some(Self::RateLimited)
+            }
+            ("api_error" | "overloaded_error" | "server_error", _) => Some(Self::Server),
+            _ if message.contains("context length")
+                || message.contains("context window")
+                || message.contains("prompt is too long") =>
+            {
+                Some(Self::ContextTooLong)
+            }
+            _ if message.contains("content management policy")
+                || message.contains("content filter") =>
+            {
+                Some(Self::ContentFiltered)
+            }
+            _ if (kind == "not_found_error" || status == 404) && message.contains("model") => {
+                Some(Self::ModelNotFound)
+            }
+            _ => None,
+        };
+
+        by_body.or(match status {
+            400 | 413 | 422 => Some(Self::InvalidRequest),
+            401 | 403 => Some(Self::Auth),
+            429 => Some(Self::RateLimited),
+            500.. => Some(Self::Server),
+            _ => None,
+        })
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ProviderError {
+    /// Why the request failed, when that is known.
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            Self::HttpStatus { status, body } => ErrorCode::from_response(*status, body),
+            Self::Transport(_) => Some(ErrorCode::Network),
+            Self::Overloaded(_) => Some(ErrorCode::RateLimited),
+            Self::RetryExhausted { code, .. } => *code,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn http(status: u16, body: &str) -> ProviderError {
+        ProviderError::HttpStatus {
+            status,
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn classifies_openai_error_bodies() {
+        let cases = [
+            (
+                400,
+                r#"{"error":{"message":"This model's maximum context length is 8192 tokens.","type":"invalid_request_error","param":"messages","code":"context_length_exceeded"}}"#,
+                ErrorCode::ContextTooLong,
+            ),
+            (
+                404,
+                r#"{"error":{"message":"The model `gpt-9` does not exist","type":"invalid_request_error","code":"model_not_found"}}"#,
+                ErrorCode::ModelNotFound,
+            ),
+            (
+                401,
+                r#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error","code":"invalid_api_key"}}"#,
+                ErrorCode::Auth,
+            ),
+            (
+                429,
+                r#"{"error":{"message":"You exceeded your current quota","type":"insufficient_quota","code":"insufficient_quota"}}"#,
+                ErrorCode::RateLimited,
+            ),
+            (
+                400,
+                r#"{"error":{"message":"The response was filtered due to the prompt triggering Azure OpenAI's content management policy.","type":null,"code":"content_filter"}}"#,
+                ErrorCode::ContentFiltered,
+            ),
+        ];
+        for (status, body, expected) in cases {
+            assert_eq!(http(status, body).code(), Some(expected), "{body}");
+        }
+    }
+
+    #[test]
+    fn classifies_anthropic_error_bodies() {
+        let cases = [
+            (
+                400,
+                r#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 210000 tokens > 200000 maximum"}}"#,
+                ErrorCode::ContextTooLong,
+            ),
+            (
+                404,
+                r#"{"type":"error","error":{"type":"not_found_error","message":"model: claude-9"}}"#,
+                ErrorCode::ModelNotFound,
+            ),
+            (
+                401,
+                r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#,
+                ErrorCode::Auth,
+            ),
+            (
+                429,
+                r#"{"type":"error","error":{"type":"rate_limit_error","message":"Number of requests has exceeded your rate limit"}}"#,
+                ErrorCode::RateLimited,
+            ),
+            (
+                529,
+                r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+                ErrorCode::Server,
+            ),
+        ];
+        for (status, body, expected) in cases {
+            assert_eq!(http(status, body).code(), Some(expected), "{body}");
+        }
+    }
+
+    #[test]
+    fn falls_back_to_the_status_and_survives_retries() {
+        assert_eq!(
+            http(503, "upstream unavailable").code(),
+            Some(ErrorCode::Server)
+        );
+        assert_eq!(http(403, "").code(), Some(ErrorCode::Auth));
+        assert_eq!(
+            http(400, "bad request").code(),
+            Some(ErrorCode::InvalidRequest)
+        );
+        assert_eq!(http(409, "conflict").code(), None);
+        assert_eq!(
+            ProviderError::Transport("connection refused".to_string()).code(),
+            Some(ErrorCode::Network)
+        );
+        assert_eq!(
+            ProviderError::RetryExhausted {
+                attempts: 3,
+                last_error: "http status 429: slow down".to_string(),
+                code: Some(ErrorCode::RateLimited),
+            }
+            .code(),
+            Some(ErrorCode::RateLimited)
+        );
+    }
+}
@@ -0,0 +1,658 @@
+//! Evaluation of AI providers against golden prompts.
+//!
+//! An [`EvalSuite`] lists prompts together with the properties a good answer
+//! must have: text it contains or leaves out, a regular expression it
+//! matches, a JSON Schema it conforms to, or a minimum score from a judge
+//! model grading it against written criteria. [`EvalRunner`] sends every
+//! prompt to an [`AIProvider`], checks the answers and returns an
+//! [`EvalReport`] scoring each case between 0 and 1.
+//!
+//! Suites are plain serde data, usually kept as TOML:
+//!
+//! ```toml
+//! name = "support answers"
+//! model = "gpt-4o-mini"
+//!
+//! [[cases]]
+//! name = "refund window"
+//! prompt = "How long do customers have to ask for a refund?"
+//! expect = [
+//!     { type = "contains", text = "30 days" },
+//!     { type = "judge", criteria = "Polite and under three sentences", min_score = 0.7 },
+//! ]
+//! ```
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{AIProvider, GenerateRequest};
+
+/// Score a judge must give when an expectation does not set `min_score`.
+const DEFAULT_MIN_JUDGE_SCORE: f64 = 0.5;
+
+#[derive(Debug, Error)]
+pub enum EvalError {
+    #[error("suite has no cases")]
+    NoCases,
+    #[error("case `{case}`: invalid regex `{pattern}`: {source}")]
+    InvalidRegex {
+        case: String,
+        pattern: String,
+        #[source]
+        source: regex::Error,
+    },
+    #[error("case `{0}` needs a judge provider")]
+    MissingJudge(String),
+}
+
+/// A named set of golden prompts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalSuite {
+    pub name: String,
+    /// Model every case asks for unless it names its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    pub cases: Vec<EvalCase>,
+}
+
+/// One prompt and what its answer is expected to look like.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalCase {
+    pub name: String,
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub expect: Vec<Expectation>,
+}
+
+/// A property an answer must have.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Expectation {
+    /// The answer contains `text`.
+    Contains {
+        text: String,
+        #[serde(default)]
+        ignore_case: bool,
+    },
+    /// The answer does not contain `text`.
+    Excludes {
+        text: String,
+        #[serde(default)]
+        ignore_case: bool,
+    },
+    /// The answer matches `pattern`.
+    Regex { pattern: String },
+    /// The answer is JSON, optionally fenced as a Markdown code block, that
+    /// conforms to `schema`. Supports the common JSON Schema keywords:
+    /// `type`, `enum`, `const`, `properties`, `required`,
+    /// `additionalProperties`, `items`, the length and item count bounds and
+    /// `minimum`/`maximum`.
+    JsonSchema { schema: Value },
+    /// A judge model scores the answer against `criteria` between 0 and 1.
+    Judge {
+        criteria: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_score: Option<f64>,
+    },
+}
+
+impl Expectation {
+    fn describe(&self) -> String {
+        match self {
+            Self::Contains { text, .. } => format!("contains {text:?}"),
+            Self::Excludes { text, .. } => format!("excludes {text:?}"),
+            Self::Regex { pattern } => format!("matches /{pattern}/"),
+            Self::JsonSchema { .. } => "conforms to JSON schema".to_string(),
+            Self::Judge { criteria, .. } => format!("judge: {criteria}"),
+        }
+    }
+}
+
+impl EvalSuite {
+    /// Check that the suite can be run, with or without a judge provider.
+    pub fn validate(&self, has_judge: bool) -> Result<(), EvalError> {
+        if self.cases.is_empty() {
+            return Err(EvalError::NoCases);
+        }
+        for case in &self.cases {
+            for expectation in &case.expect {
+                match expectation {
+                    Expectation::Regex { pattern } => {
+                        Regex::new(pattern).map_err(|source| EvalError::InvalidRegex {
+                            case: case.name.clone(),
+                            pattern: pattern.clone(),
+                            source,
+                        })?;
+                    }
+                    Expectation::Judge { .. } if !has_judge => {
+                        return Err(EvalError::MissingJudge(case.name.clone()));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of one expectation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub expectation: String,
+    pub passed: bool,
+    /// 1 or 0, or the judge's score.
+    pub score: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl CheckResult {
+    fn new(expectation: &Expectation, passed: bool, detail: Option<String>) -> Self {
+        Self {
+            expectation: expectation.describe(),
+            passed,
+            score: if passed { 1.0 } else { 0.0 },
+            detail,
+        }
+    }
+}
+
+/// Outcome of one case.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaseReport {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Set when the provider failed; the case then scores 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub checks: Vec<CheckResult>,
+    /// Mean score of the checks; 1 for a case without expectations.
+    pub score: f64,
+    /// Whether the provider answered and every check passed.
+    pub passed: bool,
+    pub latency_ms: u64,
+}
+
+/// Scored outcome of a suite run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub suite: String,
+    pub provider: String,
+    pub cases: Vec<CaseReport>,
+    pub passed: usize,
+    pub failed: usize,
+    /// Mean score of the cases.
+    pub score: f64,
+}
+
+/// Runs [`EvalSuite`]s against a provider.
+#[derive(Debug, Clone)]
+pub struct EvalRunner {
+    provider: Arc<dyn AIProvider>,
+    model: Option<String>,
+    judge: Option<(Arc<dyn AIProvider>, Option<String>)>,
+}
+
+impl EvalRunner {
+    pub fn new(provider: Arc<dyn AIProvider>) -> Self {
+        Self {
+            provider,
+            model: None,
+            judge: None,
+        }
+    }
+
+    /// Ask for `model` instead of the ones the suite and its cases name.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Grade `judge` expectations with `provider`, asking for `model`.
+    pub fn with_judge(mut self, provider: Arc<dyn AIProvider>, model: Option<String>) -> Self {
+        self.judge = Some((provider, model));
+        self
+    }
+
+    /// Run every case of `suite` in order.
+    pub async fn run(&self, suite: &EvalSuite) -> Result<EvalReport, EvalError> {
+        suite.validate(self.judge.is_some())?;
+
+        let mut cases = Vec::with_capacity(suite.cases.len());
+        for case in &suite.cases {
+            cases.push(self.run_case(suite, case).await);
+        }
+        let passed = cases.iter().filter(|case| case.passed).count();
+        let score = cases.iter().map(|case| case.score).sum::<f64>() / cases.len() as f64;
+        Ok(EvalReport {
+            suite: suite.name.clone(),
+            provider: self.provider.name().to_string(),
+            failed: cases.len() - passed,
+            passed,
+            score,
+            cases,
+        })
+    }
+
+    async fn run_case(&self, suite: &EvalSuite, case: &EvalCase) -> CaseReport {
+        let request = GenerateRequest {
+            prompt: case.prompt.clone(),
+            model: self
+                .model
+                .clone()
+                .or_else(|| case.model.clone())
+                .or_else(|| suite.model.clone()),
+            max_tokens: suite.max_tokens,
+            temperature: suite.temperature,
+            metadata: None,
+            images: Vec::new(),
+        };
+        let started = Instant::now();
+        let result = self.provider.generate(request).await;
+        let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let output = match result {
+            Ok(response) => response.content,
+            Err(err) => {
+                return CaseReport {
+                    name: case.name.clone(),
+                    output: None,
+                    error: Some(err.to_string()),
+                    checks: Vec::new(),
+                    score: 0.0,
+                    passed: false,
+                    latency_ms,
+                };
+            }
+        };
+
+        let mut checks = Vec::with_capacity(case.expect.len());
+        for expectation in &case.expect {
+            checks.push(self.check(case, expectation, &output).await);
+        }
+        let score = if checks.is_empty() {
+            1.0
+        } else {
+            checks.iter().map(|check| check.score).sum::<f64>() / checks.len() as f64
+        };
+        CaseReport {
+            name: case.name.clone(),
+            passed: checks.iter().all(|check| check.passed),
+            output: Some(output),
+            error: None,
+            checks,
+            score,
+            latency_ms,
+        }
+    }
+
+    async fn check(&self, case: &EvalCase, expectation: &Expectation, output: &str) -> CheckResult {
+        match expectation {
+            Expectation::Contains { text, ignore_case } => {
+                CheckResult::new(expectation, contains(output, text, *ignore_case), None)
+            }
+            Expectation::Excludes { text, ignore_case } => {
+                CheckResult::new(expectation, !contains(output, text, *ignore_case), None)
+            }
+            Expectation::Regex { pattern } => {
+                // Patterns were checked by `EvalSuite::validate`.
+                let passed = Regex::new(pattern).is_ok_and(|regex| regex.is_match(output));
+                CheckResult::new(expectation, passed, None)
+            }
+            Expectation::JsonSchema { schema } => {
+                let violations = match serde_json::from_str::<Value>(strip_code_fence(output)) {
+                    Ok(value) => schema_violations(schema, &value, "$"),
+                    Err(err) => vec![format!("not JSON: {err}")],
+                };
+                CheckResult::new(
+                    expectation,
+                    violations.is_empty(),
+                    (!violations.is_empty()).then(|| violations.join("; ")),
+                )
+            }
+            Expectation::Judge {
+                criteria,
+                min_score,
+            } => {
+                let min_score = min_score.unwrap_or(DEFAULT_MIN_JUDGE_SCORE);
+                match self.judge(case, criteria, output).await {
+                    Ok(score) => CheckResult {
+                        expectation: expectation.describe(),
+                        passed: score >= min_score,
+                        score,
+                        detail: Some(format!("scored {score:.2}, needs {min_score:.2}")),
+                    },
+                    Err(detail) => CheckResult::new(expectation, false, Some(detail)),
+                }
+            }
+        }
+    }
+
+    /// Ask the judge to grade `output`; its score is read from the first
+    /// number of its answer, on a scale of 0 to 10.
+    async fn judge(&self, case: &EvalCase, criteria: &str, output: &str) -> Result<f64, String> {
+        let Some((provider, model)) = &self.judge else {
+            return Err("no judge provider".to_string());
+        };
+        let prompt = format!(
+            "You grade answers of an AI assistant.\n\n\
+             Prompt:\n{}\n\nAnswer:\n{}\n\nCriteria:\n{}\n\n\
+             Reply with a single integer score from 0 (fails the criteria) \
+             to 10 (fully meets them) and nothing else.",
+            case.prompt, output, criteria
+        );
+        let response = provider
+            .generate(GenerateRequest {
+                prompt,
+                model: model.clone(),
+                max_tokens: Some(8),
+                temperature: Some(0.0),
+                metadata: None,
+                images: Vec::new(),
+            })
+            .await
+            .map_err(|err| format!("judge failed: {err}"))?;
+        parse_judge_score(&response.content)
+            .ok_or_else(|| format!("judge gave no score: {:?}", response.content))
+    }
+}
+
+fn contains(output: &str, text: &str, ignore_case: bool) -> bool {
+    if ignore_case {
+        output.to_lowercase().contains(&text.to_lowercase())
+    } else {
+        output.contains(text)
+    }
+}
+
+/// The first number in `answer`, on a scale of 0 to 10, as a score
+/// between 0 and 1.
+fn parse_judge_score(answer: &str) -> Option<f64> {
+    let start = answer.find(|c: char| c.is_ascii_digit())?;
+    let number: String = answer[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let score = number.trim_end_matches('.').parse::<f64>().ok()?;
+    Some((score / 10.0).clamp(0.0, 1.0))
+}
+
+/// `output` without a surrounding Markdown code fence, if it has one.
+fn strip_code_fence(output: &str) -> &str {
+    let trimmed = output.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    let Some(body) = rest.strip_suffix("```") else {
+        return trimmed;
+    };
+    // Skip the info string, e.g. `json`.
+    body.split_once('\n').map_or(body, |(_, body)| body).trim()
+}
+
+/// Why `value` at `path` does not conform to `schema`.
+fn schema_violations(schema: &Value, value: &Value, path: &str) -> Vec<String> {
+    let Some(schema) = schema.as_object() else {
+        // `true` accepts anything and `false` nothing.
+        return if schema == &Value::Bool(false) {
+            vec![format!("{path}: not allowed")]
+        } else {
+            Vec::new()
+        };
+    };
+    let mut violations = Vec::new();
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+            violations.push(format!("{path}: expected {}", types.join(" or ")));
+            return violations;
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            violations.push(format!("{path}: not one of the allowed values"));
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if constant != value {
+            violations.push(format!("{path}: expected {constant}"));
+        }
+    }
+
+    match value {
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if length < min {
+                    violations.push(format!("{path}: shorter than {min} characters"));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if length > max {
+                    violations.push(format!("{path}: longer than {max} characters"));
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if number < min {
+                    violations.push(format!("{path}: less than {min}"));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if number > max {
+                    violations.push(format!("{path}: greater than {max}"));
+                }
+            }
+        }
+        Value::Array(items) => {
+            let count = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if count < min {
+                    violations.push(format!("{path}: fewer than {min} items"));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if count > max {
+                    violations.push(format!("{path}: more than {max} items"));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    violations.extend(schema_violations(
+                        item_schema,
+                        item,
+                        &format!("{path}[{index}]"),
+                    ));
+                }
+            }
+        }
+        Value::Object(fields) => {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !fields.contains_key(name) {
+                        violations.push(format!("{path}: missing property `{name}`"));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, field) in fields {
+                let field_path = format!("{path}.{name}");
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(field_schema) => {
+                        violations.extend(schema_violations(field_schema, field, &field_path));
+                    }
+                    None => {
+                        if let Some(additional) = schema.get("additionalProperties") {
+                            violations.extend(schema_violations(additional, field, &field_path));
+                        }
+                    }
+                }
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+    violations
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GenerateResponse, MockProvider, ProviderError};
+
+    fn answer(content: &str) -> Result<GenerateResponse, ProviderError> {
+        Ok(GenerateResponse {
+            content: content.to_string(),
+            model: None,
+            finish_reason: None,
+            usage: None,
+        })
+    }
+
+    fn case(name: &str, expect: Vec<Expectation>) -> EvalCase {
+        EvalCase {
+            name: name.to_string(),
+            prompt: format!("prompt for {name}"),
+            model: None,
+            expect,
+        }
+    }
+
+    #[tokio::test]
+    async fn runner_scores_cases_and_reports_failures() {
+        let provider = Arc::new(MockProvider::new());
+        provider.enqueue_generate(answer("Refunds are possible within 30 Days."));
+        provider.enqueue_generate(answer(
+            "```json\n{\"answer\": \"yes\", \"confidence\": 2}\n```",
+        ));
+        provider.enqueue_generate(Err(ProviderError::Message("down".to_string())));
+        let judge = Arc::new(MockProvider::new());
+        judge.enqueue_generate(answer("Score: 8"));
+
+        let suite = EvalSuite {
+            name: "golden".to_string(),
+            cases: vec![
+                case(
+                    "refunds",
+                    vec![
+                        Expectation::Contains {
+                            text: "30 days".to_string(),
+                            ignore_case: true,
+                        },
+                        Expectation::Excludes {
+                            text: "sorry".to_string(),
+                            ignore_case: false,
+                        },
+                        Expectation::Regex {
+                            pattern: r"(?i)\d+ days".to_string(),
+                        },
+                        Expectation::Judge {
+                            criteria: "Polite".to_string(),
+                            min_score: Some(0.7),
+                        },
+                    ],
+                ),
+                case(
+                    "structured",
+                    vec![Expectation::JsonSchema {
+                        schema: serde_json::json!({
+                            "type": "object",
+                            "required": ["answer", "sources"],
+                            "properties": {
+                                "answer": { "enum": ["yes", "no"] },
+                                "confidence": { "type": "number", "maximum": 1 }
+                            }
+                        }),
+                    }],
+                ),
+                case("unavailable", Vec::new()),
+            ],
+            ..EvalSuite::default()
+        };
+
+        let runner = EvalRunner::new(provider.clone());
+        assert!(matches!(
+            runner.run(&suite).await,
+            Err(EvalError::MissingJudge(name)) if name == "refunds"
+        ));
+
+        let report = runner.with_judge(judge, None).run(&suite).await.unwrap();
+        assert_eq!((report.passed, report.failed), (1, 2));
+
+        let refunds = &report.cases[0];
+        assert!(refunds.passed);
+        assert!(refunds.checks[2].passed, "{:?}", refunds.checks[2]);
+        assert!((refunds.checks[3].score - 0.8).abs() < 1e-9);
+
+        let structured = &report.cases[1];
+        assert!(!structured.passed);
+        assert_eq!(
+            structured.checks[0].detail.as_deref(),
+            Some("$: missing property `sources`; $.confidence: greater than 1")
+        );
+
+        let unavailable = &report.cases[2];
+        assert_eq!(unavailable.error.as_deref(), Some("provider error: down"));
+        assert_eq!(unavailable.score, 0.0);
+        assert!((refunds.score - 0.95).abs() < 1e-9);
+        assert!((report.score - 0.95 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn suites_parse_from_toml_and_reject_bad_patterns() {
+        let suite: EvalSuite = toml::from_str(
+            r#"
+            name = "support"
+            model = "gpt-4o-mini"
+
+            [[cases]]
+            name = "json"
+            prompt = "Answer as JSON"
+            expect = [
+                { type = "json_schema", schema = { type = "object", required = ["answer"] } },
+                { type = "regex", pattern = "(unclosed" },
+            ]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(suite.cases[0].expect.len(), 2);
+        assert!(matches!(
+            suite.validate(false),
+            Err(EvalError::InvalidRegex { pattern, .. }) if pattern == "(unclosed"
+        ));
+        assert_eq!(parse_judge_score("7/10"), Some(0.7));
+        assert_eq!(parse_judge_score("none"), None);
+    }
+}
@@ -0,0 +1,328 @@
+//! Sending one request to several providers at once.
+//!
+//! [`FanoutProvider`] forwards each [`GenerateRequest`] to all of its
+//! providers concurrently. In [`FanoutMode::Race`] the first successful
+//! response wins and the requests still in flight are dropped, which cancels
+//! them; this trades spend for latency. In [`FanoutMode::Compare`] every
+//! provider is waited for, so their outputs can be compared side by side,
+//! e.g. for A/B tests of a new model. Each provider gets its own timeout.
+
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{AIProvider, GenerateRequest, GenerateResponse, ProviderError, ProviderStream};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FanoutMode {
+    /// Answer with the fastest successful response.
+    #[default]
+    Race,
+    /// Wait for every provider; `generate` answers with the first listed
+    /// provider that succeeded.
+    Compare,
+}
+
+/// What one provider made of a fanned-out request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FanoutOutcome {
+    pub provider: &'static str,
+    pub result: Result<GenerateResponse, ProviderError>,
+    pub latency: Duration,
+}
+
+/// [`AIProvider`] that sends every request to several providers at once.
+#[derive(Debug, Clone)]
+pub struct FanoutProvider {
+    providers: Vec<Arc<dyn AIProvider>>,
+    mode: FanoutMode,
+    timeout: Option<Duration>,
+}
+
+impl FanoutProvider {
+    /// Fan out over `providers`, listed in order of preference.
+    pub fn new(providers: Vec<Arc<dyn AIProvider>>, mode: FanoutMode) -> Self {
+        Self {
+            providers,
+            mode,
+            timeout: None,
+        }
+    }
+
+    /// Give up on a provider that has not answered within `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn mode(&self) -> FanoutMode {
+        self.mode
+    }
+
+    /// The first successful response, in the order they arrive. Providers
+    /// still working on the request when it arrives are cancelled.
+    pub async fn race(&self, req: GenerateRequest) -> Result<FanoutOutcome, ProviderError> {
+        self.ensure_providers()?;
+        let mut pending = self.dispatch(&req);
+        let mut failures = Vec::new();
+        while let Some(outcome) = pending.next().await {
+            if outcome.result.is_ok() {
+                return Ok(outcome);
+            }
+            failures.push(outcome);
+        }
+        Err(all_failed(&failures))
+    }
+
+    /// Every provider's outcome, in the order the providers are listed.
+    pub async fn compare(&self, req: GenerateRequest) -> Vec<FanoutOutcome> {
+        let mut outcomes = self.dispatch(&req).collect::<Vec<_>>().await;
+        outcomes.sort_by_key(|outcome| {
+            self.providers
+                .iter()
+                .position(|provider| provider.name() == outcome.provider)
+        });
+        outcomes
+    }
+
+    fn ensure_providers(&self) -> Result<(), ProviderError> {
+        if self.providers.is_empty() {
+            Err(ProviderError::Message(
+                "fanout has no providers".to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn dispatch(
+        &self,
+        req: &GenerateRequest,
+    ) -> FuturesUnordered<impl std::future::Future<Output = FanoutOutcome> + '_> {
+        self.providers
+            .iter()
+            .map(|provider| {
+                let req = req.clone();
+                async move {
+                    let started = Instant::now();
+                    let result = match self.timeout {
+                        Some(timeout) => tokio::time::timeout(timeout, provider.generate(req))
+                            .await
+                            .unwrap_or_else(|_| Err(timed_out(provider.name(), timeout))),
+                        None => provider.generate(req).await,
+                    };
+                    FanoutOutcome {
+                        provider: provider.name(),
+                        result,
+                        latency: started.elapsed(),
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+fn timed_out(provider: &str, timeout: Duration) -> ProviderError {
+    ProviderError::Transport(format!(
+        "{provider} timed out after {} ms",
+        timeout.as_millis()
+    ))
+}
+
+fn all_failed(failures: &[FanoutOutcome]) -> ProviderError {
+    let mut message = format!("all {} providers failed", failures.len());
+    for failure in failures {
+        if let Err(err) = &failure.result {
+            let _ = write!(message, "; {}: {err}", failure.provider);
+        }
+    }
+    ProviderError::Message(message)
+}
+
+#[async_trait]
+impl AIProvider for FanoutProvider {
+    fn name(&self) -> &'static str {
+        "fanout"
+    }
+
+    fn supports_images(&self) -> bool {
+        self.providers
+            .iter()
+            .all(|provider| provider.supports_images())
+    }
+
+    async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+        match self.mode {
+            FanoutMode::Race => self.race(req).await.and_then(|outcome| outcome.result),
+            FanoutMode::Compare => {
+                self.ensure_providers()?;
+                let outcomes = self.compare(req).await;
+                if let Some(outcome) = outcomes.iter().find(|outcome| outcome.result.is_ok()) {
+                    return outcome.result.clone();
+                }
+                Err(all_failed(&outcomes))
+            }
+        }
+    }
+
+    /// Streams from whichever provider opens its stream first; the other
+    /// attempts are cancelled.
+    async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
+        self.ensure_providers()?;
+        let mut pending = self
+            .providers
+            .iter()
+            .map(|provider| {
+                let req = req.clone();
+                async move {
+                    let result = match self.timeout {
+                        Some(timeout) => {
+                            tokio::time::timeout(timeout, provider.generate_stream(req))
+                                .await
+                                .unwrap_or_else(|_| Err(timed_out(provider.name(), timeout)))
+                        }
+                        None => provider.generate_stream(req).await,
+                    };
+                    (provider.name(), result)
+                }
+            })
+            .collect::<FuturesUnordered<_>>();
+        let mut errors = Vec::new();
+        while let Some((provider, result)) = pending.next().await {
+            match result {
+                Ok(stream) => return Ok(stream),
+                Err(err) => errors.push(format!("{provider}: {err}")),
+            }
+        }
+        Err(ProviderError::Message(format!(
+            "all {} providers failed; {}",
+            errors.len(),
+            errors.join("; ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    /// Answers after `delay`; notes whether it was cancelled before then.
+    #[derive(Debug)]
+    struct SlowProvider {
+        name: &'static str,
+        delay: Duration,
+        reply: Result<&'static str, ProviderError>,
+        cancelled: Arc<AtomicBool>,
+    }
+
+    impl SlowProvider {
+        fn new(
+            name: &'static str,
+            delay_ms: u64,
+            reply: Result<&'static str, ProviderError>,
+        ) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                delay: Duration::from_millis(delay_ms),
+                reply,
+                cancelled: Arc::new(AtomicBool::new(false)),
+            })
+        }
+    }
+
+    struct CancelGuard(Arc<AtomicBool>, bool);
+
+    impl Drop for CancelGuard {
+        fn drop(&mut self) {
+            if !self.1 {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AIProvider for SlowProvider {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn generate(&self, _req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+            let mut guard = CancelGuard(self.cancelled.clone(), false);
+            tokio::time::sleep(self.delay).await;
+            guard.1 = true;
+            self.reply.clone().map(|content| GenerateResponse {
+                content: content.to_string(),
+                model: Some(format!("{}-model", self.name)),
+                finish_reason: Some("stop".to_string()),
+                usage: None,
+            })
+        }
+
+        async fn generate_stream(
+            &self,
+            _req: GenerateRequest,
+        ) -> Result<ProviderStream, ProviderError> {
+            Err(ProviderError::Unsupported("streaming".to_string()))
+        }
+    }
+
+    fn request() -> GenerateRequest {
+        GenerateRequest {
+            prompt: "hello".to_string(),
+            model: None,
+            max_tokens: None,
+            temperature: None,
+            metadata: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn race_returns_the_fastest_success_and_compare_waits_for_all() {
+        let failing = SlowProvider::new("failing", 1, Err(ProviderError::Message("down".into())));
+        let fast = SlowProvider::new("fast", 20, Ok("quick"));
+        let slow = SlowProvider::new("slow", 2_000, Ok("late"));
+        let stuck = SlowProvider::new("stuck", 60_000, Ok("never"));
+
+        let race = FanoutProvider::new(
+            vec![failing.clone(), slow.clone(), fast.clone()],
+            FanoutMode::Race,
+        );
+        let winner = race.race(request()).await.unwrap();
+        assert_eq!(winner.provider, "fast");
+        assert_eq!(winner.result.unwrap().content, "quick");
+        assert!(slow.cancelled.load(Ordering::SeqCst));
+        assert!(!fast.cancelled.load(Ordering::SeqCst));
+
+        let compare = FanoutProvider::new(
+            vec![stuck.clone(), failing.clone(), fast.clone()],
+            FanoutMode::Compare,
+        )
+        .with_timeout(Duration::from_millis(200));
+        let outcomes = compare.compare(request()).await;
+        let providers = outcomes.iter().map(|o| o.provider).collect::<Vec<_>>();
+        assert_eq!(providers, ["stuck", "failing", "fast"]);
+        assert!(matches!(
+            &outcomes[0].result,
+            Err(ProviderError::Transport(message)) if message.contains("timed out")
+        ));
+        assert!(stuck.cancelled.load(Ordering::SeqCst));
+        assert_eq!(compare.generate(request()).await.unwrap().content, "quick");
+
+        let all_down = FanoutProvider::new(vec![failing], FanoutMode::Race);
+        let err = all_down.generate(request()).await.unwrap_err();
+        assert_eq!(
+            err,
+            ProviderError::Message(
+                "all 1 providers failed; failing: provider error: down".to_string()
+            )
+        );
+    }
+}
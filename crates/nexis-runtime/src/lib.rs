@@ -1,43 +1,108 @@
 //! Runtime abstractions and HTTP providers for AI integrations.
 //!
 //! This crate provides:
-//! - AI provider traits and implementations
-//! - Tool calling system for AI agents
-//! - Control plane client for task management
+//! - AI provider traits and implementations, with machine-readable error codes
+//! - Per-request timeouts and cancellation for provider calls
+//! - Tool calling system for AI agents, with human approval of risky calls
+//! - Room-resident agent runtime
+//! - Multi-agent turn-taking
+//! - Task delegation workers for agents
+//! - Control plane client for task management, with queue introspection,
+//!   cancellation, deduplication and awaitable task handles
+//! - Speech-to-text transcription providers
+//! - Response caching for deterministic prompts
+//! - Recording and replaying provider traffic for offline tests
+//! - Fanning requests out to several providers at once
+//! - Weighted load balancing over provider backends with health checks
+//! - Capping concurrent provider calls, globally or per provider
+//! - Token cost tracking, monthly budgets and daily room budgets
+//! - Evaluation of providers against golden prompts
 
 pub mod agent;
+pub mod agent_runtime;
+pub mod approval;
+pub mod balancer;
+pub mod cache;
+pub mod cassette;
+pub mod cost;
+pub mod delegation;
 pub mod embedding;
+pub mod eval;
+pub mod fanout;
+pub mod limiter;
+pub mod orchestration;
 pub mod providers;
+#[cfg(feature = "axum")]
+pub mod queue_api;
 pub mod registry;
+pub mod request_options;
 pub mod tool;
+pub mod transcription;
+
+mod error_code;
+mod telemetry;
 
 pub use agent::{compose_agent_prompt, AgentConfig, AgentRegistry, AgentRegistryError};
+pub use agent_runtime::{
+    is_mentioned, relay_tool_output, AgentRuntime, AgentRuntimeConfig, AgentRuntimeError,
+    GatewayTransport, RoomMessage, RoomMessageStream, RoomTransport, TriggerPolicy,
+};
+pub use approval::{ApprovalDecision, ApprovalPolicy, Approver, RoomApprover};
+pub use balancer::{Backend, BackendHealth, HealthConfig, LoadBalancingProvider};
+pub use cache::{CacheConfig, CacheStats, CachingProvider, MemoryResponseCache, ResponseCache};
+pub use cassette::{Cassette, CassetteError, RecordingProvider, ReplayProvider};
+pub use cost::{
+    BudgetExceeded, CostSummary, CostTotals, CostTracker, ModelPrice, PriceTable,
+    RoomBudgetExceeded, RoomBudgets, RoomLimits, RoomUsage,
+};
+pub use delegation::{TaskBoard, TaskWorker};
 pub use embedding::{
     BatchEmbeddingRequest, BatchEmbeddingResponse, EmbeddingProvider, EmbeddingRequest,
     EmbeddingResponse, EmbeddingUsage, MockEmbeddingProvider, OpenAIEmbeddingProvider,
 };
-pub use providers::{AnthropicProvider, OpenAIProvider};
+pub use error_code::ErrorCode;
+pub use eval::{EvalCase, EvalError, EvalReport, EvalRunner, EvalSuite, Expectation};
+pub use fanout::{FanoutMode, FanoutOutcome, FanoutProvider};
+pub use limiter::{ConcurrencyLimitedProvider, ConcurrencyLimiter, LimiterStats};
+pub use orchestration::{
+    ProviderModerator, TurnDecision, TurnModerator, TurnOrchestrator, TurnPolicy,
+};
+pub use providers::{AnthropicProvider, OpenAICompatibleProvider, OpenAIProvider};
 
 // Re-export registry types
 pub use registry::ProviderRegistry;
+pub use request_options::RequestOptions;
+// Re-exported so callers can cancel requests without depending on tokio-util
+pub use tokio_util::sync::CancellationToken;
 
 // Re-export tool types for convenience
 pub use tool::{
-    CodeExecuteTool, FileReadTool, Tool, ToolCall, ToolDefinition, ToolError, ToolRegistry,
-    ToolResult, WebSearchTool,
+    CodeExecuteTool, FileReadTool, HttpRequestConfig, HttpRequestTool, Tool, ToolCall,
+    ToolDefinition, ToolError, ToolOutputStream, ToolRegistry, ToolRegistryBuilder, ToolResult,
+    WebSearchTool,
+};
+pub use transcription::{
+    MockTranscriptionProvider, OpenAITranscriptionProvider, TranscriptionProvider,
+    TranscriptionRequest, TranscriptionResponse,
 };
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
-use std::time::Duration;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use futures::stream::{self, Stream};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use thiserror::Error;
+use tokio::sync::{broadcast, oneshot};
 use tokio::time::sleep;
 
+use crate::telemetry::TraceContextExt;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GenerateRequest {
     pub prompt: String,
@@ -45,6 +110,44 @@ pub struct GenerateRequest {
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
     pub metadata: Option<serde_json::Value>,
+    /// Images shown to the model alongside `prompt`. Providers without
+    /// vision support reject requests that carry any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageInput>,
+}
+
+impl GenerateRequest {
+    /// Fail with [`ProviderError::Unsupported`] if the request carries
+    /// images, for providers that can only handle text.
+    pub fn ensure_text_only(&self, provider: &str) -> Result<(), ProviderError> {
+        if self.images.is_empty() {
+            Ok(())
+        } else {
+            Err(ProviderError::Unsupported(format!(
+                "{provider} does not accept image inputs"
+            )))
+        }
+    }
+}
+
+/// Image passed to a multimodal model.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageInput {
+    /// Image the provider fetches itself.
+    Url { url: String },
+    /// Inline image bytes, base64 encoded, e.g. `media_type: "image/png"`.
+    Base64 { media_type: String, data: String },
+}
+
+impl ImageInput {
+    /// The image as a URL, inlining base64 images as a `data:` URL.
+    pub fn to_url(&self) -> String {
+        match self {
+            Self::Url { url } => url.clone(),
+            Self::Base64 { media_type, data } => format!("data:{media_type};base64,{data}"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -52,6 +155,21 @@ pub struct GenerateResponse {
     pub content: String,
     pub model: Option<String>,
     pub finish_reason: Option<String>,
+    /// Token counts reported by the provider, when it reports them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+}
+
+impl TokenUsage {
+    pub fn total(&self) -> u32 {
+        self.input_tokens.saturating_add(self.output_tokens)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -81,6 +199,7 @@ pub struct ToolCallResponse {
 enum TaskKind {
     Generate(GenerateRequest),
     ToolCall(ToolCallRequest),
+    GenerateBatch(Vec<GenerateRequest>),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -103,16 +222,55 @@ pub enum ProviderError {
     #[error("response decode error: {0}")]
     Decode(String),
     #[error("retry exhausted after {attempts} attempts: {last_error}")]
-    RetryExhausted { attempts: u32, last_error: String },
+    RetryExhausted {
+        attempts: u32,
+        last_error: String,
+        /// [`ProviderError::code`] of the last attempt.
+        code: Option<ErrorCode>,
+    },
+    #[error("unsupported request: {0}")]
+    Unsupported(String),
+    #[error("request cancelled")]
+    Cancelled,
+    /// No concurrency slot freed up in time; reported as
+    /// [`ErrorCode::RateLimited`].
+    #[error("provider overloaded: {0}")]
+    Overloaded(String),
 }
 
 #[async_trait]
 pub trait AIProvider: Send + Sync + std::fmt::Debug {
     fn name(&self) -> &'static str;
 
+    /// Whether [`GenerateRequest::images`] reach the model.
+    fn supports_images(&self) -> bool {
+        false
+    }
+
     async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError>;
 
     async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError>;
+
+    /// [`Self::generate`], abandoned on `options`' timeout or cancellation.
+    async fn generate_with_options(
+        &self,
+        req: GenerateRequest,
+        options: RequestOptions,
+    ) -> Result<GenerateResponse, ProviderError> {
+        options.run(options.deadline(), self.generate(req)).await
+    }
+
+    /// [`Self::generate_stream`], cut off on `options`' timeout or
+    /// cancellation, which also closes the provider's stream.
+    async fn generate_stream_with_options(
+        &self,
+        req: GenerateRequest,
+        options: RequestOptions,
+    ) -> Result<ProviderStream, ProviderError> {
+        let deadline = options.deadline();
+        let stream = options.run(deadline, self.generate_stream(req)).await?;
+        Ok(options.guard(deadline, stream))
+    }
 }
 
 #[async_trait]
@@ -163,12 +321,14 @@ impl HttpJsonProvider {
         TRes: DeserializeOwned,
     {
         let mut last_error = None;
+        let mut last_code = None;
         for attempt in 0..=self.max_retries {
             match self.try_post_json(path, payload).await {
                 Ok(value) => return Ok(value),
                 Err(err) => {
                     let retriable = is_retriable(&err);
                     last_error = Some(err.to_string());
+                    last_code = err.code();
                     if retriable && attempt < self.max_retries {
                         sleep(backoff(self.retry_base_delay, attempt)).await;
                         continue;
@@ -178,6 +338,7 @@ impl HttpJsonProvider {
                             attempts: attempt + 1,
                             last_error: last_error
                                 .unwrap_or_else(|| "unknown retry error".to_string()),
+                            code: last_code,
                         });
                     }
                     return Err(err);
@@ -188,9 +349,11 @@ impl HttpJsonProvider {
         Err(ProviderError::RetryExhausted {
             attempts: self.max_retries + 1,
             last_error: last_error.unwrap_or_else(|| "unknown retry error".to_string()),
+            code: last_code,
         })
     }
 
+    #[tracing::instrument(name = "provider.http.post", skip_all, fields(otel.kind = "client", path = %path))]
     async fn try_post_json<TReq, TRes>(
         &self,
         path: &str,
@@ -203,6 +366,7 @@ impl HttpJsonProvider {
         let response = self
             .client
             .post(self.endpoint(path))
+            .with_trace_context()
             .bearer_auth(&self.api_key)
             .json(payload)
             .send()
@@ -234,6 +398,11 @@ impl AIProvider for HttpJsonProvider {
         "http-json"
     }
 
+    /// Images are forwarded as part of the JSON request body.
+    fn supports_images(&self) -> bool {
+        true
+    }
+
     async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
         self.post_json_with_retry("/v1/generate", &req).await
     }
@@ -265,10 +434,166 @@ impl ToolProvider for HttpJsonProvider {
     }
 }
 
+impl TaskKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Generate(_) => "generate",
+            Self::ToolCall(_) => "tool_call",
+            Self::GenerateBatch(_) => "generate_batch",
+        }
+    }
+}
+
+/// A task waiting in, or being dispatched from, a [`ControlPlaneClient`].
+#[derive(Debug, Clone)]
+struct QueueEntry {
+    /// Tells the entry apart from others once it leaves the queue.
+    seq: u64,
+    task: QueuedTask,
+    /// Ids of the enqueuers still waiting for this execution, the original
+    /// one first. Never empty while the entry is queued or in flight.
+    task_ids: Vec<String>,
+    dedup_key: Option<String>,
+    cancel: CancellationToken,
+    /// When the task was first enqueued; retries keep it.
+    enqueued_at: Instant,
+    last_error: Option<String>,
+}
+
+impl QueueEntry {
+    fn info(&self, state: TaskState, now: Instant) -> TaskInfo {
+        TaskInfo {
+            id: self.task_ids[0].clone(),
+            coalesced_ids: self.task_ids[1..].to_vec(),
+            kind: self.task.kind.as_str().to_string(),
+            dedup_key: self.dedup_key.clone(),
+            state,
+            attempts: self.task.attempts,
+            last_error: self.last_error.clone(),
+            age_ms: now.duration_since(self.enqueued_at).as_millis() as u64,
+        }
+    }
+
+    fn has_id(&self, task_id: &str) -> bool {
+        self.task_ids.iter().any(|id| id == task_id)
+    }
+}
+
+/// Where a task of a [`ControlPlaneClient`] currently is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    /// Waiting in the queue, possibly after a failed attempt.
+    Pending,
+    /// Being dispatched to the control plane.
+    InFlight,
+}
+
+/// A queued task as [`ControlPlaneClient::task`] reports it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
+    pub id: String,
+    /// Ids of later enqueues that were coalesced into this task.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub coalesced_ids: Vec<String>,
+    /// `generate`, `tool_call` or `generate_batch`.
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedup_key: Option<String>,
+    pub state: TaskState,
+    /// Failed attempts so far.
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// Time since the task was first enqueued.
+    pub age_ms: u64,
+}
+
+/// Counters and gauges of a [`ControlPlaneClient`]'s queue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueStats {
+    pub pending: usize,
+    pub in_flight: usize,
+    /// Age of the task enqueued longest ago that is still pending or in flight.
+    pub oldest_task_age_ms: Option<u64>,
+    /// Tasks enqueued since the client was created.
+    pub enqueued: u64,
+    /// Tasks the control plane accepted.
+    pub completed: u64,
+    /// Tasks dropped after a non-retriable error or their last attempt.
+    pub failed: u64,
+    /// Failed attempts that were put back in the queue.
+    pub retried: u64,
+    /// Enqueues that joined a task with the same dedup key.
+    pub coalesced: u64,
+    /// Tasks dropped because every enqueuer cancelled them.
+    pub cancelled: u64,
+}
+
+#[derive(Debug, Default)]
+struct QueueCounters {
+    enqueued: AtomicU64,
+    completed: AtomicU64,
+    failed: AtomicU64,
+    retried: AtomicU64,
+    coalesced: AtomicU64,
+    cancelled: AtomicU64,
+}
+
+/// What became of an enqueue with a dedup key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Enqueued {
+    /// The task was queued on its own.
+    Queued,
+    /// A pending or in-flight task with the same key will answer for it.
+    Coalesced { into: String },
+}
+
+/// How a task ended, as [`ControlPlaneClient::subscribe`] reports it: once
+/// for each id coalesced into the task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskCompletion {
+    pub task_id: String,
+    pub result: Result<serde_json::Value, ProviderError>,
+}
+
+type TaskResult = Result<serde_json::Value, ProviderError>;
+
+/// Resolves with the result of one task of a [`ControlPlaneClient`] once
+/// whoever drains the queue has processed it. A task cancelled, or a client
+/// dropped, before then resolves to [`ProviderError::Cancelled`].
+#[derive(Debug)]
+pub struct TaskHandle {
+    task_id: String,
+    receiver: oneshot::Receiver<TaskResult>,
+}
+
+impl TaskHandle {
+    pub fn task_id(&self) -> &str {
+        &self.task_id
+    }
+}
+
+impl Future for TaskHandle {
+    type Output = TaskResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver)
+            .poll(cx)
+            .map(|result| result.unwrap_or(Err(ProviderError::Cancelled)))
+    }
+}
+
 #[derive(Debug)]
 pub struct ControlPlaneClient {
     http: HttpJsonProvider,
-    queue: Mutex<VecDeque<QueuedTask>>,
+    queue: Mutex<VecDeque<QueueEntry>>,
+    in_flight: Mutex<Vec<QueueEntry>>,
+    counters: QueueCounters,
+    completions: broadcast::Sender<TaskCompletion>,
+    /// Handles waiting for a task, by task id.
+    waiters: Mutex<HashMap<String, Vec<oneshot::Sender<TaskResult>>>>,
     max_task_attempts: u32,
     retry_delay: Duration,
 }
@@ -278,6 +603,10 @@ impl ControlPlaneClient {
         Self {
             http,
             queue: Mutex::new(VecDeque::new()),
+            in_flight: Mutex::new(Vec::new()),
+            counters: QueueCounters::default(),
+            completions: broadcast::channel(256).0,
+            waiters: Mutex::new(HashMap::new()),
             max_task_attempts: 3,
             retry_delay: Duration::from_millis(100),
         }
@@ -290,55 +619,357 @@ impl ControlPlaneClient {
     }
 
     pub fn enqueue_generate(&self, task_id: impl Into<String>, req: GenerateRequest) {
-        self.push_task(QueuedTask {
-            id: task_id.into(),
-            attempts: 0,
-            kind: TaskKind::Generate(req),
-        });
+        self.push_task(
+            QueuedTask {
+                id: task_id.into(),
+                attempts: 0,
+                kind: TaskKind::Generate(req),
+            },
+            None,
+        );
+    }
+
+    /// Like [`Self::enqueue_generate`], but returns a handle to await the
+    /// result with while another task drains the queue.
+    pub fn enqueue_generate_with_handle(
+        &self,
+        task_id: impl Into<String>,
+        req: GenerateRequest,
+    ) -> TaskHandle {
+        let task_id = task_id.into();
+        let handle = self.register_waiter(&task_id);
+        self.enqueue_generate(task_id, req);
+        handle
+    }
+
+    /// Like [`Self::enqueue_generate`], but joins a pending or in-flight task
+    /// enqueued with the same `dedup_key` instead of generating twice.
+    pub fn enqueue_generate_deduplicated(
+        &self,
+        task_id: impl Into<String>,
+        dedup_key: impl Into<String>,
+        req: GenerateRequest,
+    ) -> Enqueued {
+        self.push_task(
+            QueuedTask {
+                id: task_id.into(),
+                attempts: 0,
+                kind: TaskKind::Generate(req),
+            },
+            Some(dedup_key.into()),
+        )
     }
 
     pub fn enqueue_tool_call(&self, task_id: impl Into<String>, req: ToolCallRequest) {
-        self.push_task(QueuedTask {
-            id: task_id.into(),
-            attempts: 0,
-            kind: TaskKind::ToolCall(req),
-        });
+        self.push_task(
+            QueuedTask {
+                id: task_id.into(),
+                attempts: 0,
+                kind: TaskKind::ToolCall(req),
+            },
+            None,
+        );
+    }
+
+    /// Like [`Self::enqueue_tool_call`], but returns a handle to await the
+    /// result with.
+    pub fn enqueue_tool_call_with_handle(
+        &self,
+        task_id: impl Into<String>,
+        req: ToolCallRequest,
+    ) -> TaskHandle {
+        let task_id = task_id.into();
+        let handle = self.register_waiter(&task_id);
+        self.enqueue_tool_call(task_id, req);
+        handle
+    }
+
+    /// Like [`Self::enqueue_tool_call`], but joins a pending or in-flight
+    /// task enqueued with the same `dedup_key` instead of calling twice.
+    pub fn enqueue_tool_call_deduplicated(
+        &self,
+        task_id: impl Into<String>,
+        dedup_key: impl Into<String>,
+        req: ToolCallRequest,
+    ) -> Enqueued {
+        self.push_task(
+            QueuedTask {
+                id: task_id.into(),
+                attempts: 0,
+                kind: TaskKind::ToolCall(req),
+            },
+            Some(dedup_key.into()),
+        )
+    }
+
+    /// Queue `requests` for the gateway's batch endpoint, which generates
+    /// them in the background. The result is the batch it accepted; poll
+    /// `/v1/generate/batch/:batchId` for the replies.
+    pub fn enqueue_batch(&self, task_id: impl Into<String>, requests: Vec<GenerateRequest>) {
+        self.push_task(
+            QueuedTask {
+                id: task_id.into(),
+                attempts: 0,
+                kind: TaskKind::GenerateBatch(requests),
+            },
+            None,
+        );
+    }
+
+    /// Like [`Self::enqueue_batch`], but returns a handle to await the
+    /// accepted batch with.
+    pub fn enqueue_batch_with_handle(
+        &self,
+        task_id: impl Into<String>,
+        requests: Vec<GenerateRequest>,
+    ) -> TaskHandle {
+        let task_id = task_id.into();
+        let handle = self.register_waiter(&task_id);
+        self.enqueue_batch(task_id, requests);
+        handle
+    }
+
+    /// A handle to the pending or in-flight task `task_id`, e.g. one a
+    /// deduplicated enqueue was coalesced into.
+    pub fn handle(&self, task_id: &str) -> Option<TaskHandle> {
+        // Holding both locks keeps the task from finishing before the
+        // handle is registered.
+        let in_flight = self.in_flight.lock().expect("in-flight tasks poisoned");
+        let queue = self.queue.lock().expect("task queue poisoned");
+        in_flight
+            .iter()
+            .chain(queue.iter())
+            .any(|entry| entry.has_id(task_id))
+            .then(|| self.register_waiter(task_id))
+    }
+
+    /// Receive the outcome of every task finished from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<TaskCompletion> {
+        self.completions.subscribe()
+    }
+
+    /// Withdraw `task_id`. A task others were coalesced into keeps running
+    /// for them; otherwise a pending task is dropped and an in-flight
+    /// dispatch is aborted. Subscribers see the task end as
+    /// [`ProviderError::Cancelled`]. Returns whether `task_id` was found.
+    pub fn cancel(&self, task_id: &str) -> bool {
+        let mut in_flight = self.in_flight.lock().expect("in-flight tasks poisoned");
+        let mut queue = self.queue.lock().expect("task queue poisoned");
+        let found = if let Some(index) = queue.iter().position(|entry| entry.has_id(task_id)) {
+            let entry = &mut queue[index];
+            entry.task_ids.retain(|id| id != task_id);
+            if entry.task_ids.is_empty() {
+                queue.remove(index);
+                self.counters.cancelled.fetch_add(1, Ordering::Relaxed);
+            }
+            true
+        } else if let Some(index) = in_flight.iter().position(|entry| entry.has_id(task_id)) {
+            let entry = &mut in_flight[index];
+            entry.task_ids.retain(|id| id != task_id);
+            if entry.task_ids.is_empty() {
+                // `drain_once` notices the entry is gone once the dispatch
+                // stops.
+                in_flight.remove(index).cancel.cancel();
+                self.counters.cancelled.fetch_add(1, Ordering::Relaxed);
+            }
+            true
+        } else {
+            false
+        };
+        drop((queue, in_flight));
+        if found {
+            self.notify(&[task_id.to_string()], Err(ProviderError::Cancelled));
+        }
+        found
     }
 
     pub fn queued_tasks(&self) -> usize {
         self.queue.lock().expect("task queue poisoned").len()
     }
 
+    /// Every pending and in-flight task, in-flight ones first.
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        let now = Instant::now();
+        let mut tasks = self
+            .in_flight
+            .lock()
+            .expect("in-flight tasks poisoned")
+            .iter()
+            .map(|entry| entry.info(TaskState::InFlight, now))
+            .collect::<Vec<_>>();
+        tasks.extend(
+            self.queue
+                .lock()
+                .expect("task queue poisoned")
+                .iter()
+                .map(|entry| entry.info(TaskState::Pending, now)),
+        );
+        tasks
+    }
+
+    /// The pending or in-flight task `task_id`, or the one it was coalesced
+    /// into.
+    pub fn task(&self, task_id: &str) -> Option<TaskInfo> {
+        self.tasks()
+            .into_iter()
+            .find(|task| task.id == task_id || task.coalesced_ids.iter().any(|id| id == task_id))
+    }
+
+    /// Time since the longest-waiting pending or in-flight task was enqueued.
+    pub fn oldest_task_age(&self) -> Option<Duration> {
+        let now = Instant::now();
+        let in_flight = self.in_flight.lock().expect("in-flight tasks poisoned");
+        let queue = self.queue.lock().expect("task queue poisoned");
+        in_flight
+            .iter()
+            .chain(queue.iter())
+            .map(|entry| now.duration_since(entry.enqueued_at))
+            .max()
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        let counters = &self.counters;
+        let in_flight = self
+            .in_flight
+            .lock()
+            .expect("in-flight tasks poisoned")
+            .len();
+        QueueStats {
+            pending: self.queued_tasks(),
+            in_flight,
+            oldest_task_age_ms: self.oldest_task_age().map(|age| age.as_millis() as u64),
+            enqueued: counters.enqueued.load(Ordering::Relaxed),
+            completed: counters.completed.load(Ordering::Relaxed),
+            failed: counters.failed.load(Ordering::Relaxed),
+            retried: counters.retried.load(Ordering::Relaxed),
+            coalesced: counters.coalesced.load(Ordering::Relaxed),
+            cancelled: counters.cancelled.load(Ordering::Relaxed),
+        }
+    }
+
     pub async fn drain_once(&self) -> Result<Option<serde_json::Value>, ProviderError> {
-        let task = self.queue.lock().expect("task queue poisoned").pop_front();
-
-        match task {
-            None => Ok(None),
-            Some(mut task) => match self.dispatch_task(task.clone()).await {
-                Ok(result) => Ok(Some(result)),
-                Err(err) if is_retriable(&err) => {
-                    task.attempts += 1;
-                    if task.attempts < self.max_task_attempts {
-                        self.push_task(task);
-                        sleep(self.retry_delay).await;
-                        Ok(None)
-                    } else {
-                        Err(ProviderError::RetryExhausted {
-                            attempts: task.attempts,
-                            last_error: err.to_string(),
-                        })
-                    }
+        let (seq, task, cancel) = {
+            let mut in_flight = self.in_flight.lock().expect("in-flight tasks poisoned");
+            let Some(entry) = self.queue.lock().expect("task queue poisoned").pop_front() else {
+                return Ok(None);
+            };
+            let picked = (entry.seq, entry.task.clone(), entry.cancel.clone());
+            in_flight.push(entry);
+            picked
+        };
+
+        let result = tokio::select! {
+            result = self.dispatch_task(task) => result,
+            () = cancel.cancelled() => Err(ProviderError::Cancelled),
+        };
+        let finished = {
+            let mut in_flight = self.in_flight.lock().expect("in-flight tasks poisoned");
+            // A task cancelled mid-dispatch has already been taken out.
+            let Some(index) = in_flight.iter().position(|entry| entry.seq == seq) else {
+                return Err(ProviderError::Cancelled);
+            };
+            let mut entry = in_flight.remove(index);
+            match result {
+                Err(err)
+                    if is_retriable(&err) && entry.task.attempts + 1 < self.max_task_attempts =>
+                {
+                    entry.task.attempts += 1;
+                    entry.last_error = Some(err.to_string());
+                    self.queue
+                        .lock()
+                        .expect("task queue poisoned")
+                        .push_back(entry);
+                    None
                 }
-                Err(err) => Err(err),
-            },
+                result => Some((entry, result)),
+            }
+        };
+        let Some((entry, result)) = finished else {
+            self.counters.retried.fetch_add(1, Ordering::Relaxed);
+            sleep(self.retry_delay).await;
+            return Ok(None);
+        };
+
+        match result {
+            Ok(result) => {
+                self.counters.completed.fetch_add(1, Ordering::Relaxed);
+                self.notify(&entry.task_ids, Ok(result.clone()));
+                Ok(Some(result))
+            }
+            Err(err) => {
+                self.counters.failed.fetch_add(1, Ordering::Relaxed);
+                let err = if is_retriable(&err) {
+                    ProviderError::RetryExhausted {
+                        attempts: entry.task.attempts + 1,
+                        last_error: err.to_string(),
+                        code: err.code(),
+                    }
+                } else {
+                    err
+                };
+                self.notify(&entry.task_ids, Err(err.clone()));
+                Err(err)
+            }
+        }
+    }
+
+    fn push_task(&self, task: QueuedTask, dedup_key: Option<String>) -> Enqueued {
+        let seq = self.counters.enqueued.fetch_add(1, Ordering::Relaxed);
+        let mut in_flight = self.in_flight.lock().expect("in-flight tasks poisoned");
+        let mut queue = self.queue.lock().expect("task queue poisoned");
+        if let Some(key) = &dedup_key {
+            if let Some(existing) = in_flight
+                .iter_mut()
+                .chain(queue.iter_mut())
+                .find(|entry| entry.dedup_key.as_ref() == Some(key))
+            {
+                existing.task_ids.push(task.id);
+                self.counters.coalesced.fetch_add(1, Ordering::Relaxed);
+                return Enqueued::Coalesced {
+                    into: existing.task_ids[0].clone(),
+                };
+            }
         }
+        queue.push_back(QueueEntry {
+            seq,
+            task_ids: vec![task.id.clone()],
+            task,
+            dedup_key,
+            cancel: CancellationToken::new(),
+            enqueued_at: Instant::now(),
+            last_error: None,
+        });
+        Enqueued::Queued
     }
 
-    fn push_task(&self, task: QueuedTask) {
-        self.queue
+    fn register_waiter(&self, task_id: &str) -> TaskHandle {
+        let (sender, receiver) = oneshot::channel();
+        self.waiters
             .lock()
-            .expect("task queue poisoned")
-            .push_back(task);
+            .expect("task waiters poisoned")
+            .entry(task_id.to_string())
+            .or_default()
+            .push(sender);
+        TaskHandle {
+            task_id: task_id.to_string(),
+            receiver,
+        }
+    }
+
+    /// Tell handles and subscribers how the task behind `task_ids` ended.
+    fn notify(&self, task_ids: &[String], result: TaskResult) {
+        let mut waiters = self.waiters.lock().expect("task waiters poisoned");
+        for task_id in task_ids {
+            for waiter in waiters.remove(task_id).unwrap_or_default() {
+                // A dropped handle no longer cares.
+                let _ = waiter.send(result.clone());
+            }
+            // Nobody listening is fine.
+            let _ = self.completions.send(TaskCompletion {
+                task_id: task_id.clone(),
+                result: result.clone(),
+            });
+        }
     }
 
     async fn dispatch_task(&self, task: QueuedTask) -> Result<serde_json::Value, ProviderError> {
@@ -347,9 +978,40 @@ impl ControlPlaneClient {
             result: serde_json::Value,
         }
 
-        let path = match task.kind {
+        /// One item of a `/v1/generate/batch` request.
+        #[derive(Debug, Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct BatchItem<'a> {
+            prompt: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            model: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            max_tokens: Option<u32>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            temperature: Option<f32>,
+        }
+
+        let path = match &task.kind {
             TaskKind::Generate(_) => "/v1/tasks/generate",
             TaskKind::ToolCall(_) => "/v1/tasks/tool-call",
+            TaskKind::GenerateBatch(requests) => {
+                let items = requests
+                    .iter()
+                    .map(|req| BatchItem {
+                        prompt: &req.prompt,
+                        model: req.model.as_deref(),
+                        max_tokens: req.max_tokens,
+                        temperature: req.temperature,
+                    })
+                    .collect::<Vec<_>>();
+                return self
+                    .http
+                    .post_json_with_retry(
+                        "/v1/generate/batch",
+                        &serde_json::json!({ "requests": items }),
+                    )
+                    .await;
+            }
         };
 
         let envelope: ControlPlaneEnvelope = self.http.post_json_with_retry(path, &task).await?;
@@ -389,6 +1051,10 @@ impl AIProvider for MockProvider {
         "mock"
     }
 
+    fn supports_images(&self) -> bool {
+        true
+    }
+
     async fn generate(&self, _req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
         self.generate_queue
             .lock()
@@ -427,8 +1093,8 @@ fn is_retriable(err: &ProviderError) -> bool {
 #[cfg(test)]
 mod tests {
     use super::{
-        AIProvider, ControlPlaneClient, GenerateRequest, GenerateResponse, HttpJsonProvider,
-        MockProvider, ProviderError, StreamChunk, ToolCallRequest,
+        AIProvider, ControlPlaneClient, Enqueued, GenerateRequest, GenerateResponse,
+        HttpJsonProvider, MockProvider, ProviderError, StreamChunk, TaskState, ToolCallRequest,
     };
     use futures::StreamExt;
     use httpmock::Method::POST;
@@ -447,6 +1113,7 @@ mod tests {
             max_tokens: Some(64),
             temperature: Some(0.0),
             metadata: None,
+            images: Vec::new(),
         }
     }
 
@@ -457,6 +1124,7 @@ mod tests {
             content: "hello from mock".to_string(),
             model: Some("mock-1".to_string()),
             finish_reason: Some("stop".to_string()),
+            usage: None,
         }));
 
         let response = provider.generate(request()).await.unwrap();
@@ -620,6 +1288,265 @@ mod tests {
         assert_eq!(queue.queued_tasks(), 0);
     }
 
+    #[tokio::test]
+    async fn task_queue_reports_pending_tasks_and_counters() {
+        if !network_tests_enabled() {
+            eprintln!("skipping network test: set NEXIS_RUN_NETWORK_TESTS=1 to enable");
+            return;
+        }
+
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v1/tasks/generate");
+                then.status(503).body("control plane unavailable");
+            })
+            .await;
+
+        let queue = ControlPlaneClient::new(
+            HttpJsonProvider::new(server.base_url(), "test-key")
+                .with_retry_policy(0, Duration::from_millis(1)),
+        )
+        .with_retry_policy(2, Duration::from_millis(1));
+        queue.enqueue_generate("task_1", request());
+        queue.enqueue_tool_call(
+            "task_2",
+            ToolCallRequest {
+                tool_name: "search".to_string(),
+                input: json!({"query": "nexis"}),
+                metadata: None,
+            },
+        );
+        assert_eq!(queue.stats().pending, 2);
+        assert!(queue.oldest_task_age().is_some());
+
+        assert!(queue.drain_once().await.unwrap().is_none());
+
+        let tasks = queue.tasks();
+        assert_eq!(
+            tasks
+                .iter()
+                .map(|task| task.id.as_str())
+                .collect::<Vec<_>>(),
+            ["task_2", "task_1"]
+        );
+        let retried = queue.task("task_1").unwrap();
+        assert_eq!(retried.kind, "generate");
+        assert_eq!(retried.state, TaskState::Pending);
+        assert_eq!(retried.attempts, 1);
+        assert!(retried.last_error.is_some());
+        assert_eq!(queue.task("task_2").unwrap().kind, "tool_call");
+        assert_eq!(queue.task("task_3"), None);
+
+        let stats = queue.stats();
+        assert_eq!(
+            (
+                stats.pending,
+                stats.in_flight,
+                stats.enqueued,
+                stats.retried
+            ),
+            (2, 0, 2, 1)
+        );
+        assert_eq!((stats.completed, stats.failed), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn task_queue_coalesces_and_cancels_pending_tasks() {
+        let queue = ControlPlaneClient::new(HttpJsonProvider::new(
+            "http://control-plane.invalid",
+            "test-key",
+        ));
+        let mut completions = queue.subscribe();
+        assert_eq!(
+            queue.enqueue_generate_deduplicated("task_1", "summary:room_1", request()),
+            Enqueued::Queued
+        );
+        assert_eq!(
+            queue.enqueue_generate_deduplicated("task_2", "summary:room_1", request()),
+            Enqueued::Coalesced {
+                into: "task_1".to_string()
+            }
+        );
+        queue.enqueue_generate("task_3", request());
+        assert_eq!(queue.queued_tasks(), 2);
+        assert_eq!(queue.task("task_2").unwrap().id, "task_1");
+
+        // The coalesced enqueue keeps the task alive.
+        assert!(queue.cancel("task_1"));
+        assert_eq!(queue.task("task_2").unwrap().id, "task_2");
+        let completion = completions.recv().await.unwrap();
+        assert_eq!(completion.task_id, "task_1");
+        assert_eq!(completion.result, Err(ProviderError::Cancelled));
+
+        assert!(queue.cancel("task_2"));
+        assert!(!queue.cancel("task_2"));
+        assert_eq!(queue.queued_tasks(), 1);
+        let stats = queue.stats();
+        assert_eq!((stats.coalesced, stats.cancelled), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn task_queue_shares_results_and_aborts_cancelled_dispatches() {
+        if !network_tests_enabled() {
+            eprintln!("skipping network test: set NEXIS_RUN_NETWORK_TESTS=1 to enable");
+            return;
+        }
+
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v1/tasks/generate");
+                then.status(200)
+                    .json_body(json!({"result": {"content": "done"}}));
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v1/tasks/tool-call");
+                then.status(200)
+                    .delay(Duration::from_secs(30))
+                    .json_body(json!({"result": {}}));
+            })
+            .await;
+
+        let queue = std::sync::Arc::new(ControlPlaneClient::new(HttpJsonProvider::new(
+            server.base_url(),
+            "test-key",
+        )));
+        let mut completions = queue.subscribe();
+        queue.enqueue_generate_deduplicated("task_1", "summary:room_1", request());
+        queue.enqueue_generate_deduplicated("task_2", "summary:room_1", request());
+        queue.drain_once().await.unwrap();
+        for expected in ["task_1", "task_2"] {
+            let completion = completions.recv().await.unwrap();
+            assert_eq!(completion.task_id, expected);
+            assert_eq!(completion.result, Ok(json!({"content": "done"})));
+        }
+
+        queue.enqueue_tool_call(
+            "task_3",
+            ToolCallRequest {
+                tool_name: "search".to_string(),
+                input: json!({"query": "nexis"}),
+                metadata: None,
+            },
+        );
+        let drain = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.drain_once().await }
+        });
+        while queue.stats().in_flight == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert!(queue.cancel("task_3"));
+        let result = tokio::time::timeout(Duration::from_secs(5), drain)
+            .await
+            .expect("cancelled dispatch should stop")
+            .unwrap();
+        assert_eq!(result, Err(ProviderError::Cancelled));
+        assert_eq!(queue.stats().in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn task_handles_resolve_when_their_task_ends() {
+        let queue = ControlPlaneClient::new(HttpJsonProvider::new(
+            "http://control-plane.invalid",
+            "test-key",
+        ));
+        let handle = queue.enqueue_generate_with_handle("task_1", request());
+        assert_eq!(handle.task_id(), "task_1");
+        assert!(queue.handle("task_2").is_none());
+        let second = queue.handle("task_1").unwrap();
+
+        assert!(queue.cancel("task_1"));
+        assert_eq!(handle.await, Err(ProviderError::Cancelled));
+        assert_eq!(second.await, Err(ProviderError::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn task_handles_receive_results_drained_by_a_worker() {
+        if !network_tests_enabled() {
+            eprintln!("skipping network test: set NEXIS_RUN_NETWORK_TESTS=1 to enable");
+            return;
+        }
+
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v1/tasks/generate");
+                then.status(200)
+                    .json_body(json!({"result": {"content": "done"}}));
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v1/tasks/tool-call");
+                then.status(400).body("unknown tool");
+            })
+            .await;
+
+        let queue = std::sync::Arc::new(ControlPlaneClient::new(HttpJsonProvider::new(
+            server.base_url(),
+            "test-key",
+        )));
+        let generated = queue.enqueue_generate_with_handle("task_1", request());
+        let called = queue.enqueue_tool_call_with_handle(
+            "task_2",
+            ToolCallRequest {
+                tool_name: "search".to_string(),
+                input: json!({"query": "nexis"}),
+                metadata: None,
+            },
+        );
+        let worker = tokio::spawn({
+            let queue = queue.clone();
+            async move {
+                while queue.queued_tasks() > 0 {
+                    let _ = queue.drain_once().await;
+                }
+            }
+        });
+
+        assert_eq!(generated.await, Ok(json!({"content": "done"})));
+        assert!(matches!(
+            called.await,
+            Err(ProviderError::HttpStatus { status: 400, .. })
+        ));
+        worker.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn task_queue_submits_batches_to_the_batch_endpoint() {
+        if !network_tests_enabled() {
+            eprintln!("skipping network test: set NEXIS_RUN_NETWORK_TESTS=1 to enable");
+            return;
+        }
+
+        let server = MockServer::start_async().await;
+        let submitted = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v1/generate/batch").json_body(json!({
+                    "requests": [
+                        { "prompt": "hello", "model": "mock-1", "maxTokens": 64, "temperature": 0.0 },
+                        { "prompt": "hello", "model": "mock-1", "maxTokens": 64, "temperature": 0.0 }
+                    ]
+                }));
+                then.status(202)
+                    .json_body(json!({"batchId": "batch_1", "status": "running", "total": 2}));
+            })
+            .await;
+
+        let queue = ControlPlaneClient::new(HttpJsonProvider::new(server.base_url(), "test-key"));
+        queue.enqueue_batch("task_1", vec![request(), request()]);
+
+        let result = queue.drain_once().await.unwrap().unwrap();
+
+        submitted.assert_async().await;
+        assert_eq!(result["batchId"], "batch_1");
+        assert_eq!(queue.queued_tasks(), 0);
+    }
+
     #[tokio::test]
     async fn task_queue_requeues_retriable_failures() {
         if !network_tests_enabled() {
@@ -0,0 +1,307 @@
+//! Capping how many provider calls run at once.
+//!
+//! A [`ConcurrencyLimiter`] hands out a fixed number of slots. Each
+//! [`ConcurrencyLimitedProvider`] takes one for the duration of a call, or of
+//! a whole streamed answer, and queues callers while none is free. Share one
+//! limiter between several providers for a global cap, or give each its own
+//! to cap them separately. With a maximum wait, a caller that has queued too
+//! long fails instead of piling up behind a provider that is being rate
+//! limited. [`ConcurrencyLimiter::stats`] reports queue depth and wait times.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{AIProvider, GenerateRequest, GenerateResponse, ProviderError, ProviderStream};
+
+/// Slots and wait times of a [`ConcurrencyLimiter`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LimiterStats {
+    /// Slots the limiter hands out.
+    pub max_concurrent: usize,
+    /// Calls holding a slot right now.
+    pub in_flight: usize,
+    /// Calls queued for a slot right now.
+    pub waiting: usize,
+    /// Calls that got a slot since the limiter was built.
+    pub acquired: u64,
+    /// Calls that gave up after the maximum wait.
+    pub rejected: u64,
+    /// Time all calls that got a slot spent queued.
+    pub total_wait_ms: u64,
+    /// Longest time one call spent queued before getting a slot.
+    pub longest_wait_ms: u64,
+}
+
+/// Fixed number of slots shared by the providers it limits.
+#[derive(Debug)]
+pub struct ConcurrencyLimiter {
+    semaphore: Arc<Semaphore>,
+    max_concurrent: usize,
+    max_wait: Option<Duration>,
+    waiting: AtomicUsize,
+    acquired: AtomicU64,
+    rejected: AtomicU64,
+    total_wait_us: AtomicU64,
+    longest_wait_us: AtomicU64,
+}
+
+impl ConcurrencyLimiter {
+    /// Allow `max_concurrent` calls at a time; at least one.
+    pub fn new(max_concurrent: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            max_wait: None,
+            waiting: AtomicUsize::new(0),
+            acquired: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            total_wait_us: AtomicU64::new(0),
+            longest_wait_us: AtomicU64::new(0),
+        }
+    }
+
+    /// Fail calls that have waited `max_wait` for a slot.
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = Some(max_wait);
+        self
+    }
+
+    /// Slots in use, queued calls and wait times so far.
+    pub fn stats(&self) -> LimiterStats {
+        LimiterStats {
+            max_concurrent: self.max_concurrent,
+            in_flight: self.max_concurrent - self.semaphore.available_permits(),
+            waiting: self.waiting.load(Ordering::Relaxed),
+            acquired: self.acquired.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            total_wait_ms: self.total_wait_us.load(Ordering::Relaxed) / 1_000,
+            longest_wait_ms: self.longest_wait_us.load(Ordering::Relaxed) / 1_000,
+        }
+    }
+
+    /// Wait for a slot for a call to `provider`.
+    async fn acquire(&self, provider: &str) -> Result<OwnedSemaphorePermit, ProviderError> {
+        let started = Instant::now();
+        let queued = self.waiting.fetch_add(1, Ordering::Relaxed) + 1;
+        // Leaves the queue even if the caller is dropped while waiting.
+        let in_queue = Waiting(&self.waiting);
+        let permit = self.semaphore.clone().acquire_owned();
+        let permit = match self.max_wait {
+            Some(max_wait) => tokio::time::timeout(max_wait, permit).await.ok(),
+            None => Some(permit.await),
+        };
+        drop(in_queue);
+
+        let waited = started.elapsed();
+        let Some(permit) = permit else {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(provider, queued, "no provider slot freed up in time");
+            return Err(ProviderError::Overloaded(format!(
+                "{provider} is at its concurrency limit; no slot freed up within {} ms",
+                waited.as_millis()
+            )));
+        };
+        let permit = permit.expect("limiter semaphore is never closed");
+
+        let waited_us = waited.as_micros() as u64;
+        self.acquired.fetch_add(1, Ordering::Relaxed);
+        self.total_wait_us.fetch_add(waited_us, Ordering::Relaxed);
+        self.longest_wait_us.fetch_max(waited_us, Ordering::Relaxed);
+        tracing::debug!(
+            provider,
+            queued,
+            wait_ms = waited.as_millis() as u64,
+            "acquired provider slot"
+        );
+        Ok(permit)
+    }
+}
+
+/// A queued [`ConcurrencyLimiter::acquire`], counted in
+/// [`LimiterStats::waiting`] until dropped.
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// [`AIProvider`] decorator that takes a [`ConcurrencyLimiter`] slot per call.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimitedProvider {
+    inner: Arc<dyn AIProvider>,
+    limiter: Arc<ConcurrencyLimiter>,
+}
+
+impl ConcurrencyLimitedProvider {
+    /// Limit calls to `inner` with `limiter`.
+    pub fn new(inner: Arc<dyn AIProvider>, limiter: Arc<ConcurrencyLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+
+    /// The limiter calls take their slot from.
+    pub fn limiter(&self) -> &Arc<ConcurrencyLimiter> {
+        &self.limiter
+    }
+}
+
+#[async_trait]
+impl AIProvider for ConcurrencyLimitedProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn supports_images(&self) -> bool {
+        self.inner.supports_images()
+    }
+
+    async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+        let _permit = self.limiter.acquire(self.inner.name()).await?;
+        self.inner.generate(req).await
+    }
+
+    async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
+        let permit = self.limiter.acquire(self.inner.name()).await?;
+        let stream = self.inner.generate_stream(req).await?;
+        // The slot is given back once the stream is finished or dropped.
+        Ok(Box::pin(stream.map(move |chunk| {
+            let _permit = &permit;
+            chunk
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockProvider, StreamChunk};
+
+    /// Answers after `delay`.
+    #[derive(Debug)]
+    struct SlowProvider {
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl AIProvider for SlowProvider {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        async fn generate(&self, _req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+            tokio::time::sleep(self.delay).await;
+            Ok(GenerateResponse {
+                content: "done".to_string(),
+                model: None,
+                finish_reason: Some("stop".to_string()),
+                usage: None,
+            })
+        }
+
+        async fn generate_stream(
+            &self,
+            _req: GenerateRequest,
+        ) -> Result<ProviderStream, ProviderError> {
+            Err(ProviderError::Unsupported("streaming".to_string()))
+        }
+    }
+
+    fn request() -> GenerateRequest {
+        GenerateRequest {
+            prompt: "hello".to_string(),
+            model: None,
+            max_tokens: None,
+            temperature: None,
+            metadata: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn calls_queue_for_a_free_slot() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(1));
+        let provider = Arc::new(ConcurrencyLimitedProvider::new(
+            Arc::new(SlowProvider {
+                delay: Duration::from_millis(50),
+            }),
+            limiter.clone(),
+        ));
+
+        let calls = (0..3).map(|_| {
+            let provider = provider.clone();
+            tokio::spawn(async move { provider.generate(request()).await })
+        });
+        let calls = calls.collect::<Vec<_>>();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let stats = limiter.stats();
+        assert_eq!((stats.in_flight, stats.waiting), (1, 2));
+
+        for call in calls {
+            assert_eq!(call.await.unwrap().unwrap().content, "done");
+        }
+        let stats = limiter.stats();
+        assert_eq!((stats.in_flight, stats.waiting), (0, 0));
+        assert_eq!((stats.acquired, stats.rejected), (3, 0));
+        assert!(stats.longest_wait_ms >= 50);
+    }
+
+    #[tokio::test]
+    async fn calls_fail_after_the_maximum_wait() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(1).with_max_wait(Duration::from_millis(10)));
+        let slow = ConcurrencyLimitedProvider::new(
+            Arc::new(SlowProvider {
+                delay: Duration::from_millis(200),
+            }),
+            limiter.clone(),
+        );
+        let mock = MockProvider::new();
+        mock.enqueue_stream(Ok(vec![StreamChunk::Done]));
+        let streaming = ConcurrencyLimitedProvider::new(Arc::new(mock), limiter.clone());
+
+        let (first, second) = tokio::join!(slow.generate(request()), async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            streaming.generate_stream(request()).await
+        });
+        assert!(first.is_ok());
+        assert!(
+            matches!(&second, Err(ProviderError::Overloaded(message)) if message.contains("concurrency limit"))
+        );
+        assert_eq!(
+            second.err().and_then(|err| err.code()),
+            Some(crate::ErrorCode::RateLimited)
+        );
+        assert_eq!(limiter.stats().rejected, 1);
+
+        // A stream holds its slot until it is dropped.
+        let stream = streaming.generate_stream(request()).await.unwrap();
+        assert_eq!(limiter.stats().in_flight, 1);
+        drop(stream);
+        assert_eq!(limiter.stats().in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn callers_dropped_while_queued_leave_the_queue() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(1));
+        let provider = ConcurrencyLimitedProvider::new(
+            Arc::new(SlowProvider {
+                delay: Duration::from_millis(200),
+            }),
+            limiter.clone(),
+        );
+
+        let (first, second) = tokio::join!(
+            provider.generate(request()),
+            tokio::time::timeout(Duration::from_millis(20), provider.generate(request()))
+        );
+        assert!(first.is_ok());
+        assert!(second.is_err());
+        assert_eq!(limiter.stats().waiting, 0);
+    }
+}
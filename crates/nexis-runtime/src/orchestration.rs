@@ -0,0 +1,545 @@
+//! Multi-agent turn-taking.
+//!
+//! A [`TurnOrchestrator`] is shared by every [`crate::AgentRuntime`] in a room
+//! and decides, once per message, which agents respond. Decisions are
+//! memoized by message id so all runtimes observing the same message agree,
+//! and a loop guard stops agents from answering each other indefinitely.
+//! Agents may register with the [`Capabilities`] they declare; messages that
+//! need more than plain chat are only routed to agents that cover them.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use nexis_protocol::{Capabilities, MemberId, MemberType};
+use tokio::sync::Mutex;
+
+use crate::agent_runtime::{is_mentioned, RoomMessage};
+use crate::{AIProvider, GenerateRequest};
+
+/// Picks the single agent that should answer a message.
+#[async_trait]
+pub trait TurnModerator: Send + Sync {
+    /// Choose one of `candidates` to respond to `message`, or `None` to stay quiet.
+    async fn select(&self, message: &RoomMessage, candidates: &[MemberId]) -> Option<MemberId>;
+}
+
+/// How agents in a room take turns.
+#[derive(Clone, Default)]
+pub enum TurnPolicy {
+    /// Agents answer in registration order, one per message.
+    #[default]
+    RoundRobin,
+    /// A moderator picks which agent answers.
+    Moderator(Arc<dyn TurnModerator>),
+    /// Agents answer when mentioned or when the message replies to them.
+    ReplyToMention,
+}
+
+impl fmt::Debug for TurnPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RoundRobin => f.write_str("RoundRobin"),
+            Self::Moderator(_) => f.write_str("Moderator"),
+            Self::ReplyToMention => f.write_str("ReplyToMention"),
+        }
+    }
+}
+
+/// Outcome of a turn decision.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TurnDecision {
+    /// These agents should respond, in order.
+    Respond(Vec<MemberId>),
+    /// No agent should respond.
+    NoResponder,
+    /// Agents have been talking among themselves for too long.
+    LoopDetected,
+}
+
+impl TurnDecision {
+    /// Whether `member` was selected to respond.
+    pub fn includes(&self, member: &MemberId) -> bool {
+        matches!(self, Self::Respond(members) if members.contains(member))
+    }
+}
+
+const DECISION_CACHE_SIZE: usize = 256;
+
+#[derive(Debug, Default)]
+struct OrchestratorState {
+    agents: Vec<MemberId>,
+    capabilities: HashMap<MemberId, Capabilities>,
+    next: usize,
+    consecutive_agent_turns: usize,
+    recent: VecDeque<(String, String)>,
+    decisions: VecDeque<(String, TurnDecision)>,
+}
+
+/// Coordinates which agents respond to each room message.
+pub struct TurnOrchestrator {
+    policy: TurnPolicy,
+    max_agent_turns: usize,
+    state: Mutex<OrchestratorState>,
+}
+
+impl fmt::Debug for TurnOrchestrator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TurnOrchestrator")
+            .field("policy", &self.policy)
+            .field("max_agent_turns", &self.max_agent_turns)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TurnOrchestrator {
+    /// Default number of consecutive agent messages allowed before a human must speak.
+    pub const DEFAULT_MAX_AGENT_TURNS: usize = 4;
+
+    pub fn new(policy: TurnPolicy) -> Self {
+        Self {
+            policy,
+            max_agent_turns: Self::DEFAULT_MAX_AGENT_TURNS,
+            state: Mutex::new(OrchestratorState::default()),
+        }
+    }
+
+    /// Cap consecutive agent-authored messages; `0` disables agent-to-agent replies.
+    pub fn with_max_agent_turns(mut self, max_agent_turns: usize) -> Self {
+        self.max_agent_turns = max_agent_turns;
+        self
+    }
+
+    /// Active turn policy.
+    pub fn policy(&self) -> &TurnPolicy {
+        &self.policy
+    }
+
+    /// Add an agent to the rotation.
+    pub async fn register(&self, agent: MemberId) {
+        self.register_with_capabilities(agent, Capabilities::default())
+            .await;
+    }
+
+    /// Add an agent to the rotation, declaring what it can handle. Registering
+    /// again replaces the declared capabilities.
+    pub async fn register_with_capabilities(&self, agent: MemberId, capabilities: Capabilities) {
+        let mut state = self.state.lock().await;
+        if !state.agents.contains(&agent) {
+            state.agents.push(agent.clone());
+        }
+        state.capabilities.insert(agent, capabilities);
+    }
+
+    /// Remove an agent from the rotation.
+    pub async fn unregister(&self, agent: &MemberId) {
+        let mut state = self.state.lock().await;
+        state.agents.retain(|registered| registered != agent);
+        state.capabilities.remove(agent);
+        if state.next >= state.agents.len() {
+            state.next = 0;
+        }
+    }
+
+    /// Registered agents in rotation order.
+    pub async fn agents(&self) -> Vec<MemberId> {
+        self.state.lock().await.agents.clone()
+    }
+
+    /// Registered agents whose capabilities satisfy `required`, in rotation
+    /// order.
+    pub async fn capable_agents(&self, required: &Capabilities) -> Vec<MemberId> {
+        let state = self.state.lock().await;
+        state
+            .agents
+            .iter()
+            .filter(|agent| state.is_capable(agent, required))
+            .cloned()
+            .collect()
+    }
+
+    /// Decide who responds to `message`.
+    ///
+    /// The first call for a message id records it and computes the decision;
+    /// later calls for the same id return the same decision.
+    pub async fn decide(&self, message: &RoomMessage) -> TurnDecision {
+        self.decide_requiring(message, &Capabilities::default())
+            .await
+    }
+
+    /// Decide who responds to `message`, considering only agents whose
+    /// capabilities satisfy `required`.
+    ///
+    /// Decisions are memoized by message id like [`Self::decide`], so every
+    /// caller must derive `required` from the message alone.
+    pub async fn decide_requiring(
+        &self,
+        message: &RoomMessage,
+        required: &Capabilities,
+    ) -> TurnDecision {
+        let mut state = self.state.lock().await;
+        if let Some((_, decision)) = state.decisions.iter().find(|(id, _)| *id == message.id) {
+            return decision.clone();
+        }
+
+        if is_agent_sender(&message.sender) {
+            state.consecutive_agent_turns += 1;
+        } else {
+            state.consecutive_agent_turns = 0;
+        }
+
+        let decision = if state.consecutive_agent_turns > self.max_agent_turns {
+            TurnDecision::LoopDetected
+        } else {
+            let candidates: Vec<MemberId> = state
+                .agents
+                .iter()
+                .filter(|agent| agent.to_string() != message.sender)
+                .filter(|agent| state.is_capable(agent, required))
+                .cloned()
+                .collect();
+            let responders = if candidates.is_empty() {
+                Vec::new()
+            } else {
+                match &self.policy {
+                    TurnPolicy::RoundRobin => round_robin(&mut state, &candidates),
+                    TurnPolicy::Moderator(moderator) => moderator
+                        .select(message, &candidates)
+                        .await
+                        .filter(|selected| candidates.contains(selected))
+                        .into_iter()
+                        .collect(),
+                    TurnPolicy::ReplyToMention => reply_to_mention(&state, message, &candidates),
+                }
+            };
+            if responders.is_empty() {
+                TurnDecision::NoResponder
+            } else {
+                TurnDecision::Respond(responders)
+            }
+        };
+
+        if matches!(decision, TurnDecision::LoopDetected) {
+            tracing::warn!(
+                message_id = %message.id,
+                turns = state.consecutive_agent_turns,
+                "agent loop detected; waiting for a non-agent message"
+            );
+        }
+
+        state
+            .recent
+            .push_back((message.id.clone(), message.sender.clone()));
+        state
+            .decisions
+            .push_back((message.id.clone(), decision.clone()));
+        while state.recent.len() > DECISION_CACHE_SIZE {
+            state.recent.pop_front();
+        }
+        while state.decisions.len() > DECISION_CACHE_SIZE {
+            state.decisions.pop_front();
+        }
+        decision
+    }
+}
+
+impl OrchestratorState {
+    fn is_capable(&self, agent: &MemberId, required: &Capabilities) -> bool {
+        self.capabilities
+            .get(agent)
+            .is_some_and(|declared| declared.satisfies(required))
+    }
+}
+
+fn is_agent_sender(sender: &str) -> bool {
+    sender
+        .parse::<MemberId>()
+        .map(|member| matches!(member.member_type(), MemberType::Agent | MemberType::Ai))
+        .unwrap_or(false)
+}
+
+fn round_robin(state: &mut OrchestratorState, candidates: &[MemberId]) -> Vec<MemberId> {
+    let count = state.agents.len();
+    for offset in 0..count {
+        let index = (state.next + offset) % count;
+        if candidates.contains(&state.agents[index]) {
+            state.next = (index + 1) % count;
+            return vec![state.agents[index].clone()];
+        }
+    }
+    Vec::new()
+}
+
+fn reply_to_mention(
+    state: &OrchestratorState,
+    message: &RoomMessage,
+    candidates: &[MemberId],
+) -> Vec<MemberId> {
+    let replied_to = message.reply_to.as_deref().and_then(|reply_to| {
+        state
+            .recent
+            .iter()
+            .find(|(id, _)| id == reply_to)
+            .map(|(_, sender)| sender.as_str())
+    });
+
+    candidates
+        .iter()
+        .filter(|agent| {
+            is_mentioned(agent, &message.text) || replied_to == Some(agent.to_string().as_str())
+        })
+        .cloned()
+        .collect()
+}
+
+/// [`TurnModerator`] that asks an AI provider to pick the next speaker.
+#[derive(Debug, Clone)]
+pub struct ProviderModerator {
+    provider: Arc<dyn AIProvider>,
+    model: Option<String>,
+}
+
+impl ProviderModerator {
+    pub fn new(provider: Arc<dyn AIProvider>) -> Self {
+        Self {
+            provider,
+            model: None,
+        }
+    }
+
+    /// Request a specific model for moderation.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+}
+
+#[async_trait]
+impl TurnModerator for ProviderModerator {
+    async fn select(&self, message: &RoomMessage, candidates: &[MemberId]) -> Option<MemberId> {
+        let mut prompt = String::from(
+            "You moderate a chat room with several AI agents. \
+             Pick the one agent best suited to answer the message below. \
+             Reply with the agent id only, or `none` if no agent should answer.\n\nAgents:\n",
+        );
+        for candidate in candidates {
+            prompt.push_str(&format!("- {candidate}\n"));
+        }
+        prompt.push_str(&format!("\nMessage:\n{}: {}", message.sender, message.text));
+
+        let request = GenerateRequest {
+            prompt,
+            model: self.model.clone(),
+            max_tokens: Some(32),
+            temperature: Some(0.0),
+            metadata: Some(serde_json::json!({ "roomId": message.room_id.clone() })),
+            images: Vec::new(),
+        };
+        let response = match self.provider.generate(request).await {
+            Ok(response) => response,
+            Err(err) => {
+                tracing::warn!("turn moderator failed: {}", err);
+                return None;
+            }
+        };
+
+        let answer = response.content.trim();
+        candidates
+            .iter()
+            .find(|candidate| answer.contains(&candidate.to_string()))
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .find(|candidate| answer.eq_ignore_ascii_case(candidate.identifier()))
+            })
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GenerateResponse, MockProvider};
+
+    fn member(id: &str) -> MemberId {
+        id.parse().unwrap()
+    }
+
+    fn message(id: &str, sender: &str, text: &str) -> RoomMessage {
+        RoomMessage {
+            id: id.to_string(),
+            room_id: "room_1".to_string(),
+            sender: sender.to_string(),
+            text: text.to_string(),
+            reply_to: None,
+        }
+    }
+
+    async fn orchestrator(policy: TurnPolicy) -> TurnOrchestrator {
+        let orchestrator = TurnOrchestrator::new(policy);
+        orchestrator.register(member("nexis:agent:alpha")).await;
+        orchestrator.register(member("nexis:agent:beta")).await;
+        orchestrator
+    }
+
+    #[tokio::test]
+    async fn round_robin_rotates_and_memoizes_by_message_id() {
+        let orchestrator = orchestrator(TurnPolicy::RoundRobin).await;
+        let first = message("m1", "nexis:human:alice", "hi");
+
+        let decision = orchestrator.decide(&first).await;
+        assert_eq!(
+            decision,
+            TurnDecision::Respond(vec![member("nexis:agent:alpha")])
+        );
+        assert_eq!(orchestrator.decide(&first).await, decision);
+
+        let second = orchestrator
+            .decide(&message("m2", "nexis:human:alice", "again"))
+            .await;
+        assert!(second.includes(&member("nexis:agent:beta")));
+    }
+
+    #[tokio::test]
+    async fn round_robin_skips_the_sender() {
+        let orchestrator = orchestrator(TurnPolicy::RoundRobin).await;
+        let decision = orchestrator
+            .decide(&message("m1", "nexis:agent:alpha", "my turn"))
+            .await;
+        assert_eq!(
+            decision,
+            TurnDecision::Respond(vec![member("nexis:agent:beta")])
+        );
+    }
+
+    #[tokio::test]
+    async fn reply_to_mention_selects_mentioned_and_replied_agents() {
+        let orchestrator = orchestrator(TurnPolicy::ReplyToMention).await;
+
+        assert_eq!(
+            orchestrator
+                .decide(&message("m1", "nexis:human:alice", "no mentions"))
+                .await,
+            TurnDecision::NoResponder
+        );
+        assert_eq!(
+            orchestrator
+                .decide(&message("m2", "nexis:human:alice", "@beta thoughts?"))
+                .await,
+            TurnDecision::Respond(vec![member("nexis:agent:beta")])
+        );
+
+        orchestrator
+            .decide(&message("m3", "nexis:agent:alpha", "here is a plan"))
+            .await;
+        let mut reply = message("m4", "nexis:human:alice", "why?");
+        reply.reply_to = Some("m3".to_string());
+        assert_eq!(
+            orchestrator.decide(&reply).await,
+            TurnDecision::Respond(vec![member("nexis:agent:alpha")])
+        );
+    }
+
+    #[tokio::test]
+    async fn loop_guard_stops_agent_ping_pong_until_a_human_speaks() {
+        let orchestrator = orchestrator(TurnPolicy::RoundRobin)
+            .await
+            .with_max_agent_turns(2);
+
+        orchestrator
+            .decide(&message("m1", "nexis:human:alice", "start"))
+            .await;
+        assert!(matches!(
+            orchestrator
+                .decide(&message("m2", "nexis:agent:alpha", "ping"))
+                .await,
+            TurnDecision::Respond(_)
+        ));
+        assert!(matches!(
+            orchestrator
+                .decide(&message("m3", "nexis:agent:beta", "pong"))
+                .await,
+            TurnDecision::Respond(_)
+        ));
+        assert_eq!(
+            orchestrator
+                .decide(&message("m4", "nexis:agent:alpha", "ping"))
+                .await,
+            TurnDecision::LoopDetected
+        );
+
+        assert!(matches!(
+            orchestrator
+                .decide(&message("m5", "nexis:human:alice", "carry on"))
+                .await,
+            TurnDecision::Respond(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn routes_only_to_agents_with_matching_capabilities() {
+        let orchestrator = TurnOrchestrator::new(TurnPolicy::RoundRobin);
+        orchestrator.register(member("nexis:agent:chatter")).await;
+        orchestrator
+            .register_with_capabilities(
+                member("nexis:agent:reviewer"),
+                Capabilities::new()
+                    .with_content_type("code")
+                    .with_tool("run_tests"),
+            )
+            .await;
+        let review = Capabilities::new().with_content_type("code");
+
+        assert_eq!(
+            orchestrator.capable_agents(&review).await,
+            vec![member("nexis:agent:reviewer")]
+        );
+        for id in ["m1", "m2"] {
+            assert_eq!(
+                orchestrator
+                    .decide_requiring(&message(id, "nexis:human:alice", "review"), &review)
+                    .await,
+                TurnDecision::Respond(vec![member("nexis:agent:reviewer")])
+            );
+        }
+        assert_eq!(
+            orchestrator
+                .decide_requiring(
+                    &message("m3", "nexis:human:alice", "deploy"),
+                    &Capabilities::new().with_tool("deploy"),
+                )
+                .await,
+            TurnDecision::NoResponder
+        );
+
+        orchestrator
+            .unregister(&member("nexis:agent:reviewer"))
+            .await;
+        assert!(orchestrator.capable_agents(&review).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn provider_moderator_picks_named_candidate() {
+        let provider = Arc::new(MockProvider::new());
+        provider.enqueue_generate(Ok(GenerateResponse {
+            content: "nexis:agent:beta".to_string(),
+            model: None,
+            finish_reason: None,
+            usage: None,
+        }));
+        let orchestrator = orchestrator(TurnPolicy::Moderator(Arc::new(ProviderModerator::new(
+            provider,
+        ))))
+        .await;
+
+        let decision = orchestrator
+            .decide(&message("m1", "nexis:human:alice", "review this"))
+            .await;
+
+        assert_eq!(
+            decision,
+            TurnDecision::Respond(vec![member("nexis:agent:beta")])
+        );
+    }
+}
@@ -1,7 +1,8 @@
 //! Anthropic Claude API Provider
 //!
 //! Implements the AIProvider trait for Anthropic's Messages API
-//! with support for streaming responses.
+//! with support for streaming responses. Image inputs are sent as `image`
+//! content blocks ahead of the prompt text.
 
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -10,8 +11,10 @@ use serde::{Deserialize, Serialize};
 use std::env;
 use std::time::Duration;
 
+use crate::telemetry::TraceContextExt;
 use crate::{
-    AIProvider, GenerateRequest, GenerateResponse, ProviderError, ProviderStream, StreamChunk,
+    AIProvider, GenerateRequest, GenerateResponse, ImageInput, ProviderError, ProviderStream,
+    StreamChunk, TokenUsage,
 };
 
 const ANTHROPIC_API_BASE: &str = "https://api.anthropic.com/v1";
@@ -90,7 +93,56 @@ struct MessagesRequest {
 #[derive(Debug, Serialize, Clone)]
 struct AnthropicMessage {
     role: String,
-    content: String,
+    content: AnthropicContent,
+}
+
+/// A bare string, or content blocks once images are attached
+#[derive(Debug, Serialize, Clone)]
+#[serde(untagged)]
+enum AnthropicContent {
+    Text(String),
+    Blocks(Vec<RequestBlock>),
+}
+
+/// Anthropic request content block
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RequestBlock {
+    Text { text: String },
+    Image { source: ImageSource },
+}
+
+/// Anthropic image source
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ImageSource {
+    Url { url: String },
+    Base64 { media_type: String, data: String },
+}
+
+/// Images go first; Anthropic recommends placing them before the question.
+fn user_message(prompt: String, images: &[ImageInput]) -> AnthropicMessage {
+    let content = if images.is_empty() {
+        AnthropicContent::Text(prompt)
+    } else {
+        let mut blocks = images
+            .iter()
+            .map(|image| RequestBlock::Image {
+                source: match image.clone() {
+                    ImageInput::Url { url } => ImageSource::Url { url },
+                    ImageInput::Base64 { media_type, data } => {
+                        ImageSource::Base64 { media_type, data }
+                    }
+                },
+            })
+            .collect::<Vec<_>>();
+        blocks.push(RequestBlock::Text { text: prompt });
+        AnthropicContent::Blocks(blocks)
+    };
+    AnthropicMessage {
+        role: "user".to_string(),
+        content,
+    }
 }
 
 /// Anthropic Messages Response
@@ -106,7 +158,6 @@ struct MessagesResponse {
     content: Vec<ContentBlock>,
     model: String,
     stop_reason: Option<String>,
-    #[allow(dead_code)]
     usage: Usage,
 }
 
@@ -122,9 +173,7 @@ struct ContentBlock {
 /// Anthropic Usage
 #[derive(Debug, Deserialize)]
 struct Usage {
-    #[allow(dead_code)]
     input_tokens: u32,
-    #[allow(dead_code)]
     output_tokens: u32,
 }
 
@@ -174,13 +223,15 @@ impl AIProvider for AnthropicProvider {
         "anthropic"
     }
 
+    fn supports_images(&self) -> bool {
+        true
+    }
+
+    #[tracing::instrument(name = "provider.anthropic.generate", skip_all, fields(otel.kind = "client"))]
     async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
         let anthropic_req = MessagesRequest {
             model: self.get_model(&req),
-            messages: vec![AnthropicMessage {
-                role: "user".to_string(),
-                content: req.prompt,
-            }],
+            messages: vec![user_message(req.prompt, &req.images)],
             max_tokens: req.max_tokens.unwrap_or(1024),
             stream: None,
         };
@@ -188,6 +239,7 @@ impl AIProvider for AnthropicProvider {
         let response = self
             .client
             .post(self.endpoint("/messages"))
+            .with_trace_context()
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", API_VERSION)
             .header("content-type", "application/json")
@@ -231,18 +283,20 @@ impl AIProvider for AnthropicProvider {
             content,
             model: Some(anthropic_resp.model),
             finish_reason: anthropic_resp.stop_reason,
+            usage: Some(TokenUsage {
+                input_tokens: anthropic_resp.usage.input_tokens,
+                output_tokens: anthropic_resp.usage.output_tokens,
+            }),
         })
     }
 
+    #[tracing::instrument(name = "provider.anthropic.generate_stream", skip_all, fields(otel.kind = "client"))]
     async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
         use reqwest_eventsource::{Event, EventSource};
 
         let anthropic_req = MessagesRequest {
             model: self.get_model(&req),
-            messages: vec![AnthropicMessage {
-                role: "user".to_string(),
-                content: req.prompt,
-            }],
+            messages: vec![user_message(req.prompt, &req.images)],
             max_tokens: req.max_tokens.unwrap_or(1024),
             stream: Some(true),
         };
@@ -254,6 +308,7 @@ impl AIProvider for AnthropicProvider {
         let event_source = EventSource::new(
             client
                 .post(&endpoint)
+                .with_trace_context()
                 .header("x-api-key", &api_key)
                 .header("anthropic-version", API_VERSION)
                 .header("content-type", "application/json")
@@ -297,6 +352,44 @@ mod tests {
         assert_eq!(provider.default_model, "claude-3-opus");
     }
 
+    #[test]
+    fn images_become_image_blocks_before_the_prompt() {
+        let message = user_message(
+            "Describe both.".to_string(),
+            &[
+                ImageInput::Base64 {
+                    media_type: "image/png".to_string(),
+                    data: "iVBORw0K".to_string(),
+                },
+                ImageInput::Url {
+                    url: "https://example.com/dog.jpg".to_string(),
+                },
+            ],
+        );
+
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            json!({
+                "role": "user",
+                "content": [
+                    {
+                        "type": "image",
+                        "source": { "type": "base64", "media_type": "image/png", "data": "iVBORw0K" }
+                    },
+                    {
+                        "type": "image",
+                        "source": { "type": "url", "url": "https://example.com/dog.jpg" }
+                    },
+                    { "type": "text", "text": "Describe both." }
+                ]
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(user_message("Hi".to_string(), &[])).unwrap(),
+            json!({ "role": "user", "content": "Hi" })
+        );
+    }
+
     #[test]
     fn provider_creation_from_env() {
         if env::var("ANTHROPIC_API_KEY").is_ok() {
@@ -345,6 +438,7 @@ mod tests {
             max_tokens: Some(100),
             temperature: None,
             metadata: None,
+            images: Vec::new(),
         };
 
         let resp = provider.generate(req).await.unwrap();
@@ -352,6 +446,13 @@ mod tests {
         mock.assert();
         assert_eq!(resp.content, "Hello! I'm Claude.");
         assert_eq!(resp.model, Some("claude-3-5-sonnet-20241022".to_string()));
+        assert_eq!(
+            resp.usage,
+            Some(TokenUsage {
+                input_tokens: 10,
+                output_tokens: 20
+            })
+        );
     }
 
     #[tokio::test]
@@ -382,6 +483,7 @@ mod tests {
             max_tokens: None,
             temperature: None,
             metadata: None,
+            images: Vec::new(),
         };
 
         let err = provider.generate(req).await.unwrap_err();
@@ -425,6 +527,7 @@ mod tests {
             max_tokens: None,
             temperature: None,
             metadata: None,
+            images: Vec::new(),
         };
 
         let mut stream = provider.generate_stream(req).await.unwrap();
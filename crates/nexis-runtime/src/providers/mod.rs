@@ -1,10 +1,13 @@
 //! AI Provider implementations
 //!
 //! This module contains concrete implementations of the AIProvider trait
-//! for various AI services (OpenAI, Anthropic, Gemini, etc.)
+//! for various AI services (OpenAI, Anthropic, OpenAI-compatible local
+//! servers such as Ollama and vLLM, etc.)
 
 pub mod anthropic;
 pub mod openai;
+pub mod openai_compatible;
 
 pub use anthropic::AnthropicProvider;
 pub use openai::OpenAIProvider;
+pub use openai_compatible::OpenAICompatibleProvider;
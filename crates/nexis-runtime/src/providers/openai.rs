@@ -1,7 +1,8 @@
 //! OpenAI API Provider
 //!
 //! Implements the AIProvider trait for OpenAI's Chat Completions API
-//! with support for streaming responses.
+//! with support for streaming responses. Image inputs are sent as vision
+//! `image_url` content parts.
 
 use async_trait::async_trait;
 use reqwest::Client;
@@ -9,8 +10,10 @@ use serde::{Deserialize, Serialize};
 use std::env;
 use std::time::Duration;
 
+use crate::telemetry::TraceContextExt;
 use crate::{
-    AIProvider, GenerateRequest, GenerateResponse, ProviderError, ProviderStream, StreamChunk,
+    AIProvider, GenerateRequest, GenerateResponse, ImageInput, ProviderError, ProviderStream,
+    StreamChunk, TokenUsage,
 };
 use futures::StreamExt;
 
@@ -73,13 +76,15 @@ impl AIProvider for OpenAIProvider {
         "openai"
     }
 
+    fn supports_images(&self) -> bool {
+        true
+    }
+
+    #[tracing::instrument(name = "provider.openai.generate", skip_all, fields(otel.kind = "client"))]
     async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
         let openai_req = ChatCompletionRequest {
             model: self.get_model(&req),
-            messages: vec![Message {
-                role: "user".to_string(),
-                content: req.prompt,
-            }],
+            messages: vec![user_message(req.prompt, &req.images)],
             max_tokens: req.max_tokens,
             temperature: req.temperature,
             stream: None,
@@ -88,6 +93,7 @@ impl AIProvider for OpenAIProvider {
         let response = self
             .client
             .post(self.endpoint("/chat/completions"))
+            .with_trace_context()
             .bearer_auth(&self.api_key)
             .json(&openai_req)
             .send()
@@ -125,19 +131,21 @@ impl AIProvider for OpenAIProvider {
                 .choices
                 .first()
                 .and_then(|c| c.finish_reason.clone()),
+            usage: openai_resp.usage.as_ref().map(|usage| TokenUsage {
+                input_tokens: usage.prompt_tokens,
+                output_tokens: usage.completion_tokens,
+            }),
         })
     }
 
+    #[tracing::instrument(name = "provider.openai.generate_stream", skip_all, fields(otel.kind = "client"))]
     async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
         use futures::stream;
         use reqwest_eventsource::{Event, EventSource};
 
         let openai_req = ChatCompletionRequest {
             model: self.get_model(&req),
-            messages: vec![Message {
-                role: "user".to_string(),
-                content: req.prompt,
-            }],
+            messages: vec![user_message(req.prompt, &req.images)],
             max_tokens: req.max_tokens,
             temperature: req.temperature,
             stream: Some(true),
@@ -151,6 +159,7 @@ impl AIProvider for OpenAIProvider {
         let event_source = EventSource::new(
             client
                 .post(&endpoint)
+                .with_trace_context()
                 .bearer_auth(&api_key)
                 .json(&openai_req),
         )
@@ -201,16 +210,60 @@ impl AIProvider for OpenAIProvider {
 // OpenAI API Types
 // ============================================================================
 
+/// Plain text for text-only prompts, content parts once images are attached.
+pub(super) fn user_message(prompt: String, images: &[ImageInput]) -> RequestMessage {
+    let content = if images.is_empty() {
+        RequestContent::Text(prompt)
+    } else {
+        let mut parts = vec![ContentPart::Text { text: prompt }];
+        parts.extend(images.iter().map(|image| ContentPart::ImageUrl {
+            image_url: ImageUrl {
+                url: image.to_url(),
+            },
+        }));
+        RequestContent::Parts(parts)
+    };
+    RequestMessage {
+        role: "user".to_string(),
+        content,
+    }
+}
+
 #[derive(Debug, Serialize)]
-struct ChatCompletionRequest {
-    model: String,
-    messages: Vec<Message>,
+pub(super) struct ChatCompletionRequest {
+    pub(super) model: String,
+    pub(super) messages: Vec<RequestMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    max_tokens: Option<u32>,
+    pub(super) max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    temperature: Option<f32>,
+    pub(super) temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    stream: Option<bool>,
+    pub(super) stream: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub(super) struct RequestMessage {
+    role: String,
+    content: RequestContent,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(untagged)]
+enum RequestContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ImageUrl {
+    url: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -316,6 +369,7 @@ mod tests {
             max_tokens: None,
             temperature: None,
             metadata: None,
+            images: Vec::new(),
         };
 
         assert_eq!(provider.get_model(&req), "gpt-4-turbo");
@@ -331,6 +385,7 @@ mod tests {
             max_tokens: None,
             temperature: None,
             metadata: None,
+            images: Vec::new(),
         };
 
         assert_eq!(provider.get_model(&req), "gpt-3.5-turbo");
@@ -348,10 +403,7 @@ mod tests {
     fn chat_completion_request_serialization() {
         let req = ChatCompletionRequest {
             model: "gpt-4".to_string(),
-            messages: vec![Message {
-                role: "user".to_string(),
-                content: "Hello".to_string(),
-            }],
+            messages: vec![user_message("Hello".to_string(), &[])],
             max_tokens: Some(100),
             temperature: Some(0.7),
             stream: None,
@@ -362,6 +414,35 @@ mod tests {
         assert!(json.contains("\"max_tokens\":100"));
         assert!(json.contains("\"temperature\":0.7"));
         assert!(!json.contains("\"stream\""));
+        assert!(json.contains("\"content\":\"Hello\""));
+    }
+
+    #[test]
+    fn images_become_vision_content_parts() {
+        let message = user_message(
+            "What is this?".to_string(),
+            &[
+                ImageInput::Url {
+                    url: "https://example.com/cat.png".to_string(),
+                },
+                ImageInput::Base64 {
+                    media_type: "image/jpeg".to_string(),
+                    data: "AAAA".to_string(),
+                },
+            ],
+        );
+
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            json!({
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": "What is this?" },
+                    { "type": "image_url", "image_url": { "url": "https://example.com/cat.png" } },
+                    { "type": "image_url", "image_url": { "url": "data:image/jpeg;base64,AAAA" } }
+                ]
+            })
+        );
     }
 
     #[test]
@@ -437,6 +518,7 @@ mod tests {
             max_tokens: Some(100),
             temperature: Some(0.7),
             metadata: None,
+            images: Vec::new(),
         };
 
         let resp = provider.generate(req).await.unwrap();
@@ -445,6 +527,13 @@ mod tests {
         assert_eq!(resp.content, "Hello! How can I help you?");
         assert_eq!(resp.model, Some("gpt-4".to_string()));
         assert_eq!(resp.finish_reason, Some("stop".to_string()));
+        assert_eq!(
+            resp.usage,
+            Some(TokenUsage {
+                input_tokens: 10,
+                output_tokens: 20
+            })
+        );
     }
 
     #[tokio::test]
@@ -474,6 +563,7 @@ mod tests {
             max_tokens: None,
             temperature: None,
             metadata: None,
+            images: Vec::new(),
         };
 
         let err = provider.generate(req).await.unwrap_err();
@@ -517,6 +607,7 @@ mod tests {
             max_tokens: None,
             temperature: None,
             metadata: None,
+            images: Vec::new(),
         };
 
         let mut stream = provider.generate_stream(req).await.unwrap();
@@ -0,0 +1,444 @@
+//! OpenAI-compatible local model servers
+//!
+//! Implements the AIProvider trait for servers that speak OpenAI's Chat
+//! Completions API but are not OpenAI, such as Ollama and vLLM. Compared
+//! with [`OpenAIProvider`](super::OpenAIProvider):
+//!
+//! - the API key is optional; no `Authorization` header is sent without one
+//! - the served models are listed with [`OpenAICompatibleProvider::list_models`],
+//!   and without a default model the first one listed is used
+//! - responses may omit `id`, `model`, `usage` or the message content, and
+//!   finish reasons like `eos` or `max_tokens` are normalised to OpenAI's
+//!   `stop` and `length`
+//! - nothing beyond the basic request fields (no `logprobs`, no
+//!   `stream_options`) is sent, since servers reject what they don't know
+
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use std::env;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+use super::openai::{user_message, ChatCompletionRequest};
+use crate::telemetry::TraceContextExt;
+use crate::{
+    AIProvider, GenerateRequest, GenerateResponse, ProviderError, ProviderStream, StreamChunk,
+    TokenUsage,
+};
+use futures::StreamExt;
+
+const OLLAMA_API_BASE: &str = "http://localhost:11434/v1";
+const OLLAMA_DEFAULT_MODEL: &str = "llama3.1";
+const VLLM_API_BASE: &str = "http://localhost:8000/v1";
+
+#[derive(Debug)]
+pub struct OpenAICompatibleProvider {
+    name: &'static str,
+    client: Client,
+    api_key: Option<String>,
+    base_url: String,
+    default_model: Option<String>,
+    discovered_model: OnceCell<String>,
+}
+
+impl OpenAICompatibleProvider {
+    /// Provider `name` talking to `base_url`. An empty `default_model`
+    /// means the first model the server lists.
+    pub fn new(
+        name: &'static str,
+        base_url: impl Into<String>,
+        default_model: impl Into<String>,
+    ) -> Self {
+        let client = Client::builder()
+            // Local models load on first use and can be slow to answer.
+            .timeout(Duration::from_secs(300))
+            .build()
+            .expect("Failed to create HTTP client");
+        let default_model = default_model.into();
+
+        Self {
+            name,
+            client,
+            api_key: None,
+            base_url: base_url.into(),
+            default_model: (!default_model.trim().is_empty()).then_some(default_model),
+            discovered_model: OnceCell::new(),
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key.filter(|key| !key.trim().is_empty());
+        self
+    }
+
+    /// Ollama, from `OLLAMA_API_BASE` and `OLLAMA_DEFAULT_MODEL`.
+    pub fn ollama_from_env() -> Self {
+        let base_url = env::var("OLLAMA_API_BASE").unwrap_or_else(|_| OLLAMA_API_BASE.to_string());
+        let default_model =
+            env::var("OLLAMA_DEFAULT_MODEL").unwrap_or_else(|_| OLLAMA_DEFAULT_MODEL.to_string());
+        Self::new("ollama", base_url, default_model).with_api_key(env::var("OLLAMA_API_KEY").ok())
+    }
+
+    /// vLLM, from `VLLM_API_BASE`, `VLLM_API_KEY` and `VLLM_DEFAULT_MODEL`.
+    pub fn vllm_from_env() -> Self {
+        let base_url = env::var("VLLM_API_BASE").unwrap_or_else(|_| VLLM_API_BASE.to_string());
+        let default_model = env::var("VLLM_DEFAULT_MODEL").unwrap_or_default();
+        Self::new("vllm", base_url, default_model).with_api_key(env::var("VLLM_API_KEY").ok())
+    }
+
+    pub fn endpoint(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    /// Ids of the models the server can serve, from `GET /models`.
+    pub async fn list_models(&self) -> Result<Vec<String>, ProviderError> {
+        let response = self
+            .authorize(self.client.get(self.endpoint("/models")))
+            .with_trace_context()
+            .send()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+        let response = check_status(response).await?;
+        let models: ModelList = response
+            .json()
+            .await
+            .map_err(|e| ProviderError::Decode(e.to_string()))?;
+        Ok(models.data.into_iter().map(|model| model.id).collect())
+    }
+
+    /// The model for `req`: the requested one, the default, or the first
+    /// model the server lists.
+    pub async fn get_model(&self, req: &GenerateRequest) -> Result<String, ProviderError> {
+        if let Some(model) = req.model.clone().or_else(|| self.default_model.clone()) {
+            return Ok(model);
+        }
+        self.discovered_model
+            .get_or_try_init(|| async {
+                self.list_models().await?.into_iter().next().ok_or_else(|| {
+                    ProviderError::Message(format!("{} serves no models", self.name))
+                })
+            })
+            .await
+            .cloned()
+    }
+
+    async fn chat_request(
+        &self,
+        req: GenerateRequest,
+        stream: bool,
+    ) -> Result<RequestBuilder, ProviderError> {
+        req.ensure_text_only(self.name)?;
+        let body = ChatCompletionRequest {
+            model: self.get_model(&req).await?,
+            messages: vec![user_message(req.prompt, &[])],
+            max_tokens: req.max_tokens,
+            temperature: req.temperature,
+            stream: stream.then_some(true),
+        };
+        Ok(self
+            .authorize(self.client.post(self.endpoint("/chat/completions")))
+            .with_trace_context()
+            .json(&body))
+    }
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, ProviderError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "<unable to read body>".to_string());
+    Err(ProviderError::HttpStatus {
+        status: status.as_u16(),
+        body,
+    })
+}
+
+/// Map the finish reasons local servers report onto OpenAI's.
+fn normalize_finish_reason(reason: String) -> String {
+    match reason.as_str() {
+        "eos" | "eos_token" | "end_turn" | "stop_sequence" => "stop".to_string(),
+        "max_tokens" | "model_length" => "length".to_string(),
+        _ => reason,
+    }
+}
+
+#[async_trait]
+impl AIProvider for OpenAICompatibleProvider {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    #[tracing::instrument(name = "provider.openai_compatible.generate", skip_all, fields(otel.kind = "client", provider = self.name))]
+    async fn generate(&self, req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+        let response = self
+            .chat_request(req, false)
+            .await?
+            .send()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+        let response = check_status(response).await?;
+        let completion: ChatCompletionResponse = response
+            .json()
+            .await
+            .map_err(|e| ProviderError::Decode(e.to_string()))?;
+        completion.into_response()
+    }
+
+    #[tracing::instrument(name = "provider.openai_compatible.generate_stream", skip_all, fields(otel.kind = "client", provider = self.name))]
+    async fn generate_stream(&self, req: GenerateRequest) -> Result<ProviderStream, ProviderError> {
+        use futures::stream;
+        use reqwest_eventsource::{Event, EventSource};
+
+        let event_source = EventSource::new(self.chat_request(req, true).await?)
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+
+        let stream = event_source
+            .take_while(|event| {
+                futures::future::ready(
+                    !matches!(event, Ok(Event::Message(ref msg)) if msg.data == "[DONE]"),
+                )
+            })
+            .filter_map(|event| async move {
+                match event {
+                    Ok(Event::Message(msg)) => {
+                        match serde_json::from_str::<ChatCompletionChunk>(&msg.data) {
+                            Ok(chunk) => chunk.text().map(|text| Ok(StreamChunk::Delta { text })),
+                            Err(e) => Some(Err(ProviderError::Decode(e.to_string()))),
+                        }
+                    }
+                    Ok(Event::Open) => None,
+                    // Some servers close the connection instead of sending
+                    // `[DONE]`.
+                    Err(reqwest_eventsource::Error::StreamEnded) => None,
+                    Err(e) => Some(Err(ProviderError::Transport(e.to_string()))),
+                }
+            })
+            .chain(stream::iter(vec![Ok(StreamChunk::Done)]));
+
+        Ok(Box::pin(stream))
+    }
+}
+
+// ============================================================================
+// Response Types
+// ============================================================================
+//
+// Every field a server might leave out is optional.
+
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    #[serde(default)]
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+impl ChatCompletionResponse {
+    fn into_response(self) -> Result<GenerateResponse, ProviderError> {
+        let choice = self
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| ProviderError::Decode("No choices in response".to_string()))?;
+        Ok(GenerateResponse {
+            content: choice.message.content.unwrap_or_default(),
+            model: self.model,
+            finish_reason: choice.finish_reason.map(normalize_finish_reason),
+            usage: self.usage.map(|usage| TokenUsage {
+                input_tokens: usage.prompt_tokens,
+                output_tokens: usage.completion_tokens,
+            }),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: Message,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Usage {
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+}
+
+impl ChatCompletionChunk {
+    /// The chunk's text, if it carries any; usage-only chunks don't.
+    fn text(self) -> Option<String> {
+        self.choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.delta.content)
+            .filter(|text| !text.is_empty())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: Delta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    fn network_tests_enabled() -> bool {
+        matches!(std::env::var("NEXIS_RUN_NETWORK_TESTS"), Ok(value) if value == "1")
+    }
+
+    fn request(model: Option<&str>) -> GenerateRequest {
+        GenerateRequest {
+            prompt: "Hello".to_string(),
+            model: model.map(str::to_string),
+            max_tokens: None,
+            temperature: None,
+            metadata: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn sparse_responses_decode_with_normalised_finish_reasons() {
+        let resp: ChatCompletionResponse = serde_json::from_value(json!({
+            "choices": [{
+                "message": { "role": "assistant", "content": null },
+                "finish_reason": "eos"
+            }]
+        }))
+        .unwrap();
+        let resp = resp.into_response().unwrap();
+        assert_eq!(resp.content, "");
+        assert_eq!(resp.model, None);
+        assert_eq!(resp.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(resp.usage, None);
+
+        assert_eq!(normalize_finish_reason("max_tokens".to_string()), "length");
+        assert_eq!(normalize_finish_reason("length".to_string()), "length");
+        assert_eq!(
+            normalize_finish_reason("tool_calls".to_string()),
+            "tool_calls"
+        );
+
+        let usage_only: ChatCompletionChunk = serde_json::from_value(json!({
+            "choices": [],
+            "usage": { "prompt_tokens": 3, "completion_tokens": 4 }
+        }))
+        .unwrap();
+        assert_eq!(usage_only.text(), None);
+    }
+
+    #[tokio::test]
+    async fn images_are_rejected_and_keys_are_optional() {
+        let provider = OpenAICompatibleProvider::new("ollama", OLLAMA_API_BASE, "llama3.1")
+            .with_api_key(Some(" ".to_string()));
+        assert_eq!(provider.name(), "ollama");
+        assert_eq!(provider.api_key, None);
+        assert!(!provider.supports_images());
+        assert_eq!(
+            provider.get_model(&request(None)).await.unwrap(),
+            "llama3.1"
+        );
+
+        let mut with_image = request(None);
+        with_image.images.push(crate::ImageInput::Url {
+            url: "https://example.com/cat.png".to_string(),
+        });
+        assert!(matches!(
+            provider.generate(with_image).await,
+            Err(ProviderError::Unsupported(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn generate_uses_the_first_listed_model_without_a_default() {
+        if !network_tests_enabled() {
+            eprintln!("skipping network test: set NEXIS_RUN_NETWORK_TESTS=1 to enable");
+            return;
+        }
+
+        let server = MockServer::start();
+        let models = server.mock(|when, then| {
+            when.method(GET).path("/models");
+            then.status(200).json_body(json!({
+                "object": "list",
+                "data": [{ "id": "mistral-7b" }, { "id": "llama-3-8b" }]
+            }));
+        });
+        let completion = server.mock(|when, then| {
+            when.method(POST)
+                .path("/chat/completions")
+                .header_missing("authorization")
+                .json_body_includes(r#"{ "model": "mistral-7b" }"#);
+            then.status(200).json_body(json!({
+                "choices": [{
+                    "message": { "role": "assistant", "content": "Hi!" },
+                    "finish_reason": "length"
+                }]
+            }));
+        });
+
+        let provider = OpenAICompatibleProvider::new("vllm", server.base_url(), "");
+        assert_eq!(
+            provider.list_models().await.unwrap(),
+            ["mistral-7b", "llama-3-8b"]
+        );
+        let resp = provider.generate(request(None)).await.unwrap();
+        provider.generate(request(None)).await.unwrap();
+
+        models.assert_calls(2);
+        completion.assert_calls(2);
+        assert_eq!(resp.content, "Hi!");
+        assert_eq!(resp.finish_reason.as_deref(), Some("length"));
+    }
+}
@@ -0,0 +1,113 @@
+//! HTTP view of a [`ControlPlaneClient`]'s queue.
+//!
+//! [`task_routes`] returns an axum router fragment serving `GET /v1/tasks`,
+//! the queue's [`QueueStats`] together with every pending and in-flight
+//! task, and `GET /v1/tasks/:id` for a single [`TaskInfo`]. A gateway that
+//! runs a control-plane client merges it into its own router, behind
+//! whatever authentication it applies to the rest of its admin routes.
+//! Only built with the `axum` feature.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{ControlPlaneClient, QueueStats, TaskInfo};
+
+/// Body of `GET /v1/tasks`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskList {
+    pub stats: QueueStats,
+    pub tasks: Vec<TaskInfo>,
+}
+
+/// Routes exposing `queue` under `/v1/tasks`.
+pub fn task_routes<S>(queue: Arc<ControlPlaneClient>) -> Router<S> {
+    Router::new()
+        .route("/v1/tasks", get(list_tasks))
+        .route("/v1/tasks/:id", get(get_task))
+        .with_state(queue)
+}
+
+async fn list_tasks(State(queue): State<Arc<ControlPlaneClient>>) -> Json<TaskList> {
+    Json(TaskList {
+        stats: queue.stats(),
+        tasks: queue.tasks(),
+    })
+}
+
+async fn get_task(
+    State(queue): State<Arc<ControlPlaneClient>>,
+    Path(id): Path<String>,
+) -> Response {
+    match queue.task(&id) {
+        Some(task) => Json(task).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "task not found" })),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{GenerateRequest, HttpJsonProvider, TaskState};
+
+    async fn get_json(router: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = router
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn task_routes_report_queued_tasks() {
+        let queue = Arc::new(ControlPlaneClient::new(HttpJsonProvider::new(
+            "http://control-plane.invalid",
+            "test-key",
+        )));
+        queue.enqueue_generate(
+            "task_1",
+            GenerateRequest {
+                prompt: "hello".to_string(),
+                model: None,
+                max_tokens: None,
+                temperature: None,
+                metadata: None,
+                images: Vec::new(),
+            },
+        );
+        let router: Router = task_routes(queue);
+
+        let (status, body) = get_json(router.clone(), "/v1/tasks").await;
+        assert_eq!(status, StatusCode::OK);
+        let list: TaskList = serde_json::from_value(body).unwrap();
+        assert_eq!((list.stats.pending, list.stats.enqueued), (1, 1));
+        assert_eq!(list.tasks.len(), 1);
+        assert_eq!(list.tasks[0].state, TaskState::Pending);
+
+        let (status, body) = get_json(router.clone(), "/v1/tasks/task_1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["kind"], "generate");
+        assert_eq!(body["attempts"], 0);
+
+        let (status, body) = get_json(router, "/v1/tasks/task_2").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "task not found");
+    }
+}
@@ -121,6 +121,7 @@ mod tests {
                 content: "mock response".to_string(),
                 model: Some("mock".to_string()),
                 finish_reason: None,
+                usage: None,
             })
         }
 
@@ -0,0 +1,268 @@
+//! Per-request timeouts and cancellation.
+//!
+//! HTTP providers time out after a fixed 30 or 60 seconds set when their
+//! client is built. [`RequestOptions`] bounds a single call more tightly, or
+//! ties it to a [`CancellationToken`], through
+//! [`AIProvider::generate_with_options`] and
+//! [`AIProvider::generate_stream_with_options`]. Giving up drops the
+//! provider's future or stream, which closes its HTTP request or SSE
+//! connection, so every provider honours the options without doing anything
+//! itself. A timeout fails with [`ProviderError::Transport`], a cancellation
+//! with [`ProviderError::Cancelled`].
+//!
+//! [`AIProvider::generate_with_options`]: crate::AIProvider::generate_with_options
+//! [`AIProvider::generate_stream_with_options`]: crate::AIProvider::generate_stream_with_options
+
+use std::future::Future;
+use std::time::Duration;
+
+use futures::stream::{self, StreamExt};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::{ProviderError, ProviderStream};
+
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    timeout: Option<Duration>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl RequestOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Give up once the whole request, streamed or not, has taken `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Give up as soon as `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// When a request starting now has to be done by.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.timeout.map(|timeout| Instant::now() + timeout)
+    }
+
+    /// Run `request` unless it is cancelled or `deadline` passes first.
+    pub(crate) async fn run<T>(
+        &self,
+        deadline: Option<Instant>,
+        request: impl Future<Output = Result<T, ProviderError>>,
+    ) -> Result<T, ProviderError> {
+        tokio::select! {
+            result = request => result,
+            err = self.stopped(deadline) => Err(err),
+        }
+    }
+
+    /// End `stream` with an error, dropping it, once the request is
+    /// cancelled or `deadline` passes.
+    pub(crate) fn guard(
+        &self,
+        deadline: Option<Instant>,
+        stream: ProviderStream,
+    ) -> ProviderStream {
+        if deadline.is_none() && self.cancellation.is_none() {
+            return stream;
+        }
+        let stopped = Box::pin(self.stopped(deadline));
+        Box::pin(stream::unfold(
+            Some((stream, stopped)),
+            |state| async move {
+                let (mut stream, mut stopped) = state?;
+                tokio::select! {
+                    err = &mut stopped => Some((Err(err), None)),
+                    item = stream.next() => item.map(|item| (item, Some((stream, stopped)))),
+                }
+            },
+        ))
+    }
+
+    /// Resolves with the error to fail with once the request is cancelled
+    /// or `deadline` passes; never if neither can happen.
+    fn stopped(
+        &self,
+        deadline: Option<Instant>,
+    ) -> impl Future<Output = ProviderError> + Send + 'static {
+        let cancellation = self.cancellation.clone();
+        let timeout = self.timeout.unwrap_or_default();
+        async move {
+            let cancelled = async {
+                match cancellation {
+                    Some(token) => token.cancelled_owned().await,
+                    None => std::future::pending().await,
+                }
+            };
+            let expired = async {
+                match deadline {
+                    Some(deadline) => tokio::time::sleep_until(deadline).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                () = cancelled => ProviderError::Cancelled,
+                () = expired => ProviderError::Transport(format!(
+                    "request timed out after {} ms",
+                    timeout.as_millis()
+                )),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::{AIProvider, GenerateRequest, GenerateResponse, StreamChunk};
+
+    /// Takes `delay` to answer; streams one chunk and then stalls.
+    #[derive(Debug, Default)]
+    struct StallingProvider {
+        delay: Duration,
+        stream_dropped: Arc<AtomicBool>,
+    }
+
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait]
+    impl AIProvider for StallingProvider {
+        fn name(&self) -> &'static str {
+            "stalling"
+        }
+
+        async fn generate(&self, _req: GenerateRequest) -> Result<GenerateResponse, ProviderError> {
+            tokio::time::sleep(self.delay).await;
+            Ok(GenerateResponse {
+                content: "late".to_string(),
+                model: None,
+                finish_reason: Some("stop".to_string()),
+                usage: None,
+            })
+        }
+
+        async fn generate_stream(
+            &self,
+            _req: GenerateRequest,
+        ) -> Result<ProviderStream, ProviderError> {
+            let flag = DropFlag(self.stream_dropped.clone());
+            let first = stream::iter([Ok(StreamChunk::Delta {
+                text: "partial".to_string(),
+            })]);
+            let stalled =
+                stream::pending::<Result<StreamChunk, ProviderError>>().map(move |item| {
+                    let _keep = &flag;
+                    item
+                });
+            Ok(Box::pin(first.chain(stalled)))
+        }
+    }
+
+    fn request() -> GenerateRequest {
+        GenerateRequest {
+            prompt: "hello".to_string(),
+            model: None,
+            max_tokens: None,
+            temperature: None,
+            metadata: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn generate_gives_up_after_the_timeout() {
+        let provider = StallingProvider {
+            delay: Duration::from_secs(30),
+            ..Default::default()
+        };
+        let started = std::time::Instant::now();
+        let err = provider
+            .generate_with_options(
+                request(),
+                RequestOptions::new().with_timeout(Duration::from_millis(20)),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ProviderError::Transport("request timed out after 20 ms".to_string())
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let quick = StallingProvider::default();
+        let reply = quick
+            .generate_with_options(
+                request(),
+                RequestOptions::new().with_timeout(Duration::from_secs(5)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(reply.content, "late");
+    }
+
+    #[tokio::test]
+    async fn cancelling_stops_a_generation() {
+        let provider = StallingProvider {
+            delay: Duration::from_secs(30),
+            ..Default::default()
+        };
+        let token = CancellationToken::new();
+        let canceller = {
+            let token = token.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                token.cancel();
+            })
+        };
+        let result = provider
+            .generate_with_options(request(), RequestOptions::new().with_cancellation(token))
+            .await;
+        assert_eq!(result.unwrap_err(), ProviderError::Cancelled);
+        canceller.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_stream_drops_the_connection() {
+        let provider = StallingProvider::default();
+        let token = CancellationToken::new();
+        let mut stream = provider
+            .generate_stream_with_options(
+                request(),
+                RequestOptions::new().with_cancellation(token.clone()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            stream.next().await,
+            Some(Ok(StreamChunk::Delta {
+                text: "partial".to_string()
+            }))
+        );
+        token.cancel();
+        assert_eq!(stream.next().await, Some(Err(ProviderError::Cancelled)));
+        assert!(provider.stream_dropped.load(Ordering::SeqCst));
+        assert_eq!(stream.next().await, None);
+    }
+}
@@ -0,0 +1,51 @@
+//! Trace context propagation for outbound HTTP calls.
+//!
+//! With the `otel` feature enabled, the OpenTelemetry context of the current
+//! span is injected into outgoing requests (W3C `traceparent`/`tracestate`)
+//! so provider, embedding and gateway calls join the caller's trace. Without
+//! the feature this is a no-op.
+
+use reqwest::RequestBuilder;
+
+/// Chainable wrapper around [`inject_trace_context`].
+pub(crate) trait TraceContextExt {
+    fn with_trace_context(self) -> Self;
+}
+
+impl TraceContextExt for RequestBuilder {
+    fn with_trace_context(self) -> Self {
+        inject_trace_context(self)
+    }
+}
+
+#[cfg(feature = "otel")]
+fn inject_trace_context(request: RequestBuilder) -> RequestBuilder {
+    use opentelemetry::propagation::Injector;
+    use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    struct HeaderInjector(HeaderMap);
+
+    impl Injector for HeaderInjector {
+        fn set(&mut self, key: &str, value: String) {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(key.as_bytes()),
+                HeaderValue::from_str(&value),
+            ) {
+                self.0.insert(name, value);
+            }
+        }
+    }
+
+    let context = tracing::Span::current().context();
+    let mut injector = HeaderInjector(HeaderMap::new());
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut injector)
+    });
+    request.headers(injector.0)
+}
+
+#[cfg(not(feature = "otel"))]
+fn inject_trace_context(request: RequestBuilder) -> RequestBuilder {
+    request
+}
@@ -1,14 +1,19 @@
 //! Tool calling system for AI agents
 //!
 //! This module provides a standardized way for AI agents to call tools
-//! and execute actions in the real world.
+//! and execute actions in the real world. Risky tools can be gated behind
+//! an [`ApprovalPolicy`] so a person decides whether each call runs.
 
 use async_trait::async_trait;
+use futures::stream::{self, Stream};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::pin::Pin;
 use std::sync::Arc;
 use thiserror::Error;
 
+use crate::approval::ApprovalPolicy;
+
 /// Tool execution error
 #[derive(Debug, Error)]
 pub enum ToolError {
@@ -23,6 +28,12 @@ pub enum ToolError {
 
     #[error("timeout after {0}ms")]
     Timeout(u64),
+
+    #[error("{approver} denied running {tool}")]
+    ApprovalDenied { tool: String, approver: String },
+
+    #[error("no approval to run {0} arrived in time")]
+    ApprovalTimedOut(String),
 }
 
 /// Tool definition for function calling
@@ -67,6 +78,9 @@ pub struct ToolResult {
     pub is_error: bool,
 }
 
+/// Output of a tool, chunk by chunk as it is produced
+pub type ToolOutputStream = Pin<Box<dyn Stream<Item = Result<String, ToolError>> + Send>>;
+
 /// Tool trait for implementers
 #[async_trait]
 pub trait Tool: Send + Sync {
@@ -75,11 +89,24 @@ pub trait Tool: Send + Sync {
 
     /// Execute the tool
     async fn execute(&self, arguments: serde_json::Value) -> Result<String, ToolError>;
+
+    /// Execute the tool, yielding output while it runs
+    ///
+    /// Long-running tools override this; the default yields the result of
+    /// [`Tool::execute`] as a single chunk.
+    async fn execute_streaming(
+        &self,
+        arguments: serde_json::Value,
+    ) -> Result<ToolOutputStream, ToolError> {
+        let output = self.execute(arguments).await?;
+        Ok(Box::pin(stream::once(async move { Ok(output) })))
+    }
 }
 
 /// Registry of available tools
 pub struct ToolRegistry {
     tools: HashMap<String, Arc<dyn Tool>>,
+    approval: Option<ApprovalPolicy>,
 }
 
 impl ToolRegistry {
@@ -87,9 +114,15 @@ impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            approval: None,
         }
     }
 
+    /// Pause calls to the tools `policy` names until they are approved
+    pub fn set_approval_policy(&mut self, policy: ApprovalPolicy) {
+        self.approval = Some(policy);
+    }
+
     /// Register a tool
     pub fn register(&mut self, tool: Arc<dyn Tool>) {
         let def = tool.definition();
@@ -107,6 +140,9 @@ impl ToolRegistry {
             .tools
             .get(&call.name)
             .ok_or_else(|| ToolError::NotFound(call.name.clone()))?;
+        if let Some(policy) = &self.approval {
+            policy.authorize(&call).await?;
+        }
 
         let content = tool
             .execute(call.arguments.clone())
@@ -120,6 +156,19 @@ impl ToolRegistry {
             is_error: false,
         })
     }
+
+    /// Execute a tool call, yielding its output as it is produced
+    pub async fn execute_streaming(&self, call: &ToolCall) -> Result<ToolOutputStream, ToolError> {
+        let tool = self
+            .tools
+            .get(&call.name)
+            .ok_or_else(|| ToolError::NotFound(call.name.clone()))?;
+        if let Some(policy) = &self.approval {
+            policy.authorize(call).await?;
+        }
+
+        tool.execute_streaming(call.arguments.clone()).await
+    }
 }
 
 impl Default for ToolRegistry {
@@ -128,6 +177,64 @@ impl Default for ToolRegistry {
     }
 }
 
+impl ToolRegistry {
+    /// Start building a registry with the built-in tools
+    pub fn builder() -> ToolRegistryBuilder {
+        ToolRegistryBuilder::default()
+    }
+}
+
+/// Builder that registers built-in tools behind opt-in flags
+#[derive(Default)]
+pub struct ToolRegistryBuilder {
+    web_search: bool,
+    http_request: Option<HttpRequestConfig>,
+    tools: Vec<Arc<dyn Tool>>,
+    approval: Option<ApprovalPolicy>,
+}
+
+impl ToolRegistryBuilder {
+    /// Register the web search tool
+    pub fn with_web_search(mut self) -> Self {
+        self.web_search = true;
+        self
+    }
+
+    /// Register the HTTP request tool with the given limits
+    pub fn with_http_requests(mut self, config: HttpRequestConfig) -> Self {
+        self.http_request = Some(config);
+        self
+    }
+
+    /// Register an additional tool
+    pub fn with_tool(mut self, tool: Arc<dyn Tool>) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    /// Require approval before the tools `policy` names run
+    pub fn with_approval_policy(mut self, policy: ApprovalPolicy) -> Self {
+        self.approval = Some(policy);
+        self
+    }
+
+    /// Build the registry
+    pub fn build(self) -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        registry.approval = self.approval;
+        if self.web_search {
+            registry.register(Arc::new(WebSearchTool::new()));
+        }
+        if let Some(config) = self.http_request {
+            registry.register(Arc::new(HttpRequestTool::new(config)));
+        }
+        for tool in self.tools {
+            registry.register(tool);
+        }
+        registry
+    }
+}
+
 // ============================================================================
 // Built-in Tools
 // ============================================================================
@@ -306,6 +413,272 @@ impl Tool for FileReadTool {
     }
 }
 
+/// Limits applied by [`HttpRequestTool`]
+#[derive(Debug, Clone)]
+pub struct HttpRequestConfig {
+    /// Hosts the tool may contact; subdomains of an entry are allowed too
+    pub allowed_domains: Vec<String>,
+    /// Maximum response body size returned to the agent
+    pub max_response_bytes: usize,
+    /// Request timeout
+    pub timeout: std::time::Duration,
+}
+
+impl Default for HttpRequestConfig {
+    fn default() -> Self {
+        Self {
+            allowed_domains: Vec::new(),
+            max_response_bytes: 64 * 1024,
+            timeout: std::time::Duration::from_secs(10),
+        }
+    }
+}
+
+impl HttpRequestConfig {
+    /// Allow requests to these domains
+    pub fn new<I, S>(allowed_domains: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            allowed_domains: allowed_domains
+                .into_iter()
+                .map(|domain| domain.into().to_ascii_lowercase())
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Check a host against the allow-list
+    pub fn is_allowed(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.allowed_domains.iter().any(|domain| {
+            host == *domain
+                || host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+    }
+}
+
+const REDACTED_HEADERS: [&str; 3] = ["authorization", "proxy-authorization", "cookie"];
+
+/// Header pairs safe to log, with credentials replaced
+fn redact_headers(headers: &[(String, String)]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            if REDACTED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                (name.clone(), "[REDACTED]".to_string())
+            } else {
+                (name.clone(), value.clone())
+            }
+        })
+        .collect()
+}
+
+type ParsedHttpRequest = (reqwest::Method, reqwest::Url, Vec<(String, String)>);
+
+/// HTTP fetch tool restricted to an allow-list of domains
+pub struct HttpRequestTool {
+    config: Arc<HttpRequestConfig>,
+    client: reqwest::Client,
+}
+
+impl HttpRequestTool {
+    pub fn new(config: HttpRequestConfig) -> Self {
+        let config = Arc::new(config);
+        let redirect_config = Arc::clone(&config);
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                let allowed = attempt
+                    .url()
+                    .host_str()
+                    .is_some_and(|host| redirect_config.is_allowed(host));
+                if !allowed {
+                    attempt.error("redirect target is not in the allow-list")
+                } else if attempt.previous().len() >= 5 {
+                    attempt.stop()
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .build()
+            .expect("reqwest client should build");
+        Self { config, client }
+    }
+
+    /// Active limits
+    pub fn config(&self) -> &HttpRequestConfig {
+        &self.config
+    }
+
+    fn parse_request(&self, arguments: &serde_json::Value) -> Result<ParsedHttpRequest, ToolError> {
+        let method = match arguments
+            .get("method")
+            .and_then(|v| v.as_str())
+            .unwrap_or("GET")
+            .to_ascii_uppercase()
+            .as_str()
+        {
+            "GET" => reqwest::Method::GET,
+            "POST" => reqwest::Method::POST,
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "unsupported method: {other}"
+                )))
+            }
+        };
+
+        let url = arguments
+            .get("url")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("missing url".into()))?;
+        let url = reqwest::Url::parse(url)
+            .map_err(|e| ToolError::InvalidParameters(format!("invalid url: {e}")))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(ToolError::InvalidParameters(format!(
+                "unsupported scheme: {}",
+                url.scheme()
+            )));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| ToolError::InvalidParameters("url has no host".into()))?;
+        if !self.config.is_allowed(host) {
+            return Err(ToolError::InvalidParameters(format!(
+                "host not allowed: {host}"
+            )));
+        }
+
+        let headers = match arguments.get("headers") {
+            None | Some(serde_json::Value::Null) => Vec::new(),
+            Some(serde_json::Value::Object(map)) => map
+                .iter()
+                .map(|(name, value)| match value.as_str() {
+                    Some(value) => Ok((name.clone(), value.to_string())),
+                    None => Err(ToolError::InvalidParameters(format!(
+                        "header {name} must be a string"
+                    ))),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(_) => {
+                return Err(ToolError::InvalidParameters(
+                    "headers must be an object".into(),
+                ))
+            }
+        };
+
+        Ok((method, url, headers))
+    }
+}
+
+#[async_trait]
+impl Tool for HttpRequestTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "http_request".to_string(),
+            description: "Fetch a URL from an allow-listed domain".to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "method": {
+                        "type": "string",
+                        "enum": ["GET", "POST"],
+                        "description": "HTTP method (defaults to GET)"
+                    },
+                    "url": {
+                        "type": "string",
+                        "description": "Absolute http(s) URL"
+                    },
+                    "headers": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" },
+                        "description": "Request headers"
+                    },
+                    "body": {
+                        "description": "Request body; objects are sent as JSON"
+                    }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: serde_json::Value) -> Result<String, ToolError> {
+        let (method, url, headers) = self.parse_request(&arguments)?;
+        tracing::info!(
+            method = %method,
+            url = %url,
+            headers = ?redact_headers(&headers),
+            "http_request tool call"
+        );
+
+        let mut request = self.client.request(method, url);
+        for (name, value) in &headers {
+            request = request.header(name, value);
+        }
+        request = match arguments.get("body") {
+            None | Some(serde_json::Value::Null) => request,
+            Some(serde_json::Value::String(body)) => request.body(body.clone()),
+            Some(body) => request.json(body),
+        };
+
+        let timeout_ms = self.config.timeout.as_millis() as u64;
+        let mut response = request.send().await.map_err(|e| {
+            if e.is_timeout() {
+                ToolError::Timeout(timeout_ms)
+            } else {
+                ToolError::ExecutionFailed(format!("request failed: {e}"))
+            }
+        })?;
+
+        let status = response.status();
+        let mut body = Vec::new();
+        let mut truncated = false;
+        while let Some(chunk) = response.chunk().await.map_err(|e| {
+            if e.is_timeout() {
+                ToolError::Timeout(timeout_ms)
+            } else {
+                ToolError::ExecutionFailed(format!("failed to read response: {e}"))
+            }
+        })? {
+            let remaining = self.config.max_response_bytes - body.len();
+            if chunk.len() > remaining {
+                body.extend_from_slice(&chunk[..remaining]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let mut content = format!(
+            "HTTP {}\n\n{}",
+            status.as_u16(),
+            String::from_utf8_lossy(&body)
+        );
+        if truncated {
+            content.push_str(&format!(
+                "\n\n[Truncated - response exceeded {} bytes]",
+                self.config.max_response_bytes
+            ));
+        }
+        Ok(content)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -338,6 +711,96 @@ mod tests {
         assert!(result.is_err());
     }
 
+    fn network_tests_enabled() -> bool {
+        matches!(std::env::var("NEXIS_RUN_NETWORK_TESTS"), Ok(value) if value == "1")
+    }
+
+    #[test]
+    fn builder_registers_http_tool_only_when_enabled() {
+        let registry = ToolRegistry::builder().with_web_search().build();
+        assert_eq!(registry.definitions().len(), 1);
+
+        let registry = ToolRegistry::builder()
+            .with_http_requests(HttpRequestConfig::new(["example.com"]))
+            .build();
+        let defs = registry.definitions();
+        assert_eq!(defs.len(), 1);
+        assert_eq!(defs[0].name, "http_request");
+    }
+
+    #[test]
+    fn http_allow_list_matches_domain_and_subdomains() {
+        let config = HttpRequestConfig::new(["Example.com"]);
+        assert!(config.is_allowed("example.com"));
+        assert!(config.is_allowed("api.example.com"));
+        assert!(!config.is_allowed("badexample.com"));
+        assert!(!config.is_allowed("example.com.evil.io"));
+        assert!(!HttpRequestConfig::default().is_allowed("example.com"));
+    }
+
+    #[test]
+    fn http_headers_are_redacted_for_logs() {
+        let redacted = redact_headers(&[
+            ("Authorization".to_string(), "Bearer secret".to_string()),
+            ("Accept".to_string(), "text/plain".to_string()),
+        ]);
+        assert_eq!(redacted[0].1, "[REDACTED]");
+        assert_eq!(redacted[1].1, "text/plain");
+    }
+
+    #[tokio::test]
+    async fn http_request_rejects_disallowed_hosts_and_methods() {
+        let tool = HttpRequestTool::new(HttpRequestConfig::new(["example.com"]));
+
+        let result = tool
+            .execute(serde_json::json!({"url": "https://evil.io/"}))
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+
+        let result = tool
+            .execute(serde_json::json!({"url": "https://example.com/", "method": "DELETE"}))
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+
+        let result = tool
+            .execute(serde_json::json!({"url": "file:///etc/passwd"}))
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+    }
+
+    #[tokio::test]
+    async fn http_request_caps_response_size() {
+        if !network_tests_enabled() {
+            eprintln!("skipping network test: set NEXIS_RUN_NETWORK_TESTS=1 to enable");
+            return;
+        }
+
+        let server = httpmock::MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/echo")
+                    .header("authorization", "Bearer secret");
+                then.status(200).body("x".repeat(100));
+            })
+            .await;
+
+        let tool =
+            HttpRequestTool::new(HttpRequestConfig::new(["127.0.0.1"]).with_max_response_bytes(10));
+        let result = tool
+            .execute(serde_json::json!({
+                "method": "POST",
+                "url": server.url("/echo"),
+                "headers": {"Authorization": "Bearer secret"},
+                "body": {"hello": "world"}
+            }))
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert!(result.starts_with("HTTP 200\n\nxxxxxxxxxx\n\n[Truncated"));
+    }
+
     #[tokio::test]
     async fn file_read_prevents_traversal() {
         let tool = FileReadTool::new("/tmp");
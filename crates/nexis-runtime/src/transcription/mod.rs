@@ -0,0 +1,161 @@
+//! Speech-to-text provider trait and implementations
+//!
+//! This module turns recorded audio (voice notes, meeting snippets) into
+//! text that can be posted to rooms and indexed like any other message.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::ProviderError;
+
+pub mod openai;
+
+pub use openai::OpenAITranscriptionProvider;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptionRequest {
+    pub audio: Vec<u8>,
+    pub file_name: String,
+    pub mime_type: String,
+    pub model: Option<String>,
+    /// ISO-639-1 language hint; detected automatically when unset.
+    pub language: Option<String>,
+}
+
+impl TranscriptionRequest {
+    pub fn new(audio: Vec<u8>, file_name: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self {
+            audio,
+            file_name: file_name.into(),
+            mime_type: mime_type.into(),
+            model: None,
+            language: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptionResponse {
+    pub text: String,
+    pub model: String,
+    pub language: Option<String>,
+    pub duration_secs: Option<f32>,
+}
+
+impl TranscriptionResponse {
+    pub fn new(text: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            model: model.into(),
+            language: None,
+            duration_secs: None,
+        }
+    }
+}
+
+#[async_trait]
+pub trait TranscriptionProvider: Send + Sync + std::fmt::Debug {
+    fn name(&self) -> &'static str;
+
+    async fn transcribe(
+        &self,
+        req: TranscriptionRequest,
+    ) -> Result<TranscriptionResponse, ProviderError>;
+}
+
+/// Returns queued results in order, then echoes the file name back.
+#[derive(Debug, Default)]
+pub struct MockTranscriptionProvider {
+    queue: Mutex<VecDeque<Result<TranscriptionResponse, ProviderError>>>,
+    requests: Mutex<Vec<TranscriptionRequest>>,
+}
+
+impl MockTranscriptionProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enqueue(&self, result: Result<TranscriptionResponse, ProviderError>) {
+        self.queue
+            .lock()
+            .expect("mock transcription queue poisoned")
+            .push_back(result);
+    }
+
+    /// Requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<TranscriptionRequest> {
+        self.requests
+            .lock()
+            .expect("mock transcription requests poisoned")
+            .clone()
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for MockTranscriptionProvider {
+    fn name(&self) -> &'static str {
+        "mock-transcription"
+    }
+
+    async fn transcribe(
+        &self,
+        req: TranscriptionRequest,
+    ) -> Result<TranscriptionResponse, ProviderError> {
+        let file_name = req.file_name.clone();
+        self.requests
+            .lock()
+            .expect("mock transcription requests poisoned")
+            .push(req);
+        self.queue
+            .lock()
+            .expect("mock transcription queue poisoned")
+            .pop_front()
+            .unwrap_or_else(|| {
+                Ok(TranscriptionResponse::new(
+                    format!("transcript of {file_name}"),
+                    "mock-transcription-model",
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn mock_provider_replays_queue_then_echoes() {
+        let provider = MockTranscriptionProvider::new();
+        provider.enqueue(Ok(TranscriptionResponse::new("hello", "whisper-1")));
+        provider.enqueue(Err(ProviderError::Message("boom".to_string())));
+
+        let req =
+            TranscriptionRequest::new(vec![1, 2, 3], "note.ogg", "audio/ogg").with_language("en");
+        assert_eq!(
+            provider.transcribe(req.clone()).await.unwrap().text,
+            "hello"
+        );
+        assert!(provider.transcribe(req.clone()).await.is_err());
+        assert_eq!(
+            provider.transcribe(req).await.unwrap().text,
+            "transcript of note.ogg"
+        );
+
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].language.as_deref(), Some("en"));
+    }
+}
@@ -0,0 +1,291 @@
+//! OpenAI Whisper Transcription Provider
+//!
+//! Implements the TranscriptionProvider trait for OpenAI's
+//! `/audio/transcriptions` endpoint.
+
+use async_trait::async_trait;
+use reqwest::multipart::{Form, Part};
+use reqwest::Client;
+use serde::Deserialize;
+use std::env;
+use std::time::Duration;
+
+use crate::telemetry::TraceContextExt;
+use crate::transcription::{TranscriptionProvider, TranscriptionRequest, TranscriptionResponse};
+use crate::ProviderError;
+
+const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
+pub const DEFAULT_TRANSCRIPTION_MODEL: &str = "whisper-1";
+
+#[derive(Debug)]
+pub struct OpenAITranscriptionProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    default_model: String,
+    max_retries: u32,
+    retry_base_delay: Duration,
+}
+
+impl OpenAITranscriptionProvider {
+    pub fn from_env() -> Self {
+        let api_key =
+            env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY environment variable must be set");
+
+        let base_url = env::var("OPENAI_API_BASE").unwrap_or_else(|_| OPENAI_API_BASE.to_string());
+
+        let default_model = env::var("OPENAI_TRANSCRIPTION_MODEL")
+            .unwrap_or_else(|_| DEFAULT_TRANSCRIPTION_MODEL.to_string());
+
+        Self::new(api_key, base_url, default_model)
+    }
+
+    pub fn new(
+        api_key: impl Into<String>,
+        base_url: impl Into<String>,
+        default_model: impl Into<String>,
+    ) -> Self {
+        let client = Client::builder()
+            // Long voice notes take a while to upload and transcribe.
+            .timeout(Duration::from_secs(120))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            client,
+            api_key: api_key.into(),
+            base_url: base_url.into(),
+            default_model: default_model.into(),
+            max_retries: 2,
+            retry_base_delay: Duration::from_millis(500),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, max_retries: u32, retry_base_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_base_delay = retry_base_delay;
+        self
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+    }
+
+    fn get_model(&self, req: &TranscriptionRequest) -> String {
+        req.model
+            .clone()
+            .unwrap_or_else(|| self.default_model.clone())
+    }
+
+    /// Multipart bodies are consumed on send, so each attempt builds a new one.
+    fn form(&self, req: &TranscriptionRequest, model: &str) -> Result<Form, ProviderError> {
+        let file = Part::bytes(req.audio.clone())
+            .file_name(req.file_name.clone())
+            .mime_str(&req.mime_type)
+            .map_err(|e| ProviderError::Message(format!("invalid audio mime type: {e}")))?;
+        let mut form = Form::new()
+            .part("file", file)
+            .text("model", model.to_string())
+            .text("response_format", "verbose_json");
+        if let Some(language) = &req.language {
+            form = form.text("language", language.clone());
+        }
+        Ok(form)
+    }
+
+    async fn try_transcribe(
+        &self,
+        req: &TranscriptionRequest,
+        model: &str,
+    ) -> Result<TranscriptionResponse, ProviderError> {
+        let response = self
+            .client
+            .post(self.endpoint("/audio/transcriptions"))
+            .with_trace_context()
+            .bearer_auth(&self.api_key)
+            .multipart(self.form(req, model)?)
+            .send()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "<unable to read body>".to_string());
+            return Err(ProviderError::HttpStatus {
+                status: status.as_u16(),
+                body,
+            });
+        }
+
+        let body: TranscriptionBody = response
+            .json()
+            .await
+            .map_err(|e| ProviderError::Decode(e.to_string()))?;
+
+        Ok(body.into_response(model))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptionBody {
+    text: String,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    duration: Option<f32>,
+}
+
+impl TranscriptionBody {
+    fn into_response(self, model: &str) -> TranscriptionResponse {
+        TranscriptionResponse {
+            text: self.text.trim().to_string(),
+            model: model.to_string(),
+            language: self.language,
+            duration_secs: self.duration,
+        }
+    }
+}
+
+fn is_retriable(err: &ProviderError) -> bool {
+    match err {
+        ProviderError::Transport(_) => true,
+        ProviderError::HttpStatus { status, .. } => *status >= 500 || *status == 429,
+        _ => false,
+    }
+}
+
+fn backoff(base: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(1_u32 << attempt)
+}
+
+#[async_trait]
+impl TranscriptionProvider for OpenAITranscriptionProvider {
+    fn name(&self) -> &'static str {
+        "openai-transcription"
+    }
+
+    #[tracing::instrument(
+        name = "transcription.openai.transcribe",
+        skip_all,
+        fields(otel.kind = "client", audio_bytes = req.audio.len())
+    )]
+    async fn transcribe(
+        &self,
+        req: TranscriptionRequest,
+    ) -> Result<TranscriptionResponse, ProviderError> {
+        let model = self.get_model(&req);
+
+        let mut last_error = None;
+        let mut last_code = None;
+        for attempt in 0..=self.max_retries {
+            match self.try_transcribe(&req, &model).await {
+                Ok(response) => return Ok(response),
+                Err(err) => {
+                    let retriable = is_retriable(&err);
+                    last_error = Some(err.to_string());
+                    last_code = err.code();
+                    if retriable && attempt < self.max_retries {
+                        tokio::time::sleep(backoff(self.retry_base_delay, attempt)).await;
+                        continue;
+                    }
+                    if retriable {
+                        return Err(ProviderError::RetryExhausted {
+                            attempts: attempt + 1,
+                            last_error: last_error
+                                .unwrap_or_else(|| "unknown retry error".to_string()),
+                            code: last_code,
+                        });
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        Err(ProviderError::RetryExhausted {
+            attempts: self.max_retries + 1,
+            last_error: last_error.unwrap_or_else(|| "unknown retry error".to_string()),
+            code: last_code,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    fn network_tests_enabled() -> bool {
+        matches!(std::env::var("NEXIS_RUN_NETWORK_TESTS"), Ok(value) if value == "1")
+    }
+
+    #[test]
+    fn get_model_prefers_request_model() {
+        let provider = OpenAITranscriptionProvider::new("key", OPENAI_API_BASE, "whisper-1");
+        let req = TranscriptionRequest::new(vec![0], "a.mp3", "audio/mpeg");
+        assert_eq!(provider.get_model(&req), "whisper-1");
+        let req = req.with_model("gpt-4o-transcribe");
+        assert_eq!(provider.get_model(&req), "gpt-4o-transcribe");
+    }
+
+    #[test]
+    fn verbose_json_body_maps_to_response() {
+        let body: TranscriptionBody = serde_json::from_str(
+            r#"{"task":"transcribe","language":"english","duration":3.5,"text":" Hello there. "}"#,
+        )
+        .unwrap();
+        let response = body.into_response("whisper-1");
+        assert_eq!(response.text, "Hello there.");
+        assert_eq!(response.language.as_deref(), Some("english"));
+        assert_eq!(response.duration_secs, Some(3.5));
+    }
+
+    #[test]
+    fn invalid_mime_type_is_rejected_before_sending() {
+        let provider = OpenAITranscriptionProvider::new("key", OPENAI_API_BASE, "whisper-1");
+        let req = TranscriptionRequest::new(vec![0], "a.mp3", "not a mime type");
+        assert!(matches!(
+            provider.form(&req, "whisper-1"),
+            Err(ProviderError::Message(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn transcribe_posts_multipart_audio() {
+        if !network_tests_enabled() {
+            eprintln!("skipping network test: set NEXIS_RUN_NETWORK_TESTS=1 to enable");
+            return;
+        }
+
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/audio/transcriptions")
+                .header("Authorization", "Bearer test-key")
+                .body_includes("name=\"model\"")
+                .body_includes("whisper-1");
+            then.status(200).json_body(json!({
+                "text": "voice note",
+                "language": "english",
+                "duration": 1.25
+            }));
+        });
+
+        let provider = OpenAITranscriptionProvider::new("test-key", server.base_url(), "whisper-1");
+        let response = provider
+            .transcribe(TranscriptionRequest::new(
+                b"RIFF".to_vec(),
+                "note.wav",
+                "audio/wav",
+            ))
+            .await
+            .unwrap();
+
+        mock.assert();
+        assert_eq!(response.text, "voice note");
+        assert_eq!(response.model, "whisper-1");
+    }
+}
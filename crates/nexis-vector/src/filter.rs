@@ -0,0 +1,449 @@
+//! Filter construction and backend-neutral filter conditions
+
+use chrono::{DateTime, Utc};
+use nexis_protocol::RoomId;
+use uuid::Uuid;
+
+use crate::error::{VectorError, VectorResult};
+use crate::types::{SearchFilter, TagMatch, TimeRange};
+
+/// Builder for a validated [`SearchFilter`]
+///
+/// ```rust
+/// use chrono::{Duration, Utc};
+/// use nexis_vector::FilterBuilder;
+///
+/// let filter = FilterBuilder::new()
+///     .tenant("acme")
+///     .all_tags(["language:rust", "reviewed"])
+///     .created_since(Utc::now() - Duration::days(7))
+///     .build()
+///     .unwrap();
+/// assert_eq!(filter.tags.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FilterBuilder {
+    filter: SearchFilter,
+    all_tags: Vec<String>,
+    any_tags: Vec<String>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+}
+
+impl FilterBuilder {
+    /// Start an empty filter
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only documents of this tenant
+    pub fn tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.filter.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    /// Only documents of this room
+    pub fn room(mut self, room_id: RoomId) -> Self {
+        self.filter.room_id = Some(room_id);
+        self
+    }
+
+    /// Only documents of one of these rooms
+    pub fn any_room(mut self, room_ids: impl IntoIterator<Item = RoomId>) -> Self {
+        self.filter.room_ids.extend(room_ids);
+        self
+    }
+
+    /// Only documents created by this user
+    pub fn user(mut self, user_id: Uuid) -> Self {
+        self.filter.user_id = Some(user_id);
+        self
+    }
+
+    /// Only documents of this content type
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.filter.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Require `tag`, in addition to any other required tags
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.all_tags.push(tag.into());
+        self
+    }
+
+    /// Require every one of `tags`
+    pub fn all_tags<I, T>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.all_tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Require at least one of `tags`
+    pub fn any_tag<I, T>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.any_tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only documents created at or after `start`
+    pub fn created_since(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Only documents created at or before `end`
+    pub fn created_until(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    /// Only documents created within `start..=end`
+    pub fn created_between(self, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        self.created_since(start).created_until(end)
+    }
+
+    /// Validate and return the filter
+    ///
+    /// Fails with [`VectorError::InvalidQuery`] on an empty tenant, content
+    /// type or tag, on a time range that ends before it starts, and when both
+    /// required and alternative tags are given, which a single filter can't
+    /// express.
+    pub fn build(self) -> VectorResult<SearchFilter> {
+        let mut filter = self.filter;
+
+        if filter.tenant_id.as_deref() == Some("") {
+            return Err(VectorError::invalid_query("Filter tenant cannot be empty"));
+        }
+        if filter.content_type.as_deref() == Some("") {
+            return Err(VectorError::invalid_query(
+                "Filter content type cannot be empty",
+            ));
+        }
+
+        match (self.all_tags.is_empty(), self.any_tags.is_empty()) {
+            (_, true) => {
+                filter.tags = self.all_tags;
+                filter.tag_match = TagMatch::All;
+            }
+            (true, false) => {
+                filter.tags = self.any_tags;
+                filter.tag_match = TagMatch::Any;
+            }
+            (false, false) => {
+                return Err(VectorError::invalid_query(
+                    "Filter cannot combine required tags with alternative tags",
+                ));
+            }
+        }
+
+        if self.start.is_some() || self.end.is_some() {
+            filter.time_range = Some(TimeRange {
+                start: self.start,
+                end: self.end,
+            });
+        }
+
+        filter.validate().map_err(VectorError::invalid_query)?;
+        Ok(filter)
+    }
+}
+
+/// One payload condition of a [`SearchFilter`], in the shape payload-indexed
+/// backends such as Qdrant understand
+///
+/// A document matches a filter when it satisfies every condition of
+/// [`SearchFilter::conditions`]. Keys name fields of the stored payload.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterCondition {
+    /// The field equals `value`, or for a list field, contains it
+    Keyword { key: &'static str, value: String },
+    /// The field equals one of `values`, or for a list field, contains one
+    AnyKeyword {
+        key: &'static str,
+        values: Vec<String>,
+    },
+    /// The datetime field lies within the inclusive bounds
+    DatetimeRange {
+        key: &'static str,
+        gte: Option<DateTime<Utc>>,
+        lte: Option<DateTime<Utc>>,
+    },
+}
+
+impl SearchFilter {
+    /// Conditions that together express this filter
+    pub fn conditions(&self) -> Vec<FilterCondition> {
+        let mut conditions = Vec::new();
+
+        if let Some(ref tenant_id) = self.tenant_id {
+            conditions.push(FilterCondition::Keyword {
+                key: "tenant_id",
+                value: tenant_id.clone(),
+            });
+        }
+        if let Some(ref room_id) = self.room_id {
+            conditions.push(FilterCondition::Keyword {
+                key: "room_id",
+                value: room_id.to_string(),
+            });
+        }
+        if !self.room_ids.is_empty() {
+            conditions.push(FilterCondition::AnyKeyword {
+                key: "room_id",
+                values: self.room_ids.iter().map(ToString::to_string).collect(),
+            });
+        }
+        if let Some(user_id) = self.user_id {
+            conditions.push(FilterCondition::Keyword {
+                key: "user_id",
+                value: user_id.to_string(),
+            });
+        }
+        if !self.tags.is_empty() {
+            match self.tag_match {
+                TagMatch::All => {
+                    conditions.extend(self.tags.iter().map(|tag| FilterCondition::Keyword {
+                        key: "tags",
+                        value: tag.clone(),
+                    }))
+                }
+                TagMatch::Any => conditions.push(FilterCondition::AnyKeyword {
+                    key: "tags",
+                    values: self.tags.clone(),
+                }),
+            }
+        }
+        if let Some(ref content_type) = self.content_type {
+            conditions.push(FilterCondition::Keyword {
+                key: "content_type",
+                value: content_type.clone(),
+            });
+        }
+        if let Some(ref range) = self.time_range {
+            conditions.push(FilterCondition::DatetimeRange {
+                key: "created_at",
+                gte: range.start,
+                lte: range.end,
+            });
+        }
+
+        conditions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Document, DocumentMetadata, Vector};
+    use chrono::{Duration, TimeZone};
+    use proptest::prelude::*;
+    use serde_json::{json, Value};
+
+    const TENANTS: [&str; 2] = ["acme", "globex"];
+    const TAGS: [&str; 4] = ["a", "b", "c", "d"];
+    const CONTENT_TYPES: [&str; 2] = ["text", "code"];
+
+    fn rooms() -> [RoomId; 2] {
+        [
+            RoomId::from_uuid(Uuid::from_u128(10)),
+            RoomId::from_uuid(Uuid::from_u128(11)),
+        ]
+    }
+
+    fn users() -> [Uuid; 2] {
+        [Uuid::from_u128(1), Uuid::from_u128(2)]
+    }
+
+    fn at(offset_micros: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap() + Duration::microseconds(offset_micros)
+    }
+
+    /// The payload the Qdrant store writes for `doc`.
+    fn payload(doc: &Document) -> Value {
+        let meta = &doc.metadata;
+        json!({
+            "tenant_id": meta.tenant_id,
+            "room_id": meta.room_id.as_ref().map(ToString::to_string),
+            "user_id": meta.user_id.map(|id| id.to_string()),
+            "tags": meta.tags,
+            "content_type": meta.content_type,
+            "created_at": doc.created_at.to_rfc3339(),
+        })
+    }
+
+    /// Qdrant's evaluation of one `must` condition against a payload.
+    fn qdrant_matches(condition: &FilterCondition, payload: &Value) -> bool {
+        let values = |key: &str| -> Vec<String> {
+            match &payload[key] {
+                Value::String(value) => vec![value.clone()],
+                Value::Array(items) => items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(str::to_string))
+                    .collect(),
+                _ => Vec::new(),
+            }
+        };
+        match condition {
+            FilterCondition::Keyword { key, value } => values(key).contains(value),
+            FilterCondition::AnyKeyword {
+                key,
+                values: wanted,
+            } => values(key).iter().any(|value| wanted.contains(value)),
+            FilterCondition::DatetimeRange { key, gte, lte } => {
+                let Some(stored) = payload[*key]
+                    .as_str()
+                    .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+                else {
+                    return false;
+                };
+                let micros = stored.timestamp_micros();
+                gte.is_none_or(|gte| micros >= gte.timestamp_micros())
+                    && lte.is_none_or(|lte| micros <= lte.timestamp_micros())
+            }
+        }
+    }
+
+    fn document() -> impl Strategy<Value = Document> {
+        (
+            proptest::option::of(0..2usize),
+            proptest::option::of(0..2usize),
+            proptest::option::of(0..2usize),
+            proptest::sample::subsequence(TAGS.to_vec(), 0..=TAGS.len()),
+            proptest::option::of(0..2usize),
+            -5_000i64..5_000,
+        )
+            .prop_map(|(tenant, room, user, tags, content_type, created)| {
+                let mut metadata = DocumentMetadata::new();
+                metadata.tenant_id = tenant.map(|i| TENANTS[i].to_string());
+                metadata.room_id = room.map(|i| rooms()[i].clone());
+                metadata.user_id = user.map(|i| users()[i]);
+                metadata.tags = tags.into_iter().map(str::to_string).collect();
+                metadata.content_type = content_type.map(|i| CONTENT_TYPES[i].to_string());
+                let mut doc = Document::new(Vector::new(vec![1.0]), String::new(), metadata);
+                doc.created_at = at(created);
+                doc
+            })
+    }
+
+    fn filter() -> impl Strategy<Value = SearchFilter> {
+        (
+            proptest::option::of(0..2usize),
+            proptest::option::of(0..2usize),
+            proptest::sample::subsequence(vec![0usize, 1], 0..=2),
+            proptest::option::of(0..2usize),
+            proptest::sample::subsequence(TAGS.to_vec(), 0..=TAGS.len()),
+            any::<bool>(),
+            proptest::option::of(0..2usize),
+            proptest::option::of(-5_000i64..5_000),
+            proptest::option::of(-5_000i64..5_000),
+        )
+            .prop_map(
+                |(tenant, room, any_room, user, tags, any_tag, content_type, start, end)| {
+                    let mut builder = FilterBuilder::new();
+                    if let Some(i) = tenant {
+                        builder = builder.tenant(TENANTS[i]);
+                    }
+                    if let Some(i) = room {
+                        builder = builder.room(rooms()[i].clone());
+                    }
+                    builder = builder.any_room(any_room.into_iter().map(|i| rooms()[i].clone()));
+                    if let Some(i) = user {
+                        builder = builder.user(users()[i]);
+                    }
+                    builder = if any_tag {
+                        builder.any_tag(tags)
+                    } else {
+                        builder.all_tags(tags)
+                    };
+                    if let Some(i) = content_type {
+                        builder = builder.content_type(CONTENT_TYPES[i]);
+                    }
+                    let (start, end) = match (start, end) {
+                        (Some(start), Some(end)) => (Some(start.min(end)), Some(start.max(end))),
+                        bounds => bounds,
+                    };
+                    if let Some(start) = start {
+                        builder = builder.created_since(at(start));
+                    }
+                    if let Some(end) = end {
+                        builder = builder.created_until(at(end));
+                    }
+                    builder.build().unwrap()
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn in_memory_matching_agrees_with_qdrant_conditions(
+            filter in filter(),
+            docs in proptest::collection::vec(document(), 1..16),
+        ) {
+            let conditions = filter.conditions();
+            for doc in &docs {
+                let payload = payload(doc);
+                let qdrant = conditions
+                    .iter()
+                    .all(|condition| qdrant_matches(condition, &payload));
+                prop_assert_eq!(filter.matches(doc), qdrant, "document {:?}", doc.metadata);
+            }
+        }
+    }
+
+    #[test]
+    fn tags_require_all_unless_any_is_asked_for() {
+        let doc = Document::new(
+            Vector::new(vec![1.0]),
+            String::new(),
+            DocumentMetadata::new().with_tag("a"),
+        );
+
+        let all = FilterBuilder::new().all_tags(["a", "b"]).build().unwrap();
+        assert!(!all.matches(&doc));
+        let any = FilterBuilder::new().any_tag(["a", "b"]).build().unwrap();
+        assert!(any.matches(&doc));
+    }
+
+    #[test]
+    fn time_range_bounds_are_inclusive() {
+        let mut doc = Document::new(
+            Vector::new(vec![1.0]),
+            String::new(),
+            DocumentMetadata::new(),
+        );
+        doc.created_at = at(0);
+
+        let exact = FilterBuilder::new()
+            .created_between(at(0), at(0))
+            .build()
+            .unwrap();
+        assert!(exact.matches(&doc));
+        let after = FilterBuilder::new().created_since(at(1)).build().unwrap();
+        assert!(!after.matches(&doc));
+    }
+
+    #[test]
+    fn build_rejects_invalid_filters() {
+        let inverted = FilterBuilder::new().created_between(at(1), at(0)).build();
+        assert!(matches!(inverted, Err(VectorError::InvalidQuery { .. })));
+
+        let mixed = FilterBuilder::new().tag("a").any_tag(["b"]).build();
+        assert!(matches!(mixed, Err(VectorError::InvalidQuery { .. })));
+
+        let empty_tag = FilterBuilder::new().tag("").build();
+        assert!(matches!(empty_tag, Err(VectorError::InvalidQuery { .. })));
+
+        let empty_tenant = FilterBuilder::new().tenant("").build();
+        assert!(matches!(
+            empty_tenant,
+            Err(VectorError::InvalidQuery { .. })
+        ));
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! This crate provides:
 //! - `VectorStore` trait for abstracting vector storage backends
-//! - In-memory vector store for testing
+//! - In-memory vector store for testing, with optional scalar quantization
 //! - Qdrant integration (optional, feature-gated)
 //! - Semantic search capabilities
 //!
@@ -31,6 +31,8 @@
 //! ```
 
 pub mod error;
+pub mod filter;
+pub mod quantization;
 pub mod store;
 pub mod types;
 
@@ -38,23 +40,27 @@ pub mod types;
 pub mod qdrant;
 
 pub use error::{VectorError, VectorResult};
+pub use filter::{FilterBuilder, FilterCondition};
+pub use quantization::ScalarQuantization;
 pub use store::{InMemoryVectorStore, VectorStore};
 pub use types::{
-    BatchResult, Document, DocumentMetadata, SearchFilter, SearchQuery, SearchResult, TimeRange,
-    Vector,
+    BatchResult, DistanceMetric, Document, DocumentMetadata, SearchFilter, SearchQuery,
+    SearchResult, TagMatch, TimeRange, Vector,
 };
 
 #[cfg(feature = "qdrant")]
-pub use qdrant::{QdrantConfig, QdrantVectorStore};
+pub use qdrant::{QdrantCollections, QdrantConfig, QdrantVectorStore};
 
 /// Prelude for common imports
 pub mod prelude {
     pub use crate::error::{VectorError, VectorResult};
+    pub use crate::filter::FilterBuilder;
     pub use crate::store::VectorStore;
     pub use crate::types::{
-        BatchResult, Document, DocumentMetadata, SearchFilter, SearchQuery, SearchResult, Vector,
+        BatchResult, DistanceMetric, Document, DocumentMetadata, SearchFilter, SearchQuery,
+        SearchResult, Vector,
     };
 
     #[cfg(feature = "qdrant")]
-    pub use crate::qdrant::{QdrantConfig, QdrantVectorStore};
+    pub use crate::qdrant::{QdrantCollections, QdrantConfig, QdrantVectorStore};
 }
@@ -2,9 +2,11 @@
 
 use async_trait::async_trait;
 use qdrant_client::qdrant::{
-    point_id::PointIdOptions, vectors_output::VectorsOptions, Condition, CreateCollectionBuilder,
-    DeletePointsBuilder, Distance, GetPointsBuilder, PointId, PointStruct, QueryPointsBuilder,
-    RetrievedPoint, UpsertPointsBuilder, VectorParamsBuilder,
+    point_id::PointIdOptions, vectors_config::Config as VectorsConfigKind,
+    vectors_output::VectorsOptions, Condition, CountPointsBuilder, CreateCollectionBuilder,
+    DeletePointsBuilder, Distance, GetPointsBuilder, PointId, PointStruct,
+    QuantizationSearchParamsBuilder, QuantizationType, QueryPointsBuilder, RetrievedPoint,
+    ScalarQuantizationBuilder, SearchParamsBuilder, UpsertPointsBuilder, VectorParamsBuilder,
 };
 use qdrant_client::{Payload, Qdrant};
 use std::collections::HashMap;
@@ -13,8 +15,13 @@ use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 use crate::error::{VectorError, VectorResult};
-use crate::store::VectorStore;
-use crate::types::{BatchResult, Document, DocumentMetadata, SearchQuery, SearchResult, Vector};
+use crate::filter::FilterCondition;
+use crate::quantization::ScalarQuantization;
+use crate::store::{validate_delete_filter, VectorStore};
+use crate::types::{
+    BatchResult, DistanceMetric, Document, DocumentMetadata, SearchFilter, SearchQuery,
+    SearchResult, Vector,
+};
 
 /// Configuration for Qdrant connection
 #[derive(Debug, Clone)]
@@ -25,10 +32,17 @@ pub struct QdrantConfig {
     pub collection_name: String,
     /// Vector dimension
     pub dimension: usize,
+    /// Distance metric of the collection
+    pub metric: DistanceMetric,
     /// API key (optional)
     pub api_key: Option<String>,
     /// Connection timeout in seconds
     pub timeout_secs: u64,
+    /// Scalar quantization for new collections and searches (optional)
+    ///
+    /// Qdrant keeps every original vector, so only `oversampling` applies;
+    /// `full_precision_fraction` is an in-memory store setting.
+    pub quantization: Option<ScalarQuantization>,
 }
 
 impl Default for QdrantConfig {
@@ -37,8 +51,10 @@ impl Default for QdrantConfig {
             url: "http://localhost:6334".to_string(),
             collection_name: "nexis_vectors".to_string(),
             dimension: 1536,
+            metric: DistanceMetric::default(),
             api_key: None,
             timeout_secs: 30,
+            quantization: None,
         }
     }
 }
@@ -69,6 +85,18 @@ impl QdrantConfig {
         self.timeout_secs = timeout_secs;
         self
     }
+
+    /// Set distance metric
+    pub fn with_metric(mut self, metric: DistanceMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Enable scalar quantization
+    pub fn with_quantization(mut self, quantization: ScalarQuantization) -> Self {
+        self.quantization = Some(quantization);
+        self
+    }
 }
 
 /// Qdrant vector store implementation
@@ -77,21 +105,30 @@ pub struct QdrantVectorStore {
     config: QdrantConfig,
 }
 
-impl QdrantVectorStore {
-    /// Create a new Qdrant vector store
-    pub async fn new(config: QdrantConfig) -> VectorResult<Self> {
-        let mut builder = Qdrant::from_url(&config.url);
+/// Build a client for the server `config` points at
+fn connect(config: &QdrantConfig) -> VectorResult<Qdrant> {
+    let mut builder = Qdrant::from_url(&config.url);
 
-        if let Some(ref api_key) = config.api_key {
-            builder = builder.api_key(api_key.clone());
-        }
+    if let Some(ref api_key) = config.api_key {
+        builder = builder.api_key(api_key.clone());
+    }
+
+    builder = builder.timeout(Duration::from_secs(config.timeout_secs));
 
-        builder = builder.timeout(Duration::from_secs(config.timeout_secs));
+    builder
+        .build()
+        .map_err(|e| VectorError::connection(e.to_string()))
+}
 
-        let client = builder
-            .build()
-            .map_err(|e| VectorError::connection(e.to_string()))?;
+impl QdrantVectorStore {
+    /// Create a new Qdrant vector store
+    pub async fn new(config: QdrantConfig) -> VectorResult<Self> {
+        let client = connect(&config)?;
+        Self::with_client(client, config).await
+    }
 
+    /// Open `config.collection_name` through an existing client
+    async fn with_client(client: Qdrant, config: QdrantConfig) -> VectorResult<Self> {
         let store = Self { client, config };
         store.ensure_collection().await?;
 
@@ -122,21 +159,65 @@ impl QdrantVectorStore {
         if !exists {
             info!(collection = %collection_name, dimension = self.config.dimension, "Creating Qdrant collection");
 
+            let mut collection = CreateCollectionBuilder::new(collection_name).vectors_config(
+                VectorParamsBuilder::new(
+                    self.config.dimension as u64,
+                    qdrant_distance(self.config.metric),
+                ),
+            );
+            if self.config.quantization.is_some() {
+                collection = collection.quantization_config(
+                    ScalarQuantizationBuilder::default()
+                        .r#type(QuantizationType::Int8.into())
+                        .always_ram(true),
+                );
+            }
+
             self.client
-                .create_collection(
-                    CreateCollectionBuilder::new(collection_name).vectors_config(
-                        VectorParamsBuilder::new(self.config.dimension as u64, Distance::Cosine),
-                    ),
-                )
+                .create_collection(collection)
                 .await
                 .map_err(|e| VectorError::backend("qdrant", e.to_string()))?;
 
             debug!(collection = %collection_name, "Collection created successfully");
+        } else {
+            self.check_collection_metric().await?;
         }
 
         Ok(())
     }
 
+    /// Fail if the existing collection compares vectors with another metric
+    async fn check_collection_metric(&self) -> VectorResult<()> {
+        let info = self
+            .client
+            .collection_info(&self.config.collection_name)
+            .await
+            .map_err(|e| VectorError::backend("qdrant", e.to_string()))?;
+
+        let distance = info
+            .result
+            .and_then(|info| info.config)
+            .and_then(|config| config.params)
+            .and_then(|params| params.vectors_config)
+            .and_then(|vectors| vectors.config)
+            .and_then(|config| match config {
+                VectorsConfigKind::Params(params) => Some(params.distance),
+                VectorsConfigKind::ParamsMap(_) => None,
+            });
+
+        match distance {
+            Some(distance) if distance != qdrant_distance(self.config.metric) as i32 => {
+                Err(VectorError::configuration(format!(
+                    "Collection {} uses {:?} distance, configured metric is {}",
+                    self.config.collection_name,
+                    Distance::try_from(distance).unwrap_or(Distance::UnknownDistance),
+                    self.config.metric
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Convert document to Qdrant point
     fn doc_to_point(&self, doc: &Document) -> VectorResult<PointStruct> {
         let id = doc.id.to_string();
@@ -146,8 +227,14 @@ impl QdrantVectorStore {
         payload.insert("content", doc.content.clone());
         payload.insert("created_at", doc.created_at.to_rfc3339());
         payload.insert("updated_at", doc.updated_at.to_rfc3339());
+        if let Some(expires_at) = doc.expires_at {
+            payload.insert("expires_at", expires_at.to_rfc3339());
+        }
 
-        if let Some(room_id) = doc.metadata.room_id {
+        if let Some(ref tenant_id) = doc.metadata.tenant_id {
+            payload.insert("tenant_id", tenant_id.clone());
+        }
+        if let Some(ref room_id) = doc.metadata.room_id {
             payload.insert("room_id", room_id.to_string());
         }
         if let Some(user_id) = doc.metadata.user_id {
@@ -156,7 +243,16 @@ impl QdrantVectorStore {
         if let Some(message_id) = doc.metadata.message_id {
             payload.insert("message_id", message_id.to_string());
         }
+        if let Some(parent_message_id) = doc.metadata.parent_message_id {
+            payload.insert("parent_message_id", parent_message_id.to_string());
+        }
+        if let Some(chunk_index) = doc.metadata.chunk_index {
+            payload.insert("chunk_index", i64::from(chunk_index));
+        }
         payload.insert("tags", doc.metadata.tags.clone());
+        if let Some(ref content_type) = doc.metadata.content_type {
+            payload.insert("content_type", content_type.clone());
+        }
 
         Ok(PointStruct::new(id, vector, payload))
     }
@@ -183,6 +279,19 @@ impl QdrantVectorStore {
         })
     }
 
+    /// Get integer value from payload
+    fn get_integer_value(
+        payload: &HashMap<String, qdrant_client::qdrant::Value>,
+        key: &str,
+    ) -> Option<i64> {
+        payload.get(key).and_then(|v| {
+            v.kind.as_ref().and_then(|k| match k {
+                qdrant_client::qdrant::value::Kind::IntegerValue(i) => Some(*i),
+                _ => None,
+            })
+        })
+    }
+
     /// Get list value from payload
     fn get_list_value(
         payload: &HashMap<String, qdrant_client::qdrant::Value>,
@@ -240,8 +349,9 @@ impl QdrantVectorStore {
 
         let content = Self::get_string_value(&payload, "content").unwrap_or_default();
 
-        let room_id =
-            Self::get_string_value(&payload, "room_id").and_then(|s| Uuid::parse_str(&s).ok());
+        let tenant_id = Self::get_string_value(&payload, "tenant_id");
+
+        let room_id = Self::get_string_value(&payload, "room_id").and_then(|s| s.parse().ok());
 
         let user_id =
             Self::get_string_value(&payload, "user_id").and_then(|s| Uuid::parse_str(&s).ok());
@@ -249,8 +359,16 @@ impl QdrantVectorStore {
         let message_id =
             Self::get_string_value(&payload, "message_id").and_then(|s| Uuid::parse_str(&s).ok());
 
+        let parent_message_id = Self::get_string_value(&payload, "parent_message_id")
+            .and_then(|s| Uuid::parse_str(&s).ok());
+
+        let chunk_index =
+            Self::get_integer_value(&payload, "chunk_index").and_then(|i| u32::try_from(i).ok());
+
         let tags = Self::get_list_value(&payload, "tags");
 
+        let content_type = Self::get_string_value(&payload, "content_type");
+
         let created_at = Self::get_string_value(&payload, "created_at")
             .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
             .map(|dt| dt.with_timezone(&chrono::Utc))
@@ -261,11 +379,19 @@ impl QdrantVectorStore {
             .map(|dt| dt.with_timezone(&chrono::Utc))
             .unwrap_or_else(chrono::Utc::now);
 
+        let expires_at = Self::get_string_value(&payload, "expires_at")
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&chrono::Utc));
+
         let metadata = DocumentMetadata {
+            tenant_id,
             room_id,
             user_id,
             message_id,
+            parent_message_id,
+            chunk_index,
             tags,
+            content_type,
             extra: HashMap::new(),
         };
 
@@ -276,12 +402,14 @@ impl QdrantVectorStore {
             content,
             created_at,
             updated_at,
+            expires_at,
         })
     }
 }
 
 #[async_trait]
 impl VectorStore for QdrantVectorStore {
+    #[tracing::instrument(name = "vector.qdrant.upsert", skip_all, fields(otel.kind = "client", id = %document.id))]
     async fn upsert(&self, document: Document) -> VectorResult<Uuid> {
         if document.vector.dimensions != self.config.dimension {
             return Err(VectorError::invalid_dimension(
@@ -305,6 +433,7 @@ impl VectorStore for QdrantVectorStore {
         Ok(id)
     }
 
+    #[tracing::instrument(name = "vector.qdrant.upsert_batch", skip_all, fields(otel.kind = "client", batch_size = documents.len()))]
     async fn upsert_batch(&self, documents: Vec<Document>) -> VectorResult<BatchResult> {
         let mut result = BatchResult::new();
         let mut points = Vec::with_capacity(documents.len());
@@ -399,6 +528,7 @@ impl VectorStore for QdrantVectorStore {
         Ok(docs)
     }
 
+    #[tracing::instrument(name = "vector.qdrant.delete", skip_all, fields(otel.kind = "client", id = %id))]
     async fn delete(&self, id: Uuid) -> VectorResult<()> {
         let point_ids = vec![PointId {
             point_id_options: Some(PointIdOptions::Uuid(id.to_string())),
@@ -434,8 +564,28 @@ impl VectorStore for QdrantVectorStore {
         Ok(result)
     }
 
+    #[tracing::instrument(name = "vector.qdrant.delete_by_filter", skip_all, fields(otel.kind = "client"))]
+    async fn delete_by_filter(&self, filter: &SearchFilter) -> VectorResult<usize> {
+        validate_delete_filter(filter)?;
+        let filter = self
+            .build_qdrant_filter(filter)
+            .ok_or_else(|| VectorError::invalid_query("delete_by_filter needs a condition"))?;
+        self.delete_matching(filter).await
+    }
+
+    #[tracing::instrument(name = "vector.qdrant.delete_expired", skip_all, fields(otel.kind = "client"))]
+    async fn delete_expired(&self, now: chrono::DateTime<chrono::Utc>) -> VectorResult<usize> {
+        self.delete_matching(qdrant_client::qdrant::Filter::must([expired_condition(
+            now,
+        )]))
+        .await
+    }
+
+    #[tracing::instrument(name = "vector.qdrant.search", skip_all, fields(otel.kind = "client", limit = query.limit))]
     async fn search(&self, query: SearchQuery) -> VectorResult<Vec<SearchResult>> {
-        query.validate().map_err(VectorError::invalid_query)?;
+        query
+            .validate_for(self.config.metric)
+            .map_err(VectorError::invalid_query)?;
 
         let mut query_builder = QueryPointsBuilder::new(&self.config.collection_name)
             .query(query.vector.data.clone())
@@ -447,12 +597,26 @@ impl VectorStore for QdrantVectorStore {
             query_builder = query_builder.score_threshold(min_score);
         }
 
-        if let Some(filter) = &query.filter {
-            if let Some(qdrant_filter) = self.build_qdrant_filter(filter) {
-                query_builder = query_builder.filter(qdrant_filter);
-            }
+        if let Some(ref quantization) = self.config.quantization {
+            query_builder = query_builder.params(
+                SearchParamsBuilder::default().quantization(
+                    QuantizationSearchParamsBuilder::default()
+                        .rescore(true)
+                        .oversampling(f64::from(quantization.oversampling)),
+                ),
+            );
         }
 
+        let mut qdrant_filter = query
+            .filter
+            .as_ref()
+            .and_then(|filter| self.build_qdrant_filter(filter))
+            .unwrap_or_default();
+        qdrant_filter
+            .must_not
+            .push(expired_condition(chrono::Utc::now()));
+        query_builder = query_builder.filter(qdrant_filter);
+
         let response = self
             .client
             .query(query_builder)
@@ -514,64 +678,159 @@ impl VectorStore for QdrantVectorStore {
         self.config.dimension
     }
 
+    fn metric(&self) -> DistanceMetric {
+        self.config.metric
+    }
+
     fn backend_name(&self) -> &'static str {
         "qdrant"
     }
 }
 
+/// Condition matching documents expired at `now`; documents without
+/// `expires_at` never match
+fn expired_condition(now: chrono::DateTime<chrono::Utc>) -> Condition {
+    Condition::datetime_range(
+        "expires_at",
+        qdrant_client::qdrant::DatetimeRange {
+            lte: Some(timestamp(now)),
+            ..Default::default()
+        },
+    )
+}
+
+fn timestamp(at: chrono::DateTime<chrono::Utc>) -> qdrant_client::qdrant::Timestamp {
+    qdrant_client::qdrant::Timestamp {
+        seconds: at.timestamp(),
+        nanos: at.timestamp_subsec_micros() as i32 * 1_000,
+    }
+}
+
+/// Opens, lists and drops the collections of one Qdrant server
+///
+/// Every collection shares the connection and the dimension, metric and
+/// quantization of the config it was created with.
+pub struct QdrantCollections {
+    client: Qdrant,
+    config: QdrantConfig,
+}
+
+impl QdrantCollections {
+    /// Connect to the server `config` points at; its `collection_name` is
+    /// not used
+    pub fn new(config: QdrantConfig) -> VectorResult<Self> {
+        let client = connect(&config)?;
+        Ok(Self { client, config })
+    }
+
+    /// Open the collection `name`, creating it if it doesn't exist
+    pub async fn open(&self, name: &str) -> VectorResult<QdrantVectorStore> {
+        let config = QdrantConfig {
+            collection_name: name.to_string(),
+            ..self.config.clone()
+        };
+        QdrantVectorStore::with_client(self.client.clone(), config).await
+    }
+
+    /// Names of the existing collections
+    pub async fn list(&self) -> VectorResult<Vec<String>> {
+        let collections = self
+            .client
+            .list_collections()
+            .await
+            .map_err(|e| VectorError::backend("qdrant", e.to_string()))?;
+        Ok(collections
+            .collections
+            .into_iter()
+            .map(|collection| collection.name)
+            .collect())
+    }
+
+    /// Drop the collection `name` with every point in it
+    pub async fn drop_collection(&self, name: &str) -> VectorResult<()> {
+        self.client
+            .delete_collection(name)
+            .await
+            .map_err(|e| VectorError::backend("qdrant", e.to_string()))?;
+        info!(collection = %name, "Qdrant collection dropped");
+        Ok(())
+    }
+}
+
+/// Qdrant collection distance for a metric
+fn qdrant_distance(metric: DistanceMetric) -> Distance {
+    match metric {
+        DistanceMetric::Cosine => Distance::Cosine,
+        DistanceMetric::Dot => Distance::Dot,
+        DistanceMetric::Euclidean => Distance::Euclid,
+    }
+}
+
 impl QdrantVectorStore {
+    /// Delete the points matching `filter`, returning how many there were
+    async fn delete_matching(&self, filter: qdrant_client::qdrant::Filter) -> VectorResult<usize> {
+        let matching = self
+            .client
+            .count(
+                CountPointsBuilder::new(&self.config.collection_name)
+                    .filter(filter.clone())
+                    .exact(true),
+            )
+            .await
+            .map_err(|e| VectorError::backend("qdrant", e.to_string()))?
+            .result
+            .map_or(0, |result| result.count as usize);
+        if matching == 0 {
+            return Ok(0);
+        }
+
+        self.client
+            .delete_points(
+                DeletePointsBuilder::new(&self.config.collection_name)
+                    .points(filter)
+                    .wait(true),
+            )
+            .await
+            .map_err(|e| VectorError::backend("qdrant", e.to_string()))?;
+
+        debug!(
+            deleted = matching,
+            "Documents deleted from Qdrant by filter"
+        );
+        Ok(matching)
+    }
+
     /// Build Qdrant filter from SearchFilter
+    ///
+    /// Translates [`crate::types::SearchFilter::conditions`] one to one into
+    /// `must` conditions, so results agree with the in-memory store.
     fn build_qdrant_filter(
         &self,
         filter: &crate::types::SearchFilter,
     ) -> Option<qdrant_client::qdrant::Filter> {
-        use qdrant_client::qdrant::{FieldCondition, Filter, Range};
-
-        let mut conditions: Vec<Condition> = Vec::new();
+        use qdrant_client::qdrant::{DatetimeRange, Filter};
 
-        if let Some(room_id) = filter.room_id {
-            conditions.push(Condition::matches("room_id", room_id.to_string()));
-        }
-
-        if let Some(user_id) = filter.user_id {
-            conditions.push(Condition::matches("user_id", user_id.to_string()));
-        }
-
-        for tag in &filter.tags {
-            conditions.push(Condition::matches("tags", tag.clone()));
-        }
-
-        if let Some(ref time_range) = filter.time_range {
-            let start_ts = time_range.start.timestamp();
-            let end_ts = time_range.end.timestamp();
-
-            conditions.push(Condition::from(FieldCondition {
-                key: "created_at".to_string(),
-                r#match: None,
-                range: Some(Range {
-                    gte: Some(start_ts as f64),
-                    lte: Some(end_ts as f64),
-                    ..Default::default()
-                }),
-                geo_bounding_box: None,
-                geo_radius: None,
-                values_count: None,
-                is_empty: None,
-                is_null: None,
-                geo_polygon: None,
-                datetime_range: None,
-            }));
-        }
+        let conditions: Vec<Condition> = filter
+            .conditions()
+            .into_iter()
+            .map(|condition| match condition {
+                FilterCondition::Keyword { key, value } => Condition::matches(key, value),
+                FilterCondition::AnyKeyword { key, values } => Condition::matches(key, values),
+                FilterCondition::DatetimeRange { key, gte, lte } => Condition::datetime_range(
+                    key,
+                    DatetimeRange {
+                        gte: gte.map(timestamp),
+                        lte: lte.map(timestamp),
+                        ..Default::default()
+                    },
+                ),
+            })
+            .collect();
 
         if conditions.is_empty() {
             None
         } else {
-            Some(Filter {
-                should: Vec::new(),
-                min_should: None,
-                must: conditions,
-                must_not: Vec::new(),
-            })
+            Some(Filter::must(conditions))
         }
     }
 }
@@ -588,6 +847,13 @@ mod tests {
         assert_eq!(config.dimension, 512);
     }
 
+    #[test]
+    fn test_metric_maps_to_qdrant_distance() {
+        assert_eq!(qdrant_distance(DistanceMetric::Cosine), Distance::Cosine);
+        assert_eq!(qdrant_distance(DistanceMetric::Dot), Distance::Dot);
+        assert_eq!(qdrant_distance(DistanceMetric::Euclidean), Distance::Euclid);
+    }
+
     fn qdrant_available() -> bool {
         std::env::var("NEXIS_QDRANT_URL").is_ok()
     }
@@ -616,4 +882,22 @@ mod tests {
 
         assert_eq!(doc.content, retrieved.content);
     }
+
+    #[tokio::test]
+    async fn test_qdrant_collections_open_list_and_drop() {
+        if !qdrant_available() {
+            eprintln!("Skipping Qdrant test: set NEXIS_QDRANT_URL to enable");
+            return;
+        }
+
+        let url = std::env::var("NEXIS_QDRANT_URL").unwrap();
+        let collections = QdrantCollections::new(QdrantConfig::new(&url, "unused", 3)).unwrap();
+        let name = format!("test_{}", Uuid::new_v4());
+
+        collections.open(&name).await.unwrap();
+        assert!(collections.list().await.unwrap().contains(&name));
+
+        collections.drop_collection(&name).await.unwrap();
+        assert!(!collections.list().await.unwrap().contains(&name));
+    }
 }
@@ -0,0 +1,208 @@
+//! Scalar vector quantization
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::types::DistanceMetric;
+
+/// Scalar (f32 → i8) quantization settings
+///
+/// Quantized vectors take a quarter of the memory of full-precision ones.
+/// Candidates are ranked on the quantized vectors, then the best
+/// `limit * oversampling` are re-scored on full precision where it was kept.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScalarQuantization {
+    /// Share of documents (0.0 to 1.0) that also keep their full-precision
+    /// vector for re-scoring
+    pub full_precision_fraction: f32,
+    /// Candidates re-scored per requested result (at least 1.0)
+    pub oversampling: f32,
+}
+
+impl Default for ScalarQuantization {
+    fn default() -> Self {
+        Self {
+            full_precision_fraction: 0.1,
+            oversampling: 2.0,
+        }
+    }
+}
+
+impl ScalarQuantization {
+    /// Create settings with the defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the share of documents that keep full precision
+    pub fn with_full_precision_fraction(mut self, fraction: f32) -> Self {
+        self.full_precision_fraction = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the candidates re-scored per requested result
+    pub fn with_oversampling(mut self, oversampling: f32) -> Self {
+        self.oversampling = oversampling.max(1.0);
+        self
+    }
+
+    /// Whether the document keeps its full-precision vector
+    ///
+    /// Decided by the document ID alone, so a document keeps the same
+    /// precision across upserts.
+    pub fn keeps_full_precision(&self, id: Uuid) -> bool {
+        const BUCKETS: u128 = 1_000_000;
+        let bucket = (id.as_u128() % BUCKETS) as f32 / BUCKETS as f32;
+        bucket < self.full_precision_fraction
+    }
+
+    /// Number of candidates to re-score for `wanted` results
+    pub(crate) fn rescore_count(&self, wanted: usize) -> usize {
+        (wanted as f32 * self.oversampling.max(1.0)).ceil() as usize
+    }
+}
+
+/// Vector quantized to i8 with one scale for all components
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct QuantizedVector {
+    data: Vec<i8>,
+    scale: f32,
+    norm: f32,
+}
+
+impl QuantizedVector {
+    /// Quantize `data`, mapping its largest magnitude to ±127
+    pub(crate) fn quantize(data: &[f32]) -> Self {
+        let max = data.iter().fold(0.0f32, |max, x| max.max(x.abs()));
+        let scale = if max == 0.0 { 1.0 } else { max / 127.0 };
+        let data: Vec<i8> = data
+            .iter()
+            .map(|x| (x / scale).round().clamp(-127.0, 127.0) as i8)
+            .collect();
+        let norm = data
+            .iter()
+            .map(|&x| f32::from(x) * f32::from(x))
+            .sum::<f32>()
+            .sqrt();
+        Self { data, scale, norm }
+    }
+
+    /// Approximate full-precision vector
+    pub(crate) fn dequantize(&self) -> Vec<f32> {
+        self.data
+            .iter()
+            .map(|&x| f32::from(x) * self.scale)
+            .collect()
+    }
+
+    /// Cosine similarity with a full-precision query
+    ///
+    /// The scale cancels out, so the i8 components are used as is.
+    pub(crate) fn cosine_similarity(&self, query: &[f32]) -> f32 {
+        if query.len() != self.data.len() {
+            return 0.0;
+        }
+        let dot: f32 = query
+            .iter()
+            .zip(&self.data)
+            .map(|(q, &x)| q * f32::from(x))
+            .sum();
+        let query_norm = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if query_norm == 0.0 || self.norm == 0.0 {
+            0.0
+        } else {
+            dot / (query_norm * self.norm)
+        }
+    }
+
+    /// Score of a full-precision query under `metric`
+    pub(crate) fn score(&self, metric: DistanceMetric, query: &[f32]) -> f32 {
+        match metric {
+            DistanceMetric::Cosine => self.cosine_similarity(query),
+            DistanceMetric::Dot if query.len() == self.data.len() => {
+                self.scale
+                    * query
+                        .iter()
+                        .zip(&self.data)
+                        .map(|(q, &x)| q * f32::from(x))
+                        .sum::<f32>()
+            }
+            DistanceMetric::Dot => 0.0,
+            DistanceMetric::Euclidean if query.len() == self.data.len() => query
+                .iter()
+                .zip(&self.data)
+                .map(|(q, &x)| (q - f32::from(x) * self.scale).powi(2))
+                .sum::<f32>()
+                .sqrt(),
+            DistanceMetric::Euclidean => f32::INFINITY,
+        }
+    }
+
+    /// Bytes held by the quantized components
+    pub(crate) fn size_bytes(&self) -> usize {
+        self.data.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantization_round_trips_within_one_step() {
+        let data = vec![0.5, -1.0, 0.25, 0.0, 0.9];
+        let quantized = QuantizedVector::quantize(&data);
+        let step = 1.0 / 127.0;
+
+        for (original, restored) in data.iter().zip(quantized.dequantize()) {
+            assert!((original - restored).abs() <= step);
+        }
+    }
+
+    #[test]
+    fn quantized_similarity_tracks_full_precision() {
+        let data = vec![0.3, -0.7, 0.1, 0.6];
+        let query = vec![0.2, -0.5, 0.4, 0.1];
+        let exact = crate::types::Vector::new(data.clone())
+            .cosine_similarity(&crate::types::Vector::new(query.clone()));
+
+        let approximate = QuantizedVector::quantize(&data).cosine_similarity(&query);
+        assert!((exact - approximate).abs() < 0.01);
+    }
+
+    #[test]
+    fn quantized_scores_track_every_metric() {
+        let data = vec![0.3, -0.7, 0.1, 0.6];
+        let query = vec![0.2, -0.5, 0.4, 0.1];
+        let quantized = QuantizedVector::quantize(&data);
+
+        for metric in [
+            DistanceMetric::Cosine,
+            DistanceMetric::Dot,
+            DistanceMetric::Euclidean,
+        ] {
+            let exact = metric.score(
+                &crate::types::Vector::new(data.clone()),
+                &crate::types::Vector::new(query.clone()),
+            );
+            assert!((exact - quantized.score(metric, &query)).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn zero_vectors_have_zero_similarity() {
+        let quantized = QuantizedVector::quantize(&[0.0, 0.0]);
+        assert_eq!(quantized.cosine_similarity(&[1.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn full_precision_fraction_bounds() {
+        let id = Uuid::new_v4();
+        assert!(ScalarQuantization::new()
+            .with_full_precision_fraction(1.0)
+            .keeps_full_precision(id));
+        assert!(!ScalarQuantization::new()
+            .with_full_precision_fraction(0.0)
+            .keeps_full_precision(id));
+    }
+}
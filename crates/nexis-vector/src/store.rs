@@ -1,13 +1,17 @@
 //! Vector store trait and implementations
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::error::{VectorError, VectorResult};
-use crate::types::{BatchResult, Document, SearchQuery, SearchResult};
+use crate::quantization::{QuantizedVector, ScalarQuantization};
+use crate::types::{
+    BatchResult, DistanceMetric, Document, SearchFilter, SearchQuery, SearchResult, Vector,
+};
 
 /// Vector store abstraction
 #[async_trait]
@@ -30,6 +34,16 @@ pub trait VectorStore: Send + Sync {
     /// Delete multiple documents by ID
     async fn delete_batch(&self, ids: Vec<Uuid>) -> VectorResult<BatchResult>;
 
+    /// Delete every document matching `filter`, returning how many were
+    /// deleted
+    ///
+    /// An empty filter is rejected rather than emptying the store.
+    async fn delete_by_filter(&self, filter: &SearchFilter) -> VectorResult<usize>;
+
+    /// Delete every document that has expired at `now`, returning how many
+    /// were deleted
+    async fn delete_expired(&self, now: DateTime<Utc>) -> VectorResult<usize>;
+
     /// Search for similar documents
     async fn search(&self, query: SearchQuery) -> VectorResult<Vec<SearchResult>>;
 
@@ -42,14 +56,83 @@ pub trait VectorStore: Send + Sync {
     /// Get the dimension of vectors in this store
     fn dimension(&self) -> usize;
 
+    /// Get the metric this store compares vectors with
+    fn metric(&self) -> DistanceMetric;
+
     /// Get the name of this store backend
     fn backend_name(&self) -> &'static str;
 }
 
 /// In-memory vector store for testing and development
+///
+/// With [`ScalarQuantization`] enabled, vectors are stored as i8 and only a
+/// share of documents keeps the f32 original; documents read back without one
+/// carry the dequantized approximation.
 pub struct InMemoryVectorStore {
-    documents: Arc<RwLock<HashMap<Uuid, Document>>>,
+    documents: Arc<RwLock<HashMap<Uuid, StoredDocument>>>,
     dimension: usize,
+    metric: DistanceMetric,
+    quantization: Option<ScalarQuantization>,
+}
+
+/// Document as held by [`InMemoryVectorStore`]
+struct StoredDocument {
+    /// The document; its vector data is empty when only `quantized` is kept
+    document: Document,
+    quantized: Option<QuantizedVector>,
+}
+
+impl StoredDocument {
+    fn new(mut document: Document, quantization: Option<&ScalarQuantization>) -> Self {
+        let Some(quantization) = quantization else {
+            return Self {
+                document,
+                quantized: None,
+            };
+        };
+        let quantized = QuantizedVector::quantize(&document.vector.data);
+        if !quantization.keeps_full_precision(document.id) {
+            document.vector.data = Vec::new();
+        }
+        Self {
+            document,
+            quantized: Some(quantized),
+        }
+    }
+
+    fn has_full_precision(&self) -> bool {
+        self.document.vector.data.len() == self.document.vector.dimensions
+    }
+
+    fn to_document(&self) -> Document {
+        let mut document = self.document.clone();
+        if let (false, Some(quantized)) = (self.has_full_precision(), &self.quantized) {
+            document.vector.data = quantized.dequantize();
+        }
+        document
+    }
+
+    /// Score on the quantized vector when there is one
+    fn approximate_score(&self, metric: DistanceMetric, query: &Vector) -> f32 {
+        match &self.quantized {
+            Some(quantized) => quantized.score(metric, &query.data),
+            None => metric.score(query, &self.document.vector),
+        }
+    }
+
+    /// Score on the full-precision vector, if it was kept
+    fn exact_score(&self, metric: DistanceMetric, query: &Vector) -> Option<f32> {
+        self.has_full_precision()
+            .then(|| metric.score(query, &self.document.vector))
+    }
+
+    fn vector_bytes(&self) -> usize {
+        self.document.vector.data.len() * std::mem::size_of::<f32>()
+            + self
+                .quantized
+                .as_ref()
+                .map_or(0, QuantizedVector::size_bytes)
+    }
 }
 
 impl InMemoryVectorStore {
@@ -58,13 +141,46 @@ impl InMemoryVectorStore {
         Self {
             documents: Arc::new(RwLock::new(HashMap::new())),
             dimension,
+            metric: DistanceMetric::default(),
+            quantization: None,
         }
     }
 
+    /// Compare vectors with `metric` instead of cosine similarity
+    pub fn with_metric(mut self, metric: DistanceMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Quantize vectors stored from now on
+    pub fn with_quantization(mut self, quantization: ScalarQuantization) -> Self {
+        self.quantization = Some(quantization);
+        self
+    }
+
+    /// Quantization settings, if enabled
+    pub fn quantization(&self) -> Option<&ScalarQuantization> {
+        self.quantization.as_ref()
+    }
+
     /// Clear all documents
     pub async fn clear(&self) {
         self.documents.write().await.clear();
     }
+
+    /// Bytes held by stored vectors, full-precision and quantized
+    pub async fn vector_memory_bytes(&self) -> usize {
+        self.documents
+            .read()
+            .await
+            .values()
+            .map(StoredDocument::vector_bytes)
+            .sum()
+    }
+
+    fn store(&self, document: Document) -> StoredDocument {
+        StoredDocument::new(document, self.quantization.as_ref())
+    }
 }
 
 impl Default for InMemoryVectorStore {
@@ -75,6 +191,7 @@ impl Default for InMemoryVectorStore {
 
 #[async_trait]
 impl VectorStore for InMemoryVectorStore {
+    #[tracing::instrument(name = "vector.memory.upsert", skip_all, fields(id = %document.id))]
     async fn upsert(&self, document: Document) -> VectorResult<Uuid> {
         if document.vector.dimensions != self.dimension {
             return Err(VectorError::invalid_dimension(
@@ -83,10 +200,12 @@ impl VectorStore for InMemoryVectorStore {
             ));
         }
         let id = document.id;
-        self.documents.write().await.insert(id, document);
+        let stored = self.store(document);
+        self.documents.write().await.insert(id, stored);
         Ok(id)
     }
 
+    #[tracing::instrument(name = "vector.memory.upsert_batch", skip_all, fields(batch_size = documents.len()))]
     async fn upsert_batch(&self, documents: Vec<Document>) -> VectorResult<BatchResult> {
         let mut result = BatchResult::new();
         let mut docs = self.documents.write().await;
@@ -102,7 +221,7 @@ impl VectorStore for InMemoryVectorStore {
                 );
             } else {
                 result.add_success(doc.id);
-                docs.insert(doc.id, doc);
+                docs.insert(doc.id, self.store(doc));
             }
         }
 
@@ -114,7 +233,7 @@ impl VectorStore for InMemoryVectorStore {
             .read()
             .await
             .get(&id)
-            .cloned()
+            .map(StoredDocument::to_document)
             .ok_or_else(|| VectorError::not_found(id))
     }
 
@@ -123,12 +242,13 @@ impl VectorStore for InMemoryVectorStore {
         let mut result = Vec::with_capacity(ids.len());
         for id in ids {
             if let Some(doc) = docs.get(&id) {
-                result.push(doc.clone());
+                result.push(doc.to_document());
             }
         }
         Ok(result)
     }
 
+    #[tracing::instrument(name = "vector.memory.delete", skip_all, fields(id = %id))]
     async fn delete(&self, id: Uuid) -> VectorResult<()> {
         self.documents
             .write()
@@ -153,30 +273,70 @@ impl VectorStore for InMemoryVectorStore {
         Ok(result)
     }
 
+    #[tracing::instrument(name = "vector.memory.delete_by_filter", skip_all)]
+    async fn delete_by_filter(&self, filter: &SearchFilter) -> VectorResult<usize> {
+        validate_delete_filter(filter)?;
+        let mut docs = self.documents.write().await;
+        let before = docs.len();
+        docs.retain(|_, doc| !filter.matches(&doc.document));
+        Ok(before - docs.len())
+    }
+
+    #[tracing::instrument(name = "vector.memory.delete_expired", skip_all)]
+    async fn delete_expired(&self, now: DateTime<Utc>) -> VectorResult<usize> {
+        let mut docs = self.documents.write().await;
+        let before = docs.len();
+        docs.retain(|_, doc| !doc.document.is_expired(now));
+        Ok(before - docs.len())
+    }
+
+    #[tracing::instrument(name = "vector.memory.search", skip_all, fields(limit = query.limit))]
     async fn search(&self, query: SearchQuery) -> VectorResult<Vec<SearchResult>> {
-        query.validate().map_err(VectorError::invalid_query)?;
+        query
+            .validate_for(self.metric)
+            .map_err(VectorError::invalid_query)?;
 
+        let metric = self.metric;
+        let now = Utc::now();
         let docs = self.documents.read().await;
-        let mut results: Vec<SearchResult> = docs
+        let mut candidates: Vec<(&StoredDocument, f32)> = docs
             .values()
-            .filter(|doc| query.filter.as_ref().is_none_or(|f| f.matches(doc)))
-            .map(|doc| {
-                let score = query.vector.cosine_similarity(&doc.vector);
-                SearchResult::new(doc.clone(), score)
+            .filter(|doc| {
+                !doc.document.is_expired(now)
+                    && query
+                        .filter
+                        .as_ref()
+                        .is_none_or(|f| f.matches(&doc.document))
             })
-            .filter(|r| query.min_score.is_none_or(|min| r.score >= min))
+            .map(|doc| (doc, doc.approximate_score(metric, &query.vector)))
             .collect();
-
-        results.sort_by(|a, b| {
-            b.score
-                .partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-
-        if query.offset > 0 {
-            results = results.into_iter().skip(query.offset).collect();
+        sort_by_score(metric, &mut candidates);
+
+        // Quantized scores only rank; the best candidates are re-scored on
+        // full precision where it was kept.
+        if let Some(ref quantization) = self.quantization {
+            let rescored = quantization
+                .rescore_count(query.offset + query.limit)
+                .min(candidates.len());
+            for (doc, score) in &mut candidates[..rescored] {
+                if let Some(exact) = doc.exact_score(metric, &query.vector) {
+                    *score = exact;
+                }
+            }
+            sort_by_score(metric, &mut candidates[..rescored]);
         }
-        results.truncate(query.limit);
+
+        let results = candidates
+            .into_iter()
+            .filter(|(_, score)| {
+                query
+                    .min_score
+                    .is_none_or(|min| metric.meets_threshold(*score, min))
+            })
+            .skip(query.offset)
+            .take(query.limit)
+            .map(|(doc, score)| SearchResult::new(doc.to_document(), score))
+            .collect();
 
         Ok(results)
     }
@@ -193,16 +353,36 @@ impl VectorStore for InMemoryVectorStore {
         self.dimension
     }
 
+    fn metric(&self) -> DistanceMetric {
+        self.metric
+    }
+
     fn backend_name(&self) -> &'static str {
         "in-memory"
     }
 }
 
+fn sort_by_score<T>(metric: DistanceMetric, candidates: &mut [(T, f32)]) {
+    candidates.sort_by(|a, b| metric.compare(a.1, b.1));
+}
+
+/// Check a filter handed to [`VectorStore::delete_by_filter`]
+pub(crate) fn validate_delete_filter(filter: &SearchFilter) -> VectorResult<()> {
+    filter.validate().map_err(VectorError::invalid_query)?;
+    if filter.is_empty() {
+        return Err(VectorError::invalid_query(
+            "delete_by_filter needs at least one condition",
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{DocumentMetadata, SearchFilter, Vector};
+    use crate::types::DocumentMetadata;
     use chrono::{Duration, Utc};
+    use nexis_protocol::RoomId;
 
     fn create_test_doc(content: &str, vector_data: Vec<f32>) -> Document {
         Document::new(
@@ -270,12 +450,12 @@ mod tests {
     #[tokio::test]
     async fn test_search_with_filter() {
         let store = InMemoryVectorStore::new(3);
-        let room_id = Uuid::new_v4();
+        let room_id = RoomId::generate();
 
         let doc1 = Document::new(
             Vector::new(vec![1.0, 0.0, 0.0]),
             "first".to_string(),
-            DocumentMetadata::new().with_room(room_id),
+            DocumentMetadata::new().with_room(room_id.clone()),
         );
         let doc2 = Document::new(
             Vector::new(vec![1.0, 0.1, 0.0]),
@@ -294,6 +474,55 @@ mod tests {
         assert_eq!(results[0].document.content, "first");
     }
 
+    #[tokio::test]
+    async fn test_search_with_content_type_filter() {
+        let store = InMemoryVectorStore::new(3);
+
+        let code = Document::new(
+            Vector::new(vec![1.0, 0.0, 0.0]),
+            "fn main() {}".to_string(),
+            DocumentMetadata::new().with_content_type("code"),
+        );
+        let text = Document::new(
+            Vector::new(vec![1.0, 0.0, 0.0]),
+            "hello".to_string(),
+            DocumentMetadata::new().with_content_type("text"),
+        );
+
+        store.upsert(code).await.unwrap();
+        store.upsert(text).await.unwrap();
+
+        let filter = SearchFilter::new().with_content_type("code");
+        let query = SearchQuery::new(Vector::new(vec![1.0, 0.0, 0.0])).with_filter(filter);
+
+        let results = store.search(query).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.content, "fn main() {}");
+    }
+
+    #[tokio::test]
+    async fn test_search_with_tenant_filter() {
+        let store = InMemoryVectorStore::new(3);
+
+        for tenant in ["acme", "globex"] {
+            store
+                .upsert(Document::new(
+                    Vector::new(vec![1.0, 0.0, 0.0]),
+                    format!("{tenant} secret"),
+                    DocumentMetadata::new().with_tenant(tenant),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let filter = SearchFilter::new().with_tenant("acme");
+        let query = SearchQuery::new(Vector::new(vec![1.0, 0.0, 0.0])).with_filter(filter);
+
+        let results = store.search(query).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.content, "acme secret");
+    }
+
     #[tokio::test]
     async fn test_search_with_min_score() {
         let store = InMemoryVectorStore::new(3);
@@ -335,6 +564,18 @@ mod tests {
         assert_eq!(results.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_search_rejects_inverted_time_range() {
+        let store = InMemoryVectorStore::new(3);
+
+        let now = Utc::now();
+        let filter = SearchFilter::new().with_time_range(now, now - Duration::hours(1));
+        let query = SearchQuery::new(Vector::new(vec![1.0, 0.0, 0.0])).with_filter(filter);
+
+        let err = store.search(query).await.unwrap_err();
+        assert!(matches!(err, VectorError::InvalidQuery { .. }));
+    }
+
     #[tokio::test]
     async fn test_search_pagination() {
         let store = InMemoryVectorStore::new(3);
@@ -354,6 +595,124 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_quantization_cuts_vector_memory() {
+        let full = InMemoryVectorStore::new(64);
+        let quantized = InMemoryVectorStore::new(64)
+            .with_quantization(ScalarQuantization::new().with_full_precision_fraction(0.0));
+
+        for i in 0..10 {
+            let data: Vec<f32> = (0..64).map(|j| ((i * 64 + j) as f32).sin()).collect();
+            let doc = create_test_doc(&format!("doc{i}"), data);
+            full.upsert(doc.clone()).await.unwrap();
+            quantized.upsert(doc).await.unwrap();
+        }
+
+        assert_eq!(
+            full.vector_memory_bytes().await,
+            4 * quantized.vector_memory_bytes().await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quantized_get_returns_approximate_vector() {
+        let store = InMemoryVectorStore::new(3)
+            .with_quantization(ScalarQuantization::new().with_full_precision_fraction(0.0));
+        let id = store
+            .upsert(create_test_doc("doc", vec![0.5, -1.0, 0.3]))
+            .await
+            .unwrap();
+
+        let vector = store.get(id).await.unwrap().vector;
+        assert_eq!(vector.data.len(), 3);
+        for (original, restored) in [0.5, -1.0, 0.3].iter().zip(&vector.data) {
+            assert!((original - restored).abs() <= 1.0 / 127.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quantized_search_rescores_with_full_precision() {
+        // Quantization rounds away the small components: "coarse" looks
+        // identical to the query, "fine" keeps a step and ranks behind it,
+        // though at full precision "fine" is the closer one.
+        let docs = [
+            create_test_doc("coarse", vec![1.0, 0.0039, 0.0039]),
+            create_test_doc("fine", vec![1.0, 0.004, 0.0]),
+        ];
+        let query = SearchQuery::new(Vector::new(vec![1.0, 0.0, 0.0])).with_limit(1);
+
+        let approximate = InMemoryVectorStore::new(3)
+            .with_quantization(ScalarQuantization::new().with_full_precision_fraction(0.0));
+        let rescored = InMemoryVectorStore::new(3)
+            .with_quantization(ScalarQuantization::new().with_full_precision_fraction(1.0));
+        for doc in docs {
+            approximate.upsert(doc.clone()).await.unwrap();
+            rescored.upsert(doc).await.unwrap();
+        }
+
+        let results = approximate.search(query.clone()).await.unwrap();
+        assert_eq!(results[0].document.content, "coarse");
+
+        let results = rescored.search(query).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.content, "fine");
+        let exact =
+            Vector::new(vec![1.0, 0.0, 0.0]).cosine_similarity(&Vector::new(vec![1.0, 0.004, 0.0]));
+        assert_eq!(results[0].score, exact);
+    }
+
+    #[tokio::test]
+    async fn test_search_with_euclidean_metric() {
+        let store = InMemoryVectorStore::new(2).with_metric(DistanceMetric::Euclidean);
+        store
+            .upsert(create_test_doc("near", vec![1.0, 1.0]))
+            .await
+            .unwrap();
+        store
+            .upsert(create_test_doc("far", vec![4.0, 5.0]))
+            .await
+            .unwrap();
+
+        let query = SearchQuery::new(Vector::new(vec![1.0, 2.0]));
+        let results = store.search(query.clone()).await.unwrap();
+        assert_eq!(results[0].document.content, "near");
+        assert_eq!(results[0].score, 1.0);
+        assert_eq!(results[1].score, 4.242_640_7);
+
+        let results = store.search(query.with_min_score(2.0)).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.content, "near");
+    }
+
+    #[tokio::test]
+    async fn test_search_with_dot_metric_favors_magnitude() {
+        let store = InMemoryVectorStore::new(2).with_metric(DistanceMetric::Dot);
+        store
+            .upsert(create_test_doc("aligned", vec![1.0, 0.0]))
+            .await
+            .unwrap();
+        store
+            .upsert(create_test_doc("long", vec![3.0, 3.0]))
+            .await
+            .unwrap();
+
+        let query = SearchQuery::new(Vector::new(vec![1.0, 0.0])).with_min_score(2.0);
+        let results = store.search(query).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.content, "long");
+        assert_eq!(results[0].score, 3.0);
+    }
+
+    #[tokio::test]
+    async fn test_search_rejects_mismatched_metric() {
+        let store = InMemoryVectorStore::new(3);
+        let query =
+            SearchQuery::new(Vector::new(vec![1.0, 0.0, 0.0])).with_metric(DistanceMetric::Dot);
+
+        let err = store.search(query).await.unwrap_err();
+        assert!(matches!(err, VectorError::InvalidQuery { .. }));
+    }
+
     #[tokio::test]
     async fn test_delete() {
         let store = InMemoryVectorStore::new(3);
@@ -380,6 +739,45 @@ mod tests {
         assert!(store.get(id2).await.is_err());
     }
 
+    #[tokio::test]
+    async fn test_delete_by_filter() {
+        let store = InMemoryVectorStore::new(3);
+        let room_a = RoomId::generate();
+        let room_b = RoomId::generate();
+        for room in [&room_a, &room_a, &room_b] {
+            let doc = Document::new(
+                Vector::new(vec![1.0, 0.0, 0.0]),
+                "doc".to_string(),
+                DocumentMetadata::new().with_room(room.clone()),
+            );
+            store.upsert(doc).await.unwrap();
+        }
+
+        let filter = SearchFilter::new().with_room(room_a);
+        assert_eq!(store.delete_by_filter(&filter).await.unwrap(), 2);
+        assert_eq!(store.count().await.unwrap(), 1);
+        assert!(store.delete_by_filter(&SearchFilter::new()).await.is_err());
+        assert_eq!(store.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_expired_documents_are_hidden_and_deleted() {
+        let store = InMemoryVectorStore::new(3);
+        let expired = create_test_doc("expired", vec![1.0, 0.0, 0.0])
+            .with_expires_at(Utc::now() - Duration::seconds(1));
+        let fresh = create_test_doc("fresh", vec![1.0, 0.0, 0.0]).with_ttl(Duration::days(1));
+        store.upsert(expired).await.unwrap();
+        store.upsert(fresh).await.unwrap();
+
+        let query = SearchQuery::new(Vector::new(vec![1.0, 0.0, 0.0]));
+        let results = store.search(query).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.content, "fresh");
+
+        assert_eq!(store.delete_expired(Utc::now()).await.unwrap(), 1);
+        assert_eq!(store.count().await.unwrap(), 1);
+    }
+
     #[tokio::test]
     async fn test_count_and_exists() {
         let store = InMemoryVectorStore::new(3);
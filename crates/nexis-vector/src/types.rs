@@ -1,6 +1,7 @@
 //! Core types for vector storage
 
 use chrono::{DateTime, Utc};
+use nexis_protocol::RoomId;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -38,6 +39,27 @@ impl Vector {
         }
     }
 
+    /// Dot product with another vector
+    pub fn dot(&self, other: &Vector) -> f32 {
+        if self.dimensions != other.dimensions {
+            return 0.0;
+        }
+        self.data.iter().zip(&other.data).map(|(a, b)| a * b).sum()
+    }
+
+    /// Euclidean distance to another vector
+    pub fn euclidean_distance(&self, other: &Vector) -> f32 {
+        if self.dimensions != other.dimensions {
+            return f32::INFINITY;
+        }
+        self.data
+            .iter()
+            .zip(&other.data)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f32>()
+            .sqrt()
+    }
+
     /// Validate vector dimensions
     pub fn validate(&self) -> Result<(), String> {
         if self.data.is_empty() {
@@ -54,17 +76,91 @@ impl Vector {
     }
 }
 
+/// How vectors are compared
+///
+/// Matches Qdrant's collection distances: cosine and dot scores grow with
+/// similarity, the Euclidean score is the distance itself and shrinks with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceMetric {
+    /// Cosine similarity, -1.0 to 1.0
+    #[default]
+    Cosine,
+    /// Dot product
+    Dot,
+    /// Euclidean distance
+    Euclidean,
+}
+
+impl DistanceMetric {
+    /// Score of `a` against `b`
+    pub fn score(self, a: &Vector, b: &Vector) -> f32 {
+        match self {
+            Self::Cosine => a.cosine_similarity(b),
+            Self::Dot => a.dot(b),
+            Self::Euclidean => a.euclidean_distance(b),
+        }
+    }
+
+    /// Order scores best first
+    pub fn compare(self, a: f32, b: f32) -> std::cmp::Ordering {
+        let ordering = match self {
+            Self::Cosine | Self::Dot => b.partial_cmp(&a),
+            Self::Euclidean => a.partial_cmp(&b),
+        };
+        ordering.unwrap_or(std::cmp::Ordering::Equal)
+    }
+
+    /// Whether `score` is at least as good as `threshold`
+    ///
+    /// For Euclidean the threshold is a maximum distance.
+    pub fn meets_threshold(self, score: f32, threshold: f32) -> bool {
+        match self {
+            Self::Cosine | Self::Dot => score >= threshold,
+            Self::Euclidean => score <= threshold,
+        }
+    }
+
+    /// Name of the metric
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cosine => "cosine",
+            Self::Dot => "dot",
+            Self::Euclidean => "euclidean",
+        }
+    }
+}
+
+impl std::fmt::Display for DistanceMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Document metadata with typed fields
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct DocumentMetadata {
+    /// Tenant that owns this document (multi-tenant deployments)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
     /// Room ID this document belongs to
-    pub room_id: Option<Uuid>,
+    pub room_id: Option<RoomId>,
     /// User ID who created the document
     pub user_id: Option<Uuid>,
     /// Message ID if derived from a message
     pub message_id: Option<Uuid>,
+    /// Message this document is one chunk of, for long messages split
+    /// before embedding; the id of the message's first chunk
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_message_id: Option<Uuid>,
+    /// Position of this chunk within its parent message, from zero
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_index: Option<u32>,
     /// Tags for categorization
     pub tags: Vec<String>,
+    /// Kind of content the document was derived from (e.g. "text", "code", "tool")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
     /// Custom metadata fields
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
@@ -76,8 +172,14 @@ impl DocumentMetadata {
         Self::default()
     }
 
+    /// Create metadata with tenant ID
+    pub fn with_tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
     /// Create metadata with room ID
-    pub fn with_room(mut self, room_id: Uuid) -> Self {
+    pub fn with_room(mut self, room_id: RoomId) -> Self {
         self.room_id = Some(room_id);
         self
     }
@@ -94,12 +196,25 @@ impl DocumentMetadata {
         self
     }
 
+    /// Mark as chunk `chunk_index` of the message `parent_message_id`
+    pub fn with_chunk(mut self, parent_message_id: Uuid, chunk_index: u32) -> Self {
+        self.parent_message_id = Some(parent_message_id);
+        self.chunk_index = Some(chunk_index);
+        self
+    }
+
     /// Add a tag
     pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
         self.tags.push(tag.into());
         self
     }
 
+    /// Set the content type
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
     /// Add custom field
     pub fn with_extra(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
         self.extra.insert(key.into(), value);
@@ -132,6 +247,10 @@ pub struct Document {
     pub created_at: DateTime<Utc>,
     /// Last update timestamp
     pub updated_at: DateTime<Utc>,
+    /// When the document expires; expired documents are left out of search
+    /// results and removed by [`crate::VectorStore::delete_expired`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 impl Document {
@@ -145,6 +264,7 @@ impl Document {
             content,
             created_at: now,
             updated_at: now,
+            expires_at: None,
         }
     }
 
@@ -158,9 +278,27 @@ impl Document {
             content,
             created_at: now,
             updated_at: now,
+            expires_at: None,
         }
     }
 
+    /// Expire the document `ttl` after its creation
+    pub fn with_ttl(mut self, ttl: chrono::Duration) -> Self {
+        self.expires_at = Some(self.created_at + ttl);
+        self
+    }
+
+    /// Expire the document at `expires_at`
+    pub fn with_expires_at(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Whether the document has expired at `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
     /// Update the document content
     pub fn update_content(&mut self, content: String) {
         self.content = content;
@@ -175,39 +313,109 @@ impl Document {
 }
 
 /// Filter for search queries
+///
+/// Every set field must hold for a document to match; unset fields don't
+/// constrain. Both built-in stores apply the same semantics (see
+/// [`SearchFilter::matches`]); build filters with [`crate::FilterBuilder`]
+/// to have them validated up front.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SearchFilter {
+    /// Filter by tenant ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
     /// Filter by room ID
-    pub room_id: Option<Uuid>,
+    pub room_id: Option<RoomId>,
+    /// Filter to any of these rooms; an empty list doesn't constrain
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub room_ids: Vec<RoomId>,
     /// Filter by user ID
     pub user_id: Option<Uuid>,
-    /// Filter by tags (matches any)
+    /// Filter by tags, combined according to `tag_match`
     pub tags: Vec<String>,
-    /// Time range filter
+    /// Whether a document needs all of `tags` or any one of them
+    #[serde(default)]
+    pub tag_match: TagMatch,
+    /// Time range filter on `created_at`
     pub time_range: Option<TimeRange>,
-    /// Custom filter conditions
+    /// Filter by content type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Backend-specific conditions, passed through untouched; the built-in
+    /// stores don't evaluate them
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+/// How the tags of a [`SearchFilter`] combine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatch {
+    /// The document carries every tag
+    #[default]
+    All,
+    /// The document carries at least one of the tags
+    Any,
+}
+
 /// Time range filter
+///
+/// Both bounds are inclusive and either may be left open. Timestamps compare
+/// at microsecond precision, the precision Qdrant keeps for datetimes.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeRange {
-    /// Start of time range
-    pub start: DateTime<Utc>,
-    /// End of time range (inclusive)
-    pub end: DateTime<Utc>,
+    /// Start of time range (inclusive), open if unset
+    #[serde(default)]
+    pub start: Option<DateTime<Utc>>,
+    /// End of time range (inclusive), open if unset
+    #[serde(default)]
+    pub end: Option<DateTime<Utc>>,
 }
 
 impl TimeRange {
     /// Create a new time range
     pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
-        Self { start, end }
+        Self {
+            start: Some(start),
+            end: Some(end),
+        }
+    }
+
+    /// Range from `start` on
+    pub fn since(start: DateTime<Utc>) -> Self {
+        Self {
+            start: Some(start),
+            end: None,
+        }
+    }
+
+    /// Range up to and including `end`
+    pub fn until(end: DateTime<Utc>) -> Self {
+        Self {
+            start: None,
+            end: Some(end),
+        }
     }
 
     /// Check if a timestamp is within the range
     pub fn contains(&self, timestamp: DateTime<Utc>) -> bool {
-        timestamp >= self.start && timestamp <= self.end
+        let micros = timestamp.timestamp_micros();
+        self.start
+            .is_none_or(|start| micros >= start.timestamp_micros())
+            && self.end.is_none_or(|end| micros <= end.timestamp_micros())
+    }
+
+    /// Check that the range isn't empty
+    pub fn validate(&self) -> Result<(), String> {
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            if start.timestamp_micros() > end.timestamp_micros() {
+                return Err(format!(
+                    "time range starts at {} after it ends at {}",
+                    start.to_rfc3339(),
+                    end.to_rfc3339()
+                ));
+            }
+        }
+        Ok(())
     }
 }
 
@@ -217,12 +425,29 @@ impl SearchFilter {
         Self::default()
     }
 
+    /// Start a validated filter
+    pub fn builder() -> crate::FilterBuilder {
+        crate::FilterBuilder::new()
+    }
+
+    /// Filter by tenant ID
+    pub fn with_tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
     /// Filter by room ID
-    pub fn with_room(mut self, room_id: Uuid) -> Self {
+    pub fn with_room(mut self, room_id: RoomId) -> Self {
         self.room_id = Some(room_id);
         self
     }
 
+    /// Filter to any of these rooms
+    pub fn with_rooms(mut self, room_ids: impl IntoIterator<Item = RoomId>) -> Self {
+        self.room_ids.extend(room_ids);
+        self
+    }
+
     /// Filter by user ID
     pub fn with_user(mut self, user_id: Uuid) -> Self {
         self.user_id = Some(user_id);
@@ -235,20 +460,53 @@ impl SearchFilter {
         self
     }
 
+    /// Set how tags combine
+    pub fn with_tag_match(mut self, tag_match: TagMatch) -> Self {
+        self.tag_match = tag_match;
+        self
+    }
+
     /// Filter by time range
     pub fn with_time_range(mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
         self.time_range = Some(TimeRange::new(start, end));
         self
     }
 
+    /// Filter by content type
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
     /// Check if a document matches this filter
+    ///
+    /// Tenant, room, user and content type match exactly, and the room must
+    /// be one of `room_ids` when any are given. Tags follow
+    /// `tag_match`; an empty tag list matches everything. The time range
+    /// applies to `created_at`, see [`TimeRange::contains`].
     pub fn matches(&self, doc: &Document) -> bool {
-        if let Some(room_id) = self.room_id {
-            if doc.metadata.room_id != Some(room_id) {
+        if let Some(ref tenant_id) = self.tenant_id {
+            if doc.metadata.tenant_id.as_ref() != Some(tenant_id) {
+                return false;
+            }
+        }
+
+        if let Some(ref room_id) = self.room_id {
+            if doc.metadata.room_id.as_ref() != Some(room_id) {
                 return false;
             }
         }
 
+        if !self.room_ids.is_empty()
+            && !doc
+                .metadata
+                .room_id
+                .as_ref()
+                .is_some_and(|room_id| self.room_ids.contains(room_id))
+        {
+            return false;
+        }
+
         if let Some(user_id) = self.user_id {
             if doc.metadata.user_id != Some(user_id) {
                 return false;
@@ -256,8 +514,12 @@ impl SearchFilter {
         }
 
         if !self.tags.is_empty() {
-            let has_match = self.tags.iter().any(|tag| doc.metadata.tags.contains(tag));
-            if !has_match {
+            let mut tags = self.tags.iter();
+            let has_tags = match self.tag_match {
+                TagMatch::All => tags.all(|tag| doc.metadata.tags.contains(tag)),
+                TagMatch::Any => tags.any(|tag| doc.metadata.tags.contains(tag)),
+            };
+            if !has_tags {
                 return false;
             }
         }
@@ -268,9 +530,31 @@ impl SearchFilter {
             }
         }
 
+        if let Some(ref content_type) = self.content_type {
+            if doc.metadata.content_type.as_ref() != Some(content_type) {
+                return false;
+            }
+        }
+
         true
     }
 
+    /// Whether the filter constrains nothing (`extra` aside)
+    pub fn is_empty(&self) -> bool {
+        self.conditions().is_empty()
+    }
+
+    /// Validate the filter
+    pub fn validate(&self) -> Result<(), String> {
+        if self.tags.iter().any(|tag| tag.is_empty()) {
+            return Err("Filter tags cannot be empty".to_string());
+        }
+        if let Some(ref range) = self.time_range {
+            range.validate()?;
+        }
+        Ok(())
+    }
+
     /// Convert to JSON value for backend-specific filtering
     pub fn to_json(&self) -> serde_json::Value {
         serde_json::to_value(self).unwrap_or(serde_json::json!({}))
@@ -286,8 +570,12 @@ pub struct SearchQuery {
     pub limit: usize,
     /// Offset for pagination
     pub offset: usize,
-    /// Minimum similarity threshold (0.0 to 1.0)
+    /// Score threshold: a minimum similarity (0.0 to 1.0 for cosine), or
+    /// for Euclidean a maximum distance
     pub min_score: Option<f32>,
+    /// Metric the query expects; must match the store's, which is used if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric: Option<DistanceMetric>,
     /// Filter conditions
     pub filter: Option<SearchFilter>,
     /// Include content in results (can be large)
@@ -304,6 +592,7 @@ impl SearchQuery {
             limit: 10,
             offset: 0,
             min_score: None,
+            metric: None,
             filter: None,
             include_content: true,
             include_metadata: true,
@@ -322,9 +611,15 @@ impl SearchQuery {
         self
     }
 
-    /// Set minimum score threshold
+    /// Set the score threshold, see [`SearchQuery::min_score`]
     pub fn with_min_score(mut self, score: f32) -> Self {
-        self.min_score = Some(score.clamp(0.0, 1.0));
+        self.min_score = Some(score);
+        self
+    }
+
+    /// Expect the store to compare vectors with `metric`
+    pub fn with_metric(mut self, metric: DistanceMetric) -> Self {
+        self.metric = Some(metric);
         self
     }
 
@@ -335,7 +630,7 @@ impl SearchQuery {
     }
 
     /// Set room filter
-    pub fn with_room(mut self, room_id: Uuid) -> Self {
+    pub fn with_room(mut self, room_id: RoomId) -> Self {
         self.filter = Some(self.filter.take().unwrap_or_default().with_room(room_id));
         self
     }
@@ -358,9 +653,34 @@ impl SearchQuery {
         if self.limit == 0 {
             return Err("Limit must be at least 1".to_string());
         }
+        if self.min_score.is_some_and(|score| !score.is_finite()) {
+            return Err("min_score must be a finite number".to_string());
+        }
+        if let Some(ref filter) = self.filter {
+            filter.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Validate the query against a store comparing vectors with `metric`
+    pub fn validate_for(&self, metric: DistanceMetric) -> Result<(), String> {
+        self.validate()?;
+        if let Some(expected) = self.metric {
+            if expected != metric {
+                return Err(format!(
+                    "Query expects {expected} distance but the store uses {metric}"
+                ));
+            }
+        }
         if let Some(score) = self.min_score {
-            if !(0.0..=1.0).contains(&score) {
-                return Err("min_score must be between 0.0 and 1.0".to_string());
+            match metric {
+                DistanceMetric::Cosine if !(0.0..=1.0).contains(&score) => {
+                    return Err("min_score must be between 0.0 and 1.0".to_string());
+                }
+                DistanceMetric::Euclidean if score < 0.0 => {
+                    return Err("min_score must be a non-negative distance".to_string());
+                }
+                _ => {}
             }
         }
         Ok(())
@@ -372,7 +692,7 @@ impl SearchQuery {
 pub struct SearchResult {
     /// Matched document
     pub document: Document,
-    /// Similarity score (0.0 to 1.0)
+    /// Score under the store's [`DistanceMetric`]
     pub score: f32,
     /// Match explanation (optional)
     pub explanation: Option<String>,
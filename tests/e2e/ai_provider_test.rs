@@ -52,6 +52,7 @@ async fn ai_provider_end_to_end_with_mock_api() {
         max_tokens: Some(32),
         temperature: Some(0.0),
         metadata: None,
+        images: Vec::new(),
     };
 
     let default_provider = registry
@@ -17,6 +17,7 @@ async fn openai_generate_real_api() {
         max_tokens: Some(50),
         temperature: Some(0.0),
         metadata: None,
+        images: Vec::new(),
     };
 
     let resp = provider.generate(req).await.unwrap();
@@ -37,6 +38,7 @@ async fn openai_stream_real_api() {
         max_tokens: Some(50),
         temperature: Some(0.0),
         metadata: None,
+        images: Vec::new(),
     };
 
     let mut stream = provider.generate_stream(req).await.unwrap();